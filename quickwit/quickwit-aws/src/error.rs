@@ -33,8 +33,15 @@ use aws_sdk_s3::operation::delete_object::DeleteObjectError;
 use aws_sdk_s3::operation::delete_objects::DeleteObjectsError;
 use aws_sdk_s3::operation::get_object::GetObjectError;
 use aws_sdk_s3::operation::head_object::HeadObjectError;
+use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Error;
 use aws_sdk_s3::operation::put_object::PutObjectError;
 use aws_sdk_s3::operation::upload_part::UploadPartError;
+#[cfg(feature = "sqs")]
+use aws_sdk_sqs::operation::{
+    change_message_visibility_batch::ChangeMessageVisibilityBatchError,
+    delete_message_batch::DeleteMessageBatchError, get_queue_attributes::GetQueueAttributesError,
+    receive_message::ReceiveMessageError,
+};
 use aws_smithy_client::SdkError;
 
 use crate::retry::AwsRetryable;
@@ -108,6 +115,12 @@ impl AwsRetryable for HeadObjectError {
     }
 }
 
+impl AwsRetryable for ListObjectsV2Error {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
 #[cfg(feature = "kinesis")]
 impl AwsRetryable for GetRecordsError {
     fn is_retryable(&self) -> bool {
@@ -195,3 +208,31 @@ impl AwsRetryable for SplitShardError {
         )
     }
 }
+
+#[cfg(feature = "sqs")]
+impl AwsRetryable for ReceiveMessageError {
+    fn is_retryable(&self) -> bool {
+        matches!(self, ReceiveMessageError::OverLimit(_))
+    }
+}
+
+#[cfg(feature = "sqs")]
+impl AwsRetryable for DeleteMessageBatchError {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "sqs")]
+impl AwsRetryable for ChangeMessageVisibilityBatchError {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "sqs")]
+impl AwsRetryable for GetQueueAttributesError {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
@@ -23,7 +23,9 @@ use quickwit_serve::EnvFilterReloadFn;
 use tracing::Level;
 
 use crate::index::{build_index_command, IndexCliCommand};
+use crate::ingester::{build_ingester_command, IngesterCliCommand};
 use crate::service::{build_run_command, RunCliCommand};
+use crate::shard::{build_shard_command, ShardCliCommand};
 use crate::source::{build_source_command, SourceCliCommand};
 use crate::split::{build_split_command, SplitCliCommand};
 use crate::tool::{build_tool_command, ToolCliCommand};
@@ -51,7 +53,9 @@ pub fn build_cli() -> Command {
         .subcommand(build_index_command().display_order(2))
         .subcommand(build_source_command().display_order(3))
         .subcommand(build_split_command().display_order(4))
-        .subcommand(build_tool_command().display_order(5))
+        .subcommand(build_shard_command().display_order(5))
+        .subcommand(build_ingester_command().display_order(6))
+        .subcommand(build_tool_command().display_order(7))
         .arg_required_else_help(true)
         .disable_help_subcommand(true)
         .subcommand_required(true)
@@ -62,6 +66,8 @@ pub enum CliCommand {
     Run(RunCliCommand),
     Index(IndexCliCommand),
     Split(SplitCliCommand),
+    Shard(ShardCliCommand),
+    Ingester(IngesterCliCommand),
     Source(SourceCliCommand),
     Tool(ToolCliCommand),
 }
@@ -73,6 +79,8 @@ impl CliCommand {
             CliCommand::Index(subcommand) => subcommand.default_log_level(),
             CliCommand::Source(_) => Level::ERROR,
             CliCommand::Split(_) => Level::ERROR,
+            CliCommand::Shard(_) => Level::ERROR,
+            CliCommand::Ingester(_) => Level::ERROR,
             CliCommand::Tool(_) => Level::ERROR,
         }
     }
@@ -86,6 +94,8 @@ impl CliCommand {
             "run" => RunCliCommand::parse_cli_args(submatches).map(CliCommand::Run),
             "source" => SourceCliCommand::parse_cli_args(submatches).map(CliCommand::Source),
             "split" => SplitCliCommand::parse_cli_args(submatches).map(CliCommand::Split),
+            "shard" => ShardCliCommand::parse_cli_args(submatches).map(CliCommand::Shard),
+            "ingester" => IngesterCliCommand::parse_cli_args(submatches).map(CliCommand::Ingester),
             "tool" => ToolCliCommand::parse_cli_args(submatches).map(CliCommand::Tool),
             _ => bail!("unknown command `{subcommand}`"),
         }
@@ -97,6 +107,8 @@ impl CliCommand {
             CliCommand::Run(subcommand) => subcommand.execute(env_filter_reload_fn).await,
             CliCommand::Source(subcommand) => subcommand.execute().await,
             CliCommand::Split(subcommand) => subcommand.execute().await,
+            CliCommand::Shard(subcommand) => subcommand.execute().await,
+            CliCommand::Ingester(subcommand) => subcommand.execute().await,
             CliCommand::Tool(subcommand) => subcommand.execute().await,
         }
     }
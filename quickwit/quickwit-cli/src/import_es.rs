@@ -0,0 +1,737 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Building blocks of the `quickwit tool import-es` command: translation of an Elasticsearch
+//! (or OpenSearch) mapping into a Quickwit doc mapping, and resumable streaming of the source
+//! documents with a point in time (PIT) and `search_after`.
+
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use reqwest::{Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+/// Date formats applied when an Elasticsearch `date` field does not define any.
+const ES_DEFAULT_DATE_FORMATS: &[&str] = &["rfc3339", "unix_timestamp"];
+
+/// Name of the field used as timestamp field when `--timestamp-field` is not set.
+const ES_DEFAULT_TIMESTAMP_FIELD: &str = "@timestamp";
+
+/// The result of the translation of an Elasticsearch mapping.
+#[derive(Debug)]
+pub(crate) struct MappingTranslation {
+    /// The Quickwit doc mapping, in its JSON form.
+    pub doc_mapping: JsonValue,
+    /// The features of the source mapping that could not be translated faithfully.
+    pub warnings: Vec<String>,
+}
+
+/// Translates the `mappings` object of an Elasticsearch index into a Quickwit doc mapping.
+///
+/// Features without a Quickwit equivalent (unsupported field types, custom analyzers, runtime
+/// fields, ...) are dropped and reported in the returned warnings rather than failing the
+/// translation, so that the user can review them before importing.
+pub(crate) fn translate_es_mapping(
+    mappings: &JsonValue,
+    timestamp_field_opt: Option<&str>,
+) -> anyhow::Result<MappingTranslation> {
+    let mut warnings = Vec::new();
+    let empty_properties = JsonMap::new();
+    let properties = mappings
+        .get("properties")
+        .and_then(JsonValue::as_object)
+        .unwrap_or(&empty_properties);
+    let mut field_mappings = translate_properties("", properties, &mut warnings);
+
+    let mut doc_mapping = JsonMap::new();
+    match mappings.get("dynamic") {
+        None | Some(JsonValue::Bool(true)) => {
+            doc_mapping.insert("mode".to_string(), json!("dynamic"));
+        }
+        Some(JsonValue::String(dynamic)) if dynamic == "true" => {
+            doc_mapping.insert("mode".to_string(), json!("dynamic"));
+        }
+        Some(JsonValue::Bool(false)) => {
+            // Unmapped fields are kept in `_source` but not indexed.
+            doc_mapping.insert("mode".to_string(), json!("dynamic"));
+            doc_mapping.insert("dynamic_mapping".to_string(), json!({"indexed": false}));
+        }
+        Some(JsonValue::String(dynamic)) if dynamic == "false" => {
+            doc_mapping.insert("mode".to_string(), json!("dynamic"));
+            doc_mapping.insert("dynamic_mapping".to_string(), json!({"indexed": false}));
+        }
+        Some(JsonValue::String(dynamic)) if dynamic == "strict" => {
+            doc_mapping.insert("mode".to_string(), json!("strict"));
+        }
+        Some(dynamic) => {
+            warnings.push(format!(
+                "dynamic mapping `{dynamic}` is not supported: unmapped fields are indexed \
+                 dynamically"
+            ));
+            doc_mapping.insert("mode".to_string(), json!("dynamic"));
+        }
+    }
+    if mappings.get("dynamic_templates").is_some() {
+        warnings.push("dynamic templates are not supported and were dropped".to_string());
+    }
+    if let Some(runtime_fields) = mappings.get("runtime").and_then(JsonValue::as_object) {
+        if !runtime_fields.is_empty() {
+            warnings.push(format!(
+                "runtime fields are not supported and were dropped: {}",
+                runtime_fields
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+    if let Some(source) = mappings.get("_source") {
+        if source.get("enabled") == Some(&JsonValue::Bool(false)) {
+            warnings.push(
+                "`_source` is disabled on the source index: its documents cannot be imported"
+                    .to_string(),
+            );
+        } else if source.get("includes").is_some() || source.get("excludes").is_some() {
+            warnings.push(
+                "`_source` includes/excludes are not supported: only the fields present in \
+                 `_source` are imported"
+                    .to_string(),
+            );
+        }
+    }
+    let timestamp_field_opt = match timestamp_field_opt {
+        Some(timestamp_field) => {
+            if !mark_timestamp_field(&mut field_mappings, timestamp_field) {
+                bail!(
+                    "timestamp field `{timestamp_field}` is not a `date` field of the source \
+                     mapping"
+                );
+            }
+            Some(timestamp_field)
+        }
+        None => mark_timestamp_field(&mut field_mappings, ES_DEFAULT_TIMESTAMP_FIELD)
+            .then_some(ES_DEFAULT_TIMESTAMP_FIELD),
+    };
+    if let Some(timestamp_field) = timestamp_field_opt {
+        doc_mapping.insert("timestamp_field".to_string(), json!(timestamp_field));
+    }
+    doc_mapping.insert(
+        "field_mappings".to_string(),
+        JsonValue::Array(field_mappings),
+    );
+    Ok(MappingTranslation {
+        doc_mapping: JsonValue::Object(doc_mapping),
+        warnings,
+    })
+}
+
+/// Builds the JSON index config of the target index from a translated doc mapping.
+pub(crate) fn build_index_config(index_id: &str, doc_mapping: &JsonValue) -> JsonValue {
+    json!({
+        "version": "0.8",
+        "index_id": index_id,
+        "doc_mapping": doc_mapping,
+    })
+}
+
+fn translate_properties(
+    path_prefix: &str,
+    properties: &JsonMap<String, JsonValue>,
+    warnings: &mut Vec<String>,
+) -> Vec<JsonValue> {
+    properties
+        .iter()
+        .filter_map(|(field_name, es_field)| {
+            let field_path = format!("{path_prefix}{field_name}");
+            translate_field(&field_path, field_name, es_field, warnings)
+        })
+        .collect()
+}
+
+fn translate_field(
+    field_path: &str,
+    field_name: &str,
+    es_field: &JsonValue,
+    warnings: &mut Vec<String>,
+) -> Option<JsonValue> {
+    let es_type = es_field.get("type").and_then(JsonValue::as_str).unwrap_or(
+        if es_field.get("properties").is_some() {
+            "object"
+        } else {
+            "unknown"
+        },
+    );
+    let is_indexed = es_field.get("index") != Some(&JsonValue::Bool(false));
+    // Doc values are enabled by default for every type but `text` and `binary`.
+    let doc_values_by_default = !matches!(es_type, "text" | "match_only_text" | "binary");
+    let is_fast = es_field
+        .get("doc_values")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(doc_values_by_default);
+
+    if es_field.get("copy_to").is_some() {
+        warnings.push(format!("field `{field_path}`: `copy_to` is not supported"));
+    }
+    if let Some(multi_fields) = es_field.get("fields").and_then(JsonValue::as_object) {
+        warnings.push(format!(
+            "field `{field_path}`: multi-fields are not supported and were dropped: {}",
+            multi_fields.keys().cloned().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    let mut field_mapping = JsonMap::new();
+    field_mapping.insert("name".to_string(), json!(field_name));
+
+    match es_type {
+        "text" | "match_only_text" => {
+            let tokenizer = match es_field.get("analyzer").and_then(JsonValue::as_str) {
+                None | Some("standard") => "default",
+                Some("english") => "en_stem",
+                Some("keyword") => "raw",
+                Some(analyzer) => {
+                    warnings.push(format!(
+                        "field `{field_path}`: analyzer `{analyzer}` is not supported, the \
+                         `default` tokenizer is used instead"
+                    ));
+                    "default"
+                }
+            };
+            field_mapping.insert("type".to_string(), json!("text"));
+            field_mapping.insert("tokenizer".to_string(), json!(tokenizer));
+            field_mapping.insert("record".to_string(), json!("position"));
+        }
+        "keyword" | "constant_keyword" | "wildcard" => {
+            if es_type != "keyword" {
+                warnings.push(format!(
+                    "field `{field_path}`: type `{es_type}` is imported as a `keyword` field"
+                ));
+            }
+            if es_field.get("ignore_above").is_some() {
+                warnings.push(format!(
+                    "field `{field_path}`: `ignore_above` is not supported, long values are \
+                     indexed too"
+                ));
+            }
+            let normalizer = match es_field.get("normalizer").and_then(JsonValue::as_str) {
+                None => "raw",
+                Some("lowercase") => "lowercase",
+                Some(normalizer) => {
+                    warnings.push(format!(
+                        "field `{field_path}`: normalizer `{normalizer}` is not supported, values \
+                         are indexed as is"
+                    ));
+                    "raw"
+                }
+            };
+            field_mapping.insert("type".to_string(), json!("text"));
+            field_mapping.insert("tokenizer".to_string(), json!(normalizer));
+            if is_fast {
+                field_mapping.insert("fast".to_string(), json!({ "normalizer": normalizer }));
+            }
+        }
+        "long" | "integer" | "short" | "byte" => {
+            field_mapping.insert("type".to_string(), json!("i64"));
+        }
+        "unsigned_long" => {
+            field_mapping.insert("type".to_string(), json!("u64"));
+        }
+        "double" | "float" | "half_float" | "scaled_float" => {
+            if es_type == "scaled_float" {
+                warnings.push(format!(
+                    "field `{field_path}`: `scaled_float` is imported as an unscaled `f64` field"
+                ));
+            }
+            field_mapping.insert("type".to_string(), json!("f64"));
+        }
+        "boolean" => {
+            field_mapping.insert("type".to_string(), json!("bool"));
+        }
+        "date" | "date_nanos" => {
+            let input_formats = translate_date_formats(field_path, es_field, warnings);
+            let fast_precision = if es_type == "date" {
+                "milliseconds"
+            } else {
+                "nanoseconds"
+            };
+            field_mapping.insert("type".to_string(), json!("datetime"));
+            field_mapping.insert("input_formats".to_string(), json!(input_formats));
+            field_mapping.insert("fast_precision".to_string(), json!(fast_precision));
+        }
+        "ip" => {
+            field_mapping.insert("type".to_string(), json!("ip"));
+        }
+        "binary" => {
+            field_mapping.insert("type".to_string(), json!("bytes"));
+        }
+        "flattened" => {
+            field_mapping.insert("type".to_string(), json!("json"));
+            field_mapping.insert("tokenizer".to_string(), json!("raw"));
+        }
+        "object" | "nested" => {
+            if es_type == "nested" {
+                warnings.push(format!(
+                    "field `{field_path}`: nested documents are imported as plain objects, \
+                     queries can no longer match fields of the same nested object together"
+                ));
+            }
+            if es_field.get("enabled") == Some(&JsonValue::Bool(false)) {
+                field_mapping.insert("type".to_string(), json!("json"));
+                field_mapping.insert("indexed".to_string(), json!(false));
+                return Some(JsonValue::Object(field_mapping));
+            }
+            if es_field.get("dynamic").is_some() {
+                warnings.push(format!(
+                    "field `{field_path}`: `dynamic` is only supported at the root of the mapping"
+                ));
+            }
+            let empty_properties = JsonMap::new();
+            let properties = es_field
+                .get("properties")
+                .and_then(JsonValue::as_object)
+                .unwrap_or(&empty_properties);
+            let field_mappings =
+                translate_properties(&format!("{field_path}."), properties, warnings);
+            if field_mappings.is_empty() {
+                // Quickwit rejects objects without any field: the object is captured as JSON.
+                field_mapping.insert("type".to_string(), json!("json"));
+                return Some(JsonValue::Object(field_mapping));
+            }
+            field_mapping.insert("type".to_string(), json!("object"));
+            field_mapping.insert("field_mappings".to_string(), json!(field_mappings));
+            return Some(JsonValue::Object(field_mapping));
+        }
+        _ => {
+            warnings.push(format!(
+                "field `{field_path}`: type `{es_type}` is not supported and was dropped"
+            ));
+            return None;
+        }
+    }
+    if !is_indexed {
+        field_mapping.insert("indexed".to_string(), json!(false));
+    }
+    if is_fast && !field_mapping.contains_key("fast") && es_type != "flattened" {
+        field_mapping.insert("fast".to_string(), json!(true));
+    }
+    Some(JsonValue::Object(field_mapping))
+}
+
+fn translate_date_formats(
+    field_path: &str,
+    es_field: &JsonValue,
+    warnings: &mut Vec<String>,
+) -> Vec<&'static str> {
+    let Some(es_formats) = es_field.get("format").and_then(JsonValue::as_str) else {
+        return ES_DEFAULT_DATE_FORMATS.to_vec();
+    };
+    let mut input_formats: Vec<&'static str> = Vec::new();
+    for es_format in es_formats.split("||") {
+        let input_format = match es_format.trim() {
+            "strict_date_optional_time"
+            | "date_optional_time"
+            | "strict_date_optional_time_nanos"
+            | "strict_date_time"
+            | "date_time" => "rfc3339",
+            "epoch_millis" | "epoch_second" => "unix_timestamp",
+            es_format => {
+                warnings.push(format!(
+                    "field `{field_path}`: date format `{es_format}` is not supported"
+                ));
+                continue;
+            }
+        };
+        if !input_formats.contains(&input_format) {
+            input_formats.push(input_format);
+        }
+    }
+    if input_formats.is_empty() {
+        return ES_DEFAULT_DATE_FORMATS.to_vec();
+    }
+    input_formats
+}
+
+/// Makes the `datetime` field at `field_path` fast so that it can be used as timestamp field.
+/// Returns `false` if there is no such field.
+fn mark_timestamp_field(field_mappings: &mut [JsonValue], field_path: &str) -> bool {
+    let (field_name, sub_path_opt) = match field_path.split_once('.') {
+        Some((field_name, sub_path)) => (field_name, Some(sub_path)),
+        None => (field_path, None),
+    };
+    let Some(field_mapping) = field_mappings
+        .iter_mut()
+        .find(|field_mapping| field_mapping["name"] == field_name)
+    else {
+        return false;
+    };
+    match sub_path_opt {
+        Some(sub_path) => match field_mapping
+            .get_mut("field_mappings")
+            .and_then(JsonValue::as_array_mut)
+        {
+            Some(sub_field_mappings) => mark_timestamp_field(sub_field_mappings, sub_path),
+            None => false,
+        },
+        None if field_mapping["type"] == "datetime" => {
+            field_mapping["fast"] = json!(true);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The flavor of the source cluster, which drives the point in time API to use.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum SourceDistribution {
+    Elasticsearch,
+    OpenSearch,
+}
+
+/// A page of documents fetched from the source cluster.
+#[derive(Debug)]
+pub(crate) struct SourcePage {
+    pub pit_id: String,
+    pub docs: Vec<JsonValue>,
+    pub search_after_opt: Option<Vec<JsonValue>>,
+}
+
+/// A minimal Elasticsearch/OpenSearch client covering the APIs used by the import.
+pub(crate) struct SourceClient {
+    http_client: reqwest::Client,
+    endpoint: Url,
+    username_opt: Option<String>,
+    password_opt: Option<String>,
+    distribution: SourceDistribution,
+}
+
+impl SourceClient {
+    pub async fn connect(
+        endpoint: Url,
+        username_opt: Option<String>,
+        password_opt: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let mut source_client = Self {
+            http_client: reqwest::Client::new(),
+            endpoint,
+            username_opt,
+            password_opt,
+            distribution: SourceDistribution::Elasticsearch,
+        };
+        let root_info = source_client
+            .send(Method::GET, "", None)
+            .await
+            .context("failed to connect to the source cluster")?;
+        if root_info["version"]["distribution"] == "opensearch" {
+            source_client.distribution = SourceDistribution::OpenSearch;
+        }
+        Ok(source_client)
+    }
+
+    /// Returns the `mappings` object of the source index.
+    pub async fn mappings(&self, index: &str) -> anyhow::Result<JsonValue> {
+        let response = self
+            .send(Method::GET, &format!("{index}/_mapping"), None)
+            .await?;
+        let Some(index_mappings) = response.as_object() else {
+            bail!("unexpected response to the mapping request of index `{index}`");
+        };
+        // The source index may be an alias or a pattern resolving to several indexes: their
+        // mappings are expected to be the same.
+        let mut mappings_iter = index_mappings.values();
+        let Some(first_index_mappings) = mappings_iter.next() else {
+            bail!("source index `{index}` not found");
+        };
+        if mappings_iter.any(|other_index_mappings| other_index_mappings != first_index_mappings) {
+            bail!("source index `{index}` resolves to several indexes with different mappings");
+        }
+        Ok(first_index_mappings["mappings"].clone())
+    }
+
+    pub async fn open_point_in_time(
+        &self,
+        index: &str,
+        keep_alive: &str,
+    ) -> anyhow::Result<String> {
+        let (path, id_key) = match self.distribution {
+            SourceDistribution::Elasticsearch => {
+                (format!("{index}/_pit?keep_alive={keep_alive}"), "id")
+            }
+            SourceDistribution::OpenSearch => (
+                format!("{index}/_search/point_in_time?keep_alive={keep_alive}"),
+                "pit_id",
+            ),
+        };
+        let response = self.send(Method::POST, &path, None).await?;
+        let pit_id = response[id_key]
+            .as_str()
+            .context("point in time ID missing from response")?;
+        Ok(pit_id.to_string())
+    }
+
+    pub async fn close_point_in_time(&self, pit_id: &str) -> anyhow::Result<()> {
+        let (path, body) = match self.distribution {
+            SourceDistribution::Elasticsearch => ("_pit", json!({ "id": pit_id })),
+            SourceDistribution::OpenSearch => {
+                ("_search/point_in_time", json!({ "pit_id": [pit_id] }))
+            }
+        };
+        self.send(Method::DELETE, path, Some(body)).await?;
+        Ok(())
+    }
+
+    /// Fetches the page of documents following `search_after_opt` in the point in time.
+    pub async fn fetch_page(
+        &self,
+        pit_id: &str,
+        keep_alive: &str,
+        batch_size: usize,
+        search_after_opt: Option<&[JsonValue]>,
+    ) -> anyhow::Result<SourcePage> {
+        let mut search_body = json!({
+            "size": batch_size,
+            "pit": {"id": pit_id, "keep_alive": keep_alive},
+            "sort": [{"_shard_doc": "asc"}],
+            "track_total_hits": false,
+        });
+        if let Some(search_after) = search_after_opt {
+            search_body["search_after"] = json!(search_after);
+        }
+        let response = self
+            .send(Method::POST, "_search", Some(search_body))
+            .await
+            .context(
+                "failed to fetch documents from the source cluster, the point in time may have \
+                 expired",
+            )?;
+        let hits = response["hits"]["hits"]
+            .as_array()
+            .context("hits missing from search response")?;
+        let mut docs = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let Some(source) = hit.get("_source") else {
+                bail!("document `{}` has no `_source`", hit["_id"]);
+            };
+            docs.push(source.clone());
+        }
+        let search_after_opt = hits.last().and_then(|hit| hit["sort"].as_array()).cloned();
+        // The point in time ID may change between requests: the latest one must be used.
+        let pit_id = response["pit_id"].as_str().unwrap_or(pit_id).to_string();
+        Ok(SourcePage {
+            pit_id,
+            docs,
+            search_after_opt,
+        })
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body_opt: Option<JsonValue>,
+    ) -> anyhow::Result<JsonValue> {
+        let url = self.endpoint.join(path)?;
+        let mut request = self.http_client.request(method, url);
+        if let Some(username) = &self.username_opt {
+            request = request.basic_auth(username, self.password_opt.as_ref());
+        }
+        if let Some(body) = body_opt {
+            request = request.json(&body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            bail!("not found: {}", response.text().await?);
+        }
+        if !status.is_success() {
+            bail!("request failed ({status}): {}", response.text().await?);
+        }
+        let response_json = response.json().await?;
+        Ok(response_json)
+    }
+}
+
+/// Tracks the progress of an import so that it can be resumed after an interruption.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ImportCheckpoint {
+    pub source_index: String,
+    pub target_index: String,
+    pub pit_id: String,
+    /// Sort values of the last imported document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_after: Option<Vec<JsonValue>>,
+    pub num_imported_docs: u64,
+    #[serde(default)]
+    pub completed: bool,
+}
+
+impl ImportCheckpoint {
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let checkpoint_json = std::fs::read(path)
+            .with_context(|| format!("failed to read checkpoint file `{}`", path.display()))?;
+        let checkpoint = serde_json::from_slice(&checkpoint_json)
+            .with_context(|| format!("failed to parse checkpoint file `{}`", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Writes the checkpoint to a temporary file first so that an interruption never leaves a
+    /// truncated checkpoint behind.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("failed to write checkpoint file `{}`", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_es_mapping() {
+        let mappings = json!({
+            "properties": {
+                "@timestamp": {"type": "date"},
+                "message": {
+                    "type": "text",
+                    "fields": {"keyword": {"type": "keyword", "ignore_above": 256}}
+                },
+                "level": {"type": "keyword"},
+                "status": {"type": "integer", "index": false},
+                "latency": {"type": "scaled_float", "scaling_factor": 100},
+                "client_ip": {"type": "ip"},
+                "location": {"type": "geo_point"},
+                "user": {
+                    "properties": {
+                        "name": {"type": "keyword", "normalizer": "lowercase"},
+                        "created_at": {"type": "date", "format": "yyyy-MM-dd||epoch_millis"}
+                    }
+                },
+                "labels": {"type": "flattened"}
+            },
+            "runtime": {"day_of_week": {"type": "keyword"}}
+        });
+        let translation = translate_es_mapping(&mappings, None).unwrap();
+        let expected_doc_mapping = json!({
+            "mode": "dynamic",
+            "timestamp_field": "@timestamp",
+            "field_mappings": [
+                {
+                    "name": "@timestamp",
+                    "type": "datetime",
+                    "input_formats": ["rfc3339", "unix_timestamp"],
+                    "fast_precision": "milliseconds",
+                    "fast": true
+                },
+                {"name": "client_ip", "type": "ip", "fast": true},
+                {"name": "labels", "type": "json", "tokenizer": "raw"},
+                {"name": "latency", "type": "f64", "fast": true},
+                {"name": "level", "type": "text", "tokenizer": "raw", "fast": {"normalizer": "raw"}},
+                {"name": "message", "type": "text", "tokenizer": "default", "record": "position"},
+                {"name": "status", "type": "i64", "indexed": false, "fast": true},
+                {
+                    "name": "user",
+                    "type": "object",
+                    "field_mappings": [
+                        {
+                            "name": "created_at",
+                            "type": "datetime",
+                            "input_formats": ["unix_timestamp"],
+                            "fast_precision": "milliseconds",
+                            "fast": true
+                        },
+                        {
+                            "name": "name",
+                            "type": "text",
+                            "tokenizer": "lowercase",
+                            "fast": {"normalizer": "lowercase"}
+                        }
+                    ]
+                }
+            ]
+        });
+        assert_eq!(translation.doc_mapping, expected_doc_mapping);
+        assert_eq!(
+            translation.warnings,
+            [
+                "field `latency`: `scaled_float` is imported as an unscaled `f64` field",
+                "field `location`: type `geo_point` is not supported and was dropped",
+                "field `message`: multi-fields are not supported and were dropped: keyword",
+                "field `user.created_at`: date format `yyyy-MM-dd` is not supported",
+                "runtime fields are not supported and were dropped: day_of_week",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_translate_es_mapping_dynamic_and_timestamp_field() {
+        let mappings = json!({
+            "dynamic": "strict",
+            "properties": {
+                "event": {"properties": {"created": {"type": "date_nanos"}}},
+                "count": {"type": "long"}
+            }
+        });
+        let translation = translate_es_mapping(&mappings, Some("event.created")).unwrap();
+        assert_eq!(translation.doc_mapping["mode"], "strict");
+        assert_eq!(translation.doc_mapping["timestamp_field"], "event.created");
+        assert_eq!(
+            translation.doc_mapping["field_mappings"][1]["field_mappings"][0]["fast_precision"],
+            "nanoseconds"
+        );
+        assert!(translation.warnings.is_empty());
+
+        let error = translate_es_mapping(&mappings, Some("count")).unwrap_err();
+        assert!(error.to_string().contains("is not a `date` field"));
+
+        let mappings = json!({"dynamic": false, "properties": {}});
+        let translation = translate_es_mapping(&mappings, None).unwrap();
+        assert_eq!(
+            translation.doc_mapping,
+            json!({
+                "mode": "dynamic",
+                "dynamic_mapping": {"indexed": false},
+                "field_mappings": []
+            })
+        );
+    }
+
+    #[test]
+    fn test_import_checkpoint_save_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let checkpoint_path = temp_dir.path().join("checkpoint.json");
+        assert!(ImportCheckpoint::load(&checkpoint_path).unwrap().is_none());
+
+        let checkpoint = ImportCheckpoint {
+            source_index: "logs".to_string(),
+            target_index: "logs-qw".to_string(),
+            pit_id: "pit-id".to_string(),
+            search_after: Some(vec![json!(42)]),
+            num_imported_docs: 1_000,
+            completed: false,
+        };
+        checkpoint.save(&checkpoint_path).unwrap();
+        let loaded_checkpoint = ImportCheckpoint::load(&checkpoint_path).unwrap().unwrap();
+        assert_eq!(loaded_checkpoint, checkpoint);
+    }
+}
@@ -161,14 +161,17 @@ pub async fn update_retention_policy_cli(args: RetentionPolicyArgs) -> anyhow::R
         (false, None, Some(schedule), Some(policy)) => Some(RetentionPolicy {
             retention_period: policy.retention_period,
             evaluation_schedule: schedule,
+            partition_overrides: policy.partition_overrides,
         }),
         (false, Some(period), schedule_opt, None) => Some(RetentionPolicy {
             retention_period: period,
             evaluation_schedule: schedule_opt.unwrap_or(RetentionPolicy::default_schedule()),
+            partition_overrides: Vec::new(),
         }),
         (false, Some(period), schedule_opt, Some(policy)) => Some(RetentionPolicy {
             retention_period: period,
             evaluation_schedule: schedule_opt.unwrap_or(policy.evaluation_schedule.clone()),
+            partition_overrides: policy.partition_overrides,
         }),
     };
     if let Some(new_retention_policy) = new_retention_policy_opt.as_ref() {
@@ -186,6 +189,9 @@ pub async fn update_retention_policy_cli(args: RetentionPolicyArgs) -> anyhow::R
             IndexUpdates {
                 retention_policy_opt: new_retention_policy_opt,
                 search_settings: metadata.index_config.search_settings,
+                // Carries over the current legal hold. The update never lifts it.
+                legal_hold_opt: metadata.index_config.legal_hold_opt.map(Some),
+                doc_mapping_opt: None,
             },
         )
         .await?;
@@ -200,6 +206,8 @@ pub async fn update_search_settings_cli(args: SearchSettingsArgs) -> anyhow::Res
     let metadata = qw_client.indexes().get(&args.index_id).await?;
     let search_settings = SearchSettings {
         default_search_fields: args.default_search_fields,
+        query_rules: metadata.index_config.search_settings.query_rules,
+        reranker: metadata.index_config.search_settings.reranker,
     };
     println!(
         "New search settings: {}",
@@ -212,6 +220,9 @@ pub async fn update_search_settings_cli(args: SearchSettingsArgs) -> anyhow::Res
             IndexUpdates {
                 retention_policy_opt: metadata.index_config.retention_policy_opt,
                 search_settings,
+                // Carries over the current legal hold. The update never lifts it.
+                legal_hold_opt: metadata.index_config.legal_hold_opt.map(Some),
+                doc_mapping_opt: None,
             },
         )
         .await?;
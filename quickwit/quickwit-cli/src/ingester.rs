@@ -0,0 +1,161 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use clap::{arg, Arg, ArgAction, ArgMatches, Command};
+use colored::Colorize;
+use tracing::debug;
+
+use crate::checklist::GREEN_COLOR;
+use crate::{client_args, ClientArgs};
+
+/// Interval between two polls of the decommissioning progress when `--wait` is set.
+const DECOMMISSION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn build_ingester_command() -> Command {
+    Command::new("ingester")
+        .about("Manages ingesters: decommissions...")
+        .args(client_args())
+        .subcommand(
+            Command::new("decommission")
+                .about(
+                    "Decommissions an ingester: its open shards are moved to other ingesters and \
+                     it leaves the ingester pool once its shards are fully published.",
+                )
+                .args(&[
+                    arg!(--"node-id" <NODE_ID> "ID of the ingester to decommission.")
+                        .display_order(1)
+                        .required(true),
+                    Arg::new("wait")
+                        .long("wait")
+                        .short('w')
+                        .help("Wait for the ingester to be decommissioned before exiting.")
+                        .display_order(2)
+                        .action(ArgAction::SetTrue),
+                ]),
+        )
+        .arg_required_else_help(true)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DecommissionIngesterArgs {
+    pub client_args: ClientArgs,
+    pub node_id: String,
+    pub wait: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum IngesterCliCommand {
+    Decommission(DecommissionIngesterArgs),
+}
+
+impl IngesterCliCommand {
+    pub fn parse_cli_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let (subcommand, submatches) = matches
+            .remove_subcommand()
+            .context("failed to parse ingester subcommand")?;
+        match subcommand.as_str() {
+            "decommission" => Self::parse_decommission_args(submatches),
+            _ => bail!("unknown ingester subcommand `{subcommand}`"),
+        }
+    }
+
+    fn parse_decommission_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let node_id = matches
+            .remove_one::<String>("node-id")
+            .expect("`node-id` should be a required arg.");
+        let wait = matches.get_flag("wait");
+        Ok(Self::Decommission(DecommissionIngesterArgs {
+            client_args,
+            node_id,
+            wait,
+        }))
+    }
+
+    pub async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            Self::Decommission(args) => decommission_ingester_cli(args).await,
+        }
+    }
+}
+
+async fn decommission_ingester_cli(args: DecommissionIngesterArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "decommission-ingester");
+    println!("❯ Decommissioning ingester...");
+    let qw_client = args.client_args.client();
+
+    loop {
+        let decommission_ingester_response = qw_client
+            .ingesters()
+            .decommission(&args.node_id)
+            .await
+            .context("failed to decommission ingester")?;
+
+        if decommission_ingester_response.is_decommissioned {
+            println!(
+                "{} Ingester `{}` successfully decommissioned.",
+                "✔".color(GREEN_COLOR),
+                args.node_id
+            );
+            return Ok(());
+        }
+        println!(
+            "Ingester `{}` is decommissioning: {} shard(s) remaining.",
+            args.node_id, decommission_ingester_response.num_remaining_shards
+        );
+        if !args.wait {
+            return Ok(());
+        }
+        tokio::time::sleep(DECOMMISSION_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{build_cli, CliCommand};
+
+    #[test]
+    fn test_parse_decommission_ingester_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "ingester",
+            "decommission",
+            "--node-id",
+            "ingester-1",
+            "--wait",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command =
+            CliCommand::Ingester(IngesterCliCommand::Decommission(DecommissionIngesterArgs {
+                client_args: ClientArgs::default(),
+                node_id: "ingester-1".to_string(),
+                wait: true,
+            }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        app.try_get_matches_from(vec!["ingester", "decommission"])
+            .unwrap_err();
+        Ok(())
+    }
+}
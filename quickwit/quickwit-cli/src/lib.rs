@@ -50,12 +50,15 @@ use crate::checklist::run_checklist;
 
 pub mod checklist;
 pub mod cli;
+mod import_es;
 pub mod index;
+pub mod ingester;
 #[cfg(feature = "jemalloc")]
 pub mod jemalloc;
 pub mod logger;
 pub mod metrics;
 pub mod service;
+pub mod shard;
 pub mod source;
 pub mod split;
 pub mod stats;
@@ -69,6 +72,8 @@ pub const QW_ENABLE_TOKIO_CONSOLE_ENV_KEY: &str = "QW_ENABLE_TOKIO_CONSOLE";
 pub const QW_ENABLE_OPENTELEMETRY_OTLP_EXPORTER_ENV_KEY: &str =
     "QW_ENABLE_OPENTELEMETRY_OTLP_EXPORTER";
 
+pub const QW_AUDIT_LOG_PATH_ENV_KEY: &str = "QW_AUDIT_LOG_PATH";
+
 fn config_cli_arg() -> Arg {
     Arg::new("config")
         .long("config")
@@ -18,7 +18,8 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::env;
-use std::sync::Arc;
+use std::fs::OpenOptions;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
 use opentelemetry::sdk::propagation::TraceContextPropagator;
@@ -26,15 +27,17 @@ use opentelemetry::sdk::trace::BatchConfig;
 use opentelemetry::sdk::{trace, Resource};
 use opentelemetry::{global, KeyValue};
 use opentelemetry_otlp::WithExportConfig;
+use quickwit_common::shared_consts::AUDIT_LOG_TARGET;
 use quickwit_serve::{BuildInfo, EnvFilterReloadFn};
 use tracing::Level;
+use tracing_subscriber::filter::filter_fn;
 use tracing_subscriber::fmt::time::UtcTime;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
-use crate::QW_ENABLE_OPENTELEMETRY_OTLP_EXPORTER_ENV_KEY;
 #[cfg(feature = "tokio-console")]
 use crate::QW_ENABLE_TOKIO_CONSOLE_ENV_KEY;
+use crate::{QW_AUDIT_LOG_PATH_ENV_KEY, QW_ENABLE_OPENTELEMETRY_OTLP_EXPORTER_ENV_KEY};
 
 pub fn setup_logging_and_tracing(
     level: Level,
@@ -52,6 +55,7 @@ pub fn setup_logging_and_tracing(
         .map(|_| EnvFilter::from_default_env())
         .or_else(|_| EnvFilter::try_new(format!("quickwit={level},tantivy=WARN")))
         .context("failed to set up tracing env filter")?;
+    let env_filter = with_audit_log_directive(env_filter)?;
     global::set_text_map_propagator(TraceContextPropagator::new());
     let (reloadable_env_filter, reload_handle) = tracing_subscriber::reload::Layer::new(env_filter);
     let registry = tracing_subscriber::registry().with(reloadable_env_filter);
@@ -67,6 +71,34 @@ pub fn setup_logging_and_tracing(
                 .expect("Time format invalid."),
             ),
         );
+    // When an audit log file is set, the audit events are written to it rather than to the regular
+    // logs.
+    let audit_log_file_opt = env::var_os(QW_AUDIT_LOG_PATH_ENV_KEY)
+        .map(|audit_log_path| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&audit_log_path)
+                .with_context(|| {
+                    format!(
+                        "failed to open audit log file `{}`",
+                        audit_log_path.to_string_lossy()
+                    )
+                })
+        })
+        .transpose()?;
+    let has_audit_log_file = audit_log_file_opt.is_some();
+    let audit_log_layer_opt = audit_log_file_opt.map(|audit_log_file| {
+        tracing_subscriber::fmt::layer()
+            .event_format(event_format.clone())
+            .with_ansi(false)
+            .with_writer(Mutex::new(audit_log_file))
+            .with_filter(filter_fn(|metadata| metadata.target() == AUDIT_LOG_TARGET))
+    });
+    let regular_log_filter =
+        filter_fn(move |metadata| !has_audit_log_file || metadata.target() != AUDIT_LOG_TARGET);
+    let registry = registry.with(audit_log_layer_opt);
+
     // Note on disabling ANSI characters: setting the ansi boolean on event format is insufficient.
     // It is thus set on layers, see https://github.com/tokio-rs/tracing/issues/1817
     if std::env::var_os(QW_ENABLE_OPENTELEMETRY_OTLP_EXPORTER_ENV_KEY).is_some() {
@@ -90,7 +122,8 @@ pub fn setup_logging_and_tracing(
             .with(
                 tracing_subscriber::fmt::layer()
                     .event_format(event_format)
-                    .with_ansi(ansi_colors),
+                    .with_ansi(ansi_colors)
+                    .with_filter(regular_log_filter),
             )
             .try_init()
             .context("failed to register tracing subscriber")?;
@@ -99,14 +132,22 @@ pub fn setup_logging_and_tracing(
             .with(
                 tracing_subscriber::fmt::layer()
                     .event_format(event_format)
-                    .with_ansi(ansi_colors),
+                    .with_ansi(ansi_colors)
+                    .with_filter(regular_log_filter),
             )
             .try_init()
             .context("failed to register tracing subscriber")?;
     }
     Ok(Arc::new(move |env_filter_def: &str| {
         let new_env_filter = EnvFilter::try_new(env_filter_def)?;
+        let new_env_filter = with_audit_log_directive(new_env_filter)?;
         reload_handle.reload(new_env_filter)?;
         Ok(())
     }))
 }
+
+/// The audit events are recorded whatever the log level, so the env filter always enables them.
+fn with_audit_log_directive(env_filter: EnvFilter) -> anyhow::Result<EnvFilter> {
+    let audit_log_directive = format!("{AUDIT_LOG_TARGET}=info").parse()?;
+    Ok(env_filter.add_directive(audit_log_directive))
+}
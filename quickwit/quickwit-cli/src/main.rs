@@ -125,8 +125,8 @@ mod tests {
     };
     use quickwit_cli::split::{DescribeSplitArgs, SplitCliCommand};
     use quickwit_cli::tool::{
-        ExtractSplitArgs, GarbageCollectIndexArgs, LocalIngestDocsArgs, LocalSearchArgs, MergeArgs,
-        ToolCliCommand,
+        BackfillFastFieldsArgs, ExtractSplitArgs, GarbageCollectIndexArgs, ImportEsArgs,
+        LocalIngestDocsArgs, LocalSearchArgs, MergeArgs, RecoverIndexArgs, ToolCliCommand,
     };
     use quickwit_cli::ClientArgs;
     use quickwit_common::uri::Uri;
@@ -758,6 +758,146 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_recover_index_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "recover-index",
+            "--index-uri",
+            "s3://my-bucket/indexes/wikipedia",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_index_uri = Uri::from_str("s3://my-bucket/indexes/wikipedia").unwrap();
+        assert!(matches!(
+            command,
+            CliCommand::Tool(ToolCliCommand::RecoverIndex(RecoverIndexArgs {
+                index_uri,
+                index_config_uri_opt: None,
+                dry_run: false,
+                ..
+            })) if index_uri == expected_index_uri
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "recover-index",
+            "--index-uri",
+            "s3://my-bucket/indexes/wikipedia",
+            "--index-config",
+            "/index-config.yaml",
+            "--config",
+            "/config.yaml",
+            "--dry-run",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_index_config_uri = Uri::from_str("file:///index-config.yaml").unwrap();
+        assert!(matches!(
+            command,
+            CliCommand::Tool(ToolCliCommand::RecoverIndex(RecoverIndexArgs {
+                index_config_uri_opt: Some(index_config_uri),
+                dry_run: true,
+                ..
+            })) if index_config_uri == expected_index_config_uri
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_backfill_fast_fields_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "backfill-fast-fields",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Tool(ToolCliCommand::BackfillFastFields(BackfillFastFieldsArgs {
+                index_id,
+                ..
+            })) if &index_id == "wikipedia"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_import_es_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "import-es",
+            "--source-endpoint",
+            "http://localhost:9200",
+            "--source-index",
+            "logs",
+            "--endpoint",
+            "http://127.0.0.1:7280",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command = CliCommand::Tool(ToolCliCommand::ImportEs(ImportEsArgs {
+            client_args: ClientArgs::default(),
+            source_endpoint: Url::from_str("http://localhost:9200")?,
+            source_index: "logs".to_string(),
+            index_id: "logs".to_string(),
+            source_username: None,
+            source_password: None,
+            timestamp_field: None,
+            batch_size: 1_000,
+            keep_alive: "1h".to_string(),
+            checkpoint_path: PathBuf::from("logs.import-es-checkpoint.json"),
+            dry_run: false,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "import-es",
+            "--source-endpoint",
+            "https://opensearch:9200",
+            "--source-index",
+            "logs-*",
+            "--index",
+            "logs",
+            "--source-username",
+            "admin",
+            "--timestamp-field",
+            "event.created",
+            "--batch-size",
+            "500",
+            "--checkpoint-path",
+            "/tmp/logs.json",
+            "--dry-run",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Tool(ToolCliCommand::ImportEs(ImportEsArgs {
+                source_index,
+                index_id,
+                source_username: Some(source_username),
+                timestamp_field: Some(timestamp_field),
+                batch_size: 500,
+                checkpoint_path,
+                dry_run: true,
+                ..
+            })) if source_index == "logs-*"
+                && index_id == "logs"
+                && source_username == "admin"
+                && timestamp_field == "event.created"
+                && checkpoint_path == PathBuf::from("/tmp/logs.json")
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_parse_no_color() {
         let previous_no_color_res = std::env::var("NO_COLOR");
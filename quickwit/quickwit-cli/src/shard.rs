@@ -0,0 +1,280 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use clap::{arg, ArgMatches, Command};
+use quickwit_proto::control_plane::{ListShardsResponse, ShardInfo};
+use quickwit_proto::ingest::ShardState;
+use quickwit_serve::ListShardsQueryParams;
+use tabled::{Table, Tabled};
+use tracing::debug;
+
+use crate::split::OutputFormat;
+use crate::{client_args, make_table, ClientArgs};
+
+pub fn build_shard_command() -> Command {
+    Command::new("shard")
+        .about("Manages shards: lists...")
+        .args(client_args())
+        .subcommand(
+            Command::new("list")
+                .about("Lists the shards tracked by the control plane.")
+                .alias("ls")
+                .args(&[
+                    arg!(--index <INDEX> "Selects the shards of this index.")
+                        .display_order(1)
+                        .required(false),
+                    arg!(--source <SOURCE> "Selects the shards of this source.")
+                        .display_order(2)
+                        .required(false),
+                    arg!(--state <SHARD_STATE> "Selects the shards in this state. Possible values are `open`, `unavailable`, and `closed`.")
+                        .display_order(3)
+                        .required(false),
+                    arg!(--leader <LEADER> "Selects the shards led by this ingester.")
+                        .display_order(4)
+                        .required(false),
+                    arg!(--"offset" <OFFSET> "Number of shards to skip.")
+                        .display_order(5)
+                        .required(false),
+                    arg!(--"limit" <LIMIT> "Maximum number of shards to retrieve.")
+                        .display_order(6)
+                        .required(false),
+                    arg!(--"output-format" <OUTPUT_FORMAT> "Output format. Possible values are `table`, `json`, and `pretty-json`.")
+                        .alias("format")
+                        .display_order(7)
+                        .required(false)
+                ])
+            )
+        .arg_required_else_help(true)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ListShardsArgs {
+    pub client_args: ClientArgs,
+    pub index_id: Option<String>,
+    pub source_id: Option<String>,
+    pub shard_state: Option<ShardState>,
+    pub leader_id: Option<String>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+    output_format: OutputFormat,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ShardCliCommand {
+    List(ListShardsArgs),
+}
+
+impl ShardCliCommand {
+    pub fn parse_cli_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let (subcommand, submatches) = matches
+            .remove_subcommand()
+            .context("failed to parse shard subcommand")?;
+        match subcommand.as_str() {
+            "list" => Self::parse_list_args(submatches),
+            _ => bail!("unknown shard subcommand `{subcommand}`"),
+        }
+    }
+
+    fn parse_list_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches.remove_one::<String>("index");
+        let source_id = matches.remove_one::<String>("source");
+        let shard_state = matches
+            .remove_one::<String>("state")
+            .map(|shard_state_str| parse_shard_state(&shard_state_str))
+            .transpose()?;
+        let leader_id = matches.remove_one::<String>("leader");
+        let offset = matches
+            .remove_one::<String>("offset")
+            .map(|offset_str| offset_str.parse::<u32>())
+            .transpose()
+            .context("failed to parse `--offset` option")?;
+        let limit = matches
+            .remove_one::<String>("limit")
+            .map(|limit_str| limit_str.parse::<u32>())
+            .transpose()
+            .context("failed to parse `--limit` option")?;
+        let output_format = matches
+            .remove_one::<String>("output-format")
+            .map(|s| OutputFormat::from_str(s.as_str()))
+            .transpose()?
+            .unwrap_or(OutputFormat::Table);
+        Ok(Self::List(ListShardsArgs {
+            client_args,
+            index_id,
+            source_id,
+            shard_state,
+            leader_id,
+            offset,
+            limit,
+            output_format,
+        }))
+    }
+
+    pub async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            Self::List(args) => list_shards_cli(args).await,
+        }
+    }
+}
+
+async fn list_shards_cli(args: ListShardsArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "list-shards");
+    let qw_client = args.client_args.client();
+    let list_shards_query_params = ListShardsQueryParams {
+        index_id: args.index_id,
+        source_id: args.source_id,
+        shard_state: args.shard_state,
+        leader_id: args.leader_id,
+        offset: args.offset,
+        limit: args.limit,
+    };
+    let list_shards_response = qw_client
+        .shards()
+        .list(list_shards_query_params)
+        .await
+        .context("failed to list shards")?;
+    let output = match args.output_format {
+        OutputFormat::Json => serde_json::to_string(&list_shards_response)?,
+        OutputFormat::PrettyJson => serde_json::to_string_pretty(&list_shards_response)?,
+        OutputFormat::Table => make_shard_table(&list_shards_response).to_string(),
+    };
+    println!("{output}");
+    Ok(())
+}
+
+fn make_shard_table(list_shards_response: &ListShardsResponse) -> Table {
+    let rows = list_shards_response
+        .shards
+        .iter()
+        .filter_map(ShardRow::from_shard_info);
+    let title = format!(
+        "Shards ({}/{})",
+        list_shards_response.shards.len(),
+        list_shards_response.num_matching_shards
+    );
+    make_table(&title, rows, false)
+}
+
+fn parse_shard_state(shard_state_arg: &str) -> anyhow::Result<ShardState> {
+    match ShardState::from_json_str_name(&shard_state_arg.to_lowercase()) {
+        Some(ShardState::Unspecified) | None => bail!(
+            "unknown shard state `{shard_state_arg}`. possible values are `open`, `unavailable`, \
+             and `closed`"
+        ),
+        Some(shard_state) => Ok(shard_state),
+    }
+}
+
+#[derive(Tabled)]
+struct ShardRow {
+    #[tabled(rename = "Index ID")]
+    index_id: String,
+    #[tabled(rename = "Source ID")]
+    source_id: String,
+    #[tabled(rename = "Shard ID")]
+    shard_id: String,
+    #[tabled(rename = "State")]
+    shard_state: &'static str,
+    #[tabled(rename = "Leader")]
+    leader_id: String,
+    #[tabled(rename = "Follower")]
+    follower_id: String,
+    #[tabled(rename = "Ingestion rate (MiB/s)")]
+    ingestion_rate_mib_per_sec: u32,
+    #[tabled(rename = "Publish position")]
+    publish_position: String,
+}
+
+impl ShardRow {
+    fn from_shard_info(shard_info: &ShardInfo) -> Option<Self> {
+        let shard = shard_info.shard.as_ref()?;
+        let shard_row = Self {
+            index_id: shard.index_uid().index_id.clone(),
+            source_id: shard.source_id.clone(),
+            shard_id: shard.shard_id().to_string(),
+            shard_state: shard.shard_state().as_json_str_name(),
+            leader_id: shard.leader_id.clone(),
+            follower_id: shard.follower_id.clone().unwrap_or_default(),
+            ingestion_rate_mib_per_sec: shard_info.ingestion_rate_mib_per_sec,
+            publish_position: shard
+                .publish_position_inclusive
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        };
+        Some(shard_row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{build_cli, CliCommand};
+
+    #[test]
+    fn test_parse_list_shards_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "shard",
+            "list",
+            "--index",
+            "hdfs",
+            "--state",
+            "open",
+            "--leader",
+            "ingester-1",
+            "--limit",
+            "10",
+            "--format",
+            "json",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Shard(ShardCliCommand::List(ListShardsArgs {
+                index_id,
+                source_id: None,
+                shard_state: Some(ShardState::Open),
+                leader_id,
+                offset: None,
+                limit: Some(10),
+                output_format: OutputFormat::Json,
+                ..
+            })) if index_id.as_deref() == Some("hdfs")
+                   && leader_id.as_deref() == Some("ingester-1")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_shard_state() {
+        assert_eq!(parse_shard_state("open").unwrap(), ShardState::Open);
+        assert_eq!(
+            parse_shard_state("Unavailable").unwrap(),
+            ShardState::Unavailable
+        );
+        assert_eq!(parse_shard_state("closed").unwrap(), ShardState::Closed);
+        parse_shard_state("unspecified").unwrap_err();
+        parse_shard_state("foo").unwrap_err();
+    }
+}
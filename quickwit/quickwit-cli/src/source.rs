@@ -745,6 +745,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file("path/to/file"),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         }];
         let expected_source = vec![SourceRow {
@@ -805,6 +807,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::stdin(),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             },
             SourceConfig {
@@ -813,6 +817,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::stdin(),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             },
         ];
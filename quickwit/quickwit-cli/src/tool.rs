@@ -31,40 +31,55 @@ use colored::{ColoredString, Colorize};
 use humantime::format_duration;
 use quickwit_actors::{ActorExitStatus, ActorHandle, Mailbox, Universe};
 use quickwit_cluster::{ChannelTransport, Cluster, ClusterMember, FailureDetectorConfig};
+use quickwit_common::io::IoControls;
 use quickwit_common::pubsub::EventBroker;
 use quickwit_common::runtimes::RuntimesConfig;
+use quickwit_common::temp_dir;
 use quickwit_common::uri::Uri;
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
-    IndexerConfig, NodeConfig, SourceConfig, SourceInputFormat, SourceParams, TransformConfig,
-    VecSourceParams, CLI_SOURCE_ID,
+    build_doc_mapper, load_index_config_from_user_config, ConfigFormat, IndexerConfig, NodeConfig,
+    SourceConfig, SourceInputFormat, SourceParams, TransformConfig, VecSourceParams, CLI_SOURCE_ID,
 };
 use quickwit_index_management::{clear_cache_directory, IndexService};
 use quickwit_indexing::actors::{
-    IndexingService, MergePipeline, MergePipelineId, MergeSchedulerService,
+    schedule_merge, IndexingService, MergeExecutor, MergePipeline, MergePipelineId,
+    MergeSchedulerService, MergeSplitDownloader, Packager, Publisher, PublisherType,
+    SplitsUpdateMailbox, Uploader, UploaderType,
 };
+use quickwit_indexing::merge_policy::{merge_policy_from_settings, MergeOperation};
 use quickwit_indexing::models::{
     DetachIndexingPipeline, DetachMergePipeline, IndexingStatistics, SpawnPipeline,
 };
-use quickwit_indexing::IndexingPipeline;
+use quickwit_indexing::{IndexingPipeline, IndexingSplitStore};
 use quickwit_ingest::IngesterPool;
-use quickwit_metastore::IndexMetadataResponseExt;
-use quickwit_proto::indexing::CpuCapacity;
-use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
+use quickwit_metastore::{
+    IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    MetastoreServiceStreamSplitsExt, SplitState,
+};
+use quickwit_proto::indexing::{CpuCapacity, IndexingPipelineId};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, ListSplitsRequest, MetastoreService, MetastoreServiceClient,
+};
 use quickwit_proto::search::{CountHits, SearchResponse};
 use quickwit_proto::types::{NodeId, PipelineUid};
+use quickwit_rest_client::models::IngestSource;
+use quickwit_rest_client::rest_client::CommitType;
 use quickwit_search::{single_node_search, SearchResponseRest};
 use quickwit_serve::{
     search_request_from_api_request, BodyFormat, SearchRequestQueryString, SortBy,
 };
-use quickwit_storage::{BundleStorage, Storage};
+use quickwit_storage::{load_file, BundleStorage, Storage};
+use reqwest::Url;
+use tantivy::Inventory;
 use thousands::Separable;
 use tracing::{debug, info};
 
 use crate::checklist::{GREEN_COLOR, RED_COLOR};
+use crate::import_es::{build_index_config, translate_es_mapping, ImportCheckpoint, SourceClient};
 use crate::{
-    config_cli_arg, get_resolvers, load_node_config, run_index_checklist, start_actor_runtimes,
-    THROUGHPUT_WINDOW_SIZE,
+    client_args, config_cli_arg, get_resolvers, load_node_config, run_index_checklist,
+    start_actor_runtimes, ClientArgs, THROUGHPUT_WINDOW_SIZE,
 };
 
 pub fn build_tool_command() -> Command {
@@ -155,6 +170,68 @@ pub fn build_tool_command() -> Command {
                         .required(false),
                 ])
             )
+        .subcommand(
+            Command::new("backfill-fast-fields")
+                .display_order(10)
+                .about("Rewrites the splits of an index so that they include the fast fields enabled after they were created.")
+                .long_about("Rewrites the published splits of an index by reindexing their documents with the current doc mapping. Splits that already have all the fast fields of the doc mapping are left untouched. A split that gets merged concurrently fails to be backfilled: the command can safely be run again.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index.")
+                        .display_order(1)
+                        .required(true),
+                ])
+            )
+        .subcommand(
+            Command::new("import-es")
+                .display_order(10)
+                .about("Imports an Elasticsearch or OpenSearch index into Quickwit.")
+                .long_about("Translates the mapping of an Elasticsearch or OpenSearch index into a Quickwit doc mapping, creates the target index if it does not exist, and streams the documents of the source index into it through the ingest API of the Quickwit cluster. The mapping features that cannot be translated are reported before the import starts. The progress is recorded in a checkpoint file so that an interrupted import can be resumed by running the same command again, as long as the point in time of the source cluster has not expired.")
+                .args(client_args())
+                .args(&[
+                    arg!(--"source-endpoint" <SOURCE_ENDPOINT> "Endpoint of the source Elasticsearch or OpenSearch cluster.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"source-index" <SOURCE_INDEX> "Name of the source index, alias, or index pattern.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--index <INDEX> "ID of the target index. Defaults to the name of the source index.")
+                        .display_order(3)
+                        .required(false),
+                    arg!(--"source-username" <USERNAME> "Username used to authenticate against the source cluster.")
+                        .required(false),
+                    arg!(--"source-password" <PASSWORD> "Password used to authenticate against the source cluster.")
+                        .env("QW_SOURCE_PASSWORD")
+                        .required(false),
+                    arg!(--"timestamp-field" <TIMESTAMP_FIELD> "Date field of the source mapping used as timestamp field. Defaults to `@timestamp` if it exists.")
+                        .required(false),
+                    arg!(--"batch-size" <BATCH_SIZE> "Number of documents fetched from the source cluster per request.")
+                        .default_value("1000")
+                        .required(false),
+                    arg!(--"keep-alive" <KEEP_ALIVE> "Keep alive of the point in time opened on the source cluster, in the source cluster time unit format.")
+                        .default_value("1h")
+                        .required(false),
+                    arg!(--"checkpoint-path" <CHECKPOINT_PATH> "Location of the checkpoint file. Defaults to `<source-index>.import-es-checkpoint.json`.")
+                        .required(false),
+                    arg!(--"dry-run" "Only displays the translated index config and the features of the source mapping that cannot be translated.")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("recover-index")
+                .display_order(10)
+                .about("Recreates the metadata of an index from the manifests stored along with its splits.")
+                .long_about("Rebuilds the metastore entry of an index that was lost or corrupted by reading the index manifest and the split manifests stored in the index storage. The splits that were merged into another split and the splits that were created before split manifests existed are skipped. The checkpoints of the sources are not recovered, so the sources start consuming from their initial position.")
+                .args(&[
+                    arg!(--"index-uri" <INDEX_URI> "URI of the index storage, for instance `s3://my-bucket/indexes/my-index`.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"index-config" <INDEX_CONFIG> "Location of the index config file. Required if the index storage does not contain an index manifest; overrides the manifest otherwise.")
+                        .display_order(2)
+                        .required(false),
+                    arg!(--"dry-run" "Only displays the splits that would be recovered.")
+                        .required(false),
+                ])
+            )
         .subcommand(
             Command::new("merge")
                 .display_order(10)
@@ -212,6 +289,12 @@ pub struct MergeArgs {
     pub source_id: String,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct BackfillFastFieldsArgs {
+    pub config_uri: Uri,
+    pub index_id: String,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct ExtractSplitArgs {
     pub config_uri: Uri,
@@ -220,13 +303,39 @@ pub struct ExtractSplitArgs {
     pub target_dir: PathBuf,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct ImportEsArgs {
+    pub client_args: ClientArgs,
+    pub source_endpoint: Url,
+    pub source_index: String,
+    pub index_id: String,
+    pub source_username: Option<String>,
+    pub source_password: Option<String>,
+    pub timestamp_field: Option<String>,
+    pub batch_size: usize,
+    pub keep_alive: String,
+    pub checkpoint_path: PathBuf,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RecoverIndexArgs {
+    pub config_uri: Uri,
+    pub index_uri: Uri,
+    pub index_config_uri_opt: Option<Uri>,
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ToolCliCommand {
     GarbageCollect(GarbageCollectIndexArgs),
     LocalIngest(LocalIngestDocsArgs),
     LocalSearch(LocalSearchArgs),
     Merge(MergeArgs),
+    BackfillFastFields(BackfillFastFieldsArgs),
     ExtractSplit(ExtractSplitArgs),
+    ImportEs(ImportEsArgs),
+    RecoverIndex(RecoverIndexArgs),
 }
 
 impl ToolCliCommand {
@@ -239,7 +348,10 @@ impl ToolCliCommand {
             "local-ingest" => Self::parse_local_ingest_args(submatches),
             "local-search" => Self::parse_local_search_args(submatches),
             "merge" => Self::parse_merge_args(submatches),
+            "backfill-fast-fields" => Self::parse_backfill_fast_fields_args(submatches),
             "extract-split" => Self::parse_extract_split_args(submatches),
+            "import-es" => Self::parse_import_es_args(submatches),
+            "recover-index" => Self::parse_recover_index_args(submatches),
             _ => bail!("unknown tool subcommand `{subcommand}`"),
         }
     }
@@ -348,6 +460,20 @@ impl ToolCliCommand {
         }))
     }
 
+    fn parse_backfill_fast_fields_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = matches
+            .remove_one::<String>("config")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`config` should be a required arg.")?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        Ok(Self::BackfillFastFields(BackfillFastFieldsArgs {
+            config_uri,
+            index_id,
+        }))
+    }
+
     fn parse_garbage_collect_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
         let config_uri = matches
             .get_one("config")
@@ -392,13 +518,83 @@ impl ToolCliCommand {
         }))
     }
 
+    fn parse_import_es_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let source_endpoint = matches
+            .remove_one::<String>("source-endpoint")
+            .map(|endpoint_str| Url::from_str(&endpoint_str))
+            .expect("`source-endpoint` should be a required arg.")?;
+        let source_index = matches
+            .remove_one::<String>("source-index")
+            .expect("`source-index` should be a required arg.");
+        let index_id = matches
+            .remove_one::<String>("index")
+            .unwrap_or_else(|| source_index.clone());
+        let source_username = matches.remove_one::<String>("source-username");
+        let source_password = matches.remove_one::<String>("source-password");
+        let timestamp_field = matches.remove_one::<String>("timestamp-field");
+        let batch_size = matches
+            .remove_one::<String>("batch-size")
+            .expect("`batch-size` should have a default value.")
+            .parse()?;
+        if batch_size == 0 {
+            bail!("`batch-size` must be strictly positive");
+        }
+        let keep_alive = matches
+            .remove_one::<String>("keep-alive")
+            .expect("`keep-alive` should have a default value.");
+        let checkpoint_path = matches
+            .remove_one::<String>("checkpoint-path")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(format!("{source_index}.import-es-checkpoint.json")));
+        let dry_run = matches.get_flag("dry-run");
+        Ok(Self::ImportEs(ImportEsArgs {
+            client_args,
+            source_endpoint,
+            source_index,
+            index_id,
+            source_username,
+            source_password,
+            timestamp_field,
+            batch_size,
+            keep_alive,
+            checkpoint_path,
+            dry_run,
+        }))
+    }
+
+    fn parse_recover_index_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = matches
+            .remove_one::<String>("config")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`config` should be a required arg.")?;
+        let index_uri = matches
+            .remove_one::<String>("index-uri")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`index-uri` should be a required arg.")?;
+        let index_config_uri_opt = matches
+            .remove_one::<String>("index-config")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .transpose()?;
+        let dry_run = matches.get_flag("dry-run");
+        Ok(Self::RecoverIndex(RecoverIndexArgs {
+            config_uri,
+            index_uri,
+            index_config_uri_opt,
+            dry_run,
+        }))
+    }
+
     pub async fn execute(self) -> anyhow::Result<()> {
         match self {
             Self::GarbageCollect(args) => garbage_collect_index_cli(args).await,
             Self::LocalIngest(args) => local_ingest_docs_cli(args).await,
             Self::LocalSearch(args) => local_search_cli(args).await,
             Self::Merge(args) => merge_cli(args).await,
+            Self::BackfillFastFields(args) => backfill_fast_fields_cli(args).await,
             Self::ExtractSplit(args) => extract_split_cli(args).await,
+            Self::ImportEs(args) => import_es_cli(args).await,
+            Self::RecoverIndex(args) => recover_index_cli(args).await,
         }
     }
 }
@@ -426,6 +622,8 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
         enabled: true,
         source_params,
         transform_config,
+        json_schema_config: None,
+        wasm_plugin_config: None,
         input_format: args.input_format,
     };
     run_index_checklist(
@@ -554,6 +752,15 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
         format: BodyFormat::Json,
         sort_by,
         count_all: CountHits::CountAll,
+        include_held_splits: false,
+        bypass_cache: false,
+        max_staleness_secs: None,
+        tenant_id: None,
+        priority: None,
+        profile: false,
+        pit_id: None,
+        lookup: None,
+        highlight: None,
     };
     let search_request =
         search_request_from_api_request(vec![args.index_id], search_request_query_string)?;
@@ -611,6 +818,8 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
                 enabled: true,
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             },
             pipeline_uid: PipelineUid::new(),
@@ -652,6 +861,147 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn backfill_fast_fields_cli(args: BackfillFastFieldsArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "backfill-fast-fields");
+    println!("❯ Backfilling fast fields...");
+    let config = load_node_config(&args.config_uri).await?;
+    let (storage_resolver, metastore_resolver) =
+        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let mut metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
+    let index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(args.index_id))
+        .await?
+        .deserialize_index_metadata()?;
+    let index_uid = index_metadata.index_uid.clone();
+    let index_config = index_metadata.into_index_config();
+
+    let list_splits_query =
+        ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&list_splits_query)?;
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits_metadata()
+        .await?;
+    if splits.is_empty() {
+        println!("No splits to backfill.");
+        return Ok(());
+    }
+    let num_splits = splits.len();
+
+    start_actor_runtimes(
+        RuntimesConfig::default(),
+        &HashSet::from_iter([QuickwitService::Indexer]),
+    )?;
+    let universe = Universe::new();
+    let merge_scheduler_service: Mailbox<MergeSchedulerService> = universe.get_or_spawn_one();
+
+    // The backfill operations go through the same chain of actors as the delete operations of the
+    // janitor: downloader -> executor -> packager -> uploader -> publisher.
+    let publisher = Publisher::new(PublisherType::MergePublisher, metastore.clone(), None, None);
+    let (publisher_mailbox, publisher_handle) = universe.spawn_builder().spawn(publisher);
+
+    let index_storage = storage_resolver.resolve(&index_config.index_uri).await?;
+    let split_store = IndexingSplitStore::create_without_local_store_for_test(index_storage);
+    let uploader = Uploader::new(
+        UploaderType::MergeUploader,
+        metastore.clone(),
+        merge_policy_from_settings(&index_config.indexing_settings),
+        split_store.clone(),
+        SplitsUpdateMailbox::Publisher(publisher_mailbox),
+        IndexerConfig::default().max_concurrent_split_uploads,
+        EventBroker::default(),
+    );
+    let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
+
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)?;
+    let packager = Packager::new(
+        "MergePackager",
+        doc_mapper.tag_named_fields()?,
+        doc_mapper.term_digest_named_fields()?,
+        uploader_mailbox,
+    );
+    let (packager_mailbox, packager_handle) = universe.spawn_builder().spawn(packager);
+
+    let pipeline_id = IndexingPipelineId {
+        index_uid: index_uid.clone(),
+        node_id: config.node_id.to_string(),
+        pipeline_uid: PipelineUid::new(),
+        source_id: "unknown".to_string(),
+    };
+    let io_controls = IoControls::default().set_component("backfiller");
+    let merge_executor = MergeExecutor::new(
+        pipeline_id,
+        metastore,
+        doc_mapper,
+        io_controls.clone(),
+        packager_mailbox,
+    );
+    let (merge_executor_mailbox, merge_executor_handle) =
+        universe.spawn_builder().spawn(merge_executor);
+
+    let scratch_directory = temp_dir::Builder::default()
+        .join(&index_uid.index_id)
+        .tempdir_in(&config.data_dir_path)?;
+    let merge_split_downloader = MergeSplitDownloader {
+        scratch_directory,
+        split_store,
+        executor_mailbox: merge_executor_mailbox,
+        io_controls: io_controls.set_component("split_downloader_backfill"),
+    };
+    let (downloader_mailbox, downloader_handle) =
+        universe.spawn_builder().spawn(merge_split_downloader);
+
+    let ongoing_backfill_operations_inventory: Inventory<MergeOperation> = Inventory::new();
+
+    for split in splits {
+        let backfill_operation = MergeOperation::new_fast_field_backfill_operation(split);
+        let tracked_backfill_operation =
+            ongoing_backfill_operations_inventory.track(backfill_operation);
+        schedule_merge(
+            &merge_scheduler_service,
+            tracked_backfill_operation,
+            downloader_mailbox.clone(),
+            None,
+        )
+        .await?;
+    }
+    let mut check_interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        check_interval.tick().await;
+
+        if ongoing_backfill_operations_inventory.list().is_empty() {
+            info!("no more ongoing backfill operations, exiting");
+            break;
+        }
+        if merge_executor_handle.state().is_exit() || downloader_handle.state().is_exit() {
+            info!("backfill pipeline has exited, exiting");
+            break;
+        }
+    }
+    // The packager, uploader, and publisher are drained before checking the outcome.
+    let (merge_executor_exit_status, _) = merge_executor_handle.quit().await;
+    downloader_handle.quit().await;
+    packager_handle.process_pending_and_observe().await;
+    uploader_handle.process_pending_and_observe().await;
+    let publisher_counters = publisher_handle.process_pending_and_observe().await.state;
+    universe.quit().await;
+
+    if !matches!(
+        merge_executor_exit_status,
+        ActorExitStatus::Success | ActorExitStatus::Quit
+    ) {
+        bail!(merge_executor_exit_status);
+    }
+    println!(
+        "{} Fast fields successfully backfilled: {} split(s) out of {} rewritten.",
+        "✔".color(GREEN_COLOR),
+        publisher_counters.num_replace_operations,
+        num_splits
+    );
+    Ok(())
+}
+
 pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow::Result<()> {
     debug!(args=?args, "garbage-collect-index");
     println!("❯ Garbage collecting index...");
@@ -717,6 +1067,70 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
     Ok(())
 }
 
+pub async fn recover_index_cli(args: RecoverIndexArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "recover-index");
+    println!("❯ Recovering index...");
+
+    let config = load_node_config(&args.config_uri).await?;
+    let (storage_resolver, metastore_resolver) =
+        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let index_config_opt = if let Some(index_config_uri) = &args.index_config_uri_opt {
+        let file_content = load_file(&storage_resolver, index_config_uri).await?;
+        let config_format = ConfigFormat::sniff_from_uri(index_config_uri)?;
+        let index_config = load_index_config_from_user_config(
+            config_format,
+            &file_content,
+            &config.default_index_root_uri,
+        )?;
+        Some(index_config)
+    } else {
+        None
+    };
+    let metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
+    let mut index_service = IndexService::new(metastore, storage_resolver);
+    let report = index_service
+        .recover_index(&args.index_uri, index_config_opt, args.dry_run)
+        .await?;
+
+    if !report.has_index_manifest {
+        println!(
+            "The index storage does not contain an index manifest: the sources of the index are \
+             not recovered."
+        );
+    }
+    if !report.replaced_split_ids.is_empty() {
+        println!(
+            "{} split(s) merged into other splits are skipped.",
+            report.replaced_split_ids.len()
+        );
+    }
+    if !report.split_ids_without_manifest.is_empty() {
+        println!("The following splits do not contain a split manifest and cannot be recovered.");
+        for split_id in &report.split_ids_without_manifest {
+            println!(" - {split_id}");
+        }
+    }
+    if args.dry_run {
+        println!(
+            "The following splits of index `{}` will be recovered ({} documents).",
+            report.index_id,
+            report.num_recovered_docs.separate_with_commas()
+        );
+        for split_id in &report.recovered_split_ids {
+            println!(" - {split_id}");
+        }
+        return Ok(());
+    }
+    println!(
+        "{} Index `{}` successfully recovered: {} split(s), {} documents.",
+        "✔".color(GREEN_COLOR),
+        report.index_id,
+        report.recovered_split_ids.len(),
+        report.num_recovered_docs.separate_with_commas()
+    );
+    Ok(())
+}
+
 async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     debug!(args=?args, "extract-split");
     println!("❯ Extracting split...");
@@ -749,6 +1163,150 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn import_es_cli(args: ImportEsArgs) -> anyhow::Result<()> {
+    debug!(
+        source_endpoint=%args.source_endpoint,
+        source_index=%args.source_index,
+        index_id=%args.index_id,
+        "import-es"
+    );
+    println!("❯ Importing Elasticsearch index...");
+
+    let source_client = SourceClient::connect(
+        args.source_endpoint,
+        args.source_username,
+        args.source_password,
+    )
+    .await?;
+    let mut checkpoint_opt = ImportCheckpoint::load(&args.checkpoint_path)?;
+
+    if let Some(checkpoint) = &checkpoint_opt {
+        if checkpoint.source_index != args.source_index || checkpoint.target_index != args.index_id
+        {
+            bail!(
+                "checkpoint file `{}` belongs to the import of `{}` into `{}`",
+                args.checkpoint_path.display(),
+                checkpoint.source_index,
+                checkpoint.target_index
+            );
+        }
+        if checkpoint.completed {
+            println!(
+                "{} Import already completed ({} documents). Remove the checkpoint file `{}` to \
+                 import the index again.",
+                "✔".color(GREEN_COLOR),
+                checkpoint.num_imported_docs.separate_with_commas(),
+                args.checkpoint_path.display()
+            );
+            return Ok(());
+        }
+    }
+    let qw_client = args.client_args.client();
+
+    if checkpoint_opt.is_none() {
+        let mappings = source_client.mappings(&args.source_index).await?;
+        let translation = translate_es_mapping(&mappings, args.timestamp_field.as_deref())?;
+        for warning in &translation.warnings {
+            println!("{} {warning}", "⚠".color(RED_COLOR));
+        }
+        let index_config = build_index_config(&args.index_id, &translation.doc_mapping);
+
+        if args.dry_run {
+            println!("{}", serde_json::to_string_pretty(&index_config)?);
+            return Ok(());
+        }
+        match qw_client.indexes().get(&args.index_id).await {
+            Ok(_) => println!("Using existing index `{}`.", args.index_id),
+            Err(error) if error.status_code() == Some(reqwest::StatusCode::NOT_FOUND) => {
+                qw_client
+                    .indexes()
+                    .create(index_config, ConfigFormat::Json, false)
+                    .await?;
+                println!(
+                    "{} Index `{}` created.",
+                    "✔".color(GREEN_COLOR),
+                    args.index_id
+                );
+            }
+            Err(error) => return Err(error.into()),
+        }
+        let pit_id = source_client
+            .open_point_in_time(&args.source_index, &args.keep_alive)
+            .await?;
+        let checkpoint = ImportCheckpoint {
+            source_index: args.source_index.clone(),
+            target_index: args.index_id.clone(),
+            pit_id,
+            search_after: None,
+            num_imported_docs: 0,
+            completed: false,
+        };
+        checkpoint.save(&args.checkpoint_path)?;
+        checkpoint_opt = Some(checkpoint);
+    } else if args.dry_run {
+        bail!("`--dry-run` cannot be used to resume an import");
+    }
+    let mut checkpoint = checkpoint_opt.expect("checkpoint should be initialized");
+
+    if checkpoint.num_imported_docs > 0 {
+        println!(
+            "Resuming import after {} documents.",
+            checkpoint.num_imported_docs.separate_with_commas()
+        );
+    }
+    loop {
+        let page = source_client
+            .fetch_page(
+                &checkpoint.pit_id,
+                &args.keep_alive,
+                args.batch_size,
+                checkpoint.search_after.as_deref(),
+            )
+            .await?;
+        if page.docs.is_empty() {
+            break;
+        }
+        let num_docs = page.docs.len() as u64;
+        let mut ndjson = String::new();
+        for doc in page.docs {
+            ndjson.push_str(&serde_json::to_string(&doc)?);
+            ndjson.push('\n');
+        }
+        qw_client
+            .ingest(
+                &args.index_id,
+                IngestSource::Str(ndjson),
+                None,
+                None,
+                CommitType::Auto,
+            )
+            .await?;
+        // The checkpoint only moves forward once the batch is persisted by Quickwit, so a
+        // resumed import never skips documents.
+        checkpoint.pit_id = page.pit_id;
+        checkpoint.search_after = page.search_after_opt;
+        checkpoint.num_imported_docs += num_docs;
+        checkpoint.save(&args.checkpoint_path)?;
+        println!(
+            "Imported {} documents.",
+            checkpoint.num_imported_docs.separate_with_commas()
+        );
+    }
+    checkpoint.completed = true;
+    checkpoint.save(&args.checkpoint_path)?;
+
+    if let Err(error) = source_client.close_point_in_time(&checkpoint.pit_id).await {
+        debug!(error=?error, "failed to close point in time");
+    }
+    println!(
+        "{} {} documents successfully imported into `{}`.",
+        "✔".color(GREEN_COLOR),
+        checkpoint.num_imported_docs.separate_with_commas(),
+        args.index_id
+    );
+    Ok(())
+}
+
 /// Starts a tokio task that displays the indexing statistics
 /// every once in awhile.
 pub async fn start_statistics_reporting_loop(
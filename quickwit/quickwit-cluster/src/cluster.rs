@@ -32,6 +32,7 @@ use chitchat::{
 };
 use itertools::Itertools;
 use quickwit_proto::indexing::{IndexingPipelineId, IndexingTask, PipelineMetrics};
+use quickwit_proto::ingest::ingester::IngesterStatus;
 use quickwit_proto::types::{NodeId, NodeIdRef, PipelineUid, ShardId};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, watch, Mutex, RwLock};
@@ -41,11 +42,15 @@ use tokio_stream::StreamExt;
 use tracing::{info, warn};
 
 use crate::change::{compute_cluster_change_events, ClusterChange, ClusterChangeStreamFactory};
+use crate::features::{
+    supported_features_str, ClusterUpgradeStatus, NodeUpgradeStatus, FEATURES_KEY,
+    PROTOCOL_VERSION, PROTOCOL_VERSION_KEY,
+};
 use crate::grpc_gossip::spawn_catchup_callback_task;
 use crate::member::{
     build_cluster_member, ClusterMember, NodeStateExt, ENABLED_SERVICES_KEY,
-    GRPC_ADVERTISE_ADDR_KEY, PIPELINE_METRICS_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY,
-    READINESS_VALUE_READY,
+    GRPC_ADVERTISE_ADDR_KEY, INGESTER_STATUS_KEY, MAINTENANCE_KEY, MAINTENANCE_VALUE_DRAINING,
+    PIPELINE_METRICS_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY, READINESS_VALUE_READY,
 };
 use crate::metrics::spawn_metrics_task;
 use crate::{ClusterChangeStream, ClusterNode};
@@ -163,6 +168,11 @@ impl Cluster {
                     READINESS_KEY.to_string(),
                     READINESS_VALUE_NOT_READY.to_string(),
                 ),
+                (
+                    PROTOCOL_VERSION_KEY.to_string(),
+                    PROTOCOL_VERSION.to_string(),
+                ),
+                (FEATURES_KEY.to_string(), supported_features_str()),
             ],
             transport,
         )
@@ -270,6 +280,34 @@ impl Cluster {
             .await
     }
 
+    /// Returns whether the self node is in maintenance.
+    pub async fn is_self_node_in_maintenance(&self) -> bool {
+        self.chitchat()
+            .await
+            .lock()
+            .await
+            .node_state(&self.self_chitchat_id)
+            .expect("The self node should always be present in the set of live nodes.")
+            .is_in_maintenance()
+    }
+
+    /// Puts the self node in or out of maintenance. Nodes in maintenance are gradually drained
+    /// by the other nodes of the cluster.
+    pub async fn set_self_node_maintenance(&self, maintenance: bool) {
+        if maintenance {
+            self.set_self_key_value(MAINTENANCE_KEY, MAINTENANCE_VALUE_DRAINING)
+                .await
+        } else {
+            self.remove_self_key(MAINTENANCE_KEY).await
+        }
+    }
+
+    /// Advertises the status of the ingester running on the self node.
+    pub async fn set_self_ingester_status(&self, ingester_status: IngesterStatus) {
+        self.set_self_key_value(INGESTER_STATUS_KEY, ingester_status.as_json_str_name())
+            .await
+    }
+
     /// Sets a key-value pair on the cluster node's state.
     pub async fn set_self_key_value(&self, key: impl Display, value: impl Display) {
         self.chitchat()
@@ -376,6 +414,20 @@ impl Cluster {
         }
     }
 
+    /// Returns the upgrade status of the cluster, computed from the protocol version and the
+    /// features advertised by the live nodes.
+    pub async fn upgrade_status(&self) -> ClusterUpgradeStatus {
+        let nodes = self
+            .inner
+            .read()
+            .await
+            .live_nodes
+            .values()
+            .map(NodeUpgradeStatus::from)
+            .collect();
+        ClusterUpgradeStatus::from_nodes(nodes)
+    }
+
     /// Leaves the cluster.
     pub async fn shutdown(self) {
         info!(
@@ -743,7 +795,7 @@ pub async fn create_cluster_for_test(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeSet, HashMap};
     use std::net::SocketAddr;
     use std::time::Duration;
 
@@ -756,6 +808,7 @@ mod tests {
     use rand::Rng;
 
     use super::*;
+    use crate::ClusterFeature;
 
     #[tokio::test]
     async fn test_single_node_cluster_readiness() {
@@ -829,6 +882,99 @@ mod tests {
         node.shutdown().await;
     }
 
+    #[tokio::test]
+    async fn test_single_node_cluster_maintenance() {
+        let transport = ChannelTransport::default();
+        let node = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        assert!(!node.is_self_node_in_maintenance().await);
+
+        let mut change_stream = node.change_stream();
+        let cluster_change = change_stream.next().await.unwrap();
+        let ClusterChange::Add(self_node) = cluster_change else {
+            panic!("expected `ClusterChange::Add` event, got `{cluster_change:?}`");
+        };
+        assert!(!self_node.is_in_maintenance());
+
+        node.set_self_node_maintenance(true).await;
+        assert!(node.is_self_node_in_maintenance().await);
+        assert_eq!(
+            node.get_self_key_value(MAINTENANCE_KEY).await.unwrap(),
+            MAINTENANCE_VALUE_DRAINING
+        );
+        let cluster_change = change_stream.next().await.unwrap();
+        let ClusterChange::Update(self_node) = cluster_change else {
+            panic!("expected `ClusterChange::Update` event, got `{cluster_change:?}`");
+        };
+        assert!(self_node.is_in_maintenance());
+
+        node.set_self_node_maintenance(false).await;
+        assert!(!node.is_self_node_in_maintenance().await);
+
+        let cluster_change = change_stream.next().await.unwrap();
+        let ClusterChange::Update(self_node) = cluster_change else {
+            panic!("expected `ClusterChange::Update` event, got `{cluster_change:?}`");
+        };
+        assert!(!self_node.is_in_maintenance());
+        node.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_single_node_cluster_ingester_status() {
+        let transport = ChannelTransport::default();
+        let node = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let mut change_stream = node.change_stream();
+        let cluster_change = change_stream.next().await.unwrap();
+        let ClusterChange::Add(self_node) = cluster_change else {
+            panic!("expected `ClusterChange::Add` event, got `{cluster_change:?}`");
+        };
+        assert!(self_node.ingester_status().is_none());
+
+        node.set_self_ingester_status(IngesterStatus::Decommissioning)
+            .await;
+        assert_eq!(
+            node.get_self_key_value(INGESTER_STATUS_KEY).await.unwrap(),
+            "decommissioning"
+        );
+        let cluster_change = change_stream.next().await.unwrap();
+        let ClusterChange::Update(self_node) = cluster_change else {
+            panic!("expected `ClusterChange::Update` event, got `{cluster_change:?}`");
+        };
+        assert_eq!(
+            self_node.ingester_status(),
+            Some(IngesterStatus::Decommissioning)
+        );
+        node.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_single_node_cluster_upgrade_status() {
+        let transport = ChannelTransport::default();
+        let node = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let mut change_stream = node.change_stream();
+        let cluster_change = change_stream.next().await.unwrap();
+        let ClusterChange::Add(self_node) = cluster_change else {
+            panic!("expected `ClusterChange::Add` event, got `{cluster_change:?}`");
+        };
+        assert_eq!(self_node.protocol_version(), PROTOCOL_VERSION);
+        assert!(self_node.supports_feature(ClusterFeature::IngesterPing));
+
+        let upgrade_status = node.upgrade_status().await;
+        assert!(upgrade_status.is_upgrade_complete);
+        assert_eq!(upgrade_status.min_protocol_version, PROTOCOL_VERSION);
+        assert_eq!(
+            upgrade_status.enabled_features,
+            BTreeSet::from(ClusterFeature::SUPPORTED)
+        );
+        assert_eq!(upgrade_status.nodes.len(), 1);
+        node.shutdown().await;
+    }
+
     #[tokio::test]
     async fn test_cluster_multiple_nodes() -> anyhow::Result<()> {
         let transport = ChannelTransport::default();
@@ -0,0 +1,422 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use chitchat::NodeState;
+use futures::StreamExt;
+use itertools::Itertools;
+use quickwit_proto::types::NodeId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::warn;
+
+use crate::{ClusterChange, ClusterChangeStream, ClusterNode};
+
+/// Version of the protocol spoken by the nodes of the cluster. It is bumped when the nodes
+/// exchange messages that older nodes cannot interpret and that cannot be gated behind a
+/// [`ClusterFeature`].
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Wire features that may not be supported by all the nodes of a cluster during a rolling
+/// upgrade. Nodes advertise the features they support in their Chitchat state, and a feature is
+/// only enabled once all the nodes of the cluster advertise it.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    Hash,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterFeature {
+    /// The ingesters answer the `Ping` health probes of the control plane.
+    IngesterPing,
+    /// The ingesters and indexers decode compressed documents from the WAL and the replication
+    /// stream.
+    WalDocCompression,
+    /// The ingesters deduplicate persist subrequests by idempotency key, and the ingesters and
+    /// indexers decode the idempotency key records of the WAL and the replication stream.
+    IdempotencyKey,
+    /// The ingesters persist the subrequests of atomic persist requests all or none and roll back
+    /// the replica shards of the aborted requests.
+    AtomicPersist,
+    /// The control plane moves shards between ingesters and decommissions ingesters on demand.
+    ShardMove,
+}
+
+impl ClusterFeature {
+    /// Features supported by this node.
+    pub const SUPPORTED: [ClusterFeature; 5] = [
+        ClusterFeature::IngesterPing,
+        ClusterFeature::WalDocCompression,
+        ClusterFeature::IdempotencyKey,
+        ClusterFeature::AtomicPersist,
+        ClusterFeature::ShardMove,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IngesterPing => "ingester_ping",
+            Self::WalDocCompression => "wal_doc_compression",
+            Self::IdempotencyKey => "idempotency_key",
+            Self::AtomicPersist => "atomic_persist",
+            Self::ShardMove => "shard_move",
+        }
+    }
+}
+
+impl fmt::Display for ClusterFeature {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ClusterFeature {
+    type Err = String;
+
+    fn from_str(feature_str: &str) -> Result<Self, Self::Err> {
+        match feature_str {
+            "ingester_ping" => Ok(Self::IngesterPing),
+            "wal_doc_compression" => Ok(Self::WalDocCompression),
+            "idempotency_key" => Ok(Self::IdempotencyKey),
+            "atomic_persist" => Ok(Self::AtomicPersist),
+            "shard_move" => Ok(Self::ShardMove),
+            _ => Err(format!("unknown cluster feature `{feature_str}`")),
+        }
+    }
+}
+
+// Keys used to store the protocol version and the features of a node in its Chitchat state.
+pub(crate) const PROTOCOL_VERSION_KEY: &str = "protocol_version";
+pub(crate) const FEATURES_KEY: &str = "features";
+
+pub(crate) fn supported_features_str() -> String {
+    ClusterFeature::SUPPORTED.iter().join(",")
+}
+
+/// Parses the protocol version of a node. Nodes that predate the negotiation of features do not
+/// advertise any protocol version, which is interpreted as version 0.
+pub(crate) fn parse_protocol_version(node_state: &NodeState) -> u32 {
+    let Some(protocol_version_str) = node_state.get(PROTOCOL_VERSION_KEY) else {
+        return 0;
+    };
+    protocol_version_str.parse().unwrap_or_else(|_| {
+        warn!(
+            protocol_version=%protocol_version_str,
+            "received an unparseable protocol version from node"
+        );
+        0
+    })
+}
+
+/// Parses the features advertised by a node. Features unknown to this node, which may be
+/// advertised by newer nodes, are ignored.
+pub(crate) fn parse_features(node_state: &NodeState) -> BTreeSet<ClusterFeature> {
+    let Some(features_str) = node_state.get(FEATURES_KEY) else {
+        return BTreeSet::new();
+    };
+    features_str
+        .split(',')
+        .filter_map(|feature_str| feature_str.parse().ok())
+        .collect()
+}
+
+/// Tracks the features advertised by the ready nodes of the cluster from a
+/// [`crate::ClusterChangeStream`], to determine which features can be enabled.
+#[derive(Debug, Default)]
+pub struct ClusterFeatureGates {
+    node_features: BTreeMap<NodeId, BTreeSet<ClusterFeature>>,
+}
+
+impl ClusterFeatureGates {
+    pub fn apply_cluster_change(&mut self, cluster_change: &ClusterChange) {
+        match cluster_change {
+            ClusterChange::Add(node) | ClusterChange::Update(node) => {
+                self.node_features
+                    .insert(node.node_id().to_owned(), node.features().clone());
+            }
+            ClusterChange::Remove(node) => {
+                self.node_features.remove(node.node_id());
+            }
+        }
+    }
+
+    /// Returns whether a feature is enabled, i.e. all the known nodes of the cluster support it.
+    /// Features are disabled until at least one node is known.
+    pub fn is_enabled(&self, feature: ClusterFeature) -> bool {
+        !self.node_features.is_empty()
+            && self
+                .node_features
+                .values()
+                .all(|features| features.contains(&feature))
+    }
+
+    /// Returns the features enabled in the cluster.
+    pub fn enabled_features(&self) -> BTreeSet<ClusterFeature> {
+        ClusterFeature::SUPPORTED
+            .into_iter()
+            .filter(|feature| self.is_enabled(*feature))
+            .collect()
+    }
+}
+
+/// Cheaply cloneable view of the features enabled in the cluster, kept up to date in the
+/// background from a [`ClusterChangeStream`]. No feature is enabled until the first cluster
+/// changes are received.
+#[derive(Debug, Clone)]
+pub struct EnabledClusterFeatures {
+    enabled_features_rx: watch::Receiver<BTreeSet<ClusterFeature>>,
+}
+
+impl EnabledClusterFeatures {
+    pub fn spawn(mut cluster_change_stream: ClusterChangeStream) -> Self {
+        let (enabled_features_tx, enabled_features_rx) = watch::channel(BTreeSet::new());
+
+        tokio::spawn(async move {
+            let mut feature_gates = ClusterFeatureGates::default();
+
+            while let Some(cluster_change) = cluster_change_stream.next().await {
+                feature_gates.apply_cluster_change(&cluster_change);
+                let enabled_features = feature_gates.enabled_features();
+
+                enabled_features_tx.send_if_modified(|current_enabled_features| {
+                    if *current_enabled_features == enabled_features {
+                        return false;
+                    }
+                    *current_enabled_features = enabled_features;
+                    true
+                });
+                if enabled_features_tx.is_closed() {
+                    break;
+                }
+            }
+        });
+        Self {
+            enabled_features_rx,
+        }
+    }
+
+    pub fn is_enabled(&self, feature: ClusterFeature) -> bool {
+        self.enabled_features_rx.borrow().contains(&feature)
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(enabled_features: impl IntoIterator<Item = ClusterFeature>) -> Self {
+        let (_enabled_features_tx, enabled_features_rx) =
+            watch::channel(enabled_features.into_iter().collect());
+        Self {
+            enabled_features_rx,
+        }
+    }
+}
+
+/// Upgrade status of a node of the cluster.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct NodeUpgradeStatus {
+    /// The ID of the node.
+    pub node_id: String,
+    /// The version of the protocol spoken by the node. 0 for the nodes that predate feature
+    /// negotiation.
+    pub protocol_version: u32,
+    /// The features supported by the node.
+    pub features: BTreeSet<ClusterFeature>,
+}
+
+impl From<&ClusterNode> for NodeUpgradeStatus {
+    fn from(node: &ClusterNode) -> Self {
+        NodeUpgradeStatus {
+            node_id: node.node_id().to_string(),
+            protocol_version: node.protocol_version(),
+            features: node.features().clone(),
+        }
+    }
+}
+
+/// Upgrade status of the cluster: the features that are enabled because all the nodes support
+/// them, and the features that are pending until the remaining nodes are upgraded.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ClusterUpgradeStatus {
+    /// Whether all the nodes of the cluster speak the same protocol and support the same
+    /// features.
+    pub is_upgrade_complete: bool,
+    /// The minimum protocol version spoken by the nodes of the cluster.
+    pub min_protocol_version: u32,
+    /// The maximum protocol version spoken by the nodes of the cluster.
+    pub max_protocol_version: u32,
+    /// The features supported by all the nodes, which are enabled.
+    pub enabled_features: BTreeSet<ClusterFeature>,
+    /// The features supported by some nodes only, which are disabled until all the nodes
+    /// support them.
+    pub pending_features: BTreeSet<ClusterFeature>,
+    /// The upgrade status of each node.
+    pub nodes: Vec<NodeUpgradeStatus>,
+}
+
+impl ClusterUpgradeStatus {
+    pub(crate) fn from_nodes(nodes: Vec<NodeUpgradeStatus>) -> Self {
+        let min_protocol_version = nodes
+            .iter()
+            .map(|node| node.protocol_version)
+            .min()
+            .unwrap_or(PROTOCOL_VERSION);
+        let max_protocol_version = nodes
+            .iter()
+            .map(|node| node.protocol_version)
+            .max()
+            .unwrap_or(PROTOCOL_VERSION);
+        let all_features: BTreeSet<ClusterFeature> = nodes
+            .iter()
+            .flat_map(|node| node.features.iter().copied())
+            .collect();
+        let (enabled_features, pending_features): (BTreeSet<_>, BTreeSet<_>) = all_features
+            .into_iter()
+            .partition(|feature| nodes.iter().all(|node| node.features.contains(feature)));
+        let is_upgrade_complete =
+            min_protocol_version == max_protocol_version && pending_features.is_empty();
+
+        ClusterUpgradeStatus {
+            is_upgrade_complete,
+            min_protocol_version,
+            max_protocol_version,
+            enabled_features,
+            pending_features,
+            nodes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_feature_str_round_trip() {
+        for feature in ClusterFeature::SUPPORTED {
+            assert_eq!(feature.as_str().parse::<ClusterFeature>().unwrap(), feature);
+        }
+        "unknown_feature".parse::<ClusterFeature>().unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_features_and_protocol_version() {
+        let mut node_state = NodeState::for_test();
+        assert_eq!(parse_protocol_version(&node_state), 0);
+        assert!(parse_features(&node_state).is_empty());
+
+        node_state.set(PROTOCOL_VERSION_KEY, "2");
+        node_state.set(FEATURES_KEY, "ingester_ping,feature_from_the_future");
+        assert_eq!(parse_protocol_version(&node_state), 2);
+        assert_eq!(
+            parse_features(&node_state),
+            BTreeSet::from([ClusterFeature::IngesterPing])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cluster_feature_gates() {
+        let mut feature_gates = ClusterFeatureGates::default();
+        assert!(!feature_gates.is_enabled(ClusterFeature::IngesterPing));
+
+        let upgraded_node =
+            ClusterNode::for_test("upgraded-node", 1337, false, &["indexer"], &[]).await;
+        feature_gates.apply_cluster_change(&ClusterChange::Add(upgraded_node.clone()));
+        assert!(feature_gates.is_enabled(ClusterFeature::IngesterPing));
+
+        let legacy_node =
+            ClusterNode::for_test_with_features("legacy-node", 1339, &["indexer"], None).await;
+        feature_gates.apply_cluster_change(&ClusterChange::Add(legacy_node.clone()));
+        assert!(!feature_gates.is_enabled(ClusterFeature::IngesterPing));
+
+        feature_gates.apply_cluster_change(&ClusterChange::Remove(legacy_node));
+        assert!(feature_gates.is_enabled(ClusterFeature::IngesterPing));
+        assert_eq!(
+            feature_gates.enabled_features(),
+            BTreeSet::from(ClusterFeature::SUPPORTED)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enabled_cluster_features() {
+        let (cluster_change_stream, cluster_change_tx) = ClusterChangeStream::new_unbounded();
+        let enabled_features = EnabledClusterFeatures::spawn(cluster_change_stream);
+        assert!(!enabled_features.is_enabled(ClusterFeature::WalDocCompression));
+
+        let upgraded_node =
+            ClusterNode::for_test("upgraded-node", 1337, false, &["indexer"], &[]).await;
+        cluster_change_tx
+            .send(ClusterChange::Add(upgraded_node))
+            .unwrap();
+
+        let mut enabled_features_rx = enabled_features.enabled_features_rx.clone();
+        enabled_features_rx.changed().await.unwrap();
+        assert!(enabled_features.is_enabled(ClusterFeature::WalDocCompression));
+
+        let legacy_node =
+            ClusterNode::for_test_with_features("legacy-node", 1339, &["indexer"], None).await;
+        cluster_change_tx
+            .send(ClusterChange::Add(legacy_node))
+            .unwrap();
+
+        enabled_features_rx.changed().await.unwrap();
+        assert!(!enabled_features.is_enabled(ClusterFeature::WalDocCompression));
+    }
+
+    #[test]
+    fn test_cluster_upgrade_status() {
+        let upgrade_status = ClusterUpgradeStatus::from_nodes(vec![
+            NodeUpgradeStatus {
+                node_id: "upgraded-node".to_string(),
+                protocol_version: 1,
+                features: BTreeSet::from([
+                    ClusterFeature::IngesterPing,
+                    ClusterFeature::WalDocCompression,
+                ]),
+            },
+            NodeUpgradeStatus {
+                node_id: "legacy-node".to_string(),
+                protocol_version: 0,
+                features: BTreeSet::from([ClusterFeature::WalDocCompression]),
+            },
+        ]);
+        assert!(!upgrade_status.is_upgrade_complete);
+        assert_eq!(upgrade_status.min_protocol_version, 0);
+        assert_eq!(upgrade_status.max_protocol_version, 1);
+        assert_eq!(
+            upgrade_status.enabled_features,
+            BTreeSet::from([ClusterFeature::WalDocCompression])
+        );
+        assert_eq!(
+            upgrade_status.pending_features,
+            BTreeSet::from([ClusterFeature::IngesterPing])
+        );
+
+        let upgrade_status = ClusterUpgradeStatus::from_nodes(upgrade_status.nodes[..1].to_vec());
+        assert!(upgrade_status.is_upgrade_complete);
+        assert!(upgrade_status.pending_features.is_empty());
+    }
+}
@@ -21,6 +21,7 @@
 
 mod change;
 mod cluster;
+mod features;
 mod grpc_gossip;
 mod grpc_service;
 mod member;
@@ -50,6 +51,10 @@ pub use crate::cluster::{
     create_cluster_for_test, create_cluster_for_test_with_id, grpc_addr_from_listen_addr_for_test,
 };
 pub use crate::cluster::{Cluster, ClusterSnapshot, NodeIdSchema};
+pub use crate::features::{
+    ClusterFeature, ClusterFeatureGates, ClusterUpgradeStatus, EnabledClusterFeatures,
+    NodeUpgradeStatus, PROTOCOL_VERSION,
+};
 pub use crate::member::{ClusterMember, INDEXING_CPU_CAPACITY_KEY};
 pub use crate::node::ClusterNode;
 
@@ -25,6 +25,7 @@ use std::str::FromStr;
 use anyhow::Context;
 use chitchat::{ChitchatId, NodeState, Version};
 use quickwit_proto::indexing::{CpuCapacity, IndexingTask};
+use quickwit_proto::ingest::ingester::IngesterStatus;
 use quickwit_proto::types::NodeId;
 use tracing::{error, warn};
 
@@ -41,6 +42,16 @@ pub(crate) const READINESS_KEY: &str = "readiness";
 pub(crate) const READINESS_VALUE_READY: &str = "READY";
 pub(crate) const READINESS_VALUE_NOT_READY: &str = "NOT_READY";
 
+// Maintenance key used to signal that a node is being drained. The key is absent when the node is
+// not in maintenance.
+pub(crate) const MAINTENANCE_KEY: &str = "maintenance";
+pub(crate) const MAINTENANCE_VALUE_DRAINING: &str = "DRAINING";
+
+// Ingester status key used to advertise the status of the ingester running on a node, so that the
+// other nodes stop routing to decommissioned ingesters. The key is absent until the ingester
+// reports its status.
+pub(crate) const INGESTER_STATUS_KEY: &str = "ingester_status";
+
 pub const INDEXING_CPU_CAPACITY_KEY: &str = "indexing_cpu_capacity";
 
 pub(crate) trait NodeStateExt {
@@ -48,6 +59,10 @@ pub(crate) trait NodeStateExt {
 
     fn is_ready(&self) -> bool;
 
+    fn is_in_maintenance(&self) -> bool;
+
+    fn ingester_status(&self) -> Option<IngesterStatus>;
+
     fn size_bytes(&self) -> usize;
 }
 
@@ -70,6 +85,25 @@ impl NodeStateExt for NodeState {
             .unwrap_or(false)
     }
 
+    fn is_in_maintenance(&self) -> bool {
+        self.get(MAINTENANCE_KEY)
+            .map(|maintenance_value| maintenance_value == MAINTENANCE_VALUE_DRAINING)
+            .unwrap_or(false)
+    }
+
+    fn ingester_status(&self) -> Option<IngesterStatus> {
+        let ingester_status_str = self.get(INGESTER_STATUS_KEY)?;
+        let ingester_status_opt = IngesterStatus::from_json_str_name(ingester_status_str);
+
+        if ingester_status_opt.is_none() {
+            warn!(
+                ingester_status=%ingester_status_str,
+                "received an unknown ingester status from node"
+            );
+        }
+        ingester_status_opt
+    }
+
     // TODO: Expose more accurate size of the state in Chitchat.
     fn size_bytes(&self) -> usize {
         const SIZE_OF_VERSION: usize = size_of::<Version>();
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt::Debug;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -25,10 +25,12 @@ use std::sync::Arc;
 use chitchat::{ChitchatId, NodeState};
 use quickwit_config::service::QuickwitService;
 use quickwit_proto::indexing::{CpuCapacity, IndexingTask};
+use quickwit_proto::ingest::ingester::IngesterStatus;
 use quickwit_proto::types::NodeIdRef;
 use tonic::transport::Channel;
 
-use crate::member::build_cluster_member;
+use crate::features::{parse_features, parse_protocol_version, ClusterFeature};
+use crate::member::{build_cluster_member, NodeStateExt};
 
 #[derive(Clone)]
 pub struct ClusterNode {
@@ -52,7 +54,11 @@ impl ClusterNode {
             indexing_tasks: member.indexing_tasks,
             indexing_capacity: member.indexing_cpu_capacity,
             is_ready: member.is_ready,
+            is_in_maintenance: node_state.is_in_maintenance(),
+            ingester_status_opt: node_state.ingester_status(),
             is_self_node,
+            protocol_version: parse_protocol_version(node_state),
+            features: parse_features(node_state),
         };
         let node = ClusterNode {
             inner: Arc::new(inner),
@@ -68,10 +74,74 @@ impl ClusterNode {
         enabled_services: &[&str],
         indexing_tasks: &[IndexingTask],
     ) -> Self {
+        Self::for_test_inner(
+            node_id,
+            port,
+            is_self_node,
+            enabled_services,
+            indexing_tasks,
+            Some(&ClusterFeature::SUPPORTED),
+            None,
+        )
+        .await
+    }
+
+    /// Creates a node advertising the given features, or, if `features_opt` is `None`, a node
+    /// that predates feature negotiation.
+    #[cfg(any(test, feature = "testsuite"))]
+    pub async fn for_test_with_features(
+        node_id: &str,
+        port: u16,
+        enabled_services: &[&str],
+        features_opt: Option<&[ClusterFeature]>,
+    ) -> Self {
+        Self::for_test_inner(
+            node_id,
+            port,
+            false,
+            enabled_services,
+            &[],
+            features_opt,
+            None,
+        )
+        .await
+    }
+
+    /// Creates an indexer node whose ingester advertises the given status.
+    #[cfg(any(test, feature = "testsuite"))]
+    pub async fn for_test_with_ingester_status(
+        node_id: &str,
+        port: u16,
+        ingester_status: IngesterStatus,
+    ) -> Self {
+        Self::for_test_inner(
+            node_id,
+            port,
+            false,
+            &["indexer"],
+            &[],
+            Some(&ClusterFeature::SUPPORTED),
+            Some(ingester_status),
+        )
+        .await
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    async fn for_test_inner(
+        node_id: &str,
+        port: u16,
+        is_self_node: bool,
+        enabled_services: &[&str],
+        indexing_tasks: &[IndexingTask],
+        features_opt: Option<&[ClusterFeature]>,
+        ingester_status_opt: Option<IngesterStatus>,
+    ) -> Self {
+        use itertools::Itertools;
         use quickwit_common::tower::make_channel;
 
         use crate::cluster::set_indexing_tasks_in_node_state;
-        use crate::member::{ENABLED_SERVICES_KEY, GRPC_ADVERTISE_ADDR_KEY};
+        use crate::features::{FEATURES_KEY, PROTOCOL_VERSION, PROTOCOL_VERSION_KEY};
+        use crate::member::{ENABLED_SERVICES_KEY, GRPC_ADVERTISE_ADDR_KEY, INGESTER_STATUS_KEY};
 
         let gossip_advertise_addr = ([127, 0, 0, 1], port).into();
         let grpc_advertise_addr = ([127, 0, 0, 1], port + 1).into();
@@ -81,6 +151,14 @@ impl ClusterNode {
         node_state.set(ENABLED_SERVICES_KEY, enabled_services.join(","));
         node_state.set(GRPC_ADVERTISE_ADDR_KEY, grpc_advertise_addr.to_string());
         set_indexing_tasks_in_node_state(indexing_tasks, &mut node_state);
+
+        if let Some(features) = features_opt {
+            node_state.set(PROTOCOL_VERSION_KEY, PROTOCOL_VERSION.to_string());
+            node_state.set(FEATURES_KEY, features.iter().join(","));
+        }
+        if let Some(ingester_status) = ingester_status_opt {
+            node_state.set(INGESTER_STATUS_KEY, ingester_status.as_json_str_name());
+        }
         Self::try_new(chitchat_id, &node_state, channel, is_self_node).unwrap()
     }
 
@@ -134,9 +212,34 @@ impl ClusterNode {
         self.inner.is_ready
     }
 
+    /// Returns whether the node is in maintenance, i.e. it is being drained and should gradually
+    /// stop receiving work.
+    pub fn is_in_maintenance(&self) -> bool {
+        self.inner.is_in_maintenance
+    }
+
+    /// Returns the status advertised by the ingester running on the node, if any.
+    pub fn ingester_status(&self) -> Option<IngesterStatus> {
+        self.inner.ingester_status_opt
+    }
+
     pub fn is_self_node(&self) -> bool {
         self.inner.is_self_node
     }
+
+    /// Returns the version of the protocol spoken by the node.
+    pub fn protocol_version(&self) -> u32 {
+        self.inner.protocol_version
+    }
+
+    /// Returns the wire features supported by the node.
+    pub fn features(&self) -> &BTreeSet<ClusterFeature> {
+        &self.inner.features
+    }
+
+    pub fn supports_feature(&self, feature: ClusterFeature) -> bool {
+        self.inner.features.contains(&feature)
+    }
 }
 
 impl Debug for ClusterNode {
@@ -157,7 +260,10 @@ impl PartialEq for ClusterNode {
             && self.inner.grpc_advertise_addr == other.inner.grpc_advertise_addr
             && self.inner.indexing_tasks == other.inner.indexing_tasks
             && self.inner.is_ready == other.inner.is_ready
+            && self.inner.is_in_maintenance == other.inner.is_in_maintenance
+            && self.inner.ingester_status_opt == other.inner.ingester_status_opt
             && self.inner.is_self_node == other.inner.is_self_node
+            && self.inner.features == other.inner.features
     }
 }
 
@@ -169,5 +275,9 @@ struct InnerNode {
     indexing_tasks: Vec<IndexingTask>,
     indexing_capacity: CpuCapacity,
     is_ready: bool,
+    is_in_maintenance: bool,
+    ingester_status_opt: Option<IngesterStatus>,
     is_self_node: bool,
+    protocol_version: u32,
+    features: BTreeSet<ClusterFeature>,
 }
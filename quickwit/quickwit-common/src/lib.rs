@@ -79,6 +79,11 @@ pub fn split_file(split_id: impl Display) -> String {
     format!("{split_id}.split")
 }
 
+/// Name of the file storing the term digests of a split, next to the split file.
+pub fn term_digests_file(split_id: impl Display) -> String {
+    format!("{split_id}.digests")
+}
+
 pub fn get_from_env<T: FromStr + Debug>(key: &str, default_value: T) -> T {
     if let Ok(value_str) = std::env::var(key) {
         if let Ok(value) = T::from_str(&value_str) {
@@ -136,6 +141,11 @@ pub fn is_false(value: &bool) -> bool {
     !*value
 }
 
+/// For use with the `skip_serializing_if` serde attribute.
+pub fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
 pub fn no_color() -> bool {
     matches!(env::var("NO_COLOR"), Ok(value) if !value.is_empty())
 }
@@ -161,6 +161,24 @@ pub fn new_histogram_vec<const N: usize>(
     subsystem: &str,
     const_labels: &[(&str, &str)],
     label_names: [&str; N],
+) -> HistogramVec<N> {
+    new_histogram_vec_with_buckets(
+        name,
+        help,
+        subsystem,
+        const_labels,
+        label_names,
+        prometheus::DEFAULT_BUCKETS.to_vec(),
+    )
+}
+
+pub fn new_histogram_vec_with_buckets<const N: usize>(
+    name: &str,
+    help: &str,
+    subsystem: &str,
+    const_labels: &[(&str, &str)],
+    label_names: [&str; N],
+    buckets: Vec<f64>,
 ) -> HistogramVec<N> {
     let owned_const_labels: HashMap<String, String> = const_labels
         .iter()
@@ -169,7 +187,8 @@ pub fn new_histogram_vec<const N: usize>(
     let histogram_opts = HistogramOpts::new(name, help)
         .namespace("quickwit")
         .subsystem(subsystem)
-        .const_labels(owned_const_labels);
+        .const_labels(owned_const_labels)
+        .buckets(buckets);
     let underlying = PrometheusHistogramVec::new(histogram_opts, &label_names)
         .expect("failed to create histogram vec");
 
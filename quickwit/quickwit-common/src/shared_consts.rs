@@ -19,6 +19,10 @@
 
 use std::time::Duration;
 
+/// Target of the audit log events. The audit logs are always recorded, whatever the log level, and
+/// can be written to a dedicated file.
+pub const AUDIT_LOG_TARGET: &str = "quickwit::audit";
+
 /// Field name reserved for storing the dynamically indexed fields.
 pub const FIELD_PRESENCE_FIELD_NAME: &str = "_field_presence";
 
@@ -41,3 +45,11 @@ pub const INGESTER_PRIMARY_SHARDS_PREFIX: &str = "ingester.primary_shards:";
 
 /// File name for the encoded list of fields in the split
 pub const SPLIT_FIELDS_FILE_NAME: &str = "split_fields";
+
+/// File name for the split manifest, the self-describing metadata of the split embedded in the
+/// split file.
+pub const SPLIT_MANIFEST_FILE_NAME: &str = "split_manifest.json";
+
+/// File name for the index manifest, the copy of the index config and sources written to the
+/// index storage.
+pub const INDEX_MANIFEST_FILE_NAME: &str = "index_manifest.json";
@@ -178,7 +178,7 @@ where
     }
 
     /// Removes a value from the pool.
-    fn remove(&self, key: &K) {
+    pub fn remove(&self, key: &K) {
         self.pool
             .write()
             .expect("lock should not be poisoned")
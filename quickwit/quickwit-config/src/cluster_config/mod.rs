@@ -19,14 +19,18 @@
 
 use quickwit_common::uri::Uri;
 
+use crate::ShardIdStrategy;
+
 /// An embryo of a cluster config.
 // TODO: Move to `quickwit-config` and version object.
 #[derive(Debug, Clone)]
 pub struct ClusterConfig {
     pub cluster_id: String,
     pub auto_create_indexes: bool,
+    pub enable_force_close_shards: bool,
     pub default_index_root_uri: Uri,
     pub replication_factor: usize,
+    pub shard_id_strategy: ShardIdStrategy,
 }
 
 impl ClusterConfig {
@@ -35,8 +39,10 @@ impl ClusterConfig {
         ClusterConfig {
             cluster_id: "test-cluster".to_string(),
             auto_create_indexes: false,
+            enable_force_close_shards: false,
             default_index_root_uri: Uri::for_test("ram:///indexes"),
             replication_factor: 1,
+            shard_id_strategy: ShardIdStrategy::Ulid,
         }
     }
 }
@@ -0,0 +1,191 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::{bail, ensure};
+use bytesize::ByteSize;
+use serde::{Deserialize, Serialize};
+
+/// Cluster-wide settings that can be updated at runtime, without restarting the nodes.
+///
+/// The settings are stored in the metastore and gossiped to the nodes of the cluster. A setting
+/// left unset falls back to the value defined in the node config or to the built-in default.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterSettings {
+    /// Overrides `searcher.max_num_concurrent_split_searches`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_num_concurrent_split_searches: Option<usize>,
+    /// Overrides `searcher.max_num_concurrent_split_streams`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_num_concurrent_split_streams: Option<usize>,
+    /// Overrides `searcher.split_footer_cache_capacity`.
+    #[schema(value_type = String)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_footer_cache_capacity: Option<ByteSize>,
+    /// Overrides `searcher.partial_request_cache_capacity`.
+    #[schema(value_type = String)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_request_cache_capacity: Option<ByteSize>,
+    /// Average ingestion rate per shard above which the control plane opens new shards.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard_scale_up_threshold_mib_per_sec: Option<f32>,
+    /// Average ingestion rate per shard below which the control plane closes shards.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard_scale_down_threshold_mib_per_sec: Option<f32>,
+}
+
+impl ClusterSettings {
+    /// Returns an error if any of the settings holds an invalid value.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(max_num_concurrent_split_searches) = self.max_num_concurrent_split_searches {
+            ensure!(
+                max_num_concurrent_split_searches > 0,
+                "`max_num_concurrent_split_searches` must be strictly positive"
+            );
+        }
+        if let Some(max_num_concurrent_split_streams) = self.max_num_concurrent_split_streams {
+            ensure!(
+                max_num_concurrent_split_streams > 0,
+                "`max_num_concurrent_split_streams` must be strictly positive"
+            );
+        }
+        match (
+            self.shard_scale_up_threshold_mib_per_sec,
+            self.shard_scale_down_threshold_mib_per_sec,
+        ) {
+            (Some(scale_up_threshold), Some(scale_down_threshold)) => {
+                ensure!(
+                    scale_up_threshold.is_finite() && scale_up_threshold > 0.,
+                    "`shard_scale_up_threshold_mib_per_sec` must be a strictly positive number"
+                );
+                ensure!(
+                    scale_down_threshold.is_finite() && scale_down_threshold > 0.,
+                    "`shard_scale_down_threshold_mib_per_sec` must be a strictly positive number"
+                );
+                ensure!(
+                    scale_down_threshold < scale_up_threshold,
+                    "`shard_scale_down_threshold_mib_per_sec` ({scale_down_threshold}) must be \
+                     lower than `shard_scale_up_threshold_mib_per_sec` ({scale_up_threshold})"
+                );
+            }
+            (None, None) => {}
+            _ => bail!(
+                "`shard_scale_up_threshold_mib_per_sec` and \
+                 `shard_scale_down_threshold_mib_per_sec` must be set together"
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_settings_serde() {
+        let cluster_settings: ClusterSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(cluster_settings, ClusterSettings::default());
+        assert_eq!(serde_json::to_string(&cluster_settings).unwrap(), "{}");
+
+        let cluster_settings_json = r#"{
+            "max_num_concurrent_split_searches": 50,
+            "split_footer_cache_capacity": "1G",
+            "shard_scale_up_threshold_mib_per_sec": 4.0,
+            "shard_scale_down_threshold_mib_per_sec": 1.0
+        }"#;
+        let cluster_settings: ClusterSettings =
+            serde_json::from_str(cluster_settings_json).unwrap();
+        assert_eq!(cluster_settings.max_num_concurrent_split_searches, Some(50));
+        assert_eq!(cluster_settings.max_num_concurrent_split_streams, None);
+        assert_eq!(
+            cluster_settings.split_footer_cache_capacity,
+            Some(ByteSize::gb(1))
+        );
+        assert_eq!(cluster_settings.partial_request_cache_capacity, None);
+        assert_eq!(
+            cluster_settings.shard_scale_up_threshold_mib_per_sec,
+            Some(4.)
+        );
+        assert_eq!(
+            cluster_settings.shard_scale_down_threshold_mib_per_sec,
+            Some(1.)
+        );
+
+        let error = serde_json::from_str::<ClusterSettings>(r#"{"unknown_setting": 1}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown field `unknown_setting`"));
+    }
+
+    #[test]
+    fn test_cluster_settings_validate() {
+        ClusterSettings::default().validate().unwrap();
+
+        let cluster_settings = ClusterSettings {
+            max_num_concurrent_split_searches: Some(0),
+            ..Default::default()
+        };
+        cluster_settings.validate().unwrap_err();
+
+        let cluster_settings = ClusterSettings {
+            max_num_concurrent_split_streams: Some(0),
+            ..Default::default()
+        };
+        cluster_settings.validate().unwrap_err();
+
+        let cluster_settings = ClusterSettings {
+            shard_scale_up_threshold_mib_per_sec: Some(4.),
+            ..Default::default()
+        };
+        let error = cluster_settings.validate().unwrap_err().to_string();
+        assert!(error.contains("must be set together"));
+
+        let cluster_settings = ClusterSettings {
+            shard_scale_up_threshold_mib_per_sec: Some(1.),
+            shard_scale_down_threshold_mib_per_sec: Some(4.),
+            ..Default::default()
+        };
+        let error = cluster_settings.validate().unwrap_err().to_string();
+        assert!(error.contains("must be lower than"));
+
+        let cluster_settings = ClusterSettings {
+            shard_scale_up_threshold_mib_per_sec: Some(f32::NAN),
+            shard_scale_down_threshold_mib_per_sec: Some(1.),
+            ..Default::default()
+        };
+        cluster_settings.validate().unwrap_err();
+
+        let cluster_settings = ClusterSettings {
+            max_num_concurrent_split_searches: Some(50),
+            max_num_concurrent_split_streams: Some(10),
+            split_footer_cache_capacity: Some(ByteSize::mb(500)),
+            partial_request_cache_capacity: Some(ByteSize::b(0)),
+            shard_scale_up_threshold_mib_per_sec: Some(4.),
+            shard_scale_down_threshold_mib_per_sec: Some(1.),
+        };
+        cluster_settings.validate().unwrap();
+    }
+}
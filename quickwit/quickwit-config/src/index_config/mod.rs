@@ -20,29 +20,32 @@
 pub(crate) mod serialize;
 
 use std::collections::BTreeSet;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{ensure, Context};
+use anyhow::{bail, ensure, Context};
 use bytesize::ByteSize;
 use chrono::Utc;
 use cron::Schedule;
 use humantime::parse_duration;
+use quickwit_common::is_zero;
 use quickwit_common::uri::Uri;
+use quickwit_doc_mapper::tag_pruning::field_tag;
 use quickwit_doc_mapper::{
     DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, Mode, ModeType,
     QuickwitJsonOptions, TokenizerEntry,
 };
 use quickwit_proto::types::IndexId;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 pub use serialize::load_index_config_from_user_config;
 use tracing::warn;
 
 use crate::index_config::serialize::VersionedIndexConfig;
 use crate::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
-use crate::TestableForRegression;
+use crate::{validate_identifier, TestableForRegression, WasmPluginConfig};
 
 // Note(fmassot): `DocMapping` is a struct only used for
 // serialization/deserialization of `DocMapper` parameters.
@@ -65,12 +68,21 @@ pub struct DocMapping {
     #[schema(value_type = Vec<String>)]
     #[serde(default)]
     pub tag_fields: BTreeSet<String>,
+    #[schema(value_type = Vec<String>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub term_digest_fields: BTreeSet<String>,
     #[serde(default)]
     pub store_source: bool,
     #[serde(default)]
     pub index_field_presence: bool,
     #[serde(default)]
     pub timestamp_field: Option<String>,
+    /// Additional datetime fields whose time range is recorded for each split, so that range
+    /// queries on them can prune splits like the timestamp field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub secondary_timestamp_fields: Vec<String>,
     #[serde_multikey(
         deserializer = Mode::from_parts,
         serializer = Mode::into_parts,
@@ -93,6 +105,11 @@ pub struct DocMapping {
     /// Record document length
     #[serde(default)]
     pub document_length: bool,
+    /// Version of the doc mapping, incremented by the metastore every time the doc mapping of the
+    /// index is updated. Splits record the version they were built with.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    pub doc_mapping_version: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -147,6 +164,481 @@ impl Default for IndexingResources {
     }
 }
 
+/// Configuration of the optional enrichment stage of the indexing pipeline. Documents are sent in
+/// batches to an external gRPC service implementing the `quickwit.enrichment.DocumentEnricher`
+/// service, which returns them enriched.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EnrichmentConfig {
+    /// Endpoint of the enrichment service, for instance `http://enricher:50051`.
+    pub endpoint: String,
+    /// Maximum number of documents sent to the enrichment service in a single request.
+    #[schema(default = 1_000)]
+    #[serde(default = "EnrichmentConfig::default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Timeout of a single enrichment request.
+    #[schema(default = 1_000)]
+    #[serde(default = "EnrichmentConfig::default_timeout_millis")]
+    pub timeout_millis: u64,
+    /// Number of consecutive failed requests after which the enrichment stage is bypassed.
+    #[schema(default = 5)]
+    #[serde(default = "EnrichmentConfig::default_failure_threshold")]
+    pub failure_threshold: usize,
+    /// Duration during which the enrichment stage is bypassed once the failure threshold is
+    /// reached.
+    #[schema(default = 30)]
+    #[serde(default = "EnrichmentConfig::default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl EnrichmentConfig {
+    fn default_max_batch_size() -> usize {
+        1_000
+    }
+
+    fn default_timeout_millis() -> u64 {
+        1_000
+    }
+
+    fn default_failure_threshold() -> usize {
+        5
+    }
+
+    fn default_cooldown_secs() -> u64 {
+        30
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_millis)
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs)
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            max_batch_size: Self::default_max_batch_size(),
+            timeout_millis: Self::default_timeout_millis(),
+            failure_threshold: Self::default_failure_threshold(),
+            cooldown_secs: Self::default_cooldown_secs(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.endpoint.starts_with("http://") || self.endpoint.starts_with("https://"),
+            "enrichment endpoint `{}` must start with `http://` or `https://`",
+            self.endpoint
+        );
+        ensure!(
+            self.max_batch_size > 0,
+            "enrichment `max_batch_size` must be strictly positive"
+        );
+        ensure!(
+            self.timeout_millis > 0,
+            "enrichment `timeout_millis` must be strictly positive"
+        );
+        ensure!(
+            self.failure_threshold > 0,
+            "enrichment `failure_threshold` must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+/// Configuration of the optional embedding stage of the indexing pipeline. The values of the
+/// source fields are sent in batches to an external HTTP embedding service, and the returned
+/// vectors are stored into the target fields.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EmbeddingConfig {
+    /// Endpoint of the embedding service, for instance `http://embedder:8080/embed`.
+    pub endpoint: String,
+    /// Text fields to embed and fields receiving their embeddings.
+    pub fields: Vec<EmbeddingFieldConfig>,
+    /// Maximum number of texts sent to the embedding service in a single request.
+    #[schema(default = 32)]
+    #[serde(default = "EmbeddingConfig::default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Maximum number of concurrent requests sent to the embedding service.
+    #[schema(default = 4)]
+    #[serde(default = "EmbeddingConfig::default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Timeout of a single embedding request.
+    #[schema(default = 5_000)]
+    #[serde(default = "EmbeddingConfig::default_timeout_millis")]
+    pub timeout_millis: u64,
+    /// What to do with the documents that could not be embedded.
+    #[serde(default)]
+    pub on_failure: EmbeddingFailurePolicy,
+}
+
+/// Text field to embed and field receiving its embedding.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EmbeddingFieldConfig {
+    /// Name of the text field to embed.
+    pub source_field: String,
+    /// Name of the field receiving the embedding, usually declared as `array<f64>`.
+    pub target_field: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingFailurePolicy {
+    /// The documents are indexed without embedding.
+    #[default]
+    Skip,
+    /// The documents are dropped and counted as invalid.
+    Drop,
+    /// The indexing pipeline fails and restarts from the last checkpoint.
+    Fail,
+}
+
+impl EmbeddingConfig {
+    fn default_max_batch_size() -> usize {
+        32
+    }
+
+    fn default_max_concurrency() -> usize {
+        4
+    }
+
+    fn default_timeout_millis() -> u64 {
+        5_000
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_millis)
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(endpoint: &str, source_field: &str, target_field: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            fields: vec![EmbeddingFieldConfig {
+                source_field: source_field.to_string(),
+                target_field: target_field.to_string(),
+            }],
+            max_batch_size: Self::default_max_batch_size(),
+            max_concurrency: Self::default_max_concurrency(),
+            timeout_millis: Self::default_timeout_millis(),
+            on_failure: EmbeddingFailurePolicy::default(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.endpoint.starts_with("http://") || self.endpoint.starts_with("https://"),
+            "embedding endpoint `{}` must start with `http://` or `https://`",
+            self.endpoint
+        );
+        ensure!(
+            !self.fields.is_empty(),
+            "embedding `fields` must not be empty"
+        );
+        let mut target_fields = BTreeSet::new();
+
+        for field_config in &self.fields {
+            ensure!(
+                field_config.source_field != field_config.target_field,
+                "embedding source and target fields must differ, got `{}` for both",
+                field_config.source_field
+            );
+            ensure!(
+                target_fields.insert(&field_config.target_field),
+                "embedding target field `{}` is declared more than once",
+                field_config.target_field
+            );
+        }
+        ensure!(
+            self.max_batch_size > 0,
+            "embedding `max_batch_size` must be strictly positive"
+        );
+        ensure!(
+            self.max_concurrency > 0,
+            "embedding `max_concurrency` must be strictly positive"
+        );
+        ensure!(
+            self.timeout_millis > 0,
+            "embedding `timeout_millis` must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+/// Maximum aggregate ingestion rate of an index, enforced by the control plane: once the ingestion
+/// rate observed over the open shards reaches the quota, no more shards are opened. Indexes
+/// labeled with the same `tenant_id` share a single quota.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IngestionQuotaConfig {
+    /// Tenant the index belongs to. When several indexes share the same tenant, their ingestion
+    /// rates are aggregated and the lowest quota applies.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// Maximum aggregate ingestion rate in MiB/s.
+    pub max_ingestion_rate_mib_per_sec: f32,
+}
+
+impl IngestionQuotaConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_ingestion_rate_mib_per_sec.is_finite()
+                && self.max_ingestion_rate_mib_per_sec > 0.,
+            "ingestion quota `max_ingestion_rate_mib_per_sec` must be strictly positive"
+        );
+        if let Some(tenant_id) = &self.tenant_id {
+            ensure!(
+                !tenant_id.is_empty(),
+                "ingestion quota `tenant_id` must not be empty"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Configuration of the dead-letter index of an index. The documents that fail the VRL transform
+/// of a source or the doc mapping of the index are written, along with the reason of the failure
+/// and their original payload, to the dead-letter index instead of being dropped.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DeadLetterConfig {
+    /// ID of the index receiving the rejected documents. The index must exist and be fed by the
+    /// ingest API.
+    pub index_id: String,
+}
+
+impl DeadLetterConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        validate_identifier("dead-letter index ID", &self.index_id)
+    }
+}
+
+/// Configuration of the ingest mirror of an index. A share of the valid documents of the index is
+/// duplicated asynchronously to another index, of the local cluster or of a remote one, for
+/// instance to try out a new doc mapping or to warm up a migration target with production traffic.
+/// Mirroring never slows down indexing: when the target cannot keep up, the mirrored documents are
+/// dropped.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IngestMirrorConfig {
+    /// ID of the index receiving the mirrored documents. The index must exist and, on the local
+    /// cluster, be fed by the ingest API.
+    pub index_id: String,
+    /// REST endpoint of the remote cluster hosting the target index, for instance
+    /// `http://quickwit-staging:7280`. When unset, the documents are mirrored to the local cluster.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Percentage of the valid documents to mirror.
+    #[schema(default = 100.0)]
+    #[serde(default = "IngestMirrorConfig::default_percentage")]
+    pub percentage: f32,
+    /// Maximum number of batches of mirrored documents waiting to be sent. Batches are dropped
+    /// once the limit is reached.
+    #[schema(default = 16)]
+    #[serde(default = "IngestMirrorConfig::default_max_pending_batches")]
+    pub max_pending_batches: usize,
+    /// Timeout of a single mirroring request.
+    #[schema(default = 5_000)]
+    #[serde(default = "IngestMirrorConfig::default_timeout_millis")]
+    pub timeout_millis: u64,
+}
+
+impl IngestMirrorConfig {
+    fn default_percentage() -> f32 {
+        100.
+    }
+
+    fn default_max_pending_batches() -> usize {
+        16
+    }
+
+    fn default_timeout_millis() -> u64 {
+        5_000
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_millis)
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(index_id: &str) -> Self {
+        Self {
+            index_id: index_id.to_string(),
+            endpoint: None,
+            percentage: Self::default_percentage(),
+            max_pending_batches: Self::default_max_pending_batches(),
+            timeout_millis: Self::default_timeout_millis(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        validate_identifier("ingest mirror index ID", &self.index_id)?;
+
+        if let Some(endpoint) = &self.endpoint {
+            ensure!(
+                endpoint.starts_with("http://") || endpoint.starts_with("https://"),
+                "ingest mirror endpoint `{endpoint}` must start with `http://` or `https://`"
+            );
+        }
+        ensure!(
+            self.percentage > 0. && self.percentage <= 100.,
+            "ingest mirror `percentage` must be in the range ]0, 100]"
+        );
+        ensure!(
+            self.max_pending_batches > 0,
+            "ingest mirror `max_pending_batches` must be strictly positive"
+        );
+        ensure!(
+            self.timeout_millis > 0,
+            "ingest mirror `timeout_millis` must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+/// Trace-aware sampling of the spans exported to the index via OTLP. The decision is made for each
+/// export request: the traces of the request with at least one error span are kept, and the other
+/// traces are sampled by trace ID. The spans of a trace exported in separate requests are sampled
+/// independently, so an error span does not keep the OK spans of its trace exported earlier or
+/// later.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TraceSamplingConfig {
+    /// Percentage of the traces without any error span to keep.
+    pub ok_traces_sample_percent: u8,
+    /// ID of the index receiving the span counts and durations of the sampled-out spans, rolled up
+    /// per service, operation, and minute. When unset, the sampled-out spans are only counted in
+    /// the metrics of the node.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollup_index_id: Option<String>,
+}
+
+impl TraceSamplingConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.ok_traces_sample_percent <= 100,
+            "trace sampling `ok_traces_sample_percent` must be in the range [0, 100]"
+        );
+        if let Some(rollup_index_id) = &self.rollup_index_id {
+            validate_identifier("trace sampling rollup index ID", rollup_index_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Separate merge concurrency budgets for the recent and the historical splits of an index. The
+/// merges of a historical backfill are typically numerous and heavy: capping their concurrency
+/// leaves merge slots available to the splits holding fresh data.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MergeThrottlingConfig {
+    /// A merge is recent if its most recent document is younger than this period, expressed in a
+    /// human-friendly way (`1 day`, `7 days`, ...), and historical otherwise.
+    pub recent_period: String,
+    /// Maximum number of concurrent merges of recent splits of the index on an indexer.
+    #[schema(value_type = Option<usize>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_recent_merges: Option<NonZeroUsize>,
+    /// Maximum number of concurrent merges of historical splits of the index on an indexer.
+    #[schema(value_type = Option<usize>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_historical_merges: Option<NonZeroUsize>,
+}
+
+impl MergeThrottlingConfig {
+    pub fn recent_period(&self) -> anyhow::Result<Duration> {
+        parse_duration(&self.recent_period).with_context(|| {
+            format!(
+                "failed to parse merge throttling recent period `{}`",
+                self.recent_period
+            )
+        })
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.recent_period()?;
+        ensure!(
+            self.max_concurrent_recent_merges.is_some()
+                || self.max_concurrent_historical_merges.is_some(),
+            "merge throttling requires `max_concurrent_recent_merges` or \
+             `max_concurrent_historical_merges` to be set"
+        );
+        Ok(())
+    }
+}
+
+/// Adapts the target number of documents of the splits of an index to the way the index is
+/// queried. Indexes serving mostly selective queries get smaller splits, which the searchers prune
+/// more effectively, while indexes serving mostly scans and aggregations get larger splits, which
+/// are cheaper to read in full.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveSplitSizeConfig {
+    /// Target number of documents of the splits of an index serving mostly selective queries.
+    #[schema(default = 1_000_000)]
+    #[serde(default = "AdaptiveSplitSizeConfig::default_min_split_num_docs_target")]
+    pub min_split_num_docs_target: usize,
+    /// Target number of documents of the splits of an index serving mostly scans and
+    /// aggregations.
+    #[schema(default = 40_000_000)]
+    #[serde(default = "AdaptiveSplitSizeConfig::default_max_split_num_docs_target")]
+    pub max_split_num_docs_target: usize,
+    /// Number of queries to observe before the target departs from `split_num_docs_target`.
+    #[schema(default = 100)]
+    #[serde(default = "AdaptiveSplitSizeConfig::default_min_num_queries")]
+    pub min_num_queries: u64,
+}
+
+impl AdaptiveSplitSizeConfig {
+    fn default_min_split_num_docs_target() -> usize {
+        1_000_000
+    }
+
+    fn default_max_split_num_docs_target() -> usize {
+        40_000_000
+    }
+
+    fn default_min_num_queries() -> u64 {
+        100
+    }
+
+    pub fn validate(&self, split_num_docs_target: usize) -> anyhow::Result<()> {
+        ensure!(
+            self.min_split_num_docs_target > 0,
+            "adaptive split size `min_split_num_docs_target` must be strictly positive"
+        );
+        ensure!(
+            self.min_split_num_docs_target <= split_num_docs_target
+                && split_num_docs_target <= self.max_split_num_docs_target,
+            "adaptive split size requires `min_split_num_docs_target` ({}) <= \
+             `split_num_docs_target` ({split_num_docs_target}) <= `max_split_num_docs_target` ({})",
+            self.min_split_num_docs_target,
+            self.max_split_num_docs_target
+        );
+        Ok(())
+    }
+}
+
+impl Default for AdaptiveSplitSizeConfig {
+    fn default() -> Self {
+        Self {
+            min_split_num_docs_target: Self::default_min_split_num_docs_target(),
+            max_split_num_docs_target: Self::default_max_split_num_docs_target(),
+            min_num_queries: Self::default_min_num_queries(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct IndexingSettings {
@@ -170,6 +662,32 @@ pub struct IndexingSettings {
     pub merge_policy: MergePolicyConfig,
     #[serde(default)]
     pub resources: IndexingResources,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enrichment: Option<EnrichmentConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<EmbeddingConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingestion_quota: Option<IngestionQuotaConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter: Option<DeadLetterConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingest_mirror: Option<IngestMirrorConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_sampling: Option<TraceSamplingConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_throttling: Option<MergeThrottlingConfig>,
+    /// When set, the target number of documents of the splits follows the query access patterns
+    /// of the index instead of being fixed to `split_num_docs_target`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adaptive_split_size: Option<AdaptiveSplitSizeConfig>,
 }
 
 impl IndexingSettings {
@@ -211,6 +729,14 @@ impl Default for IndexingSettings {
             split_num_docs_target: Self::default_split_num_docs_target(),
             merge_policy: MergePolicyConfig::default(),
             resources: IndexingResources::default(),
+            enrichment: None,
+            embedding: None,
+            ingestion_quota: None,
+            dead_letter: None,
+            ingest_mirror: None,
+            trace_sampling: None,
+            merge_throttling: None,
+            adaptive_split_size: None,
         }
     }
 }
@@ -220,6 +746,189 @@ impl Default for IndexingSettings {
 pub struct SearchSettings {
     #[serde(default)]
     pub default_search_fields: Vec<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "QueryRules::is_empty")]
+    pub query_rules: QueryRules,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reranker: Option<RerankerConfig>,
+}
+
+/// Configuration of the optional re-ranking stage of the searches sorted by relevance. The
+/// top-N hits retrieved by the searchers are sent to an external HTTP scoring service or to a
+/// WebAssembly plugin, which returns a score per hit, and the hits are reordered by decreasing
+/// score.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RerankerConfig {
+    /// Endpoint of the scoring service, for instance `http://reranker:8080/rerank`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub endpoint: String,
+    /// WebAssembly plugin scoring the hits in place of the scoring service. The plugin can also
+    /// filter out hits.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wasm_plugin: Option<WasmPluginConfig>,
+    /// Number of top hits sent to the scoring service. The hits ranked below are returned in
+    /// their original order after the re-ranked hits.
+    #[schema(default = 100)]
+    #[serde(default = "RerankerConfig::default_top_n")]
+    pub top_n: usize,
+    /// Fields of the hits sent to the scoring service. When empty, the whole documents are sent.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    /// Timeout of a re-ranking request.
+    #[schema(default = 1_000)]
+    #[serde(default = "RerankerConfig::default_timeout_millis")]
+    pub timeout_millis: u64,
+    /// What to do with the search response when the scoring service fails.
+    #[serde(default)]
+    pub on_failure: RerankerFailurePolicy,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RerankerFailurePolicy {
+    /// The hits are returned in their original order.
+    #[default]
+    Ignore,
+    /// The search request fails.
+    Fail,
+}
+
+impl RerankerConfig {
+    fn default_top_n() -> usize {
+        100
+    }
+
+    fn default_timeout_millis() -> u64 {
+        1_000
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_millis)
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            wasm_plugin: None,
+            top_n: Self::default_top_n(),
+            fields: Vec::new(),
+            timeout_millis: Self::default_timeout_millis(),
+            on_failure: RerankerFailurePolicy::default(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(wasm_plugin_config) = &self.wasm_plugin {
+            ensure!(
+                self.endpoint.is_empty(),
+                "reranker must have either an endpoint or a WASM plugin, not both"
+            );
+            wasm_plugin_config.validate()?;
+        } else {
+            ensure!(
+                self.endpoint.starts_with("http://") || self.endpoint.starts_with("https://"),
+                "reranker endpoint `{}` must start with `http://` or `https://`",
+                self.endpoint
+            );
+        }
+        ensure!(self.top_n > 0, "reranker `top_n` must be strictly positive");
+        ensure!(
+            self.timeout_millis > 0,
+            "reranker `timeout_millis` must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+/// Rules applied by the searchers to the queries targeting an index before planning them. They
+/// allow admins to reject queries known to be pathological, cap the time range of queries, and
+/// restrict queries to a subset of the documents.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QueryRules {
+    /// Queries matching one of these patterns are rejected with a policy error.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_queries: Vec<BlockedQueryPattern>,
+
+    /// Maximum time range covered by a query, expressed in a human-friendly way (`1 day`,
+    /// `7 days`, ...). Queries covering a longer or an unbounded time range are narrowed down to
+    /// the most recent part of their range.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_time_range: Option<String>,
+
+    /// Filters, expressed in the query language, added to every query.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+}
+
+impl QueryRules {
+    pub fn is_empty(&self) -> bool {
+        self.blocked_queries.is_empty() && self.max_time_range.is_none() && self.filters.is_empty()
+    }
+
+    pub fn max_time_range(&self) -> anyhow::Result<Option<Duration>> {
+        let Some(max_time_range) = &self.max_time_range else {
+            return Ok(None);
+        };
+        let max_time_range = parse_duration(max_time_range).with_context(|| {
+            format!("failed to parse query rules max time range `{max_time_range}`")
+        })?;
+        Ok(Some(max_time_range))
+    }
+
+    pub(super) fn validate(&self) -> anyhow::Result<()> {
+        for blocked_query in &self.blocked_queries {
+            ensure!(
+                blocked_query.kind != BlockedQueryKind::MatchAll || blocked_query.field.is_none(),
+                "blocked query pattern `match_all` does not apply to a field"
+            );
+        }
+        if let Some(max_time_range) = self.max_time_range()? {
+            ensure!(
+                max_time_range.as_secs() > 0,
+                "query rules max time range must be at least one second"
+            );
+        }
+        ensure!(
+            self.filters.iter().all(|filter| !filter.trim().is_empty()),
+            "query rules filters must not be empty"
+        );
+        Ok(())
+    }
+}
+
+/// Pattern of queries rejected by the searchers.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BlockedQueryPattern {
+    pub kind: BlockedQueryKind,
+    /// Field the pattern applies to. When absent, the pattern applies to all the fields.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockedQueryKind {
+    /// Wildcard queries, e.g. `body:err*`.
+    Wildcard,
+    /// Wildcard queries starting with a wildcard, e.g. `body:*rror`, which scan the whole term
+    /// dictionary.
+    LeadingWildcard,
+    /// Phrase prefix queries, e.g. `body:"connection ref"*`.
+    PhrasePrefix,
+    /// Queries matching all the documents, e.g. `*`.
+    MatchAll,
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -236,6 +945,43 @@ pub struct RetentionPolicy {
     #[serde(default = "RetentionPolicy::default_schedule")]
     #[serde(rename = "schedule")]
     pub evaluation_schedule: String,
+
+    /// Retention periods applied to the splits of given partitions instead of `period`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub partition_overrides: Vec<PartitionRetentionOverride>,
+}
+
+/// Retention period of the splits of a partition, identified by the value of a tag field.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PartitionRetentionOverride {
+    /// Tag of the partition, expressed as `{tag field}:{value}` (`tenant_id:foo`).
+    pub tag: String,
+
+    /// Duration of time for which the splits of the partition should be retained, expressed in a
+    /// human-friendly way (`1 hour`, `3 days`, `1 week`, ...).
+    #[serde(rename = "period")]
+    pub retention_period: String,
+}
+
+impl PartitionRetentionOverride {
+    /// Returns the tag field of the partition.
+    pub fn tag_field(&self) -> Option<&str> {
+        self.tag
+            .split_once(':')
+            .map(|(tag_field, _)| tag_field)
+            .filter(|tag_field| !tag_field.is_empty())
+    }
+
+    pub fn retention_period(&self) -> anyhow::Result<Duration> {
+        parse_duration(&self.retention_period).with_context(|| {
+            format!(
+                "failed to parse retention period `{}` of partition `{}`",
+                self.retention_period, self.tag
+            )
+        })
+    }
 }
 
 impl RetentionPolicy {
@@ -275,9 +1021,129 @@ impl RetentionPolicy {
         Ok(duration)
     }
 
+    /// Returns the tag field of the partitions the retention period is overridden for, if any.
+    pub fn partition_overrides_tag_field(&self) -> Option<&str> {
+        self.partition_overrides
+            .first()
+            .and_then(PartitionRetentionOverride::tag_field)
+    }
+
+    /// Returns the shortest retention period of the policy, partition overrides included.
+    pub fn min_retention_period(&self) -> anyhow::Result<Duration> {
+        let mut min_retention_period = self.retention_period()?;
+        for partition_override in &self.partition_overrides {
+            min_retention_period = min_retention_period.min(partition_override.retention_period()?);
+        }
+        Ok(min_retention_period)
+    }
+
+    /// Returns the retention period that applies to a split given its tags.
+    ///
+    /// The retention period of a partition only applies to the splits whose documents all belong
+    /// to partitions with an override, in which case the longest of their retention periods
+    /// applies. This is always the case when the tag field is also the partition key of the
+    /// index. The splits whose tag values are unknown, because the tag field has too many values
+    /// in the split, are retained for the default period.
+    pub fn split_retention_period(
+        &self,
+        split_tags: &BTreeSet<String>,
+    ) -> anyhow::Result<Duration> {
+        let Some(tag_field) = self.partition_overrides_tag_field() else {
+            return self.retention_period();
+        };
+        if !split_tags.contains(&field_tag(tag_field)) {
+            return self.retention_period();
+        }
+        let tag_prefix = format!("{tag_field}:");
+        let mut split_retention_period_opt: Option<Duration> = None;
+
+        for split_tag in split_tags
+            .iter()
+            .filter(|split_tag| split_tag.starts_with(&tag_prefix))
+        {
+            let Some(partition_override) = self
+                .partition_overrides
+                .iter()
+                .find(|partition_override| partition_override.tag == *split_tag)
+            else {
+                return self.retention_period();
+            };
+            let retention_period = partition_override.retention_period()?;
+            split_retention_period_opt = split_retention_period_opt.max(Some(retention_period));
+        }
+        match split_retention_period_opt {
+            Some(split_retention_period) => Ok(split_retention_period),
+            None => self.retention_period(),
+        }
+    }
+
     pub(super) fn validate(&self) -> anyhow::Result<()> {
         self.retention_period()?;
         self.evaluation_schedule()?;
+
+        let tag_field_opt = self.partition_overrides_tag_field();
+        let mut partition_tags = BTreeSet::new();
+
+        for partition_override in &self.partition_overrides {
+            partition_override.retention_period()?;
+            let Some(tag_field) = partition_override.tag_field() else {
+                bail!(
+                    "invalid partition tag `{}`: expected `{{tag field}}:{{value}}`",
+                    partition_override.tag
+                );
+            };
+            ensure!(
+                Some(tag_field) == tag_field_opt,
+                "the partition retention overrides must all apply to the same tag field"
+            );
+            ensure!(
+                partition_tags.insert(&partition_override.tag),
+                "partition `{}` has more than one retention override",
+                partition_override.tag
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A legal hold prevents the splits of an index from being physically deleted: splits deleted
+/// logically, for instance by the retention policy or after a merge, are kept in storage and can
+/// still be searched by privileged search requests.
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LegalHold {
+    /// IDs of the splits under legal hold. When empty, the hold applies to all the splits of the
+    /// index.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub split_ids: Vec<String>,
+
+    /// Free-form description of the hold, such as a case number.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl LegalHold {
+    /// Returns whether the hold applies to all the splits of the index.
+    pub fn holds_all_splits(&self) -> bool {
+        self.split_ids.is_empty()
+    }
+
+    /// Returns whether the split is under legal hold.
+    pub fn holds_split(&self, split_id: &str) -> bool {
+        self.holds_all_splits()
+            || self
+                .split_ids
+                .iter()
+                .any(|held_split_id| held_split_id == split_id)
+    }
+
+    pub(super) fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.split_ids.iter().all(|split_id| !split_id.is_empty()),
+            "legal hold split IDs must not be empty"
+        );
         Ok(())
     }
 }
@@ -307,6 +1173,10 @@ pub struct IndexConfig {
     pub indexing_settings: IndexingSettings,
     pub search_settings: SearchSettings,
     pub retention_policy_opt: Option<RetentionPolicy>,
+    /// Number of replicas of the ingest shards of the index. When unset, the cluster-wide
+    /// replication factor applies.
+    pub replication_factor_opt: Option<usize>,
+    pub legal_hold_opt: Option<LegalHold>,
 }
 
 impl IndexConfig {
@@ -384,6 +1254,7 @@ impl IndexConfig {
                 r#"attributes.server"#.to_string(),
                 r"attributes.server\.status".to_string(),
             ],
+            ..Default::default()
         };
         IndexConfig {
             index_id: index_id.to_string(),
@@ -392,6 +1263,8 @@ impl IndexConfig {
             indexing_settings,
             search_settings,
             retention_policy_opt: Default::default(),
+            replication_factor_opt: None,
+            legal_hold_opt: None,
         }
     }
 }
@@ -451,17 +1324,21 @@ impl TestableForRegression for IndexConfig {
                 .into_iter()
                 .map(|tag_field| tag_field.to_string())
                 .collect::<BTreeSet<String>>(),
+            term_digest_fields: BTreeSet::new(),
             store_source: true,
             mode: Mode::default(),
             partition_key: Some("tenant_id".to_string()),
             max_num_partitions: NonZeroU32::new(100).unwrap(),
             timestamp_field: Some("timestamp".to_string()),
+            secondary_timestamp_fields: Vec::new(),
             tokenizers: vec![tokenizer],
             document_length: false,
+            doc_mapping_version: 0,
         };
         let retention_policy = Some(RetentionPolicy {
             retention_period: "90 days".to_string(),
             evaluation_schedule: "daily".to_string(),
+            partition_overrides: Vec::new(),
         });
         let stable_log_config = StableLogMergePolicyConfig {
             merge_factor: 9,
@@ -482,6 +1359,7 @@ impl TestableForRegression for IndexConfig {
         };
         let search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            ..Default::default()
         };
         IndexConfig {
             index_id: "my-index".to_string(),
@@ -490,6 +1368,8 @@ impl TestableForRegression for IndexConfig {
             indexing_settings,
             retention_policy_opt: retention_policy,
             search_settings,
+            replication_factor_opt: None,
+            legal_hold_opt: None,
         }
     }
 
@@ -516,6 +1396,8 @@ impl TestableForRegression for IndexConfig {
         );
         assert_eq!(self.indexing_settings, other.indexing_settings);
         assert_eq!(self.search_settings, other.search_settings);
+        assert_eq!(self.replication_factor_opt, other.replication_factor_opt);
+        assert_eq!(self.legal_hold_opt, other.legal_hold_opt);
     }
 }
 
@@ -529,17 +1411,174 @@ pub fn build_doc_mapper(
         index_field_presence: doc_mapping.index_field_presence,
         default_search_fields: search_settings.default_search_fields.clone(),
         timestamp_field: doc_mapping.timestamp_field.clone(),
+        secondary_timestamp_fields: doc_mapping.secondary_timestamp_fields.clone(),
         field_mappings: doc_mapping.field_mappings.clone(),
         tag_fields: doc_mapping.tag_fields.iter().cloned().collect(),
+        term_digest_fields: doc_mapping.term_digest_fields.iter().cloned().collect(),
         mode: doc_mapping.mode.clone(),
         partition_key: doc_mapping.partition_key.clone(),
         max_num_partitions: doc_mapping.max_num_partitions,
         tokenizers: doc_mapping.tokenizers.clone(),
         document_length: doc_mapping.document_length,
+        doc_mapping_version: doc_mapping.doc_mapping_version,
     };
     Ok(Arc::new(builder.try_build()?))
 }
 
+/// Changes made to the fields of a doc mapping by an update.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct DocMappingUpdate {
+    /// Paths of the fields added by the update.
+    pub new_field_paths: Vec<String>,
+    /// Paths of the fields turned into fast fields.
+    pub new_fast_field_paths: Vec<String>,
+    /// Paths of the fast fields turned into regular fields.
+    pub disabled_fast_field_paths: Vec<String>,
+    /// Paths of the fields whose tokenizer changed.
+    pub retokenized_field_paths: Vec<String>,
+}
+
+/// Validates an update of the doc mapping of an index and returns the changes it makes to the
+/// fields.
+///
+/// The update can add fields, add tokenizers, enable or disable the `fast` option of existing
+/// fields, and change their tokenizer. The changes only apply to the splits created after the
+/// update: existing splits are searched with the options they were built with. For this reason,
+/// fields cannot be removed or change type, and the tokenizers already defined cannot be modified.
+pub fn validate_doc_mapping_update(
+    current_doc_mapping: &DocMapping,
+    new_doc_mapping: &DocMapping,
+    search_settings: &SearchSettings,
+) -> anyhow::Result<DocMappingUpdate> {
+    build_doc_mapper(new_doc_mapping, search_settings)?;
+
+    let mut current_doc_mapping_json = serde_json::to_value(current_doc_mapping)?;
+    let mut new_doc_mapping_json = serde_json::to_value(new_doc_mapping)?;
+    let current_field_mappings = take_json_key(&mut current_doc_mapping_json, "field_mappings");
+    let new_field_mappings = take_json_key(&mut new_doc_mapping_json, "field_mappings");
+    let current_tokenizers = take_json_key(&mut current_doc_mapping_json, "tokenizers");
+    let new_tokenizers = take_json_key(&mut new_doc_mapping_json, "tokenizers");
+    take_json_key(&mut current_doc_mapping_json, "doc_mapping_version");
+    take_json_key(&mut new_doc_mapping_json, "doc_mapping_version");
+    ensure!(
+        current_doc_mapping_json == new_doc_mapping_json,
+        "only the field mappings and the tokenizers of the doc mapping can be updated"
+    );
+    let new_tokenizers = json_array(new_tokenizers);
+    for current_tokenizer in json_array(current_tokenizers) {
+        ensure!(
+            new_tokenizers.contains(&current_tokenizer),
+            "tokenizer `{}` cannot be updated or removed: existing splits may use it",
+            json_name(&current_tokenizer)
+        );
+    }
+    let mut doc_mapping_update = DocMappingUpdate::default();
+    diff_field_mappings(
+        "",
+        current_field_mappings,
+        new_field_mappings,
+        &mut doc_mapping_update,
+    )?;
+    Ok(doc_mapping_update)
+}
+
+fn take_json_key(json_value: &mut JsonValue, key: &str) -> Option<JsonValue> {
+    json_value
+        .as_object_mut()
+        .and_then(|json_obj| json_obj.remove(key))
+}
+
+fn json_array(json_value_opt: Option<JsonValue>) -> Vec<JsonValue> {
+    match json_value_opt {
+        Some(JsonValue::Array(json_values)) => json_values,
+        _ => Vec::new(),
+    }
+}
+
+fn json_name(json_value: &JsonValue) -> &str {
+    json_value
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default()
+}
+
+fn is_fast_option_enabled(fast_opt: &Option<JsonValue>) -> bool {
+    !matches!(fast_opt, None | Some(JsonValue::Bool(false)))
+}
+
+fn diff_field_mappings(
+    path_prefix: &str,
+    current_field_mappings_opt: Option<JsonValue>,
+    new_field_mappings_opt: Option<JsonValue>,
+    doc_mapping_update: &mut DocMappingUpdate,
+) -> anyhow::Result<()> {
+    let field_path = |field_name: &str| {
+        if path_prefix.is_empty() {
+            field_name.to_string()
+        } else {
+            format!("{path_prefix}.{field_name}")
+        }
+    };
+    let mut new_field_mappings = json_array(new_field_mappings_opt);
+
+    for mut current_field_mapping in json_array(current_field_mappings_opt) {
+        let field_path = field_path(json_name(&current_field_mapping));
+        let Some(new_field_mapping_pos) = new_field_mappings.iter().position(|new_field_mapping| {
+            json_name(new_field_mapping) == json_name(&current_field_mapping)
+        }) else {
+            bail!("field `{field_path}` cannot be removed");
+        };
+        let mut new_field_mapping = new_field_mappings.remove(new_field_mapping_pos);
+
+        let current_fast_opt = take_json_key(&mut current_field_mapping, "fast");
+        let new_fast_opt = take_json_key(&mut new_field_mapping, "fast");
+        let current_tokenizer_opt = take_json_key(&mut current_field_mapping, "tokenizer");
+        let new_tokenizer_opt = take_json_key(&mut new_field_mapping, "tokenizer");
+        let current_sub_field_mappings =
+            take_json_key(&mut current_field_mapping, "field_mappings");
+        let new_sub_field_mappings = take_json_key(&mut new_field_mapping, "field_mappings");
+        ensure!(
+            current_field_mapping == new_field_mapping,
+            "field `{field_path}` cannot be updated: only its `fast` and `tokenizer` options can \
+             be changed"
+        );
+        if current_fast_opt != new_fast_opt {
+            match (
+                is_fast_option_enabled(&current_fast_opt),
+                is_fast_option_enabled(&new_fast_opt),
+            ) {
+                (false, true) => doc_mapping_update
+                    .new_fast_field_paths
+                    .push(field_path.clone()),
+                (true, false) => doc_mapping_update
+                    .disabled_fast_field_paths
+                    .push(field_path.clone()),
+                (true, true) => {
+                    bail!("the fast field normalizer of field `{field_path}` cannot be updated")
+                }
+                (false, false) => {}
+            }
+        }
+        if current_tokenizer_opt != new_tokenizer_opt {
+            doc_mapping_update
+                .retokenized_field_paths
+                .push(field_path.clone());
+        }
+        diff_field_mappings(
+            &field_path,
+            current_sub_field_mappings,
+            new_sub_field_mappings,
+            doc_mapping_update,
+        )?;
+    }
+    for new_field_mapping in &new_field_mappings {
+        doc_mapping_update
+            .new_field_paths
+            .push(field_path(json_name(new_field_mapping)));
+    }
+    Ok(())
+}
+
 /// Validates the objects that make up an index configuration. This is a "free" function as opposed
 /// to a method on `IndexConfig` so we can reuse it for validating index templates.
 pub(super) fn validate_index_config(
@@ -547,15 +1586,54 @@ pub(super) fn validate_index_config(
     indexing_settings: &IndexingSettings,
     search_settings: &SearchSettings,
     retention_policy_opt: &Option<RetentionPolicy>,
+    replication_factor_opt: Option<usize>,
 ) -> anyhow::Result<()> {
     // Note: this needs a deep refactoring to separate the doc mapping configuration,
     // and doc mapper implementations.
     // TODO see if we should store the byproducton the IndexConfig.
     build_doc_mapper(doc_mapping, search_settings)?;
+    search_settings.query_rules.validate()?;
+
+    if let Some(reranker_config) = &search_settings.reranker {
+        reranker_config.validate()?;
+    }
+
+    if search_settings.query_rules.max_time_range.is_some() {
+        ensure!(
+            doc_mapping.timestamp_field.is_some(),
+            "query rules max time range requires a timestamp field, but doc mapping does not \
+             declare one"
+        );
+    }
 
     indexing_settings.merge_policy.validate()?;
     indexing_settings.resources.validate()?;
 
+    if let Some(enrichment_config) = &indexing_settings.enrichment {
+        enrichment_config.validate()?;
+    }
+    if let Some(embedding_config) = &indexing_settings.embedding {
+        embedding_config.validate()?;
+    }
+    if let Some(ingestion_quota_config) = &indexing_settings.ingestion_quota {
+        ingestion_quota_config.validate()?;
+    }
+    if let Some(dead_letter_config) = &indexing_settings.dead_letter {
+        dead_letter_config.validate()?;
+    }
+    if let Some(ingest_mirror_config) = &indexing_settings.ingest_mirror {
+        ingest_mirror_config.validate()?;
+    }
+    if let Some(trace_sampling_config) = &indexing_settings.trace_sampling {
+        trace_sampling_config.validate()?;
+    }
+    if let Some(merge_throttling_config) = &indexing_settings.merge_throttling {
+        merge_throttling_config.validate()?;
+    }
+    if let Some(adaptive_split_size_config) = &indexing_settings.adaptive_split_size {
+        adaptive_split_size_config.validate(indexing_settings.split_num_docs_target)?;
+    }
+
     if let Some(retention_policy) = retention_policy_opt {
         retention_policy.validate()?;
 
@@ -563,6 +1641,19 @@ pub(super) fn validate_index_config(
             doc_mapping.timestamp_field.is_some(),
             "retention policy requires a timestamp field, but doc mapping does not declare one"
         );
+        if let Some(tag_field) = retention_policy.partition_overrides_tag_field() {
+            ensure!(
+                doc_mapping.tag_fields.contains(tag_field),
+                "partition retention overrides apply to field `{tag_field}`, which is not a tag \
+                 field"
+            );
+        }
+    }
+    if let Some(replication_factor) = replication_factor_opt {
+        ensure!(
+            (1..=2).contains(&replication_factor),
+            "replication factor must be either 1 or 2, got `{replication_factor}`"
+        );
     }
     Ok(())
 }
@@ -618,6 +1709,7 @@ mod tests {
         let expected_retention_policy = RetentionPolicy {
             retention_period: "90 days".to_string(),
             evaluation_schedule: "daily".to_string(),
+            partition_overrides: Vec::new(),
         };
         assert_eq!(
             index_config.retention_policy_opt.unwrap(),
@@ -650,6 +1742,7 @@ mod tests {
             index_config.search_settings,
             SearchSettings {
                 default_search_fields: vec!["severity_text".to_string(), "body".to_string()],
+                ..Default::default()
             }
         );
     }
@@ -692,6 +1785,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    ..Default::default()
                 }
             );
         }
@@ -726,6 +1820,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    ..Default::default()
                 }
             );
         }
@@ -793,6 +1888,7 @@ mod tests {
         let retention_policy = RetentionPolicy {
             retention_period: "90 days".to_string(),
             evaluation_schedule: "hourly".to_string(),
+            partition_overrides: Vec::new(),
         };
         let retention_policy_yaml = serde_yaml::to_string(&retention_policy).unwrap();
         assert_eq!(
@@ -813,6 +1909,7 @@ mod tests {
             let expected_retention_policy = RetentionPolicy {
                 retention_period: "90 days".to_string(),
                 evaluation_schedule: "hourly".to_string(),
+                partition_overrides: Vec::new(),
             };
             assert_eq!(retention_policy, expected_retention_policy);
         }
@@ -827,6 +1924,7 @@ mod tests {
             let expected_retention_policy = RetentionPolicy {
                 retention_period: "90 days".to_string(),
                 evaluation_schedule: "daily".to_string(),
+                partition_overrides: Vec::new(),
             };
             assert_eq!(retention_policy, expected_retention_policy);
         }
@@ -838,6 +1936,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: "hourly".to_string(),
+                partition_overrides: Vec::new(),
             };
             assert_eq!(
                 retention_policy.retention_period().unwrap(),
@@ -847,6 +1946,7 @@ mod tests {
                 let retention_policy = RetentionPolicy {
                     retention_period: "foo".to_string(),
                     evaluation_schedule: "hourly".to_string(),
+                    partition_overrides: Vec::new(),
                 };
                 assert_eq!(
                     retention_policy.retention_period().unwrap_err().to_string(),
@@ -871,6 +1971,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: "@hourly".to_string(),
+                partition_overrides: Vec::new(),
             };
             assert_eq!(
                 retention_policy.evaluation_schedule().unwrap(),
@@ -881,6 +1982,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: "hourly".to_string(),
+                partition_overrides: Vec::new(),
             };
             assert_eq!(
                 retention_policy.evaluation_schedule().unwrap(),
@@ -891,6 +1993,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: "0 * * * * *".to_string(),
+                partition_overrides: Vec::new(),
             };
             let evaluation_schedule = retention_policy.evaluation_schedule().unwrap();
             assert_eq!(evaluation_schedule.seconds().count(), 1);
@@ -904,6 +2007,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: "hourly".to_string(),
+                partition_overrides: Vec::new(),
             };
             retention_policy.validate().unwrap();
         }
@@ -911,6 +2015,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "foo".to_string(),
                 evaluation_schedule: "hourly".to_string(),
+                partition_overrides: Vec::new(),
             };
             retention_policy.validate().unwrap_err();
         }
@@ -918,9 +2023,560 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: "foo".to_string(),
+                partition_overrides: Vec::new(),
+            };
+            retention_policy.validate().unwrap_err();
+        }
+        {
+            let retention_policy_yaml = r#"
+                period: 30 days
+                partition_overrides:
+                  - tag: tenant_id:foo
+                    period: 7 days
+                  - tag: tenant_id:bar
+                    period: 90 days
+            "#;
+            let retention_policy: RetentionPolicy =
+                serde_yaml::from_str(retention_policy_yaml).unwrap();
+            retention_policy.validate().unwrap();
+            assert_eq!(
+                retention_policy.min_retention_period().unwrap(),
+                Duration::from_secs(7 * 24 * 3600)
+            );
+        }
+        for invalid_tag in ["tenant_id", ":foo"] {
+            let retention_policy = RetentionPolicy {
+                retention_period: "30 days".to_string(),
+                evaluation_schedule: "hourly".to_string(),
+                partition_overrides: vec![PartitionRetentionOverride {
+                    tag: invalid_tag.to_string(),
+                    retention_period: "7 days".to_string(),
+                }],
             };
             retention_policy.validate().unwrap_err();
         }
+        {
+            let retention_policy_yaml = r#"
+                period: 30 days
+                partition_overrides:
+                  - tag: tenant_id:foo
+                    period: 7 days
+                  - tag: region:eu
+                    period: 90 days
+            "#;
+            let retention_policy: RetentionPolicy =
+                serde_yaml::from_str(retention_policy_yaml).unwrap();
+            let error = retention_policy.validate().unwrap_err();
+            assert!(error.to_string().contains("same tag field"));
+        }
+    }
+
+    #[test]
+    fn test_retention_policy_split_retention_period() {
+        let retention_policy_yaml = r#"
+            period: 30 days
+            partition_overrides:
+              - tag: tenant_id:foo
+                period: 7 days
+              - tag: tenant_id:bar
+                period: 14 days
+        "#;
+        let retention_policy: RetentionPolicy =
+            serde_yaml::from_str(retention_policy_yaml).unwrap();
+        let days = |num_days: u64| Duration::from_secs(num_days * 24 * 3600);
+        let tags = |tags: &[&str]| -> BTreeSet<String> {
+            tags.iter().map(|tag| tag.to_string()).collect()
+        };
+        assert_eq!(
+            retention_policy
+                .split_retention_period(&tags(&["tenant_id!", "tenant_id:foo"]))
+                .unwrap(),
+            days(7)
+        );
+        assert_eq!(
+            retention_policy
+                .split_retention_period(&tags(&["tenant_id!", "tenant_id:foo", "tenant_id:bar"]))
+                .unwrap(),
+            days(14)
+        );
+        // The split contains documents of a partition without override.
+        assert_eq!(
+            retention_policy
+                .split_retention_period(&tags(&["tenant_id!", "tenant_id:foo", "tenant_id:baz"]))
+                .unwrap(),
+            days(30)
+        );
+        // The tag values of the split are unknown.
+        assert_eq!(
+            retention_policy
+                .split_retention_period(&tags(&["tenant_id:foo"]))
+                .unwrap(),
+            days(30)
+        );
+        assert_eq!(
+            retention_policy.split_retention_period(&tags(&[])).unwrap(),
+            days(30)
+        );
+    }
+
+    #[test]
+    fn test_query_rules_deserialization() {
+        let search_settings_yaml = r#"
+            default_search_fields: [body]
+            query_rules:
+              blocked_queries:
+                - kind: leading_wildcard
+                  field: body
+                - kind: match_all
+              max_time_range: 7 days
+              filters:
+                - "tenant_id:acme"
+        "#;
+        let search_settings = serde_yaml::from_str::<SearchSettings>(search_settings_yaml).unwrap();
+        let query_rules = search_settings.query_rules;
+        assert_eq!(
+            query_rules.blocked_queries,
+            [
+                BlockedQueryPattern {
+                    kind: BlockedQueryKind::LeadingWildcard,
+                    field: Some("body".to_string()),
+                },
+                BlockedQueryPattern {
+                    kind: BlockedQueryKind::MatchAll,
+                    field: None,
+                },
+            ]
+        );
+        assert_eq!(
+            query_rules.max_time_range().unwrap(),
+            Some(Duration::from_secs(7 * 24 * 3_600))
+        );
+        assert_eq!(query_rules.filters, ["tenant_id:acme"]);
+        query_rules.validate().unwrap();
+
+        let search_settings = serde_yaml::from_str::<SearchSettings>("{}").unwrap();
+        assert!(search_settings.query_rules.is_empty());
+        assert_eq!(
+            serde_json::to_string(&search_settings).unwrap(),
+            r#"{"default_search_fields":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_query_rules_validate() {
+        {
+            let query_rules = QueryRules {
+                max_time_range: Some("foo".to_string()),
+                ..Default::default()
+            };
+            query_rules.validate().unwrap_err();
+        }
+        {
+            let query_rules = QueryRules {
+                blocked_queries: vec![BlockedQueryPattern {
+                    kind: BlockedQueryKind::MatchAll,
+                    field: Some("body".to_string()),
+                }],
+                ..Default::default()
+            };
+            query_rules.validate().unwrap_err();
+        }
+        {
+            let query_rules = QueryRules {
+                filters: vec![" ".to_string()],
+                ..Default::default()
+            };
+            query_rules.validate().unwrap_err();
+        }
+        {
+            let mut index_config = IndexConfig::for_test("test-index", "s3://test-index");
+            index_config.search_settings.query_rules.max_time_range = Some("1 day".to_string());
+            index_config.doc_mapping.timestamp_field = None;
+            let error = validate_index_config(
+                &index_config.doc_mapping,
+                &index_config.indexing_settings,
+                &index_config.search_settings,
+                &index_config.retention_policy_opt,
+                index_config.replication_factor_opt,
+            )
+            .unwrap_err();
+            assert!(error.to_string().contains("requires a timestamp field"));
+        }
+    }
+
+    #[test]
+    fn test_enrichment_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            enrichment:
+              endpoint: http://enricher:50051
+              timeout_millis: 250
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let expected_enrichment_config = EnrichmentConfig {
+            timeout_millis: 250,
+            ..EnrichmentConfig::for_test("http://enricher:50051")
+        };
+        assert_eq!(
+            indexing_settings.enrichment.unwrap(),
+            expected_enrichment_config
+        );
+        assert_eq!(expected_enrichment_config.max_batch_size, 1_000);
+        assert_eq!(expected_enrichment_config.failure_threshold, 5);
+        assert_eq!(
+            expected_enrichment_config.cooldown(),
+            Duration::from_secs(30)
+        );
+
+        let indexing_settings = serde_yaml::from_str::<IndexingSettings>("{}").unwrap();
+        assert!(indexing_settings.enrichment.is_none());
+    }
+
+    #[test]
+    fn test_enrichment_config_validate() {
+        EnrichmentConfig::for_test("http://enricher:50051")
+            .validate()
+            .unwrap();
+        EnrichmentConfig::for_test("enricher:50051")
+            .validate()
+            .unwrap_err();
+        {
+            let enrichment_config = EnrichmentConfig {
+                max_batch_size: 0,
+                ..EnrichmentConfig::for_test("http://enricher:50051")
+            };
+            enrichment_config.validate().unwrap_err();
+        }
+        {
+            let enrichment_config = EnrichmentConfig {
+                failure_threshold: 0,
+                ..EnrichmentConfig::for_test("http://enricher:50051")
+            };
+            enrichment_config.validate().unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_reranker_config_deserialization_and_validate() {
+        let search_settings_yaml = r#"
+            reranker:
+              endpoint: http://reranker:8080/rerank
+              top_n: 50
+              fields: [title, body]
+              on_failure: fail
+        "#;
+        let search_settings = serde_yaml::from_str::<SearchSettings>(search_settings_yaml).unwrap();
+        let reranker_config = search_settings.reranker.unwrap();
+        assert_eq!(reranker_config.endpoint, "http://reranker:8080/rerank");
+        assert_eq!(reranker_config.top_n, 50);
+        assert_eq!(reranker_config.fields, ["title", "body"]);
+        assert_eq!(reranker_config.timeout(), Duration::from_secs(1));
+        assert_eq!(reranker_config.on_failure, RerankerFailurePolicy::Fail);
+        reranker_config.validate().unwrap();
+
+        RerankerConfig::for_test("reranker:8080")
+            .validate()
+            .unwrap_err();
+        {
+            let reranker_config = RerankerConfig {
+                top_n: 0,
+                ..RerankerConfig::for_test("http://reranker:8080")
+            };
+            reranker_config.validate().unwrap_err();
+        }
+        {
+            let reranker_config = RerankerConfig {
+                wasm_plugin: Some(WasmPluginConfig::for_test("scorer.wasm")),
+                ..RerankerConfig::for_test("http://reranker:8080")
+            };
+            reranker_config.validate().unwrap_err();
+        }
+        {
+            let search_settings_yaml = r#"
+                reranker:
+                  wasm_plugin:
+                    path: /var/lib/quickwit/plugins/scorer.wasm
+                    max_fuel_per_call: 1000000
+            "#;
+            let search_settings =
+                serde_yaml::from_str::<SearchSettings>(search_settings_yaml).unwrap();
+            let reranker_config = search_settings.reranker.unwrap();
+            assert!(reranker_config.endpoint.is_empty());
+            assert_eq!(
+                reranker_config
+                    .wasm_plugin
+                    .as_ref()
+                    .unwrap()
+                    .max_fuel_per_call,
+                1_000_000
+            );
+            reranker_config.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dead_letter_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            dead_letter:
+              index_id: my-index-dead-letter
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let dead_letter_config = indexing_settings.dead_letter.unwrap();
+        assert_eq!(dead_letter_config.index_id, "my-index-dead-letter");
+        dead_letter_config.validate().unwrap();
+
+        let indexing_settings = serde_yaml::from_str::<IndexingSettings>("{}").unwrap();
+        assert!(indexing_settings.dead_letter.is_none());
+
+        let dead_letter_config = DeadLetterConfig {
+            index_id: "invalid index".to_string(),
+        };
+        dead_letter_config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_ingest_mirror_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            ingest_mirror:
+              index_id: my-index-v2
+              endpoint: http://quickwit-staging:7280
+              percentage: 10
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let ingest_mirror_config = indexing_settings.ingest_mirror.unwrap();
+        assert_eq!(ingest_mirror_config.index_id, "my-index-v2");
+        assert_eq!(
+            ingest_mirror_config.endpoint.as_deref(),
+            Some("http://quickwit-staging:7280")
+        );
+        assert_eq!(ingest_mirror_config.percentage, 10.);
+        assert_eq!(ingest_mirror_config.max_pending_batches, 16);
+        assert_eq!(ingest_mirror_config.timeout(), Duration::from_secs(5));
+        ingest_mirror_config.validate().unwrap();
+
+        let indexing_settings = serde_yaml::from_str::<IndexingSettings>("{}").unwrap();
+        assert!(indexing_settings.ingest_mirror.is_none());
+
+        let invalid_configs = [
+            IngestMirrorConfig {
+                percentage: 0.,
+                ..IngestMirrorConfig::for_test("my-index-v2")
+            },
+            IngestMirrorConfig {
+                percentage: 150.,
+                ..IngestMirrorConfig::for_test("my-index-v2")
+            },
+            IngestMirrorConfig {
+                endpoint: Some("quickwit-staging:7280".to_string()),
+                ..IngestMirrorConfig::for_test("my-index-v2")
+            },
+            IngestMirrorConfig {
+                max_pending_batches: 0,
+                ..IngestMirrorConfig::for_test("my-index-v2")
+            },
+            IngestMirrorConfig::for_test("invalid index"),
+        ];
+        for invalid_config in invalid_configs {
+            invalid_config.validate().unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_trace_sampling_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            trace_sampling:
+              ok_traces_sample_percent: 10
+              rollup_index_id: otel-traces-rollup
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let trace_sampling_config = indexing_settings.trace_sampling.unwrap();
+        assert_eq!(trace_sampling_config.ok_traces_sample_percent, 10);
+        assert_eq!(
+            trace_sampling_config.rollup_index_id.as_deref(),
+            Some("otel-traces-rollup")
+        );
+        trace_sampling_config.validate().unwrap();
+
+        let indexing_settings = serde_yaml::from_str::<IndexingSettings>("{}").unwrap();
+        assert!(indexing_settings.trace_sampling.is_none());
+
+        let invalid_configs = [
+            TraceSamplingConfig {
+                ok_traces_sample_percent: 101,
+                rollup_index_id: None,
+            },
+            TraceSamplingConfig {
+                ok_traces_sample_percent: 10,
+                rollup_index_id: Some("invalid index".to_string()),
+            },
+        ];
+        for invalid_config in invalid_configs {
+            invalid_config.validate().unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_embedding_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            embedding:
+              endpoint: http://embedder:8080/embed
+              fields:
+                - source_field: body
+                  target_field: body_embedding
+              max_concurrency: 2
+              on_failure: drop
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let expected_embedding_config = EmbeddingConfig {
+            max_concurrency: 2,
+            on_failure: EmbeddingFailurePolicy::Drop,
+            ..EmbeddingConfig::for_test("http://embedder:8080/embed", "body", "body_embedding")
+        };
+        assert_eq!(
+            indexing_settings.embedding.unwrap(),
+            expected_embedding_config
+        );
+        assert_eq!(expected_embedding_config.max_batch_size, 32);
+        assert_eq!(expected_embedding_config.timeout(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_embedding_config_validate() {
+        let embedding_config =
+            EmbeddingConfig::for_test("http://embedder:8080/embed", "body", "body_embedding");
+        embedding_config.validate().unwrap();
+
+        EmbeddingConfig::for_test("embedder:8080", "body", "body_embedding")
+            .validate()
+            .unwrap_err();
+        EmbeddingConfig::for_test("http://embedder:8080/embed", "body", "body")
+            .validate()
+            .unwrap_err();
+        {
+            let mut embedding_config = embedding_config.clone();
+            embedding_config.fields.clear();
+            embedding_config.validate().unwrap_err();
+        }
+        {
+            let mut embedding_config = embedding_config.clone();
+            embedding_config.fields.push(EmbeddingFieldConfig {
+                source_field: "title".to_string(),
+                target_field: "body_embedding".to_string(),
+            });
+            embedding_config.validate().unwrap_err();
+        }
+        {
+            let embedding_config = EmbeddingConfig {
+                max_concurrency: 0,
+                ..embedding_config
+            };
+            embedding_config.validate().unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_ingestion_quota_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            ingestion_quota:
+              tenant_id: acme
+              max_ingestion_rate_mib_per_sec: 20
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let ingestion_quota_config = indexing_settings.ingestion_quota.unwrap();
+        assert_eq!(ingestion_quota_config.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(ingestion_quota_config.max_ingestion_rate_mib_per_sec, 20.);
+        ingestion_quota_config.validate().unwrap();
+
+        let indexing_settings = serde_yaml::from_str::<IndexingSettings>("{}").unwrap();
+        assert!(indexing_settings.ingestion_quota.is_none());
+    }
+
+    #[test]
+    fn test_merge_throttling_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            merge_throttling:
+              recent_period: 7 days
+              max_concurrent_historical_merges: 1
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let merge_throttling_config = indexing_settings.merge_throttling.unwrap();
+        assert_eq!(
+            merge_throttling_config.recent_period().unwrap(),
+            Duration::from_secs(7 * 24 * 3600)
+        );
+        assert!(merge_throttling_config
+            .max_concurrent_recent_merges
+            .is_none());
+        assert_eq!(
+            merge_throttling_config.max_concurrent_historical_merges,
+            NonZeroUsize::new(1)
+        );
+        merge_throttling_config.validate().unwrap();
+
+        let merge_throttling_config = MergeThrottlingConfig {
+            recent_period: "7 days".to_string(),
+            max_concurrent_recent_merges: None,
+            max_concurrent_historical_merges: None,
+        };
+        merge_throttling_config.validate().unwrap_err();
+
+        let merge_throttling_config = MergeThrottlingConfig {
+            recent_period: "last week".to_string(),
+            max_concurrent_recent_merges: NonZeroUsize::new(2),
+            max_concurrent_historical_merges: None,
+        };
+        merge_throttling_config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_adaptive_split_size_config_deserialization() {
+        let indexing_settings_yaml = r#"
+            split_num_docs_target: 5000000
+            adaptive_split_size:
+              min_split_num_docs_target: 500000
+        "#;
+        let indexing_settings =
+            serde_yaml::from_str::<IndexingSettings>(indexing_settings_yaml).unwrap();
+        let adaptive_split_size_config = indexing_settings.adaptive_split_size.unwrap();
+        assert_eq!(
+            adaptive_split_size_config.min_split_num_docs_target,
+            500_000
+        );
+        assert_eq!(
+            adaptive_split_size_config.max_split_num_docs_target,
+            40_000_000
+        );
+        assert_eq!(adaptive_split_size_config.min_num_queries, 100);
+        adaptive_split_size_config.validate(5_000_000).unwrap();
+        adaptive_split_size_config.validate(100_000).unwrap_err();
+        adaptive_split_size_config.validate(50_000_000).unwrap_err();
+
+        let adaptive_split_size_config = AdaptiveSplitSizeConfig {
+            min_split_num_docs_target: 0,
+            ..Default::default()
+        };
+        adaptive_split_size_config.validate(5_000_000).unwrap_err();
+    }
+
+    #[test]
+    fn test_ingestion_quota_config_validate() {
+        let ingestion_quota_config = IngestionQuotaConfig {
+            tenant_id: None,
+            max_ingestion_rate_mib_per_sec: 0.,
+        };
+        ingestion_quota_config.validate().unwrap_err();
+
+        let ingestion_quota_config = IngestionQuotaConfig {
+            tenant_id: Some("".to_string()),
+            max_ingestion_rate_mib_per_sec: 10.,
+        };
+        ingestion_quota_config.validate().unwrap_err();
     }
 
     #[test]
@@ -930,6 +2586,7 @@ mod tests {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
                 evaluation_schedule: schedule_str.to_string(),
+                partition_overrides: Vec::new(),
             };
 
             let next_evaluation_duration = chrono::Duration::nanoseconds(
@@ -949,4 +2606,126 @@ mod tests {
         schedule_test_helper_fn("monthly");
         schedule_test_helper_fn("* * * ? * ?");
     }
+
+    #[test]
+    fn test_validate_doc_mapping_update() {
+        let doc_mapping_json = r#"{
+            "field_mappings": [
+                {"name": "timestamp", "type": "datetime", "fast": true},
+                {"name": "status", "type": "u64"},
+                {"name": "service", "type": "text", "tokenizer": "raw"},
+                {
+                    "name": "attributes",
+                    "type": "object",
+                    "field_mappings": [{"name": "latency", "type": "f64"}]
+                }
+            ],
+            "timestamp_field": "timestamp"
+        }"#;
+        let current_doc_mapping: DocMapping = serde_json::from_str(doc_mapping_json).unwrap();
+        let search_settings = SearchSettings::default();
+
+        let doc_mapping_update = validate_doc_mapping_update(
+            &current_doc_mapping,
+            &current_doc_mapping,
+            &search_settings,
+        )
+        .unwrap();
+        assert_eq!(doc_mapping_update, DocMappingUpdate::default());
+
+        let mut new_doc_mapping_json: JsonValue = serde_json::from_str(doc_mapping_json).unwrap();
+        new_doc_mapping_json["field_mappings"][1]["fast"] = JsonValue::Bool(true);
+        new_doc_mapping_json["field_mappings"][2]["fast"] =
+            serde_json::json!({"normalizer": "raw"});
+        new_doc_mapping_json["field_mappings"][3]["field_mappings"][0]["fast"] =
+            JsonValue::Bool(true);
+        let new_doc_mapping: DocMapping = serde_json::from_value(new_doc_mapping_json).unwrap();
+
+        let doc_mapping_update =
+            validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping, &search_settings)
+                .unwrap();
+        assert_eq!(
+            doc_mapping_update.new_fast_field_paths,
+            ["status", "service", "attributes.latency"]
+        );
+
+        // Fast fields can be turned back into regular fields.
+        let doc_mapping_update =
+            validate_doc_mapping_update(&new_doc_mapping, &current_doc_mapping, &search_settings)
+                .unwrap();
+        assert_eq!(
+            doc_mapping_update.disabled_fast_field_paths,
+            ["status", "service", "attributes.latency"]
+        );
+
+        // Fields can be added and their tokenizer can be changed.
+        let mut new_doc_mapping_json: JsonValue = serde_json::from_str(doc_mapping_json).unwrap();
+        new_doc_mapping_json["field_mappings"][2]["tokenizer"] =
+            JsonValue::String("default".to_string());
+        new_doc_mapping_json["field_mappings"]
+            .as_array_mut()
+            .unwrap()
+            .insert(0, serde_json::json!({"name": "host", "type": "text"}));
+        new_doc_mapping_json["field_mappings"][4]["field_mappings"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({"name": "region", "type": "text"}));
+        let new_doc_mapping: DocMapping = serde_json::from_value(new_doc_mapping_json).unwrap();
+        let doc_mapping_update =
+            validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping, &search_settings)
+                .unwrap();
+        assert_eq!(
+            doc_mapping_update,
+            DocMappingUpdate {
+                new_field_paths: vec!["attributes.region".to_string(), "host".to_string()],
+                retokenized_field_paths: vec!["service".to_string()],
+                ..Default::default()
+            }
+        );
+
+        // Other options cannot be updated.
+        let mut new_doc_mapping_json: JsonValue = serde_json::from_str(doc_mapping_json).unwrap();
+        new_doc_mapping_json["field_mappings"][1]["indexed"] = JsonValue::Bool(false);
+        let new_doc_mapping: DocMapping = serde_json::from_value(new_doc_mapping_json).unwrap();
+        let error =
+            validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping, &search_settings)
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("field `status` cannot be updated"));
+
+        // Fields cannot be removed.
+        let mut new_doc_mapping_json: JsonValue = serde_json::from_str(doc_mapping_json).unwrap();
+        new_doc_mapping_json["field_mappings"][3]["field_mappings"] = serde_json::json!([]);
+        let new_doc_mapping: DocMapping = serde_json::from_value(new_doc_mapping_json).unwrap();
+        let error =
+            validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping, &search_settings)
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("field `attributes.latency` cannot be removed"));
+
+        // Existing tokenizers cannot be updated.
+        let mut current_doc_mapping_json: JsonValue =
+            serde_json::from_str(doc_mapping_json).unwrap();
+        current_doc_mapping_json["tokenizers"] = serde_json::json!([
+            {"name": "custom", "type": "ngram", "min_gram": 3, "max_gram": 3}
+        ]);
+        let current_doc_mapping: DocMapping =
+            serde_json::from_value(current_doc_mapping_json.clone()).unwrap();
+        current_doc_mapping_json["tokenizers"][0]["max_gram"] = serde_json::json!(4);
+        let new_doc_mapping: DocMapping = serde_json::from_value(current_doc_mapping_json).unwrap();
+        let error =
+            validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping, &search_settings)
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("tokenizer `custom` cannot be updated or removed"));
+
+        // The rest of the doc mapping cannot be updated either.
+        let mut new_doc_mapping = current_doc_mapping.clone();
+        new_doc_mapping.store_source = true;
+        validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping, &search_settings)
+            .unwrap_err();
+    }
 }
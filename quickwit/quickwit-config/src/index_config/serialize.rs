@@ -17,15 +17,15 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use anyhow::Context;
+use anyhow::{ensure, Context};
 use quickwit_common::uri::Uri;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use super::validate_index_config;
 use crate::{
-    validate_identifier, ConfigFormat, DocMapping, IndexConfig, IndexingSettings, RetentionPolicy,
-    SearchSettings,
+    validate_identifier, ConfigFormat, DocMapping, IndexConfig, IndexingSettings, LegalHold,
+    RetentionPolicy, SearchSettings,
 };
 
 /// Alias for the latest serialization format.
@@ -97,13 +97,41 @@ impl IndexConfigForSerialization {
             indexing_settings: self.indexing_settings,
             search_settings: self.search_settings,
             retention_policy_opt: self.retention_policy_opt,
+            replication_factor_opt: self.replication_factor_opt,
+            legal_hold_opt: self.legal_hold_opt,
         };
         validate_index_config(
             &index_config.doc_mapping,
             &index_config.indexing_settings,
             &index_config.search_settings,
             &index_config.retention_policy_opt,
+            index_config.replication_factor_opt,
         )?;
+        if let Some(legal_hold) = &index_config.legal_hold_opt {
+            legal_hold.validate()?;
+        }
+        if let Some(dead_letter_config) = &index_config.indexing_settings.dead_letter {
+            ensure!(
+                dead_letter_config.index_id != index_config.index_id,
+                "index `{}` cannot be its own dead-letter index",
+                index_config.index_id
+            );
+        }
+        if let Some(ingest_mirror_config) = &index_config.indexing_settings.ingest_mirror {
+            ensure!(
+                ingest_mirror_config.endpoint.is_some()
+                    || ingest_mirror_config.index_id != index_config.index_id,
+                "index `{}` cannot mirror its documents to itself",
+                index_config.index_id
+            );
+        }
+        if let Some(trace_sampling_config) = &index_config.indexing_settings.trace_sampling {
+            ensure!(
+                trace_sampling_config.rollup_index_id.as_ref() != Some(&index_config.index_id),
+                "index `{}` cannot be its own trace sampling rollup index",
+                index_config.index_id
+            );
+        }
         Ok(index_config)
     }
 }
@@ -139,6 +167,14 @@ pub struct IndexConfigV0_8 {
     #[serde(rename = "retention")]
     #[serde(default)]
     pub retention_policy_opt: Option<RetentionPolicy>,
+    #[serde(rename = "replication_factor")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication_factor_opt: Option<usize>,
+    #[serde(rename = "legal_hold")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legal_hold_opt: Option<LegalHold>,
 }
 
 impl From<IndexConfig> for IndexConfigV0_8 {
@@ -150,6 +186,8 @@ impl From<IndexConfig> for IndexConfigV0_8 {
             indexing_settings: index_config.indexing_settings,
             search_settings: index_config.search_settings,
             retention_policy_opt: index_config.retention_policy_opt,
+            replication_factor_opt: index_config.replication_factor_opt,
+            legal_hold_opt: index_config.legal_hold_opt,
         }
     }
 }
@@ -158,6 +196,7 @@ impl From<IndexConfig> for IndexConfigV0_8 {
 mod test {
     use super::*;
     use crate::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
+    use crate::{DeadLetterConfig, IngestMirrorConfig};
 
     fn minimal_index_config_for_serialization() -> IndexConfigForSerialization {
         serde_yaml::from_str(
@@ -209,6 +248,7 @@ mod test {
         invalid_index_config.retention_policy_opt = Some(RetentionPolicy {
             retention_period: "90 days".to_string(),
             evaluation_schedule: "hourly".to_string(),
+            partition_overrides: Vec::new(),
         });
         let validation_err = invalid_index_config
             .build_and_validate(None)
@@ -217,6 +257,125 @@ mod test {
         assert!(validation_err.contains("retention policy requires a timestamp field"));
     }
 
+    #[test]
+    fn test_validate_replication_factor() {
+        let mut index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        index_config.replication_factor_opt = Some(2);
+        let index_config = index_config.build_and_validate(None).unwrap();
+        assert_eq!(index_config.replication_factor_opt, Some(2));
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.replication_factor_opt = Some(3);
+        let validation_err = invalid_index_config
+            .build_and_validate(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "replication factor must be either 1 or 2, got `3`"
+        );
+    }
+
+    #[test]
+    fn test_index_config_legal_hold() {
+        let index_config = minimal_index_config_for_serialization()
+            .build_and_validate(None)
+            .unwrap();
+        assert!(index_config.legal_hold_opt.is_none());
+
+        let index_config_json = serde_json::to_value(&index_config).unwrap();
+        assert!(index_config_json.get("legal_hold").is_none());
+
+        let mut index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        index_config.legal_hold_opt = Some(LegalHold {
+            split_ids: vec!["split-1".to_string()],
+            reason: Some("case-42".to_string()),
+        });
+        let index_config = index_config.build_and_validate(None).unwrap();
+        let legal_hold = index_config.legal_hold_opt.as_ref().unwrap();
+        assert!(!legal_hold.holds_all_splits());
+        assert!(legal_hold.holds_split("split-1"));
+        assert!(!legal_hold.holds_split("split-2"));
+
+        let index_config_json = serde_json::to_value(&index_config).unwrap();
+        assert_eq!(
+            index_config_json["legal_hold"],
+            serde_json::json!({"split_ids": ["split-1"], "reason": "case-42"})
+        );
+        let index_config_roundtrip: IndexConfig =
+            serde_json::from_value(index_config_json).unwrap();
+        assert_eq!(index_config_roundtrip, index_config);
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.legal_hold_opt = Some(LegalHold {
+            split_ids: vec!["".to_string()],
+            reason: None,
+        });
+        let validation_err = invalid_index_config
+            .build_and_validate(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(validation_err, "legal hold split IDs must not be empty");
+    }
+
+    #[test]
+    fn test_validate_dead_letter_index() {
+        let mut index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        index_config.indexing_settings.dead_letter = Some(DeadLetterConfig {
+            index_id: "hdfs-logs-dead-letter".to_string(),
+        });
+        index_config.build_and_validate(None).unwrap();
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.indexing_settings.dead_letter = Some(DeadLetterConfig {
+            index_id: "hdfs-logs".to_string(),
+        });
+        let validation_err = invalid_index_config
+            .build_and_validate(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "index `hdfs-logs` cannot be its own dead-letter index"
+        );
+    }
+
+    #[test]
+    fn test_validate_ingest_mirror_index() {
+        let mut index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        index_config.indexing_settings.ingest_mirror =
+            Some(IngestMirrorConfig::for_test("hdfs-logs-v2"));
+        index_config.build_and_validate(None).unwrap();
+
+        let mut index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        index_config.indexing_settings.ingest_mirror = Some(IngestMirrorConfig {
+            endpoint: Some("http://quickwit-staging:7280".to_string()),
+            ..IngestMirrorConfig::for_test("hdfs-logs")
+        });
+        index_config.build_and_validate(None).unwrap();
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.indexing_settings.ingest_mirror =
+            Some(IngestMirrorConfig::for_test("hdfs-logs"));
+        let validation_err = invalid_index_config
+            .build_and_validate(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "index `hdfs-logs` cannot mirror its documents to itself"
+        );
+    }
+
     #[test]
     fn test_minimal_index_config_missing_root_uri_no_default_uri() {
         let config_yaml = r#"
@@ -19,7 +19,11 @@
 
 mod serialize;
 
-use anyhow::ensure;
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use bytesize::ByteSize;
+use humantime::parse_duration;
 use quickwit_common::uri::Uri;
 use quickwit_proto::types::IndexId;
 use serde::{Deserialize, Serialize};
@@ -54,6 +58,117 @@ pub struct IndexTemplate {
     #[serde(rename = "retention")]
     #[serde(default)]
     pub retention_policy_opt: Option<RetentionPolicy>,
+    #[serde(rename = "rollover")]
+    #[serde(default)]
+    pub rollover_policy_opt: Option<RolloverPolicy>,
+    #[serde(rename = "replication_factor")]
+    #[serde(default)]
+    pub replication_factor_opt: Option<usize>,
+}
+
+/// Defines when the write index of an index template with rollover enabled must be rolled over.
+///
+/// When rollover is enabled, the index IDs matched by the template are write aliases: documents
+/// ingested into `my-logs` are written to the current write index `my-logs-000001`. Once the write
+/// index exceeds one of the thresholds below, the control plane creates the next write index
+/// `my-logs-000002` from the template and flips the alias to it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RolloverPolicy {
+    /// Maximum age of the write index, expressed in a human-friendly way (`1 day`, `1 week`,
+    /// ...).
+    #[serde(default)]
+    pub max_age: Option<String>,
+
+    /// Maximum size of the published splits of the write index, expressed in a human-friendly
+    /// way (`50 GB`, `1 TB`, ...).
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    pub max_size: Option<ByteSize>,
+}
+
+impl RolloverPolicy {
+    /// Number of digits of the generation suffix appended to the alias to build the write index
+    /// IDs. The suffix is zero-padded so that the IDs of successive write indexes sort
+    /// lexicographically.
+    const GENERATION_NUM_DIGITS: usize = 6;
+
+    pub fn max_age(&self) -> anyhow::Result<Option<Duration>> {
+        let Some(max_age) = &self.max_age else {
+            return Ok(None);
+        };
+        let max_age = parse_duration(max_age)
+            .with_context(|| format!("failed to parse rollover max age `{max_age}`"))?;
+        Ok(Some(max_age))
+    }
+
+    /// Returns `true` if a write index of age `index_age` and size `index_size` must be rolled
+    /// over.
+    pub fn should_rollover(&self, index_age: Duration, index_size: ByteSize) -> bool {
+        if let Ok(Some(max_age)) = self.max_age() {
+            if index_age >= max_age {
+                return true;
+            }
+        }
+        if let Some(max_size) = self.max_size {
+            if index_size >= max_size {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the ID of the write index of generation `generation` for the alias `alias`.
+    pub fn write_index_id(alias: &str, generation: u64) -> IndexId {
+        format!(
+            "{alias}-{generation:0width$}",
+            width = Self::GENERATION_NUM_DIGITS
+        )
+    }
+
+    /// Returns the generation of the write index `index_id` if it belongs to the alias `alias`.
+    pub fn parse_generation(alias: &str, index_id: &str) -> Option<u64> {
+        let suffix = index_id.strip_prefix(alias)?.strip_prefix('-')?;
+
+        if suffix.len() != Self::GENERATION_NUM_DIGITS
+            || !suffix.chars().all(|ch| ch.is_ascii_digit())
+        {
+            return None;
+        }
+        suffix.parse().ok()
+    }
+
+    /// Returns the alias and the generation of `index_id` if it is shaped like a write index ID,
+    /// i.e. `<alias>-NNNNNN`.
+    pub fn parse_alias(index_id: &str) -> Option<(&str, u64)> {
+        let (alias, _suffix) = index_id.rsplit_once('-')?;
+
+        if alias.is_empty() {
+            return None;
+        }
+        let generation = Self::parse_generation(alias, index_id)?;
+        Some((alias, generation))
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_age.is_some() || self.max_size.is_some(),
+            "rollover policy requires at least one of `max_age` or `max_size`"
+        );
+        if let Some(max_age) = self.max_age()? {
+            ensure!(
+                !max_age.is_zero(),
+                "rollover max age must be greater than zero"
+            );
+        }
+        if let Some(max_size) = self.max_size {
+            ensure!(
+                max_size.as_u64() > 0,
+                "rollover max size must be greater than zero"
+            );
+        }
+        Ok(())
+    }
 }
 
 impl IndexTemplate {
@@ -75,6 +190,8 @@ impl IndexTemplate {
             indexing_settings: self.indexing_settings.clone(),
             search_settings: self.search_settings.clone(),
             retention_policy_opt: self.retention_policy_opt.clone(),
+            replication_factor_opt: self.replication_factor_opt,
+            legal_hold_opt: None,
         };
         Ok(index_config)
     }
@@ -94,7 +211,11 @@ impl IndexTemplate {
             &self.indexing_settings,
             &self.search_settings,
             &self.retention_policy_opt,
+            self.replication_factor_opt,
         )?;
+        if let Some(rollover_policy) = &self.rollover_policy_opt {
+            rollover_policy.validate()?;
+        }
         Ok(())
     }
 
@@ -131,6 +252,8 @@ impl IndexTemplate {
             indexing_settings: IndexingSettings::default(),
             search_settings: SearchSettings::default(),
             retention_policy_opt: None,
+            rollover_policy_opt: None,
+            replication_factor_opt: None,
         }
     }
 }
@@ -171,7 +294,13 @@ impl TestableForRegression for IndexTemplate {
             retention_policy_opt: Some(RetentionPolicy {
                 retention_period: "42 days".to_string(),
                 evaluation_schedule: "daily".to_string(),
+                partition_overrides: Vec::new(),
+            }),
+            rollover_policy_opt: Some(RolloverPolicy {
+                max_age: Some("30 days".to_string()),
+                max_size: Some(ByteSize::gb(50)),
             }),
+            replication_factor_opt: None,
         }
     }
 
@@ -214,6 +343,102 @@ mod tests {
         assert_eq!(index_template.priority, 100);
         assert_eq!(index_template.description.unwrap(), "Test description.");
         assert_eq!(index_template.doc_mapping.timestamp_field.unwrap(), "ts");
+        assert!(index_template.rollover_policy_opt.is_none());
+    }
+
+    #[test]
+    fn test_index_template_rollover_serde() {
+        let index_template_yaml = r#"
+            version: 0.8
+
+            template_id: test-template
+            index_id_patterns:
+              - test-logs-*
+            doc_mapping:
+              field_mappings:
+                - name: ts
+                  type: datetime
+                  fast: true
+              timestamp_field: ts
+            rollover:
+              max_age: 7 days
+              max_size: 50 GB
+        "#;
+        let index_template: IndexTemplate = serde_yaml::from_str(index_template_yaml).unwrap();
+        let rollover_policy = index_template.rollover_policy_opt.unwrap();
+        assert_eq!(rollover_policy.max_age.as_deref(), Some("7 days"));
+        assert_eq!(
+            rollover_policy.max_age().unwrap(),
+            Some(Duration::from_secs(7 * 24 * 3600))
+        );
+        assert_eq!(rollover_policy.max_size, Some(ByteSize::gb(50)));
+    }
+
+    #[test]
+    fn test_rollover_policy_should_rollover() {
+        let rollover_policy = RolloverPolicy {
+            max_age: Some("1 day".to_string()),
+            max_size: None,
+        };
+        let one_hour = Duration::from_secs(3600);
+        let one_day = Duration::from_secs(24 * 3600);
+
+        assert!(!rollover_policy.should_rollover(one_hour, ByteSize::tb(1)));
+        assert!(rollover_policy.should_rollover(one_day, ByteSize(0)));
+
+        let rollover_policy = RolloverPolicy {
+            max_age: None,
+            max_size: Some(ByteSize::gb(1)),
+        };
+        assert!(!rollover_policy.should_rollover(one_day * 365, ByteSize::mb(999)));
+        assert!(rollover_policy.should_rollover(one_hour, ByteSize::gb(1)));
+
+        let rollover_policy = RolloverPolicy {
+            max_age: Some("1 day".to_string()),
+            max_size: Some(ByteSize::gb(1)),
+        };
+        assert!(!rollover_policy.should_rollover(one_hour, ByteSize::mb(1)));
+        assert!(rollover_policy.should_rollover(one_day, ByteSize::mb(1)));
+        assert!(rollover_policy.should_rollover(one_hour, ByteSize::gb(2)));
+    }
+
+    #[test]
+    fn test_rollover_policy_write_index_id() {
+        assert_eq!(
+            RolloverPolicy::write_index_id("test-logs", 1),
+            "test-logs-000001"
+        );
+        assert_eq!(
+            RolloverPolicy::write_index_id("test-logs", 42),
+            "test-logs-000042"
+        );
+        assert_eq!(
+            RolloverPolicy::parse_generation("test-logs", "test-logs-000042"),
+            Some(42)
+        );
+        assert_eq!(
+            RolloverPolicy::parse_generation("test-logs", "test-logs"),
+            None
+        );
+        assert_eq!(
+            RolloverPolicy::parse_generation("test-logs", "test-logs-42"),
+            None
+        );
+        assert_eq!(
+            RolloverPolicy::parse_generation("test-logs", "test-logs-foo-000042"),
+            None
+        );
+        assert_eq!(
+            RolloverPolicy::parse_alias("test-logs-000042"),
+            Some(("test-logs", 42))
+        );
+        assert_eq!(RolloverPolicy::parse_alias("test-logs"), None);
+        assert_eq!(RolloverPolicy::parse_alias("test-logs-42"), None);
+        assert_eq!(RolloverPolicy::parse_alias("-000042"), None);
+        assert_eq!(
+            RolloverPolicy::parse_generation("test-logs", "test-logsfoo000042"),
+            None
+        );
     }
 
     #[test]
@@ -226,10 +451,12 @@ mod tests {
         };
         index_template.search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            ..Default::default()
         };
         index_template.retention_policy_opt = Some(RetentionPolicy {
             retention_period: "42 days".to_string(),
             evaluation_schedule: "hourly".to_string(),
+            partition_overrides: Vec::new(),
         });
         let default_index_root_uri = Uri::for_test("s3://test-bucket/indexes");
 
@@ -281,10 +508,36 @@ mod tests {
         index_template.retention_policy_opt = Some(RetentionPolicy {
             retention_period: "".to_string(),
             evaluation_schedule: "".to_string(),
+            partition_overrides: Vec::new(),
         });
         let error = index_template.validate().unwrap_err();
         assert!(error
             .to_string()
             .contains("failed to parse retention period"));
+
+        let mut index_template = IndexTemplate::for_test("test-template", &["test-index-*"], 0);
+        index_template.rollover_policy_opt = Some(RolloverPolicy {
+            max_age: None,
+            max_size: None,
+        });
+        let error = index_template.validate().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("requires at least one of `max_age` or `max_size`"));
+
+        index_template.rollover_policy_opt = Some(RolloverPolicy {
+            max_age: Some("foo".to_string()),
+            max_size: None,
+        });
+        let error = index_template.validate().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("failed to parse rollover max age"));
+
+        index_template.rollover_policy_opt = Some(RolloverPolicy {
+            max_age: Some("1 day".to_string()),
+            max_size: Some(ByteSize::gb(50)),
+        });
+        index_template.validate().unwrap();
     }
 }
@@ -29,6 +29,7 @@ use quickwit_common::uri::Uri;
 use regex::Regex;
 
 mod cluster_config;
+mod cluster_settings;
 mod config_value;
 mod index_config;
 mod index_template;
@@ -40,28 +41,39 @@ pub mod service;
 mod source_config;
 mod storage_config;
 mod templating;
+mod wasm_plugin_config;
 
 pub use cluster_config::ClusterConfig;
+pub use cluster_settings::ClusterSettings;
 // We export that one for backward compatibility.
 // See #2048
 use index_config::serialize::{IndexConfigV0_8, VersionedIndexConfig};
 pub use index_config::{
-    build_doc_mapper, load_index_config_from_user_config, DocMapping, IndexConfig,
-    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
+    build_doc_mapper, load_index_config_from_user_config, validate_doc_mapping_update,
+    AdaptiveSplitSizeConfig, BlockedQueryKind, BlockedQueryPattern, DeadLetterConfig, DocMapping,
+    DocMappingUpdate, EmbeddingConfig, EmbeddingFailurePolicy, EmbeddingFieldConfig,
+    EnrichmentConfig, IndexConfig, IndexingResources, IndexingSettings, IngestMirrorConfig,
+    IngestionQuotaConfig, LegalHold, MergeThrottlingConfig, QueryRules, RerankerConfig,
+    RerankerFailurePolicy, RetentionPolicy, SearchSettings, TraceSamplingConfig,
 };
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 pub use source_config::{
-    load_source_config_from_user_config, FileSourceParams, KafkaSourceParams, KinesisSourceParams,
-    PubSubSourceParams, PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint, SourceConfig,
-    SourceInputFormat, SourceParams, TransformConfig, VecSourceParams, VoidSourceParams,
-    CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
+    load_source_config_from_user_config, FileSourceParams, JsonSchemaConfig,
+    JsonSchemaVersionConfig, KafkaSourceParams, KinesisSourceParams, ParquetSourceParams,
+    PubSubSourceParams, PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint,
+    ReindexSourceParams, SourceConfig, SourceInputFormat, SourceParams, SqsMessageType,
+    SqsSourceParams, SyslogProtocol, SyslogSourceParams, TransformConfig, TransformStageAction,
+    TransformStageConfig, VecSourceParams, VoidSourceParams, CLI_SOURCE_ID, INGEST_API_SOURCE_ID,
+    INGEST_V2_SOURCE_ID,
 };
 use tracing::warn;
 
 use crate::index_template::IndexTemplateV0_8;
-pub use crate::index_template::{IndexTemplate, IndexTemplateId, VersionedIndexTemplate};
+pub use crate::index_template::{
+    IndexTemplate, IndexTemplateId, RolloverPolicy, VersionedIndexTemplate,
+};
 use crate::merge_policy_config::{
     ConstWriteAmplificationMergePolicyConfig, MergePolicyConfig, StableLogMergePolicyConfig,
 };
@@ -69,21 +81,41 @@ pub use crate::metastore_config::{
     MetastoreBackend, MetastoreConfig, MetastoreConfigs, PostgresMetastoreConfig,
 };
 pub use crate::node_config::{
-    enable_ingest_v2, IndexerConfig, IngestApiConfig, JaegerConfig, NodeConfig, SearcherConfig,
-    SplitCacheLimits, DEFAULT_QW_CONFIG_PATH,
+    enable_ingest_v2, ControlPlaneConfig, IndexerConfig, IngestApiConfig, JaegerConfig,
+    KafkaApiConfig, KafkaSaslPlainUser, LegalHoldSearcher, NodeConfig, OtelApiKeyConfig,
+    OtelAuthConfig, OtelJwtConfig, OtelRole, OtlpApiKeyQuotaConfig, SearcherConfig,
+    ShardIdStrategy, SplitCacheLimits, WalCompression, WalOffloadConfig, DEFAULT_QW_CONFIG_PATH,
 };
 use crate::source_config::serialize::{SourceConfigV0_7, SourceConfigV0_8, VersionedSourceConfig};
 pub use crate::storage_config::{
     AzureStorageConfig, FileStorageConfig, GoogleCloudStorageConfig, RamStorageConfig,
     S3StorageConfig, StorageBackend, StorageBackendFlavor, StorageConfig, StorageConfigs,
 };
+pub use crate::wasm_plugin_config::WasmPluginConfig;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(
+    ClusterSettings,
+    DeadLetterConfig,
+    EmbeddingConfig,
+    EmbeddingFailurePolicy,
+    EmbeddingFieldConfig,
+    EnrichmentConfig,
+    AdaptiveSplitSizeConfig,
     IndexingResources,
+    IngestionQuotaConfig,
+    IngestMirrorConfig,
     IndexingSettings,
+    MergeThrottlingConfig,
     SearchSettings,
+    TraceSamplingConfig,
+    QueryRules,
+    RerankerConfig,
+    RerankerFailurePolicy,
+    BlockedQueryPattern,
+    BlockedQueryKind,
     RetentionPolicy,
+    RolloverPolicy,
     MergePolicyConfig,
     DocMapping,
     VersionedSourceConfig,
@@ -99,12 +131,23 @@ pub use crate::storage_config::{
     PubSubSourceParams,
     KafkaSourceParams,
     KinesisSourceParams,
+    ParquetSourceParams,
     PulsarSourceParams,
     PulsarSourceAuth,
     RegionOrEndpoint,
+    ReindexSourceParams,
+    SqsMessageType,
+    SqsSourceParams,
+    SyslogProtocol,
+    SyslogSourceParams,
     ConstWriteAmplificationMergePolicyConfig,
     StableLogMergePolicyConfig,
     TransformConfig,
+    TransformStageAction,
+    TransformStageConfig,
+    WasmPluginConfig,
+    JsonSchemaConfig,
+    JsonSchemaVersionConfig,
     VecSourceParams,
     VoidSourceParams,
 )))]
@@ -124,6 +167,18 @@ pub fn validate_identifier(label: &str, value: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Checks whether a tenant ID conforms to Quickwit naming conventions. Tenant IDs are appended to
+/// index IDs after a `-` separator, so they must not contain any `-` themselves for the suffix of
+/// an index ID to identify its tenant unambiguously.
+pub fn validate_tenant_id(tenant_id: &str) -> anyhow::Result<()> {
+    validate_identifier("Tenant", tenant_id)?;
+    ensure!(
+        !tenant_id.contains('-'),
+        "Tenant ID `{tenant_id}` is invalid: tenant IDs must not contain `-`"
+    );
+    Ok(())
+}
+
 /// Checks whether an index ID pattern conforms to Quickwit conventions.
 /// Index ID patterns accept the same characters as identifiers AND accept `*`
 /// chars to allow for glob-like patterns.
@@ -269,6 +324,15 @@ mod tests {
     use super::validate_identifier;
     use crate::validate_index_id_pattern;
 
+    #[test]
+    fn test_validate_tenant_id() {
+        validate_tenant_id("acme").unwrap();
+        validate_tenant_id("acme_corp.eu").unwrap();
+        validate_tenant_id("ac").unwrap_err();
+        validate_tenant_id("acme/corp").unwrap_err();
+        validate_tenant_id("acme-corp").unwrap_err();
+    }
+
     #[test]
     fn test_validate_identifier() {
         validate_identifier("cluster", "").unwrap_err();
@@ -19,14 +19,14 @@
 
 mod serialize;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::net::SocketAddr;
 use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
 
-use anyhow::{bail, ensure};
+use anyhow::{bail, ensure, Context};
 use bytesize::ByteSize;
 use http::HeaderMap;
 use once_cell::sync::Lazy;
@@ -34,12 +34,14 @@ use quickwit_common::net::HostAddr;
 use quickwit_common::uri::Uri;
 use quickwit_proto::indexing::CpuCapacity;
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 use tracing::warn;
 
+use crate::merge_policy_config::{parse_human_duration, serialize_duration};
 use crate::node_config::serialize::load_node_config_with_env;
 use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
-use crate::{ConfigFormat, MetastoreConfigs};
+use crate::{validate_identifier, validate_tenant_id, ConfigFormat, MetastoreConfigs};
 
 pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
 
@@ -100,10 +102,35 @@ pub struct IndexerConfig {
     /// (defaults to num_cpu / 2).
     #[serde(default = "IndexerConfig::default_merge_concurrency")]
     pub merge_concurrency: NonZeroUsize,
+    /// Maximum amount of local disk space that the in-flight merges of a single indexing
+    /// pipeline can reserve for their scratch directories (downloaded splits and merged split
+    /// being packaged). Merges exceeding the quota are delayed until some space is released.
+    #[serde(default)]
+    pub merge_scratch_space_quota_per_pipeline: Option<ByteSize>,
+    /// Maximum amount of local disk space that all the in-flight merges of the node can reserve
+    /// for their scratch directories.
+    #[serde(default)]
+    pub merge_scratch_space_quota: Option<ByteSize>,
+    /// Caps the aggregate bandwidth (per second) of the split uploads to the object storage
+    /// performed by the node. Uploads of freshly indexed splits are prioritized over the uploads
+    /// of merged splits.
+    #[serde(default)]
+    pub max_upload_bandwidth: Option<ByteSize>,
     /// Enables the OpenTelemetry exporter endpoint to ingest logs and traces via the OpenTelemetry
     /// Protocol (OTLP).
     #[serde(default = "IndexerConfig::default_enable_otlp_endpoint")]
     pub enable_otlp_endpoint: bool,
+    /// Span and log record throughput quotas enforced on the OTLP endpoint, per API key. The
+    /// exports authenticated with an API key not listed here are not throttled.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub otlp_api_key_quotas: Vec<OtlpApiKeyQuotaConfig>,
+    /// Resource attribute routing the logs and traces ingested via OTLP to per-tenant indexes:
+    /// the records of a resource carrying the attribute are ingested into the index
+    /// `{index_id}-{attribute_value}`, where `index_id` is the index targeted by the request.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub otlp_tenant_resource_attribute: Option<String>,
     #[serde(default = "IndexerConfig::default_enable_cooperative_indexing")]
     pub enable_cooperative_indexing: bool,
     #[serde(default = "IndexerConfig::default_cpu_capacity")]
@@ -158,9 +185,70 @@ impl IndexerConfig {
             cpu_capacity: PIPELINE_FULL_CAPACITY * 4u32,
             max_merge_write_throughput: None,
             merge_concurrency: NonZeroUsize::new(3).unwrap(),
+            merge_scratch_space_quota_per_pipeline: None,
+            merge_scratch_space_quota: None,
+            max_upload_bandwidth: None,
+            otlp_api_key_quotas: Vec::new(),
+            otlp_tenant_resource_attribute: None,
         };
         Ok(indexer_config)
     }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let mut api_keys = HashSet::with_capacity(self.otlp_api_key_quotas.len());
+        for quota_config in &self.otlp_api_key_quotas {
+            quota_config.validate()?;
+            ensure!(
+                api_keys.insert(&quota_config.api_key),
+                "API key of OTLP tenant `{}` is already assigned to another tenant",
+                quota_config.tenant_id
+            );
+        }
+        if let Some(resource_attribute) = &self.otlp_tenant_resource_attribute {
+            ensure!(
+                !resource_attribute.trim().is_empty(),
+                "`otlp_tenant_resource_attribute` must not be empty"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Throughput quotas of the spans and log records exported via OTLP with a given API key.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpApiKeyQuotaConfig {
+    /// API key sent by the clients in the `x-api-key` header or as an `authorization` bearer
+    /// token.
+    pub api_key: String,
+    /// Identifies the tenant in the logs and metrics so that the API key is never exposed.
+    pub tenant_id: String,
+    /// Maximum number of spans per second. Not capped when unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_spans_per_sec: Option<NonZeroU32>,
+    /// Maximum number of log records per second. Not capped when unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_log_records_per_sec: Option<NonZeroU32>,
+}
+
+impl OtlpApiKeyQuotaConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_identifier("Tenant ID", &self.tenant_id)?;
+        ensure!(
+            !self.api_key.trim().is_empty(),
+            "API key of OTLP tenant `{}` must not be empty",
+            self.tenant_id
+        );
+        ensure!(
+            self.max_spans_per_sec.is_some() || self.max_log_records_per_sec.is_some(),
+            "OTLP tenant `{}` must set at least one of `max_spans_per_sec` and \
+             `max_log_records_per_sec`",
+            self.tenant_id
+        );
+        Ok(())
+    }
 }
 
 impl Default for IndexerConfig {
@@ -174,6 +262,11 @@ impl Default for IndexerConfig {
             cpu_capacity: Self::default_cpu_capacity(),
             merge_concurrency: Self::default_merge_concurrency(),
             max_merge_write_throughput: None,
+            merge_scratch_space_quota_per_pipeline: None,
+            merge_scratch_space_quota: None,
+            max_upload_bandwidth: None,
+            otlp_api_key_quotas: Vec::new(),
+            otlp_tenant_resource_attribute: None,
         }
     }
 }
@@ -214,11 +307,31 @@ pub struct SearcherConfig {
     pub partial_request_cache_capacity: ByteSize,
     pub max_num_concurrent_split_searches: usize,
     pub max_num_concurrent_split_streams: usize,
+    /// Maximum number of searches of a single Elasticsearch `_msearch` request running
+    /// concurrently. Requests can lower it with the `max_concurrent_searches` parameter.
+    pub max_num_concurrent_msearch_searches: usize,
     // Strangely, if None, this will also have the effect of not forwarding
     // to searcher.
     // TODO document and fix if necessary.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub split_cache: Option<SplitCacheLimits>,
+    /// Callers allowed to search the splits under legal hold that have been marked for deletion.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub legal_hold_searchers: Vec<LegalHoldSearcher>,
+    /// Maximum aggregate bandwidth of the storage reads of the searcher, shared fairly between
+    /// tenants and priorities. Not capped when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_storage_read_bandwidth: Option<ByteSize>,
+    /// Maximum length, in characters, of the regexes of regex queries.
+    pub max_regex_length: usize,
+    /// Maximum number of states of the automaton compiled from the regex of a regex query.
+    pub max_regex_automaton_states: usize,
+    /// Whether term filters may be evaluated by scanning their fast field instead of reading
+    /// their posting list, see `fast_field_scan_min_doc_freq`.
+    pub enable_fast_field_pushdown: bool,
+    /// Minimum number of documents of the posting list of a term filter for the filter to be
+    /// evaluated by scanning its fast field over the time range targeted by the query.
+    pub fast_field_scan_min_doc_freq: u64,
 }
 
 impl Default for SearcherConfig {
@@ -229,15 +342,40 @@ impl Default for SearcherConfig {
             partial_request_cache_capacity: ByteSize::mb(64),
             max_num_concurrent_split_streams: 100,
             max_num_concurrent_split_searches: 100,
+            max_num_concurrent_msearch_searches: 10,
             aggregation_memory_limit: ByteSize::mb(500),
             aggregation_bucket_limit: 65000,
             split_cache: None,
+            legal_hold_searchers: Vec::new(),
+            max_storage_read_bandwidth: None,
+            max_regex_length: 1_000,
+            max_regex_automaton_states: 10_000,
+            enable_fast_field_pushdown: true,
+            fast_field_scan_min_doc_freq: 10_000,
         }
     }
 }
 
 impl SearcherConfig {
     fn validate(&self) -> anyhow::Result<()> {
+        if self.max_num_concurrent_msearch_searches == 0 {
+            anyhow::bail!("max_num_concurrent_msearch_searches must be strictly positive");
+        }
+        if self.max_regex_automaton_states == 0 {
+            anyhow::bail!("max_regex_automaton_states must be strictly positive");
+        }
+        let mut legal_hold_search_tokens = HashSet::new();
+        for legal_hold_searcher in &self.legal_hold_searchers {
+            if legal_hold_searcher.name.is_empty() || legal_hold_searcher.token.is_empty() {
+                anyhow::bail!("legal hold searchers must have a non-empty name and token");
+            }
+            if !legal_hold_search_tokens.insert(&legal_hold_searcher.token) {
+                anyhow::bail!(
+                    "legal hold searcher `{}` shares its token with another legal hold searcher",
+                    legal_hold_searcher.name
+                );
+            }
+        }
         if let Some(split_cache_limits) = self.split_cache {
             if self.max_num_concurrent_split_searches
                 > split_cache_limits.max_file_descriptors.get() as usize
@@ -262,6 +400,75 @@ impl SearcherConfig {
         }
         Ok(())
     }
+
+    /// Returns the caller allowed to search the splits under legal hold that authenticates with
+    /// `token`, if any. The tokens are compared in constant time so that they cannot be guessed
+    /// from the response times.
+    pub fn find_legal_hold_searcher(&self, token: &str) -> Option<&LegalHoldSearcher> {
+        let mut matching_legal_hold_searcher_opt: Option<&LegalHoldSearcher> = None;
+
+        for legal_hold_searcher in &self.legal_hold_searchers {
+            if bool::from(legal_hold_searcher.token.as_bytes().ct_eq(token.as_bytes())) {
+                matching_legal_hold_searcher_opt = Some(legal_hold_searcher);
+            }
+        }
+        matching_legal_hold_searcher_opt
+    }
+
+    /// Redacts the tokens of the legal hold searchers.
+    pub fn redact(&mut self) {
+        for legal_hold_searcher in &mut self.legal_hold_searchers {
+            legal_hold_searcher.token = "***redacted***".to_string();
+        }
+    }
+}
+
+/// Caller allowed to search the splits under legal hold that have been marked for deletion.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LegalHoldSearcher {
+    /// Name of the caller, recorded in the audit logs.
+    pub name: String,
+    /// Token sent by the caller along with its search requests.
+    pub token: String,
+}
+
+impl std::fmt::Debug for LegalHoldSearcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LegalHoldSearcher")
+            .field("name", &self.name)
+            .field("token", &"***redacted***")
+            .finish()
+    }
+}
+
+/// Strategy used by the control plane to generate the IDs of new shards.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardIdStrategy {
+    /// Plain ULIDs, e.g. `01HV4CVBF1WBZ4V5XRTGKMXDMP`.
+    #[default]
+    Ulid,
+    /// ULIDs prefixed with the UTC creation time, e.g.
+    /// `20240410T083512Z-01HV4CVBF1WBZ4V5XRTGKMXDMP`.
+    TimePrefixed,
+    /// ULIDs prefixed with the ID of the node running the control plane, e.g.
+    /// `control-plane-0-01HV4CVBF1WBZ4V5XRTGKMXDMP`.
+    NodePrefixed,
+}
+
+/// Compression applied by the ingesters to the documents written to their write-ahead log and
+/// sent over the replication stream.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalCompression {
+    /// Documents are stored uncompressed.
+    #[default]
+    None,
+    /// LZ4 block compression: cheap on CPU, moderate compression ratio.
+    Lz4,
+    /// Zstandard compression: higher compression ratio at a higher CPU cost.
+    Zstd,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -271,6 +478,152 @@ pub struct IngestApiConfig {
     pub max_queue_disk_usage: ByteSize,
     pub replication_factor: usize,
     pub content_length_limit: ByteSize,
+    pub shard_id_strategy: ShardIdStrategy,
+    pub wal_compression: WalCompression,
+    /// Settings of the Kafka-compatible ingest endpoint, disabled when unset.
+    pub kafka: Option<KafkaApiConfig>,
+    /// Settings of the offloading of the WAL to object storage, disabled when unset.
+    pub wal_offload: Option<WalOffloadConfig>,
+}
+
+/// Settings of the endpoint speaking a subset of the Kafka wire protocol (Produce API only), which
+/// lets Kafka producers write directly into the ingest API v2.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaApiConfig {
+    /// Port of the endpoint, which listens on the same IP address as the REST server.
+    pub listen_port: u16,
+    /// Maps topics to index IDs. Producing to a topic missing from the mapping fails with
+    /// `UNKNOWN_TOPIC_OR_PARTITION`.
+    #[serde(default)]
+    pub topic_mapping: BTreeMap<String, String>,
+    /// Users allowed to authenticate with the SASL/PLAIN mechanism. The clients must authenticate
+    /// before sending any request other than `ApiVersions`.
+    pub sasl_plain_users: Vec<KafkaSaslPlainUser>,
+    /// Maximum number of concurrent connections. The endpoint stops accepting new connections
+    /// until one of the open connections closes.
+    #[serde(default = "KafkaApiConfig::default_max_connections")]
+    pub max_connections: NonZeroUsize,
+}
+
+impl KafkaApiConfig {
+    fn default_max_connections() -> NonZeroUsize {
+        NonZeroUsize::new(1_024).unwrap()
+    }
+
+    /// Returns the ID of the index the records of `topic` are written to, if the topic is mapped.
+    pub fn index_id_for_topic(&self, topic: &str) -> Option<&str> {
+        self.topic_mapping.get(topic).map(String::as_str)
+    }
+
+    /// Returns the SASL/PLAIN user authenticating with `username` and `password`, if any. The
+    /// passwords are compared in constant time so that they cannot be guessed from the response
+    /// times.
+    pub fn authenticate(&self, username: &str, password: &str) -> Option<&KafkaSaslPlainUser> {
+        let mut matching_user_opt: Option<&KafkaSaslPlainUser> = None;
+
+        for sasl_plain_user in &self.sasl_plain_users {
+            if sasl_plain_user.username == username
+                && bool::from(
+                    sasl_plain_user
+                        .password
+                        .as_bytes()
+                        .ct_eq(password.as_bytes()),
+                )
+            {
+                matching_user_opt = Some(sasl_plain_user);
+            }
+        }
+        matching_user_opt
+    }
+
+    /// Redacts the passwords of the SASL/PLAIN users.
+    pub fn redact(&mut self) {
+        for sasl_plain_user in &mut self.sasl_plain_users {
+            sasl_plain_user.password = "***redacted***".to_string();
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for index_id in self.topic_mapping.values() {
+            validate_identifier("Index ID", index_id)?;
+        }
+        ensure!(
+            !self.sasl_plain_users.is_empty(),
+            "the Kafka endpoint requires at least one SASL/PLAIN user"
+        );
+        let mut usernames = HashSet::with_capacity(self.sasl_plain_users.len());
+        for sasl_plain_user in &self.sasl_plain_users {
+            ensure!(
+                !sasl_plain_user.username.is_empty() && !sasl_plain_user.password.is_empty(),
+                "SASL/PLAIN users must have a non-empty username and password"
+            );
+            ensure!(
+                usernames.insert(&sasl_plain_user.username),
+                "SASL/PLAIN user `{}` is declared more than once",
+                sasl_plain_user.username
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Kafka client allowed to authenticate with the SASL/PLAIN mechanism.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaSaslPlainUser {
+    pub username: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for KafkaSaslPlainUser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaSaslPlainUser")
+            .field("username", &self.username)
+            .field("password", &"***redacted***")
+            .finish()
+    }
+}
+
+/// Settings of the offloading of the WAL to object storage. The records not yet consumed by the
+/// indexing pipelines are offloaded and truncated locally when the WAL is close to full, or once
+/// they reach `max_local_age`. The ingesters then serve the truncated records from the offloaded
+/// copy. The followers truncate the records offloaded by the leaders of their replica shards, so
+/// all the ingesters of the cluster must share the same `uri`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalOffloadConfig {
+    /// URI of the directory where the WAL segments are offloaded.
+    pub uri: Uri,
+    /// Disk usage of the WAL, as a percentage of `max_queue_disk_usage`, above which the
+    /// ingesters start offloading.
+    #[serde(default = "WalOffloadConfig::default_disk_usage_threshold_percent")]
+    pub disk_usage_threshold_percent: u8,
+    /// Age (e.g. `10m`) after which the records are offloaded regardless of the disk usage of
+    /// the WAL. Disabled when unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_local_age: Option<String>,
+}
+
+impl WalOffloadConfig {
+    fn default_disk_usage_threshold_percent() -> u8 {
+        75
+    }
+
+    pub fn max_local_age(&self) -> anyhow::Result<Option<Duration>> {
+        let Some(max_local_age) = &self.max_local_age else {
+            return Ok(None);
+        };
+        let max_local_age = humantime::parse_duration(max_local_age).with_context(|| {
+            format!("failed to parse WAL offload max local age `{max_local_age}`")
+        })?;
+        ensure!(
+            !max_local_age.is_zero(),
+            "WAL offload max local age must be strictly positive"
+        );
+        Ok(Some(max_local_age))
+    }
 }
 
 impl Default for IngestApiConfig {
@@ -280,6 +633,10 @@ impl Default for IngestApiConfig {
             max_queue_disk_usage: ByteSize::gib(4),   // TODO maybe we want more?
             replication_factor: 1,
             content_length_limit: ByteSize::mib(10),
+            shard_id_strategy: ShardIdStrategy::default(),
+            wal_compression: WalCompression::default(),
+            kafka: None,
+            wal_offload: None,
         }
     }
 }
@@ -325,6 +682,79 @@ impl IngestApiConfig {
             self.max_queue_disk_usage,
             self.max_queue_memory_usage
         );
+        if let Some(kafka_config) = &self.kafka {
+            kafka_config.validate()?;
+        }
+        if let Some(wal_offload_config) = &self.wal_offload {
+            ensure!(
+                (1..=100).contains(&wal_offload_config.disk_usage_threshold_percent),
+                "disk_usage_threshold_percent must be between 1 and 100, got `{}`",
+                wal_offload_config.disk_usage_threshold_percent
+            );
+            wal_offload_config.max_local_age()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ControlPlaneConfig {
+    /// URI of the directory where the control plane saves a snapshot of its model when it shuts
+    /// down gracefully. The next control plane restores its model from the snapshot instead of
+    /// loading it from the metastore. Snapshots are disabled when unset.
+    pub model_snapshot_uri: Option<Uri>,
+    /// Snapshots older than this are discarded and the model is loaded from the metastore.
+    pub model_snapshot_max_age_secs: NonZeroU64,
+    /// Timeout of the close shards requests sent by the control plane to the ingesters.
+    #[serde(deserialize_with = "parse_human_duration")]
+    #[serde(serialize_with = "serialize_duration")]
+    pub close_shards_request_timeout: Duration,
+    /// Timeout of the init shards requests sent by the control plane to the ingesters.
+    #[serde(deserialize_with = "parse_human_duration")]
+    #[serde(serialize_with = "serialize_duration")]
+    pub init_shards_request_timeout: Duration,
+    /// Delay between opening the new shards of a rebalance and closing the shards they replace,
+    /// which gives the ingesters time to learn about the new shards via gossip.
+    #[serde(deserialize_with = "parse_human_duration")]
+    #[serde(serialize_with = "serialize_duration")]
+    pub close_shards_upon_rebalance_delay: Duration,
+    /// Whether the control plane creates the indexes targeted by ingest requests that do not
+    /// exist yet, from the index template matching their ID.
+    pub auto_create_indexes: bool,
+    /// Whether the control plane accepts force close shards requests. Force-closing shards
+    /// bypasses the regular shard lifecycle, so it is disabled by default.
+    pub enable_force_close_shards: bool,
+}
+
+impl Default for ControlPlaneConfig {
+    fn default() -> Self {
+        Self {
+            model_snapshot_uri: None,
+            model_snapshot_max_age_secs: NonZeroU64::new(300).unwrap(),
+            close_shards_request_timeout: Duration::from_secs(3),
+            init_shards_request_timeout: Duration::from_secs(3),
+            close_shards_upon_rebalance_delay: Duration::from_secs(10),
+            auto_create_indexes: false,
+            enable_force_close_shards: false,
+        }
+    }
+}
+
+impl ControlPlaneConfig {
+    pub fn model_snapshot_max_age(&self) -> Duration {
+        Duration::from_secs(self.model_snapshot_max_age_secs.get())
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.close_shards_request_timeout.is_zero(),
+            "close_shards_request_timeout must be strictly positive"
+        );
+        ensure!(
+            !self.init_shards_request_timeout.is_zero(),
+            "init_shards_request_timeout must be strictly positive"
+        );
         Ok(())
     }
 }
@@ -398,6 +828,166 @@ impl Default for JaegerConfig {
     }
 }
 
+/// Authentication and authorization of the OTLP ingest and Jaeger query endpoints. The endpoints
+/// are open until at least one API key or a JWT verifier is configured.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtelAuthConfig {
+    /// API keys sent by the clients in the `x-api-key` header or as an `authorization` bearer
+    /// token.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<OtelApiKeyConfig>,
+    /// Verifies the JSON Web Tokens sent by the clients as `authorization` bearer tokens.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<OtelJwtConfig>,
+}
+
+impl OtelAuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.api_keys.is_empty() || self.jwt.is_some()
+    }
+
+    /// Redacts the API keys and the JWT secret.
+    pub fn redact(&mut self) {
+        for api_key_config in &mut self.api_keys {
+            api_key_config.api_key = "***redacted***".to_string();
+        }
+        if let Some(jwt_config) = &mut self.jwt {
+            jwt_config.hmac_secret = "***redacted***".to_string();
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let mut api_keys = HashSet::with_capacity(self.api_keys.len());
+        for api_key_config in &self.api_keys {
+            api_key_config.validate()?;
+            ensure!(
+                api_keys.insert(&api_key_config.api_key),
+                "API key `{}` is declared more than once",
+                api_key_config.name
+            );
+        }
+        if let Some(jwt_config) = &self.jwt {
+            jwt_config.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Roles granted to the callers of the OTLP ingest and Jaeger query endpoints.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OtelRole {
+    /// Exports logs and traces via OTLP.
+    Ingest,
+    /// Queries traces via the Jaeger APIs.
+    Read,
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtelApiKeyConfig {
+    /// Identifies the API key in the logs so that the key itself is never exposed.
+    pub name: String,
+    pub api_key: String,
+    pub roles: Vec<OtelRole>,
+    /// Confines the caller to the indexes of the tenant: the records it exports are ingested into
+    /// `{index_id}-{tenant_id}` and its queries only target the indexes suffixed with
+    /// `-{tenant_id}`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+}
+
+impl std::fmt::Debug for OtelApiKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OtelApiKeyConfig")
+            .field("name", &self.name)
+            .field("api_key", &"***redacted***")
+            .field("roles", &self.roles)
+            .field("tenant_id", &self.tenant_id)
+            .finish()
+    }
+}
+
+impl OtelApiKeyConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_identifier("API key name", &self.name)?;
+        ensure!(
+            !self.api_key.trim().is_empty(),
+            "API key `{}` must not be empty",
+            self.name
+        );
+        ensure!(
+            !self.roles.is_empty(),
+            "API key `{}` must grant at least one role",
+            self.name
+        );
+        if let Some(tenant_id) = &self.tenant_id {
+            validate_tenant_id(tenant_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies the JSON Web Tokens signed with HMAC-SHA256 and extracts the roles and the tenant of
+/// the caller from their claims.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtelJwtConfig {
+    pub hmac_secret: String,
+    /// Expected `iss` claim. Not checked when unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// Expected `aud` claim. Not checked when unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+    /// Claim listing the roles of the caller.
+    #[serde(default = "OtelJwtConfig::default_roles_claim")]
+    pub roles_claim: String,
+    /// Claim carrying the tenant of the caller, if any.
+    #[serde(default = "OtelJwtConfig::default_tenant_claim")]
+    pub tenant_claim: String,
+}
+
+impl std::fmt::Debug for OtelJwtConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OtelJwtConfig")
+            .field("hmac_secret", &"***redacted***")
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("roles_claim", &self.roles_claim)
+            .field("tenant_claim", &self.tenant_claim)
+            .finish()
+    }
+}
+
+impl OtelJwtConfig {
+    fn default_roles_claim() -> String {
+        "roles".to_string()
+    }
+
+    fn default_tenant_claim() -> String {
+        "tenant".to_string()
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.hmac_secret.len() >= 32,
+            "JWT HMAC secret must be at least 32 bytes long"
+        );
+        ensure!(
+            !self.roles_claim.is_empty() && !self.tenant_claim.is_empty(),
+            "JWT roles and tenant claims must not be empty"
+        );
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct NodeConfig {
     pub cluster_id: String,
@@ -419,7 +1009,9 @@ pub struct NodeConfig {
     pub indexer_config: IndexerConfig,
     pub searcher_config: SearcherConfig,
     pub ingest_api_config: IngestApiConfig,
+    pub control_plane_config: ControlPlaneConfig,
     pub jaeger_config: JaegerConfig,
+    pub otel_auth_config: OtelAuthConfig,
 }
 
 impl NodeConfig {
@@ -468,6 +1060,12 @@ impl NodeConfig {
         self.metastore_configs.redact();
         self.metastore_uri.redact();
         self.storage_configs.redact();
+        self.searcher_config.redact();
+        self.otel_auth_config.redact();
+
+        if let Some(kafka_config) = &mut self.ingest_api_config.kafka {
+            kafka_config.redact();
+        }
     }
 
     #[cfg(any(test, feature = "testsuite"))]
@@ -525,6 +1123,27 @@ mod tests {
                 5
             );
         }
+        {
+            let indexer_config: IndexerConfig = serde_yaml::from_str(
+                r#"
+                    merge_scratch_space_quota_per_pipeline: 10GB
+                    merge_scratch_space_quota: 50GB
+                "#,
+            )
+            .unwrap();
+            assert_eq!(
+                indexer_config.merge_scratch_space_quota_per_pipeline,
+                Some(ByteSize::gb(10))
+            );
+            assert_eq!(
+                indexer_config.merge_scratch_space_quota,
+                Some(ByteSize::gb(50))
+            );
+            let indexer_config_json = serde_json::to_value(&indexer_config).unwrap();
+            let indexer_config_roundtrip: IndexerConfig =
+                serde_json::from_value(indexer_config_json).unwrap();
+            assert_eq!(indexer_config_roundtrip, indexer_config);
+        }
         {
             let indexer_config: IndexerConfig =
                 serde_yaml::from_str(r#"cpu_capacity: 1500m"#).unwrap();
@@ -543,6 +1162,96 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_validate_indexer_config_otlp_api_key_quotas() {
+        {
+            let indexer_config: IndexerConfig = serde_yaml::from_str(
+                r#"
+                    otlp_api_key_quotas:
+                      - api_key: secret-key-1
+                        tenant_id: tenant-1
+                        max_spans_per_sec: 1000
+                      - api_key: secret-key-2
+                        tenant_id: tenant-2
+                        max_log_records_per_sec: 500
+                "#,
+            )
+            .unwrap();
+            indexer_config.validate().unwrap();
+            assert_eq!(indexer_config.otlp_api_key_quotas.len(), 2);
+            assert_eq!(
+                indexer_config.otlp_api_key_quotas[0].max_spans_per_sec,
+                NonZeroU32::new(1000)
+            );
+            assert!(indexer_config.otlp_api_key_quotas[0]
+                .max_log_records_per_sec
+                .is_none());
+        }
+        {
+            let indexer_config: IndexerConfig = serde_yaml::from_str(
+                r#"
+                    otlp_api_key_quotas:
+                      - api_key: secret-key
+                        tenant_id: tenant-1
+                "#,
+            )
+            .unwrap();
+            assert_eq!(
+                indexer_config.validate().unwrap_err().to_string(),
+                "OTLP tenant `tenant-1` must set at least one of `max_spans_per_sec` and \
+                 `max_log_records_per_sec`"
+            );
+        }
+        {
+            let indexer_config: IndexerConfig = serde_yaml::from_str(
+                r#"
+                    otlp_api_key_quotas:
+                      - api_key: secret-key
+                        tenant_id: tenant-1
+                        max_spans_per_sec: 1000
+                      - api_key: secret-key
+                        tenant_id: tenant-2
+                        max_spans_per_sec: 1000
+                "#,
+            )
+            .unwrap();
+            assert_eq!(
+                indexer_config.validate().unwrap_err().to_string(),
+                "API key of OTLP tenant `tenant-2` is already assigned to another tenant"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_indexer_config_otlp_tenant_resource_attribute() {
+        {
+            let indexer_config: IndexerConfig = serde_yaml::from_str(
+                r#"
+                    otlp_tenant_resource_attribute: tenant.id
+                "#,
+            )
+            .unwrap();
+            indexer_config.validate().unwrap();
+            assert_eq!(
+                indexer_config.otlp_tenant_resource_attribute.as_deref(),
+                Some("tenant.id")
+            );
+        }
+        {
+            let indexer_config: IndexerConfig = serde_yaml::from_str(
+                r#"
+                    otlp_tenant_resource_attribute: " "
+                "#,
+            )
+            .unwrap();
+            assert_eq!(
+                indexer_config.validate().unwrap_err().to_string(),
+                "`otlp_tenant_resource_attribute` must not be empty"
+            );
+        }
+    }
+
     #[test]
     fn test_validate_ingest_api_config() {
         {
@@ -573,6 +1282,386 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ingest_api_config_shard_id_strategy_serialization() {
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(ingest_api_config.shard_id_strategy, ShardIdStrategy::Ulid);
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                shard_id_strategy: node_prefixed
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            ingest_api_config.shard_id_strategy,
+            ShardIdStrategy::NodePrefixed
+        );
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                shard_id_strategy: time_prefixed
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            ingest_api_config.shard_id_strategy,
+            ShardIdStrategy::TimePrefixed
+        );
+
+        serde_yaml::from_str::<IngestApiConfig>(
+            r#"
+                shard_id_strategy: uuid
+            "#,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_ingest_api_config_wal_compression_serialization() {
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(ingest_api_config.wal_compression, WalCompression::None);
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                wal_compression: lz4
+            "#,
+        )
+        .unwrap();
+        assert_eq!(ingest_api_config.wal_compression, WalCompression::Lz4);
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                wal_compression: zstd
+            "#,
+        )
+        .unwrap();
+        assert_eq!(ingest_api_config.wal_compression, WalCompression::Zstd);
+
+        serde_yaml::from_str::<IngestApiConfig>(
+            r#"
+                wal_compression: gzip
+            "#,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_ingest_api_config_kafka_serialization() {
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str("{}").unwrap();
+        assert!(ingest_api_config.kafka.is_none());
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                kafka:
+                  listen_port: 9092
+                  topic_mapping:
+                    app-logs: logs
+                  sasl_plain_users:
+                    - username: producer
+                      password: secret
+            "#,
+        )
+        .unwrap();
+        let kafka_config = ingest_api_config.kafka.as_ref().unwrap();
+        assert_eq!(kafka_config.listen_port, 9092);
+        assert_eq!(kafka_config.max_connections.get(), 1_024);
+        assert_eq!(kafka_config.index_id_for_topic("app-logs"), Some("logs"));
+        assert_eq!(kafka_config.index_id_for_topic("otel-logs"), None);
+        assert_eq!(
+            kafka_config
+                .authenticate("producer", "secret")
+                .unwrap()
+                .username,
+            "producer"
+        );
+        assert!(kafka_config.authenticate("producer", "").is_none());
+        assert!(kafka_config.authenticate("consumer", "secret").is_none());
+        assert!(!format!("{kafka_config:?}").contains("secret"));
+        ingest_api_config.validate().unwrap();
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                kafka:
+                  listen_port: 9092
+                  topic_mapping:
+                    app-logs: "invalid index"
+                  sasl_plain_users:
+                    - username: producer
+                      password: secret
+            "#,
+        )
+        .unwrap();
+        ingest_api_config.validate().unwrap_err();
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                kafka:
+                  listen_port: 9092
+                  sasl_plain_users: []
+            "#,
+        )
+        .unwrap();
+        let error = ingest_api_config.validate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the Kafka endpoint requires at least one SASL/PLAIN user"
+        );
+
+        serde_yaml::from_str::<IngestApiConfig>(
+            r#"
+                kafka:
+                  listen_port: 9092
+                  topic_mapping: {}
+            "#,
+        )
+        .unwrap_err();
+
+        serde_yaml::from_str::<IngestApiConfig>(
+            r#"
+                kafka:
+                  topic_mapping: {}
+                  sasl_plain_users: []
+            "#,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_ingest_api_config_wal_offload_serialization() {
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str("{}").unwrap();
+        assert!(ingest_api_config.wal_offload.is_none());
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                wal_offload:
+                  uri: s3://my-bucket/wal
+            "#,
+        )
+        .unwrap();
+        let wal_offload_config = ingest_api_config.wal_offload.as_ref().unwrap();
+        assert_eq!(wal_offload_config.uri, "s3://my-bucket/wal");
+        assert_eq!(wal_offload_config.disk_usage_threshold_percent, 75);
+        assert!(wal_offload_config.max_local_age().unwrap().is_none());
+        ingest_api_config.validate().unwrap();
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                wal_offload:
+                  uri: s3://my-bucket/wal
+                  max_local_age: 10m
+            "#,
+        )
+        .unwrap();
+        let wal_offload_config = ingest_api_config.wal_offload.as_ref().unwrap();
+        assert_eq!(
+            wal_offload_config.max_local_age().unwrap(),
+            Some(Duration::from_secs(600))
+        );
+        ingest_api_config.validate().unwrap();
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                wal_offload:
+                  uri: s3://my-bucket/wal
+                  max_local_age: soon
+            "#,
+        )
+        .unwrap();
+        ingest_api_config.validate().unwrap_err();
+
+        let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+            r#"
+                wal_offload:
+                  uri: s3://my-bucket/wal
+                  disk_usage_threshold_percent: 101
+            "#,
+        )
+        .unwrap();
+        ingest_api_config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_control_plane_config_serialization() {
+        let control_plane_config: ControlPlaneConfig = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(control_plane_config, ControlPlaneConfig::default());
+        assert!(control_plane_config.model_snapshot_uri.is_none());
+        assert!(!control_plane_config.auto_create_indexes);
+        assert!(!control_plane_config.enable_force_close_shards);
+
+        let control_plane_config: ControlPlaneConfig = serde_yaml::from_str(
+            r#"
+                model_snapshot_uri: s3://my-bucket/control-plane
+                model_snapshot_max_age_secs: 60
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            control_plane_config.model_snapshot_uri.unwrap(),
+            "s3://my-bucket/control-plane"
+        );
+        assert_eq!(
+            control_plane_config.model_snapshot_max_age(),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            control_plane_config.close_shards_request_timeout,
+            Duration::from_secs(3)
+        );
+
+        let control_plane_config: ControlPlaneConfig = serde_yaml::from_str(
+            r#"
+                close_shards_request_timeout: 15s
+                init_shards_request_timeout: 20s
+                close_shards_upon_rebalance_delay: 1m
+                auto_create_indexes: true
+                enable_force_close_shards: true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            control_plane_config.close_shards_request_timeout,
+            Duration::from_secs(15)
+        );
+        assert_eq!(
+            control_plane_config.init_shards_request_timeout,
+            Duration::from_secs(20)
+        );
+        assert_eq!(
+            control_plane_config.close_shards_upon_rebalance_delay,
+            Duration::from_secs(60)
+        );
+        assert!(control_plane_config.auto_create_indexes);
+        assert!(control_plane_config.enable_force_close_shards);
+
+        let control_plane_config: ControlPlaneConfig = serde_yaml::from_str(
+            r#"
+                init_shards_request_timeout: 0s
+            "#,
+        )
+        .unwrap();
+        control_plane_config.validate().unwrap_err();
+
+        serde_yaml::from_str::<ControlPlaneConfig>(
+            r#"
+                model_snapshot_max_age_secs: 0
+            "#,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_otel_auth_config_serialization() {
+        let otel_auth_config: OtelAuthConfig = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(otel_auth_config, OtelAuthConfig::default());
+        assert!(!otel_auth_config.is_enabled());
+
+        let otel_auth_config: OtelAuthConfig = serde_yaml::from_str(
+            r#"
+                api_keys:
+                  - name: collector-acme
+                    api_key: acme-secret
+                    roles: [ingest]
+                    tenant_id: acme
+                  - name: grafana
+                    api_key: grafana-secret
+                    roles: [read]
+                jwt:
+                  hmac_secret: 0123456789abcdef0123456789abcdef
+                  issuer: https://auth.example.com
+            "#,
+        )
+        .unwrap();
+        otel_auth_config.validate().unwrap();
+        assert!(otel_auth_config.is_enabled());
+        assert!(!format!("{otel_auth_config:?}").contains("secret"));
+
+        let mut redacted_otel_auth_config = otel_auth_config.clone();
+        redacted_otel_auth_config.redact();
+        assert_eq!(
+            redacted_otel_auth_config.api_keys[0].api_key,
+            "***redacted***"
+        );
+        assert_eq!(
+            redacted_otel_auth_config.jwt.unwrap().hmac_secret,
+            "***redacted***"
+        );
+        assert_eq!(otel_auth_config.api_keys.len(), 2);
+        assert_eq!(otel_auth_config.api_keys[0].roles, [OtelRole::Ingest]);
+        assert_eq!(
+            otel_auth_config.api_keys[0].tenant_id.as_deref(),
+            Some("acme")
+        );
+        assert!(otel_auth_config.api_keys[1].tenant_id.is_none());
+
+        let jwt_config = otel_auth_config.jwt.unwrap();
+        assert_eq!(jwt_config.issuer.unwrap(), "https://auth.example.com");
+        assert!(jwt_config.audience.is_none());
+        assert_eq!(jwt_config.roles_claim, "roles");
+        assert_eq!(jwt_config.tenant_claim, "tenant");
+
+        serde_yaml::from_str::<OtelAuthConfig>(
+            r#"
+                api_keys:
+                  - name: collector
+                    api_key: secret
+                    roles: [write]
+            "#,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_otel_auth_config_validate() {
+        let api_key_config = OtelApiKeyConfig {
+            name: "collector".to_string(),
+            api_key: "secret".to_string(),
+            roles: vec![OtelRole::Ingest],
+            tenant_id: None,
+        };
+        let otel_auth_config = OtelAuthConfig {
+            api_keys: vec![api_key_config.clone()],
+            jwt: None,
+        };
+        otel_auth_config.validate().unwrap();
+
+        let otel_auth_config = OtelAuthConfig {
+            api_keys: vec![api_key_config.clone(), api_key_config.clone()],
+            jwt: None,
+        };
+        otel_auth_config.validate().unwrap_err();
+
+        let otel_auth_config = OtelAuthConfig {
+            api_keys: vec![OtelApiKeyConfig {
+                roles: Vec::new(),
+                ..api_key_config.clone()
+            }],
+            jwt: None,
+        };
+        otel_auth_config.validate().unwrap_err();
+
+        let otel_auth_config = OtelAuthConfig {
+            api_keys: vec![OtelApiKeyConfig {
+                tenant_id: Some("acme-corp".to_string()),
+                ..api_key_config
+            }],
+            jwt: None,
+        };
+        otel_auth_config.validate().unwrap_err();
+
+        let otel_auth_config = OtelAuthConfig {
+            api_keys: Vec::new(),
+            jwt: Some(OtelJwtConfig {
+                hmac_secret: "too-short".to_string(),
+                issuer: None,
+                audience: None,
+                roles_claim: OtelJwtConfig::default_roles_claim(),
+                tenant_claim: OtelJwtConfig::default_tenant_claim(),
+            }),
+        };
+        otel_auth_config.validate().unwrap_err();
+    }
+
     #[test]
     fn test_grpc_config_serialization() {
         let grpc_config: GrpcConfig = serde_json::from_str(r#"{}"#).unwrap();
@@ -37,8 +37,8 @@ use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
 use crate::templating::render_config;
 use crate::{
-    validate_identifier, validate_node_id, ConfigFormat, IndexerConfig, IngestApiConfig,
-    JaegerConfig, MetastoreConfigs, NodeConfig, SearcherConfig,
+    validate_identifier, validate_node_id, ConfigFormat, ControlPlaneConfig, IndexerConfig,
+    IngestApiConfig, JaegerConfig, MetastoreConfigs, NodeConfig, OtelAuthConfig, SearcherConfig,
 };
 
 pub const DEFAULT_CLUSTER_ID: &str = "quickwit-default-cluster";
@@ -212,9 +212,15 @@ struct NodeConfigBuilder {
     #[serde(rename = "ingest_api")]
     #[serde(default)]
     ingest_api_config: IngestApiConfig,
+    #[serde(rename = "control_plane")]
+    #[serde(default)]
+    control_plane_config: ControlPlaneConfig,
     #[serde(rename = "jaeger")]
     #[serde(default)]
     jaeger_config: JaegerConfig,
+    #[serde(rename = "otel_auth")]
+    #[serde(default)]
+    otel_auth_config: OtelAuthConfig,
 }
 
 impl NodeConfigBuilder {
@@ -297,7 +303,10 @@ impl NodeConfigBuilder {
         self.storage_configs.validate()?;
         self.storage_configs.apply_flavors();
         self.ingest_api_config.validate()?;
+        self.control_plane_config.validate()?;
+        self.indexer_config.validate()?;
         self.searcher_config.validate()?;
+        self.otel_auth_config.validate()?;
 
         let gossip_interval = self
             .gossip_interval_ms
@@ -325,7 +334,9 @@ impl NodeConfigBuilder {
             indexer_config: self.indexer_config,
             searcher_config: self.searcher_config,
             ingest_api_config: self.ingest_api_config,
+            control_plane_config: self.control_plane_config,
             jaeger_config: self.jaeger_config,
+            otel_auth_config: self.otel_auth_config,
         };
 
         validate(&node_config)?;
@@ -370,7 +381,9 @@ impl Default for NodeConfigBuilder {
             indexer_config: IndexerConfig::default(),
             searcher_config: SearcherConfig::default(),
             ingest_api_config: IngestApiConfig::default(),
+            control_plane_config: ControlPlaneConfig::default(),
             jaeger_config: JaegerConfig::default(),
+            otel_auth_config: OtelAuthConfig::default(),
         }
     }
 }
@@ -463,7 +476,9 @@ pub fn node_config_for_test() -> NodeConfig {
         indexer_config: IndexerConfig::default(),
         searcher_config: SearcherConfig::default(),
         ingest_api_config: IngestApiConfig::default(),
+        control_plane_config: ControlPlaneConfig::default(),
         jaeger_config: JaegerConfig::default(),
+        otel_auth_config: OtelAuthConfig::default(),
     }
 }
 
@@ -591,6 +606,11 @@ mod tests {
                 cpu_capacity: IndexerConfig::default_cpu_capacity(),
                 enable_cooperative_indexing: false,
                 max_merge_write_throughput: Some(ByteSize::mb(100)),
+                merge_scratch_space_quota_per_pipeline: None,
+                merge_scratch_space_quota: None,
+                max_upload_bandwidth: None,
+                otlp_api_key_quotas: Vec::new(),
+                otlp_tenant_resource_attribute: None,
             }
         );
         assert_eq!(
@@ -610,7 +630,14 @@ mod tests {
                 partial_request_cache_capacity: ByteSize::mb(64),
                 max_num_concurrent_split_searches: 150,
                 max_num_concurrent_split_streams: 120,
+                max_num_concurrent_msearch_searches: 20,
                 split_cache: None,
+                legal_hold_searchers: Vec::new(),
+                max_storage_read_bandwidth: None,
+                max_regex_length: 1_000,
+                max_regex_automaton_states: 10_000,
+                enable_fast_field_pushdown: true,
+                fast_field_scan_min_doc_freq: 10_000,
             }
         );
         assert_eq!(
@@ -834,7 +861,9 @@ mod tests {
         assert_eq!(config.indexer_config, IndexerConfig::default());
         assert_eq!(config.searcher_config, SearcherConfig::default());
         assert_eq!(config.ingest_api_config, IngestApiConfig::default());
+        assert_eq!(config.control_plane_config, ControlPlaneConfig::default());
         assert_eq!(config.jaeger_config, JaegerConfig::default());
+        assert_eq!(config.otel_auth_config, OtelAuthConfig::default());
     }
 
     #[tokio::test]
@@ -1256,4 +1285,75 @@ mod tests {
         .to_string();
         assert!(error_message.contains("replication factor"));
     }
+
+    #[tokio::test]
+    async fn test_node_config_validates_searcher_config() {
+        let node_config_yaml = r#"
+            version: 0.8
+            searcher:
+              max_num_concurrent_msearch_searches: 0
+        "#;
+        let error_message = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error_message.contains("max_num_concurrent_msearch_searches"));
+
+        let node_config_yaml = r#"
+            version: 0.8
+            searcher:
+              legal_hold_searchers:
+                - name: legal-team
+                  token: secret
+                - name: audit-team
+                  token: secret
+        "#;
+        let error_message = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error_message.contains("shares its token"));
+    }
+
+    #[tokio::test]
+    async fn test_node_config_legal_hold_searchers() {
+        let node_config_yaml = r#"
+            version: 0.8
+            searcher:
+              legal_hold_searchers:
+                - name: legal-team
+                  token: secret
+        "#;
+        let mut node_config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let searcher_config = &node_config.searcher_config;
+        assert_eq!(
+            searcher_config
+                .find_legal_hold_searcher("secret")
+                .unwrap()
+                .name,
+            "legal-team"
+        );
+        assert!(searcher_config.find_legal_hold_searcher("").is_none());
+        assert!(!format!("{searcher_config:?}").contains("secret"));
+
+        node_config.redact();
+        assert!(node_config
+            .searcher_config
+            .find_legal_hold_searcher("secret")
+            .is_none());
+    }
 }
@@ -19,6 +19,7 @@
 
 pub(crate) mod serialize;
 
+use std::collections::HashSet;
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -34,7 +35,7 @@ pub use serialize::load_source_config_from_user_config;
 // For backward compatibility.
 use serialize::VersionedSourceConfig;
 
-use crate::{enable_ingest_v2, TestableForRegression};
+use crate::{enable_ingest_v2, TestableForRegression, WasmPluginConfig};
 
 /// Reserved source ID for the `quickwit index ingest` CLI command.
 pub const CLI_SOURCE_ID: &str = "_ingest-cli-source";
@@ -65,6 +66,13 @@ pub struct SourceConfig {
 
     pub transform_config: Option<TransformConfig>,
 
+    /// JSON Schema against which the documents are validated, after the transform if any.
+    pub json_schema_config: Option<JsonSchemaConfig>,
+
+    /// WebAssembly plugin transforming the documents, after the transform if any and before the
+    /// JSON Schema validation.
+    pub wasm_plugin_config: Option<WasmPluginConfig>,
+
     // Denotes the input data format.
     #[serde(default)]
     pub input_format: SourceInputFormat,
@@ -79,8 +87,12 @@ impl SourceConfig {
             SourceParams::IngestCli => SourceType::Cli,
             SourceParams::Kafka(_) => SourceType::Kafka,
             SourceParams::Kinesis(_) => SourceType::Kinesis,
+            SourceParams::Parquet(_) => SourceType::Parquet,
             SourceParams::PubSub(_) => SourceType::PubSub,
             SourceParams::Pulsar(_) => SourceType::Pulsar,
+            SourceParams::Reindex(_) => SourceType::Reindex,
+            SourceParams::Sqs(_) => SourceType::Sqs,
+            SourceParams::Syslog(_) => SourceType::Syslog,
             SourceParams::Vec(_) => SourceType::Vec,
             SourceParams::Void(_) => SourceType::Void,
         }
@@ -96,7 +108,11 @@ impl SourceConfig {
             SourceParams::IngestCli => serde_json::to_value(()),
             SourceParams::Kafka(params) => serde_json::to_value(params),
             SourceParams::Kinesis(params) => serde_json::to_value(params),
+            SourceParams::Parquet(params) => serde_json::to_value(params),
             SourceParams::Pulsar(params) => serde_json::to_value(params),
+            SourceParams::Reindex(params) => serde_json::to_value(params),
+            SourceParams::Sqs(params) => serde_json::to_value(params),
+            SourceParams::Syslog(params) => serde_json::to_value(params),
             SourceParams::Vec(params) => serde_json::to_value(params),
             SourceParams::Void(params) => serde_json::to_value(params),
         }
@@ -167,7 +183,10 @@ impl TestableForRegression for SourceConfig {
             transform_config: Some(TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: default_timezone(),
+                stages: Vec::new(),
             }),
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         }
     }
@@ -220,9 +239,13 @@ pub enum SourceParams {
     IngestCli,
     Kafka(KafkaSourceParams),
     Kinesis(KinesisSourceParams),
+    Parquet(ParquetSourceParams),
     #[serde(rename = "pubsub")]
     PubSub(PubSubSourceParams),
     Pulsar(PulsarSourceParams),
+    Reindex(ReindexSourceParams),
+    Sqs(SqsSourceParams),
+    Syslog(SyslogSourceParams),
     Vec(VecSourceParams),
     Void(VoidSourceParams),
 }
@@ -368,6 +391,140 @@ impl TryFrom<KinesisSourceParamsInner> for KinesisSourceParams {
     }
 }
 
+/// Format of the messages of an SQS source.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SqsMessageType {
+    /// The body of each message is a document.
+    #[default]
+    Raw,
+    /// Each message is an S3 event notification, possibly wrapped in an SNS notification. The
+    /// created objects are read line by line, one document per line.
+    S3Notification,
+}
+
+fn default_sqs_visibility_timeout_secs() -> u32 {
+    300
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(try_from = "SqsSourceParamsInner")]
+pub struct SqsSourceParams {
+    pub queue_url: String,
+    #[serde(flatten)]
+    pub region_or_endpoint: Option<RegionOrEndpoint>,
+    pub message_type: SqsMessageType,
+    /// Duration during which received messages are hidden from other consumers. The source
+    /// keeps extending it until the documents of the messages are published, then deletes the
+    /// messages.
+    pub visibility_timeout_secs: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SqsSourceParamsInner {
+    pub queue_url: String,
+    pub region: Option<String>,
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub message_type: SqsMessageType,
+    #[serde(default = "default_sqs_visibility_timeout_secs")]
+    pub visibility_timeout_secs: u32,
+}
+
+impl TryFrom<SqsSourceParamsInner> for SqsSourceParams {
+    type Error = &'static str;
+
+    fn try_from(value: SqsSourceParamsInner) -> Result<Self, Self::Error> {
+        if value.region.is_some() && value.endpoint.is_some() {
+            return Err("SQS source parameters `region` and `endpoint` are mutually exclusive");
+        }
+        let region = value.region.map(RegionOrEndpoint::Region);
+        let endpoint = value.endpoint.map(RegionOrEndpoint::Endpoint);
+        let region_or_endpoint = region.or(endpoint);
+
+        Ok(SqsSourceParams {
+            queue_url: value.queue_url,
+            region_or_endpoint,
+            message_type: value.message_type,
+            visibility_timeout_secs: value.visibility_timeout_secs,
+        })
+    }
+}
+
+/// Parameters of a Parquet source, which indexes the rows of Parquet files of an external table.
+/// More files are registered by adding other Parquet sources to the index.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetSourceParams {
+    /// URIs of the Parquet files of the external table.
+    #[schema(value_type = Vec<String>)]
+    pub filepaths: Vec<Uri>,
+    /// Columns read from the files, each becoming the field of the same name of the documents.
+    /// All the columns are read when empty.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    /// When lazy mode is enabled, the source is not scheduled on the indexers. Instead, the files
+    /// are turned into splits by the searchers the first time a search request targets them.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub lazy: bool,
+}
+
+/// Parameters of a reindex source, which reads the documents of the splits of another index and
+/// indexes them into the index the source belongs to.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReindexSourceParams {
+    /// ID of the index whose documents are reindexed.
+    pub source_index_id: String,
+    /// Maximum number of documents reindexed per second by each pipeline of the source. The
+    /// reindexing rate is not limited when unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_num_docs_per_sec: Option<u32>,
+    /// Write alias flipped by the control plane from the source index to the index of the source
+    /// once the source index has been backfilled. Until then, the documents ingested into the
+    /// alias are written to the source index.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+/// Transport protocols of a syslog source.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    /// Octet-counted or newline-delimited frames over TCP.
+    #[default]
+    Tcp,
+    /// One message per datagram.
+    Udp,
+    /// Same framing as TCP over TLS.
+    Tls,
+}
+
+/// Parameters of a syslog source, which receives RFC 3164 and RFC 5424 messages from the network.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogSourceParams {
+    /// Port the source listens on, on all the network interfaces of the indexer.
+    pub listen_port: u16,
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+    /// Path to the PEM file holding the certificate chain presented to the clients. Required
+    /// with the `tls` protocol.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_cert_path: Option<String>,
+    /// Path to the PEM file holding the private key of the certificate. Required with the `tls`
+    /// protocol.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_key_path: Option<String>,
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct VecSourceParams {
@@ -438,21 +595,116 @@ fn default_consumer_name() -> String {
 #[serde(deny_unknown_fields)]
 pub struct TransformConfig {
     /// [VRL] source code of the transform compiled to a VRL [`Program`](vrl::compiler::Program).
+    /// Optional when the transform is made of stages.
     ///
     /// [VRL]: https://vector.dev/docs/reference/vrl/
-    #[serde(rename = "script")]
+    #[serde(rename = "script", default, skip_serializing_if = "String::is_empty")]
     vrl_script: String,
 
     /// Timezone used in the VRL [`Program`](vrl::compiler::Program) for date and time
     /// manipulations. Defaults to `UTC` if not timezone is specified.
     #[serde(default = "default_timezone")]
     timezone: String,
+
+    /// Named stages applied in order after the script.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stages: Vec<TransformStageConfig>,
 }
 
 fn default_timezone() -> String {
     "UTC".to_string()
 }
 
+/// What a transform stage does to the documents matching its condition.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformStageAction {
+    /// Runs the stage's script on the document.
+    #[default]
+    Mutate,
+    /// Drops the document.
+    Drop,
+    /// Sends the document to another index instead of the source's index.
+    Route,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TransformStageConfig {
+    pub name: String,
+    /// VRL boolean expression. When set, the stage only applies to the documents for which the
+    /// expression evaluates to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(default)]
+    pub action: TransformStageAction,
+    /// VRL script run by `mutate` stages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    /// Target index of `route` stages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_id: Option<String>,
+}
+
+impl TransformStageConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            anyhow::bail!("transform stage name must not be empty");
+        }
+        match self.action {
+            TransformStageAction::Mutate => {
+                if self.script.is_none() {
+                    anyhow::bail!("`mutate` transform stage `{}` requires a script", self.name);
+                }
+            }
+            TransformStageAction::Drop => {}
+            TransformStageAction::Route => {
+                let Some(index_id) = &self.index_id else {
+                    anyhow::bail!(
+                        "`route` transform stage `{}` requires an index ID",
+                        self.name
+                    );
+                };
+                crate::validate_identifier("index", index_id)?;
+            }
+        }
+        if self.action != TransformStageAction::Mutate && self.script.is_some() {
+            anyhow::bail!(
+                "transform stage `{}` has a script but only `mutate` stages run scripts",
+                self.name
+            );
+        }
+        if self.action != TransformStageAction::Route && self.index_id.is_some() {
+            anyhow::bail!(
+                "transform stage `{}` has an index ID but only `route` stages send documents to \
+                 another index",
+                self.name
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "vrl")]
+    /// Compiles the condition of the stage to a VRL [`Program`](vrl::compiler::Program) returning
+    /// a boolean.
+    pub fn compile_condition(&self) -> anyhow::Result<Option<vrl::compiler::Program>> {
+        self.condition
+            .as_deref()
+            .map(compile_vrl_program)
+            .transpose()
+    }
+
+    #[cfg(feature = "vrl")]
+    /// Compiles the script of a `mutate` stage to a VRL [`Program`](vrl::compiler::Program)
+    /// returning the entire document.
+    pub fn compile_script(&self) -> anyhow::Result<Option<vrl::compiler::Program>> {
+        self.script
+            .as_ref()
+            .map(|script| compile_vrl_program(&(script.clone() + "\n.")))
+            .transpose()
+    }
+}
+
 impl TransformConfig {
     /// Creates a new [`TransformConfig`] instance from the provided VRL script and optional
     /// timezone.
@@ -460,12 +712,57 @@ impl TransformConfig {
         Self {
             vrl_script,
             timezone: timezone_opt.unwrap_or_else(default_timezone),
+            stages: Vec::new(),
         }
     }
 
+    /// Returns whether the transform has a script, in addition to its stages.
+    pub fn has_script(&self) -> bool {
+        !self.vrl_script.is_empty()
+    }
+
+    /// Returns the stages of the transform.
+    pub fn stages(&self) -> &[TransformStageConfig] {
+        &self.stages
+    }
+
+    /// Returns whether some stages send documents to another index.
+    pub fn has_route_stages(&self) -> bool {
+        self.stages
+            .iter()
+            .any(|stage| stage.action == TransformStageAction::Route)
+    }
+
+    fn validate_stages(&self) -> anyhow::Result<()> {
+        if self.vrl_script.is_empty() && self.stages.is_empty() {
+            anyhow::bail!("transform must have a script or at least one stage");
+        }
+        let mut stage_names = HashSet::with_capacity(self.stages.len());
+
+        for stage in &self.stages {
+            stage.validate()?;
+
+            if !stage_names.insert(stage.name.as_str()) {
+                anyhow::bail!("transform stage `{}` is defined more than once", stage.name);
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "vrl")]
     pub(crate) fn validate_vrl_script(&self) -> anyhow::Result<()> {
+        use anyhow::Context;
+        self.validate_stages()?;
         self.compile_vrl_script()?;
+
+        for stage in &self.stages {
+            stage.compile_condition().with_context(|| {
+                format!("invalid condition in transform stage `{}`", stage.name)
+            })?;
+            stage
+                .compile_script()
+                .with_context(|| format!("invalid script in transform stage `{}`", stage.name))?;
+        }
         Ok(())
     }
 
@@ -475,7 +772,7 @@ impl TransformConfig {
         // to avoid breaking unit tests.
         //
         // We do return an explicit error on instanciation of the program however.
-        Ok(())
+        self.validate_stages()
     }
 
     #[cfg(feature = "vrl")]
@@ -495,26 +792,7 @@ impl TransformConfig {
         // Append "\n." to the script to return the entire document and not only the modified
         // fields.
         let vrl_script = self.vrl_script.clone() + "\n.";
-        let functions = vrl::stdlib::all();
-
-        let compilation_res = match vrl::compiler::compile(&vrl_script, &functions) {
-            Ok(compilation_res) => compilation_res,
-            Err(diagnostics) => {
-                let mut formatter = vrl::diagnostic::Formatter::new(&vrl_script, diagnostics);
-                formatter.enable_colors(!quickwit_common::no_color());
-                anyhow::bail!("failed to compile VRL script:\n {formatter}")
-            }
-        };
-
-        let vrl::compiler::CompilationResult {
-            program, warnings, ..
-        } = compilation_res;
-
-        if !warnings.is_empty() {
-            let mut formatter = vrl::diagnostic::Formatter::new(&vrl_script, warnings);
-            formatter.enable_colors(!quickwit_common::no_color());
-            tracing::warn!("VRL program compiled with some warnings: {formatter}");
-        }
+        let program = compile_vrl_program(&vrl_script)?;
         Ok((program, timezone))
     }
 
@@ -523,10 +801,147 @@ impl TransformConfig {
         Self {
             vrl_script: vrl_script.to_string(),
             timezone: default_timezone(),
+            stages: Vec::new(),
+        }
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test_with_stages(vrl_script: &str, stages: Vec<TransformStageConfig>) -> Self {
+        Self {
+            vrl_script: vrl_script.to_string(),
+            timezone: default_timezone(),
+            stages,
+        }
+    }
+}
+
+/// JSON Schema against which the documents of a source are validated before being indexed. The
+/// schema is versioned so that producers can migrate from one version to the next.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonSchemaConfig {
+    /// Versions of the schema.
+    pub versions: Vec<JsonSchemaVersionConfig>,
+    /// Field of the documents holding the version of the schema they were produced with.
+    /// Documents without this field are validated against the latest version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_field: Option<String>,
+    /// Oldest version of the schema still accepted. Documents produced with an older version are
+    /// rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonSchemaVersionConfig {
+    pub version: u32,
+    /// Inline JSON Schema.
+    #[schema(value_type = Option<Object>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<JsonValue>,
+    /// HTTP URL from which the JSON Schema is fetched when the indexing pipeline starts, for
+    /// instance the endpoint of a schema registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl JsonSchemaConfig {
+    /// Returns the latest version of the schema.
+    pub fn latest_version(&self) -> Option<u32> {
+        self.versions.iter().map(|version| version.version).max()
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        let Some(latest_version) = self.latest_version() else {
+            anyhow::bail!("JSON Schema must have at least one version");
+        };
+        let mut versions = HashSet::with_capacity(self.versions.len());
+
+        for version in &self.versions {
+            if !versions.insert(version.version) {
+                anyhow::bail!(
+                    "JSON Schema version `{}` is defined more than once",
+                    version.version
+                );
+            }
+            match (&version.schema, &version.url) {
+                (Some(schema), None) => {
+                    if !schema.is_object() && !schema.is_boolean() {
+                        anyhow::bail!(
+                            "JSON Schema version `{}` must be an object or a boolean",
+                            version.version
+                        );
+                    }
+                }
+                (None, Some(url)) => {
+                    if !url.starts_with("http://") && !url.starts_with("https://") {
+                        anyhow::bail!(
+                            "URL of JSON Schema version `{}` must use the HTTP or HTTPS scheme, \
+                             got `{url}`",
+                            version.version
+                        );
+                    }
+                }
+                _ => anyhow::bail!(
+                    "JSON Schema version `{}` must have either a `schema` or a `url`",
+                    version.version
+                ),
+            }
+        }
+        if self.version_field.as_deref() == Some("") {
+            anyhow::bail!("JSON Schema `version_field` must not be empty");
+        }
+        if let Some(min_version) = self.min_version {
+            if min_version > latest_version {
+                anyhow::bail!(
+                    "JSON Schema `min_version` ({min_version}) is greater than the latest version \
+                     ({latest_version})"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(schema: JsonValue) -> Self {
+        Self {
+            versions: vec![JsonSchemaVersionConfig {
+                version: 1,
+                schema: Some(schema),
+                url: None,
+            }],
+            version_field: None,
+            min_version: None,
         }
     }
 }
 
+#[cfg(feature = "vrl")]
+fn compile_vrl_program(vrl_script: &str) -> anyhow::Result<vrl::compiler::Program> {
+    let functions = vrl::stdlib::all();
+
+    let compilation_res = match vrl::compiler::compile(vrl_script, &functions) {
+        Ok(compilation_res) => compilation_res,
+        Err(diagnostics) => {
+            let mut formatter = vrl::diagnostic::Formatter::new(vrl_script, diagnostics);
+            formatter.enable_colors(!quickwit_common::no_color());
+            anyhow::bail!("failed to compile VRL script:\n {formatter}")
+        }
+    };
+
+    let vrl::compiler::CompilationResult {
+        program, warnings, ..
+    } = compilation_res;
+
+    if !warnings.is_empty() {
+        let mut formatter = vrl::diagnostic::Formatter::new(vrl_script, warnings);
+        formatter.enable_colors(!quickwit_common::no_color());
+        tracing::warn!("VRL program compiled with some warnings: {formatter}");
+    }
+    Ok(program)
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -567,7 +982,10 @@ mod tests {
             transform_config: Some(TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: "local".to_string(),
+                stages: Vec::new(),
             }),
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         assert_eq!(source_config, expected_source_config);
@@ -662,7 +1080,10 @@ mod tests {
             transform_config: Some(TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: "local".to_string(),
+                stages: Vec::new(),
             }),
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         assert_eq!(source_config, expected_source_config);
@@ -861,6 +1282,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sqs_source_params_deserialization() {
+        {
+            let yaml = r#"
+                    queue_url: https://sqs.us-east-1.amazonaws.com/123456789012/my-queue
+                "#;
+            assert_eq!(
+                serde_yaml::from_str::<SqsSourceParams>(yaml).unwrap(),
+                SqsSourceParams {
+                    queue_url: "https://sqs.us-east-1.amazonaws.com/123456789012/my-queue"
+                        .to_string(),
+                    region_or_endpoint: None,
+                    message_type: SqsMessageType::Raw,
+                    visibility_timeout_secs: 300,
+                }
+            );
+        }
+        {
+            let yaml = r#"
+                    queue_url: http://localhost:4566/000000000000/my-queue
+                    endpoint: http://localhost:4566
+                    message_type: s3_notification
+                    visibility_timeout_secs: 60
+                "#;
+            let params = serde_yaml::from_str::<SqsSourceParams>(yaml).unwrap();
+            assert_eq!(
+                params,
+                SqsSourceParams {
+                    queue_url: "http://localhost:4566/000000000000/my-queue".to_string(),
+                    region_or_endpoint: Some(RegionOrEndpoint::Endpoint(
+                        "http://localhost:4566".to_string()
+                    )),
+                    message_type: SqsMessageType::S3Notification,
+                    visibility_timeout_secs: 60,
+                }
+            );
+            let params_yaml = serde_yaml::to_string(&params).unwrap();
+            assert_eq!(
+                serde_yaml::from_str::<SqsSourceParams>(&params_yaml).unwrap(),
+                params
+            );
+        }
+        {
+            let yaml = r#"
+                    queue_url: https://sqs.us-east-1.amazonaws.com/123456789012/my-queue
+                    region: us-east-1
+                    endpoint: http://localhost:4566
+                "#;
+            let error = serde_yaml::from_str::<SqsSourceParams>(yaml).unwrap_err();
+            assert!(error.to_string().starts_with("SQS source parameters "));
+        }
+    }
+
     #[test]
     fn test_kinesis_source_params_deserialization() {
         {
@@ -902,6 +1376,232 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reindex_source_params_deserialization() {
+        {
+            let yaml = r#"
+                    source_index_id: my-index
+                "#;
+            assert_eq!(
+                serde_yaml::from_str::<ReindexSourceParams>(yaml).unwrap(),
+                ReindexSourceParams {
+                    source_index_id: "my-index".to_string(),
+                    max_num_docs_per_sec: None,
+                    alias: None,
+                }
+            );
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-reindex-source
+                desired_num_pipelines: 2
+                source_type: reindex
+                params:
+                    source_index_id: my-index
+                    max_num_docs_per_sec: 10000
+                    alias: my-alias
+                transform:
+                    script: .message = downcase(string!(.message))
+                "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                    .unwrap();
+            assert_eq!(source_config.source_type(), SourceType::Reindex);
+            assert_eq!(source_config.num_pipelines.get(), 2);
+            assert_eq!(
+                source_config.source_params,
+                SourceParams::Reindex(ReindexSourceParams {
+                    source_index_id: "my-index".to_string(),
+                    max_num_docs_per_sec: Some(10_000),
+                    alias: Some("my-alias".to_string()),
+                })
+            );
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-reindex-source
+                source_type: reindex
+                params:
+                    source_index_id: my-index
+                    max_num_docs_per_sec: 0
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-reindex-source
+                source_type: reindex
+                params:
+                    source_index_id: my-index
+                    alias: my-index
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_parquet_source_params_deserialization() {
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-parquet-source
+                source_type: parquet
+                params:
+                    filepaths:
+                        - s3://my-bucket/events/part-0.parquet
+                        - s3://my-bucket/events/part-1.parquet
+                    columns: [timestamp, body]
+                "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                    .unwrap();
+            assert_eq!(source_config.source_type(), SourceType::Parquet);
+            assert_eq!(
+                source_config.source_params,
+                SourceParams::Parquet(ParquetSourceParams {
+                    filepaths: vec![
+                        Uri::for_test("s3://my-bucket/events/part-0.parquet"),
+                        Uri::for_test("s3://my-bucket/events/part-1.parquet"),
+                    ],
+                    columns: vec!["timestamp".to_string(), "body".to_string()],
+                    lazy: false,
+                })
+            );
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-parquet-source
+                source_type: parquet
+                params:
+                    filepaths:
+                        - s3://my-bucket/events/part-0.parquet
+                    lazy: true
+                "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                    .unwrap();
+            assert_eq!(
+                source_config.source_params,
+                SourceParams::Parquet(ParquetSourceParams {
+                    filepaths: vec![Uri::for_test("s3://my-bucket/events/part-0.parquet")],
+                    columns: Vec::new(),
+                    lazy: true,
+                })
+            );
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-parquet-source
+                source_type: parquet
+                params:
+                    filepaths: []
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-parquet-source
+                source_type: parquet
+                params:
+                    filepaths:
+                        - s3://my-bucket/events/part-0.parquet
+                        - s3://my-bucket/events/part-0.parquet
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-parquet-source
+                source_type: parquet
+                input_format: plain_text
+                params:
+                    filepaths:
+                        - s3://my-bucket/events/part-0.parquet
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_syslog_source_params_deserialization() {
+        {
+            let yaml = r#"
+                    listen_port: 6514
+                "#;
+            assert_eq!(
+                serde_yaml::from_str::<SyslogSourceParams>(yaml).unwrap(),
+                SyslogSourceParams {
+                    listen_port: 6514,
+                    protocol: SyslogProtocol::Tcp,
+                    tls_cert_path: None,
+                    tls_key_path: None,
+                }
+            );
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-syslog-source
+                source_type: syslog
+                params:
+                    listen_port: 6514
+                    protocol: tls
+                    tls_cert_path: /etc/quickwit/syslog.crt
+                    tls_key_path: /etc/quickwit/syslog.key
+                "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                    .unwrap();
+            assert_eq!(source_config.source_type(), SourceType::Syslog);
+            assert_eq!(
+                source_config.source_params,
+                SourceParams::Syslog(SyslogSourceParams {
+                    listen_port: 6514,
+                    protocol: SyslogProtocol::Tls,
+                    tls_cert_path: Some("/etc/quickwit/syslog.crt".to_string()),
+                    tls_key_path: Some("/etc/quickwit/syslog.key".to_string()),
+                })
+            );
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-syslog-source
+                source_type: syslog
+                params:
+                    listen_port: 6514
+                    protocol: tls
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+        {
+            let content = r#"
+                version: 0.8
+                source_id: my-syslog-source
+                source_type: syslog
+                params:
+                    listen_port: 514
+                    protocol: udp
+                    tls_cert_path: /etc/quickwit/syslog.crt
+                "#;
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes())
+                .unwrap_err();
+        }
+    }
+
     #[test]
     fn test_pulsar_source_params_deserialization() {
         {
@@ -1084,7 +1784,10 @@ mod tests {
             transform_config: Some(TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: default_timezone(),
+                stages: Vec::new(),
             }),
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         assert_eq!(source_config, expected_source_config);
@@ -1097,6 +1800,7 @@ mod tests {
             let transform_config = TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: "local".to_string(),
+                stages: Vec::new(),
             };
             let transform_config_yaml = serde_yaml::to_string(&transform_config).unwrap();
             assert_eq!(
@@ -1108,6 +1812,7 @@ mod tests {
             let transform_config = TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: default_timezone(),
+                stages: Vec::new(),
             };
             let transform_config_yaml = serde_yaml::to_string(&transform_config).unwrap();
             assert_eq!(
@@ -1129,6 +1834,7 @@ mod tests {
             let expected_transform_config = TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: default_timezone(),
+                stages: Vec::new(),
             };
             assert_eq!(transform_config, expected_transform_config);
         }
@@ -1143,11 +1849,86 @@ mod tests {
             let expected_transform_config = TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: "Turkey".to_string(),
+                stages: Vec::new(),
             };
             assert_eq!(transform_config, expected_transform_config);
         }
     }
 
+    #[test]
+    fn test_transform_config_stages_deserialization() {
+        let transform_config_yaml = r#"
+            stages:
+              - name: parse
+                script: . = parse_json!(string!(.message))
+              - name: drop-debug
+                condition: .level == "debug"
+                action: drop
+              - name: route-audit
+                condition: exists(.audit)
+                action: route
+                index_id: audit-logs
+        "#;
+        let transform_config =
+            serde_yaml::from_str::<TransformConfig>(transform_config_yaml).unwrap();
+        transform_config.validate_vrl_script().unwrap();
+
+        assert!(transform_config.vrl_script.is_empty());
+        assert!(transform_config.has_route_stages());
+
+        let stages = transform_config.stages();
+        assert_eq!(stages.len(), 3);
+        assert_eq!(stages[0].action, TransformStageAction::Mutate);
+        assert_eq!(stages[1].action, TransformStageAction::Drop);
+        assert_eq!(stages[1].condition.as_deref(), Some(r#".level == "debug""#));
+        assert_eq!(stages[2].action, TransformStageAction::Route);
+        assert_eq!(stages[2].index_id.as_deref(), Some("audit-logs"));
+    }
+
+    #[test]
+    fn test_transform_config_stages_validation() {
+        let stage = |name: &str, action: TransformStageAction| TransformStageConfig {
+            name: name.to_string(),
+            condition: None,
+            action,
+            script: None,
+            index_id: None,
+        };
+        {
+            let transform_config = TransformConfig::for_test_with_stages("", Vec::new());
+            let error = transform_config.validate_vrl_script().unwrap_err();
+            assert!(error.to_string().contains("script or at least one stage"));
+        }
+        {
+            let stages = vec![
+                stage("drop", TransformStageAction::Drop),
+                stage("drop", TransformStageAction::Drop),
+            ];
+            let transform_config = TransformConfig::for_test_with_stages("", stages);
+            let error = transform_config.validate_vrl_script().unwrap_err();
+            assert!(error.to_string().contains("defined more than once"));
+        }
+        {
+            let stages = vec![stage("mutate", TransformStageAction::Mutate)];
+            let transform_config = TransformConfig::for_test_with_stages("", stages);
+            let error = transform_config.validate_vrl_script().unwrap_err();
+            assert!(error.to_string().contains("requires a script"));
+        }
+        {
+            let stages = vec![stage("route", TransformStageAction::Route)];
+            let transform_config = TransformConfig::for_test_with_stages("", stages);
+            let error = transform_config.validate_vrl_script().unwrap_err();
+            assert!(error.to_string().contains("requires an index ID"));
+        }
+        {
+            let mut drop_stage = stage("drop", TransformStageAction::Drop);
+            drop_stage.index_id = Some("other-index".to_string());
+            let transform_config = TransformConfig::for_test_with_stages("", vec![drop_stage]);
+            let error = transform_config.validate_vrl_script().unwrap_err();
+            assert!(error.to_string().contains("only `route` stages"));
+        }
+    }
+
     #[cfg(feature = "vrl")]
     #[test]
     fn test_transform_config_compile_vrl_script() {
@@ -1155,6 +1936,7 @@ mod tests {
             let transform_config = TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: "Turkey".to_string(),
+                stages: Vec::new(),
             };
             transform_config.compile_vrl_script().unwrap();
         }
@@ -1168,6 +1950,7 @@ mod tests {
                 "#
                 .to_string(),
                 timezone: default_timezone(),
+                stages: Vec::new(),
             };
             transform_config.compile_vrl_script().unwrap();
         }
@@ -1175,6 +1958,7 @@ mod tests {
             let transform_config = TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
                 timezone: "foo".to_string(),
+                stages: Vec::new(),
             };
             let error = transform_config.compile_vrl_script().unwrap_err();
             assert!(error.to_string().starts_with("failed to parse timezone"));
@@ -1183,6 +1967,7 @@ mod tests {
             let transform_config = TransformConfig {
                 vrl_script: "foo".to_string(),
                 timezone: "Turkey".to_string(),
+                stages: Vec::new(),
             };
             let error = transform_config.compile_vrl_script().unwrap_err();
             assert!(error.to_string().starts_with("failed to compile"));
@@ -1205,4 +1990,98 @@ mod tests {
                 .unwrap();
         assert_eq!(source_config.input_format, SourceInputFormat::PlainText);
     }
+
+    #[test]
+    fn test_source_config_json_schema() {
+        let content = r#"
+            version: 0.8
+            source_id: orders
+            source_type: void
+            params: {}
+            json_schema:
+              version_field: schema_version
+              min_version: 2
+              versions:
+                - version: 2
+                  schema:
+                    type: object
+                    required: [order_id]
+                - version: 3
+                  url: https://registry.example.com/subjects/orders/versions/3
+        "#;
+        let source_config =
+            load_source_config_from_user_config(ConfigFormat::Yaml, content.as_bytes()).unwrap();
+        let json_schema_config = source_config.json_schema_config.unwrap();
+        assert_eq!(json_schema_config.versions.len(), 2);
+        assert_eq!(
+            json_schema_config.version_field.as_deref(),
+            Some("schema_version")
+        );
+        assert_eq!(json_schema_config.min_version, Some(2));
+        assert_eq!(json_schema_config.latest_version(), Some(3));
+        assert_eq!(
+            json_schema_config.versions[0].schema,
+            Some(json!({"type": "object", "required": ["order_id"]}))
+        );
+    }
+
+    #[test]
+    fn test_json_schema_config_validate() {
+        JsonSchemaConfig::for_test(json!({"type": "object"}))
+            .validate()
+            .unwrap();
+        {
+            let json_schema_config = JsonSchemaConfig {
+                versions: Vec::new(),
+                version_field: None,
+                min_version: None,
+            };
+            let error = json_schema_config.validate().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "JSON Schema must have at least one version"
+            );
+        }
+        {
+            let mut json_schema_config = JsonSchemaConfig::for_test(json!({"type": "object"}));
+            json_schema_config.versions[0].url = Some("https://registry".to_string());
+            let error = json_schema_config.validate().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "JSON Schema version `1` must have either a `schema` or a `url`"
+            );
+        }
+        {
+            let mut json_schema_config = JsonSchemaConfig::for_test(json!({"type": "object"}));
+            json_schema_config.versions[0].schema = None;
+            json_schema_config.versions[0].url = Some("file:///schema.json".to_string());
+            json_schema_config.validate().unwrap_err();
+        }
+        {
+            let mut json_schema_config = JsonSchemaConfig::for_test(json!("object"));
+            json_schema_config.validate().unwrap_err();
+
+            json_schema_config.versions[0].schema = Some(json!(true));
+            json_schema_config.validate().unwrap();
+        }
+        {
+            let mut json_schema_config = JsonSchemaConfig::for_test(json!({"type": "object"}));
+            let version = json_schema_config.versions[0].clone();
+            json_schema_config.versions.push(version);
+            let error = json_schema_config.validate().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "JSON Schema version `1` is defined more than once"
+            );
+        }
+        {
+            let mut json_schema_config = JsonSchemaConfig::for_test(json!({"type": "object"}));
+            json_schema_config.min_version = Some(2);
+            let error = json_schema_config.validate().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "JSON Schema `min_version` (2) is greater than the latest version (1)"
+            );
+        }
+    }
 }
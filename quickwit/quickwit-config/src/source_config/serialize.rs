@@ -17,13 +17,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use std::num::NonZeroUsize;
 
 use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
-use super::{TransformConfig, RESERVED_SOURCE_IDS};
-use crate::{validate_identifier, ConfigFormat, SourceConfig, SourceInputFormat, SourceParams};
+use super::{JsonSchemaConfig, TransformConfig, RESERVED_SOURCE_IDS};
+use crate::{
+    validate_identifier, ConfigFormat, SourceConfig, SourceInputFormat, SourceParams,
+    SyslogProtocol, WasmPluginConfig,
+};
 
 type SourceConfigForSerialization = SourceConfigV0_8;
 
@@ -91,6 +95,86 @@ impl SourceConfigForSerialization {
             SourceParams::Kafka(_) | SourceParams::Kinesis(_) | SourceParams::Pulsar(_) => {
                 // TODO consider any validation opportunity
             }
+            SourceParams::Parquet(parquet_params) => {
+                if parquet_params.filepaths.is_empty() {
+                    bail!(
+                        "source `{}` of type `parquet` must contain at least one filepath",
+                        self.source_id
+                    )
+                }
+                let mut filepaths = HashSet::with_capacity(parquet_params.filepaths.len());
+
+                for filepath in &parquet_params.filepaths {
+                    if !filepaths.insert(filepath) {
+                        bail!(
+                            "source `{}` of type `parquet` contains the filepath `{filepath}` \
+                             more than once",
+                            self.source_id
+                        )
+                    }
+                }
+                // The rows of the Parquet files are converted to JSON documents.
+                if self.input_format != SourceInputFormat::Json {
+                    bail!(
+                        "source `{}` of type `parquet` must use the `json` input format",
+                        self.source_id
+                    )
+                }
+            }
+            SourceParams::Reindex(reindex_params) => {
+                validate_identifier("Index ID", &reindex_params.source_index_id)?;
+
+                if reindex_params.max_num_docs_per_sec == Some(0) {
+                    bail!(
+                        "source `{}` of type `reindex` must have a strictly positive \
+                         `max_num_docs_per_sec`",
+                        self.source_id
+                    )
+                }
+                if let Some(alias) = &reindex_params.alias {
+                    validate_identifier("Alias", alias)?;
+
+                    if *alias == reindex_params.source_index_id {
+                        bail!(
+                            "source `{}` of type `reindex` must have an `alias` different from \
+                             its `source_index_id`",
+                            self.source_id
+                        )
+                    }
+                }
+            }
+            SourceParams::Sqs(sqs_params) => {
+                // SQS caps the visibility timeout at 12 hours.
+                if !(1..=43_200).contains(&sqs_params.visibility_timeout_secs) {
+                    bail!(
+                        "source `{}` of type `sqs` must have a `visibility_timeout_secs` between \
+                         1 and 43200",
+                        self.source_id
+                    )
+                }
+            }
+            SourceParams::Syslog(syslog_params) => {
+                let has_tls_paths =
+                    syslog_params.tls_cert_path.is_some() && syslog_params.tls_key_path.is_some();
+
+                if syslog_params.protocol == SyslogProtocol::Tls && !has_tls_paths {
+                    bail!(
+                        "source `{}` of type `syslog` must specify `tls_cert_path` and \
+                         `tls_key_path` with the `tls` protocol",
+                        self.source_id
+                    )
+                }
+                if syslog_params.protocol != SyslogProtocol::Tls
+                    && (syslog_params.tls_cert_path.is_some()
+                        || syslog_params.tls_key_path.is_some())
+                {
+                    bail!(
+                        "source `{}` of type `syslog` must not specify `tls_cert_path` or \
+                         `tls_key_path` without the `tls` protocol",
+                        self.source_id
+                    )
+                }
+            }
             SourceParams::PubSub(_)
             | SourceParams::Ingest
             | SourceParams::IngestApi
@@ -99,7 +183,10 @@ impl SourceConfigForSerialization {
             | SourceParams::Void(_) => {}
         }
         match &self.source_params {
-            SourceParams::PubSub(_) | SourceParams::Kafka(_) => {}
+            SourceParams::PubSub(_)
+            | SourceParams::Kafka(_)
+            | SourceParams::Reindex(_)
+            | SourceParams::Sqs(_) => {}
             _ => {
                 if self.num_pipelines > 1 {
                     bail!("Quickwit currently supports multiple pipelines only for GCP PubSub or Kafka sources. open an issue https://github.com/quickwit-oss/quickwit/issues if you need the feature for other source types");
@@ -119,6 +206,12 @@ impl SourceConfigForSerialization {
             }
             transform_config.validate_vrl_script()?;
         }
+        if let Some(json_schema_config) = &self.json_schema {
+            json_schema_config.validate()?;
+        }
+        if let Some(wasm_plugin_config) = &self.wasm_plugin {
+            wasm_plugin_config.validate()?;
+        }
 
         Ok(SourceConfig {
             source_id: self.source_id,
@@ -126,6 +219,8 @@ impl SourceConfigForSerialization {
             enabled: self.enabled,
             source_params: self.source_params,
             transform_config: self.transform,
+            json_schema_config: self.json_schema,
+            wasm_plugin_config: self.wasm_plugin,
             input_format: self.input_format,
         })
     }
@@ -139,6 +234,8 @@ impl From<SourceConfig> for SourceConfigV0_8 {
             enabled: source_config.enabled,
             source_params: source_config.source_params,
             transform: source_config.transform_config,
+            json_schema: source_config.json_schema_config,
+            wasm_plugin: source_config.wasm_plugin_config,
             input_format: source_config.input_format,
         }
     }
@@ -218,6 +315,12 @@ pub struct SourceConfigV0_8 {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transform: Option<TransformConfig>,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<JsonSchemaConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm_plugin: Option<WasmPluginConfig>,
+
     // Denotes the input data format.
     #[serde(default)]
     pub input_format: SourceInputFormat,
@@ -240,6 +343,8 @@ impl From<SourceConfigV0_7> for SourceConfigV0_8 {
             enabled,
             source_params,
             transform,
+            json_schema: None,
+            wasm_plugin: None,
             input_format,
         }
     }
@@ -17,10 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::{env, fmt};
 
 use anyhow::ensure;
+use bytesize::ByteSize;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, EnumMap};
@@ -44,6 +46,8 @@ pub enum StorageBackend {
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum StorageBackendFlavor {
+    /// Ceph Object Gateway
+    Ceph,
     /// Digital Ocean Spaces
     #[serde(alias = "do")]
     DigitalOcean,
@@ -104,6 +108,9 @@ impl StorageConfigs {
                 "{left:?} storage config is defined multiple times",
             );
         }
+        if let Some(s3_storage_config) = self.find_s3() {
+            s3_storage_config.validate()?;
+        }
         Ok(())
     }
 
@@ -332,11 +339,60 @@ pub struct S3StorageConfig {
     pub disable_multi_object_delete: bool,
     #[serde(default)]
     pub disable_multipart_upload: bool,
+    /// Disables the computation of the `Content-MD5` checksums of the uploaded parts, which some
+    /// S3-compatible providers reject.
+    #[serde(default)]
+    pub disable_checksums: bool,
+    /// Size above which objects are uploaded with multipart uploads.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multipart_threshold: Option<ByteSize>,
+    /// Target size of the parts of multipart uploads.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multipart_part_size: Option<ByteSize>,
+    /// Storage configurations overriding this one for the storage URIs of specific buckets.
+    /// Bucket configurations do not inherit the settings of the parent configuration.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub buckets: BTreeMap<String, S3StorageConfig>,
 }
 
 impl S3StorageConfig {
+    /// S3 rejects the parts of multipart uploads smaller than 5MiB, except for the last one.
+    const MIN_MULTIPART_PART_SIZE: ByteSize = ByteSize::mib(5);
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(multipart_part_size) = self.multipart_part_size {
+            ensure!(
+                multipart_part_size >= Self::MIN_MULTIPART_PART_SIZE,
+                "S3 multipart part size must be at least {}, got {multipart_part_size}",
+                Self::MIN_MULTIPART_PART_SIZE,
+            );
+        }
+        for (bucket, bucket_config) in &self.buckets {
+            ensure!(
+                bucket_config.buckets.is_empty(),
+                "S3 storage config of bucket `{bucket}` cannot define bucket storage configs"
+            );
+            bucket_config.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the storage configuration to use for the given bucket.
+    pub fn for_bucket(&self, bucket: &str) -> &S3StorageConfig {
+        self.buckets.get(bucket).unwrap_or(self)
+    }
+
     fn apply_flavor(&mut self) {
+        for bucket_config in self.buckets.values_mut() {
+            bucket_config.apply_flavor();
+        }
         match self.flavor {
+            Some(StorageBackendFlavor::Ceph) => {
+                self.force_path_style_access = true;
+            }
             Some(StorageBackendFlavor::DigitalOcean) => {
                 self.force_path_style_access = true;
                 self.disable_multi_object_delete = true;
@@ -360,6 +416,9 @@ impl S3StorageConfig {
         if let Some(secret_access_key) = self.secret_access_key.as_mut() {
             *secret_access_key = "***redacted***".to_string();
         }
+        for bucket_config in self.buckets.values_mut() {
+            bucket_config.redact();
+        }
     }
 
     pub fn endpoint(&self) -> Option<String> {
@@ -388,6 +447,11 @@ impl fmt::Debug for S3StorageConfig {
                 "disable_multi_object_delete",
                 &self.disable_multi_object_delete,
             )
+            .field("disable_multipart_upload", &self.disable_multipart_upload)
+            .field("disable_checksums", &self.disable_checksums)
+            .field("multipart_threshold", &self.multipart_threshold)
+            .field("multipart_part_size", &self.multipart_part_size)
+            .field("buckets", &self.buckets)
             .finish()
     }
 }
@@ -480,6 +544,24 @@ mod tests {
         ]);
         storage_configs.apply_flavors();
 
+        let mut s3_storage_config = S3StorageConfig {
+            buckets: BTreeMap::from([(
+                "ceph-bucket".to_string(),
+                S3StorageConfig {
+                    flavor: Some(StorageBackendFlavor::Ceph),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        s3_storage_config.apply_flavor();
+        assert!(!s3_storage_config.force_path_style_access);
+        assert!(
+            s3_storage_config
+                .for_bucket("ceph-bucket")
+                .force_path_style_access
+        );
+
         let do_storage_config = storage_configs[0].as_s3().unwrap();
         assert!(do_storage_config.force_path_style_access);
         assert!(do_storage_config.disable_multi_object_delete);
@@ -511,6 +593,27 @@ mod tests {
             .into(),
         ]);
         storage_configs.validate().unwrap_err();
+
+        let storage_configs = StorageConfigs(vec![S3StorageConfig {
+            multipart_part_size: Some(ByteSize::mib(1)),
+            ..Default::default()
+        }
+        .into()]);
+        let error = storage_configs.validate().unwrap_err();
+        assert!(error.to_string().contains("multipart part size"));
+
+        let storage_configs = StorageConfigs(vec![S3StorageConfig {
+            buckets: BTreeMap::from([(
+                "bucket".to_string(),
+                S3StorageConfig {
+                    buckets: BTreeMap::from([("nested-bucket".to_string(), Default::default())]),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        }
+        .into()]);
+        storage_configs.validate().unwrap_err();
     }
 
     #[test]
@@ -635,6 +738,37 @@ mod tests {
             };
             assert_eq!(s3_storage_config, expected_s3_config);
         }
+        {
+            let s3_storage_config_yaml = r#"
+                region: eu-west-3
+                buckets:
+                  onprem-indexes:
+                    flavor: ceph
+                    endpoint: http://ceph-rgw:7480
+                    disable_checksums: true
+                    multipart_threshold: 64MiB
+                    multipart_part_size: 16MiB
+            "#;
+            let s3_storage_config: S3StorageConfig =
+                serde_yaml::from_str(s3_storage_config_yaml).unwrap();
+
+            let expected_bucket_config = S3StorageConfig {
+                flavor: Some(StorageBackendFlavor::Ceph),
+                endpoint: Some("http://ceph-rgw:7480".to_string()),
+                disable_checksums: true,
+                multipart_threshold: Some(ByteSize::mib(64)),
+                multipart_part_size: Some(ByteSize::mib(16)),
+                ..Default::default()
+            };
+            assert_eq!(
+                s3_storage_config.for_bucket("onprem-indexes"),
+                &expected_bucket_config
+            );
+            assert_eq!(
+                s3_storage_config.for_bucket("other-indexes").region,
+                Some("eu-west-3".to_string())
+            );
+        }
     }
 
     #[test]
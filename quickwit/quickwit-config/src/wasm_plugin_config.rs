@@ -0,0 +1,104 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::ensure;
+use bytesize::ByteSize;
+use serde::{Deserialize, Serialize};
+
+/// Configuration of a WebAssembly plugin. Plugins are read from the local file system of the
+/// nodes running them, so the module must be deployed on each of these nodes. Plugins are
+/// sandboxed: they cannot access the file system or the network, and their CPU and memory usage
+/// is bounded.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WasmPluginConfig {
+    /// Path of the WebAssembly module, in binary (`.wasm`) or text (`.wat`) format.
+    pub path: String,
+    /// Fuel granted to each call of the plugin. Executing a WebAssembly instruction consumes
+    /// about one unit of fuel. Calls running out of fuel fail.
+    #[schema(default = 100_000_000)]
+    #[serde(default = "WasmPluginConfig::default_max_fuel_per_call")]
+    pub max_fuel_per_call: u64,
+    /// Maximum size of the memory of the plugin.
+    #[schema(value_type = String, default = "64 MiB")]
+    #[serde(default = "WasmPluginConfig::default_max_memory")]
+    pub max_memory: ByteSize,
+}
+
+impl WasmPluginConfig {
+    fn default_max_fuel_per_call() -> u64 {
+        100_000_000
+    }
+
+    fn default_max_memory() -> ByteSize {
+        ByteSize::mib(64)
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            max_fuel_per_call: Self::default_max_fuel_per_call(),
+            max_memory: Self::default_max_memory(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(!self.path.is_empty(), "WASM plugin path must not be empty");
+        ensure!(
+            self.max_fuel_per_call > 0,
+            "WASM plugin `max_fuel_per_call` must be strictly positive"
+        );
+        ensure!(
+            self.max_memory.as_u64() > 0,
+            "WASM plugin `max_memory` must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_plugin_config_serde() {
+        let wasm_plugin_config: WasmPluginConfig =
+            serde_yaml::from_str("path: /var/lib/quickwit/plugins/transform.wasm").unwrap();
+        assert_eq!(
+            wasm_plugin_config,
+            WasmPluginConfig::for_test("/var/lib/quickwit/plugins/transform.wasm")
+        );
+        wasm_plugin_config.validate().unwrap();
+
+        let wasm_plugin_config: WasmPluginConfig = serde_yaml::from_str(
+            r#"
+            path: /var/lib/quickwit/plugins/transform.wasm
+            max_fuel_per_call: 1000
+            max_memory: 1MiB
+            "#,
+        )
+        .unwrap();
+        assert_eq!(wasm_plugin_config.max_fuel_per_call, 1000);
+        assert_eq!(wasm_plugin_config.max_memory, ByteSize::mib(1));
+
+        serde_yaml::from_str::<WasmPluginConfig>("path: transform.wasm\nunknown: 1").unwrap_err();
+        WasmPluginConfig::for_test("").validate().unwrap_err();
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use quickwit_control_plane::model_bench::ShardTableBench;
+
+const NUM_SOURCES: usize = 1_000;
+const NUM_INGESTERS: usize = 50;
+
+fn shard_table_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shard_table");
+
+    for num_shards in [1_000, 10_000, 100_000] {
+        let mut shard_table_bench = ShardTableBench::new(NUM_SOURCES, num_shards, NUM_INGESTERS);
+
+        group.bench_with_input(
+            BenchmarkId::new("count_open_shards_per_leader", num_shards),
+            &shard_table_bench,
+            |b, shard_table_bench| {
+                b.iter(|| black_box(shard_table_bench.count_open_shards_per_leader()));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("count_open_shards_per_leader_full_scan", num_shards),
+            &shard_table_bench,
+            |b, shard_table_bench| {
+                b.iter(|| black_box(shard_table_bench.count_open_shards_per_leader_full_scan()));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("list_open_shards_per_leader", num_shards),
+            &shard_table_bench,
+            |b, shard_table_bench| {
+                b.iter(|| black_box(shard_table_bench.list_open_shards_per_leader()));
+            },
+        );
+        group.bench_function(
+            BenchmarkId::new("insert_and_delete_shard", num_shards),
+            |b| {
+                b.iter(|| shard_table_bench.insert_and_delete_shard());
+            },
+        );
+        group.bench_function(
+            BenchmarkId::new("set_shards_as_unavailable", num_shards),
+            |b| {
+                b.iter_batched(
+                    || ShardTableBench::new(NUM_SOURCES, num_shards, NUM_INGESTERS),
+                    |mut shard_table_bench| shard_table_bench.set_shards_as_unavailable(0),
+                    criterion::BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+}
+
+criterion_group!(shard_table_benches, shard_table_benchmark);
+criterion_main!(shard_table_benches);
@@ -17,51 +17,75 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::fmt::Formatter;
 use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
+use bytesize::ByteSize;
 use futures::stream::FuturesUnordered;
-use futures::{Future, StreamExt};
+use futures::{Future, StreamExt, TryStreamExt};
 use quickwit_actors::{
     Actor, ActorContext, ActorExitStatus, ActorHandle, DeferableReplyHandler, Handler, Mailbox,
     Supervisor, Universe, WeakMailbox,
 };
 use quickwit_cluster::{
-    ClusterChange, ClusterChangeStream, ClusterChangeStreamFactory, ClusterNode,
+    ClusterChange, ClusterChangeStream, ClusterChangeStreamFactory, ClusterFeature,
+    ClusterFeatureGates, ClusterNode,
 };
 use quickwit_common::pubsub::EventSubscriber;
 use quickwit_common::uri::Uri;
 use quickwit_common::Progress;
 use quickwit_config::service::QuickwitService;
-use quickwit_config::{ClusterConfig, IndexConfig, IndexTemplate, SourceConfig};
+use quickwit_config::{
+    ClusterConfig, ClusterSettings, IndexConfig, IndexTemplate, ReindexSourceParams,
+    RolloverPolicy, SourceConfig, SourceParams,
+};
 use quickwit_ingest::{IngesterPool, LocalShardsUpdate};
-use quickwit_metastore::{CreateIndexRequestExt, CreateIndexResponseExt};
+use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpoint};
+use quickwit_metastore::{
+    get_reindex_progress, reindex_alias_flipped_checkpoint_delta, CreateIndexRequestExt,
+    CreateIndexResponseExt, IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    ListSplitsResponseExt, ReindexProgress, SplitState,
+};
 use quickwit_proto::control_plane::{
     AdviseResetShardsRequest, AdviseResetShardsResponse, ControlPlaneError, ControlPlaneResult,
-    GetOrCreateOpenShardsRequest, GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSubrequest,
+    DecommissionIngesterRequest, DecommissionIngesterResponse, ForceCloseShardsRequest,
+    ForceCloseShardsResponse, GetOrCreateOpenShardsRequest, GetOrCreateOpenShardsResponse,
+    GetOrCreateOpenShardsSubrequest, GetShardEventsRequest, GetShardEventsResponse,
+    ListShardsRequest, ListShardsResponse, MoveShardRequest, MoveShardResponse, PauseSourceRequest,
+    PauseSourceResponse, ProjectSourceShardsRequest, ProjectSourceShardsResponse,
+    RebalanceShardsDryRunRequest, RebalanceShardsDryRunResponse, ResumeSourceRequest,
+    ResumeSourceResponse, ShardEventType,
 };
 use quickwit_proto::indexing::ShardPositionsUpdate;
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteIndexRequest,
     DeleteShardsRequest, DeleteSourceRequest, EmptyResponse, FindIndexTemplateMatchesRequest,
-    IndexTemplateMatch, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
-    ToggleSourceRequest,
+    IndexMetadataRequest, IndexMetadataResponse, ListSplitsRequest, MetastoreError,
+    MetastoreResult, MetastoreService, MetastoreServiceClient, PublishSplitsRequest,
+    ToggleSourceRequest, UpdateIndexRequest,
 };
-use quickwit_proto::types::{IndexUid, NodeId, ShardId, SourceUid};
+use quickwit_proto::types::{IndexId, IndexUid, NodeId, ShardId, SourceUid};
 use serde::Serialize;
 use serde_json::{json, Value as JsonValue};
+use time::OffsetDateTime;
 use tokio::sync::watch;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::debouncer::Debouncer;
 use crate::indexing_scheduler::{IndexingScheduler, IndexingSchedulerState};
-use crate::ingest::ingest_controller::{IngestControllerStats, RebalanceShardsCallback};
-use crate::ingest::IngestController;
-use crate::model::ControlPlaneModel;
+use crate::ingest::health_prober::{
+    spawn_probe_ingesters_task, IngesterProbeResults, HEALTH_PROBE_INTERVAL,
+};
+use crate::ingest::ingest_controller::{
+    IngestControllerStats, InitShardsRetryCallback, RebalanceShardsCallback,
+};
+use crate::ingest::shard_id_generator::build_shard_id_generator;
+use crate::ingest::{IngestController, IngestControllerTimeouts};
+use crate::model::{ControlPlaneModel, ModelSnapshotStore, WriteAlias};
 use crate::IndexerPool;
 
 /// Interval between two controls (or checks) of the desired plan VS running plan.
@@ -74,15 +98,33 @@ pub(crate) const CONTROL_PLAN_LOOP_INTERVAL: Duration = if cfg!(any(test, featur
 /// Minimum period between two rebuild plan operations.
 const REBUILD_PLAN_COOLDOWN_PERIOD: Duration = Duration::from_secs(2);
 
+/// Interval between two checks of the write indexes against their rollover policy.
+const ROLLOVER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interval between two checks of the progress of the reindex sources.
+const REINDEX_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Debug)]
 struct ControlPlanLoop;
 
+#[derive(Debug)]
+struct RolloverCheck;
+
+#[derive(Debug)]
+struct ReindexCheck;
+
+#[derive(Debug)]
+struct ProbeIngesters;
+
 #[derive(Debug, Default)]
 struct RebuildPlan;
 
 pub struct ControlPlane {
     cluster_config: ClusterConfig,
     cluster_change_stream_opt: Option<ClusterChangeStream>,
+    // Wire features supported by all the nodes of the cluster. During a rolling upgrade, the
+    // control plane refrains from using the features that some nodes do not support yet.
+    feature_gates: ClusterFeatureGates,
     // The control plane state is split into to independent functions, that we naturally isolated
     // code wise and state wise.
     //
@@ -94,7 +136,11 @@ pub struct ControlPlane {
     ingest_controller: IngestController,
     metastore: MetastoreServiceClient,
     model: ControlPlaneModel,
+    // Saves the model when the control plane quits and restores it when the next one starts.
+    model_snapshot_store_opt: Option<ModelSnapshotStore>,
     rebuild_plan_debouncer: Debouncer,
+    // Progress of the reindex sources as of the last reindex check.
+    reindex_jobs: HashMap<SourceUid, ReindexProgress>,
     readiness_tx: watch::Sender<bool>,
     // Disables the control loop. This is useful for unit testing.
     disable_control_loop: bool,
@@ -107,6 +153,7 @@ impl fmt::Debug for ControlPlane {
 }
 
 impl ControlPlane {
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         universe: &Universe,
         cluster_config: ClusterConfig,
@@ -115,6 +162,8 @@ impl ControlPlane {
         indexer_pool: IndexerPool,
         ingester_pool: IngesterPool,
         metastore: MetastoreServiceClient,
+        model_snapshot_store_opt: Option<ModelSnapshotStore>,
+        ingest_controller_timeouts: IngestControllerTimeouts,
     ) -> (
         Mailbox<Self>,
         ActorHandle<Supervisor<Self>>,
@@ -130,6 +179,8 @@ impl ControlPlane {
             indexer_pool,
             ingester_pool,
             metastore,
+            model_snapshot_store_opt,
+            ingest_controller_timeouts,
             disable_control_loop,
         )
     }
@@ -143,6 +194,8 @@ impl ControlPlane {
         indexer_pool: IndexerPool,
         ingester_pool: IngesterPool,
         metastore: MetastoreServiceClient,
+        model_snapshot_store_opt: Option<ModelSnapshotStore>,
+        ingest_controller_timeouts: IngestControllerTimeouts,
         disable_control_loop: bool,
     ) -> (
         Mailbox<Self>,
@@ -158,11 +211,15 @@ impl ControlPlane {
                 let replication_factor = cluster_config.replication_factor;
                 let indexing_scheduler =
                     IndexingScheduler::new(cluster_id, self_node_id.clone(), indexer_pool.clone());
+                let shard_id_generator =
+                    build_shard_id_generator(cluster_config.shard_id_strategy, &self_node_id);
                 let ingest_controller = IngestController::new(
                     metastore.clone(),
                     ingester_pool.clone(),
                     replication_factor,
-                );
+                    ingest_controller_timeouts,
+                )
+                .with_shard_id_generator(shard_id_generator);
 
                 let readiness_tx = readiness_tx.clone();
                 let _ = readiness_tx.send(false);
@@ -170,11 +227,14 @@ impl ControlPlane {
                 ControlPlane {
                     cluster_config: cluster_config.clone(),
                     cluster_change_stream_opt: Some(cluster_change_stream_factory.create()),
+                    feature_gates: ClusterFeatureGates::default(),
                     indexing_scheduler,
                     ingest_controller,
                     metastore: metastore.clone(),
                     model: Default::default(),
+                    model_snapshot_store_opt: model_snapshot_store_opt.clone(),
                     rebuild_plan_debouncer: Debouncer::new(REBUILD_PLAN_COOLDOWN_PERIOD),
+                    reindex_jobs: HashMap::new(),
                     readiness_tx,
                     disable_control_loop,
                 }
@@ -212,16 +272,24 @@ impl Actor for ControlPlane {
 
     async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
         crate::metrics::CONTROL_PLANE_METRICS.restart_total.inc();
-        self.model
-            .load_from_metastore(&mut self.metastore, ctx.progress())
-            .await
-            .context("failed to initialize control plane model")?;
+
+        if !self.restore_model_from_snapshot(ctx.progress()).await {
+            self.model
+                .load_from_metastore(&mut self.metastore, ctx.progress())
+                .await
+                .context("failed to initialize control plane model")?;
+        }
 
         let _rebuild_plan_waiter = self.rebuild_plan_debounced(ctx);
 
         self.ingest_controller.sync_with_all_ingesters(&self.model);
+        self.ingest_controller
+            .set_control_plane_mailbox(ctx.mailbox().downgrade());
 
         ctx.schedule_self_msg(CONTROL_PLAN_LOOP_INTERVAL, ControlPlanLoop);
+        ctx.schedule_self_msg(ROLLOVER_CHECK_INTERVAL, RolloverCheck);
+        ctx.schedule_self_msg(REINDEX_CHECK_INTERVAL, ReindexCheck);
+        ctx.schedule_self_msg(HEALTH_PROBE_INTERVAL, ProbeIngesters);
 
         let weak_mailbox = ctx.mailbox().downgrade();
         let cluster_change_stream = self
@@ -232,9 +300,85 @@ impl Actor for ControlPlane {
         let _ = self.readiness_tx.send(true);
         Ok(())
     }
+
+    async fn finalize(
+        &mut self,
+        exit_status: &ActorExitStatus,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<()> {
+        // The model is only known to be consistent with the metastore when the control plane
+        // quits gracefully after a successful initialization. Otherwise, the next control plane
+        // must load it from the metastore.
+        if matches!(exit_status, ActorExitStatus::Quit) && *self.readiness_tx.borrow() {
+            self.save_model_snapshot(ctx.progress()).await;
+        }
+        Ok(())
+    }
 }
 
 impl ControlPlane {
+    /// Restores the model from the snapshot saved by the previous control plane, if any, after
+    /// reconciling it with the metastore. Returns `false` if the model must be loaded from the
+    /// metastore instead.
+    async fn restore_model_from_snapshot(&mut self, progress: &Progress) -> bool {
+        let Some(model_snapshot_store) = &self.model_snapshot_store_opt else {
+            return false;
+        };
+        let cluster_id = &self.cluster_config.cluster_id;
+
+        let mut model_snapshot = match progress
+            .protect_future(model_snapshot_store.take(cluster_id))
+            .await
+        {
+            Ok(Some(model_snapshot)) => model_snapshot,
+            Ok(None) => return false,
+            Err(error) => {
+                warn!(%error, "failed to restore control plane model from snapshot");
+                return false;
+            }
+        };
+        match ControlPlaneModel::reconcile_snapshot(
+            &mut model_snapshot,
+            &mut self.metastore,
+            progress,
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(error) => {
+                warn!(%error, "failed to reconcile control plane model snapshot with metastore");
+                return false;
+            }
+        }
+        self.model.restore_from_snapshot(model_snapshot);
+
+        // Write aliases are not part of the snapshot.
+        if let Err(error) = self
+            .model
+            .load_write_aliases(&mut self.metastore, progress)
+            .await
+        {
+            warn!(%error, "failed to load write aliases");
+            return false;
+        }
+        true
+    }
+
+    async fn save_model_snapshot(&self, progress: &Progress) {
+        let Some(model_snapshot_store) = &self.model_snapshot_store_opt else {
+            return;
+        };
+        let model_snapshot = self.model.snapshot(self.cluster_config.cluster_id.clone());
+
+        if let Err(error) = progress
+            .protect_future(model_snapshot_store.save(&model_snapshot))
+            .await
+        {
+            warn!(%error, "failed to save control plane model snapshot");
+        }
+    }
+
     async fn auto_create_indexes(
         &mut self,
         subrequests: &[GetOrCreateOpenShardsSubrequest],
@@ -246,7 +390,11 @@ impl ControlPlane {
         let mut index_ids = Vec::new();
 
         for subrequest in subrequests {
-            if self.model.index_uid(&subrequest.index_id).is_none() {
+            if self
+                .model
+                .resolve_write_index_uid(&subrequest.index_id)
+                .is_none()
+            {
                 index_ids.push(subrequest.index_id.clone());
             }
         }
@@ -262,14 +410,40 @@ impl ControlPlane {
             .await?;
 
         let mut create_index_futures = FuturesUnordered::new();
+        // Maps the IDs of the write indexes being created to their write alias.
+        let mut pending_write_aliases: HashMap<IndexId, (IndexId, IndexTemplate)> = HashMap::new();
 
         for index_template_match in find_index_template_matches_response.matches {
+            let index_template: IndexTemplate =
+                serde_utils::from_json_str(&index_template_match.index_template_json)?;
+            let mut index_id = index_template_match.index_id;
+
+            // When the index template enables rollover, the index ID is a write alias. We resolve
+            // it to its latest write index if one already exists or create the first one
+            // otherwise.
+            if index_template.rollover_policy_opt.is_some() {
+                if let Some((generation, write_index_uid)) =
+                    self.model.latest_write_index(&index_id)
+                {
+                    let write_alias = WriteAlias {
+                        index_template,
+                        write_index_uid,
+                        generation,
+                    };
+                    self.model.set_write_alias(index_id, write_alias);
+                    continue;
+                }
+                let alias = index_id;
+                index_id = RolloverPolicy::write_index_id(&alias, 1);
+                pending_write_aliases.insert(index_id.clone(), (alias, index_template.clone()));
+            }
             // TODO: It's a bit brutal to fail the entire operation if applying a single index
             // template fails. We should return a partial failure instead for the subrequest. I
             // want to do so in an upcoming refactor where the `GetOrCreateOpenShardsRequest` will
             // be processed in multiple steps in a dedicated workbench.
-            let index_config = apply_index_template_match(
-                index_template_match,
+            let index_config = apply_index_template(
+                &index_template,
+                index_id,
                 &self.cluster_config.default_index_root_uri,
             )?;
             // We disable ingest V1 for index templates.
@@ -291,7 +465,315 @@ impl ControlPlane {
             // Same here.
             let create_index_response = create_index_response_result?;
             let index_metadata = create_index_response.deserialize_index_metadata()?;
+            let index_uid = index_metadata.index_uid.clone();
             self.model.add_index(index_metadata);
+
+            if let Some((alias, index_template)) = pending_write_aliases.remove(&index_uid.index_id)
+            {
+                let write_alias = WriteAlias {
+                    index_template,
+                    write_index_uid: index_uid,
+                    generation: 1,
+                };
+                self.model.set_write_alias(alias, write_alias);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolls over the write indexes that exceed the rollover policy of their index template: for
+    /// each of them, the control plane creates the next write index from the template, flips the
+    /// write alias to it, and closes the ingest shards of the previous write index.
+    ///
+    /// A failed rollover does not prevent the other aliases from rolling over. The control plane
+    /// only exits, and reloads its model from the metastore, when the outcome of the creation of
+    /// the new write index is unknown.
+    async fn rollover_write_indexes(
+        &mut self,
+        progress: &Progress,
+    ) -> Result<usize, ActorExitStatus> {
+        let write_aliases: Vec<(IndexId, WriteAlias)> = self
+            .model
+            .write_aliases()
+            .map(|(alias, write_alias)| (alias.clone(), write_alias.clone()))
+            .collect();
+        let mut num_rollovers = 0;
+
+        for (alias, write_alias) in write_aliases {
+            if !self.should_rollover(&write_alias, progress).await {
+                continue;
+            }
+            match self
+                .rollover_write_index(alias.clone(), write_alias, progress)
+                .await
+            {
+                Ok(()) => num_rollovers += 1,
+                Err(metastore_error) => {
+                    if let Err(control_plane_error) =
+                        convert_metastore_error::<()>(metastore_error)?
+                    {
+                        error!(
+                            alias=%alias,
+                            error=%control_plane_error,
+                            "failed to roll over write index"
+                        );
+                    }
+                }
+            }
+        }
+        Ok(num_rollovers)
+    }
+
+    async fn should_rollover(&mut self, write_alias: &WriteAlias, progress: &Progress) -> bool {
+        let write_index_uid = &write_alias.write_index_uid;
+        let rollover_policy = write_alias.rollover_policy();
+
+        let Some(index_metadata) = self.model.index_metadata(write_index_uid) else {
+            return false;
+        };
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let index_age_secs = (now_timestamp - index_metadata.create_timestamp).max(0) as u64;
+        let index_age = Duration::from_secs(index_age_secs);
+
+        // The age of the index is checked first because it does not require listing its splits.
+        if rollover_policy.should_rollover(index_age, ByteSize(0)) {
+            return true;
+        }
+        let Some(max_size) = rollover_policy.max_size else {
+            return false;
+        };
+        match self
+            .published_splits_size_up_to(write_index_uid.clone(), max_size, progress)
+            .await
+        {
+            Ok(index_size) => rollover_policy.should_rollover(index_age, index_size),
+            Err(metastore_error) => {
+                warn!(
+                    index_uid=%write_index_uid,
+                    error=%metastore_error,
+                    "failed to list splits of write index"
+                );
+                false
+            }
+        }
+    }
+
+    /// Returns the total size of the published splits of an index. The splits are listed page by
+    /// page, and the listing stops as soon as the total size reaches `max_size`.
+    async fn published_splits_size_up_to(
+        &mut self,
+        index_uid: IndexUid,
+        max_size: ByteSize,
+        progress: &Progress,
+    ) -> MetastoreResult<ByteSize> {
+        let list_splits_query =
+            ListSplitsQuery::for_index(index_uid).with_split_state(SplitState::Published);
+        let list_splits_request =
+            ListSplitsRequest::try_from_list_splits_query(&list_splits_query)?;
+        let mut list_splits_stream = progress
+            .protect_future(self.metastore.list_splits(list_splits_request))
+            .await?;
+        let mut num_bytes: u64 = 0;
+
+        while let Some(list_splits_response) = progress
+            .protect_future(list_splits_stream.try_next())
+            .await?
+        {
+            num_bytes += list_splits_response
+                .deserialize_splits_metadata()?
+                .iter()
+                .map(|split_metadata| split_metadata.footer_offsets.end)
+                .sum::<u64>();
+
+            if num_bytes >= max_size.as_u64() {
+                break;
+            }
+        }
+        Ok(ByteSize(num_bytes))
+    }
+
+    async fn rollover_write_index(
+        &mut self,
+        alias: IndexId,
+        write_alias: WriteAlias,
+        progress: &Progress,
+    ) -> MetastoreResult<()> {
+        let previous_write_index_uid = write_alias.write_index_uid;
+        let generation = write_alias.generation + 1;
+        let index_id = RolloverPolicy::write_index_id(&alias, generation);
+
+        let index_config = apply_index_template(
+            &write_alias.index_template,
+            index_id,
+            &self.cluster_config.default_index_root_uri,
+        )?;
+        let source_configs = [SourceConfig::ingest_v2(), SourceConfig::cli()];
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_and_source_configs(&index_config, &source_configs)?;
+        let create_index_response = progress
+            .protect_future(self.metastore.create_index(create_index_request))
+            .await?;
+        let index_metadata = create_index_response.deserialize_index_metadata()?;
+        let write_index_uid = index_metadata.index_uid.clone();
+        self.model.add_index(index_metadata);
+
+        info!(
+            alias=%alias,
+            previous_write_index_uid=%previous_write_index_uid,
+            write_index_uid=%write_index_uid,
+            "rolling over write index"
+        );
+        // The model is only mutated by the control plane actor, so flipping the alias is atomic
+        // from the point of view of the `GetOrCreateOpenShardsRequest` requests: they are routed
+        // either to the previous write index or to the new one.
+        let write_alias = WriteAlias {
+            index_template: write_alias.index_template,
+            write_index_uid,
+            generation,
+        };
+        self.model.set_write_alias(alias, write_alias);
+
+        // Closing the shards of the previous write index forces the routers to request new
+        // shards, which are now opened in the new write index.
+        self.ingest_controller
+            .close_index_shards(&previous_write_index_uid, &mut self.model, progress)
+            .await;
+        Ok(())
+    }
+
+    /// Refreshes the progress of the reindex sources and flips the write alias of the reindexes
+    /// whose source index has been backfilled.
+    ///
+    /// Reindexes are resumed from the checkpoints of their sources, so the control plane only
+    /// needs to schedule them like any other source. Once the mature splits of the source index
+    /// have been reindexed, the documents ingested into the alias are written to the index of the
+    /// reindex source instead, and the remaining immature splits are reindexed as they mature.
+    async fn check_reindex_jobs(&mut self, progress: &Progress) -> Result<(), ActorExitStatus> {
+        let reindex_sources: Vec<(SourceUid, ReindexSourceParams, bool)> = self
+            .model
+            .source_configs()
+            .filter_map(
+                |(source_uid, source_config)| match &source_config.source_params {
+                    SourceParams::Reindex(reindex_params) => {
+                        Some((source_uid, reindex_params.clone(), source_config.enabled))
+                    }
+                    _ => None,
+                },
+            )
+            .collect();
+        let mut reindex_jobs = HashMap::with_capacity(reindex_sources.len());
+
+        for (source_uid, reindex_params, enabled) in reindex_sources {
+            let mut reindex_progress = match self
+                .fetch_reindex_progress(&source_uid, &reindex_params, enabled, progress)
+                .await
+            {
+                Ok(reindex_progress) => reindex_progress,
+                Err(metastore_error) => {
+                    warn!(
+                        index_uid=%source_uid.index_uid,
+                        source_id=%source_uid.source_id,
+                        error=%metastore_error,
+                        "failed to fetch reindex progress"
+                    );
+                    continue;
+                }
+            };
+            if let Some(alias) = &reindex_params.alias {
+                let is_alias_pending =
+                    self.model
+                        .reindex_alias(alias)
+                        .is_some_and(|reindex_alias| {
+                            reindex_alias.source_uid == source_uid && !reindex_alias.flipped
+                        });
+                if enabled && is_alias_pending && reindex_progress.stage.is_backfilled() {
+                    match self.flip_reindex_alias(alias, &source_uid, progress).await {
+                        Ok(()) => reindex_progress.alias_flipped = true,
+                        Err(metastore_error) => {
+                            if let Err(control_plane_error) =
+                                convert_metastore_error::<()>(metastore_error)?
+                            {
+                                error!(
+                                    alias=%alias,
+                                    error=%control_plane_error,
+                                    "failed to flip reindex alias"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            reindex_jobs.insert(source_uid, reindex_progress);
+        }
+        self.reindex_jobs = reindex_jobs;
+        Ok(())
+    }
+
+    async fn fetch_reindex_progress(
+        &mut self,
+        source_uid: &SourceUid,
+        reindex_params: &ReindexSourceParams,
+        enabled: bool,
+        progress: &Progress,
+    ) -> MetastoreResult<ReindexProgress> {
+        // The checkpoints of the model are stale: the indexers publish their splits without going
+        // through the control plane.
+        let index_metadata_request =
+            IndexMetadataRequest::for_index_uid(source_uid.index_uid.clone());
+        let checkpoint: SourceCheckpoint = progress
+            .protect_future(self.metastore.index_metadata(index_metadata_request))
+            .await?
+            .deserialize_index_metadata()?
+            .checkpoint
+            .source_checkpoint(&source_uid.source_id)
+            .cloned()
+            .unwrap_or_default();
+        progress
+            .protect_future(get_reindex_progress(
+                &mut self.metastore,
+                reindex_params,
+                &checkpoint,
+                enabled,
+            ))
+            .await
+    }
+
+    /// Flips the write alias of a reindex from the source index to the index of the reindex
+    /// source. The flip is first recorded in the checkpoint of the reindex source, so that it
+    /// survives control plane restarts.
+    async fn flip_reindex_alias(
+        &mut self,
+        alias: &str,
+        source_uid: &SourceUid,
+        progress: &Progress,
+    ) -> MetastoreResult<()> {
+        let index_checkpoint_delta = IndexCheckpointDelta {
+            source_id: source_uid.source_id.clone(),
+            source_delta: reindex_alias_flipped_checkpoint_delta(),
+        };
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(source_uid.index_uid.clone()),
+            staged_split_ids: Vec::new(),
+            replaced_split_ids: Vec::new(),
+            index_checkpoint_delta_json_opt: Some(serde_utils::to_json_str(
+                &index_checkpoint_delta,
+            )?),
+            publish_token_opt: None,
+        };
+        progress
+            .protect_future(self.metastore.publish_splits(publish_splits_request))
+            .await?;
+
+        let source_index_uid_opt = self.model.resolve_write_index_uid(alias);
+        self.model.flip_reindex_alias(alias);
+
+        // As for rollovers, closing the shards of the source index forces the routers to request
+        // new shards, which are now opened in the index of the reindex source.
+        if let Some(source_index_uid) = source_index_uid_opt {
+            self.ingest_controller
+                .close_index_shards(&source_index_uid, &mut self.model, progress)
+                .await;
         }
         Ok(())
     }
@@ -322,10 +804,31 @@ impl ControlPlane {
             .protect_future(self.metastore.delete_shards(delete_shards_request))
             .await
             .context("failed to delete shards from metastore")?;
+        self.ingest_controller.record_shard_events(
+            ShardEventType::Deleted,
+            source_uid,
+            shard_ids,
+            &self.model,
+            "fully indexed",
+        );
         self.model.delete_shards(source_uid, shard_ids);
         Ok(())
     }
 
+    /// Shards are only moved, and ingesters decommissioned, once all the nodes of the cluster
+    /// support it.
+    fn check_shard_move_enabled(&self) -> ControlPlaneResult<()> {
+        if self.feature_gates.is_enabled(ClusterFeature::ShardMove) {
+            return Ok(());
+        }
+        let message = format!(
+            "moving shards and decommissioning ingesters are not supported until all the nodes of \
+             the cluster support the `{}` feature",
+            ClusterFeature::ShardMove
+        );
+        Err(ControlPlaneError::Unavailable(message))
+    }
+
     fn debug_info(&self) -> JsonValue {
         let physical_indexing_plan: Vec<JsonValue> = self
             .indexing_scheduler
@@ -368,9 +871,22 @@ impl ControlPlane {
             })
             .collect();
 
+        let reindex_jobs: Vec<JsonValue> = self
+            .reindex_jobs
+            .iter()
+            .map(|(source_uid, reindex_progress)| {
+                json!({
+                    "index_uid": source_uid.index_uid.clone(),
+                    "source_id": source_uid.source_id.clone(),
+                    "progress": reindex_progress,
+                })
+            })
+            .collect();
+
         json!({
             "physical_indexing_plan": physical_indexing_plan,
             "shard_table": shard_table,
+            "reindex_jobs": reindex_jobs,
         })
     }
 
@@ -462,6 +978,8 @@ impl Handler<ControlPlanLoop> for ControlPlane {
         if self.disable_control_loop {
             return Ok(());
         }
+        self.ingest_controller
+            .advance_decommissioning_ingesters(&self.model);
         self.ingest_controller
             .rebalance_shards(&mut self.model, ctx.mailbox(), ctx.progress())
             .await;
@@ -471,6 +989,83 @@ impl Handler<ControlPlanLoop> for ControlPlane {
     }
 }
 
+#[async_trait]
+impl Handler<RolloverCheck> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: RolloverCheck,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        let num_rollovers = self.rollover_write_indexes(ctx.progress()).await?;
+
+        if num_rollovers > 0 {
+            let _rebuild_plan_waiter = self.rebuild_plan_debounced(ctx);
+        }
+        ctx.schedule_self_msg(ROLLOVER_CHECK_INTERVAL, RolloverCheck);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<ReindexCheck> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: ReindexCheck,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.check_reindex_jobs(ctx.progress()).await?;
+        ctx.schedule_self_msg(REINDEX_CHECK_INTERVAL, ReindexCheck);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<ProbeIngesters> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: ProbeIngesters,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if self.disable_control_loop {
+            return Ok(());
+        }
+        // Ingesters running an older version do not answer pings and would be wrongly considered
+        // unhealthy, so we wait for all the nodes to support them.
+        if self.feature_gates.is_enabled(ClusterFeature::IngesterPing) {
+            // The probes are sent in the background so that slow or unresponsive ingesters do not
+            // block the control plane.
+            spawn_probe_ingesters_task(
+                self.ingest_controller.ingester_pool().clone(),
+                ctx.mailbox().clone(),
+            );
+        }
+        ctx.schedule_self_msg(HEALTH_PROBE_INTERVAL, ProbeIngesters);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<IngesterProbeResults> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: IngesterProbeResults,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.ingest_controller
+            .handle_ingester_probe_results(message.probe_results, &mut self.model, ctx.progress())
+            .await;
+        Ok(())
+    }
+}
+
 /// This function converts a metastore error into an actor error.
 ///
 /// If the metastore error is implying the transaction has not been
@@ -575,6 +1170,49 @@ impl DeferableReplyHandler<CreateIndexRequest> for ControlPlane {
     }
 }
 
+// This handler is a metastore call proxied through the control plane: we must first forward the
+// request to the metastore, and then act on the event.
+#[async_trait]
+impl Handler<UpdateIndexRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<IndexMetadataResponse>;
+
+    async fn handle(
+        &mut self,
+        request: UpdateIndexRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let response = match ctx
+            .protect_future(self.metastore.update_index(request))
+            .await
+        {
+            Ok(response) => response,
+            Err(metastore_error) => return convert_metastore_error(metastore_error),
+        };
+        let index_metadata = match response.deserialize_index_metadata() {
+            Ok(index_metadata) => index_metadata,
+            Err(serde_error) => {
+                error!(error=?serde_error, "failed to deserialize index metadata");
+                return Err(ActorExitStatus::from(anyhow::anyhow!(serde_error)));
+            }
+        };
+        let index_uid = index_metadata.index_uid.clone();
+        let doc_mapping_changed = self.model.update_index(index_metadata);
+
+        // The indexing pipelines are restarted so that new splits honor the updated doc mapping
+        // right away.
+        if doc_mapping_changed {
+            let num_restarted_pipelines =
+                self.indexing_scheduler.restart_index_pipelines(&index_uid);
+            info!(
+                %index_uid,
+                num_restarted_pipelines,
+                "restarting indexing pipelines after doc mapping update"
+            );
+        }
+        Ok(Ok(response))
+    }
+}
+
 // This handler is a metastore call proxied through the control plane: we must first forward the
 // request to the metastore, and then act on the event.
 #[async_trait]
@@ -786,26 +1424,217 @@ impl Handler<AdviseResetShardsRequest> for ControlPlane {
         request: AdviseResetShardsRequest,
         _ctx: &ActorContext<Self>,
     ) -> Result<Self::Reply, ActorExitStatus> {
-        let response = self
+        let response_result = self
             .ingest_controller
             .advise_reset_shards(request, &self.model);
-        Ok(Ok(response))
+        Ok(response_result)
     }
 }
 
+// This is neither a proxied call nor a metastore callback.
 #[async_trait]
-impl Handler<LocalShardsUpdate> for ControlPlane {
-    type Reply = ControlPlaneResult<()>;
+impl Handler<DecommissionIngesterRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<DecommissionIngesterResponse>;
 
     async fn handle(
         &mut self,
-        local_shards_update: LocalShardsUpdate,
+        request: DecommissionIngesterRequest,
         ctx: &ActorContext<Self>,
     ) -> Result<Self::Reply, ActorExitStatus> {
-        self.ingest_controller
-            .handle_local_shards_update(local_shards_update, &mut self.model, ctx.progress())
+        if let Err(error) = self.check_shard_move_enabled() {
+            return Ok(Err(error));
+        }
+        let response_res = self
+            .ingest_controller
+            .decommission_ingester(request, &mut self.model, ctx.mailbox(), ctx.progress())
             .await;
-        let _rebuild_plan_waiter = self.rebuild_plan_debounced(ctx);
+        Ok(response_res)
+    }
+}
+
+// This is neither a proxied call nor a metastore callback.
+#[async_trait]
+impl Handler<MoveShardRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<MoveShardResponse>;
+
+    async fn handle(
+        &mut self,
+        request: MoveShardRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        if let Err(error) = self.check_shard_move_enabled() {
+            return Ok(Err(error));
+        }
+        let response_result = self
+            .ingest_controller
+            .move_shard(request, &mut self.model, ctx.mailbox(), ctx.progress())
+            .await;
+        Ok(response_result)
+    }
+}
+
+// This is neither a proxied call nor a metastore callback.
+#[async_trait]
+impl Handler<GetShardEventsRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<GetShardEventsResponse>;
+
+    async fn handle(
+        &mut self,
+        request: GetShardEventsRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let events = self.ingest_controller.shard_event_log.events(&request);
+        let response = GetShardEventsResponse { events };
+        Ok(Ok(response))
+    }
+}
+
+#[async_trait]
+impl Handler<RebalanceShardsDryRunRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<RebalanceShardsDryRunResponse>;
+
+    async fn handle(
+        &mut self,
+        _request: RebalanceShardsDryRunRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let response = self.ingest_controller.rebalance_shards_dry_run(&self.model);
+        Ok(Ok(response))
+    }
+}
+
+#[async_trait]
+impl Handler<ListShardsRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<ListShardsResponse>;
+
+    async fn handle(
+        &mut self,
+        request: ListShardsRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let response = self.model.list_shards(&request);
+        Ok(Ok(response))
+    }
+}
+
+#[async_trait]
+impl Handler<ProjectSourceShardsRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<ProjectSourceShardsResponse>;
+
+    async fn handle(
+        &mut self,
+        request: ProjectSourceShardsRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let response_res = self
+            .ingest_controller
+            .project_source_shards(request, &self.model);
+        Ok(response_res)
+    }
+}
+
+#[async_trait]
+impl Handler<ForceCloseShardsRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<ForceCloseShardsResponse>;
+
+    async fn handle(
+        &mut self,
+        request: ForceCloseShardsRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        if !self.cluster_config.enable_force_close_shards {
+            let message = "force-closing shards is disabled: set \
+                           `control_plane.enable_force_close_shards` to `true` in the node config \
+                           to enable it"
+                .to_string();
+            return Ok(Err(ControlPlaneError::Unavailable(message)));
+        }
+        let response_res = self
+            .ingest_controller
+            .force_close_shards(request, &mut self.model, ctx.progress())
+            .await;
+        Ok(response_res)
+    }
+}
+
+#[async_trait]
+impl Handler<PauseSourceRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<PauseSourceResponse>;
+
+    async fn handle(
+        &mut self,
+        request: PauseSourceRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let source_uid = match self.model.source_uid(&request.index_id, &request.source_id) {
+            Ok(source_uid) => source_uid,
+            Err(error) => return Ok(Err(error)),
+        };
+        let was_paused = !self.model.pause_source(&source_uid);
+
+        if !was_paused {
+            info!(
+                index_uid=%source_uid.index_uid,
+                source_id=%source_uid.source_id,
+                "paused source"
+            );
+            let _rebuild_plan_waiter = self.rebuild_plan_debounced(ctx);
+        }
+        // The shards are closed even if the source was already paused, in case their leaders
+        // failed to close them the first time around.
+        let num_closed_shards = self
+            .ingest_controller
+            .close_source_shards(&source_uid, &mut self.model, ctx.progress())
+            .await;
+        let response = PauseSourceResponse {
+            was_paused,
+            num_closed_shards: num_closed_shards as u32,
+        };
+        Ok(Ok(response))
+    }
+}
+
+#[async_trait]
+impl Handler<ResumeSourceRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<ResumeSourceResponse>;
+
+    async fn handle(
+        &mut self,
+        request: ResumeSourceRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let source_uid = match self.model.source_uid(&request.index_id, &request.source_id) {
+            Ok(source_uid) => source_uid,
+            Err(error) => return Ok(Err(error)),
+        };
+        let was_paused = self.model.resume_source(&source_uid);
+
+        if was_paused {
+            info!(
+                index_uid=%source_uid.index_uid,
+                source_id=%source_uid.source_id,
+                "resumed source"
+            );
+            let _rebuild_plan_waiter = self.rebuild_plan_debounced(ctx);
+        }
+        let response = ResumeSourceResponse { was_paused };
+        Ok(Ok(response))
+    }
+}
+
+#[async_trait]
+impl Handler<LocalShardsUpdate> for ControlPlane {
+    type Reply = ControlPlaneResult<()>;
+
+    async fn handle(
+        &mut self,
+        local_shards_update: LocalShardsUpdate,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        self.ingest_controller
+            .handle_local_shards_update(local_shards_update, &mut self.model, ctx.progress())
+            .await;
+        let _rebuild_plan_waiter = self.rebuild_plan_debounced(ctx);
         Ok(Ok(()))
     }
 }
@@ -826,6 +1655,26 @@ impl Handler<GetDebugInfo> for ControlPlane {
     }
 }
 
+/// Applies the dynamic cluster settings relevant to the control plane.
+#[derive(Debug)]
+pub struct ApplyClusterSettings(pub ClusterSettings);
+
+#[async_trait]
+impl Handler<ApplyClusterSettings> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        apply_cluster_settings: ApplyClusterSettings,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let ApplyClusterSettings(cluster_settings) = apply_cluster_settings;
+        self.ingest_controller
+            .apply_cluster_settings(&cluster_settings);
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct ControlPlaneEventSubscriber(WeakMailbox<ControlPlane>);
 
@@ -863,14 +1712,13 @@ impl EventSubscriber<ShardPositionsUpdate> for ControlPlaneEventSubscriber {
     }
 }
 
-fn apply_index_template_match(
-    index_template_match: IndexTemplateMatch,
+fn apply_index_template(
+    index_template: &IndexTemplate,
+    index_id: IndexId,
     default_index_root_uri: &Uri,
 ) -> MetastoreResult<IndexConfig> {
-    let index_template: IndexTemplate =
-        serde_utils::from_json_str(&index_template_match.index_template_json)?;
     let index_config = index_template
-        .apply_template(index_template_match.index_id, default_index_root_uri)
+        .apply_template(index_id, default_index_root_uri)
         .map_err(|error| MetastoreError::Internal {
             message: "failed to apply index template".to_string(),
             cause: error.to_string(),
@@ -878,6 +1726,53 @@ fn apply_index_template_match(
     Ok(index_config)
 }
 
+/// A node joined, left, or was updated in the cluster.
+#[derive(Debug)]
+struct UpdateFeatureGates(ClusterChange);
+
+#[async_trait]
+impl Handler<UpdateFeatureGates> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: UpdateFeatureGates,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let previously_enabled_features = self.feature_gates.enabled_features();
+        self.feature_gates.apply_cluster_change(&message.0);
+        let enabled_features = self.feature_gates.enabled_features();
+
+        for feature in previously_enabled_features.symmetric_difference(&enabled_features) {
+            info!(
+                feature=%feature,
+                enabled=enabled_features.contains(feature),
+                "cluster feature toggled"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The ingester of a node advertises a `Decommissioning` or `Decommissioned` status.
+#[derive(Debug)]
+struct IngesterDecommissioning(NodeId);
+
+#[async_trait]
+impl Handler<IngesterDecommissioning> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: IngesterDecommissioning,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        self.ingest_controller
+            .track_decommissioning_ingester(message.0);
+        Ok(())
+    }
+}
+
 /// The indexer joined the cluster.
 #[derive(Debug)]
 struct IndexerJoined(ClusterNode);
@@ -930,6 +1825,22 @@ impl Handler<IndexerLeft> for ControlPlane {
     }
 }
 
+#[async_trait]
+impl Handler<InitShardsRetryCallback> for ControlPlane {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: InitShardsRetryCallback,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        self.ingest_controller
+            .handle_init_shards_retry_callback(message, &mut self.model, ctx.progress())
+            .await;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<RebalanceShardsCallback> for ControlPlane {
     type Reply = ();
@@ -943,13 +1854,23 @@ impl Handler<RebalanceShardsCallback> for ControlPlane {
             "closing {} shards after rebalance",
             message.closed_shards.len()
         );
+        self.ingest_controller
+            .stats
+            .record_closed_shards(message.closed_shards.len());
         for closed_shard in message.closed_shards {
             let shard_id = closed_shard.shard_id().clone();
             let source_uid = SourceUid {
                 index_uid: closed_shard.index_uid().clone(),
                 source_id: closed_shard.source_id,
             };
-            self.model.close_shards(&source_uid, &[shard_id]);
+            let closed_shard_ids = self.model.close_shards(&source_uid, &[shard_id]);
+            self.ingest_controller.record_shard_events(
+                ShardEventType::Closed,
+                &source_uid,
+                &closed_shard_ids,
+                &self.model,
+                "rebalance",
+            );
         }
         // We drop the rebalance guard explicitly here to put some emphasis on where a the rebalance
         // lock is released.
@@ -973,6 +1894,27 @@ async fn watcher_indexers(
         let Some(mailbox) = weak_mailbox.upgrade() else {
             return;
         };
+        let update_feature_gates = UpdateFeatureGates(cluster_change.clone());
+
+        if let Err(error) = mailbox.send_message(update_feature_gates).await {
+            error!(error=%error, "failed to forward `UpdateFeatureGates` event to control plane");
+        }
+        if let ClusterChange::Add(node) | ClusterChange::Update(node) = &cluster_change {
+            let is_decommissioning = node
+                .ingester_status()
+                .is_some_and(|ingester_status| ingester_status.is_decommissioning());
+
+            if is_decommissioning {
+                let ingester_decommissioning = IngesterDecommissioning(node.node_id().into());
+
+                if let Err(error) = mailbox.send_message(ingester_decommissioning).await {
+                    error!(
+                        error=%error,
+                        "failed to forward `IngesterDecommissioning` event to control plane"
+                    );
+                }
+            }
+        }
         match cluster_change {
             ClusterChange::Add(node) => {
                 if node.enabled_services().contains(&QuickwitService::Indexer) {
@@ -988,24 +1930,28 @@ async fn watcher_indexers(
                     }
                 }
             }
-            ClusterChange::Update(_) => {
-                // We are not interested in updates (yet).
-            }
+            ClusterChange::Update(_) => {}
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
 
     use mockall::Sequence;
     use quickwit_actors::{AskError, Observe, SupervisorMetrics};
     use quickwit_cluster::ClusterChangeStreamFactoryForTest;
-    use quickwit_config::{IndexConfig, SourceParams, CLI_SOURCE_ID, INGEST_V2_SOURCE_ID};
+    use quickwit_common::ServiceStream;
+    use quickwit_config::{IndexConfig, SourceInputFormat, CLI_SOURCE_ID, INGEST_V2_SOURCE_ID};
     use quickwit_indexing::IndexingService;
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use quickwit_metastore::{
-        CreateIndexRequestExt, IndexMetadata, ListIndexesMetadataResponseExt,
+        is_reindex_alias_flipped, CreateIndexRequestExt, IndexMetadata,
+        ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
+        PublishSplitsRequestExt, Split, SplitMetadata,
     };
     use quickwit_proto::control_plane::{
         GetOrCreateOpenShardsFailureReason, GetOrCreateOpenShardsSubrequest,
@@ -1015,16 +1961,18 @@ mod tests {
         MockIndexingService,
     };
     use quickwit_proto::ingest::ingester::{
-        IngesterServiceClient, InitShardSuccess, InitShardsResponse, MockIngesterService,
-        RetainShardsResponse,
+        IngesterServiceClient, IngesterStatus, InitShardSuccess, InitShardsResponse,
+        MockIngesterService, RetainShardsResponse,
     };
     use quickwit_proto::ingest::{Shard, ShardPKey, ShardState};
     use quickwit_proto::metastore::{
-        EntityKind, FindIndexTemplateMatchesResponse, ListIndexesMetadataRequest,
-        ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse, ListShardsSubresponse,
-        MetastoreError, MockMetastoreService, OpenShardSubresponse, OpenShardsResponse, SourceType,
+        EntityKind, FindIndexTemplateMatchesResponse, IndexTemplateMatch,
+        ListIndexesMetadataRequest, ListIndexesMetadataResponse, ListShardsRequest,
+        ListShardsResponse, ListShardsSubresponse, ListSplitsResponse, MetastoreError,
+        MockMetastoreService, OpenShardSubresponse, OpenShardsResponse, SourceType,
     };
     use quickwit_proto::types::Position;
+    use quickwit_storage::RamStorage;
     use tokio::sync::Mutex;
 
     use super::*;
@@ -1071,6 +2019,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let index_config = IndexConfig::for_test("test-index", "ram:///test-index");
         let create_index_request =
@@ -1114,6 +2064,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let delete_index_request = DeleteIndexRequest {
             index_uid: Some(index_uid),
@@ -1167,6 +2119,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let source_config = SourceConfig::for_test("test-source", SourceParams::void());
         let add_source_request = AddSourceRequest {
@@ -1229,6 +2183,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let enable_source_request = ToggleSourceRequest {
             index_uid: Some(index_uid.clone()),
@@ -1285,6 +2241,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let delete_source_request = DeleteSourceRequest {
             index_uid: Some(index_uid),
@@ -1354,15 +2312,19 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let get_open_shards_request = GetOrCreateOpenShardsRequest {
             subrequests: vec![GetOrCreateOpenShardsSubrequest {
                 subrequest_id: 0,
                 index_id: "test-index".to_string(),
                 source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
             }],
             closed_shards: Vec::new(),
             unavailable_leaders: Vec::new(),
+            router_id: String::new(),
         };
         let get_open_shards_response = control_plane_mailbox
             .ask_for_res(get_open_shards_request)
@@ -1446,6 +2408,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         tokio::time::timeout(
             Duration::from_secs(5),
@@ -1594,6 +2558,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let source_uid = SourceUid {
             index_uid: index_0.index_uid.clone(),
@@ -1727,6 +2693,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let control_plane_debug_info = control_plane_mailbox.ask(GetDebugInfo).await.unwrap();
         let shard = &control_plane_debug_info["shard_table"][0]["shards"][0];
@@ -1804,6 +2772,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         let source_uid = SourceUid {
             index_uid: index_0.index_uid.clone(),
@@ -1886,7 +2856,7 @@ mod tests {
                     request.retain_shards_for_sources[0].shard_ids,
                     [ShardId::from(15)]
                 );
-                Ok(RetainShardsResponse {})
+                Ok(RetainShardsResponse::default())
             });
 
         let index_uid_clone = index_0.index_uid.clone();
@@ -1906,7 +2876,7 @@ mod tests {
                 assert_eq!(request.retain_shards_for_sources.len(), 1);
                 let retain_shards_for_source = request.retain_shards_for_sources.pop().unwrap();
                 assert!(&retain_shards_for_source.shard_ids.is_empty());
-                Ok(RetainShardsResponse {})
+                Ok(RetainShardsResponse::default())
             });
         let ingester = IngesterServiceClient::from_mock(mock_ingester);
         ingester_pool.insert("node1".into(), ingester);
@@ -1921,6 +2891,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         // This update should not trigger anything in the control plane.
         control_plane_mailbox
@@ -1952,7 +2924,7 @@ mod tests {
                     request.retain_shards_for_sources[0].shard_ids,
                     [ShardId::from(15)]
                 );
-                Ok(RetainShardsResponse {})
+                Ok(RetainShardsResponse::default())
             });
         let ingester = IngesterServiceClient::from_mock(mock_ingester);
         ingester_pool.insert("node1".into(), ingester);
@@ -2015,6 +2987,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
         // This update should not trigger anything in the control plane.
         control_plane_mailbox
@@ -2098,6 +3072,8 @@ mod tests {
             indexer_pool,
             ingester_pool,
             MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
         );
 
         let response = control_plane_mailbox
@@ -2106,9 +3082,11 @@ mod tests {
                     subrequest_id: 0,
                     index_id: "test-index-foo".to_string(),
                     source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    known_shard_table_version: None,
                 }],
                 closed_shards: Vec::new(),
                 unavailable_leaders: Vec::new(),
+                router_id: String::new(),
             })
             .await
             .unwrap()
@@ -2127,6 +3105,352 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_index_rollover() {
+        let universe = Universe::default();
+
+        let mut cluster_config = ClusterConfig::for_test();
+        cluster_config.auto_create_indexes = true;
+
+        let node_id = NodeId::from("test-node");
+        let cluster_change_stream_factory = ClusterChangeStreamFactoryForTest::default();
+        let indexer_pool = IndexerPool::default();
+        let ingester_pool = IngesterPool::default();
+
+        let mut mock_metastore = MockMetastoreService::new();
+
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(|_| Ok(ListIndexesMetadataResponse::for_test(Vec::new())));
+
+        mock_metastore
+            .expect_find_index_template_matches()
+            .return_once(|request| {
+                assert_eq!(request.index_ids, ["test-logs"]);
+
+                let mut index_template =
+                    IndexTemplate::for_test("test-template", &["test-logs*"], 100);
+                index_template.rollover_policy_opt = Some(RolloverPolicy {
+                    max_age: None,
+                    max_size: Some(ByteSize::mb(1)),
+                });
+                let index_template_json = serde_json::to_string(&index_template).unwrap();
+
+                Ok(FindIndexTemplateMatchesResponse {
+                    matches: vec![IndexTemplateMatch {
+                        template_id: "test-template".to_string(),
+                        index_id: "test-logs".to_string(),
+                        index_template_json,
+                    }],
+                })
+            });
+
+        let create_index_counter = Arc::new(AtomicUsize::new(0));
+        let create_index_counter_clone = create_index_counter.clone();
+
+        mock_metastore
+            .expect_create_index()
+            .times(2)
+            .returning(move |request| {
+                let generation = create_index_counter_clone.fetch_add(1, Ordering::Relaxed) + 1;
+                let expected_index_id = format!("test-logs-00000{generation}");
+
+                let index_config = request.deserialize_index_config().unwrap();
+                assert_eq!(index_config.index_id, expected_index_id);
+                assert_eq!(
+                    index_config.index_uri,
+                    format!("ram:///indexes/{expected_index_id}").as_str()
+                );
+                let source_configs = request.deserialize_source_configs().unwrap();
+
+                let index_uid = IndexUid::from_parts(&expected_index_id, 0);
+                let mut index_metadata = IndexMetadata::new_with_index_uid(index_uid, index_config);
+
+                for source_config in source_configs {
+                    index_metadata.add_source(source_config).unwrap();
+                }
+                let index_metadata_json = serde_json::to_string(&index_metadata).unwrap();
+
+                Ok(CreateIndexResponse {
+                    index_uid: index_metadata.index_uid.into(),
+                    index_metadata_json,
+                })
+            });
+
+        mock_metastore.expect_list_splits().return_once(|request| {
+            let list_splits_query = request.deserialize_list_splits_query().unwrap();
+            assert_eq!(
+                list_splits_query.index_uids[0],
+                IndexUid::from_parts("test-logs-000001", 0)
+            );
+            assert_eq!(list_splits_query.split_states, [SplitState::Published]);
+
+            let splits = vec![Split {
+                split_metadata: SplitMetadata {
+                    split_id: "test-split".to_string(),
+                    footer_offsets: 0..2_000_000,
+                    ..Default::default()
+                },
+                split_state: SplitState::Published,
+                update_timestamp: 0,
+                publish_timestamp: None,
+            }];
+            let response = ListSplitsResponse::try_from_splits(splits).unwrap();
+            // The listing stops as soon as the size of the index reaches the rollover threshold,
+            // so the next page is never fetched.
+            let error = MetastoreError::Internal {
+                message: "next page should not be fetched".to_string(),
+                cause: String::new(),
+            };
+            Ok(ServiceStream::from(vec![Ok(response), Err(error)]))
+        });
+
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) = ControlPlane::spawn(
+            &universe,
+            cluster_config,
+            node_id,
+            cluster_change_stream_factory,
+            indexer_pool,
+            ingester_pool,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
+        );
+        let get_or_create_open_shards_request = GetOrCreateOpenShardsRequest {
+            subrequests: vec![GetOrCreateOpenShardsSubrequest {
+                subrequest_id: 0,
+                index_id: "test-logs".to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
+            }],
+            closed_shards: Vec::new(),
+            unavailable_leaders: Vec::new(),
+            router_id: String::new(),
+        };
+        let response = control_plane_mailbox
+            .ask(get_or_create_open_shards_request.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.failures.len(), 1);
+        assert_eq!(response.failures[0].index_id, "test-logs-000001");
+
+        control_plane_mailbox.ask(RolloverCheck).await.unwrap();
+        assert_eq!(create_index_counter.load(Ordering::Relaxed), 2);
+
+        let control_plane_state = control_plane_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(control_plane_state.num_indexes, 2);
+
+        // The write alias now resolves to the new write index.
+        let response = control_plane_mailbox
+            .ask(get_or_create_open_shards_request)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.failures.len(), 1);
+        assert_eq!(response.failures[0].index_id, "test-logs-000002");
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_reindex_alias_flip() {
+        let universe = Universe::default();
+
+        let cluster_config = ClusterConfig::for_test();
+        let node_id = NodeId::from("test-node");
+        let cluster_change_stream_factory = ClusterChangeStreamFactoryForTest::default();
+        let indexer_pool = IndexerPool::default();
+        let ingester_pool = IngesterPool::default();
+
+        let source_index_metadata = IndexMetadata::for_test("source-logs", "ram:///source-logs");
+        let source_index_uid = source_index_metadata.index_uid.clone();
+
+        let mut dest_index_metadata = IndexMetadata::for_test("dest-logs", "ram:///dest-logs");
+        let dest_index_uid = dest_index_metadata.index_uid.clone();
+        dest_index_metadata
+            .add_source(SourceConfig::ingest_v2())
+            .unwrap();
+        let reindex_source_config = SourceConfig {
+            source_id: "reindex-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Reindex(ReindexSourceParams {
+                source_index_id: "source-logs".to_string(),
+                max_num_docs_per_sec: None,
+                alias: Some("logs".to_string()),
+            }),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        dest_index_metadata
+            .add_source(reindex_source_config)
+            .unwrap();
+
+        let mut mock_metastore = MockMetastoreService::new();
+
+        let indexes_metadata = vec![source_index_metadata.clone(), dest_index_metadata.clone()];
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(|_| Ok(ListIndexesMetadataResponse::for_test(indexes_metadata)));
+        mock_metastore
+            .expect_list_shards()
+            .return_once(|_| Ok(ListShardsResponse::default()));
+
+        let num_reindex_checks = Arc::new(AtomicUsize::new(0));
+        let num_reindex_checks_clone = num_reindex_checks.clone();
+
+        mock_metastore
+            .expect_index_metadata()
+            .returning(move |request| {
+                if request.index_id.as_deref() == Some("source-logs") {
+                    return Ok(IndexMetadataResponse::try_from_index_metadata(
+                        &source_index_metadata,
+                    )
+                    .unwrap());
+                }
+                assert_eq!(request.index_uid, Some(dest_index_uid.clone()));
+                let mut dest_index_metadata = dest_index_metadata.clone();
+
+                // The split of the source index is reindexed before the second reindex check.
+                if num_reindex_checks_clone.fetch_add(1, Ordering::Relaxed) > 0 {
+                    let checkpoint_delta = IndexCheckpointDelta {
+                        source_id: "reindex-source".to_string(),
+                        source_delta: SourceCheckpointDelta::from_partition_delta(
+                            "test-split".into(),
+                            Position::Beginning,
+                            Position::eof(1u64),
+                        )
+                        .unwrap(),
+                    };
+                    dest_index_metadata
+                        .checkpoint
+                        .try_apply_delta(checkpoint_delta)
+                        .unwrap();
+                }
+                Ok(IndexMetadataResponse::try_from_index_metadata(&dest_index_metadata).unwrap())
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |request| {
+                let list_splits_query = request.deserialize_list_splits_query().unwrap();
+                assert_eq!(list_splits_query.index_uids[0], source_index_uid);
+
+                let splits = vec![Split {
+                    split_metadata: SplitMetadata {
+                        split_id: "test-split".to_string(),
+                        num_docs: 1,
+                        ..Default::default()
+                    },
+                    split_state: SplitState::Published,
+                    update_timestamp: 0,
+                    publish_timestamp: None,
+                }];
+                let response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(response)]))
+            });
+        mock_metastore
+            .expect_publish_splits()
+            .times(1)
+            .returning(|request| {
+                assert_eq!(request.index_uid().index_id, "dest-logs");
+                assert!(request.staged_split_ids.is_empty());
+
+                let index_checkpoint_delta =
+                    request.deserialize_index_checkpoint().unwrap().unwrap();
+                assert_eq!(index_checkpoint_delta.source_id, "reindex-source");
+
+                let mut checkpoint = SourceCheckpoint::default();
+                checkpoint
+                    .try_apply_delta(index_checkpoint_delta.source_delta)
+                    .unwrap();
+                assert!(is_reindex_alias_flipped(&checkpoint));
+                Ok(EmptyResponse {})
+            });
+
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) = ControlPlane::spawn(
+            &universe,
+            cluster_config,
+            node_id,
+            cluster_change_stream_factory,
+            indexer_pool,
+            ingester_pool,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            IngestControllerTimeouts::default(),
+        );
+        let get_or_create_open_shards_request = GetOrCreateOpenShardsRequest {
+            subrequests: vec![GetOrCreateOpenShardsSubrequest {
+                subrequest_id: 0,
+                index_id: "logs".to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
+            }],
+            closed_shards: Vec::new(),
+            unavailable_leaders: Vec::new(),
+            router_id: String::new(),
+        };
+        // The alias resolves to the source index, which has no ingest source.
+        let response = control_plane_mailbox
+            .ask(get_or_create_open_shards_request.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.failures.len(), 1);
+        assert_eq!(
+            response.failures[0].reason(),
+            GetOrCreateOpenShardsFailureReason::SourceNotFound
+        );
+
+        // The source index is still being backfilled.
+        control_plane_mailbox.ask(ReindexCheck).await.unwrap();
+
+        let control_plane_debug_info = control_plane_mailbox.ask(GetDebugInfo).await.unwrap();
+        let reindex_job = &control_plane_debug_info["reindex_jobs"][0];
+        assert_eq!(reindex_job["source_id"], "reindex-source");
+        assert_eq!(reindex_job["progress"]["stage"], "backfill");
+        assert_eq!(reindex_job["progress"]["alias_flipped"], false);
+
+        let response = control_plane_mailbox
+            .ask(get_or_create_open_shards_request.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            response.failures[0].reason(),
+            GetOrCreateOpenShardsFailureReason::SourceNotFound
+        );
+
+        // The source index is backfilled, so the alias is flipped.
+        control_plane_mailbox.ask(ReindexCheck).await.unwrap();
+
+        let control_plane_debug_info = control_plane_mailbox.ask(GetDebugInfo).await.unwrap();
+        let reindex_job = &control_plane_debug_info["reindex_jobs"][0];
+        assert_eq!(reindex_job["progress"]["stage"], "completed");
+        assert_eq!(reindex_job["progress"]["alias_flipped"], true);
+        assert_eq!(reindex_job["progress"]["num_reindexed_docs"], 1);
+
+        // The alias now resolves to the index of the reindex source, whose ingest source has no
+        // shards yet and no ingesters to open them on.
+        let response = control_plane_mailbox
+            .ask(get_or_create_open_shards_request.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.failures.len(), 1);
+        assert_eq!(
+            response.failures[0].reason(),
+            GetOrCreateOpenShardsFailureReason::NoIngestersAvailable
+        );
+
+        // The flip is recorded once and for all.
+        control_plane_mailbox.ask(ReindexCheck).await.unwrap();
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_watch_indexers() {
         let universe = Universe::with_accelerated_time();
@@ -2152,12 +3476,25 @@ mod tests {
         let cluster_change = ClusterChange::Remove(indexer_node.clone());
         cluster_change_stream_tx.send(cluster_change).unwrap();
 
+        let decommissioning_node = ClusterNode::for_test_with_ingester_status(
+            "test-ingester",
+            1717,
+            IngesterStatus::Decommissioning,
+        )
+        .await;
+        let cluster_change = ClusterChange::Update(decommissioning_node);
+        cluster_change_stream_tx.send(cluster_change).unwrap();
+
         let IndexerJoined(joined) = control_plane_inbox.recv_typed_message().await.unwrap();
         assert_eq!(joined.grpc_advertise_addr().port(), 1516);
 
         let IndexerLeft(left) = control_plane_inbox.recv_typed_message().await.unwrap();
         assert_eq!(left.grpc_advertise_addr().port(), 1516);
 
+        let IngesterDecommissioning(node_id) =
+            control_plane_inbox.recv_typed_message().await.unwrap();
+        assert_eq!(node_id, "test-ingester");
+
         universe.assert_quit().await;
     }
 
@@ -2186,6 +3523,8 @@ mod tests {
                 indexer_pool.clone(),
                 ingester_pool,
                 metastore,
+                None,
+                IngestControllerTimeouts::default(),
                 disable_control_loop,
             );
         let cluster_change_stream_tx = cluster_change_stream_factory.change_stream_tx();
@@ -2222,6 +3561,120 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_control_plane_decommission_ingester_requires_shard_move_feature() {
+        let universe = Universe::with_accelerated_time();
+
+        let cluster_config = ClusterConfig::for_test();
+        let node_id = NodeId::from("test-control-plane");
+        let cluster_change_stream_factory = ClusterChangeStreamFactoryForTest::default();
+
+        let indexer_pool = IndexerPool::default();
+        let ingester_pool = IngesterPool::default();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(|_| Ok(ListIndexesMetadataResponse::for_test(Vec::new())));
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let disable_control_loop = true;
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) =
+            ControlPlane::spawn_inner(
+                &universe,
+                cluster_config,
+                node_id,
+                cluster_change_stream_factory.clone(),
+                indexer_pool,
+                ingester_pool,
+                metastore,
+                None,
+                IngestControllerTimeouts::default(),
+                disable_control_loop,
+            );
+        let decommission_request = DecommissionIngesterRequest {
+            node_id: "test-ingester".to_string(),
+        };
+        let control_plane_error = control_plane_mailbox
+            .ask_for_res(decommission_request.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            control_plane_error,
+            AskError::ErrorReply(ControlPlaneError::Unavailable(_))
+        ));
+
+        let cluster_change_stream_tx = cluster_change_stream_factory.change_stream_tx();
+        let ingester_node =
+            ClusterNode::for_test("test-ingester", 1515, false, &["indexer"], &[]).await;
+        cluster_change_stream_tx
+            .send(ClusterChange::Add(ingester_node))
+            .unwrap();
+
+        universe.sleep(Duration::from_secs(1)).await;
+
+        let decommission_response = control_plane_mailbox
+            .ask_for_res(decommission_request)
+            .await
+            .unwrap();
+        assert!(decommission_response.is_decommissioned);
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_control_plane_force_close_shards_requires_opt_in() {
+        for enable_force_close_shards in [false, true] {
+            let universe = Universe::with_accelerated_time();
+
+            let mut cluster_config = ClusterConfig::for_test();
+            cluster_config.enable_force_close_shards = enable_force_close_shards;
+            let node_id = NodeId::from("test-control-plane");
+            let indexer_pool = IndexerPool::default();
+            let ingester_pool = IngesterPool::default();
+            let mut mock_metastore = MockMetastoreService::new();
+            mock_metastore
+                .expect_list_indexes_metadata()
+                .return_once(|_| Ok(ListIndexesMetadataResponse::for_test(Vec::new())));
+            let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+            let disable_control_loop = true;
+            let (control_plane_mailbox, _control_plane_handle, _readiness_rx) =
+                ControlPlane::spawn_inner(
+                    &universe,
+                    cluster_config,
+                    node_id,
+                    ClusterChangeStreamFactoryForTest::default(),
+                    indexer_pool,
+                    ingester_pool,
+                    metastore,
+                    None,
+                    IngestControllerTimeouts::default(),
+                    disable_control_loop,
+                );
+            let force_close_shards_request = ForceCloseShardsRequest {
+                shard_pkeys: Vec::new(),
+                reason: "stuck shards".to_string(),
+            };
+            let control_plane_error = control_plane_mailbox
+                .ask_for_res(force_close_shards_request)
+                .await
+                .unwrap_err();
+
+            // Once enabled, the request reaches the ingest controller, which rejects it because it
+            // does not list any shard.
+            if enable_force_close_shards {
+                assert!(matches!(
+                    control_plane_error,
+                    AskError::ErrorReply(ControlPlaneError::InvalidArgument(_))
+                ));
+            } else {
+                assert!(matches!(
+                    control_plane_error,
+                    AskError::ErrorReply(ControlPlaneError::Unavailable(_))
+                ));
+            }
+            universe.assert_quit().await;
+        }
+    }
+
     #[tokio::test]
     async fn test_control_plane_handles_rebalance_shards_callback() {
         let universe = Universe::with_accelerated_time();
@@ -2236,7 +3689,7 @@ mod tests {
         let mut mock_ingester = MockIngesterService::new();
         mock_ingester
             .expect_retain_shards()
-            .return_once(|_| Ok(RetainShardsResponse {}));
+            .return_once(|_| Ok(RetainShardsResponse::default()));
         mock_ingester.expect_init_shards().return_once(|request| {
             let shard = request.subrequests[0].shard().clone();
             let response = InitShardsResponse {
@@ -2300,6 +3753,8 @@ mod tests {
             indexer_pool.clone(),
             ingester_pool,
             metastore,
+            None,
+            IngestControllerTimeouts::default(),
         );
         let index_config = IndexConfig::for_test("test-index", "ram:///test-index");
         let mut source_config = SourceConfig::ingest_v2();
@@ -2319,9 +3774,11 @@ mod tests {
                 subrequest_id: 0,
                 index_id: "test-index".to_string(),
                 source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
             }],
             closed_shards: Vec::new(),
             unavailable_leaders: Vec::new(),
+            router_id: String::new(),
         };
         control_plane_mailbox
             .ask(get_or_create_open_shards_request)
@@ -2338,7 +3795,7 @@ mod tests {
             ShardPKey {
                 index_uid: Some(IndexUid::for_test("test-index", 0u128)),
                 source_id: INGEST_V2_SOURCE_ID.to_string(),
-                shard_id: Some(ShardId::from(1u64)),
+                shard_id: Some(ShardId::from(1)),
             },
         ];
         let rebalance_lock = Arc::new(Mutex::new(()));
@@ -2387,7 +3844,7 @@ mod tests {
         let mut mock_ingester = MockIngesterService::new();
         mock_ingester
             .expect_retain_shards()
-            .return_once(|_| Ok(RetainShardsResponse {}));
+            .return_once(|_| Ok(RetainShardsResponse::default()));
         mock_ingester.expect_init_shards().return_once(|request| {
             let shard = request.subrequests[0].shard().clone();
             let response = InitShardsResponse {
@@ -2451,6 +3908,8 @@ mod tests {
             indexer_pool.clone(),
             ingester_pool,
             metastore,
+            None,
+            IngestControllerTimeouts::default(),
         );
         let index_config = IndexConfig::for_test("test-index", "ram:///test-index");
         let mut source_config = SourceConfig::ingest_v2();
@@ -2470,9 +3929,11 @@ mod tests {
                 subrequest_id: 0,
                 index_id: "test-index".to_string(),
                 source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
             }],
             closed_shards: Vec::new(),
             unavailable_leaders: Vec::new(),
+            router_id: String::new(),
         };
         control_plane_mailbox
             .ask(get_or_create_open_shards_request)
@@ -2505,4 +3966,134 @@ mod tests {
 
         universe.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_control_plane_restore_model_from_snapshot() {
+        let universe = Universe::default();
+        let storage = Arc::new(RamStorage::default());
+        let model_snapshot_store = ModelSnapshotStore::new(storage, Duration::from_secs(60));
+
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata
+            .add_source(SourceConfig::ingest_v2())
+            .unwrap();
+        let index_uid = index_metadata.index_uid.clone();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        // The indexes and shards are listed by the first control plane to load its model, and by
+        // the second one to reconcile the snapshot.
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(2)
+            .returning(move |_list_indexes_request| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        let mut list_shards_seq = Sequence::new();
+        let index_uid_clone = index_uid.clone();
+        mock_metastore
+            .expect_list_shards()
+            .times(1)
+            .in_sequence(&mut list_shards_seq)
+            .return_once(move |_list_shards_request| {
+                let shard = Shard {
+                    index_uid: Some(index_uid_clone.clone()),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    shard_state: ShardState::Open as i32,
+                    leader_id: "test-ingester".to_string(),
+                    ..Default::default()
+                };
+                let list_shards_response = ListShardsResponse {
+                    subresponses: vec![ListShardsSubresponse {
+                        index_uid: Some(index_uid_clone),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shards: vec![shard],
+                    }],
+                };
+                Ok(list_shards_response)
+            });
+        // The shard was closed while no control plane was running.
+        mock_metastore
+            .expect_list_shards()
+            .times(1)
+            .in_sequence(&mut list_shards_seq)
+            .return_once(|_list_shards_request| {
+                let shard = Shard {
+                    index_uid: Some(index_uid.clone()),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    shard_state: ShardState::Closed as i32,
+                    leader_id: "test-ingester".to_string(),
+                    ..Default::default()
+                };
+                let list_shards_response = ListShardsResponse {
+                    subresponses: vec![ListShardsSubresponse {
+                        index_uid: Some(index_uid),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shards: vec![shard],
+                    }],
+                };
+                Ok(list_shards_response)
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let (_control_plane_mailbox, control_plane_handle, mut readiness_rx) = ControlPlane::spawn(
+            &universe,
+            ClusterConfig::for_test(),
+            NodeId::from("test-node"),
+            ClusterChangeStreamFactoryForTest::default(),
+            IndexerPool::default(),
+            IngesterPool::default(),
+            metastore.clone(),
+            Some(model_snapshot_store.clone()),
+            IngestControllerTimeouts::default(),
+        );
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            readiness_rx.wait_for(|readiness| *readiness),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // Quitting the control plane saves the snapshot of its model.
+        control_plane_handle.quit().await;
+
+        let (control_plane_mailbox, _control_plane_handle, mut readiness_rx) = ControlPlane::spawn(
+            &universe,
+            ClusterConfig::for_test(),
+            NodeId::from("test-node"),
+            ClusterChangeStreamFactoryForTest::default(),
+            IndexerPool::default(),
+            IngesterPool::default(),
+            metastore,
+            Some(model_snapshot_store),
+            IngestControllerTimeouts::default(),
+        );
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            readiness_rx.wait_for(|readiness| *readiness),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let list_shards_request = quickwit_proto::control_plane::ListShardsRequest::default();
+        let list_shards_response = control_plane_mailbox
+            .ask_for_res(list_shards_request)
+            .await
+            .unwrap();
+        assert_eq!(list_shards_response.num_matching_shards, 1);
+
+        // The snapshot is reconciled with the metastore.
+        let shard = list_shards_response.shards[0].shard.as_ref().unwrap();
+        assert_eq!(shard.shard_state(), ShardState::Closed);
+
+        let observation = control_plane_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(observation.num_indexes, 1);
+
+        universe.assert_quit().await;
+    }
 }
@@ -28,11 +28,12 @@ use std::time::{Duration, Instant};
 
 use fnv::{FnvHashMap, FnvHashSet};
 use itertools::Itertools;
+use quickwit_config::SourceParams;
 use quickwit_proto::indexing::{
     ApplyIndexingPlanRequest, CpuCapacity, IndexingService, IndexingTask, PIPELINE_FULL_CAPACITY,
 };
 use quickwit_proto::metastore::SourceType;
-use quickwit_proto::types::{NodeId, ShardId};
+use quickwit_proto::types::{IndexUid, NodeId, PipelineUid, ShardId};
 use scheduling::{SourceToSchedule, SourceToScheduleType};
 use serde::Serialize;
 use tracing::{debug, info, warn};
@@ -124,9 +125,15 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
     let mut sources = Vec::new();
 
     for (source_uid, source_config) in model.source_configs() {
-        if !source_config.enabled {
+        if !source_config.enabled || model.is_source_paused(&source_uid) {
             continue;
         }
+        // Lazy Parquet sources are materialized by the searchers at query time.
+        if let SourceParams::Parquet(parquet_params) = &source_config.source_params {
+            if parquet_params.lazy {
+                continue;
+            }
+        }
         match source_config.source_type() {
             SourceType::Cli
             | SourceType::File
@@ -169,7 +176,11 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
             | SourceType::Kinesis
             | SourceType::PubSub
             | SourceType::Nats
-            | SourceType::Pulsar => {
+            | SourceType::Parquet
+            | SourceType::Pulsar
+            | SourceType::Reindex
+            | SourceType::Sqs
+            | SourceType::Syslog => {
                 sources.push(SourceToSchedule {
                     source_uid,
                     source_type: SourceToScheduleType::NonSharded {
@@ -256,6 +267,32 @@ impl IndexingScheduler {
         self.state.num_schedule_indexing_plan += 1;
     }
 
+    /// Restarts the indexing pipelines of an index so that they pick up its latest index config.
+    ///
+    /// The pipelines are assigned new pipeline UIDs in the last applied plan, which is then
+    /// reapplied: indexers shut down the pipelines they no longer recognize and spawn the new ones
+    /// with freshly fetched index metadata. Returns the number of restarted pipelines.
+    pub(crate) fn restart_index_pipelines(&mut self, index_uid: &IndexUid) -> usize {
+        let Some(mut physical_plan) = self.state.last_applied_physical_plan.clone() else {
+            return 0;
+        };
+        let mut num_restarted_pipelines = 0;
+
+        for indexing_tasks in physical_plan.indexing_tasks_per_indexer_mut().values_mut() {
+            for indexing_task in indexing_tasks.iter_mut() {
+                if indexing_task.index_uid() == index_uid {
+                    indexing_task.pipeline_uid = Some(PipelineUid::new());
+                    num_restarted_pipelines += 1;
+                }
+            }
+        }
+        if num_restarted_pipelines > 0 {
+            let indexers: Vec<IndexerNodeInfo> = self.get_indexers_from_indexer_pool();
+            self.apply_physical_indexing_plan(&indexers, physical_plan, None);
+        }
+        num_restarted_pipelines
+    }
+
     /// Checks if the last applied plan corresponds to the running indexing tasks present in the
     /// chitchat cluster state. If true, do nothing.
     /// - If node IDs differ, schedule a new indexing plan.
@@ -538,9 +575,15 @@ mod tests {
     use std::str::FromStr;
 
     use proptest::{prop_compose, proptest};
-    use quickwit_config::{IndexConfig, KafkaSourceParams, SourceConfig, SourceParams};
+    use quickwit_common::uri::Uri;
+    use quickwit_config::{
+        IndexConfig, KafkaSourceParams, ParquetSourceParams, SourceConfig, SourceParams,
+    };
     use quickwit_metastore::IndexMetadata;
-    use quickwit_proto::types::{IndexUid, PipelineUid, SourceUid};
+    use quickwit_proto::indexing::{
+        ApplyIndexingPlanResponse, IndexingServiceClient, MockIndexingService,
+    };
+    use quickwit_proto::types::SourceUid;
 
     use super::*;
     use crate::model::ShardLocations;
@@ -717,6 +760,8 @@ mod tests {
                     enabled: false,
                     source_params: SourceParams::Kafka(kafka_source_params.clone()),
                     transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
                     input_format: Default::default(),
                 },
             )
@@ -730,6 +775,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::Kafka(kafka_source_params.clone()),
                     transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
                     input_format: Default::default(),
                 },
             )
@@ -744,6 +791,8 @@ mod tests {
                     // ingest v1
                     source_params: SourceParams::IngestApi,
                     transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
                     input_format: Default::default(),
                 },
             )
@@ -758,6 +807,8 @@ mod tests {
                     // ingest v2
                     source_params: SourceParams::Ingest,
                     transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
                     input_format: Default::default(),
                 },
             )
@@ -773,6 +824,8 @@ mod tests {
                     // ingest v2
                     source_params: SourceParams::Ingest,
                     transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
                     input_format: Default::default(),
                 },
             )
@@ -787,6 +840,28 @@ mod tests {
                     // ingest v1
                     source_params: SourceParams::IngestCli,
                     transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
+                    input_format: Default::default(),
+                },
+            )
+            .unwrap();
+        model
+            .add_source(
+                &index_uid,
+                SourceConfig {
+                    source_id: "parquet_lazy".to_string(),
+                    num_pipelines: NonZeroUsize::new(1).unwrap(),
+                    enabled: true,
+                    // lazy parquet sources are materialized at query time
+                    source_params: SourceParams::Parquet(ParquetSourceParams {
+                        filepaths: vec![Uri::for_test("s3://my-bucket/part-0.parquet")],
+                        columns: Vec::new(),
+                        lazy: true,
+                    }),
+                    transform_config: None,
+                    json_schema_config: None,
+                    wasm_plugin_config: None,
                     input_format: Default::default(),
                 },
             )
@@ -801,6 +876,18 @@ mod tests {
         model.insert_shards(&index_uid, &"ingest_v2".to_string(), vec![shard]);
         let shards: Vec<SourceToSchedule> = get_sources_to_schedule(&model);
         assert_eq!(shards.len(), 3);
+
+        // Paused sources are not scheduled.
+        let paused_source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: "source_enabled".to_string(),
+        };
+        model.pause_source(&paused_source_uid);
+        let shards: Vec<SourceToSchedule> = get_sources_to_schedule(&model);
+        assert_eq!(shards.len(), 2);
+        assert!(shards
+            .iter()
+            .all(|shard| shard.source_uid != paused_source_uid));
     }
 
     #[test]
@@ -842,6 +929,73 @@ mod tests {
         assert_eq!(indexer_2_tasks.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_restart_index_pipelines() {
+        let indexer_pool = IndexerPool::default();
+        let (apply_plan_tx, mut apply_plan_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut mock_indexer = MockIndexingService::new();
+        mock_indexer
+            .expect_apply_indexing_plan()
+            .returning(move |apply_plan_request| {
+                apply_plan_tx.send(apply_plan_request).unwrap();
+                Ok(ApplyIndexingPlanResponse {})
+            });
+        let indexer_node_info = IndexerNodeInfo {
+            node_id: NodeId::from("indexer-1"),
+            generation_id: 0,
+            client: IndexingServiceClient::from_mock(mock_indexer),
+            indexing_tasks: Vec::new(),
+            indexing_capacity: mcpu(4_000),
+        };
+        indexer_pool.insert(indexer_node_info.node_id.clone(), indexer_node_info);
+
+        let mut indexing_scheduler = IndexingScheduler::new(
+            "test-cluster".to_string(),
+            NodeId::from("test-node"),
+            indexer_pool,
+        );
+        let index_uid_0 = IndexUid::for_test("test-index-0", 0);
+        let index_uid_1 = IndexUid::for_test("test-index-1", 0);
+        assert_eq!(indexing_scheduler.restart_index_pipelines(&index_uid_0), 0);
+
+        let mut physical_plan = PhysicalIndexingPlan::with_indexer_ids(&["indexer-1".to_string()]);
+        for (index_uid, pipeline_uid) in [
+            (&index_uid_0, 0u128),
+            (&index_uid_0, 1u128),
+            (&index_uid_1, 2u128),
+        ] {
+            let indexing_task = IndexingTask {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                pipeline_uid: Some(PipelineUid::for_test(pipeline_uid)),
+                shard_ids: Vec::new(),
+            };
+            physical_plan.add_indexing_task("indexer-1", indexing_task);
+        }
+        indexing_scheduler.state.last_applied_physical_plan = Some(physical_plan);
+
+        assert_eq!(indexing_scheduler.restart_index_pipelines(&index_uid_0), 2);
+
+        let apply_plan_request = apply_plan_rx.recv().await.unwrap();
+        let indexing_tasks = apply_plan_request.indexing_tasks;
+        assert_eq!(indexing_tasks.len(), 3);
+
+        for indexing_task in &indexing_tasks {
+            let pipeline_uid = indexing_task.pipeline_uid();
+
+            if indexing_task.index_uid() == &index_uid_0 {
+                assert_ne!(pipeline_uid, PipelineUid::for_test(0u128));
+                assert_ne!(pipeline_uid, PipelineUid::for_test(1u128));
+            } else {
+                assert_eq!(pipeline_uid, PipelineUid::for_test(2u128));
+            }
+        }
+        assert_eq!(
+            indexing_scheduler.state.num_applied_physical_indexing_plan,
+            1
+        );
+    }
+
     proptest! {
         #[test]
         fn test_building_indexing_tasks_and_physical_plan(num_indexers in 1usize..50usize, index_id_sources in proptest::collection::vec(gen_kafka_source(), 1..20)) {
@@ -895,6 +1049,8 @@ mod tests {
               enabled: true,
               source_params: kafka_source_params_for_test(),
               transform_config: None,
+              json_schema_config: None,
+              wasm_plugin_config: None,
               input_format: SourceInputFormat::Json,
           })
       }
@@ -0,0 +1,299 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use fnv::FnvHashSet;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use quickwit_actors::Mailbox;
+use quickwit_ingest::IngesterPool;
+use quickwit_proto::ingest::ingester::{IngesterService, IngesterStatus, PingRequest};
+use quickwit_proto::types::NodeId;
+use tracing::{error, info, warn};
+
+use crate::control_plane::ControlPlane;
+use crate::metrics::CONTROL_PLANE_METRICS;
+
+/// Interval between two rounds of health probes.
+// The mocked ingesters of the unit tests do not expect pings, so we rarely probe them.
+pub(crate) const HEALTH_PROBE_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_secs(10)
+} else {
+    Duration::from_secs(2)
+};
+
+/// Duration after which a probe that has not completed is considered failed.
+const HEALTH_PROBE_TIMEOUT: Duration = if cfg!(any(test, feature = "testsuite")) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(1)
+};
+
+/// Number of consecutive failed probes after which a healthy ingester is considered unhealthy.
+const UNHEALTHY_THRESHOLD: usize = 3;
+
+/// Number of consecutive successful probes after which an unhealthy ingester is considered healthy
+/// again.
+const HEALTHY_THRESHOLD: usize = 2;
+
+/// Outcome of a round of health probes, sent by the health prober task to the control plane.
+#[derive(Debug)]
+pub(crate) struct IngesterProbeResults {
+    pub probe_results: Vec<(NodeId, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct IngesterHealthState {
+    is_unhealthy: bool,
+    num_consecutive_failures: usize,
+    num_consecutive_successes: usize,
+}
+
+/// Tracks the health of the ingesters from the outcome of the probes. The state of an ingester only
+/// flips after several consecutive probes agree (hysteresis) so that a single slow or lost probe
+/// does not trigger a costly reallocation of its shards.
+#[derive(Debug, Default)]
+pub(crate) struct IngesterHealthTracker {
+    health_states: HashMap<NodeId, IngesterHealthState>,
+}
+
+impl IngesterHealthTracker {
+    /// Records the outcome of a round of probes and returns the ingesters that just became
+    /// unhealthy.
+    pub fn record_probe_results(
+        &mut self,
+        probe_results: Vec<(NodeId, bool)>,
+    ) -> FnvHashSet<NodeId> {
+        let mut newly_unhealthy_ingesters = FnvHashSet::default();
+
+        for (ingester_id, is_success) in probe_results {
+            let health_state = self.health_states.entry(ingester_id.clone()).or_default();
+
+            if is_success {
+                health_state.num_consecutive_failures = 0;
+                health_state.num_consecutive_successes += 1;
+
+                if health_state.is_unhealthy
+                    && health_state.num_consecutive_successes >= HEALTHY_THRESHOLD
+                {
+                    info!("ingester `{ingester_id}` is healthy again");
+                    health_state.is_unhealthy = false;
+                }
+            } else {
+                health_state.num_consecutive_successes = 0;
+                health_state.num_consecutive_failures += 1;
+
+                if !health_state.is_unhealthy
+                    && health_state.num_consecutive_failures >= UNHEALTHY_THRESHOLD
+                {
+                    warn!(
+                        "ingester `{ingester_id}` failed {} consecutive health probes: marking it \
+                         as unhealthy",
+                        health_state.num_consecutive_failures
+                    );
+                    health_state.is_unhealthy = true;
+                    newly_unhealthy_ingesters.insert(ingester_id);
+                }
+            }
+        }
+        CONTROL_PLANE_METRICS
+            .unhealthy_ingesters
+            .set(self.num_unhealthy_ingesters() as i64);
+        newly_unhealthy_ingesters
+    }
+
+    pub fn is_unhealthy(&self, ingester_id: &NodeId) -> bool {
+        self.health_states
+            .get(ingester_id)
+            .map(|health_state| health_state.is_unhealthy)
+            .unwrap_or(false)
+    }
+
+    pub fn unhealthy_ingesters(&self) -> impl Iterator<Item = &NodeId> + '_ {
+        self.health_states
+            .iter()
+            .filter(|(_, health_state)| health_state.is_unhealthy)
+            .map(|(ingester_id, _)| ingester_id)
+    }
+
+    fn num_unhealthy_ingesters(&self) -> usize {
+        self.unhealthy_ingesters().count()
+    }
+
+    /// Forgets the ingesters that left the ingester pool.
+    pub fn retain(&mut self, ingester_pool: &IngesterPool) {
+        self.health_states
+            .retain(|ingester_id, _| ingester_pool.contains_key(ingester_id));
+    }
+}
+
+/// Spawns a task that pings all the ingesters of the pool and forwards the outcome of the probes
+/// to the control plane.
+pub(crate) fn spawn_probe_ingesters_task(
+    ingester_pool: IngesterPool,
+    mailbox: Mailbox<ControlPlane>,
+) {
+    let probe_ingesters_fut = async move {
+        let probe_results = probe_ingesters(&ingester_pool).await;
+
+        if probe_results.is_empty() {
+            return;
+        }
+        let probe_results_msg = IngesterProbeResults { probe_results };
+
+        if let Err(error) = mailbox.send_message(probe_results_msg).await {
+            error!(%error, "failed to forward ingester probe results to control plane");
+        }
+    };
+    tokio::spawn(probe_ingesters_fut);
+}
+
+/// Pings all the ingesters of the pool concurrently. An ingester fails the probe if it does not
+/// respond in time, returns an error, or reports itself as failed.
+async fn probe_ingesters(ingester_pool: &IngesterPool) -> Vec<(NodeId, bool)> {
+    let mut probe_futures = FuturesUnordered::new();
+
+    for (ingester_id, mut ingester) in ingester_pool.pairs() {
+        let probe_future = async move {
+            let ping_result =
+                tokio::time::timeout(HEALTH_PROBE_TIMEOUT, ingester.ping(PingRequest {})).await;
+            let is_success = match ping_result {
+                Ok(Ok(ping_response)) => ping_response.status() != IngesterStatus::Failed,
+                Ok(Err(_)) | Err(_) => false,
+            };
+            (ingester_id, is_success)
+        };
+        probe_futures.push(probe_future);
+    }
+    let mut probe_results = Vec::with_capacity(probe_futures.len());
+
+    while let Some(probe_result) = probe_futures.next().await {
+        probe_results.push(probe_result);
+    }
+    probe_results
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::ingest::ingester::{
+        IngesterServiceClient, MockIngesterService, PingResponse,
+    };
+    use quickwit_proto::ingest::IngestV2Error;
+
+    use super::*;
+
+    #[test]
+    fn test_ingester_health_tracker_hysteresis() {
+        let mut health_tracker = IngesterHealthTracker::default();
+        let ingester_id = NodeId::from("test-ingester");
+
+        for _ in 0..UNHEALTHY_THRESHOLD - 1 {
+            let newly_unhealthy_ingesters =
+                health_tracker.record_probe_results(vec![(ingester_id.clone(), false)]);
+            assert!(newly_unhealthy_ingesters.is_empty());
+            assert!(!health_tracker.is_unhealthy(&ingester_id));
+        }
+        // A successful probe resets the count of consecutive failures.
+        health_tracker.record_probe_results(vec![(ingester_id.clone(), true)]);
+
+        for _ in 0..UNHEALTHY_THRESHOLD - 1 {
+            health_tracker.record_probe_results(vec![(ingester_id.clone(), false)]);
+        }
+        assert!(!health_tracker.is_unhealthy(&ingester_id));
+
+        let newly_unhealthy_ingesters =
+            health_tracker.record_probe_results(vec![(ingester_id.clone(), false)]);
+        assert_eq!(newly_unhealthy_ingesters.len(), 1);
+        assert!(newly_unhealthy_ingesters.contains(&ingester_id));
+        assert!(health_tracker.is_unhealthy(&ingester_id));
+
+        // The ingester is only reported once.
+        let newly_unhealthy_ingesters =
+            health_tracker.record_probe_results(vec![(ingester_id.clone(), false)]);
+        assert!(newly_unhealthy_ingesters.is_empty());
+
+        for _ in 0..HEALTHY_THRESHOLD - 1 {
+            health_tracker.record_probe_results(vec![(ingester_id.clone(), true)]);
+            assert!(health_tracker.is_unhealthy(&ingester_id));
+        }
+        health_tracker.record_probe_results(vec![(ingester_id.clone(), true)]);
+        assert!(!health_tracker.is_unhealthy(&ingester_id));
+        assert_eq!(health_tracker.unhealthy_ingesters().count(), 0);
+    }
+
+    #[test]
+    fn test_ingester_health_tracker_retain() {
+        let mut health_tracker = IngesterHealthTracker::default();
+        let ingester_id = NodeId::from("test-ingester");
+
+        for _ in 0..UNHEALTHY_THRESHOLD {
+            health_tracker.record_probe_results(vec![(ingester_id.clone(), false)]);
+        }
+        assert!(health_tracker.is_unhealthy(&ingester_id));
+
+        let ingester_pool = IngesterPool::default();
+        health_tracker.retain(&ingester_pool);
+        assert!(!health_tracker.is_unhealthy(&ingester_id));
+    }
+
+    #[tokio::test]
+    async fn test_probe_ingesters() {
+        let ingester_pool = IngesterPool::default();
+
+        let mut mock_ingester_0 = MockIngesterService::new();
+        mock_ingester_0.expect_ping().once().returning(|_| {
+            Ok(PingResponse {
+                status: IngesterStatus::Ready as i32,
+            })
+        });
+        let ingester_0 = IngesterServiceClient::from_mock(mock_ingester_0);
+        ingester_pool.insert("test-ingester-0".into(), ingester_0);
+
+        let mut mock_ingester_1 = MockIngesterService::new();
+        mock_ingester_1.expect_ping().once().returning(|_| {
+            Ok(PingResponse {
+                status: IngesterStatus::Failed as i32,
+            })
+        });
+        let ingester_1 = IngesterServiceClient::from_mock(mock_ingester_1);
+        ingester_pool.insert("test-ingester-1".into(), ingester_1);
+
+        let mut mock_ingester_2 = MockIngesterService::new();
+        mock_ingester_2
+            .expect_ping()
+            .once()
+            .returning(|_| Err(IngestV2Error::Unavailable("connection refused".to_string())));
+        let ingester_2 = IngesterServiceClient::from_mock(mock_ingester_2);
+        ingester_pool.insert("test-ingester-2".into(), ingester_2);
+
+        let mut probe_results = probe_ingesters(&ingester_pool).await;
+        probe_results.sort();
+
+        assert_eq!(
+            probe_results,
+            vec![
+                (NodeId::from("test-ingester-0"), true),
+                (NodeId::from("test-ingester-1"), false),
+                (NodeId::from("test-ingester-2"), false),
+            ]
+        );
+    }
+}
@@ -17,44 +17,58 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::future::Future;
 use std::iter::zip;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{cmp, fmt};
 
 use fnv::FnvHashSet;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use itertools::Itertools;
-use quickwit_actors::Mailbox;
+use quickwit_actors::{Mailbox, WeakMailbox};
 use quickwit_common::pretty::PrettySample;
+use quickwit_common::shared_consts::AUDIT_LOG_TARGET;
 use quickwit_common::Progress;
+use quickwit_config::{ClusterSettings, ControlPlaneConfig};
 use quickwit_ingest::{IngesterPool, LeaderId, LocalShardsUpdate};
 use quickwit_proto::control_plane::{
-    AdviseResetShardsRequest, AdviseResetShardsResponse, ControlPlaneResult,
-    GetOrCreateOpenShardsFailure, GetOrCreateOpenShardsFailureReason, GetOrCreateOpenShardsRequest,
-    GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSuccess,
+    AdviseResetShardsRequest, AdviseResetShardsResponse, ControlPlaneError, ControlPlaneResult,
+    DecommissionIngesterRequest, DecommissionIngesterResponse, ForceCloseShardsRequest,
+    ForceCloseShardsResponse, GetOrCreateOpenShardsFailure, GetOrCreateOpenShardsFailureReason,
+    GetOrCreateOpenShardsRequest, GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSuccess,
+    IngesterShardDistribution, IngestionPressure, MoveShardRequest, MoveShardResponse,
+    ProjectSourceShardsRequest, ProjectSourceShardsResponse, RebalanceShardsDryRunResponse,
+    ShardCountProjection, ShardEventType, ShardMove,
 };
 use quickwit_proto::ingest::ingester::{
-    CloseShardsRequest, CloseShardsResponse, IngesterService, InitShardFailure,
-    InitShardSubrequest, InitShardsRequest, InitShardsResponse, RetainShardsForSource,
-    RetainShardsRequest,
+    source_shards_digest, CloseShardsRequest, CloseShardsResponse, DecommissionRequest,
+    IngesterService, InitShardFailure, InitShardSubrequest, InitShardSuccess, InitShardsRequest,
+    InitShardsResponse, RetainShardsForSource, RetainShardsRequest,
+};
+use quickwit_proto::ingest::{
+    IngestV2Error, Shard, ShardIdPosition, ShardIdPositions, ShardIds, ShardPKey,
 };
-use quickwit_proto::ingest::{Shard, ShardIdPosition, ShardIdPositions, ShardIds, ShardPKey};
 use quickwit_proto::metastore;
-use quickwit_proto::metastore::{MetastoreService, MetastoreServiceClient};
-use quickwit_proto::types::{IndexUid, NodeId, Position, ShardId, SourceUid};
+use quickwit_proto::metastore::{
+    EntityKind, MetastoreError, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::{IndexUid, NodeId, Position, QueueId, ShardId, SourceUid};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, OwnedMutexGuard};
 use tokio::task::JoinHandle;
 use tracing::{debug, enabled, error, info, warn, Level};
-use ulid::Ulid;
 
 use crate::control_plane::ControlPlane;
+use crate::ingest::health_prober::IngesterHealthTracker;
+use crate::ingest::shard_event_log::ShardEventLog;
+use crate::ingest::shard_id_generator::{ShardIdGenerator, UlidShardIdGenerator};
+use crate::ingest::unavailable_leaders::UnavailableLeaderReports;
 use crate::ingest::wait_handle::WaitHandle;
-use crate::model::{ControlPlaneModel, ScalingMode, ShardEntry, ShardStats};
+use crate::metrics::CONTROL_PLANE_METRICS;
+use crate::model::{ControlPlaneModel, OpenShardsSync, ScalingMode, ShardEntry, ShardStats};
 
 const MAX_SHARD_INGESTION_THROUGHPUT_MIB_PER_SEC: f32 = 5.;
 
@@ -66,6 +80,11 @@ const SCALE_UP_SHARDS_THRESHOLD_MIB_PER_SEC: f32 =
 const SCALE_DOWN_SHARDS_THRESHOLD_MIB_PER_SEC: f32 =
     MAX_SHARD_INGESTION_THROUGHPUT_MIB_PER_SEC * 2. / 10.;
 
+/// Threshold in MiB/s above which the ingestion pressure of a source is reported as high to the
+/// routers, which then start rejecting ingest requests for the source.
+const HIGH_INGESTION_PRESSURE_THRESHOLD_MIB_PER_SEC: f32 =
+    MAX_SHARD_INGESTION_THROUGHPUT_MIB_PER_SEC * 95. / 100.;
+
 const CLOSE_SHARDS_REQUEST_TIMEOUT: Duration = if cfg!(test) {
     Duration::from_millis(50)
 } else {
@@ -74,6 +93,16 @@ const CLOSE_SHARDS_REQUEST_TIMEOUT: Duration = if cfg!(test) {
 
 const INIT_SHARDS_REQUEST_TIMEOUT: Duration = CLOSE_SHARDS_REQUEST_TIMEOUT;
 
+/// Maximum number of attempts at initializing a shard before giving up.
+const MAX_INIT_SHARDS_ATTEMPTS: u32 = 3;
+
+/// Delay before the first init shards retry. The delay doubles after each attempt.
+const INIT_SHARDS_RETRY_BASE_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(10)
+} else {
+    Duration::from_millis(250)
+};
+
 const CLOSE_SHARDS_UPON_REBALANCE_DELAY: Duration = if cfg!(test) {
     Duration::ZERO
 } else {
@@ -82,6 +111,173 @@ const CLOSE_SHARDS_UPON_REBALANCE_DELAY: Duration = if cfg!(test) {
 
 const FIRE_AND_FORGET_TIMEOUT: Duration = Duration::from_secs(3);
 
+/// Timeouts and delays of the requests sent by the ingest controller to the ingesters. The
+/// defaults suit clusters whose nodes are in the same region and can be raised for clusters whose
+/// nodes are separated by a WAN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IngestControllerTimeouts {
+    pub close_shards_request_timeout: Duration,
+    pub init_shards_request_timeout: Duration,
+    pub close_shards_upon_rebalance_delay: Duration,
+}
+
+impl Default for IngestControllerTimeouts {
+    fn default() -> Self {
+        IngestControllerTimeouts {
+            close_shards_request_timeout: CLOSE_SHARDS_REQUEST_TIMEOUT,
+            init_shards_request_timeout: INIT_SHARDS_REQUEST_TIMEOUT,
+            close_shards_upon_rebalance_delay: CLOSE_SHARDS_UPON_REBALANCE_DELAY,
+        }
+    }
+}
+
+impl From<&ControlPlaneConfig> for IngestControllerTimeouts {
+    fn from(control_plane_config: &ControlPlaneConfig) -> Self {
+        IngestControllerTimeouts {
+            close_shards_request_timeout: control_plane_config.close_shards_request_timeout,
+            init_shards_request_timeout: control_plane_config.init_shards_request_timeout,
+            close_shards_upon_rebalance_delay: control_plane_config
+                .close_shards_upon_rebalance_delay,
+        }
+    }
+}
+
+/// Maximum estimated size of an advise reset shards response. Larger responses are truncated and
+/// the ingester resumes with the continuation token, which keeps the responses well under the
+/// gRPC message size limit even after a long outage.
+const MAX_ADVISE_RESET_SHARDS_RESPONSE_NUM_BYTES: usize = 4 * 1024 * 1024;
+
+/// Estimated size of the encoding of a shard ID or a shard position in an advise reset shards
+/// response, excluding the shard ID itself.
+const ADVISE_RESET_SHARD_OVERHEAD_NUM_BYTES: usize = 32;
+
+/// Average ingestion rates per shard, in MiB/s, that drive the scaling of the number of shards of
+/// a source. They default to the constants above and can be overridden by the cluster settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ShardScalingThresholds {
+    pub scale_up_mib_per_sec: f32,
+    pub scale_down_mib_per_sec: f32,
+}
+
+impl Default for ShardScalingThresholds {
+    fn default() -> Self {
+        Self {
+            scale_up_mib_per_sec: SCALE_UP_SHARDS_THRESHOLD_MIB_PER_SEC,
+            scale_down_mib_per_sec: SCALE_DOWN_SHARDS_THRESHOLD_MIB_PER_SEC,
+        }
+    }
+}
+
+impl ShardScalingThresholds {
+    fn from_cluster_settings(cluster_settings: &ClusterSettings) -> Self {
+        let default_thresholds = Self::default();
+        Self {
+            scale_up_mib_per_sec: cluster_settings
+                .shard_scale_up_threshold_mib_per_sec
+                .unwrap_or(default_thresholds.scale_up_mib_per_sec),
+            scale_down_mib_per_sec: cluster_settings
+                .shard_scale_down_threshold_mib_per_sec
+                .unwrap_or(default_thresholds.scale_down_mib_per_sec),
+        }
+    }
+}
+
+/// Returns whether an init shards request that failed with `error` may succeed if retried.
+fn is_retryable_init_shards_error(error: &IngestV2Error) -> bool {
+    matches!(
+        error,
+        IngestV2Error::Timeout(_) | IngestV2Error::TooManyRequests | IngestV2Error::Unavailable(_)
+    )
+}
+
+/// Makes a single attempt at initializing the shards on their leaders. The successes and
+/// definitive failures are appended to `successes` and `failures`, and the shards that can be
+/// retried are returned.
+async fn try_init_shards(
+    ingester_pool: &IngesterPool,
+    init_shards_request_timeout: Duration,
+    shards_to_init: Vec<(InitShardSubrequest, InitShardFailure)>,
+    successes: &mut Vec<InitShardSuccess>,
+    failures: &mut Vec<InitShardFailure>,
+) -> Vec<(InitShardSubrequest, InitShardFailure)> {
+    let mut shards_to_retry = Vec::new();
+
+    let mut per_leader_shards_to_init: HashMap<
+        String,
+        Vec<(InitShardSubrequest, InitShardFailure)>,
+    > = HashMap::default();
+
+    for (init_shard_subrequest, init_shard_failure) in shards_to_init {
+        let leader_id = init_shard_subrequest.shard().leader_id.clone();
+        per_leader_shards_to_init
+            .entry(leader_id)
+            .or_default()
+            .push((init_shard_subrequest, init_shard_failure));
+    }
+    let mut init_shards_futures = FuturesUnordered::new();
+
+    for (leader_id, leader_shards_to_init) in per_leader_shards_to_init {
+        let Some(mut leader) = ingester_pool.get(&leader_id) else {
+            warn!("failed to init shards: ingester `{leader_id}` is unavailable");
+            shards_to_retry.extend(leader_shards_to_init);
+            continue;
+        };
+        let subrequests: Vec<InitShardSubrequest> = leader_shards_to_init
+            .iter()
+            .map(|(init_shard_subrequest, _)| init_shard_subrequest.clone())
+            .collect();
+        let init_shards_request = InitShardsRequest { subrequests };
+        let init_shards_future = async move {
+            let init_shards_result = tokio::time::timeout(
+                init_shards_request_timeout,
+                leader.init_shards(init_shards_request),
+            )
+            .await;
+            (leader_id, init_shards_result, leader_shards_to_init)
+        };
+        init_shards_futures.push(init_shards_future);
+    }
+    while let Some((leader_id, init_shards_result, leader_shards_to_init)) =
+        init_shards_futures.next().await
+    {
+        match init_shards_result {
+            Ok(Ok(init_shards_response)) => {
+                successes.extend(init_shards_response.successes);
+
+                for init_shard_failure in init_shards_response.failures {
+                    // Report the failure of the shard originally passed to `init_shards`, which
+                    // may have been reopened on this leader under another ID.
+                    let original_init_shard_failure = leader_shards_to_init
+                        .iter()
+                        .find(|(init_shard_subrequest, _)| {
+                            init_shard_subrequest.subrequest_id == init_shard_failure.subrequest_id
+                        })
+                        .map(|(_, original_init_shard_failure)| original_init_shard_failure.clone())
+                        .unwrap_or(init_shard_failure);
+                    failures.push(original_init_shard_failure);
+                }
+            }
+            Ok(Err(error)) if is_retryable_init_shards_error(&error) => {
+                warn!(%error, "failed to init shards on `{leader_id}`");
+                shards_to_retry.extend(leader_shards_to_init);
+            }
+            Ok(Err(error)) => {
+                error!(%error, "failed to init shards on `{leader_id}`");
+                failures.extend(
+                    leader_shards_to_init
+                        .into_iter()
+                        .map(|(_, init_shard_failure)| init_shard_failure),
+                );
+            }
+            Err(_elapsed) => {
+                warn!("failed to init shards on `{leader_id}`: request timed out");
+                shards_to_retry.extend(leader_shards_to_init);
+            }
+        }
+    }
+    shards_to_retry
+}
+
 /// Spawns a new task to execute the given future,
 /// and stops polling it/drops it after a timeout.
 ///
@@ -100,6 +296,94 @@ fn fire_and_forget(
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct IngestControllerStats {
     pub num_rebalance_shards_ops: usize,
+    pub num_opened_shards: usize,
+    pub num_closed_shards: usize,
+    pub num_failed_init_shards: usize,
+    pub num_scale_up_shards_ops: usize,
+    pub num_scale_down_shards_ops: usize,
+    pub num_suppressed_scale_shards_ops: usize,
+    pub num_rejected_shard_allocations: usize,
+    pub num_ingestion_quota_rejections: usize,
+}
+
+// The stats are reset whenever the control plane restarts, whereas the Prometheus counters are
+// not. Recording both through the methods below keeps them in sync.
+impl IngestControllerStats {
+    fn record_rebalance_shards_op(&mut self) {
+        self.num_rebalance_shards_ops += 1;
+        CONTROL_PLANE_METRICS.rebalance_shards_ops_total.inc();
+    }
+
+    fn record_opened_shards(&mut self, num_opened_shards: usize) {
+        self.num_opened_shards += num_opened_shards;
+        CONTROL_PLANE_METRICS
+            .opened_shards_total
+            .inc_by(num_opened_shards as u64);
+    }
+
+    fn record_init_shards(&mut self, init_shards_response: &InitShardsResponse) {
+        let num_opened_shards = init_shards_response.successes.len();
+        let num_failed_init_shards = init_shards_response.failures.len();
+
+        self.num_opened_shards += num_opened_shards;
+        self.num_failed_init_shards += num_failed_init_shards;
+
+        CONTROL_PLANE_METRICS
+            .opened_shards_total
+            .inc_by(num_opened_shards as u64);
+        CONTROL_PLANE_METRICS
+            .failed_init_shards_total
+            .inc_by(num_failed_init_shards as u64);
+    }
+
+    pub(crate) fn record_closed_shards(&mut self, num_closed_shards: usize) {
+        self.num_closed_shards += num_closed_shards;
+        CONTROL_PLANE_METRICS
+            .closed_shards_total
+            .inc_by(num_closed_shards as u64);
+    }
+
+    fn record_scale_shards_op(&mut self, scaling_mode: ScalingMode) {
+        match scaling_mode {
+            ScalingMode::Up => {
+                self.num_scale_up_shards_ops += 1;
+                CONTROL_PLANE_METRICS.scale_up_shards_ops_total.inc();
+            }
+            ScalingMode::Down => {
+                self.num_scale_down_shards_ops += 1;
+                CONTROL_PLANE_METRICS.scale_down_shards_ops_total.inc();
+            }
+        }
+    }
+
+    fn record_suppressed_scale_shards_op(&mut self, scaling_mode: ScalingMode) {
+        self.num_suppressed_scale_shards_ops += 1;
+
+        match scaling_mode {
+            ScalingMode::Up => {
+                CONTROL_PLANE_METRICS
+                    .suppressed_scale_up_shards_ops_total
+                    .inc();
+            }
+            ScalingMode::Down => {
+                CONTROL_PLANE_METRICS
+                    .suppressed_scale_down_shards_ops_total
+                    .inc();
+            }
+        }
+    }
+
+    fn record_ingestion_quota_rejection(&mut self) {
+        self.num_ingestion_quota_rejections += 1;
+        CONTROL_PLANE_METRICS.ingestion_quota_rejections_total.inc();
+    }
+
+    fn record_rejected_shard_allocations(&mut self, num_shards: usize) {
+        self.num_rejected_shard_allocations += num_shards;
+        CONTROL_PLANE_METRICS
+            .rejected_shard_allocations_total
+            .inc_by(num_shards as u64);
+    }
 }
 
 pub struct IngestController {
@@ -108,15 +392,50 @@ pub struct IngestController {
     replication_factor: usize,
     // This lock ensures that only one rebalance operation is performed at a time.
     rebalance_lock: Arc<Mutex<()>>,
+    // Ingesters that are being drained. No new shards are allocated to them and their open shards
+    // are moved to other ingesters.
+    decommissioning_ingesters: BTreeSet<NodeId>,
+    // Decommissioning ingesters to which the decommission RPC is in flight or was acknowledged.
+    // An ingester is removed from this set when the RPC fails, so that it is sent again during the
+    // next iteration of the control loop.
+    decommission_requests: Arc<std::sync::Mutex<FnvHashSet<NodeId>>>,
+    // Ingesters that completed their decommissioning and left the ingester pool. Kept to answer
+    // the requests polling the progress of a decommissioning.
+    decommissioned_ingesters: BTreeSet<NodeId>,
+    // Ingesters that failed several consecutive health probes. No new shards are allocated to
+    // them and their open shards are marked as unavailable.
+    health_tracker: IngesterHealthTracker,
+    // Leaders reported as unavailable by the routers. A leader reported by a quorum of distinct
+    // routers is considered unavailable even if it is still present in the ingester pool.
+    unavailable_leader_reports: UnavailableLeaderReports,
+    // Recent shard lifecycle transitions, exposed for debugging purposes.
+    pub(crate) shard_event_log: ShardEventLog,
+    // Thresholds driving the scaling of the number of shards, overridable by the cluster settings.
+    scaling_thresholds: ShardScalingThresholds,
+    // Generates the IDs of the shards opened by the controller.
+    shard_id_generator: Arc<dyn ShardIdGenerator>,
+    timeouts: IngestControllerTimeouts,
+    // Per-source digests of the shards each ingester acknowledged during the last retain shards
+    // sync. Only the sources whose digest changed are sent during the next sync.
+    retain_shards_digests: Arc<std::sync::Mutex<HashMap<NodeId, SourceShardsDigests>>>,
+    // Mailbox of the control plane, to which the outcome of the background init shards retries
+    // is sent. Shards are not retried in the background until it is set.
+    control_plane_mailbox_opt: Option<WeakMailbox<ControlPlane>>,
     pub stats: IngestControllerStats,
 }
 
+type SourceShardsDigests = HashMap<SourceUid, u64>;
+
 impl fmt::Debug for IngestController {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("IngestController")
             .field("ingester_pool", &self.ingester_pool)
             .field("metastore", &self.metastore)
             .field("replication_factor", &self.replication_factor)
+            .field("decommissioning_ingesters", &self.decommissioning_ingesters)
+            .field("decommissioned_ingesters", &self.decommissioned_ingesters)
+            .field("scaling_thresholds", &self.scaling_thresholds)
+            .field("timeouts", &self.timeouts)
             .finish()
     }
 }
@@ -126,16 +445,62 @@ impl IngestController {
         metastore: MetastoreServiceClient,
         ingester_pool: IngesterPool,
         replication_factor: usize,
+        timeouts: IngestControllerTimeouts,
     ) -> Self {
         IngestController {
             metastore,
             ingester_pool,
             replication_factor,
             rebalance_lock: Arc::new(Mutex::new(())),
+            decommissioning_ingesters: BTreeSet::new(),
+            decommission_requests: Arc::default(),
+            decommissioned_ingesters: BTreeSet::new(),
+            health_tracker: IngesterHealthTracker::default(),
+            unavailable_leader_reports: UnavailableLeaderReports::default(),
+            shard_event_log: ShardEventLog::default(),
+            scaling_thresholds: ShardScalingThresholds::default(),
+            shard_id_generator: Arc::new(UlidShardIdGenerator),
+            timeouts,
+            retain_shards_digests: Arc::default(),
+            control_plane_mailbox_opt: None,
             stats: IngestControllerStats::default(),
         }
     }
 
+    /// Sets the mailbox of the control plane, which receives the outcome of the background init
+    /// shards retries.
+    pub(crate) fn set_control_plane_mailbox(&mut self, mailbox: WeakMailbox<ControlPlane>) {
+        self.control_plane_mailbox_opt = Some(mailbox);
+    }
+
+    /// Sets the generator of the IDs of the shards opened by the controller.
+    pub(crate) fn with_shard_id_generator(
+        mut self,
+        shard_id_generator: Arc<dyn ShardIdGenerator>,
+    ) -> Self {
+        self.shard_id_generator = shard_id_generator;
+        self
+    }
+
+    /// Applies the ingest settings of the cluster settings. Settings that are not set fall back to
+    /// the built-in defaults.
+    pub(crate) fn apply_cluster_settings(&mut self, cluster_settings: &ClusterSettings) {
+        let scaling_thresholds = ShardScalingThresholds::from_cluster_settings(cluster_settings);
+
+        if scaling_thresholds != self.scaling_thresholds {
+            info!(
+                scale_up_threshold_mib_per_sec = scaling_thresholds.scale_up_mib_per_sec,
+                scale_down_threshold_mib_per_sec = scaling_thresholds.scale_down_mib_per_sec,
+                "updating shard scaling thresholds"
+            );
+            self.scaling_thresholds = scaling_thresholds;
+        }
+    }
+
+    pub(crate) fn ingester_pool(&self) -> &IngesterPool {
+        &self.ingester_pool
+    }
+
     /// Sends a retain shard request to the given list of ingesters.
     ///
     /// If the request fails, we just log an error.
@@ -151,6 +516,11 @@ impl IngestController {
 
     pub(crate) fn sync_with_all_ingesters(&self, model: &ControlPlaneModel) {
         let ingesters: Vec<NodeId> = self.ingester_pool.keys();
+        self.retain_shards_digests
+            .lock()
+            .expect("lock should not be poisoned")
+            .retain(|ingester, _| ingesters.contains(ingester));
+
         for ingester in ingesters {
             self.sync_with_ingester(&ingester, model);
         }
@@ -158,6 +528,12 @@ impl IngestController {
 
     /// Syncs the ingester in a fire and forget manner.
     ///
+    /// The first sync with an ingester lists all its shards. Subsequent syncs are incremental:
+    /// they only list the sources whose shards changed since the last acknowledged sync, along
+    /// with a digest of all the shards the ingester is expected to host. On digest mismatch, the
+    /// ingester reports the digest of each of its sources and the diverging sources are sent again
+    /// during the next sync.
+    ///
     /// The returned oneshot is just here for unit test to wait for the operation to terminate.
     fn sync_with_ingester(&self, ingester: &NodeId, model: &ControlPlaneModel) -> WaitHandle {
         info!(ingester = %ingester, "sync_with_ingester");
@@ -168,8 +544,38 @@ impl IngestController {
             warn!("failed to sync with ingester `{ingester}`: not available");
             return wait_handle;
         };
+        let shards_for_node = model.list_shards_for_node(ingester);
+        let expected_digests: SourceShardsDigests = shards_for_node
+            .iter()
+            .filter(|(_, shard_ids)| !shard_ids.is_empty())
+            .map(|(source_uid, shard_ids)| {
+                let digest =
+                    source_shards_digest(&source_uid.index_uid, &source_uid.source_id, shard_ids);
+                (source_uid.clone(), digest)
+            })
+            .collect();
+        let acknowledged_digests_opt: Option<SourceShardsDigests> = self
+            .retain_shards_digests
+            .lock()
+            .expect("lock should not be poisoned")
+            .get(ingester)
+            .cloned();
+
         let mut retain_shards_req = RetainShardsRequest::default();
-        for (source_uid, shard_ids) in &*model.list_shards_for_node(ingester) {
+
+        if acknowledged_digests_opt.is_some() {
+            retain_shards_req.incremental = true;
+            retain_shards_req.expected_digest =
+                expected_digests.values().fold(0, |digest, source_digest| {
+                    digest.wrapping_add(*source_digest)
+                });
+        }
+        for (source_uid, shard_ids) in &*shards_for_node {
+            if let Some(acknowledged_digests) = &acknowledged_digests_opt {
+                if acknowledged_digests.get(source_uid) == expected_digests.get(source_uid) {
+                    continue;
+                }
+            }
             let shards_for_source = RetainShardsForSource {
                 index_uid: Some(source_uid.index_uid.clone()),
                 source_id: source_uid.source_id.clone(),
@@ -179,14 +585,84 @@ impl IngestController {
                 .retain_shards_for_sources
                 .push(shards_for_source);
         }
-        info!(ingester = %ingester, "retain shards ingester");
+        if let Some(acknowledged_digests) = &acknowledged_digests_opt {
+            // The sources the ingester should no longer host are listed without any shards.
+            for source_uid in acknowledged_digests.keys() {
+                if !shards_for_node.contains_key(source_uid) {
+                    let shards_for_source = RetainShardsForSource {
+                        index_uid: Some(source_uid.index_uid.clone()),
+                        source_id: source_uid.source_id.clone(),
+                        shard_ids: Vec::new(),
+                    };
+                    retain_shards_req
+                        .retain_shards_for_sources
+                        .push(shards_for_source);
+                }
+            }
+        }
+        info!(
+            ingester = %ingester,
+            incremental = retain_shards_req.incremental,
+            num_sources = retain_shards_req.retain_shards_for_sources.len(),
+            "retain shards ingester"
+        );
+        let ingester_id = ingester.clone();
+        let retain_shards_digests = self.retain_shards_digests.clone();
         let operation: String = format!("retain shards `{ingester}`");
         fire_and_forget(
             async move {
-                if let Err(retain_shards_err) =
-                    ingester_client.retain_shards(retain_shards_req).await
-                {
-                    error!(%retain_shards_err, "retain shards error");
+                match ingester_client.retain_shards(retain_shards_req).await {
+                    Ok(retain_shards_response) => {
+                        let mut retain_shards_digests_guard = retain_shards_digests
+                            .lock()
+                            .expect("lock should not be poisoned");
+
+                        if !retain_shards_response.supports_incremental {
+                            retain_shards_digests_guard.remove(&ingester_id);
+                        } else if retain_shards_response.digest_mismatch {
+                            let reported_digests: SourceShardsDigests = retain_shards_response
+                                .source_digests
+                                .iter()
+                                .map(|source_digest| {
+                                    let source_uid = SourceUid {
+                                        index_uid: source_digest.index_uid().clone(),
+                                        source_id: source_digest.source_id.clone(),
+                                    };
+                                    (source_uid, source_digest.digest)
+                                })
+                                .collect();
+                            let diverging_sources: Vec<String> = expected_digests
+                                .keys()
+                                .chain(reported_digests.keys())
+                                .unique()
+                                .filter(|source_uid| {
+                                    expected_digests.get(source_uid)
+                                        != reported_digests.get(source_uid)
+                                })
+                                .map(|source_uid| source_uid.to_string())
+                                .collect();
+                            warn!(
+                                ingester = %ingester_id,
+                                diverging_sources = ?PrettySample::new(&diverging_sources, 5),
+                                "shards hosted by ingester do not match the shards expected by \
+                                 the control plane"
+                            );
+                            CONTROL_PLANE_METRICS
+                                .retain_shards_digest_mismatches_total
+                                .inc();
+                            retain_shards_digests_guard.insert(ingester_id, reported_digests);
+                        } else {
+                            retain_shards_digests_guard.insert(ingester_id, expected_digests);
+                        }
+                    }
+                    Err(retain_shards_err) => {
+                        error!(%retain_shards_err, "retain shards error");
+                        // The next sync lists all the shards of the ingester.
+                        retain_shards_digests
+                            .lock()
+                            .expect("lock should not be poisoned")
+                            .remove(&ingester_id);
+                    }
                 }
                 // just a way to force moving the drop guard.
                 drop(wait_drop_guard);
@@ -196,7 +672,49 @@ impl IngestController {
         wait_handle
     }
 
-    fn handle_closed_shards(&self, closed_shards: Vec<ShardIds>, model: &mut ControlPlaneModel) {
+    /// Records a transition for the shards of a source currently present in the model.
+    pub(crate) fn record_shard_events(
+        &mut self,
+        event_type: ShardEventType,
+        source_uid: &SourceUid,
+        shard_ids: &[ShardId],
+        model: &ControlPlaneModel,
+        reason: &str,
+    ) {
+        let Some(shard_entries) = model.get_shards_for_source(source_uid) else {
+            return;
+        };
+        for shard_id in shard_ids {
+            if let Some(shard_entry) = shard_entries.get(shard_id) {
+                self.shard_event_log.record(event_type, shard_entry, reason);
+            }
+        }
+    }
+
+    /// Records an unavailable transition for the open shards of the unavailable leaders. Must be
+    /// called before the shards are marked as unavailable in the model.
+    fn record_unavailable_shard_events(
+        &mut self,
+        unavailable_leaders: &FnvHashSet<NodeId>,
+        model: &ControlPlaneModel,
+        reason: &str,
+    ) {
+        for (leader_id, open_shard_entries) in model.open_shards_per_leader() {
+            if unavailable_leaders.contains(leader_id) {
+                self.shard_event_log.record_all(
+                    ShardEventType::Unavailable,
+                    open_shard_entries.map(|shard_entry| &shard_entry.shard),
+                    reason,
+                );
+            }
+        }
+    }
+
+    fn handle_closed_shards(
+        &mut self,
+        closed_shards: Vec<ShardIds>,
+        model: &mut ControlPlaneModel,
+    ) {
         for closed_shard in closed_shards {
             let index_uid: IndexUid = closed_shard.index_uid().clone();
             let source_id = closed_shard.source_id;
@@ -208,6 +726,13 @@ impl IngestController {
             let closed_shard_ids = model.close_shards(&source_uid, &closed_shard.shard_ids);
 
             if !closed_shard_ids.is_empty() {
+                self.record_shard_events(
+                    ShardEventType::Closed,
+                    &source_uid,
+                    &closed_shard_ids,
+                    model,
+                    "reported closed by router",
+                );
                 info!(
                     index_id=%source_uid.index_uid.index_id,
                     source_id=%source_uid.source_id,
@@ -225,14 +750,27 @@ impl IngestController {
         model: &mut ControlPlaneModel,
         progress: &Progress,
     ) {
+        let source_uid = &local_shards_update.source_uid;
+
+        for shard_info in &local_shards_update.shard_infos {
+            if let Some(publish_latency) = shard_info.publish_latency_opt {
+                CONTROL_PLANE_METRICS
+                    .ingest_to_publish_latency_seconds
+                    .with_label_values([
+                        source_uid.index_uid.index_id.as_str(),
+                        source_uid.source_id.as_str(),
+                    ])
+                    .observe(publish_latency.as_secs_f64());
+            }
+        }
         let shard_stats = model.update_shards(
             &local_shards_update.source_uid,
             &local_shards_update.shard_infos,
         );
-        if shard_stats.avg_ingestion_rate >= SCALE_UP_SHARDS_THRESHOLD_MIB_PER_SEC {
+        if shard_stats.avg_ingestion_rate >= self.scaling_thresholds.scale_up_mib_per_sec {
             self.try_scale_up_shards(local_shards_update.source_uid, shard_stats, model, progress)
                 .await;
-        } else if shard_stats.avg_ingestion_rate <= SCALE_DOWN_SHARDS_THRESHOLD_MIB_PER_SEC
+        } else if shard_stats.avg_ingestion_rate <= self.scaling_thresholds.scale_down_mib_per_sec
             && shard_stats.num_open_shards > 1
         {
             self.try_scale_down_shards(
@@ -245,26 +783,149 @@ impl IngestController {
         }
     }
 
+    /// Marks the shards of the leaders reported as unavailable by a router as unavailable if the
+    /// control plane can confirm it: the leader left the ingester pool, failed its health probes,
+    /// or has been reported by a quorum of distinct routers recently.
     fn handle_unavailable_leaders(
-        &self,
+        &mut self,
+        router_id: &str,
         unavailable_leaders: &FnvHashSet<NodeId>,
         model: &mut ControlPlaneModel,
     ) {
         let mut confirmed_unavailable_leaders = FnvHashSet::default();
+        let mut unconfirmed_unavailable_leaders = Vec::new();
 
         for leader_id in unavailable_leaders {
-            if !self.ingester_pool.contains_key(leader_id) {
+            if !self.ingester_pool.contains_key(leader_id)
+                || self.health_tracker.is_unhealthy(leader_id)
+            {
                 confirmed_unavailable_leaders.insert(leader_id.clone());
             } else {
-                // TODO: If a majority of ingesters consistenly reports a leader as unavailable, we
-                // should probably mark it as unavailable too.
+                unconfirmed_unavailable_leaders.push(leader_id);
             }
         }
+        // Routers that do not identify themselves cannot be told apart, so their reports do not
+        // count towards the quorum.
+        if !router_id.is_empty() && !unconfirmed_unavailable_leaders.is_empty() {
+            let router_id = NodeId::from(router_id);
+            let leaders_confirmed_by_quorum = self.unavailable_leader_reports.record_reports(
+                &router_id,
+                unconfirmed_unavailable_leaders,
+                Instant::now(),
+            );
+            confirmed_unavailable_leaders.extend(leaders_confirmed_by_quorum);
+        }
         if !confirmed_unavailable_leaders.is_empty() {
+            self.record_unavailable_shard_events(
+                &confirmed_unavailable_leaders,
+                model,
+                "leader reported unavailable by routers",
+            );
             model.set_shards_as_unavailable(&confirmed_unavailable_leaders);
         }
     }
 
+    /// Records the outcome of a round of health probes. The open shards of the ingesters that just
+    /// became unhealthy are marked as unavailable and replaced by new shards allocated to healthy
+    /// ingesters, without waiting for the routers to report them or for the ingesters to leave
+    /// the cluster.
+    pub(crate) async fn handle_ingester_probe_results(
+        &mut self,
+        probe_results: Vec<(NodeId, bool)>,
+        model: &mut ControlPlaneModel,
+        progress: &Progress,
+    ) {
+        self.health_tracker.retain(&self.ingester_pool);
+
+        let unhealthy_ingesters = self.health_tracker.record_probe_results(probe_results);
+
+        if unhealthy_ingesters.is_empty() {
+            return;
+        }
+        let shards_to_replace: Vec<(IndexUid, String)> = model
+            .all_shards()
+            .filter(|shard_entry| {
+                shard_entry.is_open()
+                    && unhealthy_ingesters.contains(shard_entry.leader_id.as_str())
+            })
+            .map(|shard_entry| {
+                (
+                    shard_entry.index_uid().clone(),
+                    shard_entry.source_id.clone(),
+                )
+            })
+            .collect();
+
+        self.record_unavailable_shard_events(
+            &unhealthy_ingesters,
+            model,
+            "leader failed health probes",
+        );
+        model.set_shards_as_unavailable(&unhealthy_ingesters);
+
+        if shards_to_replace.is_empty() {
+            return;
+        }
+        info!(
+            "replacing {} shards hosted on unhealthy ingesters",
+            shards_to_replace.len()
+        );
+        let replication_factors: Vec<usize> = shards_to_replace
+            .iter()
+            .map(|(index_uid, _)| self.index_replication_factor(index_uid, model))
+            .collect();
+        let Some(leader_follower_pairs) =
+            self.allocate_shards(&replication_factors, &unhealthy_ingesters, model)
+        else {
+            warn!("failed to replace shards hosted on unhealthy ingesters");
+            return;
+        };
+        let open_shards_subrequests: Vec<metastore::OpenShardSubrequest> =
+            zip(shards_to_replace, leader_follower_pairs)
+                .enumerate()
+                .map(
+                    |(subrequest_id, ((index_uid, source_id), (leader_id, follower_id_opt)))| {
+                        metastore::OpenShardSubrequest {
+                            subrequest_id: subrequest_id as u32,
+                            index_uid: Some(index_uid),
+                            source_id,
+                            shard_id: Some(self.shard_id_generator.next_shard_id()),
+                            leader_id: leader_id.into(),
+                            follower_id: follower_id_opt.map(Into::into),
+                        }
+                    },
+                )
+                .collect();
+        let open_shards_request = metastore::OpenShardsRequest {
+            subrequests: open_shards_subrequests,
+        };
+        let open_shards_response = match progress
+            .protect_future(self.metastore.open_shards(open_shards_request))
+            .await
+        {
+            Ok(open_shards_response) => open_shards_response,
+            Err(error) => {
+                warn!("failed to replace shards hosted on unhealthy ingesters: {error}");
+                return;
+            }
+        };
+        let init_shards_response = self
+            .init_shards(&open_shards_response.subresponses, model, progress)
+            .await;
+
+        for init_shard_success in init_shards_response.successes {
+            let shard = init_shard_success.shard().clone();
+            let index_uid = shard.index_uid().clone();
+            let source_id = shard.source_id.clone();
+            self.shard_event_log.record(
+                ShardEventType::Opened,
+                &shard,
+                "replaces shard hosted on unhealthy ingester",
+            );
+            model.insert_shards(&index_uid, &source_id, vec![shard]);
+        }
+    }
+
     /// Finds the open shards that satisfies the [`GetOrCreateOpenShardsRequest`] request sent by an
     /// ingest router. First, the control plane checks its internal shard table to find
     /// candidates. If it does not contain any, the control plane will ask
@@ -277,21 +938,30 @@ impl IngestController {
     ) -> ControlPlaneResult<GetOrCreateOpenShardsResponse> {
         self.handle_closed_shards(get_open_shards_request.closed_shards, model);
 
-        let unavailable_leaders: FnvHashSet<NodeId> = get_open_shards_request
+        let mut unavailable_leaders: FnvHashSet<NodeId> = get_open_shards_request
             .unavailable_leaders
             .into_iter()
             .map(|ingester_id| ingester_id.into())
             .collect();
 
-        self.handle_unavailable_leaders(&unavailable_leaders, model);
+        self.handle_unavailable_leaders(
+            &get_open_shards_request.router_id,
+            &unavailable_leaders,
+            model,
+        );
+
+        unavailable_leaders.extend(self.health_tracker.unhealthy_ingesters().cloned());
 
         let num_subrequests = get_open_shards_request.subrequests.len();
         let mut get_or_create_open_shards_successes = Vec::with_capacity(num_subrequests);
         let mut get_or_create_open_shards_failures = Vec::new();
         let mut open_shards_subrequests = Vec::new();
+        let mut known_shard_table_versions: HashMap<u32, u64> = HashMap::new();
 
         for get_open_shards_subrequest in get_open_shards_request.subrequests {
-            let Some(index_uid) = model.index_uid(&get_open_shards_subrequest.index_id) else {
+            let Some(index_uid) =
+                model.resolve_write_index_uid(&get_open_shards_subrequest.index_id)
+            else {
                 let get_or_create_open_shards_failure = GetOrCreateOpenShardsFailure {
                     subrequest_id: get_open_shards_subrequest.subrequest_id,
                     index_id: get_open_shards_subrequest.index_id,
@@ -301,10 +971,25 @@ impl IngestController {
                 get_or_create_open_shards_failures.push(get_or_create_open_shards_failure);
                 continue;
             };
-            let Some(open_shard_entries) = model.find_open_shards(
+            let source_uid = SourceUid {
+                index_uid: index_uid.clone(),
+                source_id: get_open_shards_subrequest.source_id.clone(),
+            };
+            if model.is_source_paused(&source_uid) {
+                let get_or_create_open_shards_failure = GetOrCreateOpenShardsFailure {
+                    subrequest_id: get_open_shards_subrequest.subrequest_id,
+                    index_id: get_open_shards_subrequest.index_id,
+                    source_id: get_open_shards_subrequest.source_id,
+                    reason: GetOrCreateOpenShardsFailureReason::SourcePaused as i32,
+                };
+                get_or_create_open_shards_failures.push(get_or_create_open_shards_failure);
+                continue;
+            }
+            let Some(open_shards_sync) = model.sync_open_shards(
                 &index_uid,
                 &get_open_shards_subrequest.source_id,
                 &unavailable_leaders,
+                get_open_shards_subrequest.known_shard_table_version,
             ) else {
                 let get_or_create_open_shards_failure = GetOrCreateOpenShardsFailure {
                     subrequest_id: get_open_shards_subrequest.subrequest_id,
@@ -315,20 +1000,35 @@ impl IngestController {
                 get_or_create_open_shards_failures.push(get_or_create_open_shards_failure);
                 continue;
             };
-            if !open_shard_entries.is_empty() {
-                let open_shards: Vec<Shard> = open_shard_entries
-                    .into_iter()
-                    .map(|shard_entry| shard_entry.shard)
-                    .collect();
-                let get_or_create_open_shards_success = GetOrCreateOpenShardsSuccess {
+            if open_shards_sync.num_open_shards > 0 {
+                let ingestion_pressure =
+                    compute_ingestion_pressure(&model.shard_stats(&source_uid));
+                let get_or_create_open_shards_success = make_get_or_create_open_shards_success(
+                    get_open_shards_subrequest.subrequest_id,
+                    source_uid,
+                    open_shards_sync,
+                    ingestion_pressure,
+                );
+                get_or_create_open_shards_successes.push(get_or_create_open_shards_success);
+            } else if model.is_ingestion_quota_exceeded(&index_uid) {
+                self.stats.record_ingestion_quota_rejection();
+                let get_or_create_open_shards_failure = GetOrCreateOpenShardsFailure {
                     subrequest_id: get_open_shards_subrequest.subrequest_id,
-                    index_uid: index_uid.into(),
+                    index_id: get_open_shards_subrequest.index_id,
                     source_id: get_open_shards_subrequest.source_id,
-                    open_shards,
+                    reason: GetOrCreateOpenShardsFailureReason::QuotaExceeded as i32,
                 };
-                get_or_create_open_shards_successes.push(get_or_create_open_shards_success);
+                get_or_create_open_shards_failures.push(get_or_create_open_shards_failure);
             } else {
-                let shard_id = ShardId::from(Ulid::new());
+                if let Some(known_shard_table_version) =
+                    get_open_shards_subrequest.known_shard_table_version
+                {
+                    known_shard_table_versions.insert(
+                        get_open_shards_subrequest.subrequest_id,
+                        known_shard_table_version,
+                    );
+                }
+                let shard_id = self.shard_id_generator.next_shard_id();
                 let open_shard_subrequest = metastore::OpenShardSubrequest {
                     subrequest_id: get_open_shards_subrequest.subrequest_id,
                     index_uid: index_uid.into(),
@@ -342,8 +1042,14 @@ impl IngestController {
             }
         }
         if !open_shards_subrequests.is_empty() {
+            let replication_factors: Vec<usize> = open_shards_subrequests
+                .iter()
+                .map(|open_shards_subrequest| {
+                    self.index_replication_factor(open_shards_subrequest.index_uid(), model)
+                })
+                .collect();
             if let Some(leader_follower_pairs) =
-                self.allocate_shards(open_shards_subrequests.len(), &unavailable_leaders, model)
+                self.allocate_shards(&replication_factors, &unavailable_leaders, model)
             {
                 for (open_shards_subrequest, (leader_id, follower_opt)) in open_shards_subrequests
                     .iter_mut()
@@ -360,28 +1066,43 @@ impl IngestController {
                     .await?;
 
                 let init_shards_response = self
-                    .init_shards(&open_shards_response.subresponses, progress)
+                    .init_shards(&open_shards_response.subresponses, model, progress)
                     .await;
 
                 for init_shard_success in init_shards_response.successes {
                     let shard = init_shard_success.shard().clone();
                     let index_uid = shard.index_uid().clone();
                     let source_id = shard.source_id.clone();
+                    self.shard_event_log.record(
+                        ShardEventType::Opened,
+                        &shard,
+                        "no open shard available for router",
+                    );
                     model.insert_shards(&index_uid, &source_id, vec![shard]);
 
-                    if let Some(open_shard_entries) =
-                        model.find_open_shards(&index_uid, &source_id, &unavailable_leaders)
-                    {
-                        let open_shards = open_shard_entries
-                            .into_iter()
-                            .map(|shard_entry| shard_entry.shard)
-                            .collect();
-                        let get_or_create_open_shards_success = GetOrCreateOpenShardsSuccess {
-                            subrequest_id: init_shard_success.subrequest_id,
-                            index_uid: Some(index_uid),
+                    let known_shard_table_version_opt = known_shard_table_versions
+                        .get(&init_shard_success.subrequest_id)
+                        .copied();
+
+                    if let Some(open_shards_sync) = model.sync_open_shards(
+                        &index_uid,
+                        &source_id,
+                        &unavailable_leaders,
+                        known_shard_table_version_opt,
+                    ) {
+                        let source_uid = SourceUid {
+                            index_uid,
                             source_id,
-                            open_shards,
                         };
+                        let ingestion_pressure =
+                            compute_ingestion_pressure(&model.shard_stats(&source_uid));
+                        let get_or_create_open_shards_success =
+                            make_get_or_create_open_shards_success(
+                                init_shard_success.subrequest_id,
+                                source_uid,
+                                open_shards_sync,
+                                ingestion_pressure,
+                            );
                         get_or_create_open_shards_successes.push(get_or_create_open_shards_success);
                     }
                 }
@@ -404,178 +1125,509 @@ impl IngestController {
         Ok(response)
     }
 
-    /// Allocates and assigns new shards to ingesters.
+    /// Returns the replication factor of the index: the one set in the index config if any, the
+    /// cluster-wide one otherwise.
+    fn index_replication_factor(&self, index_uid: &IndexUid, model: &ControlPlaneModel) -> usize {
+        model
+            .index_metadata(index_uid)
+            .and_then(|index_metadata| index_metadata.index_config.replication_factor_opt)
+            .unwrap_or(self.replication_factor)
+    }
+
+    /// Allocates and assigns new shards to ingesters. One shard is allocated per entry of
+    /// `replication_factors`, and a follower is assigned to the shard if its replication factor is
+    /// greater than 1.
     fn allocate_shards(
-        &self,
-        num_shards_to_allocate: usize,
+        &mut self,
+        replication_factors: &[usize],
         unavailable_leaders: &FnvHashSet<NodeId>,
         model: &ControlPlaneModel,
     ) -> Option<Vec<(NodeId, Option<NodeId>)>> {
-        let ingesters: Vec<NodeId> = self
-            .ingester_pool
+        let leader_follower_pairs_opt =
+            self.plan_shard_allocation(replication_factors, unavailable_leaders, model);
+
+        if leader_follower_pairs_opt.is_none() {
+            self.stats
+                .record_rejected_shard_allocations(replication_factors.len());
+        }
+        leader_follower_pairs_opt
+    }
+
+    /// Returns the sorted IDs of the ingesters that can host new shard replicas.
+    fn available_ingesters(&self, unavailable_leaders: &FnvHashSet<NodeId>) -> Vec<NodeId> {
+        let now = Instant::now();
+
+        self.ingester_pool
             .keys()
             .into_iter()
-            .filter(|ingester| !unavailable_leaders.contains(ingester))
+            .filter(|ingester| {
+                !unavailable_leaders.contains(ingester)
+                    && !self.decommissioning_ingesters.contains(ingester)
+                    && !self.health_tracker.is_unhealthy(ingester)
+                    && !self
+                        .unavailable_leader_reports
+                        .is_confirmed_unavailable(ingester, now)
+            })
             .sorted_by(|left, right| left.cmp(right))
-            .collect();
+            .collect()
+    }
 
+    /// Computes the leader-follower pairs of the shards to allocate without recording any stats.
+    /// See [`Self::allocate_shards`].
+    fn plan_shard_allocation(
+        &self,
+        replication_factors: &[usize],
+        unavailable_leaders: &FnvHashSet<NodeId>,
+        model: &ControlPlaneModel,
+    ) -> Option<Vec<(NodeId, Option<NodeId>)>> {
+        let num_shards_to_allocate = replication_factors.len();
+        let ingesters = self.available_ingesters(unavailable_leaders);
         let num_ingesters = ingesters.len();
 
         if num_ingesters == 0 {
             warn!("failed to allocate {num_shards_to_allocate} shards: no ingesters available");
             return None;
-        } else if self.replication_factor > num_ingesters {
+        } else if replication_factors
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_default()
+            > num_ingesters
+        {
             warn!(
                 "failed to allocate {num_shards_to_allocate} shards: replication factor is \
                  greater than the number of available ingesters"
             );
             return None;
         }
-        let mut leader_follower_pairs = Vec::with_capacity(num_shards_to_allocate);
-
-        let mut num_open_shards: usize = 0;
-        let mut per_leader_num_open_shards: HashMap<&str, usize> =
-            HashMap::with_capacity(num_ingesters);
-
-        for shard in model.all_shards() {
-            if shard.is_open() && !unavailable_leaders.contains(&shard.leader_id) {
-                num_open_shards += 1;
-
-                *per_leader_num_open_shards
-                    .entry(&shard.leader_id)
-                    .or_default() += 1;
+        // Both leaders and followers receive the writes of the shards they host, so we balance
+        // the number of open shard replicas hosted by each ingester, then the number of shards
+        // they lead.
+        let mut per_node_num_replicas: HashMap<&str, usize> = HashMap::with_capacity(num_ingesters);
+        let mut per_node_num_leaders: HashMap<&str, usize> = HashMap::with_capacity(num_ingesters);
+
+        for (leader_id, open_shards) in model.open_shards_per_leader() {
+            if unavailable_leaders.contains(leader_id) {
+                continue;
+            }
+            for shard in open_shards {
+                *per_node_num_replicas.entry(leader_id.as_str()).or_default() += 1;
+                *per_node_num_leaders.entry(leader_id.as_str()).or_default() += 1;
+
+                if let Some(follower_id) = &shard.follower_id {
+                    *per_node_num_replicas
+                        .entry(follower_id.as_str())
+                        .or_default() += 1;
+                }
             }
         }
-        let mut num_remaining_shards_to_allocate = num_shards_to_allocate;
-        let num_open_shards_target = num_shards_to_allocate + num_open_shards;
-        let max_num_shards_to_allocate_per_node = num_open_shards_target / num_ingesters;
+        let mut leader_follower_pairs = Vec::with_capacity(num_shards_to_allocate);
 
-        // Allocate at most `max_num_shards_to_allocate_per_node` shards to each ingester.
-        for (leader_id, follower_id) in ingesters.iter().zip(ingesters.iter().cycle().skip(1)) {
-            if num_remaining_shards_to_allocate == 0 {
-                break;
+        for &replication_factor in replication_factors {
+            let ingester_load = |ingester_id: &str| {
+                (
+                    per_node_num_replicas
+                        .get(ingester_id)
+                        .copied()
+                        .unwrap_or_default(),
+                    per_node_num_leaders
+                        .get(ingester_id)
+                        .copied()
+                        .unwrap_or_default(),
+                )
+            };
+            let mut least_loaded_ingesters = ingesters
+                .iter()
+                .map(|ingester| (ingester_load(ingester.as_str()), ingester))
+                .k_smallest(2)
+                .map(|(_, ingester)| ingester);
+
+            let mut leader_id = least_loaded_ingesters
+                .next()
+                .expect("there should be at least one available ingester");
+            let mut follower_id_opt = None;
+
+            if replication_factor > 1 {
+                let mut follower_id = least_loaded_ingesters
+                    .next()
+                    .expect("there should be at least two available ingesters");
+                // The ingester leading the fewest shards becomes the leader.
+                if ingester_load(follower_id.as_str()).1 < ingester_load(leader_id.as_str()).1 {
+                    std::mem::swap(&mut leader_id, &mut follower_id);
+                }
+                follower_id_opt = Some(follower_id);
             }
-            let num_open_shards_inner = per_leader_num_open_shards
-                .get(leader_id.as_str())
-                .copied()
-                .unwrap_or_default();
+            *per_node_num_replicas.entry(leader_id.as_str()).or_default() += 1;
+            *per_node_num_leaders.entry(leader_id.as_str()).or_default() += 1;
 
-            let num_shards_to_allocate_inner = max_num_shards_to_allocate_per_node
-                .saturating_sub(num_open_shards_inner)
-                .min(num_remaining_shards_to_allocate);
-
-            for _ in 0..num_shards_to_allocate_inner {
-                num_remaining_shards_to_allocate -= 1;
-
-                let leader = leader_id.clone();
-                let mut follower_opt = None;
-
-                if self.replication_factor > 1 {
-                    follower_opt = Some(follower_id.clone());
-                }
-                leader_follower_pairs.push((leader, follower_opt));
+            if let Some(follower_id) = follower_id_opt {
+                *per_node_num_replicas
+                    .entry(follower_id.as_str())
+                    .or_default() += 1;
             }
-        }
-        // Allocate remaining shards one by one.
-        for (leader_id, follower_id) in ingesters.iter().zip(ingesters.iter().cycle().skip(1)) {
-            if num_remaining_shards_to_allocate == 0 {
-                break;
-            }
-            num_remaining_shards_to_allocate -= 1;
-
-            let leader = leader_id.clone();
-            let mut follower_opt = None;
-
-            if self.replication_factor > 1 {
-                follower_opt = Some(follower_id.clone());
-            }
-            leader_follower_pairs.push((leader, follower_opt));
+            leader_follower_pairs.push((leader_id.clone(), follower_id_opt.cloned()));
         }
         Some(leader_follower_pairs)
     }
 
     /// Calls init shards on the leaders hosting newly opened shards.
+    ///
+    /// The shards whose leader is unavailable, times out, or fails with a transient error are
+    /// immediately reopened on alternative leaders, if any. The shards that cannot be reopened or
+    /// fail again are reported as failures, and then retried in the background with an
+    /// exponential backoff, up to `MAX_INIT_SHARDS_ATTEMPTS` times, so that the control plane
+    /// never waits for a backoff while handling a request. The outcome of these retries is sent
+    /// back to the control plane in an [`InitShardsRetryCallback`]. The failures always refer to
+    /// the shards originally passed to this method.
     async fn init_shards(
-        &self,
+        &mut self,
         open_shards_subresponses: &[metastore::OpenShardSubresponse],
+        model: &ControlPlaneModel,
         progress: &Progress,
     ) -> InitShardsResponse {
         let mut successes = Vec::with_capacity(open_shards_subresponses.len());
         let mut failures = Vec::new();
 
-        let mut per_leader_shards_to_init: HashMap<&String, Vec<InitShardSubrequest>> =
-            HashMap::default();
+        let shards_to_init: Vec<(InitShardSubrequest, InitShardFailure)> = open_shards_subresponses
+            .iter()
+            .map(|subresponse| {
+                let shard = subresponse.open_shard();
+                let init_shard_subrequest = InitShardSubrequest {
+                    subrequest_id: subresponse.subrequest_id,
+                    shard: Some(shard.clone()),
+                };
+                let init_shard_failure = InitShardFailure {
+                    subrequest_id: subresponse.subrequest_id,
+                    index_uid: Some(shard.index_uid().clone()),
+                    source_id: shard.source_id.clone(),
+                    shard_id: Some(shard.shard_id().clone()),
+                };
+                (init_shard_subrequest, init_shard_failure)
+            })
+            .collect();
+        let init_shards_request_timeout = self.timeouts.init_shards_request_timeout;
+
+        let shards_to_retry = progress
+            .protect_future(try_init_shards(
+                &self.ingester_pool,
+                init_shards_request_timeout,
+                shards_to_init,
+                &mut successes,
+                &mut failures,
+            ))
+            .await;
+
+        if !shards_to_retry.is_empty() {
+            let (reopened_shards, mut shards_to_retry) = self
+                .reopen_shards_on_alternative_leaders(
+                    shards_to_retry,
+                    model,
+                    &mut failures,
+                    progress,
+                )
+                .await;
+
+            if !reopened_shards.is_empty() {
+                let reopened_shards_to_retry = progress
+                    .protect_future(try_init_shards(
+                        &self.ingester_pool,
+                        init_shards_request_timeout,
+                        reopened_shards,
+                        &mut successes,
+                        &mut failures,
+                    ))
+                    .await;
+                shards_to_retry.extend(reopened_shards_to_retry);
+            }
+            failures.extend(
+                shards_to_retry
+                    .iter()
+                    .map(|(_, init_shard_failure)| init_shard_failure.clone()),
+            );
+            self.spawn_init_shards_retries(shards_to_retry);
+        }
+        let init_shards_response = InitShardsResponse {
+            successes,
+            failures,
+        };
+        self.stats.record_init_shards(&init_shards_response);
+        init_shards_response
+    }
+
+    /// Retries initializing the shards on their current leaders in a background task, with an
+    /// exponential backoff. The outcome is sent to the control plane in an
+    /// [`InitShardsRetryCallback`].
+    fn spawn_init_shards_retries(
+        &self,
+        shards_to_retry: Vec<(InitShardSubrequest, InitShardFailure)>,
+    ) {
+        if shards_to_retry.is_empty() {
+            return;
+        }
+        let Some(weak_mailbox) = self.control_plane_mailbox_opt.clone() else {
+            warn!("failed to init {} shards", shards_to_retry.len());
+            return;
+        };
+        let ingester_pool = self.ingester_pool.clone();
+        let init_shards_request_timeout = self.timeouts.init_shards_request_timeout;
+
+        let retry_init_shards_fut = async move {
+            let mut shards_to_init = shards_to_retry;
+            let mut successes = Vec::new();
+            let mut failures = Vec::new();
+
+            // The shards have already been through one attempt on their original leader and,
+            // possibly, one on an alternative leader.
+            for num_attempts in 1..MAX_INIT_SHARDS_ATTEMPTS {
+                let backoff = INIT_SHARDS_RETRY_BASE_DELAY * 2u32.pow(num_attempts - 1);
+                tokio::time::sleep(backoff).await;
+
+                shards_to_init = try_init_shards(
+                    &ingester_pool,
+                    init_shards_request_timeout,
+                    shards_to_init,
+                    &mut successes,
+                    &mut failures,
+                )
+                .await;
+
+                if shards_to_init.is_empty() {
+                    break;
+                }
+            }
+            if !shards_to_init.is_empty() {
+                warn!(
+                    "failed to init {} shards after {MAX_INIT_SHARDS_ATTEMPTS} attempts",
+                    shards_to_init.len()
+                );
+                failures.extend(
+                    shards_to_init
+                        .into_iter()
+                        .map(|(_, init_shard_failure)| init_shard_failure),
+                );
+            }
+            let Some(mailbox) = weak_mailbox.upgrade() else {
+                return;
+            };
+            let callback = InitShardsRetryCallback {
+                init_shards_response: InitShardsResponse {
+                    successes,
+                    failures,
+                },
+            };
+            let _ = mailbox.send_message(callback).await;
+        };
+        tokio::spawn(retry_init_shards_fut);
+    }
+
+    /// Handles the outcome of the background init shards retries: the shards eventually
+    /// initialized are added to the model, and the shards that could not be initialized are
+    /// deleted from the metastore.
+    pub(crate) async fn handle_init_shards_retry_callback(
+        &mut self,
+        callback: InitShardsRetryCallback,
+        model: &mut ControlPlaneModel,
+        progress: &Progress,
+    ) {
+        let InitShardsResponse {
+            successes,
+            failures,
+        } = callback.init_shards_response;
+
+        self.stats.record_opened_shards(successes.len());
+
+        for init_shard_success in successes {
+            let shard = init_shard_success.shard().clone();
+            let index_uid = shard.index_uid().clone();
+            let source_id = shard.source_id.clone();
+            self.shard_event_log
+                .record(ShardEventType::Opened, &shard, "init shards retry");
+            model.insert_shards(&index_uid, &source_id, vec![shard]);
+        }
+        let mut failed_shards: HashMap<SourceUid, Vec<ShardId>> = HashMap::default();
 
-        for subresponse in open_shards_subresponses {
-            let shard = subresponse.open_shard();
-            let init_shards_subrequest = InitShardSubrequest {
-                subrequest_id: subresponse.subrequest_id,
-                shard: Some(shard.clone()),
+        for init_shard_failure in failures {
+            let source_uid = SourceUid {
+                index_uid: init_shard_failure.index_uid().clone(),
+                source_id: init_shard_failure.source_id.clone(),
             };
-            per_leader_shards_to_init
-                .entry(&shard.leader_id)
+            failed_shards
+                .entry(source_uid)
                 .or_default()
-                .push(init_shards_subrequest);
+                .push(init_shard_failure.shard_id().clone());
+        }
+        for (source_uid, shard_ids) in failed_shards {
+            // The failed shards were never handed to the routers, so they contain no data.
+            let delete_shards_request = metastore::DeleteShardsRequest {
+                index_uid: Some(source_uid.index_uid),
+                source_id: source_uid.source_id,
+                shard_ids,
+                force: true,
+            };
+            if let Err(error) = progress
+                .protect_future(self.metastore.delete_shards(delete_shards_request))
+                .await
+            {
+                warn!("failed to delete shards that failed to init: {error}");
+            }
         }
-        let mut init_shards_futures = FuturesUnordered::new();
+    }
 
-        for (leader_id, subrequests) in per_leader_shards_to_init {
-            let init_shard_failures: Vec<InitShardFailure> = subrequests
-                .iter()
-                .map(|subrequest| {
-                    let shard = subrequest.shard();
-
-                    InitShardFailure {
-                        subrequest_id: subrequest.subrequest_id,
-                        index_uid: Some(shard.index_uid().clone()),
-                        source_id: shard.source_id.clone(),
-                        shard_id: Some(shard.shard_id().clone()),
-                    }
-                })
-                .collect();
-            let Some(mut leader) = self.ingester_pool.get(leader_id) else {
-                warn!("failed to init shards: ingester `{leader_id}` is unavailable");
-                failures.extend(init_shard_failures);
-                continue;
+    /// Reopens the shards that failed to initialize on alternative leaders. The reopened shards get
+    /// new IDs. Beforehand, the original shards are closed on their leaders, which may have
+    /// initialized them despite reporting an error, and deleted from the metastore, so that the
+    /// same shard is never open on two leaders.
+    ///
+    /// Returns the reopened shards and the shards that must be retried on their original leaders
+    /// because no alternative leaders are available. Shards that were abandoned but could not be
+    /// reopened are appended to `failures`.
+    async fn reopen_shards_on_alternative_leaders(
+        &mut self,
+        shards_to_reopen: Vec<(InitShardSubrequest, InitShardFailure)>,
+        model: &ControlPlaneModel,
+        failures: &mut Vec<InitShardFailure>,
+        progress: &Progress,
+    ) -> (
+        Vec<(InitShardSubrequest, InitShardFailure)>,
+        Vec<(InitShardSubrequest, InitShardFailure)>,
+    ) {
+        let failed_leaders: FnvHashSet<NodeId> = shards_to_reopen
+            .iter()
+            .map(|(init_shard_subrequest, _)| {
+                NodeId::from(init_shard_subrequest.shard().leader_id.clone())
+            })
+            .collect();
+        let has_alternative_leaders = self
+            .ingester_pool
+            .keys()
+            .iter()
+            .any(|ingester| !failed_leaders.contains(ingester));
+
+        if !has_alternative_leaders {
+            return (Vec::new(), shards_to_reopen);
+        }
+        let replication_factors: Vec<usize> = shards_to_reopen
+            .iter()
+            .map(|(init_shard_subrequest, _)| {
+                self.index_replication_factor(init_shard_subrequest.shard().index_uid(), model)
+            })
+            .collect();
+        let Some(leader_follower_pairs) =
+            self.allocate_shards(&replication_factors, &failed_leaders, model)
+        else {
+            return (Vec::new(), shards_to_reopen);
+        };
+        let shards_to_close = shards_to_reopen.iter().map(|(init_shard_subrequest, _)| {
+            let abandoned_shard = init_shard_subrequest.shard();
+            let leader_id = NodeId::from(abandoned_shard.leader_id.clone());
+            let shard_pkey = ShardPKey {
+                index_uid: abandoned_shard.index_uid.clone(),
+                source_id: abandoned_shard.source_id.clone(),
+                shard_id: abandoned_shard.shard_id.clone(),
             };
-            let init_shards_request = InitShardsRequest { subrequests };
-            let init_shards_future = async move {
-                let init_shards_result = tokio::time::timeout(
-                    INIT_SHARDS_REQUEST_TIMEOUT,
-                    leader.init_shards(init_shards_request),
-                )
-                .await;
-                (leader_id.clone(), init_shards_result, init_shard_failures)
+            (leader_id, shard_pkey)
+        });
+        progress
+            .protect_future(self.close_shards(shards_to_close))
+            .await;
+
+        let mut abandoned_shards: HashMap<SourceUid, Vec<ShardId>> = HashMap::default();
+
+        for (init_shard_subrequest, _) in &shards_to_reopen {
+            let abandoned_shard = init_shard_subrequest.shard();
+            let source_uid = SourceUid {
+                index_uid: abandoned_shard.index_uid().clone(),
+                source_id: abandoned_shard.source_id.clone(),
+            };
+            abandoned_shards
+                .entry(source_uid)
+                .or_default()
+                .push(abandoned_shard.shard_id().clone());
+        }
+        for (source_uid, shard_ids) in abandoned_shards {
+            // The abandoned shards were never handed to the routers, so they contain no data.
+            let delete_shards_request = metastore::DeleteShardsRequest {
+                index_uid: Some(source_uid.index_uid),
+                source_id: source_uid.source_id,
+                shard_ids,
+                force: true,
             };
-            init_shards_futures.push(init_shards_future);
+            if let Err(error) = progress
+                .protect_future(self.metastore.delete_shards(delete_shards_request))
+                .await
+            {
+                warn!("failed to delete abandoned shards: {error}");
+            }
         }
-        while let Some((leader_id, init_shards_result, init_shard_failures)) =
-            progress.protect_future(init_shards_futures.next()).await
+        // The subrequest IDs of the metastore request are the positions of the shards in
+        // `shards_to_reopen`, because the subrequest IDs of the original shards may not be unique.
+        let open_shards_subrequests: Vec<metastore::OpenShardSubrequest> =
+            zip(&shards_to_reopen, leader_follower_pairs)
+                .enumerate()
+                .map(
+                    |(position, ((init_shard_subrequest, _), (leader_id, follower_id_opt)))| {
+                        let shard = init_shard_subrequest.shard();
+
+                        metastore::OpenShardSubrequest {
+                            subrequest_id: position as u32,
+                            index_uid: shard.index_uid.clone(),
+                            source_id: shard.source_id.clone(),
+                            shard_id: Some(self.shard_id_generator.next_shard_id()),
+                            leader_id: leader_id.into(),
+                            follower_id: follower_id_opt.map(Into::into),
+                        }
+                    },
+                )
+                .collect();
+        let open_shards_request = metastore::OpenShardsRequest {
+            subrequests: open_shards_subrequests,
+        };
+        let mut reopened_shards: Vec<Option<Shard>> = vec![None; shards_to_reopen.len()];
+
+        match progress
+            .protect_future(self.metastore.open_shards(open_shards_request))
+            .await
         {
-            match init_shards_result {
-                Ok(Ok(init_shards_response)) => {
-                    successes.extend(init_shards_response.successes);
-                    failures.extend(init_shards_response.failures);
-                }
-                Ok(Err(error)) => {
-                    error!(%error, "failed to init shards on `{leader_id}`");
-                    failures.extend(init_shard_failures);
-                }
-                Err(_elapsed) => {
-                    error!("failed to init shards on `{leader_id}`: request timed out");
-                    failures.extend(init_shard_failures);
+            Ok(open_shards_response) => {
+                for subresponse in open_shards_response.subresponses {
+                    let position = subresponse.subrequest_id as usize;
+
+                    if position < reopened_shards.len() {
+                        reopened_shards[position] = subresponse.open_shard;
+                    }
                 }
             }
+            Err(error) => {
+                warn!("failed to reopen shards on alternative leaders: {error}");
+            }
+        };
+        let mut shards_to_init = Vec::with_capacity(shards_to_reopen.len());
+
+        for ((mut init_shard_subrequest, init_shard_failure), reopened_shard_opt) in
+            zip(shards_to_reopen, reopened_shards)
+        {
+            let Some(reopened_shard) = reopened_shard_opt else {
+                failures.push(init_shard_failure);
+                continue;
+            };
+            let abandoned_shard = init_shard_subrequest.shard();
+            info!(
+                index_id=%abandoned_shard.index_uid().index_id,
+                source_id=%abandoned_shard.source_id,
+                shard_id=%abandoned_shard.shard_id(),
+                "reopening shard on ingester `{}` after failing to init it on `{}`",
+                reopened_shard.leader_id,
+                abandoned_shard.leader_id,
+            );
+            init_shard_subrequest.shard = Some(reopened_shard);
+            shards_to_init.push((init_shard_subrequest, init_shard_failure));
         }
-        InitShardsResponse {
-            successes,
-            failures,
-        }
+        (shards_to_init, Vec::new())
     }
 
     /// Attempts to increase the number of shards. This operation is rate limited to avoid creating
-    /// to many shards in a short period of time. As a result, this method may not create any
-    /// shard.
+    /// to many shards in a short period of time and is refused once the ingestion quota of the
+    /// index is exceeded. As a result, this method may not create any shard.
     async fn try_scale_up_shards(
         &mut self,
         source_uid: SourceUid,
@@ -585,12 +1637,31 @@ impl IngestController {
     ) {
         const NUM_PERMITS: u64 = 1;
 
+        if model.is_source_paused(&source_uid) {
+            return;
+        }
+        if model.is_ingestion_quota_exceeded(&source_uid.index_uid) {
+            info!(
+                index_id=%source_uid.index_uid.index_id,
+                source_id=%source_uid.source_id,
+                "ingestion quota exceeded: not scaling up number of shards"
+            );
+            self.stats.record_ingestion_quota_rejection();
+            return;
+        }
+        if model.check_scaling_cooldown(&source_uid, ScalingMode::Up) == Some(false) {
+            self.stats
+                .record_suppressed_scale_shards_op(ScalingMode::Up);
+            return;
+        }
         if !model
             .acquire_scaling_permits(&source_uid, ScalingMode::Up, NUM_PERMITS)
             .unwrap_or(false)
         {
             return;
         }
+        self.stats.record_scale_shards_op(ScalingMode::Up);
+
         let new_num_open_shards = shard_stats.num_open_shards + 1;
 
         info!(
@@ -599,16 +1670,17 @@ impl IngestController {
             "scaling up number of shards to {new_num_open_shards}"
         );
         let unavailable_leaders: FnvHashSet<NodeId> = FnvHashSet::default();
+        let replication_factor = self.index_replication_factor(&source_uid.index_uid, model);
 
         let Some((leader_id, follower_id)) = self
-            .allocate_shards(1, &unavailable_leaders, model)
+            .allocate_shards(&[replication_factor], &unavailable_leaders, model)
             .and_then(|pairs| pairs.into_iter().next())
         else {
             warn!("failed to scale up number of shards: no ingesters available");
             model.release_scaling_permits(&source_uid, ScalingMode::Up, NUM_PERMITS);
             return;
         };
-        let shard_id = ShardId::from(Ulid::new());
+        let shard_id = self.shard_id_generator.next_shard_id();
         let open_shard_subrequest = metastore::OpenShardSubrequest {
             subrequest_id: 0,
             index_uid: source_uid.index_uid.clone().into(),
@@ -632,7 +1704,7 @@ impl IngestController {
             }
         };
         let init_shards_response = self
-            .init_shards(&open_shards_response.subresponses, progress)
+            .init_shards(&open_shards_response.subresponses, model, progress)
             .await;
 
         if init_shards_response.successes.is_empty() {
@@ -644,15 +1716,18 @@ impl IngestController {
             let open_shard = init_shard_success.shard().clone();
             let index_uid = open_shard.index_uid().clone();
             let source_id = open_shard.source_id.clone();
+            self.shard_event_log
+                .record(ShardEventType::Opened, &open_shard, "scale up");
             let open_shards = vec![open_shard];
             model.insert_shards(&index_uid, &source_id, open_shards);
         }
+        model.record_scaling_action(&source_uid, ScalingMode::Up);
     }
 
     /// Attempts to decrease the number of shards. This operation is rate limited to avoid closing
     /// shards too aggressively. As a result, this method may not close any shard.
     async fn try_scale_down_shards(
-        &self,
+        &mut self,
         source_uid: SourceUid,
         shard_stats: ShardStats,
         model: &mut ControlPlaneModel,
@@ -660,12 +1735,19 @@ impl IngestController {
     ) {
         const NUM_PERMITS: u64 = 1;
 
+        if model.check_scaling_cooldown(&source_uid, ScalingMode::Down) == Some(false) {
+            self.stats
+                .record_suppressed_scale_shards_op(ScalingMode::Down);
+            return;
+        }
         if !model
             .acquire_scaling_permits(&source_uid, ScalingMode::Down, NUM_PERMITS)
             .unwrap_or(false)
         {
             return;
         }
+        self.stats.record_scale_shards_op(ScalingMode::Down);
+
         let new_num_open_shards = shard_stats.num_open_shards - 1;
 
         info!(
@@ -695,17 +1777,55 @@ impl IngestController {
             model.release_scaling_permits(&source_uid, ScalingMode::Down, NUM_PERMITS);
             return;
         }
-        model.close_shards(&source_uid, &[shard_id]);
+        let closed_shard_ids = model.close_shards(&source_uid, &[shard_id]);
+        self.record_shard_events(
+            ShardEventType::Closed,
+            &source_uid,
+            &closed_shard_ids,
+            model,
+            "scale down",
+        );
+        model.record_scaling_action(&source_uid, ScalingMode::Down);
+        self.stats.record_closed_shards(1);
     }
 
     pub(crate) fn advise_reset_shards(
         &self,
         request: AdviseResetShardsRequest,
         model: &ControlPlaneModel,
-    ) -> AdviseResetShardsResponse {
+    ) -> ControlPlaneResult<AdviseResetShardsResponse> {
+        self.advise_reset_shards_with_max_response_size(
+            request,
+            model,
+            MAX_ADVISE_RESET_SHARDS_RESPONSE_NUM_BYTES,
+        )
+    }
+
+    /// Advises the ingester to delete or truncate the shards listed in the request, in the order
+    /// of the request. Once the estimated size of the response reaches `max_response_num_bytes`,
+    /// the response is truncated and carries a continuation token: the number of shards of the
+    /// request advised so far.
+    fn advise_reset_shards_with_max_response_size(
+        &self,
+        request: AdviseResetShardsRequest,
+        model: &ControlPlaneModel,
+        max_response_num_bytes: usize,
+    ) -> ControlPlaneResult<AdviseResetShardsResponse> {
         info!("advise reset shards");
         debug!(shard_ids=?summarize_shard_ids(&request.shard_ids), "advise reset shards");
 
+        let num_shards_to_skip = match request.continuation_token.as_deref() {
+            Some(continuation_token) => continuation_token.parse::<usize>().map_err(|_| {
+                let message = format!("invalid continuation token `{continuation_token}`");
+                ControlPlaneError::InvalidArgument(message)
+            })?,
+            None => 0,
+        };
+        let mut num_skipped_shards = 0;
+        let mut num_advised_shards = 0;
+        let mut response_num_bytes = 0;
+        let mut continuation_token_opt: Option<String> = None;
+
         let mut shards_to_delete: Vec<ShardIds> = Vec::new();
         let mut shards_to_truncate: Vec<ShardIdPositions> = Vec::new();
 
@@ -717,16 +1837,29 @@ impl IngestController {
                 index_uid,
                 source_id,
             };
-            let Some(shard_entries) = model.get_shards_for_source(&source_uid) else {
-                // The source no longer exists: we can safely delete all the shards.
-                shards_to_delete.push(shard_ids);
-                continue;
-            };
+            // If the source no longer exists, we can safely delete all its shards.
+            let shard_entries_opt = model.get_shards_for_source(&source_uid);
+
             let mut shard_ids_to_delete = Vec::new();
             let mut shard_positions_to_truncate = Vec::new();
 
             for shard_id in shard_ids.shard_ids {
-                if let Some(shard_entry) = shard_entries.get(&shard_id) {
+                if num_skipped_shards < num_shards_to_skip {
+                    num_skipped_shards += 1;
+                    continue;
+                }
+                if response_num_bytes >= max_response_num_bytes {
+                    let continuation_token = num_shards_to_skip + num_advised_shards;
+                    continuation_token_opt = Some(continuation_token.to_string());
+                    break;
+                }
+                num_advised_shards += 1;
+                response_num_bytes +=
+                    shard_id.as_str().len() + ADVISE_RESET_SHARD_OVERHEAD_NUM_BYTES;
+
+                if let Some(shard_entry) =
+                    shard_entries_opt.and_then(|shard_entries| shard_entries.get(&shard_id))
+                {
                     let publish_position_inclusive =
                         shard_entry.publish_position_inclusive().clone();
 
@@ -752,6 +1885,9 @@ impl IngestController {
                     shard_positions: shard_positions_to_truncate,
                 });
             }
+            if continuation_token_opt.is_some() {
+                break;
+            }
         }
 
         if enabled!(Level::DEBUG) {
@@ -772,10 +1908,12 @@ impl IngestController {
             debug!(shard_ids_to_delete=?summarize_shard_ids(&shards_to_delete), shards_to_truncate=?shards_to_truncate, "advise reset shards response");
         }
 
-        AdviseResetShardsResponse {
+        let response = AdviseResetShardsResponse {
             shards_to_delete,
             shards_to_truncate,
-        }
+            continuation_token: continuation_token_opt,
+        };
+        Ok(response)
     }
 
     /// Moves shards from ingesters with too many shards to ingesters with too few shards. Moving a
@@ -793,39 +1931,15 @@ impl IngestController {
         let Ok(rebalance_guard) = self.rebalance_lock.clone().try_lock_owned() else {
             return None;
         };
-        self.stats.num_rebalance_shards_ops += 1;
-
-        let num_ingesters = self.ingester_pool.len();
-        let mut num_open_shards: usize = 0;
+        self.stats.record_rebalance_shards_op();
 
+        let num_ingesters = self.num_rebalance_target_ingesters();
         if num_ingesters == 0 {
             return None;
         }
-        let mut per_leader_open_shards: HashMap<&str, Vec<&ShardEntry>> =
-            HashMap::with_capacity(num_ingesters);
-
-        for shard in model.all_shards() {
-            if shard.is_open() {
-                num_open_shards += 1;
-
-                per_leader_open_shards
-                    .entry(&shard.leader_id)
-                    .or_default()
-                    .push(shard);
-            }
-        }
-        let num_open_shards_per_leader_target = num_open_shards / num_ingesters;
-        let num_open_shards_per_leader_threshold = cmp::max(
-            num_open_shards_per_leader_target * 12 / 10,
-            num_open_shards_per_leader_target + 1,
-        );
-        let mut shards_to_move: Vec<&ShardEntry> = Vec::new();
+        let shards_to_move =
+            find_shards_to_rebalance(model, &self.decommissioning_ingesters, num_ingesters);
 
-        for open_shards in per_leader_open_shards.values() {
-            if open_shards.len() > num_open_shards_per_leader_threshold {
-                shards_to_move.extend(&open_shards[num_open_shards_per_leader_threshold..]);
-            }
-        }
         if shards_to_move.is_empty() {
             return None;
         }
@@ -833,8 +1947,12 @@ impl IngestController {
         let num_shards_to_move = shards_to_move.len();
         let unavailable_leaders: FnvHashSet<NodeId> = FnvHashSet::default();
 
+        let replication_factors: Vec<usize> = shards_to_move
+            .iter()
+            .map(|shard| self.index_replication_factor(shard.index_uid(), model))
+            .collect();
         let leader_follower_pairs =
-            self.allocate_shards(num_shards_to_move, &unavailable_leaders, model)?;
+            self.allocate_shards(&replication_factors, &unavailable_leaders, model)?;
         let mut open_shards_subrequests = Vec::with_capacity(num_shards_to_move);
         let mut shards_to_close: HashMap<ShardId, (LeaderId, ShardPKey)> =
             HashMap::with_capacity(num_shards_to_move);
@@ -842,7 +1960,7 @@ impl IngestController {
         for (subrequest_id, (shard_to_move, (leader_id, follower_id_opt))) in
             zip(&shards_to_move, leader_follower_pairs).enumerate()
         {
-            let shard_id = ShardId::from(Ulid::new());
+            let shard_id = self.shard_id_generator.next_shard_id();
             let open_shard_subrequest = metastore::OpenShardSubrequest {
                 subrequest_id: subrequest_id as u32,
                 index_uid: shard_to_move.index_uid.clone(),
@@ -861,28 +1979,229 @@ impl IngestController {
             };
             shards_to_close.insert(shard_id, (leader_id, shard_pkey));
         }
-        let open_shards_request = metastore::OpenShardsRequest {
-            subrequests: open_shards_subrequests,
-        };
-        let open_shards_response = match progress
-            .protect_future(self.metastore.open_shards(open_shards_request))
+        match self
+            .open_then_close_shards(
+                open_shards_subrequests,
+                shards_to_close,
+                rebalance_guard,
+                model,
+                mailbox,
+                progress,
+            )
             .await
         {
-            Ok(open_shards_response) => open_shards_response,
+            Ok((_, join_handle)) => Some(join_handle),
             Err(error) => {
                 error!(%error, "failed to rebalance shards");
-                return None;
+                None
+            }
+        }
+    }
+
+    /// Computes the shards that [`Self::rebalance_shards`] would move and the ingesters they would
+    /// be moved to, along with the resulting number of open shard replicas hosted by each
+    /// ingester. Neither the metastore nor the ingesters are called, and the model is left
+    /// untouched.
+    pub(crate) fn rebalance_shards_dry_run(
+        &self,
+        model: &ControlPlaneModel,
+    ) -> RebalanceShardsDryRunResponse {
+        let mut per_node_num_replicas: BTreeMap<NodeId, (u32, u32)> = self
+            .ingester_pool
+            .keys()
+            .into_iter()
+            .map(|ingester_id| (ingester_id, (0, 0)))
+            .collect();
+
+        for (_, open_shards) in model.open_shards_per_leader() {
+            for shard in open_shards {
+                for ingester_id in shard.ingesters() {
+                    let (num_replicas_before, num_replicas_after) =
+                        per_node_num_replicas.entry(ingester_id).or_default();
+                    *num_replicas_before += 1;
+                    *num_replicas_after += 1;
+                }
+            }
+        }
+        let mut shard_moves = Vec::new();
+        let num_ingesters = self.num_rebalance_target_ingesters();
+
+        if num_ingesters > 0 {
+            let shards_to_move =
+                find_shards_to_rebalance(model, &self.decommissioning_ingesters, num_ingesters);
+            let replication_factors: Vec<usize> = shards_to_move
+                .iter()
+                .map(|shard| self.index_replication_factor(shard.index_uid(), model))
+                .collect();
+            let leader_follower_pairs = self
+                .plan_shard_allocation(&replication_factors, &FnvHashSet::default(), model)
+                .unwrap_or_default();
+
+            for (shard_to_move, (target_leader_id, target_follower_id_opt)) in
+                zip(shards_to_move, leader_follower_pairs)
+            {
+                for ingester_id in shard_to_move.ingesters() {
+                    if let Some((_, num_replicas_after)) =
+                        per_node_num_replicas.get_mut(&ingester_id)
+                    {
+                        *num_replicas_after -= 1;
+                    }
+                }
+                for ingester_id in [Some(&target_leader_id), target_follower_id_opt.as_ref()]
+                    .into_iter()
+                    .flatten()
+                {
+                    let (_, num_replicas_after) = per_node_num_replicas
+                        .entry(ingester_id.clone())
+                        .or_default();
+                    *num_replicas_after += 1;
+                }
+                let shard_move = ShardMove {
+                    index_uid: shard_to_move.index_uid.clone(),
+                    source_id: shard_to_move.source_id.clone(),
+                    shard_id: shard_to_move.shard_id.clone(),
+                    leader_id: shard_to_move.leader_id.clone(),
+                    follower_id: shard_to_move.follower_id.clone(),
+                    target_leader_id: target_leader_id.into(),
+                    target_follower_id: target_follower_id_opt.map(Into::into),
+                };
+                shard_moves.push(shard_move);
             }
+        }
+        let shard_distribution = per_node_num_replicas
+            .into_iter()
+            .map(
+                |(node_id, (num_replicas_before, num_replicas_after))| IngesterShardDistribution {
+                    node_id: node_id.into(),
+                    num_shard_replicas_before: num_replicas_before,
+                    num_shard_replicas_after: num_replicas_after,
+                },
+            )
+            .collect();
+        RebalanceShardsDryRunResponse {
+            shard_moves,
+            shard_distribution,
+        }
+    }
+
+    /// Projects the number of open shards of a source at its current ingestion rate and at the
+    /// hypothetical ingestion rates of the request, using the same scaling thresholds as
+    /// [`Self::handle_local_shards_update`].
+    pub(crate) fn project_source_shards(
+        &self,
+        request: ProjectSourceShardsRequest,
+        model: &ControlPlaneModel,
+    ) -> ControlPlaneResult<ProjectSourceShardsResponse> {
+        let Some(index_uid) = model.index_uid(&request.index_id) else {
+            let entity = EntityKind::Index {
+                index_id: request.index_id,
+            };
+            return Err(ControlPlaneError::Metastore(MetastoreError::NotFound(
+                entity,
+            )));
+        };
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: request.source_id.clone(),
+        };
+        if model.get_shards_for_source(&source_uid).is_none() {
+            let entity = EntityKind::Source {
+                index_id: request.index_id,
+                source_id: request.source_id,
+            };
+            return Err(ControlPlaneError::Metastore(MetastoreError::NotFound(
+                entity,
+            )));
+        }
+        if let Some(ingestion_rate) = request
+            .ingestion_rates_mib_per_sec
+            .iter()
+            .find(|ingestion_rate| !ingestion_rate.is_finite() || **ingestion_rate < 0.)
+        {
+            let message = format!("invalid ingestion rate `{ingestion_rate}`");
+            return Err(ControlPlaneError::InvalidArgument(message));
+        }
+        let shard_stats = model.shard_stats(&source_uid);
+        let ingestion_rate = shard_stats.avg_ingestion_rate * shard_stats.num_open_shards as f32;
+        let target_num_open_shards = target_num_open_shards(
+            shard_stats.num_open_shards,
+            ingestion_rate,
+            &self.scaling_thresholds,
+        );
+        let replication_factor = self.index_replication_factor(&index_uid, model);
+
+        let projections = request
+            .ingestion_rates_mib_per_sec
+            .into_iter()
+            .map(|ingestion_rate| {
+                let num_open_shards = target_num_open_shards(
+                    shard_stats.num_open_shards,
+                    ingestion_rate,
+                    &self.scaling_thresholds,
+                );
+                ShardCountProjection {
+                    ingestion_rate_mib_per_sec: ingestion_rate,
+                    num_open_shards: num_open_shards as u32,
+                    num_shard_replicas: (num_open_shards * replication_factor) as u32,
+                }
+            })
+            .collect();
+        let response = ProjectSourceShardsResponse {
+            index_uid: Some(index_uid),
+            source_id: request.source_id,
+            num_open_shards: shard_stats.num_open_shards as u32,
+            ingestion_rate_mib_per_sec: ingestion_rate,
+            target_num_open_shards: target_num_open_shards as u32,
+            scale_up_threshold_mib_per_sec: self.scaling_thresholds.scale_up_mib_per_sec,
+            scale_down_threshold_mib_per_sec: self.scaling_thresholds.scale_down_mib_per_sec,
+            replication_factor: replication_factor as u32,
+            projections,
+        };
+        Ok(response)
+    }
+
+    /// Returns the number of ingesters that can receive shards moved by the rebalancer.
+    fn num_rebalance_target_ingesters(&self) -> usize {
+        self.ingester_pool
+            .keys()
+            .into_iter()
+            .filter(|ingester| !self.decommissioning_ingesters.contains(ingester))
+            .count()
+    }
+
+    /// Opens the shards described by `open_shards_subrequests` and, once they are initialized,
+    /// closes the shards they replace. The shards are closed in the background after a short delay
+    /// to give the ingesters some time to learn about the new shards. Returns the newly opened
+    /// shards.
+    async fn open_then_close_shards(
+        &mut self,
+        open_shards_subrequests: Vec<metastore::OpenShardSubrequest>,
+        mut shards_to_close: HashMap<ShardId, (LeaderId, ShardPKey)>,
+        rebalance_guard: OwnedMutexGuard<()>,
+        model: &mut ControlPlaneModel,
+        mailbox: &Mailbox<ControlPlane>,
+        progress: &Progress,
+    ) -> ControlPlaneResult<(Vec<Shard>, JoinHandle<()>)> {
+        let open_shards_request = metastore::OpenShardsRequest {
+            subrequests: open_shards_subrequests,
         };
+        let open_shards_response = progress
+            .protect_future(self.metastore.open_shards(open_shards_request))
+            .await?;
         let init_shards_response = self
-            .init_shards(&open_shards_response.subresponses, progress)
+            .init_shards(&open_shards_response.subresponses, model, progress)
             .await;
 
+        let mut opened_shards = Vec::with_capacity(init_shards_response.successes.len());
+
         for init_shard_success in init_shards_response.successes {
             let shard = init_shard_success.shard().clone();
             let index_uid = shard.index_uid().clone();
             let source_id = shard.source_id.clone();
-            model.insert_shards(&index_uid, &source_id, vec![shard]);
+            self.shard_event_log
+                .record(ShardEventType::Opened, &shard, "rebalance");
+            model.insert_shards(&index_uid, &source_id, vec![shard.clone()]);
+            opened_shards.push(shard);
 
             let source_uid = SourceUid {
                 index_uid,
@@ -896,13 +2215,21 @@ impl IngestController {
             let shard_id = init_shard_failure.shard_id();
             shards_to_close.remove(shard_id);
         }
+        for (new_shard_id, (_, shard_pkey)) in &shards_to_close {
+            if let Some(shard_entry) = model.find_shard(shard_pkey.shard_id()) {
+                let reason = format!("moved to shard `{new_shard_id}`");
+                self.shard_event_log
+                    .record(ShardEventType::Moved, shard_entry, &reason);
+            }
+        }
         let close_shards_fut = self.close_shards(shards_to_close.into_values());
         let mailbox_clone = mailbox.clone();
+        let close_shards_upon_rebalance_delay = self.timeouts.close_shards_upon_rebalance_delay;
 
         let close_shards_and_send_callback_fut = async move {
             // We wait for a few seconds before closing the shards to give the ingesters some time
             // to learn about the ones we just opened via gossip.
-            tokio::time::sleep(CLOSE_SHARDS_UPON_REBALANCE_DELAY).await;
+            tokio::time::sleep(close_shards_upon_rebalance_delay).await;
 
             let closed_shards = close_shards_fut.await;
 
@@ -915,22 +2242,441 @@ impl IngestController {
             };
             let _ = mailbox_clone.send_message(callback).await;
         };
-        Some(tokio::spawn(close_shards_and_send_callback_fut))
+        Ok((
+            opened_shards,
+            tokio::spawn(close_shards_and_send_callback_fut),
+        ))
     }
 
-    fn close_shards(
-        &self,
-        shards_to_close: impl Iterator<Item = (LeaderId, ShardPKey)>,
-    ) -> impl Future<Output = Vec<ShardPKey>> + Send + 'static {
-        let mut per_leader_shards_to_close: HashMap<LeaderId, Vec<ShardPKey>> = HashMap::new();
-
-        for (leader_id, shard_pkey) in shards_to_close {
+    /// Starts decommissioning an ingester: the ingester no longer receives new shards, and its open
+    /// shards are moved to other ingesters using the rebalance machinery. Once it no longer leads
+    /// any open shards, the ingester is asked to decommission itself (see
+    /// [`Self::advance_decommissioning_ingesters`]). It then advertises its status in the cluster
+    /// state, and every node evicts it from its ingester pool once it is decommissioned.
+    ///
+    /// This operation is idempotent and can be polled to track the progress of the
+    /// decommissioning. It fails with a not found error if the ingester is neither part of the
+    /// cluster, nor hosting shards, nor known to be decommissioning or decommissioned.
+    pub(crate) async fn decommission_ingester(
+        &mut self,
+        request: DecommissionIngesterRequest,
+        model: &mut ControlPlaneModel,
+        mailbox: &Mailbox<ControlPlane>,
+        progress: &Progress,
+    ) -> ControlPlaneResult<DecommissionIngesterResponse> {
+        let ingester_id = NodeId::from(request.node_id);
+        let num_remaining_shards = model.num_shards_for_node(&ingester_id);
+
+        if num_remaining_shards == 0 && !self.ingester_pool.contains_key(&ingester_id) {
+            let was_decommissioning = self.decommissioning_ingesters.remove(&ingester_id);
+
+            if !was_decommissioning && !self.decommissioned_ingesters.contains(&ingester_id) {
+                let message = format!("ingester `{ingester_id}` not found");
+                return Err(ControlPlaneError::NotFound(message));
+            }
+            self.decommissioned_ingesters.insert(ingester_id);
+
+            let response = DecommissionIngesterResponse {
+                num_remaining_shards: 0,
+                is_decommissioned: true,
+            };
+            return Ok(response);
+        }
+        // The ingester may have rejoined the cluster after a previous decommissioning.
+        self.decommissioned_ingesters.remove(&ingester_id);
+
+        if self.decommissioning_ingesters.insert(ingester_id.clone()) {
+            info!("decommissioning ingester `{ingester_id}` hosting {num_remaining_shards} shards");
+        }
+        // If a rebalance operation is already in progress, the open shards of the ingester will be
+        // moved during the next iteration of the control loop.
+        self.rebalance_shards(model, mailbox, progress).await;
+        self.advance_decommissioning_ingesters(model);
+
+        // The rebalance may have moved some shards off the ingester already.
+        let num_remaining_shards = model.num_shards_for_node(&ingester_id);
+
+        let response = DecommissionIngesterResponse {
+            num_remaining_shards: num_remaining_shards as u32,
+            is_decommissioned: false,
+        };
+        Ok(response)
+    }
+
+    /// Tracks an ingester that advertises a `Decommissioning` or `Decommissioned` status, so that
+    /// decommissions started before a control plane restart are carried on.
+    pub(crate) fn track_decommissioning_ingester(&mut self, ingester_id: NodeId) {
+        if self.decommissioning_ingesters.insert(ingester_id.clone()) {
+            info!("tracking decommissioning ingester `{ingester_id}`");
+        }
+    }
+
+    /// Asks the decommissioning ingesters that no longer lead any open shards to decommission
+    /// themselves, and forgets the ones that have left the ingester pool, either because they are
+    /// decommissioned or because they left the cluster.
+    ///
+    /// The decommission RPC is sent once per ingester, and sent again only if it failed.
+    pub(crate) fn advance_decommissioning_ingesters(&mut self, model: &ControlPlaneModel) {
+        let leaders_with_open_shards: FnvHashSet<&NodeId> = model
+            .num_open_shards_per_leader()
+            .filter(|(_, num_open_shards)| *num_open_shards > 0)
+            .map(|(leader_id, _)| leader_id)
+            .collect();
+        let mut decommission_requests_guard = self
+            .decommission_requests
+            .lock()
+            .expect("lock should not be poisoned");
+
+        self.decommissioning_ingesters.retain(|ingester_id| {
+            let Some(mut ingester) = self.ingester_pool.get(ingester_id) else {
+                info!("ingester `{ingester_id}` is decommissioned");
+                decommission_requests_guard.remove(ingester_id);
+                self.decommissioned_ingesters.insert(ingester_id.clone());
+                return false;
+            };
+            if leaders_with_open_shards.contains(ingester_id)
+                || !decommission_requests_guard.insert(ingester_id.clone())
+            {
+                return true;
+            }
+            // Decommissioning is idempotent on the ingester side: it closes its remaining shards
+            // and reports `Decommissioned` once they are all indexed.
+            let ingester_id = ingester_id.clone();
+            let decommission_requests = self.decommission_requests.clone();
+
+            let decommission_fut = async move {
+                if let Err(error) = ingester.decommission(DecommissionRequest {}).await {
+                    warn!(%error, "failed to decommission ingester `{ingester_id}`");
+
+                    decommission_requests
+                        .lock()
+                        .expect("lock should not be poisoned")
+                        .remove(&ingester_id);
+                }
+            };
+            fire_and_forget(decommission_fut, "decommission_ingester");
+            true
+        });
+    }
+
+    /// Moves an open shard to the target ingester using the same open-then-close flow as the
+    /// rebalance operation: a new shard is opened with the target ingester as leader, then the
+    /// original shard is closed.
+    pub(crate) async fn move_shard(
+        &mut self,
+        request: MoveShardRequest,
+        model: &mut ControlPlaneModel,
+        mailbox: &Mailbox<ControlPlane>,
+        progress: &Progress,
+    ) -> ControlPlaneResult<MoveShardResponse> {
+        let shard_id = request.shard_id().clone();
+        let target_ingester_id = NodeId::from(request.target_ingester_id);
+
+        let Some(shard_to_move) = model.find_shard(&shard_id).cloned() else {
+            let entity = EntityKind::Shard {
+                queue_id: shard_id.to_string(),
+            };
+            return Err(ControlPlaneError::Metastore(MetastoreError::NotFound(
+                entity,
+            )));
+        };
+        if !shard_to_move.is_open() {
+            let message = format!("shard `{shard_id}` is not open");
+            return Err(ControlPlaneError::InvalidArgument(message));
+        }
+        if shard_to_move.leader_id == target_ingester_id.as_str() {
+            let message =
+                format!("shard `{shard_id}` is already hosted by ingester `{target_ingester_id}`");
+            return Err(ControlPlaneError::InvalidArgument(message));
+        }
+        let is_available = |ingester_id: &NodeId| {
+            self.ingester_pool.contains_key(ingester_id)
+                && !self.decommissioning_ingesters.contains(ingester_id)
+        };
+        if !is_available(&target_ingester_id) {
+            let message = format!("ingester `{target_ingester_id}` is not available");
+            return Err(ControlPlaneError::Unavailable(message));
+        }
+        let replication_factor = self.index_replication_factor(shard_to_move.index_uid(), model);
+
+        let follower_id_opt = if replication_factor > 1 {
+            // We keep the current follower if possible to avoid moving data unnecessarily.
+            let current_follower_opt =
+                shard_to_move
+                    .follower_id
+                    .clone()
+                    .map(NodeId::from)
+                    .filter(|follower_id| {
+                        *follower_id != target_ingester_id && is_available(follower_id)
+                    });
+            // Otherwise, the available ingester hosting the fewest shards becomes the follower.
+            let follower_id_opt = current_follower_opt.or_else(|| {
+                self.ingester_pool
+                    .keys()
+                    .into_iter()
+                    .filter(|ingester_id| {
+                        *ingester_id != target_ingester_id && is_available(ingester_id)
+                    })
+                    .min_by_key(|ingester_id| {
+                        (model.num_shards_for_node(ingester_id), ingester_id.clone())
+                    })
+            });
+            let Some(follower_id) = follower_id_opt else {
+                let message = format!(
+                    "failed to move shard `{shard_id}`: replication factor is greater than the \
+                     number of available ingesters"
+                );
+                return Err(ControlPlaneError::Unavailable(message));
+            };
+            Some(follower_id)
+        } else {
+            None
+        };
+        let Ok(rebalance_guard) = self.rebalance_lock.clone().try_lock_owned() else {
+            let message = format!(
+                "failed to move shard `{shard_id}`: a rebalance operation is already in progress"
+            );
+            return Err(ControlPlaneError::Unavailable(message));
+        };
+        info!(
+            shard_id=%shard_id,
+            leader_id=%shard_to_move.leader_id,
+            "moving shard to ingester `{target_ingester_id}`"
+        );
+        let new_shard_id = self.shard_id_generator.next_shard_id();
+        let open_shard_subrequest = metastore::OpenShardSubrequest {
+            subrequest_id: 0,
+            index_uid: shard_to_move.index_uid.clone(),
+            source_id: shard_to_move.source_id.clone(),
+            shard_id: Some(new_shard_id.clone()),
+            leader_id: target_ingester_id.into(),
+            follower_id: follower_id_opt.map(Into::into),
+        };
+        let shard_pkey = ShardPKey {
+            index_uid: shard_to_move.index_uid.clone(),
+            source_id: shard_to_move.source_id.clone(),
+            shard_id: shard_to_move.shard_id.clone(),
+        };
+        let leader_id = NodeId::from(shard_to_move.leader_id.clone());
+        let shards_to_close = HashMap::from_iter([(new_shard_id, (leader_id, shard_pkey))]);
+
+        let (opened_shards, _join_handle) = self
+            .open_then_close_shards(
+                vec![open_shard_subrequest],
+                shards_to_close,
+                rebalance_guard,
+                model,
+                mailbox,
+                progress,
+            )
+            .await?;
+
+        let Some(opened_shard) = opened_shards.into_iter().next() else {
+            let message =
+                format!("failed to move shard `{shard_id}`: failed to initialize new shard");
+            return Err(ControlPlaneError::Internal(message));
+        };
+        let response = MoveShardResponse {
+            shard: Some(opened_shard),
+        };
+        Ok(response)
+    }
+
+    /// Closes all the open shards of an index and marks them as closed in the model. Returns the
+    /// number of shards closed.
+    pub(crate) async fn close_index_shards(
+        &mut self,
+        index_uid: &IndexUid,
+        model: &mut ControlPlaneModel,
+        progress: &Progress,
+    ) -> usize {
+        let shards_to_close: Vec<(LeaderId, ShardPKey)> = model
+            .list_shards_for_index(index_uid)
+            .filter(|shard| shard.is_open())
+            .map(leader_and_shard_pkey)
+            .collect();
+        self.close_open_shards(shards_to_close, model, progress, "index shards closed")
+            .await
+    }
+
+    /// Closes all the open shards of a source and marks them as closed in the model. Returns the
+    /// number of shards closed.
+    pub(crate) async fn close_source_shards(
+        &mut self,
+        source_uid: &SourceUid,
+        model: &mut ControlPlaneModel,
+        progress: &Progress,
+    ) -> usize {
+        let shards_to_close: Vec<(LeaderId, ShardPKey)> = model
+            .get_shards_for_source(source_uid)
+            .into_iter()
+            .flat_map(|shard_entries| shard_entries.values())
+            .filter(|shard| shard.is_open())
+            .map(leader_and_shard_pkey)
+            .collect();
+        self.close_open_shards(shards_to_close, model, progress, "source paused")
+            .await
+    }
+
+    async fn close_open_shards(
+        &mut self,
+        shards_to_close: Vec<(LeaderId, ShardPKey)>,
+        model: &mut ControlPlaneModel,
+        progress: &Progress,
+        reason: &str,
+    ) -> usize {
+        if shards_to_close.is_empty() {
+            return 0;
+        }
+        let closed_shards = progress
+            .protect_future(self.close_shards(shards_to_close.into_iter()))
+            .await;
+        let num_closed_shards = closed_shards.len();
+        self.stats.record_closed_shards(num_closed_shards);
+
+        for closed_shard in closed_shards {
+            let shard_id = closed_shard.shard_id().clone();
+            let source_uid = SourceUid {
+                index_uid: closed_shard.index_uid().clone(),
+                source_id: closed_shard.source_id,
+            };
+            let closed_shard_ids = model.close_shards(&source_uid, &[shard_id]);
+            self.record_shard_events(
+                ShardEventType::Closed,
+                &source_uid,
+                &closed_shard_ids,
+                model,
+                reason,
+            );
+        }
+        num_closed_shards
+    }
+
+    /// Closes the shards listed in the request regardless of their state. The shards are marked as
+    /// closed in the metastore first, then in the model. Finally, their leaders are asked to close
+    /// them on a best-effort basis since a leader may be unavailable or may have lost its WAL.
+    pub(crate) async fn force_close_shards(
+        &mut self,
+        request: ForceCloseShardsRequest,
+        model: &mut ControlPlaneModel,
+        progress: &Progress,
+    ) -> ControlPlaneResult<ForceCloseShardsResponse> {
+        if request.shard_pkeys.is_empty() {
+            let message = "no shards to close were provided".to_string();
+            return Err(ControlPlaneError::InvalidArgument(message));
+        }
+        let reason = request.reason.trim();
+
+        if reason.is_empty() {
+            let message = "a reason for force-closing the shards must be provided".to_string();
+            return Err(ControlPlaneError::InvalidArgument(message));
+        }
+        let mut per_source_shard_ids: BTreeMap<SourceUid, Vec<ShardId>> = BTreeMap::new();
+        let mut shards_to_close: Vec<(LeaderId, ShardPKey)> =
+            Vec::with_capacity(request.shard_pkeys.len());
+
+        // We validate the whole request before closing any shard.
+        for shard_pkey in &request.shard_pkeys {
+            if shard_pkey.index_uid.is_none() || shard_pkey.shard_id.is_none() {
+                let message = "shard primary keys must include an index UID and a shard ID";
+                return Err(ControlPlaneError::InvalidArgument(message.to_string()));
+            }
+            let source_uid = SourceUid {
+                index_uid: shard_pkey.index_uid().clone(),
+                source_id: shard_pkey.source_id.clone(),
+            };
+            let shard_id = shard_pkey.shard_id();
+
+            let Some(shard_entry) = model
+                .get_shards_for_source(&source_uid)
+                .and_then(|shard_entries| shard_entries.get(shard_id))
+            else {
+                let entity = EntityKind::Shard {
+                    queue_id: shard_pkey.queue_id(),
+                };
+                return Err(ControlPlaneError::Metastore(MetastoreError::NotFound(
+                    entity,
+                )));
+            };
+            let leader_id = NodeId::from(shard_entry.leader_id.clone());
+            let shard_ids = per_source_shard_ids.entry(source_uid).or_default();
+
+            if !shard_ids.contains(shard_id) {
+                shard_ids.push(shard_id.clone());
+                shards_to_close.push((leader_id, shard_pkey.clone()));
+            }
+        }
+        warn!(
+            num_shards=%shards_to_close.len(),
+            reason=%reason,
+            "force-closing shards"
+        );
+        let event_reason = format!("force close: {reason}");
+        let mut closed_shard_pkeys = Vec::new();
+
+        for (source_uid, shard_ids) in per_source_shard_ids {
+            let mark_shards_closed_request = metastore::MarkShardsClosedRequest {
+                index_uid: Some(source_uid.index_uid.clone()),
+                source_id: source_uid.source_id.clone(),
+                shard_ids: shard_ids.clone(),
+            };
+            progress
+                .protect_future(
+                    self.metastore
+                        .mark_shards_closed(mark_shards_closed_request),
+                )
+                .await?;
+
+            let closed_shard_ids = model.close_shards(&source_uid, &shard_ids);
+            self.record_shard_events(
+                ShardEventType::Closed,
+                &source_uid,
+                &closed_shard_ids,
+                model,
+                &event_reason,
+            );
+            self.stats.record_closed_shards(closed_shard_ids.len());
+
+            info!(
+                target: AUDIT_LOG_TARGET,
+                index_uid=%source_uid.index_uid,
+                source_id=%source_uid.source_id,
+                shard_ids=?shard_ids,
+                reason=%reason,
+                "force-closed {} shards",
+                shard_ids.len()
+            );
+            closed_shard_pkeys.extend(closed_shard_ids.into_iter().map(|shard_id| ShardPKey {
+                index_uid: Some(source_uid.index_uid.clone()),
+                source_id: source_uid.source_id.clone(),
+                shard_id: Some(shard_id),
+            }));
+        }
+        let acknowledged_shard_pkeys = progress
+            .protect_future(self.close_shards(shards_to_close.into_iter()))
+            .await;
+
+        let response = ForceCloseShardsResponse {
+            closed_shard_pkeys,
+            acknowledged_shard_pkeys,
+        };
+        Ok(response)
+    }
+
+    fn close_shards(
+        &self,
+        shards_to_close: impl Iterator<Item = (LeaderId, ShardPKey)>,
+    ) -> impl Future<Output = Vec<ShardPKey>> + Send + 'static {
+        let mut per_leader_shards_to_close: HashMap<LeaderId, Vec<ShardPKey>> = HashMap::new();
+
+        for (leader_id, shard_pkey) in shards_to_close {
             per_leader_shards_to_close
                 .entry(leader_id)
                 .or_default()
                 .push(shard_pkey);
         }
         let mut close_shards_futures = FuturesUnordered::new();
+        let close_shards_request_timeout = self.timeouts.close_shards_request_timeout;
 
         for (leader_id, shard_pkeys) in per_leader_shards_to_close {
             let Some(mut ingester) = self.ingester_pool.get(&leader_id) else {
@@ -940,7 +2686,7 @@ impl IngestController {
             let shards_to_close_request = CloseShardsRequest { shard_pkeys };
             let close_shards_future = async move {
                 tokio::time::timeout(
-                    CLOSE_SHARDS_REQUEST_TIMEOUT,
+                    close_shards_request_timeout,
                     ingester.close_shards(shards_to_close_request),
                 )
                 .await
@@ -968,6 +2714,16 @@ impl IngestController {
     }
 }
 
+fn leader_and_shard_pkey(shard: &ShardEntry) -> (LeaderId, ShardPKey) {
+    let leader_id = NodeId::from(shard.leader_id.clone());
+    let shard_pkey = ShardPKey {
+        index_uid: shard.index_uid.clone(),
+        source_id: shard.source_id.clone(),
+        shard_id: shard.shard_id.clone(),
+    };
+    (leader_id, shard_pkey)
+}
+
 fn summarize_shard_ids(shard_ids: &[ShardIds]) -> Vec<&str> {
     shard_ids
         .iter()
@@ -989,6 +2745,14 @@ pub(crate) struct RebalanceShardsCallback {
     pub rebalance_guard: OwnedMutexGuard<()>,
 }
 
+/// Shards that fail to initialize with a transient error are retried in the background, so that
+/// the control plane does not wait for the retry backoff while handling a request. The outcome of
+/// these retries is sent back to the control plane with this callback.
+#[derive(Debug)]
+pub(crate) struct InitShardsRetryCallback {
+    pub init_shards_response: InitShardsResponse,
+}
+
 /// Finds the shard with the highest ingestion rate on the ingester with the most number of open
 /// shards. If multiple shards have the same ingestion rate, the shard with the lowest (oldest)
 /// shard ID is chosen.
@@ -1025,6 +2789,161 @@ fn find_scale_down_candidate(
         })
 }
 
+/// Returns the number of open shards the ingest controller converges to, one scaling operation at a
+/// time, when a source with `num_open_shards` open shards receives `ingestion_rate` MiB/s.
+///
+/// The controller scales up while the average ingestion rate per shard is above the scale up
+/// threshold and scales down while it is below the scale down threshold, so any number of shards
+/// between the two bounds is stable.
+fn target_num_open_shards(
+    num_open_shards: usize,
+    ingestion_rate: f32,
+    scaling_thresholds: &ShardScalingThresholds,
+) -> usize {
+    // The first shard of a source is opened on the first ingest request.
+    let num_open_shards = num_open_shards.max(1);
+    let avg_ingestion_rate = ingestion_rate / num_open_shards as f32;
+
+    if avg_ingestion_rate >= scaling_thresholds.scale_up_mib_per_sec {
+        (ingestion_rate / scaling_thresholds.scale_up_mib_per_sec).floor() as usize + 1
+    } else if avg_ingestion_rate <= scaling_thresholds.scale_down_mib_per_sec && num_open_shards > 1
+    {
+        ((ingestion_rate / scaling_thresholds.scale_down_mib_per_sec).ceil() as usize)
+            .saturating_sub(1)
+            .clamp(1, num_open_shards)
+    } else {
+        num_open_shards
+    }
+}
+
+/// Derives the ingestion pressure of a source from the average ingestion rate of its open shards.
+fn compute_ingestion_pressure(shard_stats: &ShardStats) -> IngestionPressure {
+    if shard_stats.avg_ingestion_rate >= HIGH_INGESTION_PRESSURE_THRESHOLD_MIB_PER_SEC {
+        IngestionPressure::High
+    } else if shard_stats.avg_ingestion_rate >= SCALE_UP_SHARDS_THRESHOLD_MIB_PER_SEC {
+        IngestionPressure::Moderate
+    } else {
+        IngestionPressure::Low
+    }
+}
+
+/// Builds the response to a `GetOrCreateOpenShards` subrequest from the open shards of the source,
+/// returned in full or as a delta since the shard table version known by the router.
+fn make_get_or_create_open_shards_success(
+    subrequest_id: u32,
+    source_uid: SourceUid,
+    open_shards_sync: OpenShardsSync,
+    ingestion_pressure: IngestionPressure,
+) -> GetOrCreateOpenShardsSuccess {
+    let open_shards: Vec<Shard> = open_shards_sync
+        .open_shards
+        .into_iter()
+        .map(|shard_entry| shard_entry.shard)
+        .collect();
+    GetOrCreateOpenShardsSuccess {
+        subrequest_id,
+        index_uid: Some(source_uid.index_uid),
+        source_id: source_uid.source_id,
+        open_shards,
+        ingestion_pressure: ingestion_pressure as i32,
+        shard_table_version: open_shards_sync.shard_table_version,
+        is_delta: open_shards_sync.is_delta,
+        removed_shard_ids: open_shards_sync.removed_shard_ids,
+    }
+}
+
+/// Selects the open shards to move so that the number of shard replicas, leaders and followers,
+/// hosted by each ingester stays under a threshold. The open shards hosted by decommissioning
+/// ingesters are always selected. For overloaded ingesters, the shards they lead are selected
+/// first, then the shards they follow.
+fn find_shards_to_rebalance<'a>(
+    model: &'a ControlPlaneModel,
+    decommissioning_ingesters: &BTreeSet<NodeId>,
+    num_ingesters: usize,
+) -> Vec<&'a ShardEntry> {
+    let mut num_replicas: usize = 0;
+    let mut per_node_num_replicas: HashMap<&str, usize> = HashMap::with_capacity(num_ingesters);
+    let mut per_leader_open_shards: HashMap<&str, Vec<&ShardEntry>> =
+        HashMap::with_capacity(num_ingesters);
+    let mut per_follower_open_shards: HashMap<&str, Vec<&ShardEntry>> =
+        HashMap::with_capacity(num_ingesters);
+    let mut shards_to_move: Vec<&ShardEntry> = Vec::new();
+
+    for (leader_id, open_shards) in model.open_shards_per_leader() {
+        for shard in open_shards {
+            num_replicas += 1 + shard.follower_id.is_some() as usize;
+
+            // The open shards hosted by decommissioning ingesters, either as leader or
+            // follower, are always moved.
+            if shard
+                .ingesters()
+                .any(|ingester| decommissioning_ingesters.contains(&ingester))
+            {
+                shards_to_move.push(shard);
+                continue;
+            }
+            *per_node_num_replicas.entry(leader_id.as_str()).or_default() += 1;
+            per_leader_open_shards
+                .entry(leader_id.as_str())
+                .or_default()
+                .push(shard);
+
+            if let Some(follower_id) = &shard.follower_id {
+                *per_node_num_replicas
+                    .entry(follower_id.as_str())
+                    .or_default() += 1;
+                per_follower_open_shards
+                    .entry(follower_id.as_str())
+                    .or_default()
+                    .push(shard);
+            }
+        }
+    }
+    let num_replicas_per_node_target = num_replicas / num_ingesters;
+    let num_replicas_per_node_threshold = cmp::max(
+        num_replicas_per_node_target * 12 / 10,
+        num_replicas_per_node_target + 1,
+    );
+    // We relieve the most loaded ingesters first. Moving a shard also relieves the other ingester
+    // hosting a replica of the shard, so we keep the replica counts up to date as we go.
+    let node_ids: Vec<&str> = per_node_num_replicas
+        .iter()
+        .filter(|(_, num_node_replicas)| **num_node_replicas > num_replicas_per_node_threshold)
+        .sorted_by(|(left_id, left_count), (right_id, right_count)| {
+            right_count
+                .cmp(left_count)
+                .then_with(|| left_id.cmp(right_id))
+        })
+        .map(|(node_id, _)| *node_id)
+        .collect();
+    let mut moved_queue_ids: FnvHashSet<QueueId> = FnvHashSet::default();
+
+    for node_id in node_ids {
+        let leader_shards = per_leader_open_shards.get(node_id).into_iter().flatten();
+        let follower_shards = per_follower_open_shards.get(node_id).into_iter().flatten();
+
+        for &shard in leader_shards.chain(follower_shards) {
+            if per_node_num_replicas[node_id] <= num_replicas_per_node_threshold {
+                break;
+            }
+            if !moved_queue_ids.insert(shard.queue_id()) {
+                continue;
+            }
+            for ingester_id in [Some(&shard.leader_id), shard.follower_id.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                if let Some(num_node_replicas) = per_node_num_replicas.get_mut(ingester_id.as_str())
+                {
+                    *num_node_replicas -= 1;
+                }
+            }
+            shards_to_move.push(shard);
+        }
+    }
+    shards_to_move
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1037,16 +2956,16 @@ mod tests {
     use quickwit_actors::Universe;
     use quickwit_common::setup_logging_for_tests;
     use quickwit_common::tower::DelayLayer;
-    use quickwit_config::{SourceConfig, INGEST_V2_SOURCE_ID};
+    use quickwit_config::{IngestionQuotaConfig, SourceConfig, INGEST_V2_SOURCE_ID};
     use quickwit_ingest::{RateMibPerSec, ShardInfo};
     use quickwit_metastore::IndexMetadata;
     use quickwit_proto::control_plane::GetOrCreateOpenShardsSubrequest;
     use quickwit_proto::ingest::ingester::{
-        CloseShardsResponse, IngesterServiceClient, InitShardSuccess, InitShardsResponse,
-        MockIngesterService, RetainShardsResponse,
+        CloseShardsResponse, DecommissionResponse, IngesterServiceClient, InitShardSuccess,
+        InitShardsResponse, MockIngesterService, RetainShardsResponse,
     };
     use quickwit_proto::ingest::{IngestV2Error, Shard, ShardState};
-    use quickwit_proto::metastore::{MetastoreError, MockMetastoreService};
+    use quickwit_proto::metastore::{EmptyResponse, MetastoreError, MockMetastoreService};
     use quickwit_proto::types::{Position, SourceId};
 
     use super::*;
@@ -1128,8 +3047,12 @@ mod tests {
         ingester_pool.insert("test-ingester-2".into(), ingester.clone());
 
         let replication_factor = 2;
-        let mut ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let mut model = ControlPlaneModel::default();
         model.add_index(index_metadata_0.clone());
@@ -1168,6 +3091,7 @@ mod tests {
             subrequests: Vec::new(),
             closed_shards: Vec::new(),
             unavailable_leaders: Vec::new(),
+            router_id: String::new(),
         };
         let response = ingest_controller
             .get_or_create_open_shards(request, &mut model, &progress)
@@ -1182,21 +3106,25 @@ mod tests {
                 subrequest_id: 0,
                 index_id: "test-index-0".to_string(),
                 source_id: source_id.to_string(),
+                known_shard_table_version: None,
             },
             GetOrCreateOpenShardsSubrequest {
                 subrequest_id: 1,
                 index_id: "test-index-1".to_string(),
                 source_id: source_id.to_string(),
+                known_shard_table_version: None,
             },
             GetOrCreateOpenShardsSubrequest {
                 subrequest_id: 2,
                 index_id: "index-not-found".to_string(),
                 source_id: "source-not-found".to_string(),
+                known_shard_table_version: None,
             },
             GetOrCreateOpenShardsSubrequest {
                 subrequest_id: 3,
                 index_id: "test-index-0".to_string(),
                 source_id: "source-not-found".to_string(),
+                known_shard_table_version: None,
             },
         ];
         let closed_shards = Vec::new();
@@ -1205,6 +3133,7 @@ mod tests {
             subrequests,
             closed_shards,
             unavailable_leaders,
+            router_id: String::new(),
         };
         let response = ingest_controller
             .get_or_create_open_shards(request, &mut model, &progress)
@@ -1221,6 +3150,7 @@ mod tests {
         assert_eq!(success.open_shards.len(), 1);
         assert_eq!(success.open_shards[0].shard_id(), ShardId::from(2));
         assert_eq!(success.open_shards[0].leader_id, "test-ingester-1");
+        assert_eq!(success.ingestion_pressure(), IngestionPressure::Low);
 
         let success = &response.successes[1];
         assert_eq!(success.subrequest_id, 1);
@@ -1257,8 +3187,12 @@ mod tests {
         let ingester_pool = IngesterPool::default();
         let replication_factor = 2;
 
-        let mut ingest_controller =
-            IngestController::new(metastore, ingester_pool, replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
         let mut model = ControlPlaneModel::default();
 
         let index_uid = IndexUid::for_test("test-index-0", 0);
@@ -1282,6 +3216,7 @@ mod tests {
                 shard_ids: vec![ShardId::from(1), ShardId::from(2)],
             }],
             unavailable_leaders: Vec::new(),
+            router_id: String::new(),
         };
         let progress = Progress::default();
 
@@ -1295,6 +3230,106 @@ mod tests {
             .find(|shard| shard.shard_id() == ShardId::from(1))
             .unwrap();
         assert!(shard_1.is_closed());
+
+        let shard_events = ingest_controller
+            .shard_event_log
+            .events(&Default::default());
+        assert_eq!(shard_events.len(), 1);
+        assert_eq!(shard_events[0].event_type(), ShardEventType::Closed);
+        assert_eq!(shard_events[0].shard_id(), ShardId::from(1));
+        assert_eq!(shard_events[0].leader_id, "test-ingester-0");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_enforces_ingestion_quota() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+        let mut model = ControlPlaneModel::default();
+
+        let ingestion_quota = IngestionQuotaConfig {
+            tenant_id: Some("acme".to_string()),
+            max_ingestion_rate_mib_per_sec: 10.,
+        };
+        let mut index_uids = Vec::new();
+
+        for index_id in ["test-index-0", "test-index-1"] {
+            let mut index_metadata =
+                IndexMetadata::for_test(index_id, &format!("ram:///indexes/{index_id}"));
+            index_metadata
+                .index_config
+                .indexing_settings
+                .ingestion_quota = Some(ingestion_quota.clone());
+            let index_uid = index_metadata.index_uid.clone();
+            model.add_index(index_metadata);
+            model
+                .add_source(&index_uid, SourceConfig::ingest_v2())
+                .unwrap();
+            index_uids.push(index_uid);
+        }
+        let source_uid_0 = SourceUid {
+            index_uid: index_uids[0].clone(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+        };
+        let shard = Shard {
+            index_uid: Some(index_uids[0].clone()),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            shard_id: Some(ShardId::from(1)),
+            leader_id: "test-ingester".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        };
+        model.insert_shards(&index_uids[0], &source_uid_0.source_id, vec![shard]);
+        model
+            .get_shards_for_source_mut(&source_uid_0)
+            .unwrap()
+            .get_mut(&ShardId::from(1))
+            .unwrap()
+            .ingestion_rate = RateMibPerSec(12);
+
+        let request = GetOrCreateOpenShardsRequest {
+            subrequests: vec![GetOrCreateOpenShardsSubrequest {
+                subrequest_id: 0,
+                index_id: "test-index-1".to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
+            }],
+            closed_shards: Vec::new(),
+            unavailable_leaders: Vec::new(),
+            router_id: String::new(),
+        };
+        let progress = Progress::default();
+
+        let response = ingest_controller
+            .get_or_create_open_shards(request, &mut model, &progress)
+            .await
+            .unwrap();
+        assert!(response.successes.is_empty());
+        assert_eq!(response.failures.len(), 1);
+
+        let failure = &response.failures[0];
+        assert_eq!(failure.index_id, "test-index-1");
+        assert_eq!(
+            failure.reason(),
+            GetOrCreateOpenShardsFailureReason::QuotaExceeded
+        );
+
+        let shard_stats = ShardStats {
+            num_open_shards: 1,
+            ..Default::default()
+        };
+        ingest_controller
+            .try_scale_up_shards(source_uid_0, shard_stats, &mut model, &progress)
+            .await;
+        assert_eq!(model.all_shards().count(), 1);
+        assert_eq!(ingest_controller.stats.num_ingestion_quota_rejections, 2);
     }
 
     #[tokio::test]
@@ -1307,8 +3342,12 @@ mod tests {
 
         let replication_factor = 2;
 
-        let mut ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
         let mut model = ControlPlaneModel::default();
 
         let index_uid = IndexUid::for_test("test-index-0", 0);
@@ -1346,6 +3385,7 @@ mod tests {
             subrequests: Vec::new(),
             closed_shards: Vec::new(),
             unavailable_leaders: vec!["test-ingester-0".to_string()],
+            router_id: String::new(),
         };
         let progress = Progress::default();
 
@@ -1371,22 +3411,107 @@ mod tests {
             .find(|shard| shard.shard_id() == ShardId::from(3))
             .unwrap();
         assert!(shard_3.is_open());
+
+        let shard_events = ingest_controller
+            .shard_event_log
+            .events(&Default::default());
+        assert_eq!(shard_events.len(), 1);
+        assert_eq!(shard_events[0].event_type(), ShardEventType::Unavailable);
+        assert_eq!(shard_events[0].shard_id(), ShardId::from(1));
     }
 
-    #[test]
-    fn test_ingest_controller_allocate_shards() {
+    #[tokio::test]
+    async fn test_ingest_controller_get_open_shards_handles_unavailable_leaders_quorum() {
         let metastore = MetastoreServiceClient::mocked();
+
         let ingester_pool = IngesterPool::default();
-        let replication_factor = 2;
+        let ingester_0 = IngesterServiceClient::mocked();
+        ingester_pool.insert("test-ingester-0".into(), ingester_0);
+
+        let replication_factor = 1;
 
-        let ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+        ingest_controller.unavailable_leader_reports =
+            UnavailableLeaderReports::new(2, Duration::from_secs(60));
 
         let mut model = ControlPlaneModel::default();
 
-        let leader_follower_pairs_opt =
-            ingest_controller.allocate_shards(0, &FnvHashSet::default(), &model);
-        assert!(leader_follower_pairs_opt.is_none());
+        let index_uid = IndexUid::for_test("test-index-0", 0);
+        let source_id: SourceId = "test-source".into();
+
+        let shards = vec![Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+            shard_id: Some(ShardId::from(1)),
+            leader_id: "test-ingester-0".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        }];
+        model.insert_shards(&index_uid, &source_id, shards);
+
+        let progress = Progress::default();
+
+        // The leader is still in the ingester pool, so a single router is not trusted.
+        for router_id in ["", "test-router-0", "test-router-0"] {
+            let request = GetOrCreateOpenShardsRequest {
+                subrequests: Vec::new(),
+                closed_shards: Vec::new(),
+                unavailable_leaders: vec!["test-ingester-0".to_string()],
+                router_id: router_id.to_string(),
+            };
+            ingest_controller
+                .get_or_create_open_shards(request, &mut model, &progress)
+                .await
+                .unwrap();
+
+            let shard_1 = model.all_shards().next().unwrap();
+            assert!(shard_1.is_open());
+        }
+
+        // A second router reports the same leader: the quorum is reached.
+        let request = GetOrCreateOpenShardsRequest {
+            subrequests: Vec::new(),
+            closed_shards: Vec::new(),
+            unavailable_leaders: vec!["test-ingester-0".to_string()],
+            router_id: "test-router-1".to_string(),
+        };
+        ingest_controller
+            .get_or_create_open_shards(request, &mut model, &progress)
+            .await
+            .unwrap();
+
+        let shard_1 = model.all_shards().next().unwrap();
+        assert!(shard_1.is_unavailable());
+
+        // No new shards are allocated to the leader while the reports are recent.
+        assert!(ingest_controller
+            .allocate_shards(&[1], &FnvHashSet::default(), &model)
+            .is_none());
+    }
+
+    #[test]
+    fn test_ingest_controller_allocate_shards() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 2;
+
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let leader_follower_pairs_opt =
+            ingest_controller.allocate_shards(&[], &FnvHashSet::default(), &model);
+        assert!(leader_follower_pairs_opt.is_none());
 
         ingester_pool.insert(
             "test-ingester-1".into(),
@@ -1394,7 +3519,7 @@ mod tests {
         );
 
         let leader_follower_pairs_opt =
-            ingest_controller.allocate_shards(0, &FnvHashSet::default(), &model);
+            ingest_controller.allocate_shards(&[2], &FnvHashSet::default(), &model);
         assert!(leader_follower_pairs_opt.is_none());
 
         ingester_pool.insert(
@@ -1403,12 +3528,12 @@ mod tests {
         );
 
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(0, &FnvHashSet::default(), &model)
+            .allocate_shards(&[], &FnvHashSet::default(), &model)
             .unwrap();
         assert!(leader_follower_pairs.is_empty());
 
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(1, &FnvHashSet::default(), &model)
+            .allocate_shards(&[2], &FnvHashSet::default(), &model)
             .unwrap();
         assert_eq!(leader_follower_pairs.len(), 1);
         assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
@@ -1418,7 +3543,7 @@ mod tests {
         );
 
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(2, &FnvHashSet::default(), &model)
+            .allocate_shards(&[2; 2], &FnvHashSet::default(), &model)
             .unwrap();
         assert_eq!(leader_follower_pairs.len(), 2);
         assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
@@ -1434,7 +3559,7 @@ mod tests {
         );
 
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(3, &FnvHashSet::default(), &model)
+            .allocate_shards(&[2; 3], &FnvHashSet::default(), &model)
             .unwrap();
         assert_eq!(leader_follower_pairs.len(), 3);
         assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
@@ -1468,13 +3593,13 @@ mod tests {
         model.insert_shards(&index_uid, &source_id, open_shards);
 
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(3, &FnvHashSet::default(), &model)
+            .allocate_shards(&[2; 3], &FnvHashSet::default(), &model)
             .unwrap();
         assert_eq!(leader_follower_pairs.len(), 3);
-        assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
+        assert_eq!(leader_follower_pairs[0].0, "test-ingester-2");
         assert_eq!(
             leader_follower_pairs[0].1,
-            Some(NodeId::from("test-ingester-2"))
+            Some(NodeId::from("test-ingester-1"))
         );
 
         assert_eq!(leader_follower_pairs[1].0, "test-ingester-2");
@@ -1483,10 +3608,10 @@ mod tests {
             Some(NodeId::from("test-ingester-1"))
         );
 
-        assert_eq!(leader_follower_pairs[2].0, "test-ingester-2");
+        assert_eq!(leader_follower_pairs[2].0, "test-ingester-1");
         assert_eq!(
             leader_follower_pairs[2].1,
-            Some(NodeId::from("test-ingester-1"))
+            Some(NodeId::from("test-ingester-2"))
         );
 
         let open_shards = vec![
@@ -1510,7 +3635,7 @@ mod tests {
         model.insert_shards(&index_uid, &source_id, open_shards);
 
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(1, &FnvHashSet::default(), &model)
+            .allocate_shards(&[2], &FnvHashSet::default(), &model)
             .unwrap();
         assert_eq!(leader_follower_pairs.len(), 1);
         assert_eq!(leader_follower_pairs[0].0, "test-ingester-2");
@@ -1525,7 +3650,7 @@ mod tests {
         );
         let unavailable_leaders = FnvHashSet::from_iter([NodeId::from("test-ingester-2")]);
         let leader_follower_pairs = ingest_controller
-            .allocate_shards(4, &unavailable_leaders, &model)
+            .allocate_shards(&[2; 4], &unavailable_leaders, &model)
             .unwrap();
         assert_eq!(leader_follower_pairs.len(), 4);
         assert_eq!(leader_follower_pairs[0].0, "test-ingester-3");
@@ -1546,21 +3671,135 @@ mod tests {
             Some(NodeId::from("test-ingester-1"))
         );
 
-        assert_eq!(leader_follower_pairs[3].0, "test-ingester-1");
+        assert_eq!(leader_follower_pairs[3].0, "test-ingester-3");
         assert_eq!(
             leader_follower_pairs[3].1,
-            Some(NodeId::from("test-ingester-3"))
+            Some(NodeId::from("test-ingester-1"))
+        );
+
+        // Followers count towards the load of the ingesters: ingester 3 follows many shards, so
+        // it is no longer selected.
+        let open_shards = (4..8)
+            .map(|shard_id| Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_id.clone(),
+                shard_id: Some(ShardId::from(shard_id)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-ingester-2".to_string(),
+                follower_id: Some("test-ingester-3".to_string()),
+                ..Default::default()
+            })
+            .collect();
+        model.insert_shards(&index_uid, &source_id, open_shards);
+
+        let leader_follower_pairs = ingest_controller
+            .allocate_shards(&[1], &FnvHashSet::default(), &model)
+            .unwrap();
+        assert_eq!(leader_follower_pairs.len(), 1);
+        assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
+        assert!(leader_follower_pairs[0].1.is_none());
+    }
+
+    #[test]
+    fn test_ingest_controller_allocate_shards_per_index_replication_factor() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 2;
+
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata_0 =
+            IndexMetadata::for_test("test-index-0", "ram://indexes/test-index-0");
+        let index_uid_0 = index_metadata_0.index_uid.clone();
+        model.add_index(index_metadata_0);
+
+        let mut index_metadata_1 =
+            IndexMetadata::for_test("test-index-1", "ram://indexes/test-index-1");
+        index_metadata_1.index_config.replication_factor_opt = Some(1);
+        let index_uid_1 = index_metadata_1.index_uid.clone();
+        model.add_index(index_metadata_1);
+
+        assert_eq!(
+            ingest_controller.index_replication_factor(&index_uid_0, &model),
+            2
+        );
+        assert_eq!(
+            ingest_controller.index_replication_factor(&index_uid_1, &model),
+            1
+        );
+        ingester_pool.insert(
+            "test-ingester-1".into(),
+            IngesterServiceClient::from_mock(MockIngesterService::new()),
+        );
+        let leader_follower_pairs_opt =
+            ingest_controller.allocate_shards(&[2, 1], &FnvHashSet::default(), &model);
+        assert!(leader_follower_pairs_opt.is_none());
+
+        let leader_follower_pairs = ingest_controller
+            .allocate_shards(&[1], &FnvHashSet::default(), &model)
+            .unwrap();
+        assert_eq!(leader_follower_pairs.len(), 1);
+        assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
+        assert!(leader_follower_pairs[0].1.is_none());
+
+        ingester_pool.insert(
+            "test-ingester-2".into(),
+            IngesterServiceClient::from_mock(MockIngesterService::new()),
+        );
+        let leader_follower_pairs = ingest_controller
+            .allocate_shards(&[2, 1], &FnvHashSet::default(), &model)
+            .unwrap();
+        assert_eq!(leader_follower_pairs.len(), 2);
+        assert_eq!(leader_follower_pairs[0].0, "test-ingester-1");
+        assert_eq!(
+            leader_follower_pairs[0].1,
+            Some(NodeId::from("test-ingester-2"))
         );
+        assert_eq!(leader_follower_pairs[1].0, "test-ingester-2");
+        assert!(leader_follower_pairs[1].1.is_none());
     }
 
     #[tokio::test]
     async fn test_ingest_controller_init_shards() {
-        let metastore = MetastoreServiceClient::mocked();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_delete_shards()
+            .once()
+            .returning(|request| {
+                // Shards 3 and 4 are abandoned before being reopened on alternative leaders.
+                assert_eq!(request.shard_ids, [ShardId::from(3), ShardId::from(4)]);
+                assert!(request.force);
+
+                Ok(EmptyResponse {})
+            });
+        mock_metastore
+            .expect_open_shards()
+            .once()
+            .returning(|request| {
+                // The metastore fails to reopen shards 3 and 4, so they are reported as failures.
+                assert_eq!(request.subrequests.len(), 2);
+
+                Err(MetastoreError::Unavailable(
+                    "metastore unavailable".to_string(),
+                ))
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
         let ingester_pool = IngesterPool::default();
         let replication_factor = 1;
 
-        let ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let ingester_id_0 = NodeId::from("test-ingester-0");
         let mut mock_ingester_0 = MockIngesterService::new();
@@ -1636,23 +3875,37 @@ mod tests {
         let ingester_id_2 = NodeId::from("test-ingester-2");
         let mut mock_ingester_2 = MockIngesterService::new();
         mock_ingester_2.expect_init_shards().never();
+        mock_ingester_2
+            .expect_close_shards()
+            .once()
+            .returning(|request| {
+                // Ingester 2 may have initialized shard 3 despite timing out.
+                assert_eq!(request.shard_pkeys.len(), 1);
+                assert_eq!(request.shard_pkeys[0].shard_id(), ShardId::from(3));
+
+                Ok(CloseShardsResponse {
+                    successes: request.shard_pkeys,
+                })
+            });
 
         let ingester_2 = IngesterServiceClient::tower()
             .stack_init_shards_layer(DelayLayer::new(INIT_SHARDS_REQUEST_TIMEOUT * 2))
             .build_from_mock(mock_ingester_2);
         ingester_pool.insert(ingester_id_2, ingester_2);
 
+        let model = ControlPlaneModel::default();
+
         let init_shards_response = ingest_controller
-            .init_shards(&[], &Progress::default())
+            .init_shards(&[], &model, &Progress::default())
             .await;
         assert_eq!(init_shards_response.successes.len(), 0);
         assert_eq!(init_shards_response.failures.len(), 0);
 
         // In this test:
         // - ingester 0 will initialize shard 0 successfully and fail to initialize shard 1;
-        // - ingester 1 will return an error;
-        // - ingester 2 will time out;
-        // - ingester 3 will be unavailable.
+        // - ingester 1 will return a non-retryable error;
+        // - ingester 2 will time out on every attempt;
+        // - ingester 3 will be unavailable on every attempt.
 
         let open_shards_subresponses = [
             metastore::OpenShardSubresponse {
@@ -1712,7 +3965,7 @@ mod tests {
             },
         ];
         let init_shards_response = ingest_controller
-            .init_shards(&open_shards_subresponses, &Progress::default())
+            .init_shards(&open_shards_subresponses, &model, &Progress::default())
             .await;
         assert_eq!(init_shards_response.successes.len(), 1);
         assert_eq!(init_shards_response.failures.len(), 4);
@@ -1727,86 +3980,308 @@ mod tests {
         assert_eq!(failures[1].subrequest_id, 2);
         assert_eq!(failures[2].subrequest_id, 3);
         assert_eq!(failures[3].subrequest_id, 4);
+
+        assert_eq!(ingest_controller.stats.num_opened_shards, 1);
+        assert_eq!(ingest_controller.stats.num_failed_init_shards, 4);
     }
 
     #[tokio::test]
-    async fn test_ingest_controller_handle_local_shards_update() {
+    async fn test_ingest_controller_init_shards_retries_on_alternative_leader() {
         let mut mock_metastore = MockMetastoreService::new();
         mock_metastore
             .expect_open_shards()
             .once()
             .returning(|request| {
                 assert_eq!(request.subrequests.len(), 1);
-                let subrequest = &request.subrequests[0];
 
+                let subrequest = &request.subrequests[0];
+                assert_eq!(subrequest.subrequest_id, 0);
                 assert_eq!(subrequest.index_uid(), &IndexUid::for_test("test-index", 0));
                 assert_eq!(subrequest.source_id, "test-source");
-                assert_eq!(subrequest.leader_id, "test-ingester");
+                assert_ne!(subrequest.shard_id(), ShardId::from(0));
+                assert_eq!(subrequest.leader_id, "test-ingester-1");
 
-                Err(MetastoreError::InvalidArgument {
-                    message: "failed to open shards".to_string(),
-                })
+                let subresponses = vec![metastore::OpenShardSubresponse {
+                    subrequest_id: 0,
+                    open_shard: Some(Shard {
+                        index_uid: subrequest.index_uid.clone(),
+                        source_id: subrequest.source_id.clone(),
+                        shard_id: subrequest.shard_id.clone(),
+                        leader_id: subrequest.leader_id.clone(),
+                        shard_state: ShardState::Open as i32,
+                        ..Default::default()
+                    }),
+                }];
+                let response = metastore::OpenShardsResponse { subresponses };
+                Ok(response)
+            });
+        mock_metastore
+            .expect_delete_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.index_uid(), &IndexUid::for_test("test-index", 0));
+                assert_eq!(request.source_id, "test-source");
+                assert_eq!(request.shard_ids, [ShardId::from(0)]);
+                assert!(request.force);
+
+                Ok(EmptyResponse {})
             });
         let metastore = MetastoreServiceClient::from_mock(mock_metastore);
         let ingester_pool = IngesterPool::default();
         let replication_factor = 1;
 
-        let mut ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
-
-        let index_uid = IndexUid::for_test("test-index", 0);
-        let source_id: SourceId = "test-source".into();
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
-        let source_uid = SourceUid {
-            index_uid: index_uid.clone(),
-            source_id: source_id.clone(),
-        };
-        let mut model = ControlPlaneModel::default();
-        let progress = Progress::default();
+        let mut mock_ingester_0 = MockIngesterService::new();
+        mock_ingester_0
+            .expect_init_shards()
+            .once()
+            .returning(|_request| Err(IngestV2Error::Unavailable("connection reset".to_string())));
+        mock_ingester_0
+            .expect_close_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.shard_pkeys.len(), 1);
+                assert_eq!(request.shard_pkeys[0].shard_id(), ShardId::from(0));
 
-        let shards = vec![Shard {
-            index_uid: Some(index_uid.clone()),
-            source_id: source_id.clone(),
-            shard_id: Some(ShardId::from(1)),
-            leader_id: "test-ingester".to_string(),
-            shard_state: ShardState::Open as i32,
-            ..Default::default()
-        }];
-        model.insert_shards(&index_uid, &source_id, shards);
-        let shard_entries: Vec<ShardEntry> = model.all_shards().cloned().collect();
+                Ok(CloseShardsResponse {
+                    successes: request.shard_pkeys,
+                })
+            });
+        let ingester_0 = IngesterServiceClient::from_mock(mock_ingester_0);
+        ingester_pool.insert("test-ingester-0".into(), ingester_0);
 
-        assert_eq!(shard_entries.len(), 1);
-        assert_eq!(shard_entries[0].ingestion_rate, 0);
+        let mut mock_ingester_1 = MockIngesterService::new();
+        mock_ingester_1
+            .expect_init_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.subrequests.len(), 1);
 
-        // Test update shard ingestion rate but no scale down because num open shards is 1.
-        let shard_infos = BTreeSet::from_iter([ShardInfo {
-            shard_id: ShardId::from(1),
-            shard_state: ShardState::Open,
-            ingestion_rate: RateMibPerSec(1),
-        }]);
-        let local_shards_update = LocalShardsUpdate {
-            leader_id: "test-ingester".into(),
-            source_uid: source_uid.clone(),
-            shard_infos,
-        };
-        ingest_controller
-            .handle_local_shards_update(local_shards_update, &mut model, &progress)
-            .await;
+                let subrequest = &request.subrequests[0];
+                assert_eq!(subrequest.subrequest_id, 7);
+                assert_eq!(subrequest.shard().leader_id, "test-ingester-1");
 
-        let shard_entries: Vec<ShardEntry> = model.all_shards().cloned().collect();
-        assert_eq!(shard_entries.len(), 1);
-        assert_eq!(shard_entries[0].ingestion_rate, 1);
+                let successes = vec![InitShardSuccess {
+                    subrequest_id: subrequest.subrequest_id,
+                    shard: subrequest.shard.clone(),
+                }];
+                let response = InitShardsResponse {
+                    successes,
+                    failures: Vec::new(),
+                };
+                Ok(response)
+            });
+        let ingester_1 = IngesterServiceClient::from_mock(mock_ingester_1);
+        ingester_pool.insert("test-ingester-1".into(), ingester_1);
 
-        // Test update shard ingestion rate with failing scale down.
-        let shards = vec![Shard {
-            index_uid: Some(index_uid.clone()),
-            source_id: source_id.clone(),
-            shard_id: Some(ShardId::from(2)),
-            shard_state: ShardState::Open as i32,
-            leader_id: "test-ingester".to_string(),
-            ..Default::default()
+        let model = ControlPlaneModel::default();
+        let open_shards_subresponses = [metastore::OpenShardSubresponse {
+            subrequest_id: 7,
+            open_shard: Some(Shard {
+                index_uid: IndexUid::for_test("test-index", 0).into(),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester-0".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            }),
         }];
-        model.insert_shards(&index_uid, &source_id, shards);
+        let init_shards_response = ingest_controller
+            .init_shards(&open_shards_subresponses, &model, &Progress::default())
+            .await;
+        assert_eq!(init_shards_response.successes.len(), 1);
+        assert!(init_shards_response.failures.is_empty());
+
+        let success = &init_shards_response.successes[0];
+        assert_eq!(success.subrequest_id, 7);
+        assert_eq!(success.shard().leader_id, "test-ingester-1");
+
+        assert_eq!(ingest_controller.stats.num_opened_shards, 1);
+        assert_eq!(ingest_controller.stats.num_failed_init_shards, 0);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_init_shards_retries_on_same_leader() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+        let universe = Universe::with_accelerated_time();
+        let (control_plane_mailbox, control_plane_inbox) = universe.create_test_mailbox();
+        ingest_controller.set_control_plane_mailbox(control_plane_mailbox.downgrade());
+
+        let num_calls = Arc::new(AtomicUsize::new(0));
+        let num_calls_clone = num_calls.clone();
+
+        let mut mock_ingester_0 = MockIngesterService::new();
+        mock_ingester_0
+            .expect_init_shards()
+            .times(2)
+            .returning(move |request| {
+                // The first attempt times out, the second one succeeds.
+                if num_calls_clone.fetch_add(1, Ordering::Relaxed) == 0 {
+                    return Err(IngestV2Error::Timeout("request timed out".to_string()));
+                }
+                let subrequest = &request.subrequests[0];
+                assert_eq!(subrequest.shard().shard_id(), ShardId::from(0));
+
+                let successes = vec![InitShardSuccess {
+                    subrequest_id: subrequest.subrequest_id,
+                    shard: subrequest.shard.clone(),
+                }];
+                let response = InitShardsResponse {
+                    successes,
+                    failures: Vec::new(),
+                };
+                Ok(response)
+            });
+        let ingester_0 = IngesterServiceClient::from_mock(mock_ingester_0);
+        ingester_pool.insert("test-ingester-0".into(), ingester_0);
+
+        let model = ControlPlaneModel::default();
+        let open_shards_subresponses = [metastore::OpenShardSubresponse {
+            subrequest_id: 0,
+            open_shard: Some(Shard {
+                index_uid: IndexUid::for_test("test-index", 0).into(),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester-0".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            }),
+        }];
+        let init_shards_response = ingest_controller
+            .init_shards(&open_shards_subresponses, &model, &Progress::default())
+            .await;
+        // No alternative leader is available, so the shard is retried in the background.
+        assert!(init_shards_response.successes.is_empty());
+        assert_eq!(init_shards_response.failures.len(), 1);
+        assert_eq!(num_calls.load(Ordering::Relaxed), 1);
+
+        let callback = control_plane_inbox
+            .recv_typed_message::<InitShardsRetryCallback>()
+            .await
+            .unwrap();
+        let init_shards_response = callback.init_shards_response;
+        assert_eq!(init_shards_response.successes.len(), 1);
+        assert!(init_shards_response.failures.is_empty());
+        assert_eq!(num_calls.load(Ordering::Relaxed), 2);
+
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+        model
+            .add_source(&index_uid, SourceConfig::ingest_v2())
+            .unwrap();
+
+        let callback = InitShardsRetryCallback {
+            init_shards_response,
+        };
+        ingest_controller
+            .handle_init_shards_retry_callback(callback, &mut model, &Progress::default())
+            .await;
+        assert_eq!(model.num_shards(), 1);
+        assert_eq!(ingest_controller.stats.num_opened_shards, 1);
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_handle_local_shards_update() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_open_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.subrequests.len(), 1);
+                let subrequest = &request.subrequests[0];
+
+                assert_eq!(subrequest.index_uid(), &IndexUid::for_test("test-index", 0));
+                assert_eq!(subrequest.source_id, "test-source");
+                assert_eq!(subrequest.leader_id, "test-ingester");
+
+                Err(MetastoreError::InvalidArgument {
+                    message: "failed to open shards".to_string(),
+                })
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let source_id: SourceId = "test-source".into();
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        let mut model = ControlPlaneModel::default();
+        let progress = Progress::default();
+
+        let shards = vec![Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+            shard_id: Some(ShardId::from(1)),
+            leader_id: "test-ingester".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        }];
+        model.insert_shards(&index_uid, &source_id, shards);
+        let shard_entries: Vec<ShardEntry> = model.all_shards().cloned().collect();
+
+        assert_eq!(shard_entries.len(), 1);
+        assert_eq!(shard_entries[0].ingestion_rate, 0);
+
+        // Test update shard ingestion rate but no scale down because num open shards is 1.
+        let shard_infos = BTreeSet::from_iter([ShardInfo {
+            shard_id: ShardId::from(1),
+            shard_state: ShardState::Open,
+            ingestion_rate: RateMibPerSec(1),
+            publish_latency_opt: None,
+        }]);
+        let local_shards_update = LocalShardsUpdate {
+            leader_id: "test-ingester".into(),
+            source_uid: source_uid.clone(),
+            shard_infos,
+        };
+        ingest_controller
+            .handle_local_shards_update(local_shards_update, &mut model, &progress)
+            .await;
+
+        let shard_entries: Vec<ShardEntry> = model.all_shards().cloned().collect();
+        assert_eq!(shard_entries.len(), 1);
+        assert_eq!(shard_entries[0].ingestion_rate, 1);
+
+        // Test update shard ingestion rate with failing scale down.
+        let shards = vec![Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+            shard_id: Some(ShardId::from(2)),
+            shard_state: ShardState::Open as i32,
+            leader_id: "test-ingester".to_string(),
+            ..Default::default()
+        }];
+        model.insert_shards(&index_uid, &source_id, shards);
 
         let shard_entries: Vec<ShardEntry> = model.all_shards().cloned().collect();
         assert_eq!(shard_entries.len(), 2);
@@ -1834,11 +4309,13 @@ mod tests {
                 shard_id: ShardId::from(1),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(1),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(2),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(1),
+                publish_latency_opt: None,
             },
         ]);
         let local_shards_update = LocalShardsUpdate {
@@ -1856,11 +4333,13 @@ mod tests {
                 shard_id: ShardId::from(1),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(4),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(2),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(4),
+                publish_latency_opt: None,
             },
         ]);
         let local_shards_update = LocalShardsUpdate {
@@ -1920,8 +4399,12 @@ mod tests {
         let ingester_pool = IngesterPool::default();
         let replication_factor = 1;
 
-        let mut ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let index_uid = IndexUid::for_test("test-index", 0);
         let source_id: SourceId = INGEST_V2_SOURCE_ID.to_string();
@@ -2017,6 +4500,151 @@ mod tests {
             model.all_shards().filter(|shard| shard.is_open()).count(),
             1
         );
+        assert_eq!(ingest_controller.stats.num_suppressed_scale_shards_ops, 0);
+
+        // Test scale down suppressed by the scaling cooldown. The mock ingester does not expect
+        // any close shards request.
+        ingest_controller
+            .try_scale_down_shards(source_uid.clone(), shard_stats, &mut model, &progress)
+            .await;
+        assert_eq!(
+            model.all_shards().filter(|shard| shard.is_open()).count(),
+            1
+        );
+        assert_eq!(ingest_controller.stats.num_suppressed_scale_shards_ops, 1);
+        assert_eq!(ingest_controller.stats.num_scale_down_shards_ops, 0);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_handle_ingester_probe_results() {
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let source_id: SourceId = INGEST_V2_SOURCE_ID.to_string();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_uid_clone = index_uid.clone();
+        mock_metastore
+            .expect_open_shards()
+            .once()
+            .returning(move |request| {
+                assert_eq!(request.subrequests.len(), 1);
+                assert_eq!(request.subrequests[0].index_uid(), &index_uid_clone);
+                assert_eq!(request.subrequests[0].source_id, INGEST_V2_SOURCE_ID);
+                assert_eq!(request.subrequests[0].leader_id, "test-ingester-2");
+
+                let subresponses = vec![metastore::OpenShardSubresponse {
+                    subrequest_id: 0,
+                    open_shard: Some(Shard {
+                        index_uid: Some(index_uid_clone.clone()),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shard_id: Some(ShardId::from(2)),
+                        leader_id: "test-ingester-2".to_string(),
+                        shard_state: ShardState::Open as i32,
+                        ..Default::default()
+                    }),
+                }];
+                let response = metastore::OpenShardsResponse { subresponses };
+                Ok(response)
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let ingester_pool = IngesterPool::default();
+
+        let mock_ingester_1 = MockIngesterService::new();
+        let ingester_1 = IngesterServiceClient::from_mock(mock_ingester_1);
+        ingester_pool.insert("test-ingester-1".into(), ingester_1);
+
+        let mut mock_ingester_2 = MockIngesterService::new();
+        mock_ingester_2
+            .expect_init_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.subrequests.len(), 1);
+
+                let shard = request.subrequests[0].shard();
+                assert_eq!(shard.shard_id(), ShardId::from(2));
+
+                let successes = vec![InitShardSuccess {
+                    subrequest_id: request.subrequests[0].subrequest_id,
+                    shard: Some(shard.clone()),
+                }];
+                let response = InitShardsResponse {
+                    successes,
+                    failures: Vec::new(),
+                };
+                Ok(response)
+            });
+        let ingester_2 = IngesterServiceClient::from_mock(mock_ingester_2);
+        ingester_pool.insert("test-ingester-2".into(), ingester_2);
+
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+        let index_metadata =
+            IndexMetadata::for_test(&index_uid.index_id, "ram://indexes/test-index:0");
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let shard = Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+            shard_id: Some(ShardId::from(1)),
+            leader_id: "test-ingester-1".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        };
+        model.insert_shards(&index_uid, &source_id, vec![shard]);
+
+        let progress = Progress::default();
+
+        let probe_results = vec![
+            (NodeId::from("test-ingester-1"), false),
+            (NodeId::from("test-ingester-2"), true),
+        ];
+        for _ in 0..2 {
+            ingest_controller
+                .handle_ingester_probe_results(probe_results.clone(), &mut model, &progress)
+                .await;
+        }
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        let shard_entries = model.get_shards_for_source(&source_uid).unwrap();
+        assert_eq!(shard_entries.len(), 1);
+        assert!(shard_entries[&ShardId::from(1)].is_open());
+
+        ingest_controller
+            .handle_ingester_probe_results(probe_results, &mut model, &progress)
+            .await;
+
+        let shard_entries = model.get_shards_for_source(&source_uid).unwrap();
+        assert_eq!(shard_entries.len(), 2);
+        assert!(shard_entries[&ShardId::from(1)].is_unavailable());
+        assert!(shard_entries[&ShardId::from(2)].is_open());
+        assert_eq!(
+            shard_entries[&ShardId::from(2)].leader_id,
+            "test-ingester-2"
+        );
+
+        // The unhealthy ingester is no longer returned to the routers.
+        let unavailable_leaders = FnvHashSet::default();
+        assert!(ingest_controller
+            .health_tracker
+            .is_unhealthy(&NodeId::from("test-ingester-1")));
+        let leader_follower_pairs = ingest_controller
+            .allocate_shards(&[1, 1], &unavailable_leaders, &model)
+            .unwrap();
+        for (leader_id, _) in leader_follower_pairs {
+            assert_eq!(leader_id, "test-ingester-2");
+        }
     }
 
     #[tokio::test]
@@ -2025,8 +4653,12 @@ mod tests {
         let ingester_pool = IngesterPool::default();
         let replication_factor = 1;
 
-        let ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let index_uid = IndexUid::for_test("test-index", 0);
         let source_id: SourceId = "test-source".into();
@@ -2195,31 +4827,37 @@ mod tests {
                 shard_id: ShardId::from(1),
                 shard_state: ShardState::Open,
                 ingestion_rate: quickwit_ingest::RateMibPerSec(1),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(2),
                 shard_state: ShardState::Open,
                 ingestion_rate: quickwit_ingest::RateMibPerSec(2),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(3),
                 shard_state: ShardState::Open,
                 ingestion_rate: quickwit_ingest::RateMibPerSec(3),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(4),
                 shard_state: ShardState::Open,
                 ingestion_rate: quickwit_ingest::RateMibPerSec(4),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(5),
                 shard_state: ShardState::Open,
                 ingestion_rate: quickwit_ingest::RateMibPerSec(5),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(6),
                 shard_state: ShardState::Open,
                 ingestion_rate: quickwit_ingest::RateMibPerSec(6),
+                publish_latency_opt: None,
             },
         ]);
         model.update_shards(&source_uid, &shard_infos);
@@ -2235,8 +4873,12 @@ mod tests {
         let ingester_pool = IngesterPool::default();
         let replication_factor = 2;
 
-        let ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let index_uid = IndexUid::for_test("test-index", 0);
         let source_id: SourceId = "test-source".into();
@@ -2276,19 +4918,60 @@ mod tests {
         let mock_ingester_2 = MockIngesterService::new();
         let mock_ingester_3 = MockIngesterService::new();
 
+        let expected_digest = source_shards_digest(
+            &index_uid,
+            &source_id,
+            &[ShardId::from(1), ShardId::from(3)],
+        );
         let count_calls = Arc::new(AtomicUsize::new(0));
         let count_calls_clone = count_calls.clone();
         mock_ingester_1
             .expect_retain_shards()
-            .once()
+            .times(3)
             .returning(move |request| {
-                assert_eq!(request.retain_shards_for_sources.len(), 1);
-                assert_eq!(
-                    request.retain_shards_for_sources[0].shard_ids,
-                    [ShardId::from(1), ShardId::from(3)]
-                );
-                count_calls_clone.fetch_add(1, Ordering::Release);
-                Ok(RetainShardsResponse {})
+                let num_calls = count_calls_clone.fetch_add(1, Ordering::Release);
+
+                match num_calls {
+                    0 => {
+                        // The first sync lists all the shards.
+                        assert!(!request.incremental);
+                        assert_eq!(request.retain_shards_for_sources.len(), 1);
+                        assert_eq!(
+                            request.retain_shards_for_sources[0].shard_ids,
+                            [ShardId::from(1), ShardId::from(3)]
+                        );
+                        Ok(RetainShardsResponse {
+                            supports_incremental: true,
+                            ..Default::default()
+                        })
+                    }
+                    1 => {
+                        // Nothing changed since the last sync.
+                        assert!(request.incremental);
+                        assert_eq!(request.expected_digest, expected_digest);
+                        assert!(request.retain_shards_for_sources.is_empty());
+                        Ok(RetainShardsResponse {
+                            supports_incremental: true,
+                            digest_mismatch: true,
+                            source_digests: Vec::new(),
+                        })
+                    }
+                    2 => {
+                        // The source reported as diverging is sent again.
+                        assert!(request.incremental);
+                        assert_eq!(request.expected_digest, expected_digest);
+                        assert_eq!(request.retain_shards_for_sources.len(), 1);
+                        assert_eq!(
+                            request.retain_shards_for_sources[0].shard_ids,
+                            [ShardId::from(1), ShardId::from(3)]
+                        );
+                        Ok(RetainShardsResponse {
+                            supports_incremental: true,
+                            ..Default::default()
+                        })
+                    }
+                    _ => unreachable!(),
+                }
             });
         ingester_pool.insert(
             "node-1".into(),
@@ -2306,6 +4989,24 @@ mod tests {
         let wait_handle = ingest_controller.sync_with_ingester(&node_id, &model);
         wait_handle.wait().await;
         assert_eq!(count_calls.load(Ordering::Acquire), 1);
+
+        let wait_handle = ingest_controller.sync_with_ingester(&node_id, &model);
+        wait_handle.wait().await;
+        assert_eq!(count_calls.load(Ordering::Acquire), 2);
+
+        let wait_handle = ingest_controller.sync_with_ingester(&node_id, &model);
+        wait_handle.wait().await;
+        assert_eq!(count_calls.load(Ordering::Acquire), 3);
+
+        let retain_shards_digests_guard = ingest_controller.retain_shards_digests.lock().unwrap();
+        let source_uid = SourceUid {
+            index_uid,
+            source_id,
+        };
+        assert_eq!(
+            retain_shards_digests_guard[&node_id][&source_uid],
+            expected_digest
+        );
     }
 
     #[tokio::test]
@@ -2314,7 +5015,12 @@ mod tests {
         let ingester_pool = IngesterPool::default();
         let replication_factor = 2;
 
-        let ingest_controller = IngestController::new(metastore, ingester_pool, replication_factor);
+        let ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let mut model = ControlPlaneModel::default();
 
@@ -2345,9 +5051,12 @@ mod tests {
                     shard_ids: vec![ShardId::from(3)],
                 },
             ],
+            continuation_token: None,
         };
-        let advise_reset_shards_response =
-            ingest_controller.advise_reset_shards(advise_reset_shards_request, &model);
+        let advise_reset_shards_response = ingest_controller
+            .advise_reset_shards(advise_reset_shards_request, &model)
+            .unwrap();
+        assert!(advise_reset_shards_response.continuation_token.is_none());
 
         assert_eq!(advise_reset_shards_response.shards_to_delete.len(), 2);
 
@@ -2379,13 +5088,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ingest_controller_advise_reset_shards_with_continuation_token() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+
+        let ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+        let model = ControlPlaneModel::default();
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let mut advise_reset_shards_request = AdviseResetShardsRequest {
+            shard_ids: vec![
+                ShardIds {
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source-0".to_string(),
+                    shard_ids: (0..3u64).map(ShardId::from).collect(),
+                },
+                ShardIds {
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source-1".to_string(),
+                    shard_ids: (3..5u64).map(ShardId::from).collect(),
+                },
+            ],
+            continuation_token: None,
+        };
+        // Each shard ID is 20 bytes long, so the responses hold at most two shards.
+        let max_response_num_bytes = 2 * (20 + ADVISE_RESET_SHARD_OVERHEAD_NUM_BYTES);
+        let mut advised_shard_ids: Vec<ShardId> = Vec::new();
+
+        for expected_continuation_token_opt in [Some("2"), Some("4"), None] {
+            let advise_reset_shards_response = ingest_controller
+                .advise_reset_shards_with_max_response_size(
+                    advise_reset_shards_request.clone(),
+                    &model,
+                    max_response_num_bytes,
+                )
+                .unwrap();
+            assert!(advise_reset_shards_response.shards_to_truncate.is_empty());
+            assert_eq!(
+                advise_reset_shards_response.continuation_token.as_deref(),
+                expected_continuation_token_opt
+            );
+            advised_shard_ids.extend(
+                advise_reset_shards_response
+                    .shards_to_delete
+                    .into_iter()
+                    .flat_map(|shard_ids| shard_ids.shard_ids),
+            );
+            advise_reset_shards_request.continuation_token =
+                advise_reset_shards_response.continuation_token;
+        }
+        let expected_shard_ids: Vec<ShardId> = (0..5u64).map(ShardId::from).collect();
+        assert_eq!(advised_shard_ids, expected_shard_ids);
+
+        advise_reset_shards_request.continuation_token = Some("foo".to_string());
+        let error = ingest_controller
+            .advise_reset_shards(advise_reset_shards_request, &model)
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::InvalidArgument(_)));
+    }
+
     #[tokio::test]
     async fn test_ingest_controller_close_shards() {
         let metastore = MetastoreServiceClient::mocked();
         let ingester_pool = IngesterPool::default();
         let replication_factor = 1;
-        let ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let closed_shards = ingest_controller.close_shards(empty()).await;
         assert_eq!(closed_shards.len(), 0);
@@ -2506,6 +5285,295 @@ mod tests {
         assert_eq!(closed_shard.shard_id(), ShardId::from(0));
     }
 
+    #[tokio::test]
+    async fn test_ingest_controller_close_index_shards() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+        let progress = Progress::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let num_closed_shards = ingest_controller
+            .close_index_shards(&index_uid, &mut model, &progress)
+            .await;
+        assert_eq!(num_closed_shards, 0);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let shards = vec![
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(1)),
+                leader_id: "test-ingester".to_string(),
+                shard_state: ShardState::Closed as i32,
+                ..Default::default()
+            },
+        ];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), shards);
+
+        let mut mock_ingester = MockIngesterService::new();
+        let index_uid_clone = index_uid.clone();
+        mock_ingester
+            .expect_close_shards()
+            .once()
+            .returning(move |request| {
+                assert_eq!(request.shard_pkeys.len(), 1);
+
+                let shard = &request.shard_pkeys[0];
+                assert_eq!(shard.index_uid(), &index_uid_clone);
+                assert_eq!(shard.source_id, INGEST_V2_SOURCE_ID);
+                assert_eq!(shard.shard_id(), ShardId::from(0));
+
+                let response = CloseShardsResponse {
+                    successes: request.shard_pkeys,
+                };
+                Ok(response)
+            });
+        let ingester = IngesterServiceClient::from_mock(mock_ingester);
+        ingester_pool.insert("test-ingester".into(), ingester);
+
+        let num_closed_shards = ingest_controller
+            .close_index_shards(&index_uid, &mut model, &progress)
+            .await;
+        assert_eq!(num_closed_shards, 1);
+        assert_eq!(ingest_controller.stats.num_closed_shards, 1);
+
+        assert!(model
+            .list_shards_for_index(&index_uid)
+            .all(|shard| shard.is_closed()));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_paused_source() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+        let progress = Progress::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+        model
+            .add_source(&index_uid, SourceConfig::ingest_v2())
+            .unwrap();
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+        };
+        let shards = vec![Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            shard_id: Some(ShardId::from(0)),
+            leader_id: "test-ingester".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        }];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), shards);
+
+        let mut mock_ingester = MockIngesterService::new();
+        mock_ingester
+            .expect_close_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.shard_pkeys.len(), 1);
+                assert_eq!(request.shard_pkeys[0].shard_id(), ShardId::from(0));
+
+                let response = CloseShardsResponse {
+                    successes: request.shard_pkeys,
+                };
+                Ok(response)
+            });
+        let ingester = IngesterServiceClient::from_mock(mock_ingester);
+        ingester_pool.insert("test-ingester".into(), ingester);
+
+        assert!(model.pause_source(&source_uid));
+
+        let num_closed_shards = ingest_controller
+            .close_source_shards(&source_uid, &mut model, &progress)
+            .await;
+        assert_eq!(num_closed_shards, 1);
+        assert!(model
+            .list_shards_for_index(&index_uid)
+            .all(|shard| shard.is_closed()));
+
+        let request = GetOrCreateOpenShardsRequest {
+            subrequests: vec![GetOrCreateOpenShardsSubrequest {
+                subrequest_id: 0,
+                index_id: "test-index".to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                known_shard_table_version: None,
+            }],
+            closed_shards: Vec::new(),
+            unavailable_leaders: Vec::new(),
+            router_id: "test-router".to_string(),
+        };
+        let response = ingest_controller
+            .get_or_create_open_shards(request, &mut model, &progress)
+            .await
+            .unwrap();
+        assert!(response.successes.is_empty());
+        assert_eq!(response.failures.len(), 1);
+        assert_eq!(
+            response.failures[0].reason(),
+            GetOrCreateOpenShardsFailureReason::SourcePaused
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_force_close_shards() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_mark_shards_closed()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.index_uid(), &("test-index", 0));
+                assert_eq!(request.source_id, INGEST_V2_SOURCE_ID);
+                assert_eq!(request.shard_ids, [ShardId::from(0), ShardId::from(1)]);
+
+                Ok(EmptyResponse {})
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+        let progress = Progress::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let shards = vec![
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester-1".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(1)),
+                leader_id: "test-ingester-2".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+        ];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), shards);
+
+        let shard_pkey = |shard_id: u64| ShardPKey {
+            index_uid: Some(index_uid.clone()),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            shard_id: Some(ShardId::from(shard_id)),
+        };
+        let force_close_shards_request = ForceCloseShardsRequest {
+            shard_pkeys: vec![shard_pkey(0)],
+            reason: " ".to_string(),
+        };
+        let error = ingest_controller
+            .force_close_shards(force_close_shards_request, &mut model, &progress)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::InvalidArgument(_)));
+
+        let force_close_shards_request = ForceCloseShardsRequest {
+            shard_pkeys: vec![shard_pkey(0), shard_pkey(2)],
+            reason: "ingester lost its WAL".to_string(),
+        };
+        let error = ingest_controller
+            .force_close_shards(force_close_shards_request, &mut model, &progress)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ControlPlaneError::Metastore(MetastoreError::NotFound(_))
+        ));
+        assert!(model
+            .list_shards_for_index(&index_uid)
+            .all(|shard| shard.is_open()));
+
+        // Only the leader of shard 0 is available.
+        let mut mock_ingester = MockIngesterService::new();
+        mock_ingester
+            .expect_close_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.shard_pkeys.len(), 1);
+                assert_eq!(request.shard_pkeys[0].shard_id(), ShardId::from(0));
+
+                let response = CloseShardsResponse {
+                    successes: request.shard_pkeys,
+                };
+                Ok(response)
+            });
+        let ingester = IngesterServiceClient::from_mock(mock_ingester);
+        ingester_pool.insert("test-ingester-1".into(), ingester);
+
+        let force_close_shards_request = ForceCloseShardsRequest {
+            shard_pkeys: vec![shard_pkey(0), shard_pkey(1), shard_pkey(0)],
+            reason: "ingester lost its WAL".to_string(),
+        };
+        let response = ingest_controller
+            .force_close_shards(force_close_shards_request, &mut model, &progress)
+            .await
+            .unwrap();
+        assert_eq!(response.closed_shard_pkeys, [shard_pkey(0), shard_pkey(1)]);
+        assert_eq!(response.acknowledged_shard_pkeys, [shard_pkey(0)]);
+        assert_eq!(ingest_controller.stats.num_closed_shards, 2);
+
+        assert!(model
+            .list_shards_for_index(&index_uid)
+            .all(|shard| shard.is_closed()));
+
+        let shard_events = ingest_controller
+            .shard_event_log
+            .events(&Default::default());
+        assert_eq!(shard_events.len(), 2);
+        assert_eq!(shard_events[0].event_type(), ShardEventType::Closed);
+        assert_eq!(shard_events[0].reason, "force close: ingester lost its WAL");
+    }
+
     #[tokio::test]
     async fn test_ingest_controller_rebalance_shards() {
         setup_logging_for_tests();
@@ -2558,8 +5626,12 @@ mod tests {
         let metastore = MetastoreServiceClient::from_mock(mock_metastore);
         let ingester_pool = IngesterPool::default();
         let replication_factor = 1;
-        let mut ingest_controller =
-            IngestController::new(metastore, ingester_pool.clone(), replication_factor);
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
 
         let mut model = ControlPlaneModel::default();
 
@@ -2706,4 +5778,836 @@ mod tests {
         let callback = &callbacks[0];
         assert_eq!(callback.closed_shards.len(), 1);
     }
+
+    #[test]
+    fn test_compute_ingestion_pressure() {
+        let shard_stats = ShardStats {
+            num_open_shards: 0,
+            avg_ingestion_rate: 0.0,
+        };
+        assert_eq!(
+            compute_ingestion_pressure(&shard_stats),
+            IngestionPressure::Low
+        );
+
+        let shard_stats = ShardStats {
+            num_open_shards: 2,
+            avg_ingestion_rate: SCALE_UP_SHARDS_THRESHOLD_MIB_PER_SEC,
+        };
+        assert_eq!(
+            compute_ingestion_pressure(&shard_stats),
+            IngestionPressure::Moderate
+        );
+
+        let shard_stats = ShardStats {
+            num_open_shards: 2,
+            avg_ingestion_rate: MAX_SHARD_INGESTION_THROUGHPUT_MIB_PER_SEC,
+        };
+        assert_eq!(
+            compute_ingestion_pressure(&shard_stats),
+            IngestionPressure::High
+        );
+    }
+
+    #[test]
+    fn test_target_num_open_shards() {
+        let thresholds = ShardScalingThresholds::default();
+
+        // Stable.
+        assert_eq!(target_num_open_shards(0, 0., &thresholds), 1);
+        assert_eq!(target_num_open_shards(1, 0., &thresholds), 1);
+        assert_eq!(target_num_open_shards(2, 4., &thresholds), 2);
+        assert_eq!(target_num_open_shards(3, 4., &thresholds), 3);
+
+        // Scale up.
+        assert_eq!(target_num_open_shards(1, 4., &thresholds), 2);
+        assert_eq!(target_num_open_shards(1, 7., &thresholds), 2);
+        assert_eq!(target_num_open_shards(1, 8., &thresholds), 3);
+        assert_eq!(target_num_open_shards(2, 100., &thresholds), 26);
+
+        // Scale down.
+        assert_eq!(target_num_open_shards(4, 0., &thresholds), 1);
+        assert_eq!(target_num_open_shards(4, 2., &thresholds), 1);
+        assert_eq!(target_num_open_shards(10, 3., &thresholds), 2);
+        assert_eq!(target_num_open_shards(10, 3.5, &thresholds), 3);
+
+        for num_open_shards in 1..10 {
+            for ingestion_rate in [0., 0.5, 1., 2.5, 4., 10., 42.] {
+                let target = target_num_open_shards(num_open_shards, ingestion_rate, &thresholds);
+                assert_eq!(
+                    target_num_open_shards(target, ingestion_rate, &thresholds),
+                    target
+                );
+            }
+        }
+
+        let thresholds = ShardScalingThresholds {
+            scale_up_mib_per_sec: 2.,
+            scale_down_mib_per_sec: 0.5,
+        };
+        assert_eq!(target_num_open_shards(1, 1., &thresholds), 1);
+        assert_eq!(target_num_open_shards(1, 4., &thresholds), 3);
+        assert_eq!(target_num_open_shards(4, 1., &thresholds), 1);
+    }
+
+    #[test]
+    fn test_ingest_controller_apply_cluster_settings() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+        assert_eq!(
+            ingest_controller.scaling_thresholds,
+            ShardScalingThresholds::default()
+        );
+
+        let cluster_settings = ClusterSettings {
+            shard_scale_up_threshold_mib_per_sec: Some(2.),
+            shard_scale_down_threshold_mib_per_sec: Some(0.5),
+            ..Default::default()
+        };
+        ingest_controller.apply_cluster_settings(&cluster_settings);
+        assert_eq!(
+            ingest_controller.scaling_thresholds,
+            ShardScalingThresholds {
+                scale_up_mib_per_sec: 2.,
+                scale_down_mib_per_sec: 0.5,
+            }
+        );
+
+        ingest_controller.apply_cluster_settings(&ClusterSettings::default());
+        assert_eq!(
+            ingest_controller.scaling_thresholds,
+            ShardScalingThresholds::default()
+        );
+    }
+
+    #[test]
+    fn test_ingest_controller_project_source_shards() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 2;
+        let ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let open_shards: Vec<Shard> = (0..2)
+            .map(|shard_id| Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                leader_id: "test-ingester-0".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            })
+            .collect();
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), open_shards);
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+        };
+        for shard_entry in model
+            .get_shards_for_source_mut(&source_uid)
+            .unwrap()
+            .values_mut()
+        {
+            shard_entry.ingestion_rate = RateMibPerSec(5);
+        }
+        let request = ProjectSourceShardsRequest {
+            index_id: "test-index".to_string(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            ingestion_rates_mib_per_sec: vec![1., 100.],
+        };
+        let response = ingest_controller
+            .project_source_shards(request, &model)
+            .unwrap();
+        assert_eq!(response.index_uid(), &index_uid);
+        assert_eq!(response.num_open_shards, 2);
+        assert_eq!(response.ingestion_rate_mib_per_sec, 10.);
+        assert_eq!(response.target_num_open_shards, 3);
+        assert_eq!(response.replication_factor, 2);
+        assert_eq!(
+            response.projections,
+            [
+                ShardCountProjection {
+                    ingestion_rate_mib_per_sec: 1.,
+                    num_open_shards: 1,
+                    num_shard_replicas: 2,
+                },
+                ShardCountProjection {
+                    ingestion_rate_mib_per_sec: 100.,
+                    num_open_shards: 26,
+                    num_shard_replicas: 52,
+                },
+            ]
+        );
+
+        let request = ProjectSourceShardsRequest {
+            index_id: "test-index".to_string(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            ingestion_rates_mib_per_sec: vec![-1.],
+        };
+        let error = ingest_controller
+            .project_source_shards(request, &model)
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::InvalidArgument(_)));
+
+        let request = ProjectSourceShardsRequest {
+            index_id: "test-index".to_string(),
+            source_id: "test-source".to_string(),
+            ingestion_rates_mib_per_sec: Vec::new(),
+        };
+        let error = ingest_controller
+            .project_source_shards(request, &model)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ControlPlaneError::Metastore(MetastoreError::NotFound(EntityKind::Source { .. }))
+        ));
+
+        let request = ProjectSourceShardsRequest {
+            index_id: "test-index-foo".to_string(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            ingestion_rates_mib_per_sec: Vec::new(),
+        };
+        let error = ingest_controller
+            .project_source_shards(request, &model)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ControlPlaneError::Metastore(MetastoreError::NotFound(EntityKind::Index { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_find_shards_to_rebalance_counts_followers() {
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        // Leaders are evenly spread over ingesters 0 and 1, but ingester 2 follows all the shards.
+        let open_shards: Vec<Shard> = (0..6)
+            .map(|shard_id| Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                leader_id: format!("test-ingester-{}", shard_id % 2),
+                follower_id: Some("test-ingester-2".to_string()),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            })
+            .collect();
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), open_shards);
+
+        let decommissioning_ingesters = BTreeSet::new();
+        let shards_to_move = find_shards_to_rebalance(&model, &decommissioning_ingesters, 3);
+        assert_eq!(shards_to_move.len(), 1);
+        assert_eq!(
+            shards_to_move[0].follower_id.as_deref(),
+            Some("test-ingester-2")
+        );
+
+        // With a fourth ingester, the target drops and ingester 2 must shed more replicas.
+        let shards_to_move = find_shards_to_rebalance(&model, &decommissioning_ingesters, 4);
+        assert_eq!(shards_to_move.len(), 2);
+        assert!(shards_to_move
+            .iter()
+            .all(|shard| shard.follower_id.as_deref() == Some("test-ingester-2")));
+
+        // Shards hosted by a decommissioning ingester are always moved.
+        let decommissioning_ingesters = BTreeSet::from_iter([NodeId::from("test-ingester-2")]);
+        let shards_to_move = find_shards_to_rebalance(&model, &decommissioning_ingesters, 2);
+        assert_eq!(shards_to_move.len(), 6);
+    }
+
+    #[test]
+    fn test_ingest_controller_rebalance_shards_dry_run() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        ingester_pool.insert("test-ingester-0".into(), IngesterServiceClient::mocked());
+        ingester_pool.insert("test-ingester-1".into(), IngesterServiceClient::mocked());
+
+        let replication_factor = 1;
+        let ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool,
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let response = ingest_controller.rebalance_shards_dry_run(&model);
+        assert!(response.shard_moves.is_empty());
+        assert_eq!(response.shard_distribution.len(), 2);
+        assert!(response
+            .shard_distribution
+            .iter()
+            .all(|distribution| distribution.num_shard_replicas_before == 0
+                && distribution.num_shard_replicas_after == 0));
+
+        let open_shards: Vec<Shard> = (0..4)
+            .map(|shard_id| Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                leader_id: "test-ingester-0".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            })
+            .collect();
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), open_shards);
+
+        let response = ingest_controller.rebalance_shards_dry_run(&model);
+        assert_eq!(response.shard_moves.len(), 1);
+
+        let shard_move = &response.shard_moves[0];
+        assert_eq!(shard_move.index_uid(), &index_uid);
+        assert_eq!(shard_move.leader_id, "test-ingester-0");
+        assert_eq!(shard_move.target_leader_id, "test-ingester-1");
+        assert!(shard_move.target_follower_id.is_none());
+
+        assert_eq!(
+            response.shard_distribution,
+            [
+                IngesterShardDistribution {
+                    node_id: "test-ingester-0".to_string(),
+                    num_shard_replicas_before: 4,
+                    num_shard_replicas_after: 3,
+                },
+                IngesterShardDistribution {
+                    node_id: "test-ingester-1".to_string(),
+                    num_shard_replicas_before: 0,
+                    num_shard_replicas_after: 1,
+                },
+            ]
+        );
+        // The dry run leaves the model and the stats untouched.
+        assert_eq!(
+            model.num_shards_for_node(&NodeId::from("test-ingester-0")),
+            4
+        );
+        assert_eq!(ingest_controller.stats.num_rebalance_shards_ops, 0);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_decommission_ingester() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_open_shards().return_once(|request| {
+            assert_eq!(request.subrequests.len(), 1);
+
+            let subrequest = &request.subrequests[0];
+            assert_eq!(subrequest.leader_id, "test-ingester-1");
+
+            let subresponses = vec![metastore::OpenShardSubresponse {
+                subrequest_id: 0,
+                open_shard: Some(Shard {
+                    index_uid: Some(IndexUid::for_test("test-index", 0)),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shard_id: subrequest.shard_id.clone(),
+                    leader_id: "test-ingester-1".to_string(),
+                    shard_state: ShardState::Open as i32,
+                    ..Default::default()
+                }),
+            }];
+            let response = metastore::OpenShardsResponse { subresponses };
+            Ok(response)
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+        };
+        let open_shards = vec![Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            shard_id: Some(ShardId::from(0)),
+            leader_id: "test-ingester-0".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        }];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), open_shards);
+
+        let ingester_id_0 = NodeId::from("test-ingester-0");
+        let mut mock_ingester_0 = MockIngesterService::new();
+        mock_ingester_0.expect_close_shards().returning(|request| {
+            assert_eq!(request.shard_pkeys.len(), 1);
+            assert_eq!(request.shard_pkeys[0].shard_id(), ShardId::from(0));
+
+            let response = CloseShardsResponse {
+                successes: request.shard_pkeys,
+            };
+            Ok(response)
+        });
+        mock_ingester_0
+            .expect_decommission()
+            .once()
+            .returning(|_| Ok(DecommissionResponse {}));
+        let ingester_0 = IngesterServiceClient::from_mock(mock_ingester_0);
+        ingester_pool.insert(ingester_id_0.clone(), ingester_0);
+
+        let ingester_id_1 = NodeId::from("test-ingester-1");
+        let mut mock_ingester_1 = MockIngesterService::new();
+        mock_ingester_1.expect_init_shards().return_once(|request| {
+            assert_eq!(request.subrequests.len(), 1);
+
+            let successes = vec![InitShardSuccess {
+                subrequest_id: request.subrequests[0].subrequest_id,
+                shard: request.subrequests[0].shard.clone(),
+            }];
+            let response = InitShardsResponse {
+                successes,
+                failures: Vec::new(),
+            };
+            Ok(response)
+        });
+        let ingester_1 = IngesterServiceClient::from_mock(mock_ingester_1);
+        ingester_pool.insert(ingester_id_1.clone(), ingester_1);
+
+        let universe = Universe::with_accelerated_time();
+        let (control_plane_mailbox, control_plane_inbox) = universe.create_test_mailbox();
+        let progress = Progress::default();
+
+        let decommission_request = DecommissionIngesterRequest {
+            node_id: "test-ingester-0".to_string(),
+        };
+        let decommission_response = ingest_controller
+            .decommission_ingester(
+                decommission_request.clone(),
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap();
+        assert_eq!(decommission_response.num_remaining_shards, 1);
+        assert!(!decommission_response.is_decommissioned);
+
+        // The decommissioning ingester is no longer eligible for new shards.
+        let leader_follower_pairs = ingest_controller
+            .allocate_shards(&[1; 2], &FnvHashSet::default(), &model)
+            .unwrap();
+        assert!(leader_follower_pairs
+            .iter()
+            .all(|(leader_id, _)| *leader_id == ingester_id_1));
+
+        tokio::time::sleep(CLOSE_SHARDS_REQUEST_TIMEOUT * 2).await;
+
+        let callbacks: Vec<RebalanceShardsCallback> = control_plane_inbox.drain_for_test_typed();
+        assert_eq!(callbacks.len(), 1);
+        assert_eq!(callbacks[0].closed_shards.len(), 1);
+
+        // The ingester no longer leads any open shards, so it is asked to decommission itself.
+        model.close_shards(&source_uid, &[ShardId::from(0)]);
+        ingest_controller.advance_decommissioning_ingesters(&model);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // The decommission RPC succeeded, so it is not sent again.
+        ingest_controller.advance_decommissioning_ingesters(&model);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Once its shards are fully published, the ingester advertises its `Decommissioned` status
+        // and the nodes of the cluster evict it from their ingester pool.
+        model.delete_shards(&source_uid, &[ShardId::from(0)]);
+        ingester_pool.remove(&ingester_id_0);
+
+        ingest_controller.advance_decommissioning_ingesters(&model);
+        assert!(ingester_pool.contains_key(&ingester_id_1));
+        assert!(ingest_controller.decommissioning_ingesters.is_empty());
+
+        let decommission_response = ingest_controller
+            .decommission_ingester(
+                decommission_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap();
+        assert_eq!(decommission_response.num_remaining_shards, 0);
+        assert!(decommission_response.is_decommissioned);
+
+        let decommission_request = DecommissionIngesterRequest {
+            node_id: "test-ingester-unknown".to_string(),
+        };
+        let error = ingest_controller
+            .decommission_ingester(
+                decommission_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_move_shard_picks_least_loaded_follower() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_open_shards().return_once(|request| {
+            assert_eq!(request.subrequests.len(), 1);
+
+            let subrequest = &request.subrequests[0];
+            assert_eq!(subrequest.leader_id, "test-ingester-1");
+            assert_eq!(subrequest.follower_id(), "test-ingester-3");
+
+            let subresponses = vec![metastore::OpenShardSubresponse {
+                subrequest_id: 0,
+                open_shard: Some(Shard {
+                    index_uid: Some(IndexUid::for_test("test-index", 0)),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shard_id: subrequest.shard_id.clone(),
+                    leader_id: "test-ingester-1".to_string(),
+                    follower_id: Some("test-ingester-3".to_string()),
+                    shard_state: ShardState::Open as i32,
+                    ..Default::default()
+                }),
+            }];
+            let response = metastore::OpenShardsResponse { subresponses };
+            Ok(response)
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 2;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        // The shard to move is followed by the target ingester, so a new follower is picked among
+        // `test-ingester-0`, `test-ingester-2`, and `test-ingester-3`, which host 2, 2, and 0
+        // shards respectively.
+        let open_shards = vec![
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester-0".to_string(),
+                follower_id: Some("test-ingester-1".to_string()),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(1)),
+                leader_id: "test-ingester-2".to_string(),
+                follower_id: Some("test-ingester-0".to_string()),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(2)),
+                leader_id: "test-ingester-2".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+        ];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), open_shards);
+
+        let mut mock_ingester_0 = MockIngesterService::new();
+        mock_ingester_0.expect_close_shards().returning(|request| {
+            let response = CloseShardsResponse {
+                successes: request.shard_pkeys,
+            };
+            Ok(response)
+        });
+        let ingester_0 = IngesterServiceClient::from_mock(mock_ingester_0);
+        ingester_pool.insert(NodeId::from("test-ingester-0"), ingester_0);
+
+        let mut mock_ingester_1 = MockIngesterService::new();
+        mock_ingester_1.expect_init_shards().return_once(|request| {
+            let successes = vec![InitShardSuccess {
+                subrequest_id: request.subrequests[0].subrequest_id,
+                shard: request.subrequests[0].shard.clone(),
+            }];
+            let response = InitShardsResponse {
+                successes,
+                failures: Vec::new(),
+            };
+            Ok(response)
+        });
+        let ingester_1 = IngesterServiceClient::from_mock(mock_ingester_1);
+        ingester_pool.insert(NodeId::from("test-ingester-1"), ingester_1);
+
+        let ingester_2 = IngesterServiceClient::from_mock(MockIngesterService::new());
+        ingester_pool.insert(NodeId::from("test-ingester-2"), ingester_2);
+
+        let ingester_3 = IngesterServiceClient::from_mock(MockIngesterService::new());
+        ingester_pool.insert(NodeId::from("test-ingester-3"), ingester_3);
+
+        let universe = Universe::with_accelerated_time();
+        let (control_plane_mailbox, _control_plane_inbox) = universe.create_test_mailbox();
+        let progress = Progress::default();
+
+        let move_shard_request = MoveShardRequest {
+            shard_id: Some(ShardId::from(0)),
+            target_ingester_id: "test-ingester-1".to_string(),
+        };
+        let move_shard_response = ingest_controller
+            .move_shard(
+                move_shard_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap();
+        let new_shard = move_shard_response.shard();
+        assert_eq!(new_shard.leader_id, "test-ingester-1");
+        assert_eq!(new_shard.follower_id(), "test-ingester-3");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_controller_move_shard() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_open_shards().return_once(|request| {
+            assert_eq!(request.subrequests.len(), 1);
+
+            let subrequest = &request.subrequests[0];
+            assert_eq!(subrequest.leader_id, "test-ingester-2");
+            assert!(subrequest.follower_id.is_none());
+
+            let subresponses = vec![metastore::OpenShardSubresponse {
+                subrequest_id: 0,
+                open_shard: Some(Shard {
+                    index_uid: Some(IndexUid::for_test("test-index", 0)),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shard_id: subrequest.shard_id.clone(),
+                    leader_id: "test-ingester-2".to_string(),
+                    shard_state: ShardState::Open as i32,
+                    ..Default::default()
+                }),
+            }];
+            let response = metastore::OpenShardsResponse { subresponses };
+            Ok(response)
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut ingest_controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            IngestControllerTimeouts::default(),
+        );
+
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let open_shards = vec![
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester-0".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(1)),
+                leader_id: "test-ingester-0".to_string(),
+                shard_state: ShardState::Closed as i32,
+                ..Default::default()
+            },
+        ];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), open_shards);
+
+        let mut mock_ingester_0 = MockIngesterService::new();
+        mock_ingester_0.expect_close_shards().returning(|request| {
+            assert_eq!(request.shard_pkeys.len(), 1);
+            assert_eq!(request.shard_pkeys[0].shard_id(), ShardId::from(0));
+
+            let response = CloseShardsResponse {
+                successes: request.shard_pkeys,
+            };
+            Ok(response)
+        });
+        let ingester_0 = IngesterServiceClient::from_mock(mock_ingester_0);
+        ingester_pool.insert(NodeId::from("test-ingester-0"), ingester_0);
+
+        let ingester_1 = IngesterServiceClient::from_mock(MockIngesterService::new());
+        ingester_pool.insert(NodeId::from("test-ingester-1"), ingester_1);
+
+        let mut mock_ingester_2 = MockIngesterService::new();
+        mock_ingester_2.expect_init_shards().return_once(|request| {
+            assert_eq!(request.subrequests.len(), 1);
+
+            let successes = vec![InitShardSuccess {
+                subrequest_id: request.subrequests[0].subrequest_id,
+                shard: request.subrequests[0].shard.clone(),
+            }];
+            let response = InitShardsResponse {
+                successes,
+                failures: Vec::new(),
+            };
+            Ok(response)
+        });
+        let ingester_2 = IngesterServiceClient::from_mock(mock_ingester_2);
+        ingester_pool.insert(NodeId::from("test-ingester-2"), ingester_2);
+
+        let universe = Universe::with_accelerated_time();
+        let (control_plane_mailbox, control_plane_inbox) = universe.create_test_mailbox();
+        let progress = Progress::default();
+
+        let move_shard_request = MoveShardRequest {
+            shard_id: Some(ShardId::from(42)),
+            target_ingester_id: "test-ingester-2".to_string(),
+        };
+        let error = ingest_controller
+            .move_shard(
+                move_shard_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ControlPlaneError::Metastore(MetastoreError::NotFound(_))
+        ));
+
+        let move_shard_request = MoveShardRequest {
+            shard_id: Some(ShardId::from(1)),
+            target_ingester_id: "test-ingester-2".to_string(),
+        };
+        let error = ingest_controller
+            .move_shard(
+                move_shard_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::InvalidArgument(_)));
+
+        let move_shard_request = MoveShardRequest {
+            shard_id: Some(ShardId::from(0)),
+            target_ingester_id: "test-ingester-0".to_string(),
+        };
+        let error = ingest_controller
+            .move_shard(
+                move_shard_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::InvalidArgument(_)));
+
+        let move_shard_request = MoveShardRequest {
+            shard_id: Some(ShardId::from(0)),
+            target_ingester_id: "test-ingester-3".to_string(),
+        };
+        let error = ingest_controller
+            .move_shard(
+                move_shard_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ControlPlaneError::Unavailable(_)));
+
+        let move_shard_request = MoveShardRequest {
+            shard_id: Some(ShardId::from(0)),
+            target_ingester_id: "test-ingester-2".to_string(),
+        };
+        let move_shard_response = ingest_controller
+            .move_shard(
+                move_shard_request,
+                &mut model,
+                &control_plane_mailbox,
+                &progress,
+            )
+            .await
+            .unwrap();
+        let new_shard = move_shard_response.shard();
+        assert_eq!(new_shard.leader_id, "test-ingester-2");
+        assert!(new_shard.is_open());
+
+        let new_shard_entry = model.find_shard(new_shard.shard_id()).unwrap();
+        assert_eq!(new_shard_entry.leader_id, "test-ingester-2");
+
+        tokio::time::sleep(CLOSE_SHARDS_REQUEST_TIMEOUT * 2).await;
+
+        let callbacks: Vec<RebalanceShardsCallback> = control_plane_inbox.drain_for_test_typed();
+        assert_eq!(callbacks.len(), 1);
+        assert_eq!(callbacks[0].closed_shards.len(), 1);
+        assert_eq!(callbacks[0].closed_shards[0].shard_id(), ShardId::from(0));
+    }
 }
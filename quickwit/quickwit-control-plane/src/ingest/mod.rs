@@ -17,8 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+pub(crate) mod health_prober;
 pub(crate) mod ingest_controller;
+mod shard_event_log;
+pub(crate) mod shard_id_generator;
+mod unavailable_leaders;
 mod wait_handle;
 
-pub use ingest_controller::IngestController;
+pub use ingest_controller::{IngestController, IngestControllerTimeouts};
 pub use wait_handle::WaitHandle;
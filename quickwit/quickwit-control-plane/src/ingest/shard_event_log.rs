@@ -0,0 +1,238 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+
+use quickwit_proto::control_plane::{GetShardEventsRequest, ShardEvent, ShardEventType};
+use quickwit_proto::ingest::Shard;
+use time::OffsetDateTime;
+
+/// Default maximum number of events retained by the shard event log.
+const DEFAULT_SHARD_EVENT_LOG_CAPACITY: usize = 10_000;
+
+/// Append-only, bounded, in-memory log of the shard lifecycle transitions (open, close, move,
+/// unavailable, delete) observed by the control plane. Once the log is full, the oldest events are
+/// evicted. The log is lost when the control plane restarts: it is meant for debugging only.
+#[derive(Debug)]
+pub(crate) struct ShardEventLog {
+    capacity: usize,
+    events: VecDeque<ShardEvent>,
+}
+
+impl Default for ShardEventLog {
+    fn default() -> Self {
+        let capacity = quickwit_common::get_from_env(
+            "QW_SHARD_EVENT_LOG_CAPACITY",
+            DEFAULT_SHARD_EVENT_LOG_CAPACITY,
+        );
+        Self::new(capacity)
+    }
+}
+
+impl ShardEventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Records a transition of `shard`.
+    pub fn record(&mut self, event_type: ShardEventType, shard: &Shard, reason: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        let timestamp_millis =
+            (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64;
+        let event = ShardEvent {
+            timestamp_millis,
+            event_type: event_type as i32,
+            index_uid: shard.index_uid.clone(),
+            source_id: shard.source_id.clone(),
+            shard_id: shard.shard_id.clone(),
+            leader_id: shard.leader_id.clone(),
+            follower_id: shard.follower_id.clone(),
+            reason: reason.to_string(),
+        };
+        self.events.push_back(event);
+    }
+
+    /// Records the same transition for several shards.
+    pub fn record_all<'a>(
+        &mut self,
+        event_type: ShardEventType,
+        shards: impl IntoIterator<Item = &'a Shard>,
+        reason: &str,
+    ) {
+        for shard in shards {
+            self.record(event_type, shard, reason);
+        }
+    }
+
+    /// Returns the events matching the filters of the request, oldest first. If the request sets
+    /// a limit, only the most recent matching events are returned.
+    pub fn events(&self, request: &GetShardEventsRequest) -> Vec<ShardEvent> {
+        let limit = request
+            .limit
+            .map(|limit| limit as usize)
+            .unwrap_or(usize::MAX);
+
+        let mut events: Vec<ShardEvent> = self
+            .events
+            .iter()
+            .rev()
+            .filter(|event| {
+                if let Some(index_id) = &request.index_id {
+                    let Some(index_uid) = &event.index_uid else {
+                        return false;
+                    };
+                    if &index_uid.index_id != index_id {
+                        return false;
+                    }
+                }
+                if let Some(source_id) = &request.source_id {
+                    if &event.source_id != source_id {
+                        return false;
+                    }
+                }
+                if let Some(shard_id) = &request.shard_id {
+                    if event.shard_id.as_ref() != Some(shard_id) {
+                        return false;
+                    }
+                }
+                true
+            })
+            .take(limit)
+            .cloned()
+            .collect();
+        events.reverse();
+        events
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::types::{IndexUid, ShardId};
+
+    use super::*;
+
+    fn shard_for_test(index_uid: &IndexUid, source_id: &str, shard_id: u64) -> Shard {
+        Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.to_string(),
+            shard_id: Some(ShardId::from(shard_id)),
+            leader_id: "test-ingester-0".to_string(),
+            follower_id: Some("test-ingester-1".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_shard_event_log_record() {
+        let mut shard_event_log = ShardEventLog::new(2);
+        let index_uid = IndexUid::for_test("test-index", 0);
+
+        let shard_0 = shard_for_test(&index_uid, "test-source", 0);
+        let shard_1 = shard_for_test(&index_uid, "test-source", 1);
+
+        shard_event_log.record(ShardEventType::Opened, &shard_0, "scale up");
+        shard_event_log.record(ShardEventType::Opened, &shard_1, "scale up");
+        shard_event_log.record(ShardEventType::Closed, &shard_0, "scale down");
+        assert_eq!(shard_event_log.len(), 2);
+
+        let events = shard_event_log.events(&GetShardEventsRequest::default());
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].event_type(), ShardEventType::Opened);
+        assert_eq!(events[0].shard_id(), ShardId::from(1));
+        assert_eq!(events[0].reason, "scale up");
+
+        assert_eq!(events[1].event_type(), ShardEventType::Closed);
+        assert_eq!(events[1].index_uid(), &index_uid);
+        assert_eq!(events[1].source_id, "test-source");
+        assert_eq!(events[1].shard_id(), ShardId::from(0));
+        assert_eq!(events[1].leader_id, "test-ingester-0");
+        assert_eq!(events[1].follower_id(), "test-ingester-1");
+        assert_eq!(events[1].reason, "scale down");
+        assert!(events[1].timestamp_millis >= events[0].timestamp_millis);
+    }
+
+    #[test]
+    fn test_shard_event_log_events_filters() {
+        let mut shard_event_log = ShardEventLog::new(100);
+        let index_uid_0 = IndexUid::for_test("test-index-0", 0);
+        let index_uid_1 = IndexUid::for_test("test-index-1", 0);
+
+        let shards = [
+            shard_for_test(&index_uid_0, "test-source-0", 0),
+            shard_for_test(&index_uid_0, "test-source-1", 1),
+            shard_for_test(&index_uid_1, "test-source-0", 2),
+        ];
+        shard_event_log.record_all(ShardEventType::Opened, &shards, "scale up");
+        shard_event_log.record_all(ShardEventType::Unavailable, &shards, "ingester unavailable");
+
+        let request = GetShardEventsRequest {
+            index_id: Some("test-index-0".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(shard_event_log.events(&request).len(), 4);
+
+        let request = GetShardEventsRequest {
+            index_id: Some("test-index-0".to_string()),
+            source_id: Some("test-source-1".to_string()),
+            ..Default::default()
+        };
+        let events = shard_event_log.events(&request);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].shard_id(), ShardId::from(1));
+
+        let request = GetShardEventsRequest {
+            shard_id: Some(ShardId::from(2)),
+            ..Default::default()
+        };
+        let events = shard_event_log.events(&request);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type(), ShardEventType::Opened);
+        assert_eq!(events[1].event_type(), ShardEventType::Unavailable);
+
+        let request = GetShardEventsRequest {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let events = shard_event_log.events(&request);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].shard_id(), ShardId::from(1));
+        assert_eq!(events[0].event_type(), ShardEventType::Unavailable);
+        assert_eq!(events[1].shard_id(), ShardId::from(2));
+
+        let request = GetShardEventsRequest {
+            index_id: Some("test-index-2".to_string()),
+            ..Default::default()
+        };
+        assert!(shard_event_log.events(&request).is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::Arc;
+
+use quickwit_config::ShardIdStrategy;
+use quickwit_proto::types::{NodeId, ShardId};
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use ulid::Ulid;
+
+/// Generates the IDs of the shards opened by the control plane. Shard IDs must be globally
+/// unique, so every implementation embeds a ULID in the IDs it generates.
+pub trait ShardIdGenerator: fmt::Debug + Send + Sync + 'static {
+    fn next_shard_id(&self) -> ShardId;
+}
+
+/// Builds the shard ID generator for the given strategy.
+pub fn build_shard_id_generator(
+    shard_id_strategy: ShardIdStrategy,
+    self_node_id: &NodeId,
+) -> Arc<dyn ShardIdGenerator> {
+    match shard_id_strategy {
+        ShardIdStrategy::Ulid => Arc::new(UlidShardIdGenerator),
+        ShardIdStrategy::TimePrefixed => Arc::new(TimePrefixedShardIdGenerator),
+        ShardIdStrategy::NodePrefixed => Arc::new(NodePrefixedShardIdGenerator {
+            node_id: self_node_id.clone(),
+        }),
+    }
+}
+
+/// Generates plain ULIDs, e.g. `01HV4CVBF1WBZ4V5XRTGKMXDMP`.
+#[derive(Debug, Default)]
+pub struct UlidShardIdGenerator;
+
+impl ShardIdGenerator for UlidShardIdGenerator {
+    fn next_shard_id(&self) -> ShardId {
+        ShardId::from(Ulid::new())
+    }
+}
+
+/// Generates ULIDs prefixed with the UTC creation time, e.g.
+/// `20240410T083512Z-01HV4CVBF1WBZ4V5XRTGKMXDMP`.
+#[derive(Debug, Default)]
+pub struct TimePrefixedShardIdGenerator;
+
+impl ShardIdGenerator for TimePrefixedShardIdGenerator {
+    fn next_shard_id(&self) -> ShardId {
+        const TIME_PREFIX_FORMAT: &[FormatItem] =
+            format_description!("[year][month][day]T[hour][minute][second]Z");
+
+        let time_prefix = OffsetDateTime::now_utc()
+            .format(TIME_PREFIX_FORMAT)
+            .expect("formatting a UTC timestamp should not fail");
+        ShardId::from(format!("{time_prefix}-{}", Ulid::new()))
+    }
+}
+
+/// Generates ULIDs prefixed with the ID of the node running the control plane, e.g.
+/// `control-plane-0-01HV4CVBF1WBZ4V5XRTGKMXDMP`.
+#[derive(Debug)]
+pub struct NodePrefixedShardIdGenerator {
+    node_id: NodeId,
+}
+
+impl ShardIdGenerator for NodePrefixedShardIdGenerator {
+    fn next_shard_id(&self) -> ShardId {
+        ShardId::from(format!("{}-{}", self.node_id, Ulid::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shard_id_generators() {
+        let node_id = NodeId::from("test-node");
+
+        let shard_id_generator = build_shard_id_generator(ShardIdStrategy::Ulid, &node_id);
+        let shard_id = shard_id_generator.next_shard_id();
+        Ulid::from_string(shard_id.as_str()).unwrap();
+        assert_ne!(shard_id_generator.next_shard_id(), shard_id);
+
+        let shard_id_generator = build_shard_id_generator(ShardIdStrategy::TimePrefixed, &node_id);
+        let shard_id = shard_id_generator.next_shard_id();
+        let (time_prefix, ulid_str) = shard_id.as_str().split_once('-').unwrap();
+        assert_eq!(time_prefix.len(), 16);
+        assert!(time_prefix.ends_with('Z'));
+        Ulid::from_string(ulid_str).unwrap();
+
+        let shard_id_generator = build_shard_id_generator(ShardIdStrategy::NodePrefixed, &node_id);
+        let shard_id = shard_id_generator.next_shard_id();
+        let ulid_str = shard_id.as_str().strip_prefix("test-node-").unwrap();
+        Ulid::from_string(ulid_str).unwrap();
+    }
+}
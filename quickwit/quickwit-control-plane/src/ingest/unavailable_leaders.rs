@@ -0,0 +1,179 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use fnv::FnvHashSet;
+use quickwit_proto::types::NodeId;
+use tracing::warn;
+
+/// Default number of distinct routers that must report a leader as unavailable before the control
+/// plane considers it unavailable, even though it is still present in the ingester pool.
+const DEFAULT_UNAVAILABLE_LEADER_QUORUM: usize = 2;
+
+/// Default duration during which a report of an unavailable leader counts towards the quorum.
+const DEFAULT_UNAVAILABLE_LEADER_REPORT_WINDOW_SECS: u64 = 60;
+
+/// Tracks which routers reported which leaders as unavailable. A leader is confirmed unavailable
+/// once a quorum of distinct routers reported it within a sliding time window. This catches the
+/// ingesters that keep gossiping, and therefore stay in the ingester pool, but drop all ingest
+/// traffic.
+#[derive(Debug)]
+pub(crate) struct UnavailableLeaderReports {
+    quorum: usize,
+    report_window: Duration,
+    // Leader ID -> router ID -> time of the latest report.
+    reports: HashMap<NodeId, HashMap<NodeId, Instant>>,
+}
+
+impl Default for UnavailableLeaderReports {
+    fn default() -> Self {
+        let quorum = quickwit_common::get_from_env(
+            "QW_UNAVAILABLE_LEADER_QUORUM",
+            DEFAULT_UNAVAILABLE_LEADER_QUORUM,
+        );
+        let report_window_secs = quickwit_common::get_from_env(
+            "QW_UNAVAILABLE_LEADER_REPORT_WINDOW_SECS",
+            DEFAULT_UNAVAILABLE_LEADER_REPORT_WINDOW_SECS,
+        );
+        Self::new(quorum, Duration::from_secs(report_window_secs))
+    }
+}
+
+impl UnavailableLeaderReports {
+    pub fn new(quorum: usize, report_window: Duration) -> Self {
+        Self {
+            quorum: quorum.max(1),
+            report_window,
+            reports: HashMap::new(),
+        }
+    }
+
+    /// Records that `router_id` reported `leader_ids` as unavailable and returns the leaders that
+    /// have been reported by a quorum of distinct routers within the report window.
+    pub fn record_reports<'a>(
+        &mut self,
+        router_id: &NodeId,
+        leader_ids: impl IntoIterator<Item = &'a NodeId>,
+        now: Instant,
+    ) -> FnvHashSet<NodeId> {
+        self.evict_expired_reports(now);
+
+        let mut confirmed_leaders = FnvHashSet::default();
+
+        for leader_id in leader_ids {
+            let reports = self.reports.entry(leader_id.clone()).or_default();
+            reports.insert(router_id.clone(), now);
+
+            if reports.len() >= self.quorum {
+                if reports.len() == self.quorum {
+                    warn!(
+                        "{} routers reported leader `{leader_id}` as unavailable: marking it as \
+                         unavailable",
+                        reports.len()
+                    );
+                }
+                confirmed_leaders.insert(leader_id.clone());
+            }
+        }
+        confirmed_leaders
+    }
+
+    /// Returns whether the leader has been reported by a quorum of distinct routers within the
+    /// report window.
+    pub fn is_confirmed_unavailable(&self, leader_id: &NodeId, now: Instant) -> bool {
+        self.reports
+            .get(leader_id)
+            .map(|reports| self.num_recent_reports(reports, now) >= self.quorum)
+            .unwrap_or(false)
+    }
+
+    fn num_recent_reports(&self, reports: &HashMap<NodeId, Instant>, now: Instant) -> usize {
+        reports
+            .values()
+            .filter(|reported_at| {
+                now.saturating_duration_since(**reported_at) <= self.report_window
+            })
+            .count()
+    }
+
+    fn evict_expired_reports(&mut self, now: Instant) {
+        let report_window = self.report_window;
+
+        self.reports.retain(|_, reports| {
+            reports.retain(|_, reported_at| {
+                now.saturating_duration_since(*reported_at) <= report_window
+            });
+            !reports.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unavailable_leader_reports_quorum() {
+        let mut reports = UnavailableLeaderReports::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        let router_0: NodeId = "test-router-0".into();
+        let router_1: NodeId = "test-router-1".into();
+        let leader: NodeId = "test-ingester".into();
+
+        let confirmed_leaders = reports.record_reports(&router_0, [&leader], now);
+        assert!(confirmed_leaders.is_empty());
+        assert!(!reports.is_confirmed_unavailable(&leader, now));
+
+        // The same router reporting twice does not count twice.
+        let confirmed_leaders = reports.record_reports(&router_0, [&leader], now);
+        assert!(confirmed_leaders.is_empty());
+
+        let confirmed_leaders = reports.record_reports(&router_1, [&leader], now);
+        assert_eq!(confirmed_leaders.len(), 1);
+        assert!(confirmed_leaders.contains(&leader));
+        assert!(reports.is_confirmed_unavailable(&leader, now));
+    }
+
+    #[test]
+    fn test_unavailable_leader_reports_window() {
+        let mut reports = UnavailableLeaderReports::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        let router_0: NodeId = "test-router-0".into();
+        let router_1: NodeId = "test-router-1".into();
+        let leader: NodeId = "test-ingester".into();
+
+        reports.record_reports(&router_0, [&leader], now);
+
+        // The first report has expired by the time the second router reports the leader.
+        let later = now + Duration::from_secs(61);
+        let confirmed_leaders = reports.record_reports(&router_1, [&leader], later);
+        assert!(confirmed_leaders.is_empty());
+
+        let confirmed_leaders =
+            reports.record_reports(&router_0, [&leader], later + Duration::from_secs(1));
+        assert_eq!(confirmed_leaders.len(), 1);
+
+        // The confirmation expires along with the reports.
+        assert!(!reports.is_confirmed_unavailable(&leader, later + Duration::from_secs(120)));
+    }
+}
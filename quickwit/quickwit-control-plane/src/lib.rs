@@ -23,11 +23,15 @@ pub mod indexing_scheduler;
 pub mod ingest;
 pub(crate) mod metrics;
 pub(crate) mod model;
+#[cfg(feature = "testsuite")]
+pub mod model_bench;
 
 use quickwit_common::tower::Pool;
 use quickwit_proto::indexing::{CpuCapacity, IndexingServiceClient, IndexingTask};
 use quickwit_proto::types::NodeId;
 
+pub use crate::model::ModelSnapshotStore;
+
 /// Indexer-node specific information stored in the pool of available indexer nodes
 #[derive(Debug, Clone)]
 pub struct IndexerNodeInfo {
@@ -19,7 +19,8 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    new_counter, new_gauge, new_gauge_vec, IntCounter, IntGauge, IntGaugeVec,
+    new_counter, new_counter_vec, new_gauge, new_gauge_vec, new_histogram_vec_with_buckets,
+    HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -37,6 +38,19 @@ pub struct ControlPlaneMetrics {
     pub open_shards_total: IntGaugeVec<1>,
     pub local_shards: IntGauge,
     pub remote_shards: IntGauge,
+    pub opened_shards_total: IntCounter,
+    pub closed_shards_total: IntCounter,
+    pub failed_init_shards_total: IntCounter,
+    pub rejected_shard_allocations_total: IntCounter,
+    pub ingestion_quota_rejections_total: IntCounter,
+    pub rebalance_shards_ops_total: IntCounter,
+    pub scale_up_shards_ops_total: IntCounter,
+    pub scale_down_shards_ops_total: IntCounter,
+    pub suppressed_scale_up_shards_ops_total: IntCounter,
+    pub suppressed_scale_down_shards_ops_total: IntCounter,
+    pub unhealthy_ingesters: IntGauge,
+    pub retain_shards_digest_mismatches_total: IntCounter,
+    pub ingest_to_publish_latency_seconds: HistogramVec<2>,
 }
 
 impl ControlPlaneMetrics {
@@ -59,6 +73,29 @@ impl Default for ControlPlaneMetrics {
         );
         let local_shards = shards.with_label_values(["local"]);
         let remote_shards = shards.with_label_values(["remote"]);
+
+        let scale_shards_ops_total: IntCounterVec<1> = new_counter_vec(
+            "scale_shards_ops_total",
+            "Number of attempts to scale up or down the number of shards of a source.",
+            "control_plane",
+            &[],
+            ["scaling_mode"],
+        );
+        let scale_up_shards_ops_total = scale_shards_ops_total.with_label_values(["up"]);
+        let scale_down_shards_ops_total = scale_shards_ops_total.with_label_values(["down"]);
+
+        let suppressed_scale_shards_ops_total: IntCounterVec<1> = new_counter_vec(
+            "suppressed_scale_shards_ops_total",
+            "Number of attempts to scale up or down the number of shards of a source suppressed \
+             by the scaling cooldown.",
+            "control_plane",
+            &[],
+            ["scaling_mode"],
+        );
+        let suppressed_scale_up_shards_ops_total =
+            suppressed_scale_shards_ops_total.with_label_values(["up"]);
+        let suppressed_scale_down_shards_ops_total =
+            suppressed_scale_shards_ops_total.with_label_values(["down"]);
         ControlPlaneMetrics {
             indexes_total: new_gauge("indexes_total", "Number of indexes.", "control_plane", &[]),
             restart_total: new_counter(
@@ -92,6 +129,65 @@ impl Default for ControlPlaneMetrics {
             ),
             local_shards,
             remote_shards,
+            opened_shards_total: new_counter(
+                "opened_shards_total",
+                "Number of shards opened and initialized by the control plane.",
+                "control_plane",
+            ),
+            closed_shards_total: new_counter(
+                "closed_shards_total",
+                "Number of shards closed by the control plane upon scale down or rebalance.",
+                "control_plane",
+            ),
+            failed_init_shards_total: new_counter(
+                "failed_init_shards_total",
+                "Number of shards the control plane failed to initialize on ingesters.",
+                "control_plane",
+            ),
+            rejected_shard_allocations_total: new_counter(
+                "rejected_shard_allocations_total",
+                "Number of shard allocations rejected because not enough ingesters were available.",
+                "control_plane",
+            ),
+            ingestion_quota_rejections_total: new_counter(
+                "ingestion_quota_rejections_total",
+                "Number of shard openings refused because the ingestion quota of the index or its \
+                 tenant was exceeded.",
+                "control_plane",
+            ),
+            rebalance_shards_ops_total: new_counter(
+                "rebalance_shards_ops_total",
+                "Number of rebalance shards operations.",
+                "control_plane",
+            ),
+            scale_up_shards_ops_total,
+            scale_down_shards_ops_total,
+            suppressed_scale_up_shards_ops_total,
+            suppressed_scale_down_shards_ops_total,
+            unhealthy_ingesters: new_gauge(
+                "unhealthy_ingesters",
+                "Number of ingesters that failed several consecutive health probes.",
+                "control_plane",
+                &[],
+            ),
+            retain_shards_digest_mismatches_total: new_counter(
+                "retain_shards_digest_mismatches_total",
+                "Number of incremental retain shards syncs for which the shards hosted by the \
+                 ingester did not match the shards expected by the control plane.",
+                "control_plane",
+            ),
+            ingest_to_publish_latency_seconds: new_histogram_vec_with_buckets(
+                "ingest_to_publish_latency_seconds",
+                "Time elapsed between the append of a batch of documents to a shard and the \
+                 publication of the split containing it (i.e. the moment it becomes searchable) \
+                 per source, as reported by the ingesters.",
+                "control_plane",
+                &[],
+                ["index_id", "source_id"],
+                vec![
+                    0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 45.0, 60.0, 90.0, 120.0, 300.0, 600.0,
+                ],
+            ),
         }
     }
 }
@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod shard_table;
+mod snapshot;
 
 use std::borrow::Cow;
 use std::collections::BTreeSet;
@@ -29,19 +30,34 @@ use anyhow::bail;
 use fnv::{FnvHashMap, FnvHashSet};
 use quickwit_common::pretty::PrettyDisplay;
 use quickwit_common::Progress;
-use quickwit_config::SourceConfig;
+use quickwit_config::{IndexTemplate, RolloverPolicy, SourceConfig, SourceParams};
 use quickwit_ingest::ShardInfos;
-use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt};
-use quickwit_proto::control_plane::ControlPlaneResult;
+use quickwit_metastore::checkpoint::IndexCheckpointDelta;
+use quickwit_metastore::{
+    is_reindex_alias_flipped, reindex_alias_flipped_checkpoint_delta, IndexMetadata,
+    ListIndexesMetadataResponseExt,
+};
+use quickwit_proto::control_plane::{
+    ControlPlaneResult, ListShardsRequest, ListShardsResponse, ShardInfo,
+};
 use quickwit_proto::ingest::Shard;
 use quickwit_proto::metastore::{
-    self, EntityKind, ListIndexesMetadataRequest, ListShardsSubrequest, ListShardsSubresponse,
-    MetastoreError, MetastoreService, MetastoreServiceClient, SourceType,
+    self, serde_utils, EntityKind, FindIndexTemplateMatchesRequest, ListIndexesMetadataRequest,
+    ListShardsSubrequest, ListShardsSubresponse, MetastoreError, MetastoreService,
+    MetastoreServiceClient, SourceType,
 };
 use quickwit_proto::types::{IndexId, IndexUid, NodeId, ShardId, SourceId, SourceUid};
-pub(super) use shard_table::{ScalingMode, ShardEntry, ShardLocations, ShardStats, ShardTable};
+pub(super) use shard_table::{
+    OpenShardsSync, ScalingMode, ShardEntry, ShardLocations, ShardStats, ShardTable,
+};
+pub(crate) use snapshot::ModelSnapshot;
+pub use snapshot::ModelSnapshotStore;
+use snapshot::SourceSnapshot;
 use tracing::{info, instrument, warn};
 
+/// Maximum number of subrequests of the `ListShards` requests issued when loading the model.
+const LIST_SHARDS_BATCH_SIZE: usize = 500;
+
 /// The control plane maintains a model in sync with the metastore.
 ///
 /// The model stays consistent with the metastore, because all
@@ -56,6 +72,45 @@ pub(crate) struct ControlPlaneModel {
     index_uid_table: FnvHashMap<IndexId, IndexUid>,
     index_table: FnvHashMap<IndexUid, IndexMetadata>,
     shard_table: ShardTable,
+    // Unlike the rest of the model, write aliases are not persisted in the metastore. They are
+    // rebuilt from the index templates and the existing `<alias>-NNNNNN` write indexes when the
+    // model is loaded, so that rollovers resume after a control plane restart.
+    write_alias_table: FnvHashMap<IndexId, WriteAlias>,
+    // Write aliases of the reindex sources. They are derived from the source configs, and whether
+    // they were flipped is recorded in the checkpoints of the reindex sources.
+    reindex_alias_table: FnvHashMap<IndexId, ReindexAlias>,
+    // Sources paused with the `PauseSource` API. Pausing a source is a transient operational
+    // decision, so it is not persisted in the metastore either.
+    paused_sources: FnvHashSet<SourceUid>,
+}
+
+/// A write alias is an index ID matched by an index template with rollover enabled. It resolves to
+/// the current write index of the alias, which is rolled over according to the template's
+/// rollover policy.
+#[derive(Debug, Clone)]
+pub(crate) struct WriteAlias {
+    pub index_template: IndexTemplate,
+    pub write_index_uid: IndexUid,
+    pub generation: u64,
+}
+
+impl WriteAlias {
+    pub fn rollover_policy(&self) -> &RolloverPolicy {
+        self.index_template
+            .rollover_policy_opt
+            .as_ref()
+            .expect("index template of a write alias should have a rollover policy")
+    }
+}
+
+/// The write alias of a reindex source. It resolves to the source index of the reindex until the
+/// control plane flips it to the index of the reindex source, once the source index has been
+/// backfilled.
+#[derive(Debug, Clone)]
+pub(crate) struct ReindexAlias {
+    pub source_uid: SourceUid,
+    pub source_index_id: IndexId,
+    pub flipped: bool,
 }
 
 impl ControlPlaneModel {
@@ -87,8 +142,6 @@ impl ControlPlaneModel {
         metastore: &mut MetastoreServiceClient,
         progress: &Progress,
     ) -> ControlPlaneResult<()> {
-        const BATCH_SIZE: usize = 500;
-
         let now = Instant::now();
         self.clear();
 
@@ -124,7 +177,9 @@ impl ControlPlaneModel {
             }
             let num_subrequests = next_list_shards_request.subrequests.len();
 
-            if num_subrequests > 0 && (num_subrequests >= BATCH_SIZE || idx == num_indexes - 1) {
+            if num_subrequests > 0
+                && (num_subrequests >= LIST_SHARDS_BATCH_SIZE || idx == num_indexes - 1)
+            {
                 let list_shards_request = mem::take(&mut next_list_shards_request);
                 let list_shards_response = progress
                     .protect_future(metastore.list_shards(list_shards_request))
@@ -149,13 +204,347 @@ impl ControlPlaneModel {
              {num_sources} sources, {num_shards} shards)",
             now.elapsed().pretty_display()
         );
+        self.load_write_aliases(metastore, progress).await?;
+        Ok(())
+    }
+
+    /// Rebuilds the write aliases from the write indexes of the model and the index templates
+    /// with rollover enabled that match their alias.
+    pub(crate) async fn load_write_aliases(
+        &mut self,
+        metastore: &mut MetastoreServiceClient,
+        progress: &Progress,
+    ) -> ControlPlaneResult<()> {
+        let aliases: BTreeSet<IndexId> = self
+            .index_uid_table
+            .keys()
+            .filter_map(|index_id| RolloverPolicy::parse_alias(index_id))
+            .map(|(alias, _generation)| alias.to_string())
+            .filter(|alias| !self.index_uid_table.contains_key(alias))
+            .collect();
+
+        if aliases.is_empty() {
+            return Ok(());
+        }
+        let find_index_template_matches_request = FindIndexTemplateMatchesRequest {
+            index_ids: aliases.into_iter().collect(),
+        };
+        let find_index_template_matches_response = progress
+            .protect_future(
+                metastore.find_index_template_matches(find_index_template_matches_request),
+            )
+            .await?;
+
+        for index_template_match in find_index_template_matches_response.matches {
+            let index_template: IndexTemplate =
+                serde_utils::from_json_str(&index_template_match.index_template_json)?;
+
+            if index_template.rollover_policy_opt.is_none() {
+                continue;
+            }
+            let alias = index_template_match.index_id;
+
+            if let Some((generation, write_index_uid)) = self.latest_write_index(&alias) {
+                let write_alias = WriteAlias {
+                    index_template,
+                    write_index_uid,
+                    generation,
+                };
+                self.set_write_alias(alias, write_alias);
+            }
+        }
         Ok(())
     }
 
+    /// Takes a snapshot of the indexes, sources, and shards of the model.
+    pub(crate) fn snapshot(&self, cluster_id: String) -> ModelSnapshot {
+        let indexes = self.index_table.values().cloned().collect();
+        let sources = self.shard_table.snapshot_sources();
+        let paused_sources = self
+            .paused_sources
+            .iter()
+            .map(|source_uid| (source_uid.index_uid.clone(), source_uid.source_id.clone()))
+            .collect();
+        ModelSnapshot::new(cluster_id, indexes, sources, paused_sources)
+    }
+
+    /// Reconciles a snapshot with the metastore, which may have been modified while no control
+    /// plane was running: indexes created or deleted with the CLI, shards closed or truncated by
+    /// indexers, etc. The index metadata and the shard states and positions of the snapshot are
+    /// replaced with the ones listed from the metastore. Returns `false` if the indexes, sources,
+    /// or shard IDs of the snapshot diverge from the metastore, in which case the snapshot must be
+    /// discarded.
+    pub(crate) async fn reconcile_snapshot(
+        snapshot: &mut ModelSnapshot,
+        metastore: &mut MetastoreServiceClient,
+        progress: &Progress,
+    ) -> ControlPlaneResult<bool> {
+        let indexes_metadata = progress
+            .protect_future(metastore.list_indexes_metadata(ListIndexesMetadataRequest::all()))
+            .await?
+            .deserialize_indexes_metadata()
+            .await?;
+
+        let index_uids: BTreeSet<&IndexUid> = indexes_metadata
+            .iter()
+            .map(|index_metadata| &index_metadata.index_uid)
+            .collect();
+        let snapshot_index_uids: BTreeSet<&IndexUid> = snapshot
+            .indexes
+            .iter()
+            .map(|index_metadata| &index_metadata.index_uid)
+            .collect();
+
+        if index_uids != snapshot_index_uids {
+            info!("discarding control plane model snapshot: indexes were created or deleted");
+            return Ok(false);
+        }
+        let source_uids: BTreeSet<SourceUid> = indexes_metadata
+            .iter()
+            .flat_map(|index_metadata| {
+                index_metadata
+                    .sources
+                    .values()
+                    .filter(|source_config| source_config.source_type() == SourceType::IngestV2)
+                    .map(|source_config| SourceUid {
+                        index_uid: index_metadata.index_uid.clone(),
+                        source_id: source_config.source_id.clone(),
+                    })
+            })
+            .collect();
+        let mut snapshot_sources: FnvHashMap<SourceUid, &mut SourceSnapshot> = snapshot
+            .sources
+            .iter_mut()
+            .map(|source_snapshot| {
+                let source_uid = SourceUid {
+                    index_uid: source_snapshot.index_uid.clone(),
+                    source_id: source_snapshot.source_id.clone(),
+                };
+                (source_uid, source_snapshot)
+            })
+            .collect();
+
+        if snapshot_sources
+            .keys()
+            .any(|source_uid| !source_uids.contains(source_uid))
+        {
+            info!("discarding control plane model snapshot: sources were deleted");
+            return Ok(false);
+        }
+        let source_uids: Vec<SourceUid> = source_uids.into_iter().collect();
+
+        for source_uids_chunk in source_uids.chunks(LIST_SHARDS_BATCH_SIZE) {
+            let subrequests = source_uids_chunk
+                .iter()
+                .map(|source_uid| ListShardsSubrequest {
+                    index_uid: Some(source_uid.index_uid.clone()),
+                    source_id: source_uid.source_id.clone(),
+                    shard_state: None,
+                })
+                .collect();
+            let list_shards_request = metastore::ListShardsRequest { subrequests };
+            let list_shards_response = progress
+                .protect_future(metastore.list_shards(list_shards_request))
+                .await?;
+
+            for list_shards_subresponse in list_shards_response.subresponses {
+                let ListShardsSubresponse {
+                    index_uid,
+                    source_id,
+                    shards,
+                } = list_shards_subresponse;
+                let source_uid = SourceUid {
+                    index_uid: index_uid.expect("`index_uid` should be a required field"),
+                    source_id,
+                };
+                let mut shards_by_id: FnvHashMap<ShardId, Shard> = shards
+                    .into_iter()
+                    .map(|shard| (shard.shard_id().clone(), shard))
+                    .collect();
+                let source_snapshot_opt = snapshot_sources.get_mut(&source_uid);
+
+                let shard_ids_match = match &source_snapshot_opt {
+                    Some(source_snapshot) => {
+                        shards_by_id.len() == source_snapshot.shards.len()
+                            && source_snapshot.shards.iter().all(|shard_snapshot| {
+                                shards_by_id.contains_key(shard_snapshot.shard.shard_id())
+                            })
+                    }
+                    None => shards_by_id.is_empty(),
+                };
+                if !shard_ids_match {
+                    info!(
+                        "discarding control plane model snapshot: shards were opened or deleted \
+                         for source `{source_uid}`"
+                    );
+                    return Ok(false);
+                }
+                let Some(source_snapshot) = source_snapshot_opt else {
+                    continue;
+                };
+                for shard_snapshot in source_snapshot.shards.iter_mut() {
+                    if let Some(shard) = shards_by_id.remove(shard_snapshot.shard.shard_id()) {
+                        shard_snapshot.shard = shard;
+                    }
+                }
+            }
+        }
+        snapshot.indexes = indexes_metadata;
+        Ok(true)
+    }
+
+    /// Replaces the entire state of the model with the content of a snapshot.
+    pub(crate) fn restore_from_snapshot(&mut self, snapshot: ModelSnapshot) {
+        let now = Instant::now();
+        self.clear();
+
+        let snapshot_age = snapshot.age();
+        let num_indexes = snapshot.indexes.len();
+        let mut num_shards = 0;
+
+        for index_metadata in snapshot.indexes {
+            self.add_index(index_metadata);
+        }
+        for source_snapshot in snapshot.sources {
+            num_shards += source_snapshot.shards.len();
+            self.shard_table
+                .restore_source(source_snapshot, snapshot_age);
+        }
+        for (index_uid, source_id) in snapshot.paused_sources {
+            self.paused_sources.insert(SourceUid {
+                index_uid,
+                source_id,
+            });
+        }
+        info!(
+            "restored control plane model from snapshot in {} ({num_indexes} indexes, \
+             {num_sources} sources, {num_shards} shards)",
+            now.elapsed().pretty_display(),
+            num_sources = self.num_sources(),
+        );
+    }
+
     pub fn index_uid(&self, index_id: &str) -> Option<IndexUid> {
         self.index_uid_table.get(index_id).cloned()
     }
 
+    /// Returns the UID of the index the documents targeting `index_id` should be written to.
+    /// `index_id` is either an index ID or a write alias.
+    pub fn resolve_write_index_uid(&self, index_id: &str) -> Option<IndexUid> {
+        if let Some(index_uid) = self.index_uid(index_id) {
+            return Some(index_uid);
+        }
+        if let Some(write_alias) = self.write_alias_table.get(index_id) {
+            return Some(write_alias.write_index_uid.clone());
+        }
+        let reindex_alias = self.reindex_alias_table.get(index_id)?;
+
+        if reindex_alias.flipped {
+            Some(reindex_alias.source_uid.index_uid.clone())
+        } else {
+            self.index_uid(&reindex_alias.source_index_id)
+        }
+    }
+
+    pub(crate) fn index_metadata(&self, index_uid: &IndexUid) -> Option<&IndexMetadata> {
+        self.index_table.get(index_uid)
+    }
+
+    pub(crate) fn write_aliases(&self) -> impl Iterator<Item = (&IndexId, &WriteAlias)> + '_ {
+        self.write_alias_table.iter()
+    }
+
+    /// Points the write alias `alias` to a new write index.
+    pub(crate) fn set_write_alias(&mut self, alias: IndexId, write_alias: WriteAlias) {
+        info!(
+            alias=%alias,
+            write_index_uid=%write_alias.write_index_uid,
+            "setting write alias"
+        );
+        self.write_alias_table.insert(alias, write_alias);
+    }
+
+    pub(crate) fn reindex_alias(&self, alias: &str) -> Option<&ReindexAlias> {
+        self.reindex_alias_table.get(alias)
+    }
+
+    /// Flips the write alias of a reindex source to the index of the source and records it in the
+    /// checkpoint of the source, as the metastore does when the flip is published.
+    pub(crate) fn flip_reindex_alias(&mut self, alias: &str) {
+        let Some(reindex_alias) = self.reindex_alias_table.get_mut(alias) else {
+            return;
+        };
+        info!(
+            alias=%alias,
+            index_uid=%reindex_alias.source_uid.index_uid,
+            source_id=%reindex_alias.source_uid.source_id,
+            "flipping reindex alias"
+        );
+        reindex_alias.flipped = true;
+
+        if let Some(index_metadata) = self
+            .index_table
+            .get_mut(&reindex_alias.source_uid.index_uid)
+        {
+            let checkpoint_delta = IndexCheckpointDelta {
+                source_id: reindex_alias.source_uid.source_id.clone(),
+                source_delta: reindex_alias_flipped_checkpoint_delta(),
+            };
+            if let Err(error) = index_metadata.checkpoint.try_apply_delta(checkpoint_delta) {
+                warn!(%error, alias=%alias, "reindex alias already flipped");
+            }
+        }
+    }
+
+    /// Registers the write aliases of the reindex sources of an index.
+    fn add_reindex_aliases(&mut self, index_metadata: &IndexMetadata) {
+        for (source_id, source_config) in &index_metadata.sources {
+            let SourceParams::Reindex(reindex_params) = &source_config.source_params else {
+                continue;
+            };
+            let Some(alias) = &reindex_params.alias else {
+                continue;
+            };
+            let flipped = index_metadata
+                .checkpoint
+                .source_checkpoint(source_id)
+                .map_or(false, is_reindex_alias_flipped);
+            let reindex_alias = ReindexAlias {
+                source_uid: SourceUid {
+                    index_uid: index_metadata.index_uid.clone(),
+                    source_id: source_id.clone(),
+                },
+                source_index_id: reindex_params.source_index_id.clone(),
+                flipped,
+            };
+            if let Some(previous_reindex_alias) = self
+                .reindex_alias_table
+                .insert(alias.clone(), reindex_alias)
+            {
+                if previous_reindex_alias.source_uid.index_uid != index_metadata.index_uid
+                    || previous_reindex_alias.source_uid.source_id != *source_id
+                {
+                    warn!(
+                        alias=%alias,
+                        "reindex alias is shared by several reindex sources, last one wins"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Finds the most recent write index of the write alias `alias`, i.e. the index with the
+    /// highest generation, and returns its generation and UID.
+    pub(crate) fn latest_write_index(&self, alias: &str) -> Option<(u64, IndexUid)> {
+        self.index_uid_table
+            .iter()
+            .filter_map(|(index_id, index_uid)| {
+                RolloverPolicy::parse_generation(alias, index_id)
+                    .map(|generation| (generation, index_uid.clone()))
+            })
+            .max_by_key(|(generation, _)| *generation)
+    }
+
     fn update_metrics(&self) {
         crate::metrics::CONTROL_PLANE_METRICS
             .indexes_total
@@ -190,14 +579,40 @@ impl ControlPlaneModel {
                 self.shard_table.add_source(&index_uid, source_id);
             }
         }
+        self.add_reindex_aliases(&index_metadata);
         self.index_table.insert(index_uid, index_metadata);
         self.update_metrics();
     }
 
+    /// Replaces the metadata of an existing index, typically after an index update. Returns
+    /// whether the doc mapping of the index changed.
+    pub(crate) fn update_index(&mut self, index_metadata: IndexMetadata) -> bool {
+        let index_uid = index_metadata.index_uid.clone();
+
+        let Some(current_index_metadata) = self.index_table.get(&index_uid) else {
+            self.add_index(index_metadata);
+            return false;
+        };
+        let doc_mapping_changed = current_index_metadata.index_config.doc_mapping
+            != index_metadata.index_config.doc_mapping;
+
+        self.reindex_alias_table
+            .retain(|_, reindex_alias| reindex_alias.source_uid.index_uid != index_uid);
+        self.add_reindex_aliases(&index_metadata);
+        self.index_table.insert(index_uid, index_metadata);
+        doc_mapping_changed
+    }
+
     pub(crate) fn delete_index(&mut self, index_uid: &IndexUid) {
         self.index_table.remove(index_uid);
         self.index_uid_table.remove(&index_uid.index_id);
         self.shard_table.delete_index(&index_uid.index_id);
+        self.write_alias_table
+            .retain(|_, write_alias| write_alias.write_index_uid != *index_uid);
+        self.reindex_alias_table
+            .retain(|_, reindex_alias| reindex_alias.source_uid.index_uid != *index_uid);
+        self.paused_sources
+            .retain(|source_uid| source_uid.index_uid != *index_uid);
         self.update_metrics();
     }
 
@@ -219,10 +634,27 @@ impl ControlPlaneModel {
             self.shard_table
                 .add_source(index_uid, &source_config.source_id);
         }
+        if let SourceParams::Reindex(reindex_params) = &source_config.source_params {
+            if let Some(alias) = &reindex_params.alias {
+                let reindex_alias = ReindexAlias {
+                    source_uid: SourceUid {
+                        index_uid: index_uid.clone(),
+                        source_id: source_config.source_id.clone(),
+                    },
+                    source_index_id: reindex_params.source_index_id.clone(),
+                    flipped: false,
+                };
+                self.reindex_alias_table
+                    .insert(alias.clone(), reindex_alias);
+            }
+        }
         Ok(())
     }
 
     pub(crate) fn delete_source(&mut self, source_uid: &SourceUid) {
+        self.paused_sources.remove(source_uid);
+        self.reindex_alias_table
+            .retain(|_, reindex_alias| reindex_alias.source_uid != *source_uid);
         // Removing shards from shard table.
         self.shard_table
             .delete_source(&source_uid.index_uid, &source_uid.source_id);
@@ -259,6 +691,52 @@ impl ControlPlaneModel {
         Ok(has_changed)
     }
 
+    /// Resolves the UID of a source from its index ID and source ID. Returns an error if the index
+    /// or the source could not be found.
+    pub(crate) fn source_uid(
+        &self,
+        index_id: &str,
+        source_id: &str,
+    ) -> ControlPlaneResult<SourceUid> {
+        let Some(index_uid) = self.index_uid(index_id) else {
+            let entity = EntityKind::Index {
+                index_id: index_id.to_string(),
+            };
+            return Err(MetastoreError::NotFound(entity).into());
+        };
+        let source_exists = self
+            .index_table
+            .get(&index_uid)
+            .map(|index_metadata| index_metadata.sources.contains_key(source_id))
+            .unwrap_or(false);
+        if !source_exists {
+            let entity = EntityKind::Source {
+                index_id: index_id.to_string(),
+                source_id: source_id.to_string(),
+            };
+            return Err(MetastoreError::NotFound(entity).into());
+        }
+        let source_uid = SourceUid {
+            index_uid,
+            source_id: source_id.to_string(),
+        };
+        Ok(source_uid)
+    }
+
+    /// Pauses a source. Returns `true` if the source was not already paused.
+    pub(crate) fn pause_source(&mut self, source_uid: &SourceUid) -> bool {
+        self.paused_sources.insert(source_uid.clone())
+    }
+
+    /// Resumes a paused source. Returns `true` if the source was paused.
+    pub(crate) fn resume_source(&mut self, source_uid: &SourceUid) -> bool {
+        self.paused_sources.remove(source_uid)
+    }
+
+    pub(crate) fn is_source_paused(&self, source_uid: &SourceUid) -> bool {
+        self.paused_sources.contains(source_uid)
+    }
+
     pub(crate) fn set_shards_as_unavailable(&mut self, unavailable_leaders: &FnvHashSet<NodeId>) {
         self.shard_table
             .set_shards_as_unavailable(unavailable_leaders);
@@ -268,12 +746,70 @@ impl ControlPlaneModel {
         self.shard_table.all_shards()
     }
 
+    /// Finds a shard by its ID. Shard IDs are unique across sources.
+    pub(crate) fn find_shard(&self, shard_id: &ShardId) -> Option<&ShardEntry> {
+        self.shard_table
+            .all_shards()
+            .find(|shard_entry| shard_entry.shard_id() == shard_id)
+    }
+
     pub(crate) fn all_shards_with_source(
         &self,
     ) -> impl Iterator<Item = (&SourceUid, impl Iterator<Item = &ShardEntry>)> + '_ {
         self.shard_table.all_shards_with_source()
     }
 
+    /// Lists the shards matching the filters of the request, sorted by index UID, source ID, and
+    /// shard ID.
+    pub(crate) fn list_shards(&self, request: &ListShardsRequest) -> ListShardsResponse {
+        let shard_state_opt = request.shard_state.map(|_| request.shard_state());
+
+        let mut matching_shard_entries: Vec<&ShardEntry> = self
+            .shard_table
+            .all_shards()
+            .filter(|shard_entry| {
+                request.index_id.as_ref().map_or(true, |index_id| {
+                    shard_entry.index_uid().index_id == *index_id
+                }) && request
+                    .source_id
+                    .as_ref()
+                    .map_or(true, |source_id| shard_entry.source_id == *source_id)
+                    && shard_state_opt
+                        .map_or(true, |shard_state| shard_entry.shard_state() == shard_state)
+                    && request
+                        .leader_id
+                        .as_ref()
+                        .map_or(true, |leader_id| shard_entry.leader_id == *leader_id)
+            })
+            .collect();
+        let num_matching_shards = matching_shard_entries.len() as u32;
+
+        matching_shard_entries.sort_unstable_by(|left, right| {
+            (left.index_uid(), &left.source_id, left.shard_id()).cmp(&(
+                right.index_uid(),
+                &right.source_id,
+                right.shard_id(),
+            ))
+        });
+        let limit = request
+            .limit
+            .map(|limit| limit as usize)
+            .unwrap_or(usize::MAX);
+        let shards = matching_shard_entries
+            .into_iter()
+            .skip(request.offset as usize)
+            .take(limit)
+            .map(|shard_entry| ShardInfo {
+                shard: Some(shard_entry.shard.clone()),
+                ingestion_rate_mib_per_sec: shard_entry.ingestion_rate.0 as u32,
+            })
+            .collect();
+        ListShardsResponse {
+            shards,
+            num_matching_shards,
+        }
+    }
+
     pub fn list_shards_for_node(
         &self,
         ingester: &NodeId,
@@ -285,6 +821,26 @@ impl ControlPlaneModel {
         }
     }
 
+    /// Returns the number of shards (leader or follower) hosted by the given ingester.
+    pub fn num_shards_for_node(&self, ingester: &NodeId) -> usize {
+        self.shard_table
+            .list_shards_for_node(ingester)
+            .map(|shards_for_node| shards_for_node.values().map(BTreeSet::len).sum())
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of open shards of each leader.
+    pub fn num_open_shards_per_leader(&self) -> impl Iterator<Item = (&NodeId, usize)> + '_ {
+        self.shard_table.num_open_shards_per_leader()
+    }
+
+    /// Lists the open shards of each leader.
+    pub(crate) fn open_shards_per_leader(
+        &self,
+    ) -> impl Iterator<Item = (&NodeId, impl Iterator<Item = &ShardEntry>)> + '_ {
+        self.shard_table.open_shards_per_leader()
+    }
+
     pub fn list_shards_for_index<'a>(
         &'a self,
         index_uid: &'a IndexUid,
@@ -320,15 +876,68 @@ impl ControlPlaneModel {
     }
 
     /// Finds open shards for a given index and source and whose leaders are not in the set of
-    /// unavailable ingesters.
-    pub fn find_open_shards(
-        &self,
+    /// unavailable ingesters, sorted by shard ID. The shards are returned in full or as a delta
+    /// since the shard table version known by the router.
+    pub fn sync_open_shards(
+        &mut self,
         index_uid: &IndexUid,
         source_id: &SourceId,
         unavailable_leaders: &FnvHashSet<NodeId>,
-    ) -> Option<Vec<ShardEntry>> {
+        known_version_opt: Option<u64>,
+    ) -> Option<OpenShardsSync> {
+        self.shard_table.sync_open_shards(
+            index_uid,
+            source_id,
+            unavailable_leaders,
+            known_version_opt,
+        )
+    }
+
+    /// Returns true if the ingestion rate observed over the open shards of the index, or of all the
+    /// indexes of its tenant, reached the ingestion quota of the index. When the indexes of a
+    /// tenant are configured with different quotas, the lowest one applies.
+    pub(crate) fn is_ingestion_quota_exceeded(&self, index_uid: &IndexUid) -> bool {
+        let Some(ingestion_quota) = self.index_table.get(index_uid).and_then(|index_metadata| {
+            index_metadata
+                .index_config
+                .indexing_settings
+                .ingestion_quota
+                .as_ref()
+        }) else {
+            return false;
+        };
+        let Some(tenant_id) = &ingestion_quota.tenant_id else {
+            return self.open_shards_ingestion_rate(index_uid)
+                >= ingestion_quota.max_ingestion_rate_mib_per_sec;
+        };
+        let mut max_ingestion_rate = ingestion_quota.max_ingestion_rate_mib_per_sec;
+        let mut ingestion_rate = 0.;
+
+        for (tenant_index_uid, tenant_index_metadata) in &self.index_table {
+            let Some(tenant_ingestion_quota) = &tenant_index_metadata
+                .index_config
+                .indexing_settings
+                .ingestion_quota
+            else {
+                continue;
+            };
+            if tenant_ingestion_quota.tenant_id.as_ref() != Some(tenant_id) {
+                continue;
+            }
+            max_ingestion_rate =
+                max_ingestion_rate.min(tenant_ingestion_quota.max_ingestion_rate_mib_per_sec);
+            ingestion_rate += self.open_shards_ingestion_rate(tenant_index_uid);
+        }
+        ingestion_rate >= max_ingestion_rate
+    }
+
+    /// Sums the ingestion rates of the open shards of an index.
+    fn open_shards_ingestion_rate(&self, index_uid: &IndexUid) -> f32 {
         self.shard_table
-            .find_open_shards(index_uid, source_id, unavailable_leaders)
+            .list_shards_for_index(index_uid)
+            .filter(|shard_entry| shard_entry.is_open())
+            .map(|shard_entry| shard_entry.ingestion_rate.0 as f32)
+            .sum()
     }
 
     /// Updates the state and ingestion rate of the shards according to the given shard infos.
@@ -340,6 +949,11 @@ impl ControlPlaneModel {
         self.shard_table.update_shards(source_uid, shard_infos)
     }
 
+    /// Computes the number of open shards and their average ingestion rate for a source.
+    pub fn shard_stats(&self, source_uid: &SourceUid) -> ShardStats {
+        self.shard_table.shard_stats(source_uid)
+    }
+
     /// Sets the state of the shards identified by their index UID, source ID, and shard IDs to
     /// `Closed`.
     pub fn close_shards(&mut self, source_uid: &SourceUid, shard_ids: &[ShardId]) -> Vec<ShardId> {
@@ -362,6 +976,20 @@ impl ControlPlaneModel {
             .acquire_scaling_permits(source_uid, scaling_mode, num_permits)
     }
 
+    pub fn check_scaling_cooldown(
+        &mut self,
+        source_uid: &SourceUid,
+        scaling_mode: ScalingMode,
+    ) -> Option<bool> {
+        self.shard_table
+            .check_scaling_cooldown(source_uid, scaling_mode)
+    }
+
+    pub fn record_scaling_action(&mut self, source_uid: &SourceUid, scaling_mode: ScalingMode) {
+        self.shard_table
+            .record_scaling_action(source_uid, scaling_mode)
+    }
+
     pub fn drain_scaling_permits(&mut self, source_uid: &SourceUid, scaling_mode: ScalingMode) {
         self.shard_table
             .drain_scaling_permits(source_uid, scaling_mode)
@@ -382,10 +1010,18 @@ impl ControlPlaneModel {
 mod tests {
     use std::str::FromStr;
 
-    use quickwit_config::{SourceConfig, SourceParams, INGEST_V2_SOURCE_ID};
+    use quickwit_config::{
+        IngestionQuotaConfig, ReindexSourceParams, SourceConfig, SourceParams, INGEST_V2_SOURCE_ID,
+    };
+    use quickwit_ingest::RateMibPerSec;
     use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::control_plane::ControlPlaneError;
     use quickwit_proto::ingest::{Shard, ShardState};
-    use quickwit_proto::metastore::{ListIndexesMetadataResponse, MockMetastoreService};
+    use quickwit_proto::metastore::{
+        FindIndexTemplateMatchesResponse, IndexTemplateMatch, ListIndexesMetadataResponse,
+        MockMetastoreService,
+    };
+    use quickwit_proto::types::Position;
 
     use super::*;
 
@@ -508,6 +1144,108 @@ mod tests {
         assert_eq!(model.index_uid("test-index").unwrap(), index_uid);
     }
 
+    #[test]
+    fn test_control_plane_model_update_index() {
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata.clone());
+
+        let mut updated_index_metadata = index_metadata.clone();
+        updated_index_metadata.index_config.retention_policy_opt = None;
+        assert!(!model.update_index(updated_index_metadata.clone()));
+
+        updated_index_metadata.index_config.doc_mapping.store_source =
+            !index_metadata.index_config.doc_mapping.store_source;
+        assert!(model.update_index(updated_index_metadata.clone()));
+        assert_eq!(
+            model.index_table.get(&index_uid).unwrap(),
+            &updated_index_metadata
+        );
+    }
+
+    #[test]
+    fn test_control_plane_model_is_ingestion_quota_exceeded() {
+        fn add_index_with_shard(
+            model: &mut ControlPlaneModel,
+            index_id: &str,
+            ingestion_quota_opt: Option<IngestionQuotaConfig>,
+            rate: u16,
+        ) -> IndexUid {
+            let mut index_metadata = IndexMetadata::for_test(index_id, "ram:///indexes");
+            index_metadata
+                .index_config
+                .indexing_settings
+                .ingestion_quota = ingestion_quota_opt;
+            index_metadata
+                .add_source(SourceConfig::ingest_v2())
+                .unwrap();
+            let index_uid = index_metadata.index_uid.clone();
+            model.add_index(index_metadata);
+
+            let shard = Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(1)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-ingester".to_string(),
+                ..Default::default()
+            };
+            model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), vec![shard]);
+
+            let source_uid = SourceUid {
+                index_uid: index_uid.clone(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+            };
+            let shard_entry = model
+                .get_shards_for_source_mut(&source_uid)
+                .unwrap()
+                .get_mut(&ShardId::from(1))
+                .unwrap();
+            shard_entry.ingestion_rate = RateMibPerSec(rate);
+            index_uid
+        }
+        let mut model = ControlPlaneModel::default();
+        let index_uid_0 = add_index_with_shard(&mut model, "test-index-0", None, 100);
+        let index_uid_1 = add_index_with_shard(
+            &mut model,
+            "test-index-1",
+            Some(IngestionQuotaConfig {
+                tenant_id: None,
+                max_ingestion_rate_mib_per_sec: 10.,
+            }),
+            5,
+        );
+        let index_uid_2 = add_index_with_shard(
+            &mut model,
+            "test-index-2",
+            Some(IngestionQuotaConfig {
+                tenant_id: None,
+                max_ingestion_rate_mib_per_sec: 10.,
+            }),
+            10,
+        );
+        let tenant_quota = IngestionQuotaConfig {
+            tenant_id: Some("acme".to_string()),
+            max_ingestion_rate_mib_per_sec: 20.,
+        };
+        let index_uid_3 =
+            add_index_with_shard(&mut model, "test-index-3", Some(tenant_quota.clone()), 8);
+        let index_uid_4 =
+            add_index_with_shard(&mut model, "test-index-4", Some(tenant_quota.clone()), 8);
+
+        assert!(!model.is_ingestion_quota_exceeded(&index_uid_0));
+        assert!(!model.is_ingestion_quota_exceeded(&index_uid_1));
+        assert!(model.is_ingestion_quota_exceeded(&index_uid_2));
+        assert!(!model.is_ingestion_quota_exceeded(&index_uid_3));
+        assert!(!model.is_ingestion_quota_exceeded(&index_uid_4));
+
+        let index_uid_5 = add_index_with_shard(&mut model, "test-index-5", Some(tenant_quota), 4);
+        assert!(model.is_ingestion_quota_exceeded(&index_uid_3));
+        assert!(model.is_ingestion_quota_exceeded(&index_uid_4));
+        assert!(model.is_ingestion_quota_exceeded(&index_uid_5));
+    }
+
     #[test]
     fn test_control_plane_model_add_index_with_sources() {
         let mut model = ControlPlaneModel::default();
@@ -554,6 +1292,181 @@ mod tests {
         assert_eq!(model.shard_table.num_sources(), 0);
     }
 
+    #[tokio::test]
+    async fn test_control_plane_model_load_write_aliases() {
+        let progress = Progress::default();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(|_| {
+                let indexes = vec![
+                    IndexMetadata::for_test("test-logs-000001", "ram:///indexes/test-logs-000001"),
+                    IndexMetadata::for_test("test-logs-000002", "ram:///indexes/test-logs-000002"),
+                    IndexMetadata::for_test("test-traces-000001", "ram:///indexes/traces-000001"),
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index"),
+                ];
+                Ok(ListIndexesMetadataResponse::for_test(indexes))
+            });
+        mock_metastore
+            .expect_find_index_template_matches()
+            .return_once(|request| {
+                assert_eq!(request.index_ids, ["test-logs", "test-traces"]);
+
+                let mut index_template =
+                    IndexTemplate::for_test("test-template-logs", &["test-logs*"], 0);
+                index_template.rollover_policy_opt = Some(RolloverPolicy {
+                    max_age: Some("1 day".to_string()),
+                    max_size: None,
+                });
+                let index_template_json = serde_json::to_string(&index_template).unwrap();
+
+                // The template matching `test-traces` does not enable rollover.
+                let index_template =
+                    IndexTemplate::for_test("test-template-traces", &["test-traces*"], 0);
+                let other_index_template_json = serde_json::to_string(&index_template).unwrap();
+
+                Ok(FindIndexTemplateMatchesResponse {
+                    matches: vec![
+                        IndexTemplateMatch {
+                            template_id: "test-template-logs".to_string(),
+                            index_id: "test-logs".to_string(),
+                            index_template_json,
+                        },
+                        IndexTemplateMatch {
+                            template_id: "test-template-traces".to_string(),
+                            index_id: "test-traces".to_string(),
+                            index_template_json: other_index_template_json,
+                        },
+                    ],
+                })
+            });
+        let mut model = ControlPlaneModel::default();
+        let mut metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        model
+            .load_from_metastore(&mut metastore, &progress)
+            .await
+            .unwrap();
+
+        assert_eq!(model.write_aliases().count(), 1);
+
+        let write_index_uid = model.resolve_write_index_uid("test-logs").unwrap();
+        assert_eq!(write_index_uid.index_id, "test-logs-000002");
+
+        let (_, write_alias) = model.write_aliases().next().unwrap();
+        assert_eq!(write_alias.generation, 2);
+        assert_eq!(write_alias.index_template.template_id, "test-template-logs");
+
+        assert!(model.resolve_write_index_uid("test-traces").is_none());
+    }
+
+    #[test]
+    fn test_control_plane_model_write_aliases() {
+        let mut model = ControlPlaneModel::default();
+        assert!(model.latest_write_index("test-logs").is_none());
+        assert!(model.resolve_write_index_uid("test-logs").is_none());
+
+        let index_metadata = IndexMetadata::for_test("test-logs-000001", "ram:///indexes");
+        model.add_index(index_metadata);
+
+        let index_metadata = IndexMetadata::for_test("test-logs-000002", "ram:///indexes");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let index_metadata = IndexMetadata::for_test("test-logs-foo-000003", "ram:///indexes");
+        model.add_index(index_metadata);
+
+        let (generation, latest_index_uid) = model.latest_write_index("test-logs").unwrap();
+        assert_eq!(generation, 2);
+        assert_eq!(latest_index_uid, index_uid);
+
+        let mut index_template = IndexTemplate::for_test("test-template", &["test-logs*"], 0);
+        index_template.rollover_policy_opt = Some(RolloverPolicy {
+            max_age: Some("1 day".to_string()),
+            max_size: None,
+        });
+        let write_alias = WriteAlias {
+            index_template,
+            write_index_uid: index_uid.clone(),
+            generation,
+        };
+        model.set_write_alias("test-logs".to_string(), write_alias);
+
+        assert_eq!(
+            model.resolve_write_index_uid("test-logs").unwrap(),
+            index_uid
+        );
+        assert_eq!(
+            model
+                .resolve_write_index_uid("test-logs-000001")
+                .unwrap()
+                .index_id,
+            "test-logs-000001"
+        );
+        assert_eq!(model.write_aliases().count(), 1);
+
+        model.delete_index(&index_uid);
+        assert!(model.resolve_write_index_uid("test-logs").is_none());
+        assert_eq!(model.write_aliases().count(), 0);
+    }
+
+    #[test]
+    fn test_control_plane_model_reindex_aliases() {
+        let mut model = ControlPlaneModel::default();
+
+        let source_index_metadata = IndexMetadata::for_test("source-logs", "ram:///indexes");
+        let source_index_uid = source_index_metadata.index_uid.clone();
+        model.add_index(source_index_metadata);
+
+        let dest_index_metadata = IndexMetadata::for_test("dest-logs", "ram:///indexes");
+        let dest_index_uid = dest_index_metadata.index_uid.clone();
+        model.add_index(dest_index_metadata);
+
+        let reindex_params = ReindexSourceParams {
+            source_index_id: "source-logs".to_string(),
+            max_num_docs_per_sec: None,
+            alias: Some("logs".to_string()),
+        };
+        let source_config =
+            SourceConfig::for_test("reindex-source", SourceParams::Reindex(reindex_params));
+        model.add_source(&dest_index_uid, source_config).unwrap();
+
+        let source_uid = SourceUid {
+            index_uid: dest_index_uid.clone(),
+            source_id: "reindex-source".to_string(),
+        };
+        let reindex_alias = model.reindex_alias("logs").unwrap();
+        assert_eq!(reindex_alias.source_uid, source_uid);
+        assert!(!reindex_alias.flipped);
+        assert_eq!(
+            model.resolve_write_index_uid("logs").unwrap(),
+            source_index_uid
+        );
+
+        model.flip_reindex_alias("logs");
+        assert!(model.reindex_alias("logs").unwrap().flipped);
+        assert_eq!(
+            model.resolve_write_index_uid("logs").unwrap(),
+            dest_index_uid
+        );
+
+        // The flip is recorded in the checkpoint of the reindex source, so it survives reloading
+        // the index.
+        let dest_index_metadata = model.index_metadata(&dest_index_uid).unwrap().clone();
+        model.delete_index(&dest_index_uid);
+        assert!(model.reindex_alias("logs").is_none());
+        assert_eq!(model.resolve_write_index_uid("logs"), None);
+        model.add_index(dest_index_metadata);
+        assert!(model.reindex_alias("logs").unwrap().flipped);
+        assert_eq!(
+            model.resolve_write_index_uid("logs").unwrap(),
+            dest_index_uid
+        );
+
+        model.delete_source(&source_uid);
+        assert!(model.reindex_alias("logs").is_none());
+    }
+
     #[test]
     fn test_control_plane_model_toggle_source() {
         let mut model = ControlPlaneModel::default();
@@ -599,4 +1512,332 @@ mod tests {
             assert!(!has_changed);
         }
     }
+
+    #[test]
+    fn test_control_plane_model_snapshot_and_restore() {
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+        model
+            .add_source(&index_uid, SourceConfig::ingest_v2())
+            .unwrap();
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+        };
+        let shards = vec![
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(1u64)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-ingester-0".to_string(),
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(2u64)),
+                shard_state: ShardState::Closed as i32,
+                leader_id: "test-ingester-1".to_string(),
+                follower_id: Some("test-ingester-0".to_string()),
+                ..Default::default()
+            },
+        ];
+        model.insert_shards(&index_uid, &INGEST_V2_SOURCE_ID.to_string(), shards);
+        model
+            .get_shards_for_source_mut(&source_uid)
+            .unwrap()
+            .get_mut(&ShardId::from(1u64))
+            .unwrap()
+            .ingestion_rate = RateMibPerSec(3);
+        model.record_scaling_action(&source_uid, ScalingMode::Up);
+        model.pause_source(&source_uid);
+
+        let snapshot = model.snapshot("test-cluster".to_string());
+        let snapshot_json = serde_json::to_vec(&snapshot).unwrap();
+        let snapshot: ModelSnapshot = serde_json::from_slice(&snapshot_json).unwrap();
+
+        let mut restored_model = ControlPlaneModel::default();
+        restored_model.restore_from_snapshot(snapshot);
+
+        assert_eq!(restored_model.num_indexes(), 1);
+        assert_eq!(restored_model.num_sources(), 1);
+        assert_eq!(restored_model.num_shards(), 2);
+        assert_eq!(restored_model.index_uid("test-index").unwrap(), index_uid);
+
+        let shard_entries = restored_model.get_shards_for_source(&source_uid).unwrap();
+        let shard_entry = shard_entries.get(&ShardId::from(1u64)).unwrap();
+        assert!(shard_entry.is_open());
+        assert_eq!(shard_entry.ingestion_rate, RateMibPerSec(3));
+
+        let shard_entry = shard_entries.get(&ShardId::from(2u64)).unwrap();
+        assert!(shard_entry.is_closed());
+
+        assert_eq!(
+            restored_model.num_shards_for_node(&NodeId::from("test-ingester-0")),
+            2
+        );
+        assert_eq!(
+            restored_model
+                .num_open_shards_per_leader()
+                .collect::<Vec<_>>(),
+            [(&NodeId::from("test-ingester-0"), 1)]
+        );
+        // The scaling cooldown survives the restore.
+        assert!(!restored_model
+            .check_scaling_cooldown(&source_uid, ScalingMode::Down)
+            .unwrap());
+        assert!(restored_model.is_source_paused(&source_uid));
+    }
+
+    #[tokio::test]
+    async fn test_control_plane_model_reconcile_snapshot() {
+        let progress = Progress::default();
+
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata
+            .add_source(SourceConfig::ingest_v2())
+            .unwrap();
+        let index_uid = index_metadata.index_uid.clone();
+
+        let mut model = ControlPlaneModel::default();
+        model.add_index(index_metadata.clone());
+
+        let shard = Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            shard_id: Some(ShardId::from(1u64)),
+            shard_state: ShardState::Open as i32,
+            leader_id: "test-ingester-0".to_string(),
+            ..Default::default()
+        };
+        model.insert_shards(
+            &index_uid,
+            &INGEST_V2_SOURCE_ID.to_string(),
+            vec![shard.clone()],
+        );
+
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata_clone = index_metadata.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_clone.clone(),
+                ]))
+            });
+        let mut sequence = mockall::Sequence::new();
+        let mut closed_shard = shard.clone();
+        closed_shard.shard_state = ShardState::Closed as i32;
+        closed_shard.publish_position_inclusive = Some(Position::offset(42u64));
+        let index_uid_clone = index_uid.clone();
+        mock_metastore
+            .expect_list_shards()
+            .once()
+            .in_sequence(&mut sequence)
+            .return_once(move |_| {
+                let subresponse = ListShardsSubresponse {
+                    index_uid: Some(index_uid_clone),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shards: vec![closed_shard],
+                };
+                Ok(metastore::ListShardsResponse {
+                    subresponses: vec![subresponse],
+                })
+            });
+        let mut opened_shard = shard.clone();
+        opened_shard.shard_id = Some(ShardId::from(2u64));
+        mock_metastore
+            .expect_list_shards()
+            .once()
+            .in_sequence(&mut sequence)
+            .return_once(move |_| {
+                let subresponse = ListShardsSubresponse {
+                    index_uid: Some(index_uid),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shards: vec![shard, opened_shard],
+                };
+                Ok(metastore::ListShardsResponse {
+                    subresponses: vec![subresponse],
+                })
+            });
+        let mut metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        // The shard states and positions are refreshed from the metastore.
+        let mut snapshot = model.snapshot("test-cluster".to_string());
+        let is_consistent =
+            ControlPlaneModel::reconcile_snapshot(&mut snapshot, &mut metastore, &progress)
+                .await
+                .unwrap();
+        assert!(is_consistent);
+
+        let shard = &snapshot.sources[0].shards[0].shard;
+        assert_eq!(shard.shard_state(), ShardState::Closed);
+        assert_eq!(shard.publish_position_inclusive(), &Position::offset(42u64));
+
+        // A shard was opened while no control plane was running.
+        let mut snapshot = model.snapshot("test-cluster".to_string());
+        let is_consistent =
+            ControlPlaneModel::reconcile_snapshot(&mut snapshot, &mut metastore, &progress)
+                .await
+                .unwrap();
+        assert!(!is_consistent);
+
+        // The index was deleted while no control plane was running.
+        let mut model = ControlPlaneModel::default();
+        model.add_index(index_metadata);
+        model.add_index(IndexMetadata::for_test(
+            "test-index-1",
+            "ram:///test-index-1",
+        ));
+        let mut snapshot = model.snapshot("test-cluster".to_string());
+        let is_consistent =
+            ControlPlaneModel::reconcile_snapshot(&mut snapshot, &mut metastore, &progress)
+                .await
+                .unwrap();
+        assert!(!is_consistent);
+    }
+
+    #[test]
+    fn test_control_plane_model_pause_source() {
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+        model
+            .add_source(&index_uid, SourceConfig::ingest_v2())
+            .unwrap();
+
+        let error = model
+            .source_uid("test-index-foo", INGEST_V2_SOURCE_ID)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ControlPlaneError::Metastore(MetastoreError::NotFound(EntityKind::Index { .. }))
+        ));
+        let error = model
+            .source_uid("test-index", "test-source-foo")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ControlPlaneError::Metastore(MetastoreError::NotFound(EntityKind::Source { .. }))
+        ));
+        let source_uid = model.source_uid("test-index", INGEST_V2_SOURCE_ID).unwrap();
+        assert_eq!(source_uid.index_uid, index_uid);
+        assert!(!model.is_source_paused(&source_uid));
+
+        assert!(model.pause_source(&source_uid));
+        assert!(!model.pause_source(&source_uid));
+        assert!(model.is_source_paused(&source_uid));
+
+        assert!(model.resume_source(&source_uid));
+        assert!(!model.resume_source(&source_uid));
+        assert!(!model.is_source_paused(&source_uid));
+
+        model.pause_source(&source_uid);
+        model.delete_source(&source_uid);
+        assert!(!model.is_source_paused(&source_uid));
+    }
+
+    #[test]
+    fn test_control_plane_model_list_shards() {
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata_0 = IndexMetadata::for_test("test-index-0", "ram:///test-index-0");
+        let index_uid_0 = index_metadata_0.index_uid.clone();
+        model.add_index(index_metadata_0);
+        model
+            .add_source(&index_uid_0, SourceConfig::ingest_v2())
+            .unwrap();
+
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let index_uid_1 = index_metadata_1.index_uid.clone();
+        model.add_index(index_metadata_1);
+        model
+            .add_source(&index_uid_1, SourceConfig::ingest_v2())
+            .unwrap();
+
+        let shard =
+            |index_uid: &IndexUid, shard_id: u64, shard_state: ShardState, leader_id: &str| Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                shard_state: shard_state as i32,
+                leader_id: leader_id.to_string(),
+                ..Default::default()
+            };
+        let source_id = INGEST_V2_SOURCE_ID.to_string();
+        model.insert_shards(
+            &index_uid_0,
+            &source_id,
+            vec![
+                shard(&index_uid_0, 2, ShardState::Open, "test-ingester-0"),
+                shard(&index_uid_0, 1, ShardState::Closed, "test-ingester-1"),
+                shard(&index_uid_0, 3, ShardState::Open, "test-ingester-1"),
+            ],
+        );
+        model.insert_shards(
+            &index_uid_1,
+            &source_id,
+            vec![shard(&index_uid_1, 4, ShardState::Open, "test-ingester-0")],
+        );
+        let shard_ids = |response: &ListShardsResponse| -> Vec<ShardId> {
+            response
+                .shards
+                .iter()
+                .map(|shard_info| shard_info.shard.as_ref().unwrap().shard_id().clone())
+                .collect()
+        };
+
+        let response = model.list_shards(&ListShardsRequest::default());
+        assert_eq!(response.num_matching_shards, 4);
+        assert_eq!(
+            shard_ids(&response),
+            [
+                ShardId::from(1),
+                ShardId::from(2),
+                ShardId::from(3),
+                ShardId::from(4)
+            ]
+        );
+
+        let request = ListShardsRequest {
+            index_id: Some("test-index-0".to_string()),
+            shard_state: Some(ShardState::Open as i32),
+            ..Default::default()
+        };
+        let response = model.list_shards(&request);
+        assert_eq!(response.num_matching_shards, 2);
+        assert_eq!(shard_ids(&response), [ShardId::from(2), ShardId::from(3)]);
+
+        let request = ListShardsRequest {
+            leader_id: Some("test-ingester-0".to_string()),
+            ..Default::default()
+        };
+        let response = model.list_shards(&request);
+        assert_eq!(response.num_matching_shards, 2);
+        assert_eq!(shard_ids(&response), [ShardId::from(2), ShardId::from(4)]);
+
+        let request = ListShardsRequest {
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let response = model.list_shards(&request);
+        assert_eq!(response.num_matching_shards, 4);
+        assert_eq!(shard_ids(&response), [ShardId::from(2), ShardId::from(3)]);
+
+        let request = ListShardsRequest {
+            source_id: Some("test-source".to_string()),
+            ..Default::default()
+        };
+        let response = model.list_shards(&request);
+        assert_eq!(response.num_matching_shards, 0);
+        assert!(response.shards.is_empty());
+    }
 }
@@ -18,18 +18,22 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::hash_map::Entry;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::ops::{Deref, DerefMut};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use fnv::{FnvHashMap, FnvHashSet};
+use once_cell::sync::Lazy;
 use quickwit_common::rate_limiter::{RateLimiter, RateLimiterSettings};
 use quickwit_common::tower::ConstantRate;
 use quickwit_ingest::{RateMibPerSec, ShardInfo, ShardInfos};
 use quickwit_proto::ingest::{Shard, ShardState};
 use quickwit_proto::types::{IndexUid, NodeId, ShardId, SourceId, SourceUid};
+use serde::{Deserialize, Serialize};
 use tracing::{error, info, warn};
 
+use super::snapshot::{ScalingCooldownSnapshot, ShardSnapshot, SourceSnapshot};
+
 /// Limits the number of shards that can be opened for scaling up a source to 5 per minute.
 const SCALING_UP_RATE_LIMITER_SETTINGS: RateLimiterSettings = RateLimiterSettings {
     burst_limit: 5,
@@ -44,12 +48,71 @@ const SCALING_DOWN_RATE_LIMITER_SETTINGS: RateLimiterSettings = RateLimiterSetti
     refill_period: Duration::from_secs(60),
 };
 
-#[derive(Debug, Clone, Copy)]
+/// Number of past versions of the open shards of a source that the control plane retains to
+/// compute the deltas sent to the routers.
+const MAX_RETAINED_SHARD_TABLE_VERSIONS: usize = 8;
+
+/// Default minimum duration between a scaling action and a scaling action in the opposite
+/// direction for the same source.
+const DEFAULT_SCALING_COOLDOWN_SECS: u64 = 120;
+
+static SCALING_COOLDOWN: Lazy<Duration> = Lazy::new(|| {
+    let scaling_cooldown_secs = quickwit_common::get_from_env(
+        "QW_SHARD_SCALING_COOLDOWN_SECS",
+        DEFAULT_SCALING_COOLDOWN_SECS,
+    );
+    Duration::from_secs(scaling_cooldown_secs)
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub(crate) enum ScalingMode {
     Up,
     Down,
 }
 
+/// Hysteresis between scaling decisions: after scaling a source up (resp. down), the source cannot
+/// be scaled down (resp. up) until the cooldown has elapsed. This prevents spiky traffic from
+/// opening shards only to close them a few seconds later. Consecutive scaling actions in the same
+/// direction are only limited by the scaling rate limiters.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ScalingCooldown {
+    /// The source has not been scaled yet, or its cooldown has elapsed.
+    Idle,
+    /// The source was last scaled in the given direction at the given instant.
+    CoolingDown {
+        scaling_mode: ScalingMode,
+        scaled_at: Instant,
+    },
+}
+
+impl ScalingCooldown {
+    /// Returns whether the source can be scaled in the given direction, and transitions back to
+    /// `Idle` if the cooldown has elapsed.
+    pub fn allows(&mut self, scaling_mode: ScalingMode, cooldown: Duration, now: Instant) -> bool {
+        let ScalingCooldown::CoolingDown {
+            scaling_mode: last_scaling_mode,
+            scaled_at,
+        } = *self
+        else {
+            return true;
+        };
+        if now.saturating_duration_since(scaled_at) >= cooldown {
+            *self = ScalingCooldown::Idle;
+            return true;
+        }
+        last_scaling_mode == scaling_mode
+    }
+
+    /// Records a scaling action, which (re)starts the cooldown.
+    pub fn record(&mut self, scaling_mode: ScalingMode, now: Instant) {
+        *self = ScalingCooldown::CoolingDown {
+            scaling_mode,
+            scaled_at: now,
+        };
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct ShardEntry {
     pub shard: Shard,
@@ -84,6 +147,10 @@ pub(crate) struct ShardTableEntry {
     shard_entries: FnvHashMap<ShardId, ShardEntry>,
     scaling_up_rate_limiter: RateLimiter,
     scaling_down_rate_limiter: RateLimiter,
+    scaling_cooldown: ScalingCooldown,
+    // Latest versions of the open shards of the source, as sorted lists of shard IDs, oldest
+    // first.
+    open_shard_versions: VecDeque<(u64, Vec<ShardId>)>,
 }
 
 impl Default for ShardTableEntry {
@@ -94,6 +161,8 @@ impl Default for ShardTableEntry {
             scaling_down_rate_limiter: RateLimiter::from_settings(
                 SCALING_DOWN_RATE_LIMITER_SETTINGS,
             ),
+            scaling_cooldown: ScalingCooldown::Idle,
+            open_shard_versions: VecDeque::new(),
         }
     }
 }
@@ -109,6 +178,94 @@ impl ShardTableEntry {
             .filter(|shard_entry| shard_entry.is_open())
             .count()
     }
+
+    /// Records the sorted IDs of the open shards of the source as a new version if they differ
+    /// from the latest version, and returns the latest version.
+    fn record_open_shard_ids(
+        &mut self,
+        open_shard_ids: Vec<ShardId>,
+        version_generator: &mut ShardTableVersionGenerator,
+    ) -> u64 {
+        if let Some((latest_version, latest_shard_ids)) = self.open_shard_versions.back() {
+            if *latest_shard_ids == open_shard_ids {
+                return *latest_version;
+            }
+        }
+        if self.open_shard_versions.len() == MAX_RETAINED_SHARD_TABLE_VERSIONS {
+            self.open_shard_versions.pop_front();
+        }
+        let version = version_generator.next_version();
+        self.open_shard_versions
+            .push_back((version, open_shard_ids));
+        version
+    }
+
+    /// Returns the sorted IDs of the open shards of the source at the given version if the
+    /// version is still retained.
+    fn open_shard_ids_at(&self, version: u64) -> Option<&[ShardId]> {
+        self.open_shard_versions
+            .iter()
+            .find(|(retained_version, _)| *retained_version == version)
+            .map(|(_, shard_ids)| shard_ids.as_slice())
+    }
+}
+
+/// Generates the versions of the shard tables of the sources. The generator is seeded with the
+/// current time so that versions keep increasing across control plane restarts and a router never
+/// receives a delta computed against a version issued by a previous control plane.
+#[derive(Debug)]
+struct ShardTableVersionGenerator {
+    next_version: u64,
+}
+
+impl Default for ShardTableVersionGenerator {
+    fn default() -> Self {
+        let next_version = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_micros() as u64)
+            .unwrap_or_default()
+            .max(1);
+        Self { next_version }
+    }
+}
+
+impl ShardTableVersionGenerator {
+    fn next_version(&mut self) -> u64 {
+        let version = self.next_version;
+        self.next_version += 1;
+        version
+    }
+}
+
+/// The open shards of a source returned to a router, either in full or as a delta since the
+/// version of the shard table of the source last received by the router.
+#[derive(Debug)]
+pub(crate) struct OpenShardsSync {
+    /// Version of the shard table of the source, or 0 if the open shards were filtered for a
+    /// specific router and do not match any version.
+    pub shard_table_version: u64,
+    /// Whether `open_shards` and `removed_shard_ids` describe a delta.
+    pub is_delta: bool,
+    /// All the open shards of the source or, for a delta, the shards opened since the known
+    /// version, sorted by shard ID.
+    pub open_shards: Vec<ShardEntry>,
+    /// For a delta, the shards open at the known version that are no longer open.
+    pub removed_shard_ids: Vec<ShardId>,
+    /// Total number of open shards of the source.
+    pub num_open_shards: usize,
+}
+
+impl OpenShardsSync {
+    fn full(shard_table_version: u64, open_shards: Vec<ShardEntry>) -> Self {
+        let num_open_shards = open_shards.len();
+        Self {
+            shard_table_version,
+            is_delta: false,
+            open_shards,
+            removed_shard_ids: Vec::new(),
+            num_open_shards,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -137,13 +294,18 @@ impl<'a> ShardLocations<'a> {
 }
 
 // A table that keeps track of the existing shards for each index and source,
-// and for each ingester, the list of shards it is supposed to host.
+// for each ingester, the list of shards it is supposed to host, and for each leader, the list of
+// open shards it leads.
 //
-// (All mutable methods must maintain the two consistent)
+// (All mutable methods must maintain the three consistent)
 #[derive(Debug, Default)]
 pub(crate) struct ShardTable {
     table_entries: FnvHashMap<SourceUid, ShardTableEntry>,
     ingester_shards: FnvHashMap<NodeId, FnvHashMap<SourceUid, BTreeSet<ShardId>>>,
+    // Secondary index of the open shards per leader. It allows the ingest controller to count and
+    // list the open shards of each leader without scanning the entire table.
+    leader_open_shards: FnvHashMap<NodeId, FnvHashSet<(SourceUid, ShardId)>>,
+    version_generator: ShardTableVersionGenerator,
 }
 
 // Removes the shards from the ingester_shards map.
@@ -170,6 +332,41 @@ fn remove_shard_from_ingesters_internal(
     }
 }
 
+// Adds the shard to the leader_open_shards map if it is open.
+//
+// This function is used to maintain the shard table invariant.
+fn add_open_shard_to_leader_internal(
+    source_uid: &SourceUid,
+    shard: &Shard,
+    leader_open_shards: &mut FnvHashMap<NodeId, FnvHashSet<(SourceUid, ShardId)>>,
+) {
+    if shard.is_open() {
+        leader_open_shards
+            .entry(shard.leader_id.clone().into())
+            .or_default()
+            .insert((source_uid.clone(), shard.shard_id().clone()));
+    }
+}
+
+// Removes the shard from the leader_open_shards map, if present.
+//
+// This function is used to maintain the shard table invariant. It must be called whenever a shard
+// is removed from the table or transitions out of the `Open` state.
+fn remove_open_shard_from_leader_internal(
+    source_uid: &SourceUid,
+    shard: &Shard,
+    leader_open_shards: &mut FnvHashMap<NodeId, FnvHashSet<(SourceUid, ShardId)>>,
+) {
+    let Some(open_shards) = leader_open_shards.get_mut(shard.leader_id.as_str()) else {
+        return;
+    };
+    open_shards.remove(&(source_uid.clone(), shard.shard_id().clone()));
+
+    if open_shards.is_empty() {
+        leader_open_shards.remove(shard.leader_id.as_str());
+    }
+}
+
 impl ShardTable {
     /// Returns a ShardLocations object that maps each shard to the list of ingesters hosting it.
     /// All shards are considered regardless of their state (including unavailable).
@@ -199,6 +396,7 @@ impl ShardTable {
             });
         for (source_uid, shard) in shards_removed {
             remove_shard_from_ingesters_internal(source_uid, shard, &mut self.ingester_shards);
+            remove_open_shard_from_leader_internal(source_uid, shard, &mut self.leader_open_shards);
         }
         self.table_entries
             .retain(|source_uid, _| source_uid.index_uid.index_id != index_id);
@@ -215,6 +413,7 @@ impl ShardTable {
             return;
         };
         let mut shard_sets_in_shard_table = FnvHashSet::default();
+        let mut num_open_shards = 0;
         for (source_uid, shard_table_entry) in &self.table_entries {
             for (shard_id, shard_entry) in &shard_table_entry.shard_entries {
                 debug_assert_eq!(shard_id, shard_entry.shard.shard_id());
@@ -222,8 +421,24 @@ impl ShardTable {
                 for node in shard_entry.shard.ingesters() {
                     shard_sets_in_shard_table.insert((node, source_uid, shard_id));
                 }
+                if shard_entry.is_open() {
+                    num_open_shards += 1;
+                    debug_assert!(self
+                        .leader_open_shards
+                        .get(shard_entry.leader_id.as_str())
+                        .map(|open_shards| open_shards
+                            .contains(&(source_uid.clone(), shard_id.clone())))
+                        .unwrap_or(false));
+                }
             }
         }
+        debug_assert_eq!(
+            num_open_shards,
+            self.leader_open_shards
+                .values()
+                .map(FnvHashSet::len)
+                .sum::<usize>()
+        );
         for (node, ingester_shards) in &self.ingester_shards {
             for (source_uid, shard_ids) in ingester_shards {
                 for shard_id in shard_ids {
@@ -248,6 +463,33 @@ impl ShardTable {
         self.ingester_shards.get(ingester)
     }
 
+    /// Returns the number of open shards of each leader.
+    pub fn num_open_shards_per_leader(&self) -> impl Iterator<Item = (&NodeId, usize)> + '_ {
+        self.leader_open_shards
+            .iter()
+            .map(|(leader_id, open_shards)| (leader_id, open_shards.len()))
+    }
+
+    /// Lists the open shards of each leader.
+    pub(crate) fn open_shards_per_leader(
+        &self,
+    ) -> impl Iterator<Item = (&NodeId, impl Iterator<Item = &ShardEntry>)> + '_ {
+        self.leader_open_shards
+            .iter()
+            .map(|(leader_id, open_shards)| (leader_id, self.resolve_shard_entries(open_shards)))
+    }
+
+    fn resolve_shard_entries<'a>(
+        &'a self,
+        shard_keys: &'a FnvHashSet<(SourceUid, ShardId)>,
+    ) -> impl Iterator<Item = &'a ShardEntry> + 'a {
+        shard_keys.iter().filter_map(|(source_uid, shard_id)| {
+            self.table_entries
+                .get(source_uid)
+                .and_then(|table_entry| table_entry.shard_entries.get(shard_id))
+        })
+    }
+
     pub fn list_shards_for_index<'a>(
         &'a self,
         index_uid: &'a IndexUid,
@@ -305,6 +547,11 @@ impl ShardTable {
                 &shard_entry.shard,
                 &mut self.ingester_shards,
             );
+            remove_open_shard_from_leader_internal(
+                &source_uid,
+                &shard_entry.shard,
+                &mut self.leader_open_shards,
+            );
         }
         self.check_invariant();
     }
@@ -324,22 +571,29 @@ impl ShardTable {
     }
 
     pub(crate) fn set_shards_as_unavailable(&mut self, unavailable_leaders: &FnvHashSet<NodeId>) {
-        for (source_uid, shard_table_entry) in &mut self.table_entries {
-            let mut modified = false;
-            for shard_entry in shard_table_entry.shard_entries.values_mut() {
-                if shard_entry.is_open() && unavailable_leaders.contains(&shard_entry.leader_id) {
+        let mut modified_source_uids: FnvHashSet<SourceUid> = FnvHashSet::default();
+
+        for unavailable_leader in unavailable_leaders {
+            // Only the open shards of the leader are affected, so we can drain its entry from the
+            // secondary index instead of scanning the entire table.
+            let Some(open_shards) = self.leader_open_shards.remove(unavailable_leader) else {
+                continue;
+            };
+            for (source_uid, shard_id) in open_shards {
+                if let Some(shard_entry) = self
+                    .table_entries
+                    .get_mut(&source_uid)
+                    .and_then(|table_entry| table_entry.shard_entries.get_mut(&shard_id))
+                {
                     shard_entry.set_shard_state(ShardState::Unavailable);
-                    modified = true;
+                    modified_source_uids.insert(source_uid);
                 }
             }
-            if modified {
-                let num_open_shards = shard_table_entry.num_open_shards();
-                crate::metrics::CONTROL_PLANE_METRICS
-                    .open_shards_total
-                    .with_label_values([source_uid.index_uid.index_id.as_str()])
-                    .set(num_open_shards as i64);
-            };
         }
+        for source_uid in &modified_source_uids {
+            self.update_shard_metrics_for_source_uid(source_uid);
+        }
+        self.check_invariant();
     }
 
     /// Lists the shards of a given source. Returns `None` if the source does not exist.
@@ -393,10 +647,17 @@ impl ShardTable {
                 for opened_shard in opened_shards {
                     // We only insert shards that we don't know about because the control plane
                     // knows more about the state of the shards than the metastore.
-                    table_entry
+                    if let Entry::Vacant(shard_entry) = table_entry
                         .shard_entries
                         .entry(opened_shard.shard_id().clone())
-                        .or_insert_with(|| ShardEntry::from(opened_shard));
+                    {
+                        add_open_shard_to_leader_internal(
+                            &source_uid,
+                            &opened_shard,
+                            &mut self.leader_open_shards,
+                        );
+                        shard_entry.insert(ShardEntry::from(opened_shard));
+                    }
                 }
             }
             // This should never happen if the control plane view is consistent with the state of
@@ -411,6 +672,13 @@ impl ShardTable {
                     .into_iter()
                     .map(|shard| (shard.shard_id().clone(), shard.into()))
                     .collect();
+                for shard_entry in shard_entries.values() {
+                    add_open_shard_to_leader_internal(
+                        &source_uid,
+                        shard_entry,
+                        &mut self.leader_open_shards,
+                    );
+                }
                 let table_entry = ShardTableEntry {
                     shard_entries,
                     ..Default::default()
@@ -424,7 +692,8 @@ impl ShardTable {
     }
 
     /// Finds open shards for a given index and source and whose leaders are not in the set of
-    /// unavailable ingesters.
+    /// unavailable ingesters. The shards are sorted by shard ID so that the routers receive a
+    /// stable ordering of the shards of the source.
     pub fn find_open_shards(
         &self,
         index_uid: &IndexUid,
@@ -436,7 +705,7 @@ impl ShardTable {
             source_id: source_id.clone(),
         };
         let table_entry = self.table_entries.get(&source_uid)?;
-        let open_shards: Vec<ShardEntry> = table_entry
+        let mut open_shards: Vec<ShardEntry> = table_entry
             .shard_entries
             .values()
             .filter(|shard_entry| {
@@ -444,9 +713,72 @@ impl ShardTable {
             })
             .cloned()
             .collect();
+        open_shards.sort_unstable_by(|left, right| left.shard.shard_id.cmp(&right.shard.shard_id));
         Some(open_shards)
     }
 
+    /// Finds the open shards of a source like [`Self::find_open_shards`] and records them as a new
+    /// version of the shard table of the source if they changed. If the version known by the
+    /// router is still retained, only the changes since that version are returned.
+    pub fn sync_open_shards(
+        &mut self,
+        index_uid: &IndexUid,
+        source_id: &SourceId,
+        unavailable_leaders: &FnvHashSet<NodeId>,
+        known_version_opt: Option<u64>,
+    ) -> Option<OpenShardsSync> {
+        let open_shards = self.find_open_shards(index_uid, source_id, unavailable_leaders)?;
+
+        // The shards led by unavailable leaders are only filtered out for the router that issued
+        // the request, so the response does not match any version of the shard table.
+        if !unavailable_leaders.is_empty() {
+            return Some(OpenShardsSync::full(0, open_shards));
+        }
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        let table_entry = self.table_entries.get_mut(&source_uid)?;
+        let open_shard_ids: Vec<ShardId> = open_shards
+            .iter()
+            .map(|shard_entry| shard_entry.shard_id().clone())
+            .collect();
+        let version =
+            table_entry.record_open_shard_ids(open_shard_ids, &mut self.version_generator);
+
+        let Some(known_shard_ids) = known_version_opt
+            .and_then(|known_version| table_entry.open_shard_ids_at(known_version))
+        else {
+            return Some(OpenShardsSync::full(version, open_shards));
+        };
+        let num_open_shards = open_shards.len();
+        let removed_shard_ids: Vec<ShardId> = known_shard_ids
+            .iter()
+            .filter(|shard_id| {
+                open_shards
+                    .binary_search_by(|shard_entry| shard_entry.shard_id().cmp(shard_id))
+                    .is_err()
+            })
+            .cloned()
+            .collect();
+        let added_shards: Vec<ShardEntry> = open_shards
+            .into_iter()
+            .filter(|shard_entry| {
+                known_shard_ids
+                    .binary_search(shard_entry.shard_id())
+                    .is_err()
+            })
+            .collect();
+        let open_shards_sync = OpenShardsSync {
+            shard_table_version: version,
+            is_delta: true,
+            open_shards: added_shards,
+            removed_shard_ids,
+            num_open_shards,
+        };
+        Some(open_shards_sync)
+    }
+
     pub fn update_shard_metrics_for_source_uid(&self, source_uid: &SourceUid) {
         let num_open_shards: usize =
             if let Some(shard_table_entry) = self.table_entries.get(source_uid) {
@@ -465,15 +797,13 @@ impl ShardTable {
         source_uid: &SourceUid,
         shard_infos: &ShardInfos,
     ) -> ShardStats {
-        let mut num_open_shards = 0;
-        let mut ingestion_rate_sum = RateMibPerSec::default();
-
         if let Some(table_entry) = self.table_entries.get_mut(source_uid) {
             for shard_info in shard_infos {
                 let ShardInfo {
                     shard_id,
                     shard_state,
                     ingestion_rate,
+                    ..
                 } = shard_info;
 
                 if let Some(shard_entry) = table_entry.shard_entries.get_mut(shard_id) {
@@ -481,10 +811,25 @@ impl ShardTable {
                     // `ShardInfos` are broadcasted via Chitchat and eventually consistent. As a
                     // result, we can only trust the `Closed` state, which is final.
                     if shard_state.is_closed() {
+                        remove_open_shard_from_leader_internal(
+                            source_uid,
+                            shard_entry,
+                            &mut self.leader_open_shards,
+                        );
                         shard_entry.set_shard_state(ShardState::Closed);
                     }
                 }
             }
+        }
+        self.shard_stats(source_uid)
+    }
+
+    /// Computes the number of open shards and their average ingestion rate for a source.
+    pub fn shard_stats(&self, source_uid: &SourceUid) -> ShardStats {
+        let mut num_open_shards = 0;
+        let mut ingestion_rate_sum = RateMibPerSec::default();
+
+        if let Some(table_entry) = self.table_entries.get(source_uid) {
             for shard_entry in table_entry.shard_entries.values() {
                 if shard_entry.is_open() {
                     num_open_shards += 1;
@@ -513,6 +858,11 @@ impl ShardTable {
             for shard_id in shard_ids {
                 if let Some(shard_entry) = table_entry.shard_entries.get_mut(shard_id) {
                     if !shard_entry.is_closed() {
+                        remove_open_shard_from_leader_internal(
+                            source_uid,
+                            shard_entry,
+                            &mut self.leader_open_shards,
+                        );
                         shard_entry.set_shard_state(ShardState::Closed);
                         closed_shard_ids.push(shard_id.clone());
                     }
@@ -543,6 +893,11 @@ impl ShardTable {
                 &shard_entry.shard,
                 &mut self.ingester_shards,
             );
+            remove_open_shard_from_leader_internal(
+                source_uid,
+                &shard_entry.shard,
+                &mut self.leader_open_shards,
+            );
         }
         self.update_shard_metrics_for_source_uid(source_uid);
         self.check_invariant();
@@ -562,6 +917,31 @@ impl ShardTable {
         Some(scaling_rate_limiter.acquire(num_permits))
     }
 
+    /// Returns whether the scaling cooldown of the source allows scaling it in the given
+    /// direction. Returns `None` if the source does not exist.
+    pub fn check_scaling_cooldown(
+        &mut self,
+        source_uid: &SourceUid,
+        scaling_mode: ScalingMode,
+    ) -> Option<bool> {
+        let table_entry = self.table_entries.get_mut(source_uid)?;
+        let allowed =
+            table_entry
+                .scaling_cooldown
+                .allows(scaling_mode, *SCALING_COOLDOWN, Instant::now());
+        Some(allowed)
+    }
+
+    /// Records that the source was scaled in the given direction, which starts its scaling
+    /// cooldown.
+    pub fn record_scaling_action(&mut self, source_uid: &SourceUid, scaling_mode: ScalingMode) {
+        if let Some(table_entry) = self.table_entries.get_mut(source_uid) {
+            table_entry
+                .scaling_cooldown
+                .record(scaling_mode, Instant::now());
+        }
+    }
+
     pub fn drain_scaling_permits(&mut self, source_uid: &SourceUid, scaling_mode: ScalingMode) {
         if let Some(table_entry) = self.table_entries.get_mut(source_uid) {
             let scaling_rate_limiter = match scaling_mode {
@@ -586,6 +966,89 @@ impl ShardTable {
             scaling_rate_limiter.release(num_permits);
         }
     }
+
+    /// Returns the shards and scaling cooldown of every source for a snapshot of the model. The
+    /// scaling rate limiters are not part of the snapshot.
+    pub(super) fn snapshot_sources(&self) -> Vec<SourceSnapshot> {
+        let now = Instant::now();
+
+        self.table_entries
+            .iter()
+            .map(|(source_uid, table_entry)| {
+                let shards = table_entry
+                    .shard_entries
+                    .values()
+                    .map(|shard_entry| ShardSnapshot {
+                        shard: shard_entry.shard.clone(),
+                        ingestion_rate_mib_per_sec: shard_entry.ingestion_rate.0,
+                    })
+                    .collect();
+                let scaling_cooldown_opt = match table_entry.scaling_cooldown {
+                    ScalingCooldown::Idle => None,
+                    ScalingCooldown::CoolingDown {
+                        scaling_mode,
+                        scaled_at,
+                    } => Some(ScalingCooldownSnapshot {
+                        scaling_mode,
+                        elapsed_millis: now.saturating_duration_since(scaled_at).as_millis() as u64,
+                    }),
+                };
+                SourceSnapshot {
+                    index_uid: source_uid.index_uid.clone(),
+                    source_id: source_uid.source_id.clone(),
+                    shards,
+                    scaling_cooldown_opt,
+                }
+            })
+            .collect()
+    }
+
+    /// Restores the shards and scaling cooldown of a source from a snapshot taken `snapshot_age`
+    /// ago.
+    pub(super) fn restore_source(
+        &mut self,
+        source_snapshot: SourceSnapshot,
+        snapshot_age: Duration,
+    ) {
+        let SourceSnapshot {
+            index_uid,
+            source_id,
+            shards: shard_snapshots,
+            scaling_cooldown_opt,
+        } = source_snapshot;
+        let mut ingestion_rates = Vec::with_capacity(shard_snapshots.len());
+        let mut shards = Vec::with_capacity(shard_snapshots.len());
+
+        for shard_snapshot in shard_snapshots {
+            let ingestion_rate = RateMibPerSec(shard_snapshot.ingestion_rate_mib_per_sec);
+            ingestion_rates.push((shard_snapshot.shard.shard_id().clone(), ingestion_rate));
+            shards.push(shard_snapshot.shard);
+        }
+        self.insert_shards(&index_uid, &source_id, shards);
+
+        let source_uid = SourceUid {
+            index_uid,
+            source_id,
+        };
+        let Some(table_entry) = self.table_entries.get_mut(&source_uid) else {
+            return;
+        };
+        for (shard_id, ingestion_rate) in ingestion_rates {
+            if let Some(shard_entry) = table_entry.shard_entries.get_mut(&shard_id) {
+                shard_entry.ingestion_rate = ingestion_rate;
+            }
+        }
+        if let Some(scaling_cooldown_snapshot) = scaling_cooldown_opt {
+            let elapsed =
+                Duration::from_millis(scaling_cooldown_snapshot.elapsed_millis) + snapshot_age;
+
+            if let Some(scaled_at) = Instant::now().checked_sub(elapsed) {
+                table_entry
+                    .scaling_cooldown
+                    .record(scaling_cooldown_snapshot.scaling_mode, scaled_at);
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default)]
@@ -613,23 +1076,6 @@ mod tests {
         }
     }
 
-    impl ShardTable {
-        pub fn find_open_shards_sorted(
-            &self,
-            index_uid: &IndexUid,
-            source_id: &SourceId,
-            unavailable_leaders: &FnvHashSet<NodeId>,
-        ) -> Option<Vec<ShardEntry>> {
-            self.find_open_shards(index_uid, source_id, unavailable_leaders)
-                .map(|mut shards| {
-                    shards.sort_unstable_by(|left, right| {
-                        left.shard.shard_id.cmp(&right.shard.shard_id)
-                    });
-                    shards
-                })
-        }
-    }
-
     #[test]
     fn test_shard_table_delete_index() {
         let mut shard_table = ShardTable::default();
@@ -730,13 +1176,7 @@ mod tests {
         assert_eq!(shards[0], shard_01);
 
         shard_table
-            .table_entries
-            .get_mut(&source_uid)
-            .unwrap()
-            .shard_entries
-            .get_mut(&ShardId::from(1))
-            .unwrap()
-            .set_shard_state(ShardState::Unavailable);
+            .set_shards_as_unavailable(&FnvHashSet::from_iter([NodeId::from("test-leader-0")]));
 
         let shard_02 = Shard {
             index_uid: index_uid_0.clone().into(),
@@ -777,7 +1217,7 @@ mod tests {
         let mut unavailable_ingesters = FnvHashSet::default();
 
         let open_shards = shard_table
-            .find_open_shards_sorted(&index_uid, &source_id, &unavailable_ingesters)
+            .find_open_shards(&index_uid, &source_id, &unavailable_ingesters)
             .unwrap();
         assert_eq!(open_shards.len(), 0);
 
@@ -819,7 +1259,7 @@ mod tests {
             vec![shard_01, shard_02, shard_03.clone(), shard_04.clone()],
         );
         let open_shards = shard_table
-            .find_open_shards_sorted(&index_uid, &source_id, &unavailable_ingesters)
+            .find_open_shards(&index_uid, &source_id, &unavailable_ingesters)
             .unwrap();
         assert_eq!(open_shards.len(), 2);
         assert_eq!(open_shards[0].shard, shard_03);
@@ -828,12 +1268,133 @@ mod tests {
         unavailable_ingesters.insert("test-leader-0".into());
 
         let open_shards = shard_table
-            .find_open_shards_sorted(&index_uid, &source_id, &unavailable_ingesters)
+            .find_open_shards(&index_uid, &source_id, &unavailable_ingesters)
             .unwrap();
         assert_eq!(open_shards.len(), 1);
         assert_eq!(open_shards[0].shard, shard_04);
     }
 
+    #[test]
+    fn test_shard_table_sync_open_shards() {
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let source_id = "test-source".to_string();
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        let mut shard_table = ShardTable::default();
+        shard_table.add_source(&index_uid, &source_id);
+
+        let mut unavailable_leaders = FnvHashSet::default();
+
+        let shard_01 = Shard {
+            index_uid: index_uid.clone().into(),
+            source_id: source_id.clone(),
+            shard_id: Some(ShardId::from(1)),
+            leader_id: "test-leader-0".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        };
+        let shard_02 = Shard {
+            index_uid: index_uid.clone().into(),
+            source_id: source_id.clone(),
+            shard_id: Some(ShardId::from(2)),
+            leader_id: "test-leader-1".to_string(),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        };
+        shard_table.insert_shards(&index_uid, &source_id, vec![shard_01.clone()]);
+
+        let sync = shard_table
+            .sync_open_shards(&index_uid, &source_id, &unavailable_leaders, None)
+            .unwrap();
+        assert!(!sync.is_delta);
+        assert_eq!(sync.num_open_shards, 1);
+        assert_eq!(sync.open_shards.len(), 1);
+        assert_eq!(sync.open_shards[0].shard, shard_01);
+        let version_1 = sync.shard_table_version;
+        assert!(version_1 > 0);
+
+        // The shard table did not change: the version is the same and the delta is empty.
+        let sync = shard_table
+            .sync_open_shards(
+                &index_uid,
+                &source_id,
+                &unavailable_leaders,
+                Some(version_1),
+            )
+            .unwrap();
+        assert!(sync.is_delta);
+        assert_eq!(sync.shard_table_version, version_1);
+        assert_eq!(sync.num_open_shards, 1);
+        assert!(sync.open_shards.is_empty());
+        assert!(sync.removed_shard_ids.is_empty());
+
+        shard_table.insert_shards(&index_uid, &source_id, vec![shard_02.clone()]);
+        shard_table.close_shards(&source_uid, &[ShardId::from(1)]);
+
+        let sync = shard_table
+            .sync_open_shards(
+                &index_uid,
+                &source_id,
+                &unavailable_leaders,
+                Some(version_1),
+            )
+            .unwrap();
+        assert!(sync.is_delta);
+        assert!(sync.shard_table_version > version_1);
+        assert_eq!(sync.num_open_shards, 1);
+        assert_eq!(sync.open_shards.len(), 1);
+        assert_eq!(sync.open_shards[0].shard, shard_02);
+        assert_eq!(sync.removed_shard_ids, [ShardId::from(1)]);
+
+        // Unknown versions fall back to a full sync.
+        let sync = shard_table
+            .sync_open_shards(&index_uid, &source_id, &unavailable_leaders, Some(1))
+            .unwrap();
+        assert!(!sync.is_delta);
+        assert_eq!(sync.open_shards.len(), 1);
+        assert_eq!(sync.open_shards[0].shard, shard_02);
+
+        // Responses filtered for unavailable leaders are never versioned.
+        unavailable_leaders.insert("test-leader-1".into());
+
+        let sync = shard_table
+            .sync_open_shards(
+                &index_uid,
+                &source_id,
+                &unavailable_leaders,
+                Some(version_1),
+            )
+            .unwrap();
+        assert!(!sync.is_delta);
+        assert_eq!(sync.shard_table_version, 0);
+        assert_eq!(sync.num_open_shards, 0);
+        assert!(sync.open_shards.is_empty());
+    }
+
+    #[test]
+    fn test_shard_table_entry_retains_bounded_open_shard_versions() {
+        let mut table_entry = ShardTableEntry::default();
+        let mut version_generator = ShardTableVersionGenerator::default();
+
+        let first_version = table_entry.record_open_shard_ids(Vec::new(), &mut version_generator);
+
+        for shard_id in 0..MAX_RETAINED_SHARD_TABLE_VERSIONS as u64 {
+            table_entry
+                .record_open_shard_ids(vec![ShardId::from(shard_id)], &mut version_generator);
+        }
+        assert_eq!(
+            table_entry.open_shard_versions.len(),
+            MAX_RETAINED_SHARD_TABLE_VERSIONS
+        );
+        assert!(table_entry.open_shard_ids_at(first_version).is_none());
+        assert_eq!(
+            table_entry.open_shard_ids_at(first_version + 1).unwrap(),
+            [ShardId::from(0)]
+        );
+    }
+
     #[test]
     fn test_shard_table_update_shards() {
         let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
@@ -883,26 +1444,31 @@ mod tests {
                 shard_id: ShardId::from(1),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(1),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(2),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(2),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(3),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(3),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(4),
                 shard_state: ShardState::Closed,
                 ingestion_rate: RateMibPerSec(4),
+                publish_latency_opt: None,
             },
             ShardInfo {
                 shard_id: ShardId::from(5),
                 shard_state: ShardState::Open,
                 ingestion_rate: RateMibPerSec(5),
+                publish_latency_opt: None,
             },
         ]);
         let shard_stats = shard_table.update_shards(&source_uid, &shard_infos);
@@ -1046,6 +1612,67 @@ mod tests {
         assert!(table_entry.is_empty());
     }
 
+    #[test]
+    fn test_scaling_cooldown() {
+        let cooldown = Duration::from_secs(60);
+        let now = Instant::now();
+
+        let mut scaling_cooldown = ScalingCooldown::Idle;
+        assert!(scaling_cooldown.allows(ScalingMode::Up, cooldown, now));
+        assert!(scaling_cooldown.allows(ScalingMode::Down, cooldown, now));
+
+        scaling_cooldown.record(ScalingMode::Up, now);
+
+        let later = now + Duration::from_secs(30);
+        assert!(scaling_cooldown.allows(ScalingMode::Up, cooldown, later));
+        assert!(!scaling_cooldown.allows(ScalingMode::Down, cooldown, later));
+
+        // Scaling up again restarts the cooldown.
+        scaling_cooldown.record(ScalingMode::Up, later);
+
+        let even_later = now + Duration::from_secs(61);
+        assert!(!scaling_cooldown.allows(ScalingMode::Down, cooldown, even_later));
+
+        let much_later = later + cooldown;
+        assert!(scaling_cooldown.allows(ScalingMode::Down, cooldown, much_later));
+        assert!(matches!(scaling_cooldown, ScalingCooldown::Idle));
+
+        scaling_cooldown.record(ScalingMode::Down, much_later);
+        assert!(!scaling_cooldown.allows(ScalingMode::Up, cooldown, much_later));
+        assert!(scaling_cooldown.allows(ScalingMode::Down, cooldown, much_later));
+    }
+
+    #[test]
+    fn test_shard_table_check_scaling_cooldown() {
+        let mut shard_table = ShardTable::default();
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let source_id = "test-source".to_string();
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        assert!(shard_table
+            .check_scaling_cooldown(&source_uid, ScalingMode::Up)
+            .is_none());
+
+        shard_table.add_source(&index_uid, &source_id);
+
+        assert!(shard_table
+            .check_scaling_cooldown(&source_uid, ScalingMode::Down)
+            .unwrap());
+
+        shard_table.record_scaling_action(&source_uid, ScalingMode::Up);
+
+        assert!(shard_table
+            .check_scaling_cooldown(&source_uid, ScalingMode::Up)
+            .unwrap());
+        assert!(!shard_table
+            .check_scaling_cooldown(&source_uid, ScalingMode::Down)
+            .unwrap());
+    }
+
     #[test]
     fn test_shard_table_acquire_scaling_up_permits() {
         let mut shard_table = ShardTable::default();
@@ -1320,4 +1947,92 @@ mod tests {
             &[&NodeId::from("indexer1"), &NodeId::from("indexer2")]
         );
     }
+
+    #[test]
+    fn test_shard_table_open_shards_per_leader() {
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let source_id = "test-source".to_string();
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        let mut shard_table = ShardTable::default();
+        shard_table.add_source(&index_uid, &source_id);
+
+        let num_open_shards_per_leader = |shard_table: &ShardTable| {
+            shard_table
+                .num_open_shards_per_leader()
+                .map(|(leader_id, num_open_shards)| (leader_id.to_string(), num_open_shards))
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        assert!(num_open_shards_per_leader(&shard_table).is_empty());
+
+        let shards = (0..4)
+            .map(|shard_id| {
+                let shard_state = if shard_id == 3 {
+                    ShardState::Closed
+                } else {
+                    ShardState::Open
+                };
+                Shard {
+                    index_uid: index_uid.clone().into(),
+                    source_id: source_id.clone(),
+                    shard_id: Some(ShardId::from(shard_id)),
+                    leader_id: format!("test-leader-{}", shard_id % 2),
+                    shard_state: shard_state as i32,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        shard_table.insert_shards(&index_uid, &source_id, shards);
+
+        assert_eq!(
+            num_open_shards_per_leader(&shard_table),
+            [
+                ("test-leader-0".to_string(), 2),
+                ("test-leader-1".to_string(), 1)
+            ]
+        );
+        let open_shard_ids: Vec<(String, ShardId)> = shard_table
+            .open_shards_per_leader()
+            .flat_map(|(leader_id, open_shards)| {
+                open_shards.map(move |shard| (leader_id.to_string(), shard.shard_id().clone()))
+            })
+            .sorted()
+            .collect();
+        assert_eq!(
+            open_shard_ids,
+            [
+                ("test-leader-0".to_string(), ShardId::from(0)),
+                ("test-leader-0".to_string(), ShardId::from(2)),
+                ("test-leader-1".to_string(), ShardId::from(1)),
+            ]
+        );
+        shard_table.close_shards(&source_uid, &[ShardId::from(0)]);
+        assert_eq!(
+            num_open_shards_per_leader(&shard_table),
+            [
+                ("test-leader-0".to_string(), 1),
+                ("test-leader-1".to_string(), 1)
+            ]
+        );
+        shard_table.delete_shards(&source_uid, &[ShardId::from(1)]);
+        assert_eq!(
+            num_open_shards_per_leader(&shard_table),
+            [("test-leader-0".to_string(), 1)]
+        );
+        shard_table
+            .set_shards_as_unavailable(&FnvHashSet::from_iter([NodeId::from("test-leader-0")]));
+        assert!(num_open_shards_per_leader(&shard_table).is_empty());
+
+        let shard_entries = shard_table.get_shards(&source_uid).unwrap();
+        assert!(shard_entries
+            .get(&ShardId::from(2))
+            .unwrap()
+            .is_unavailable());
+
+        shard_table.delete_source(&index_uid, &source_id);
+        assert!(num_open_shards_per_leader(&shard_table).is_empty());
+    }
 }
@@ -0,0 +1,242 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use quickwit_common::pretty::PrettyDisplay;
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::ingest::Shard;
+use quickwit_proto::types::{IndexUid, SourceId};
+use quickwit_storage::{Storage, StorageErrorKind};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+use super::ScalingMode;
+
+const MODEL_SNAPSHOT_FILE_NAME: &str = "control-plane-model.json";
+
+/// Version of the snapshot format. Snapshots written with another version are discarded.
+const MODEL_SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// A snapshot of the [`super::ControlPlaneModel`]. Write aliases are not part of the snapshot:
+/// they are rebuilt from the index templates once the snapshot is restored. Neither are the
+/// decommissioning ingesters, which are tracked again from the status they advertise in the
+/// cluster state.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ModelSnapshot {
+    pub format_version: u32,
+    pub cluster_id: String,
+    /// Unix timestamp of the snapshot in seconds.
+    pub create_timestamp: i64,
+    pub indexes: Vec<IndexMetadata>,
+    pub sources: Vec<SourceSnapshot>,
+    /// Sources paused with the `PauseSource` API.
+    #[serde(default)]
+    pub paused_sources: Vec<(IndexUid, SourceId)>,
+}
+
+impl ModelSnapshot {
+    pub(super) fn new(
+        cluster_id: String,
+        indexes: Vec<IndexMetadata>,
+        sources: Vec<SourceSnapshot>,
+        paused_sources: Vec<(IndexUid, SourceId)>,
+    ) -> Self {
+        Self {
+            format_version: MODEL_SNAPSHOT_FORMAT_VERSION,
+            cluster_id,
+            create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            indexes,
+            sources,
+            paused_sources,
+        }
+    }
+
+    /// Returns the time elapsed since the snapshot was taken.
+    pub fn age(&self) -> Duration {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        Duration::from_secs((now_timestamp - self.create_timestamp).max(0) as u64)
+    }
+}
+
+/// The shards and scaling state of a source.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SourceSnapshot {
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    pub shards: Vec<ShardSnapshot>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scaling_cooldown_opt: Option<ScalingCooldownSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ShardSnapshot {
+    pub shard: Shard,
+    pub ingestion_rate_mib_per_sec: u16,
+}
+
+/// The last scaling action of a source that is still cooling down.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ScalingCooldownSnapshot {
+    pub scaling_mode: ScalingMode,
+    /// Time elapsed between the scaling action and the snapshot.
+    pub elapsed_millis: u64,
+}
+
+/// Saves and restores snapshots of the control plane model to and from a storage, so that a new
+/// control plane does not have to load its entire model from the metastore.
+#[derive(Clone)]
+pub struct ModelSnapshotStore {
+    storage: Arc<dyn Storage>,
+    max_age: Duration,
+}
+
+impl fmt::Debug for ModelSnapshotStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ModelSnapshotStore")
+            .field("storage_uri", &self.storage.uri())
+            .field("max_age", &self.max_age)
+            .finish()
+    }
+}
+
+impl ModelSnapshotStore {
+    pub fn new(storage: Arc<dyn Storage>, max_age: Duration) -> Self {
+        Self { storage, max_age }
+    }
+
+    pub(crate) async fn save(&self, snapshot: &ModelSnapshot) -> anyhow::Result<()> {
+        let snapshot_json = serde_json::to_vec(snapshot)?;
+        let num_bytes = snapshot_json.len();
+        self.storage
+            .put(Path::new(MODEL_SNAPSHOT_FILE_NAME), Box::new(snapshot_json))
+            .await
+            .context("failed to write control plane model snapshot")?;
+        info!(
+            num_indexes = snapshot.indexes.len(),
+            num_bytes, "saved control plane model snapshot"
+        );
+        Ok(())
+    }
+
+    /// Loads the latest snapshot and deletes it, so that a snapshot is restored at most once.
+    /// Returns `None` if there is no snapshot, or if it is too old or was taken by another
+    /// cluster.
+    pub(crate) async fn take(&self, cluster_id: &str) -> anyhow::Result<Option<ModelSnapshot>> {
+        let snapshot_path = Path::new(MODEL_SNAPSHOT_FILE_NAME);
+
+        let snapshot_bytes = match self.storage.get_all(snapshot_path).await {
+            Ok(snapshot_bytes) => snapshot_bytes,
+            Err(storage_error) if storage_error.kind() == StorageErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(storage_error) => {
+                return Err(storage_error).context("failed to read control plane model snapshot");
+            }
+        };
+        // The model of the control plane diverges from the snapshot as soon as it is mutated, so
+        // the snapshot must not outlive this control plane.
+        self.storage
+            .delete(snapshot_path)
+            .await
+            .context("failed to delete control plane model snapshot")?;
+
+        let snapshot: ModelSnapshot = serde_json::from_slice(&snapshot_bytes)
+            .context("failed to deserialize control plane model snapshot")?;
+
+        if snapshot.format_version != MODEL_SNAPSHOT_FORMAT_VERSION {
+            warn!(
+                format_version = snapshot.format_version,
+                "discarding control plane model snapshot with unsupported format version"
+            );
+            return Ok(None);
+        }
+        if snapshot.cluster_id != cluster_id {
+            warn!(
+                cluster_id=%snapshot.cluster_id,
+                "discarding control plane model snapshot of another cluster"
+            );
+            return Ok(None);
+        }
+        let snapshot_age = snapshot.age();
+
+        if snapshot_age > self.max_age {
+            info!(
+                "discarding control plane model snapshot taken {} ago",
+                snapshot_age.pretty_display()
+            );
+            return Ok(None);
+        }
+        Ok(Some(snapshot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_storage::RamStorage;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_model_snapshot_store() {
+        let storage = Arc::new(RamStorage::default());
+        let snapshot_store = ModelSnapshotStore::new(storage.clone(), Duration::from_secs(60));
+
+        let snapshot_opt = snapshot_store.take("test-cluster").await.unwrap();
+        assert!(snapshot_opt.is_none());
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        let snapshot = ModelSnapshot::new(
+            "test-cluster".to_string(),
+            vec![index_metadata.clone()],
+            Vec::new(),
+            Vec::new(),
+        );
+        snapshot_store.save(&snapshot).await.unwrap();
+
+        let snapshot = snapshot_store.take("test-cluster").await.unwrap().unwrap();
+        assert_eq!(snapshot.indexes, [index_metadata]);
+
+        // The snapshot is deleted once it has been taken.
+        let snapshot_opt = snapshot_store.take("test-cluster").await.unwrap();
+        assert!(snapshot_opt.is_none());
+
+        // Snapshots of another cluster are discarded.
+        snapshot_store.save(&snapshot).await.unwrap();
+        let snapshot_opt = snapshot_store.take("other-cluster").await.unwrap();
+        assert!(snapshot_opt.is_none());
+
+        // So are stale snapshots.
+        let mut stale_snapshot = snapshot;
+        stale_snapshot.create_timestamp -= 3_600;
+        snapshot_store.save(&stale_snapshot).await.unwrap();
+        let snapshot_opt = snapshot_store.take("test-cluster").await.unwrap();
+        assert!(snapshot_opt.is_none());
+        assert!(!storage
+            .exists(Path::new(MODEL_SNAPSHOT_FILE_NAME))
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Exposes the shard table of the control plane model to the benchmarks, which cannot access the
+//! crate-private model directly.
+
+use std::collections::HashMap;
+
+use fnv::FnvHashSet;
+use quickwit_proto::ingest::{Shard, ShardState};
+use quickwit_proto::types::{IndexUid, NodeId, ShardId, SourceUid};
+
+use crate::model::ShardTable;
+
+pub struct ShardTableBench {
+    shard_table: ShardTable,
+    source_uids: Vec<SourceUid>,
+    num_ingesters: usize,
+    next_shard_id: u64,
+}
+
+impl ShardTableBench {
+    /// Creates a shard table with `num_shards` shards spread over `num_sources` sources and
+    /// `num_ingesters` ingesters. One shard out of four is closed, the others are open and
+    /// replicated on the next ingester.
+    pub fn new(num_sources: usize, num_shards: usize, num_ingesters: usize) -> Self {
+        let mut shard_table_bench = ShardTableBench {
+            shard_table: ShardTable::default(),
+            source_uids: Vec::with_capacity(num_sources),
+            num_ingesters,
+            next_shard_id: 0,
+        };
+        for source_idx in 0..num_sources {
+            let index_uid = IndexUid::new_with_random_ulid(&format!("test-index-{source_idx}"));
+            let source_id = "test-source".to_string();
+            shard_table_bench
+                .shard_table
+                .add_source(&index_uid, &source_id);
+            shard_table_bench.source_uids.push(SourceUid {
+                index_uid,
+                source_id,
+            });
+        }
+        let mut shards_per_source: Vec<Vec<Shard>> = vec![Vec::new(); num_sources];
+
+        for shard_idx in 0..num_shards {
+            let shard_state = if shard_idx % 4 == 3 {
+                ShardState::Closed
+            } else {
+                ShardState::Open
+            };
+            let shard = shard_table_bench.make_shard(shard_idx % num_sources, shard_state);
+            shards_per_source[shard_idx % num_sources].push(shard);
+        }
+        for (source_uid, shards) in shard_table_bench.source_uids.iter().zip(shards_per_source) {
+            shard_table_bench.shard_table.insert_shards(
+                &source_uid.index_uid,
+                &source_uid.source_id,
+                shards,
+            );
+        }
+        shard_table_bench
+    }
+
+    fn make_shard(&mut self, source_idx: usize, shard_state: ShardState) -> Shard {
+        let source_uid = &self.source_uids[source_idx];
+        let shard_id = self.next_shard_id;
+        self.next_shard_id += 1;
+
+        let leader_idx = shard_id as usize % self.num_ingesters;
+        let follower_idx = (leader_idx + 1) % self.num_ingesters;
+
+        Shard {
+            index_uid: source_uid.index_uid.clone().into(),
+            source_id: source_uid.source_id.clone(),
+            shard_id: Some(ShardId::from(shard_id)),
+            leader_id: format!("test-ingester-{leader_idx}"),
+            follower_id: Some(format!("test-ingester-{follower_idx}")),
+            shard_state: shard_state as i32,
+            ..Default::default()
+        }
+    }
+
+    /// Counts the open shards per leader using the per-leader secondary index, as
+    /// `allocate_shards` does.
+    pub fn count_open_shards_per_leader(&self) -> usize {
+        self.shard_table
+            .num_open_shards_per_leader()
+            .map(|(_, num_open_shards)| num_open_shards)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Counts the open shards per leader by scanning the entire table.
+    pub fn count_open_shards_per_leader_full_scan(&self) -> usize {
+        let mut per_leader_num_open_shards: HashMap<&str, usize> =
+            HashMap::with_capacity(self.num_ingesters);
+
+        for shard in self.shard_table.all_shards() {
+            if shard.is_open() {
+                *per_leader_num_open_shards
+                    .entry(&shard.leader_id)
+                    .or_default() += 1;
+            }
+        }
+        per_leader_num_open_shards
+            .into_values()
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Lists the open shards of each leader, as `rebalance_shards` does.
+    pub fn list_open_shards_per_leader(&self) -> usize {
+        self.shard_table
+            .open_shards_per_leader()
+            .map(|(_, open_shards)| open_shards.count())
+            .sum()
+    }
+
+    /// Opens a new shard and deletes it right away.
+    pub fn insert_and_delete_shard(&mut self) {
+        let source_idx = self.next_shard_id as usize % self.source_uids.len();
+        let shard = self.make_shard(source_idx, ShardState::Open);
+        let shard_id = shard.shard_id().clone();
+        let source_uid = self.source_uids[source_idx].clone();
+
+        self.shard_table
+            .insert_shards(&source_uid.index_uid, &source_uid.source_id, vec![shard]);
+        self.shard_table.delete_shards(&source_uid, &[shard_id]);
+    }
+
+    /// Marks the shards led by the given ingester as unavailable.
+    pub fn set_shards_as_unavailable(&mut self, ingester_idx: usize) {
+        let unavailable_leaders: FnvHashSet<NodeId> =
+            FnvHashSet::from_iter([NodeId::from(format!("test-ingester-{ingester_idx}"))]);
+        self.shard_table
+            .set_shards_as_unavailable(&unavailable_leaders);
+    }
+}
@@ -41,6 +41,7 @@ use serde_json::json;
 
 use crate::control_plane::{ControlPlane, CONTROL_PLAN_LOOP_INTERVAL};
 use crate::indexing_scheduler::MIN_DURATION_BETWEEN_SCHEDULING;
+use crate::ingest::IngestControllerTimeouts;
 use crate::IndexerNodeInfo;
 
 fn index_metadata_for_test(index_id: &str, source_id: &str, num_pipelines: usize) -> IndexMetadata {
@@ -58,6 +59,8 @@ fn index_metadata_for_test(index_id: &str, source_id: &str, num_pipelines: usize
             enable_backfill_mode: true,
         }),
         transform_config: None,
+        json_schema_config: None,
+        wasm_plugin_config: None,
         input_format: SourceInputFormat::Json,
     };
     index_metadata
@@ -152,6 +155,8 @@ async fn start_control_plane(
         indexer_pool,
         ingester_pool,
         MetastoreServiceClient::from_mock(mock_metastore),
+        None,
+        IngestControllerTimeouts::default(),
     );
 
     (indexer_inboxes, control_plane_mailbox)
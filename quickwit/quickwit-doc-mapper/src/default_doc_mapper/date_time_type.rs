@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use indexmap::IndexSet;
+use quickwit_common::is_false;
 use quickwit_datetime::{DateTimeInputFormat, DateTimeOutputFormat};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
@@ -55,6 +56,17 @@ pub struct QuickwitDateTimeOptions {
 
     #[serde(default)]
     pub fast: bool,
+
+    /// If true, values that cannot be parsed with the accepted input formats are parsed as RFC
+    /// 3339 or ISO 8601 datetimes, or as Unix timestamps.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub coerce: bool,
+
+    /// Value indexed when the field is missing or `null`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<JsonValue>,
 }
 
 impl Default for QuickwitDateTimeOptions {
@@ -67,38 +79,73 @@ impl Default for QuickwitDateTimeOptions {
             indexed: true,
             stored: true,
             fast: false,
+            coerce: false,
+            default_value: None,
         }
     }
 }
 
 impl QuickwitDateTimeOptions {
     pub(crate) fn parse_json(&self, json_value: JsonValue) -> Result<TantivyValue, String> {
-        let date_time = match json_value {
-            JsonValue::Number(timestamp) => {
-                // `.as_f64()` actually converts floats to integers, so we must check for integers
-                // first.
-                if let Some(timestamp_i64) = timestamp.as_i64() {
-                    quickwit_datetime::parse_timestamp_int(timestamp_i64, &self.input_formats.0)?
-                } else if let Some(timestamp_f64) = timestamp.as_f64() {
-                    quickwit_datetime::parse_timestamp_float(timestamp_f64, &self.input_formats.0)?
-                } else {
-                    return Err(format!(
-                        "failed to parse datetime `{timestamp:?}`: value is larger than i64::MAX",
-                    ));
-                }
-            }
-            JsonValue::String(date_time_str) => {
-                quickwit_datetime::parse_date_time_str(&date_time_str, &self.input_formats.0)?
+        parse_json_with_input_formats(json_value, &self.input_formats.0)
+    }
+
+    /// Parses a JSON value into a datetime, falling back to the coercion input formats if
+    /// `coerce` is enabled. The returned boolean is true if the value had to be coerced.
+    pub(crate) fn parse_json_with_coercion(
+        &self,
+        json_value: JsonValue,
+    ) -> Result<(TantivyValue, bool), String> {
+        if !self.coerce {
+            return self
+                .parse_json(json_value)
+                .map(|tantivy_value| (tantivy_value, false));
+        }
+        match self.parse_json(json_value.clone()) {
+            Ok(tantivy_value) => Ok((tantivy_value, false)),
+            Err(error) => {
+                let coercion_input_formats = [
+                    DateTimeInputFormat::Rfc3339,
+                    DateTimeInputFormat::Iso8601,
+                    DateTimeInputFormat::Timestamp,
+                ];
+                parse_json_with_input_formats(json_value, &coercion_input_formats)
+                    .map(|tantivy_value| (tantivy_value, true))
+                    .map_err(|_| error)
             }
-            _ => {
+        }
+    }
+}
+
+fn parse_json_with_input_formats(
+    json_value: JsonValue,
+    input_formats: &[DateTimeInputFormat],
+) -> Result<TantivyValue, String> {
+    let date_time = match json_value {
+        JsonValue::Number(timestamp) => {
+            // `.as_f64()` actually converts floats to integers, so we must check for integers
+            // first.
+            if let Some(timestamp_i64) = timestamp.as_i64() {
+                quickwit_datetime::parse_timestamp_int(timestamp_i64, input_formats)?
+            } else if let Some(timestamp_f64) = timestamp.as_f64() {
+                quickwit_datetime::parse_timestamp_float(timestamp_f64, input_formats)?
+            } else {
                 return Err(format!(
-                    "failed to parse datetime: expected a float, integer, or string, got \
-                     `{json_value}`"
-                ))
+                    "failed to parse datetime `{timestamp:?}`: value is larger than i64::MAX",
+                ));
             }
-        };
-        Ok(TantivyValue::Date(date_time))
-    }
+        }
+        JsonValue::String(date_time_str) => {
+            quickwit_datetime::parse_date_time_str(&date_time_str, input_formats)?
+        }
+        _ => {
+            return Err(format!(
+                "failed to parse datetime: expected a float, integer, or string, got \
+                 `{json_value}`"
+            ))
+        }
+    };
+    Ok(TantivyValue::Date(date_time))
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
@@ -171,6 +218,8 @@ mod tests {
             indexed: true,
             fast: true,
             stored: false,
+            coerce: false,
+            default_value: None,
         };
         assert_eq!(date_time_options, expected_date_time_options);
     }
@@ -241,6 +290,8 @@ mod tests {
             indexed: true,
             fast: true,
             stored: false,
+            coerce: false,
+            default_value: None,
         };
         assert_eq!(date_time_options, expected_date_time_options);
     }
@@ -387,4 +438,47 @@ mod tests {
             assert_eq!(date_time.into_timestamp_secs(), expected_timestamp);
         }
     }
+
+    #[test]
+    fn test_date_time_options_parse_json_with_coercion() {
+        let mut date_time_options = QuickwitDateTimeOptions {
+            input_formats: InputFormats(vec![DateTimeInputFormat::Rfc3339]),
+            ..Default::default()
+        };
+        let expected_timestamp = datetime!(2012-05-21 12:09:14 UTC).unix_timestamp();
+        let timestamp_millis_json = serde_json::json!(expected_timestamp * 1_000);
+
+        date_time_options
+            .parse_json_with_coercion(timestamp_millis_json.clone())
+            .unwrap_err();
+
+        date_time_options.coerce = true;
+        {
+            let json_value = serde_json::json!("2012-05-21T12:09:14Z");
+            let (tantivy_value, is_coerced) = date_time_options
+                .parse_json_with_coercion(json_value)
+                .unwrap();
+            assert!(!is_coerced);
+            let TantivyValue::Date(date_time) = tantivy_value else {
+                panic!("Expected a tantivy date time, got `{tantivy_value:?}`.");
+            };
+            assert_eq!(date_time.into_timestamp_secs(), expected_timestamp);
+        }
+        {
+            let (tantivy_value, is_coerced) = date_time_options
+                .parse_json_with_coercion(timestamp_millis_json)
+                .unwrap();
+            assert!(is_coerced);
+            let TantivyValue::Date(date_time) = tantivy_value else {
+                panic!("Expected a tantivy date time, got `{tantivy_value:?}`.");
+            };
+            assert_eq!(date_time.into_timestamp_secs(), expected_timestamp);
+        }
+        {
+            let json_value = serde_json::json!("not a date");
+            date_time_options
+                .parse_json_with_coercion(json_value)
+                .unwrap_err();
+        }
+    }
 }
@@ -25,7 +25,9 @@ use fnv::FnvHashSet;
 use quickwit_common::PathHasher;
 use quickwit_query::create_default_quickwit_tokenizer_manager;
 use quickwit_query::query_ast::QueryAst;
-use quickwit_query::tokenizers::TokenizerManager;
+use quickwit_query::tokenizers::{
+    parse_index_prefixes_tokenizer_name, IndexPrefixesTokenizer, TokenizerManager,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use tantivy::query::Query;
@@ -45,8 +47,9 @@ use crate::doc_mapper::{JsonObject, Partition};
 use crate::query_builder::build_query;
 use crate::routing_expression::RoutingExpr;
 use crate::{
-    Cardinality, DocMapper, DocParsingError, Mode, QueryParserError, TokenizerEntry, WarmupInfo,
-    DOCUMENT_LEN_FIELD_NAME, DYNAMIC_FIELD_NAME, FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
+    Cardinality, DocMapper, DocParsingError, DocParsingStats, Mode, QueryParserError,
+    TokenizerEntry, WarmupInfo, DOCUMENT_LEN_FIELD_NAME, DYNAMIC_FIELD_NAME,
+    FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
 };
 
 const FIELD_PRESENCE_FIELD: Field = Field::from_field_id(0u32);
@@ -75,6 +78,8 @@ pub struct DefaultDocMapper {
     default_search_field_names: Vec<String>,
     /// Timestamp field name.
     timestamp_field_name: Option<String>,
+    /// Names of the additional datetime fields whose time range is recorded for each split.
+    secondary_timestamp_field_names: Vec<String>,
     /// Root node of the field mapping tree.
     /// See [`MappingNode`].
     field_mappings: MappingNode,
@@ -84,6 +89,8 @@ pub struct DefaultDocMapper {
     schema: Schema,
     /// List of field names used for tagging.
     tag_field_names: BTreeSet<String>,
+    /// List of field names for which a term digest is computed for each split.
+    term_digest_field_names: BTreeSet<String>,
     /// The partition key is a DSL used to route documents
     /// into specific splits.
     partition_key: RoutingExpr,
@@ -97,6 +104,8 @@ pub struct DefaultDocMapper {
     tokenizer_entries: Vec<TokenizerEntry>,
     /// Tokenizer manager.
     tokenizer_manager: TokenizerManager,
+    /// Version of the doc mapping of the index.
+    doc_mapping_version: u64,
 }
 
 impl DefaultDocMapper {
@@ -184,6 +193,17 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
         if let Some(timestamp_field_path) = builder.timestamp_field.as_ref() {
             validate_timestamp_field(timestamp_field_path, &field_mappings)?;
         };
+        for (field_idx, secondary_timestamp_field_path) in
+            builder.secondary_timestamp_fields.iter().enumerate()
+        {
+            if builder.timestamp_field.as_ref() == Some(secondary_timestamp_field_path)
+                || builder.secondary_timestamp_fields[..field_idx]
+                    .contains(secondary_timestamp_field_path)
+            {
+                bail!("duplicated secondary timestamp field `{secondary_timestamp_field_path}`");
+            }
+            validate_timestamp_field(secondary_timestamp_field_path, &field_mappings)?;
+        }
 
         let schema = schema_builder.build();
 
@@ -224,6 +244,7 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             tokenizer_manager.register(&tokenizer_config_entry.name, tokenizer, does_lowercasing);
             custom_tokenizer_names.insert(&tokenizer_config_entry.name);
         }
+        register_index_prefixes_tokenizers(&schema, &tokenizer_manager)?;
         validate_fields_tokenizers(&schema, &tokenizer_manager)?;
 
         // Resolve default search fields
@@ -252,6 +273,13 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             validate_tag(tag_field_name, &schema)?;
         }
 
+        // Resolve term digest fields
+        let term_digest_field_names: BTreeSet<String> =
+            builder.term_digest_fields.iter().cloned().collect();
+        for term_digest_field_name in &term_digest_field_names {
+            validate_term_digest_field(term_digest_field_name, &schema)?;
+        }
+
         let partition_key_expr: &str = builder.partition_key.as_deref().unwrap_or("");
         let partition_key = RoutingExpr::new(partition_key_expr).with_context(|| {
             format!("failed to interpret the partition key: `{partition_key_expr}`")
@@ -273,15 +301,18 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             document_len_field,
             default_search_field_names,
             timestamp_field_name: builder.timestamp_field,
+            secondary_timestamp_field_names: builder.secondary_timestamp_fields,
             field_mappings,
             concatenate_dynamic_fields,
             tag_field_names,
+            term_digest_field_names,
             required_fields,
             partition_key,
             max_num_partitions: builder.max_num_partitions,
             mode: builder.mode,
             tokenizer_entries: builder.tokenizers,
             tokenizer_manager,
+            doc_mapping_version: builder.doc_mapping_version,
         })
     }
 }
@@ -338,6 +369,72 @@ fn validate_tag(tag_field_name: &str, schema: &Schema) -> Result<(), anyhow::Err
     Ok(())
 }
 
+/// Checks that a given field name is a valid candidate for a term digest.
+///
+/// The conditions are:
+/// - the field must be a text field
+/// - the field must use the `raw` tokenizer for indexing.
+fn validate_term_digest_field(
+    term_digest_field_name: &str,
+    schema: &Schema,
+) -> Result<(), anyhow::Error> {
+    let field = schema
+        .get_field(term_digest_field_name)
+        .with_context(|| format!("unknown term digest field: `{term_digest_field_name}`"))?;
+    let field_type = schema.get_field_entry(field).field_type();
+    let FieldType::Str(options) = field_type else {
+        bail!(
+            "term digests are not allowed on `{}` fields",
+            field_type.value_type().name().to_lowercase()
+        )
+    };
+    let tokenizer_opt = options
+        .get_indexing_options()
+        .map(|text_options: &tantivy::schema::TextFieldIndexing| text_options.tokenizer());
+    if tokenizer_opt != Some(RAW_TOKENIZER_NAME) {
+        bail!(
+            "term digests are only allowed on indexed text fields with the `raw` tokenizer \
+             (`{term_digest_field_name}` does not meet this requirement)"
+        );
+    }
+    Ok(())
+}
+
+/// Registers the tokenizers of the fields indexing the prefixes of text fields. They wrap the
+/// tokenizer of the text field, which must be registered beforehand.
+fn register_index_prefixes_tokenizers(
+    schema: &Schema,
+    tokenizer_manager: &TokenizerManager,
+) -> anyhow::Result<()> {
+    for (_, field_entry) in schema.fields() {
+        let FieldType::Str(options) = field_entry.field_type() else {
+            continue;
+        };
+        let Some(text_field_indexing) = options.get_indexing_options() else {
+            continue;
+        };
+        let tokenizer_name = text_field_indexing.tokenizer();
+        let Some((base_tokenizer_name, min_chars, max_chars)) =
+            parse_index_prefixes_tokenizer_name(tokenizer_name)
+        else {
+            continue;
+        };
+        let base_tokenizer = tokenizer_manager
+            .get_tokenizer(base_tokenizer_name)
+            .with_context(|| {
+                format!(
+                    "unknown tokenizer `{}` for field `{}`",
+                    base_tokenizer_name,
+                    field_entry.name()
+                )
+            })?;
+        let index_prefixes_tokenizer =
+            IndexPrefixesTokenizer::new(base_tokenizer, min_chars, max_chars);
+        tokenizer_manager.register(tokenizer_name, index_prefixes_tokenizer, false);
+    }
+    Ok(())
+}
+
 /// Checks that a given text/json field name has a registered tokenizer.
 fn validate_fields_tokenizers(
     schema: &Schema,
@@ -380,14 +477,20 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             timestamp_field: default_doc_mapper
                 .timestamp_field_name()
                 .map(ToString::to_string),
+            secondary_timestamp_fields: default_doc_mapper.secondary_timestamp_field_names,
             field_mappings: default_doc_mapper.field_mappings.into(),
             tag_fields: default_doc_mapper.tag_field_names.into_iter().collect(),
+            term_digest_fields: default_doc_mapper
+                .term_digest_field_names
+                .into_iter()
+                .collect(),
             default_search_fields: default_doc_mapper.default_search_field_names,
             mode: default_doc_mapper.mode,
             partition_key: partition_key_opt,
             max_num_partitions: default_doc_mapper.max_num_partitions,
             tokenizers: default_doc_mapper.tokenizer_entries,
             document_length: false,
+            doc_mapping_version: default_doc_mapper.doc_mapping_version,
         }
     }
 }
@@ -572,6 +675,15 @@ impl DocMapper for DefaultDocMapper {
         &self,
         json_obj: JsonObject,
         document_len: u64,
+    ) -> Result<(Partition, Document), DocParsingError> {
+        self.doc_from_json_obj_with_stats(json_obj, document_len, &mut DocParsingStats::default())
+    }
+
+    fn doc_from_json_obj_with_stats(
+        &self,
+        json_obj: JsonObject,
+        document_len: u64,
+        doc_parsing_stats: &mut DocParsingStats,
     ) -> Result<(Partition, Document), DocParsingError> {
         let partition: Partition = self.partition_key.eval_hash(&json_obj);
 
@@ -597,6 +709,7 @@ impl DocMapper for DefaultDocMapper {
             &mut document,
             &mut field_path,
             &mut dynamic_json_obj,
+            doc_parsing_stats,
         )?;
 
         if let Some(dynamic_field) = self.dynamic_field {
@@ -716,14 +829,26 @@ impl DocMapper for DefaultDocMapper {
         self.timestamp_field_name.as_deref()
     }
 
+    fn secondary_timestamp_field_names(&self) -> &[String] {
+        &self.secondary_timestamp_field_names
+    }
+
     fn tag_field_names(&self) -> BTreeSet<String> {
         self.tag_field_names.clone()
     }
 
+    fn term_digest_field_names(&self) -> BTreeSet<String> {
+        self.term_digest_field_names.clone()
+    }
+
     fn max_num_partitions(&self) -> NonZeroU32 {
         self.max_num_partitions
     }
 
+    fn doc_mapping_version(&self) -> u64 {
+        self.doc_mapping_version
+    }
+
     fn tokenizer_manager(&self) -> &TokenizerManager {
         &self.tokenizer_manager
     }
@@ -736,7 +861,8 @@ mod tests {
     use std::sync::Arc;
 
     use quickwit_common::PathHasher;
-    use quickwit_query::query_ast::query_ast_from_user_text;
+    use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
+    use quickwit_query::ElasticQueryDsl;
     use serde_json::{self, json, Value as JsonValue};
     use tantivy::schema::{FieldType, IndexRecordOption, OwnedValue as TantivyValue, Type, Value};
 
@@ -744,8 +870,8 @@ mod tests {
     use crate::default_doc_mapper::field_mapping_entry::DEFAULT_TOKENIZER_NAME;
     use crate::default_doc_mapper::mapping_tree::value_to_pretokenized;
     use crate::{
-        DefaultDocMapperBuilder, DocMapper, DocParsingError, DOCUMENT_LEN_FIELD_NAME,
-        DYNAMIC_FIELD_NAME, FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
+        DefaultDocMapperBuilder, DocMapper, DocParsingError, DocParsingStats,
+        DOCUMENT_LEN_FIELD_NAME, DYNAMIC_FIELD_NAME, FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
     };
 
     fn example_json_doc_value() -> JsonValue {
@@ -1163,6 +1289,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fail_to_build_doc_mapper_with_invalid_default_value() {
+        let doc_mapper = r#"{
+            "field_mappings": [
+                {
+                    "name": "http",
+                    "type": "object",
+                    "field_mappings": [
+                        {"name": "status", "type": "u64", "default_value": "not-a-number"}
+                    ]
+                }
+            ]
+        }"#;
+        let builder = serde_json::from_str::<DefaultDocMapperBuilder>(doc_mapper).unwrap();
+        let error_msg = builder.try_build().unwrap_err().to_string();
+        assert!(
+            error_msg.starts_with("invalid default value for field `http.status`"),
+            "{error_msg}"
+        );
+    }
+
+    #[test]
+    fn test_doc_from_json_with_default_and_coerced_values() {
+        let doc_mapper = r#"{
+            "field_mappings": [
+                {"name": "body", "type": "text", "default_value": "n/a"},
+                {"name": "is_error", "type": "bool", "coerce": true},
+                {
+                    "name": "http",
+                    "type": "object",
+                    "field_mappings": [
+                        {"name": "status", "type": "u64", "default_value": 200},
+                        {"name": "client_ip", "type": "ip"}
+                    ]
+                }
+            ]
+        }"#;
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(doc_mapper).unwrap();
+        let schema = doc_mapper.schema();
+        let body_field = schema.get_field("body").unwrap();
+        let is_error_field = schema.get_field("is_error").unwrap();
+        let status_field = schema.get_field("http.status").unwrap();
+        {
+            let json_doc = json!({"body": null, "is_error": "true"});
+            let mut doc_parsing_stats = DocParsingStats::default();
+            let (_, doc) = doc_mapper
+                .doc_from_json_obj_with_stats(
+                    json_doc.as_object().unwrap().clone(),
+                    0,
+                    &mut doc_parsing_stats,
+                )
+                .unwrap();
+            assert_eq!(doc.get_first(body_field).unwrap().as_str(), Some("n/a"));
+            assert_eq!(doc.get_first(is_error_field).unwrap().as_bool(), Some(true));
+            assert_eq!(doc.get_first(status_field).unwrap().as_u64(), Some(200));
+            assert_eq!(doc_parsing_stats.num_coerced_values, 1);
+            assert_eq!(doc_parsing_stats.num_defaulted_values, 2);
+        }
+        {
+            let json_doc = json!({"body": "hello", "http": {"status": "404"}});
+            let mut doc_parsing_stats = DocParsingStats::default();
+            let (_, doc) = doc_mapper
+                .doc_from_json_obj_with_stats(
+                    json_doc.as_object().unwrap().clone(),
+                    0,
+                    &mut doc_parsing_stats,
+                )
+                .unwrap();
+            assert_eq!(doc.get_first(body_field).unwrap().as_str(), Some("hello"));
+            assert!(doc.get_first(is_error_field).is_none());
+            assert_eq!(doc.get_first(status_field).unwrap().as_u64(), Some(404));
+            assert_eq!(doc_parsing_stats.num_coerced_values, 1);
+            assert_eq!(doc_parsing_stats.num_defaulted_values, 0);
+        }
+        {
+            let error = doc_mapper
+                .doc_from_json_str(r#"{"is_error": "yes"}"#)
+                .unwrap_err();
+            assert_eq!(
+                error,
+                DocParsingError::ValueError(
+                    "is_error".to_string(),
+                    "failed to coerce JSON string `\"yes\"` to boolean".to_string()
+                )
+            );
+        }
+    }
+
     #[test]
     fn test_should_build_doc_mapper_with_duplicate_fields_at_different_level() {
         let doc_mapper = r#"{
@@ -1478,6 +1692,107 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_doc_mapper_with_term_digest_fields() {
+        let doc_mapper = r#"{
+            "term_digest_fields": ["trace_id"],
+            "field_mappings": [
+                {
+                    "name": "trace_id",
+                    "type": "text",
+                    "tokenizer": "raw"
+                },
+                {
+                    "name": "body",
+                    "type": "text"
+                },
+                {
+                    "name": "status",
+                    "type": "u64"
+                }
+            ]
+        }"#;
+        let builder = serde_json::from_str::<DefaultDocMapperBuilder>(doc_mapper).unwrap();
+        let doc_mapper = builder.clone().try_build().unwrap();
+        assert_eq!(
+            doc_mapper.term_digest_field_names(),
+            BTreeSet::from(["trace_id".to_string()])
+        );
+        let named_fields = doc_mapper.term_digest_named_fields().unwrap();
+        assert_eq!(named_fields.len(), 1);
+        assert_eq!(named_fields[0].name, "trace_id");
+
+        let mut builder_body = builder.clone();
+        builder_body.term_digest_fields = vec!["body".to_string()];
+        assert_eq!(
+            builder_body.try_build().unwrap_err().to_string(),
+            "term digests are only allowed on indexed text fields with the `raw` tokenizer \
+             (`body` does not meet this requirement)"
+        );
+
+        let mut builder_status = builder.clone();
+        builder_status.term_digest_fields = vec!["status".to_string()];
+        assert_eq!(
+            builder_status.try_build().unwrap_err().to_string(),
+            "term digests are not allowed on `u64` fields"
+        );
+
+        let mut builder_unknown = builder;
+        builder_unknown.term_digest_fields = vec!["unknown".to_string()];
+        assert_eq!(
+            builder_unknown.try_build().unwrap_err().to_string(),
+            "unknown term digest field: `unknown`"
+        );
+    }
+
+    #[test]
+    fn test_build_doc_mapper_with_secondary_timestamp_fields() {
+        let doc_mapper = r#"{
+            "timestamp_field": "event_time",
+            "secondary_timestamp_fields": ["ingest_time"],
+            "field_mappings": [
+                {
+                    "name": "event_time",
+                    "type": "datetime",
+                    "fast_precision": "nanoseconds",
+                    "fast": true
+                },
+                {
+                    "name": "ingest_time",
+                    "type": "datetime",
+                    "fast": true
+                },
+                {
+                    "name": "created_at",
+                    "type": "datetime"
+                }
+            ]
+        }"#;
+        let builder = serde_json::from_str::<DefaultDocMapperBuilder>(doc_mapper).unwrap();
+        let doc_mapper = builder.clone().try_build().unwrap();
+        assert_eq!(
+            doc_mapper.secondary_timestamp_field_names(),
+            ["ingest_time".to_string()]
+        );
+        let named_fields = doc_mapper.secondary_timestamp_named_fields().unwrap();
+        assert_eq!(named_fields.len(), 1);
+        assert_eq!(named_fields[0].name, "ingest_time");
+
+        let mut builder_duplicated = builder.clone();
+        builder_duplicated.secondary_timestamp_fields = vec!["event_time".to_string()];
+        assert_eq!(
+            builder_duplicated.try_build().unwrap_err().to_string(),
+            "duplicated secondary timestamp field `event_time`"
+        );
+
+        let mut builder_not_fast = builder;
+        builder_not_fast.secondary_timestamp_fields = vec!["created_at".to_string()];
+        assert_eq!(
+            builder_not_fast.try_build().unwrap_err().to_string(),
+            "timestamp field `created_at` should be a fast field"
+        );
+    }
+
     // See #1132
     #[test]
     fn test_by_default_store_source_is_false_and_fields_are_stored_individually() {
@@ -2029,6 +2344,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_index_prefixes_field() {
+        test_doc_from_json_test_aux(
+            r#"{
+                "field_mappings": [
+                    {
+                        "name": "body",
+                        "type": "text",
+                        "index_prefixes": {"min_chars": 1, "max_chars": 3}
+                    }
+                ],
+                "mode": "strict"
+            }"#,
+            "body#prefixes",
+            r#"{"body": "this is a text"}"#,
+            vec!["this is a text".into()],
+        );
+    }
+
+    #[test]
+    fn test_reject_invalid_index_prefixes() {
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+                "field_mappings": [
+                    {
+                        "name": "body",
+                        "type": "text",
+                        "index_prefixes": {"min_chars": 4, "max_chars": 3}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("invalid `index_prefixes` for field `body`"));
+
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+                "field_mappings": [
+                    {
+                        "name": "body",
+                        "type": "text",
+                        "indexed": false,
+                        "index_prefixes": {}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`index_prefixes` requires field `body` to be indexed"));
+    }
+
     #[test]
     fn test_length_field() {
         let raw_doc = r#"{ "some_obj": { "json_obj": {"hello": 2} } }"#;
@@ -2089,6 +2459,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_doc_mapper_query_with_index_prefixes() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "body", "type": "text", "index_prefixes": {}},
+                {"name": "title", "type": "text"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        let prefixes_field = doc_mapper.schema().get_field("body#prefixes").unwrap();
+        let prefixes_field_id = prefixes_field.field_id();
+
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "body:Qui*").unwrap(),
+            format!(r#"TermQuery(Term(field={prefixes_field_id}, type=Str, "qui"))"#)
+        );
+        // Prefixes longer than `max_chars` are expanded over the term dictionary of the field.
+        let query = default_doc_mapper_query_aux(&doc_mapper, "body:quickly*").unwrap();
+        assert!(query.starts_with("PhrasePrefixQuery"), "{query}");
+
+        let query = default_doc_mapper_query_aux(&doc_mapper, "title:qui*").unwrap();
+        assert!(query.starts_with("PhrasePrefixQuery"), "{query}");
+
+        let query_ast: QueryAst = serde_json::from_str::<ElasticQueryDsl>(
+            r#"{"match_bool_prefix": {"body": "quick bro"}}"#,
+        )
+        .unwrap()
+        .try_into()
+        .unwrap();
+        let (query, warmup_info) = doc_mapper
+            .query(doc_mapper.schema(), &query_ast, true)
+            .unwrap();
+        let query_str = format!("{query:?}");
+        assert!(
+            query_str.contains(&format!(
+                r#"TermQuery(Term(field={prefixes_field_id}, type=Str, "bro"))"#
+            )),
+            "{query_str}"
+        );
+        assert!(warmup_info.term_ranges_grouped_by_field.is_empty());
+        assert!(warmup_info
+            .terms_grouped_by_field
+            .contains_key(&prefixes_field));
+    }
+
     #[test]
     fn test_doc_mapper_object_dot_collision_with_object_field() {
         let doc_mapper: DefaultDocMapper = serde_json::from_str(
@@ -19,6 +19,7 @@
 
 use std::num::NonZeroU32;
 
+use quickwit_common::is_zero;
 use serde::{Deserialize, Serialize};
 
 use super::tokenizer_entry::TokenizerEntry;
@@ -49,12 +50,20 @@ pub struct DefaultDocMapperBuilder {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp_field: Option<String>,
+    /// Name of the additional datetime fields whose time range is recorded for each split.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub secondary_timestamp_fields: Vec<String>,
     /// Describes which fields are indexed and how.
     #[serde(default)]
     pub field_mappings: Vec<FieldMappingEntry>,
     /// Name of the fields that are tagged.
     #[serde(default)]
     pub tag_fields: Vec<String>,
+    /// Name of the fields for which a term digest is computed for each split.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub term_digest_fields: Vec<String>,
     /// The partition key is a DSL used to route documents
     /// into specific splits.
     #[serde(default)]
@@ -83,6 +92,10 @@ pub struct DefaultDocMapperBuilder {
     /// Record document length
     #[serde(default)]
     pub document_length: bool,
+    /// Version of the doc mapping of the index this doc mapper was built from.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    pub doc_mapping_version: u64,
 }
 
 /// Defines how an unmapped field should be handled.
@@ -22,6 +22,7 @@ use std::convert::TryFrom;
 
 use anyhow::bail;
 use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_common::is_false;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tantivy::schema::{
@@ -99,6 +100,10 @@ pub struct QuickwitNumericOptions {
     pub coerce: bool,
     #[serde(default)]
     pub output_format: NumericOutputFormat,
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<JsonValue>,
 }
 
 impl Default for QuickwitNumericOptions {
@@ -110,6 +115,7 @@ impl Default for QuickwitNumericOptions {
             fast: false,
             coerce: true,
             output_format: NumericOutputFormat::default(),
+            default_value: None,
         }
     }
 }
@@ -126,6 +132,14 @@ pub struct QuickwitBoolOptions {
     pub indexed: bool,
     #[serde(default)]
     pub fast: bool,
+    /// If true, the strings `"true"` and `"false"` are coerced into booleans.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub coerce: bool,
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<JsonValue>,
 }
 
 impl Default for QuickwitBoolOptions {
@@ -135,6 +149,8 @@ impl Default for QuickwitBoolOptions {
             indexed: true,
             stored: true,
             fast: false,
+            coerce: false,
+            default_value: None,
         }
     }
 }
@@ -249,6 +265,10 @@ pub struct QuickwitIpAddrOptions {
     pub indexed: bool,
     #[serde(default)]
     pub fast: bool,
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<JsonValue>,
 }
 
 impl Default for QuickwitIpAddrOptions {
@@ -258,6 +278,7 @@ impl Default for QuickwitIpAddrOptions {
             indexed: true,
             stored: true,
             fast: false,
+            default_value: None,
         }
     }
 }
@@ -449,6 +470,69 @@ pub struct QuickwitTextOptions {
     pub stored: bool,
     #[serde(default)]
     pub fast: FastFieldOptions,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_prefixes: Option<IndexPrefixesOptions>,
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<JsonValue>,
+}
+
+/// Indexes the prefixes of the tokens of a text field in a hidden field, so that
+/// prefix queries (search-as-you-type) on short prefixes are answered with a single term lookup
+/// instead of an expansion over the term dictionary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IndexPrefixesOptions {
+    /// Minimum length, in characters, of the indexed prefixes.
+    #[serde(default = "IndexPrefixesOptions::default_min_chars")]
+    pub min_chars: usize,
+    /// Maximum length, in characters, of the indexed prefixes.
+    #[serde(default = "IndexPrefixesOptions::default_max_chars")]
+    pub max_chars: usize,
+}
+
+impl IndexPrefixesOptions {
+    const MAX_CHARS_LIMIT: usize = 20;
+
+    fn default_min_chars() -> usize {
+        2
+    }
+
+    fn default_max_chars() -> usize {
+        5
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.min_chars == 0 {
+            bail!("`index_prefixes.min_chars` must be strictly positive");
+        }
+        if self.min_chars > self.max_chars {
+            bail!(
+                "`index_prefixes.min_chars` ({}) must be less than or equal to \
+                 `index_prefixes.max_chars` ({})",
+                self.min_chars,
+                self.max_chars
+            );
+        }
+        if self.max_chars > Self::MAX_CHARS_LIMIT {
+            bail!(
+                "`index_prefixes.max_chars` must be less than or equal to {}",
+                Self::MAX_CHARS_LIMIT
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for IndexPrefixesOptions {
+    fn default() -> Self {
+        Self {
+            min_chars: Self::default_min_chars(),
+            max_chars: Self::default_max_chars(),
+        }
+    }
 }
 
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -514,6 +598,8 @@ impl Default for QuickwitTextOptions {
             indexing_options: Some(TextIndexingOptions::default()),
             stored: true,
             fast: FastFieldOptions::default(),
+            index_prefixes: None,
+            default_value: None,
         }
     }
 }
@@ -1218,7 +1304,8 @@ mod tests {
         assert_eq!(
             error.to_string(),
             "error while parsing field `my_field_name`: unknown field `tokenizer`, expected one \
-             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`"
+             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`, \
+             `default_value`"
         );
     }
 
@@ -1310,7 +1397,8 @@ mod tests {
             .unwrap_err()
             .to_string(),
             "error while parsing field `my_field_name`: unknown field `tokenizer`, expected one \
-             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`"
+             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`, \
+             `default_value`"
         );
     }
 
@@ -1433,6 +1521,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_bool_mapping_with_coerce_and_default_value() {
+        let entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "my_field_name",
+                "type": "bool",
+                "coerce": true,
+                "default_value": false
+            }
+            "#,
+        )
+        .unwrap();
+        let FieldMappingType::Bool(options, _) = &entry.mapping_type else {
+            panic!("expected bool mapping type");
+        };
+        assert!(options.coerce);
+        assert_eq!(options.default_value, Some(json!(false)));
+
+        let entry_deserser = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            entry_deserser,
+            json!({
+                "name": "my_field_name",
+                "type": "bool",
+                "stored": true,
+                "fast": false,
+                "indexed": true,
+                "coerce": true,
+                "default_value": false,
+            })
+        );
+    }
+
     #[test]
     fn test_parse_ip_addr_mapping() {
         let entry = serde_json::from_str::<FieldMappingEntry>(
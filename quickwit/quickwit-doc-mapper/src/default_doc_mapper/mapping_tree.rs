@@ -22,12 +22,13 @@ use std::collections::BTreeMap;
 use std::net::IpAddr;
 use std::str::FromStr;
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use itertools::Itertools;
+use quickwit_query::{index_prefixes_field_name, index_prefixes_tokenizer_name};
 use serde_json::Value as JsonValue;
 use tantivy::schema::{
-    BytesOptions, Field, IntoIpv6Addr, IpAddrOptions, JsonObjectOptions, NumericOptions,
-    OwnedValue as TantivyValue, SchemaBuilder, TextOptions,
+    BytesOptions, Field, IndexRecordOption, IntoIpv6Addr, IpAddrOptions, JsonObjectOptions,
+    NumericOptions, OwnedValue as TantivyValue, SchemaBuilder, TextFieldIndexing, TextOptions,
 };
 use tantivy::tokenizer::{PreTokenizedString, Token};
 use tantivy::{DateOptions, TantivyDocument as Document};
@@ -36,11 +37,11 @@ use tracing::warn;
 use super::date_time_type::QuickwitDateTimeOptions;
 use super::field_mapping_entry::{NumericOutputFormat, QuickwitBoolOptions};
 use crate::default_doc_mapper::field_mapping_entry::{
-    QuickwitBytesOptions, QuickwitIpAddrOptions, QuickwitNumericOptions, QuickwitObjectOptions,
-    QuickwitTextOptions,
+    IndexPrefixesOptions, QuickwitBytesOptions, QuickwitIpAddrOptions, QuickwitNumericOptions,
+    QuickwitObjectOptions, QuickwitTextOptions,
 };
 use crate::default_doc_mapper::{FieldMappingType, QuickwitJsonOptions};
-use crate::{Cardinality, DocParsingError, FieldMappingEntry, ModeType};
+use crate::{Cardinality, DocParsingError, DocParsingStats, FieldMappingEntry, ModeType};
 
 #[derive(Clone, Debug)]
 pub enum LeafType {
@@ -173,46 +174,87 @@ pub(crate) fn map_primitive_json_to_tantivy(value: JsonValue) -> Option<TantivyV
     }
 }
 
+/// Converts a JSON value into a boolean. If `coerce` is true, the strings `"true"` and `"false"`
+/// are accepted as well. The returned flag is true if the value was coerced.
+fn bool_from_json(json_val: JsonValue, coerce: bool) -> Result<(bool, bool), String> {
+    match json_val {
+        JsonValue::Bool(val) => Ok((val, false)),
+        JsonValue::String(str_val) if coerce => match str_val.as_str() {
+            "true" => Ok((true, true)),
+            "false" => Ok((false, true)),
+            _ => Err(format!(
+                "failed to coerce JSON string `\"{str_val}\"` to boolean"
+            )),
+        },
+        _ => Err(format!("expected boolean, got `{json_val}`")),
+    }
+}
+
 impl LeafType {
     fn value_from_json(&self, json_val: JsonValue) -> Result<TantivyValue, String> {
+        self.value_from_json_with_coercion(json_val)
+            .map(|(value, _is_coerced)| value)
+    }
+
+    /// Converts a JSON value into a tantivy value. The returned boolean is true if the value had
+    /// to be coerced into the type of the field.
+    fn value_from_json_with_coercion(
+        &self,
+        json_val: JsonValue,
+    ) -> Result<(TantivyValue, bool), String> {
         match self {
             LeafType::Text(_) => {
                 if let JsonValue::String(text) = json_val {
-                    Ok(TantivyValue::Str(text))
+                    Ok((TantivyValue::Str(text), false))
                 } else {
                     Err(format!("expected string, got `{json_val}`"))
                 }
             }
-            LeafType::I64(numeric_options) => i64::from_json(json_val, numeric_options.coerce),
-            LeafType::U64(numeric_options) => u64::from_json(json_val, numeric_options.coerce),
-            LeafType::F64(numeric_options) => f64::from_json(json_val, numeric_options.coerce),
-            LeafType::Bool(_) => {
-                if let JsonValue::Bool(val) = json_val {
-                    Ok(TantivyValue::Bool(val))
-                } else {
-                    Err(format!("expected boolean, got `{json_val}`"))
-                }
+            LeafType::I64(numeric_options) => {
+                let is_coerced = json_val.is_string();
+                let value = i64::from_json(json_val, numeric_options.coerce)?;
+                Ok((value, is_coerced))
+            }
+            LeafType::U64(numeric_options) => {
+                let is_coerced = json_val.is_string();
+                let value = u64::from_json(json_val, numeric_options.coerce)?;
+                Ok((value, is_coerced))
+            }
+            LeafType::F64(numeric_options) => {
+                let is_coerced = json_val.is_string();
+                let value = f64::from_json(json_val, numeric_options.coerce)?;
+                Ok((value, is_coerced))
+            }
+            LeafType::Bool(bool_options) => {
+                let (val, is_coerced) = bool_from_json(json_val, bool_options.coerce)?;
+                Ok((TantivyValue::Bool(val), is_coerced))
             }
             LeafType::IpAddr(_) => {
                 if let JsonValue::String(ip_address) = json_val {
                     let ipv6_value = IpAddr::from_str(ip_address.as_str())
                         .map_err(|err| format!("failed to parse IP address `{ip_address}`: {err}"))?
                         .into_ipv6_addr();
-                    Ok(TantivyValue::IpAddr(ipv6_value))
+                    Ok((TantivyValue::IpAddr(ipv6_value), false))
                 } else {
                     Err(format!("expected string, got `{json_val}`"))
                 }
             }
-            LeafType::DateTime(date_time_options) => date_time_options.parse_json(json_val),
-            LeafType::Bytes(binary_options) => binary_options.input_format.parse_json(json_val),
+            LeafType::DateTime(date_time_options) => {
+                date_time_options.parse_json_with_coercion(json_val)
+            }
+            LeafType::Bytes(binary_options) => {
+                let value = binary_options.input_format.parse_json(json_val)?;
+                Ok((value, false))
+            }
             LeafType::Json(_) => {
                 if let JsonValue::Object(json_obj) = json_val {
-                    Ok(TantivyValue::Object(
+                    let value = TantivyValue::Object(
                         json_obj
                             .into_iter()
                             .map(|(key, val)| (key, val.into()))
                             .collect(),
-                    ))
+                    );
+                    Ok((value, false))
                 } else {
                     Err(format!("expected object, got `{json_val}`"))
                 }
@@ -220,6 +262,20 @@ impl LeafType {
         }
     }
 
+    /// Returns the value indexed when the field is missing or `null`, if any.
+    fn default_value_opt(&self) -> Option<&JsonValue> {
+        match self {
+            LeafType::Bool(options) => options.default_value.as_ref(),
+            LeafType::DateTime(options) => options.default_value.as_ref(),
+            LeafType::F64(options) | LeafType::I64(options) | LeafType::U64(options) => {
+                options.default_value.as_ref()
+            }
+            LeafType::IpAddr(options) => options.default_value.as_ref(),
+            LeafType::Text(options) => options.default_value.as_ref(),
+            LeafType::Bytes(_) | LeafType::Json(_) => None,
+        }
+    }
+
     fn tantivy_string_value_from_json(
         &self,
         json_val: JsonValue,
@@ -241,12 +297,9 @@ impl LeafType {
                 Ok(OneOrIter::one(value_to_pretokenized(val).into()))
             }
             LeafType::F64(_) => Err("unsuported concat type: f64".to_string()),
-            LeafType::Bool(_) => {
-                if let JsonValue::Bool(val) = json_val {
-                    Ok(OneOrIter::one(value_to_pretokenized(val).into()))
-                } else {
-                    Err(format!("expected boolean, got `{json_val}`"))
-                }
+            LeafType::Bool(bool_options) => {
+                let (val, _is_coerced) = bool_from_json(json_val, bool_options.coerce)?;
+                Ok(OneOrIter::one(value_to_pretokenized(val).into()))
             }
             LeafType::IpAddr(_) => Err("unsuported concat type: IpAddr".to_string()),
             LeafType::DateTime(_date_time_options) => {
@@ -297,10 +350,11 @@ impl MappingLeaf {
         json_val: JsonValue,
         document: &mut Document,
         path: &mut [String],
+        doc_parsing_stats: &mut DocParsingStats,
     ) -> Result<(), DocParsingError> {
         if json_val.is_null() {
-            // We just ignore `null`.
-            return Ok(());
+            // We just ignore `null`, unless the field has a default value.
+            return self.doc_from_default_value(document, path, doc_parsing_stats);
         }
         if let JsonValue::Array(els) = json_val {
             if self.cardinality == Cardinality::SingleValue {
@@ -322,10 +376,13 @@ impl MappingLeaf {
                         }
                     }
                 }
-                let value = self
+                let (value, is_coerced) = self
                     .typ
-                    .value_from_json(el_json_val)
+                    .value_from_json_with_coercion(el_json_val)
                     .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
+                if is_coerced {
+                    doc_parsing_stats.num_coerced_values += 1;
+                }
                 document.add_field_value(self.field, value);
             }
             return Ok(());
@@ -342,14 +399,50 @@ impl MappingLeaf {
                 }
             }
         }
-        let value = self
+        let (value, is_coerced) = self
             .typ
-            .value_from_json(json_val)
+            .value_from_json_with_coercion(json_val)
             .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
+        if is_coerced {
+            doc_parsing_stats.num_coerced_values += 1;
+        }
         document.add_field_value(self.field, value);
         Ok(())
     }
 
+    /// Adds the default value of the field to the document, if the field has one.
+    fn doc_from_default_value(
+        &self,
+        document: &mut Document,
+        path: &mut [String],
+        doc_parsing_stats: &mut DocParsingStats,
+    ) -> Result<(), DocParsingError> {
+        let Some(default_value) = self.typ.default_value_opt() else {
+            return Ok(());
+        };
+        doc_parsing_stats.num_defaulted_values += 1;
+        self.doc_from_json(default_value.clone(), document, path, doc_parsing_stats)
+    }
+
+    /// Checks that the default value of the field, if any, can be parsed.
+    fn validate_default_value(&self) -> Result<(), String> {
+        let Some(default_value) = self.typ.default_value_opt() else {
+            return Ok(());
+        };
+        let mut document = Document::default();
+        self.doc_from_json(
+            default_value.clone(),
+            &mut document,
+            &mut [],
+            &mut DocParsingStats::default(),
+        )
+        .map_err(|error| error.to_string())
+    }
+
+    fn has_default_value(&self) -> bool {
+        self.typ.default_value_opt().is_some()
+    }
+
     fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<TantivyValue>>,
@@ -540,6 +633,8 @@ impl NumVal for f64 {
 pub(crate) struct MappingNode {
     pub branches: fnv::FnvHashMap<String, MappingTree>,
     branches_order: Vec<String>,
+    // True if at least one of the leaves under this node has a default value.
+    has_default_values: bool,
 }
 
 fn get_or_insert_path<'a>(
@@ -613,6 +708,7 @@ impl MappingNode {
     }
 
     pub fn insert(&mut self, path: &str, node: MappingTree) {
+        self.has_default_values |= node.has_default_values();
         self.branches_order.push(path.to_string());
         self.branches.insert(path.to_string(), node);
     }
@@ -638,11 +734,27 @@ impl MappingNode {
         document: &mut Document,
         path: &mut Vec<String>,
         dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
+        doc_parsing_stats: &mut DocParsingStats,
     ) -> Result<(), DocParsingError> {
+        let missing_field_names: Vec<&String> = if self.has_default_values {
+            self.branches_order
+                .iter()
+                .filter(|field_name| !json_obj.contains_key(field_name.as_str()))
+                .collect()
+        } else {
+            Vec::new()
+        };
         for (field_name, val) in json_obj {
             if let Some(child_tree) = self.branches.get(&field_name) {
                 path.push(field_name);
-                child_tree.doc_from_json(val, mode, document, path, dynamic_json_obj)?;
+                child_tree.doc_from_json(
+                    val,
+                    mode,
+                    document,
+                    path,
+                    dynamic_json_obj,
+                    doc_parsing_stats,
+                )?;
                 path.pop();
             } else {
                 match mode {
@@ -662,6 +774,31 @@ impl MappingNode {
                 }
             }
         }
+        for field_name in missing_field_names {
+            let child_tree = self.branches.get(field_name).expect("Missing field");
+            path.push(field_name.clone());
+            child_tree.doc_from_default_values(document, path, doc_parsing_stats)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// Adds the default values of all the leaves under this node to the document.
+    fn doc_from_default_values(
+        &self,
+        document: &mut Document,
+        path: &mut Vec<String>,
+        doc_parsing_stats: &mut DocParsingStats,
+    ) -> Result<(), DocParsingError> {
+        if !self.has_default_values {
+            return Ok(());
+        }
+        for field_name in &self.branches_order {
+            let child_tree = self.branches.get(field_name).expect("Missing field");
+            path.push(field_name.clone());
+            child_tree.doc_from_default_values(document, path, doc_parsing_stats)?;
+            path.pop();
+        }
         Ok(())
     }
 
@@ -726,14 +863,22 @@ impl MappingTree {
         document: &mut Document,
         path: &mut Vec<String>,
         dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
+        doc_parsing_stats: &mut DocParsingStats,
     ) -> Result<(), DocParsingError> {
         match self {
             MappingTree::Leaf(mapping_leaf) => {
-                mapping_leaf.doc_from_json(json_value, document, path)
+                mapping_leaf.doc_from_json(json_value, document, path, doc_parsing_stats)
             }
             MappingTree::Node(mapping_node) => {
                 if let JsonValue::Object(json_obj) = json_value {
-                    mapping_node.doc_from_json(json_obj, mode, document, path, dynamic_json_obj)
+                    mapping_node.doc_from_json(
+                        json_obj,
+                        mode,
+                        document,
+                        path,
+                        dynamic_json_obj,
+                        doc_parsing_stats,
+                    )
                 } else {
                     Err(DocParsingError::ValueError(
                         path.join("."),
@@ -744,6 +889,29 @@ impl MappingTree {
         }
     }
 
+    fn doc_from_default_values(
+        &self,
+        document: &mut Document,
+        path: &mut Vec<String>,
+        doc_parsing_stats: &mut DocParsingStats,
+    ) -> Result<(), DocParsingError> {
+        match self {
+            MappingTree::Leaf(mapping_leaf) => {
+                mapping_leaf.doc_from_default_value(document, path, doc_parsing_stats)
+            }
+            MappingTree::Node(mapping_node) => {
+                mapping_node.doc_from_default_values(document, path, doc_parsing_stats)
+            }
+        }
+    }
+
+    fn has_default_values(&self) -> bool {
+        match self {
+            MappingTree::Leaf(mapping_leaf) => mapping_leaf.has_default_value(),
+            MappingTree::Node(mapping_node) => mapping_node.has_default_values,
+        }
+    }
+
     fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<TantivyValue>>,
@@ -794,6 +962,14 @@ fn build_mapping_tree_from_entries<'a>(
             }
             let (child_tree, mut dynamic_fields) =
                 build_mapping_from_field_type(&entry.mapping_type, field_path, schema)?;
+            if let MappingTree::Leaf(mapping_leaf) = &child_tree {
+                if let Err(error_msg) = mapping_leaf.validate_default_value() {
+                    bail!(
+                        "invalid default value for field `{}`: {error_msg}",
+                        field_path.join(".")
+                    );
+                }
+            }
             field_path.pop();
             mapping_node.insert(&entry.name, child_tree);
             concatenate_dynamic_fields.append(&mut dynamic_fields);
@@ -962,6 +1138,31 @@ fn escape_dots(field_name: &str) -> String {
     escaped_field_name
 }
 
+fn build_index_prefixes_field(
+    field_name: &str,
+    options: &QuickwitTextOptions,
+    index_prefixes: &IndexPrefixesOptions,
+    schema_builder: &mut SchemaBuilder,
+) -> anyhow::Result<Field> {
+    index_prefixes
+        .validate()
+        .with_context(|| format!("invalid `index_prefixes` for field `{field_name}`"))?;
+    let Some(indexing_options) = &options.indexing_options else {
+        bail!("`index_prefixes` requires field `{field_name}` to be indexed");
+    };
+    let tokenizer_name = index_prefixes_tokenizer_name(
+        indexing_options.tokenizer.name(),
+        index_prefixes.min_chars,
+        index_prefixes.max_chars,
+    );
+    let text_field_indexing = TextFieldIndexing::default()
+        .set_index_option(IndexRecordOption::Basic)
+        .set_tokenizer(&tokenizer_name);
+    let text_options = TextOptions::default().set_indexing_options(text_field_indexing);
+    let field = schema_builder.add_text_field(&index_prefixes_field_name(field_name), text_options);
+    Ok(field)
+}
+
 /// build a sub-mapping tree from the fields it contains.
 ///
 /// also returns the list of concatenate fields which consume the dynamic field
@@ -975,11 +1176,22 @@ fn build_mapping_from_field_type<'a>(
         FieldMappingType::Text(options, cardinality) => {
             let text_options: TextOptions = options.clone().into();
             let field = schema_builder.add_text_field(&field_name, text_options);
+            // The prefixes field is fed like a concatenate field: it receives the raw text of
+            // the field and its tokenizer takes care of emitting the prefixes.
+            let mut concatenate = Vec::new();
+            if let Some(index_prefixes) = &options.index_prefixes {
+                concatenate.push(build_index_prefixes_field(
+                    &field_name,
+                    options,
+                    index_prefixes,
+                    schema_builder,
+                )?);
+            }
             let mapping_leaf = MappingLeaf {
                 field,
                 typ: LeafType::Text(options.clone()),
                 cardinality: *cardinality,
-                concatenate: Vec::new(),
+                concatenate,
             };
             Ok((MappingTree::Leaf(mapping_leaf), Vec::new()))
         }
@@ -1107,7 +1319,7 @@ mod tests {
         BinaryFormat, NumericOutputFormat, QuickwitBoolOptions, QuickwitBytesOptions,
         QuickwitIpAddrOptions, QuickwitNumericOptions, QuickwitTextOptions,
     };
-    use crate::Cardinality;
+    use crate::{Cardinality, DocParsingStats};
 
     #[test]
     fn test_field_name_from_field_path() {
@@ -1288,7 +1500,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(json!([true, false, true]), &mut document, &mut path)
+            .doc_from_json(
+                json!([true, false, true]),
+                &mut document,
+                &mut path,
+                &mut DocParsingStats::default(),
+            )
             .unwrap();
         assert_eq!(document.len(), 3);
         let values: Vec<bool> = document
@@ -1298,6 +1515,67 @@ mod tests {
         assert_eq!(&values, &[true, false, true])
     }
 
+    #[test]
+    fn test_parse_bool_with_coercion() {
+        let typ = LeafType::Bool(QuickwitBoolOptions::default());
+        assert_eq!(
+            typ.value_from_json(json!("true")).unwrap_err(),
+            "expected boolean, got `\"true\"`"
+        );
+        let typ = LeafType::Bool(QuickwitBoolOptions {
+            coerce: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            typ.value_from_json_with_coercion(json!(false)).unwrap(),
+            (TantivyValue::Bool(false), false)
+        );
+        assert_eq!(
+            typ.value_from_json_with_coercion(json!("true")).unwrap(),
+            (TantivyValue::Bool(true), true)
+        );
+        assert_eq!(
+            typ.value_from_json_with_coercion(json!("True"))
+                .unwrap_err(),
+            "failed to coerce JSON string `\"True\"` to boolean"
+        );
+    }
+
+    #[test]
+    fn test_parse_null_with_default_value() {
+        let typ = LeafType::U64(QuickwitNumericOptions {
+            default_value: Some(json!("42")),
+            ..Default::default()
+        });
+        let field = Field::from_field_id(10);
+        let leaf_entry = MappingLeaf {
+            field,
+            typ,
+            cardinality: Cardinality::SingleValue,
+            concatenate: Vec::new(),
+        };
+        let mut document = Document::default();
+        let mut path = vec!["my_field".to_string()];
+        let mut doc_parsing_stats = DocParsingStats::default();
+        leaf_entry
+            .doc_from_json(
+                json!(null),
+                &mut document,
+                &mut path,
+                &mut doc_parsing_stats,
+            )
+            .unwrap();
+        assert_eq!(document.len(), 1);
+        assert_eq!(document.get_first(field).unwrap(), &TantivyValue::U64(42));
+        assert_eq!(
+            doc_parsing_stats,
+            DocParsingStats {
+                num_coerced_values: 1,
+                num_defaulted_values: 1,
+            }
+        );
+    }
+
     #[test]
     fn test_parse_ip_addr_from_str() {
         let leaf = LeafType::IpAddr(QuickwitIpAddrOptions::default());
@@ -1340,7 +1618,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(serde_json::json!([10u64, 20u64]), &mut document, &mut path)
+            .doc_from_json(
+                serde_json::json!([10u64, 20u64]),
+                &mut document,
+                &mut path,
+                &mut DocParsingStats::default(),
+            )
             .unwrap();
         assert_eq!(document.len(), 2);
         let values: Vec<i64> = document
@@ -1363,7 +1646,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(serde_json::json!(null), &mut document, &mut path)
+            .doc_from_json(
+                serde_json::json!(null),
+                &mut document,
+                &mut path,
+                &mut DocParsingStats::default(),
+            )
             .unwrap();
         assert_eq!(document.len(), 0);
     }
@@ -1381,7 +1669,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(serde_json::json!(10u64), &mut document, &mut path)
+            .doc_from_json(
+                serde_json::json!(10u64),
+                &mut document,
+                &mut path,
+                &mut DocParsingStats::default(),
+            )
             .unwrap();
         assert_eq!(document.len(), 1);
         assert_eq!(document.get_first(field).unwrap().as_i64().unwrap(), 10i64);
@@ -1404,6 +1697,7 @@ mod tests {
                 serde_json::json!([10u64, [1u64, 2u64]]),
                 &mut document,
                 &mut path,
+                &mut DocParsingStats::default(),
             )
             .unwrap_err();
         assert_eq!(
@@ -1553,6 +1847,7 @@ mod tests {
                 ]),
                 &mut document,
                 &mut path,
+                &mut DocParsingStats::default(),
             )
             .unwrap();
         assert_eq!(document.len(), 2);
@@ -36,7 +36,8 @@ pub use self::field_mapping_entry::{
     QuickwitTextNormalizer,
 };
 pub(crate) use self::field_mapping_entry::{
-    FieldMappingEntryForSerialization, IndexRecordOptionSchema, QuickwitTextTokenizer,
+    FieldMappingEntryForSerialization, IndexPrefixesOptions, IndexRecordOptionSchema,
+    QuickwitTextTokenizer,
 };
 #[cfg(all(test, feature = "multilang"))]
 pub(crate) use self::field_mapping_entry::{QuickwitTextOptions, TextIndexingOptions};
@@ -55,6 +55,17 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         document_len: u64,
     ) -> Result<(Partition, Document), DocParsingError>;
 
+    /// Same as [`DocMapper::doc_from_json_obj`], but also records the number of values that were
+    /// coerced or defaulted while parsing the document into `doc_parsing_stats`.
+    fn doc_from_json_obj_with_stats(
+        &self,
+        json_obj: JsonObject,
+        document_len: u64,
+        _doc_parsing_stats: &mut DocParsingStats,
+    ) -> Result<(Partition, Document), DocParsingError> {
+        self.doc_from_json_obj(json_obj, document_len)
+    }
+
     /// Parses a JSON byte slice into a tantivy [`Document`].
     fn doc_from_json_bytes(
         &self,
@@ -115,6 +126,18 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         None
     }
 
+    /// Returns the names of the additional datetime fields whose time range is recorded for each
+    /// split.
+    fn secondary_timestamp_field_names(&self) -> &[String] {
+        &[]
+    }
+
+    /// Returns the secondary timestamp `NamedField`s on the current schema.
+    /// Returns an error if a secondary timestamp field is not found in this schema.
+    fn secondary_timestamp_named_fields(&self) -> anyhow::Result<Vec<NamedField>> {
+        named_fields(&self.schema(), self.secondary_timestamp_field_names())
+    }
+
     /// Returns the list of search fields to search into, when no field is specified.
     /// (See `UserInputQuery`).
     fn default_search_fields(&self) -> &[String];
@@ -127,29 +150,42 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     /// Returns the tag `NameField`s on the current schema.
     /// Returns an error if a tag field is not found in this schema.
     fn tag_named_fields(&self) -> anyhow::Result<Vec<NamedField>> {
-        let index_schema = self.schema();
-        self.tag_field_names()
-            .iter()
-            .map(|field_name| {
-                index_schema
-                    .get_field(field_name)
-                    .context(format!("field `{field_name}` must exist in the schema"))
-                    .map(|field| NamedField {
-                        name: field_name.clone(),
-                        field,
-                        field_type: index_schema.get_field_entry(field).field_type().clone(),
-                    })
-            })
-            .collect::<Result<Vec<_>, _>>()
+        named_fields(&self.schema(), &self.tag_field_names())
+    }
+
+    /// Returns the names of the fields for which a term digest is computed for each split.
+    fn term_digest_field_names(&self) -> BTreeSet<String> {
+        Default::default()
+    }
+
+    /// Returns the term digest `NameField`s on the current schema.
+    /// Returns an error if a term digest field is not found in this schema.
+    fn term_digest_named_fields(&self) -> anyhow::Result<Vec<NamedField>> {
+        named_fields(&self.schema(), &self.term_digest_field_names())
     }
 
     /// Returns the maximum number of partitions.
     fn max_num_partitions(&self) -> NonZeroU32;
 
+    /// Returns the version of the doc mapping this doc mapper was built from. Splits record it so
+    /// that splits built under different versions of the doc mapping are never merged together.
+    fn doc_mapping_version(&self) -> u64 {
+        0
+    }
+
     /// Returns the tokenizer manager.
     fn tokenizer_manager(&self) -> &TokenizerManager;
 }
 
+/// Counters collected while parsing a document.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DocParsingStats {
+    /// Number of values that had to be coerced into the type of their field.
+    pub num_coerced_values: u64,
+    /// Number of missing or `null` values replaced by the default value of their field.
+    pub num_defaulted_values: u64,
+}
+
 /// A struct to wrap a tantivy field with its name.
 #[derive(Clone, Debug)]
 pub struct NamedField {
@@ -161,6 +197,25 @@ pub struct NamedField {
     pub field_type: FieldType,
 }
 
+fn named_fields<'a>(
+    index_schema: &Schema,
+    field_names: impl IntoIterator<Item = &'a String>,
+) -> anyhow::Result<Vec<NamedField>> {
+    field_names
+        .into_iter()
+        .map(|field_name| {
+            index_schema
+                .get_field(field_name)
+                .context(format!("field `{field_name}` must exist in the schema"))
+                .map(|field| NamedField {
+                    name: field_name.clone(),
+                    field,
+                    field_type: index_schema.get_field_entry(field).field_type().clone(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()
+}
+
 clone_trait_object!(DocMapper);
 
 /// Bounds for a range of terms, with an optional max count of terms being matched.
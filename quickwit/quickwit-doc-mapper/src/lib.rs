@@ -34,17 +34,20 @@ mod routing_expression;
 /// Pruning tags manipulation.
 pub mod tag_pruning;
 
+/// Per-split term digests used for split pruning.
+pub mod term_digest;
+
 pub use default_doc_mapper::{
     analyze_text, BinaryFormat, DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry,
     FieldMappingType, Mode, ModeType, QuickwitBytesOptions, QuickwitJsonOptions, TokenizerConfig,
     TokenizerEntry,
 };
 use default_doc_mapper::{
-    FastFieldOptions, FieldMappingEntryForSerialization, IndexRecordOptionSchema,
-    NgramTokenizerOption, QuickwitTextNormalizer, QuickwitTextTokenizer, RegexTokenizerOption,
-    TokenFilterType, TokenizerType,
+    FastFieldOptions, FieldMappingEntryForSerialization, IndexPrefixesOptions,
+    IndexRecordOptionSchema, NgramTokenizerOption, QuickwitTextNormalizer, QuickwitTextTokenizer,
+    RegexTokenizerOption, TokenFilterType, TokenizerType,
 };
-pub use doc_mapper::{DocMapper, JsonObject, NamedField, TermRange, WarmupInfo};
+pub use doc_mapper::{DocMapper, DocParsingStats, JsonObject, NamedField, TermRange, WarmupInfo};
 pub use error::{DocParsingError, QueryParserError};
 use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
 pub use routing_expression::RoutingExpr;
@@ -79,6 +82,7 @@ pub enum Cardinality {
 #[openapi(components(schemas(
     FastFieldOptions,
     FieldMappingEntryForSerialization,
+    IndexPrefixesOptions,
     IndexRecordOptionSchema,
     ModeType,
     NgramTokenizerOption,
@@ -22,11 +22,11 @@ use std::convert::Infallible;
 use std::ops::Bound;
 
 use quickwit_query::query_ast::{
-    FieldPresenceQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery,
-    TermSetQuery, WildcardQuery,
+    FieldPresenceQuery, FullTextQuery, JoinQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor,
+    RangeQuery, TermSetQuery, WildcardQuery,
 };
 use quickwit_query::tokenizers::TokenizerManager;
-use quickwit_query::{find_field_or_hit_dynamic, InvalidQuery};
+use quickwit_query::{find_field_or_hit_dynamic, index_prefixes_term, InvalidQuery};
 use tantivy::query::Query;
 use tantivy::schema::{Field, Schema};
 use tantivy::Term;
@@ -68,6 +68,21 @@ impl<'a> QueryAstVisitor<'a> for ExistsQueryFields {
     }
 }
 
+#[derive(Default)]
+struct JoinQueryFields {
+    join_query_field_names: HashSet<String>,
+}
+
+impl<'a> QueryAstVisitor<'a> for JoinQueryFields {
+    type Err = Infallible;
+
+    fn visit_join(&mut self, join_query: &'a JoinQuery) -> Result<(), Infallible> {
+        self.join_query_field_names
+            .insert(join_query.join_field.to_string());
+        self.visit(&join_query.query)
+    }
+}
+
 /// Build a `Query` with field resolution & forbidding range clauses.
 pub(crate) fn build_query(
     query_ast: &QueryAst,
@@ -84,8 +99,13 @@ pub(crate) fn build_query(
     // This cannot fail. The error type is Infallible.
     let _: Result<(), Infallible> = exists_query_fields.visit(query_ast);
 
+    let mut join_query_fields = JoinQueryFields::default();
+    // This cannot fail. The error type is Infallible.
+    let _: Result<(), Infallible> = join_query_fields.visit(query_ast);
+
     let mut fast_field_names = HashSet::new();
     fast_field_names.extend(range_query_fields.range_query_field_names);
+    fast_field_names.extend(join_query_fields.join_query_field_names);
     fast_field_names.extend(
         exists_query_fields
             .exists_query_field_names
@@ -232,6 +252,10 @@ impl<'a, 'b: 'a> QueryAstVisitor<'a> for ExtractPrefixTermRanges<'b> {
         if let Some(prefix_term) =
             full_text_query.get_prefix_term(self.schema, self.tokenizer_manager)
         {
+            if index_prefixes_term(self.schema, &prefix_term).is_some() {
+                // The prefix is looked up as a regular term of the prefixes field.
+                return Ok(());
+            }
             // the max_expansion expansion of a bool prefix query is used for the fuzzy part of the
             // query, not for the expension to a range request.
             // see https://github.com/elastic/elasticsearch/blob/6ad48306d029e6e527c0481e2e9880bd2f06b239/docs/reference/query-dsl/match-bool-prefix-query.asciidoc#parameters
@@ -245,6 +269,12 @@ impl<'a, 'b: 'a> QueryAstVisitor<'a> for ExtractPrefixTermRanges<'b> {
         phrase_prefix: &'a PhrasePrefixQuery,
     ) -> Result<(), Self::Err> {
         let (_, terms) = phrase_prefix.get_terms(self.schema, self.tokenizer_manager)?;
+        if phrase_prefix
+            .index_prefixes_term(self.schema, &terms)
+            .is_some()
+        {
+            return Ok(());
+        }
         if let Some((_, term)) = terms.last() {
             self.add_prefix_term(term.clone(), phrase_prefix.max_expansions, terms.len() > 1);
         }
@@ -272,7 +302,7 @@ fn extract_prefix_term_ranges(
 mod test {
     use quickwit_datetime::{parse_date_time_str, DateTimeInputFormat};
     use quickwit_query::create_default_quickwit_tokenizer_manager;
-    use quickwit_query::query_ast::query_ast_from_user_text;
+    use quickwit_query::query_ast::{query_ast_from_user_text, JoinQuery, QueryAst};
     use tantivy::columnar::MonotonicallyMappableToU64;
     use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
     use tantivy::{DateOptions, DateTime, DateTimePrecision};
@@ -679,4 +709,45 @@ mod test {
         .unwrap();
         assert!(warmup_info.term_dict_fields.is_empty());
     }
+
+    #[test]
+    fn test_build_query_warmup_info_join_query() {
+        let underlying_query_ast = query_ast_from_user_text("desc: IN [hello]", None)
+            .parse_user_query(&[])
+            .unwrap();
+        let join_query_ast: QueryAst = JoinQuery {
+            join_field: "u64_fast".to_string(),
+            query: Box::new(underlying_query_ast),
+        }
+        .into();
+        let (_, warmup_info) = build_query(
+            &join_query_ast,
+            make_schema(true),
+            &create_default_quickwit_tokenizer_manager(),
+            &[],
+            true,
+        )
+        .unwrap();
+        assert!(warmup_info
+            .term_dict_fields
+            .contains(&tantivy::schema::Field::from_field_id(1)));
+        assert!(warmup_info.fast_field_names.contains("u64_fast"));
+
+        let join_query_ast: QueryAst = JoinQuery {
+            join_field: "title".to_string(),
+            query: Box::new(QueryAst::MatchAll),
+        }
+        .into();
+        let error = build_query(
+            &join_query_ast,
+            make_schema(true),
+            &create_default_quickwit_tokenizer_manager(),
+            &[],
+            true,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("join field `title` is not a fast field"));
+    }
 }
@@ -118,6 +118,11 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
             panic!("Extract unsimplified should only be called on AST without UserInputQuery.");
         }
         QueryAst::FieldPresence(_) => UnsimplifiedTagFilterAst::Uninformative,
+        // Fuzzy and regex queries match terms that differ from their value.
+        QueryAst::FuzzyTerm(_) | QueryAst::Regex(_) => UnsimplifiedTagFilterAst::Uninformative,
+        // The documents matched by a join query do not necessarily match its underlying query:
+        // for instance, they may not hold the tags it requires.
+        QueryAst::Join(_) => UnsimplifiedTagFilterAst::Uninformative,
     }
 }
 
@@ -0,0 +1,528 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::hash::Hasher;
+use std::ops::Range;
+
+use anyhow::{bail, Context};
+use quickwit_query::query_ast::QueryAst;
+use siphasher::sip::SipHasher;
+use tantivy::directory::OwnedBytes;
+
+/// Number of bits allocated per term. With 7 hash functions, this yields a false positive
+/// probability of about 1%.
+const NUM_BITS_PER_TERM: usize = 10;
+
+const NUM_HASHES: u8 = 7;
+
+/// Upper bound on the size of a single digest, reached for about 27 million distinct terms.
+/// Beyond this size, digests still never produce false negatives, but their false positive rate
+/// degrades gracefully.
+const MAX_DIGEST_NUM_BYTES: usize = 32 * 1024 * 1024;
+
+const MIN_DIGEST_NUM_BYTES: usize = 8;
+
+/// Version of the format of the term digests files.
+const TERM_DIGESTS_FORMAT_VERSION: u8 = 1;
+
+/// Compact probabilistic summary (a Bloom filter) of the set of terms of a field within a
+/// split. Digests are sized according to the number of terms of the split, so their false
+/// positive rate does not depend on the size of the split.
+///
+/// A digest never produces false negatives: if `may_contain` returns false, the term is
+/// guaranteed to be absent from the split.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct TermDigest {
+    num_hashes: u8,
+    bits: Vec<u8>,
+}
+
+impl fmt::Debug for TermDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TermDigest")
+            .field("num_hashes", &self.num_hashes)
+            .field("num_bytes", &self.bits.len())
+            .finish()
+    }
+}
+
+impl TermDigest {
+    /// Creates an empty digest sized for `num_terms` terms.
+    pub fn with_num_terms(num_terms: usize) -> Self {
+        let num_bytes = num_terms.saturating_mul(NUM_BITS_PER_TERM).div_ceil(8);
+        let num_bytes = num_bytes.clamp(MIN_DIGEST_NUM_BYTES, MAX_DIGEST_NUM_BYTES);
+        TermDigest {
+            num_hashes: NUM_HASHES,
+            bits: vec![0u8; num_bytes],
+        }
+    }
+
+    /// Size of the digest in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Adds a term to the digest.
+    pub fn insert(&mut self, term: &[u8]) {
+        let num_bits = self.bits.len() as u64 * 8;
+        if num_bits == 0 {
+            return;
+        }
+        for bit_idx in bit_indexes(term, self.num_hashes, num_bits) {
+            self.bits[(bit_idx / 8) as usize] |= 1 << (bit_idx % 8);
+        }
+    }
+
+    /// Returns false if the term is guaranteed to be absent from the set of terms the digest
+    /// was built from.
+    pub fn may_contain(&self, term: &[u8]) -> bool {
+        self.as_digest_ref().may_contain(term)
+    }
+
+    fn as_digest_ref(&self) -> TermDigestRef<'_> {
+        TermDigestRef {
+            num_hashes: self.num_hashes,
+            bits: &self.bits,
+        }
+    }
+}
+
+/// Read-only view of a [`TermDigest`], borrowed from the bytes of a term digests file.
+#[derive(Clone, Copy)]
+pub struct TermDigestRef<'a> {
+    num_hashes: u8,
+    bits: &'a [u8],
+}
+
+impl TermDigestRef<'_> {
+    /// Returns false if the term is guaranteed to be absent from the set of terms the digest
+    /// was built from.
+    pub fn may_contain(&self, term: &[u8]) -> bool {
+        let num_bits = self.bits.len() as u64 * 8;
+        if num_bits == 0 {
+            // An empty (or corrupted) digest cannot be used to prune anything.
+            return true;
+        }
+        bit_indexes(term, self.num_hashes, num_bits)
+            .all(|bit_idx| self.bits[(bit_idx / 8) as usize] & (1 << (bit_idx % 8)) != 0)
+    }
+}
+
+/// Enhanced double hashing (Kirsch-Mitzenmacher) over a single stable 64-bit hash.
+fn bit_indexes(term: &[u8], num_hashes: u8, num_bits: u64) -> impl Iterator<Item = u64> {
+    let mut hasher = SipHasher::new();
+    hasher.write(term);
+    let hash = hasher.finish();
+    let first_hash = hash & 0xFFFF_FFFF;
+    let second_hash = (hash >> 32) | 1;
+    (0..num_hashes as u64)
+        .map(move |idx| first_hash.wrapping_add(idx.wrapping_mul(second_hash)) % num_bits)
+}
+
+/// The term digests of a split, keyed by field name. They are stored next to the split file in a
+/// dedicated file rather than in the split metadata, so that large splits can have large digests
+/// without bloating the metastore.
+///
+/// The file starts with the format version (u8) and the number of digests (u32). Each digest is
+/// then encoded as the length of the field name (u32), the field name, the number of hash
+/// functions (u8), the length of the bits (u32), and the bits. Integers are little-endian.
+pub struct SplitTermDigests {
+    bytes: OwnedBytes,
+    // Number of hash functions and byte range of the bits of the digest of each field.
+    digests: BTreeMap<String, (u8, Range<usize>)>,
+}
+
+impl fmt::Debug for SplitTermDigests {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SplitTermDigests")
+            .field("fields", &self.digests.keys())
+            .field("num_bytes", &self.bytes.len())
+            .finish()
+    }
+}
+
+impl SplitTermDigests {
+    /// Serializes the term digests of a split.
+    pub fn serialize(term_digests: &BTreeMap<String, TermDigest>) -> Vec<u8> {
+        let num_bytes = 5 + term_digests
+            .iter()
+            .map(|(field_name, term_digest)| 9 + field_name.len() + term_digest.num_bytes())
+            .sum::<usize>();
+        let mut buffer = Vec::with_capacity(num_bytes);
+        buffer.push(TERM_DIGESTS_FORMAT_VERSION);
+        buffer.extend_from_slice(&(term_digests.len() as u32).to_le_bytes());
+
+        for (field_name, term_digest) in term_digests {
+            buffer.extend_from_slice(&(field_name.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(field_name.as_bytes());
+            buffer.push(term_digest.num_hashes);
+            buffer.extend_from_slice(&(term_digest.bits.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(&term_digest.bits);
+        }
+        buffer
+    }
+
+    /// Opens the term digests of a split without copying the bits of the digests.
+    pub fn open(bytes: OwnedBytes) -> anyhow::Result<Self> {
+        let mut reader = ByteReader {
+            bytes: bytes.as_slice(),
+            offset: 0,
+        };
+        let format_version = reader.read_u8()?;
+
+        if format_version != TERM_DIGESTS_FORMAT_VERSION {
+            bail!("unsupported term digests format version `{format_version}`");
+        }
+        let num_digests = reader.read_u32()?;
+        let mut digests = BTreeMap::new();
+
+        for _ in 0..num_digests {
+            let field_name_len = reader.read_u32()? as usize;
+            let field_name_range = reader.read_range(field_name_len)?;
+            let field_name = std::str::from_utf8(&bytes.as_slice()[field_name_range])
+                .context("field name is not valid UTF-8")?
+                .to_string();
+            let num_hashes = reader.read_u8()?;
+            let num_bits_bytes = reader.read_u32()? as usize;
+            let bits_range = reader.read_range(num_bits_bytes)?;
+            digests.insert(field_name, (num_hashes, bits_range));
+        }
+        if reader.offset != bytes.len() {
+            bail!("term digests file has trailing bytes");
+        }
+        Ok(Self { bytes, digests })
+    }
+
+    /// Returns the digest of a field, if any.
+    pub fn get(&self, field_name: &str) -> Option<TermDigestRef<'_>> {
+        let (num_hashes, bits_range) = self.digests.get(field_name)?;
+        let term_digest_ref = TermDigestRef {
+            num_hashes: *num_hashes,
+            bits: &self.bytes.as_slice()[bits_range.clone()],
+        };
+        Some(term_digest_ref)
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl ByteReader<'_> {
+    fn read_range(&mut self, len: usize) -> anyhow::Result<Range<usize>> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .context("term digests file is truncated")?;
+        let range = self.offset..end;
+        self.offset = end;
+        Ok(range)
+    }
+
+    fn read_u8(&mut self) -> anyhow::Result<u8> {
+        let range = self.read_range(1)?;
+        Ok(self.bytes[range.start])
+    }
+
+    fn read_u32(&mut self) -> anyhow::Result<u32> {
+        let range = self.read_range(4)?;
+        let le_bytes: [u8; 4] = self.bytes[range]
+            .try_into()
+            .expect("slice should be 4 bytes");
+        Ok(u32::from_le_bytes(le_bytes))
+    }
+}
+
+/// Returns false if and only if the term digests of a split prove that no document of
+/// the split can match the query.
+///
+/// The evaluation is conservative: any query node that cannot be evaluated against the
+/// digests is assumed to match.
+pub fn can_match_term_digests(query_ast: &QueryAst, term_digests: &SplitTermDigests) -> bool {
+    if term_digests.digests.is_empty() {
+        return true;
+    }
+    match query_ast {
+        QueryAst::Bool(bool_query) => {
+            let mut required_clauses = bool_query.must.iter().chain(&bool_query.filter).peekable();
+            if required_clauses.peek().is_some() {
+                return required_clauses.all(|clause| can_match_term_digests(clause, term_digests));
+            }
+            if bool_query.should.is_empty() {
+                return true;
+            }
+            bool_query
+                .should
+                .iter()
+                .any(|clause| can_match_term_digests(clause, term_digests))
+        }
+        QueryAst::Term(term_query) => {
+            can_match_term(&term_query.field, &term_query.value, term_digests)
+        }
+        QueryAst::TermSet(term_set_query) => {
+            term_set_query.terms_per_field.iter().any(|(field, terms)| {
+                terms
+                    .iter()
+                    .any(|term| can_match_term(field, term, term_digests))
+            })
+        }
+        // Term digest fields use the `raw` tokenizer, so unless the query overrides the
+        // tokenizer, the text is searched as a single term.
+        QueryAst::FullText(full_text_query) if full_text_query.params.tokenizer.is_none() => {
+            can_match_term(&full_text_query.field, &full_text_query.text, term_digests)
+        }
+        QueryAst::Boost { underlying, .. } => can_match_term_digests(underlying, term_digests),
+        // A join query can only match documents of splits containing at least one document
+        // matching its underlying query.
+        QueryAst::Join(join_query) => can_match_term_digests(&join_query.query, term_digests),
+        QueryAst::MatchNone => false,
+        _ => true,
+    }
+}
+
+fn can_match_term(field: &str, value: &str, term_digests: &SplitTermDigests) -> bool {
+    let Some(term_digest) = term_digests.get(field) else {
+        return true;
+    };
+    term_digest.may_contain(value.as_bytes())
+}
+
+/// Returns the fields whose term digests may be used by [`can_match_term_digests`] to evaluate
+/// the query. Loading the digests of a split is pointless if it has none of these fields.
+pub fn prunable_fields(query_ast: &QueryAst) -> BTreeSet<&str> {
+    let mut fields = BTreeSet::new();
+    collect_prunable_fields(query_ast, &mut fields);
+    fields
+}
+
+fn collect_prunable_fields<'a>(query_ast: &'a QueryAst, fields: &mut BTreeSet<&'a str>) {
+    match query_ast {
+        QueryAst::Bool(bool_query) => {
+            for clause in bool_query
+                .must
+                .iter()
+                .chain(&bool_query.filter)
+                .chain(&bool_query.should)
+            {
+                collect_prunable_fields(clause, fields);
+            }
+        }
+        QueryAst::Term(term_query) => {
+            fields.insert(&term_query.field);
+        }
+        QueryAst::TermSet(term_set_query) => {
+            fields.extend(term_set_query.terms_per_field.keys().map(String::as_str));
+        }
+        QueryAst::FullText(full_text_query) if full_text_query.params.tokenizer.is_none() => {
+            fields.insert(&full_text_query.field);
+        }
+        QueryAst::Boost { underlying, .. } => collect_prunable_fields(underlying, fields),
+        QueryAst::Join(join_query) => collect_prunable_fields(&join_query.query, fields),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use quickwit_query::query_ast::{
+        qast_helper, query_ast_from_user_text, BoolQuery, TermQuery, TermSetQuery,
+    };
+
+    use super::*;
+
+    fn split_term_digests_for_test(
+        term_digests: &BTreeMap<String, TermDigest>,
+    ) -> SplitTermDigests {
+        let term_digests_bytes = SplitTermDigests::serialize(term_digests);
+        SplitTermDigests::open(OwnedBytes::new(term_digests_bytes)).unwrap()
+    }
+
+    fn term_digests_for_test(terms: &[&str]) -> SplitTermDigests {
+        let mut term_digest = TermDigest::with_num_terms(terms.len());
+        for term in terms {
+            term_digest.insert(term.as_bytes());
+        }
+        split_term_digests_for_test(&BTreeMap::from([("trace_id".to_string(), term_digest)]))
+    }
+
+    #[test]
+    fn test_term_digest_no_false_negatives() {
+        let terms: Vec<String> = (0..10_000).map(|idx| format!("trace-{idx}")).collect();
+        let mut term_digest = TermDigest::with_num_terms(terms.len());
+        for term in &terms {
+            term_digest.insert(term.as_bytes());
+        }
+        assert!(terms
+            .iter()
+            .all(|term| term_digest.may_contain(term.as_bytes())));
+
+        let num_false_positives = (0..10_000)
+            .filter(|idx| term_digest.may_contain(format!("absent-{idx}").as_bytes()))
+            .count();
+        assert!(num_false_positives < 300, "{num_false_positives}");
+    }
+
+    #[test]
+    fn test_term_digest_size() {
+        assert_eq!(
+            TermDigest::with_num_terms(0).num_bytes(),
+            MIN_DIGEST_NUM_BYTES
+        );
+        // Digests are sized according to the number of terms of the split.
+        assert_eq!(TermDigest::with_num_terms(1_000_000).num_bytes(), 1_250_000);
+        assert_eq!(
+            TermDigest::with_num_terms(100_000_000).num_bytes(),
+            MAX_DIGEST_NUM_BYTES
+        );
+    }
+
+    #[test]
+    fn test_split_term_digests_serialization() {
+        let mut trace_id_digest = TermDigest::with_num_terms(3);
+        trace_id_digest.insert(b"abc");
+        let span_id_digest = TermDigest::with_num_terms(1_000);
+        let term_digests = BTreeMap::from([
+            ("span_id".to_string(), span_id_digest),
+            ("trace_id".to_string(), trace_id_digest),
+        ]);
+        let term_digests_bytes = SplitTermDigests::serialize(&term_digests);
+        assert_eq!(term_digests_bytes.len(), 5 + 9 + 7 + 1_250 + 9 + 8 + 8);
+
+        let split_term_digests =
+            SplitTermDigests::open(OwnedBytes::new(term_digests_bytes.clone())).unwrap();
+        assert!(split_term_digests
+            .get("trace_id")
+            .unwrap()
+            .may_contain(b"abc"));
+        assert!(!split_term_digests
+            .get("span_id")
+            .unwrap()
+            .may_contain(b"abc"));
+        assert!(split_term_digests.get("service").is_none());
+
+        let truncated_bytes = term_digests_bytes[..term_digests_bytes.len() - 1].to_vec();
+        SplitTermDigests::open(OwnedBytes::new(truncated_bytes)).unwrap_err();
+
+        let mut unknown_version_bytes = term_digests_bytes;
+        unknown_version_bytes[0] = 0;
+        SplitTermDigests::open(OwnedBytes::new(unknown_version_bytes)).unwrap_err();
+    }
+
+    #[test]
+    fn test_prunable_fields() {
+        assert_eq!(
+            prunable_fields(&qast_helper(
+                "trace_id:abc AND (service:foo OR NOT span_id:def)",
+                &[]
+            )),
+            BTreeSet::from(["service", "trace_id"])
+        );
+        assert!(prunable_fields(&QueryAst::MatchAll).is_empty());
+    }
+
+    #[test]
+    fn test_can_match_term_digests() {
+        let term_digests = term_digests_for_test(&["abc", "def"]);
+
+        assert!(can_match_term_digests(
+            &qast_helper("trace_id:abc", &[]),
+            &term_digests
+        ));
+        assert!(!can_match_term_digests(
+            &qast_helper("trace_id:xyz", &[]),
+            &term_digests
+        ));
+        // Fields without digest can always match.
+        assert!(can_match_term_digests(
+            &qast_helper("service:xyz", &[]),
+            &term_digests
+        ));
+        assert!(!can_match_term_digests(
+            &qast_helper("service:xyz AND trace_id:xyz", &[]),
+            &term_digests
+        ));
+        assert!(can_match_term_digests(
+            &qast_helper("service:xyz OR trace_id:xyz", &[]),
+            &term_digests
+        ));
+        assert!(can_match_term_digests(
+            &qast_helper("trace_id:abc OR trace_id:xyz", &[]),
+            &term_digests
+        ));
+        assert!(!can_match_term_digests(
+            &qast_helper("trace_id:uvw OR trace_id:xyz", &[]),
+            &term_digests
+        ));
+        // Negations are never used for pruning.
+        assert!(can_match_term_digests(
+            &qast_helper("NOT trace_id:abc", &[]),
+            &term_digests
+        ));
+        // Unresolved user queries cannot be evaluated.
+        assert!(can_match_term_digests(
+            &query_ast_from_user_text("trace_id:xyz", None),
+            &term_digests
+        ));
+    }
+
+    #[test]
+    fn test_can_match_term_digests_term_and_term_set() {
+        let term_digests = term_digests_for_test(&["abc"]);
+
+        let term_query: QueryAst = TermQuery {
+            field: "trace_id".to_string(),
+            value: "xyz".to_string(),
+        }
+        .into();
+        assert!(!can_match_term_digests(&term_query, &term_digests));
+
+        let term_set_query: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "trace_id".to_string(),
+                BTreeSet::from(["xyz".to_string(), "abc".to_string()]),
+            )]),
+        }
+        .into();
+        assert!(can_match_term_digests(&term_set_query, &term_digests));
+
+        let term_set_query: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "trace_id".to_string(),
+                BTreeSet::from(["xyz".to_string(), "uvw".to_string()]),
+            )]),
+        }
+        .into();
+        assert!(!can_match_term_digests(&term_set_query, &term_digests));
+
+        let bool_query: QueryAst = BoolQuery {
+            filter: vec![term_query.clone()],
+            ..Default::default()
+        }
+        .into();
+        assert!(!can_match_term_digests(&bool_query, &term_digests));
+        let empty_term_digests = split_term_digests_for_test(&BTreeMap::new());
+        assert!(can_match_term_digests(&term_query, &empty_term_digests));
+    }
+}
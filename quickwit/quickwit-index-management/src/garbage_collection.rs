@@ -25,6 +25,7 @@ use std::time::Duration;
 use futures::Future;
 use quickwit_common::pretty::PrettySample;
 use quickwit_common::{Progress, ServiceStream};
+use quickwit_config::LegalHold;
 use quickwit_metastore::{
     ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitInfo,
     SplitMetadata, SplitState,
@@ -37,7 +38,7 @@ use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_storage::{BulkDeleteError, Storage};
 use thiserror::Error;
 use time::OffsetDateTime;
-use tracing::{error, instrument};
+use tracing::{error, instrument, warn};
 
 /// The maximum number of splits that the GC should delete per attempt.
 const DELETE_SPLITS_BATCH_SIZE: usize = 1000;
@@ -82,14 +83,18 @@ pub struct SplitRemovalInfo {
 ///   collected.
 /// * `deletion_grace_period` -  Threshold period after which a marked as deleted split can be
 ///   safely deleted.
+/// * `legal_hold_opt` - The legal hold of the index, if any. Splits under legal hold are never
+///   deleted, even if they are marked for deletion.
 /// * `dry_run` - Should this only return a list of affected files without performing deletion.
 /// * `progress` - For reporting progress (useful when called from within a quickwit actor).
+#[allow(clippy::too_many_arguments)]
 pub async fn run_garbage_collect(
     index_uid: IndexUid,
     storage: Arc<dyn Storage>,
     mut metastore: MetastoreServiceClient,
     staged_grace_period: Duration,
     deletion_grace_period: Duration,
+    legal_hold_opt: Option<&LegalHold>,
     dry_run: bool,
     progress_opt: Option<&Progress>,
 ) -> anyhow::Result<SplitRemovalInfo> {
@@ -122,6 +127,9 @@ pub async fn run_garbage_collect(
         .await?
         .collect_splits_metadata()
         .await?;
+        if let Some(legal_hold) = legal_hold_opt {
+            splits_marked_for_deletion.retain(|split| !legal_hold.holds_split(&split.split_id));
+        }
         splits_marked_for_deletion.extend(deletable_staged_splits);
 
         let candidate_entries: Vec<SplitInfo> = splits_marked_for_deletion
@@ -154,11 +162,18 @@ pub async fn run_garbage_collect(
     let updated_before_timestamp =
         OffsetDateTime::now_utc().unix_timestamp() - deletion_grace_period.as_secs() as i64;
 
+    if legal_hold_opt.is_some_and(|legal_hold| legal_hold.holds_all_splits()) {
+        return Ok(SplitRemovalInfo {
+            removed_split_entries: Vec::new(),
+            failed_splits: Vec::new(),
+        });
+    }
     let deleted_splits = delete_splits_marked_for_deletion(
         index_uid,
         updated_before_timestamp,
         storage,
         metastore,
+        legal_hold_opt,
         progress_opt,
     )
     .await;
@@ -171,22 +186,33 @@ pub async fn run_garbage_collect(
 ///
 /// The aim of this is to spread the load out across a longer period
 /// rather than short, heavy bursts on the metastore and storage system itself.
+///
+/// Splits held by `legal_hold_opt` are skipped.
 async fn delete_splits_marked_for_deletion(
     index_uid: IndexUid,
     updated_before_timestamp: i64,
     storage: Arc<dyn Storage>,
     mut metastore: MetastoreServiceClient,
+    legal_hold_opt: Option<&LegalHold>,
     progress_opt: Option<&Progress>,
 ) -> SplitRemovalInfo {
     let mut removed_splits = Vec::new();
     let mut failed_splits = Vec::new();
+    // Held splits are never deleted, so we page past them instead of listing them again.
+    let mut last_held_split_opt: Option<SplitMetadata> = None;
 
     loop {
-        let query = ListSplitsQuery::for_index(index_uid.clone())
+        let mut query = ListSplitsQuery::for_index(index_uid.clone())
             .with_split_state(SplitState::MarkedForDeletion)
             .with_update_timestamp_lte(updated_before_timestamp)
             .with_limit(DELETE_SPLITS_BATCH_SIZE);
 
+        if legal_hold_opt.is_some() {
+            query = query.sort_by_time_range_end_desc();
+        }
+        if let Some(last_held_split) = &last_held_split_opt {
+            query = query.after_split_by_time_range_end_desc(last_held_split);
+        }
         let list_splits_request = match ListSplitsRequest::try_from_list_splits_query(&query) {
             Ok(request) => request,
             Err(error) => {
@@ -205,7 +231,7 @@ async fn delete_splits_marked_for_deletion(
                 }
             };
 
-        let splits_metadata_to_delete: Vec<SplitMetadata> =
+        let mut splits_metadata_to_delete: Vec<SplitMetadata> =
             match splits_to_delete_stream.collect_splits_metadata().await {
                 Ok(splits) => splits,
                 Err(error) => {
@@ -219,6 +245,25 @@ async fn delete_splits_marked_for_deletion(
         if num_splits_to_delete == 0 {
             break;
         }
+        if let Some(legal_hold) = legal_hold_opt {
+            // The splits of the batch listed before the last held split are either deleted below
+            // or held as well, so the next batch starts after it.
+            if let Some(last_held_split) = splits_metadata_to_delete
+                .iter()
+                .rev()
+                .find(|split| legal_hold.holds_split(&split.split_id))
+            {
+                last_held_split_opt = Some(last_held_split.clone());
+            }
+            splits_metadata_to_delete.retain(|split| !legal_hold.holds_split(&split.split_id));
+
+            if splits_metadata_to_delete.is_empty() {
+                if num_splits_to_delete < DELETE_SPLITS_BATCH_SIZE {
+                    break;
+                }
+                continue;
+            }
+        }
         let delete_splits_result = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage.clone(),
@@ -262,11 +307,33 @@ pub async fn delete_splits_from_storage_and_metastore(
     progress_opt: Option<&Progress>,
 ) -> anyhow::Result<Vec<SplitInfo>, DeleteSplitsError> {
     let mut split_infos: HashMap<PathBuf, SplitInfo> = HashMap::with_capacity(splits.len());
+    let mut term_digests_paths: Vec<PathBuf> = Vec::new();
 
     for split in splits {
+        if !split.term_digest_fields.is_empty() {
+            let term_digests_path =
+                PathBuf::from(quickwit_common::term_digests_file(&split.split_id));
+            term_digests_paths.push(term_digests_path);
+        }
         let split_info = split.as_split_info();
         split_infos.insert(split_info.file_name.clone(), split_info);
     }
+    // The term digests are deleted first and on a best-effort basis: if the deletion of the split
+    // files fails afterwards, the root searchers simply stop pruning these splits.
+    if !term_digests_paths.is_empty() {
+        let term_digests_path_refs: Vec<&Path> =
+            term_digests_paths.iter().map(PathBuf::as_path).collect();
+        let delete_result =
+            protect_future(progress_opt, storage.bulk_delete(&term_digests_path_refs)).await;
+
+        if let Err(bulk_delete_error) = delete_result {
+            warn!(
+                error=?bulk_delete_error.error,
+                index_id=index_uid.index_id,
+                "failed to delete term digests file(s) from storage",
+            );
+        }
+    }
     let split_paths = split_infos
         .keys()
         .map(|split_path_buf| split_path_buf.as_path())
@@ -353,13 +420,13 @@ mod tests {
 
     use itertools::Itertools;
     use quickwit_common::ServiceStream;
-    use quickwit_config::IndexConfig;
+    use quickwit_config::{IndexConfig, LegalHold};
     use quickwit_metastore::{
-        metastore_for_test, CreateIndexRequestExt, ListSplitsQuery,
-        MetastoreServiceStreamSplitsExt, SplitMetadata, SplitState, StageSplitsRequestExt,
+        metastore_for_test, CreateIndexRequestExt, ListSplitsQuery, ListSplitsResponseExt,
+        MetastoreServiceStreamSplitsExt, Split, SplitMetadata, SplitState, StageSplitsRequestExt,
     };
     use quickwit_proto::metastore::{
-        CreateIndexRequest, EntityKind, MockMetastoreService, StageSplitsRequest,
+        CreateIndexRequest, EmptyResponse, EntityKind, MockMetastoreService, StageSplitsRequest,
     };
     use quickwit_proto::types::IndexUid;
     use quickwit_storage::{
@@ -419,6 +486,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(30),
+            None,
             false,
             None,
         )
@@ -447,6 +515,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(0),
             Duration::from_secs(30),
+            None,
             false,
             None,
         )
@@ -525,6 +594,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(30),
+            None,
             false,
             None,
         )
@@ -553,6 +623,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(0),
+            None,
             false,
             None,
         )
@@ -589,6 +660,7 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             Duration::from_secs(30),
             Duration::from_secs(30),
+            None,
             false,
             None,
         )
@@ -596,6 +668,190 @@ mod tests {
         .unwrap();
     }
 
+    #[tokio::test]
+    async fn test_run_gc_skips_splits_under_legal_hold() {
+        let storage = storage_for_test();
+        let mut metastore = metastore_for_test();
+
+        let index_id = "test-run-gc-legal-hold--index";
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+
+        let split_ids = ["held-split", "released-split"];
+        let splits_metadata: Vec<SplitMetadata> = split_ids
+            .iter()
+            .map(|split_id| SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                ..Default::default()
+            })
+            .collect();
+        let stage_splits_request =
+            StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata)
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+        let mark_splits_for_deletion_request = MarkSplitsForDeletionRequest::new(
+            index_uid.clone(),
+            split_ids
+                .iter()
+                .map(|split_id| split_id.to_string())
+                .collect(),
+        );
+        metastore
+            .mark_splits_for_deletion(mark_splits_for_deletion_request)
+            .await
+            .unwrap();
+
+        // The hold covers the whole index, so nothing is deleted.
+        let legal_hold = LegalHold::default();
+        let removal_info = run_garbage_collect(
+            index_uid.clone(),
+            storage.clone(),
+            metastore.clone(),
+            Duration::from_secs(30),
+            Duration::from_secs(0),
+            Some(&legal_hold),
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(removal_info.removed_split_entries.is_empty());
+
+        // The hold covers a single split, so only the other one is deleted.
+        let legal_hold = LegalHold {
+            split_ids: vec!["held-split".to_string()],
+            reason: None,
+        };
+        let dry_run_removal_info = run_garbage_collect(
+            index_uid.clone(),
+            storage.clone(),
+            metastore.clone(),
+            Duration::from_secs(30),
+            Duration::from_secs(0),
+            Some(&legal_hold),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(dry_run_removal_info.removed_split_entries.len(), 1);
+        assert_eq!(
+            dry_run_removal_info.removed_split_entries[0].split_id,
+            "released-split"
+        );
+
+        let removal_info = run_garbage_collect(
+            index_uid.clone(),
+            storage.clone(),
+            metastore.clone(),
+            Duration::from_secs(30),
+            Duration::from_secs(0),
+            Some(&legal_hold),
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(removal_info.removed_split_entries.len(), 1);
+        assert_eq!(
+            removal_info.removed_split_entries[0].split_id,
+            "released-split"
+        );
+
+        let query = ListSplitsQuery::for_index(index_uid);
+        let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+        let splits = metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        assert_eq!(splits.len(), 1);
+        assert_eq!(splits[0].split_id(), "held-split");
+    }
+
+    #[tokio::test]
+    async fn test_delete_splits_marked_for_deletion_pages_past_held_splits() {
+        let storage = storage_for_test();
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+
+        let held_split_ids: Vec<String> = (0..DELETE_SPLITS_BATCH_SIZE)
+            .map(|split_ord| format!("held-split-{split_ord:04}"))
+            .collect();
+        let legal_hold = LegalHold {
+            split_ids: held_split_ids.clone(),
+            reason: None,
+        };
+        let make_split = |split_id: &str| Split {
+            split_state: SplitState::MarkedForDeletion,
+            update_timestamp: 0,
+            publish_timestamp: None,
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                ..Default::default()
+            },
+        };
+        let held_splits: Vec<Split> = held_split_ids
+            .iter()
+            .map(|split_id| make_split(split_id))
+            .collect();
+        let released_split = make_split("released-split");
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_splits()
+            .times(2)
+            .returning(move |list_splits_request| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                assert!(list_splits_query.sort_by_time_range_end_desc);
+
+                let splits = match list_splits_query.after_split_by_time_range_end_desc {
+                    None => held_splits.clone(),
+                    Some((None, split_id)) => {
+                        assert_eq!(split_id, "held-split-0999");
+                        vec![released_split.clone()]
+                    }
+                    Some(cursor) => panic!("unexpected cursor `{cursor:?}`"),
+                };
+                let list_splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(list_splits_response)]))
+            });
+        mock_metastore
+            .expect_delete_splits()
+            .once()
+            .returning(|delete_splits_request| {
+                assert_eq!(delete_splits_request.split_ids, ["released-split"]);
+                Ok(EmptyResponse {})
+            });
+        let removal_info = delete_splits_marked_for_deletion(
+            index_uid,
+            0,
+            storage,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            Some(&legal_hold),
+            None,
+        )
+        .await;
+        assert_eq!(removal_info.removed_split_entries.len(), 1);
+        assert_eq!(
+            removal_info.removed_split_entries[0].split_id,
+            "released-split"
+        );
+        assert!(removal_info.failed_splits.is_empty());
+    }
+
     #[tokio::test]
     async fn test_delete_splits_from_storage_and_metastore_happy_path() {
         let storage = storage_for_test();
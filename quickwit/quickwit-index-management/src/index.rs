@@ -25,29 +25,35 @@ use futures_util::StreamExt;
 use itertools::Itertools;
 use quickwit_common::fs::{empty_dir, get_cache_directory_path};
 use quickwit_common::pretty::PrettySample;
+use quickwit_common::shared_consts::INDEX_MANIFEST_FILE_NAME;
+use quickwit_common::uri::Uri;
 use quickwit_config::{validate_identifier, IndexConfig, SourceConfig};
 use quickwit_indexing::check_source_connectivity;
 use quickwit_metastore::{
     AddSourceRequestExt, CreateIndexResponseExt, IndexMetadata, IndexMetadataResponseExt,
     ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
-    MetastoreServiceStreamSplitsExt, SplitInfo, SplitMetadata, SplitState,
+    MetastoreServiceStreamSplitsExt, SplitInfo, SplitMetadata, SplitState, StageSplitsRequestExt,
 };
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, EntityKind,
     IndexMetadataRequest, ListIndexesMetadataRequest, ListSplitsRequest,
     MarkSplitsForDeletionRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
-    ResetSourceCheckpointRequest,
+    PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_proto::{ServiceError, ServiceErrorCode};
 use quickwit_storage::{StorageResolver, StorageResolverError};
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::garbage_collection::{
     delete_splits_from_storage_and_metastore, run_garbage_collect, DeleteSplitsError,
     SplitRemovalInfo,
 };
+use crate::recovery::{read_stored_index, IndexRecoveryReport};
+
+/// Number of splits staged and published at once when recovering an index.
+const RECOVER_SPLITS_BATCH_SIZE: usize = 1_000;
 
 #[derive(Error, Debug)]
 pub enum IndexServiceError {
@@ -161,6 +167,11 @@ impl IndexService {
             .index_metadata(index_metadata_request)
             .await?
             .deserialize_index_metadata()?;
+        if !dry_run && index_metadata.index_config.legal_hold_opt.is_some() {
+            return Err(IndexServiceError::OperationNotAllowed(format!(
+                "index `{index_id}` is under legal hold and cannot be deleted"
+            )));
+        }
         let index_uid = index_metadata.index_uid.clone();
         let index_uri = index_metadata.into_index_config().index_uri.clone();
         let storage = self.storage_resolver.resolve(&index_uri).await?;
@@ -207,12 +218,15 @@ impl IndexService {
 
         let deleted_splits = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
-            storage,
+            storage.clone(),
             self.metastore.clone(),
             splits_metadata_to_delete,
             None,
         )
         .await?;
+        if let Err(error) = storage.delete(Path::new(INDEX_MANIFEST_FILE_NAME)).await {
+            warn!(index_uid=%index_uid, error=?error, "failed to delete index manifest");
+        }
         let delete_index_request = DeleteIndexRequest {
             index_uid: Some(index_uid),
         };
@@ -221,6 +235,95 @@ impl IndexService {
         Ok(deleted_splits)
     }
 
+    /// Recovers an index whose metadata is lost, for instance because the metastore was lost,
+    /// from the manifests stored in the index storage along with its splits.
+    ///
+    /// The index is created with the config and the sources of the index manifest, unless
+    /// `index_config_opt` is provided, and the splits are published. The splits merged into other
+    /// splits are skipped, as well as the splits without a manifest. The checkpoints of the
+    /// sources are not recovered.
+    pub async fn recover_index(
+        &mut self,
+        index_uri: &Uri,
+        index_config_opt: Option<IndexConfig>,
+        dry_run: bool,
+    ) -> Result<IndexRecoveryReport, IndexServiceError> {
+        if let Some(index_config) = &index_config_opt {
+            if index_config.index_uri != *index_uri {
+                return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+                    "the URI of the index config `{}` does not match the URI of the index to \
+                     recover `{index_uri}`",
+                    index_config.index_uri
+                )));
+            }
+        }
+        let storage = self.storage_resolver.resolve(index_uri).await?;
+        let stored_index = read_stored_index(&*storage, index_config_opt)
+            .await
+            .map_err(IndexServiceError::InvalidConfig)?;
+        let mut report = stored_index.report;
+
+        if dry_run {
+            return Ok(report);
+        }
+        let index_config_json = serde_utils::to_json_str(&stored_index.index_config)?;
+        let source_configs = stored_index.source_configs_opt.unwrap_or_else(|| {
+            vec![
+                SourceConfig::ingest_api_default(),
+                SourceConfig::ingest_v2(),
+                SourceConfig::cli(),
+            ]
+        });
+        let source_configs_json = source_configs
+            .iter()
+            .map(serde_utils::to_json_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        let create_index_request = CreateIndexRequest {
+            index_config_json,
+            source_configs_json,
+        };
+        let index_uid = self
+            .metastore
+            .create_index(create_index_request)
+            .await?
+            .index_uid()
+            .clone();
+
+        for splits_metadata_chunk in stored_index
+            .splits_metadata
+            .chunks(RECOVER_SPLITS_BATCH_SIZE)
+        {
+            let splits_metadata = splits_metadata_chunk.iter().cloned().map(|mut split| {
+                split.index_uid = index_uid.clone();
+                split
+            });
+            let stage_splits_request =
+                StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata)?;
+            self.metastore.stage_splits(stage_splits_request).await?;
+
+            let publish_splits_request = PublishSplitsRequest {
+                index_uid: Some(index_uid.clone()),
+                staged_split_ids: splits_metadata_chunk
+                    .iter()
+                    .map(|split| split.split_id.clone())
+                    .collect(),
+                replaced_split_ids: Vec::new(),
+                index_checkpoint_delta_json_opt: None,
+                publish_token_opt: None,
+            };
+            self.metastore
+                .publish_splits(publish_splits_request)
+                .await?;
+        }
+        info!(
+            index_uid=%index_uid,
+            num_splits=report.recovered_split_ids.len(),
+            "index successfully recovered"
+        );
+        report.index_uid_opt = Some(index_uid);
+        Ok(report)
+    }
+
     /// Deletes the indexes specified with `index_id_patterns`.
     /// This is a wrapper of delete_index, and support index delete with index pattern
     ///
@@ -356,6 +459,7 @@ impl IndexService {
             // deletion_grace_period of zero, so that a cli call directly deletes splits after
             // marking to be deleted.
             Duration::ZERO,
+            index_config.legal_hold_opt.as_ref(),
             dry_run,
             None,
         )
@@ -380,6 +484,11 @@ impl IndexService {
             .index_metadata(index_metadata_request)
             .await?
             .deserialize_index_metadata()?;
+        if index_metadata.index_config.legal_hold_opt.is_some() {
+            return Err(IndexServiceError::OperationNotAllowed(format!(
+                "index `{index_id}` is under legal hold and cannot be cleared"
+            )));
+        }
         let index_uid = index_metadata.index_uid.clone();
         let storage = self
             .storage_resolver
@@ -513,13 +622,17 @@ pub async fn validate_storage_uri(
 #[cfg(test)]
 mod tests {
 
+    use quickwit_common::shared_consts::SPLIT_MANIFEST_FILE_NAME;
     use quickwit_common::uri::Uri;
-    use quickwit_config::{IndexConfig, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID};
+    use quickwit_config::{
+        IndexConfig, LegalHold, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
+    };
     use quickwit_metastore::{
-        metastore_for_test, MetastoreServiceExt, SplitMetadata, StageSplitsRequestExt,
+        metastore_for_test, IndexManifest, MetastoreServiceExt, SplitManifest, SplitMetadata,
+        StageSplitsRequestExt,
     };
     use quickwit_proto::metastore::StageSplitsRequest;
-    use quickwit_storage::PutPayload;
+    use quickwit_storage::{PutPayload, SplitPayloadBuilder, Storage};
 
     use super::*;
 
@@ -614,8 +727,15 @@ mod tests {
         storage.put(split_path, payload).await.unwrap();
         assert!(storage.exists(split_path).await.unwrap());
 
+        let index_manifest_path = Path::new(INDEX_MANIFEST_FILE_NAME);
+        storage
+            .put(index_manifest_path, Box::new(b"{}".to_vec()))
+            .await
+            .unwrap();
+
         let split_infos = index_service.delete_index(index_id, false).await.unwrap();
         assert_eq!(split_infos.len(), 1);
+        assert!(!storage.exists(index_manifest_path).await.unwrap());
 
         assert!(!metastore.index_exists(index_id).await.unwrap());
         let splits = metastore
@@ -628,4 +748,157 @@ mod tests {
         assert!(splits.is_empty());
         assert!(!storage.exists(split_path).await.unwrap());
     }
+
+    async fn put_split_for_test(
+        storage: &dyn Storage,
+        split_metadata: SplitMetadata,
+        replaced_split_ids: &[&str],
+    ) {
+        let split_file = quickwit_common::split_file(&split_metadata.split_id);
+        let replaced_split_ids = replaced_split_ids
+            .iter()
+            .map(|split_id| split_id.to_string())
+            .collect();
+        let split_manifest = SplitManifest::new(split_metadata, replaced_split_ids);
+        let mut split_payload_builder = SplitPayloadBuilder::default();
+        split_payload_builder.add_payload(
+            SPLIT_MANIFEST_FILE_NAME.to_string(),
+            Box::new(split_manifest.serialize()),
+        );
+        let split_payload = split_payload_builder.finalize(&[]).unwrap();
+        storage
+            .put(Path::new(&split_file), Box::new(split_payload))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recover_index() {
+        let mut metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let index_uri = Uri::for_test("ram:///indexes/test-index");
+        let storage = storage_resolver.resolve(&index_uri).await.unwrap();
+        let mut index_service = IndexService::new(metastore.clone(), storage_resolver);
+
+        let error = index_service
+            .recover_index(&index_uri, None, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidConfig(_)));
+
+        let index_config = IndexConfig::for_test("test-index", index_uri.as_str());
+        let mut index_metadata = IndexMetadata::new(index_config);
+        index_metadata
+            .add_source(SourceConfig::ingest_api_default())
+            .unwrap();
+        IndexManifest::new(&index_metadata)
+            .write(&*storage)
+            .await
+            .unwrap();
+
+        for (split_id, num_docs, replaced_split_ids) in [
+            ("split-1", 10, &[][..]),
+            ("split-2", 20, &[][..]),
+            ("split-3", 30, &["split-0", "split-1"][..]),
+        ] {
+            let split_metadata = SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_metadata.index_uid.clone(),
+                num_docs,
+                ..Default::default()
+            };
+            put_split_for_test(&*storage, split_metadata, replaced_split_ids).await;
+        }
+        let legacy_split_payload = SplitPayloadBuilder::get_split_payload(&[], &[], &[]).unwrap();
+        storage
+            .put(Path::new("split-4.split"), Box::new(legacy_split_payload))
+            .await
+            .unwrap();
+
+        let report = index_service
+            .recover_index(&index_uri, None, true)
+            .await
+            .unwrap();
+        assert_eq!(report.index_id, "test-index");
+        assert!(report.index_uid_opt.is_none());
+        assert!(report.has_index_manifest);
+        assert_eq!(report.recovered_split_ids, ["split-2", "split-3"]);
+        assert_eq!(report.num_recovered_docs, 50);
+        assert_eq!(report.replaced_split_ids, ["split-1"]);
+        assert_eq!(report.split_ids_without_manifest, ["split-4"]);
+        assert!(!metastore.index_exists("test-index").await.unwrap());
+
+        let report = index_service
+            .recover_index(&index_uri, None, false)
+            .await
+            .unwrap();
+        let index_uid = report.index_uid_opt.unwrap();
+        assert_ne!(index_uid, index_metadata.index_uid);
+
+        let recovered_index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("test-index".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_eq!(recovered_index_metadata.index_uid, index_uid);
+        assert_eq!(recovered_index_metadata.sources.len(), 1);
+        assert!(recovered_index_metadata
+            .sources
+            .contains_key(INGEST_API_SOURCE_ID));
+
+        let mut splits = metastore
+            .list_splits(ListSplitsRequest::try_from_index_uid(index_uid.clone()).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        splits.sort_by(|left, right| left.split_id().cmp(right.split_id()));
+        assert_eq!(splits.len(), 2);
+        assert_eq!(splits[0].split_id(), "split-2");
+        assert_eq!(splits[1].split_id(), "split-3");
+
+        for split in &splits {
+            assert_eq!(split.split_state, SplitState::Published);
+            assert_eq!(split.split_metadata.index_uid, index_uid);
+            assert!(!split.split_metadata.footer_offsets.is_empty());
+        }
+        let error = index_service
+            .recover_index(&index_uri, None, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            IndexServiceError::Metastore(MetastoreError::AlreadyExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_clear_index_under_legal_hold() {
+        let mut metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut index_service = IndexService::new(metastore.clone(), storage_resolver);
+        let index_id = "test-index";
+        let index_uri = "ram://indexes/test-index";
+        let mut index_config = IndexConfig::for_test(index_id, index_uri);
+        index_config.legal_hold_opt = Some(LegalHold::default());
+        index_service
+            .create_index(index_config, false)
+            .await
+            .unwrap();
+
+        let error = index_service
+            .delete_index(index_id, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::OperationNotAllowed(_)));
+
+        let error = index_service.clear_index(index_id).await.unwrap_err();
+        assert!(matches!(error, IndexServiceError::OperationNotAllowed(_)));
+
+        // Dry runs do not delete anything and are allowed.
+        index_service.delete_index(index_id, true).await.unwrap();
+        assert!(metastore.index_exists(index_id).await.unwrap());
+    }
 }
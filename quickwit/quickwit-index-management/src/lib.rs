@@ -19,6 +19,8 @@
 
 mod garbage_collection;
 mod index;
+mod recovery;
 
 pub use garbage_collection::run_garbage_collect;
 pub use index::{clear_cache_directory, validate_storage_uri, IndexService, IndexServiceError};
+pub use recovery::IndexRecoveryReport;
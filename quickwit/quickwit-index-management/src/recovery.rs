@@ -0,0 +1,150 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Recovery of the indexes whose metadata is lost, from the manifests stored next to their
+//! splits. See [`SplitManifest`] and [`IndexManifest`].
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
+use futures::{stream, StreamExt, TryStreamExt};
+use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_metastore::{IndexManifest, SplitManifest, SplitMetadata};
+use quickwit_proto::types::IndexUid;
+use quickwit_storage::Storage;
+use tracing::info;
+
+/// Number of split manifests fetched concurrently.
+const FETCH_SPLIT_MANIFESTS_CONCURRENCY: usize = 10;
+
+/// Summary of the recovery of an index.
+#[derive(Debug)]
+pub struct IndexRecoveryReport {
+    /// ID of the recovered index.
+    pub index_id: String,
+    /// UID of the recovered index, `None` for a dry run.
+    pub index_uid_opt: Option<IndexUid>,
+    /// Whether the config of the index was read from the index manifest.
+    pub has_index_manifest: bool,
+    /// IDs of the recovered splits.
+    pub recovered_split_ids: Vec<String>,
+    /// Number of documents of the recovered splits.
+    pub num_recovered_docs: u64,
+    /// IDs of the splits merged into other splits but not garbage collected yet, which are not
+    /// recovered.
+    pub replaced_split_ids: Vec<String>,
+    /// IDs of the splits created before the split manifests were introduced, which cannot be
+    /// recovered.
+    pub split_ids_without_manifest: Vec<String>,
+}
+
+/// Index rebuilt from the files of its storage.
+pub(crate) struct StoredIndex {
+    pub index_config: IndexConfig,
+    /// Sources of the index, `None` if the storage does not hold an index manifest.
+    pub source_configs_opt: Option<Vec<SourceConfig>>,
+    pub splits_metadata: Vec<SplitMetadata>,
+    pub report: IndexRecoveryReport,
+}
+
+/// Reads the manifests of the index stored in `index_storage`. `index_config_opt` overrides the
+/// config of the index manifest and is required if the storage does not hold one.
+pub(crate) async fn read_stored_index(
+    index_storage: &dyn Storage,
+    index_config_opt: Option<IndexConfig>,
+) -> anyhow::Result<StoredIndex> {
+    let index_manifest_opt = IndexManifest::fetch(index_storage).await?;
+    let has_index_manifest = index_manifest_opt.is_some();
+
+    let index_config = match (index_config_opt, &index_manifest_opt) {
+        (Some(index_config), _) => index_config,
+        (None, Some(index_manifest)) => index_manifest.index_config.clone(),
+        (None, None) => anyhow::bail!(
+            "storage `{}` does not contain an index manifest, the index config must be provided",
+            index_storage.uri()
+        ),
+    };
+
+    let split_ids: Vec<String> = index_storage
+        .list_files(Path::new(""))
+        .await
+        .context("failed to list the files of the index storage")?
+        .iter()
+        .filter(|path| path.parent() == Some(Path::new("")))
+        .filter_map(|path| path.to_str()?.strip_suffix(".split"))
+        .map(ToString::to_string)
+        .collect();
+    info!(
+        index_id=%index_config.index_id,
+        num_splits=split_ids.len(),
+        "fetching split manifests"
+    );
+    let split_manifests: Vec<(String, Option<SplitManifest>)> = stream::iter(split_ids)
+        .map(|split_id| async move {
+            let split_manifest_opt = SplitManifest::fetch(index_storage, &split_id).await?;
+            anyhow::Ok((split_id, split_manifest_opt))
+        })
+        .buffer_unordered(FETCH_SPLIT_MANIFESTS_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let all_replaced_split_ids: HashSet<&str> = split_manifests
+        .iter()
+        .flat_map(|(_, split_manifest_opt)| split_manifest_opt)
+        .flat_map(|split_manifest| &split_manifest.replaced_split_ids)
+        .map(String::as_str)
+        .collect();
+
+    let mut splits_metadata = Vec::new();
+    let mut report = IndexRecoveryReport {
+        index_id: index_config.index_id.clone(),
+        index_uid_opt: None,
+        has_index_manifest,
+        recovered_split_ids: Vec::new(),
+        num_recovered_docs: 0,
+        replaced_split_ids: Vec::new(),
+        split_ids_without_manifest: Vec::new(),
+    };
+    for (split_id, split_manifest_opt) in &split_manifests {
+        let Some(split_manifest) = split_manifest_opt else {
+            report.split_ids_without_manifest.push(split_id.clone());
+            continue;
+        };
+        let split_metadata = &split_manifest.split_metadata;
+
+        if all_replaced_split_ids.contains(split_id.as_str()) {
+            report.replaced_split_ids.push(split_id.clone());
+        } else {
+            report.recovered_split_ids.push(split_id.clone());
+            report.num_recovered_docs += split_metadata.num_docs as u64;
+            splits_metadata.push(split_metadata.clone());
+        }
+    }
+    report.recovered_split_ids.sort();
+    report.replaced_split_ids.sort();
+    report.split_ids_without_manifest.sort();
+
+    Ok(StoredIndex {
+        index_config,
+        source_configs_opt: index_manifest_opt.map(|index_manifest| index_manifest.sources),
+        splits_metadata,
+        report,
+    })
+}
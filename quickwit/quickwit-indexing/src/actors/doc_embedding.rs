@@ -0,0 +1,371 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use quickwit_common::rate_limited_tracing::rate_limited_warn;
+use quickwit_config::{EmbeddingConfig, EmbeddingFailurePolicy, EmbeddingFieldConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use super::doc_processor::JsonDoc;
+
+/// Client of the external embedding service, abstracted away for testing.
+#[async_trait]
+pub(super) trait EmbedTextsClient: Send + Sync + 'static {
+    async fn embed_texts(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>>;
+}
+
+#[derive(Serialize)]
+struct EmbedTextsRequest {
+    inputs: Vec<String>,
+}
+
+/// Embedding services either return the list of embeddings directly or wrap it in an object.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EmbedTextsResponse {
+    Embeddings(Vec<Vec<f32>>),
+    Object { embeddings: Vec<Vec<f32>> },
+}
+
+/// Calls an embedding service over HTTP: the texts are posted as `{"inputs": [...]}` and the
+/// service returns one embedding per text, in the same order.
+struct HttpEmbedTextsClient {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+#[async_trait]
+impl EmbedTextsClient for HttpEmbedTextsClient {
+    async fn embed_texts(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>> {
+        let request = EmbedTextsRequest { inputs: texts };
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<EmbedTextsResponse>()
+            .await
+            .context("embedding service returned an invalid response")?;
+        let embeddings = match response {
+            EmbedTextsResponse::Embeddings(embeddings) => embeddings,
+            EmbedTextsResponse::Object { embeddings } => embeddings,
+        };
+        Ok(embeddings)
+    }
+}
+
+/// Outcome of the embedding of a batch of documents.
+#[derive(Default)]
+pub(super) struct EmbeddingOutcome {
+    /// Number of documents that could not be embedded and are indexed without embedding.
+    pub num_skipped_docs: usize,
+    /// Documents that could not be embedded and were removed from the batch.
+    pub dropped_docs: Vec<JsonDoc>,
+}
+
+/// Embedding stage of the doc processor. The values of the source fields are sent in batches of
+/// `max_batch_size` texts to the embedding service, with at most `max_concurrency` requests in
+/// flight, and the returned vectors are stored into the target fields. Documents without a string
+/// value for a source field are left untouched. The documents that could not be embedded are
+/// handled according to the failure policy.
+pub(super) struct DocEmbedder {
+    index_id: String,
+    source_id: String,
+    client: Arc<dyn EmbedTextsClient>,
+    fields: Vec<EmbeddingFieldConfig>,
+    max_batch_size: usize,
+    max_concurrency: usize,
+    timeout: Duration,
+    on_failure: EmbeddingFailurePolicy,
+}
+
+impl DocEmbedder {
+    pub fn try_from_embedding_config(
+        index_id: String,
+        source_id: String,
+        embedding_config: EmbeddingConfig,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(embedding_config.timeout())
+            .build()
+            .context("failed to build embedding service HTTP client")?;
+        let client = HttpEmbedTextsClient {
+            client,
+            endpoint: embedding_config.endpoint.clone(),
+        };
+        Ok(Self::new(
+            index_id,
+            source_id,
+            Arc::new(client),
+            embedding_config,
+        ))
+    }
+
+    fn new(
+        index_id: String,
+        source_id: String,
+        client: Arc<dyn EmbedTextsClient>,
+        embedding_config: EmbeddingConfig,
+    ) -> Self {
+        Self {
+            index_id,
+            source_id,
+            client,
+            max_batch_size: embedding_config.max_batch_size,
+            max_concurrency: embedding_config.max_concurrency,
+            timeout: embedding_config.timeout(),
+            on_failure: embedding_config.on_failure,
+            fields: embedding_config.fields,
+        }
+    }
+
+    /// Embeds the documents in place. Returns an error if some documents could not be embedded
+    /// and the failure policy is [`EmbeddingFailurePolicy::Fail`].
+    pub async fn embed_docs(
+        &self,
+        json_docs: &mut Vec<JsonDoc>,
+    ) -> anyhow::Result<EmbeddingOutcome> {
+        // Texts to embed along with the document and the field config they belong to.
+        let mut texts: Vec<String> = Vec::new();
+        let mut text_targets: Vec<(usize, &EmbeddingFieldConfig)> = Vec::new();
+
+        for (doc_idx, json_doc) in json_docs.iter().enumerate() {
+            for field_config in &self.fields {
+                if let Some(JsonValue::String(text)) =
+                    json_doc.json_obj.get(&field_config.source_field)
+                {
+                    texts.push(text.clone());
+                    text_targets.push((doc_idx, field_config));
+                }
+            }
+        }
+        if texts.is_empty() {
+            return Ok(EmbeddingOutcome::default());
+        }
+        let embedding_results: Vec<anyhow::Result<Vec<Vec<f32>>>> = stream::iter(
+            texts
+                .chunks(self.max_batch_size)
+                .map(|texts_chunk| self.embed_texts_chunk(texts_chunk.to_vec())),
+        )
+        .buffered(self.max_concurrency)
+        .collect()
+        .await;
+
+        let mut failed_doc_idxs: BTreeSet<usize> = BTreeSet::new();
+        let mut first_error_opt: Option<anyhow::Error> = None;
+
+        for (text_targets_chunk, embedding_result) in text_targets
+            .chunks(self.max_batch_size)
+            .zip(embedding_results)
+        {
+            match embedding_result {
+                Ok(embeddings) => {
+                    for ((doc_idx, field_config), embedding) in
+                        text_targets_chunk.iter().zip(embeddings)
+                    {
+                        let embedding_json = embedding.into_iter().map(JsonValue::from).collect();
+                        json_docs[*doc_idx].json_obj.insert(
+                            field_config.target_field.clone(),
+                            JsonValue::Array(embedding_json),
+                        );
+                    }
+                }
+                Err(error) => {
+                    failed_doc_idxs.extend(text_targets_chunk.iter().map(|(doc_idx, _)| doc_idx));
+                    first_error_opt.get_or_insert(error);
+                }
+            }
+        }
+        let Some(error) = first_error_opt else {
+            return Ok(EmbeddingOutcome::default());
+        };
+        let num_failed_docs = failed_doc_idxs.len();
+
+        if self.on_failure == EmbeddingFailurePolicy::Fail {
+            return Err(error.context(format!("failed to embed {num_failed_docs} documents")));
+        }
+        rate_limited_warn!(
+            limit_per_min = 10,
+            index_id = self.index_id,
+            source_id = self.source_id,
+            "failed to embed {num_failed_docs} documents ({:?}): {error:#}",
+            self.on_failure
+        );
+        if self.on_failure == EmbeddingFailurePolicy::Skip {
+            let outcome = EmbeddingOutcome {
+                num_skipped_docs: num_failed_docs,
+                dropped_docs: Vec::new(),
+            };
+            return Ok(outcome);
+        }
+        let mut dropped_docs = Vec::with_capacity(num_failed_docs);
+        let mut kept_docs = Vec::with_capacity(json_docs.len() - num_failed_docs);
+
+        for (doc_idx, json_doc) in json_docs.drain(..).enumerate() {
+            if failed_doc_idxs.contains(&doc_idx) {
+                dropped_docs.push(json_doc);
+            } else {
+                kept_docs.push(json_doc);
+            }
+        }
+        *json_docs = kept_docs;
+
+        let outcome = EmbeddingOutcome {
+            num_skipped_docs: 0,
+            dropped_docs,
+        };
+        Ok(outcome)
+    }
+
+    async fn embed_texts_chunk(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>> {
+        let num_texts = texts.len();
+        let embeddings = tokio::time::timeout(self.timeout, self.client.embed_texts(texts))
+            .await
+            .context("embedding request timed out")??;
+
+        if embeddings.len() != num_texts {
+            anyhow::bail!(
+                "embedding service returned {} embeddings, expected {num_texts}",
+                embeddings.len(),
+            );
+        }
+        Ok(embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Embeds a text into `[text length, 1.0]` and fails on texts starting with `fail`.
+    #[derive(Default)]
+    struct MockEmbedTextsClient {
+        num_calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbedTextsClient for MockEmbedTextsClient {
+        async fn embed_texts(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>> {
+            self.num_calls.fetch_add(1, Ordering::Relaxed);
+
+            if texts.iter().any(|text| text.starts_with("fail")) {
+                anyhow::bail!("embedding service unavailable");
+            }
+            let embeddings = texts
+                .iter()
+                .map(|text| vec![text.len() as f32, 1.0])
+                .collect();
+            Ok(embeddings)
+        }
+    }
+
+    fn json_docs_for_test(bodies: &[&str]) -> Vec<JsonDoc> {
+        bodies
+            .iter()
+            .map(|body| JsonDoc::try_from_json_value(json!({"body": body}), 10).unwrap())
+            .collect()
+    }
+
+    fn doc_embedder_for_test(
+        client: Arc<MockEmbedTextsClient>,
+        max_batch_size: usize,
+        on_failure: EmbeddingFailurePolicy,
+    ) -> DocEmbedder {
+        let embedding_config = EmbeddingConfig {
+            max_batch_size,
+            on_failure,
+            ..EmbeddingConfig::for_test("http://embedder:8080", "body", "body_embedding")
+        };
+        DocEmbedder::new(
+            "test-index".to_string(),
+            "test-source".to_string(),
+            client,
+            embedding_config,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_doc_embedder_embeds_docs_in_batches() {
+        let client = Arc::new(MockEmbedTextsClient::default());
+        let doc_embedder =
+            doc_embedder_for_test(client.clone(), 2, EmbeddingFailurePolicy::default());
+
+        let mut json_docs = json_docs_for_test(&["a", "bb", "ccc"]);
+        json_docs.push(JsonDoc::try_from_json_value(json!({"title": "no body"}), 10).unwrap());
+
+        let outcome = doc_embedder.embed_docs(&mut json_docs).await.unwrap();
+        assert_eq!(outcome.num_skipped_docs, 0);
+        assert!(outcome.dropped_docs.is_empty());
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 2);
+        assert_eq!(json_docs.len(), 4);
+        assert_eq!(json_docs[0].json_obj["body_embedding"], json!([1.0, 1.0]));
+        assert_eq!(json_docs[2].json_obj["body_embedding"], json!([3.0, 1.0]));
+        assert!(!json_docs[3].json_obj.contains_key("body_embedding"));
+    }
+
+    #[tokio::test]
+    async fn test_doc_embedder_failure_policies() {
+        let bodies = ["a", "fail", "ccc"];
+        {
+            let client = Arc::new(MockEmbedTextsClient::default());
+            let doc_embedder = doc_embedder_for_test(client, 1, EmbeddingFailurePolicy::Skip);
+
+            let mut json_docs = json_docs_for_test(&bodies);
+            let outcome = doc_embedder.embed_docs(&mut json_docs).await.unwrap();
+            assert_eq!(outcome.num_skipped_docs, 1);
+            assert!(outcome.dropped_docs.is_empty());
+            assert_eq!(json_docs.len(), 3);
+            assert!(!json_docs[1].json_obj.contains_key("body_embedding"));
+            assert!(json_docs[2].json_obj.contains_key("body_embedding"));
+        }
+        {
+            let client = Arc::new(MockEmbedTextsClient::default());
+            let doc_embedder = doc_embedder_for_test(client, 1, EmbeddingFailurePolicy::Drop);
+
+            let mut json_docs = json_docs_for_test(&bodies);
+            let outcome = doc_embedder.embed_docs(&mut json_docs).await.unwrap();
+            assert_eq!(outcome.num_skipped_docs, 0);
+            assert_eq!(outcome.dropped_docs.len(), 1);
+            assert_eq!(outcome.dropped_docs[0].json_obj["body"], "fail");
+            assert_eq!(json_docs.len(), 2);
+            assert_eq!(json_docs[1].json_obj["body"], "ccc");
+        }
+        {
+            let client = Arc::new(MockEmbedTextsClient::default());
+            let doc_embedder = doc_embedder_for_test(client, 1, EmbeddingFailurePolicy::Fail);
+
+            let mut json_docs = json_docs_for_test(&bodies);
+            let error = doc_embedder.embed_docs(&mut json_docs).await.err().unwrap();
+            assert_eq!(error.to_string(), "failed to embed 1 documents");
+        }
+    }
+}
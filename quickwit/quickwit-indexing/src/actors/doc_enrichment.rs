@@ -0,0 +1,365 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_common::rate_limited_tracing::rate_limited_warn;
+use quickwit_config::EnrichmentConfig;
+use quickwit_doc_mapper::JsonObject;
+use quickwit_proto::enrichment::document_enricher_client::DocumentEnricherClient;
+use quickwit_proto::enrichment::{EnrichDocsRequest, EnrichDocsResponse};
+use quickwit_proto::tonic::transport::{Channel, Endpoint};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use super::doc_processor::JsonDoc;
+
+/// Client of the external enrichment service, abstracted away for testing.
+#[async_trait]
+pub(super) trait EnrichDocsClient: Send + 'static {
+    async fn enrich_docs(
+        &mut self,
+        request: EnrichDocsRequest,
+    ) -> anyhow::Result<EnrichDocsResponse>;
+}
+
+#[async_trait]
+impl EnrichDocsClient for DocumentEnricherClient<Channel> {
+    async fn enrich_docs(
+        &mut self,
+        request: EnrichDocsRequest,
+    ) -> anyhow::Result<EnrichDocsResponse> {
+        let response = DocumentEnricherClient::enrich_docs(self, request).await?;
+        Ok(response.into_inner())
+    }
+}
+
+/// Enrichment stage of the doc processor. Documents are sent in batches to the enrichment service.
+/// When a request fails or times out, the documents of the batch pass through unchanged. After
+/// `failure_threshold` consecutive failures, the circuit opens and the enrichment service is not
+/// called for the duration of the cooldown.
+pub(super) struct DocEnricher {
+    index_id: String,
+    source_id: String,
+    client: Box<dyn EnrichDocsClient>,
+    max_batch_size: usize,
+    timeout: Duration,
+    failure_threshold: usize,
+    cooldown: Duration,
+    num_consecutive_failures: usize,
+    circuit_open_until_opt: Option<Instant>,
+    is_probing: bool,
+}
+
+impl DocEnricher {
+    pub fn try_from_enrichment_config(
+        index_id: String,
+        source_id: String,
+        enrichment_config: EnrichmentConfig,
+    ) -> anyhow::Result<Self> {
+        let channel = Endpoint::from_shared(enrichment_config.endpoint.clone())
+            .with_context(|| {
+                format!(
+                    "invalid enrichment endpoint `{}`",
+                    enrichment_config.endpoint
+                )
+            })?
+            .connect_timeout(enrichment_config.timeout())
+            .connect_lazy();
+        let client = DocumentEnricherClient::new(channel);
+        Ok(Self::new(
+            index_id,
+            source_id,
+            Box::new(client),
+            &enrichment_config,
+        ))
+    }
+
+    fn new(
+        index_id: String,
+        source_id: String,
+        client: Box<dyn EnrichDocsClient>,
+        enrichment_config: &EnrichmentConfig,
+    ) -> Self {
+        Self {
+            index_id,
+            source_id,
+            client,
+            max_batch_size: enrichment_config.max_batch_size,
+            timeout: enrichment_config.timeout(),
+            failure_threshold: enrichment_config.failure_threshold,
+            cooldown: enrichment_config.cooldown(),
+            num_consecutive_failures: 0,
+            circuit_open_until_opt: None,
+            is_probing: false,
+        }
+    }
+
+    /// Enriches the documents in place and returns the number of documents that passed through
+    /// unchanged because the enrichment service failed or was bypassed.
+    pub async fn enrich_docs(&mut self, json_docs: &mut [JsonDoc]) -> usize {
+        let mut num_passthrough_docs = 0;
+
+        for json_docs_chunk in json_docs.chunks_mut(self.max_batch_size) {
+            if self.is_circuit_open() {
+                num_passthrough_docs += json_docs_chunk.len();
+                continue;
+            }
+            match self.enrich_docs_chunk(json_docs_chunk).await {
+                Ok(()) => self.record_success(),
+                Err(error) => {
+                    rate_limited_warn!(
+                        limit_per_min = 10,
+                        index_id = self.index_id,
+                        source_id = self.source_id,
+                        "failed to enrich documents, passing them through: {error:#}"
+                    );
+                    self.record_failure();
+                    num_passthrough_docs += json_docs_chunk.len();
+                }
+            }
+        }
+        num_passthrough_docs
+    }
+
+    async fn enrich_docs_chunk(&mut self, json_docs: &mut [JsonDoc]) -> anyhow::Result<()> {
+        let docs = json_docs
+            .iter()
+            .map(|json_doc| serde_json::to_vec(&json_doc.json_obj).map(Bytes::from))
+            .collect::<Result<Vec<Bytes>, _>>()?;
+        let request = EnrichDocsRequest {
+            index_id: self.index_id.clone(),
+            source_id: self.source_id.clone(),
+            docs,
+        };
+        let response = tokio::time::timeout(self.timeout, self.client.enrich_docs(request))
+            .await
+            .context("enrichment request timed out")??;
+
+        if response.docs.len() != json_docs.len() {
+            anyhow::bail!(
+                "enrichment service returned {} documents, expected {}",
+                response.docs.len(),
+                json_docs.len()
+            );
+        }
+        let enriched_json_objs = response
+            .docs
+            .iter()
+            .map(|doc| serde_json::from_slice::<JsonObject>(doc))
+            .collect::<Result<Vec<JsonObject>, _>>()
+            .context("enrichment service returned an invalid document")?;
+
+        for (json_doc, enriched_json_obj) in json_docs.iter_mut().zip(enriched_json_objs) {
+            json_doc.json_obj = enriched_json_obj;
+        }
+        Ok(())
+    }
+
+    fn is_circuit_open(&mut self) -> bool {
+        let Some(circuit_open_until) = self.circuit_open_until_opt else {
+            return false;
+        };
+        if Instant::now() < circuit_open_until {
+            return true;
+        }
+        // The cooldown has elapsed: the next request probes the enrichment service. If it fails,
+        // the circuit opens again right away.
+        self.circuit_open_until_opt = None;
+        self.is_probing = true;
+        false
+    }
+
+    fn record_success(&mut self) {
+        if self.is_probing {
+            info!(
+                index_id=%self.index_id,
+                source_id=%self.source_id,
+                "enrichment service recovered"
+            );
+            self.is_probing = false;
+        }
+        self.num_consecutive_failures = 0;
+    }
+
+    fn record_failure(&mut self) {
+        self.num_consecutive_failures += 1;
+
+        if self.is_probing || self.num_consecutive_failures >= self.failure_threshold {
+            warn!(
+                index_id=%self.index_id,
+                source_id=%self.source_id,
+                "bypassing enrichment service for {:?} after {} consecutive failures",
+                self.cooldown,
+                self.num_consecutive_failures
+            );
+            self.circuit_open_until_opt = Some(Instant::now() + self.cooldown);
+            self.is_probing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockEnrichDocsClient {
+        num_calls: Arc<AtomicUsize>,
+        fail: Arc<AtomicBool>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl EnrichDocsClient for MockEnrichDocsClient {
+        async fn enrich_docs(
+            &mut self,
+            request: EnrichDocsRequest,
+        ) -> anyhow::Result<EnrichDocsResponse> {
+            self.num_calls.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(self.delay).await;
+
+            if self.fail.load(Ordering::Relaxed) {
+                anyhow::bail!("enrichment service unavailable");
+            }
+            let docs = request
+                .docs
+                .iter()
+                .map(|doc| {
+                    let mut json_obj: JsonObject = serde_json::from_slice(doc).unwrap();
+                    json_obj.insert("enriched".to_string(), JsonValue::Bool(true));
+                    Bytes::from(serde_json::to_vec(&json_obj).unwrap())
+                })
+                .collect();
+            Ok(EnrichDocsResponse { docs })
+        }
+    }
+
+    fn json_docs_for_test(num_docs: usize) -> Vec<JsonDoc> {
+        (0..num_docs)
+            .map(|doc_id| {
+                let json_value = json!({"doc_id": doc_id});
+                JsonDoc::try_from_json_value(json_value, 10).unwrap()
+            })
+            .collect()
+    }
+
+    fn is_enriched(json_doc: &JsonDoc) -> bool {
+        json_doc.json_obj.get("enriched") == Some(&JsonValue::Bool(true))
+    }
+
+    fn doc_enricher_for_test(
+        client: MockEnrichDocsClient,
+        enrichment_config: EnrichmentConfig,
+    ) -> DocEnricher {
+        DocEnricher::new(
+            "test-index".to_string(),
+            "test-source".to_string(),
+            Box::new(client),
+            &enrichment_config,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_doc_enricher_enriches_docs_in_batches() {
+        let client = MockEnrichDocsClient::default();
+        let enrichment_config = EnrichmentConfig {
+            max_batch_size: 2,
+            ..EnrichmentConfig::for_test("http://enricher:50051")
+        };
+        let mut doc_enricher = doc_enricher_for_test(client.clone(), enrichment_config);
+
+        let mut json_docs = json_docs_for_test(3);
+        let num_passthrough_docs = doc_enricher.enrich_docs(&mut json_docs).await;
+        assert_eq!(num_passthrough_docs, 0);
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 2);
+        assert!(json_docs.iter().all(is_enriched));
+        assert_eq!(json_docs[2].json_obj["doc_id"], 2);
+        assert_eq!(json_docs[2].num_bytes, 10);
+    }
+
+    #[tokio::test]
+    async fn test_doc_enricher_passes_docs_through_on_timeout() {
+        let client = MockEnrichDocsClient {
+            delay: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let enrichment_config = EnrichmentConfig {
+            timeout_millis: 10,
+            ..EnrichmentConfig::for_test("http://enricher:50051")
+        };
+        let mut doc_enricher = doc_enricher_for_test(client, enrichment_config);
+
+        let mut json_docs = json_docs_for_test(2);
+        let num_passthrough_docs = doc_enricher.enrich_docs(&mut json_docs).await;
+        assert_eq!(num_passthrough_docs, 2);
+        assert!(!json_docs.iter().any(is_enriched));
+        assert_eq!(doc_enricher.num_consecutive_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_doc_enricher_circuit_breaker() {
+        let client = MockEnrichDocsClient::default();
+        client.fail.store(true, Ordering::Relaxed);
+
+        let enrichment_config = EnrichmentConfig {
+            max_batch_size: 1,
+            failure_threshold: 2,
+            ..EnrichmentConfig::for_test("http://enricher:50051")
+        };
+        let mut doc_enricher = doc_enricher_for_test(client.clone(), enrichment_config);
+        doc_enricher.cooldown = Duration::from_millis(50);
+
+        let mut json_docs = json_docs_for_test(4);
+        let num_passthrough_docs = doc_enricher.enrich_docs(&mut json_docs).await;
+        assert_eq!(num_passthrough_docs, 4);
+        assert!(!json_docs.iter().any(is_enriched));
+        // The circuit opens after two failures and the remaining docs bypass the service.
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 2);
+        assert!(doc_enricher.circuit_open_until_opt.is_some());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // The probe fails: the circuit opens again right away.
+        let mut json_docs = json_docs_for_test(2);
+        let num_passthrough_docs = doc_enricher.enrich_docs(&mut json_docs).await;
+        assert_eq!(num_passthrough_docs, 2);
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 3);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        client.fail.store(false, Ordering::Relaxed);
+
+        // The probe succeeds: the circuit closes.
+        let mut json_docs = json_docs_for_test(2);
+        let num_passthrough_docs = doc_enricher.enrich_docs(&mut json_docs).await;
+        assert_eq!(num_passthrough_docs, 0);
+        assert!(json_docs.iter().all(is_enriched));
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 5);
+        assert_eq!(doc_enricher.num_consecutive_failures, 0);
+        assert!(!doc_enricher.is_probing);
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::bail;
+use quickwit_config::WasmPluginConfig;
+use quickwit_doc_mapper::JsonObject;
+use quickwit_wasm::{load_wasm_plugin, WasmPluginError, WasmPluginInstance};
+
+/// Name of the function transforming the documents exported by the WASM plugins of the sources.
+const TRANSFORM_DOC_FUNCTION_NAME: &str = "transform_doc";
+
+/// Transforms the documents of a source with a WASM plugin. The plugin receives each document as
+/// a JSON object and returns the transformed object, or no output to drop the document.
+pub(super) struct DocPlugin {
+    plugin_instance: WasmPluginInstance,
+}
+
+impl DocPlugin {
+    pub fn try_from_wasm_plugin_config(
+        wasm_plugin_config: &WasmPluginConfig,
+    ) -> anyhow::Result<Self> {
+        let plugin = load_wasm_plugin(wasm_plugin_config)?;
+
+        if !plugin.exports_function(TRANSFORM_DOC_FUNCTION_NAME) {
+            bail!(
+                "WASM plugin `{}` does not export function `{TRANSFORM_DOC_FUNCTION_NAME}`",
+                wasm_plugin_config.path
+            );
+        }
+        let plugin_instance = plugin.instantiate()?;
+        Ok(Self { plugin_instance })
+    }
+
+    /// Returns the transformed document, or `None` if the plugin dropped it.
+    pub fn transform_doc(
+        &mut self,
+        json_obj: &JsonObject,
+    ) -> Result<Option<JsonObject>, WasmPluginError> {
+        self.plugin_instance
+            .call_json(TRANSFORM_DOC_FUNCTION_NAME, json_obj)
+    }
+}
@@ -17,22 +17,28 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
 use std::string::FromUtf8Error;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use anyhow::{bail, Context};
 use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
 use bytes::Bytes;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::rate_limited_tracing::rate_limited_warn;
 use quickwit_common::runtimes::RuntimeType;
-use quickwit_config::{SourceInputFormat, TransformConfig};
-use quickwit_doc_mapper::{DocMapper, DocParsingError, JsonObject};
+use quickwit_config::{
+    EmbeddingConfig, EnrichmentConfig, IngestMirrorConfig, SourceInputFormat, TransformConfig,
+};
+use quickwit_doc_mapper::{DocMapper, DocParsingError, DocParsingStats, JsonObject};
+use quickwit_ingest::{CommitType, DocBatchBuilder, IngestApiService, IngestRequest};
 use quickwit_opentelemetry::otlp::{
     parse_otlp_logs_json, parse_otlp_logs_protobuf, parse_otlp_spans_json,
     parse_otlp_spans_protobuf, JsonLogIterator, JsonSpanIterator, OtlpLogsError, OtlpTracesError,
 };
+use quickwit_wasm::WasmPluginError;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 use tantivy::schema::{Field, Value};
@@ -40,6 +46,11 @@ use tantivy::{DateTime, TantivyDocument};
 use thiserror::Error;
 use tokio::runtime::Handle;
 
+use super::doc_embedding::DocEmbedder;
+use super::doc_enrichment::DocEnricher;
+use super::doc_plugin::DocPlugin;
+use super::ingest_mirror::IngestMirror;
+use super::json_schema_validation::{JsonSchemaRejection, JsonSchemaValidator};
 #[cfg(feature = "vrl")]
 use super::vrl_processing::*;
 use crate::actors::Indexer;
@@ -50,8 +61,8 @@ use crate::models::{
 const PLAIN_TEXT: &str = "plain_text";
 
 pub(super) struct JsonDoc {
-    json_obj: JsonObject,
-    num_bytes: usize,
+    pub(super) json_obj: JsonObject,
+    pub(super) num_bytes: usize,
 }
 
 impl JsonDoc {
@@ -79,6 +90,13 @@ impl JsonDoc {
         let json_value = serde_json::to_value(vrl_doc.vrl_value)?;
         Self::try_from_json_value(json_value, vrl_doc.num_bytes)
     }
+
+    /// Serializes the document back to JSON.
+    pub fn to_payload(&self) -> Bytes {
+        let payload =
+            serde_json::to_vec(&self.json_obj).expect("JSON object should serialize to JSON");
+        Bytes::from(payload)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -94,6 +112,30 @@ pub enum DocProcessorError {
     #[cfg(feature = "vrl")]
     #[error("VRL transform error: {0}")]
     Transform(VrlTerminate),
+    #[error("embedding error: {0}")]
+    Embedding(String),
+    #[error("JSON Schema error: {0}")]
+    JsonSchema(JsonSchemaRejection),
+    #[error("plugin error: {0}")]
+    Plugin(WasmPluginError),
+}
+
+impl DocProcessorError {
+    /// Label of the error, used in metrics and in the documents written to the dead-letter index.
+    fn kind(&self) -> &'static str {
+        match self {
+            DocProcessorError::DocMapperParsing(_) => "doc_mapper_error",
+            DocProcessorError::JsonParsing(_) => "json_parse_error",
+            DocProcessorError::OltpLogsParsing(_) | DocProcessorError::OltpTracesParsing(_) => {
+                "otlp_parse_error"
+            }
+            #[cfg(feature = "vrl")]
+            DocProcessorError::Transform(_) => "transform_error",
+            DocProcessorError::Embedding(_) => "embedding_error",
+            DocProcessorError::JsonSchema(_) => "json_schema_error",
+            DocProcessorError::Plugin(_) => "plugin_error",
+        }
+    }
 }
 
 impl From<OtlpLogsError> for DocProcessorError {
@@ -108,6 +150,12 @@ impl From<OtlpTracesError> for DocProcessorError {
     }
 }
 
+impl From<JsonSchemaRejection> for DocProcessorError {
+    fn from(rejection: JsonSchemaRejection) -> Self {
+        Self::JsonSchema(rejection)
+    }
+}
+
 impl From<DocParsingError> for DocProcessorError {
     fn from(error: DocParsingError) -> Self {
         Self::DocMapperParsing(error)
@@ -197,14 +245,30 @@ fn parse_raw_doc(
     raw_doc: Bytes,
     num_bytes: usize,
     vrl_program_opt: Option<&mut VrlProgram>,
+    routed_docs: &mut RoutedDocs,
+    counters: &DocProcessorCounters,
 ) -> JsonDocIterator {
     let Some(vrl_program) = vrl_program_opt else {
         return try_into_json_docs(input_format, raw_doc, num_bytes);
     };
-    let json_doc_result = try_into_vrl_doc(input_format, raw_doc, num_bytes)
-        .and_then(|vrl_doc| vrl_program.transform_doc(vrl_doc))
-        .and_then(JsonDoc::try_from_vrl_doc);
-
+    let vrl_outcome_result = try_into_vrl_doc(input_format, raw_doc, num_bytes)
+        .and_then(|vrl_doc| vrl_program.transform_doc(vrl_doc));
+
+    let json_doc_result = match vrl_outcome_result {
+        Ok(VrlOutcome::Index(vrl_doc)) => JsonDoc::try_from_vrl_doc(vrl_doc),
+        Ok(VrlOutcome::Drop) => {
+            counters.record_dropped(num_bytes as u64);
+            return JsonDocIterator::One(None);
+        }
+        Ok(VrlOutcome::Route { index_id, vrl_doc }) => match JsonDoc::try_from_vrl_doc(vrl_doc) {
+            Ok(json_doc) => {
+                routed_docs.push(index_id, json_doc);
+                return JsonDocIterator::One(None);
+            }
+            Err(error) => Err(error),
+        },
+        Err(error) => Err(error),
+    };
     JsonDocIterator::from(json_doc_result)
 }
 
@@ -214,12 +278,192 @@ fn parse_raw_doc(
     raw_doc: Bytes,
     num_bytes: usize,
     _vrl_program_opt: Option<&mut VrlProgram>,
+    _routed_docs: &mut RoutedDocs,
+    _counters: &DocProcessorCounters,
 ) -> JsonDocIterator {
     try_into_json_docs(input_format, raw_doc, num_bytes)
 }
 
+/// What the transform of a source does to a document.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformDryRunAction {
+    Index,
+    Drop,
+    Route,
+    Error,
+}
+
+/// Result of running a sample document through the transform of a source.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct TransformDryRunResult {
+    pub action: TransformDryRunAction,
+    /// Index in which the document would be indexed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_id: Option<String>,
+    /// Transformed document.
+    #[schema(value_type = Option<Object>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<JsonObject>,
+    /// Names of the stages applied to the document, in order.
+    pub applied_stages: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Runs a sample document through a transform without indexing it. Errors raised while
+/// transforming the document are reported in the result.
+#[cfg(feature = "vrl")]
+pub fn dry_run_transform(
+    index_id: &str,
+    transform_config: TransformConfig,
+    input_format: SourceInputFormat,
+    raw_doc: Bytes,
+) -> anyhow::Result<TransformDryRunResult> {
+    let mut vrl_program = VrlProgram::try_from_transform_config(transform_config)?;
+    let num_bytes = raw_doc.len();
+    let mut applied_stages = Vec::new();
+
+    let vrl_outcome_result =
+        try_into_vrl_doc(input_format, raw_doc, num_bytes).and_then(|vrl_doc| {
+            vrl_program.transform_doc_with_trace(vrl_doc, Some(&mut applied_stages))
+        });
+    let (action, index_id_opt, json_doc_result) = match vrl_outcome_result {
+        Ok(VrlOutcome::Index(vrl_doc)) => (
+            TransformDryRunAction::Index,
+            Some(index_id.to_string()),
+            JsonDoc::try_from_vrl_doc(vrl_doc).map(Some),
+        ),
+        Ok(VrlOutcome::Drop) => (TransformDryRunAction::Drop, None, Ok(None)),
+        Ok(VrlOutcome::Route { index_id, vrl_doc }) => (
+            TransformDryRunAction::Route,
+            Some(index_id),
+            JsonDoc::try_from_vrl_doc(vrl_doc).map(Some),
+        ),
+        Err(error) => (TransformDryRunAction::Error, None, Err(error)),
+    };
+    let dry_run_result = match json_doc_result {
+        Ok(json_doc_opt) => TransformDryRunResult {
+            action,
+            index_id: index_id_opt,
+            doc: json_doc_opt.map(|json_doc| json_doc.json_obj),
+            applied_stages,
+            error: None,
+        },
+        Err(error) => TransformDryRunResult {
+            action: TransformDryRunAction::Error,
+            index_id: None,
+            doc: None,
+            applied_stages,
+            error: Some(error.to_string()),
+        },
+    };
+    Ok(dry_run_result)
+}
+
+#[cfg(not(feature = "vrl"))]
+pub fn dry_run_transform(
+    _index_id: &str,
+    _transform_config: TransformConfig,
+    _input_format: SourceInputFormat,
+    _raw_doc: Bytes,
+) -> anyhow::Result<TransformDryRunResult> {
+    bail!("VRL is not enabled: please recompile with the `vrl` feature")
+}
+
+/// Buffers the documents that transform stages send to other indexes until they are forwarded to
+/// the ingest API.
+#[derive(Default)]
+struct RoutedDocs {
+    doc_batch_builders: HashMap<String, DocBatchBuilder>,
+    num_docs: u64,
+    num_bytes: u64,
+}
+
+impl RoutedDocs {
+    #[cfg(feature = "vrl")]
+    fn push(&mut self, index_id: String, json_doc: JsonDoc) {
+        self.push_payload(index_id, json_doc.to_payload(), json_doc.num_bytes as u64);
+    }
+
+    fn push_payload(&mut self, index_id: String, payload: Bytes, num_bytes: u64) {
+        let doc_batch_builder = self
+            .doc_batch_builders
+            .entry(index_id)
+            .or_insert_with_key(|index_id| DocBatchBuilder::new(index_id.clone()));
+        doc_batch_builder.ingest_doc(payload);
+
+        self.num_docs += 1;
+        self.num_bytes += num_bytes;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.doc_batch_builders.is_empty()
+    }
+
+    /// Drains the buffered documents into an ingest request. Returns the request along with the
+    /// number of documents and bytes it contains.
+    fn take_ingest_request(&mut self) -> (IngestRequest, u64, u64) {
+        let doc_batches = self
+            .doc_batch_builders
+            .drain()
+            .map(|(_, doc_batch_builder)| doc_batch_builder.build())
+            .collect();
+        let ingest_request = IngestRequest {
+            doc_batches,
+            commit: CommitType::Auto.into(),
+        };
+        let num_docs = std::mem::take(&mut self.num_docs);
+        let num_bytes = std::mem::take(&mut self.num_bytes);
+        (ingest_request, num_docs, num_bytes)
+    }
+}
+
+/// Document written to the dead-letter index for each invalid document.
+#[derive(Serialize)]
+struct DeadLetterDoc<'a> {
+    /// Unix timestamp, in seconds, of the rejection.
+    timestamp: i64,
+    index_id: &'a str,
+    source_id: &'a str,
+    error_kind: &'static str,
+    error: String,
+    /// Original payload of the document, when it is valid UTF-8, so that it can be replayed as
+    /// is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<&'a str>,
+    /// Base64-encoded original payload of the document, when it is not valid UTF-8, for
+    /// instance for OTLP Protobuf payloads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload_base64: Option<String>,
+}
+
+impl<'a> DeadLetterDoc<'a> {
+    fn new(
+        index_id: &'a str,
+        source_id: &'a str,
+        error: &DocProcessorError,
+        payload: &'a [u8],
+    ) -> Self {
+        let (payload, payload_base64) = match std::str::from_utf8(payload) {
+            Ok(payload_str) => (Some(payload_str), None),
+            Err(_) => (None, Some(BASE64_STANDARD.encode(payload))),
+        };
+        Self {
+            timestamp: time::OffsetDateTime::now_utc().unix_timestamp(),
+            index_id,
+            source_id,
+            error_kind: error.kind(),
+            error: error.to_string(),
+            payload,
+            payload_base64,
+        }
+    }
+}
+
 enum JsonDocIterator {
     One(Option<Result<JsonDoc, DocProcessorError>>),
+    Many(std::vec::IntoIter<Result<JsonDoc, DocProcessorError>>),
     Logs(JsonLogIterator),
     Spans(JsonSpanIterator),
 }
@@ -230,6 +474,7 @@ impl Iterator for JsonDocIterator {
     fn next(&mut self) -> Option<Self::Item> {
         match self {
             Self::One(opt) => opt.take(),
+            Self::Many(json_docs) => json_docs.next(),
             Self::Logs(logs) => logs
                 .next()
                 .map(|(json_value, num_bytes)| JsonDoc::try_from_json_value(json_value, num_bytes)),
@@ -271,19 +516,53 @@ impl From<Result<JsonSpanIterator, OtlpTracesError>> for JsonDocIterator {
 
 #[derive(Debug, Serialize)]
 pub struct DocProcessorCounters {
-    index_id: String,
-    source_id: String,
+    pub(super) index_id: String,
+    pub(super) source_id: String,
     /// Overall number of documents received, partitioned
     /// into 4 categories:
     /// - number of docs that could not be parsed.
     /// - number of docs that could not be transformed.
+    /// - number of docs that could not be embedded and were dropped.
+    /// - number of docs that did not match the JSON Schema of the source.
+    /// - number of docs that the WASM plugin of the source failed to transform.
     /// - number of docs for which the doc mapper returnd an error.
     /// - number of valid docs.
     pub num_doc_parse_errors: AtomicU64,
     pub num_transform_errors: AtomicU64,
     pub num_oltp_parse_errors: AtomicU64,
+    pub num_embedding_errors: AtomicU64,
+    pub num_json_schema_errors: AtomicU64,
+    pub num_plugin_errors: AtomicU64,
     pub num_valid_docs: AtomicU64,
 
+    /// Number of values of valid docs that were coerced into the type of their field.
+    pub num_coerced_values: AtomicU64,
+    /// Number of missing or `null` values of valid docs that were replaced by the default value
+    /// of their field.
+    pub num_defaulted_values: AtomicU64,
+
+    /// Number of docs that went through the enrichment stage unchanged because the enrichment
+    /// service failed or was bypassed.
+    pub num_enrichment_passthrough_docs: AtomicU64,
+
+    /// Number of docs indexed without embedding because the embedding service failed.
+    pub num_embedding_skipped_docs: AtomicU64,
+
+    /// Number of docs dropped by a transform stage.
+    pub num_dropped_docs: AtomicU64,
+    /// Number of docs sent to another index by a transform stage.
+    pub num_routed_docs: AtomicU64,
+
+    /// Number of invalid docs written to the dead-letter index. These docs are also accounted
+    /// for in the error counters above.
+    pub num_dead_letter_docs: AtomicU64,
+
+    /// Number of valid docs written to the ingest mirror target index.
+    pub num_mirrored_docs: AtomicU64,
+    /// Number of valid docs sampled for the ingest mirror but dropped, because the mirror was
+    /// lagging behind or failed to write them.
+    pub num_mirror_dropped_docs: AtomicU64,
+
     /// Number of bytes that went through the indexer
     /// during its entire lifetime.
     ///
@@ -299,7 +578,19 @@ impl DocProcessorCounters {
             num_doc_parse_errors: Default::default(),
             num_transform_errors: Default::default(),
             num_oltp_parse_errors: Default::default(),
+            num_embedding_errors: Default::default(),
+            num_json_schema_errors: Default::default(),
+            num_plugin_errors: Default::default(),
             num_valid_docs: Default::default(),
+            num_coerced_values: Default::default(),
+            num_defaulted_values: Default::default(),
+            num_enrichment_passthrough_docs: Default::default(),
+            num_embedding_skipped_docs: Default::default(),
+            num_dropped_docs: Default::default(),
+            num_routed_docs: Default::default(),
+            num_dead_letter_docs: Default::default(),
+            num_mirrored_docs: Default::default(),
+            num_mirror_dropped_docs: Default::default(),
             num_bytes_total: Default::default(),
         }
     }
@@ -307,9 +598,14 @@ impl DocProcessorCounters {
     /// Returns the overall number of docs that went through the indexer (valid or not).
     pub fn num_processed_docs(&self) -> u64 {
         self.num_valid_docs.load(Ordering::Relaxed)
+            + self.num_dropped_docs.load(Ordering::Relaxed)
+            + self.num_routed_docs.load(Ordering::Relaxed)
             + self.num_doc_parse_errors.load(Ordering::Relaxed)
             + self.num_oltp_parse_errors.load(Ordering::Relaxed)
             + self.num_transform_errors.load(Ordering::Relaxed)
+            + self.num_embedding_errors.load(Ordering::Relaxed)
+            + self.num_json_schema_errors.load(Ordering::Relaxed)
+            + self.num_plugin_errors.load(Ordering::Relaxed)
     }
 
     /// Returns the overall number of docs that were sent to the indexer but were invalid.
@@ -319,6 +615,65 @@ impl DocProcessorCounters {
         self.num_doc_parse_errors.load(Ordering::Relaxed)
             + self.num_oltp_parse_errors.load(Ordering::Relaxed)
             + self.num_transform_errors.load(Ordering::Relaxed)
+            + self.num_embedding_errors.load(Ordering::Relaxed)
+            + self.num_json_schema_errors.load(Ordering::Relaxed)
+            + self.num_plugin_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn record_enrichment_passthrough(&self, num_docs: u64) {
+        self.num_enrichment_passthrough_docs
+            .fetch_add(num_docs, Ordering::Relaxed);
+    }
+
+    pub fn record_embedding_skipped(&self, num_docs: u64) {
+        self.num_embedding_skipped_docs
+            .fetch_add(num_docs, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped(&self, num_bytes: u64) {
+        self.num_dropped_docs.fetch_add(1, Ordering::Relaxed);
+        self.record_processed(1, num_bytes, "dropped");
+    }
+
+    pub fn record_routed(&self, num_docs: u64, num_bytes: u64) {
+        self.num_routed_docs.fetch_add(num_docs, Ordering::Relaxed);
+        self.record_processed(num_docs, num_bytes, "routed");
+    }
+
+    /// Records the docs that transform stages failed to send to another index as transform
+    /// errors.
+    pub fn record_route_errors(&self, num_docs: u64, num_bytes: u64) {
+        self.num_transform_errors
+            .fetch_add(num_docs, Ordering::Relaxed);
+        self.record_processed(num_docs, num_bytes, "route_error");
+    }
+
+    pub fn record_dead_letter(&self, num_docs: u64) {
+        self.num_dead_letter_docs
+            .fetch_add(num_docs, Ordering::Relaxed);
+    }
+
+    pub fn record_mirrored(&self, num_docs: u64) {
+        self.num_mirrored_docs
+            .fetch_add(num_docs, Ordering::Relaxed);
+    }
+
+    pub fn record_mirror_dropped(&self, num_docs: u64) {
+        self.num_mirror_dropped_docs
+            .fetch_add(num_docs, Ordering::Relaxed);
+    }
+
+    fn record_processed(&self, num_docs: u64, num_bytes: u64, label: &str) {
+        self.num_bytes_total.fetch_add(num_bytes, Ordering::Relaxed);
+
+        crate::metrics::INDEXER_METRICS
+            .processed_docs_total
+            .with_label_values([&self.index_id, label])
+            .inc_by(num_docs);
+        crate::metrics::INDEXER_METRICS
+            .processed_bytes
+            .with_label_values([&self.index_id, label])
+            .inc_by(num_bytes);
     }
 
     pub fn record_valid(&self, num_bytes: u64) {
@@ -335,26 +690,47 @@ impl DocProcessorCounters {
             .inc_by(num_bytes);
     }
 
+    pub fn record_doc_parsing_stats(&self, doc_parsing_stats: &DocParsingStats) {
+        if doc_parsing_stats.num_coerced_values > 0 {
+            self.num_coerced_values
+                .fetch_add(doc_parsing_stats.num_coerced_values, Ordering::Relaxed);
+            crate::metrics::INDEXER_METRICS
+                .coerced_values_total
+                .with_label_values([&self.index_id])
+                .inc_by(doc_parsing_stats.num_coerced_values);
+        }
+        if doc_parsing_stats.num_defaulted_values > 0 {
+            self.num_defaulted_values
+                .fetch_add(doc_parsing_stats.num_defaulted_values, Ordering::Relaxed);
+            crate::metrics::INDEXER_METRICS
+                .defaulted_values_total
+                .with_label_values([&self.index_id])
+                .inc_by(doc_parsing_stats.num_defaulted_values);
+        }
+    }
+
     pub fn record_error(&self, error: DocProcessorError, num_bytes: u64) {
-        let label = match error {
-            DocProcessorError::DocMapperParsing(_) => {
-                self.num_doc_parse_errors.fetch_add(1, Ordering::Relaxed);
-                "doc_mapper_error"
-            }
-            DocProcessorError::JsonParsing(_) => {
-                self.num_doc_parse_errors.fetch_add(1, Ordering::Relaxed);
-                "json_parse_error"
+        let label = error.kind();
+        let error_counter = match error {
+            DocProcessorError::DocMapperParsing(_) | DocProcessorError::JsonParsing(_) => {
+                &self.num_doc_parse_errors
             }
             DocProcessorError::OltpLogsParsing(_) | DocProcessorError::OltpTracesParsing(_) => {
-                self.num_oltp_parse_errors.fetch_add(1, Ordering::Relaxed);
-                "otlp_parse_error"
+                &self.num_oltp_parse_errors
             }
             #[cfg(feature = "vrl")]
-            DocProcessorError::Transform(_) => {
-                self.num_transform_errors.fetch_add(1, Ordering::Relaxed);
-                "transform_error"
+            DocProcessorError::Transform(_) => &self.num_transform_errors,
+            DocProcessorError::Embedding(_) => &self.num_embedding_errors,
+            DocProcessorError::JsonSchema(rejection) => {
+                crate::metrics::INDEXER_METRICS
+                    .json_schema_rejected_docs_total
+                    .with_label_values([&self.index_id, &self.source_id, &rejection.version_label])
+                    .inc();
+                &self.num_json_schema_errors
             }
+            DocProcessorError::Plugin(_) => &self.num_plugin_errors,
         };
+        error_counter.fetch_add(1, Ordering::Relaxed);
         crate::metrics::INDEXER_METRICS
             .processed_docs_total
             .with_label_values([&self.index_id, label])
@@ -378,6 +754,19 @@ pub struct DocProcessor {
     #[cfg(feature = "vrl")]
     transform_opt: Option<VrlProgram>,
     input_format: SourceInputFormat,
+    doc_enricher_opt: Option<DocEnricher>,
+    doc_embedder_opt: Option<DocEmbedder>,
+    json_schema_validator_opt: Option<JsonSchemaValidator>,
+    doc_plugin_opt: Option<DocPlugin>,
+    /// Documents sent to other indexes by transform stages, forwarded to the ingest API after
+    /// each batch.
+    routed_docs: RoutedDocs,
+    /// Index receiving the invalid documents, if any.
+    dead_letter_index_id_opt: Option<String>,
+    /// Invalid documents, forwarded to the dead-letter index after each batch.
+    dead_letter_docs: RoutedDocs,
+    ingest_mirror_opt: Option<IngestMirror>,
+    ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
 }
 
 impl DocProcessor {
@@ -388,11 +777,31 @@ impl DocProcessor {
         indexer_mailbox: Mailbox<Indexer>,
         transform_config_opt: Option<TransformConfig>,
         input_format: SourceInputFormat,
+        enrichment_config_opt: Option<EnrichmentConfig>,
+        embedding_config_opt: Option<EmbeddingConfig>,
     ) -> anyhow::Result<Self> {
         let timestamp_field_opt = extract_timestamp_field(&*doc_mapper)?;
         if cfg!(not(feature = "vrl")) && transform_config_opt.is_some() {
             bail!("VRL is not enabled: please recompile with the `vrl` feature")
         }
+        let doc_enricher_opt = enrichment_config_opt
+            .map(|enrichment_config| {
+                DocEnricher::try_from_enrichment_config(
+                    index_id.clone(),
+                    source_id.clone(),
+                    enrichment_config,
+                )
+            })
+            .transpose()?;
+        let doc_embedder_opt = embedding_config_opt
+            .map(|embedding_config| {
+                DocEmbedder::try_from_embedding_config(
+                    index_id.clone(),
+                    source_id.clone(),
+                    embedding_config,
+                )
+            })
+            .transpose()?;
         let doc_processor = Self {
             doc_mapper,
             indexer_mailbox,
@@ -404,10 +813,67 @@ impl DocProcessor {
                 .map(VrlProgram::try_from_transform_config)
                 .transpose()?,
             input_format,
+            doc_enricher_opt,
+            doc_embedder_opt,
+            json_schema_validator_opt: None,
+            doc_plugin_opt: None,
+            routed_docs: RoutedDocs::default(),
+            dead_letter_index_id_opt: None,
+            dead_letter_docs: RoutedDocs::default(),
+            ingest_mirror_opt: None,
+            ingest_api_service_opt: None,
         };
         Ok(doc_processor)
     }
 
+    /// Sets the ingest API service used to forward the documents that transform stages send to
+    /// other indexes.
+    pub fn with_ingest_api_service(
+        mut self,
+        ingest_api_service: Mailbox<IngestApiService>,
+    ) -> Self {
+        self.ingest_api_service_opt = Some(ingest_api_service);
+        self
+    }
+
+    /// Sets the validator checking the documents against the JSON Schema of the source before they
+    /// go through the doc mapper.
+    pub(super) fn with_json_schema_validator(
+        mut self,
+        json_schema_validator: JsonSchemaValidator,
+    ) -> Self {
+        self.json_schema_validator_opt = Some(json_schema_validator);
+        self
+    }
+
+    /// Sets the WASM plugin transforming the documents after the transform, if any.
+    pub(super) fn with_doc_plugin(mut self, doc_plugin: DocPlugin) -> Self {
+        self.doc_plugin_opt = Some(doc_plugin);
+        self
+    }
+
+    /// Sets the index to which the invalid documents are written, along with the reason of their
+    /// rejection, instead of being dropped. Requires the ingest API service.
+    pub fn with_dead_letter_index(mut self, dead_letter_index_id: String) -> Self {
+        self.dead_letter_index_id_opt = Some(dead_letter_index_id);
+        self
+    }
+
+    /// Sets the ingest mirror duplicating a share of the valid documents to another index.
+    /// Mirroring to the local cluster requires the ingest API service.
+    pub fn with_ingest_mirror(
+        mut self,
+        ingest_mirror_config: &IngestMirrorConfig,
+    ) -> anyhow::Result<Self> {
+        let ingest_mirror = IngestMirror::try_from_ingest_mirror_config(
+            ingest_mirror_config,
+            self.ingest_api_service_opt.clone(),
+            self.counters.clone(),
+        )?;
+        self.ingest_mirror_opt = Some(ingest_mirror);
+        Ok(self)
+    }
+
     // Extract a timestamp from a tantivy document.
     //
     // If the timestamp is set up in the docmapper and the timestamp is missing,
@@ -428,7 +894,7 @@ impl DocProcessor {
         Ok(Some(timestamp))
     }
 
-    fn process_raw_doc(&mut self, raw_doc: Bytes, processed_docs: &mut Vec<ProcessedDoc>) {
+    fn json_docs_from_raw_doc(&mut self, raw_doc: Bytes) -> JsonDocIterator {
         let num_bytes = raw_doc.len();
 
         #[cfg(feature = "vrl")]
@@ -436,35 +902,232 @@ impl DocProcessor {
         #[cfg(not(feature = "vrl"))]
         let transform_opt: Option<&mut VrlProgram> = None;
 
-        for json_doc_result in parse_raw_doc(self.input_format, raw_doc, num_bytes, transform_opt) {
-            let processed_doc_result =
-                json_doc_result.and_then(|json_doc| self.process_json_doc(json_doc));
-
-            match processed_doc_result {
-                Ok(processed_doc) => {
-                    self.counters.record_valid(processed_doc.num_bytes as u64);
-                    processed_docs.push(processed_doc);
+        let json_docs = parse_raw_doc(
+            self.input_format,
+            raw_doc,
+            num_bytes,
+            transform_opt,
+            &mut self.routed_docs,
+            &self.counters,
+        );
+        let Some(doc_plugin) = &mut self.doc_plugin_opt else {
+            return json_docs;
+        };
+        let counters = &self.counters;
+        let plugin_json_docs: Vec<Result<JsonDoc, DocProcessorError>> = json_docs
+            .filter_map(|json_doc_result| {
+                let json_doc = match json_doc_result {
+                    Ok(json_doc) => json_doc,
+                    Err(error) => return Some(Err(error)),
+                };
+                match doc_plugin.transform_doc(&json_doc.json_obj) {
+                    Ok(Some(json_obj)) => Some(Ok(JsonDoc::new(json_obj, json_doc.num_bytes))),
+                    Ok(None) => {
+                        counters.record_dropped(json_doc.num_bytes as u64);
+                        None
+                    }
+                    Err(error) => Some(Err(DocProcessorError::Plugin(error))),
                 }
+            })
+            .collect();
+        JsonDocIterator::Many(plugin_json_docs.into_iter())
+    }
+
+    /// Forwards the documents sent to other indexes by transform stages to the ingest API.
+    async fn forward_routed_docs(&mut self, ctx: &ActorContext<Self>) {
+        if self.routed_docs.is_empty() {
+            return;
+        }
+        let (ingest_request, num_docs, num_bytes) = self.routed_docs.take_ingest_request();
+
+        let Some(ingest_api_service) = &self.ingest_api_service_opt else {
+            rate_limited_warn!(
+                limit_per_min = 10,
+                index_id = self.counters.index_id,
+                source_id = self.counters.source_id,
+                "ingest API service is not available, dropping routed documents"
+            );
+            self.counters.record_route_errors(num_docs, num_bytes);
+            return;
+        };
+        match ctx
+            .protect_future(ctx.ask_for_res(ingest_api_service, ingest_request))
+            .await
+        {
+            Ok(_) => self.counters.record_routed(num_docs, num_bytes),
+            Err(error) => {
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    index_id = self.counters.index_id,
+                    source_id = self.counters.source_id,
+                    "failed to forward routed documents: {error}"
+                );
+                self.counters.record_route_errors(num_docs, num_bytes);
+            }
+        }
+    }
+
+    /// Forwards the invalid documents to the dead-letter index. These documents have already
+    /// been accounted for as errors, so failures are only logged.
+    async fn forward_dead_letter_docs(&mut self, ctx: &ActorContext<Self>) {
+        if self.dead_letter_docs.is_empty() {
+            return;
+        }
+        let (ingest_request, num_docs, _num_bytes) = self.dead_letter_docs.take_ingest_request();
+
+        let Some(ingest_api_service) = &self.ingest_api_service_opt else {
+            rate_limited_warn!(
+                limit_per_min = 10,
+                index_id = self.counters.index_id,
+                source_id = self.counters.source_id,
+                "ingest API service is not available, dropping dead-letter documents"
+            );
+            return;
+        };
+        match ctx
+            .protect_future(ctx.ask_for_res(ingest_api_service, ingest_request))
+            .await
+        {
+            Ok(_) => self.counters.record_dead_letter(num_docs),
+            Err(error) => {
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    index_id = self.counters.index_id,
+                    source_id = self.counters.source_id,
+                    "failed to write {num_docs} documents to the dead-letter index: {error}"
+                );
+            }
+        }
+    }
+
+    fn process_raw_doc(&mut self, raw_doc: Bytes, processed_docs: &mut Vec<ProcessedDoc>) {
+        let num_bytes = raw_doc.len();
+        let payload_opt = self.dead_letter_payload(|| raw_doc.clone());
+
+        for json_doc_result in self.json_docs_from_raw_doc(raw_doc) {
+            let json_doc = match json_doc_result {
+                Ok(json_doc) => json_doc,
                 Err(error) => {
-                    rate_limited_warn!(
-                        limit_per_min = 10,
-                        index_id = self.counters.index_id,
-                        source_id = self.counters.source_id,
-                        "{error}",
-                    );
-                    self.counters.record_error(error, num_bytes as u64);
+                    self.record_error(error, num_bytes, payload_opt.clone());
+                    continue;
+                }
+            };
+            let mirror_payload_opt = self.mirror_payload(|| json_doc.to_payload());
+            let processed_doc_result = self.process_json_doc(json_doc);
+            self.record_processed_doc_result(
+                processed_doc_result,
+                num_bytes,
+                payload_opt.clone(),
+                mirror_payload_opt,
+                processed_docs,
+            );
+        }
+    }
+
+    /// Parses the raw doc and appends the resulting JSON docs to `json_docs`. Used when the docs
+    /// go through the enrichment or embedding stages before being processed.
+    fn parse_raw_doc_into(&mut self, raw_doc: Bytes, json_docs: &mut Vec<JsonDoc>) {
+        let num_bytes = raw_doc.len();
+        let payload_opt = self.dead_letter_payload(|| raw_doc.clone());
+
+        for json_doc_result in self.json_docs_from_raw_doc(raw_doc) {
+            match json_doc_result {
+                Ok(json_doc) => json_docs.push(json_doc),
+                Err(error) => self.record_error(error, num_bytes, payload_opt.clone()),
+            }
+        }
+    }
+
+    fn record_processed_doc_result(
+        &mut self,
+        processed_doc_result: Result<ProcessedDoc, DocProcessorError>,
+        num_bytes: usize,
+        payload_opt: Option<Bytes>,
+        mirror_payload_opt: Option<Bytes>,
+        processed_docs: &mut Vec<ProcessedDoc>,
+    ) {
+        match processed_doc_result {
+            Ok(processed_doc) => {
+                self.counters.record_valid(processed_doc.num_bytes as u64);
+                processed_docs.push(processed_doc);
+
+                if let (Some(ingest_mirror), Some(mirror_payload)) =
+                    (&mut self.ingest_mirror_opt, mirror_payload_opt)
+                {
+                    ingest_mirror.push(mirror_payload);
                 }
             }
+            Err(error) => self.record_error(error, num_bytes, payload_opt),
+        }
+    }
+
+    /// Returns the payload to write to the dead-letter index if the document turns out to be
+    /// invalid. `payload_fn` is only called when a dead-letter index is configured.
+    fn dead_letter_payload(&self, payload_fn: impl FnOnce() -> Bytes) -> Option<Bytes> {
+        self.dead_letter_index_id_opt.as_ref().map(|_| payload_fn())
+    }
+
+    /// Returns the payload to mirror if the document is sampled by the ingest mirror and turns
+    /// out to be valid. `payload_fn` is only called for the sampled documents.
+    fn mirror_payload(&mut self, payload_fn: impl FnOnce() -> Bytes) -> Option<Bytes> {
+        let ingest_mirror = self.ingest_mirror_opt.as_mut()?;
+
+        if ingest_mirror.sample() {
+            Some(payload_fn())
+        } else {
+            None
+        }
+    }
+
+    /// Records an invalid document and buffers it for the dead-letter index, if any.
+    fn record_error(
+        &mut self,
+        error: DocProcessorError,
+        num_bytes: usize,
+        payload_opt: Option<Bytes>,
+    ) {
+        rate_limited_warn!(
+            limit_per_min = 10,
+            index_id = self.counters.index_id,
+            source_id = self.counters.source_id,
+            "{error}",
+        );
+        if let (Some(dead_letter_index_id), Some(payload)) =
+            (&self.dead_letter_index_id_opt, payload_opt)
+        {
+            let dead_letter_doc = DeadLetterDoc::new(
+                &self.counters.index_id,
+                &self.counters.source_id,
+                &error,
+                &payload,
+            );
+            let dead_letter_payload = serde_json::to_vec(&dead_letter_doc)
+                .expect("dead-letter document should serialize to JSON");
+            self.dead_letter_docs.push_payload(
+                dead_letter_index_id.clone(),
+                Bytes::from(dead_letter_payload),
+                num_bytes as u64,
+            );
         }
+        self.counters.record_error(error, num_bytes as u64);
     }
 
     fn process_json_doc(&self, json_doc: JsonDoc) -> Result<ProcessedDoc, DocProcessorError> {
         let num_bytes = json_doc.num_bytes;
 
-        let (partition, doc) = self
-            .doc_mapper
-            .doc_from_json_obj(json_doc.json_obj, json_doc.num_bytes as u64)?;
+        let json_obj = if let Some(json_schema_validator) = &self.json_schema_validator_opt {
+            json_schema_validator.validate(json_doc.json_obj)?
+        } else {
+            json_doc.json_obj
+        };
+        let mut doc_parsing_stats = DocParsingStats::default();
+        let (partition, doc) = self.doc_mapper.doc_from_json_obj_with_stats(
+            json_obj,
+            json_doc.num_bytes as u64,
+            &mut doc_parsing_stats,
+        )?;
         let timestamp_opt = self.extract_timestamp(&doc)?;
+        self.counters.record_doc_parsing_stats(&doc_parsing_stats);
         Ok(ProcessedDoc {
             doc,
             timestamp_opt,
@@ -541,11 +1204,67 @@ impl Handler<RawDocBatch> for DocProcessor {
         }
         let mut processed_docs: Vec<ProcessedDoc> = Vec::with_capacity(raw_doc_batch.docs.len());
 
-        for raw_doc in raw_doc_batch.docs {
-            let _protected_zone_guard = ctx.protect_zone();
-            self.process_raw_doc(raw_doc, &mut processed_docs);
-            ctx.record_progress();
+        if self.doc_enricher_opt.is_some() || self.doc_embedder_opt.is_some() {
+            let mut json_docs: Vec<JsonDoc> = Vec::with_capacity(raw_doc_batch.docs.len());
+
+            for raw_doc in raw_doc_batch.docs {
+                let _protected_zone_guard = ctx.protect_zone();
+                self.parse_raw_doc_into(raw_doc, &mut json_docs);
+                ctx.record_progress();
+            }
+            if let Some(doc_enricher) = self.doc_enricher_opt.as_mut() {
+                let num_passthrough_docs = ctx
+                    .protect_future(doc_enricher.enrich_docs(&mut json_docs))
+                    .await;
+                self.counters
+                    .record_enrichment_passthrough(num_passthrough_docs as u64);
+            }
+            if let Some(doc_embedder) = self.doc_embedder_opt.as_ref() {
+                let embedding_outcome = ctx
+                    .protect_future(doc_embedder.embed_docs(&mut json_docs))
+                    .await
+                    .map_err(ActorExitStatus::from)?;
+                self.counters
+                    .record_embedding_skipped(embedding_outcome.num_skipped_docs as u64);
+
+                for dropped_doc in embedding_outcome.dropped_docs {
+                    let error = DocProcessorError::Embedding(
+                        "failed to embed document, dropping it".to_string(),
+                    );
+                    let payload_opt = self.dead_letter_payload(|| dropped_doc.to_payload());
+                    self.record_error(error, dropped_doc.num_bytes, payload_opt);
+                }
+            }
+
+            for json_doc in json_docs {
+                let _protected_zone_guard = ctx.protect_zone();
+                let num_bytes = json_doc.num_bytes;
+                let payload_opt = self.dead_letter_payload(|| json_doc.to_payload());
+                let mirror_payload_opt = self.mirror_payload(|| json_doc.to_payload());
+                let processed_doc_result = self.process_json_doc(json_doc);
+                self.record_processed_doc_result(
+                    processed_doc_result,
+                    num_bytes,
+                    payload_opt,
+                    mirror_payload_opt,
+                    &mut processed_docs,
+                );
+                ctx.record_progress();
+            }
+        } else {
+            for raw_doc in raw_doc_batch.docs {
+                let _protected_zone_guard = ctx.protect_zone();
+                self.process_raw_doc(raw_doc, &mut processed_docs);
+                ctx.record_progress();
+            }
+        }
+        self.forward_routed_docs(ctx).await;
+        self.forward_dead_letter_docs(ctx).await;
+
+        if let Some(ingest_mirror) = self.ingest_mirror_opt.as_mut() {
+            ingest_mirror.flush();
         }
+
         let processed_doc_batch = ProcessedDocBatch::new(
             processed_docs,
             raw_doc_batch.checkpoint_delta,
@@ -590,12 +1309,18 @@ impl Handler<NewPublishToken> for DocProcessor {
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
+    use std::time::Duration;
 
     use prost::Message;
     use quickwit_actors::Universe;
+    use quickwit_common::test_utils::wait_until_predicate;
     use quickwit_common::uri::Uri;
-    use quickwit_config::{build_doc_mapper, SearchSettings};
+    use quickwit_config::{
+        build_doc_mapper, EmbeddingFailurePolicy, IngestApiConfig, JsonSchemaConfig,
+        JsonSchemaVersionConfig, SearchSettings,
+    };
     use quickwit_doc_mapper::{default_doc_mapper_for_test, DefaultDocMapper};
+    use quickwit_ingest::{init_ingest_api, CreateQueueRequest, FetchRequest};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use quickwit_opentelemetry::otlp::{OtlpGrpcLogsService, OtlpGrpcTracesService};
     use quickwit_proto::opentelemetry::proto::collector::logs::v1::ExportLogsServiceRequest;
@@ -625,6 +1350,8 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -699,12 +1426,24 @@ mod tests {
         }"#;
 
     #[tokio::test]
-    async fn test_doc_processor_partitioning() {
-        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
-            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_PARTITION_JSON).unwrap(),
-        );
+    async fn test_doc_processor_dead_letter_index() {
         let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
         let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let ingest_api_service =
+            init_ingest_api(&universe, tempdir.path(), &IngestApiConfig::default())
+                .await
+                .unwrap();
+        let create_queue_request = CreateQueueRequest {
+            queue_id: "my-index-dead-letter".to_string(),
+        };
+        ingest_api_service
+            .ask_for_res(create_queue_request)
+            .await
+            .unwrap();
+
         let doc_processor = DocProcessor::try_new(
             "my-index".to_string(),
             "my-source".to_string(),
@@ -712,47 +1451,92 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
+            None,
         )
-        .unwrap();
+        .unwrap()
+        .with_ingest_api_service(ingest_api_service.clone())
+        .with_dead_letter_index("my-index-dead-letter".to_string());
         let (doc_processor_mailbox, doc_processor_handle) =
             universe.spawn_builder().spawn(doc_processor);
         doc_processor_mailbox
             .send_message(RawDocBatch::for_test(
                 &[
-                    br#"{"tenant": "tenant_1", "body": "first doc for tenant 1"}"#,
-                    br#"{"tenant": "tenant_2", "body": "first doc for tenant 2"}"#,
-                    br#"{"tenant": "tenant_1", "body": "second doc for tenant 1"}"#,
-                    br#"{"tenant": "tenant_2", "body": "second doc for tenant 2"}"#,
+                    br#"{"body": "happy"}"#,                          // missing timestamp
+                    br#"{"body": "happy", "timestamp": 1628837062}"#, // ok
+                    b"{",                                             // invalid json
                 ],
-                0..2,
+                0..3,
             ))
             .await
             .unwrap();
-
-        universe
-            .send_exit_with_success(&doc_processor_mailbox)
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
             .await
+            .state;
+        assert_eq!(counters.num_doc_parse_errors.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_dead_letter_docs.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_processed_docs(), 3);
+
+        let output_messages = indexer_inbox.drain_for_test();
+        let batch = output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
             .unwrap();
-        let (exit_status, _) = doc_processor_handle.join().await;
-        assert!(matches!(exit_status, ActorExitStatus::Success));
-        let processed_doc_batches: Vec<ProcessedDocBatch> = indexer_inbox.drain_for_test_typed();
-        assert_eq!(processed_doc_batches.len(), 1);
-        let partition_ids: Vec<u64> = processed_doc_batches[0]
-            .docs
-            .iter()
-            .map(|doc| doc.partition)
+        assert_eq!(batch.docs.len(), 1);
+
+        let fetch_request = FetchRequest {
+            index_id: "my-index-dead-letter".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+        };
+        let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
+        let dead_letter_docs: Vec<JsonValue> = fetch_response
+            .doc_batch
+            .unwrap()
+            .into_iter_raw()
+            .map(|payload| serde_json::from_slice(&payload).unwrap())
             .collect();
-        assert_eq!(partition_ids[0], partition_ids[2]);
-        assert_eq!(partition_ids[1], partition_ids[3]);
-        assert_ne!(partition_ids[0], partition_ids[1]);
+        assert_eq!(dead_letter_docs.len(), 2);
+
+        assert_eq!(dead_letter_docs[0]["index_id"], "my-index");
+        assert_eq!(dead_letter_docs[0]["source_id"], "my-source");
+        assert_eq!(dead_letter_docs[0]["error_kind"], "doc_mapper_error");
+        assert_eq!(dead_letter_docs[0]["payload"], r#"{"body": "happy"}"#);
+        assert!(dead_letter_docs[0]["timestamp"].is_i64());
+
+        assert_eq!(dead_letter_docs[1]["error_kind"], "json_parse_error");
+        assert_eq!(dead_letter_docs[1]["payload"], "{");
+
         universe.assert_quit().await;
     }
 
     #[tokio::test]
-    async fn test_doc_processor_forward_publish_lock() {
-        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+    async fn test_doc_processor_ingest_mirror() {
         let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
         let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let ingest_api_service =
+            init_ingest_api(&universe, tempdir.path(), &IngestApiConfig::default())
+                .await
+                .unwrap();
+        let create_queue_request = CreateQueueRequest {
+            queue_id: "my-index-v2".to_string(),
+        };
+        ingest_api_service
+            .ask_for_res(create_queue_request)
+            .await
+            .unwrap();
+
+        let ingest_mirror_config = IngestMirrorConfig {
+            percentage: 50.,
+            ..IngestMirrorConfig::for_test("my-index-v2")
+        };
         let doc_processor = DocProcessor::try_new(
             "my-index".to_string(),
             "my-source".to_string(),
@@ -760,22 +1544,491 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
+            None,
         )
+        .unwrap()
+        .with_ingest_api_service(ingest_api_service.clone())
+        .with_ingest_mirror(&ingest_mirror_config)
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
             universe.spawn_builder().spawn(doc_processor);
-        let publish_lock = PublishLock::default();
         doc_processor_mailbox
-            .send_message(NewPublishLock(publish_lock.clone()))
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "happy1", "timestamp": 1628837062}"#,
+                    b"{", // invalid json
+                    br#"{"body": "happy2", "timestamp": 1628837062}"#,
+                    br#"{"body": "happy3", "timestamp": 1628837062}"#,
+                    br#"{"body": "happy4", "timestamp": 1628837062}"#,
+                ],
+                0..5,
+            ))
             .await
             .unwrap();
-        universe
-            .send_exit_with_success(&doc_processor_mailbox)
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
             .await
-            .unwrap();
-        let (exit_status, _) = doc_processor_handle.join().await;
-        assert!(matches!(exit_status, ActorExitStatus::Success));
-        let publish_locks: Vec<NewPublishLock> = indexer_inbox.drain_for_test_typed();
+            .state;
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 4);
+
+        wait_until_predicate(
+            || async { counters.num_mirrored_docs.load(Ordering::Relaxed) == 2 },
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(counters.num_mirror_dropped_docs.load(Ordering::Relaxed), 0);
+
+        // Mirroring does not alter the documents sent to the indexer.
+        let output_messages = indexer_inbox.drain_for_test();
+        let batch = output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
+            .unwrap();
+        assert_eq!(batch.docs.len(), 4);
+
+        let fetch_request = FetchRequest {
+            index_id: "my-index-v2".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+        };
+        let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
+        let mirrored_docs: Vec<JsonValue> = fetch_response
+            .doc_batch
+            .unwrap()
+            .into_iter_raw()
+            .map(|payload| serde_json::from_slice(&payload).unwrap())
+            .collect();
+        assert_eq!(mirrored_docs.len(), 2);
+        assert_eq!(mirrored_docs[0]["body"], "happy2");
+        assert_eq!(mirrored_docs[1]["body"], "happy4");
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_json_schema() {
+        let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let json_schema_config = JsonSchemaConfig {
+            versions: vec![
+                JsonSchemaVersionConfig {
+                    version: 1,
+                    schema: Some(serde_json::json!({"required": ["body"]})),
+                    url: None,
+                },
+                JsonSchemaVersionConfig {
+                    version: 2,
+                    schema: Some(serde_json::json!({"required": ["body", "response_time"]})),
+                    url: None,
+                },
+            ],
+            version_field: Some("schema_version".to_string()),
+            min_version: None,
+        };
+        let json_schema_validator =
+            JsonSchemaValidator::try_from_json_schema_config(json_schema_config)
+                .await
+                .unwrap();
+        let doc_processor = DocProcessor::try_new(
+            "my-json-schema-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+            None,
+        )
+        .unwrap()
+        .with_json_schema_validator(json_schema_validator);
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"schema_version": 1, "body": "happy", "timestamp": 1628837062}"#, // ok
+                    br#"{"schema_version": 2, "body": "happy", "timestamp": 1628837062}"#, // missing response_time
+                    br#"{"schema_version": 3, "body": "happy", "timestamp": 1628837062}"#, // unknown version
+                    br#"{"body": "happy", "response_time": 2, "timestamp": 1628837062}"#, // ok, latest version
+                ],
+                0..4,
+            ))
+            .await
+            .unwrap();
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_json_schema_errors.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_processed_docs(), 4);
+        assert_eq!(counters.num_invalid_docs(), 2);
+
+        for version_label in ["2", "unknown"] {
+            let num_rejected_docs = crate::metrics::INDEXER_METRICS
+                .json_schema_rejected_docs_total
+                .with_label_values(["my-json-schema-index", "my-source", version_label])
+                .get();
+            assert_eq!(num_rejected_docs, 1);
+        }
+        let output_messages = indexer_inbox.drain_for_test();
+        let batch = output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
+            .unwrap();
+        assert_eq!(batch.docs.len(), 2);
+
+        universe.assert_quit().await;
+    }
+
+    #[cfg(feature = "wasm")]
+    #[tokio::test]
+    async fn test_doc_processor_wasm_plugin() {
+        // Drops the documents shorter than 40 bytes, loops forever on the documents longer than
+        // 100 bytes, and returns the other documents unchanged.
+        let plugin_wat = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param $len i32) (result i32)
+                    (i32.const 0))
+                (func (export "transform_doc") (param $ptr i32) (param $len i32) (result i64)
+                    (if (i32.lt_u (local.get $len) (i32.const 40))
+                        (then (return (i64.const -1))))
+                    (if (i32.gt_u (local.get $len) (i32.const 100))
+                        (then (loop $forever (br $forever))))
+                    (i64.or
+                        (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                        (i64.extend_i32_u (local.get $len))))
+            )
+        "#;
+        let plugin_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(plugin_file.path(), plugin_wat).unwrap();
+        let wasm_plugin_config = quickwit_config::WasmPluginConfig {
+            max_fuel_per_call: 100_000,
+            ..quickwit_config::WasmPluginConfig::for_test(plugin_file.path().to_str().unwrap())
+        };
+        let doc_plugin = DocPlugin::try_from_wasm_plugin_config(&wasm_plugin_config).unwrap();
+
+        let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_processor = DocProcessor::try_new(
+            "my-wasm-plugin-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+            None,
+        )
+        .unwrap()
+        .with_doc_plugin(doc_plugin);
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        let long_doc = format!(
+            r#"{{"body": "{}", "timestamp": 1628837062}}"#,
+            "x".repeat(100)
+        );
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "a", "timestamp": 1628837062}"#, // dropped
+                    br#"{"body": "happy using plugins", "timestamp": 1628837062}"#, // ok
+                    long_doc.as_bytes(),                          // out of fuel
+                    br#"{"body": "happy again", "timestamp": 1628837062}"#, // ok
+                ],
+                0..4,
+            ))
+            .await
+            .unwrap();
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_dropped_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_plugin_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 2);
+
+        let output_messages = indexer_inbox.drain_for_test();
+        let batch = output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
+            .unwrap();
+        assert_eq!(batch.docs.len(), 2);
+
+        universe.assert_quit().await;
+    }
+
+    #[test]
+    fn test_dead_letter_doc_binary_payload() {
+        let error = DocProcessorError::JsonParsing("invalid".to_string());
+        let dead_letter_doc = DeadLetterDoc::new("my-index", "my-source", &error, &[0xff, 0x00]);
+        let dead_letter_json = serde_json::to_value(&dead_letter_doc).unwrap();
+        assert!(dead_letter_json.get("payload").is_none());
+        assert_eq!(dead_letter_json["payload_base64"], "/wA=");
+        assert_eq!(dead_letter_json["error"], "JSON parse error: invalid");
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_enrichment_passthrough() {
+        let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        // Nothing listens on this port: enrichment requests fail and docs pass through.
+        let enrichment_config = EnrichmentConfig::for_test("http://127.0.0.1:1");
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            Some(enrichment_config),
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:59+00:00", "response_time": 2, "response_payload": "YWJj"}"#, // ok
+                    br#"{"body": "happy2", "timestamp": 1628837062, "response_date": "2021-12-19T16:40:57+00:00", "response_time": 13, "response_payload": "YWJj"}"#, // ok
+                    b"{", // invalid json
+                ],
+                0..3,
+            ))
+            .await
+            .unwrap();
+
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_doc_parse_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            counters
+                .num_enrichment_passthrough_docs
+                .load(Ordering::Relaxed),
+            2
+        );
+        let output_messages = indexer_inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let batch = *(output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
+            .unwrap());
+        assert_eq!(batch.docs.len(), 2);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_embedding_failure_drops_docs() {
+        let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        // Nothing listens on this port: embedding requests fail and docs are dropped.
+        let embedding_config = EmbeddingConfig {
+            on_failure: EmbeddingFailurePolicy::Drop,
+            ..EmbeddingConfig::for_test("http://127.0.0.1:1", "body", "body_embedding")
+        };
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+            Some(embedding_config),
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:59+00:00", "response_time": 2, "response_payload": "YWJj"}"#, // dropped
+                    br#"{"timestamp": 1628837062, "response_date": "2021-12-19T16:40:57+00:00", "response_time": 13, "response_payload": "YWJj"}"#, // ok, nothing to embed
+                ],
+                0..2,
+            ))
+            .await
+            .unwrap();
+
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_embedding_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_processed_docs(), 2);
+
+        let output_messages = indexer_inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let batch = *(output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
+            .unwrap());
+        assert_eq!(batch.docs.len(), 1);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_partitioning() {
+        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
+            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_PARTITION_JSON).unwrap(),
+        );
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"tenant": "tenant_1", "body": "first doc for tenant 1"}"#,
+                    br#"{"tenant": "tenant_2", "body": "first doc for tenant 2"}"#,
+                    br#"{"tenant": "tenant_1", "body": "second doc for tenant 1"}"#,
+                    br#"{"tenant": "tenant_2", "body": "second doc for tenant 2"}"#,
+                ],
+                0..2,
+            ))
+            .await
+            .unwrap();
+
+        universe
+            .send_exit_with_success(&doc_processor_mailbox)
+            .await
+            .unwrap();
+        let (exit_status, _) = doc_processor_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Success));
+        let processed_doc_batches: Vec<ProcessedDocBatch> = indexer_inbox.drain_for_test_typed();
+        assert_eq!(processed_doc_batches.len(), 1);
+        let partition_ids: Vec<u64> = processed_doc_batches[0]
+            .docs
+            .iter()
+            .map(|doc| doc.partition)
+            .collect();
+        assert_eq!(partition_ids[0], partition_ids[2]);
+        assert_eq!(partition_ids[1], partition_ids[3]);
+        assert_ne!(partition_ids[0], partition_ids[1]);
+        universe.assert_quit().await;
+    }
+
+    const DOCMAPPER_WITH_DEFAULTS_JSON: &str = r#"
+        {
+            "field_mappings": [
+                { "name": "body", "type": "text" },
+                { "name": "status", "type": "u64", "default_value": 200 },
+                { "name": "is_error", "type": "bool", "coerce": true }
+            ]
+        }"#;
+
+    #[tokio::test]
+    async fn test_doc_processor_coerced_and_defaulted_values() {
+        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
+            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_DEFAULTS_JSON).unwrap(),
+        );
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "first doc", "status": "404", "is_error": "true"}"#,
+                    br#"{"body": "second doc", "status": null, "is_error": false}"#,
+                    br#"{"body": "third doc"}"#,
+                    br#"{"body": "fourth doc", "is_error": "maybe"}"#,
+                ],
+                0..4,
+            ))
+            .await
+            .unwrap();
+
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 3);
+        assert_eq!(counters.num_doc_parse_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_coerced_values.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_defaulted_values.load(Ordering::Relaxed), 2);
+
+        let processed_doc_batches: Vec<ProcessedDocBatch> = indexer_inbox.drain_for_test_typed();
+        assert_eq!(processed_doc_batches.len(), 1);
+        assert_eq!(processed_doc_batches[0].docs.len(), 3);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_forward_publish_lock() {
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        let publish_lock = PublishLock::default();
+        doc_processor_mailbox
+            .send_message(NewPublishLock(publish_lock.clone()))
+            .await
+            .unwrap();
+        universe
+            .send_exit_with_success(&doc_processor_mailbox)
+            .await
+            .unwrap();
+        let (exit_status, _) = doc_processor_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Success));
+        let publish_locks: Vec<NewPublishLock> = indexer_inbox.drain_for_test_typed();
         assert_eq!(&publish_locks, &[NewPublishLock(publish_lock)]);
         universe.assert_quit().await;
     }
@@ -792,6 +2045,8 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -838,6 +2093,8 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::OtlpLogsJson,
+            None,
+            None,
         )
         .unwrap();
 
@@ -915,6 +2172,8 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::OtlpLogsProtobuf,
+            None,
+            None,
         )
         .unwrap();
 
@@ -994,6 +2253,8 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::OtlpTracesJson,
+            None,
+            None,
         )
         .unwrap();
 
@@ -1067,6 +2328,8 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::OtlpTracesProtobuf,
+            None,
+            None,
         )
         .unwrap();
 
@@ -1131,7 +2394,9 @@ mod tests {
 #[cfg(test)]
 mod tests_vrl {
     use quickwit_actors::Universe;
+    use quickwit_config::{IngestApiConfig, TransformStageAction, TransformStageConfig};
     use quickwit_doc_mapper::default_doc_mapper_for_test;
+    use quickwit_ingest::{init_ingest_api, CreateQueueRequest, FetchRequest};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use tantivy::schema::NamedFieldDocument;
     use tantivy::Document;
@@ -1153,6 +2418,8 @@ mod tests_vrl {
             indexer_mailbox,
             Some(transform_config),
             SourceInputFormat::Json,
+            None,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -1218,6 +2485,163 @@ mod tests_vrl {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_doc_processor_vrl_stages() {
+        let index_id = "my-index";
+        let source_id = "my-source";
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let ingest_api_service =
+            init_ingest_api(&universe, tempdir.path(), &IngestApiConfig::default())
+                .await
+                .unwrap();
+        let create_queue_request = CreateQueueRequest {
+            queue_id: "audit-logs".to_string(),
+        };
+        ingest_api_service
+            .ask_for_res(create_queue_request)
+            .await
+            .unwrap();
+
+        let stages = vec![
+            TransformStageConfig {
+                name: "drop-debug".to_string(),
+                condition: Some(r#".level == "debug""#.to_string()),
+                action: TransformStageAction::Drop,
+                script: None,
+                index_id: None,
+            },
+            TransformStageConfig {
+                name: "upcase".to_string(),
+                condition: None,
+                action: TransformStageAction::Mutate,
+                script: Some(".body = upcase(string!(.body))\ndel(.level)".to_string()),
+                index_id: None,
+            },
+            TransformStageConfig {
+                name: "route-audit".to_string(),
+                condition: Some("exists(.audit)".to_string()),
+                action: TransformStageAction::Route,
+                script: None,
+                index_id: Some("audit-logs".to_string()),
+            },
+        ];
+        let transform_config = TransformConfig::for_test_with_stages("", stages);
+        let doc_processor = DocProcessor::try_new(
+            index_id.to_string(),
+            source_id.to_string(),
+            doc_mapper.clone(),
+            indexer_mailbox,
+            Some(transform_config),
+            SourceInputFormat::Json,
+            None,
+            None,
+        )
+        .unwrap()
+        .with_ingest_api_service(ingest_api_service.clone());
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "happy", "level": "debug", "timestamp": 1628837062}"#, // dropped
+                    br#"{"body": "happy", "level": "info", "timestamp": 1628837062}"#,  // indexed
+                    br#"{"body": "happy", "audit": true, "timestamp": 1628837062}"#,    // routed
+                ],
+                0..3,
+            ))
+            .await
+            .unwrap();
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_dropped_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_routed_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_transform_errors.load(Ordering::Relaxed), 0);
+        assert_eq!(counters.num_processed_docs(), 3);
+
+        let output_messages = indexer_inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let batch = *(output_messages
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<ProcessedDocBatch>()
+            .unwrap());
+        assert_eq!(batch.docs.len(), 1);
+        assert_eq!(
+            batch.checkpoint_delta,
+            SourceCheckpointDelta::from_range(0..3)
+        );
+        let fetch_request = FetchRequest {
+            index_id: "audit-logs".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+        };
+        let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
+        let doc_batch = fetch_response.doc_batch.unwrap();
+        assert_eq!(doc_batch.num_docs(), 1);
+
+        universe.assert_quit().await;
+    }
+
+    #[test]
+    fn test_dry_run_transform() {
+        let stages = vec![TransformStageConfig {
+            name: "route-audit".to_string(),
+            condition: Some("exists(.audit)".to_string()),
+            action: TransformStageAction::Route,
+            script: None,
+            index_id: Some("audit-logs".to_string()),
+        }];
+        let transform_config =
+            TransformConfig::for_test_with_stages(".body = upcase(string!(.body))", stages);
+        {
+            let dry_run_result = dry_run_transform(
+                "my-index",
+                transform_config.clone(),
+                SourceInputFormat::Json,
+                Bytes::from_static(br#"{"body": "happy"}"#),
+            )
+            .unwrap();
+            assert_eq!(dry_run_result.action, TransformDryRunAction::Index);
+            assert_eq!(dry_run_result.index_id.as_deref(), Some("my-index"));
+            assert_eq!(
+                dry_run_result.doc.unwrap()["body"],
+                JsonValue::String("HAPPY".to_string())
+            );
+            assert!(dry_run_result.applied_stages.is_empty());
+        }
+        {
+            let dry_run_result = dry_run_transform(
+                "my-index",
+                transform_config.clone(),
+                SourceInputFormat::Json,
+                Bytes::from_static(br#"{"body": "happy", "audit": true}"#),
+            )
+            .unwrap();
+            assert_eq!(dry_run_result.action, TransformDryRunAction::Route);
+            assert_eq!(dry_run_result.index_id.as_deref(), Some("audit-logs"));
+            assert_eq!(dry_run_result.applied_stages, ["route-audit"]);
+        }
+        {
+            let dry_run_result = dry_run_transform(
+                "my-index",
+                transform_config,
+                SourceInputFormat::Json,
+                Bytes::from_static(br#"{"body": 42}"#),
+            )
+            .unwrap();
+            assert_eq!(dry_run_result.action, TransformDryRunAction::Error);
+            assert!(dry_run_result.error.is_some());
+        }
+    }
+
     #[tokio::test]
     async fn test_doc_processor_with_plain_text_input() {
         let index_id = "my-index";
@@ -1243,6 +2667,8 @@ mod tests_vrl {
             indexer_mailbox,
             Some(transform_config),
             SourceInputFormat::PlainText,
+            None,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
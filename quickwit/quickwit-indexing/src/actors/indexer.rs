@@ -100,6 +100,7 @@ struct IndexerState {
     schema: Schema,
     tokenizer_manager: TokenizerManager,
     max_num_partitions: NonZeroU32,
+    doc_mapping_version: u64,
     index_settings: IndexSettings,
     cooperative_indexing_opt: Option<CooperativeIndexingCycle>,
 }
@@ -130,6 +131,7 @@ impl IndexerState {
             self.pipeline_id.clone(),
             partition_id,
             last_delete_opstamp,
+            self.doc_mapping_version,
             self.indexing_directory.clone(),
             index_builder,
             io_controls,
@@ -567,6 +569,7 @@ impl Indexer {
                 tokenizer_manager: tokenizer_manager.tantivy_manager().clone(),
                 index_settings,
                 max_num_partitions: doc_mapper.max_num_partitions(),
+                doc_mapping_version: doc_mapper.doc_mapping_version(),
                 cooperative_indexing_opt,
             },
             index_serializer_mailbox,
@@ -32,7 +32,7 @@ use quickwit_common::temp_dir::TempDirectory;
 use quickwit_common::KillSwitch;
 use quickwit_config::{IndexingSettings, SourceConfig};
 use quickwit_doc_mapper::DocMapper;
-use quickwit_ingest::IngesterPool;
+use quickwit_ingest::{get_ingest_api_service, IngesterPool};
 use quickwit_metastore::IndexMetadataResponseExt;
 use quickwit_proto::indexing::IndexingPipelineId;
 use quickwit_proto::metastore::{
@@ -44,8 +44,10 @@ use tokio::sync::Semaphore;
 use tracing::{debug, error, info, instrument};
 
 use super::MergePlanner;
+use crate::actors::doc_plugin::DocPlugin;
 use crate::actors::doc_processor::DocProcessor;
 use crate::actors::index_serializer::IndexSerializer;
+use crate::actors::json_schema_validation::JsonSchemaValidator;
 use crate::actors::publisher::PublisherType;
 use crate::actors::sequencer::Sequencer;
 use crate::actors::uploader::UploaderType;
@@ -381,7 +383,15 @@ impl IndexingPipeline {
 
         // Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let packager = Packager::new("Packager", tag_fields, uploader_mailbox);
+        let term_digest_fields = self.params.doc_mapper.term_digest_named_fields()?;
+        let secondary_timestamp_fields =
+            self.params.doc_mapper.secondary_timestamp_named_fields()?;
+        let packager = Packager::new("Packager", tag_fields, term_digest_fields, uploader_mailbox)
+            .with_secondary_timestamp_fields(secondary_timestamp_fields)
+            .with_split_validation(
+                self.params.doc_mapper.schema(),
+                self.params.split_quarantine_dir_path.clone(),
+            );
         let (packager_mailbox, packager_handle) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -414,14 +424,57 @@ impl IndexingPipeline {
             .set_kill_switch(self.kill_switch.clone())
             .spawn(indexer);
 
-        let doc_processor = DocProcessor::try_new(
+        let mut doc_processor = DocProcessor::try_new(
             index_id.to_string(),
             source_id.to_string(),
             self.params.doc_mapper.clone(),
             indexer_mailbox,
             self.params.source_config.transform_config.clone(),
             self.params.source_config.input_format,
+            self.params.indexing_settings.enrichment.clone(),
+            self.params.indexing_settings.embedding.clone(),
         )?;
+        let has_route_stages = self
+            .params
+            .source_config
+            .transform_config
+            .as_ref()
+            .map_or(false, |transform_config| {
+                transform_config.has_route_stages()
+            });
+        let dead_letter_index_id_opt = self
+            .params
+            .indexing_settings
+            .dead_letter
+            .as_ref()
+            .map(|dead_letter_config| dead_letter_config.index_id.clone());
+        let ingest_mirror_config_opt = self.params.indexing_settings.ingest_mirror.as_ref();
+        let has_local_ingest_mirror = ingest_mirror_config_opt
+            .map_or(false, |ingest_mirror_config| {
+                ingest_mirror_config.endpoint.is_none()
+            });
+        if has_route_stages || dead_letter_index_id_opt.is_some() || has_local_ingest_mirror {
+            let ingest_api_service = get_ingest_api_service(&self.params.queues_dir_path).await?;
+            doc_processor = doc_processor.with_ingest_api_service(ingest_api_service);
+        }
+        if let Some(dead_letter_index_id) = dead_letter_index_id_opt {
+            doc_processor = doc_processor.with_dead_letter_index(dead_letter_index_id);
+        }
+        if let Some(ingest_mirror_config) = ingest_mirror_config_opt {
+            doc_processor = doc_processor.with_ingest_mirror(ingest_mirror_config)?;
+        }
+        if let Some(json_schema_config) = self.params.source_config.json_schema_config.clone() {
+            let json_schema_validator = ctx
+                .protect_future(JsonSchemaValidator::try_from_json_schema_config(
+                    json_schema_config,
+                ))
+                .await?;
+            doc_processor = doc_processor.with_json_schema_validator(json_schema_validator);
+        }
+        if let Some(wasm_plugin_config) = &self.params.source_config.wasm_plugin_config {
+            let doc_plugin = DocPlugin::try_from_wasm_plugin_config(wasm_plugin_config)?;
+            doc_processor = doc_processor.with_doc_plugin(doc_plugin);
+        }
         let (doc_processor_mailbox, doc_processor_handle) = ctx
             .spawn_actor()
             .set_backpressure_micros_counter(
@@ -587,6 +640,7 @@ pub struct IndexingPipelineParams {
     // Indexing-related parameters
     pub doc_mapper: Arc<dyn DocMapper>,
     pub indexing_directory: TempDirectory,
+    pub split_quarantine_dir_path: PathBuf,
     pub indexing_settings: IndexingSettings,
     pub split_store: IndexingSplitStore,
     pub max_concurrent_split_uploads_index: usize,
@@ -707,6 +761,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file(PathBuf::from(test_file)),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let storage = Arc::new(RamStorage::default());
@@ -719,6 +775,7 @@ mod tests {
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
+            split_quarantine_dir_path: PathBuf::from("./quarantine"),
             indexing_settings: IndexingSettings::for_test(),
             ingester_pool: IngesterPool::default(),
             metastore: MetastoreServiceClient::from_mock(mock_metastore),
@@ -814,6 +871,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file(PathBuf::from(test_file)),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let storage = Arc::new(RamStorage::default());
@@ -825,6 +884,7 @@ mod tests {
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
+            split_quarantine_dir_path: PathBuf::from("./quarantine"),
             indexing_settings: IndexingSettings::for_test(),
             ingester_pool: IngesterPool::default(),
             metastore: MetastoreServiceClient::from_mock(mock_metastore),
@@ -890,6 +950,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::Void(VoidSourceParams),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = MetastoreServiceClient::from_mock(mock_metastore);
@@ -899,9 +961,11 @@ mod tests {
             pipeline_id: pipeline_id.clone(),
             doc_mapper: doc_mapper.clone(),
             indexing_directory: TempDirectory::for_test(),
+            split_quarantine_dir_path: PathBuf::from("./quarantine"),
             metastore: metastore.clone(),
             split_store: split_store.clone(),
             merge_policy: default_merge_policy(),
+            merge_throttling_opt: None,
             max_concurrent_split_uploads: 2,
             merge_io_throughput_limiter_opt: None,
             merge_scheduler_service: universe.get_or_spawn_one(),
@@ -917,6 +981,7 @@ mod tests {
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
+            split_quarantine_dir_path: PathBuf::from("./quarantine"),
             indexing_settings: IndexingSettings::for_test(),
             ingester_pool: IngesterPool::default(),
             metastore,
@@ -1008,6 +1073,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file(PathBuf::from(test_file)),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let storage = Arc::new(RamStorage::default());
@@ -1037,6 +1104,7 @@ mod tests {
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
+            split_quarantine_dir_path: PathBuf::from("./quarantine"),
             indexing_settings: IndexingSettings::for_test(),
             ingester_pool: IngesterPool::default(),
             metastore: MetastoreServiceClient::from_mock(mock_metastore),
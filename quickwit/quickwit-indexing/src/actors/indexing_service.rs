@@ -31,7 +31,7 @@ use quickwit_actors::{
     Actor, ActorContext, ActorExitStatus, ActorHandle, ActorState, Handler, Healthz, Mailbox,
     Observation,
 };
-use quickwit_cluster::Cluster;
+use quickwit_cluster::{Cluster, ListenerHandle};
 use quickwit_common::fs::get_cache_directory_path;
 use quickwit_common::io::Limiter;
 use quickwit_common::pubsub::EventBroker;
@@ -43,7 +43,9 @@ use quickwit_ingest::{
     DropQueueRequest, GetPartitionId, IngestApiService, IngesterPool, ListQueuesRequest,
     QUEUES_DIR_NAME,
 };
-use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt};
+use quickwit_metastore::{
+    IndexManifest, IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt,
+};
 use quickwit_proto::indexing::{
     ApplyIndexingPlanRequest, ApplyIndexingPlanResponse, IndexingError, IndexingPipelineId,
     IndexingTask, PipelineMetrics,
@@ -59,13 +61,17 @@ use tracing::{debug, error, info, warn};
 
 use super::merge_pipeline::{MergePipeline, MergePipelineParams};
 use super::{MergePlanner, MergeSchedulerService};
-use crate::models::{DetachIndexingPipeline, DetachMergePipeline, ObservePipeline, SpawnPipeline};
+use crate::models::{
+    DetachIndexingPipeline, DetachMergePipeline, ObservePipeline, QueryAccessStatsRegistry,
+    SpawnPipeline,
+};
 use crate::source::{AssignShards, Assignment};
 use crate::split_store::{LocalSplitStore, SplitStoreQuota};
 use crate::{IndexingPipeline, IndexingPipelineParams, IndexingSplitStore, IndexingStatistics};
 
 /// Name of the indexing directory, usually located at `<data_dir_path>/indexing`.
 pub const INDEXING_DIR_NAME: &str = "indexing";
+pub const SPLIT_QUARANTINE_DIR_NAME: &str = "quarantine";
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct IndexingServiceCounters {
@@ -118,6 +124,7 @@ struct PipelineHandle {
 pub struct IndexingService {
     node_id: String,
     indexing_root_directory: PathBuf,
+    split_quarantine_dir_path: PathBuf,
     queue_dir_path: PathBuf,
     cluster: Cluster,
     metastore: MetastoreServiceClient,
@@ -133,6 +140,8 @@ pub struct IndexingService {
     cooperative_indexing_permits: Option<Arc<Semaphore>>,
     merge_io_throughput_limiter_opt: Option<Limiter>,
     event_broker: EventBroker,
+    query_access_stats_registry: QueryAccessStatsRegistry,
+    _query_access_stats_listener_handle: ListenerHandle,
 }
 
 impl Debug for IndexingService {
@@ -172,15 +181,19 @@ impl IndexingService {
             LocalSplitStore::open(split_cache_dir_path, split_store_space_quota).await?;
         let indexing_root_directory =
             temp_dir::create_or_purge_directory(&data_dir_path.join(INDEXING_DIR_NAME)).await?;
+        let split_quarantine_dir_path = data_dir_path.join(SPLIT_QUARANTINE_DIR_NAME);
         let queue_dir_path = data_dir_path.join(QUEUES_DIR_NAME);
         let cooperative_indexing_permits = if indexer_config.enable_cooperative_indexing {
             Some(Arc::new(Semaphore::new(num_blocking_threads)))
         } else {
             None
         };
+        let (query_access_stats_registry, query_access_stats_listener_handle) =
+            QueryAccessStatsRegistry::subscribe(&cluster).await;
         Ok(IndexingService {
             node_id,
             indexing_root_directory,
+            split_quarantine_dir_path,
             queue_dir_path,
             cluster,
             metastore,
@@ -196,6 +209,8 @@ impl IndexingService {
             merge_io_throughput_limiter_opt,
             cooperative_indexing_permits,
             event_broker,
+            query_access_stats_registry,
+            _query_access_stats_listener_handle: query_access_stats_listener_handle,
         })
     }
 
@@ -253,6 +268,7 @@ impl IndexingService {
         pipeline_uid: PipelineUid,
     ) -> Result<IndexingPipelineId, IndexingError> {
         let index_metadata = self.index_metadata(ctx, &index_id).await?;
+        self.write_index_manifest(ctx, &index_metadata).await;
         let pipeline_id = IndexingPipelineId {
             index_uid: index_metadata.index_uid.clone(),
             source_id: source_config.source_id.clone(),
@@ -298,8 +314,11 @@ impl IndexingService {
                 let message = format!("failed to spawn indexing pipeline: {error}");
                 IndexingError::Internal(message)
             })?;
-        let merge_policy =
-            crate::merge_policy::merge_policy_from_settings(&index_config.indexing_settings);
+        let merge_policy = crate::merge_policy::adaptive_merge_policy_from_settings(
+            &pipeline_id.index_uid,
+            &index_config.indexing_settings,
+            &self.query_access_stats_registry,
+        );
         let split_store = IndexingSplitStore::new(storage.clone(), self.local_split_store.clone());
 
         let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
@@ -309,10 +328,12 @@ impl IndexingService {
             pipeline_id: pipeline_id.clone(),
             doc_mapper: doc_mapper.clone(),
             indexing_directory: indexing_directory.clone(),
+            split_quarantine_dir_path: self.split_quarantine_dir_path.clone(),
             metastore: self.metastore.clone(),
             split_store: split_store.clone(),
             merge_scheduler_service: self.merge_scheduler_service.clone(),
             merge_policy: merge_policy.clone(),
+            merge_throttling_opt: index_config.indexing_settings.merge_throttling.clone(),
             merge_io_throughput_limiter_opt: self.merge_io_throughput_limiter_opt.clone(),
             max_concurrent_split_uploads: self.max_concurrent_split_uploads,
             event_broker: self.event_broker.clone(),
@@ -335,6 +356,7 @@ impl IndexingService {
             // Indexing-related parameters
             doc_mapper,
             indexing_directory,
+            split_quarantine_dir_path: self.split_quarantine_dir_path.clone(),
             indexing_settings: index_config.indexing_settings.clone(),
             split_store,
             max_concurrent_split_uploads_index,
@@ -380,6 +402,29 @@ impl IndexingService {
         Ok(index_metadata)
     }
 
+    /// Writes the manifest of the index to the index storage, from which the index can be
+    /// recovered if the metastore is lost. The manifest is refreshed every time pipelines of the
+    /// index are spawned, so failures are only logged.
+    async fn write_index_manifest(&self, ctx: &ActorContext<Self>, index_metadata: &IndexMetadata) {
+        let _protect_guard = ctx.protect_zone();
+        let index_manifest = IndexManifest::new(index_metadata);
+        let index_uri = &index_metadata.index_config.index_uri;
+
+        let write_result: anyhow::Result<()> = async {
+            let storage = self.storage_resolver.resolve(index_uri).await?;
+            index_manifest.write(&*storage).await
+        }
+        .await;
+
+        if let Err(error) = write_result {
+            warn!(
+                index_uid=%index_metadata.index_uid,
+                error=?error,
+                "failed to write index manifest"
+            );
+        }
+    }
+
     async fn handle_supervise(&mut self) -> Result<(), ActorExitStatus> {
         self.indexing_pipelines
             .retain(|pipeline_uid, pipeline_handle| {
@@ -591,6 +636,10 @@ impl IndexingService {
             .map(|index_metadata| (index_metadata.index_uid.clone(), index_metadata))
             .collect();
 
+        for index_metadata in indexes_metadata_by_index_id.values() {
+            self.write_index_manifest(ctx, index_metadata).await;
+        }
+
         let mut failed_spawning_pipeline_ids: Vec<IndexingPipelineId> = Vec::new();
 
         // Add new pipelines.
@@ -979,6 +1028,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let spawn_pipeline_msg = SpawnPipeline {
@@ -1005,6 +1056,16 @@ mod tests {
                 .num_running_pipelines,
             1
         );
+        let index_storage = StorageResolver::unconfigured()
+            .resolve(&index_config.index_uri)
+            .await
+            .unwrap();
+        let index_manifest = IndexManifest::fetch(&*index_storage)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(index_manifest.index_config.index_id, index_id);
+        assert_eq!(index_manifest.sources.len(), 1);
 
         // Test `observe_pipeline`.
         let observation = indexing_service
@@ -1070,6 +1131,8 @@ mod tests {
                 partition: "0".to_string(),
             }),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         indexing_service
@@ -1136,6 +1199,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let add_source_request =
@@ -1185,6 +1250,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::Kafka(kafka_params),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let add_source_request_2 =
@@ -1341,6 +1408,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let create_index_request =
@@ -1470,6 +1539,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         index_metadata
@@ -0,0 +1,384 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_actors::Mailbox;
+use quickwit_common::rate_limited_tracing::rate_limited_warn;
+use quickwit_config::IngestMirrorConfig;
+use quickwit_ingest::{CommitType, DocBatchBuilder, IngestApiService, IngestRequest};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use super::doc_processor::DocProcessorCounters;
+
+/// Client writing the mirrored documents to the target index, abstracted away for testing.
+#[async_trait]
+pub(super) trait MirrorDocsClient: Send + Sync + 'static {
+    async fn mirror_docs(&self, docs: Vec<Bytes>) -> anyhow::Result<()>;
+}
+
+/// Writes the mirrored documents to an index of the local cluster through the ingest API.
+struct LocalMirrorDocsClient {
+    index_id: String,
+    ingest_api_service: Mailbox<IngestApiService>,
+}
+
+#[async_trait]
+impl MirrorDocsClient for LocalMirrorDocsClient {
+    async fn mirror_docs(&self, docs: Vec<Bytes>) -> anyhow::Result<()> {
+        let mut doc_batch_builder = DocBatchBuilder::new(self.index_id.clone());
+
+        for doc in docs {
+            doc_batch_builder.ingest_doc(doc);
+        }
+        let ingest_request = IngestRequest {
+            doc_batches: vec![doc_batch_builder.build()],
+            commit: CommitType::Auto.into(),
+        };
+        self.ingest_api_service
+            .ask_for_res(ingest_request)
+            .await
+            .context("failed to ingest mirrored documents")?;
+        Ok(())
+    }
+}
+
+/// Posts the mirrored documents as NDJSON to the ingest API of a remote cluster.
+struct HttpMirrorDocsClient {
+    client: reqwest::Client,
+    ingest_url: String,
+}
+
+#[async_trait]
+impl MirrorDocsClient for HttpMirrorDocsClient {
+    async fn mirror_docs(&self, docs: Vec<Bytes>) -> anyhow::Result<()> {
+        let num_bytes = docs.iter().map(|doc| doc.len() + 1).sum();
+        let mut body = Vec::with_capacity(num_bytes);
+
+        for doc in docs {
+            body.extend_from_slice(&doc);
+            body.push(b'\n');
+        }
+        self.client
+            .post(&self.ingest_url)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Ingest mirror of the doc processor. A share of the valid documents is sampled and buffered
+/// until the end of each batch, then handed over to a background task writing them to the target
+/// index. The hand-over never waits: when `max_pending_batches` batches are already waiting to be
+/// sent, the batch is dropped.
+pub(super) struct IngestMirror {
+    target_index_id: String,
+    sampling_ratio: f64,
+    sampling_credit: f64,
+    pending_docs: Vec<Bytes>,
+    batch_tx: mpsc::Sender<Vec<Bytes>>,
+    counters: Arc<DocProcessorCounters>,
+}
+
+impl IngestMirror {
+    pub fn try_from_ingest_mirror_config(
+        ingest_mirror_config: &IngestMirrorConfig,
+        ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
+        counters: Arc<DocProcessorCounters>,
+    ) -> anyhow::Result<Self> {
+        let client: Arc<dyn MirrorDocsClient> =
+            if let Some(endpoint) = &ingest_mirror_config.endpoint {
+                let ingest_url = format!(
+                    "{}/api/v1/{}/ingest",
+                    endpoint.trim_end_matches('/'),
+                    ingest_mirror_config.index_id
+                );
+                Arc::new(HttpMirrorDocsClient {
+                    client: reqwest::Client::new(),
+                    ingest_url,
+                })
+            } else {
+                let ingest_api_service = ingest_api_service_opt
+                    .context("ingest mirror requires the ingest API service")?;
+                Arc::new(LocalMirrorDocsClient {
+                    index_id: ingest_mirror_config.index_id.clone(),
+                    ingest_api_service,
+                })
+            };
+        Ok(Self::spawn(ingest_mirror_config, client, counters))
+    }
+
+    fn spawn(
+        ingest_mirror_config: &IngestMirrorConfig,
+        client: Arc<dyn MirrorDocsClient>,
+        counters: Arc<DocProcessorCounters>,
+    ) -> Self {
+        let (batch_tx, batch_rx) = mpsc::channel(ingest_mirror_config.max_pending_batches);
+        tokio::spawn(mirror_batches(
+            batch_rx,
+            client,
+            ingest_mirror_config.index_id.clone(),
+            ingest_mirror_config.timeout(),
+            counters.clone(),
+        ));
+        Self {
+            target_index_id: ingest_mirror_config.index_id.clone(),
+            sampling_ratio: ingest_mirror_config.percentage as f64 / 100.,
+            sampling_credit: 0.,
+            pending_docs: Vec::new(),
+            batch_tx,
+            counters,
+        }
+    }
+
+    /// Returns whether the next valid document should be mirrored. Documents are sampled evenly
+    /// rather than randomly, so that exactly the configured share of the documents is mirrored.
+    pub fn sample(&mut self) -> bool {
+        self.sampling_credit += self.sampling_ratio;
+
+        if self.sampling_credit < 1. {
+            return false;
+        }
+        self.sampling_credit -= 1.;
+        true
+    }
+
+    pub fn push(&mut self, doc: Bytes) {
+        self.pending_docs.push(doc);
+    }
+
+    /// Hands the documents buffered since the last call over to the background task, or drops
+    /// them if the task is lagging behind.
+    pub fn flush(&mut self) {
+        if self.pending_docs.is_empty() {
+            return;
+        }
+        let docs = std::mem::take(&mut self.pending_docs);
+        let num_docs = docs.len() as u64;
+
+        if let Err(TrySendError::Full(_) | TrySendError::Closed(_)) = self.batch_tx.try_send(docs) {
+            rate_limited_warn!(
+                limit_per_min = 10,
+                index_id = self.counters.index_id,
+                source_id = self.counters.source_id,
+                "ingest mirror to index `{}` is lagging behind, dropping {num_docs} documents",
+                self.target_index_id
+            );
+            self.counters.record_mirror_dropped(num_docs);
+        }
+    }
+}
+
+async fn mirror_batches(
+    mut batch_rx: mpsc::Receiver<Vec<Bytes>>,
+    client: Arc<dyn MirrorDocsClient>,
+    target_index_id: String,
+    timeout: Duration,
+    counters: Arc<DocProcessorCounters>,
+) {
+    while let Some(docs) = batch_rx.recv().await {
+        let num_docs = docs.len() as u64;
+
+        let mirror_result = tokio::time::timeout(timeout, client.mirror_docs(docs))
+            .await
+            .context("mirroring request timed out")
+            .and_then(|mirror_result| mirror_result);
+
+        match mirror_result {
+            Ok(()) => counters.record_mirrored(num_docs),
+            Err(error) => {
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    index_id = counters.index_id,
+                    source_id = counters.source_id,
+                    "failed to mirror {num_docs} documents to index `{target_index_id}`: {error:#}"
+                );
+                counters.record_mirror_dropped(num_docs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use quickwit_common::test_utils::wait_until_predicate;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockMirrorDocsClient {
+        num_calls: Arc<AtomicUsize>,
+        mirrored_docs: Arc<Mutex<Vec<Bytes>>>,
+        block: bool,
+    }
+
+    #[async_trait]
+    impl MirrorDocsClient for MockMirrorDocsClient {
+        async fn mirror_docs(&self, docs: Vec<Bytes>) -> anyhow::Result<()> {
+            self.num_calls.fetch_add(1, Ordering::Relaxed);
+
+            if self.block {
+                futures::future::pending::<()>().await;
+            }
+            self.mirrored_docs.lock().unwrap().extend(docs);
+            Ok(())
+        }
+    }
+
+    fn counters_for_test() -> Arc<DocProcessorCounters> {
+        Arc::new(DocProcessorCounters::new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_ingest_mirror_samples_docs_evenly() {
+        let ingest_mirror_config = IngestMirrorConfig {
+            percentage: 25.,
+            ..IngestMirrorConfig::for_test("my-index-v2")
+        };
+        let client = Arc::new(MockMirrorDocsClient::default());
+        let mut ingest_mirror =
+            IngestMirror::spawn(&ingest_mirror_config, client, counters_for_test());
+
+        let samples: Vec<bool> = (0..8).map(|_| ingest_mirror.sample()).collect();
+        assert_eq!(
+            samples,
+            [false, false, false, true, false, false, false, true]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_mirror_mirrors_docs() {
+        let ingest_mirror_config = IngestMirrorConfig::for_test("my-index-v2");
+        let client = MockMirrorDocsClient::default();
+        let counters = counters_for_test();
+        let mut ingest_mirror = IngestMirror::spawn(
+            &ingest_mirror_config,
+            Arc::new(client.clone()),
+            counters.clone(),
+        );
+
+        assert!(ingest_mirror.sample());
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "foo"}"#));
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "bar"}"#));
+        ingest_mirror.flush();
+
+        // Flushing without any pending doc is a no-op.
+        ingest_mirror.flush();
+
+        wait_until_predicate(
+            || async { counters.num_mirrored_docs.load(Ordering::Relaxed) == 2 },
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            *client.mirrored_docs.lock().unwrap(),
+            [
+                Bytes::from_static(br#"{"body": "foo"}"#),
+                Bytes::from_static(br#"{"body": "bar"}"#)
+            ]
+        );
+        assert_eq!(counters.num_mirror_dropped_docs.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_mirror_drops_docs_on_backpressure() {
+        let ingest_mirror_config = IngestMirrorConfig {
+            max_pending_batches: 1,
+            timeout_millis: 60_000,
+            ..IngestMirrorConfig::for_test("my-index-v2")
+        };
+        let client = MockMirrorDocsClient {
+            block: true,
+            ..Default::default()
+        };
+        let counters = counters_for_test();
+        let mut ingest_mirror = IngestMirror::spawn(
+            &ingest_mirror_config,
+            Arc::new(client.clone()),
+            counters.clone(),
+        );
+
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "foo"}"#));
+        ingest_mirror.flush();
+
+        // Wait for the background task to pick up the first batch and block on it.
+        wait_until_predicate(
+            || async { client.num_calls.load(Ordering::Relaxed) == 1 },
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "bar"}"#));
+        ingest_mirror.flush();
+        assert_eq!(counters.num_mirror_dropped_docs.load(Ordering::Relaxed), 0);
+
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "baz"}"#));
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "qux"}"#));
+        ingest_mirror.flush();
+        assert_eq!(counters.num_mirror_dropped_docs.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_mirrored_docs.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_mirror_drops_docs_on_timeout() {
+        let ingest_mirror_config = IngestMirrorConfig {
+            timeout_millis: 10,
+            ..IngestMirrorConfig::for_test("my-index-v2")
+        };
+        let client = MockMirrorDocsClient {
+            block: true,
+            ..Default::default()
+        };
+        let counters = counters_for_test();
+        let mut ingest_mirror =
+            IngestMirror::spawn(&ingest_mirror_config, Arc::new(client), counters.clone());
+
+        ingest_mirror.push(Bytes::from_static(br#"{"body": "foo"}"#));
+        ingest_mirror.flush();
+
+        wait_until_predicate(
+            || async { counters.num_mirror_dropped_docs.load(Ordering::Relaxed) == 1 },
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(counters.num_mirrored_docs.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use itertools::Itertools;
+use jsonschema::JSONSchema;
+use quickwit_config::JsonSchemaConfig;
+use quickwit_doc_mapper::JsonObject;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+const FETCH_SCHEMA_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of validation errors reported for a rejected document.
+const MAX_NUM_REPORTED_ERRORS: usize = 3;
+
+/// Label of the rejections for which the version of the schema could not be determined.
+const UNKNOWN_VERSION_LABEL: &str = "unknown";
+
+#[derive(Debug, Error)]
+#[error("document does not match JSON Schema version `{version_label}`: {reason}")]
+pub struct JsonSchemaRejection {
+    /// Version of the schema the document was validated against, used to label the rejection
+    /// metrics.
+    pub version_label: String,
+    pub reason: String,
+}
+
+impl JsonSchemaRejection {
+    fn new(version_label: impl ToString, reason: String) -> Self {
+        Self {
+            version_label: version_label.to_string(),
+            reason,
+        }
+    }
+}
+
+/// Validates the documents of a source against the version of its JSON Schema they were produced
+/// with.
+pub(super) struct JsonSchemaValidator {
+    schemas: BTreeMap<u32, JSONSchema>,
+    version_field_opt: Option<String>,
+    min_version: u32,
+    latest_version: u32,
+}
+
+impl JsonSchemaValidator {
+    /// Compiles the versions of the schema, fetching the ones referenced by URL.
+    pub async fn try_from_json_schema_config(
+        json_schema_config: JsonSchemaConfig,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_SCHEMA_TIMEOUT)
+            .build()
+            .context("failed to build JSON Schema registry HTTP client")?;
+        let mut schema_jsons = BTreeMap::new();
+
+        for version_config in json_schema_config.versions {
+            let version = version_config.version;
+            let schema_json = match (version_config.schema, version_config.url) {
+                (Some(schema_json), _) => schema_json,
+                (None, Some(url)) => fetch_schema(&client, &url).await.with_context(|| {
+                    format!("failed to fetch JSON Schema version `{version}` from `{url}`")
+                })?,
+                (None, None) => bail!("JSON Schema version `{version}` has no schema nor URL"),
+            };
+            schema_jsons.insert(version, schema_json);
+        }
+        Self::try_new(
+            schema_jsons,
+            json_schema_config.version_field,
+            json_schema_config.min_version,
+        )
+    }
+
+    fn try_new(
+        schema_jsons: BTreeMap<u32, JsonValue>,
+        version_field_opt: Option<String>,
+        min_version_opt: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        let mut schemas = BTreeMap::new();
+
+        for (version, schema_json) in schema_jsons {
+            let schema = JSONSchema::compile(&schema_json).map_err(|error| {
+                anyhow!("failed to compile JSON Schema version `{version}`: {error}")
+            })?;
+            schemas.insert(version, schema);
+        }
+        let Some(latest_version) = schemas.keys().next_back().copied() else {
+            bail!("JSON Schema must have at least one version");
+        };
+        let min_version = min_version_opt.unwrap_or(0);
+
+        Ok(Self {
+            schemas,
+            version_field_opt,
+            min_version,
+            latest_version,
+        })
+    }
+
+    /// Returns the version of the schema the document was produced with: the value of the version
+    /// field if any, the latest version otherwise.
+    fn resolve_version(&self, json_obj: &JsonObject) -> Result<u32, JsonSchemaRejection> {
+        let Some(version_json) = self
+            .version_field_opt
+            .as_ref()
+            .and_then(|version_field| json_obj.get(version_field))
+        else {
+            return Ok(self.latest_version);
+        };
+        let version_opt = match version_json {
+            JsonValue::Number(version_number) => version_number
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok()),
+            JsonValue::String(version_str) => version_str.parse().ok(),
+            _ => None,
+        };
+        let Some(version) = version_opt else {
+            return Err(JsonSchemaRejection::new(
+                UNKNOWN_VERSION_LABEL,
+                format!("invalid schema version `{version_json}`"),
+            ));
+        };
+        if version < self.min_version {
+            return Err(JsonSchemaRejection::new(
+                version,
+                format!(
+                    "schema version is no longer accepted, the oldest accepted version is `{}`",
+                    self.min_version
+                ),
+            ));
+        }
+        if !self.schemas.contains_key(&version) {
+            return Err(JsonSchemaRejection::new(
+                UNKNOWN_VERSION_LABEL,
+                format!("unknown schema version `{version}`"),
+            ));
+        }
+        Ok(version)
+    }
+
+    /// Validates the document and hands it back if it matches its schema.
+    pub fn validate(&self, json_obj: JsonObject) -> Result<JsonObject, JsonSchemaRejection> {
+        let version = self.resolve_version(&json_obj)?;
+        let schema = &self.schemas[&version];
+        let json_value = JsonValue::Object(json_obj);
+
+        if let Err(errors) = schema.validate(&json_value) {
+            let reason = errors
+                .take(MAX_NUM_REPORTED_ERRORS)
+                .map(|error| {
+                    let instance_path = error.instance_path.to_string();
+                    if instance_path.is_empty() {
+                        error.to_string()
+                    } else {
+                        format!("{instance_path}: {error}")
+                    }
+                })
+                .join(", ");
+            return Err(JsonSchemaRejection::new(version, reason));
+        }
+        let JsonValue::Object(json_obj) = json_value else {
+            unreachable!("the document should be a JSON object");
+        };
+        Ok(json_obj)
+    }
+}
+
+/// Fetches a JSON Schema over HTTP. Schema registries, such as the Confluent Schema Registry,
+/// return the schema serialized as a string in the `schema` field of an object, while plain HTTP
+/// servers return the schema itself.
+async fn fetch_schema(client: &reqwest::Client, url: &str) -> anyhow::Result<JsonValue> {
+    let response_json: JsonValue = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if let Some(JsonValue::String(schema_str)) = response_json.get("schema") {
+        let schema_json =
+            serde_json::from_str(schema_str).context("failed to parse registry schema")?;
+        return Ok(schema_json);
+    }
+    Ok(response_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn json_obj(json_value: JsonValue) -> JsonObject {
+        let JsonValue::Object(json_obj) = json_value else {
+            panic!("expected a JSON object");
+        };
+        json_obj
+    }
+
+    fn validator_for_test() -> JsonSchemaValidator {
+        let schema_jsons = BTreeMap::from([
+            (
+                1,
+                json!({
+                    "type": "object",
+                    "required": ["order_id"],
+                }),
+            ),
+            (
+                2,
+                json!({
+                    "type": "object",
+                    "required": ["order_id", "amount"],
+                    "properties": {"amount": {"type": "number"}},
+                }),
+            ),
+            (
+                3,
+                json!({
+                    "type": "object",
+                    "required": ["order_id", "amount", "currency"],
+                }),
+            ),
+        ]);
+        JsonSchemaValidator::try_new(schema_jsons, Some("schema_version".to_string()), Some(2))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_json_schema_validator_validates_against_document_version() {
+        let validator = validator_for_test();
+
+        let doc = json_obj(json!({"schema_version": 2, "order_id": 1, "amount": 10.5}));
+        assert_eq!(validator.validate(doc.clone()).unwrap(), doc);
+
+        let doc = json_obj(json!({"schema_version": "3", "order_id": 1, "amount": 10.5}));
+        let rejection = validator.validate(doc).unwrap_err();
+        assert_eq!(rejection.version_label, "3");
+        assert!(rejection
+            .reason
+            .contains("\"currency\" is a required property"));
+
+        let doc = json_obj(json!({"schema_version": 2, "order_id": 1, "amount": "ten"}));
+        let rejection = validator.validate(doc).unwrap_err();
+        assert_eq!(rejection.version_label, "2");
+        assert!(rejection.reason.starts_with("/amount: "));
+    }
+
+    #[test]
+    fn test_json_schema_validator_defaults_to_latest_version() {
+        let validator = validator_for_test();
+
+        let doc = json_obj(json!({"order_id": 1, "amount": 10.5}));
+        let rejection = validator.validate(doc).unwrap_err();
+        assert_eq!(rejection.version_label, "3");
+
+        let doc = json_obj(json!({"order_id": 1, "amount": 10.5, "currency": "EUR"}));
+        validator.validate(doc).unwrap();
+    }
+
+    #[test]
+    fn test_json_schema_validator_rejects_unsupported_versions() {
+        let validator = validator_for_test();
+
+        let doc = json_obj(json!({"schema_version": 1, "order_id": 1}));
+        let rejection = validator.validate(doc).unwrap_err();
+        assert_eq!(rejection.version_label, "1");
+        assert_eq!(
+            rejection.reason,
+            "schema version is no longer accepted, the oldest accepted version is `2`"
+        );
+
+        let doc = json_obj(json!({"schema_version": 4, "order_id": 1}));
+        let rejection = validator.validate(doc).unwrap_err();
+        assert_eq!(rejection.version_label, "unknown");
+        assert_eq!(rejection.reason, "unknown schema version `4`");
+
+        let doc = json_obj(json!({"schema_version": [2], "order_id": 1}));
+        let rejection = validator.validate(doc).unwrap_err();
+        assert_eq!(rejection.version_label, "unknown");
+        assert_eq!(rejection.reason, "invalid schema version `[2]`");
+    }
+
+    #[test]
+    fn test_json_schema_validator_invalid_schema() {
+        let schema_jsons = BTreeMap::from([(1, json!({"type": "not-a-type"}))]);
+        let error = JsonSchemaValidator::try_new(schema_jsons, None, None)
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("failed to compile JSON Schema version `1`"));
+    }
+
+    #[tokio::test]
+    async fn test_json_schema_validator_from_config() {
+        let json_schema_config = JsonSchemaConfig::for_test(json!({"required": ["order_id"]}));
+        let validator = JsonSchemaValidator::try_from_json_schema_config(json_schema_config)
+            .await
+            .unwrap();
+        validator
+            .validate(json_obj(json!({"order_id": 1})))
+            .unwrap();
+        validator
+            .validate(json_obj(json!({"amount": 10.5})))
+            .unwrap_err();
+    }
+}
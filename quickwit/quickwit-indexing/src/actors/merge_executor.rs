@@ -32,7 +32,7 @@ use quickwit_common::io::IoControls;
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_directories::UnionDirectory;
-use quickwit_doc_mapper::DocMapper;
+use quickwit_doc_mapper::{DocMapper, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME};
 use quickwit_metastore::SplitMetadata;
 use quickwit_proto::indexing::IndexingPipelineId;
 use quickwit_proto::metastore::{
@@ -42,9 +42,14 @@ use quickwit_proto::metastore::{
 use quickwit_proto::types::PipelineUid;
 use quickwit_query::get_quickwit_fastfield_normalizer_manager;
 use quickwit_query::query_ast::QueryAst;
+use serde_json::Value as JsonValue;
 use tantivy::directory::{Advice, DirectoryClone, MmapDirectory, RamDirectory};
+use tantivy::schema::{Document as DocumentTrait, NamedFieldDocument, Schema};
 use tantivy::tokenizer::TokenizerManager;
-use tantivy::{DateTime, Directory, Index, IndexMeta, IndexWriter, SegmentId, SegmentReader};
+use tantivy::{
+    DateTime, Directory, DocAddress, Index, IndexBuilder, IndexMeta, IndexReader, IndexWriter,
+    ReloadPolicy, SegmentId, SegmentReader, TantivyDocument,
+};
 use tokio::runtime::Handle;
 use tracing::{debug, info, instrument, warn};
 
@@ -121,6 +126,23 @@ impl Handler<MergeScratch> for MergeExecutor {
                 )
                 .await?
             }
+            MergeOperationType::FastFieldBackfill => {
+                assert_eq!(
+                    merge_task.splits.len(),
+                    1,
+                    "Fast fields can be backfilled only on one split."
+                );
+                assert_eq!(merge_scratch.tantivy_dirs.len(), 1);
+                let split_to_backfill = merge_task.splits[0].clone();
+                self.process_fast_field_backfill(
+                    merge_task.merge_split_id.clone(),
+                    split_to_backfill,
+                    merge_scratch.tantivy_dirs,
+                    merge_scratch.merge_scratch_directory,
+                    ctx,
+                )
+                .await?
+            }
         };
         if let Some(indexed_split) = indexed_split_opt {
             info!(
@@ -259,9 +281,50 @@ pub fn merge_split_attrs(
         uncompressed_docs_size_in_bytes,
         delete_opstamp,
         num_merge_ops: max_merge_ops(splits) + 1,
+        // The merge planner only merges splits built under the same doc mapping version.
+        doc_mapping_version: splits
+            .first()
+            .map(|split| split.doc_mapping_version)
+            .unwrap_or(0),
     }
 }
 
+/// Returns the names of the fields that are fast in `doc_mapper_schema` but not in
+/// `split_schema`.
+fn missing_fast_field_names(split_schema: &Schema, doc_mapper_schema: &Schema) -> Vec<String> {
+    doc_mapper_schema
+        .fields()
+        .filter(|(_, field_entry)| field_entry.is_fast())
+        .filter(|(_, field_entry)| {
+            split_schema
+                .get_field(field_entry.name())
+                .map(|field| !split_schema.get_field_entry(field).is_fast())
+                .unwrap_or(true)
+        })
+        .map(|(_, field_entry)| field_entry.name().to_string())
+        .collect()
+}
+
+/// Returns the names of the fields whose values cannot be recovered from the doc store of a split,
+/// which makes it impossible to reindex its documents.
+fn unrecoverable_field_names(split_schema: &Schema) -> Vec<String> {
+    if let Ok(source_field) = split_schema.get_field(SOURCE_FIELD_NAME) {
+        if split_schema.get_field_entry(source_field).is_stored() {
+            return Vec::new();
+        }
+    }
+    split_schema
+        .fields()
+        .filter(|(_, field_entry)| {
+            let field_name = field_entry.name();
+            // Internal fields are derived from the document when it is indexed.
+            (!field_name.starts_with('_') || field_name == DYNAMIC_FIELD_NAME)
+                && !field_entry.is_stored()
+        })
+        .map(|(_, field_entry)| field_entry.name().to_string())
+        .collect()
+}
+
 fn max_merge_ops(splits: &[SplitMetadata]) -> usize {
     splits
         .iter()
@@ -446,6 +509,7 @@ impl MergeExecutor {
                 uncompressed_docs_size_in_bytes,
                 delete_opstamp: last_delete_opstamp,
                 num_merge_ops: split.num_merge_ops,
+                doc_mapping_version: split.doc_mapping_version,
             },
             index: merged_index,
             split_scratch_directory: merge_scratch_directory,
@@ -454,6 +518,142 @@ impl MergeExecutor {
         Ok(Some(indexed_split))
     }
 
+    /// Rewrites a split by reindexing its documents with the current doc mapper, so that the fast
+    /// fields enabled after the split was created get materialized. Returns `None` if the split
+    /// already has all the fast fields of the doc mapper.
+    async fn process_fast_field_backfill(
+        &mut self,
+        merge_split_id: String,
+        split: SplitMetadata,
+        tantivy_dirs: Vec<Box<dyn Directory>>,
+        merge_scratch_directory: TempDirectory,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<Option<IndexedSplit>> {
+        let split_index = open_index(
+            tantivy_dirs[0].clone(),
+            self.doc_mapper.tokenizer_manager().tantivy_manager(),
+        )?;
+        let split_schema = split_index.schema();
+        let doc_mapper_schema = self.doc_mapper.schema();
+
+        let missing_fast_field_names = missing_fast_field_names(&split_schema, &doc_mapper_schema);
+        if missing_fast_field_names.is_empty() {
+            info!(
+                "Split `{}` already has all the fast fields of the doc mapping.",
+                split.split_id()
+            );
+            return Ok(None);
+        }
+        let unrecoverable_field_names = unrecoverable_field_names(&split_schema);
+        if !unrecoverable_field_names.is_empty() {
+            anyhow::bail!(
+                "failed to backfill fast fields of split `{}`: fields {:?} are not stored and \
+                 `store_source` is disabled",
+                split.split_id(),
+                unrecoverable_field_names
+            );
+        }
+        info!(missing_fast_fields=?missing_fast_field_names, "backfill-fast-fields");
+
+        let index_reader: IndexReader = split_index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = index_reader.searcher();
+
+        let output_directory = ControlledDirectory::new(
+            Box::new(MmapDirectory::open_with_madvice(
+                merge_scratch_directory.path(),
+                Advice::Sequential,
+            )?),
+            self.io_controls
+                .clone()
+                .set_kill_switch(ctx.kill_switch().clone())
+                .set_progress(ctx.progress().clone()),
+        );
+        let index_builder = IndexBuilder::new()
+            .settings(split_index.settings().clone())
+            .schema(doc_mapper_schema)
+            .tokenizers(
+                self.doc_mapper
+                    .tokenizer_manager()
+                    .tantivy_manager()
+                    .clone(),
+            )
+            .fast_field_tokenizers(
+                get_quickwit_fastfield_normalizer_manager()
+                    .tantivy_manager()
+                    .clone(),
+            );
+        let mut index_writer =
+            index_builder.single_segment_index_writer(output_directory.clone(), 15_000_000)?;
+
+        let _protect_guard = ctx.protect_zone();
+
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            for doc_id in segment_reader.doc_ids_alive() {
+                let doc_address = DocAddress::new(segment_ord as u32, doc_id);
+                let stored_doc: TantivyDocument = searcher.doc(doc_address)?;
+                let NamedFieldDocument(named_doc) = stored_doc.to_named_doc(&split_schema);
+                let mut json_doc = self.doc_mapper.doc_to_json(named_doc)?;
+
+                // The original document is reindexed as is when the source is stored.
+                if let Some(JsonValue::Object(source_json_doc)) = json_doc.remove(SOURCE_FIELD_NAME)
+                {
+                    json_doc = source_json_doc;
+                }
+                let doc_len = serde_json::to_vec(&json_doc)?.len() as u64;
+                let (_partition, doc) = self.doc_mapper.doc_from_json_obj(json_doc, doc_len)?;
+                index_writer.add_document(doc)?;
+            }
+        }
+        let backfilled_index = index_writer.finalize()?;
+        ctx.record_progress();
+
+        let Some(backfilled_segment) = backfilled_index.searchable_segments()?.into_iter().next()
+        else {
+            info!(
+                "Split `{}` does not contain any document.",
+                split.split_id()
+            );
+            return Ok(None);
+        };
+        let backfilled_segment_reader = SegmentReader::open(&backfilled_segment)?;
+        let time_range = if let Some(timestamp_field_name) = self.doc_mapper.timestamp_field_name()
+        {
+            let reader = backfilled_segment_reader
+                .fast_fields()
+                .date(timestamp_field_name)?;
+            Some(reader.min_value()..=reader.max_value())
+        } else {
+            None
+        };
+        let index_pipeline_id = IndexingPipelineId {
+            index_uid: split.index_uid,
+            node_id: split.node_id.clone(),
+            pipeline_uid: PipelineUid::new(),
+            source_id: split.source_id.clone(),
+        };
+        let indexed_split = IndexedSplit {
+            split_attrs: SplitAttrs {
+                split_id: merge_split_id,
+                partition_id: split.partition_id,
+                pipeline_id: index_pipeline_id,
+                replaced_split_ids: vec![split.split_id.clone()],
+                time_range,
+                num_docs: backfilled_segment_reader.num_docs() as u64,
+                uncompressed_docs_size_in_bytes: split.uncompressed_docs_size_in_bytes,
+                delete_opstamp: split.delete_opstamp,
+                num_merge_ops: split.num_merge_ops,
+                doc_mapping_version: self.doc_mapper.doc_mapping_version(),
+            },
+            index: backfilled_index,
+            split_scratch_directory: merge_scratch_directory,
+            controlled_directory_opt: Some(output_directory),
+        };
+        Ok(Some(indexed_split))
+    }
+
     async fn merge_split_directories(
         &self,
         union_index_meta: IndexMeta,
@@ -559,6 +759,7 @@ fn open_index<T: Into<Box<dyn Directory>>>(
 mod tests {
     use quickwit_actors::Universe;
     use quickwit_common::split_file;
+    use quickwit_config::{build_doc_mapper, ConfigFormat, DocMapping, SearchSettings};
     use quickwit_metastore::{
         ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitMetadata, StageSplitsRequestExt,
     };
@@ -887,6 +1088,138 @@ mod tests {
         .await
     }
 
+    #[tokio::test]
+    async fn test_fast_field_backfill_executor() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::with_accelerated_time();
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: status
+                type: text
+                tokenizer: raw
+              - name: ts
+                type: datetime
+                input_formats:
+                - unix_timestamp
+                fast: true
+            timestamp_field: ts
+        "#;
+        let test_sandbox =
+            TestSandbox::create("test-fast-field-backfill", doc_mapping_yaml, "", &["body"])
+                .await?;
+        let index_uid = test_sandbox.index_uid();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: index_uid.clone(),
+            node_id: "unknown".to_string(),
+            pipeline_uid: PipelineUid::for_test(0u128),
+            source_id: "unknown".to_string(),
+        };
+        test_sandbox
+            .add_documents(vec![
+                serde_json::json!({"body": "info", "status": "ok", "ts": 1624928208 }),
+                serde_json::json!({"body": "error", "status": "ko", "ts": 1634928208 }),
+            ])
+            .await?;
+        let mut metastore = test_sandbox.metastore();
+        let split_metadata = metastore
+            .list_splits(ListSplitsRequest::try_from_index_uid(index_uid.clone()).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap()
+            .split_metadata;
+
+        let merge_scratch_directory = TempDirectory::for_test();
+        let downloaded_splits_directory =
+            merge_scratch_directory.named_temp_child("downloaded-splits-")?;
+        let split_filename = split_file(split_metadata.split_id());
+        let dest_filepath = downloaded_splits_directory.path().join(&split_filename);
+        test_sandbox
+            .storage()
+            .copy_to_file(Path::new(&split_filename), &dest_filepath)
+            .await?;
+        let tantivy_dir = get_tantivy_directory_from_split_bundle(&dest_filepath).unwrap();
+
+        // The `status` field is made fast after the split was created.
+        let new_doc_mapping_yaml = doc_mapping_yaml.replace(
+            "tokenizer: raw",
+            "tokenizer: raw\n                fast: true",
+        );
+        let doc_mapping: DocMapping = ConfigFormat::Yaml.parse(new_doc_mapping_yaml.as_bytes())?;
+        let doc_mapper = build_doc_mapper(&doc_mapping, &SearchSettings::default())?;
+
+        let merge_operation = MergeOperation::new_fast_field_backfill_operation(split_metadata);
+        let merge_task = MergeTask::from_merge_operation_for_test(merge_operation);
+        let merge_scratch = MergeScratch {
+            merge_task,
+            tantivy_dirs: vec![tantivy_dir],
+            merge_scratch_directory,
+            downloaded_splits_directory,
+        };
+        let (merge_packager_mailbox, merge_packager_inbox) = universe.create_test_mailbox();
+        let merge_executor = MergeExecutor::new(
+            pipeline_id,
+            metastore,
+            doc_mapper,
+            IoControls::default(),
+            merge_packager_mailbox,
+        );
+        let (merge_executor_mailbox, merge_executor_handle) =
+            universe.spawn_builder().spawn(merge_executor);
+        merge_executor_mailbox.send_message(merge_scratch).await?;
+        merge_executor_handle.process_pending_and_observe().await;
+
+        let packager_msgs: Vec<IndexedSplitBatch> = merge_packager_inbox.drain_for_test_typed();
+        assert_eq!(packager_msgs.len(), 1);
+        let split = &packager_msgs[0].splits[0];
+        assert_eq!(split.split_attrs.num_docs, 2);
+        assert_eq!(split.split_attrs.num_merge_ops, 0);
+        assert_eq!(
+            split.split_attrs.time_range,
+            Some(
+                DateTime::from_timestamp_secs(1624928208)
+                    ..=DateTime::from_timestamp_secs(1634928208)
+            )
+        );
+        let reader = split
+            .index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher();
+        let status_column = searcher.segment_readers()[0]
+            .fast_fields()
+            .str("status")?
+            .unwrap();
+        assert_eq!(status_column.num_terms(), 2);
+
+        test_sandbox.assert_quit().await;
+        universe.assert_quit().await;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unrecoverable_field_names() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", tantivy::schema::TEXT);
+        schema_builder.add_text_field("status", tantivy::schema::STRING);
+        schema_builder.add_text_field("_field_presence", tantivy::schema::STRING);
+        let schema = schema_builder.build();
+        assert_eq!(unrecoverable_field_names(&schema), ["body", "status"]);
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", tantivy::schema::TEXT);
+        schema_builder.add_json_field(SOURCE_FIELD_NAME, tantivy::schema::STORED);
+        let schema = schema_builder.build();
+        assert!(unrecoverable_field_names(&schema).is_empty());
+    }
+
     #[tokio::test]
     async fn test_delete_all() -> anyhow::Result<()> {
         aux_test_delete_and_merge_executor(
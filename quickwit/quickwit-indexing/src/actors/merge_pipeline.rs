@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -29,6 +30,7 @@ use quickwit_common::io::{IoControls, Limiter};
 use quickwit_common::pubsub::EventBroker;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_common::KillSwitch;
+use quickwit_config::MergeThrottlingConfig;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_metastore::{
     ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitState,
@@ -279,7 +281,20 @@ impl MergePipeline {
 
         // Merge Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let merge_packager = Packager::new("MergePackager", tag_fields, merge_uploader_mailbox);
+        let term_digest_fields = self.params.doc_mapper.term_digest_named_fields()?;
+        let secondary_timestamp_fields =
+            self.params.doc_mapper.secondary_timestamp_named_fields()?;
+        let merge_packager = Packager::new(
+            "MergePackager",
+            tag_fields,
+            term_digest_fields,
+            merge_uploader_mailbox,
+        )
+        .with_secondary_timestamp_fields(secondary_timestamp_fields)
+        .with_split_validation(
+            self.params.doc_mapper.schema(),
+            self.params.split_quarantine_dir_path.clone(),
+        );
         let (merge_packager_mailbox, merge_packager_handler) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -332,6 +347,7 @@ impl MergePipeline {
             self.params.pipeline_id.clone(),
             published_splits_metadata,
             self.params.merge_policy.clone(),
+            self.params.merge_throttling_opt.clone(),
             merge_split_downloader_mailbox,
             self.params.merge_scheduler_service.clone(),
         );
@@ -473,10 +489,12 @@ pub struct MergePipelineParams {
     pub pipeline_id: IndexingPipelineId,
     pub doc_mapper: Arc<dyn DocMapper>,
     pub indexing_directory: TempDirectory,
+    pub split_quarantine_dir_path: PathBuf,
     pub metastore: MetastoreServiceClient,
     pub merge_scheduler_service: Mailbox<MergeSchedulerService>,
     pub split_store: IndexingSplitStore,
     pub merge_policy: Arc<dyn MergePolicy>,
+    pub merge_throttling_opt: Option<MergeThrottlingConfig>,
     pub max_concurrent_split_uploads: usize, //< TODO share with the indexing pipeline.
     pub merge_io_throughput_limiter_opt: Option<Limiter>,
     pub event_broker: EventBroker,
@@ -534,10 +552,12 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
             indexing_directory: TempDirectory::for_test(),
+            split_quarantine_dir_path: PathBuf::from("./quarantine"),
             metastore: MetastoreServiceClient::from_mock(mock_metastore),
             merge_scheduler_service: universe.get_or_spawn_one(),
             split_store,
             merge_policy: default_merge_policy(),
+            merge_throttling_opt: None,
             max_concurrent_split_uploads: 2,
             merge_io_throughput_limiter_opt: None,
             event_broker: Default::default(),
@@ -23,6 +23,7 @@ use std::time::Instant;
 
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
+use quickwit_config::MergeThrottlingConfig;
 use quickwit_metastore::SplitMetadata;
 use quickwit_proto::indexing::IndexingPipelineId;
 use serde::Serialize;
@@ -31,7 +32,7 @@ use time::OffsetDateTime;
 use tracing::{info, warn};
 
 use super::MergeSchedulerService;
-use crate::actors::merge_scheduler_service::schedule_merge;
+use crate::actors::merge_scheduler_service::{schedule_merge, MergeBudget};
 use crate::actors::MergeSplitDownloader;
 use crate::merge_policy::MergeOperation;
 use crate::models::NewSplits;
@@ -41,7 +42,10 @@ use crate::MergePolicy;
 pub struct MergePlanner {
     /// A young split is a split that has not reached maturity
     /// yet and can be candidate to merge operations.
-    partitioned_young_splits: HashMap<u64, Vec<SplitMetadata>>,
+    ///
+    /// Young splits are grouped by partition ID and doc mapping version: splits built under
+    /// different versions of the doc mapping have different schemas and must not be merged.
+    partitioned_young_splits: HashMap<(u64, u64), Vec<SplitMetadata>>,
 
     /// This set contains all of the split ids that we "acknowledged".
     /// The point of this set is to rapidly dismiss redundant `NewSplit` message.
@@ -62,6 +66,9 @@ pub struct MergePlanner {
     known_split_ids_recompute_attempt_id: usize,
 
     merge_policy: Arc<dyn MergePolicy>,
+    /// When set, the merge operations are accounted against the concurrency budget of their time
+    /// bucket by the merge scheduler.
+    merge_throttling_opt: Option<MergeThrottlingConfig>,
     merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
     merge_scheduler_service: Mailbox<MergeSchedulerService>,
 
@@ -156,6 +163,7 @@ impl MergePlanner {
         pipeline_id: IndexingPipelineId,
         published_splits: Vec<SplitMetadata>,
         merge_policy: Arc<dyn MergePolicy>,
+        merge_throttling_opt: Option<MergeThrottlingConfig>,
         merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
         merge_scheduler_service: Mailbox<MergeSchedulerService>,
     ) -> MergePlanner {
@@ -168,6 +176,7 @@ impl MergePlanner {
             known_split_ids_recompute_attempt_id: 0,
             partitioned_young_splits: Default::default(),
             merge_policy,
+            merge_throttling_opt,
             merge_split_downloader_mailbox,
             merge_scheduler_service,
             ongoing_merge_operations_inventory: Inventory::default(),
@@ -228,7 +237,7 @@ impl MergePlanner {
     fn record_split(&mut self, new_split: SplitMetadata) {
         let splits_for_partition: &mut Vec<SplitMetadata> = self
             .partitioned_young_splits
-            .entry(new_split.partition_id)
+            .entry((new_split.partition_id, new_split.doc_mapping_version))
             .or_default();
         splits_for_partition.push(new_split);
     }
@@ -280,8 +289,20 @@ impl MergePlanner {
         // The merge scheduler has the merit of knowing about merge operations from other
         // index as well.
         let merge_ops = self.compute_merge_ops(ctx).await?;
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
         for merge_operation in merge_ops {
             info!(merge_operation=?merge_operation, "schedule merge operation");
+            let merge_budget_opt =
+                self.merge_throttling_opt
+                    .as_ref()
+                    .and_then(|merge_throttling| {
+                        MergeBudget::for_merge_operation(
+                            &merge_operation,
+                            merge_throttling,
+                            now_timestamp,
+                        )
+                    });
             let tracked_merge_operation = self
                 .ongoing_merge_operations_inventory
                 .track(merge_operation);
@@ -289,6 +310,7 @@ impl MergePlanner {
                 &self.merge_scheduler_service,
                 tracked_merge_operation,
                 self.merge_split_downloader_mailbox.clone(),
+                merge_budget_opt,
             )
             .await?
         }
@@ -383,6 +405,7 @@ mod tests {
             pipeline_id,
             Vec::new(),
             merge_policy,
+            None,
             merge_split_downloader_mailbox,
             universe.get_or_spawn_one(),
         );
@@ -483,6 +506,7 @@ mod tests {
             pipeline_id,
             pre_existing_splits.clone(),
             merge_policy,
+            None,
             merge_split_downloader_mailbox,
             universe.get_or_spawn_one(),
         );
@@ -520,6 +544,77 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_merge_planner_does_not_merge_splits_with_different_doc_mapping_versions(
+    ) -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let (merge_split_downloader_mailbox, merge_split_downloader_inbox) = universe
+            .spawn_ctx()
+            .create_mailbox("MergeSplitDownloader", QueueCapacity::Bounded(2));
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let pipeline_id = IndexingPipelineId {
+            index_uid: index_uid.clone(),
+            source_id: "test-source".to_string(),
+            node_id: "test-node".to_string(),
+            pipeline_uid: PipelineUid::default(),
+        };
+        let merge_policy_config = ConstWriteAmplificationMergePolicyConfig {
+            merge_factor: 2,
+            max_merge_factor: 2,
+            max_merge_ops: 3,
+            ..Default::default()
+        };
+        let indexing_settings = IndexingSettings {
+            merge_policy: MergePolicyConfig::ConstWriteAmplification(merge_policy_config),
+            ..Default::default()
+        };
+        let mut split_after_update =
+            split_metadata_for_test(&index_uid, "b_small", 0, 1_000_000, 2);
+        split_after_update.doc_mapping_version = 1;
+        let pre_existing_splits = vec![
+            split_metadata_for_test(&index_uid, "a_small", 0, 1_000_000, 2),
+            split_after_update,
+        ];
+        let merge_policy: Arc<dyn MergePolicy> = merge_policy_from_settings(&indexing_settings);
+        let merge_planner = MergePlanner::new(
+            pipeline_id,
+            pre_existing_splits,
+            merge_policy,
+            None,
+            merge_split_downloader_mailbox,
+            universe.get_or_spawn_one(),
+        );
+        let (merge_planner_mailbox, merge_planner_handle) =
+            universe.spawn_builder().spawn(merge_planner);
+        merge_planner_handle.process_pending_and_observe().await;
+
+        let merge_tasks = merge_split_downloader_inbox.drain_for_test_typed::<MergeTask>();
+        assert!(merge_tasks.is_empty());
+
+        // A new split built under the same doc mapping version can be merged.
+        let mut new_split = split_metadata_for_test(&index_uid, "c_small", 0, 1_000_000, 2);
+        new_split.doc_mapping_version = 1;
+        merge_planner_mailbox
+            .ask(NewSplits {
+                new_splits: vec![new_split],
+            })
+            .await?;
+        merge_planner_handle.process_pending_and_observe().await;
+
+        let merge_tasks = merge_split_downloader_inbox.drain_for_test_typed::<MergeTask>();
+        assert_eq!(merge_tasks.len(), 1);
+        let merged_split_ids: Vec<&str> = merge_tasks[0]
+            .splits
+            .iter()
+            .map(|split| split.split_id())
+            .sorted()
+            .collect();
+        assert_eq!(merged_split_ids, ["b_small", "c_small"]);
+
+        universe.assert_quit().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_merge_planner_dismiss_splits_from_different_pipeline_id() -> anyhow::Result<()> {
         // This test makes sure that the merge planner ignores the splits that do not belong
@@ -570,6 +665,7 @@ mod tests {
             pipeline_id,
             pre_existing_splits.clone(),
             merge_policy,
+            None,
             merge_split_downloader_mailbox,
             universe.get_or_spawn_one(),
         );
@@ -626,6 +722,7 @@ mod tests {
             pipeline_id,
             pre_existing_splits.clone(),
             merge_policy,
+            None,
             merge_split_downloader_mailbox,
             universe.get_or_spawn_one(),
         );
@@ -18,13 +18,14 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp::Reverse;
-use std::collections::binary_heap::PeekMut;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::Arc;
 
 use anyhow::Context;
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
+use quickwit_config::{IndexerConfig, MergeThrottlingConfig};
+use quickwit_proto::types::{IndexUid, SourceId};
 use tantivy::TrackedObject;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::error;
@@ -32,8 +33,95 @@ use tracing::error;
 use super::MergeSplitDownloader;
 use crate::merge_policy::{MergeOperation, MergeTask};
 
+/// The scratch space of merges is accounted for per indexing pipeline, identified by its index
+/// and source.
+type PipelineKey = (IndexUid, SourceId);
+
+/// A merge needs enough local disk space to hold both the splits it downloads and the merged split
+/// it writes and packages before upload.
+const MERGE_SCRATCH_SPACE_FACTOR: u64 = 2;
+
+fn estimate_merge_scratch_space(merge_operation: &MergeOperation) -> u64 {
+    merge_operation.total_num_bytes() * MERGE_SCRATCH_SPACE_FACTOR
+}
+
+fn merge_pipeline_key(merge_operation: &MergeOperation) -> Option<PipelineKey> {
+    let split = merge_operation.splits.first()?;
+    Some((split.index_uid.clone(), split.source_id.clone()))
+}
+
+/// Time bucket of a merge operation. The merges of the recent and of the historical splits of an
+/// index can be given separate concurrency budgets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MergeTimeBucket {
+    Recent,
+    Historical,
+}
+
+type MergeBudgetKey = (IndexUid, MergeTimeBucket);
+
+/// Concurrency budget a merge operation is accounted against.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeBudget {
+    pub index_uid: IndexUid,
+    pub time_bucket: MergeTimeBucket,
+    pub max_concurrent_merges: usize,
+}
+
+impl MergeBudget {
+    /// Returns the budget of `merge_operation` according to the merge throttling config of its
+    /// index, or `None` if its time bucket is not throttled.
+    ///
+    /// A merge is recent if the most recent document of its splits is younger than the recent
+    /// period of the config. Merges of splits without time range are never throttled.
+    pub fn for_merge_operation(
+        merge_operation: &MergeOperation,
+        merge_throttling_config: &MergeThrottlingConfig,
+        now_timestamp: i64,
+    ) -> Option<MergeBudget> {
+        let index_uid = merge_operation.splits.first()?.index_uid.clone();
+        let max_timestamp = merge_operation
+            .splits
+            .iter()
+            .filter_map(|split| split.time_range.as_ref())
+            .map(|time_range| *time_range.end())
+            .max()?;
+        let recent_period = merge_throttling_config.recent_period().ok()?;
+        let recent_threshold = now_timestamp - recent_period.as_secs() as i64;
+
+        let (time_bucket, max_concurrent_merges_opt) = if max_timestamp >= recent_threshold {
+            (
+                MergeTimeBucket::Recent,
+                merge_throttling_config.max_concurrent_recent_merges,
+            )
+        } else {
+            (
+                MergeTimeBucket::Historical,
+                merge_throttling_config.max_concurrent_historical_merges,
+            )
+        };
+        Some(MergeBudget {
+            index_uid,
+            time_bucket,
+            max_concurrent_merges: max_concurrent_merges_opt?.get(),
+        })
+    }
+
+    fn key(&self) -> MergeBudgetKey {
+        (self.index_uid.clone(), self.time_bucket)
+    }
+}
+
+#[derive(Debug)]
+struct ScratchSpaceReservation {
+    pipeline_key: PipelineKey,
+    num_bytes: u64,
+}
+
 pub struct MergePermit {
     _semaphore_permit: Option<OwnedSemaphorePermit>,
+    scratch_space_reservation_opt: Option<ScratchSpaceReservation>,
+    merge_budget_key_opt: Option<MergeBudgetKey>,
     merge_scheduler_mailbox: Option<Mailbox<MergeSchedulerService>>,
 }
 
@@ -42,6 +130,8 @@ impl MergePermit {
     pub fn for_test() -> MergePermit {
         MergePermit {
             _semaphore_permit: None,
+            scratch_space_reservation_opt: None,
+            merge_budget_key_opt: None,
             merge_scheduler_mailbox: None,
         }
     }
@@ -52,8 +142,12 @@ impl Drop for MergePermit {
         let Some(merge_scheduler_mailbox) = self.merge_scheduler_mailbox.take() else {
             return;
         };
+        let permit_released = PermitReleased {
+            scratch_space_reservation_opt: self.scratch_space_reservation_opt.take(),
+            merge_budget_key_opt: self.merge_budget_key_opt.take(),
+        };
         if merge_scheduler_mailbox
-            .send_message_with_high_priority(PermitReleased)
+            .send_message_with_high_priority(permit_released)
             .is_err()
         {
             error!("merge scheduler service is dead");
@@ -65,8 +159,13 @@ pub async fn schedule_merge(
     merge_scheduler_service: &Mailbox<MergeSchedulerService>,
     merge_operation: TrackedObject<MergeOperation>,
     merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+    merge_budget_opt: Option<MergeBudget>,
 ) -> anyhow::Result<()> {
-    let schedule_merge = ScheduleMerge::new(merge_operation, merge_split_downloader_mailbox);
+    let schedule_merge = ScheduleMerge::new(
+        merge_operation,
+        merge_split_downloader_mailbox,
+        merge_budget_opt,
+    );
     // TODO add backpressure.
     merge_scheduler_service
         .ask(schedule_merge)
@@ -80,6 +179,7 @@ struct ScheduledMerge {
     id: u64, //< just for total ordering.
     merge_operation: TrackedObject<MergeOperation>,
     split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+    merge_budget_opt: Option<MergeBudget>,
 }
 
 impl ScheduledMerge {
@@ -108,8 +208,84 @@ impl Ord for ScheduledMerge {
     }
 }
 
+/// Keeps track of the local disk space reserved by the ongoing merges for their scratch
+/// directories, per pipeline and for the whole node.
+#[derive(Default)]
+struct MergeScratchSpace {
+    per_pipeline_quota_opt: Option<u64>,
+    total_quota_opt: Option<u64>,
+    reserved_bytes_per_pipeline: HashMap<PipelineKey, u64>,
+    total_reserved_bytes: u64,
+}
+
+impl MergeScratchSpace {
+    /// Returns whether a merge requiring `num_bytes` of scratch space can start right away.
+    ///
+    /// A merge that does not fit in a quota is still admitted if nothing else is reserved
+    /// against that quota. Otherwise, a merge larger than the quota would never be executed.
+    fn can_reserve(&self, pipeline_key: &PipelineKey, num_bytes: u64) -> bool {
+        if let Some(per_pipeline_quota) = self.per_pipeline_quota_opt {
+            let pipeline_reserved_bytes = self
+                .reserved_bytes_per_pipeline
+                .get(pipeline_key)
+                .copied()
+                .unwrap_or(0);
+            if pipeline_reserved_bytes > 0
+                && pipeline_reserved_bytes + num_bytes > per_pipeline_quota
+            {
+                return false;
+            }
+        }
+        if let Some(total_quota) = self.total_quota_opt {
+            if self.total_reserved_bytes > 0 && self.total_reserved_bytes + num_bytes > total_quota
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn reserve(&mut self, pipeline_key: PipelineKey, num_bytes: u64) -> ScratchSpaceReservation {
+        crate::metrics::INDEXER_METRICS
+            .merge_scratch_space_bytes
+            .with_label_values([&pipeline_key.0.index_id])
+            .add(num_bytes as i64);
+        *self
+            .reserved_bytes_per_pipeline
+            .entry(pipeline_key.clone())
+            .or_default() += num_bytes;
+        self.total_reserved_bytes += num_bytes;
+        ScratchSpaceReservation {
+            pipeline_key,
+            num_bytes,
+        }
+    }
+
+    fn release(&mut self, reservation: ScratchSpaceReservation) {
+        let ScratchSpaceReservation {
+            pipeline_key,
+            num_bytes,
+        } = reservation;
+        crate::metrics::INDEXER_METRICS
+            .merge_scratch_space_bytes
+            .with_label_values([&pipeline_key.0.index_id])
+            .sub(num_bytes as i64);
+        if let Some(pipeline_reserved_bytes) =
+            self.reserved_bytes_per_pipeline.get_mut(&pipeline_key)
+        {
+            *pipeline_reserved_bytes = pipeline_reserved_bytes.saturating_sub(num_bytes);
+
+            if *pipeline_reserved_bytes == 0 {
+                self.reserved_bytes_per_pipeline.remove(&pipeline_key);
+            }
+        }
+        self.total_reserved_bytes = self.total_reserved_bytes.saturating_sub(num_bytes);
+    }
+}
+
 /// The merge scheduler service is in charge of keeping track of all scheduled merge operations,
-/// and schedule them in the best possible order, respecting the `merge_concurrency` limit.
+/// and schedule them in the best possible order, respecting the `merge_concurrency` limit, the
+/// scratch space quotas, and the concurrency budgets of the throttled indexes.
 ///
 /// This actor is not supervised and should stay as simple as possible.
 /// In particular,
@@ -123,6 +299,8 @@ pub struct MergeSchedulerService {
     pending_merge_queue: BinaryHeap<ScheduledMerge>,
     next_merge_id: u64,
     pending_merge_bytes: u64,
+    scratch_space: MergeScratchSpace,
+    num_ongoing_merges_per_budget: HashMap<MergeBudgetKey, usize>,
 }
 
 impl Default for MergeSchedulerService {
@@ -140,39 +318,96 @@ impl MergeSchedulerService {
             pending_merge_queue: BinaryHeap::default(),
             next_merge_id: 0,
             pending_merge_bytes: 0,
+            scratch_space: MergeScratchSpace::default(),
+            num_ongoing_merges_per_budget: HashMap::new(),
         }
     }
 
+    pub fn from_indexer_config(indexer_config: &IndexerConfig) -> MergeSchedulerService {
+        let mut merge_scheduler_service =
+            MergeSchedulerService::new(indexer_config.merge_concurrency.get());
+        merge_scheduler_service.scratch_space.per_pipeline_quota_opt = indexer_config
+            .merge_scratch_space_quota_per_pipeline
+            .map(|quota| quota.as_u64());
+        merge_scheduler_service.scratch_space.total_quota_opt = indexer_config
+            .merge_scratch_space_quota
+            .map(|quota| quota.as_u64());
+        merge_scheduler_service
+    }
+
     fn schedule_pending_merges(&mut self, ctx: &ActorContext<Self>) {
         // We schedule as many pending merges as we can,
         // until there are no permits available or merges to schedule.
+        // Merges that do not fit in their scratch space quota or their concurrency budget are set
+        // aside and put back in the queue afterwards, so that they do not block the merges of the
+        // other pipelines or of the other time bucket.
+        let mut delayed_merges: Vec<ScheduledMerge> = Vec::new();
+
         loop {
-            let merge_semaphore = self.merge_semaphore.clone();
-            let Some(next_merge) = self.pending_merge_queue.peek_mut() else {
+            if self.merge_semaphore.available_permits() == 0 {
+                // No permit available right away.
+                break;
+            }
+            let Some(next_merge) = self.pending_merge_queue.pop() else {
                 // No merge to schedule.
                 break;
             };
-            let Ok(semaphore_permit) = Semaphore::try_acquire_owned(merge_semaphore) else {
-                // No permit available right away.
+            if let Some(merge_budget) = &next_merge.merge_budget_opt {
+                let num_ongoing_merges = self
+                    .num_ongoing_merges_per_budget
+                    .get(&merge_budget.key())
+                    .copied()
+                    .unwrap_or(0);
+                if num_ongoing_merges >= merge_budget.max_concurrent_merges {
+                    delayed_merges.push(next_merge);
+                    continue;
+                }
+            }
+            let scratch_space_num_bytes = estimate_merge_scratch_space(&next_merge.merge_operation);
+            let pipeline_key_opt = merge_pipeline_key(&next_merge.merge_operation);
+
+            if let Some(pipeline_key) = &pipeline_key_opt {
+                if !self
+                    .scratch_space
+                    .can_reserve(pipeline_key, scratch_space_num_bytes)
+                {
+                    delayed_merges.push(next_merge);
+                    continue;
+                }
+            }
+            let Ok(semaphore_permit) = Semaphore::try_acquire_owned(self.merge_semaphore.clone())
+            else {
+                self.pending_merge_queue.push(next_merge);
                 break;
             };
+            let scratch_space_reservation_opt = pipeline_key_opt.map(|pipeline_key| {
+                self.scratch_space
+                    .reserve(pipeline_key, scratch_space_num_bytes)
+            });
+            let merge_budget_key_opt = next_merge.merge_budget_opt.as_ref().map(|merge_budget| {
+                let merge_budget_key = merge_budget.key();
+                *self
+                    .num_ongoing_merges_per_budget
+                    .entry(merge_budget_key.clone())
+                    .or_default() += 1;
+                merge_budget_key
+            });
             let merge_permit = MergePermit {
                 _semaphore_permit: Some(semaphore_permit),
+                scratch_space_reservation_opt,
+                merge_budget_key_opt,
                 merge_scheduler_mailbox: Some(ctx.mailbox().clone()),
             };
             let ScheduledMerge {
                 merge_operation,
                 split_downloader_mailbox,
                 ..
-            } = PeekMut::pop(next_merge);
+            } = next_merge;
             let merge_task = MergeTask {
                 merge_operation,
                 _merge_permit: merge_permit,
             };
             self.pending_merge_bytes -= merge_task.merge_operation.total_num_bytes();
-            crate::metrics::INDEXER_METRICS
-                .pending_merge_operations
-                .set(self.pending_merge_queue.len() as i64);
             crate::metrics::INDEXER_METRICS
                 .pending_merge_bytes
                 .set(self.pending_merge_bytes as i64);
@@ -188,6 +423,14 @@ impl MergeSchedulerService {
                 }
             }
         }
+        crate::metrics::INDEXER_METRICS
+            .delayed_merge_operations
+            .set(delayed_merges.len() as i64);
+        self.pending_merge_queue.extend(delayed_merges);
+        crate::metrics::INDEXER_METRICS
+            .pending_merge_operations
+            .set(self.pending_merge_queue.len() as i64);
+
         let num_merges =
             self.merge_concurrency as i64 - self.merge_semaphore.available_permits() as i64;
         crate::metrics::INDEXER_METRICS
@@ -212,6 +455,7 @@ struct ScheduleMerge {
     score: u64,
     merge_operation: TrackedObject<MergeOperation>,
     split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+    merge_budget_opt: Option<MergeBudget>,
 }
 
 /// The higher, the sooner we will execute the merge operation.
@@ -236,12 +480,14 @@ impl ScheduleMerge {
     pub fn new(
         merge_operation: TrackedObject<MergeOperation>,
         split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+        merge_budget_opt: Option<MergeBudget>,
     ) -> ScheduleMerge {
         let score = score_merge_operation(&merge_operation);
         ScheduleMerge {
             score,
             merge_operation,
             split_downloader_mailbox,
+            merge_budget_opt,
         }
     }
 }
@@ -259,6 +505,7 @@ impl Handler<ScheduleMerge> for MergeSchedulerService {
             score,
             merge_operation,
             split_downloader_mailbox,
+            merge_budget_opt,
         } = schedule_merge;
         let merge_id = self.next_merge_id;
         self.next_merge_id += 1;
@@ -267,6 +514,7 @@ impl Handler<ScheduleMerge> for MergeSchedulerService {
             id: merge_id,
             merge_operation,
             split_downloader_mailbox,
+            merge_budget_opt,
         };
         self.pending_merge_bytes += scheduled_merge.merge_operation.total_num_bytes();
         self.pending_merge_queue.push(scheduled_merge);
@@ -282,7 +530,10 @@ impl Handler<ScheduleMerge> for MergeSchedulerService {
 }
 
 #[derive(Debug)]
-struct PermitReleased;
+struct PermitReleased {
+    scratch_space_reservation_opt: Option<ScratchSpaceReservation>,
+    merge_budget_key_opt: Option<MergeBudgetKey>,
+}
 
 #[async_trait]
 impl Handler<PermitReleased> for MergeSchedulerService {
@@ -290,9 +541,24 @@ impl Handler<PermitReleased> for MergeSchedulerService {
 
     async fn handle(
         &mut self,
-        _: PermitReleased,
+        permit_released: PermitReleased,
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
+        if let Some(scratch_space_reservation) = permit_released.scratch_space_reservation_opt {
+            self.scratch_space.release(scratch_space_reservation);
+        }
+        if let Some(merge_budget_key) = permit_released.merge_budget_key_opt {
+            if let Some(num_ongoing_merges) = self
+                .num_ongoing_merges_per_budget
+                .get_mut(&merge_budget_key)
+            {
+                *num_ongoing_merges = num_ongoing_merges.saturating_sub(1);
+
+                if *num_ongoing_merges == 0 {
+                    self.num_ongoing_merges_per_budget.remove(&merge_budget_key);
+                }
+            }
+        }
         self.schedule_pending_merges(ctx);
         Ok(())
     }
@@ -300,8 +566,10 @@ impl Handler<PermitReleased> for MergeSchedulerService {
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroUsize;
     use std::time::Duration;
 
+    use bytesize::ByteSize;
     use quickwit_actors::Universe;
     use quickwit_metastore::SplitMetadata;
     use tantivy::Inventory;
@@ -320,6 +588,103 @@ mod tests {
         MergeOperation::new_merge_operation(splits)
     }
 
+    fn build_merge_operation_for_source(
+        source_id: &str,
+        num_splits: usize,
+        num_bytes_per_split: u64,
+    ) -> MergeOperation {
+        let splits: Vec<SplitMetadata> = std::iter::repeat_with(|| SplitMetadata {
+            index_uid: IndexUid::for_test("test-index", 0),
+            source_id: source_id.to_string(),
+            footer_offsets: num_bytes_per_split..num_bytes_per_split,
+            ..Default::default()
+        })
+        .take(num_splits)
+        .collect();
+        MergeOperation::new_merge_operation(splits)
+    }
+
+    #[test]
+    fn test_merge_scratch_space_can_reserve() {
+        let mut scratch_space = MergeScratchSpace {
+            per_pipeline_quota_opt: Some(100),
+            total_quota_opt: Some(150),
+            ..Default::default()
+        };
+        let pipeline_key_foo: PipelineKey =
+            (IndexUid::for_test("test-index", 0), "foo".to_string());
+        let pipeline_key_bar: PipelineKey =
+            (IndexUid::for_test("test-index", 0), "bar".to_string());
+
+        // A merge larger than the quotas is admitted when nothing else is reserved.
+        assert!(scratch_space.can_reserve(&pipeline_key_foo, 200));
+
+        let reservation_foo = scratch_space.reserve(pipeline_key_foo.clone(), 60);
+        assert!(scratch_space.can_reserve(&pipeline_key_foo, 40));
+        assert!(!scratch_space.can_reserve(&pipeline_key_foo, 41));
+
+        assert!(scratch_space.can_reserve(&pipeline_key_bar, 90));
+        assert!(!scratch_space.can_reserve(&pipeline_key_bar, 91));
+
+        scratch_space.release(reservation_foo);
+        assert!(scratch_space.reserved_bytes_per_pipeline.is_empty());
+        assert_eq!(scratch_space.total_reserved_bytes, 0);
+        assert!(scratch_space.can_reserve(&pipeline_key_foo, 100));
+    }
+
+    fn build_merge_operation_for_time_range(
+        end_timestamp: i64,
+        num_bytes_per_split: u64,
+    ) -> MergeOperation {
+        let splits: Vec<SplitMetadata> = std::iter::repeat_with(|| SplitMetadata {
+            index_uid: IndexUid::for_test("test-index", 0),
+            time_range: Some(end_timestamp - 3600..=end_timestamp),
+            footer_offsets: num_bytes_per_split..num_bytes_per_split,
+            ..Default::default()
+        })
+        .take(10)
+        .collect();
+        MergeOperation::new_merge_operation(splits)
+    }
+
+    #[test]
+    fn test_merge_budget_for_merge_operation() {
+        let merge_throttling_config = MergeThrottlingConfig {
+            recent_period: "1 day".to_string(),
+            max_concurrent_recent_merges: None,
+            max_concurrent_historical_merges: NonZeroUsize::new(1),
+        };
+        let now_timestamp = 10 * 86_400;
+
+        let recent_merge_operation = build_merge_operation_for_time_range(now_timestamp, 1_000);
+        assert!(MergeBudget::for_merge_operation(
+            &recent_merge_operation,
+            &merge_throttling_config,
+            now_timestamp
+        )
+        .is_none());
+
+        let historical_merge_operation =
+            build_merge_operation_for_time_range(now_timestamp - 2 * 86_400, 1_000);
+        let merge_budget = MergeBudget::for_merge_operation(
+            &historical_merge_operation,
+            &merge_throttling_config,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(merge_budget.index_uid, IndexUid::for_test("test-index", 0));
+        assert_eq!(merge_budget.time_bucket, MergeTimeBucket::Historical);
+        assert_eq!(merge_budget.max_concurrent_merges, 1);
+
+        let merge_operation_without_time_range = build_merge_operation(10, 1_000);
+        assert!(MergeBudget::for_merge_operation(
+            &merge_operation_without_time_range,
+            &merge_throttling_config,
+            now_timestamp
+        )
+        .is_none());
+    }
+
     #[test]
     fn test_score_merge_operation() {
         let score_merge_operation_aux = |num_splits, num_bytes_per_split| {
@@ -355,6 +720,7 @@ mod tests {
                 &merge_scheduler_service,
                 tracked_large_merge_operation,
                 merge_split_downloader_mailbox.clone(),
+                None,
             )
             .await
             .unwrap();
@@ -366,6 +732,7 @@ mod tests {
                 &merge_scheduler_service,
                 tracked_large_merge_operation2,
                 merge_split_downloader_mailbox.clone(),
+                None,
             )
             .await
             .unwrap();
@@ -377,6 +744,7 @@ mod tests {
                 &merge_scheduler_service,
                 tracked_large_merge_operation2,
                 merge_split_downloader_mailbox.clone(),
+                None,
             )
             .await
             .unwrap();
@@ -388,6 +756,7 @@ mod tests {
                 &merge_scheduler_service,
                 tracked_large_merge_operation2,
                 merge_split_downloader_mailbox.clone(),
+                None,
             )
             .await
             .unwrap();
@@ -399,6 +768,7 @@ mod tests {
                 &merge_scheduler_service,
                 tracked_large_merge_operation2,
                 merge_split_downloader_mailbox.clone(),
+                None,
             )
             .await
             .unwrap();
@@ -459,4 +829,161 @@ mod tests {
         }
         universe.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_merge_schedule_service_scratch_space_quota() {
+        let universe = Universe::new();
+        let indexer_config = IndexerConfig {
+            merge_concurrency: NonZeroUsize::new(3).unwrap(),
+            merge_scratch_space_quota_per_pipeline: Some(ByteSize::mb(30)),
+            ..IndexerConfig::for_test().unwrap()
+        };
+        let (merge_scheduler_service, _) = universe
+            .spawn_builder()
+            .spawn(MergeSchedulerService::from_indexer_config(&indexer_config));
+        let inventory = Inventory::new();
+
+        let (merge_split_downloader_mailbox, merge_split_downloader_inbox) =
+            universe.create_test_mailbox();
+
+        // Each merge reserves 2 x 10 x 1MB = 20MB of scratch space.
+        for (source_id, num_bytes_per_split) in [
+            ("source-foo", 1_000_000),
+            ("source-foo", 1_000_001),
+            ("source-bar", 1_000_002),
+        ] {
+            let merge_operation =
+                build_merge_operation_for_source(source_id, 10, num_bytes_per_split);
+            schedule_merge(
+                &merge_scheduler_service,
+                inventory.track(merge_operation),
+                merge_split_downloader_mailbox.clone(),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let merge_task: MergeTask = merge_split_downloader_inbox
+            .recv_typed_message::<MergeTask>()
+            .await
+            .unwrap();
+        assert_eq!(merge_task.merge_operation.splits[0].source_id, "source-foo");
+        assert_eq!(
+            merge_task.merge_operation.splits[0].footer_offsets.end,
+            1_000_000
+        );
+        // The second merge of `source-foo` exceeds its pipeline quota and must not block
+        // the merge of `source-bar`.
+        let merge_task_bar: MergeTask = merge_split_downloader_inbox
+            .recv_typed_message::<MergeTask>()
+            .await
+            .unwrap();
+        assert_eq!(
+            merge_task_bar.merge_operation.splits[0].source_id,
+            "source-bar"
+        );
+        assert!(timeout(
+            Duration::from_millis(200),
+            merge_split_downloader_inbox.recv_typed_message::<MergeTask>()
+        )
+        .await
+        .is_err());
+
+        drop(merge_task);
+
+        let merge_task: MergeTask = merge_split_downloader_inbox
+            .recv_typed_message::<MergeTask>()
+            .await
+            .unwrap();
+        assert_eq!(merge_task.merge_operation.splits[0].source_id, "source-foo");
+        assert_eq!(
+            merge_task.merge_operation.splits[0].footer_offsets.end,
+            1_000_001
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_merge_schedule_service_merge_budget() {
+        let universe = Universe::new();
+        let (merge_scheduler_service, _) = universe
+            .spawn_builder()
+            .spawn(MergeSchedulerService::new(3));
+        let inventory = Inventory::new();
+
+        let (merge_split_downloader_mailbox, merge_split_downloader_inbox) =
+            universe.create_test_mailbox();
+
+        let merge_throttling_config = MergeThrottlingConfig {
+            recent_period: "1 day".to_string(),
+            max_concurrent_recent_merges: None,
+            max_concurrent_historical_merges: NonZeroUsize::new(1),
+        };
+        let now_timestamp = 10 * 86_400;
+        let historical_timestamp = now_timestamp - 2 * 86_400;
+
+        // The historical merges are lighter, hence scheduled first, but only one of them may run
+        // at a time.
+        for (end_timestamp, num_bytes_per_split) in [
+            (historical_timestamp, 1_000),
+            (historical_timestamp, 1_001),
+            (now_timestamp, 1_000_000),
+        ] {
+            let merge_operation =
+                build_merge_operation_for_time_range(end_timestamp, num_bytes_per_split);
+            let merge_budget_opt = MergeBudget::for_merge_operation(
+                &merge_operation,
+                &merge_throttling_config,
+                now_timestamp,
+            );
+            schedule_merge(
+                &merge_scheduler_service,
+                inventory.track(merge_operation),
+                merge_split_downloader_mailbox.clone(),
+                merge_budget_opt,
+            )
+            .await
+            .unwrap();
+        }
+        let historical_merge_task: MergeTask = merge_split_downloader_inbox
+            .recv_typed_message::<MergeTask>()
+            .await
+            .unwrap();
+        assert_eq!(
+            historical_merge_task.merge_operation.splits[0]
+                .footer_offsets
+                .end,
+            1_000
+        );
+        let recent_merge_task: MergeTask = merge_split_downloader_inbox
+            .recv_typed_message::<MergeTask>()
+            .await
+            .unwrap();
+        assert_eq!(
+            recent_merge_task.merge_operation.splits[0]
+                .footer_offsets
+                .end,
+            1_000_000
+        );
+        assert!(timeout(
+            Duration::from_millis(200),
+            merge_split_downloader_inbox.recv_typed_message::<MergeTask>()
+        )
+        .await
+        .is_err());
+
+        drop(historical_merge_task);
+
+        let historical_merge_task: MergeTask = merge_split_downloader_inbox
+            .recv_typed_message::<MergeTask>()
+            .await
+            .unwrap();
+        assert_eq!(
+            historical_merge_task.merge_operation.splits[0]
+                .footer_offsets
+                .end,
+            1_001
+        );
+        universe.assert_quit().await;
+    }
 }
@@ -18,11 +18,16 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod cooperative_indexing;
+mod doc_embedding;
+mod doc_enrichment;
+mod doc_plugin;
 mod doc_processor;
 mod index_serializer;
 mod indexer;
 mod indexing_pipeline;
 mod indexing_service;
+mod ingest_mirror;
+mod json_schema_validation;
 mod merge_executor;
 mod merge_pipeline;
 mod merge_planner;
@@ -31,24 +36,32 @@ mod merge_split_downloader;
 mod packager;
 mod publisher;
 mod sequencer;
+mod upload_scheduler;
 mod uploader;
 #[cfg(feature = "vrl")]
 mod vrl_processing;
 
-pub use doc_processor::{DocProcessor, DocProcessorCounters};
+pub use doc_processor::{
+    dry_run_transform, DocProcessor, DocProcessorCounters, TransformDryRunAction,
+    TransformDryRunResult,
+};
 pub use index_serializer::IndexSerializer;
 pub use indexer::{Indexer, IndexerCounters};
 pub use indexing_pipeline::{IndexingPipeline, IndexingPipelineParams};
 pub use indexing_service::{
     IndexingService, IndexingServiceCounters, MergePipelineId, INDEXING_DIR_NAME,
+    SPLIT_QUARANTINE_DIR_NAME,
 };
 pub use merge_executor::{combine_partition_ids, merge_split_attrs, MergeExecutor};
 pub use merge_pipeline::MergePipeline;
 pub use merge_planner::MergePlanner;
-pub use merge_scheduler_service::{schedule_merge, MergePermit, MergeSchedulerService};
+pub use merge_scheduler_service::{
+    schedule_merge, MergeBudget, MergePermit, MergeSchedulerService, MergeTimeBucket,
+};
 pub use merge_split_downloader::MergeSplitDownloader;
 pub use packager::Packager;
 pub use publisher::{Publisher, PublisherCounters, PublisherType};
 pub use quickwit_proto::indexing::IndexingError;
 pub use sequencer::Sequencer;
+pub use upload_scheduler::init_upload_scheduler;
 pub use uploader::{SplitsUpdateMailbox, Uploader, UploaderCounters, UploaderType};
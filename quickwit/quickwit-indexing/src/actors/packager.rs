@@ -17,8 +17,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -31,15 +32,17 @@ use quickwit_common::runtimes::RuntimeType;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_directories::write_hotcache;
 use quickwit_doc_mapper::tag_pruning::append_to_tag_set;
+use quickwit_doc_mapper::term_digest::TermDigest;
 use quickwit_doc_mapper::NamedField;
+use quickwit_metastore::SplitStorageStats;
 use quickwit_proto::search::{
     serialize_split_fields, ListFieldType, ListFields, ListFieldsEntryResponse,
 };
 use tantivy::index::FieldMetadata;
-use tantivy::schema::{FieldType, Type};
-use tantivy::{InvertedIndexReader, ReloadPolicy, SegmentMeta};
+use tantivy::schema::{FieldType, Schema, Type};
+use tantivy::{DateTime, InvertedIndexReader, ReloadPolicy, Searcher, SegmentMeta};
 use tokio::runtime::Handle;
-use tracing::{debug, info, instrument, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 /// Maximum distinct values allowed for a tag field within a split.
 const MAX_VALUES_PER_TAG_FIELD: usize = if cfg!(any(test, feature = "testsuite")) {
@@ -49,9 +52,11 @@ const MAX_VALUES_PER_TAG_FIELD: usize = if cfg!(any(test, feature = "testsuite")
 };
 
 use crate::actors::Uploader;
+use crate::metrics::INDEXER_METRICS;
 use crate::models::{
     EmptySplit, IndexedSplit, IndexedSplitBatch, PackagedSplit, PackagedSplitBatch,
 };
+use crate::split_validation::SplitValidator;
 
 /// The role of the packager is to get an index writer and
 /// produce a split file.
@@ -59,6 +64,8 @@ use crate::models::{
 /// This includes the following steps:
 /// - commit: this step is CPU heavy
 /// - identifying the list of tags for the splits, and labelling it accordingly
+/// - computing the term digests of the configured fields
+/// - computing the time ranges of the secondary timestamp fields
 /// - creating a bundle file
 /// - computing the hotcache
 /// - appending it to the split file.
@@ -70,21 +77,51 @@ pub struct Packager {
     uploader_mailbox: Mailbox<Uploader>,
     /// List of tag fields ([`Vec<NamedField>`]) defined in the index config.
     tag_fields: Vec<NamedField>,
+    /// List of term digest fields ([`Vec<NamedField>`]) defined in the index config.
+    term_digest_fields: Vec<NamedField>,
+    /// List of secondary timestamp fields ([`Vec<NamedField>`]) defined in the index config.
+    secondary_timestamp_fields: Vec<NamedField>,
+    /// Validates the packaged splits before handing them over to the uploader.
+    split_validator_opt: Option<SplitValidator>,
 }
 
 impl Packager {
     pub fn new(
         actor_name: &'static str,
         tag_fields: Vec<NamedField>,
+        term_digest_fields: Vec<NamedField>,
         uploader_mailbox: Mailbox<Uploader>,
     ) -> Packager {
         Packager {
             actor_name,
             uploader_mailbox,
             tag_fields,
+            term_digest_fields,
+            secondary_timestamp_fields: Vec::new(),
+            split_validator_opt: None,
         }
     }
 
+    /// Records the time range of `secondary_timestamp_fields` in the packaged splits.
+    pub fn with_secondary_timestamp_fields(
+        mut self,
+        secondary_timestamp_fields: Vec<NamedField>,
+    ) -> Self {
+        self.secondary_timestamp_fields = secondary_timestamp_fields;
+        self
+    }
+
+    /// Enables the validation of the packaged splits against `expected_schema`. Splits that fail
+    /// validation are copied to `quarantine_dir_path` and fail the pipeline before being staged.
+    pub fn with_split_validation(
+        mut self,
+        expected_schema: Schema,
+        quarantine_dir_path: PathBuf,
+    ) -> Self {
+        self.split_validator_opt = Some(SplitValidator::new(expected_schema, quarantine_dir_path));
+        self
+    }
+
     pub async fn process_indexed_split(
         &self,
         split: IndexedSplit,
@@ -92,8 +129,35 @@ impl Packager {
     ) -> anyhow::Result<PackagedSplit> {
         let segment_metas = split.index.searchable_segment_metas()?;
         assert_eq!(segment_metas.len(), 1);
-        let packaged_split =
-            create_packaged_split(&segment_metas[..], split, &self.tag_fields, ctx)?;
+        let index = split.index.clone();
+        let packaged_split = create_packaged_split(
+            &segment_metas[..],
+            split,
+            &self.tag_fields,
+            &self.term_digest_fields,
+            &self.secondary_timestamp_fields,
+            ctx,
+        )?;
+        if let Some(split_validator) = &self.split_validator_opt {
+            if let Err(validation_error) = split_validator.validate(&packaged_split, &index).await {
+                let split_id = packaged_split.split_id();
+                match split_validator.quarantine(&packaged_split) {
+                    Ok(quarantine_dir_path) => error!(
+                        split_id,
+                        quarantine_dir=%quarantine_dir_path.display(),
+                        "split failed validation and was quarantined: {validation_error:?}"
+                    ),
+                    Err(quarantine_error) => error!(
+                        split_id,
+                        "split failed validation and could not be quarantined: \
+                         {validation_error:?}, {quarantine_error:?}"
+                    ),
+                }
+                INDEXER_METRICS.quarantined_splits_total.inc();
+                bail!("split `{split_id}` failed validation: {validation_error}");
+            }
+            ctx.record_progress();
+        }
         Ok(packaged_split)
     }
 }
@@ -273,10 +337,60 @@ fn try_extract_terms(
     Ok(terms)
 }
 
+/// Builds the term digest of a field from the term dictionaries of its inverted indexes.
+fn build_term_digest(inv_indexes: &[Arc<InvertedIndexReader>]) -> anyhow::Result<TermDigest> {
+    let num_terms = inv_indexes
+        .iter()
+        .map(|inv_index| inv_index.terms().num_terms())
+        .sum::<usize>();
+    let mut term_digest = TermDigest::with_num_terms(num_terms);
+    for inv_index in inv_indexes {
+        let mut terms_streamer = inv_index.terms().stream()?;
+        while let Some((term_data, _)) = terms_streamer.next() {
+            term_digest.insert(term_data);
+        }
+    }
+    Ok(term_digest)
+}
+
+/// Computes the time range of a datetime fast field across the segments of a split. Returns `None`
+/// if the field has no value in the split.
+fn compute_time_range(
+    searcher: &Searcher,
+    field_name: &str,
+) -> anyhow::Result<Option<RangeInclusive<i64>>> {
+    let mut time_range_opt: Option<RangeInclusive<DateTime>> = None;
+
+    for segment_reader in searcher.segment_readers() {
+        let Some(column) = segment_reader
+            .fast_fields()
+            .column_opt::<DateTime>(field_name)?
+        else {
+            continue;
+        };
+        if column.values.num_vals() == 0 {
+            continue;
+        }
+        let (min_value, max_value) = (column.min_value(), column.max_value());
+        time_range_opt = Some(match time_range_opt {
+            Some(time_range) => {
+                min_value.min(*time_range.start())..=max_value.max(*time_range.end())
+            }
+            None => min_value..=max_value,
+        });
+    }
+    let time_range_secs_opt = time_range_opt.map(|time_range| {
+        time_range.start().into_timestamp_secs()..=time_range.end().into_timestamp_secs()
+    });
+    Ok(time_range_secs_opt)
+}
+
 fn create_packaged_split(
     segment_metas: &[SegmentMeta],
     split: IndexedSplit,
     tag_fields: &[NamedField],
+    term_digest_fields: &[NamedField],
+    secondary_timestamp_fields: &[NamedField],
     ctx: &ActorContext<Packager>,
 ) -> anyhow::Result<PackagedSplit> {
     debug!(split_id = split.split_id(), "create-packaged-split");
@@ -311,14 +425,72 @@ fn create_packaged_split(
             }
         }
     }
+    ctx.record_progress();
 
+    debug!(
+        split_id = split.split_id(),
+        term_digest_fields =? term_digest_fields,
+        "build-term-digests"
+    );
+    let mut term_digests = BTreeMap::new();
+    for named_field in term_digest_fields {
+        let inverted_indexes = index_reader
+            .searcher()
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.inverted_index(named_field.field))
+            .collect::<Result<Vec<_>, _>>()?;
+        match build_term_digest(&inverted_indexes) {
+            Ok(term_digest) => {
+                term_digests.insert(named_field.name.clone(), term_digest);
+            }
+            Err(error) => {
+                warn!(
+                    split_id = split.split_id(),
+                    field = %named_field.name,
+                    %error,
+                    "failed to build term digest"
+                );
+            }
+        }
+    }
     ctx.record_progress();
 
+    let mut secondary_time_ranges = BTreeMap::new();
+    for named_field in secondary_timestamp_fields {
+        match compute_time_range(&index_reader.searcher(), &named_field.name) {
+            Ok(Some(time_range)) => {
+                secondary_time_ranges.insert(named_field.name.clone(), time_range);
+            }
+            Ok(None) => {}
+            Err(error) => {
+                warn!(
+                    split_id = split.split_id(),
+                    field = %named_field.name,
+                    %error,
+                    "failed to compute time range"
+                );
+            }
+        }
+    }
+
     debug!(split_id = split.split_id(), "build-hotcache");
     let mut hotcache_bytes = Vec::new();
     build_hotcache(split.split_scratch_directory.path(), &mut hotcache_bytes)?;
     ctx.record_progress();
 
+    let storage_stats_opt =
+        match compute_split_storage_stats(&index_reader.searcher(), hotcache_bytes.len()) {
+            Ok(storage_stats) => Some(storage_stats),
+            Err(error) => {
+                warn!(
+                    split_id = split.split_id(),
+                    %error,
+                    "failed to compute split storage stats"
+                );
+                None
+            }
+        };
     let serialized_split_fields = serialize_field_metadata(&fields_metadata);
 
     let packaged_split = PackagedSplit {
@@ -326,12 +498,55 @@ fn create_packaged_split(
         split_attrs: split.split_attrs,
         split_scratch_directory: split.split_scratch_directory,
         tags,
+        term_digests,
+        secondary_time_ranges,
         split_files,
         hotcache_bytes,
+        storage_stats_opt,
     };
     Ok(packaged_split)
 }
 
+/// Computes the size of the split by component from the space usage reported by tantivy.
+fn compute_split_storage_stats(
+    searcher: &Searcher,
+    hotcache_num_bytes: usize,
+) -> io::Result<SplitStorageStats> {
+    let schema = searcher.schema();
+    let mut storage_stats = SplitStorageStats {
+        hotcache_num_bytes: hotcache_num_bytes as u64,
+        ..Default::default()
+    };
+    for segment_space_usage in searcher.space_usage()?.segments() {
+        storage_stats.doc_store_num_bytes += segment_space_usage.store().total().get_bytes();
+        storage_stats.fast_fields_num_bytes +=
+            segment_space_usage.fast_fields().total().get_bytes();
+
+        let inverted_index_space_usages = [
+            segment_space_usage.termdict(),
+            segment_space_usage.postings(),
+            segment_space_usage.positions(),
+            segment_space_usage.fieldnorms(),
+        ];
+        for per_field_space_usage in inverted_index_space_usages {
+            storage_stats.postings_num_bytes += per_field_space_usage.total().get_bytes();
+        }
+        for per_field_space_usage in inverted_index_space_usages
+            .into_iter()
+            .chain([segment_space_usage.fast_fields()])
+        {
+            for (field, field_usage) in per_field_space_usage.fields() {
+                let field_name = schema.get_field_name(*field);
+                *storage_stats
+                    .per_field_num_bytes
+                    .entry(field_name.to_string())
+                    .or_default() += field_usage.total().get_bytes();
+            }
+        }
+    }
+    Ok(storage_stats)
+}
+
 /// Serializes the Split fields.
 ///
 /// `fields_metadata` has to be sorted.
@@ -453,6 +668,7 @@ mod tests {
         let mut schema_builder = Schema::builder();
         let text_field = schema_builder.add_text_field("text", TEXT);
         let timestamp_field = schema_builder.add_u64_field("timestamp", FAST);
+        let event_time_field = schema_builder.add_date_field("event_time", FAST);
         let tag_str = schema_builder.add_text_field("tag_str", STRING);
         let tag_many = schema_builder.add_text_field("tag_many", STRING);
         let tag_u64 =
@@ -487,6 +703,7 @@ mod tests {
                 let doc = doc!(
                     text_field => format!("timestamp is {timestamp:?}"),
                     timestamp_field => timestamp,
+                    event_time_field => timestamp,
                     tag_str => "value",
                     tag_many => format!("many-{num}"),
                     tag_u64 => 42u64,
@@ -528,6 +745,7 @@ mod tests {
                 replaced_split_ids: Vec::new(),
                 delete_opstamp: 0,
                 num_merge_ops: 0,
+                doc_mapping_version: 0,
             },
             index,
             split_scratch_directory,
@@ -566,7 +784,16 @@ mod tests {
                 "tag_str", "tag_many", "tag_u64", "tag_i64", "tag_f64", "tag_bool",
             ],
         );
-        let packager = Packager::new("TestPackager", tag_fields, mailbox);
+        let term_digest_fields = get_tag_fields(indexed_split.index.schema(), &["tag_many"]);
+        let secondary_timestamp_fields =
+            get_tag_fields(indexed_split.index.schema(), &["event_time"]);
+        let quarantine_dir = TempDirectory::for_test();
+        let packager = Packager::new("TestPackager", tag_fields, term_digest_fields, mailbox)
+            .with_secondary_timestamp_fields(secondary_timestamp_fields)
+            .with_split_validation(
+                indexed_split.index.schema(),
+                quarantine_dir.path().to_path_buf(),
+            );
         let (packager_mailbox, packager_handle) = universe.spawn_builder().spawn(packager);
         packager_mailbox
             .send_message(IndexedSplitBatch {
@@ -610,6 +837,63 @@ mod tests {
                     ..=DateTime::from_timestamp_secs(1628203640)
             )
         );
+        assert_eq!(split.term_digests.len(), 1);
+        let term_digest = &split.term_digests["tag_many"];
+        assert!((1..10).all(|num| term_digest.may_contain(format!("many-{num}").as_bytes())));
+        assert_eq!(
+            split.secondary_time_ranges["event_time"],
+            1628203589..=1628203640
+        );
+        let storage_stats = split.storage_stats_opt.as_ref().unwrap();
+        assert!(storage_stats.postings_num_bytes > 0);
+        assert!(storage_stats.fast_fields_num_bytes > 0);
+        assert_eq!(
+            storage_stats.hotcache_num_bytes,
+            split.hotcache_bytes.len() as u64
+        );
+        assert!(storage_stats.per_field_num_bytes["text"] > 0);
+        assert!(storage_stats.per_field_num_bytes["timestamp"] > 0);
+        universe.assert_quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_packager_quarantines_invalid_split() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::with_accelerated_time();
+        let (mailbox, inbox) = universe.create_test_mailbox();
+        let indexed_split =
+            make_indexed_split_for_test(&[DateTime::from_timestamp_secs(1628203589)])?;
+        let index_uid = indexed_split.split_attrs.pipeline_id.index_uid.clone();
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("missing_field", TEXT);
+        let expected_schema = schema_builder.build();
+
+        let quarantine_dir = TempDirectory::for_test();
+        let packager = Packager::new("TestPackager", Vec::new(), Vec::new(), mailbox)
+            .with_split_validation(expected_schema, quarantine_dir.path().to_path_buf());
+        let (packager_mailbox, packager_handle) = universe.spawn_builder().spawn(packager);
+        packager_mailbox
+            .send_message(IndexedSplitBatch {
+                splits: vec![indexed_split],
+                checkpoint_delta_opt: IndexCheckpointDelta::for_test("source_id", 10..20).into(),
+                publish_lock: PublishLock::default(),
+                publish_token_opt: None,
+                merge_task_opt: None,
+                batch_parent_span: Span::none(),
+            })
+            .await?;
+        let (exit_status, _) = packager_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Failure(_)));
+        assert!(inbox.drain_for_test().is_empty());
+
+        let split_quarantine_dir_path = quarantine_dir
+            .path()
+            .join(index_uid.to_string())
+            .join("test-split");
+        assert!(split_quarantine_dir_path.join("meta.json").exists());
+        assert!(split_quarantine_dir_path.join("hotcache").exists());
         universe.assert_quit().await;
         Ok(())
     }
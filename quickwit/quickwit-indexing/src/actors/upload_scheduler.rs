@@ -0,0 +1,246 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use bytesize::ByteSize;
+use once_cell::sync::OnceCell;
+use quickwit_common::io::{self, Limiter};
+use quickwit_common::metrics::GaugeGuard;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::metrics::INDEXER_METRICS;
+
+/// Upload scheduler shared by all the uploaders of the node.
+static UPLOAD_SCHEDULER: OnceCell<UploadScheduler> = OnceCell::new();
+
+/// Merge uploads acquire their bandwidth in chunks of this size so that a commit upload arriving
+/// in the middle of a large merge upload does not have to wait for the whole merge upload.
+const UPLOAD_CHUNK_NUM_BYTES: u64 = 8 * 1024 * 1024; // 8 MiB
+
+/// Maximum amount of time a merge upload chunk waits for the pending commit uploads to complete.
+/// Past this delay, the chunk competes with the commit uploads to avoid starving merges.
+const MAX_MERGE_UPLOAD_DEFERRAL: Duration = if cfg!(test) {
+    Duration::from_millis(500)
+} else {
+    Duration::from_secs(5)
+};
+
+/// Initializes the upload scheduler of the node with the upload bandwidth cap defined in the
+/// `IndexerConfig`. Subsequent calls are no-ops.
+pub fn init_upload_scheduler(max_upload_bandwidth_opt: Option<ByteSize>) {
+    if UPLOAD_SCHEDULER
+        .set(UploadScheduler::new(max_upload_bandwidth_opt))
+        .is_err()
+    {
+        let current_max_upload_bandwidth_opt = upload_scheduler().max_upload_bandwidth_opt;
+
+        if current_max_upload_bandwidth_opt != max_upload_bandwidth_opt {
+            warn!(
+                "upload scheduler already initialized with max upload bandwidth {:?}: ignoring \
+                 max upload bandwidth {:?}",
+                current_max_upload_bandwidth_opt, max_upload_bandwidth_opt
+            );
+        }
+    }
+}
+
+/// Returns the upload scheduler of the node. If it has not been initialized, the upload
+/// bandwidth is not capped.
+pub(crate) fn upload_scheduler() -> &'static UploadScheduler {
+    UPLOAD_SCHEDULER.get_or_init(|| UploadScheduler::new(None))
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum UploadPriority {
+    /// Uploads of freshly indexed splits: they gate the freshness of the data.
+    Commit,
+    /// Uploads of the splits produced by merge and delete operations.
+    Merge,
+}
+
+/// Caps the aggregate object storage upload bandwidth of the node and prioritizes commit uploads
+/// over merge uploads.
+///
+/// Uploaders acquire the bandwidth required to upload a split before uploading it. The bandwidth
+/// is therefore capped on average rather than instantaneously. While commit uploads are waiting
+/// for bandwidth, merge uploads yield, chunk by chunk, for at most `MAX_MERGE_UPLOAD_DEFERRAL`.
+pub(crate) struct UploadScheduler {
+    max_upload_bandwidth_opt: Option<ByteSize>,
+    limiter_opt: Option<Limiter>,
+    num_pending_commit_uploads: AtomicUsize,
+    commit_uploads_drained: Notify,
+}
+
+impl UploadScheduler {
+    fn new(max_upload_bandwidth_opt: Option<ByteSize>) -> Self {
+        Self {
+            max_upload_bandwidth_opt,
+            limiter_opt: max_upload_bandwidth_opt.map(io::limiter),
+            num_pending_commit_uploads: AtomicUsize::new(0),
+            commit_uploads_drained: Notify::new(),
+        }
+    }
+
+    /// Waits until `num_bytes` can be uploaded without exceeding the upload bandwidth cap.
+    pub async fn acquire_bandwidth(&self, priority: UploadPriority, num_bytes: u64) {
+        let Some(limiter) = &self.limiter_opt else {
+            return;
+        };
+        let pending_uploads_gauge = match priority {
+            UploadPriority::Commit => &INDEXER_METRICS.pending_commit_uploads,
+            UploadPriority::Merge => &INDEXER_METRICS.pending_merge_uploads,
+        };
+        let mut pending_uploads_gauge_guard = GaugeGuard::from_gauge(pending_uploads_gauge);
+        pending_uploads_gauge_guard.add(1);
+
+        match priority {
+            UploadPriority::Commit => {
+                let _pending_commit_upload_guard = PendingCommitUploadGuard::new(self);
+                limiter.consume(num_bytes as usize).await;
+            }
+            UploadPriority::Merge => {
+                let mut num_remaining_bytes = num_bytes;
+
+                while num_remaining_bytes > 0 {
+                    self.wait_for_pending_commit_uploads().await;
+
+                    let chunk_num_bytes = num_remaining_bytes.min(UPLOAD_CHUNK_NUM_BYTES);
+                    limiter.consume(chunk_num_bytes as usize).await;
+                    num_remaining_bytes -= chunk_num_bytes;
+                }
+            }
+        }
+    }
+
+    async fn wait_for_pending_commit_uploads(&self) {
+        let deadline = Instant::now() + MAX_MERGE_UPLOAD_DEFERRAL;
+
+        loop {
+            // The future must be created before checking the counter so that we don't miss a
+            // notification sent in between.
+            let commit_uploads_drained = self.commit_uploads_drained.notified();
+
+            if self.num_pending_commit_uploads.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            if tokio::time::timeout_at(deadline, commit_uploads_drained)
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+struct PendingCommitUploadGuard<'a> {
+    upload_scheduler: &'a UploadScheduler,
+}
+
+impl<'a> PendingCommitUploadGuard<'a> {
+    fn new(upload_scheduler: &'a UploadScheduler) -> Self {
+        upload_scheduler
+            .num_pending_commit_uploads
+            .fetch_add(1, Ordering::AcqRel);
+        Self { upload_scheduler }
+    }
+}
+
+impl Drop for PendingCommitUploadGuard<'_> {
+    fn drop(&mut self) {
+        let num_pending_commit_uploads = self
+            .upload_scheduler
+            .num_pending_commit_uploads
+            .fetch_sub(1, Ordering::AcqRel);
+
+        if num_pending_commit_uploads == 1 {
+            self.upload_scheduler
+                .commit_uploads_drained
+                .notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_upload_scheduler_no_limit() {
+        let upload_scheduler = UploadScheduler::new(None);
+        let _pending_commit_upload_guard = PendingCommitUploadGuard::new(&upload_scheduler);
+
+        let start = Instant::now();
+        upload_scheduler
+            .acquire_bandwidth(UploadPriority::Merge, ByteSize::gb(10).as_u64())
+            .await;
+        assert!(start.elapsed() <= Duration::from_millis(5));
+    }
+
+    #[tokio::test]
+    async fn test_upload_scheduler_caps_bandwidth() {
+        let upload_scheduler = UploadScheduler::new(Some(ByteSize::mb(2)));
+
+        let start = Instant::now();
+        // We upload 200 KB at 2 MB/s.
+        for _ in 0..2 {
+            upload_scheduler
+                .acquire_bandwidth(UploadPriority::Commit, 100_000)
+                .await;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(50));
+        assert!(elapsed <= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_upload_scheduler_prioritizes_commit_uploads() {
+        let upload_scheduler = UploadScheduler::new(Some(ByteSize::gb(10)));
+        let pending_commit_upload_guard = PendingCommitUploadGuard::new(&upload_scheduler);
+
+        let merge_upload = upload_scheduler.acquire_bandwidth(UploadPriority::Merge, 1_000);
+        tokio::pin!(merge_upload);
+
+        tokio::time::timeout(Duration::from_millis(50), &mut merge_upload)
+            .await
+            .unwrap_err();
+
+        drop(pending_commit_upload_guard);
+
+        tokio::time::timeout(Duration::from_millis(50), merge_upload)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_upload_scheduler_does_not_starve_merge_uploads() {
+        let upload_scheduler = UploadScheduler::new(Some(ByteSize::gb(10)));
+        let _pending_commit_upload_guard = PendingCommitUploadGuard::new(&upload_scheduler);
+
+        let start = Instant::now();
+        upload_scheduler
+            .acquire_bandwidth(UploadPriority::Merge, 1_000)
+            .await;
+        assert!(start.elapsed() >= MAX_MERGE_UPLOAD_DEFERRAL);
+    }
+}
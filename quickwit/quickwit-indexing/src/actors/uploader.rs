@@ -30,19 +30,22 @@ use itertools::Itertools;
 use once_cell::sync::OnceCell;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::pubsub::EventBroker;
+use quickwit_common::shared_consts::{SPLIT_FIELDS_FILE_NAME, SPLIT_MANIFEST_FILE_NAME};
 use quickwit_common::spawn_named_task;
+use quickwit_doc_mapper::term_digest::SplitTermDigests;
 use quickwit_metastore::checkpoint::IndexCheckpointDelta;
-use quickwit_metastore::{SplitMetadata, StageSplitsRequestExt};
+use quickwit_metastore::{SplitManifest, SplitMetadata, StageSplitsRequestExt};
 use quickwit_proto::metastore::{MetastoreService, MetastoreServiceClient, StageSplitsRequest};
 use quickwit_proto::search::{ReportSplit, ReportSplitsRequest};
 use quickwit_proto::types::{IndexUid, PublishToken};
-use quickwit_storage::SplitPayloadBuilder;
+use quickwit_storage::{PutPayload, SplitPayload, SplitPayloadBuilder};
 use serde::Serialize;
 use tokio::sync::oneshot::Sender;
 use tokio::sync::{oneshot, Semaphore, SemaphorePermit};
 use tracing::{debug, info, instrument, warn, Instrument, Span};
 
 use crate::actors::sequencer::{Sequencer, SequencerCommand};
+use crate::actors::upload_scheduler::{upload_scheduler, UploadPriority};
 use crate::actors::Publisher;
 use crate::merge_policy::{MergePolicy, MergeTask};
 use crate::metrics::INDEXER_METRICS;
@@ -67,6 +70,15 @@ pub enum UploaderType {
     DeleteUploader,
 }
 
+impl UploaderType {
+    fn upload_priority(&self) -> UploadPriority {
+        match self {
+            UploaderType::IndexUploader => UploadPriority::Commit,
+            UploaderType::MergeUploader | UploaderType::DeleteUploader => UploadPriority::Merge,
+        }
+    }
+}
+
 /// [`SplitsUpdateMailbox`] wraps either a [`Mailbox<Sequencer>`] or [`Mailbox<Publisher>`].
 /// It makes it possible to send a [`SplitsUpdate`] either to the [`Sequencer`] or directly
 /// to [`Publisher`]. It is used in combination with `SplitsUpdateSender` that will do the send.
@@ -301,6 +313,7 @@ impl Handler<PackagedSplitBatch> for Uploader {
         let merge_policy = self.merge_policy.clone();
         debug!(split_ids=?split_ids, "start-stage-and-store-splits");
         let event_broker = self.event_broker.clone();
+        let upload_priority = self.uploader_type.upload_priority();
         spawn_named_task(
             async move {
                 fail_point!("uploader:intask:before");
@@ -316,17 +329,17 @@ impl Handler<PackagedSplitBatch> for Uploader {
                         return Ok(());
                     }
 
-                    let split_streamer = SplitPayloadBuilder::get_split_payload(
-                        &packaged_split.split_files,
-                        &packaged_split.serialized_split_fields,
-                        &packaged_split.hotcache_bytes,
-                    )?;
-                    let split_metadata = create_split_metadata(
+                    let mut split_metadata = create_split_metadata(
                         &merge_policy,
                         &packaged_split.split_attrs,
                         packaged_split.tags.clone(),
-                        split_streamer.footer_range.start..split_streamer.footer_range.end,
+                        packaged_split.term_digests.keys().cloned().collect(),
+                        packaged_split.secondary_time_ranges.clone(),
+                        0..0,
+                        packaged_split.storage_stats_opt.clone(),
                     );
+                    let split_streamer = create_split_payload(packaged_split, &split_metadata)?;
+                    split_metadata.footer_offsets = split_streamer.footer_range.clone();
 
                     report_splits.push(ReportSplit {
                         storage_uri: split_store.remote_uri().to_string(),
@@ -353,6 +366,7 @@ impl Handler<PackagedSplitBatch> for Uploader {
                         &packaged_split,
                         &metadata,
                         &split_store,
+                        upload_priority,
                         counters.clone(),
                     )
                     .await;
@@ -453,6 +467,33 @@ fn make_publish_operation(
     }
 }
 
+/// Builds the payload of the split file, which embeds the [`SplitManifest`] of the split. The
+/// manifest does not hold the footer offsets, so the payload is the same whether `split_metadata`
+/// holds them or not.
+fn create_split_payload(
+    packaged_split: &PackagedSplit,
+    split_metadata: &SplitMetadata,
+) -> anyhow::Result<SplitPayload> {
+    let split_manifest = SplitManifest::new(
+        split_metadata.clone(),
+        packaged_split.split_attrs.replaced_split_ids.clone(),
+    );
+    let mut split_payload_builder = SplitPayloadBuilder::default();
+
+    for split_file in &packaged_split.split_files {
+        split_payload_builder.add_file(split_file)?;
+    }
+    split_payload_builder.add_payload(
+        SPLIT_FIELDS_FILE_NAME.to_string(),
+        Box::new(packaged_split.serialized_split_fields.clone()),
+    );
+    split_payload_builder.add_payload(
+        SPLIT_MANIFEST_FILE_NAME.to_string(),
+        Box::new(split_manifest.serialize()),
+    );
+    split_payload_builder.finalize(&packaged_split.hotcache_bytes)
+}
+
 #[instrument(
     level = "info"
     name = "upload",
@@ -463,13 +504,21 @@ async fn upload_split(
     packaged_split: &PackagedSplit,
     split_metadata: &SplitMetadata,
     split_store: &IndexingSplitStore,
+    upload_priority: UploadPriority,
     counters: UploaderCounters,
 ) -> anyhow::Result<()> {
-    let split_streamer = SplitPayloadBuilder::get_split_payload(
-        &packaged_split.split_files,
-        &packaged_split.serialized_split_fields,
-        &packaged_split.hotcache_bytes,
-    )?;
+    if !packaged_split.term_digests.is_empty() {
+        // The digests are uploaded before the split, so they are available as soon as the split
+        // is published.
+        let term_digests_bytes = SplitTermDigests::serialize(&packaged_split.term_digests);
+        split_store
+            .store_term_digests(split_metadata.split_id(), term_digests_bytes)
+            .await?;
+    }
+    let split_streamer = create_split_payload(packaged_split, split_metadata)?;
+    upload_scheduler()
+        .acquire_bandwidth(upload_priority, split_streamer.len())
+        .await;
 
     split_store
         .store_split(
@@ -484,12 +533,14 @@ async fn upload_split(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
     use std::path::PathBuf;
     use std::time::Duration;
 
     use quickwit_actors::{ObservationType, Universe};
     use quickwit_common::pubsub::EventSubscriber;
     use quickwit_common::temp_dir::TempDirectory;
+    use quickwit_doc_mapper::term_digest::TermDigest;
     use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
     use quickwit_proto::indexing::IndexingPipelineId;
     use quickwit_proto::metastore::{EmptyResponse, MockMetastoreService};
@@ -563,12 +614,19 @@ mod tests {
                         split_id: "test-split".to_string(),
                         delete_opstamp: 10,
                         num_merge_ops: 0,
+                        doc_mapping_version: 0,
                     },
                     serialized_split_fields: Vec::new(),
                     split_scratch_directory,
                     tags: Default::default(),
+                    term_digests: BTreeMap::from([(
+                        "trace_id".to_string(),
+                        TermDigest::with_num_terms(1),
+                    )]),
                     hotcache_bytes: Vec::new(),
+                    storage_stats_opt: None,
                     split_files: Vec::new(),
+                    secondary_time_ranges: Default::default(),
                 }],
                 checkpoint_delta_opt,
                 PublishLock::default(),
@@ -610,9 +668,25 @@ mod tests {
             SourceCheckpointDelta::from_range(3..15)
         );
         assert!(replaced_split_ids.is_empty());
+        assert_eq!(
+            new_splits[0].term_digest_fields,
+            BTreeSet::from(["trace_id".to_string()])
+        );
         let mut files = ram_storage.list_files().await;
         files.sort();
-        assert_eq!(&files, &[PathBuf::from("test-split.split")]);
+        assert_eq!(
+            &files,
+            &[
+                PathBuf::from("test-split.digests"),
+                PathBuf::from("test-split.split")
+            ]
+        );
+
+        let split_manifest = SplitManifest::fetch(&ram_storage, "test-split")
+            .await?
+            .unwrap();
+        assert_eq!(split_manifest.split_metadata, new_splits[0]);
+        assert!(split_manifest.replaced_split_ids.is_empty());
         universe.assert_quit().await;
         Ok(())
     }
@@ -675,12 +749,16 @@ mod tests {
                 ],
                 delete_opstamp: 0,
                 num_merge_ops: 0,
+                doc_mapping_version: 0,
             },
             serialized_split_fields: Vec::new(),
             split_scratch_directory: split_scratch_directory_1,
             tags: Default::default(),
+            term_digests: Default::default(),
             split_files: Vec::new(),
             hotcache_bytes: Vec::new(),
+            storage_stats_opt: None,
+            secondary_time_ranges: Default::default(),
         };
         let package_split_2 = PackagedSplit {
             split_attrs: SplitAttrs {
@@ -699,12 +777,16 @@ mod tests {
                 ],
                 delete_opstamp: 0,
                 num_merge_ops: 0,
+                doc_mapping_version: 0,
             },
             serialized_split_fields: Vec::new(),
             split_scratch_directory: split_scratch_directory_2,
             tags: Default::default(),
+            term_digests: Default::default(),
             split_files: Vec::new(),
             hotcache_bytes: Vec::new(),
+            storage_stats_opt: None,
+            secondary_time_ranges: Default::default(),
         };
         uploader_mailbox
             .send_message(PackagedSplitBatch::new(
@@ -762,6 +844,14 @@ mod tests {
                 PathBuf::from("test-split-2.split")
             ]
         );
+        let split_manifest = SplitManifest::fetch(&ram_storage, "test-split-1")
+            .await?
+            .unwrap();
+        assert_eq!(split_manifest.split_metadata, new_splits[0]);
+        assert_eq!(
+            split_manifest.replaced_split_ids,
+            ["replaced-split-1", "replaced-split-2"]
+        );
         universe.assert_quit().await;
         Ok(())
     }
@@ -819,12 +909,16 @@ mod tests {
                         split_id: "test-split".to_string(),
                         delete_opstamp: 10,
                         num_merge_ops: 0,
+                        doc_mapping_version: 0,
                     },
                     serialized_split_fields: Vec::new(),
                     split_scratch_directory,
                     tags: Default::default(),
+                    term_digests: Default::default(),
                     hotcache_bytes: Vec::new(),
+                    storage_stats_opt: None,
                     split_files: Vec::new(),
+                    secondary_time_ranges: Default::default(),
                 }],
                 checkpoint_delta_opt,
                 PublishLock::default(),
@@ -998,12 +1092,16 @@ mod tests {
                         split_id: SPLIT_ULID_STR.to_string(),
                         delete_opstamp: 10,
                         num_merge_ops: 0,
+                        doc_mapping_version: 0,
                     },
                     serialized_split_fields: Vec::new(),
                     split_scratch_directory,
                     tags: Default::default(),
+                    term_digests: Default::default(),
                     hotcache_bytes: Vec::new(),
+                    storage_stats_opt: None,
                     split_files: Vec::new(),
+                    secondary_time_ranges: Default::default(),
                 }],
                 checkpoint_delta_opt,
                 PublishLock::default(),
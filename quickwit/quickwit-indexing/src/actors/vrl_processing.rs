@@ -19,7 +19,8 @@
 
 use std::collections::BTreeMap;
 
-use quickwit_config::TransformConfig;
+use anyhow::Context;
+use quickwit_config::{TransformConfig, TransformStageAction};
 use tracing::warn;
 use vrl::compiler::runtime::Runtime;
 pub use vrl::compiler::runtime::Terminate as VrlTerminate;
@@ -43,21 +44,141 @@ impl VrlDoc {
     }
 }
 
+/// Outcome of the transform of a document.
+pub(super) enum VrlOutcome {
+    /// The document must be indexed in the source's index.
+    Index(VrlDoc),
+    /// The document was dropped by a stage.
+    Drop,
+    /// The document must be indexed in another index.
+    Route { index_id: String, vrl_doc: VrlDoc },
+}
+
+enum VrlStageAction {
+    Mutate(Program),
+    Drop,
+    Route(String),
+}
+
+struct VrlStage {
+    name: String,
+    condition_opt: Option<Program>,
+    action: VrlStageAction,
+}
+
 pub(super) struct VrlProgram {
-    program: Program,
-    timezone: TimeZone,
-    runtime: Runtime,
-    metadata: VrlValue,
-    secrets: VrlSecrets,
+    /// Program compiled from the transform script, if any.
+    program_opt: Option<Program>,
+    stages: Vec<VrlStage>,
+    runtime: VrlRuntime,
 }
 
 impl VrlProgram {
-    pub fn transform_doc(&mut self, vrl_doc: VrlDoc) -> Result<VrlDoc, DocProcessorError> {
+    pub fn transform_doc(&mut self, vrl_doc: VrlDoc) -> Result<VrlOutcome, DocProcessorError> {
+        self.transform_doc_with_trace(vrl_doc, None)
+    }
+
+    /// Runs the script, then the stages, on the document. The names of the stages applied to
+    /// the document are appended to `applied_stages_opt`.
+    pub fn transform_doc_with_trace(
+        &mut self,
+        vrl_doc: VrlDoc,
+        mut applied_stages_opt: Option<&mut Vec<String>>,
+    ) -> Result<VrlOutcome, DocProcessorError> {
         let VrlDoc {
             mut vrl_value,
             num_bytes,
         } = vrl_doc;
 
+        if let Some(program) = &self.program_opt {
+            vrl_value = self.runtime.resolve(vrl_value, program)?;
+        }
+        for stage in &self.stages {
+            if let Some(condition) = &stage.condition_opt {
+                let condition_value = self.runtime.resolve(vrl_value.clone(), condition)?;
+
+                if condition_value != VrlValue::Boolean(true) {
+                    continue;
+                }
+            }
+            if let Some(applied_stages) = applied_stages_opt.as_mut() {
+                applied_stages.push(stage.name.clone());
+            }
+            match &stage.action {
+                VrlStageAction::Mutate(program) => {
+                    vrl_value = self.runtime.resolve(vrl_value, program)?;
+                }
+                VrlStageAction::Drop => {
+                    return Ok(VrlOutcome::Drop);
+                }
+                VrlStageAction::Route(index_id) => {
+                    return Ok(VrlOutcome::Route {
+                        index_id: index_id.clone(),
+                        vrl_doc: VrlDoc::new(vrl_value, num_bytes),
+                    });
+                }
+            }
+        }
+        Ok(VrlOutcome::Index(VrlDoc::new(vrl_value, num_bytes)))
+    }
+
+    pub fn try_from_transform_config(transform_config: TransformConfig) -> anyhow::Result<Self> {
+        let (program, timezone) = transform_config.compile_vrl_script()?;
+        let program_opt = Some(program).filter(|_| transform_config.has_script());
+        let mut stages = Vec::with_capacity(transform_config.stages().len());
+
+        for stage_config in transform_config.stages() {
+            let condition_opt = stage_config.compile_condition()?;
+            let action = match stage_config.action {
+                TransformStageAction::Mutate => {
+                    let program = stage_config
+                        .compile_script()?
+                        .context("`mutate` transform stage should have a script")?;
+                    VrlStageAction::Mutate(program)
+                }
+                TransformStageAction::Drop => VrlStageAction::Drop,
+                TransformStageAction::Route => {
+                    let index_id = stage_config
+                        .index_id
+                        .clone()
+                        .context("`route` transform stage should have an index ID")?;
+                    VrlStageAction::Route(index_id)
+                }
+            };
+            let stage = VrlStage {
+                name: stage_config.name.clone(),
+                condition_opt,
+                action,
+            };
+            stages.push(stage);
+        }
+        let runtime = VrlRuntime {
+            runtime: Runtime::new(RuntimeState::default()),
+            timezone,
+            metadata: VrlValue::Object(BTreeMap::new()),
+            secrets: VrlSecrets::default(),
+        };
+        Ok(VrlProgram {
+            program_opt,
+            stages,
+            runtime,
+        })
+    }
+}
+
+struct VrlRuntime {
+    runtime: Runtime,
+    timezone: TimeZone,
+    metadata: VrlValue,
+    secrets: VrlSecrets,
+}
+
+impl VrlRuntime {
+    fn resolve(
+        &mut self,
+        mut vrl_value: VrlValue,
+        program: &Program,
+    ) -> Result<VrlValue, DocProcessorError> {
         let mut target = TargetValueRef {
             value: &mut vrl_value,
             metadata: &mut self.metadata,
@@ -65,7 +186,7 @@ impl VrlProgram {
         };
         let runtime_res = self
             .runtime
-            .resolve(&mut target, &self.program, &self.timezone)
+            .resolve(&mut target, program, &self.timezone)
             .map_err(|transform_error| {
                 warn!(transform_error=?transform_error);
                 DocProcessorError::Transform(transform_error)
@@ -76,20 +197,6 @@ impl VrlProgram {
         }
         self.runtime.clear();
 
-        runtime_res.map(|vrl_value| VrlDoc::new(vrl_value, num_bytes))
-    }
-
-    pub fn try_from_transform_config(transform_config: TransformConfig) -> anyhow::Result<Self> {
-        let (program, timezone) = transform_config.compile_vrl_script()?;
-        let state = RuntimeState::default();
-        let runtime = Runtime::new(state);
-
-        Ok(VrlProgram {
-            program,
-            runtime,
-            timezone,
-            metadata: VrlValue::Object(BTreeMap::new()),
-            secrets: VrlSecrets::default(),
-        })
+        runtime_res
     }
 }
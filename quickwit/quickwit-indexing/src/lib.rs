@@ -29,13 +29,14 @@ use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_storage::StorageResolver;
 use tracing::info;
 
-use crate::actors::MergeSchedulerService;
 pub use crate::actors::{
-    IndexingError, IndexingPipeline, IndexingPipelineParams, IndexingService, PublisherType,
-    Sequencer, SplitsUpdateMailbox,
+    dry_run_transform, IndexingError, IndexingPipeline, IndexingPipelineParams, IndexingService,
+    PublisherType, Sequencer, SplitsUpdateMailbox, TransformDryRunAction, TransformDryRunResult,
 };
+use crate::actors::{init_upload_scheduler, MergeSchedulerService};
 pub use crate::controlled_directory::ControlledDirectory;
 use crate::models::IndexingStatistics;
+pub use crate::parquet_split_materializer::{ParquetSplitMaterializer, MATERIALIZATION_DIR_NAME};
 pub use crate::split_store::{get_tantivy_directory_from_split_bundle, IndexingSplitStore};
 
 pub mod actors;
@@ -43,8 +44,10 @@ mod controlled_directory;
 pub mod merge_policy;
 mod metrics;
 pub mod models;
+mod parquet_split_materializer;
 pub mod source;
 mod split_store;
+mod split_validation;
 #[cfg(any(test, feature = "testsuite"))]
 mod test_utils;
 
@@ -76,10 +79,11 @@ pub async fn start_indexing_service(
     event_broker: EventBroker,
 ) -> anyhow::Result<Mailbox<IndexingService>> {
     info!("starting indexer service");
+    init_upload_scheduler(config.indexer_config.max_upload_bandwidth);
     let ingest_api_service_mailbox = universe.get_one::<IngestApiService>();
-    let (merge_scheduler_mailbox, _) = universe.spawn_builder().spawn(MergeSchedulerService::new(
-        config.indexer_config.merge_concurrency.get(),
-    ));
+    let merge_scheduler_service =
+        MergeSchedulerService::from_indexer_config(&config.indexer_config);
+    let (merge_scheduler_mailbox, _) = universe.spawn_builder().spawn(merge_scheduler_service);
     // Spawn indexing service.
     let indexing_service = IndexingService::new(
         config.node_id.clone(),
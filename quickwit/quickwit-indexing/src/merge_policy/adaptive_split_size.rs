@@ -0,0 +1,346 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use quickwit_config::{AdaptiveSplitSizeConfig, IndexingSettings};
+use quickwit_metastore::{SplitMaturity, SplitMetadata};
+use quickwit_proto::search::QueryAccessStats;
+use quickwit_proto::types::IndexUid;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::merge_policy::{merge_policy_from_settings, MergeOperation, MergePolicy};
+use crate::models::QueryAccessStatsRegistry;
+
+/// An index is scan-heavy if at least this fraction of its queries are scans...
+const SCAN_HEAVY_MIN_SCAN_RATIO: f64 = 0.5;
+/// ... or if its queries match at least this fraction of the searched documents on average.
+const SCAN_HEAVY_MIN_SELECTIVITY: f64 = 0.1;
+
+/// An index is selective if its queries match at most this fraction of the searched documents on
+/// average...
+const SELECTIVE_MAX_SELECTIVITY: f64 = 0.001;
+/// ... and if at most this fraction of its queries are scans.
+const SELECTIVE_MAX_SCAN_RATIO: f64 = 0.1;
+
+/// How the queries hitting an index access its documents.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessPattern {
+    /// Not enough queries have been observed to tell.
+    Unknown,
+    /// The queries are mostly filters matching a tiny fraction of the documents.
+    Selective,
+    /// The queries are neither selective nor scan-heavy.
+    Mixed,
+    /// The queries are mostly aggregations, match-all queries or queries matching a large
+    /// fraction of the documents.
+    ScanHeavy,
+}
+
+/// Target number of documents of the splits of an index, and how it was decided.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SplitSizeDecision {
+    /// Whether the adaptive split size is enabled for the index.
+    pub adaptive: bool,
+    /// Access pattern of the index, derived from its query access statistics.
+    pub access_pattern: AccessPattern,
+    /// Target number of documents configured in the indexing settings.
+    pub base_split_num_docs_target: usize,
+    /// Target number of documents currently used by the merge policy.
+    pub split_num_docs_target: usize,
+    /// Query access statistics of the index merged across the searchers.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_access_stats: Option<QueryAccessStats>,
+}
+
+fn classify_access_pattern(
+    stats_opt: Option<&QueryAccessStats>,
+    min_num_queries: u64,
+) -> AccessPattern {
+    let Some(stats) = stats_opt else {
+        return AccessPattern::Unknown;
+    };
+    if stats.num_queries == 0 || stats.num_queries < min_num_queries {
+        return AccessPattern::Unknown;
+    }
+    let scan_ratio = stats.scan_ratio();
+
+    if scan_ratio >= SCAN_HEAVY_MIN_SCAN_RATIO || stats.selectivity >= SCAN_HEAVY_MIN_SELECTIVITY {
+        AccessPattern::ScanHeavy
+    } else if scan_ratio <= SELECTIVE_MAX_SCAN_RATIO
+        && stats.selectivity <= SELECTIVE_MAX_SELECTIVITY
+    {
+        AccessPattern::Selective
+    } else {
+        AccessPattern::Mixed
+    }
+}
+
+/// Decides the target number of documents of the splits of an index given its query access
+/// statistics:
+/// - selective indexes get splits of `min_split_num_docs_target` documents, so that the searchers
+///   can skip most of the data thanks to split pruning;
+/// - scan-heavy indexes get splits of `max_split_num_docs_target` documents, which reduces the
+///   per-split overhead of the searches reading all the data;
+/// - other indexes, and indexes for which not enough queries have been observed, keep the
+///   `split_num_docs_target` of their indexing settings.
+pub fn decide_split_num_docs_target(
+    indexing_settings: &IndexingSettings,
+    stats_opt: Option<&QueryAccessStats>,
+) -> SplitSizeDecision {
+    let base_split_num_docs_target = indexing_settings.split_num_docs_target;
+    let adaptive_split_size_config_opt = indexing_settings.adaptive_split_size.as_ref();
+    let min_num_queries = adaptive_split_size_config_opt
+        .map(|config| config.min_num_queries)
+        .unwrap_or_else(|| AdaptiveSplitSizeConfig::default().min_num_queries);
+    let access_pattern = classify_access_pattern(stats_opt, min_num_queries);
+
+    let split_num_docs_target = match (adaptive_split_size_config_opt, access_pattern) {
+        (Some(config), AccessPattern::Selective) => config.min_split_num_docs_target,
+        (Some(config), AccessPattern::ScanHeavy) => config.max_split_num_docs_target,
+        _ => base_split_num_docs_target,
+    };
+    SplitSizeDecision {
+        adaptive: adaptive_split_size_config_opt.is_some(),
+        access_pattern,
+        base_split_num_docs_target,
+        split_num_docs_target,
+        query_access_stats: stats_opt.copied(),
+    }
+}
+
+/// Merge policy whose split target follows the query access patterns of the index. The target is
+/// decided again every time the merge planner runs, and the merge operations and split
+/// maturities are delegated to the merge policy configured for the index, built with that target.
+pub(crate) struct AdaptiveSplitSizeMergePolicy {
+    index_uid: IndexUid,
+    indexing_settings: IndexingSettings,
+    query_access_stats_registry: QueryAccessStatsRegistry,
+    /// Last target used, so that changes can be logged.
+    split_num_docs_target: AtomicUsize,
+}
+
+impl AdaptiveSplitSizeMergePolicy {
+    pub fn new(
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+        query_access_stats_registry: QueryAccessStatsRegistry,
+    ) -> Self {
+        let split_num_docs_target = AtomicUsize::new(indexing_settings.split_num_docs_target);
+        Self {
+            index_uid,
+            indexing_settings,
+            query_access_stats_registry,
+            split_num_docs_target,
+        }
+    }
+
+    /// Returns the merge policy configured for the index, built with the current target, along
+    /// with that target.
+    fn current_merge_policy(&self) -> (Arc<dyn MergePolicy>, usize) {
+        let stats_opt = self
+            .query_access_stats_registry
+            .index_stats(&self.index_uid);
+        let decision = decide_split_num_docs_target(&self.indexing_settings, stats_opt.as_ref());
+        let previous_split_num_docs_target = self
+            .split_num_docs_target
+            .swap(decision.split_num_docs_target, Ordering::Relaxed);
+
+        if previous_split_num_docs_target != decision.split_num_docs_target {
+            info!(
+                index_uid=%self.index_uid,
+                access_pattern=?decision.access_pattern,
+                previous_split_num_docs_target,
+                split_num_docs_target=decision.split_num_docs_target,
+                "adapting split size to query access patterns"
+            );
+        }
+        let mut indexing_settings = self.indexing_settings.clone();
+        indexing_settings.split_num_docs_target = decision.split_num_docs_target;
+        let merge_policy = merge_policy_from_settings(&indexing_settings);
+        (merge_policy, decision.split_num_docs_target)
+    }
+}
+
+impl fmt::Debug for AdaptiveSplitSizeMergePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AdaptiveSplitSizeMergePolicy")
+            .field("index_uid", &self.index_uid)
+            .field("merge_policy", &self.indexing_settings.merge_policy)
+            .field(
+                "split_num_docs_target",
+                &self.split_num_docs_target.load(Ordering::Relaxed),
+            )
+            .finish()
+    }
+}
+
+impl MergePolicy for AdaptiveSplitSizeMergePolicy {
+    fn operations(&self, splits: &mut Vec<SplitMetadata>) -> Vec<MergeOperation> {
+        let (merge_policy, split_num_docs_target) = self.current_merge_policy();
+        // The young splits created while the target was larger can exceed the current target.
+        // They are set aside as if they were mature.
+        let (oversized_splits, young_splits): (Vec<SplitMetadata>, Vec<SplitMetadata>) = splits
+            .drain(..)
+            .partition(|split| split.num_docs >= split_num_docs_target);
+        *splits = young_splits;
+        let merge_operations = merge_policy.operations(splits);
+        splits.extend(oversized_splits);
+        merge_operations
+    }
+
+    fn split_maturity(&self, split_num_docs: usize, split_num_merge_ops: usize) -> SplitMaturity {
+        let (merge_policy, _) = self.current_merge_policy();
+        merge_policy.split_maturity(split_num_docs, split_num_merge_ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_proto::types::NodeId;
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn adaptive_indexing_settings() -> IndexingSettings {
+        IndexingSettings {
+            split_num_docs_target: 10_000_000,
+            adaptive_split_size: Some(AdaptiveSplitSizeConfig {
+                min_split_num_docs_target: 1_000_000,
+                max_split_num_docs_target: 40_000_000,
+                min_num_queries: 100,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn query_access_stats(
+        num_queries: u64,
+        num_scan_queries: u64,
+        selectivity: f64,
+    ) -> QueryAccessStats {
+        QueryAccessStats {
+            num_queries,
+            num_scan_queries,
+            selectivity,
+            latency_millis: 50.0,
+        }
+    }
+
+    #[test]
+    fn test_decide_split_num_docs_target() {
+        let indexing_settings = adaptive_indexing_settings();
+
+        let decision = decide_split_num_docs_target(&indexing_settings, None);
+        assert!(decision.adaptive);
+        assert_eq!(decision.access_pattern, AccessPattern::Unknown);
+        assert_eq!(decision.split_num_docs_target, 10_000_000);
+
+        let stats = query_access_stats(99, 0, 0.0001);
+        let decision = decide_split_num_docs_target(&indexing_settings, Some(&stats));
+        assert_eq!(decision.access_pattern, AccessPattern::Unknown);
+        assert_eq!(decision.split_num_docs_target, 10_000_000);
+
+        let stats = query_access_stats(1_000, 50, 0.0001);
+        let decision = decide_split_num_docs_target(&indexing_settings, Some(&stats));
+        assert_eq!(decision.access_pattern, AccessPattern::Selective);
+        assert_eq!(decision.split_num_docs_target, 1_000_000);
+        assert_eq!(decision.base_split_num_docs_target, 10_000_000);
+        assert_eq!(decision.query_access_stats, Some(stats));
+
+        let stats = query_access_stats(1_000, 600, 0.0001);
+        let decision = decide_split_num_docs_target(&indexing_settings, Some(&stats));
+        assert_eq!(decision.access_pattern, AccessPattern::ScanHeavy);
+        assert_eq!(decision.split_num_docs_target, 40_000_000);
+
+        let stats = query_access_stats(1_000, 0, 0.5);
+        let decision = decide_split_num_docs_target(&indexing_settings, Some(&stats));
+        assert_eq!(decision.access_pattern, AccessPattern::ScanHeavy);
+        assert_eq!(decision.split_num_docs_target, 40_000_000);
+
+        let stats = query_access_stats(1_000, 200, 0.01);
+        let decision = decide_split_num_docs_target(&indexing_settings, Some(&stats));
+        assert_eq!(decision.access_pattern, AccessPattern::Mixed);
+        assert_eq!(decision.split_num_docs_target, 10_000_000);
+
+        // Without the adaptive split size, the access pattern is reported but the target is
+        // left untouched.
+        let indexing_settings = IndexingSettings::default();
+        let stats = query_access_stats(1_000, 50, 0.0001);
+        let decision = decide_split_num_docs_target(&indexing_settings, Some(&stats));
+        assert!(!decision.adaptive);
+        assert_eq!(decision.access_pattern, AccessPattern::Selective);
+        assert_eq!(decision.split_num_docs_target, 10_000_000);
+    }
+
+    #[test]
+    fn test_adaptive_split_size_merge_policy() {
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let registry = QueryAccessStatsRegistry::default();
+        let merge_policy = AdaptiveSplitSizeMergePolicy::new(
+            index_uid.clone(),
+            adaptive_indexing_settings(),
+            registry.clone(),
+        );
+        assert!(matches!(
+            merge_policy.split_maturity(5_000_000, 0),
+            SplitMaturity::Immature { .. }
+        ));
+        registry.update(
+            NodeId::from("searcher"),
+            index_uid,
+            query_access_stats(1_000, 0, 0.0001),
+        );
+        assert_eq!(
+            merge_policy.split_maturity(5_000_000, 0),
+            SplitMaturity::Mature
+        );
+        assert_eq!(
+            merge_policy.split_num_docs_target.load(Ordering::Relaxed),
+            1_000_000
+        );
+        // The splits exceeding the shrunk target are left out of the merge operations.
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let mut splits: Vec<SplitMetadata> = (0..11)
+            .map(|split_ord| SplitMetadata {
+                split_id: format!("split-{split_ord}"),
+                num_docs: if split_ord == 0 { 5_000_000 } else { 100_000 },
+                create_timestamp: now_timestamp,
+                maturity: SplitMaturity::Immature {
+                    maturation_period: Duration::from_secs(3_600),
+                },
+                ..Default::default()
+            })
+            .collect();
+        let merge_operations = merge_policy.operations(&mut splits);
+        assert_eq!(merge_operations.len(), 1);
+        assert!(merge_operations[0]
+            .splits_as_slice()
+            .iter()
+            .all(|split| split.num_docs == 100_000));
+        assert!(splits.iter().any(|split| split.split_id == "split-0"));
+    }
+}
@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod adaptive_split_size;
 mod const_write_amplification;
 mod nop_merge_policy;
 mod stable_log_merge_policy;
@@ -25,24 +26,29 @@ use std::fmt;
 use std::ops::Deref;
 use std::sync::Arc;
 
+pub(crate) use adaptive_split_size::AdaptiveSplitSizeMergePolicy;
+pub use adaptive_split_size::{decide_split_num_docs_target, AccessPattern, SplitSizeDecision};
 pub(crate) use const_write_amplification::ConstWriteAmplificationMergePolicy;
 use itertools::Itertools;
 pub use nop_merge_policy::NopMergePolicy;
 use quickwit_config::merge_policy_config::MergePolicyConfig;
 use quickwit_config::IndexingSettings;
 use quickwit_metastore::{SplitMaturity, SplitMetadata};
+use quickwit_proto::types::IndexUid;
 use serde::Serialize;
 pub(crate) use stable_log_merge_policy::StableLogMergePolicy;
 use tantivy::TrackedObject;
 use tracing::{info_span, Span};
 
 use crate::actors::MergePermit;
+use crate::models::QueryAccessStatsRegistry;
 use crate::new_split_id;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub enum MergeOperationType {
     Merge,
     DeleteAndMerge,
+    FastFieldBackfill,
 }
 
 impl fmt::Display for MergeOperationType {
@@ -122,6 +128,17 @@ impl MergeOperation {
         }
     }
 
+    pub fn new_fast_field_backfill_operation(split: SplitMetadata) -> Self {
+        let merge_split_id = new_split_id();
+        let merge_parent_span = info_span!("backfill", merge_split_id=%merge_split_id, split_ids=?split.split_id(), typ=%MergeOperationType::FastFieldBackfill);
+        Self {
+            merge_parent_span,
+            merge_split_id,
+            splits: vec![split],
+            operation_type: MergeOperationType::FastFieldBackfill,
+        }
+    }
+
     pub fn splits_as_slice(&self) -> &[SplitMetadata] {
         self.splits.as_slice()
     }
@@ -181,6 +198,24 @@ pub fn merge_policy_from_settings(settings: &IndexingSettings) -> Arc<dyn MergeP
     }
 }
 
+/// Builds the merge policy of an index. When the adaptive split size is enabled, the split target
+/// follows the query access statistics of the index found in the registry.
+pub fn adaptive_merge_policy_from_settings(
+    index_uid: &IndexUid,
+    settings: &IndexingSettings,
+    query_access_stats_registry: &QueryAccessStatsRegistry,
+) -> Arc<dyn MergePolicy> {
+    if settings.adaptive_split_size.is_none() || settings.merge_policy == MergePolicyConfig::Nop {
+        return merge_policy_from_settings(settings);
+    }
+    let merge_policy = AdaptiveSplitSizeMergePolicy::new(
+        index_uid.clone(),
+        settings.clone(),
+        query_access_stats_registry.clone(),
+    );
+    Arc::new(merge_policy)
+}
+
 pub fn default_merge_policy() -> Arc<dyn MergePolicy> {
     merge_policy_from_settings(&IndexingSettings::default())
 }
@@ -380,7 +415,15 @@ pub mod tests {
             pipeline_uid: PipelineUid::for_test(0u128),
         };
         let split_attrs = merge_split_attrs(merged_split_id, &pipeline_id, splits);
-        create_split_metadata(merge_policy, &split_attrs, tags, 0..0)
+        create_split_metadata(
+            merge_policy,
+            &split_attrs,
+            tags,
+            Default::default(),
+            Default::default(),
+            0..0,
+            None,
+        )
     }
 
     fn apply_merge(
@@ -414,6 +457,7 @@ pub mod tests {
             pipeline_id,
             Vec::new(),
             merge_policy.clone(),
+            None,
             merge_task_mailbox,
             universe.get_or_spawn_one::<MergeSchedulerService>(),
         );
@@ -19,18 +19,27 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    new_counter_vec, new_gauge, new_gauge_vec, IntCounterVec, IntGauge, IntGaugeVec,
+    new_counter, new_counter_vec, new_gauge, new_gauge_vec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec,
 };
 
 pub struct IndexerMetrics {
     pub processed_docs_total: IntCounterVec<2>,
     pub processed_bytes: IntCounterVec<2>,
+    pub coerced_values_total: IntCounterVec<1>,
+    pub defaulted_values_total: IntCounterVec<1>,
+    pub json_schema_rejected_docs_total: IntCounterVec<3>,
     pub backpressure_micros: IntCounterVec<1>,
     pub available_concurrent_upload_permits: IntGaugeVec<1>,
     pub split_builders: IntGauge,
     pub ongoing_merge_operations: IntGauge,
     pub pending_merge_operations: IntGauge,
     pub pending_merge_bytes: IntGauge,
+    pub merge_scratch_space_bytes: IntGaugeVec<1>,
+    pub delayed_merge_operations: IntGauge,
+    pub pending_commit_uploads: IntGauge,
+    pub pending_merge_uploads: IntGauge,
+    pub quarantined_splits_total: IntCounter,
 }
 
 impl Default for IndexerMetrics {
@@ -52,6 +61,29 @@ impl Default for IndexerMetrics {
                 &[],
                 ["index", "docs_processed_status"],
             ),
+            coerced_values_total: new_counter_vec(
+                "coerced_values_total",
+                "Number of values coerced into the type of their field by index.",
+                "indexing",
+                &[],
+                ["index"],
+            ),
+            defaulted_values_total: new_counter_vec(
+                "defaulted_values_total",
+                "Number of missing or null values replaced by the default value of their field by \
+                 index.",
+                "indexing",
+                &[],
+                ["index"],
+            ),
+            json_schema_rejected_docs_total: new_counter_vec(
+                "json_schema_rejected_docs_total",
+                "Number of docs rejected by the JSON Schema of their source by index, source and \
+                 schema version.",
+                "indexing",
+                &[],
+                ["index", "source", "schema_version"],
+            ),
             backpressure_micros: new_counter_vec(
                 "backpressure_micros",
                 "Amount of time spent in backpressure (in micros). This time only includes the \
@@ -91,6 +123,38 @@ impl Default for IndexerMetrics {
                 "indexing",
                 &[],
             ),
+            merge_scratch_space_bytes: new_gauge_vec(
+                "merge_scratch_space_bytes",
+                "Number of bytes of local disk space reserved by the ongoing merge operations for \
+                 their scratch directories by index.",
+                "indexing",
+                &[],
+                ["index"],
+            ),
+            delayed_merge_operations: new_gauge(
+                "delayed_merge_operations",
+                "Number of pending merge operations delayed because their pipeline or the node \
+                 reached its scratch space quota.",
+                "indexing",
+                &[],
+            ),
+            pending_commit_uploads: new_gauge(
+                "pending_commit_uploads",
+                "Number of commit uploads waiting for upload bandwidth.",
+                "indexing",
+                &[],
+            ),
+            pending_merge_uploads: new_gauge(
+                "pending_merge_uploads",
+                "Number of merge and delete uploads waiting for upload bandwidth.",
+                "indexing",
+                &[],
+            ),
+            quarantined_splits_total: new_counter(
+                "quarantined_splits_total",
+                "Number of splits that failed validation before upload and were quarantined.",
+                "indexing",
+            ),
         }
     }
 }
@@ -29,6 +29,7 @@ mod packaged_split;
 mod processed_doc;
 mod publish_lock;
 mod publisher_message;
+mod query_access_stats;
 mod raw_doc_batch;
 mod shard_positions;
 mod split_attrs;
@@ -48,6 +49,7 @@ pub use packaged_split::{PackagedSplit, PackagedSplitBatch};
 pub use processed_doc::{ProcessedDoc, ProcessedDocBatch};
 pub use publish_lock::{NewPublishLock, PublishLock};
 pub use publisher_message::SplitsUpdate;
+pub use query_access_stats::QueryAccessStatsRegistry;
 use quickwit_proto::types::PublishToken;
 pub use raw_doc_batch::RawDocBatch;
 pub(crate) use shard_positions::LocalShardPositionsUpdate;
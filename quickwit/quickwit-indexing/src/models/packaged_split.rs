@@ -17,12 +17,15 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::ops::RangeInclusive;
 
 use itertools::Itertools;
 use quickwit_common::temp_dir::TempDirectory;
+use quickwit_doc_mapper::term_digest::TermDigest;
 use quickwit_metastore::checkpoint::IndexCheckpointDelta;
+use quickwit_metastore::SplitStorageStats;
 use quickwit_proto::types::{IndexUid, PublishToken, SplitId};
 use tracing::Span;
 
@@ -34,8 +37,11 @@ pub struct PackagedSplit {
     pub split_attrs: SplitAttrs,
     pub split_scratch_directory: TempDirectory,
     pub tags: BTreeSet<String>,
+    pub term_digests: BTreeMap<String, TermDigest>,
+    pub secondary_time_ranges: BTreeMap<String, RangeInclusive<i64>>,
     pub split_files: Vec<std::path::PathBuf>,
     pub hotcache_bytes: Vec<u8>,
+    pub storage_stats_opt: Option<SplitStorageStats>,
 }
 
 impl PackagedSplit {
@@ -0,0 +1,156 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use quickwit_cluster::{Cluster, ListenerHandle};
+use quickwit_proto::search::{QueryAccessStats, QUERY_ACCESS_STATS_KEY_PREFIX};
+use quickwit_proto::types::{IndexUid, NodeId};
+use tracing::warn;
+
+/// Statistics that have not been refreshed by a searcher for this period are ignored: the
+/// searcher has left the cluster or no longer receives queries for the index.
+const QUERY_ACCESS_STATS_STALENESS_PERIOD: Duration = Duration::from_secs(3_600);
+
+#[derive(Debug)]
+struct NodeQueryAccessStats {
+    stats: QueryAccessStats,
+    updated_at: Instant,
+}
+
+/// Query access statistics of the indexes, as advertised by the searchers of the cluster via
+/// chitchat.
+#[derive(Clone, Debug, Default)]
+pub struct QueryAccessStatsRegistry {
+    inner: Arc<Mutex<HashMap<IndexUid, HashMap<NodeId, NodeQueryAccessStats>>>>,
+}
+
+impl QueryAccessStatsRegistry {
+    /// Creates a registry kept up to date with the statistics advertised in the cluster. The
+    /// registry stops receiving updates when the returned handle is dropped.
+    pub async fn subscribe(cluster: &Cluster) -> (QueryAccessStatsRegistry, ListenerHandle) {
+        let registry = QueryAccessStatsRegistry::default();
+        let registry_clone = registry.clone();
+
+        let listener_handle = cluster
+            .subscribe(QUERY_ACCESS_STATS_KEY_PREFIX, move |event| {
+                let node_id: NodeId = event.node.node_id.clone().into();
+                registry_clone.update_from_key_value(node_id, event.key, event.value);
+            })
+            .await;
+
+        // The statistics advertised before the subscription are not replayed as events.
+        let chitchat = cluster.chitchat().await;
+        let chitchat_lock = chitchat.lock().await;
+
+        for (chitchat_id, node_state) in chitchat_lock.node_states() {
+            for (key, versioned_value) in node_state.iter_prefix(QUERY_ACCESS_STATS_KEY_PREFIX) {
+                let key_stripped = key.strip_prefix(QUERY_ACCESS_STATS_KEY_PREFIX).unwrap();
+                let node_id: NodeId = chitchat_id.node_id.clone().into();
+                registry.update_from_key_value(node_id, key_stripped, &versioned_value.value);
+            }
+        }
+        (registry, listener_handle)
+    }
+
+    fn update_from_key_value(&self, node_id: NodeId, key: &str, value: &str) {
+        let Ok(index_uid) = IndexUid::from_str(key) else {
+            warn!("failed to parse index UID `{key}`");
+            return;
+        };
+        let Ok(stats) = serde_json::from_str::<QueryAccessStats>(value) else {
+            warn!("failed to parse query access stats `{value}`");
+            return;
+        };
+        self.update(node_id, index_uid, stats);
+    }
+
+    /// Records the statistics of an index advertised by a searcher.
+    pub fn update(&self, node_id: NodeId, index_uid: IndexUid, stats: QueryAccessStats) {
+        let node_stats = NodeQueryAccessStats {
+            stats,
+            updated_at: Instant::now(),
+        };
+        self.inner
+            .lock()
+            .expect("the lock should not be poisoned")
+            .entry(index_uid)
+            .or_default()
+            .insert(node_id, node_stats);
+    }
+
+    /// Returns the statistics of an index merged across the searchers, or `None` if no searcher
+    /// has advertised statistics for the index recently.
+    pub fn index_stats(&self, index_uid: &IndexUid) -> Option<QueryAccessStats> {
+        let mut inner_guard = self.inner.lock().expect("the lock should not be poisoned");
+        let stats_per_node = inner_guard.get_mut(index_uid)?;
+
+        stats_per_node.retain(|_, node_stats| {
+            node_stats.updated_at.elapsed() < QUERY_ACCESS_STATS_STALENESS_PERIOD
+        });
+        if stats_per_node.is_empty() {
+            inner_guard.remove(index_uid);
+            return None;
+        }
+        let merged_stats =
+            QueryAccessStats::merge(stats_per_node.values().map(|node_stats| &node_stats.stats));
+        Some(merged_stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_access_stats_registry() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let stats = QueryAccessStats {
+            num_queries: 10,
+            num_scan_queries: 2,
+            selectivity: 0.01,
+            latency_millis: 15.0,
+        };
+        let key = format!("{QUERY_ACCESS_STATS_KEY_PREFIX}{index_uid}");
+        let value = serde_json::to_string(&stats).unwrap();
+        // Advertised before the subscription.
+        cluster.set_self_key_value(&key, &value).await;
+
+        let (registry, _listener_handle) = QueryAccessStatsRegistry::subscribe(&cluster).await;
+        assert_eq!(registry.index_stats(&index_uid), Some(stats));
+
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+        assert!(registry.index_stats(&other_index_uid).is_none());
+
+        registry.update(NodeId::from("searcher-2"), index_uid.clone(), stats);
+        let merged_stats = registry.index_stats(&index_uid).unwrap();
+        assert_eq!(merged_stats.num_queries, 20);
+        assert_eq!(merged_stats.num_scan_queries, 4);
+        assert_eq!(merged_stats.selectivity, 0.01);
+    }
+}
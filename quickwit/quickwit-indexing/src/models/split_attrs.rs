@@ -17,12 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 
-use quickwit_metastore::SplitMetadata;
+use quickwit_metastore::{SplitMetadata, SplitStorageStats};
 use quickwit_proto::indexing::IndexingPipelineId;
 use tantivy::DateTime;
 use time::OffsetDateTime;
@@ -63,6 +63,9 @@ pub struct SplitAttrs {
 
     // Number of merge operation the split has been through so far.
     pub num_merge_ops: usize,
+
+    /// Version of the doc mapping the split was built with.
+    pub doc_mapping_version: u64,
 }
 
 impl fmt::Debug for SplitAttrs {
@@ -78,6 +81,7 @@ impl fmt::Debug for SplitAttrs {
             )
             .field("num_docs", &self.num_docs)
             .field("num_merge_ops", &self.num_merge_ops)
+            .field("doc_mapping_version", &self.doc_mapping_version)
             .finish()
     }
 }
@@ -86,7 +90,10 @@ pub fn create_split_metadata(
     merge_policy: &Arc<dyn MergePolicy>,
     split_attrs: &SplitAttrs,
     tags: BTreeSet<String>,
+    term_digest_fields: BTreeSet<String>,
+    secondary_time_ranges: BTreeMap<String, RangeInclusive<i64>>,
     footer_offsets: Range<u64>,
+    storage_stats_opt: Option<SplitStorageStats>,
 ) -> SplitMetadata {
     let create_timestamp = OffsetDateTime::now_utc().unix_timestamp();
     let maturity =
@@ -102,12 +109,16 @@ pub fn create_split_metadata(
             .time_range
             .as_ref()
             .map(|range| range.start().into_timestamp_secs()..=range.end().into_timestamp_secs()),
+        secondary_time_ranges,
         uncompressed_docs_size_in_bytes: split_attrs.uncompressed_docs_size_in_bytes,
         create_timestamp,
         maturity,
         tags,
+        term_digest_fields,
         footer_offsets,
         delete_opstamp: split_attrs.delete_opstamp,
         num_merge_ops: split_attrs.num_merge_ops,
+        storage_stats: storage_stats_opt,
+        doc_mapping_version: split_attrs.doc_mapping_version,
     }
 }
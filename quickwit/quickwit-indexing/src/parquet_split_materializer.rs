@@ -0,0 +1,452 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use quickwit_actors::{QueueCapacity, Universe};
+use quickwit_common::pubsub::EventBroker;
+use quickwit_common::temp_dir;
+use quickwit_common::uri::Uri;
+use quickwit_config::{build_doc_mapper, IndexConfig, SourceConfig, SourceParams};
+use quickwit_ingest::{IngesterPool, QUEUES_DIR_NAME};
+use quickwit_metastore::checkpoint::PartitionId;
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::indexing::IndexingPipelineId;
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
+use quickwit_proto::types::{IndexUid, PipelineUid, SourceId};
+use quickwit_storage::StorageResolver;
+use tracing::info;
+
+use crate::actors::{MergePlanner, SPLIT_QUARANTINE_DIR_NAME};
+use crate::merge_policy::merge_policy_from_settings;
+use crate::source::read_parquet_time_range;
+use crate::split_store::{IndexingSplitStore, LocalSplitStore};
+use crate::{IndexingPipeline, IndexingPipelineParams};
+
+/// Name of the directory, in the data directory, where the files of lazy Parquet sources are
+/// materialized into splits.
+pub const MATERIALIZATION_DIR_NAME: &str = "materialization";
+
+/// Maximum duration of a materialization. The indexing pipeline retries after failures, so the
+/// search requests waiting for a failing materialization give up after this duration.
+const MATERIALIZATION_TIMEOUT: Duration = Duration::from_secs(if cfg!(test) { 30 } else { 300 });
+
+/// Materializes the files of lazy Parquet sources into regular splits.
+///
+/// The files are indexed by a one-shot indexing pipeline, which publishes the splits along with
+/// the checkpoint of the source. The checkpoint records the files materialized, so each file is
+/// materialized once: the metastore rejects the publication of a file materialized concurrently by
+/// another searcher, and the pipeline skips it when it retries.
+///
+/// The files whose time range, read from the statistics of their footer, does not overlap the time
+/// range of the search request are left for later.
+pub struct ParquetSplitMaterializer {
+    node_id: String,
+    materialization_root_directory: PathBuf,
+    split_quarantine_dir_path: PathBuf,
+    queues_dir_path: PathBuf,
+    metastore: MetastoreServiceClient,
+    storage_resolver: StorageResolver,
+    // Files are immutable, so their time range is cached by URI.
+    time_ranges: Mutex<HashMap<Uri, Option<RangeInclusive<i64>>>>,
+    // Serializes the materializations of a source on this node.
+    source_locks: Mutex<HashMap<(IndexUid, SourceId), Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl ParquetSplitMaterializer {
+    pub async fn new(
+        node_id: String,
+        data_dir_path: PathBuf,
+        metastore: MetastoreServiceClient,
+        storage_resolver: StorageResolver,
+    ) -> anyhow::Result<Self> {
+        let materialization_root_directory =
+            temp_dir::create_or_purge_directory(&data_dir_path.join(MATERIALIZATION_DIR_NAME))
+                .await?;
+        Ok(Self {
+            node_id,
+            materialization_root_directory,
+            split_quarantine_dir_path: data_dir_path.join(SPLIT_QUARANTINE_DIR_NAME),
+            queues_dir_path: data_dir_path.join(QUEUES_DIR_NAME),
+            metastore,
+            storage_resolver,
+            time_ranges: Mutex::default(),
+            source_locks: Mutex::default(),
+        })
+    }
+
+    /// Materializes the files of a lazy Parquet source that overlap the time range
+    /// `[start_timestamp, end_timestamp)`, in seconds. Returns once their splits are published.
+    pub async fn materialize(
+        &self,
+        index_uid: IndexUid,
+        index_config: IndexConfig,
+        source_config: SourceConfig,
+        filepaths: Vec<Uri>,
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let SourceParams::Parquet(parquet_params) = &source_config.source_params else {
+            bail!(
+                "source `{}` is not a Parquet source",
+                source_config.source_id
+            );
+        };
+        if !parquet_params.lazy {
+            bail!("Parquet source `{}` is not lazy", source_config.source_id);
+        }
+        let mut selected_filepaths = Vec::with_capacity(filepaths.len());
+
+        for filepath in filepaths {
+            if let Some(timestamp_field) = &index_config.doc_mapping.timestamp_field {
+                let time_range_opt = self.time_range(&filepath, timestamp_field).await?;
+
+                if !overlaps(time_range_opt.as_ref(), start_timestamp, end_timestamp) {
+                    continue;
+                }
+            }
+            selected_filepaths.push(filepath);
+        }
+        if selected_filepaths.is_empty() {
+            return Ok(());
+        }
+        let source_lock = self
+            .source_locks
+            .lock()
+            .unwrap()
+            .entry((index_uid.clone(), source_config.source_id.clone()))
+            .or_default()
+            .clone();
+        let _source_guard = source_lock.lock().await;
+
+        // Another materialization may have published some of the files while we were waiting.
+        let index_metadata_request = IndexMetadataRequest::for_index_uid(index_uid.clone());
+        let index_metadata = self
+            .metastore
+            .clone()
+            .index_metadata(index_metadata_request)
+            .await?
+            .deserialize_index_metadata()?;
+        let source_checkpoint_opt = index_metadata
+            .checkpoint
+            .source_checkpoint(&source_config.source_id);
+        selected_filepaths.retain(|filepath| {
+            !source_checkpoint_opt
+                .and_then(|source_checkpoint| {
+                    source_checkpoint.position_for_partition(&PartitionId::from(filepath.as_str()))
+                })
+                .map_or(false, |position| position.is_eof())
+        });
+        if selected_filepaths.is_empty() {
+            return Ok(());
+        }
+        let mut parquet_params = parquet_params.clone();
+        parquet_params.filepaths = selected_filepaths;
+
+        let source_config = SourceConfig {
+            source_params: SourceParams::Parquet(parquet_params),
+            ..source_config
+        };
+        self.run_pipeline(index_uid, index_config, source_config)
+            .await
+    }
+
+    async fn time_range(
+        &self,
+        filepath: &Uri,
+        timestamp_column: &str,
+    ) -> anyhow::Result<Option<RangeInclusive<i64>>> {
+        if let Some(time_range_opt) = self.time_ranges.lock().unwrap().get(filepath) {
+            return Ok(time_range_opt.clone());
+        }
+        let time_range_opt =
+            read_parquet_time_range(&self.storage_resolver, filepath, timestamp_column).await?;
+        self.time_ranges
+            .lock()
+            .unwrap()
+            .insert(filepath.clone(), time_range_opt.clone());
+        Ok(time_range_opt)
+    }
+
+    async fn run_pipeline(
+        &self,
+        index_uid: IndexUid,
+        index_config: IndexConfig,
+        source_config: SourceConfig,
+    ) -> anyhow::Result<()> {
+        let pipeline_id = IndexingPipelineId {
+            node_id: self.node_id.clone(),
+            index_uid,
+            source_id: source_config.source_id.clone(),
+            pipeline_uid: PipelineUid::new(),
+        };
+        let materialization_directory = temp_dir::Builder::default()
+            .join(&pipeline_id.index_uid.index_id)
+            .join(&pipeline_id.source_id)
+            .tempdir_in(&self.materialization_root_directory)
+            .context("failed to create materialization directory")?;
+        let storage = self
+            .storage_resolver
+            .resolve(&index_config.index_uri)
+            .await?;
+        let split_store =
+            IndexingSplitStore::new(storage.clone(), Arc::new(LocalSplitStore::no_caching()));
+        let doc_mapper =
+            build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)?;
+        let num_files = match &source_config.source_params {
+            SourceParams::Parquet(parquet_params) => parquet_params.filepaths.len(),
+            _ => 0,
+        };
+        info!(pipeline_id=%pipeline_id, num_files, "materializing Parquet files");
+
+        // The splits of lazy sources are not merged, so the merge planner is never spawned.
+        let universe = Universe::new();
+        let (merge_planner_mailbox, _merge_planner_inbox) =
+            universe.create_mailbox::<MergePlanner>("MergePlanner", QueueCapacity::Unbounded);
+        let pipeline_params = IndexingPipelineParams {
+            pipeline_id,
+            metastore: self.metastore.clone(),
+            storage,
+            doc_mapper,
+            indexing_directory: materialization_directory,
+            split_quarantine_dir_path: self.split_quarantine_dir_path.clone(),
+            indexing_settings: index_config.indexing_settings.clone(),
+            split_store,
+            max_concurrent_split_uploads_index: 1,
+            cooperative_indexing_permits: None,
+            merge_policy: merge_policy_from_settings(&index_config.indexing_settings),
+            merge_planner_mailbox,
+            max_concurrent_split_uploads_merge: 1,
+            source_config,
+            source_storage_resolver: self.storage_resolver.clone(),
+            ingester_pool: IngesterPool::default(),
+            queues_dir_path: self.queues_dir_path.clone(),
+            event_broker: EventBroker::default(),
+        };
+        let pipeline = IndexingPipeline::new(pipeline_params);
+        let (_pipeline_mailbox, pipeline_handle) = universe.spawn_builder().spawn(pipeline);
+        let join_result =
+            tokio::time::timeout(MATERIALIZATION_TIMEOUT, pipeline_handle.join()).await;
+        universe.quit().await;
+
+        let Ok((exit_status, _)) = join_result else {
+            bail!(
+                "materialization did not complete within {} seconds",
+                MATERIALIZATION_TIMEOUT.as_secs()
+            );
+        };
+        if !exit_status.is_success() {
+            bail!("materialization failed: {exit_status}");
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the inclusive time range of a file overlaps the time range
+/// `[start_timestamp, end_timestamp)` of a search request. Files without a time range always
+/// overlap.
+fn overlaps(
+    time_range_opt: Option<&RangeInclusive<i64>>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+) -> bool {
+    let Some(time_range) = time_range_opt else {
+        return true;
+    };
+    if let Some(start_timestamp) = start_timestamp {
+        if *time_range.end() < start_timestamp {
+            return false;
+        }
+    }
+    if let Some(end_timestamp) = end_timestamp {
+        if *time_range.start() >= end_timestamp {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlaps() {
+        assert!(overlaps(None, Some(10), Some(20)));
+        assert!(overlaps(Some(&(0..=100)), None, None));
+        assert!(overlaps(Some(&(0..=10)), Some(10), Some(20)));
+        assert!(overlaps(Some(&(19..=30)), Some(10), Some(20)));
+        assert!(!overlaps(Some(&(0..=9)), Some(10), Some(20)));
+        assert!(!overlaps(Some(&(20..=30)), Some(10), Some(20)));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_parquet_split_materializer() {
+        use std::num::NonZeroUsize;
+        use std::str::FromStr;
+
+        use quickwit_config::{ConfigFormat, ParquetSourceParams, SourceInputFormat};
+        use quickwit_metastore::{
+            metastore_for_test, CreateIndexRequestExt, ListSplitsRequestExt,
+            MetastoreServiceStreamSplitsExt,
+        };
+        use quickwit_proto::metastore::{CreateIndexRequest, ListSplitsRequest};
+        use quickwit_proto::types::Position;
+
+        use crate::source::write_parquet_file;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.parquet");
+        write_parquet_file(&file_path);
+        let filepath = Uri::from_str(file_path.to_str().unwrap()).unwrap();
+
+        let mut index_config = IndexConfig::for_test("test-index", "ram:///indexes/test-index");
+        index_config.doc_mapping = ConfigFormat::Yaml
+            .parse(
+                br#"
+                field_mappings:
+                  - name: timestamp
+                    type: datetime
+                    input_formats: [rfc3339]
+                    fast: true
+                  - name: body
+                    type: text
+                  - name: status
+                    type: i64
+                timestamp_field: timestamp
+                "#,
+            )
+            .unwrap();
+        let source_config = SourceConfig {
+            source_id: "test-parquet-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Parquet(ParquetSourceParams {
+                filepaths: vec![filepath.clone()],
+                columns: Vec::new(),
+                lazy: true,
+            }),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let mut metastore = metastore_for_test();
+        let create_index_request = CreateIndexRequest::try_from_index_and_source_configs(
+            &index_config,
+            &[source_config.clone()],
+        )
+        .unwrap();
+        let index_uid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let materializer = ParquetSplitMaterializer::new(
+            "test-node".to_string(),
+            temp_dir.path().to_path_buf(),
+            metastore.clone(),
+            StorageResolver::for_test(),
+        )
+        .await
+        .unwrap();
+
+        let list_splits_metastore = metastore.clone();
+        let list_splits_index_uid = index_uid.clone();
+        let list_splits = move || {
+            let mut metastore = list_splits_metastore.clone();
+            let index_uid = list_splits_index_uid.clone();
+            async move {
+                metastore
+                    .list_splits(ListSplitsRequest::try_from_index_uid(index_uid).unwrap())
+                    .await
+                    .unwrap()
+                    .collect_splits()
+                    .await
+                    .unwrap()
+            }
+        };
+        // The file is out of the time range of the request.
+        materializer
+            .materialize(
+                index_uid.clone(),
+                index_config.clone(),
+                source_config.clone(),
+                vec![filepath.clone()],
+                Some(1_800_000_000),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(list_splits().await.is_empty());
+
+        materializer
+            .materialize(
+                index_uid.clone(),
+                index_config.clone(),
+                source_config.clone(),
+                vec![filepath.clone()],
+                Some(1_700_000_000),
+                Some(1_700_000_001),
+            )
+            .await
+            .unwrap();
+        let splits = list_splits().await;
+        assert_eq!(splits.len(), 1);
+        assert_eq!(splits[0].split_metadata.num_docs, 2);
+        assert_eq!(
+            splits[0].split_metadata.time_range,
+            Some(1_700_000_000..=1_700_000_001)
+        );
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_uid(index_uid.clone()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        let position = index_metadata
+            .checkpoint
+            .source_checkpoint("test-parquet-source")
+            .unwrap()
+            .position_for_partition(&PartitionId::from(filepath.as_str()))
+            .cloned();
+        assert_eq!(position, Some(Position::eof(2u64)));
+
+        // The file is materialized only once.
+        materializer
+            .materialize(
+                index_uid,
+                index_config,
+                source_config,
+                vec![filepath],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(list_splits().await.len(), 1);
+    }
+}
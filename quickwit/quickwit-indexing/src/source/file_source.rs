@@ -272,6 +272,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
@@ -354,6 +356,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
@@ -462,6 +466,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
@@ -324,6 +324,8 @@ mod gcp_pubsub_emulator_tests {
                 max_messages_per_pull: None,
             }),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         }
     }
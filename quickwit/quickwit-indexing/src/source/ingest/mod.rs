@@ -222,7 +222,16 @@ impl IngestSource {
         let from_position_exclusive = fetch_payload.from_position_exclusive().clone();
         let to_position_inclusive = fetch_payload.to_position_inclusive().clone();
 
-        for mrecord in decoded_mrecords(mrecord_batch) {
+        // A record that cannot be decoded fails the source instead of being skipped, so the
+        // checkpoint does not move past documents that were never indexed.
+        for mrecord_res in decoded_mrecords(mrecord_batch) {
+            let mrecord = mrecord_res.with_context(|| {
+                format!(
+                    "failed to decode record fetched from shard `{}` after position `{}`",
+                    fetch_payload.queue_id(),
+                    from_position_exclusive
+                )
+            })?;
             match mrecord {
                 MRecord::Doc(doc) => {
                     batch_builder.add_doc(doc);
@@ -230,6 +239,7 @@ impl IngestSource {
                 MRecord::Commit => {
                     batch_builder.force_commit();
                 }
+                MRecord::IdempotencyKey(_) | MRecord::Rollback(_) => {}
             }
         }
         batch_builder
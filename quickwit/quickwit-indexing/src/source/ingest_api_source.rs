@@ -306,6 +306,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::IngestApi,
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         }
     }
@@ -909,6 +909,8 @@ mod kafka_broker_tests {
                 enable_backfill_mode: true,
             }),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         (source_id, source_config)
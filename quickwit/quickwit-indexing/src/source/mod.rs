@@ -66,13 +66,20 @@ mod ingest_api_source;
 mod kafka_source;
 #[cfg(feature = "kinesis")]
 mod kinesis;
+#[cfg(feature = "parquet")]
+mod parquet_source;
 #[cfg(feature = "pulsar")]
 mod pulsar_source;
+mod reindex_source;
 mod source_factory;
+#[cfg(feature = "sqs")]
+mod sqs_source;
+mod syslog_source;
 mod vec_source;
 mod void_source;
 
 use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -87,12 +94,17 @@ pub use kafka_source::{KafkaSource, KafkaSourceFactory};
 #[cfg(feature = "kinesis")]
 pub use kinesis::kinesis_source::{KinesisSource, KinesisSourceFactory};
 use once_cell::sync::OnceCell;
+#[cfg(all(test, feature = "parquet"))]
+pub(crate) use parquet_source::tests::write_parquet_file;
+#[cfg(feature = "parquet")]
+pub use parquet_source::{ParquetSource, ParquetSourceFactory};
 #[cfg(feature = "pulsar")]
 pub use pulsar_source::{PulsarSource, PulsarSourceFactory};
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
 use quickwit_common::metrics::{GaugeGuard, MEMORY_METRICS};
 use quickwit_common::pubsub::EventBroker;
 use quickwit_common::runtimes::RuntimeType;
+use quickwit_common::uri::Uri;
 use quickwit_config::{SourceConfig, SourceParams};
 use quickwit_ingest::IngesterPool;
 use quickwit_metastore::checkpoint::{SourceCheckpoint, SourceCheckpointDelta};
@@ -100,8 +112,12 @@ use quickwit_proto::indexing::IndexingPipelineId;
 use quickwit_proto::metastore::{MetastoreServiceClient, SourceType};
 use quickwit_proto::types::{IndexUid, PipelineUid, ShardId};
 use quickwit_storage::StorageResolver;
+pub use reindex_source::{ReindexSource, ReindexSourceFactory};
 use serde_json::Value as JsonValue;
 pub use source_factory::{SourceFactory, SourceLoader, TypedSourceFactory};
+#[cfg(feature = "sqs")]
+pub use sqs_source::{SqsSource, SqsSourceFactory};
+pub use syslog_source::{SyslogSource, SyslogSourceFactory};
 use tokio::runtime::Handle;
 use tracing::error;
 pub use vec_source::{VecSource, VecSourceFactory};
@@ -394,8 +410,14 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
         source_factory.add_source("kafka", KafkaSourceFactory);
         #[cfg(feature = "kinesis")]
         source_factory.add_source("kinesis", KinesisSourceFactory);
+        #[cfg(feature = "parquet")]
+        source_factory.add_source("parquet", ParquetSourceFactory);
         #[cfg(feature = "pulsar")]
         source_factory.add_source("pulsar", PulsarSourceFactory);
+        source_factory.add_source("reindex", ReindexSourceFactory);
+        #[cfg(feature = "sqs")]
+        source_factory.add_source("sqs", SqsSourceFactory);
+        source_factory.add_source("syslog", SyslogSourceFactory);
         source_factory.add_source("vec", VecSourceFactory);
         source_factory.add_source("void", VoidSourceFactory);
         source_factory
@@ -438,6 +460,17 @@ pub async fn check_source_connectivity(
             }
         }
         #[allow(unused_variables)]
+        SourceParams::Parquet(params) => {
+            #[cfg(not(feature = "parquet"))]
+            anyhow::bail!("Quickwit binary was not compiled with the `parquet` feature");
+
+            #[cfg(feature = "parquet")]
+            {
+                parquet_source::check_connectivity(storage_resolver, params).await?;
+                Ok(())
+            }
+        }
+        #[allow(unused_variables)]
         SourceParams::Pulsar(params) => {
             #[cfg(not(feature = "pulsar"))]
             anyhow::bail!("Quickwit binary was not compiled with the `pulsar` feature");
@@ -448,10 +481,40 @@ pub async fn check_source_connectivity(
                 Ok(())
             }
         }
+        #[allow(unused_variables)]
+        SourceParams::Sqs(params) => {
+            #[cfg(not(feature = "sqs"))]
+            anyhow::bail!("Quickwit binary was not compiled with the `sqs` feature");
+
+            #[cfg(feature = "sqs")]
+            {
+                sqs_source::check_connectivity(params.clone()).await?;
+                Ok(())
+            }
+        }
         _ => Ok(()),
     }
 }
 
+/// Reads the time range of a Parquet file, in seconds, from the statistics of its footer. Returns
+/// `None` when the file has no usable statistics for the timestamp column.
+#[allow(unused_variables)]
+pub(crate) async fn read_parquet_time_range(
+    storage_resolver: &StorageResolver,
+    filepath: &Uri,
+    timestamp_column: &str,
+) -> anyhow::Result<Option<RangeInclusive<i64>>> {
+    #[cfg(not(feature = "parquet"))]
+    anyhow::bail!("Quickwit binary was not compiled with the `parquet` feature");
+
+    #[cfg(feature = "parquet")]
+    {
+        let time_range_opt =
+            parquet_source::read_time_range(storage_resolver, filepath, timestamp_column).await?;
+        Ok(time_range_opt)
+    }
+}
+
 #[derive(Debug)]
 pub struct SuggestTruncate(pub SourceCheckpoint);
 
@@ -553,6 +616,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::void(),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             };
             check_source_connectivity(&StorageResolver::for_test(), &source_config).await?;
@@ -564,6 +629,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             };
             check_source_connectivity(&StorageResolver::for_test(), &source_config).await?;
@@ -575,6 +642,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::file("file-does-not-exist.json"),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             };
             assert!(
@@ -590,6 +659,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::file("data/test_corpus.json"),
                 transform_config: None,
+                json_schema_config: None,
+                wasm_plugin_config: None,
                 input_format: SourceInputFormat::Json,
             };
             assert!(
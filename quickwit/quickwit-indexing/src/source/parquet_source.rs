@@ -0,0 +1,783 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use parquet::basic::{ConvertedType, LogicalType, TimeUnit};
+use parquet::file::footer::{decode_footer, decode_metadata};
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::statistics::Statistics;
+use parquet::file::FOOTER_SIZE;
+use parquet::record::{Field, Row, RowIter};
+use parquet::schema::types::Type as ParquetType;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::uri::Uri;
+use quickwit_config::ParquetSourceParams;
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_proto::metastore::SourceType;
+use quickwit_proto::types::Position;
+use quickwit_storage::StorageResolver;
+use serde::Serialize;
+use serde_json::{Map as JsonMap, Number as JsonNumber, Value as JsonValue};
+use tempfile::TempDir;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use tracing::info;
+
+use super::{BatchBuilder, BATCH_NUM_BYTES_LIMIT};
+use crate::actors::DocProcessor;
+use crate::source::file_source::dir_and_filename;
+use crate::source::{Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
+
+/// Maximum number of rows in a batch emitted by the Parquet source.
+const BATCH_NUM_DOCS_LIMIT: usize = 1_000;
+
+/// Once all the files of an eager source have been indexed, the source has nothing left to do and
+/// polls at this interval. Lazy sources exit instead.
+const IDLE_INTERVAL: Duration = Duration::from_secs(if cfg!(test) { 1 } else { 30 });
+
+/// Reads the rows of the Parquet files of an external table and emits them as JSON documents, the
+/// columns becoming the fields of the same name. Eager sources run on the indexers like any other
+/// source, whereas lazy sources are run by the searchers, which materialize the files targeted by a
+/// search request into splits (see [`crate::ParquetSplitMaterializer`]).
+///
+/// The checkpoint of the source has one partition per file, whose position is the number of rows
+/// of the file indexed so far. The files already indexed are skipped, so registering more files
+/// only indexes the new ones.
+pub struct ParquetSource {
+    source_id: String,
+    params: ParquetSourceParams,
+    storage_resolver: StorageResolver,
+    scratch_directory: TempDir,
+    checkpoint: SourceCheckpoint,
+    pending_filepaths: VecDeque<Uri>,
+    current_file_opt: Option<ParquetRowReader>,
+    state: ParquetSourceState,
+}
+
+impl fmt::Debug for ParquetSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ParquetSource")
+            .field("source_id", &self.source_id)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ParquetSourceState {
+    num_indexed_rows: u64,
+    num_indexed_files: usize,
+    num_pending_files: usize,
+    current_filepath_opt: Option<String>,
+}
+
+pub struct ParquetSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for ParquetSourceFactory {
+    type Source = ParquetSource;
+    type Params = ParquetSourceParams;
+
+    async fn typed_create_source(
+        runtime_args: Arc<SourceRuntimeArgs>,
+        params: ParquetSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        let pending_filepaths: VecDeque<Uri> = params
+            .filepaths
+            .iter()
+            .filter(|filepath| {
+                !checkpoint
+                    .position_for_partition(&PartitionId::from(filepath.as_str()))
+                    .map_or(false, |position| position.is_eof())
+            })
+            .cloned()
+            .collect();
+        let state = ParquetSourceState {
+            num_indexed_files: params.filepaths.len() - pending_filepaths.len(),
+            num_pending_files: pending_filepaths.len(),
+            ..Default::default()
+        };
+        Ok(ParquetSource {
+            source_id: runtime_args.source_id().to_string(),
+            params,
+            storage_resolver: runtime_args.storage_resolver.clone(),
+            scratch_directory: tempfile::tempdir()?,
+            checkpoint,
+            pending_filepaths,
+            current_file_opt: None,
+            state,
+        })
+    }
+}
+
+impl ParquetSource {
+    /// Downloads the file to the scratch directory and opens it, skipping the rows already
+    /// indexed.
+    async fn open_file(
+        &self,
+        filepath: Uri,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<ParquetRowReader> {
+        let partition_id = PartitionId::from(filepath.as_str());
+        let num_rows_to_skip = self
+            .checkpoint
+            .position_for_partition(&partition_id)
+            .and_then(|position| position.as_usize())
+            .unwrap_or(0);
+        let (dir_uri, file_name) = dir_and_filename(Path::new(filepath.as_str()))?;
+        let storage = self.storage_resolver.resolve(&dir_uri).await?;
+        let local_file_path = self
+            .scratch_directory
+            .path()
+            .join(format!("{}.parquet", self.state.num_indexed_files));
+        ctx.protect_future(storage.copy_to_file(file_name, &local_file_path))
+            .await
+            .with_context(|| format!("failed to download Parquet file `{filepath}`"))?;
+        info!(filepath=%filepath, num_rows_to_skip, "indexing Parquet file");
+        ParquetRowReader::open(
+            filepath,
+            partition_id,
+            local_file_path,
+            &self.params.columns,
+            num_rows_to_skip,
+        )
+    }
+}
+
+#[async_trait]
+impl Source for ParquetSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let row_reader = if let Some(row_reader) = self.current_file_opt.take() {
+            row_reader
+        } else {
+            let Some(filepath) = self.pending_filepaths.pop_front() else {
+                // Lazy sources are run by one-shot pipelines that materialize a set of files.
+                if self.params.lazy {
+                    return Err(ActorExitStatus::Success);
+                }
+                return Ok(IDLE_INTERVAL);
+            };
+            self.state.num_pending_files = self.pending_filepaths.len();
+            self.state.current_filepath_opt = Some(filepath.to_string());
+            self.open_file(filepath, ctx).await?
+        };
+        let from_num_rows = row_reader.num_rows_read;
+
+        let (row_reader, docs_res) = ctx
+            .protect_future(spawn_blocking(move || {
+                let mut row_reader = row_reader;
+                let docs_res = row_reader.read_docs(BATCH_NUM_DOCS_LIMIT);
+                (row_reader, docs_res)
+            }))
+            .await
+            .context("failed to read rows")?;
+        let docs = docs_res?;
+        let num_docs = docs.len();
+
+        let mut batch_builder = BatchBuilder::with_capacity(num_docs, SourceType::Parquet);
+
+        for doc in docs {
+            batch_builder.add_doc(doc);
+        }
+        let from_position = if from_num_rows == 0 {
+            Position::Beginning
+        } else {
+            Position::offset(from_num_rows)
+        };
+        let to_position = if row_reader.is_exhausted {
+            Position::eof(row_reader.num_rows_read)
+        } else {
+            Position::offset(row_reader.num_rows_read)
+        };
+        batch_builder
+            .checkpoint_delta
+            .record_partition_delta(row_reader.partition_id.clone(), from_position, to_position)
+            .context("failed to record partition delta")?;
+        self.checkpoint
+            .try_apply_delta(batch_builder.checkpoint_delta.clone())
+            .context("failed to apply checkpoint delta")?;
+        self.state.num_indexed_rows += num_docs as u64;
+
+        if row_reader.is_exhausted {
+            info!(filepath=%row_reader.filepath, "indexed Parquet file");
+            self.state.num_indexed_files += 1;
+            self.state.current_filepath_opt = None;
+            tokio::fs::remove_file(&row_reader.local_file_path)
+                .await
+                .context("failed to delete Parquet file")?;
+
+            if self.pending_filepaths.is_empty() {
+                info!("indexed all the Parquet files of the source");
+            }
+        } else {
+            self.current_file_opt = Some(row_reader);
+        }
+        ctx.send_message(doc_processor_mailbox, batch_builder.build())
+            .await?;
+        Ok(Duration::ZERO)
+    }
+
+    fn name(&self) -> String {
+        format!("ParquetSource {{ source_id={} }}", self.source_id)
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        serde_json::to_value(&self.state).expect("the state should be JSON serializable")
+    }
+}
+
+/// Reads the rows of a Parquet file downloaded to the scratch directory.
+struct ParquetRowReader {
+    filepath: Uri,
+    partition_id: PartitionId,
+    local_file_path: PathBuf,
+    row_iter: RowIter<'static>,
+    num_rows_to_skip: usize,
+    num_rows_read: usize,
+    is_exhausted: bool,
+}
+
+impl ParquetRowReader {
+    fn open(
+        filepath: Uri,
+        partition_id: PartitionId,
+        local_file_path: PathBuf,
+        columns: &[String],
+        num_rows_to_skip: usize,
+    ) -> anyhow::Result<Self> {
+        let file = File::open(&local_file_path)?;
+        let file_reader = SerializedFileReader::new(file)
+            .with_context(|| format!("failed to open Parquet file `{filepath}`"))?;
+        let projection_opt = if columns.is_empty() {
+            None
+        } else {
+            let root_schema = file_reader.metadata().file_metadata().schema();
+            Some(project_columns(root_schema, columns)?)
+        };
+        let row_iter = RowIter::from_file_into(Box::new(file_reader)).project(projection_opt)?;
+
+        Ok(Self {
+            filepath,
+            partition_id,
+            local_file_path,
+            row_iter,
+            num_rows_to_skip,
+            num_rows_read: 0,
+            is_exhausted: false,
+        })
+    }
+
+    fn next_row(&mut self) -> anyhow::Result<Option<Row>> {
+        let Some(row_res) = self.row_iter.next() else {
+            self.is_exhausted = true;
+            return Ok(None);
+        };
+        let row = row_res
+            .with_context(|| format!("failed to read row of Parquet file `{}`", self.filepath))?;
+        self.num_rows_read += 1;
+        Ok(Some(row))
+    }
+
+    /// Reads up to `max_num_docs` rows and converts them to JSON.
+    fn read_docs(&mut self, max_num_docs: usize) -> anyhow::Result<Vec<Bytes>> {
+        while self.num_rows_read < self.num_rows_to_skip && self.next_row()?.is_some() {}
+
+        let mut docs = Vec::new();
+        let mut num_bytes = 0;
+
+        while docs.len() < max_num_docs && num_bytes < BATCH_NUM_BYTES_LIMIT {
+            let Some(row) = self.next_row()? else {
+                break;
+            };
+            let doc_bytes = serde_json::to_vec(&row_to_json(&row))?;
+            num_bytes += doc_bytes.len() as u64;
+            docs.push(Bytes::from(doc_bytes));
+        }
+        Ok(docs)
+    }
+}
+
+/// Builds the schema projecting the root schema of a file on the given columns.
+fn project_columns(root_schema: &ParquetType, columns: &[String]) -> anyhow::Result<ParquetType> {
+    let mut fields = Vec::with_capacity(columns.len());
+
+    for column in columns {
+        let field = root_schema
+            .get_fields()
+            .iter()
+            .find(|field| field.name() == column)
+            .with_context(|| format!("column `{column}` does not exist"))?;
+        fields.push(field.clone());
+    }
+    let projection = ParquetType::group_type_builder(root_schema.name())
+        .with_fields(fields)
+        .build()?;
+    Ok(projection)
+}
+
+fn row_to_json(row: &Row) -> JsonValue {
+    let json_obj: JsonMap<String, JsonValue> = row
+        .get_column_iter()
+        .map(|(column, field)| (column.clone(), field_to_json(field)))
+        .collect();
+    JsonValue::Object(json_obj)
+}
+
+/// Converts a Parquet value to JSON. Dates and timestamps are converted to RFC 3339 strings and
+/// binary values to base64 strings, the formats expected by the `datetime` and `bytes` fields.
+fn field_to_json(field: &Field) -> JsonValue {
+    match field {
+        Field::Null => JsonValue::Null,
+        Field::Bool(value) => JsonValue::Bool(*value),
+        Field::Byte(value) => JsonValue::from(*value),
+        Field::Short(value) => JsonValue::from(*value),
+        Field::Int(value) => JsonValue::from(*value),
+        Field::Long(value) => JsonValue::from(*value),
+        Field::UByte(value) => JsonValue::from(*value),
+        Field::UShort(value) => JsonValue::from(*value),
+        Field::UInt(value) => JsonValue::from(*value),
+        Field::ULong(value) => JsonValue::from(*value),
+        Field::Float16(value) => float_to_json(f64::from(*value)),
+        Field::Float(value) => float_to_json(*value as f64),
+        Field::Double(value) => float_to_json(*value),
+        Field::Decimal(_) => JsonValue::String(field.to_string()),
+        Field::Str(value) => JsonValue::String(value.clone()),
+        Field::Bytes(value) => JsonValue::String(BASE64_STANDARD.encode(value.data())),
+        Field::Date(num_days) => timestamp_nanos_to_json(*num_days as i128 * 86_400_000_000_000),
+        Field::TimestampMillis(millis) => timestamp_nanos_to_json(*millis as i128 * 1_000_000),
+        Field::TimestampMicros(micros) => timestamp_nanos_to_json(*micros as i128 * 1_000),
+        Field::Group(row) => row_to_json(row),
+        Field::ListInternal(list) => {
+            JsonValue::Array(list.elements().iter().map(field_to_json).collect())
+        }
+        Field::MapInternal(map) => {
+            let json_obj: JsonMap<String, JsonValue> = map
+                .entries()
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Field::Str(key) => key.clone(),
+                        key => key.to_string(),
+                    };
+                    (key, field_to_json(value))
+                })
+                .collect();
+            JsonValue::Object(json_obj)
+        }
+    }
+}
+
+fn float_to_json(value: f64) -> JsonValue {
+    JsonNumber::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+}
+
+fn timestamp_nanos_to_json(timestamp_nanos: i128) -> JsonValue {
+    OffsetDateTime::from_unix_timestamp_nanos(timestamp_nanos)
+        .ok()
+        .and_then(|datetime| datetime.format(&Rfc3339).ok())
+        .map_or(JsonValue::Null, JsonValue::String)
+}
+
+/// Reads the time range of a Parquet file, in seconds, from the statistics of the timestamp column
+/// stored in its footer, without downloading the rest of the file. Returns `None` when the column
+/// is missing, is not a timestamp column, or has no statistics.
+pub(crate) async fn read_time_range(
+    storage_resolver: &StorageResolver,
+    filepath: &Uri,
+    timestamp_column: &str,
+) -> anyhow::Result<Option<RangeInclusive<i64>>> {
+    let (dir_uri, file_name) = dir_and_filename(Path::new(filepath.as_str()))?;
+    let storage = storage_resolver.resolve(&dir_uri).await?;
+    let file_num_bytes = storage
+        .file_num_bytes(file_name)
+        .await
+        .with_context(|| format!("failed to access Parquet file `{filepath}`"))?
+        as usize;
+    let metadata_end = file_num_bytes
+        .checked_sub(FOOTER_SIZE)
+        .with_context(|| format!("Parquet file `{filepath}` is too small"))?;
+    let footer_bytes = storage
+        .get_slice(file_name, metadata_end..file_num_bytes)
+        .await
+        .with_context(|| format!("failed to read footer of Parquet file `{filepath}`"))?;
+    let footer: &[u8; FOOTER_SIZE] = footer_bytes
+        .as_slice()
+        .try_into()
+        .context("failed to read Parquet footer")?;
+    let metadata_num_bytes = decode_footer(footer)?;
+    let metadata_start = metadata_end
+        .checked_sub(metadata_num_bytes)
+        .with_context(|| format!("Parquet file `{filepath}` is corrupted"))?;
+    let metadata_bytes = storage
+        .get_slice(file_name, metadata_start..metadata_end)
+        .await
+        .with_context(|| format!("failed to read metadata of Parquet file `{filepath}`"))?;
+    let metadata = decode_metadata(metadata_bytes.as_slice())?;
+    Ok(time_range_from_metadata(&metadata, timestamp_column))
+}
+
+fn time_range_from_metadata(
+    metadata: &ParquetMetaData,
+    timestamp_column: &str,
+) -> Option<RangeInclusive<i64>> {
+    let mut min_timestamp_secs = i64::MAX;
+    let mut max_timestamp_secs = i64::MIN;
+
+    for row_group in metadata.row_groups() {
+        let column = row_group
+            .columns()
+            .iter()
+            .find(|column| column.column_path().string() == timestamp_column)?;
+        let column_descr = column.column_descr();
+        let nanos_per_unit: i128 = match column_descr.logical_type() {
+            Some(LogicalType::Timestamp { unit, .. }) => match unit {
+                TimeUnit::MILLIS(_) => 1_000_000,
+                TimeUnit::MICROS(_) => 1_000,
+                TimeUnit::NANOS(_) => 1,
+            },
+            _ => match column_descr.converted_type() {
+                ConvertedType::TIMESTAMP_MILLIS => 1_000_000,
+                ConvertedType::TIMESTAMP_MICROS => 1_000,
+                _ => return None,
+            },
+        };
+        let Some(Statistics::Int64(statistics)) = column.statistics() else {
+            return None;
+        };
+        if !statistics.has_min_max_set() {
+            return None;
+        }
+        let to_secs =
+            |timestamp: i64| (timestamp as i128 * nanos_per_unit).div_euclid(1_000_000_000) as i64;
+        min_timestamp_secs = min_timestamp_secs.min(to_secs(*statistics.min()));
+        max_timestamp_secs = max_timestamp_secs.max(to_secs(*statistics.max()));
+    }
+    if min_timestamp_secs > max_timestamp_secs {
+        return None;
+    }
+    Some(min_timestamp_secs..=max_timestamp_secs)
+}
+
+/// Checks that the files of the source exist.
+pub(super) async fn check_connectivity(
+    storage_resolver: &StorageResolver,
+    params: &ParquetSourceParams,
+) -> anyhow::Result<()> {
+    for filepath in &params.filepaths {
+        let (dir_uri, file_name) = dir_and_filename(Path::new(filepath.as_str()))?;
+        let storage = storage_resolver.resolve(&dir_uri).await?;
+        storage
+            .file_num_bytes(file_name)
+            .await
+            .with_context(|| format!("failed to access Parquet file `{filepath}`"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::num::NonZeroUsize;
+    use std::str::FromStr;
+
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use quickwit_actors::{Actor, Universe};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use quickwit_metastore::metastore_for_test;
+    use quickwit_proto::types::IndexUid;
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::SourceActor;
+
+    pub(crate) fn write_parquet_file(path: &Path) {
+        let schema = parse_message_type(
+            "message schema {
+                REQUIRED INT64 timestamp (TIMESTAMP_MILLIS);
+                REQUIRED BYTE_ARRAY body (UTF8);
+                OPTIONAL INT64 status;
+            }",
+        )
+        .unwrap();
+        let properties = WriterProperties::builder().build();
+        let file = File::create(path).unwrap();
+        let mut file_writer =
+            SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties)).unwrap();
+        let mut row_group_writer = file_writer.next_row_group().unwrap();
+
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        column_writer
+            .typed::<Int64Type>()
+            .write_batch(&[1_700_000_000_000, 1_700_000_001_000], None, None)
+            .unwrap();
+        column_writer.close().unwrap();
+
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        column_writer
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[ByteArray::from("foo"), ByteArray::from("bar")],
+                None,
+                None,
+            )
+            .unwrap();
+        column_writer.close().unwrap();
+
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        column_writer
+            .typed::<Int64Type>()
+            .write_batch(&[404], Some(&[1, 0]), None)
+            .unwrap();
+        column_writer.close().unwrap();
+
+        row_group_writer.close().unwrap();
+        file_writer.close().unwrap();
+    }
+
+    async fn run_parquet_source(
+        params: ParquetSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> (JsonValue, Vec<JsonValue>, SourceCheckpointDelta) {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_config = SourceConfig {
+            source_id: "test-parquet-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Parquet(params.clone()),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let parquet_source = ParquetSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                IndexUid::new_with_random_ulid("test-index"),
+                source_config,
+                metastore_for_test(),
+                PathBuf::from("./queues"),
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let source_actor = SourceActor {
+            source: Box::new(parquet_source),
+            doc_processor_mailbox,
+        };
+        assert_eq!(
+            source_actor.name(),
+            "ParquetSource { source_id=test-parquet-source }"
+        );
+        let (_source_mailbox, source_handle) = universe.spawn_builder().spawn(source_actor);
+        let observation = source_handle.process_pending_and_observe().await;
+
+        let mut docs = Vec::new();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+
+        for batch in doc_processor_inbox.drain_for_test_typed::<RawDocBatch>() {
+            for doc in &batch.docs {
+                docs.push(serde_json::from_slice(doc).unwrap());
+            }
+            checkpoint_delta.extend(batch.checkpoint_delta).unwrap();
+        }
+        universe.assert_quit().await;
+        (observation.state, docs, checkpoint_delta)
+    }
+
+    #[tokio::test]
+    async fn test_parquet_source() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.parquet");
+        write_parquet_file(&file_path);
+
+        let filepath = Uri::from_str(file_path.to_str().unwrap()).unwrap();
+        let params = ParquetSourceParams {
+            filepaths: vec![filepath.clone()],
+            columns: Vec::new(),
+            lazy: false,
+        };
+        let (state, docs, checkpoint_delta) =
+            run_parquet_source(params.clone(), SourceCheckpoint::default()).await;
+        assert_eq!(state["num_indexed_rows"], 2);
+        assert_eq!(state["num_indexed_files"], 1);
+        assert_eq!(state["num_pending_files"], 0);
+        assert_eq!(
+            docs,
+            [
+                json!({"timestamp": "2023-11-14T22:13:20Z", "body": "foo", "status": 404}),
+                json!({"timestamp": "2023-11-14T22:13:21Z", "body": "bar", "status": null}),
+            ]
+        );
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+        assert_eq!(
+            checkpoint.position_for_partition(&PartitionId::from(filepath.as_str())),
+            Some(&Position::eof(2u64))
+        );
+
+        // The files already indexed are skipped.
+        let (state, docs, _) = run_parquet_source(params, checkpoint).await;
+        assert_eq!(state["num_indexed_rows"], 0);
+        assert_eq!(state["num_indexed_files"], 1);
+        assert!(docs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_parquet_source_resumes_from_checkpoint() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.parquet");
+        write_parquet_file(&file_path);
+
+        let filepath = Uri::from_str(file_path.to_str().unwrap()).unwrap();
+        let params = ParquetSourceParams {
+            filepaths: vec![filepath.clone()],
+            columns: vec!["body".to_string()],
+            lazy: false,
+        };
+        let mut checkpoint = SourceCheckpoint::default();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(filepath.as_str()),
+                Position::Beginning,
+                Position::offset(1u64),
+            )
+            .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let (state, docs, _) = run_parquet_source(params, checkpoint).await;
+        assert_eq!(state["num_indexed_rows"], 1);
+        assert_eq!(docs, [json!({"body": "bar"})]);
+    }
+
+    #[tokio::test]
+    async fn test_lazy_parquet_source_exits_once_done() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.parquet");
+        write_parquet_file(&file_path);
+
+        let filepath = Uri::from_str(file_path.to_str().unwrap()).unwrap();
+        let params = ParquetSourceParams {
+            filepaths: vec![filepath],
+            columns: Vec::new(),
+            lazy: true,
+        };
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_config = SourceConfig {
+            source_id: "test-parquet-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Parquet(params.clone()),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let parquet_source = ParquetSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                IndexUid::new_with_random_ulid("test-index"),
+                source_config,
+                metastore_for_test(),
+                PathBuf::from("./queues"),
+            ),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap();
+        let source_actor = SourceActor {
+            source: Box::new(parquet_source),
+            doc_processor_mailbox,
+        };
+        let (_source_mailbox, source_handle) = universe.spawn_builder().spawn(source_actor);
+        let (exit_status, state) = source_handle.join().await;
+        assert!(exit_status.is_success());
+        assert_eq!(state["num_indexed_rows"], 2);
+
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_read_time_range() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.parquet");
+        write_parquet_file(&file_path);
+
+        let filepath = Uri::from_str(file_path.to_str().unwrap()).unwrap();
+        let storage_resolver = StorageResolver::for_test();
+
+        let time_range = read_time_range(&storage_resolver, &filepath, "timestamp")
+            .await
+            .unwrap();
+        assert_eq!(time_range, Some(1_700_000_000..=1_700_000_001));
+
+        let time_range = read_time_range(&storage_resolver, &filepath, "status")
+            .await
+            .unwrap();
+        assert_eq!(time_range, None);
+
+        let time_range = read_time_range(&storage_resolver, &filepath, "missing")
+            .await
+            .unwrap();
+        assert_eq!(time_range, None);
+    }
+
+    #[test]
+    fn test_project_columns() {
+        let root_schema = parse_message_type(
+            "message schema {
+                REQUIRED INT64 timestamp (TIMESTAMP_MILLIS);
+                REQUIRED BYTE_ARRAY body (UTF8);
+            }",
+        )
+        .unwrap();
+        let projection = project_columns(&root_schema, &["body".to_string()]).unwrap();
+        assert_eq!(projection.get_fields().len(), 1);
+        assert_eq!(projection.get_fields()[0].name(), "body");
+
+        let error = project_columns(&root_schema, &["severity".to_string()]).unwrap_err();
+        assert_eq!(error.to_string(), "column `severity` does not exist");
+    }
+}
@@ -555,6 +555,8 @@ mod pulsar_broker_tests {
                 authentication: None,
             }),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         (source_id, source_config)
@@ -0,0 +1,551 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::split_file;
+use quickwit_config::{build_doc_mapper, ReindexSourceParams};
+use quickwit_doc_mapper::{DocMapper, SOURCE_FIELD_NAME};
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_metastore::{
+    IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    MetastoreServiceStreamSplitsExt, SplitMetadata, SplitState,
+};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, ListSplitsRequest, MetastoreService, MetastoreServiceClient, SourceType,
+};
+use quickwit_proto::types::{IndexUid, PipelineUid, Position};
+use quickwit_storage::Storage;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tantivy::schema::{Document as DocumentTrait, NamedFieldDocument};
+use tantivy::{DocAddress, Index, ReloadPolicy, Searcher, TantivyDocument};
+use tempfile::TempDir;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use tracing::info;
+
+use super::{BatchBuilder, BATCH_NUM_BYTES_LIMIT};
+use crate::actors::DocProcessor;
+use crate::get_tantivy_directory_from_split_bundle;
+use crate::source::{Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
+
+/// Maximum number of documents in a batch emitted by the reindex source.
+const BATCH_NUM_DOCS_LIMIT: usize = 1_000;
+
+/// Once all the splits listed previously have been reindexed, the source lists the splits of the
+/// source index again at this interval to pick up new mature splits.
+const LIST_SPLITS_INTERVAL: Duration = Duration::from_secs(if cfg!(test) { 1 } else { 30 });
+
+/// Reads the documents of the splits of another index, the source index, and emits them for
+/// indexing into the index of the source.
+///
+/// Only mature splits are reindexed: immature splits may still be merged into new splits, which
+/// would be reindexed a second time. The checkpoint of the source has one partition per split of
+/// the source index, whose position is the number of documents of the split reindexed so far.
+pub struct ReindexSource {
+    source_id: String,
+    params: ReindexSourceParams,
+    pipeline_uid: PipelineUid,
+    metastore: MetastoreServiceClient,
+    source_index_uid: IndexUid,
+    source_index_storage: Arc<dyn Storage>,
+    source_doc_mapper: Arc<dyn DocMapper>,
+    scratch_directory: TempDir,
+    checkpoint: SourceCheckpoint,
+    pending_splits: VecDeque<SplitMetadata>,
+    current_split_opt: Option<SplitDocReader>,
+    state: ReindexSourceState,
+}
+
+impl fmt::Debug for ReindexSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReindexSource")
+            .field("source_id", &self.source_id)
+            .field("source_index_uid", &self.source_index_uid)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ReindexSourceState {
+    num_reindexed_docs: u64,
+    num_reindexed_splits: usize,
+    num_pending_splits: usize,
+    current_split_id_opt: Option<String>,
+}
+
+pub struct ReindexSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for ReindexSourceFactory {
+    type Source = ReindexSource;
+    type Params = ReindexSourceParams;
+
+    async fn typed_create_source(
+        runtime_args: Arc<SourceRuntimeArgs>,
+        params: ReindexSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        let mut metastore = runtime_args.metastore.clone();
+        let index_metadata_request =
+            IndexMetadataRequest::for_index_id(params.source_index_id.clone());
+        let source_index_metadata = metastore
+            .index_metadata(index_metadata_request)
+            .await?
+            .deserialize_index_metadata()?;
+        let source_doc_mapper = build_doc_mapper(
+            &source_index_metadata.index_config.doc_mapping,
+            &source_index_metadata.index_config.search_settings,
+        )?;
+        let source_index_storage = runtime_args
+            .storage_resolver
+            .resolve(source_index_metadata.index_uri())
+            .await?;
+        let scratch_directory = tempfile::tempdir()?;
+
+        Ok(ReindexSource {
+            source_id: runtime_args.source_id().to_string(),
+            params,
+            pipeline_uid: runtime_args.pipeline_uid(),
+            metastore,
+            source_index_uid: source_index_metadata.index_uid,
+            source_index_storage,
+            source_doc_mapper,
+            scratch_directory,
+            checkpoint,
+            pending_splits: VecDeque::new(),
+            current_split_opt: None,
+            state: ReindexSourceState::default(),
+        })
+    }
+}
+
+impl ReindexSource {
+    /// Lists the mature splits of the source index that have not been fully reindexed yet. The
+    /// splits are ordered differently for each pipeline so that the pipelines of the source
+    /// reindex different splits.
+    async fn list_pending_splits(&mut self, ctx: &SourceContext) -> anyhow::Result<()> {
+        let list_splits_query = ListSplitsQuery::for_index(self.source_index_uid.clone())
+            .with_split_state(SplitState::Published)
+            .retain_mature(OffsetDateTime::now_utc());
+        let list_splits_request =
+            ListSplitsRequest::try_from_list_splits_query(&list_splits_query)?;
+        let splits = ctx
+            .protect_future(async {
+                self.metastore
+                    .list_splits(list_splits_request)
+                    .await?
+                    .collect_splits_metadata()
+                    .await
+            })
+            .await?;
+        let mut pending_splits: Vec<SplitMetadata> = splits
+            .into_iter()
+            .filter(|split| {
+                !self
+                    .checkpoint
+                    .position_for_partition(&PartitionId::from(split.split_id()))
+                    .map_or(false, |position| position.is_eof())
+            })
+            .collect();
+        pending_splits.sort_by_cached_key(|split| {
+            let mut hasher = DefaultHasher::new();
+            (self.pipeline_uid, split.split_id()).hash(&mut hasher);
+            hasher.finish()
+        });
+        self.state.num_pending_splits = pending_splits.len();
+        self.pending_splits = pending_splits.into();
+        Ok(())
+    }
+
+    /// Downloads the split to the scratch directory and opens it, skipping the documents already
+    /// reindexed.
+    async fn open_split(
+        &self,
+        split: SplitMetadata,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<SplitDocReader> {
+        let split_id = split.split_id;
+        let partition_id = PartitionId::from(split_id.as_str());
+        let num_docs_to_skip = self
+            .checkpoint
+            .position_for_partition(&partition_id)
+            .and_then(|position| position.as_usize())
+            .unwrap_or(0);
+        let split_file_name = split_file(&split_id);
+        let split_file_path = self.scratch_directory.path().join(&split_file_name);
+        ctx.protect_future(
+            self.source_index_storage
+                .copy_to_file(Path::new(&split_file_name), &split_file_path),
+        )
+        .await
+        .with_context(|| format!("failed to download split `{split_id}`"))?;
+        info!(split_id=%split_id, num_docs_to_skip, "reindexing split");
+        SplitDocReader::open(split_id, partition_id, split_file_path, num_docs_to_skip)
+    }
+}
+
+#[async_trait]
+impl Source for ReindexSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let split_doc_reader = if let Some(split_doc_reader) = self.current_split_opt.take() {
+            split_doc_reader
+        } else {
+            if self.pending_splits.is_empty() {
+                self.list_pending_splits(ctx).await?;
+            }
+            let Some(split) = self.pending_splits.pop_front() else {
+                return Ok(LIST_SPLITS_INTERVAL);
+            };
+            self.state.num_pending_splits = self.pending_splits.len();
+            self.open_split(split, ctx).await?
+        };
+        let max_num_docs = self
+            .params
+            .max_num_docs_per_sec
+            .map_or(BATCH_NUM_DOCS_LIMIT, |max_num_docs_per_sec| {
+                BATCH_NUM_DOCS_LIMIT.min(max_num_docs_per_sec as usize)
+            });
+        let from_num_docs = split_doc_reader.num_docs_read;
+        let source_doc_mapper = self.source_doc_mapper.clone();
+
+        let (split_doc_reader, docs_res) = ctx
+            .protect_future(spawn_blocking(move || {
+                let mut split_doc_reader = split_doc_reader;
+                let docs_res = split_doc_reader.read_docs(&*source_doc_mapper, max_num_docs);
+                (split_doc_reader, docs_res)
+            }))
+            .await
+            .context("failed to read documents")?;
+        let docs = docs_res?;
+        let num_docs = docs.len();
+
+        let mut batch_builder = BatchBuilder::with_capacity(num_docs, SourceType::Reindex);
+
+        for doc in docs {
+            batch_builder.add_doc(doc);
+        }
+        let from_position = if from_num_docs == 0 {
+            Position::Beginning
+        } else {
+            Position::offset(from_num_docs)
+        };
+        let to_position = if split_doc_reader.is_exhausted {
+            Position::eof(split_doc_reader.num_docs_read)
+        } else {
+            Position::offset(split_doc_reader.num_docs_read)
+        };
+        batch_builder
+            .checkpoint_delta
+            .record_partition_delta(
+                split_doc_reader.partition_id.clone(),
+                from_position,
+                to_position,
+            )
+            .context("failed to record partition delta")?;
+        self.checkpoint
+            .try_apply_delta(batch_builder.checkpoint_delta.clone())
+            .context("failed to apply checkpoint delta")?;
+        self.state.num_reindexed_docs += num_docs as u64;
+
+        if split_doc_reader.is_exhausted {
+            info!(split_id=%split_doc_reader.split_id, "reindexed split");
+            self.state.num_reindexed_splits += 1;
+            self.state.current_split_id_opt = None;
+            tokio::fs::remove_file(&split_doc_reader.split_file_path)
+                .await
+                .context("failed to delete split file")?;
+        } else {
+            self.state.current_split_id_opt = Some(split_doc_reader.split_id.clone());
+            self.current_split_opt = Some(split_doc_reader);
+        }
+        ctx.send_message(doc_processor_mailbox, batch_builder.build())
+            .await?;
+
+        let wait_for =
+            self.params
+                .max_num_docs_per_sec
+                .map_or(Duration::ZERO, |max_num_docs_per_sec| {
+                    Duration::from_secs_f64(num_docs as f64 / max_num_docs_per_sec as f64)
+                });
+        Ok(wait_for)
+    }
+
+    fn name(&self) -> String {
+        format!("ReindexSource {{ source_id={} }}", self.source_id)
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        serde_json::to_value(&self.state).expect("the state should be JSON serializable")
+    }
+}
+
+/// Reads the alive documents of a split downloaded to the scratch directory in doc address order.
+struct SplitDocReader {
+    split_id: String,
+    partition_id: PartitionId,
+    split_file_path: PathBuf,
+    searcher: Searcher,
+    next_doc_address: DocAddress,
+    num_docs_read: usize,
+    is_exhausted: bool,
+}
+
+impl SplitDocReader {
+    fn open(
+        split_id: String,
+        partition_id: PartitionId,
+        split_file_path: PathBuf,
+        num_docs_to_skip: usize,
+    ) -> anyhow::Result<Self> {
+        let directory = get_tantivy_directory_from_split_bundle(&split_file_path)?;
+        let index = Index::open(directory)?;
+        let index_reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = index_reader.searcher();
+        let mut split_doc_reader = Self {
+            split_id,
+            partition_id,
+            split_file_path,
+            searcher,
+            next_doc_address: DocAddress::new(0, 0),
+            num_docs_read: 0,
+            is_exhausted: false,
+        };
+        while split_doc_reader.num_docs_read < num_docs_to_skip
+            && split_doc_reader.next_doc_address().is_some()
+        {}
+        Ok(split_doc_reader)
+    }
+
+    fn next_doc_address(&mut self) -> Option<DocAddress> {
+        loop {
+            let segment_ord = self.next_doc_address.segment_ord as usize;
+            let Some(segment_reader) = self.searcher.segment_readers().get(segment_ord) else {
+                self.is_exhausted = true;
+                return None;
+            };
+            if self.next_doc_address.doc_id >= segment_reader.max_doc() {
+                self.next_doc_address = DocAddress::new(segment_ord as u32 + 1, 0);
+                continue;
+            }
+            let doc_address = self.next_doc_address;
+            self.next_doc_address.doc_id += 1;
+
+            if !segment_reader.is_deleted(doc_address.doc_id) {
+                self.num_docs_read += 1;
+                return Some(doc_address);
+            }
+        }
+    }
+
+    /// Reads up to `max_num_docs` documents and converts them back to JSON.
+    fn read_docs(
+        &mut self,
+        doc_mapper: &dyn DocMapper,
+        max_num_docs: usize,
+    ) -> anyhow::Result<Vec<Bytes>> {
+        let mut docs = Vec::new();
+        let mut num_bytes = 0;
+
+        while docs.len() < max_num_docs && num_bytes < BATCH_NUM_BYTES_LIMIT {
+            let Some(doc_address) = self.next_doc_address() else {
+                break;
+            };
+            let doc: TantivyDocument = self.searcher.doc(doc_address)?;
+            let named_doc = doc.to_named_doc(self.searcher.schema());
+            let doc_bytes = doc_to_json_bytes(doc_mapper, named_doc)?;
+            num_bytes += doc_bytes.len() as u64;
+            docs.push(doc_bytes);
+        }
+        Ok(docs)
+    }
+}
+
+/// Rebuilds a JSON document from its stored fields. When the source index stores the original
+/// documents, they are reindexed as is.
+fn doc_to_json_bytes(
+    doc_mapper: &dyn DocMapper,
+    named_doc: NamedFieldDocument,
+) -> anyhow::Result<Bytes> {
+    let NamedFieldDocument(named_doc_map) = named_doc;
+    let mut doc_json = doc_mapper.doc_to_json(named_doc_map)?;
+
+    if let Some(JsonValue::Object(source_json)) = doc_json.remove(SOURCE_FIELD_NAME) {
+        doc_json = source_json;
+    }
+    let doc_bytes = serde_json::to_vec(&doc_json)?;
+    Ok(Bytes::from(doc_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use quickwit_actors::Actor;
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::{get_reindex_progress, ReindexStage};
+    use quickwit_proto::indexing::IndexingPipelineId;
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::SourceActor;
+    use crate::TestSandbox;
+
+    #[tokio::test]
+    async fn test_reindex_source() {
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: severity
+                type: text
+                tokenizer: raw
+        "#;
+        let indexing_settings_yaml = r#"
+            merge_policy:
+              type: no_merge
+        "#;
+        let test_sandbox = TestSandbox::create(
+            "test-reindex-source-index",
+            doc_mapping_yaml,
+            indexing_settings_yaml,
+            &["body"],
+        )
+        .await
+        .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "foo", "severity": "INFO"}),
+                json!({"body": "bar", "severity": "WARN"}),
+            ])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![json!({"body": "baz", "severity": "ERROR"})])
+            .await
+            .unwrap();
+
+        let params = ReindexSourceParams {
+            source_index_id: "test-reindex-source-index".to_string(),
+            max_num_docs_per_sec: None,
+            alias: None,
+        };
+        let source_config = SourceConfig {
+            source_id: "test-reindex-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Reindex(params.clone()),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let pipeline_id = IndexingPipelineId {
+            node_id: "test-node".to_string(),
+            index_uid: IndexUid::new_with_random_ulid("test-reindex-dest-index"),
+            source_id: source_config.source_id.clone(),
+            pipeline_uid: PipelineUid::for_test(0u128),
+        };
+        let runtime_args = Arc::new(SourceRuntimeArgs {
+            pipeline_id,
+            source_config,
+            metastore: test_sandbox.metastore(),
+            ingester_pool: Default::default(),
+            queues_dir_path: PathBuf::from("./queues"),
+            storage_resolver: test_sandbox.storage_resolver(),
+            event_broker: Default::default(),
+        });
+        let mut metastore = test_sandbox.metastore();
+        let checkpoint = SourceCheckpoint::default();
+
+        let reindex_progress = get_reindex_progress(&mut metastore, &params, &checkpoint, true)
+            .await
+            .unwrap();
+        assert_eq!(reindex_progress.stage, ReindexStage::Backfill);
+        assert_eq!(reindex_progress.num_splits, 2);
+        assert_eq!(reindex_progress.num_reindexed_splits, 0);
+        assert_eq!(reindex_progress.num_docs, 3);
+
+        let reindex_source =
+            ReindexSourceFactory::typed_create_source(runtime_args, params.clone(), checkpoint)
+                .await
+                .unwrap();
+        let universe = test_sandbox.universe();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_actor = SourceActor {
+            source: Box::new(reindex_source),
+            doc_processor_mailbox,
+        };
+        assert_eq!(
+            source_actor.name(),
+            "ReindexSource { source_id=test-reindex-source }"
+        );
+        let (_source_mailbox, source_handle) = universe.spawn_builder().spawn(source_actor);
+        let observation = source_handle.process_pending_and_observe().await;
+        assert_eq!(observation.state["num_reindexed_docs"], 3);
+        assert_eq!(observation.state["num_reindexed_splits"], 2);
+
+        let mut checkpoint = SourceCheckpoint::default();
+        let mut docs: Vec<JsonValue> = Vec::new();
+
+        for batch in doc_processor_inbox.drain_for_test_typed::<RawDocBatch>() {
+            for doc in &batch.docs {
+                docs.push(serde_json::from_slice(doc).unwrap());
+            }
+            checkpoint.try_apply_delta(batch.checkpoint_delta).unwrap();
+        }
+        docs.sort_by_key(|doc| doc["body"].as_str().unwrap().to_string());
+        assert_eq!(
+            docs,
+            [
+                json!({"body": "bar", "severity": "WARN"}),
+                json!({"body": "baz", "severity": "ERROR"}),
+                json!({"body": "foo", "severity": "INFO"}),
+            ]
+        );
+        let reindex_progress = get_reindex_progress(&mut metastore, &params, &checkpoint, false)
+            .await
+            .unwrap();
+        assert_eq!(reindex_progress.stage, ReindexStage::Completed);
+        assert!(reindex_progress.paused);
+        assert_eq!(reindex_progress.num_reindexed_splits, 2);
+        assert_eq!(reindex_progress.num_reindexed_docs, 3);
+
+        test_sandbox.assert_quit().await;
+    }
+}
@@ -138,6 +138,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         source_loader
@@ -0,0 +1,734 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, mem};
+
+use anyhow::{bail, Context};
+use async_compression::tokio::bufread::GzipDecoder;
+use async_trait::async_trait;
+use aws_sdk_sqs::config::Region;
+use aws_sdk_sqs::types::{
+    ChangeMessageVisibilityBatchRequestEntry, DeleteMessageBatchRequestEntry, Message,
+    QueueAttributeName,
+};
+use aws_sdk_sqs::{Client as SqsClient, Config as SqsConfig};
+use bytes::Bytes;
+use percent_encoding::percent_decode_str;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_aws::retry::aws_retry;
+use quickwit_aws::{get_aws_config, DEFAULT_AWS_REGION};
+use quickwit_common::rand::append_random_suffix;
+use quickwit_common::retry::RetryParams;
+use quickwit_config::{RegionOrEndpoint, SqsMessageType, SqsSourceParams};
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_proto::metastore::SourceType;
+use quickwit_proto::types::Position;
+use quickwit_storage::StorageErrorKind;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tracing::{debug, info, warn};
+
+use super::file_source::dir_and_filename;
+use super::{SourceActor, BATCH_NUM_BYTES_LIMIT, EMIT_BATCHES_TIMEOUT};
+use crate::actors::DocProcessor;
+use crate::source::{BatchBuilder, Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
+
+/// Maximum number of messages returned by a receive request and of entries of a batch request,
+/// as enforced by SQS.
+const MAX_NUM_MESSAGES_PER_REQUEST: usize = 10;
+
+/// Long polling duration of receive requests. It must remain below `EMIT_BATCHES_TIMEOUT`.
+const RECEIVE_WAIT_TIME_SECS: i32 = if cfg!(test) { 0 } else { 1 };
+
+pub struct SqsSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for SqsSourceFactory {
+    type Source = SqsSource;
+    type Params = SqsSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: SqsSourceParams,
+        // Messages are only deleted from the queue once their documents are published, so
+        // unpublished messages are received again after a restart.
+        _checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        SqsSource::try_new(ctx, params).await
+    }
+}
+
+#[derive(Default)]
+pub struct SqsSourceState {
+    /// Number of bytes of message bodies and S3 objects processed by the source.
+    num_bytes_processed: u64,
+    /// Number of messages processed by the source.
+    num_messages_processed: u64,
+    /// Number of S3 objects read by the source.
+    num_objects_processed: u64,
+    /// Number of invalid messages, i.e., that were empty or could not be parsed.
+    num_invalid_messages: u64,
+    /// Number of messages deleted from the queue after their documents were published.
+    num_messages_deleted: u64,
+    /// Current position of the source, i.e. the number of messages processed.
+    current_position: Position,
+}
+
+/// A message received from the queue whose documents have not been published yet.
+struct InFlightMessage {
+    receipt_handle: String,
+    /// Time at which the message becomes visible to other consumers again.
+    visibility_deadline: Instant,
+}
+
+/// A source that receives messages from an Amazon SQS queue. Messages are hidden from other
+/// consumers while their documents are indexed, and deleted once the documents are published,
+/// which provides at-least-once semantics.
+pub struct SqsSource {
+    ctx: Arc<SourceRuntimeArgs>,
+    params: SqsSourceParams,
+    sqs_client: SqsClient,
+    retry_params: RetryParams,
+    partition_id: PartitionId,
+    state: SqsSourceState,
+    /// In-flight messages indexed by their position.
+    in_flight_messages: BTreeMap<u64, InFlightMessage>,
+}
+
+impl fmt::Debug for SqsSource {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("SqsSource")
+            .field("index_id", &self.ctx.index_id())
+            .field("source_id", &self.ctx.source_id())
+            .field("queue_url", &self.params.queue_url)
+            .finish()
+    }
+}
+
+impl SqsSource {
+    pub async fn try_new(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: SqsSourceParams,
+    ) -> anyhow::Result<Self> {
+        let sqs_client = get_sqs_client(params.region_or_endpoint.clone()).await?;
+        let queue_name = params
+            .queue_url
+            .rsplit('/')
+            .next()
+            .unwrap_or(&params.queue_url);
+        // TODO: replace with "<node_id>/<index_id>/<source_id>/<pipeline_ord>"
+        let partition_id = append_random_suffix(&format!("sqs-{queue_name}"));
+        let partition_id = PartitionId::from(partition_id);
+
+        info!(
+            index_id=%ctx.index_id(),
+            source_id=%ctx.source_id(),
+            queue_url=%params.queue_url,
+            message_type=?params.message_type,
+            "starting SQS source"
+        );
+        Ok(Self {
+            ctx,
+            params,
+            sqs_client,
+            retry_params: RetryParams::standard(),
+            partition_id,
+            state: SqsSourceState::default(),
+            in_flight_messages: BTreeMap::new(),
+        })
+    }
+
+    fn visibility_timeout(&self) -> Duration {
+        Duration::from_secs(self.params.visibility_timeout_secs as u64)
+    }
+
+    async fn receive_messages(&self) -> anyhow::Result<Vec<Message>> {
+        let response = aws_retry(&self.retry_params, || async {
+            self.sqs_client
+                .receive_message()
+                .queue_url(&self.params.queue_url)
+                .max_number_of_messages(MAX_NUM_MESSAGES_PER_REQUEST as i32)
+                .wait_time_seconds(RECEIVE_WAIT_TIME_SECS)
+                .visibility_timeout(self.params.visibility_timeout_secs as i32)
+                .send()
+                .await
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "failed to receive messages from queue `{}`",
+                self.params.queue_url
+            )
+        })?;
+        Ok(response.messages.unwrap_or_default())
+    }
+
+    /// Adds the documents of a message to the batch, sending intermediate batches to the doc
+    /// processor when a large S3 object exceeds the batch size limit. The message is
+    /// checkpointed in the batch that holds its last documents.
+    async fn process_message(
+        &mut self,
+        message: Message,
+        batch_builder: &mut BatchBuilder,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let received_at = Instant::now();
+        let receipt_handle = message
+            .receipt_handle
+            .context("SQS message should have a receipt handle")?;
+        let body = message.body.unwrap_or_default();
+
+        self.state.num_messages_processed += 1;
+        self.state.num_bytes_processed += body.len() as u64;
+
+        match self.params.message_type {
+            SqsMessageType::Raw => {
+                if body.is_empty() {
+                    self.state.num_invalid_messages += 1;
+                } else {
+                    batch_builder.add_doc(Bytes::from(body));
+                }
+            }
+            SqsMessageType::S3Notification => match parse_s3_notification(&body) {
+                Ok(object_uris) => {
+                    for object_uri in object_uris {
+                        self.read_object(&object_uri, batch_builder, doc_processor_mailbox, ctx)
+                            .await?;
+                    }
+                }
+                Err(error) => {
+                    warn!(%error, "failed to parse S3 event notification");
+                    self.state.num_invalid_messages += 1;
+                }
+            },
+        }
+        let to_offset = self.state.num_messages_processed;
+        let to_position = Position::offset(to_offset);
+        let from_position = mem::replace(&mut self.state.current_position, to_position.clone());
+
+        batch_builder
+            .checkpoint_delta
+            .record_partition_delta(self.partition_id.clone(), from_position, to_position)
+            .context("failed to record partition delta")?;
+
+        let in_flight_message = InFlightMessage {
+            receipt_handle,
+            visibility_deadline: received_at + self.visibility_timeout(),
+        };
+        self.in_flight_messages.insert(to_offset, in_flight_message);
+        Ok(())
+    }
+
+    /// Reads an S3 object line by line, one document per line. Gzip objects are decompressed.
+    /// Objects deleted since the notification was sent are skipped.
+    async fn read_object(
+        &mut self,
+        object_uri: &str,
+        batch_builder: &mut BatchBuilder,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let object_path = Path::new(object_uri);
+        let (dir_uri, file_name) = dir_and_filename(object_path)?;
+        let storage = self.ctx.storage_resolver.resolve(&dir_uri).await?;
+
+        let file_size = match ctx.protect_future(storage.file_num_bytes(file_name)).await {
+            Ok(file_size) => file_size as usize,
+            Err(error) if error.kind() == StorageErrorKind::NotFound => {
+                warn!(object_uri, "S3 object not found, skipping");
+                return Ok(());
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let stream = ctx
+            .protect_future(storage.get_slice_stream(file_name, 0..file_size))
+            .await?;
+        let stream: Box<dyn AsyncRead + Send + Unpin> =
+            if object_path.extension() == Some(OsStr::new("gz")) {
+                Box::new(GzipDecoder::new(BufReader::new(stream)))
+            } else {
+                stream
+            };
+        let mut reader = BufReader::new(stream);
+
+        loop {
+            let mut doc_line = String::new();
+            let num_bytes = ctx
+                .protect_future(reader.read_line(&mut doc_line))
+                .await
+                .with_context(|| format!("failed to read S3 object `{object_uri}`"))?;
+            if num_bytes == 0 {
+                break;
+            }
+            self.state.num_bytes_processed += num_bytes as u64;
+
+            if doc_line.trim().is_empty() {
+                continue;
+            }
+            batch_builder.add_doc(Bytes::from(doc_line));
+
+            if batch_builder.num_bytes >= BATCH_NUM_BYTES_LIMIT {
+                let batch = mem::replace(batch_builder, BatchBuilder::new(SourceType::Sqs));
+                ctx.send_message(doc_processor_mailbox, batch.build())
+                    .await?;
+            }
+        }
+        self.state.num_objects_processed += 1;
+        Ok(())
+    }
+
+    /// Extends the visibility timeout of the in-flight messages that are about to become
+    /// visible again.
+    async fn extend_visibility_timeouts(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let visibility_timeout = self.visibility_timeout();
+        let extension_threshold = now + visibility_timeout / 2;
+
+        let mut expiring_messages: Vec<&mut InFlightMessage> = self
+            .in_flight_messages
+            .values_mut()
+            .filter(|in_flight_message| in_flight_message.visibility_deadline < extension_threshold)
+            .collect();
+
+        for chunk in expiring_messages.chunks_mut(MAX_NUM_MESSAGES_PER_REQUEST) {
+            let entries: Vec<ChangeMessageVisibilityBatchRequestEntry> = chunk
+                .iter()
+                .enumerate()
+                .map(|(entry_idx, in_flight_message)| {
+                    ChangeMessageVisibilityBatchRequestEntry::builder()
+                        .id(entry_idx.to_string())
+                        .receipt_handle(&in_flight_message.receipt_handle)
+                        .visibility_timeout(self.params.visibility_timeout_secs as i32)
+                        .build()
+                })
+                .collect();
+            let response = self
+                .sqs_client
+                .change_message_visibility_batch()
+                .queue_url(&self.params.queue_url)
+                .set_entries(Some(entries))
+                .send()
+                .await
+                .context("failed to extend visibility timeout of SQS messages")?;
+
+            if let Some(failed_entries) = response.failed.filter(|failed| !failed.is_empty()) {
+                warn!(
+                    num_failed_entries=%failed_entries.len(),
+                    "failed to extend visibility timeout of some SQS messages, they may be \
+                     received again"
+                );
+            }
+            for in_flight_message in chunk.iter_mut() {
+                in_flight_message.visibility_deadline = now + visibility_timeout;
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_messages(&mut self, receipt_handles: Vec<String>) -> anyhow::Result<()> {
+        for chunk in receipt_handles.chunks(MAX_NUM_MESSAGES_PER_REQUEST) {
+            let entries: Vec<DeleteMessageBatchRequestEntry> = chunk
+                .iter()
+                .enumerate()
+                .map(|(entry_idx, receipt_handle)| {
+                    DeleteMessageBatchRequestEntry::builder()
+                        .id(entry_idx.to_string())
+                        .receipt_handle(receipt_handle)
+                        .build()
+                })
+                .collect();
+            let response = aws_retry(&self.retry_params, || async {
+                self.sqs_client
+                    .delete_message_batch()
+                    .queue_url(&self.params.queue_url)
+                    .set_entries(Some(entries.clone()))
+                    .send()
+                    .await
+            })
+            .await
+            .context("failed to delete SQS messages")?;
+
+            let num_failed_entries = response.failed.map(|failed| failed.len()).unwrap_or(0);
+
+            if num_failed_entries > 0 {
+                warn!(
+                    num_failed_entries=%num_failed_entries,
+                    "failed to delete some SQS messages, they will be received again"
+                );
+            }
+            self.state.num_messages_deleted += (chunk.len() - num_failed_entries) as u64;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Source for SqsSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let now = Instant::now();
+        let deadline = now + EMIT_BATCHES_TIMEOUT;
+        let mut batch_builder = BatchBuilder::new(SourceType::Sqs);
+
+        ctx.protect_future(self.extend_visibility_timeouts())
+            .await?;
+
+        while batch_builder.num_bytes < BATCH_NUM_BYTES_LIMIT && Instant::now() < deadline {
+            let messages = ctx.protect_future(self.receive_messages()).await?;
+
+            if messages.is_empty() {
+                break;
+            }
+            for message in messages {
+                self.process_message(message, &mut batch_builder, doc_processor_mailbox, ctx)
+                    .await?;
+            }
+            ctx.record_progress();
+        }
+        if !batch_builder.checkpoint_delta.is_empty() {
+            debug!(
+                num_bytes=%batch_builder.num_bytes,
+                num_docs=%batch_builder.docs.len(),
+                num_millis=%now.elapsed().as_millis(),
+                "sending doc batch to indexer"
+            );
+            let message = batch_builder.build();
+            ctx.send_message(doc_processor_mailbox, message).await?;
+        }
+        Ok(Duration::default())
+    }
+
+    async fn suggest_truncate(
+        &mut self,
+        checkpoint: SourceCheckpoint,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let Some(published_offset) = checkpoint
+            .position_for_partition(&self.partition_id)
+            .and_then(|position| position.as_u64())
+        else {
+            return Ok(());
+        };
+        let unpublished_messages = self.in_flight_messages.split_off(&(published_offset + 1));
+        let published_messages = mem::replace(&mut self.in_flight_messages, unpublished_messages);
+        let receipt_handles: Vec<String> = published_messages
+            .into_values()
+            .map(|in_flight_message| in_flight_message.receipt_handle)
+            .collect();
+        self.delete_messages(receipt_handles).await
+    }
+
+    fn name(&self) -> String {
+        format!("SqsSource{{source_id={}}}", self.ctx.source_id())
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        json!({
+            "index_id": self.ctx.index_id(),
+            "source_id": self.ctx.source_id(),
+            "queue_url": self.params.queue_url,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_objects_processed": self.state.num_objects_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+            "num_messages_deleted": self.state.num_messages_deleted,
+            "num_in_flight_messages": self.in_flight_messages.len(),
+        })
+    }
+}
+
+async fn get_sqs_client(
+    region_or_endpoint_opt: Option<RegionOrEndpoint>,
+) -> anyhow::Result<SqsClient> {
+    let aws_config = get_aws_config().await;
+
+    let mut sqs_config = SqsConfig::builder();
+    sqs_config.set_retry_config(aws_config.retry_config().cloned());
+    sqs_config.set_credentials_provider(aws_config.credentials_provider().cloned());
+    sqs_config.set_http_connector(aws_config.http_connector().cloned());
+    sqs_config.set_timeout_config(aws_config.timeout_config().cloned());
+    sqs_config.set_credentials_cache(aws_config.credentials_cache().cloned());
+    sqs_config.set_sleep_impl(Some(Arc::new(quickwit_aws::TokioSleep::default())));
+
+    match region_or_endpoint_opt {
+        Some(RegionOrEndpoint::Region(region)) => {
+            sqs_config = sqs_config.region(Some(Region::new(region)));
+        }
+        Some(RegionOrEndpoint::Endpoint(endpoint)) => {
+            sqs_config = sqs_config.endpoint_url(endpoint);
+            sqs_config = sqs_config.region(Some(DEFAULT_AWS_REGION));
+        }
+        None => {
+            let Some(region) = aws_config.region().cloned() else {
+                bail!("unable to sniff AWS region from environment");
+            };
+            sqs_config = sqs_config.region(Some(region));
+        }
+    }
+    Ok(SqsClient::from_conf(sqs_config.build()))
+}
+
+/// Checks whether we can establish a connection to the SQS service and access the queue.
+pub(super) async fn check_connectivity(params: SqsSourceParams) -> anyhow::Result<()> {
+    let sqs_client = get_sqs_client(params.region_or_endpoint).await?;
+    let retry_params = RetryParams::standard();
+
+    aws_retry(&retry_params, || async {
+        sqs_client
+            .get_queue_attributes()
+            .queue_url(&params.queue_url)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessages)
+            .send()
+            .await
+    })
+    .await
+    .with_context(|| format!("failed to access SQS queue `{}`", params.queue_url))?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct S3Notification {
+    #[serde(rename = "Records", default)]
+    records: Vec<S3EventRecord>,
+}
+
+#[derive(Deserialize)]
+struct S3EventRecord {
+    #[serde(rename = "eventName")]
+    event_name: String,
+    s3: S3Entity,
+}
+
+#[derive(Deserialize)]
+struct S3Entity {
+    bucket: S3Bucket,
+    object: S3Object,
+}
+
+#[derive(Deserialize)]
+struct S3Bucket {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct S3Object {
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct SnsNotification {
+    #[serde(rename = "Message")]
+    message: String,
+}
+
+/// Parses an S3 event notification, delivered directly or through SNS, and returns the URIs of
+/// the created objects. Test events and other event types yield no URI.
+fn parse_s3_notification(body: &str) -> anyhow::Result<Vec<String>> {
+    let body_json: JsonValue =
+        serde_json::from_str(body).context("S3 event notification is not valid JSON")?;
+
+    if body_json.get("TopicArn").is_some() {
+        let sns_notification: SnsNotification =
+            serde_json::from_value(body_json).context("failed to parse SNS notification")?;
+        return parse_s3_notification(&sns_notification.message);
+    }
+    let s3_notification: S3Notification =
+        serde_json::from_value(body_json).context("failed to parse S3 event notification")?;
+
+    let mut object_uris = Vec::new();
+
+    for record in s3_notification.records {
+        if !record.event_name.starts_with("ObjectCreated:") {
+            continue;
+        }
+        // Object keys are URL-encoded, with spaces encoded as `+`.
+        let key = record.s3.object.key.replace('+', " ");
+        let key = percent_decode_str(&key)
+            .decode_utf8()
+            .context("S3 object key is not valid UTF-8")?;
+        let object_uri = format!("s3://{}/{key}", record.s3.bucket.name);
+        object_uris.push(object_uri);
+    }
+    Ok(object_uris)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_s3_notification() {
+        let body = r#"{
+            "Records": [
+                {
+                    "eventVersion": "2.1",
+                    "eventSource": "aws:s3",
+                    "eventName": "ObjectCreated:Put",
+                    "s3": {
+                        "bucket": {"name": "my-bucket"},
+                        "object": {"key": "logs/2024/my+log%3D1.json.gz", "size": 1024}
+                    }
+                },
+                {
+                    "eventName": "ObjectRemoved:Delete",
+                    "s3": {
+                        "bucket": {"name": "my-bucket"},
+                        "object": {"key": "logs/old.json"}
+                    }
+                }
+            ]
+        }"#;
+        assert_eq!(
+            parse_s3_notification(body).unwrap(),
+            ["s3://my-bucket/logs/2024/my log=1.json.gz"]
+        );
+        let sns_body = json!({
+            "Type": "Notification",
+            "TopicArn": "arn:aws:sns:us-east-1:123456789012:my-topic",
+            "Message": body,
+        })
+        .to_string();
+        assert_eq!(
+            parse_s3_notification(&sns_body).unwrap(),
+            ["s3://my-bucket/logs/2024/my log=1.json.gz"]
+        );
+        let test_event_body = r#"{"Service": "Amazon S3", "Event": "s3:TestEvent"}"#;
+        assert!(parse_s3_notification(test_event_body).unwrap().is_empty());
+
+        parse_s3_notification("not JSON").unwrap_err();
+    }
+}
+
+#[cfg(all(test, feature = "sqs-localstack-tests"))]
+mod localstack_tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use quickwit_actors::{ActorContext, Universe};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use quickwit_metastore::metastore_for_test;
+    use quickwit_proto::types::IndexUid;
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+
+    const LOCALSTACK_ENDPOINT: &str = "http://localhost:4566";
+
+    #[tokio::test]
+    async fn test_sqs_source_raw_messages() {
+        let region_or_endpoint = RegionOrEndpoint::Endpoint(LOCALSTACK_ENDPOINT.to_string());
+        let sqs_client = get_sqs_client(Some(region_or_endpoint.clone()))
+            .await
+            .unwrap();
+        let queue_name = append_random_suffix("test-sqs-source");
+        let queue_url = sqs_client
+            .create_queue()
+            .queue_name(&queue_name)
+            .send()
+            .await
+            .unwrap()
+            .queue_url
+            .unwrap();
+        for doc in ["doc-1", "doc-2", "doc-3"] {
+            sqs_client
+                .send_message()
+                .queue_url(&queue_url)
+                .message_body(doc)
+                .send()
+                .await
+                .unwrap();
+        }
+        let params = SqsSourceParams {
+            queue_url: queue_url.clone(),
+            region_or_endpoint: Some(region_or_endpoint),
+            message_type: SqsMessageType::Raw,
+            visibility_timeout_secs: 30,
+        };
+        let source_config = SourceConfig {
+            source_id: "test-sqs-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Sqs(params.clone()),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let runtime_args = SourceRuntimeArgs::for_test(
+            IndexUid::new_with_random_ulid("test-index"),
+            source_config,
+            metastore_for_test(),
+            PathBuf::from("./queues"),
+        );
+        let mut source = SqsSource::try_new(runtime_args, params).await.unwrap();
+
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+        let (doc_processor_mailbox, doc_processor_inbox) =
+            universe.create_test_mailbox::<DocProcessor>();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(JsonValue::Null);
+        let ctx: SourceContext =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        while source.state.num_messages_processed < 3 {
+            source
+                .emit_batches(&doc_processor_mailbox, &ctx)
+                .await
+                .unwrap();
+        }
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        let mut docs: Vec<Bytes> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter().cloned())
+            .collect();
+        docs.sort();
+        assert_eq!(docs, ["doc-1", "doc-2", "doc-3"]);
+
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        for batch in batches {
+            checkpoint_delta.extend(batch.checkpoint_delta).unwrap();
+        }
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        source.suggest_truncate(checkpoint, &ctx).await.unwrap();
+        assert!(source.in_flight_messages.is_empty());
+        assert_eq!(source.state.num_messages_deleted, 3);
+
+        sqs_client
+            .delete_queue()
+            .queue_url(&queue_url)
+            .send()
+            .await
+            .unwrap();
+    }
+}
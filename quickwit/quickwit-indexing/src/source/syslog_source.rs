@@ -0,0 +1,922 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::rand::append_random_suffix;
+use quickwit_config::{SyslogProtocol, SyslogSourceParams};
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_proto::metastore::SourceType;
+use quickwit_proto::types::Position;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_rustls::{rustls, TlsAcceptor};
+use tracing::{debug, info, warn};
+
+use super::{BATCH_NUM_BYTES_LIMIT, EMIT_BATCHES_TIMEOUT};
+use crate::actors::DocProcessor;
+use crate::source::{BatchBuilder, Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
+
+/// Maximum size of a syslog message. Larger octet-counted or newline-delimited frames cause the
+/// connection to be closed.
+const MAX_FRAME_NUM_BYTES: usize = 64 * 1024;
+
+/// Maximum number of digits of the length prefix of an octet-counted frame.
+const MAX_FRAME_LEN_NUM_DIGITS: u64 = 5;
+
+const FRAME_CHANNEL_CAPACITY: usize = 1_024;
+
+const NIL_VALUE: &str = "-";
+
+const FACILITY_NAMES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITY_NAMES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+pub struct SyslogSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for SyslogSourceFactory {
+    type Source = SyslogSource;
+    type Params = SyslogSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: SyslogSourceParams,
+        _checkpoint: SourceCheckpoint, // Syslog senders cannot replay messages.
+    ) -> anyhow::Result<Self::Source> {
+        SyslogSource::try_new(ctx, params).await
+    }
+}
+
+#[derive(Default)]
+pub struct SyslogSourceState {
+    /// Number of bytes processed by the source.
+    num_bytes_processed: u64,
+    /// Number of messages processed by the source.
+    num_messages_processed: u64,
+    /// Number of invalid messages, i.e., that do not start with a valid priority.
+    num_invalid_messages: u64,
+    /// Current position of the source, i.e. the number of messages processed.
+    current_position: Position,
+}
+
+/// A source that listens for syslog messages formatted according to RFC 3164 or RFC 5424 and
+/// converts them into JSON documents.
+pub struct SyslogSource {
+    ctx: Arc<SourceRuntimeArgs>,
+    protocol: SyslogProtocol,
+    listen_addr: SocketAddr,
+    partition_id: PartitionId,
+    state: SyslogSourceState,
+    frame_rx: mpsc::Receiver<Bytes>,
+    listener_handle: JoinHandle<()>,
+}
+
+impl fmt::Debug for SyslogSource {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("SyslogSource")
+            .field("index_id", &self.ctx.index_id())
+            .field("source_id", &self.ctx.source_id())
+            .field("protocol", &self.protocol)
+            .field("listen_addr", &self.listen_addr)
+            .finish()
+    }
+}
+
+impl Drop for SyslogSource {
+    fn drop(&mut self) {
+        self.listener_handle.abort();
+    }
+}
+
+impl SyslogSource {
+    pub async fn try_new(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: SyslogSourceParams,
+    ) -> anyhow::Result<Self> {
+        let listen_addr = SocketAddr::from(([0, 0, 0, 0], params.listen_port));
+        let (frame_tx, frame_rx) = mpsc::channel(FRAME_CHANNEL_CAPACITY);
+
+        let (listen_addr, listener_handle) = match params.protocol {
+            SyslogProtocol::Tcp | SyslogProtocol::Tls => {
+                let tls_acceptor_opt = if params.protocol == SyslogProtocol::Tls {
+                    let cert_path = params
+                        .tls_cert_path
+                        .as_deref()
+                        .context("`tls_cert_path` is required with the `tls` protocol")?;
+                    let key_path = params
+                        .tls_key_path
+                        .as_deref()
+                        .context("`tls_key_path` is required with the `tls` protocol")?;
+                    Some(load_tls_acceptor(cert_path, key_path)?)
+                } else {
+                    None
+                };
+                let listener = TcpListener::bind(listen_addr)
+                    .await
+                    .with_context(|| format!("failed to listen on TCP address `{listen_addr}`"))?;
+                let listen_addr = listener.local_addr()?;
+                let listener_handle =
+                    tokio::spawn(accept_connections(listener, tls_acceptor_opt, frame_tx));
+                (listen_addr, listener_handle)
+            }
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind(listen_addr)
+                    .await
+                    .with_context(|| format!("failed to bind UDP address `{listen_addr}`"))?;
+                let listen_addr = socket.local_addr()?;
+                let listener_handle = tokio::spawn(receive_datagrams(socket, frame_tx));
+                (listen_addr, listener_handle)
+            }
+        };
+        // TODO: replace with "<node_id>/<index_id>/<source_id>/<pipeline_ord>"
+        let partition_id = append_random_suffix(&format!("syslog-{}", listen_addr.port()));
+        let partition_id = PartitionId::from(partition_id);
+
+        info!(
+            index_id=%ctx.index_id(),
+            source_id=%ctx.source_id(),
+            protocol=?params.protocol,
+            listen_addr=%listen_addr,
+            "starting syslog source"
+        );
+        Ok(Self {
+            ctx,
+            protocol: params.protocol,
+            listen_addr,
+            partition_id,
+            state: SyslogSourceState::default(),
+            frame_rx,
+            listener_handle,
+        })
+    }
+
+    fn process_frame(
+        &mut self,
+        frame: Bytes,
+        received_at: OffsetDateTime,
+        batch_builder: &mut BatchBuilder,
+    ) {
+        self.state.num_messages_processed += 1;
+        self.state.num_bytes_processed += frame.len() as u64;
+
+        let Some(doc) = parse_syslog_message(&frame, received_at) else {
+            self.state.num_invalid_messages += 1;
+            return;
+        };
+        let doc_json = serde_json::to_vec(&doc).expect("JSON object should serialize");
+        batch_builder.add_doc(Bytes::from(doc_json));
+    }
+}
+
+#[async_trait]
+impl Source for SyslogSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let now = Instant::now();
+        let received_at = OffsetDateTime::now_utc();
+        let mut batch_builder = BatchBuilder::new(SourceType::Syslog);
+        let num_messages_processed_before = self.state.num_messages_processed;
+        let deadline = tokio::time::sleep(EMIT_BATCHES_TIMEOUT);
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                frame_opt = self.frame_rx.recv() => {
+                    let Some(frame) = frame_opt else {
+                        let error = anyhow::anyhow!("syslog listener exited unexpectedly");
+                        return Err(ActorExitStatus::from(error));
+                    };
+                    self.process_frame(frame, received_at, &mut batch_builder);
+
+                    if batch_builder.num_bytes >= BATCH_NUM_BYTES_LIMIT {
+                        break;
+                    }
+                }
+                _ = &mut deadline => {
+                    break;
+                }
+            }
+            ctx.record_progress();
+        }
+        if self.state.num_messages_processed > num_messages_processed_before {
+            let to_position = Position::offset(self.state.num_messages_processed);
+            let from_position =
+                std::mem::replace(&mut self.state.current_position, to_position.clone());
+            batch_builder
+                .checkpoint_delta
+                .record_partition_delta(self.partition_id.clone(), from_position, to_position)
+                .context("failed to record partition delta")?;
+        }
+        if !batch_builder.checkpoint_delta.is_empty() {
+            debug!(
+                num_bytes=%batch_builder.num_bytes,
+                num_docs=%batch_builder.docs.len(),
+                num_millis=%now.elapsed().as_millis(),
+                "sending doc batch to indexer"
+            );
+            let message = batch_builder.build();
+            ctx.send_message(doc_processor_mailbox, message).await?;
+        }
+        Ok(Duration::default())
+    }
+
+    fn name(&self) -> String {
+        format!("SyslogSource{{source_id={}}}", self.ctx.source_id())
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        json!({
+            "index_id": self.ctx.index_id(),
+            "source_id": self.ctx.source_id(),
+            "listen_addr": self.listen_addr.to_string(),
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+        })
+    }
+}
+
+fn load_tls_acceptor(cert_path: &str, key_path: &str) -> anyhow::Result<TlsAcceptor> {
+    let cert_file = std::fs::File::open(cert_path)
+        .with_context(|| format!("failed to open TLS certificate file `{cert_path}`"))?;
+    let certs: Vec<rustls::Certificate> =
+        rustls_pemfile::certs(&mut std::io::BufReader::new(cert_file))
+            .with_context(|| format!("failed to parse TLS certificate file `{cert_path}`"))?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+
+    let key_file = std::fs::File::open(key_path)
+        .with_context(|| format!("failed to open TLS key file `{key_path}`"))?;
+    let key = rustls_pemfile::read_all(&mut std::io::BufReader::new(key_file))
+        .with_context(|| format!("failed to parse TLS key file `{key_path}`"))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+            _ => None,
+        })
+        .with_context(|| format!("no private key found in TLS key file `{key_path}`"))?;
+
+    let server_config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("failed to build TLS server config")?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Accepts TCP connections and reads their frames. The connection tasks are aborted along with
+/// this task.
+async fn accept_connections(
+    listener: TcpListener,
+    tls_acceptor_opt: Option<TlsAcceptor>,
+    frame_tx: mpsc::Sender<Bytes>,
+) {
+    let mut connection_tasks = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accept_result = listener.accept() => {
+                let (stream, peer_addr) = match accept_result {
+                    Ok(accepted) => accepted,
+                    Err(error) => {
+                        warn!(%error, "failed to accept syslog connection");
+                        continue;
+                    }
+                };
+                let tls_acceptor_opt = tls_acceptor_opt.clone();
+                let frame_tx = frame_tx.clone();
+
+                connection_tasks.spawn(async move {
+                    let read_result = if let Some(tls_acceptor) = tls_acceptor_opt {
+                        match tls_acceptor.accept(stream).await {
+                            Ok(tls_stream) => read_frames(tls_stream, frame_tx).await,
+                            Err(error) => Err(error),
+                        }
+                    } else {
+                        read_frames(stream, frame_tx).await
+                    };
+                    if let Err(error) = read_result {
+                        debug!(%error, %peer_addr, "closing syslog connection");
+                    }
+                });
+            }
+            Some(_) = connection_tasks.join_next() => {}
+        }
+    }
+}
+
+async fn read_frames<R>(stream: R, frame_tx: mpsc::Sender<Bytes>) -> io::Result<()>
+where R: AsyncRead + Unpin {
+    let mut reader = BufReader::new(stream);
+
+    while let Some(frame) = read_frame(&mut reader).await? {
+        if frame_tx.send(frame).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the next frame of a TCP stream, which is either octet-counted (`MSG-LEN SP SYSLOG-MSG`)
+/// or delimited by a newline (RFC 6587). Returns `None` at the end of the stream.
+async fn read_frame<R>(reader: &mut R) -> io::Result<Option<Bytes>>
+where R: AsyncBufRead + Unpin {
+    loop {
+        let Some(&first_byte) = reader.fill_buf().await?.first() else {
+            return Ok(None);
+        };
+        let mut buffer = Vec::new();
+
+        if first_byte.is_ascii_digit() {
+            (&mut *reader)
+                .take(MAX_FRAME_LEN_NUM_DIGITS + 1)
+                .read_until(b' ', &mut buffer)
+                .await?;
+
+            let frame_len = buffer
+                .strip_suffix(b" ")
+                .and_then(|frame_len| std::str::from_utf8(frame_len).ok())
+                .and_then(|frame_len| frame_len.parse::<usize>().ok())
+                .filter(|frame_len| *frame_len <= MAX_FRAME_NUM_BYTES)
+                .ok_or_else(|| invalid_data("invalid octet-counted frame length"))?;
+            let mut frame = vec![0; frame_len];
+            reader.read_exact(&mut frame).await?;
+            return Ok(Some(Bytes::from(frame)));
+        }
+        (&mut *reader)
+            .take(MAX_FRAME_NUM_BYTES as u64 + 1)
+            .read_until(b'\n', &mut buffer)
+            .await?;
+
+        if buffer.last() == Some(&b'\n') {
+            buffer.pop();
+        } else if buffer.len() > MAX_FRAME_NUM_BYTES {
+            return Err(invalid_data("frame exceeds maximum size"));
+        }
+        let frame = trim_line_ending(&buffer);
+
+        // Skip empty lines, including the ones some senders append to octet-counted frames.
+        if !frame.is_empty() {
+            return Ok(Some(Bytes::copy_from_slice(frame)));
+        }
+    }
+}
+
+async fn receive_datagrams(socket: UdpSocket, frame_tx: mpsc::Sender<Bytes>) {
+    let mut buffer = vec![0; MAX_FRAME_NUM_BYTES];
+
+    loop {
+        let num_bytes = match socket.recv(&mut buffer).await {
+            Ok(num_bytes) => num_bytes,
+            Err(error) => {
+                warn!(%error, "failed to receive syslog datagram");
+                continue;
+            }
+        };
+        let frame = trim_line_ending(&buffer[..num_bytes]);
+
+        if frame.is_empty() {
+            continue;
+        }
+        if frame_tx.send(Bytes::copy_from_slice(frame)).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn trim_line_ending(frame: &[u8]) -> &[u8] {
+    let frame = frame.strip_suffix(b"\n").unwrap_or(frame);
+    frame.strip_suffix(b"\r").unwrap_or(frame)
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parses a syslog message formatted according to RFC 5424 or RFC 3164 into a JSON document.
+/// Returns `None` if the message does not start with a valid priority.
+fn parse_syslog_message(
+    message: &[u8],
+    received_at: OffsetDateTime,
+) -> Option<JsonMap<String, JsonValue>> {
+    let message = String::from_utf8_lossy(message);
+    let (priority, rest) = parse_priority(&message)?;
+
+    let mut doc = JsonMap::new();
+    doc.insert(
+        "facility".to_string(),
+        JsonValue::from(FACILITY_NAMES[priority as usize / 8]),
+    );
+    doc.insert(
+        "severity".to_string(),
+        JsonValue::from(SEVERITY_NAMES[priority as usize % 8]),
+    );
+    if is_rfc5424(rest) {
+        parse_rfc5424(rest, &mut doc)?;
+    } else {
+        parse_rfc3164(rest, received_at, &mut doc);
+    }
+    Some(doc)
+}
+
+/// Parses the `<PRI>` prefix of a message, where `PRI = facility * 8 + severity`.
+fn parse_priority(message: &str) -> Option<(u8, &str)> {
+    let (priority_str, rest) = message.strip_prefix('<')?.split_once('>')?;
+
+    if priority_str.is_empty()
+        || priority_str.len() > 3
+        || !priority_str.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let priority = priority_str
+        .parse::<u8>()
+        .ok()
+        .filter(|priority| *priority < 192)?;
+    Some((priority, rest))
+}
+
+/// RFC 5424 messages carry a version number right after the priority, whereas RFC 3164 messages
+/// start with a timestamp such as `Oct 11 22:14:15`.
+fn is_rfc5424(rest: &str) -> bool {
+    let Some((version, _)) = rest.split_once(' ') else {
+        return false;
+    };
+    !version.is_empty()
+        && version.len() <= 2
+        && !version.starts_with('0')
+        && version.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// `VERSION SP TIMESTAMP SP HOSTNAME SP APP-NAME SP PROCID SP MSGID SP STRUCTURED-DATA [SP MSG]`
+fn parse_rfc5424(rest: &str, doc: &mut JsonMap<String, JsonValue>) -> Option<()> {
+    let mut parts = rest.splitn(7, ' ');
+    let version = parts.next()?.parse::<u64>().ok()?;
+    doc.insert("version".to_string(), JsonValue::from(version));
+
+    for field_name in ["timestamp", "hostname", "app_name", "proc_id", "msg_id"] {
+        let field_value = parts.next()?;
+
+        if field_value != NIL_VALUE {
+            doc.insert(field_name.to_string(), JsonValue::from(field_value));
+        }
+    }
+    let (structured_data_opt, message) = parse_structured_data(parts.next()?)?;
+
+    if let Some(structured_data) = structured_data_opt {
+        doc.insert(
+            "structured_data".to_string(),
+            JsonValue::Object(structured_data),
+        );
+    }
+    let message = message.strip_prefix('\u{feff}').unwrap_or(message);
+
+    if !message.is_empty() {
+        doc.insert("message".to_string(), JsonValue::from(message));
+    }
+    Some(())
+}
+
+/// Parses the structured data of an RFC 5424 message, i.e. either the nil value or a sequence
+/// of `[SD-ID *(SP PARAM-NAME="PARAM-VALUE")]` elements, into an object mapping each SD-ID to its
+/// parameters. Returns the structured data along with the rest of the message.
+#[allow(clippy::type_complexity)]
+fn parse_structured_data(input: &str) -> Option<(Option<JsonMap<String, JsonValue>>, &str)> {
+    if let Some(rest) = input.strip_prefix(NIL_VALUE) {
+        return Some((None, rest.strip_prefix(' ').unwrap_or(rest)));
+    }
+    let mut structured_data = JsonMap::new();
+    let mut rest = input;
+
+    while let Some(element) = rest.strip_prefix('[') {
+        let sd_id_end = element.find([' ', ']'])?;
+        let sd_id = &element[..sd_id_end];
+
+        if sd_id.is_empty() {
+            return None;
+        }
+        let mut params = JsonMap::new();
+        rest = &element[sd_id_end..];
+
+        loop {
+            if let Some(remaining) = rest.strip_prefix(']') {
+                rest = remaining;
+                break;
+            }
+            let (param_name, remaining) = rest.strip_prefix(' ')?.split_once("=\"")?;
+            let (param_value, remaining) = parse_param_value(remaining)?;
+            params.insert(param_name.to_string(), JsonValue::from(param_value));
+            rest = remaining;
+        }
+        structured_data.insert(sd_id.to_string(), JsonValue::Object(params));
+    }
+    if structured_data.is_empty() {
+        return None;
+    }
+    Some((
+        Some(structured_data),
+        rest.strip_prefix(' ').unwrap_or(rest),
+    ))
+}
+
+/// Parses a parameter value up to its closing quote, unescaping `\"`, `\\`, and `\]`.
+fn parse_param_value(input: &str) -> Option<(String, &str)> {
+    let mut param_value = String::new();
+    let mut chars = input.char_indices();
+
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' => return Some((param_value, &input[idx + 1..])),
+            '\\' => match chars.next()? {
+                (_, escaped_ch @ ('"' | '\\' | ']')) => param_value.push(escaped_ch),
+                (_, other_ch) => {
+                    param_value.push('\\');
+                    param_value.push(other_ch);
+                }
+            },
+            _ => param_value.push(ch),
+        }
+    }
+    None
+}
+
+/// `TIMESTAMP SP HOSTNAME SP TAG[PID]: MSG`. RFC 3164 only describes common practice, so any
+/// part that does not look as expected is left in the message.
+fn parse_rfc3164(rest: &str, received_at: OffsetDateTime, doc: &mut JsonMap<String, JsonValue>) {
+    let mut rest = rest;
+
+    if let Some((timestamp, remaining)) = parse_rfc3164_timestamp(rest, received_at) {
+        doc.insert("timestamp".to_string(), JsonValue::from(timestamp));
+        rest = remaining;
+
+        // Some senders omit the hostname, in which case the timestamp is directly followed by
+        // the tag.
+        if let Some((hostname, remaining)) = rest.split_once(' ') {
+            if !hostname.is_empty() && !hostname.ends_with(':') && !hostname.contains('[') {
+                doc.insert("hostname".to_string(), JsonValue::from(hostname));
+                rest = remaining;
+            }
+        }
+    }
+    if let Some((app_name, proc_id_opt, remaining)) = parse_rfc3164_tag(rest) {
+        doc.insert("app_name".to_string(), JsonValue::from(app_name));
+
+        if let Some(proc_id) = proc_id_opt {
+            doc.insert("proc_id".to_string(), JsonValue::from(proc_id));
+        }
+        rest = remaining;
+    }
+    if !rest.is_empty() {
+        doc.insert("message".to_string(), JsonValue::from(rest));
+    }
+}
+
+/// Parses a `Mmm dd hh:mm:ss` timestamp into an RFC 3339 timestamp. The year is missing from
+/// the format, so we pick the one that puts the timestamp closest to the reception time.
+fn parse_rfc3164_timestamp(input: &str, received_at: OffsetDateTime) -> Option<(String, &str)> {
+    let timestamp = input.get(..15)?;
+    let rest = input[15..].strip_prefix(' ')?;
+    let timestamp_bytes = timestamp.as_bytes();
+
+    if !timestamp.is_ascii()
+        || timestamp_bytes[3] != b' '
+        || timestamp_bytes[6] != b' '
+        || timestamp_bytes[9] != b':'
+        || timestamp_bytes[12] != b':'
+    {
+        return None;
+    }
+    let month_idx = MONTH_NAMES
+        .iter()
+        .position(|month_name| *month_name == &timestamp[..3])?;
+    let month = Month::try_from(month_idx as u8 + 1).ok()?;
+    let day = timestamp[4..6].trim_start().parse::<u8>().ok()?;
+    let hour = timestamp[7..9].parse::<u8>().ok()?;
+    let minute = timestamp[10..12].parse::<u8>().ok()?;
+    let second = timestamp[13..15].parse::<u8>().ok()?;
+    let time = Time::from_hms(hour, minute, second).ok()?;
+
+    let mut year = received_at.year();
+
+    // A message sent on Dec 31 and received on Jan 1 belongs to the previous year.
+    if month_idx == 11 && received_at.month() == Month::January {
+        year -= 1;
+    }
+    let date = Date::from_calendar_date(year, month, day).ok()?;
+    let datetime = PrimitiveDateTime::new(date, time).assume_utc();
+    let timestamp_rfc3339 = datetime.format(&Rfc3339).ok()?;
+    Some((timestamp_rfc3339, rest))
+}
+
+/// Parses a `TAG[PID]: ` or `TAG: ` prefix.
+fn parse_rfc3164_tag(input: &str) -> Option<(&str, Option<&str>, &str)> {
+    let tag_end = input
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/')))?;
+    if tag_end == 0 || tag_end > 48 {
+        return None;
+    }
+    let tag = &input[..tag_end];
+    let mut rest = &input[tag_end..];
+    let mut proc_id_opt = None;
+
+    if let Some(remaining) = rest.strip_prefix('[') {
+        let (proc_id, remaining) = remaining.split_once(']')?;
+        proc_id_opt = Some(proc_id);
+        rest = remaining;
+    }
+    let rest = rest.strip_prefix(':')?;
+    Some((tag, proc_id_opt, rest.strip_prefix(' ').unwrap_or(rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use quickwit_actors::{ActorContext, Universe};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::metastore_for_test;
+    use quickwit_proto::types::IndexUid;
+    use time::macros::datetime;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpStream;
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::SourceActor;
+
+    fn parse_for_test(message: &str) -> Option<JsonValue> {
+        let received_at = datetime!(2024-03-12 10:00:00 UTC);
+        parse_syslog_message(message.as_bytes(), received_at).map(JsonValue::Object)
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        assert_eq!(parse_priority("<0>foo"), Some((0, "foo")));
+        assert_eq!(parse_priority("<191>foo"), Some((191, "foo")));
+        assert!(parse_priority("<192>foo").is_none());
+        assert!(parse_priority("<>foo").is_none());
+        assert!(parse_priority("<+1>foo").is_none());
+        assert!(parse_priority("foo").is_none());
+    }
+
+    #[test]
+    fn test_parse_rfc5424_message() {
+        let message = r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application" eventID="1011"][examplePriority@32473 class="high\"\]"] An application event log entry..."#;
+        assert_eq!(
+            parse_for_test(message).unwrap(),
+            json!({
+                "facility": "local4",
+                "severity": "notice",
+                "version": 1,
+                "timestamp": "2003-10-11T22:14:15.003Z",
+                "hostname": "mymachine.example.com",
+                "app_name": "evntslog",
+                "msg_id": "ID47",
+                "structured_data": {
+                    "exampleSDID@32473": {
+                        "iut": "3",
+                        "eventSource": "Application",
+                        "eventID": "1011",
+                    },
+                    "examplePriority@32473": {
+                        "class": "high\"]",
+                    },
+                },
+                "message": "An application event log entry...",
+            })
+        );
+        let message = "<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - \
+                       \u{feff}'su root' failed for lonvick on /dev/pts/8";
+        assert_eq!(
+            parse_for_test(message).unwrap(),
+            json!({
+                "facility": "auth",
+                "severity": "crit",
+                "version": 1,
+                "timestamp": "2003-10-11T22:14:15.003Z",
+                "hostname": "mymachine.example.com",
+                "app_name": "su",
+                "msg_id": "ID47",
+                "message": "'su root' failed for lonvick on /dev/pts/8",
+            })
+        );
+        let message = "<13>1 - - - - - -";
+        assert_eq!(
+            parse_for_test(message).unwrap(),
+            json!({
+                "facility": "user",
+                "severity": "notice",
+                "version": 1,
+            })
+        );
+        // Missing structured data.
+        assert!(parse_for_test("<13>1 - - - - -").is_none());
+        // Unterminated structured data element.
+        assert!(parse_for_test(r#"<13>1 - - - - - [foo bar="baz"#).is_none());
+    }
+
+    #[test]
+    fn test_parse_rfc3164_message() {
+        let message = "<34>Oct 11 22:14:15 mymachine su[1234]: 'su root' failed for lonvick";
+        assert_eq!(
+            parse_for_test(message).unwrap(),
+            json!({
+                "facility": "auth",
+                "severity": "crit",
+                "timestamp": "2024-10-11T22:14:15Z",
+                "hostname": "mymachine",
+                "app_name": "su",
+                "proc_id": "1234",
+                "message": "'su root' failed for lonvick",
+            })
+        );
+        let message = "<13>Mar  1 02:03:04 sshd: Connection closed";
+        assert_eq!(
+            parse_for_test(message).unwrap(),
+            json!({
+                "facility": "user",
+                "severity": "notice",
+                "timestamp": "2024-03-01T02:03:04Z",
+                "app_name": "sshd",
+                "message": "Connection closed",
+            })
+        );
+        let message = "<13>Use the BFG!";
+        assert_eq!(
+            parse_for_test(message).unwrap(),
+            json!({
+                "facility": "user",
+                "severity": "notice",
+                "message": "Use the BFG!",
+            })
+        );
+        assert!(parse_for_test("Use the BFG!").is_none());
+    }
+
+    #[test]
+    fn test_parse_rfc3164_timestamp_year_rollover() {
+        let received_at = datetime!(2024-01-01 00:00:01 UTC);
+        let (timestamp, rest) =
+            parse_rfc3164_timestamp("Dec 31 23:59:59 host", received_at).unwrap();
+        assert_eq!(timestamp, "2023-12-31T23:59:59Z");
+        assert_eq!(rest, "host");
+    }
+
+    #[tokio::test]
+    async fn test_read_frame() {
+        let stream: &[u8] =
+            b"17 <13>1 - - - - - -<13>foo\r\n\n25 <13>1 - - - - - - foo\nbar\n<13>baz";
+        let mut reader = BufReader::new(stream);
+
+        let mut frames = Vec::new();
+        while let Some(frame) = read_frame(&mut reader).await.unwrap() {
+            frames.push(frame);
+        }
+        assert_eq!(
+            frames,
+            [
+                &b"<13>1 - - - - - -"[..],
+                &b"<13>foo"[..],
+                &b"<13>1 - - - - - - foo\nbar"[..],
+                &b"<13>baz"[..],
+            ]
+        );
+        let stream: &[u8] = b"999999 foo";
+        let mut reader = BufReader::new(stream);
+        read_frame(&mut reader).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_syslog_source_tcp() {
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+        let (doc_processor_mailbox, doc_processor_inbox) =
+            universe.create_test_mailbox::<DocProcessor>();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(JsonValue::Null);
+        let ctx: SourceContext =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        let params = SyslogSourceParams {
+            listen_port: 0,
+            protocol: SyslogProtocol::Tcp,
+            tls_cert_path: None,
+            tls_key_path: None,
+        };
+        let source_config = SourceConfig {
+            source_id: "test-syslog-source".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Syslog(params.clone()),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let runtime_args = SourceRuntimeArgs::for_test(
+            IndexUid::new_with_random_ulid("test-index"),
+            source_config,
+            metastore_for_test(),
+            PathBuf::from("./queues"),
+        );
+        let mut source = SyslogSource::try_new(runtime_args, params).await.unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", source.listen_addr.port()))
+            .await
+            .unwrap();
+        stream
+            .write_all(b"<13>Mar  1 02:03:04 host app: foo\n21 <13>1 - - - - - - bar\n")
+            .await
+            .unwrap();
+        stream.write_all(b"invalid\n").await.unwrap();
+        stream.flush().await.unwrap();
+
+        while source.state.num_messages_processed < 3 {
+            source
+                .emit_batches(&doc_processor_mailbox, &ctx)
+                .await
+                .unwrap();
+        }
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        let docs: Vec<JsonValue> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter())
+            .map(|doc| serde_json::from_slice(doc).unwrap())
+            .collect();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["message"], "foo");
+        assert_eq!(docs[1]["message"], "bar");
+
+        let observable_state = source.observable_state();
+        assert_eq!(observable_state["num_messages_processed"], 3);
+        assert_eq!(observable_state["num_invalid_messages"], 1);
+    }
+}
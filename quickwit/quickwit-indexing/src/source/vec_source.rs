@@ -167,6 +167,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::Vec(params.clone()),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
@@ -227,6 +229,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::Vec(params.clone()),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
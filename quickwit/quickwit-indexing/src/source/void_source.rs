@@ -92,6 +92,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
@@ -117,6 +119,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let metastore = metastore_for_test();
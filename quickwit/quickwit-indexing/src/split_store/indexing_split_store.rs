@@ -164,6 +164,26 @@ impl IndexingSplitStore {
         Ok(())
     }
 
+    /// Stores the term digests of a split next to the split file.
+    pub async fn store_term_digests(
+        &self,
+        split_id: &str,
+        term_digests_bytes: Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let key = PathBuf::from(quickwit_common::term_digests_file(split_id));
+        self.inner
+            .remote_storage
+            .put(&key, Box::new(term_digests_bytes))
+            .await
+            .with_context(|| {
+                format!(
+                    "failed uploading key {} in bucket {}",
+                    key.display(),
+                    self.inner.remote_storage.uri()
+                )
+            })
+    }
+
     /// Gets a split from the split store, and makes it available to the given `output_path`.
     /// If the split is available in the local disk cache, then it will be moved
     /// from the cache to the `output_dir_path`.
@@ -0,0 +1,254 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use quickwit_common::shared_consts::SPLIT_FIELDS_FILE_NAME;
+use quickwit_storage::{BundleStorageFileOffsets, OwnedBytes, PutPayload, SplitPayloadBuilder};
+use tantivy::collector::Count;
+use tantivy::directory::FileSlice;
+use tantivy::query::AllQuery;
+use tantivy::schema::Schema;
+use tantivy::{DocAddress, HasLen, Index, IndexReader, ReloadPolicy, TantivyDocument};
+use tokio::io::AsyncReadExt;
+
+use crate::models::PackagedSplit;
+
+/// Checks that a freshly packaged split is sound before it is staged and published:
+/// - the split footer (bundle file offsets and hotcache) can be decoded and matches the split
+///   files;
+/// - the checksums of the tantivy files are valid;
+/// - every field of the doc mapping is present in the split schema with the same type;
+/// - a match-all query counts as many documents as recorded in the split metadata and the first
+///   document can be read from the doc store.
+///
+/// Splits that fail validation are copied to a quarantine directory for inspection instead of
+/// being uploaded.
+#[derive(Clone)]
+pub struct SplitValidator {
+    expected_schema: Schema,
+    quarantine_dir_path: PathBuf,
+}
+
+impl SplitValidator {
+    pub fn new(expected_schema: Schema, quarantine_dir_path: PathBuf) -> Self {
+        Self {
+            expected_schema,
+            quarantine_dir_path,
+        }
+    }
+
+    pub async fn validate(
+        &self,
+        packaged_split: &PackagedSplit,
+        index: &Index,
+    ) -> anyhow::Result<()> {
+        validate_split_footer(packaged_split)
+            .await
+            .context("invalid split footer")?;
+        validate_checksums(index)?;
+        validate_fields(&self.expected_schema, &index.schema())?;
+        validate_smoke_query(packaged_split, index).context("smoke query failed")?;
+        Ok(())
+    }
+
+    /// Copies the files of the split to `<quarantine_dir>/<index_uid>/<split_id>` and returns the
+    /// path of the quarantined split.
+    pub fn quarantine(&self, packaged_split: &PackagedSplit) -> anyhow::Result<PathBuf> {
+        let split_quarantine_dir_path = self
+            .quarantine_dir_path
+            .join(packaged_split.index_uid().to_string())
+            .join(packaged_split.split_id());
+        fs::create_dir_all(&split_quarantine_dir_path)?;
+
+        for split_file in &packaged_split.split_files {
+            let file_name = split_file
+                .file_name()
+                .with_context(|| format!("invalid split file path `{}`", split_file.display()))?;
+            fs::copy(split_file, split_quarantine_dir_path.join(file_name))?;
+        }
+        fs::write(
+            split_quarantine_dir_path.join(SPLIT_FIELDS_FILE_NAME),
+            &packaged_split.serialized_split_fields,
+        )?;
+        fs::write(
+            split_quarantine_dir_path.join("hotcache"),
+            &packaged_split.hotcache_bytes,
+        )?;
+        Ok(split_quarantine_dir_path)
+    }
+}
+
+async fn validate_split_footer(packaged_split: &PackagedSplit) -> anyhow::Result<()> {
+    let split_payload = SplitPayloadBuilder::get_split_payload(
+        &packaged_split.split_files,
+        &packaged_split.serialized_split_fields,
+        &packaged_split.hotcache_bytes,
+    )?;
+    let footer_range = split_payload.footer_range.clone();
+    let mut footer_bytes = Vec::with_capacity((footer_range.end - footer_range.start) as usize);
+    split_payload
+        .range_byte_stream(footer_range.clone())
+        .await?
+        .into_async_read()
+        .read_to_end(&mut footer_bytes)
+        .await?;
+    let footer_slice = FileSlice::new(Arc::new(OwnedBytes::new(footer_bytes)));
+    let (hotcache, bundle_file_offsets) =
+        BundleStorageFileOffsets::open_from_split_data(footer_slice)?;
+
+    if hotcache.len() != packaged_split.hotcache_bytes.len() {
+        bail!(
+            "expected hotcache of {} bytes, got {} bytes",
+            packaged_split.hotcache_bytes.len(),
+            hotcache.len()
+        );
+    }
+    for split_file in &packaged_split.split_files {
+        let file_name = split_file
+            .file_name()
+            .map(Path::new)
+            .with_context(|| format!("invalid split file path `{}`", split_file.display()))?;
+        let file_range = bundle_file_offsets.get(file_name).with_context(|| {
+            format!("file `{}` is missing from the bundle", file_name.display())
+        })?;
+        let file_num_bytes = fs::metadata(split_file)?.len();
+
+        if file_range.end - file_range.start != file_num_bytes {
+            bail!(
+                "file `{}` has {} bytes on disk but spans {} bytes in the bundle",
+                file_name.display(),
+                file_num_bytes,
+                file_range.end - file_range.start
+            );
+        }
+        if file_range.end > footer_range.start {
+            bail!(
+                "file `{}` overlaps with the split footer",
+                file_name.display()
+            );
+        }
+    }
+    if !bundle_file_offsets.exists(Path::new(SPLIT_FIELDS_FILE_NAME)) {
+        bail!("file `{SPLIT_FIELDS_FILE_NAME}` is missing from the bundle");
+    }
+    Ok(())
+}
+
+fn validate_checksums(index: &Index) -> anyhow::Result<()> {
+    let corrupted_files = index.validate_checksum()?;
+
+    if !corrupted_files.is_empty() {
+        let mut corrupted_file_names: Vec<String> = corrupted_files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        corrupted_file_names.sort();
+        bail!(
+            "checksum validation failed for files: {}",
+            corrupted_file_names.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn validate_fields(expected_schema: &Schema, split_schema: &Schema) -> anyhow::Result<()> {
+    for (_, expected_field_entry) in expected_schema.fields() {
+        let field_name = expected_field_entry.name();
+        let Ok(field) = split_schema.get_field(field_name) else {
+            bail!("field `{field_name}` is missing from the split schema");
+        };
+        let expected_type = expected_field_entry.field_type().value_type();
+        let split_type = split_schema
+            .get_field_entry(field)
+            .field_type()
+            .value_type();
+
+        if split_type != expected_type {
+            bail!(
+                "field `{field_name}` has type `{split_type:?}` in the split schema, expected \
+                 `{expected_type:?}`"
+            );
+        }
+    }
+    Ok(())
+}
+
+fn validate_smoke_query(packaged_split: &PackagedSplit, index: &Index) -> anyhow::Result<()> {
+    let index_reader: IndexReader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = index_reader.searcher();
+    let num_docs = searcher.search(&AllQuery, &Count)? as u64;
+
+    if num_docs != packaged_split.split_attrs.num_docs {
+        bail!(
+            "expected {} documents, got {num_docs}",
+            packaged_split.split_attrs.num_docs
+        );
+    }
+    if num_docs > 0 {
+        searcher
+            .doc::<TantivyDocument>(DocAddress::new(0, 0))
+            .context("failed to read the first document from the doc store")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{FAST, STRING, TEXT};
+
+    use super::*;
+
+    #[test]
+    fn test_validate_fields() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_u64_field("timestamp", FAST);
+        let expected_schema = schema_builder.build();
+
+        validate_fields(&expected_schema, &expected_schema).unwrap();
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let split_schema = schema_builder.build();
+
+        let error = validate_fields(&expected_schema, &split_schema).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "field `timestamp` is missing from the split schema"
+        );
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_text_field("timestamp", STRING);
+        let split_schema = schema_builder.build();
+
+        let error = validate_fields(&expected_schema, &split_schema).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "field `timestamp` has type `Str` in the split schema, expected `U64`"
+        );
+    }
+}
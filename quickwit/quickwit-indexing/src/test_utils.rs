@@ -166,6 +166,8 @@ impl TestSandbox {
                 partition: format!("add-docs-{add_docs_id}"),
             }),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         let pipeline_id = self
@@ -41,8 +41,12 @@ pub enum IngestServiceError {
     InvalidPosition(String),
     #[error("io error {0}")]
     IoError(String),
+    #[error("ingestion quota exceeded for index `{index_id}`")]
+    QuotaExceeded { index_id: String },
     #[error("rate limited")]
     RateLimited,
+    #[error("source `{source_id}` of index `{index_id}` is paused")]
+    SourcePaused { index_id: String, source_id: String },
     #[error("ingest service is unavailable")]
     Unavailable,
 }
@@ -96,7 +100,9 @@ impl ServiceError for IngestServiceError {
             Self::Internal(_) => ServiceErrorCode::Internal,
             Self::InvalidPosition(_) => ServiceErrorCode::BadRequest,
             Self::IoError { .. } => ServiceErrorCode::Internal,
+            Self::QuotaExceeded { .. } => ServiceErrorCode::TooManyRequests,
             Self::RateLimited => ServiceErrorCode::TooManyRequests,
+            Self::SourcePaused { .. } => ServiceErrorCode::Unavailable,
             Self::Unavailable => ServiceErrorCode::Unavailable,
         }
     }
@@ -139,7 +145,9 @@ impl From<IngestServiceError> for tonic::Status {
             IngestServiceError::Internal(_) => tonic::Code::Internal,
             IngestServiceError::InvalidPosition(_) => tonic::Code::InvalidArgument,
             IngestServiceError::IoError { .. } => tonic::Code::Internal,
+            IngestServiceError::QuotaExceeded { .. } => tonic::Code::ResourceExhausted,
             IngestServiceError::RateLimited => tonic::Code::ResourceExhausted,
+            IngestServiceError::SourcePaused { .. } => tonic::Code::Unavailable,
             IngestServiceError::Unavailable => tonic::Code::Unavailable,
         };
         let message = error.to_string();
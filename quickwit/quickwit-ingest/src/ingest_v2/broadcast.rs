@@ -51,16 +51,25 @@ pub struct ShardInfo {
     pub shard_state: ShardState,
     /// Shard ingestion rate in MiB/s.
     pub ingestion_rate: RateMibPerSec,
+    /// Longest time elapsed between the append of a batch to the shard and the publication of
+    /// the split containing it, measured since the previous broadcast.
+    pub publish_latency_opt: Option<Duration>,
 }
 
 impl Serialize for ShardInfo {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(&format!(
+        let mut shard_info_str = format!(
             "{}:{}:{}",
             self.shard_id,
             self.shard_state.as_json_str_name(),
             self.ingestion_rate.0,
-        ))
+        );
+        // The publish latency is appended last and only when present so that nodes unaware of
+        // it can still parse the shard info.
+        if let Some(publish_latency) = self.publish_latency_opt {
+            shard_info_str.push_str(&format!(":{}", publish_latency.as_millis()));
+        }
+        serializer.serialize_str(&shard_info_str)
     }
 }
 
@@ -88,10 +97,21 @@ impl<'de> Deserialize<'de> for ShardInfo {
             .map(RateMibPerSec)
             .map_err(|_| serde::de::Error::custom("invalid shard ingestion rate"))?;
 
+        let publish_latency_opt = parts
+            .next()
+            .map(|publish_latency_str| {
+                publish_latency_str
+                    .parse::<u64>()
+                    .map(Duration::from_millis)
+                    .map_err(|_| serde::de::Error::custom("invalid shard publish latency"))
+            })
+            .transpose()?;
+
         Ok(Self {
             shard_id,
             shard_state,
             ingestion_rate,
+            publish_latency_opt,
         })
     }
 }
@@ -167,12 +187,14 @@ impl BroadcastLocalShardsTask {
         };
         let mut per_source_shard_infos: BTreeMap<SourceUid, ShardInfos> = BTreeMap::new();
 
-        let queue_ids: Vec<(QueueId, ShardState)> = state_guard
+        let queue_ids: Vec<(QueueId, ShardState, Option<Duration>)> = state_guard
             .shards
-            .iter()
+            .iter_mut()
             .filter_map(|(queue_id, shard)| {
                 if !shard.is_replica() {
-                    Some((queue_id.clone(), shard.shard_state))
+                    // Each publish latency measurement is broadcast only once.
+                    let publish_latency_opt = shard.publish_latency_opt.take();
+                    Some((queue_id.clone(), shard.shard_state, publish_latency_opt))
                 } else {
                     None
                 }
@@ -182,7 +204,7 @@ impl BroadcastLocalShardsTask {
         let mut num_open_shards = 0;
         let mut num_closed_shards = 0;
 
-        for (queue_id, shard_state) in queue_ids {
+        for (queue_id, shard_state, publish_latency_opt) in queue_ids {
             let Some((_rate_limiter, rate_meter)) = state_guard.rate_trackers.get_mut(&queue_id)
             else {
                 warn!("rate limiter `{queue_id}` not found",);
@@ -205,6 +227,7 @@ impl BroadcastLocalShardsTask {
                 shard_id,
                 shard_state,
                 ingestion_rate,
+                publish_latency_opt,
             };
             per_source_shard_infos
                 .entry(source_uid)
@@ -349,12 +372,27 @@ mod tests {
             shard_id: ShardId::from(1),
             shard_state: ShardState::Open,
             ingestion_rate: RateMibPerSec(42),
+            publish_latency_opt: None,
         };
         let serialized = serde_json::to_string(&shard_info).unwrap();
         assert_eq!(serialized, r#""00000000000000000001:open:42""#);
 
         let deserialized = serde_json::from_str::<ShardInfo>(&serialized).unwrap();
         assert_eq!(deserialized, shard_info);
+
+        let shard_info = ShardInfo {
+            shard_id: ShardId::from(1),
+            shard_state: ShardState::Open,
+            ingestion_rate: RateMibPerSec(42),
+            publish_latency_opt: Some(Duration::from_millis(1337)),
+        };
+        let serialized = serde_json::to_string(&shard_info).unwrap();
+        assert_eq!(serialized, r#""00000000000000000001:open:42:1337""#);
+
+        let deserialized = serde_json::from_str::<ShardInfo>(&serialized).unwrap();
+        assert_eq!(deserialized, shard_info);
+
+        serde_json::from_str::<ShardInfo>(r#""00000000000000000001:open:42:foo""#).unwrap_err();
     }
 
     #[test]
@@ -376,6 +414,7 @@ mod tests {
                     shard_id: ShardId::from(1),
                     shard_state: ShardState::Open,
                     ingestion_rate: RateMibPerSec(42),
+                    publish_latency_opt: None,
                 }]
                 .into_iter()
                 .collect(),
@@ -416,6 +455,7 @@ mod tests {
                     shard_id: ShardId::from(1),
                     shard_state: ShardState::Closed,
                     ingestion_rate: RateMibPerSec(42),
+                    publish_latency_opt: None,
                 }]
                 .into_iter()
                 .collect(),
@@ -584,6 +624,7 @@ mod tests {
             shard_id: ShardId::from(1),
             shard_state: ShardState::Open,
             ingestion_rate: RateMibPerSec(42),
+            publish_latency_opt: None,
         }])
         .unwrap();
 
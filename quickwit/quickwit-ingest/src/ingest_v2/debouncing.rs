@@ -76,6 +76,7 @@ pub(super) struct DebouncedGetOrCreateOpenShardsRequest {
     subrequests: Vec<GetOrCreateOpenShardsSubrequest>,
     pub closed_shards: Vec<ShardIds>,
     pub unavailable_leaders: Vec<String>,
+    pub router_id: String,
     rendezvous: Rendezvous,
 }
 
@@ -92,6 +93,7 @@ impl DebouncedGetOrCreateOpenShardsRequest {
             subrequests: self.subrequests,
             closed_shards: self.closed_shards,
             unavailable_leaders: self.unavailable_leaders,
+            router_id: self.router_id,
         };
         (Some(request), self.rendezvous)
     }
@@ -41,6 +41,7 @@ use tokio::task::JoinHandle;
 use tracing::{debug, error, warn};
 
 use super::models::ShardStatus;
+use super::wal_offload::WalOffload;
 use crate::mrecordlog_async::MultiRecordLogAsync;
 use crate::{with_lock_metrics, ClientId, IngesterPool};
 
@@ -55,12 +56,17 @@ pub(super) struct FetchStreamTask {
     queue_id: QueueId,
     /// The position of the next record fetched.
     from_position_inclusive: u64,
+    /// The position of the last record to fetch. When `None`, the task fetches records until
+    /// the end of the shard.
+    to_position_inclusive_opt: Option<u64>,
     mrecordlog: Arc<RwLock<Option<MultiRecordLogAsync>>>,
     fetch_message_tx: TrackedSender<IngestV2Result<FetchMessage>>,
     /// This channel notifies the fetch task when new records are available. This way the fetch
     /// task does not need to grab the lock and poll the mrecordlog queue unnecessarily.
     shard_status_rx: watch::Receiver<ShardStatus>,
     batch_num_bytes: usize,
+    /// Serves the records truncated locally after being offloaded to object storage.
+    wal_offload_opt: Option<WalOffload>,
 }
 
 impl fmt::Debug for FetchStreamTask {
@@ -80,12 +86,17 @@ impl FetchStreamTask {
         mrecordlog: Arc<RwLock<Option<MultiRecordLogAsync>>>,
         shard_status_rx: watch::Receiver<ShardStatus>,
         batch_num_bytes: usize,
+        wal_offload_opt: Option<WalOffload>,
     ) -> (ServiceStream<IngestV2Result<FetchMessage>>, JoinHandle<()>) {
         let from_position_inclusive = open_fetch_stream_request
             .from_position_exclusive()
             .as_u64()
             .map(|offset| offset + 1)
             .unwrap_or_default();
+        let to_position_inclusive_opt = open_fetch_stream_request
+            .to_position_inclusive
+            .as_ref()
+            .and_then(|position| position.as_u64());
         let (fetch_message_tx, fetch_stream) =
             ServiceStream::new_bounded_with_gauge(3, &MEMORY_METRICS.in_flight.fetch_stream);
         let mut fetch_task = Self {
@@ -95,10 +106,12 @@ impl FetchStreamTask {
             client_id: open_fetch_stream_request.client_id,
             source_id: open_fetch_stream_request.source_id,
             from_position_inclusive,
+            to_position_inclusive_opt,
             mrecordlog,
             fetch_message_tx,
             shard_status_rx,
             batch_num_bytes,
+            wal_offload_opt,
         };
         let future = async move { fetch_task.run().await };
         let fetch_task_handle: JoinHandle<()> = spawn_named_task(future, "fetch_task");
@@ -106,7 +119,8 @@ impl FetchStreamTask {
     }
 
     /// Runs the fetch task. It waits for new records in the log and pushes them into the fetch
-    /// response channel until it reaches the end of the shard marked by an EOF record.
+    /// response channel until it reaches the end of the shard marked by an EOF record or the
+    /// requested upper bound.
     async fn run(&mut self) {
         debug!(
             client_id=%self.client_id,
@@ -124,6 +138,17 @@ impl FetchStreamTask {
         };
 
         loop {
+            if self.has_reached_to_position() {
+                debug!(
+                    client_id=%self.client_id,
+                    index_uid=%self.index_uid,
+                    source_id=%self.source_id,
+                    shard_id=%self.shard_id,
+                    to_position_inclusive=%to_position_inclusive,
+                    "fetch stream reached requested position"
+                );
+                return;
+            }
             if has_drained_queue && self.shard_status_rx.changed().await.is_err() {
                 // The shard was dropped.
                 break;
@@ -136,39 +161,88 @@ impl FetchStreamTask {
             let mrecordlog_guard =
                 with_lock_metrics!(self.mrecordlog.read().await, "fetch", "read");
 
-            let Ok(mrecords) = mrecordlog_guard
-                .as_ref()
-                .expect("mrecordlog should be initialized")
-                .range(&self.queue_id, self.from_position_inclusive..)
-            else {
-                // The queue was dropped.
-                break;
-            };
-            for Record { payload, .. } in mrecords {
-                if mrecord_buffer.len() + payload.len() > mrecord_buffer.capacity() {
-                    has_drained_queue = false;
+            // The shard status is read while holding the lock on the WAL so that the records of an
+            // atomic persist request rolled back concurrently cannot be observed.
+            let max_position_inclusive_opt =
+                self.max_position_inclusive()
+                    .filter(|max_position_inclusive| {
+                        *max_position_inclusive >= self.from_position_inclusive
+                    });
+            let mut is_offloaded = false;
+
+            if let Some(max_position_inclusive) = max_position_inclusive_opt {
+                let Ok(mrecords) = mrecordlog_guard
+                    .as_ref()
+                    .expect("mrecordlog should be initialized")
+                    .range(
+                        &self.queue_id,
+                        self.from_position_inclusive..=max_position_inclusive,
+                    )
+                else {
+                    // The queue was dropped.
                     break;
+                };
+                let mut mrecords = mrecords.peekable();
+
+                // The next records were truncated locally after being offloaded to object storage.
+                is_offloaded = self.wal_offload_opt.is_some()
+                    && mrecords.peek().map_or(false, |record| {
+                        record.position > self.from_position_inclusive
+                    });
+
+                if !is_offloaded {
+                    for Record { payload, .. } in mrecords {
+                        if mrecord_buffer.len() + payload.len() > mrecord_buffer.capacity() {
+                            has_drained_queue = false;
+                            break;
+                        }
+                        mrecord_buffer.put(payload.borrow());
+                        mrecord_lengths.push(payload.len() as u32);
+                    }
                 }
-                mrecord_buffer.put(payload.borrow());
-                mrecord_lengths.push(payload.len() as u32);
             }
             // Drop the lock while we send the message.
             drop(mrecordlog_guard);
 
-            if !mrecord_lengths.is_empty() {
+            let mrecord_batch_opt = if is_offloaded {
+                match self.fetch_offloaded().await {
+                    Ok(mrecord_batch) => {
+                        has_drained_queue = false;
+                        Some(self.truncate_to_position(mrecord_batch))
+                    }
+                    Err(ingest_error) => {
+                        error!(
+                            client_id=%self.client_id,
+                            index_uid=%self.index_uid,
+                            source_id=%self.source_id,
+                            shard_id=%self.shard_id,
+                            "{ingest_error}"
+                        );
+                        let _ = self
+                            .fetch_message_tx
+                            .send(Err(ingest_error), ByteSize(0))
+                            .await;
+                        return;
+                    }
+                }
+            } else if mrecord_lengths.is_empty() {
+                None
+            } else {
+                Some(MRecordBatch {
+                    mrecord_buffer: mrecord_buffer.freeze(),
+                    mrecord_lengths,
+                })
+            };
+            if let Some(mrecord_batch) = mrecord_batch_opt {
                 let from_position_exclusive = if self.from_position_inclusive == 0 {
                     Position::Beginning
                 } else {
                     Position::offset(self.from_position_inclusive - 1)
                 };
-                self.from_position_inclusive += mrecord_lengths.len() as u64;
+                self.from_position_inclusive += mrecord_batch.mrecord_lengths.len() as u64;
 
                 to_position_inclusive = Position::offset(self.from_position_inclusive - 1);
 
-                let mrecord_batch = MRecordBatch {
-                    mrecord_buffer: mrecord_buffer.freeze(),
-                    mrecord_lengths,
-                };
                 let batch_size = mrecord_batch.estimate_size();
                 let fetch_payload = FetchPayload {
                     index_uid: self.index_uid.clone().into(),
@@ -245,6 +319,76 @@ impl FetchStreamTask {
     }
 }
 
+impl FetchStreamTask {
+    fn has_reached_to_position(&self) -> bool {
+        self.to_position_inclusive_opt
+            .map_or(false, |to_position_inclusive| {
+                self.from_position_inclusive > to_position_inclusive
+            })
+    }
+
+    /// Returns the position of the last record that the task may fetch, if any. The records located
+    /// past the replication position of a closed shard belong to rolled back atomic persist
+    /// requests and are never fetched.
+    fn max_position_inclusive(&self) -> Option<u64> {
+        let max_position_inclusive = self.to_position_inclusive_opt.unwrap_or(u64::MAX);
+        let shard_status = self.shard_status_rx.borrow();
+        let (shard_state, replication_position_inclusive) = &*shard_status;
+
+        if !shard_state.is_closed() {
+            return Some(max_position_inclusive);
+        }
+        replication_position_inclusive
+            .as_u64()
+            .map(|replication_position_inclusive| {
+                replication_position_inclusive.min(max_position_inclusive)
+            })
+    }
+
+    /// Drops the records of the batch located past the requested upper bound.
+    fn truncate_to_position(&self, mrecord_batch: MRecordBatch) -> MRecordBatch {
+        let Some(to_position_inclusive) = self.to_position_inclusive_opt else {
+            return mrecord_batch;
+        };
+        let max_num_mrecords = (to_position_inclusive + 1 - self.from_position_inclusive) as usize;
+
+        if mrecord_batch.mrecord_lengths.len() <= max_num_mrecords {
+            return mrecord_batch;
+        }
+        let mut mrecord_lengths = mrecord_batch.mrecord_lengths;
+        mrecord_lengths.truncate(max_num_mrecords);
+
+        let num_bytes: usize = mrecord_lengths.iter().map(|len| *len as usize).sum();
+        let mrecord_buffer = mrecord_batch.mrecord_buffer.slice(..num_bytes);
+
+        MRecordBatch {
+            mrecord_buffer,
+            mrecord_lengths,
+        }
+    }
+
+    /// Fetches the next records from the segments offloaded to object storage.
+    async fn fetch_offloaded(&self) -> IngestV2Result<MRecordBatch> {
+        let wal_offload = self
+            .wal_offload_opt
+            .as_ref()
+            .expect("WAL offload should be enabled");
+        wal_offload
+            .fetch(
+                &self.queue_id,
+                self.from_position_inclusive,
+                self.batch_num_bytes,
+            )
+            .await?
+            .ok_or_else(|| {
+                IngestV2Error::Internal(format!(
+                    "records of shard `{}` from position {} are neither in the WAL nor offloaded",
+                    self.queue_id, self.from_position_inclusive
+                ))
+            })
+    }
+}
+
 #[derive(Debug)]
 pub struct FetchStreamError {
     pub index_uid: IndexUid,
@@ -496,6 +640,7 @@ async fn fault_tolerant_fetch_stream(
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(from_position_exclusive.clone()),
+            to_position_inclusive: None,
         };
         let mut fetch_stream = match ingester.open_fetch_stream(open_fetch_stream_request).await {
             Ok(fetch_stream) => fetch_stream,
@@ -664,6 +809,7 @@ pub(super) mod tests {
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let (shard_status_tx, shard_status_rx) = watch::channel(ShardStatus::default());
         let (mut fetch_stream, fetch_task_handle) = FetchStreamTask::spawn(
@@ -671,6 +817,7 @@ pub(super) mod tests {
             mrecordlog.clone(),
             shard_status_rx,
             1024,
+            None,
         );
         let mut mrecordlog_guard = mrecordlog.write().await;
 
@@ -881,6 +1028,7 @@ pub(super) mod tests {
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(Position::offset(0u64)),
+            to_position_inclusive: None,
         };
         let shard_status = (ShardState::Closed, Position::offset(0u64));
         let (_shard_status_tx, shard_status_rx) = watch::channel(shard_status);
@@ -890,6 +1038,7 @@ pub(super) mod tests {
             mrecordlog.clone(),
             shard_status_rx,
             1024,
+            None,
         );
         let fetch_message = timeout(Duration::from_millis(100), fetch_stream.next())
             .await
@@ -906,6 +1055,89 @@ pub(super) mod tests {
         fetch_task_handle.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_fetch_task_skips_rolled_back_records() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mrecordlog = Arc::new(RwLock::new(Some(
+            MultiRecordLogAsync::open(tempdir.path()).await.unwrap(),
+        )));
+        let client_id = "test-client".to_string();
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let source_id = "test-source".to_string();
+        let shard_id = ShardId::from(1);
+        let queue_id = queue_id(&index_uid, &source_id, &shard_id);
+
+        let mut mrecordlog_guard = mrecordlog.write().await;
+
+        mrecordlog_guard
+            .as_mut()
+            .unwrap()
+            .create_queue(&queue_id)
+            .await
+            .unwrap();
+
+        let mrecords = [
+            MRecord::new_doc("test-doc-foo").encode(),
+            MRecord::new_doc("test-doc-bar").encode(),
+            MRecord::new_doc("test-doc-baz").encode(),
+            MRecord::Rollback(Position::offset(0u64)).encode(),
+        ]
+        .into_iter();
+
+        mrecordlog_guard
+            .as_mut()
+            .unwrap()
+            .append_records(&queue_id, None, mrecords)
+            .await
+            .unwrap();
+        drop(mrecordlog_guard);
+
+        let open_fetch_stream_request = OpenFetchStreamRequest {
+            client_id: client_id.clone(),
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+            shard_id: Some(shard_id.clone()),
+            from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
+        };
+        let shard_status = (ShardState::Closed, Position::offset(0u64));
+        let (_shard_status_tx, shard_status_rx) = watch::channel(shard_status);
+
+        let (mut fetch_stream, fetch_task_handle) = FetchStreamTask::spawn(
+            open_fetch_stream_request,
+            mrecordlog.clone(),
+            shard_status_rx,
+            1024,
+            None,
+        );
+        let fetch_message = timeout(Duration::from_millis(100), fetch_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let fetch_payload = into_fetch_payload(fetch_message);
+
+        assert_eq!(fetch_payload.from_position_exclusive(), Position::Beginning);
+        assert_eq!(
+            fetch_payload.to_position_inclusive(),
+            Position::offset(0u64)
+        );
+        assert_eq!(
+            fetch_payload.mrecord_batch.as_ref().unwrap().mrecord_buffer,
+            "\0\0test-doc-foo"
+        );
+
+        let fetch_message = timeout(Duration::from_millis(100), fetch_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let fetch_eof = into_fetch_eof(fetch_message);
+        assert_eq!(fetch_eof.eof_position, Some(Position::eof(0u64)));
+
+        fetch_task_handle.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_fetch_task_signals_eof_at_beginning() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -924,6 +1156,7 @@ pub(super) mod tests {
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let (shard_status_tx, shard_status_rx) = watch::channel(ShardStatus::default());
         let (mut fetch_stream, fetch_task_handle) = FetchStreamTask::spawn(
@@ -931,6 +1164,7 @@ pub(super) mod tests {
             mrecordlog.clone(),
             shard_status_rx,
             1024,
+            None,
         );
         let mut mrecordlog_guard = mrecordlog.write().await;
 
@@ -978,6 +1212,7 @@ pub(super) mod tests {
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(Position::offset(0u64)),
+            to_position_inclusive: None,
         };
         let (shard_status_tx, shard_status_rx) = watch::channel(ShardStatus::default());
         let (mut fetch_stream, _fetch_task_handle) = FetchStreamTask::spawn(
@@ -985,6 +1220,7 @@ pub(super) mod tests {
             mrecordlog.clone(),
             shard_status_rx,
             1024,
+            None,
         );
         let mut mrecordlog_guard = mrecordlog.write().await;
 
@@ -1087,6 +1323,7 @@ pub(super) mod tests {
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let (_shard_status_tx, shard_status_rx) = watch::channel(ShardStatus::default());
         let (mut fetch_stream, fetch_task_handle) = FetchStreamTask::spawn(
@@ -1094,6 +1331,7 @@ pub(super) mod tests {
             mrecordlog.clone(),
             shard_status_rx,
             1024,
+            None,
         );
         let ingest_error = timeout(Duration::from_millis(100), fetch_stream.next())
             .await
@@ -1123,6 +1361,7 @@ pub(super) mod tests {
             source_id: source_id.clone(),
             shard_id: Some(shard_id.clone()),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let (shard_status_tx, shard_status_rx) = watch::channel(ShardStatus::default());
         let (mut fetch_stream, _fetch_task_handle) = FetchStreamTask::spawn(
@@ -1130,6 +1369,7 @@ pub(super) mod tests {
             mrecordlog.clone(),
             shard_status_rx,
             30,
+            None,
         );
         let mut mrecordlog_guard = mrecordlog.write().await;
 
@@ -31,7 +31,7 @@ use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use mrecordlog::error::CreateQueueError;
 use once_cell::sync::OnceCell;
-use quickwit_cluster::Cluster;
+use quickwit_cluster::{Cluster, ClusterFeature, EnabledClusterFeatures};
 use quickwit_common::metrics::{GaugeGuard, MEMORY_METRICS};
 use quickwit_common::pretty::PrettyDisplay;
 use quickwit_common::pubsub::{EventBroker, EventSubscriber};
@@ -43,17 +43,18 @@ use quickwit_proto::control_plane::{
 };
 use quickwit_proto::indexing::ShardPositionsUpdate;
 use quickwit_proto::ingest::ingester::{
-    AckReplicationMessage, CloseShardsRequest, CloseShardsResponse, DecommissionRequest,
-    DecommissionResponse, FetchMessage, IngesterService, IngesterServiceClient,
-    IngesterServiceStream, IngesterStatus, InitShardFailure, InitShardSuccess, InitShardsRequest,
-    InitShardsResponse, ObservationMessage, OpenFetchStreamRequest, OpenObservationStreamRequest,
-    OpenReplicationStreamRequest, OpenReplicationStreamResponse, PersistFailure,
-    PersistFailureReason, PersistRequest, PersistResponse, PersistSuccess, ReplicateFailureReason,
-    ReplicateSubrequest, RetainShardsForSource, RetainShardsRequest, RetainShardsResponse,
+    queue_id_digest, AckReplicationMessage, CloseShardsRequest, CloseShardsResponse,
+    DecommissionRequest, DecommissionResponse, FetchMessage, FetchShardRequest, IngesterService,
+    IngesterServiceClient, IngesterServiceStream, IngesterStatus, InitShardFailure,
+    InitShardSuccess, InitShardsRequest, InitShardsResponse, ObservationMessage,
+    OpenFetchStreamRequest, OpenObservationStreamRequest, OpenReplicationStreamRequest,
+    OpenReplicationStreamResponse, PersistFailure, PersistFailureReason, PersistRequest,
+    PersistResponse, PersistSuccess, PingRequest, PingResponse, ReplicateFailureReason,
+    ReplicateSubrequest, RetainShardsRequest, RetainShardsResponse, SourceShardsDigest,
     SynReplicationMessage, TruncateShardsRequest, TruncateShardsResponse,
 };
 use quickwit_proto::ingest::{
-    CommitTypeV2, IngestV2Error, IngestV2Result, Shard, ShardIds, ShardState,
+    CommitTypeV2, DocCompression, IngestV2Error, IngestV2Result, Shard, ShardIds, ShardState,
 };
 use quickwit_proto::types::{
     queue_id, split_queue_id, IndexUid, NodeId, Position, QueueId, ShardId, SourceId,
@@ -69,19 +70,22 @@ use super::idle::CloseIdleShardsTask;
 use super::metrics::INGEST_V2_METRICS;
 use super::models::IngesterShard;
 use super::mrecordlog_utils::{
-    append_non_empty_doc_batch, check_enough_capacity, AppendDocBatchError,
+    append_non_empty_doc_batch, check_enough_capacity, compress_doc_batch, AppendDocBatchError,
 };
 use super::rate_meter::RateMeter;
 use super::replication::{
     ReplicationClient, ReplicationStreamTask, ReplicationStreamTaskHandle, ReplicationTask,
     SYN_REPLICATION_STREAM_CAPACITY,
 };
-use super::state::{IngesterState, InnerIngesterState, WeakIngesterState};
+use super::state::{
+    FullyLockedIngesterState, IngesterState, InnerIngesterState, WeakIngesterState,
+};
+use super::wal_offload::{OffloadWalTask, WalOffload};
 use super::IngesterPool;
 use crate::ingest_v2::metrics::report_wal_usage;
 use crate::metrics::INGEST_METRICS;
 use crate::mrecordlog_async::MultiRecordLogAsync;
-use crate::{estimate_size, with_lock_metrics, FollowerId};
+use crate::{estimate_size, with_lock_metrics, FollowerId, MAX_IDEMPOTENCY_KEY_LEN};
 
 /// Minimum interval between two reset shards operations.
 const MIN_RESET_SHARDS_INTERVAL: Duration = if cfg!(any(test, feature = "testsuite")) {
@@ -100,6 +104,9 @@ pub(super) const PERSIST_REQUEST_TIMEOUT: Duration = if cfg!(any(test, feature =
 
 const DEFAULT_BATCH_NUM_BYTES: usize = 1024 * 1024; // 1 MiB
 
+/// Maximum number of shard IDs sent in a single advise reset shards request.
+const ADVISE_RESET_SHARDS_BATCH_SIZE: usize = 10_000;
+
 fn get_batch_num_bytes() -> usize {
     static BATCH_NUM_BYTES_CELL: OnceCell<usize> = OnceCell::new();
     *BATCH_NUM_BYTES_CELL.get_or_init(|| {
@@ -107,6 +114,67 @@ fn get_batch_num_bytes() -> usize {
     })
 }
 
+/// Computes the digests of the shards of each source from the queue IDs of the shards.
+fn compute_source_digests<'a>(
+    queue_ids: impl IntoIterator<Item = &'a QueueId>,
+) -> Vec<SourceShardsDigest> {
+    let mut source_digests: HashMap<(IndexUid, SourceId), u64> = HashMap::new();
+
+    for queue_id in queue_ids {
+        let Some((index_uid, source_id, _)) = split_queue_id(queue_id) else {
+            continue;
+        };
+        let digest = source_digests.entry((index_uid, source_id)).or_default();
+        *digest = digest.wrapping_add(queue_id_digest(queue_id));
+    }
+    source_digests
+        .into_iter()
+        .map(|((index_uid, source_id), digest)| SourceShardsDigest {
+            index_uid: Some(index_uid),
+            source_id,
+            digest,
+        })
+        .collect()
+}
+
+/// Splits the shard IDs into batches of at most `batch_size` shard IDs, splitting the shard IDs of
+/// a source across several batches if necessary. Returns at least one batch, possibly empty.
+fn batch_shard_ids(shard_ids: Vec<ShardIds>, batch_size: usize) -> Vec<Vec<ShardIds>> {
+    let mut batches: Vec<Vec<ShardIds>> = Vec::new();
+    let mut current_batch: Vec<ShardIds> = Vec::new();
+    let mut current_batch_size = 0;
+
+    for source_shard_ids in shard_ids {
+        let ShardIds {
+            index_uid,
+            source_id,
+            shard_ids,
+        } = source_shard_ids;
+        let mut shard_ids_iter = shard_ids.into_iter().peekable();
+
+        while shard_ids_iter.peek().is_some() {
+            if current_batch_size == batch_size {
+                batches.push(std::mem::take(&mut current_batch));
+                current_batch_size = 0;
+            }
+            let chunk: Vec<ShardId> = shard_ids_iter
+                .by_ref()
+                .take(batch_size - current_batch_size)
+                .collect();
+            current_batch_size += chunk.len();
+            current_batch.push(ShardIds {
+                index_uid: index_uid.clone(),
+                source_id: source_id.clone(),
+                shard_ids: chunk,
+            });
+        }
+    }
+    if !current_batch.is_empty() || batches.is_empty() {
+        batches.push(current_batch);
+    }
+    batches
+}
+
 #[derive(Clone)]
 pub struct Ingester {
     self_node_id: NodeId,
@@ -117,6 +185,12 @@ pub struct Ingester {
     memory_capacity: ByteSize,
     rate_limiter_settings: RateLimiterSettings,
     replication_factor: usize,
+    // Compression applied to the documents written to the WAL and sent over the replication
+    // stream once all the nodes of the cluster can decode compressed documents.
+    doc_compression: DocCompression,
+    cluster_features: EnabledClusterFeatures,
+    // Offloads the WAL to object storage when it is close to full.
+    wal_offload_opt: Option<WalOffload>,
     // This semaphore ensures that the ingester that not run two reset shards operations
     // concurrently.
     reset_shards_permits: Arc<Semaphore>,
@@ -142,13 +216,21 @@ impl Ingester {
         rate_limiter_settings: RateLimiterSettings,
         replication_factor: usize,
         idle_shard_timeout: Duration,
+        doc_compression: DocCompression,
+        wal_offload_opt: Option<WalOffload>,
     ) -> IngestV2Result<Self> {
         let self_node_id: NodeId = cluster.self_node_id().into();
         let state = IngesterState::load(wal_dir_path, rate_limiter_settings);
 
+        let cluster_features = EnabledClusterFeatures::spawn(cluster.change_stream());
+
         let weak_state = state.weak();
         BroadcastLocalShardsTask::spawn(cluster, weak_state.clone());
-        CloseIdleShardsTask::spawn(weak_state, idle_shard_timeout);
+        CloseIdleShardsTask::spawn(weak_state.clone(), idle_shard_timeout);
+
+        if let Some(wal_offload) = &wal_offload_opt {
+            OffloadWalTask::spawn(weak_state, wal_offload.clone());
+        }
 
         let ingester = Self {
             self_node_id,
@@ -159,6 +241,9 @@ impl Ingester {
             memory_capacity,
             rate_limiter_settings,
             replication_factor,
+            doc_compression,
+            cluster_features,
+            wal_offload_opt,
             reset_shards_permits: Arc::new(Semaphore::new(1)),
         };
         ingester.background_reset_shards();
@@ -166,6 +251,20 @@ impl Ingester {
         Ok(ingester)
     }
 
+    /// Returns the compression to apply to the documents written to the WAL. Documents are left
+    /// uncompressed during a rolling upgrade, until all the nodes of the cluster, which may read
+    /// the WAL of this ingester through replication or fetch streams, can decode them.
+    fn wal_doc_compression(&self) -> DocCompression {
+        if self
+            .cluster_features
+            .is_enabled(ClusterFeature::WalDocCompression)
+        {
+            self.doc_compression
+        } else {
+            DocCompression::Unspecified
+        }
+    }
+
     /// Checks whether the ingester is fully decommissioned and updates its status accordingly.
     fn check_decommissioning_status(&self, state: &mut InnerIngesterState) {
         if state.status() != IngesterStatus::Decommissioning {
@@ -297,7 +396,7 @@ impl Ingester {
         }
         drop(state_guard);
 
-        let shard_ids = per_source_shard_ids
+        let shard_ids: Vec<ShardIds> = per_source_shard_ids
             .into_iter()
             .map(|((index_uid, source_id), shard_ids)| ShardIds {
                 index_uid: Some(index_uid),
@@ -305,16 +404,40 @@ impl Ingester {
                 shard_ids,
             })
             .collect();
+        let mut num_deleted_shards = 0;
+        let mut num_truncated_shards = 0;
+        let mut outcome = "success";
 
-        let advise_reset_shards_request = AdviseResetShardsRequest { shard_ids };
-        let advise_reset_shards_future = self
-            .control_plane
-            .advise_reset_shards(advise_reset_shards_request);
-        let advise_reset_shards_result =
-            timeout(Duration::from_secs(30), advise_reset_shards_future).await;
+        // The shard IDs are sent in batches and each response may be truncated by the control
+        // plane, in which case we send the same batch again along with the continuation token.
+        'batches: for shard_ids_batch in batch_shard_ids(shard_ids, ADVISE_RESET_SHARDS_BATCH_SIZE)
+        {
+            let mut continuation_token_opt: Option<String> = None;
 
-        match advise_reset_shards_result {
-            Ok(Ok(advise_reset_shards_response)) => {
+            loop {
+                let advise_reset_shards_request = AdviseResetShardsRequest {
+                    shard_ids: shard_ids_batch.clone(),
+                    continuation_token: continuation_token_opt.take(),
+                };
+                let advise_reset_shards_future = self
+                    .control_plane
+                    .advise_reset_shards(advise_reset_shards_request);
+                let advise_reset_shards_result =
+                    timeout(Duration::from_secs(30), advise_reset_shards_future).await;
+
+                let advise_reset_shards_response = match advise_reset_shards_result {
+                    Ok(Ok(advise_reset_shards_response)) => advise_reset_shards_response,
+                    Ok(Err(error)) => {
+                        warn!("advise reset shards request failed: {error}");
+                        outcome = "error";
+                        break 'batches;
+                    }
+                    Err(_) => {
+                        warn!("advise reset shards request timed out");
+                        outcome = "timeout";
+                        break 'batches;
+                    }
+                };
                 let mut state_guard =
                     with_lock_metrics!(self.state.lock_fully().await, "reset_shards", "write")
                         .expect("ingester should be ready");
@@ -323,37 +446,32 @@ impl Ingester {
                     .reset_shards(&advise_reset_shards_response)
                     .await;
 
-                info!(
-                    "deleted {} and truncated {} shard(s) in {}",
-                    advise_reset_shards_response.shards_to_delete.len(),
-                    advise_reset_shards_response.shards_to_truncate.len(),
-                    now.elapsed().pretty_display()
-                );
-                INGEST_V2_METRICS
-                    .reset_shards_operations_total
-                    .with_label_values(["success"])
-                    .inc();
+                num_deleted_shards += advise_reset_shards_response.shards_to_delete.len();
+                num_truncated_shards += advise_reset_shards_response.shards_to_truncate.len();
 
                 let wal_usage = state_guard.mrecordlog.resource_usage();
                 report_wal_usage(wal_usage);
-            }
-            Ok(Err(error)) => {
-                warn!("advise reset shards request failed: {error}");
+                drop(state_guard);
 
-                INGEST_V2_METRICS
-                    .reset_shards_operations_total
-                    .with_label_values(["error"])
-                    .inc();
+                if advise_reset_shards_response.continuation_token.is_none() {
+                    break;
+                }
+                continuation_token_opt = advise_reset_shards_response.continuation_token;
             }
-            Err(_) => {
-                warn!("advise reset shards request timed out");
+        }
+        if outcome == "success" {
+            info!(
+                "deleted {} and truncated {} shard(s) in {}",
+                num_deleted_shards,
+                num_truncated_shards,
+                now.elapsed().pretty_display()
+            );
+        }
+        INGEST_V2_METRICS
+            .reset_shards_operations_total
+            .with_label_values([outcome])
+            .inc();
 
-                INGEST_V2_METRICS
-                    .reset_shards_operations_total
-                    .with_label_values(["timeout"])
-                    .inc();
-            }
-        };
         // We still hold the permit while sleeping so we effectively rate limit the reset shards
         // operation to once per [`MIN_RESET_SHARDS_INTERVAL`].
         if let Some(sleep_for) = MIN_RESET_SHARDS_INTERVAL.checked_sub(now.elapsed()) {
@@ -433,11 +551,15 @@ impl Ingester {
         }
         let mut persist_successes = Vec::with_capacity(persist_request.subrequests.len());
         let mut persist_failures = Vec::new();
-        let mut replicate_subrequests: HashMap<NodeId, Vec<(ReplicateSubrequest, QueueId)>> =
+        let mut replicate_subrequests: HashMap<NodeId, Vec<(ReplicateSubrequest, QueueId, u64)>> =
             HashMap::new();
         let mut local_persist_subrequests: Vec<LocalPersistSubrequest> =
             Vec::with_capacity(persist_request.subrequests.len());
 
+        // Keep track of the positions of the shards before the request so that they can be rolled
+        // back if an atomic request is aborted.
+        let mut rollback_positions: HashMap<QueueId, Position> = HashMap::new();
+
         // Keep track of the shards that need to be closed following an IO error.
         let mut shards_to_close: HashSet<QueueId> = HashSet::new();
 
@@ -447,6 +569,7 @@ impl Ingester {
 
         let commit_type = persist_request.commit_type();
         let force_commit = commit_type == CommitTypeV2::Force;
+        let atomic = persist_request.atomic;
         let leader_id: NodeId = persist_request.leader_id.into();
 
         let mut state_guard =
@@ -476,10 +599,48 @@ impl Ingester {
         // first verify if we would locally accept each subrequest
         {
             let mut total_requested_capacity = bytesize::ByteSize::b(0);
+            // Idempotency keys are ignored during a rolling upgrade, until all the nodes of the
+            // cluster can decode the idempotency key records of the WAL and the replication stream.
+            let is_idempotency_key_enabled = self
+                .cluster_features
+                .is_enabled(ClusterFeature::IdempotencyKey);
 
-            for subrequest in persist_request.subrequests {
+            for mut subrequest in persist_request.subrequests {
                 let queue_id = subrequest.queue_id();
 
+                let idempotency_key_opt = subrequest.idempotency_key.take().filter(|key| {
+                    if !is_idempotency_key_enabled {
+                        return false;
+                    }
+                    if key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+                        rate_limited_warn!(
+                            limit_per_min = 6,
+                            "ignoring idempotency key longer than {MAX_IDEMPOTENCY_KEY_LEN} bytes"
+                        );
+                        return false;
+                    }
+                    true
+                });
+                // Replays are looked up before the state of the shard is checked: the shards
+                // recovered after a restart are closed but still deduplicate the batches persisted
+                // before the restart.
+                if let Some((shard_id, position_inclusive)) = idempotency_key_opt
+                    .as_deref()
+                    .and_then(|key| find_persisted_batch(&state_guard.shards, &queue_id, key))
+                {
+                    debug!("acknowledging replayed persist subrequest for shard `{queue_id}`");
+                    INGEST_V2_METRICS.deduplicated_subrequests_total.inc();
+
+                    let persist_success = PersistSuccess {
+                        subrequest_id: subrequest.subrequest_id,
+                        index_uid: subrequest.index_uid,
+                        source_id: subrequest.source_id,
+                        shard_id: Some(shard_id),
+                        replication_position_inclusive: Some(position_inclusive),
+                    };
+                    persist_successes.push(persist_success);
+                    continue;
+                }
                 let Some(shard) = state_guard.shards.get_mut(&queue_id) else {
                     let persist_failure = PersistFailure {
                         subrequest_id: subrequest.subrequest_id,
@@ -502,7 +663,6 @@ impl Ingester {
                     persist_failures.push(persist_failure);
                     continue;
                 }
-
                 let follower_id_opt = shard.follower_id_opt().cloned();
                 let from_position_exclusive = shard.replication_position_inclusive.clone();
 
@@ -571,6 +731,15 @@ impl Ingester {
                 rate_meter.update(batch_num_bytes);
                 total_requested_capacity += requested_capacity;
 
+                if atomic {
+                    rollback_positions
+                        .entry(queue_id.clone())
+                        .or_insert_with(|| from_position_exclusive.clone());
+                }
+
+                let (doc_batch, doc_compression) =
+                    compress_doc_batch(doc_batch, self.wal_doc_compression());
+
                 if let Some(follower_id) = follower_id_opt {
                     let replicate_subrequest = ReplicateSubrequest {
                         subrequest_id: subrequest.subrequest_id,
@@ -579,11 +748,15 @@ impl Ingester {
                         shard_id: subrequest.shard_id,
                         from_position_exclusive: Some(from_position_exclusive),
                         doc_batch: Some(doc_batch),
+                        doc_compression: doc_compression as i32,
+                        idempotency_key: idempotency_key_opt,
+                        rollback_to_position_inclusive: None,
                     };
-                    replicate_subrequests
-                        .entry(follower_id)
-                        .or_default()
-                        .push((replicate_subrequest, queue_id));
+                    replicate_subrequests.entry(follower_id).or_default().push((
+                        replicate_subrequest,
+                        queue_id,
+                        batch_num_bytes,
+                    ));
                 } else {
                     local_persist_subrequests.push(LocalPersistSubrequest {
                         queue_id,
@@ -592,12 +765,56 @@ impl Ingester {
                         source_id: subrequest.source_id,
                         shard_id: subrequest.shard_id,
                         doc_batch,
+                        doc_compression,
+                        batch_num_bytes,
                         expected_position_inclusive: None,
+                        idempotency_key_opt,
                     })
                 }
             }
         }
 
+        if atomic && !persist_failures.is_empty() {
+            // At least one subrequest cannot be persisted, so we abort the whole request before
+            // replicating or appending anything.
+            for persist_success in persist_successes.drain(..) {
+                let persist_failure = PersistFailure {
+                    subrequest_id: persist_success.subrequest_id,
+                    index_uid: persist_success.index_uid,
+                    source_id: persist_success.source_id,
+                    shard_id: persist_success.shard_id,
+                    reason: PersistFailureReason::Aborted as i32,
+                };
+                persist_failures.push(persist_failure);
+            }
+            for (subrequest, ..) in replicate_subrequests.into_values().flatten() {
+                let persist_failure = PersistFailure {
+                    subrequest_id: subrequest.subrequest_id,
+                    index_uid: subrequest.index_uid,
+                    source_id: subrequest.source_id,
+                    shard_id: subrequest.shard_id,
+                    reason: PersistFailureReason::Aborted as i32,
+                };
+                persist_failures.push(persist_failure);
+            }
+            for subrequest in local_persist_subrequests {
+                let persist_failure = PersistFailure {
+                    subrequest_id: subrequest.subrequest_id,
+                    index_uid: Some(subrequest.index_uid),
+                    source_id: subrequest.source_id,
+                    shard_id: subrequest.shard_id,
+                    reason: PersistFailureReason::Aborted as i32,
+                };
+                persist_failures.push(persist_failure);
+            }
+            let persist_response = PersistResponse {
+                leader_id: leader_id.into(),
+                successes: Vec::new(),
+                failures: persist_failures,
+            };
+            return Ok(persist_response);
+        }
+
         // replicate to the follower
         {
             let mut replicate_futures = FuturesUnordered::new();
@@ -611,12 +828,23 @@ impl Ingester {
                     .replication_client();
                 let leader_id = self.self_node_id.clone();
                 let mut subrequests = Vec::with_capacity(subrequests_with_queue_id.len());
-                for (subrequest, queue_id) in subrequests_with_queue_id {
+                for (subrequest, queue_id, batch_num_bytes) in subrequests_with_queue_id {
                     let doc_batch = subrequest
                         .doc_batch
                         .clone()
                         .expect("we already verified doc is present and not empty");
-                    doc_batch_map.insert(subrequest.subrequest_id, (doc_batch, queue_id));
+                    let doc_compression = subrequest.doc_compression();
+                    let idempotency_key_opt = subrequest.idempotency_key.clone();
+                    doc_batch_map.insert(
+                        subrequest.subrequest_id,
+                        (
+                            doc_batch,
+                            doc_compression,
+                            queue_id,
+                            batch_num_bytes,
+                            idempotency_key_opt,
+                        ),
+                    );
                     subrequests.push(subrequest);
                 }
                 let replicate_future =
@@ -636,7 +864,13 @@ impl Ingester {
                     }
                 };
                 for replicate_success in replicate_response.successes {
-                    let (doc_batch, queue_id) = doc_batch_map
+                    let (
+                        doc_batch,
+                        doc_compression,
+                        queue_id,
+                        batch_num_bytes,
+                        idempotency_key_opt,
+                    ) = doc_batch_map
                         .remove(&replicate_success.subrequest_id)
                         .expect("expected known subrequest id");
                     let local_persist_subrequest = LocalPersistSubrequest {
@@ -646,8 +880,11 @@ impl Ingester {
                         source_id: replicate_success.source_id,
                         shard_id: replicate_success.shard_id,
                         doc_batch,
+                        doc_compression,
+                        batch_num_bytes,
                         expected_position_inclusive: replicate_success
                             .replication_position_inclusive,
+                        idempotency_key_opt,
                     };
                     local_persist_subrequests.push(local_persist_subrequest);
                 }
@@ -674,22 +911,101 @@ impl Ingester {
                     persist_failures.push(persist_failure);
                 }
             }
+            if atomic && (!persist_failures.is_empty() || !doc_batch_map.is_empty()) {
+                // Some subrequests were not replicated, so we abort the whole request. The replicas
+                // that received the records of the request, or for which the outcome of the
+                // replication is unknown, are rolled back. Their primaries are closed because the
+                // replicas no longer accept the records the primaries would append next.
+                let replicated_queue_ids: Vec<QueueId> = local_persist_subrequests
+                    .iter()
+                    .filter(|subrequest| subrequest.expected_position_inclusive.is_some())
+                    .map(|subrequest| subrequest.queue_id.clone())
+                    .chain(
+                        doc_batch_map
+                            .values()
+                            .map(|(_doc_batch, _doc_compression, queue_id, ..)| queue_id.clone()),
+                    )
+                    .collect();
+                let rolled_back_queue_ids = self
+                    .rollback_replicas(&state_guard, &rollback_positions, replicated_queue_ids)
+                    .await;
+
+                for subrequest in local_persist_subrequests.drain(..) {
+                    let reason = if subrequest.expected_position_inclusive.is_none() {
+                        PersistFailureReason::Aborted
+                    } else if rolled_back_queue_ids.contains(&subrequest.queue_id) {
+                        shards_to_close.insert(subrequest.queue_id);
+                        PersistFailureReason::Aborted
+                    } else {
+                        shards_to_close.insert(subrequest.queue_id);
+                        PersistFailureReason::ShardClosed
+                    };
+                    let persist_failure = PersistFailure {
+                        subrequest_id: subrequest.subrequest_id,
+                        index_uid: Some(subrequest.index_uid),
+                        source_id: subrequest.source_id,
+                        shard_id: subrequest.shard_id,
+                        reason: reason as i32,
+                    };
+                    persist_failures.push(persist_failure);
+                }
+                for (subrequest_id, (_doc_batch, _doc_compression, queue_id, ..)) in
+                    doc_batch_map.drain()
+                {
+                    let Some((index_uid, source_id, shard_id)) = split_queue_id(&queue_id) else {
+                        continue;
+                    };
+                    let reason = if rolled_back_queue_ids.contains(&queue_id) {
+                        PersistFailureReason::Aborted
+                    } else {
+                        PersistFailureReason::ShardClosed
+                    };
+                    shards_to_close.insert(queue_id);
+
+                    let persist_failure = PersistFailure {
+                        subrequest_id,
+                        index_uid: Some(index_uid),
+                        source_id,
+                        shard_id: Some(shard_id),
+                        reason: reason as i32,
+                    };
+                    persist_failures.push(persist_failure);
+                }
+                for persist_success in persist_successes.drain(..) {
+                    let persist_failure = PersistFailure {
+                        subrequest_id: persist_success.subrequest_id,
+                        index_uid: persist_success.index_uid,
+                        source_id: persist_success.source_id,
+                        shard_id: persist_success.shard_id,
+                        reason: PersistFailureReason::Aborted as i32,
+                    };
+                    persist_failures.push(persist_failure);
+                }
+            }
         }
 
-        // finally write locally
+        // finally write locally. For atomic requests, all the subrequests are appended under the
+        // same lock, and an IO error midway rolls back the subrequests appended before it.
         {
             let now = Instant::now();
-            for subrequest in local_persist_subrequests {
+            let mut local_persist_subrequests = local_persist_subrequests.into_iter();
+            // Shards to roll back if the atomic request is aborted while appending locally.
+            let mut appended_queue_ids: Vec<QueueId> = Vec::new();
+            let mut is_aborted = false;
+
+            for subrequest in local_persist_subrequests.by_ref() {
                 let queue_id = subrequest.queue_id;
 
-                let batch_num_bytes = subrequest.doc_batch.num_bytes() as u64;
+                let batch_num_bytes = subrequest.batch_num_bytes;
                 let batch_num_docs = subrequest.doc_batch.num_docs() as u64;
 
                 let append_result = append_non_empty_doc_batch(
                     &mut state_guard.mrecordlog,
                     &queue_id,
                     subrequest.doc_batch,
+                    subrequest.doc_compression,
                     force_commit,
+                    subrequest.idempotency_key_opt.as_deref(),
                 )
                 .await;
 
@@ -701,6 +1017,10 @@ impl Ingester {
                                 error!(
                                     "failed to persist records to shard `{queue_id}`: {io_error}"
                                 );
+                                if atomic {
+                                    // The outcome of the append is unknown.
+                                    appended_queue_ids.push(queue_id.clone());
+                                }
                                 shards_to_close.insert(queue_id);
                                 PersistFailureReason::ShardClosed
                             }
@@ -721,10 +1041,17 @@ impl Ingester {
                             reason: reason as i32,
                         };
                         persist_failures.push(persist_failure);
+
+                        if atomic {
+                            is_aborted = true;
+                            break;
+                        }
                         continue;
                     }
                 };
-
+                if atomic {
+                    appended_queue_ids.push(queue_id.clone());
+                }
                 if let Some(expected_position_inclusive) = subrequest.expected_position_inclusive {
                     if expected_position_inclusive != current_position_inclusive {
                         return Err(IngestV2Error::Internal(format!(
@@ -733,11 +1060,17 @@ impl Ingester {
                         )));
                     }
                 }
-                state_guard
+                let shard = state_guard
                     .shards
                     .get_mut(&queue_id)
-                    .expect("primary shard should exist")
-                    .set_replication_position_inclusive(current_position_inclusive.clone(), now);
+                    .expect("primary shard should exist");
+                shard.set_replication_position_inclusive(current_position_inclusive.clone(), now);
+
+                if let Some(idempotency_key) = subrequest.idempotency_key_opt {
+                    shard
+                        .dedup_window
+                        .insert(idempotency_key, current_position_inclusive.clone());
+                }
 
                 INGEST_METRICS.ingested_num_bytes.inc_by(batch_num_bytes);
                 INGEST_METRICS.ingested_num_docs.inc_by(batch_num_docs);
@@ -751,6 +1084,45 @@ impl Ingester {
                 };
                 persist_successes.push(persist_success);
             }
+            if is_aborted {
+                // The subrequests appended before the failure are rolled back on the leader, and
+                // all the replicas, which received the records of the request, are rolled back as
+                // well.
+                for subrequest in local_persist_subrequests {
+                    if subrequest.expected_position_inclusive.is_some() {
+                        shards_to_close.insert(subrequest.queue_id);
+                    }
+                    let persist_failure = PersistFailure {
+                        subrequest_id: subrequest.subrequest_id,
+                        index_uid: Some(subrequest.index_uid),
+                        source_id: subrequest.source_id,
+                        shard_id: subrequest.shard_id,
+                        reason: PersistFailureReason::Aborted as i32,
+                    };
+                    persist_failures.push(persist_failure);
+                }
+                for persist_success in persist_successes.drain(..) {
+                    let persist_failure = PersistFailure {
+                        subrequest_id: persist_success.subrequest_id,
+                        index_uid: persist_success.index_uid,
+                        source_id: persist_success.source_id,
+                        shard_id: persist_success.shard_id,
+                        reason: PersistFailureReason::Aborted as i32,
+                    };
+                    persist_failures.push(persist_failure);
+                }
+                for queue_id in &appended_queue_ids {
+                    if let Some(rollback_position) = rollback_positions.get(queue_id) {
+                        state_guard
+                            .rollback_shard(queue_id, rollback_position.clone())
+                            .await;
+                    }
+                }
+                let replicated_queue_ids: Vec<QueueId> =
+                    rollback_positions.keys().cloned().collect();
+                self.rollback_replicas(&state_guard, &rollback_positions, replicated_queue_ids)
+                    .await;
+            }
         }
         if !shards_to_close.is_empty() {
             for queue_id in &shards_to_close {
@@ -760,7 +1132,7 @@ impl Ingester {
                     .expect("shard should exist");
 
                 shard.close();
-                warn!("closed shard `{queue_id}` following IO error");
+                warn!("closed shard `{queue_id}` following persist failure");
             }
         }
         if !shards_to_delete.is_empty() {
@@ -789,6 +1161,94 @@ impl Ingester {
         Ok(persist_response)
     }
 
+    /// Rolls the replicas of the shards `queue_ids` back to their positions recorded in
+    /// `rollback_positions` and closes them. Returns the shards for which the followers
+    /// acknowledged the rollback.
+    async fn rollback_replicas(
+        &self,
+        state_guard: &FullyLockedIngesterState<'_>,
+        rollback_positions: &HashMap<QueueId, Position>,
+        queue_ids: Vec<QueueId>,
+    ) -> HashSet<QueueId> {
+        let mut rollback_subrequests: HashMap<NodeId, Vec<(ReplicateSubrequest, QueueId)>> =
+            HashMap::new();
+
+        for queue_id in queue_ids {
+            let Some(follower_id) = state_guard
+                .shards
+                .get(&queue_id)
+                .and_then(|shard| shard.follower_id_opt())
+            else {
+                continue;
+            };
+            let Some(rollback_position) = rollback_positions.get(&queue_id) else {
+                continue;
+            };
+            let Some((index_uid, source_id, shard_id)) = split_queue_id(&queue_id) else {
+                continue;
+            };
+            let subrequests = rollback_subrequests.entry(follower_id.clone()).or_default();
+            let rollback_subrequest = ReplicateSubrequest {
+                subrequest_id: subrequests.len() as u32,
+                index_uid: Some(index_uid),
+                source_id,
+                shard_id: Some(shard_id),
+                from_position_exclusive: Some(rollback_position.clone()),
+                doc_batch: None,
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: Some(rollback_position.clone()),
+            };
+            subrequests.push((rollback_subrequest, queue_id));
+        }
+        let mut rollback_futures = FuturesUnordered::new();
+
+        for (follower_id, subrequests_with_queue_id) in rollback_subrequests {
+            let Some(replication_stream) = state_guard.replication_streams.get(&follower_id) else {
+                continue;
+            };
+            let replication_client = replication_stream.replication_client();
+            let (subrequests, queue_ids): (Vec<ReplicateSubrequest>, Vec<QueueId>) =
+                subrequests_with_queue_id.into_iter().unzip();
+            let rollback_future = replication_client.replicate(
+                self.self_node_id.clone(),
+                follower_id.clone(),
+                subrequests,
+                CommitTypeV2::Auto,
+            );
+            rollback_futures.push(async move { (follower_id, queue_ids, rollback_future.await) });
+        }
+        let mut rolled_back_queue_ids = HashSet::new();
+
+        while let Some((follower_id, queue_ids, rollback_result)) = rollback_futures.next().await {
+            match rollback_result {
+                Ok(replicate_response) => {
+                    for replicate_success in replicate_response.successes {
+                        let queue_id = &queue_ids[replicate_success.subrequest_id as usize];
+                        rolled_back_queue_ids.insert(queue_id.clone());
+                    }
+                    for replicate_failure in replicate_response.failures {
+                        let queue_id = &queue_ids[replicate_failure.subrequest_id as usize];
+                        warn!(
+                            "failed to roll back replica shard `{queue_id}` on ingester \
+                             `{follower_id}`: {:?}",
+                            replicate_failure.reason()
+                        );
+                    }
+                }
+                Err(replication_error) => {
+                    // The replicas keep the aborted records, which can be fetched from them if
+                    // they ever serve the shard.
+                    error!(
+                        "failed to roll back replica shard(s) on ingester `{follower_id}`: \
+                         {replication_error}"
+                    );
+                }
+            }
+        }
+        rolled_back_queue_ids
+    }
+
     /// Opens a replication stream, which is a bi-directional gRPC stream. The client-side stream
     async fn open_replication_stream_inner(
         &mut self,
@@ -863,6 +1323,44 @@ impl Ingester {
             mrecordlog,
             shard_status_rx,
             get_batch_num_bytes(),
+            self.wal_offload_opt.clone(),
+        );
+        Ok(service_stream)
+    }
+
+    async fn fetch_shard_inner(
+        &mut self,
+        fetch_shard_request: FetchShardRequest,
+    ) -> IngestV2Result<ServiceStream<IngestV2Result<FetchMessage>>> {
+        let queue_id = fetch_shard_request.queue_id();
+        let shard_status_rx = {
+            let state_guard = self.state.lock_partially().await?;
+            let shard =
+                state_guard
+                    .shards
+                    .get(&queue_id)
+                    .ok_or_else(|| IngestV2Error::ShardNotFound {
+                        shard_id: fetch_shard_request.shard_id().clone(),
+                    })?;
+            // Records are acknowledged by the indexer once they are published, then truncated:
+            // consumers cannot read them anymore.
+            if *fetch_shard_request.from_position_exclusive() < shard.truncation_position_inclusive
+            {
+                let message = format!(
+                    "records of shard `{queue_id}` up to position {} were already truncated",
+                    shard.truncation_position_inclusive
+                );
+                return Err(IngestV2Error::Internal(message));
+            }
+            shard.shard_status_rx.clone()
+        };
+        let mrecordlog = self.state.mrecordlog();
+        let (service_stream, _fetch_task_handle) = FetchStreamTask::spawn(
+            fetch_shard_request.into(),
+            mrecordlog,
+            shard_status_rx,
+            get_batch_num_bytes(),
+            self.wal_offload_opt.clone(),
         );
         Ok(service_stream)
     }
@@ -1058,6 +1556,13 @@ impl IngesterService for Ingester {
             .await
     }
 
+    async fn fetch_shard(
+        &mut self,
+        fetch_shard_request: FetchShardRequest,
+    ) -> IngestV2Result<ServiceStream<IngestV2Result<FetchMessage>>> {
+        self.fetch_shard_inner(fetch_shard_request).await
+    }
+
     async fn open_observation_stream(
         &mut self,
         open_observation_stream_request: OpenObservationStreamRequest,
@@ -1077,33 +1582,68 @@ impl IngesterService for Ingester {
         &mut self,
         request: RetainShardsRequest,
     ) -> IngestV2Result<RetainShardsResponse> {
-        let retain_queue_ids: HashSet<QueueId> = request
-            .retain_shards_for_sources
-            .into_iter()
-            .flat_map(|retain_shards_for_source: RetainShardsForSource| {
-                let index_uid = retain_shards_for_source.index_uid().clone();
-                retain_shards_for_source
-                    .shard_ids
-                    .into_iter()
-                    .map(move |shard_id| {
-                        queue_id(&index_uid, &retain_shards_for_source.source_id, &shard_id)
-                    })
-            })
-            .collect();
+        let incremental = request.incremental;
+        // For incremental requests, only the shards of the listed sources may be removed.
+        let mut listed_sources: HashSet<(IndexUid, SourceId)> = HashSet::new();
+        let mut retain_queue_ids: HashSet<QueueId> = HashSet::new();
+
+        for retain_shards_for_source in request.retain_shards_for_sources {
+            let index_uid = retain_shards_for_source.index_uid().clone();
+
+            for shard_id in &retain_shards_for_source.shard_ids {
+                retain_queue_ids.insert(queue_id(
+                    &index_uid,
+                    &retain_shards_for_source.source_id,
+                    shard_id,
+                ));
+            }
+            if incremental {
+                listed_sources.insert((index_uid, retain_shards_for_source.source_id));
+            }
+        }
         let mut state_guard =
             with_lock_metrics!(self.state.lock_fully(), "retain_shards", "write").await?;
         let remove_queue_ids: HashSet<QueueId> = state_guard
             .shards
             .keys()
-            .filter(move |shard_id| !retain_queue_ids.contains(*shard_id))
+            .filter(|queue_id| {
+                if retain_queue_ids.contains(*queue_id) {
+                    return false;
+                }
+                if !incremental {
+                    return true;
+                }
+                split_queue_id(queue_id).is_some_and(|(index_uid, source_id, _)| {
+                    listed_sources.contains(&(index_uid, source_id))
+                })
+            })
             .map(ToString::to_string)
             .collect();
         info!(queues=?remove_queue_ids, "removing queues");
         for queue_id in remove_queue_ids {
             state_guard.delete_shard(&queue_id).await;
         }
+        let mut retain_shards_response = RetainShardsResponse {
+            supports_incremental: true,
+            ..Default::default()
+        };
+        if incremental {
+            let digest = state_guard.shards.keys().fold(0u64, |digest, queue_id| {
+                digest.wrapping_add(queue_id_digest(queue_id))
+            });
+            if digest != request.expected_digest {
+                let source_digests = compute_source_digests(state_guard.shards.keys());
+                warn!(
+                    num_sources = source_digests.len(),
+                    "shards hosted by ingester do not match the shards expected by the control \
+                     plane"
+                );
+                retain_shards_response.digest_mismatch = true;
+                retain_shards_response.source_digests = source_digests;
+            }
+        }
         self.check_decommissioning_status(&mut state_guard);
-        Ok(RetainShardsResponse {})
+        Ok(retain_shards_response)
     }
 
     async fn truncate_shards(
@@ -1126,6 +1666,16 @@ impl IngesterService for Ingester {
     ) -> IngestV2Result<DecommissionResponse> {
         self.decommission_inner(decommission_request).await
     }
+
+    async fn ping(&mut self, _ping_request: PingRequest) -> IngestV2Result<PingResponse> {
+        // Pings must stay cheap and never wait on the state lock, so we read the status from the
+        // status channel instead.
+        let status = *self.state.status_rx.borrow();
+        let ping_response = PingResponse {
+            status: status as i32,
+        };
+        Ok(ping_response)
+    }
 }
 
 #[async_trait]
@@ -1143,12 +1693,16 @@ impl EventSubscriber<ShardPositionsUpdate> for WeakIngesterState {
         };
         let index_uid = shard_positions_update.source_uid.index_uid;
         let source_id = shard_positions_update.source_uid.source_id;
+        let now = Instant::now();
 
         for (shard_id, shard_position) in shard_positions_update.updated_shard_positions {
             let queue_id = queue_id(&index_uid, &source_id, &shard_id);
             if shard_position.is_eof() {
                 state_guard.delete_shard(&queue_id).await;
             } else if !shard_position.is_beginning() {
+                if let Some(shard) = state_guard.shards.get_mut(&queue_id) {
+                    shard.observe_publish(&shard_position, now);
+                }
                 state_guard.truncate_shard(&queue_id, &shard_position).await;
             }
         }
@@ -1199,7 +1753,37 @@ struct LocalPersistSubrequest {
     source_id: SourceId,
     shard_id: Option<quickwit_proto::types::ShardId>,
     doc_batch: quickwit_proto::ingest::DocBatchV2,
+    // Compression applied to the documents of the batch.
+    doc_compression: DocCompression,
+    // Size of the documents before compression.
+    batch_num_bytes: u64,
     expected_position_inclusive: Option<Position>,
+    idempotency_key_opt: Option<String>,
+}
+
+/// Looks up the batch persisted with `idempotency_key` in the shard `queue_id`, then in the other
+/// shards of the same source hosted by the ingester, which covers the replays that the router sent
+/// to a new shard after the original one was closed. Returns the ID of the shard and the position
+/// of the batch.
+fn find_persisted_batch(
+    shards: &HashMap<QueueId, IngesterShard>,
+    queue_id: &QueueId,
+    idempotency_key: &str,
+) -> Option<(ShardId, Position)> {
+    let source_prefix = &queue_id[..=queue_id.rfind('/')?];
+
+    let mut candidate_shards =
+        shards
+            .get_key_value(queue_id)
+            .into_iter()
+            .chain(shards.iter().filter(|(other_queue_id, _)| {
+                *other_queue_id != queue_id && other_queue_id.starts_with(source_prefix)
+            }));
+    candidate_shards.find_map(|(queue_id, shard)| {
+        let position_inclusive = shard.dedup_window.get(idempotency_key)?;
+        let (_, _, shard_id) = split_queue_id(queue_id)?;
+        Some((shard_id, position_inclusive.clone()))
+    })
 }
 
 #[cfg(test)]
@@ -1217,8 +1801,8 @@ mod tests {
     use quickwit_config::service::QuickwitService;
     use quickwit_proto::control_plane::{AdviseResetShardsResponse, MockControlPlaneService};
     use quickwit_proto::ingest::ingester::{
-        IngesterServiceGrpcServer, IngesterServiceGrpcServerAdapter, InitShardSubrequest,
-        PersistSubrequest, TruncateShardsSubrequest,
+        source_shards_digest, IngesterServiceGrpcServer, IngesterServiceGrpcServerAdapter,
+        InitShardSubrequest, PersistSubrequest, RetainShardsForSource, TruncateShardsSubrequest,
     };
     use quickwit_proto::ingest::{
         DocBatchV2, ShardIdPosition, ShardIdPositions, ShardIds, ShardPKey,
@@ -1245,6 +1829,7 @@ mod tests {
         rate_limiter_settings: RateLimiterSettings,
         replication_factor: usize,
         idle_shard_timeout: Duration,
+        doc_compression: DocCompression,
     }
 
     impl Default for IngesterForTest {
@@ -1264,6 +1849,7 @@ mod tests {
                 rate_limiter_settings: RateLimiterSettings::default(),
                 replication_factor: 1,
                 idle_shard_timeout: DEFAULT_IDLE_SHARD_TIMEOUT,
+                doc_compression: DocCompression::Unspecified,
             }
         }
     }
@@ -1307,6 +1893,11 @@ mod tests {
             self
         }
 
+        pub fn with_doc_compression(mut self, doc_compression: DocCompression) -> Self {
+            self.doc_compression = doc_compression;
+            self
+        }
+
         pub async fn build(self) -> (IngesterContext, Ingester) {
             static GOSSIP_ADVERTISE_PORT_SEQUENCE: AtomicU16 = AtomicU16::new(1u16);
 
@@ -1329,7 +1920,7 @@ mod tests {
             .await
             .unwrap();
 
-            let ingester = Ingester::try_new(
+            let mut ingester = Ingester::try_new(
                 cluster.clone(),
                 self.control_plane.clone(),
                 self.ingester_pool.clone(),
@@ -1339,9 +1930,12 @@ mod tests {
                 self.rate_limiter_settings,
                 self.replication_factor,
                 self.idle_shard_timeout,
+                self.doc_compression,
+                None,
             )
             .await
             .unwrap();
+            ingester.cluster_features = EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED);
 
             wait_for_ingester_status(ingester.clone(), IngesterStatus::Ready)
                 .await
@@ -1405,6 +1999,7 @@ mod tests {
         let records = [
             MRecord::new_doc("test-doc-foo").encode(),
             MRecord::new_doc("test-doc-bar").encode(),
+            MRecord::IdempotencyKey("test-key".to_string()).encode(),
         ]
         .into_iter();
 
@@ -1441,12 +2036,18 @@ mod tests {
         let solo_shard_02 = state_guard.shards.get(&queue_id_02).unwrap();
         solo_shard_02.assert_is_solo();
         solo_shard_02.assert_is_closed();
-        solo_shard_02.assert_replication_position(Position::offset(1u64));
+        solo_shard_02.assert_replication_position(Position::offset(2u64));
         solo_shard_02.assert_truncation_position(Position::offset(0u64));
+        assert_eq!(
+            solo_shard_02.dedup_window.get("test-key"),
+            Some(&Position::offset(2u64))
+        );
 
-        state_guard
-            .mrecordlog
-            .assert_records_eq(&queue_id_02, .., &[(1, "\0\0test-doc-bar")]);
+        state_guard.mrecordlog.assert_records_eq(
+            &queue_id_02,
+            ..,
+            &[(1, "\0\0test-doc-bar"), (2, "\0\u{4}test-key")],
+        );
 
         state_guard.rate_trackers.contains_key(&queue_id_02);
 
@@ -1607,6 +2208,7 @@ mod tests {
                     source_id: "test-source".to_string(),
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                    idempotency_key: None,
                 },
                 PersistSubrequest {
                     subrequest_id: 1,
@@ -1614,8 +2216,10 @@ mod tests {
                     source_id: "test-source".to_string(),
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-110", "test-doc-111"])),
+                    idempotency_key: None,
                 },
             ],
+            atomic: false,
         };
         let persist_response = ingester.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-ingester");
@@ -1675,32 +2279,13 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_ingester_persist_empty() {
-        let (ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+    async fn test_ingester_persist_compressed() {
+        let (ingester_ctx, mut ingester) = IngesterForTest::default()
+            .with_doc_compression(DocCompression::Zstd)
+            .build()
+            .await;
 
         let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
-        let persist_request = PersistRequest {
-            leader_id: ingester_ctx.node_id.to_string(),
-            commit_type: CommitTypeV2::Force as i32,
-            subrequests: Vec::new(),
-        };
-        let persist_response = ingester.persist(persist_request).await.unwrap();
-        assert_eq!(persist_response.leader_id, "test-ingester");
-        assert_eq!(persist_response.successes.len(), 0);
-        assert_eq!(persist_response.failures.len(), 0);
-
-        let persist_request = PersistRequest {
-            leader_id: "test-ingester".to_string(),
-            commit_type: CommitTypeV2::Force as i32,
-            subrequests: vec![PersistSubrequest {
-                subrequest_id: 0,
-                index_uid: Some(index_uid.clone()),
-                source_id: "test-source".to_string(),
-                shard_id: Some(ShardId::from(1)),
-                doc_batch: None,
-            }],
-        };
-
         let init_shards_request = InitShardsRequest {
             subrequests: vec![InitShardSubrequest {
                 subrequest_id: 0,
@@ -1716,8 +2301,300 @@ mod tests {
         };
         ingester.init_shards(init_shards_request).await.unwrap();
 
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-010", "test-doc-011"])),
+                idempotency_key: None,
+            }],
+            atomic: false,
+        };
         let persist_response = ingester.persist(persist_request).await.unwrap();
-        assert_eq!(persist_response.leader_id, "test-ingester");
+        assert_eq!(persist_response.successes.len(), 1);
+        assert_eq!(persist_response.failures.len(), 0);
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        let queue_id_01 = queue_id(&index_uid, "test-source", &ShardId::from(1));
+
+        let mrecords: Vec<MRecord> = state_guard
+            .mrecordlog
+            .range(&queue_id_01, ..)
+            .unwrap()
+            .map(|record| MRecord::decode(record.payload.as_ref()).unwrap())
+            .collect();
+        assert_eq!(
+            mrecords,
+            [
+                MRecord::Doc(Bytes::from_static(b"test-doc-010")),
+                MRecord::Doc(Bytes::from_static(b"test-doc-011")),
+                MRecord::Commit,
+            ]
+        );
+        drop(state_guard);
+
+        // The documents are left uncompressed until all the nodes of the cluster can decode them.
+        ingester.cluster_features = EnabledClusterFeatures::for_test([]);
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-012"])),
+                idempotency_key: None,
+            }],
+            atomic: false,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 1);
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        let record_types: Vec<u8> = state_guard
+            .mrecordlog
+            .range(&queue_id_01, ..)
+            .unwrap()
+            .map(|record| record.payload[1])
+            .collect();
+        // 3 = `DocZstd`, 1 = `Commit`, 0 = `Doc`.
+        assert_eq!(record_types, [3, 3, 1, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_ingester_persist_deduplicates_idempotency_keys() {
+        let (ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let init_shards_request = InitShardsRequest {
+            subrequests: vec![
+                InitShardSubrequest {
+                    subrequest_id: 0,
+                    shard: Some(Shard {
+                        index_uid: Some(index_uid.clone()),
+                        source_id: "test-source".to_string(),
+                        shard_id: Some(ShardId::from(1)),
+                        shard_state: ShardState::Open as i32,
+                        leader_id: ingester_ctx.node_id.to_string(),
+                        ..Default::default()
+                    }),
+                },
+                InitShardSubrequest {
+                    subrequest_id: 1,
+                    shard: Some(Shard {
+                        index_uid: Some(index_uid.clone()),
+                        source_id: "test-source".to_string(),
+                        shard_id: Some(ShardId::from(2)),
+                        shard_state: ShardState::Open as i32,
+                        leader_id: ingester_ctx.node_id.to_string(),
+                        ..Default::default()
+                    }),
+                },
+            ],
+        };
+        ingester.init_shards(init_shards_request).await.unwrap();
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-010", "test-doc-011"])),
+                idempotency_key: Some("test-key".to_string()),
+            }],
+            atomic: false,
+        };
+        let persist_response = ingester.persist(persist_request.clone()).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 1);
+        assert_eq!(
+            persist_response.successes[0].replication_position_inclusive,
+            Some(Position::offset(3u64))
+        );
+
+        // The replay is acknowledged at the same position without being appended again.
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 1);
+        assert_eq!(persist_response.failures.len(), 0);
+        assert_eq!(
+            persist_response.successes[0].replication_position_inclusive,
+            Some(Position::offset(3u64))
+        );
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-012"])),
+                idempotency_key: Some("other-test-key".to_string()),
+            }],
+            atomic: false,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 1);
+
+        let queue_id_01 = queue_id(&index_uid, "test-source", &ShardId::from(1));
+
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+        state_guard.shards.get_mut(&queue_id_01).unwrap().close();
+        drop(state_guard);
+
+        // A replay routed to another shard of the source after the original shard was closed is
+        // acknowledged with the original shard.
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(2)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-010", "test-doc-011"])),
+                idempotency_key: Some("test-key".to_string()),
+            }],
+            atomic: false,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 1);
+        assert_eq!(persist_response.failures.len(), 0);
+
+        let persist_success = &persist_response.successes[0];
+        assert_eq!(persist_success.shard_id(), ShardId::from(1));
+        assert_eq!(
+            persist_success.replication_position_inclusive,
+            Some(Position::offset(3u64))
+        );
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        let queue_id_02 = queue_id(&index_uid, "test-source", &ShardId::from(2));
+        assert!(state_guard
+            .mrecordlog
+            .range(&queue_id_02, ..)
+            .unwrap()
+            .next()
+            .is_none());
+
+        let mrecords: Vec<MRecord> = state_guard
+            .mrecordlog
+            .range(&queue_id_01, ..)
+            .unwrap()
+            .map(|record| MRecord::decode(record.payload.as_ref()).unwrap())
+            .collect();
+        assert_eq!(
+            mrecords,
+            [
+                MRecord::Doc(Bytes::from_static(b"test-doc-010")),
+                MRecord::Doc(Bytes::from_static(b"test-doc-011")),
+                MRecord::Commit,
+                MRecord::IdempotencyKey("test-key".to_string()),
+                MRecord::Doc(Bytes::from_static(b"test-doc-012")),
+                MRecord::Commit,
+                MRecord::IdempotencyKey("other-test-key".to_string()),
+            ]
+        );
+        drop(state_guard);
+
+        // The idempotency keys are ignored until all the nodes of the cluster can decode them.
+        ingester.cluster_features = EnabledClusterFeatures::for_test([]);
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(2)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-020"])),
+                idempotency_key: Some("legacy-test-key".to_string()),
+            }],
+            atomic: false,
+        };
+        ingester.persist(persist_request.clone()).await.unwrap();
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 1);
+        assert_eq!(
+            persist_response.successes[0].replication_position_inclusive,
+            Some(Position::offset(3u64))
+        );
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        let mrecords: Vec<MRecord> = state_guard
+            .mrecordlog
+            .range(&queue_id_02, ..)
+            .unwrap()
+            .map(|record| MRecord::decode(record.payload.as_ref()).unwrap())
+            .collect();
+        assert_eq!(
+            mrecords,
+            [
+                MRecord::Doc(Bytes::from_static(b"test-doc-020")),
+                MRecord::Commit,
+                MRecord::Doc(Bytes::from_static(b"test-doc-020")),
+                MRecord::Commit,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingester_persist_empty() {
+        let (ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: Vec::new(),
+            atomic: false,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.leader_id, "test-ingester");
+        assert_eq!(persist_response.successes.len(), 0);
+        assert_eq!(persist_response.failures.len(), 0);
+
+        let persist_request = PersistRequest {
+            leader_id: "test-ingester".to_string(),
+            commit_type: CommitTypeV2::Force as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                doc_batch: None,
+                idempotency_key: None,
+            }],
+            atomic: false,
+        };
+
+        let init_shards_request = InitShardsRequest {
+            subrequests: vec![InitShardSubrequest {
+                subrequest_id: 0,
+                shard: Some(Shard {
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    shard_state: ShardState::Open as i32,
+                    leader_id: ingester_ctx.node_id.to_string(),
+                    ..Default::default()
+                }),
+            }],
+        };
+        ingester.init_shards(init_shards_request).await.unwrap();
+
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.leader_id, "test-ingester");
         assert_eq!(persist_response.successes.len(), 1);
         assert_eq!(persist_response.failures.len(), 0);
 
@@ -1779,7 +2656,9 @@ mod tests {
                 source_id: "test-source".to_string(),
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
+                idempotency_key: None,
             }],
+            atomic: false,
         };
         let persist_response = ingester.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-ingester");
@@ -1832,7 +2711,9 @@ mod tests {
                 source_id: "test-source".to_string(),
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
+                idempotency_key: None,
             }],
+            atomic: false,
         };
         let persist_response = ingester.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-ingester");
@@ -1917,6 +2798,7 @@ mod tests {
                     source_id: "test-source".to_string(),
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                    idempotency_key: None,
                 },
                 PersistSubrequest {
                     subrequest_id: 1,
@@ -1924,8 +2806,10 @@ mod tests {
                     source_id: "test-source".to_string(),
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-110", "test-doc-111"])),
+                    idempotency_key: None,
                 },
             ],
+            atomic: false,
         };
         let persist_response = leader.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-leader");
@@ -2109,6 +2993,7 @@ mod tests {
                     source_id: "test-source".to_string(),
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                    idempotency_key: None,
                 },
                 PersistSubrequest {
                     subrequest_id: 1,
@@ -2116,8 +3001,10 @@ mod tests {
                     source_id: "test-source".to_string(),
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-110", "test-doc-111"])),
+                    idempotency_key: None,
                 },
             ],
+            atomic: false,
         };
         let persist_response = leader.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-leader");
@@ -2214,38 +3101,364 @@ mod tests {
             .await
             .unwrap()
             .shards
-            .insert(queue_id_01.clone(), solo_shard);
+            .insert(queue_id_01.clone(), solo_shard);
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Auto as i32,
+            subrequests: vec![PersistSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                idempotency_key: None,
+            }],
+            atomic: false,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.leader_id, "test-ingester");
+        assert_eq!(persist_response.successes.len(), 0);
+        assert_eq!(persist_response.failures.len(), 1);
+
+        let persist_failure = &persist_response.failures[0];
+        assert_eq!(persist_failure.subrequest_id, 0);
+        assert_eq!(persist_failure.index_uid(), &index_uid);
+        assert_eq!(persist_failure.source_id, "test-source");
+        assert_eq!(persist_failure.shard_id(), ShardId::from(1));
+        assert_eq!(persist_failure.reason(), PersistFailureReason::ShardClosed);
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        assert_eq!(state_guard.shards.len(), 1);
+
+        let solo_shard_01 = state_guard.shards.get(&queue_id_01).unwrap();
+        solo_shard_01.assert_is_solo();
+        solo_shard_01.assert_is_closed();
+        solo_shard_01.assert_replication_position(Position::Beginning);
+    }
+
+    #[tokio::test]
+    async fn test_ingester_persist_atomic() {
+        let (ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+
+        let index_uid_0: IndexUid = IndexUid::for_test("test-index-0", 0);
+        let index_uid_1: IndexUid = IndexUid::for_test("test-index-1", 0);
+
+        for (index_uid, shard_id) in [(&index_uid_0, 1), (&index_uid_1, 2)] {
+            let primary_shard = Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                shard_state: ShardState::Open as i32,
+                leader_id: ingester_ctx.node_id.to_string(),
+                ..Default::default()
+            };
+            ingester
+                .init_primary_shard(
+                    &mut state_guard.inner,
+                    &mut state_guard.mrecordlog,
+                    primary_shard,
+                    Instant::now(),
+                )
+                .await
+                .unwrap();
+        }
+        let queue_id_01 = queue_id(&index_uid_0, "test-source", &ShardId::from(1));
+        let queue_id_11 = queue_id(&index_uid_1, "test-source", &ShardId::from(1));
+        let queue_id_12 = queue_id(&index_uid_1, "test-source", &ShardId::from(2));
+
+        let solo_shard = IngesterShard::new_solo(
+            ShardState::Closed,
+            Position::Beginning,
+            Position::Beginning,
+            Instant::now(),
+        );
+        state_guard.shards.insert(queue_id_11.clone(), solo_shard);
+        drop(state_guard);
+
+        let persist_subrequest =
+            |subrequest_id: u32, index_uid: &IndexUid, shard_id: u64, doc: &'static str| {
+                PersistSubrequest {
+                    subrequest_id,
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(shard_id)),
+                    doc_batch: Some(DocBatchV2::for_test([doc])),
+                    idempotency_key: None,
+                }
+            };
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Auto as i32,
+            subrequests: vec![
+                persist_subrequest(0, &index_uid_0, 1, "test-doc-010"),
+                persist_subrequest(1, &index_uid_1, 1, "test-doc-110"),
+            ],
+            atomic: true,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 0);
+        assert_eq!(persist_response.failures.len(), 2);
+
+        let mut persist_failures = persist_response.failures;
+        persist_failures.sort_by_key(|persist_failure| persist_failure.subrequest_id);
+
+        assert_eq!(persist_failures[0].subrequest_id, 0);
+        assert_eq!(persist_failures[0].index_uid(), &index_uid_0);
+        assert_eq!(persist_failures[0].reason(), PersistFailureReason::Aborted);
+
+        assert_eq!(persist_failures[1].subrequest_id, 1);
+        assert_eq!(persist_failures[1].index_uid(), &index_uid_1);
+        assert_eq!(
+            persist_failures[1].reason(),
+            PersistFailureReason::ShardClosed
+        );
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        state_guard
+            .shards
+            .get(&queue_id_01)
+            .unwrap()
+            .assert_replication_position(Position::Beginning);
+        state_guard
+            .mrecordlog
+            .assert_records_eq(&queue_id_01, .., &[]);
+        drop(state_guard);
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Auto as i32,
+            subrequests: vec![
+                persist_subrequest(0, &index_uid_0, 1, "test-doc-010"),
+                persist_subrequest(1, &index_uid_1, 2, "test-doc-120"),
+            ],
+            atomic: true,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 2);
+        assert_eq!(persist_response.failures.len(), 0);
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        state_guard
+            .mrecordlog
+            .assert_records_eq(&queue_id_01, .., &[(0, "\0\0test-doc-010")]);
+        state_guard
+            .mrecordlog
+            .assert_records_eq(&queue_id_12, .., &[(0, "\0\0test-doc-120")]);
+    }
+
+    #[tokio::test]
+    async fn test_ingester_persist_atomic_rolls_back_appended_subrequests() {
+        let (ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+
+        for shard_id in [1u64, 2] {
+            let primary_shard = Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                shard_state: ShardState::Open as i32,
+                leader_id: ingester_ctx.node_id.to_string(),
+                ..Default::default()
+            };
+            ingester
+                .init_primary_shard(
+                    &mut state_guard.inner,
+                    &mut state_guard.mrecordlog,
+                    primary_shard,
+                    Instant::now(),
+                )
+                .await
+                .unwrap();
+        }
+        let queue_id_01 = queue_id(&index_uid, "test-source", &ShardId::from(1));
+        let queue_id_02 = queue_id(&index_uid, "test-source", &ShardId::from(2));
+
+        // Appending to the second shard fails after the first subrequest is appended.
+        state_guard
+            .mrecordlog
+            .delete_queue(&queue_id_02)
+            .await
+            .unwrap();
+        drop(state_guard);
+
+        let persist_request = PersistRequest {
+            leader_id: ingester_ctx.node_id.to_string(),
+            commit_type: CommitTypeV2::Auto as i32,
+            subrequests: vec![
+                PersistSubrequest {
+                    subrequest_id: 0,
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                    idempotency_key: Some("test-key".to_string()),
+                },
+                PersistSubrequest {
+                    subrequest_id: 1,
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(2)),
+                    doc_batch: Some(DocBatchV2::for_test(["test-doc-020"])),
+                    idempotency_key: None,
+                },
+            ],
+            atomic: true,
+        };
+        let persist_response = ingester.persist(persist_request).await.unwrap();
+        assert_eq!(persist_response.successes.len(), 0);
+        assert_eq!(persist_response.failures.len(), 2);
+
+        let mut persist_failures = persist_response.failures;
+        persist_failures.sort_by_key(|persist_failure| persist_failure.subrequest_id);
+
+        assert_eq!(persist_failures[0].subrequest_id, 0);
+        assert_eq!(persist_failures[0].reason(), PersistFailureReason::Aborted);
+
+        assert_eq!(persist_failures[1].subrequest_id, 1);
+        assert_eq!(
+            persist_failures[1].reason(),
+            PersistFailureReason::ShardNotFound
+        );
+
+        let state_guard = ingester.state.lock_fully().await.unwrap();
+        let solo_shard_01 = state_guard.shards.get(&queue_id_01).unwrap();
+        solo_shard_01.assert_is_closed();
+        solo_shard_01.assert_replication_position(Position::Beginning);
+        assert!(solo_shard_01.dedup_window.get("test-key").is_none());
+
+        // The rolled back records remain in the WAL, followed by the rollback record.
+        state_guard.mrecordlog.assert_records_eq(
+            &queue_id_01,
+            ..,
+            &[
+                (0, "\0\0test-doc-010"),
+                (1, "\0\u{4}test-key"),
+                (2, "\0\u{5}"),
+            ],
+        );
+        assert!(!state_guard.shards.contains_key(&queue_id_02));
+    }
+
+    #[tokio::test]
+    async fn test_ingester_persist_atomic_rolls_back_replicas() {
+        let (leader_ctx, mut leader) = IngesterForTest::default()
+            .with_node_id("test-leader")
+            .with_replication()
+            .build()
+            .await;
+
+        let (follower_ctx, follower) = IngesterForTest::default()
+            .with_node_id("test-follower")
+            .with_ingester_pool(&leader_ctx.ingester_pool)
+            .with_replication()
+            .build()
+            .await;
+
+        leader_ctx.ingester_pool.insert(
+            follower_ctx.node_id.clone(),
+            IngesterServiceClient::new(follower.clone()),
+        );
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+
+        let init_shards_request = InitShardsRequest {
+            subrequests: [1u64, 2]
+                .into_iter()
+                .map(|shard_id| InitShardSubrequest {
+                    subrequest_id: shard_id as u32,
+                    shard: Some(Shard {
+                        index_uid: Some(index_uid.clone()),
+                        source_id: "test-source".to_string(),
+                        shard_id: Some(ShardId::from(shard_id)),
+                        shard_state: ShardState::Open as i32,
+                        leader_id: leader_ctx.node_id.to_string(),
+                        follower_id: Some(follower_ctx.node_id.to_string()),
+                        ..Default::default()
+                    }),
+                })
+                .collect(),
+        };
+        leader.init_shards(init_shards_request).await.unwrap();
+
+        let queue_id_01 = queue_id(&index_uid, "test-source", &ShardId::from(1));
+        let queue_id_02 = queue_id(&index_uid, "test-source", &ShardId::from(2));
+
+        // The replication of the second subrequest fails.
+        follower
+            .state
+            .lock_fully()
+            .await
+            .unwrap()
+            .shards
+            .get_mut(&queue_id_02)
+            .unwrap()
+            .close();
 
         let persist_request = PersistRequest {
-            leader_id: ingester_ctx.node_id.to_string(),
+            leader_id: leader_ctx.node_id.to_string(),
             commit_type: CommitTypeV2::Auto as i32,
-            subrequests: vec![PersistSubrequest {
-                subrequest_id: 0,
-                index_uid: Some(index_uid.clone()),
-                source_id: "test-source".to_string(),
-                shard_id: Some(ShardId::from(1)),
-                doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
-            }],
+            subrequests: vec![
+                PersistSubrequest {
+                    subrequest_id: 0,
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                    idempotency_key: None,
+                },
+                PersistSubrequest {
+                    subrequest_id: 1,
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(2)),
+                    doc_batch: Some(DocBatchV2::for_test(["test-doc-020"])),
+                    idempotency_key: None,
+                },
+            ],
+            atomic: true,
         };
-        let persist_response = ingester.persist(persist_request).await.unwrap();
-        assert_eq!(persist_response.leader_id, "test-ingester");
+        let persist_response = leader.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.successes.len(), 0);
-        assert_eq!(persist_response.failures.len(), 1);
+        assert_eq!(persist_response.failures.len(), 2);
 
-        let persist_failure = &persist_response.failures[0];
-        assert_eq!(persist_failure.subrequest_id, 0);
-        assert_eq!(persist_failure.index_uid(), &index_uid);
-        assert_eq!(persist_failure.source_id, "test-source");
-        assert_eq!(persist_failure.shard_id(), ShardId::from(1));
-        assert_eq!(persist_failure.reason(), PersistFailureReason::ShardClosed);
+        let mut persist_failures = persist_response.failures;
+        persist_failures.sort_by_key(|persist_failure| persist_failure.subrequest_id);
 
-        let state_guard = ingester.state.lock_fully().await.unwrap();
-        assert_eq!(state_guard.shards.len(), 1);
+        assert_eq!(persist_failures[0].subrequest_id, 0);
+        assert_eq!(persist_failures[0].reason(), PersistFailureReason::Aborted);
 
-        let solo_shard_01 = state_guard.shards.get(&queue_id_01).unwrap();
-        solo_shard_01.assert_is_solo();
-        solo_shard_01.assert_is_closed();
-        solo_shard_01.assert_replication_position(Position::Beginning);
+        assert_eq!(persist_failures[1].subrequest_id, 1);
+        assert_eq!(
+            persist_failures[1].reason(),
+            PersistFailureReason::ShardClosed
+        );
+
+        let leader_state_guard = leader.state.lock_fully().await.unwrap();
+        let primary_shard_01 = leader_state_guard.shards.get(&queue_id_01).unwrap();
+        primary_shard_01.assert_is_closed();
+        primary_shard_01.assert_replication_position(Position::Beginning);
+
+        leader_state_guard
+            .mrecordlog
+            .assert_records_eq(&queue_id_01, .., &[]);
+        drop(leader_state_guard);
+
+        let follower_state_guard = follower.state.lock_fully().await.unwrap();
+        let replica_shard_01 = follower_state_guard.shards.get(&queue_id_01).unwrap();
+        replica_shard_01.assert_is_closed();
+        replica_shard_01.assert_replication_position(Position::Beginning);
+
+        follower_state_guard.mrecordlog.assert_records_eq(
+            &queue_id_01,
+            ..,
+            &[(0, "\0\0test-doc-010"), (1, "\0\u{5}")],
+        );
     }
 
     #[tokio::test]
@@ -2291,7 +3504,9 @@ mod tests {
                 source_id: "test-source".to_string(),
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                idempotency_key: None,
             }],
+            atomic: false,
         };
         let persist_response = ingester.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-ingester");
@@ -2359,7 +3574,9 @@ mod tests {
                 source_id: "test-source".to_string(),
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-010"])),
+                idempotency_key: None,
             }],
+            atomic: false,
         };
         let persist_response = ingester.persist(persist_request).await.unwrap();
         assert_eq!(persist_response.leader_id, "test-ingester");
@@ -2435,6 +3652,7 @@ mod tests {
             source_id: "test-source".to_string(),
             shard_id: Some(ShardId::from(1337)),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let error = ingester
             .open_fetch_stream(open_fetch_stream_request)
@@ -2481,6 +3699,7 @@ mod tests {
             source_id: "test-source".to_string(),
             shard_id: Some(ShardId::from(1)),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let mut fetch_stream = ingester
             .open_fetch_stream(open_fetch_stream_request)
@@ -2541,6 +3760,96 @@ mod tests {
         assert_eq!(mrecord_batch.mrecord_lengths, [14]);
     }
 
+    #[tokio::test]
+    async fn test_ingester_fetch_shard() {
+        let (_ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let shard = Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: "test-source".to_string(),
+            shard_id: Some(ShardId::from(1)),
+            shard_state: ShardState::Open as i32,
+            ..Default::default()
+        };
+        let queue_id = queue_id(&index_uid, "test-source", &ShardId::from(1));
+
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+
+        ingester
+            .init_primary_shard(
+                &mut state_guard.inner,
+                &mut state_guard.mrecordlog,
+                shard,
+                Instant::now(),
+            )
+            .await
+            .unwrap();
+
+        let records = [
+            MRecord::new_doc("test-doc-foo").encode(),
+            MRecord::new_doc("test-doc-bar").encode(),
+            MRecord::new_doc("test-doc-baz").encode(),
+        ]
+        .into_iter();
+
+        state_guard
+            .mrecordlog
+            .append_records(&queue_id, None, records)
+            .await
+            .unwrap();
+
+        drop(state_guard);
+
+        let fetch_shard_request = FetchShardRequest {
+            client_id: "test-consumer".to_string(),
+            index_uid: Some(index_uid.clone()),
+            source_id: "test-source".to_string(),
+            shard_id: Some(ShardId::from(1)),
+            from_position_exclusive: Some(Position::offset(0u64)),
+            to_position_inclusive: Some(Position::offset(1u64)),
+        };
+        let mut fetch_stream = ingester
+            .fetch_shard(fetch_shard_request.clone())
+            .await
+            .unwrap();
+
+        let fetch_response = fetch_stream.next().await.unwrap().unwrap();
+        let fetch_payload = into_fetch_payload(fetch_response);
+
+        assert_eq!(
+            fetch_payload.from_position_exclusive(),
+            Position::offset(0u64)
+        );
+        assert_eq!(
+            fetch_payload.to_position_inclusive(),
+            Position::offset(1u64)
+        );
+
+        let mrecord_batch = fetch_payload.mrecord_batch.unwrap();
+        assert_eq!(
+            mrecord_batch.mrecord_buffer,
+            Bytes::from_static(b"\0\0test-doc-bar")
+        );
+        assert_eq!(mrecord_batch.mrecord_lengths, [14]);
+
+        // The stream ends once the requested position is reached.
+        assert!(fetch_stream.next().await.is_none());
+
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+        state_guard
+            .shards
+            .get_mut(&queue_id)
+            .unwrap()
+            .truncation_position_inclusive = Position::offset(1u64);
+        drop(state_guard);
+
+        let error = ingester.fetch_shard(fetch_shard_request).await.unwrap_err();
+        assert!(
+            matches!(error, IngestV2Error::Internal(message) if message.contains("already truncated"))
+        );
+    }
+
     #[tokio::test]
     async fn test_ingester_truncate_shards() {
         let (ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
@@ -2732,6 +4041,7 @@ mod tests {
                             publish_position_inclusive: Some(Position::offset(1u64)),
                         }],
                     }],
+                    continuation_token: None,
                 };
                 Ok(response)
             });
@@ -2805,6 +4115,118 @@ mod tests {
         shard_02.assert_truncation_position(Position::offset(1u64));
     }
 
+    #[tokio::test]
+    async fn test_ingester_reset_shards_with_continuation_token() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_advise_reset_shards()
+            .once()
+            .returning(|_| Ok(AdviseResetShardsResponse::default()));
+
+        mock_control_plane
+            .expect_advise_reset_shards()
+            .once()
+            .returning(|request| {
+                assert!(request.continuation_token.is_none());
+
+                let response = AdviseResetShardsResponse {
+                    shards_to_delete: vec![ShardIds {
+                        index_uid: Some(IndexUid::for_test("test-index", 0)),
+                        source_id: "test-source".to_string(),
+                        shard_ids: vec![ShardId::from(1)],
+                    }],
+                    shards_to_truncate: Vec::new(),
+                    continuation_token: Some("1".to_string()),
+                };
+                Ok(response)
+            });
+        mock_control_plane
+            .expect_advise_reset_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.continuation_token.as_deref(), Some("1"));
+
+                let response = AdviseResetShardsResponse {
+                    shards_to_delete: vec![ShardIds {
+                        index_uid: Some(IndexUid::for_test("test-index", 0)),
+                        source_id: "test-source".to_string(),
+                        shard_ids: vec![ShardId::from(2)],
+                    }],
+                    shards_to_truncate: Vec::new(),
+                    continuation_token: None,
+                };
+                Ok(response)
+            });
+        let control_plane = ControlPlaneServiceClient::from_mock(mock_control_plane);
+
+        let (_ingester_ctx, mut ingester) = IngesterForTest::default()
+            .with_control_plane(control_plane)
+            .build()
+            .await;
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+        let now = Instant::now();
+
+        for shard_id in [1u64, 2] {
+            let shard = Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            };
+            ingester
+                .init_primary_shard(
+                    &mut state_guard.inner,
+                    &mut state_guard.mrecordlog,
+                    shard,
+                    now,
+                )
+                .await
+                .unwrap();
+        }
+        drop(state_guard);
+
+        ingester.reset_shards().await;
+
+        let state_guard = ingester.state.lock_partially().await.unwrap();
+        assert!(state_guard.shards.is_empty());
+    }
+
+    #[test]
+    fn test_batch_shard_ids() {
+        let batches = batch_shard_ids(Vec::new(), 3);
+        assert_eq!(batches.len(), 1);
+        assert!(batches[0].is_empty());
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let shard_ids = vec![
+            ShardIds {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source-0".to_string(),
+                shard_ids: (0..4u64).map(ShardId::from).collect(),
+            },
+            ShardIds {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-source-1".to_string(),
+                shard_ids: (4..6u64).map(ShardId::from).collect(),
+            },
+        ];
+        let batches = batch_shard_ids(shard_ids, 3);
+        assert_eq!(batches.len(), 2);
+
+        assert_eq!(batches[0].len(), 1);
+        assert_eq!(batches[0][0].source_id, "test-source-0");
+        assert_eq!(batches[0][0].shard_ids.len(), 3);
+
+        assert_eq!(batches[1].len(), 2);
+        assert_eq!(batches[1][0].source_id, "test-source-0");
+        assert_eq!(batches[1][0].shard_ids, [ShardId::from(3)]);
+        assert_eq!(batches[1][1].source_id, "test-source-1");
+        assert_eq!(batches[1][1].shard_ids.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_ingester_retain_shards() {
         let (_ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
@@ -2866,8 +4288,11 @@ mod tests {
                 source_id: "test-source".to_string(),
                 shard_ids: vec![ShardId::from(17u64)],
             }],
+            ..Default::default()
         };
-        ingester.retain_shards(retain_shards_request).await.unwrap();
+        let retain_shards_response = ingester.retain_shards(retain_shards_request).await.unwrap();
+        assert!(retain_shards_response.supports_incremental);
+        assert!(!retain_shards_response.digest_mismatch);
 
         {
             let state_guard = ingester.state.lock_fully().await.unwrap();
@@ -2876,6 +4301,78 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_ingester_retain_shards_incremental() {
+        let (_ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+        let now = Instant::now();
+
+        for (source_id, shard_id) in [("source-0", 1), ("source-0", 2), ("source-1", 3)] {
+            let shard = Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_id.to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            };
+            ingester
+                .init_primary_shard(
+                    &mut state_guard.inner,
+                    &mut state_guard.mrecordlog,
+                    shard,
+                    now,
+                )
+                .await
+                .unwrap();
+        }
+        drop(state_guard);
+
+        let source_0_digest = source_shards_digest(&index_uid, "source-0", &[ShardId::from(1)]);
+        let source_1_digest = source_shards_digest(&index_uid, "source-1", &[ShardId::from(3)]);
+
+        // Only `source-0` is listed: the shards of `source-1` are left untouched.
+        let retain_shards_request = RetainShardsRequest {
+            retain_shards_for_sources: vec![RetainShardsForSource {
+                index_uid: Some(index_uid.clone()),
+                source_id: "source-0".to_string(),
+                shard_ids: vec![ShardId::from(1)],
+            }],
+            incremental: true,
+            expected_digest: source_0_digest.wrapping_add(source_1_digest),
+        };
+        let retain_shards_response = ingester.retain_shards(retain_shards_request).await.unwrap();
+        assert!(!retain_shards_response.digest_mismatch);
+        assert!(retain_shards_response.source_digests.is_empty());
+
+        {
+            let state_guard = ingester.state.lock_fully().await.unwrap();
+            assert_eq!(state_guard.shards.len(), 2);
+            assert!(state_guard.shards.contains_key(&queue_id(
+                &index_uid,
+                "source-1",
+                &ShardId::from(3)
+            )));
+        }
+        // The control plane does not expect `source-1` anymore but did not list it.
+        let retain_shards_request = RetainShardsRequest {
+            retain_shards_for_sources: Vec::new(),
+            incremental: true,
+            expected_digest: source_0_digest,
+        };
+        let retain_shards_response = ingester.retain_shards(retain_shards_request).await.unwrap();
+        assert!(retain_shards_response.digest_mismatch);
+
+        let mut source_digests = retain_shards_response.source_digests;
+        source_digests.sort_by(|left, right| left.source_id.cmp(&right.source_id));
+        assert_eq!(source_digests.len(), 2);
+        assert_eq!(source_digests[0].source_id, "source-0");
+        assert_eq!(source_digests[0].digest, source_0_digest);
+        assert_eq!(source_digests[1].source_id, "source-1");
+        assert_eq!(source_digests[1].digest, source_1_digest);
+    }
+
     #[tokio::test]
     async fn test_ingester_close_shards() {
         let (_ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
@@ -2909,6 +4406,7 @@ mod tests {
             source_id: "test-source".to_string(),
             shard_id: Some(ShardId::from(1)),
             from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
         };
         let mut fetch_stream = ingester
             .open_fetch_stream(open_fetch_stream_request)
@@ -2986,6 +4484,21 @@ mod tests {
         assert!(observation_opt.is_none());
     }
 
+    #[tokio::test]
+    async fn test_ingester_ping() {
+        let (_ingester_ctx, mut ingester) = IngesterForTest::default().build().await;
+
+        let ping_response = ingester.ping(PingRequest {}).await.unwrap();
+        assert_eq!(ping_response.status(), IngesterStatus::Ready);
+
+        let mut state_guard = ingester.state.lock_fully().await.unwrap();
+        state_guard.set_status(IngesterStatus::Decommissioning);
+        drop(state_guard);
+
+        let ping_response = ingester.ping(PingRequest {}).await.unwrap();
+        assert_eq!(ping_response.status(), IngesterStatus::Decommissioning);
+    }
+
     #[tokio::test]
     async fn test_check_decommissioning_status() {
         let (_ingester_ctx, ingester) = IngesterForTest::default().build().await;
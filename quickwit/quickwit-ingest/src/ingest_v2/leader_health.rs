@@ -0,0 +1,202 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use quickwit_proto::types::NodeId;
+use tokio::time::Instant;
+
+/// Smoothing factor of the exponentially weighted moving averages. Higher values discount older
+/// samples faster.
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Persist latency below which an ingester is considered healthy.
+const REFERENCE_PERSIST_LATENCY: Duration = Duration::from_millis(100);
+
+/// Weight floor, so that degraded ingesters still receive a trickle of requests, which lets the
+/// router notice when they recover.
+const MIN_WEIGHT: f64 = 0.05;
+
+/// Duration past which the stats of an ingester that has not been solicited are discarded.
+const LEADER_HEALTH_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Ewma {
+    value_opt: Option<f64>,
+}
+
+impl Ewma {
+    fn update(&mut self, sample: f64) {
+        let value = match self.value_opt {
+            Some(value) => EWMA_ALPHA * sample + (1.0 - EWMA_ALPHA) * value,
+            None => sample,
+        };
+        self.value_opt = Some(value);
+    }
+
+    fn value_or(&self, default: f64) -> f64 {
+        self.value_opt.unwrap_or(default)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LeaderHealth {
+    /// EWMA of the latency of the persist requests, in seconds.
+    persist_latency_secs: Ewma,
+    /// EWMA of the fraction of persist subrequests rejected by the ingester because it is rate
+    /// limited, out of resources, or unreachable.
+    rejection_rate: Ewma,
+    updated_at: Instant,
+}
+
+impl LeaderHealth {
+    fn new(now: Instant) -> Self {
+        Self {
+            persist_latency_secs: Ewma::default(),
+            rejection_rate: Ewma::default(),
+            updated_at: now,
+        }
+    }
+
+    fn weight(&self) -> f64 {
+        let reference_latency_secs = REFERENCE_PERSIST_LATENCY.as_secs_f64();
+        let latency_secs = self.persist_latency_secs.value_or(0.0);
+        let latency_factor = reference_latency_secs / latency_secs.max(reference_latency_secs);
+        let availability = 1.0 - self.rejection_rate.value_or(0.0).clamp(0.0, 1.0);
+        (latency_factor * availability).max(MIN_WEIGHT)
+    }
+}
+
+/// Tracks the recent persist latency and rejection rate of each ingester the router sends persist
+/// requests to. The router weighs shards by the health of their leader, steering traffic away
+/// from briefly degraded ingesters without the involvement of the control plane.
+#[derive(Debug, Default)]
+pub(super) struct LeaderHealthTable {
+    leaders: HashMap<NodeId, LeaderHealth>,
+}
+
+impl LeaderHealthTable {
+    /// Records the outcome of a persist request that reached the ingester.
+    pub fn record_persist_response(
+        &mut self,
+        leader_id: &NodeId,
+        persist_latency: Duration,
+        num_subrequests: usize,
+        num_rejected_subrequests: usize,
+    ) {
+        let rejection_rate = if num_subrequests == 0 {
+            0.0
+        } else {
+            num_rejected_subrequests as f64 / num_subrequests as f64
+        };
+        self.record(leader_id, persist_latency, rejection_rate);
+    }
+
+    /// Records a persist request that failed altogether, for instance because it timed out or the
+    /// ingester was unreachable.
+    pub fn record_persist_error(&mut self, leader_id: &NodeId, persist_latency: Duration) {
+        self.record(leader_id, persist_latency, 1.0);
+    }
+
+    fn record(&mut self, leader_id: &NodeId, persist_latency: Duration, rejection_rate: f64) {
+        let now = Instant::now();
+        let leader_health = self
+            .leaders
+            .entry(leader_id.clone())
+            .or_insert_with(|| LeaderHealth::new(now));
+
+        if now.duration_since(leader_health.updated_at) > LEADER_HEALTH_TTL {
+            *leader_health = LeaderHealth::new(now);
+        }
+        leader_health
+            .persist_latency_secs
+            .update(persist_latency.as_secs_f64());
+        leader_health.rejection_rate.update(rejection_rate);
+        leader_health.updated_at = now;
+    }
+
+    /// Returns the weight of the ingester, between `MIN_WEIGHT` and `1.0`. Ingesters without
+    /// recent stats are considered healthy.
+    pub fn weight(&self, leader_id: &NodeId) -> f64 {
+        let Some(leader_health) = self.leaders.get(leader_id) else {
+            return 1.0;
+        };
+        if leader_health.updated_at.elapsed() > LEADER_HEALTH_TTL {
+            return 1.0;
+        }
+        leader_health.weight()
+    }
+
+    /// Discards the stats of the ingesters that have not been solicited recently.
+    pub fn gc(&mut self) {
+        self.leaders
+            .retain(|_, leader_health| leader_health.updated_at.elapsed() <= LEADER_HEALTH_TTL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ewma() {
+        let mut ewma = Ewma::default();
+        assert_eq!(ewma.value_or(1.0), 1.0);
+
+        ewma.update(10.0);
+        assert_eq!(ewma.value_or(1.0), 10.0);
+
+        ewma.update(0.0);
+        assert!((ewma.value_or(1.0) - 7.0).abs() < 1e-9);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_leader_health_table() {
+        let mut leader_health_table = LeaderHealthTable::default();
+        let leader_id_0: NodeId = "test-ingester-0".into();
+        let leader_id_1: NodeId = "test-ingester-1".into();
+        let leader_id_2: NodeId = "test-ingester-2".into();
+
+        assert_eq!(leader_health_table.weight(&leader_id_0), 1.0);
+
+        leader_health_table.record_persist_response(&leader_id_0, Duration::from_millis(10), 4, 0);
+        assert_eq!(leader_health_table.weight(&leader_id_0), 1.0);
+
+        leader_health_table.record_persist_response(&leader_id_1, Duration::from_millis(400), 4, 0);
+        assert!((leader_health_table.weight(&leader_id_1) - 0.25).abs() < 1e-9);
+
+        leader_health_table.record_persist_response(&leader_id_2, Duration::from_millis(10), 4, 2);
+        assert!((leader_health_table.weight(&leader_id_2) - 0.5).abs() < 1e-9);
+
+        leader_health_table.record_persist_error(&leader_id_2, Duration::from_millis(10));
+        assert!(leader_health_table.weight(&leader_id_2) < 0.5);
+
+        for _ in 0..10 {
+            leader_health_table.record_persist_error(&leader_id_2, Duration::from_secs(5));
+        }
+        assert_eq!(leader_health_table.weight(&leader_id_2), MIN_WEIGHT);
+
+        tokio::time::advance(LEADER_HEALTH_TTL + Duration::from_secs(1)).await;
+        assert_eq!(leader_health_table.weight(&leader_id_2), 1.0);
+
+        leader_health_table.gc();
+        assert!(leader_health_table.leaders.is_empty());
+    }
+}
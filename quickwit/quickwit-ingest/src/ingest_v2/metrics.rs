@@ -20,8 +20,8 @@
 use mrecordlog::ResourceUsage;
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    new_counter_vec, new_gauge, new_gauge_vec, new_histogram_vec, HistogramVec, IntCounterVec,
-    IntGauge, IntGaugeVec,
+    new_counter, new_counter_vec, new_gauge, new_gauge_vec, new_histogram_vec, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 pub(super) struct IngestV2Metrics {
@@ -32,6 +32,7 @@ pub(super) struct IngestV2Metrics {
     pub wal_acquire_lock_request_duration_secs: HistogramVec<2>,
     pub wal_disk_used_bytes: IntGauge,
     pub wal_memory_used_bytes: IntGauge,
+    pub deduplicated_subrequests_total: IntCounter,
 }
 
 impl Default for IngestV2Metrics {
@@ -82,6 +83,12 @@ impl Default for IngestV2Metrics {
                 "ingest",
                 &[],
             ),
+            deduplicated_subrequests_total: new_counter(
+                "deduplicated_subrequests_total",
+                "Number of persist subrequests acknowledged without appending because their \
+                 idempotency key was already seen.",
+                "ingest",
+            ),
         }
     }
 }
@@ -22,6 +22,7 @@ mod debouncing;
 mod fetch;
 mod idle;
 mod ingester;
+mod leader_health;
 mod metrics;
 mod models;
 mod mrecord;
@@ -30,7 +31,9 @@ mod rate_meter;
 mod replication;
 mod router;
 mod routing_table;
+mod routing_table_snapshot;
 mod state;
+mod wal_offload;
 mod workbench;
 
 use std::collections::hash_map::Entry;
@@ -52,8 +55,9 @@ use tracing::{error, info};
 pub use self::fetch::{FetchStreamError, MultiFetchStream};
 pub use self::ingester::{wait_for_ingester_decommission, wait_for_ingester_status, Ingester};
 use self::mrecord::MRECORD_HEADER_LEN;
-pub use self::mrecord::{decoded_mrecords, MRecord};
+pub use self::mrecord::{decoded_mrecords, MRecord, MRecordDecodeError, MAX_IDEMPOTENCY_KEY_LEN};
 pub use self::router::IngestRouter;
+pub use self::wal_offload::WalOffload;
 
 pub type IngesterPool = Pool<NodeId, IngesterServiceClient>;
 
@@ -139,14 +143,28 @@ impl DocBatchV2Builder {
 /// Helper struct to build an [`IngestRequestV2`].
 #[derive(Debug, Default)]
 pub struct IngestRequestV2Builder {
-    per_index_id_doc_batch_builders: HashMap<IndexId, (u32, DocBatchV2Builder)>,
+    per_subrequest_doc_batch_builders: HashMap<(IndexId, Option<String>), (u32, DocBatchV2Builder)>,
     subrequest_id_sequence: u32,
 }
 
 impl IngestRequestV2Builder {
     /// Adds a document to the request.
     pub fn add_doc(&mut self, index_id: IndexId, doc: &[u8]) -> u32 {
-        match self.per_index_id_doc_batch_builders.entry(index_id) {
+        self.add_doc_with_routing_key(index_id, None, doc)
+    }
+
+    /// Adds a document to the request. The documents sharing the same index ID and routing key are
+    /// grouped into the same subrequest so that the router persists them to the same shard.
+    pub fn add_doc_with_routing_key(
+        &mut self,
+        index_id: IndexId,
+        routing_key_opt: Option<String>,
+        doc: &[u8],
+    ) -> u32 {
+        match self
+            .per_subrequest_doc_batch_builders
+            .entry((index_id, routing_key_opt))
+        {
             Entry::Occupied(mut entry) => {
                 let (subrequest_id, doc_batch_builder) = entry.get_mut();
                 doc_batch_builder.add_doc(doc);
@@ -166,18 +184,22 @@ impl IngestRequestV2Builder {
     /// Builds the [`IngestRequestV2`], returning `None` if the request is empty.
     pub fn build(self, source_id: &str, commit_type: CommitTypeV2) -> Option<IngestRequestV2> {
         let subrequests: Vec<IngestSubrequest> = self
-            .per_index_id_doc_batch_builders
+            .per_subrequest_doc_batch_builders
             .into_iter()
-            .flat_map(|(index_id, (subrequest_id, doc_batch_builder))| {
-                let doc_batch = doc_batch_builder.build()?;
-                let ingest_subrequest = IngestSubrequest {
-                    subrequest_id,
-                    index_id,
-                    source_id: source_id.to_string(),
-                    doc_batch: Some(doc_batch),
-                };
-                Some(ingest_subrequest)
-            })
+            .flat_map(
+                |((index_id, routing_key), (subrequest_id, doc_batch_builder))| {
+                    let doc_batch = doc_batch_builder.build()?;
+                    let ingest_subrequest = IngestSubrequest {
+                        subrequest_id,
+                        index_id,
+                        source_id: source_id.to_string(),
+                        doc_batch: Some(doc_batch),
+                        routing_key,
+                        idempotency_key: None,
+                    };
+                    Some(ingest_subrequest)
+                },
+            )
             .collect();
 
         if subrequests.is_empty() {
@@ -186,6 +208,7 @@ impl IngestRequestV2Builder {
         let ingest_request = IngestRequestV2 {
             subrequests,
             commit_type: commit_type as i32,
+            best_effort_atomic: false,
         };
         Some(ingest_request)
     }
@@ -342,6 +365,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ingest_request_builder_with_routing_key() {
+        let mut ingest_request_builder = IngestRequestV2Builder::default();
+        let subrequest_id_0 = ingest_request_builder.add_doc_with_routing_key(
+            "test-index".to_string(),
+            Some("test-routing-key-foo".to_string()),
+            b"doc-0",
+        );
+        let subrequest_id_1 = ingest_request_builder.add_doc_with_routing_key(
+            "test-index".to_string(),
+            Some("test-routing-key-bar".to_string()),
+            b"doc-1",
+        );
+        let subrequest_id_2 = ingest_request_builder.add_doc_with_routing_key(
+            "test-index".to_string(),
+            Some("test-routing-key-foo".to_string()),
+            b"doc-2",
+        );
+        let subrequest_id_3 = ingest_request_builder.add_doc("test-index".to_string(), b"doc-3");
+
+        assert_eq!(subrequest_id_0, subrequest_id_2);
+        assert_ne!(subrequest_id_0, subrequest_id_1);
+        assert_ne!(subrequest_id_0, subrequest_id_3);
+        assert_ne!(subrequest_id_1, subrequest_id_3);
+
+        let mut ingest_request = ingest_request_builder
+            .build("test-source", CommitTypeV2::Auto)
+            .unwrap();
+        ingest_request
+            .subrequests
+            .sort_by_key(|subrequest| subrequest.subrequest_id);
+
+        assert_eq!(ingest_request.subrequests.len(), 3);
+        assert_eq!(
+            ingest_request.subrequests[0].routing_key.as_deref(),
+            Some("test-routing-key-foo")
+        );
+        assert_eq!(
+            ingest_request.subrequests[0]
+                .doc_batch
+                .as_ref()
+                .unwrap()
+                .num_docs(),
+            2
+        );
+        assert_eq!(
+            ingest_request.subrequests[1].routing_key.as_deref(),
+            Some("test-routing-key-bar")
+        );
+        assert!(ingest_request.subrequests[2].routing_key.is_none());
+    }
+
     #[test]
     fn test_estimate_size() {
         let doc_batch = DocBatchV2 {
@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
 use quickwit_proto::ingest::ShardState;
@@ -36,6 +37,53 @@ pub(super) enum IngesterShardType {
 /// Status of a shard: state + position of the last record written.
 pub(super) type ShardStatus = (ShardState, Position);
 
+/// Maximum number of append instants tracked per shard for measuring publish latencies. When
+/// exceeded, the oldest instants are dropped.
+const MAX_APPEND_INSTANTS: usize = 1_000;
+
+/// Maximum number of idempotency keys remembered per shard. When exceeded, the oldest keys are
+/// forgotten and their replays are appended again.
+const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+/// Bounded window of the idempotency keys of the batches appended to a shard, along with the
+/// position of their last record.
+#[derive(Debug, Default)]
+pub(super) struct DedupWindow {
+    positions: HashMap<String, Position>,
+    keys: VecDeque<String>,
+}
+
+impl DedupWindow {
+    /// Returns the position of the batch appended with `idempotency_key`, if any.
+    pub fn get(&self, idempotency_key: &str) -> Option<&Position> {
+        self.positions.get(idempotency_key)
+    }
+
+    pub fn insert(&mut self, idempotency_key: String, position_inclusive: Position) {
+        if self
+            .positions
+            .insert(idempotency_key.clone(), position_inclusive)
+            .is_some()
+        {
+            return;
+        }
+        if self.keys.len() == MAX_IDEMPOTENCY_KEYS {
+            if let Some(oldest_key) = self.keys.pop_front() {
+                self.positions.remove(&oldest_key);
+            }
+        }
+        self.keys.push_back(idempotency_key);
+    }
+
+    /// Forgets the idempotency keys of the batches appended after `position_inclusive`.
+    pub fn forget_after(&mut self, position_inclusive: &Position) {
+        self.positions
+            .retain(|_, key_position_inclusive| key_position_inclusive <= position_inclusive);
+        let positions = &self.positions;
+        self.keys.retain(|key| positions.contains_key(key));
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct IngesterShard {
     pub shard_type: IngesterShardType,
@@ -44,10 +92,20 @@ pub(super) struct IngesterShard {
     pub replication_position_inclusive: Position,
     /// Position up to which the shard has been truncated.
     pub truncation_position_inclusive: Position,
+    /// Position up to which the records of the shard have been offloaded to object storage and
+    /// truncated locally, possibly ahead of `truncation_position_inclusive`.
+    pub offloaded_position_inclusive: Position,
     pub shard_status_tx: watch::Sender<ShardStatus>,
     pub shard_status_rx: watch::Receiver<ShardStatus>,
     /// Instant at which the shard was last written to.
     pub last_write_instant: Instant,
+    /// Instants at which the batches not yet published were appended to the shard, along with
+    /// the position of their last record.
+    pub append_instants: VecDeque<(Position, Instant)>,
+    /// Longest publish latency measured since the last broadcast of the shard info.
+    pub publish_latency_opt: Option<Duration>,
+    /// Idempotency keys of the batches recently appended to the shard.
+    pub dedup_window: DedupWindow,
 }
 
 impl IngesterShard {
@@ -65,9 +123,13 @@ impl IngesterShard {
             shard_state,
             replication_position_inclusive,
             truncation_position_inclusive,
+            offloaded_position_inclusive: Position::Beginning,
             shard_status_tx,
             shard_status_rx,
             last_write_instant: now,
+            append_instants: VecDeque::new(),
+            publish_latency_opt: None,
+            dedup_window: DedupWindow::default(),
         }
     }
 
@@ -85,9 +147,13 @@ impl IngesterShard {
             shard_state,
             replication_position_inclusive,
             truncation_position_inclusive,
+            offloaded_position_inclusive: Position::Beginning,
             shard_status_tx,
             shard_status_rx,
             last_write_instant: now,
+            append_instants: VecDeque::new(),
+            publish_latency_opt: None,
+            dedup_window: DedupWindow::default(),
         }
     }
 
@@ -104,9 +170,13 @@ impl IngesterShard {
             shard_state,
             replication_position_inclusive,
             truncation_position_inclusive,
+            offloaded_position_inclusive: Position::Beginning,
             shard_status_tx,
             shard_status_rx,
             last_write_instant: now,
+            append_instants: VecDeque::new(),
+            publish_latency_opt: None,
+            dedup_window: DedupWindow::default(),
         }
     }
 
@@ -162,10 +232,52 @@ impl IngesterShard {
         if self.replication_position_inclusive == replication_position_inclusive {
             return;
         }
+        if self.append_instants.len() == MAX_APPEND_INSTANTS {
+            self.append_instants.pop_front();
+        }
+        self.append_instants
+            .push_back((replication_position_inclusive.clone(), now));
         self.replication_position_inclusive = replication_position_inclusive;
         self.last_write_instant = now;
         self.notify_shard_status();
     }
+
+    /// Rolls the shard back to `position_inclusive` and closes it. The records appended after this
+    /// position remain in the WAL queue but are never fetched, since fetch streams do not read past
+    /// the replication position of the shard.
+    pub fn rollback(&mut self, position_inclusive: Position) {
+        while self
+            .append_instants
+            .back()
+            .map_or(false, |(position, _)| *position > position_inclusive)
+        {
+            self.append_instants.pop_back();
+        }
+        self.dedup_window.forget_after(&position_inclusive);
+        self.replication_position_inclusive = position_inclusive;
+        self.close();
+    }
+
+    /// Measures the time elapsed since the append of the batches published up to
+    /// `publish_position_inclusive` and retains the longest one for the next broadcast.
+    pub fn observe_publish(&mut self, publish_position_inclusive: &Position, now: Instant) {
+        while let Some((position, append_instant)) = self.append_instants.front() {
+            if position > publish_position_inclusive {
+                break;
+            }
+            let publish_latency = now.duration_since(*append_instant);
+            self.append_instants.pop_front();
+
+            if self
+                .publish_latency_opt
+                .map_or(true, |max_publish_latency| {
+                    publish_latency > max_publish_latency
+                })
+            {
+                self.publish_latency_opt = Some(publish_latency);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -287,4 +399,97 @@ mod tests {
             Position::Beginning
         );
     }
+
+    #[test]
+    fn test_shard_observe_publish() {
+        let now = Instant::now();
+        let mut solo_shard = IngesterShard::new_solo(
+            ShardState::Open,
+            Position::Beginning,
+            Position::Beginning,
+            now,
+        );
+        solo_shard.set_replication_position_inclusive(Position::offset(1u64), now);
+        solo_shard.set_replication_position_inclusive(
+            Position::offset(3u64),
+            now + Duration::from_secs(1),
+        );
+        solo_shard.set_replication_position_inclusive(
+            Position::offset(5u64),
+            now + Duration::from_secs(2),
+        );
+        assert_eq!(solo_shard.append_instants.len(), 3);
+        assert!(solo_shard.publish_latency_opt.is_none());
+
+        solo_shard.observe_publish(&Position::offset(0u64), now + Duration::from_secs(3));
+        assert!(solo_shard.publish_latency_opt.is_none());
+
+        solo_shard.observe_publish(&Position::offset(3u64), now + Duration::from_secs(5));
+        assert_eq!(solo_shard.append_instants.len(), 1);
+        assert_eq!(
+            solo_shard.publish_latency_opt.take(),
+            Some(Duration::from_secs(5))
+        );
+
+        solo_shard.observe_publish(&Position::offset(5u64), now + Duration::from_secs(6));
+        assert!(solo_shard.append_instants.is_empty());
+        assert_eq!(solo_shard.publish_latency_opt, Some(Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn test_shard_rollback() {
+        let now = Instant::now();
+        let mut solo_shard = IngesterShard::new_solo(
+            ShardState::Open,
+            Position::Beginning,
+            Position::Beginning,
+            now,
+        );
+        solo_shard.set_replication_position_inclusive(Position::offset(1u64), now);
+        solo_shard
+            .dedup_window
+            .insert("key-0".to_string(), Position::offset(1u64));
+
+        solo_shard.set_replication_position_inclusive(Position::offset(3u64), now);
+        solo_shard
+            .dedup_window
+            .insert("key-1".to_string(), Position::offset(3u64));
+
+        solo_shard.rollback(Position::offset(1u64));
+        solo_shard.assert_is_closed();
+        solo_shard.assert_replication_position(Position::offset(1u64));
+
+        assert_eq!(solo_shard.append_instants.len(), 1);
+        assert_eq!(
+            solo_shard.dedup_window.get("key-0"),
+            Some(&Position::offset(1u64))
+        );
+        assert!(solo_shard.dedup_window.get("key-1").is_none());
+        assert_eq!(solo_shard.dedup_window.keys.len(), 1);
+
+        let shard_status = solo_shard.shard_status_rx.borrow().clone();
+        assert_eq!(shard_status, (ShardState::Closed, Position::offset(1u64)));
+    }
+
+    #[test]
+    fn test_dedup_window() {
+        let mut dedup_window = DedupWindow::default();
+        assert!(dedup_window.get("key-0").is_none());
+
+        dedup_window.insert("key-0".to_string(), Position::offset(0u64));
+        dedup_window.insert("key-0".to_string(), Position::offset(1u64));
+        assert_eq!(dedup_window.get("key-0"), Some(&Position::offset(1u64)));
+        assert_eq!(dedup_window.keys.len(), 1);
+
+        for i in 1..MAX_IDEMPOTENCY_KEYS {
+            dedup_window.insert(format!("key-{i}"), Position::offset(i as u64 + 1));
+        }
+        assert!(dedup_window.get("key-0").is_some());
+
+        dedup_window.insert("key-last".to_string(), Position::offset(12_345u64));
+        assert!(dedup_window.get("key-0").is_none());
+        assert_eq!(dedup_window.get("key-1"), Some(&Position::offset(2u64)));
+        assert_eq!(dedup_window.keys.len(), MAX_IDEMPOTENCY_KEYS);
+        assert_eq!(dedup_window.positions.len(), MAX_IDEMPOTENCY_KEYS);
+    }
 }
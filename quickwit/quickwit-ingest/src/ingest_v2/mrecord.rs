@@ -17,9 +17,13 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::io;
+
+use bytes::buf::Chain;
 use bytes::{Buf, Bytes};
-use quickwit_proto::ingest::MRecordBatch;
-use tracing::warn;
+use quickwit_proto::ingest::{DocCompression, MRecordBatch};
+use quickwit_proto::types::Position;
+use thiserror::Error;
 
 /// The first byte of a [`MRecord`] is the version of the record header.
 #[derive(Debug)]
@@ -38,30 +42,104 @@ const DOC_HEADER_V0: &[u8; MRECORD_HEADER_LEN] = &[HeaderVersion::V0 as u8, 0];
 /// `Commit` header v0 composed of the header version and the `Commit = 1` record type.
 const COMMIT_HEADER_V0: &[u8; MRECORD_HEADER_LEN] = &[HeaderVersion::V0 as u8, 1];
 
+/// `DocLz4` header v0 composed of the header version and the `DocLz4 = 2` record type.
+const DOC_LZ4_HEADER_V0: &[u8; MRECORD_HEADER_LEN] = &[HeaderVersion::V0 as u8, 2];
+
+/// `DocZstd` header v0 composed of the header version and the `DocZstd = 3` record type.
+const DOC_ZSTD_HEADER_V0: &[u8; MRECORD_HEADER_LEN] = &[HeaderVersion::V0 as u8, 3];
+
+/// `IdempotencyKey` header v0 composed of the header version and the `IdempotencyKey = 4` record
+/// type.
+const IDEMPOTENCY_KEY_HEADER_V0: &[u8; MRECORD_HEADER_LEN] = &[HeaderVersion::V0 as u8, 4];
+
+/// `Rollback` header v0 composed of the header version and the `Rollback = 5` record type.
+const ROLLBACK_HEADER_V0: &[u8; MRECORD_HEADER_LEN] = &[HeaderVersion::V0 as u8, 5];
+
+/// Maximum length in bytes of an idempotency key. Keys are written to the WAL along with the batch
+/// they identify, so their size must be bounded.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 256;
+
+/// Compression level used for Zstd-compressed docs. Low levels favor ingestion throughput.
+const ZSTD_COMPRESSION_LEVEL: i32 = 1;
+
+#[derive(Debug, Error)]
+pub enum MRecordDecodeError {
+    #[error("mrecord is too short: expected at least {MRECORD_HEADER_LEN} bytes, got {0}")]
+    TooShort(usize),
+    #[error("unknown mrecord header version `{0}`")]
+    UnknownHeaderVersion(u8),
+    #[error("unknown mrecord type `{0}`")]
+    UnknownType(u8),
+    #[error("idempotency key mrecord is not valid UTF-8")]
+    InvalidIdempotencyKey,
+    #[error("rollback mrecord has an invalid length: expected 0 or 8 bytes, got {0}")]
+    InvalidRollback(usize),
+    #[error("failed to decompress {doc_compression:?} mrecord: {error}")]
+    Decompression {
+        doc_compression: DocCompression,
+        error: io::Error,
+    },
+}
+
+/// An encoded [`MRecord`]: its header followed by its payload.
+pub type EncodedMRecord = Chain<&'static [u8], Bytes>;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum MRecord {
     Doc(Bytes),
     Commit,
+    /// Idempotency key of the batch whose records precede this one. It carries no document and
+    /// allows the ingester to rebuild the deduplication window of a shard from its WAL queue.
+    IdempotencyKey(String),
+    /// Marks the records located between the position it carries and itself as rolled back. It is
+    /// appended after the records of an aborted atomic persist request, which are never fetched,
+    /// and allows the ingester to restore the position of the shard from its WAL queue.
+    Rollback(Position),
 }
 
 impl MRecord {
-    pub fn encode(&self) -> impl Buf {
+    pub fn encode(&self) -> EncodedMRecord {
         match &self {
             Self::Doc(doc) => DOC_HEADER_V0.chain(doc.clone()),
             Self::Commit => COMMIT_HEADER_V0.chain(Bytes::new()),
+            Self::IdempotencyKey(idempotency_key) => {
+                IDEMPOTENCY_KEY_HEADER_V0.chain(Bytes::from(idempotency_key.clone()))
+            }
+            Self::Rollback(position) => {
+                let payload = match position.as_u64() {
+                    Some(offset) => Bytes::copy_from_slice(&offset.to_be_bytes()),
+                    None => Bytes::new(),
+                };
+                ROLLBACK_HEADER_V0.chain(payload)
+            }
         }
     }
 
-    pub fn decode(mut buf: impl Buf) -> Option<Self> {
-        if buf.remaining() < 2 {
-            return None;
+    /// Encodes a doc that was already compressed with `doc_compression`, see [`compress_doc`].
+    pub(super) fn encode_compressed_doc(
+        compressed_doc: Bytes,
+        doc_compression: DocCompression,
+    ) -> EncodedMRecord {
+        let header = match doc_compression {
+            DocCompression::Unspecified => DOC_HEADER_V0,
+            DocCompression::Lz4 => DOC_LZ4_HEADER_V0,
+            DocCompression::Zstd => DOC_ZSTD_HEADER_V0,
+        };
+        header.chain(compressed_doc)
+    }
+
+    /// Decodes a record. Records that cannot be decoded, for instance because they are
+    /// corrupted, must not be skipped silently: doing so would advance the fetch position past
+    /// documents that were never indexed.
+    pub fn decode(mut buf: impl Buf) -> Result<Self, MRecordDecodeError> {
+        if buf.remaining() < MRECORD_HEADER_LEN {
+            return Err(MRecordDecodeError::TooShort(buf.remaining()));
         }
 
         let header_version = buf.get_u8();
 
         if header_version != HeaderVersion::V0 as u8 {
-            warn!("unknown mrecord header version `{header_version}`");
-            return None;
+            return Err(MRecordDecodeError::UnknownHeaderVersion(header_version));
         }
 
         let mrecord = match buf.get_u8() {
@@ -70,12 +148,32 @@ impl MRecord {
                 Self::Doc(doc)
             }
             1 => Self::Commit,
+            2 => {
+                let compressed_doc = buf.copy_to_bytes(buf.remaining());
+                let doc = decompress_doc(&compressed_doc, DocCompression::Lz4)?;
+                Self::Doc(doc)
+            }
+            3 => {
+                let compressed_doc = buf.copy_to_bytes(buf.remaining());
+                let doc = decompress_doc(&compressed_doc, DocCompression::Zstd)?;
+                Self::Doc(doc)
+            }
+            4 => {
+                let idempotency_key = buf.copy_to_bytes(buf.remaining());
+                let idempotency_key = String::from_utf8(idempotency_key.to_vec())
+                    .map_err(|_| MRecordDecodeError::InvalidIdempotencyKey)?;
+                Self::IdempotencyKey(idempotency_key)
+            }
+            5 => {
+                let position =
+                    decode_rollback_payload(buf.copy_to_bytes(buf.remaining()).as_ref())?;
+                Self::Rollback(position)
+            }
             other => {
-                warn!("unknown mrecord type `{other}`");
-                return None;
+                return Err(MRecordDecodeError::UnknownType(other));
             }
         };
-        Some(mrecord)
+        Ok(mrecord)
     }
 
     #[cfg(any(test, feature = "testsuite"))]
@@ -84,8 +182,61 @@ impl MRecord {
     }
 }
 
-pub fn decoded_mrecords(mrecord_batch: &MRecordBatch) -> impl Iterator<Item = MRecord> + '_ {
-    mrecord_batch.encoded_mrecords().flat_map(MRecord::decode)
+/// Returns the idempotency key stored in an encoded record if the record is an
+/// [`MRecord::IdempotencyKey`], without decoding the other kinds of records.
+pub(super) fn decode_idempotency_key(encoded_mrecord: &[u8]) -> Option<&str> {
+    let idempotency_key = encoded_mrecord.strip_prefix(IDEMPOTENCY_KEY_HEADER_V0)?;
+    std::str::from_utf8(idempotency_key).ok()
+}
+
+/// Returns the position stored in an encoded record if the record is an [`MRecord::Rollback`],
+/// without decoding the other kinds of records.
+pub(super) fn decode_rollback_position(encoded_mrecord: &[u8]) -> Option<Position> {
+    let payload = encoded_mrecord.strip_prefix(ROLLBACK_HEADER_V0)?;
+    decode_rollback_payload(payload).ok()
+}
+
+fn decode_rollback_payload(payload: &[u8]) -> Result<Position, MRecordDecodeError> {
+    if payload.is_empty() {
+        return Ok(Position::Beginning);
+    }
+    let offset_bytes: [u8; 8] = payload
+        .try_into()
+        .map_err(|_| MRecordDecodeError::InvalidRollback(payload.len()))?;
+    Ok(Position::offset(u64::from_be_bytes(offset_bytes)))
+}
+
+/// Compresses a doc with `doc_compression`.
+pub(super) fn compress_doc(doc: &[u8], doc_compression: DocCompression) -> io::Result<Bytes> {
+    let compressed_doc = match doc_compression {
+        DocCompression::Unspecified => doc.to_vec(),
+        DocCompression::Lz4 => lz4_flex::compress_prepend_size(doc),
+        DocCompression::Zstd => zstd::bulk::compress(doc, ZSTD_COMPRESSION_LEVEL)?,
+    };
+    Ok(Bytes::from(compressed_doc))
+}
+
+fn decompress_doc(
+    compressed_doc: &[u8],
+    doc_compression: DocCompression,
+) -> Result<Bytes, MRecordDecodeError> {
+    let doc_res = match doc_compression {
+        DocCompression::Unspecified => Ok(compressed_doc.to_vec()),
+        DocCompression::Lz4 => lz4_flex::decompress_size_prepended(compressed_doc)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+        DocCompression::Zstd => zstd::decode_all(compressed_doc),
+    };
+    let doc = doc_res.map_err(|error| MRecordDecodeError::Decompression {
+        doc_compression,
+        error,
+    })?;
+    Ok(Bytes::from(doc))
+}
+
+pub fn decoded_mrecords(
+    mrecord_batch: &MRecordBatch,
+) -> impl Iterator<Item = Result<MRecord, MRecordDecodeError>> + '_ {
+    mrecord_batch.encoded_mrecords().map(MRecord::decode)
 }
 
 #[cfg(test)]
@@ -94,10 +245,20 @@ mod tests {
 
     #[test]
     fn test_parse_invalid_mrecord() {
-        assert!(MRecord::decode(&b""[..]).is_none());
-        assert!(MRecord::decode(&b"a"[..]).is_none());
-        assert!(MRecord::decode(&[HeaderVersion::V0 as u8][..]).is_none());
-        assert!(MRecord::decode(&[HeaderVersion::V0 as u8, 19u8][..]).is_none());
+        let error = MRecord::decode(&b""[..]).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::TooShort(0)));
+
+        let error = MRecord::decode(&b"a"[..]).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::TooShort(1)));
+
+        let error = MRecord::decode(&[HeaderVersion::V0 as u8][..]).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::TooShort(1)));
+
+        let error = MRecord::decode(&[1u8, 0u8][..]).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::UnknownHeaderVersion(1)));
+
+        let error = MRecord::decode(&[HeaderVersion::V0 as u8, 19u8][..]).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::UnknownType(19)));
     }
 
     #[test]
@@ -108,6 +269,68 @@ mod tests {
         assert_eq!(record, decoded_record);
     }
 
+    #[test]
+    fn test_mrecord_compressed_doc_roundtrip() {
+        let doc = r#"{"message": "hello", "level": "info", "message_repeated": "hello hello"}"#;
+
+        for doc_compression in [
+            DocCompression::Unspecified,
+            DocCompression::Lz4,
+            DocCompression::Zstd,
+        ] {
+            let compressed_doc = compress_doc(doc.as_bytes(), doc_compression).unwrap();
+            let encoded_record = MRecord::encode_compressed_doc(compressed_doc, doc_compression);
+            let decoded_record = MRecord::decode(encoded_record).unwrap();
+            assert_eq!(decoded_record, MRecord::new_doc(doc));
+        }
+        let corrupted_record = DOC_ZSTD_HEADER_V0.chain(&b"not zstd"[..]);
+        let error = MRecord::decode(corrupted_record).unwrap_err();
+        assert!(matches!(
+            error,
+            MRecordDecodeError::Decompression {
+                doc_compression: DocCompression::Zstd,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_mrecord_idempotency_key_roundtrip() {
+        let record = MRecord::IdempotencyKey("test-key".to_string());
+        let encoded_record = record.encode();
+        let decoded_record = MRecord::decode(encoded_record).unwrap();
+        assert_eq!(record, decoded_record);
+
+        let encoded_record = record.encode().copy_to_bytes(10);
+        assert_eq!(decode_idempotency_key(&encoded_record), Some("test-key"));
+
+        let encoded_record = MRecord::new_doc("test-key").encode().copy_to_bytes(10);
+        assert!(decode_idempotency_key(&encoded_record).is_none());
+
+        let invalid_record = IDEMPOTENCY_KEY_HEADER_V0.chain(&[0xff, 0xfe][..]);
+        let error = MRecord::decode(invalid_record).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::InvalidIdempotencyKey));
+    }
+
+    #[test]
+    fn test_mrecord_rollback_roundtrip() {
+        for position in [Position::Beginning, Position::offset(42u64)] {
+            let record = MRecord::Rollback(position.clone());
+            let encoded_record = record.encode();
+            let decoded_record = MRecord::decode(encoded_record).unwrap();
+            assert_eq!(record, decoded_record);
+
+            let encoded_record = record.encode().copy_to_bytes(record.encode().remaining());
+            assert_eq!(decode_rollback_position(&encoded_record), Some(position));
+        }
+        let encoded_record = MRecord::new_doc("test-doc").encode().copy_to_bytes(10);
+        assert!(decode_rollback_position(&encoded_record).is_none());
+
+        let invalid_record = ROLLBACK_HEADER_V0.chain(&[0x00, 0x01][..]);
+        let error = MRecord::decode(invalid_record).unwrap_err();
+        assert!(matches!(error, MRecordDecodeError::InvalidRollback(2)));
+    }
+
     #[test]
     fn test_mrecord_commit_roundtrip() {
         let record = MRecord::Commit;
@@ -18,16 +18,19 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::io;
-use std::iter::once;
 use std::ops::RangeInclusive;
 
+use bytes::BytesMut;
 use bytesize::ByteSize;
 #[cfg(feature = "failpoints")]
 use fail::fail_point;
 use mrecordlog::error::{AppendError, DeleteQueueError};
-use quickwit_proto::ingest::DocBatchV2;
+use quickwit_common::rate_limited_warn;
+use quickwit_proto::ingest::{DocBatchV2, DocCompression};
 use quickwit_proto::types::{Position, QueueId};
 
+use super::models::DedupWindow;
+use super::mrecord::{compress_doc, decode_idempotency_key, decode_rollback_position};
 use crate::mrecordlog_async::MultiRecordLogAsync;
 use crate::MRecord;
 
@@ -39,7 +42,45 @@ pub(super) enum AppendDocBatchError {
     QueueNotFound(QueueId),
 }
 
-/// Appends a non-empty document batch to the WAL queue `queue_id`.
+/// Compresses the documents of a batch with `doc_compression`. The returned batch must be appended
+/// to the WAL with the returned compression, which is `DocCompression::Unspecified` if the
+/// documents could not be compressed. In that case, the batch is left uncompressed.
+pub(super) fn compress_doc_batch(
+    doc_batch: DocBatchV2,
+    doc_compression: DocCompression,
+) -> (DocBatchV2, DocCompression) {
+    if doc_compression == DocCompression::Unspecified {
+        return (doc_batch, DocCompression::Unspecified);
+    }
+    let mut doc_buffer = BytesMut::with_capacity(doc_batch.doc_buffer.len());
+    let mut doc_lengths = Vec::with_capacity(doc_batch.num_docs());
+
+    for doc in doc_batch.docs() {
+        let compressed_doc = match compress_doc(&doc, doc_compression) {
+            Ok(compressed_doc) => compressed_doc,
+            Err(error) => {
+                rate_limited_warn!(
+                    limit_per_min = 6,
+                    "failed to compress doc batch with {}, appending it uncompressed: {error}",
+                    doc_compression.as_str_name()
+                );
+                return (doc_batch, DocCompression::Unspecified);
+            }
+        };
+        doc_lengths.push(compressed_doc.len() as u32);
+        doc_buffer.extend_from_slice(&compressed_doc);
+    }
+    let compressed_doc_batch = DocBatchV2 {
+        doc_buffer: doc_buffer.freeze(),
+        doc_lengths,
+    };
+    (compressed_doc_batch, doc_compression)
+}
+
+/// Appends a non-empty document batch to the WAL queue `queue_id`. The documents of the batch must
+/// have been compressed with `doc_compression` beforehand, see [`compress_doc_batch`]. The
+/// idempotency key of the batch, if any, is appended last, so the returned position is the
+/// position of the key record.
 ///
 /// # Panics
 ///
@@ -48,36 +89,28 @@ pub(super) async fn append_non_empty_doc_batch(
     mrecordlog: &mut MultiRecordLogAsync,
     queue_id: &QueueId,
     doc_batch: DocBatchV2,
+    doc_compression: DocCompression,
     force_commit: bool,
+    idempotency_key_opt: Option<&str>,
 ) -> Result<Position, AppendDocBatchError> {
-    let append_result = if force_commit {
-        let encoded_mrecords = doc_batch
-            .docs()
-            .map(|doc| MRecord::Doc(doc).encode())
-            .chain(once(MRecord::Commit.encode()));
-
-        #[cfg(feature = "failpoints")]
-        fail_point!("ingester:append_records", |_| {
-            let io_error = io::Error::from(io::ErrorKind::PermissionDenied);
-            Err(AppendDocBatchError::Io(io_error))
-        });
-
-        mrecordlog
-            .append_records(queue_id, None, encoded_mrecords)
-            .await
-    } else {
-        let encoded_mrecords = doc_batch.docs().map(|doc| MRecord::Doc(doc).encode());
+    let commit_mrecord_opt = force_commit.then(|| MRecord::Commit.encode());
+    let idempotency_key_mrecord_opt = idempotency_key_opt
+        .map(|idempotency_key| MRecord::IdempotencyKey(idempotency_key.to_string()).encode());
+    let encoded_mrecords = doc_batch
+        .docs()
+        .map(|doc| MRecord::encode_compressed_doc(doc, doc_compression))
+        .chain(commit_mrecord_opt)
+        .chain(idempotency_key_mrecord_opt);
 
-        #[cfg(feature = "failpoints")]
-        fail_point!("ingester:append_records", |_| {
-            let io_error = io::Error::from(io::ErrorKind::PermissionDenied);
-            Err(AppendDocBatchError::Io(io_error))
-        });
-
-        mrecordlog
-            .append_records(queue_id, None, encoded_mrecords)
-            .await
-    };
+    #[cfg(feature = "failpoints")]
+    fail_point!("ingester:append_records", |_| {
+        let io_error = io::Error::from(io::ErrorKind::PermissionDenied);
+        Err(AppendDocBatchError::Io(io_error))
+    });
+
+    let append_result = mrecordlog
+        .append_records(queue_id, None, encoded_mrecords)
+        .await;
     match append_result {
         Ok(Some(offset)) => Ok(Position::offset(offset)),
         Ok(None) => panic!("`doc_batch` should not be empty"),
@@ -91,6 +124,40 @@ pub(super) async fn append_non_empty_doc_batch(
     }
 }
 
+/// Appends a rollback record to the WAL queue `queue_id`, marking the records appended after
+/// `position_inclusive` as rolled back. See [`MRecord::Rollback`].
+pub(super) async fn append_rollback_record(
+    mrecordlog: &mut MultiRecordLogAsync,
+    queue_id: &QueueId,
+    position_inclusive: &Position,
+) -> Result<(), AppendDocBatchError> {
+    let rollback_mrecord = MRecord::Rollback(position_inclusive.clone()).encode();
+
+    let append_result = mrecordlog
+        .append_records(queue_id, None, std::iter::once(rollback_mrecord))
+        .await;
+    match append_result {
+        Ok(_) => Ok(()),
+        Err(AppendError::IoError(io_error)) => Err(AppendDocBatchError::Io(io_error)),
+        Err(AppendError::MissingQueue(queue_id)) => {
+            Err(AppendDocBatchError::QueueNotFound(queue_id))
+        }
+        Err(AppendError::Past) => {
+            panic!("`append_records` should be called with `position_opt: None`")
+        }
+    }
+}
+
+/// Returns the position to which the shard of the WAL queue `queue_id` was rolled back if the last
+/// record of the queue is a rollback record.
+pub(super) fn rollback_position(
+    mrecordlog: &MultiRecordLogAsync,
+    queue_id: &QueueId,
+) -> Option<Position> {
+    let last_record = mrecordlog.last_record(queue_id).ok()??;
+    decode_rollback_position(last_record.payload.as_ref())
+}
+
 /// Error returned when the mrecordlog does not have enough capacity to store some records.
 #[derive(Debug, Clone, Copy, thiserror::Error)]
 pub(super) enum NotEnoughCapacityError {
@@ -153,6 +220,25 @@ pub async fn force_delete_queue(
     }
 }
 
+/// Rebuilds the deduplication window of a shard from the idempotency keys stored in its WAL queue.
+pub(super) fn replay_idempotency_keys(
+    mrecordlog: &MultiRecordLogAsync,
+    queue_id: &QueueId,
+    dedup_window: &mut DedupWindow,
+) {
+    let Ok(records) = mrecordlog.range(queue_id, ..) else {
+        return;
+    };
+    for record in records {
+        if let Some(idempotency_key) = decode_idempotency_key(record.payload.as_ref()) {
+            dedup_window.insert(
+                idempotency_key.to_string(),
+                Position::offset(record.position),
+            );
+        }
+    }
+}
+
 /// Returns the first and last position of the records currently stored in the queue. Returns `None`
 /// if the queue does not exist or is empty.
 pub(super) fn queue_position_range(
@@ -175,6 +261,8 @@ pub(super) fn queue_position_range(
 
 #[cfg(test)]
 mod tests {
+    use bytes::Bytes;
+
     use super::*;
 
     #[tokio::test]
@@ -185,10 +273,16 @@ mod tests {
         let queue_id = "test-queue".to_string();
         let doc_batch = DocBatchV2::for_test(["test-doc-foo"]);
 
-        let append_error =
-            append_non_empty_doc_batch(&mut mrecordlog, &queue_id, doc_batch.clone(), false)
-                .await
-                .unwrap_err();
+        let append_error = append_non_empty_doc_batch(
+            &mut mrecordlog,
+            &queue_id,
+            doc_batch.clone(),
+            DocCompression::Unspecified,
+            false,
+            None,
+        )
+        .await
+        .unwrap_err();
 
         assert!(matches!(
             append_error,
@@ -197,17 +291,93 @@ mod tests {
 
         mrecordlog.create_queue(&queue_id).await.unwrap();
 
-        let position =
-            append_non_empty_doc_batch(&mut mrecordlog, &queue_id, doc_batch.clone(), false)
-                .await
-                .unwrap();
+        let position = append_non_empty_doc_batch(
+            &mut mrecordlog,
+            &queue_id,
+            doc_batch.clone(),
+            DocCompression::Unspecified,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(position, Position::offset(0u64));
 
-        let position =
-            append_non_empty_doc_batch(&mut mrecordlog, &queue_id, doc_batch.clone(), true)
-                .await
-                .unwrap();
+        let position = append_non_empty_doc_batch(
+            &mut mrecordlog,
+            &queue_id,
+            doc_batch.clone(),
+            DocCompression::Unspecified,
+            true,
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(position, Position::offset(2u64));
+
+        let position = append_non_empty_doc_batch(
+            &mut mrecordlog,
+            &queue_id,
+            doc_batch.clone(),
+            DocCompression::Unspecified,
+            true,
+            Some("test-key"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(position, Position::offset(5u64));
+
+        let last_record = mrecordlog.last_record(&queue_id).unwrap().unwrap();
+        assert_eq!(
+            MRecord::decode(last_record.payload.as_ref()).unwrap(),
+            MRecord::IdempotencyKey("test-key".to_string())
+        );
+        let mut dedup_window = DedupWindow::default();
+        replay_idempotency_keys(&mrecordlog, &queue_id, &mut dedup_window);
+        assert_eq!(dedup_window.get("test-key"), Some(&Position::offset(5u64)));
+    }
+
+    #[tokio::test]
+    async fn test_append_compressed_doc_batch() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut mrecordlog = MultiRecordLogAsync::open(tempdir.path()).await.unwrap();
+
+        for doc_compression in [DocCompression::Lz4, DocCompression::Zstd] {
+            let queue_id = format!("test-queue-{}", doc_compression.as_str_name());
+            mrecordlog.create_queue(&queue_id).await.unwrap();
+
+            let doc_batch = DocBatchV2::for_test(["test-doc-foo", "test-doc-bar"]);
+            let (compressed_doc_batch, effective_doc_compression) =
+                compress_doc_batch(doc_batch, doc_compression);
+            assert_eq!(compressed_doc_batch.num_docs(), 2);
+            assert_eq!(effective_doc_compression, doc_compression);
+
+            let position = append_non_empty_doc_batch(
+                &mut mrecordlog,
+                &queue_id,
+                compressed_doc_batch,
+                doc_compression,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+            assert_eq!(position, Position::offset(2u64));
+
+            let mrecords: Vec<MRecord> = mrecordlog
+                .range(&queue_id, ..)
+                .unwrap()
+                .map(|record| MRecord::decode(record.payload.as_ref()).unwrap())
+                .collect();
+            assert_eq!(
+                mrecords,
+                [
+                    MRecord::Doc(Bytes::from_static(b"test-doc-foo")),
+                    MRecord::Doc(Bytes::from_static(b"test-doc-bar")),
+                    MRecord::Commit,
+                ]
+            );
+        }
     }
 
     // This test should be run manually and independently of other tests with the `failpoints`
@@ -228,9 +398,16 @@ mod tests {
         mrecordlog.create_queue(&queue_id).await.unwrap();
 
         let doc_batch = DocBatchV2::for_test(["test-doc-foo"]);
-        let append_error = append_non_empty_doc_batch(&mut mrecordlog, &queue_id, doc_batch, false)
-            .await
-            .unwrap_err();
+        let append_error = append_non_empty_doc_batch(
+            &mut mrecordlog,
+            &queue_id,
+            doc_batch,
+            DocCompression::Unspecified,
+            false,
+            None,
+        )
+        .await
+        .unwrap_err();
 
         assert!(matches!(append_error, AppendDocBatchError::Io(..)));
 
@@ -259,6 +436,47 @@ mod tests {
         check_enough_capacity(&mrecordlog, ByteSize::mb(256), ByteSize(12), ByteSize(12)).unwrap();
     }
 
+    #[tokio::test]
+    async fn test_append_rollback_record() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut mrecordlog = MultiRecordLogAsync::open(tempdir.path()).await.unwrap();
+
+        let queue_id = "test-queue".to_string();
+
+        let append_error = append_rollback_record(&mut mrecordlog, &queue_id, &Position::Beginning)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            append_error,
+            AppendDocBatchError::QueueNotFound(..)
+        ));
+
+        mrecordlog.create_queue(&queue_id).await.unwrap();
+        assert!(rollback_position(&mrecordlog, &queue_id).is_none());
+
+        let doc_batch = DocBatchV2::for_test(["test-doc-foo", "test-doc-bar"]);
+        append_non_empty_doc_batch(
+            &mut mrecordlog,
+            &queue_id,
+            doc_batch,
+            DocCompression::Unspecified,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(rollback_position(&mrecordlog, &queue_id).is_none());
+
+        append_rollback_record(&mut mrecordlog, &queue_id, &Position::offset(0u64))
+            .await
+            .unwrap();
+        assert_eq!(
+            rollback_position(&mrecordlog, &queue_id),
+            Some(Position::offset(0u64))
+        );
+        assert_eq!(queue_position_range(&mrecordlog, &queue_id).unwrap(), 0..=2);
+    }
+
     #[tokio::test]
     async fn test_append_queue_position_range() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -570,6 +570,22 @@ impl ReplicationTask {
             };
             assert!(shard.is_replica());
 
+            if let Some(rollback_to_position_inclusive) = subrequest.rollback_to_position_inclusive
+            {
+                state_guard
+                    .rollback_shard(&queue_id, rollback_to_position_inclusive.clone())
+                    .await;
+
+                let replicate_success = ReplicateSuccess {
+                    subrequest_id: subrequest.subrequest_id,
+                    index_uid: subrequest.index_uid,
+                    source_id: subrequest.source_id,
+                    shard_id: subrequest.shard_id,
+                    replication_position_inclusive: Some(rollback_to_position_inclusive),
+                };
+                replicate_successes.push(replicate_success);
+                continue;
+            }
             if shard.is_closed() {
                 let replicate_failure = ReplicateFailure {
                     subrequest_id: subrequest.subrequest_id,
@@ -584,6 +600,7 @@ impl ReplicationTask {
             if shard.replication_position_inclusive != from_position_exclusive {
                 // TODO
             }
+            let doc_compression = subrequest.doc_compression();
             let doc_batch = match subrequest.doc_batch {
                 Some(doc_batch) if !doc_batch.is_empty() => doc_batch,
                 _ => {
@@ -633,7 +650,9 @@ impl ReplicationTask {
                 &mut state_guard.mrecordlog,
                 &queue_id,
                 doc_batch,
+                doc_compression,
                 force_commit,
+                subrequest.idempotency_key.as_deref(),
             )
             .await;
 
@@ -666,12 +685,19 @@ impl ReplicationTask {
                     continue;
                 }
             };
-            state_guard
+            let replica_shard = state_guard
                 .shards
                 .get_mut(&queue_id)
-                .expect("replica shard should be initialized")
+                .expect("replica shard should be initialized");
+            replica_shard
                 .set_replication_position_inclusive(current_position_inclusive.clone(), now);
 
+            if let Some(idempotency_key) = subrequest.idempotency_key {
+                replica_shard
+                    .dedup_window
+                    .insert(idempotency_key, current_position_inclusive.clone());
+            }
+
             INGEST_METRICS
                 .replicated_num_bytes_total
                 .inc_by(batch_num_bytes);
@@ -768,7 +794,7 @@ impl Drop for ReplicationTaskHandle {
 mod tests {
 
     use quickwit_proto::ingest::ingester::{ReplicateSubrequest, ReplicateSuccess};
-    use quickwit_proto::ingest::{DocBatchV2, Shard};
+    use quickwit_proto::ingest::{DocBatchV2, DocCompression, Shard};
     use quickwit_proto::types::{queue_id, IndexUid, ShardId};
 
     use super::*;
@@ -938,6 +964,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
                 from_position_exclusive: Some(Position::Beginning),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             },
             ReplicateSubrequest {
                 subrequest_id: 1,
@@ -946,6 +975,9 @@ mod tests {
                 shard_id: Some(ShardId::from(2)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-bar", "test-doc-baz"])),
                 from_position_exclusive: Some(Position::Beginning),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             },
             ReplicateSubrequest {
                 subrequest_id: 2,
@@ -954,6 +986,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-qux", "test-doc-tux"])),
                 from_position_exclusive: Some(Position::offset(0u64)),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             },
         ];
         let replicate_response = replication_stream_task_handle
@@ -1174,6 +1209,9 @@ mod tests {
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
                     from_position_exclusive: Some(Position::Beginning),
+                    doc_compression: DocCompression::Unspecified as i32,
+                    idempotency_key: None,
+                    rollback_to_position_inclusive: None,
                 },
                 ReplicateSubrequest {
                     subrequest_id: 1,
@@ -1182,6 +1220,9 @@ mod tests {
                     shard_id: Some(ShardId::from(2)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-bar", "test-doc-baz"])),
                     from_position_exclusive: Some(Position::Beginning),
+                    doc_compression: DocCompression::Unspecified as i32,
+                    idempotency_key: None,
+                    rollback_to_position_inclusive: None,
                 },
                 ReplicateSubrequest {
                     subrequest_id: 2,
@@ -1190,6 +1231,9 @@ mod tests {
                     shard_id: Some(ShardId::from(1)),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-qux", "test-doc-tux"])),
                     from_position_exclusive: Some(Position::Beginning),
+                    doc_compression: DocCompression::Unspecified as i32,
+                    idempotency_key: Some("test-key".to_string()),
+                    rollback_to_position_inclusive: None,
                 },
             ],
             replication_seqno: 3,
@@ -1232,7 +1276,7 @@ mod tests {
         assert_eq!(replicate_success_2.shard_id(), ShardId::from(1));
         assert_eq!(
             replicate_success_2.replication_position_inclusive(),
-            Position::offset(1u64)
+            Position::offset(2u64)
         );
 
         let state_guard = state.lock_fully().await.unwrap();
@@ -1250,7 +1294,16 @@ mod tests {
         state_guard.mrecordlog.assert_records_eq(
             &queue_id_11,
             ..,
-            &[(0, "\0\0test-doc-qux"), (1, "\0\0test-doc-tux")],
+            &[
+                (0, "\0\0test-doc-qux"),
+                (1, "\0\0test-doc-tux"),
+                (2, "\0\u{4}test-key"),
+            ],
+        );
+        let replica_shard_11 = state_guard.shards.get(&queue_id_11).unwrap();
+        assert_eq!(
+            replica_shard_11.dedup_window.get("test-key"),
+            Some(&Position::offset(2u64))
         );
         drop(state_guard);
 
@@ -1265,6 +1318,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-moo"])),
                 from_position_exclusive: Some(Position::offset(0u64)),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             }],
             replication_seqno: 4,
         };
@@ -1350,6 +1406,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
                 from_position_exclusive: Position::offset(0u64).into(),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             }],
             replication_seqno: 0,
         };
@@ -1426,6 +1485,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
                 from_position_exclusive: Position::offset(0u64).into(),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             }],
             replication_seqno: 0,
         };
@@ -1521,6 +1583,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
                 from_position_exclusive: Position::offset(0u64).into(),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             }],
             replication_seqno: 0,
         };
@@ -1603,6 +1668,9 @@ mod tests {
                 shard_id: Some(ShardId::from(1)),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
                 from_position_exclusive: Some(Position::Beginning),
+                doc_compression: DocCompression::Unspecified as i32,
+                idempotency_key: None,
+                rollback_to_position_inclusive: None,
             }],
             replication_seqno: 0,
         };
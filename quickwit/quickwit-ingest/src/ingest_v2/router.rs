@@ -19,35 +19,41 @@
 
 use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::stream::FuturesUnordered;
 use futures::{Future, StreamExt};
+use quickwit_cluster::{ClusterFeature, EnabledClusterFeatures};
 use quickwit_common::metrics::{GaugeGuard, MEMORY_METRICS};
 use quickwit_common::pubsub::{EventBroker, EventSubscriber};
 use quickwit_common::{rate_limited_error, rate_limited_warn};
 use quickwit_proto::control_plane::{
     ControlPlaneService, ControlPlaneServiceClient, GetOrCreateOpenShardsRequest,
-    GetOrCreateOpenShardsSubrequest,
+    GetOrCreateOpenShardsSubrequest, ListShardsRequest,
 };
 use quickwit_proto::indexing::ShardPositionsUpdate;
 use quickwit_proto::ingest::ingester::{
     IngesterService, PersistFailureReason, PersistRequest, PersistResponse, PersistSubrequest,
 };
 use quickwit_proto::ingest::router::{IngestRequestV2, IngestResponseV2, IngestRouterService};
-use quickwit_proto::ingest::{CommitTypeV2, IngestV2Error, IngestV2Result, ShardState};
-use quickwit_proto::types::{IndexUid, NodeId, ShardId, SourceId, SubrequestId};
+use quickwit_proto::ingest::{CommitTypeV2, IngestV2Error, IngestV2Result, Shard, ShardState};
+use quickwit_proto::types::{IndexId, IndexUid, NodeId, ShardId, SourceId, SubrequestId};
 use tokio::sync::{Mutex, Semaphore};
-use tracing::info;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
 
 use super::broadcast::LocalShardsUpdate;
 use super::debouncing::{
     DebouncedGetOrCreateOpenShardsRequest, GetOrCreateOpenShardsRequestDebouncer,
 };
 use super::ingester::PERSIST_REQUEST_TIMEOUT;
+use super::leader_health::LeaderHealthTable;
 use super::routing_table::RoutingTable;
+use super::routing_table_snapshot::RoutingTableSnapshot;
 use super::workbench::IngestWorkbench;
 use super::IngesterPool;
 use crate::{get_ingest_router_buffer_size, LeaderId};
@@ -61,6 +67,13 @@ pub(super) const INGEST_REQUEST_TIMEOUT: Duration = if cfg!(any(test, feature =
 
 const MAX_PERSIST_ATTEMPTS: usize = 5;
 
+/// Interval at which the router persists a snapshot of its routing table to disk.
+const ROUTING_TABLE_SNAPSHOT_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(30)
+};
+
 type PersistResult = (PersistRequestSummary, IngestV2Result<PersistResponse>);
 
 #[derive(Clone)]
@@ -72,6 +85,7 @@ pub struct IngestRouter {
     replication_factor: usize,
     // Limits the number of ingest requests in-flight to some capacity in bytes.
     ingest_semaphore: Arc<Semaphore>,
+    cluster_features: EnabledClusterFeatures,
 }
 
 struct RouterState {
@@ -79,6 +93,8 @@ struct RouterState {
     debouncer: GetOrCreateOpenShardsRequestDebouncer,
     // Holds the routing table mapping index and source IDs to shards.
     routing_table: RoutingTable,
+    // Tracks the recent persist latency and rejection rate of the ingesters.
+    leader_health: LeaderHealthTable,
 }
 
 impl fmt::Debug for IngestRouter {
@@ -96,13 +112,16 @@ impl IngestRouter {
         control_plane: ControlPlaneServiceClient,
         ingester_pool: IngesterPool,
         replication_factor: usize,
+        cluster_features: EnabledClusterFeatures,
     ) -> Self {
         let state = Arc::new(Mutex::new(RouterState {
             debouncer: GetOrCreateOpenShardsRequestDebouncer::default(),
             routing_table: RoutingTable {
                 self_node_id: self_node_id.clone(),
                 table: HashMap::default(),
+                alias_keys: HashMap::default(),
             },
+            leader_health: LeaderHealthTable::default(),
         }));
         let ingest_semaphore_permits = get_ingest_router_buffer_size().as_u64() as usize;
         let ingest_semaphore = Arc::new(Semaphore::new(ingest_semaphore_permits));
@@ -114,6 +133,7 @@ impl IngestRouter {
             state,
             replication_factor,
             ingest_semaphore,
+            cluster_features,
         }
     }
 
@@ -127,6 +147,103 @@ impl IngestRouter {
             .forever();
     }
 
+    /// Warms up the routing table after a restart so that the first ingest requests do not all
+    /// trigger `GetOrCreateOpenShards` requests: the router first restores the snapshot it last
+    /// persisted to disk, if any, then fetches the open shards of the control plane's shard table.
+    pub async fn warm_start(&mut self, snapshot_path: &Path) {
+        match RoutingTableSnapshot::load(snapshot_path).await {
+            Ok(Some(snapshot)) => {
+                let num_restored_entries = self
+                    .state
+                    .lock()
+                    .await
+                    .routing_table
+                    .restore_snapshot(snapshot);
+                info!(
+                    "restored {num_restored_entries} routing table entries from `{}`",
+                    snapshot_path.display()
+                );
+            }
+            Ok(None) => {}
+            Err(error) => {
+                warn!(
+                    "failed to load routing table snapshot from `{}`: {error:#}",
+                    snapshot_path.display()
+                );
+            }
+        }
+        let list_shards_request = ListShardsRequest {
+            shard_state: Some(ShardState::Open as i32),
+            ..Default::default()
+        };
+        let list_shards_response = match self.control_plane.list_shards(list_shards_request).await {
+            Ok(list_shards_response) => list_shards_response,
+            Err(control_plane_error) => {
+                warn!("failed to fetch open shards from control plane: {control_plane_error}");
+                return;
+            }
+        };
+        let mut shards_per_source: HashMap<(IndexUid, SourceId), Vec<Shard>> = HashMap::new();
+
+        for shard in list_shards_response
+            .shards
+            .into_iter()
+            .filter_map(|shard_info| shard_info.shard)
+        {
+            shards_per_source
+                .entry((shard.index_uid().clone(), shard.source_id.clone()))
+                .or_default()
+                .push(shard);
+        }
+        let mut state_guard = self.state.lock().await;
+        let mut num_replaced_entries = 0;
+
+        for ((index_uid, source_id), shards) in shards_per_source {
+            if state_guard
+                .routing_table
+                .replace_shards_if_newer(index_uid, source_id, shards)
+            {
+                num_replaced_entries += 1;
+            }
+        }
+        info!("fetched {num_replaced_entries} routing table entries from control plane");
+    }
+
+    /// Spawns a task that periodically persists a snapshot of the routing table to
+    /// `snapshot_path`. The task stops when the router is dropped.
+    pub fn spawn_routing_table_snapshot_task(&self, snapshot_path: PathBuf) -> JoinHandle<()> {
+        let weak_state = Arc::downgrade(&self.state);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ROUTING_TABLE_SNAPSHOT_INTERVAL);
+            // The first tick completes immediately, before the router had a chance to warm start.
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let Some(state) = weak_state.upgrade() else {
+                    return;
+                };
+                let snapshot = state.lock().await.routing_table.snapshot();
+                drop(state);
+
+                // Never overwrite the last known shard table with an empty one.
+                if snapshot.entries.is_empty() {
+                    continue;
+                }
+
+                if let Err(error) = snapshot.save(&snapshot_path).await {
+                    rate_limited_warn!(
+                        limit_per_min = 1,
+                        "failed to save routing table snapshot to `{}`: {error:#}",
+                        snapshot_path.display()
+                    );
+                }
+            }
+        })
+    }
+
     /// Inspects the shard table for each subrequest and returns the appropriate
     /// [`GetOrCreateOpenShardsRequest`] request if open shards do not exist for all the them.
     async fn make_get_or_create_open_shard_request(
@@ -134,7 +251,10 @@ impl IngestRouter {
         workbench: &mut IngestWorkbench,
         ingester_pool: &IngesterPool,
     ) -> DebouncedGetOrCreateOpenShardsRequest {
-        let mut debounced_request = DebouncedGetOrCreateOpenShardsRequest::default();
+        let mut debounced_request = DebouncedGetOrCreateOpenShardsRequest {
+            router_id: self.self_node_id.to_string(),
+            ..Default::default()
+        };
 
         // `closed_shards` and `unavailable_leaders` are populated by calls to `has_open_shards`
         // as we're looking for open shards to route the subrequests to.
@@ -155,17 +275,24 @@ impl IngestRouter {
                 ingester_pool,
                 &mut debounced_request.closed_shards,
                 unavailable_leaders,
-            ) {
+            ) || state_guard
+                .routing_table
+                .should_refresh_ingestion_pressure(&subrequest.index_id, &subrequest.source_id)
+            {
                 let acquire_result = state_guard
                     .debouncer
                     .acquire(&subrequest.index_id, &subrequest.source_id);
 
                 match acquire_result {
                     Ok(permit) => {
+                        let known_shard_table_version = state_guard
+                            .routing_table
+                            .shard_table_version(&subrequest.index_id, &subrequest.source_id);
                         let subrequest = GetOrCreateOpenShardsSubrequest {
                             subrequest_id: subrequest.subrequest_id,
                             index_id: subrequest.index_id.clone(),
                             source_id: subrequest.source_id.clone(),
+                            known_shard_table_version,
                         };
                         debounced_request.push_subrequest(subrequest, permit);
                     }
@@ -217,6 +344,13 @@ impl IngestRouter {
         if request.subrequests.is_empty() {
             return;
         }
+        // The index IDs of the subrequests may be write aliases, which the control plane resolves
+        // to their current write index, so we key the routing table by the requested index IDs.
+        let requested_index_ids: HashMap<SubrequestId, IndexId> = request
+            .subrequests
+            .iter()
+            .map(|subrequest| (subrequest.subrequest_id, subrequest.index_id.clone()))
+            .collect();
         let response_result = self.control_plane.get_or_create_open_shards(request).await;
         let response = match response_result {
             Ok(response) => response,
@@ -238,10 +372,47 @@ impl IngestRouter {
         let mut state_guard = self.state.lock().await;
 
         for success in response.successes {
-            state_guard.routing_table.replace_shards(
-                success.index_uid().clone(),
+            let index_uid = success.index_uid().clone();
+            let index_id = requested_index_ids
+                .get(&success.subrequest_id)
+                .cloned()
+                .unwrap_or_else(|| index_uid.index_id.clone());
+            let ingestion_pressure = success.ingestion_pressure();
+
+            // Deltas only carry the shards opened or closed since the version of the shard table
+            // known by the router. If the delta cannot be applied, the version is not recorded
+            // so that the next request fetches all the open shards.
+            let shard_table_version = if success.is_delta {
+                let is_applied = state_guard.routing_table.apply_open_shards_delta(
+                    index_id.clone(),
+                    &index_uid,
+                    success.source_id.clone(),
+                    success.open_shards,
+                    &success.removed_shard_ids,
+                );
+                if is_applied {
+                    success.shard_table_version
+                } else {
+                    0
+                }
+            } else {
+                state_guard.routing_table.replace_shards_for_index_id(
+                    index_id.clone(),
+                    index_uid,
+                    success.source_id.clone(),
+                    success.open_shards,
+                );
+                success.shard_table_version
+            };
+            state_guard.routing_table.set_shard_table_version(
+                index_id.clone(),
+                success.source_id.clone(),
+                shard_table_version,
+            );
+            state_guard.routing_table.set_ingestion_pressure(
+                index_id,
                 success.source_id,
-                success.open_shards,
+                ingestion_pressure,
             );
         }
         drop(state_guard);
@@ -258,10 +429,26 @@ impl IngestRouter {
     ) {
         let mut closed_shards: HashMap<(IndexUid, SourceId), Vec<ShardId>> = HashMap::new();
         let mut deleted_shards: HashMap<(IndexUid, SourceId), Vec<ShardId>> = HashMap::new();
+        // Persist latency per leader, along with the number of subrequests and rejected
+        // subrequests, or `None` if the persist request failed altogether.
+        let mut persist_outcomes: Vec<(NodeId, Duration, Option<(usize, usize)>)> = Vec::new();
 
         while let Some((persist_summary, persist_result)) = persist_futures.next().await {
+            let persist_latency = persist_summary.sent_at.elapsed();
+
             match persist_result {
                 Ok(persist_response) => {
+                    let num_rejected_subrequests = persist_response
+                        .failures
+                        .iter()
+                        .filter(|persist_failure| is_rejection(persist_failure.reason()))
+                        .count();
+                    let num_subrequests = persist_summary.subrequest_ids.len();
+                    persist_outcomes.push((
+                        persist_summary.leader_id,
+                        persist_latency,
+                        Some((num_subrequests, num_rejected_subrequests)),
+                    ));
                     for persist_success in persist_response.successes {
                         workbench.record_persist_success(persist_success);
                     }
@@ -301,13 +488,34 @@ impl IngestRouter {
                             persist_summary.leader_id
                         );
                     }
+                    persist_outcomes.push((
+                        persist_summary.leader_id.clone(),
+                        persist_latency,
+                        None,
+                    ));
                     workbench.record_persist_error(persist_error, persist_summary);
                 }
             };
         }
-        if !closed_shards.is_empty() || !deleted_shards.is_empty() {
+        if !persist_outcomes.is_empty() || !closed_shards.is_empty() || !deleted_shards.is_empty() {
             let mut state_guard = self.state.lock().await;
 
+            for (leader_id, persist_latency, persist_counts_opt) in persist_outcomes {
+                if let Some((num_subrequests, num_rejected_subrequests)) = persist_counts_opt {
+                    state_guard.leader_health.record_persist_response(
+                        &leader_id,
+                        persist_latency,
+                        num_subrequests,
+                        num_rejected_subrequests,
+                    );
+                } else {
+                    state_guard
+                        .leader_health
+                        .record_persist_error(&leader_id, persist_latency);
+                }
+            }
+            state_guard.leader_health.gc();
+
             for ((index_uid, source_id), shard_ids) in closed_shards {
                 state_guard
                     .routing_table
@@ -329,39 +537,108 @@ impl IngestRouter {
         self.populate_routing_table_debounced(workbench, debounced_request)
             .await;
 
+        if workbench.atomic && workbench.has_permanent_failures() {
+            // Persisting the other subrequests would break atomicity.
+            return;
+        }
         // List of subrequest IDs for which no shards are available to route the subrequests to.
         let mut no_shards_available_subrequest_ids = Vec::new();
+        // List of subrequest IDs targeting sources under high ingestion pressure.
+        let mut rate_limited_subrequest_ids = Vec::new();
 
         let mut per_leader_persist_subrequests: HashMap<&LeaderId, Vec<PersistSubrequest>> =
             HashMap::new();
 
         let state_guard = self.state.lock().await;
 
+        // The subrequests of an atomic request must all be persisted by the same leader, which
+        // appends all of them or none of them.
+        let atomic_leader_opt: Option<NodeId> = if workbench.atomic {
+            let sources = workbench
+                .pending_subrequests()
+                .map(|subrequest| (subrequest.index_id.as_str(), subrequest.source_id.as_str()));
+            state_guard
+                .routing_table
+                .find_common_leader(sources, &self.ingester_pool)
+        } else {
+            None
+        };
+
         // TODO: Here would be the most optimal place to split the body of the HTTP request into
         // lines, validate, transform and then pack the docs into compressed batches routed
         // to the right shards.
 
         for subrequest in workbench.pending_subrequests() {
-            let Some(shard) = state_guard
+            let Some(entry) = state_guard
                 .routing_table
                 .find_entry(&subrequest.index_id, &subrequest.source_id)
-                .and_then(|entry| entry.next_open_shard_round_robin(&self.ingester_pool))
             else {
                 no_shards_available_subrequest_ids.push(subrequest.subrequest_id);
                 continue;
             };
+            if entry.is_under_high_ingestion_pressure() {
+                rate_limited_subrequest_ids.push(subrequest.subrequest_id);
+                continue;
+            }
+            // Replays of a subrequest must land on the same shard to be deduplicated, so the
+            // idempotency key doubles as routing key.
+            let routing_key_opt = subrequest
+                .routing_key
+                .as_ref()
+                .or(subrequest.idempotency_key.as_ref());
+            let shard_opt = if workbench.atomic {
+                atomic_leader_opt.as_ref().and_then(|leader_id| {
+                    entry.next_open_shard_on_leader(
+                        &self.ingester_pool,
+                        leader_id,
+                        routing_key_opt.map(String::as_str),
+                    )
+                })
+            } else if let Some(routing_key) = routing_key_opt {
+                entry.next_open_shard_for_routing_key(&self.ingester_pool, routing_key)
+            } else {
+                entry.next_open_shard_weighted(&self.ingester_pool, &state_guard.leader_health)
+            };
+            let Some(shard) = shard_opt else {
+                no_shards_available_subrequest_ids.push(subrequest.subrequest_id);
+                continue;
+            };
             let persist_subrequest = PersistSubrequest {
                 subrequest_id: subrequest.subrequest_id,
                 index_uid: shard.index_uid.clone().into(),
                 source_id: shard.source_id.clone(),
                 shard_id: Some(shard.shard_id.clone()),
                 doc_batch: subrequest.doc_batch.clone(),
+                idempotency_key: subrequest.idempotency_key.clone(),
             };
             per_leader_persist_subrequests
                 .entry(&shard.leader_id)
                 .or_default()
                 .push(persist_subrequest);
         }
+        if workbench.atomic
+            && (!no_shards_available_subrequest_ids.is_empty()
+                || !rate_limited_subrequest_ids.is_empty())
+        {
+            // At least one subrequest cannot be routed, so none of them is persisted.
+            let aborted_subrequest_ids: Vec<SubrequestId> = per_leader_persist_subrequests
+                .into_values()
+                .flatten()
+                .map(|subrequest| subrequest.subrequest_id)
+                .collect();
+            drop(state_guard);
+
+            for subrequest_id in aborted_subrequest_ids {
+                workbench.record_aborted(subrequest_id);
+            }
+            for subrequest_id in no_shards_available_subrequest_ids {
+                workbench.record_no_shards_available(subrequest_id);
+            }
+            for subrequest_id in rate_limited_subrequest_ids {
+                workbench.record_high_ingestion_pressure(subrequest_id);
+            }
+            return;
+        }
         let persist_futures = FuturesUnordered::new();
 
         for (leader_id, subrequests) in per_leader_persist_subrequests {
@@ -377,11 +654,13 @@ impl IngestRouter {
             let persist_summary = PersistRequestSummary {
                 leader_id: leader_id.clone(),
                 subrequest_ids,
+                sent_at: Instant::now(),
             };
             let persist_request = PersistRequest {
                 leader_id: leader_id.into(),
                 subrequests,
                 commit_type: commit_type as i32,
+                atomic: workbench.atomic,
             };
             let persist_future = async move {
                 let persist_result = tokio::time::timeout(
@@ -405,6 +684,9 @@ impl IngestRouter {
         for subrequest_id in no_shards_available_subrequest_ids {
             workbench.record_no_shards_available(subrequest_id);
         }
+        for subrequest_id in rate_limited_subrequest_ids {
+            workbench.record_high_ingestion_pressure(subrequest_id);
+        }
         self.process_persist_results(workbench, persist_futures)
             .await;
     }
@@ -415,7 +697,11 @@ impl IngestRouter {
         max_num_attempts: usize,
     ) -> IngestV2Result<IngestResponseV2> {
         let commit_type = ingest_request.commit_type();
-        let mut workbench = IngestWorkbench::new(ingest_request.subrequests, max_num_attempts);
+        let mut workbench = if ingest_request.best_effort_atomic {
+            IngestWorkbench::new_atomic(ingest_request.subrequests, max_num_attempts)
+        } else {
+            IngestWorkbench::new(ingest_request.subrequests, max_num_attempts)
+        };
         while !workbench.is_complete() {
             workbench.new_attempt();
             self.batch_persist(&mut workbench, commit_type).await;
@@ -449,6 +735,16 @@ impl IngestRouterService for IngestRouter {
         &mut self,
         ingest_request: IngestRequestV2,
     ) -> IngestV2Result<IngestResponseV2> {
+        if ingest_request.best_effort_atomic
+            && !self
+                .cluster_features
+                .is_enabled(ClusterFeature::AtomicPersist)
+        {
+            let message = "atomic ingest requests are not supported until all the nodes of the \
+                           cluster are upgraded"
+                .to_string();
+            return Err(IngestV2Error::Unavailable(message));
+        }
         let request_size_bytes = ingest_request.num_bytes();
 
         let mut gauge_guard = GaugeGuard::from_gauge(&MEMORY_METRICS.in_flight.ingest_router);
@@ -533,6 +829,18 @@ impl EventSubscriber<ShardPositionsUpdate> for WeakRouterState {
 pub(super) struct PersistRequestSummary {
     pub leader_id: NodeId,
     pub subrequest_ids: Vec<SubrequestId>,
+    pub sent_at: Instant,
+}
+
+/// Returns whether the persist failure reason indicates that the ingester is degraded, as opposed
+/// to the shard being closed or deleted.
+fn is_rejection(persist_failure_reason: PersistFailureReason) -> bool {
+    matches!(
+        persist_failure_reason,
+        PersistFailureReason::RateLimited
+            | PersistFailureReason::ResourceExhausted
+            | PersistFailureReason::Timeout
+    )
 }
 
 #[cfg(test)]
@@ -541,7 +849,8 @@ mod tests {
 
     use quickwit_proto::control_plane::{
         GetOrCreateOpenShardsFailure, GetOrCreateOpenShardsFailureReason,
-        GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSuccess, MockControlPlaneService,
+        GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSuccess, IngestionPressure,
+        ListShardsResponse, MockControlPlaneService,
     };
     use quickwit_proto::ingest::ingester::{
         IngesterServiceClient, MockIngesterService, PersistFailure, PersistResponse, PersistSuccess,
@@ -569,6 +878,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let mut workbench = IngestWorkbench::default();
         let (get_or_create_open_shard_request_opt, rendezvous) = router
@@ -602,6 +912,7 @@ mod tests {
                         leader_id: "test-ingester-0".into(),
                     },
                 ],
+                ingestion_pressure_expires_at: Some(Instant::now() + Duration::from_secs(3600)),
                 ..Default::default()
             },
         );
@@ -658,6 +969,7 @@ mod tests {
             get_or_create_open_shard_request.unavailable_leaders[0],
             "test-ingester-0"
         );
+        assert_eq!(get_or_create_open_shard_request.router_id, "test-router");
         assert_eq!(workbench.unavailable_leaders.len(), 1);
 
         let (get_or_create_open_shard_request_opt, rendezvous_2) = router
@@ -750,6 +1062,7 @@ mod tests {
                             subrequest_id: 0,
                             index_uid: Some(index_uid.clone()),
                             source_id: "test-source".to_string(),
+                            ingestion_pressure: IngestionPressure::Low as i32,
                             open_shards: vec![Shard {
                                 index_uid: Some(index_uid.clone()),
                                 source_id: "test-source".to_string(),
@@ -757,11 +1070,15 @@ mod tests {
                                 shard_state: ShardState::Open as i32,
                                 ..Default::default()
                             }],
+                            shard_table_version: 0,
+                            is_delta: false,
+                            removed_shard_ids: Vec::new(),
                         },
                         GetOrCreateOpenShardsSuccess {
                             subrequest_id: 1,
                             index_uid: Some(index_uid2.clone()),
                             source_id: "test-source".to_string(),
+                            ingestion_pressure: IngestionPressure::Low as i32,
                             open_shards: vec![
                                 Shard {
                                     index_uid: Some(index_uid2.clone()),
@@ -778,6 +1095,9 @@ mod tests {
                                     ..Default::default()
                                 },
                             ],
+                            shard_table_version: 0,
+                            is_delta: false,
+                            removed_shard_ids: Vec::new(),
                         },
                     ],
                     failures: vec![
@@ -805,6 +1125,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let ingest_subrequests = vec![
             IngestSubrequest {
@@ -840,25 +1161,30 @@ mod tests {
                     subrequest_id: 0,
                     index_id: "test-index-0".to_string(),
                     source_id: "test-source".to_string(),
+                    known_shard_table_version: None,
                 },
                 GetOrCreateOpenShardsSubrequest {
                     subrequest_id: 1,
                     index_id: "test-index-1".to_string(),
                     source_id: "test-source".to_string(),
+                    known_shard_table_version: None,
                 },
                 GetOrCreateOpenShardsSubrequest {
                     subrequest_id: 2,
                     index_id: "index-not-found".to_string(),
                     source_id: "test-source".to_string(),
+                    known_shard_table_version: None,
                 },
                 GetOrCreateOpenShardsSubrequest {
                     subrequest_id: 3,
                     index_id: "test-index-0".to_string(),
                     source_id: "source-not-found".to_string(),
+                    known_shard_table_version: None,
                 },
             ],
             closed_shards: Vec::new(),
             unavailable_leaders: Vec::new(),
+            router_id: String::new(),
         };
         router
             .populate_routing_table(&mut workbench, get_or_create_open_shards_request)
@@ -919,6 +1245,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let ingest_subrequests = vec![IngestSubrequest {
             subrequest_id: 0,
@@ -937,6 +1264,69 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_router_batch_persist_records_high_ingestion_pressure() {
+        let self_node_id = "test-router".into();
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_get_or_create_open_shards()
+            .once()
+            .returning(move |request| {
+                assert_eq!(request.subrequests.len(), 1);
+
+                let response = GetOrCreateOpenShardsResponse {
+                    successes: vec![GetOrCreateOpenShardsSuccess {
+                        subrequest_id: 0,
+                        index_uid: Some(IndexUid::for_test("test-index", 0)),
+                        source_id: "test-source".to_string(),
+                        ingestion_pressure: IngestionPressure::High as i32,
+                        open_shards: vec![Shard {
+                            index_uid: Some(IndexUid::for_test("test-index", 0)),
+                            source_id: "test-source".to_string(),
+                            shard_id: Some(ShardId::from(1)),
+                            shard_state: ShardState::Open as i32,
+                            leader_id: "test-ingester".into(),
+                            ..Default::default()
+                        }],
+                        shard_table_version: 0,
+                        is_delta: false,
+                        removed_shard_ids: Vec::new(),
+                    }],
+                    ..Default::default()
+                };
+                Ok(response)
+            });
+        let control_plane = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let ingester_pool = IngesterPool::default();
+        ingester_pool.insert("test-ingester".into(), IngesterServiceClient::mocked());
+
+        let replication_factor = 1;
+        let mut router = IngestRouter::new(
+            self_node_id,
+            control_plane,
+            ingester_pool.clone(),
+            replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
+        );
+        let ingest_subrequests = vec![IngestSubrequest {
+            subrequest_id: 0,
+            index_id: "test-index".to_string(),
+            source_id: "test-source".to_string(),
+            ..Default::default()
+        }];
+        let mut workbench = IngestWorkbench::new(ingest_subrequests, 2);
+        let commit_type = CommitTypeV2::Auto;
+        router.batch_persist(&mut workbench, commit_type).await;
+
+        let subworkbench = workbench.subworkbenches.get(&0).unwrap();
+        assert!(matches!(
+            subworkbench.last_failure_opt,
+            Some(SubworkbenchFailure::HighIngestionPressure)
+        ));
+        let error = workbench.into_ingest_result().unwrap_err();
+        assert_eq!(error, IngestV2Error::TooManyRequests);
+    }
+
     #[tokio::test]
     async fn test_router_batch_persist_records_no_shards_available_unavailable_ingester() {
         let self_node_id = "test-router".into();
@@ -956,6 +1346,7 @@ mod tests {
                         subrequest_id: 0,
                         index_uid: Some(IndexUid::for_test("test-index", 0)),
                         source_id: "test-source".to_string(),
+                        ingestion_pressure: IngestionPressure::Low as i32,
                         open_shards: vec![Shard {
                             index_uid: Some(IndexUid::for_test("test-index", 0)),
                             source_id: "test-source".to_string(),
@@ -964,6 +1355,9 @@ mod tests {
                             leader_id: "test-ingester".into(),
                             ..Default::default()
                         }],
+                        shard_table_version: 0,
+                        is_delta: false,
+                        removed_shard_ids: Vec::new(),
                     }],
                     ..Default::default()
                 };
@@ -977,6 +1371,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let ingest_subrequests = vec![IngestSubrequest {
             subrequest_id: 0,
@@ -1006,6 +1401,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let ingest_subrequests = vec![IngestSubrequest {
             subrequest_id: 0,
@@ -1021,6 +1417,7 @@ mod tests {
             let persist_summary = PersistRequestSummary {
                 leader_id: "test-ingester-0".into(),
                 subrequest_ids: vec![0],
+                sent_at: Instant::now(),
             };
             let persist_result = Ok::<_, IngestV2Error>(PersistResponse {
                 leader_id: "test-ingester-0".to_string(),
@@ -1057,6 +1454,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let ingest_subrequests = vec![IngestSubrequest {
             subrequest_id: 0,
@@ -1072,6 +1470,7 @@ mod tests {
             let persist_summary = PersistRequestSummary {
                 leader_id: "test-ingester-0".into(),
                 subrequest_ids: vec![0],
+                sent_at: Instant::now(),
             };
             let persist_result = Ok::<_, IngestV2Error>(PersistResponse {
                 leader_id: "test-ingester-0".to_string(),
@@ -1095,6 +1494,11 @@ mod tests {
             subworkbench.last_failure_opt,
             Some(SubworkbenchFailure::Persist { .. })
         ));
+
+        // The rejection is accounted for in the health of the ingester.
+        let state_guard = router.state.lock().await;
+        let weight = state_guard.leader_health.weight(&"test-ingester-0".into());
+        assert!(weight < 1.0);
     }
 
     #[tokio::test]
@@ -1108,6 +1512,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let index_uid: IndexUid = IndexUid::for_test("test-index-0", 0);
         let mut state_guard = router.state.lock().await;
@@ -1140,6 +1545,7 @@ mod tests {
             let persist_summary = PersistRequestSummary {
                 leader_id: "test-ingester-0".into(),
                 subrequest_ids: vec![0],
+                sent_at: Instant::now(),
             };
             let persist_result = Ok::<_, IngestV2Error>(PersistResponse {
                 leader_id: "test-ingester-0".to_string(),
@@ -1194,6 +1600,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let ingest_subrequests = vec![
             IngestSubrequest {
@@ -1216,6 +1623,7 @@ mod tests {
             let persist_summary = PersistRequestSummary {
                 leader_id: "test-ingester-0".into(),
                 subrequest_ids: vec![0],
+                sent_at: Instant::now(),
             };
             let persist_result =
                 Err::<_, IngestV2Error>(IngestV2Error::Internal("internal error".to_string()));
@@ -1239,6 +1647,7 @@ mod tests {
             let persist_summary = PersistRequestSummary {
                 leader_id: "test-ingester-1".into(),
                 subrequest_ids: vec![1],
+                sent_at: Instant::now(),
             };
             let persist_result =
                 Err::<_, IngestV2Error>(IngestV2Error::Unavailable("connection error".to_string()));
@@ -1273,6 +1682,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let index_uid: IndexUid = IndexUid::for_test("test-index-0", 0);
         let index_uid2: IndexUid = IndexUid::for_test("test-index-1", 0);
@@ -1313,6 +1723,10 @@ mod tests {
                 },
             ],
         );
+        // The control plane recently advertised the ingestion pressure of the sources.
+        for entry in state_guard.routing_table.table.values_mut() {
+            entry.ingestion_pressure_expires_at = Some(Instant::now() + Duration::from_secs(3600));
+        }
         drop(state_guard);
 
         let mut mock_ingester_0 = MockIngesterService::new();
@@ -1444,15 +1858,18 @@ mod tests {
                     index_id: "test-index-0".to_string(),
                     source_id: "test-source".to_string(),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-foo", "test-doc-bar"])),
+                    ..Default::default()
                 },
                 IngestSubrequest {
                     subrequest_id: 1,
                     index_id: "test-index-1".to_string(),
                     source_id: "test-source".to_string(),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-qux"])),
+                    ..Default::default()
                 },
             ],
             commit_type: CommitTypeV2::Auto as i32,
+            best_effort_atomic: false,
         };
         router.ingest(ingest_request).await.unwrap();
 
@@ -1463,19 +1880,234 @@ mod tests {
                     index_id: "test-index-0".to_string(),
                     source_id: "test-source".to_string(),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-moo", "test-doc-baz"])),
+                    ..Default::default()
                 },
                 IngestSubrequest {
                     subrequest_id: 1,
                     index_id: "test-index-1".to_string(),
                     source_id: "test-source".to_string(),
                     doc_batch: Some(DocBatchV2::for_test(["test-doc-tux"])),
+                    ..Default::default()
                 },
             ],
             commit_type: CommitTypeV2::Auto as i32,
+            best_effort_atomic: false,
         };
         router.ingest(ingest_request).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_router_ingest_atomic() {
+        let self_node_id = "test-router".into();
+        let control_plane = ControlPlaneServiceClient::from_mock(MockControlPlaneService::new());
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let mut router = IngestRouter::new(
+            self_node_id,
+            control_plane,
+            ingester_pool.clone(),
+            replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
+        );
+        let index_uid_0: IndexUid = IndexUid::for_test("test-index-0", 0);
+        let index_uid_1: IndexUid = IndexUid::for_test("test-index-1", 0);
+        let mut state_guard = router.state.lock().await;
+        state_guard.routing_table.replace_shards(
+            index_uid_0.clone(),
+            "test-source",
+            vec![Shard {
+                index_uid: Some(index_uid_0.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-ingester-1".to_string(),
+                ..Default::default()
+            }],
+        );
+        state_guard.routing_table.replace_shards(
+            index_uid_1.clone(),
+            "test-source",
+            vec![
+                Shard {
+                    index_uid: Some(index_uid_1.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    shard_state: ShardState::Open as i32,
+                    leader_id: "test-ingester-0".to_string(),
+                    ..Default::default()
+                },
+                Shard {
+                    index_uid: Some(index_uid_1.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(2)),
+                    shard_state: ShardState::Open as i32,
+                    leader_id: "test-ingester-1".to_string(),
+                    ..Default::default()
+                },
+            ],
+        );
+        drop(state_guard);
+
+        let ingester_0 = IngesterServiceClient::from_mock(MockIngesterService::new());
+        ingester_pool.insert("test-ingester-0".into(), ingester_0);
+
+        let mut mock_ingester_1 = MockIngesterService::new();
+        mock_ingester_1
+            .expect_persist()
+            .once()
+            .returning(move |request| {
+                assert_eq!(request.leader_id, "test-ingester-1");
+                assert!(request.atomic);
+                assert_eq!(request.subrequests.len(), 2);
+
+                let subrequest = &request.subrequests[0];
+                assert_eq!(subrequest.subrequest_id, 0);
+                assert_eq!(subrequest.index_uid(), &index_uid_0);
+                assert_eq!(subrequest.shard_id(), ShardId::from(1));
+
+                let subrequest = &request.subrequests[1];
+                assert_eq!(subrequest.subrequest_id, 1);
+                assert_eq!(subrequest.index_uid(), &index_uid_1);
+                assert_eq!(subrequest.shard_id(), ShardId::from(2));
+
+                let successes = request
+                    .subrequests
+                    .into_iter()
+                    .map(|subrequest| PersistSuccess {
+                        subrequest_id: subrequest.subrequest_id,
+                        index_uid: subrequest.index_uid,
+                        source_id: subrequest.source_id,
+                        shard_id: subrequest.shard_id,
+                        replication_position_inclusive: Some(Position::offset(0u64)),
+                    })
+                    .collect();
+                let response = PersistResponse {
+                    leader_id: request.leader_id,
+                    successes,
+                    failures: Vec::new(),
+                };
+                Ok(response)
+            });
+        let ingester_1 = IngesterServiceClient::from_mock(mock_ingester_1);
+        ingester_pool.insert("test-ingester-1".into(), ingester_1);
+
+        let ingest_request = IngestRequestV2 {
+            subrequests: vec![
+                IngestSubrequest {
+                    subrequest_id: 0,
+                    index_id: "test-index-0".to_string(),
+                    source_id: "test-source".to_string(),
+                    doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
+                    ..Default::default()
+                },
+                IngestSubrequest {
+                    subrequest_id: 1,
+                    index_id: "test-index-1".to_string(),
+                    source_id: "test-source".to_string(),
+                    doc_batch: Some(DocBatchV2::for_test(["test-doc-bar"])),
+                    ..Default::default()
+                },
+            ],
+            commit_type: CommitTypeV2::Auto as i32,
+            best_effort_atomic: true,
+        };
+        // Atomic requests are rejected until all the nodes of the cluster support them.
+        let supported_features = std::mem::replace(
+            &mut router.cluster_features,
+            EnabledClusterFeatures::for_test([]),
+        );
+        let error = router.ingest(ingest_request.clone()).await.unwrap_err();
+        assert!(matches!(error, IngestV2Error::Unavailable(_)));
+
+        router.cluster_features = supported_features;
+        let response = router.ingest(ingest_request).await.unwrap();
+        assert_eq!(response.successes.len(), 2);
+        assert!(response.failures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_router_warm_start() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let snapshot_path = temp_dir.path().join("routing-table.json");
+
+        let self_node_id = "test-router".into();
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_list_shards()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.shard_state, Some(ShardState::Open as i32));
+
+                let index_uid: IndexUid = IndexUid::for_test("test-index-1", 0);
+                let response = ListShardsResponse {
+                    shards: vec![quickwit_proto::control_plane::ShardInfo {
+                        shard: Some(Shard {
+                            index_uid: Some(index_uid),
+                            source_id: "test-source".to_string(),
+                            shard_id: Some(ShardId::from(2)),
+                            shard_state: ShardState::Open as i32,
+                            leader_id: "test-ingester-1".to_string(),
+                            ..Default::default()
+                        }),
+                        ingestion_rate_mib_per_sec: 0,
+                    }],
+                    num_matching_shards: 1,
+                };
+                Ok(response)
+            });
+        let control_plane = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let router = IngestRouter::new(
+            self_node_id,
+            control_plane.clone(),
+            ingester_pool.clone(),
+            replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
+        );
+        let index_uid_0: IndexUid = IndexUid::for_test("test-index-0", 0);
+        router.state.lock().await.routing_table.replace_shards(
+            index_uid_0.clone(),
+            "test-source",
+            vec![Shard {
+                index_uid: Some(index_uid_0.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-ingester-0".to_string(),
+                ..Default::default()
+            }],
+        );
+        let join_handle = router.spawn_routing_table_snapshot_task(snapshot_path.clone());
+        tokio::time::sleep(ROUTING_TABLE_SNAPSHOT_INTERVAL * 2).await;
+        drop(router);
+        join_handle.await.unwrap();
+
+        let mut restarted_router = IngestRouter::new(
+            "test-router".into(),
+            control_plane,
+            ingester_pool,
+            replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
+        );
+        restarted_router.warm_start(&snapshot_path).await;
+
+        let state_guard = restarted_router.state.lock().await;
+        let entry_0 = state_guard
+            .routing_table
+            .find_entry("test-index-0", "test-source")
+            .unwrap();
+        assert_eq!(entry_0.remote_shards.len(), 1);
+        assert_eq!(entry_0.remote_shards[0].shard_id, ShardId::from(1));
+
+        let entry_1 = state_guard
+            .routing_table
+            .find_entry("test-index-1", "test-source")
+            .unwrap();
+        assert_eq!(entry_1.remote_shards.len(), 1);
+        assert_eq!(entry_1.remote_shards[0].shard_id, ShardId::from(2));
+    }
+
     #[tokio::test]
     async fn test_router_ingest_retry() {
         let self_node_id = "test-router".into();
@@ -1487,6 +2119,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let mut state_guard = router.state.lock().await;
         let index_uid: IndexUid = IndexUid::for_test("test-index-0", 0);
@@ -1502,6 +2135,10 @@ mod tests {
                 ..Default::default()
             }],
         );
+        // The control plane recently advertised the ingestion pressure of the source.
+        for entry in state_guard.routing_table.table.values_mut() {
+            entry.ingestion_pressure_expires_at = Some(Instant::now() + Duration::from_secs(3600));
+        }
         drop(state_guard);
 
         let mut mock_ingester_0 = MockIngesterService::new();
@@ -1577,8 +2214,10 @@ mod tests {
                 index_id: "test-index-0".to_string(),
                 source_id: "test-source".to_string(),
                 doc_batch: Some(DocBatchV2::for_test(["test-doc-foo"])),
+                ..Default::default()
             }],
             commit_type: CommitTypeV2::Auto as i32,
+            best_effort_atomic: false,
         };
         router.ingest(ingest_request).await.unwrap();
     }
@@ -1594,6 +2233,7 @@ mod tests {
             control_plane,
             ingester_pool.clone(),
             replication_factor,
+            EnabledClusterFeatures::for_test(ClusterFeature::SUPPORTED),
         );
         let event_broker = EventBroker::default();
         router.subscribe(&event_broker);
@@ -1624,11 +2264,13 @@ mod tests {
                     shard_id: ShardId::from(1),
                     shard_state: ShardState::Closed,
                     ingestion_rate: RateMibPerSec(0),
+                    publish_latency_opt: None,
                 },
                 ShardInfo {
                     shard_id: ShardId::from(2),
                     shard_state: ShardState::Open,
                     ingestion_rate: RateMibPerSec(0),
+                    publish_latency_opt: None,
                 },
             ]),
         };
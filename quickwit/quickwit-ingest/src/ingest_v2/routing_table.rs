@@ -18,15 +18,36 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
+use quickwit_common::rendezvous_hasher::node_affinity;
+use quickwit_proto::control_plane::IngestionPressure;
 use quickwit_proto::ingest::{Shard, ShardIds, ShardState};
 use quickwit_proto::types::{IndexId, IndexUid, NodeId, ShardId, SourceId};
+use tokio::time::Instant;
 use tracing::{info, warn};
 
+use super::leader_health::LeaderHealthTable;
+use super::routing_table_snapshot::{RoutingTableSnapshot, RoutingTableSnapshotEntry};
 use crate::IngesterPool;
 
+/// Weight below which the local shards are bypassed in favor of healthier remote shards.
+const DEGRADED_LEADER_WEIGHT: f64 = 0.5;
+
+/// Fractional part of the golden ratio, used to spread the picks of the weighted round-robin
+/// evenly over the shards.
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+/// Duration during which the ingestion pressure advertised by the control plane for a source is
+/// honored. Past this delay, the router asks the control plane for a fresh value.
+const INGESTION_PRESSURE_TTL: Duration = if cfg!(test) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(3)
+};
+
 #[derive(Debug)]
 pub(super) struct RoutingEntry {
     pub index_uid: IndexUid,
@@ -36,6 +57,19 @@ pub(super) struct RoutingEntry {
     pub leader_id: NodeId,
 }
 
+impl RoutingEntry {
+    fn to_shard(&self) -> Shard {
+        Shard {
+            index_uid: Some(self.index_uid.clone()),
+            source_id: self.source_id.clone(),
+            shard_id: Some(self.shard_id.clone()),
+            shard_state: self.shard_state as i32,
+            leader_id: self.leader_id.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
 impl From<Shard> for RoutingEntry {
     fn from(shard: Shard) -> Self {
         let shard_id = shard.shard_id().clone();
@@ -50,6 +84,19 @@ impl From<Shard> for RoutingEntry {
     }
 }
 
+/// Returns the open and available shards along with the weight of their leader.
+fn weighted_open_shards<'a>(
+    shards: &'a [RoutingEntry],
+    ingester_pool: &IngesterPool,
+    leader_health: &LeaderHealthTable,
+) -> Vec<(&'a RoutingEntry, f64)> {
+    shards
+        .iter()
+        .filter(|shard| shard.shard_state.is_open() && ingester_pool.contains_key(&shard.leader_id))
+        .map(|shard| (shard, leader_health.weight(&shard.leader_id)))
+        .collect()
+}
+
 /// The set of shards the router is aware of for the given index and source.
 #[derive(Debug, Default)]
 pub(super) struct RoutingTableEntry {
@@ -63,6 +110,13 @@ pub(super) struct RoutingTableEntry {
     /// Shards located on remote nodes.
     pub remote_shards: Vec<RoutingEntry>,
     pub remote_round_robin_idx: AtomicUsize,
+    /// Ingestion pressure of the source last advertised by the control plane.
+    pub ingestion_pressure: IngestionPressure,
+    /// Instant past which the advertised ingestion pressure is stale.
+    pub ingestion_pressure_expires_at: Option<Instant>,
+    /// Version of the shard table of the source last received from the control plane, if the
+    /// shards of the entry still match it.
+    pub shard_table_version: Option<u64>,
 }
 
 impl RoutingTableEntry {
@@ -105,6 +159,69 @@ impl RoutingTableEntry {
         }
     }
 
+    /// Returns `true` if the control plane recently reported the source as saturated, in which
+    /// case the router rejects the requests for the source without attempting to persist them.
+    pub fn is_under_high_ingestion_pressure(&self) -> bool {
+        self.ingestion_pressure == IngestionPressure::High && !self.is_ingestion_pressure_stale()
+    }
+
+    /// Returns `true` if the ingestion pressure of the source has expired, whatever its level, so
+    /// that the router also learns when a source that was not saturated becomes saturated.
+    fn should_refresh_ingestion_pressure(&self) -> bool {
+        self.is_ingestion_pressure_stale()
+    }
+
+    fn is_ingestion_pressure_stale(&self) -> bool {
+        self.ingestion_pressure_expires_at
+            .map(|expires_at| expires_at <= Instant::now())
+            .unwrap_or(true)
+    }
+
+    fn set_ingestion_pressure(&mut self, ingestion_pressure: IngestionPressure) {
+        self.ingestion_pressure = ingestion_pressure;
+        self.ingestion_pressure_expires_at = Some(Instant::now() + INGESTION_PRESSURE_TTL);
+    }
+
+    /// Applies a delta received from the control plane: removes the shards that are no longer open
+    /// and inserts the newly opened shards.
+    fn apply_open_shards_delta(
+        &mut self,
+        self_node_id: &NodeId,
+        opened_shards: Vec<Shard>,
+        removed_shard_ids: &[ShardId],
+    ) {
+        if !removed_shard_ids.is_empty() {
+            for shards in [&mut self.local_shards, &mut self.remote_shards] {
+                shards.retain(|shard| !removed_shard_ids.contains(&shard.shard_id));
+            }
+        }
+        let mut num_inserted_shards = 0;
+
+        for shard in opened_shards {
+            if !shard.is_open() {
+                continue;
+            }
+            let routing_entry = RoutingEntry::from(shard);
+            let target_shards = if *self_node_id == routing_entry.leader_id {
+                &mut self.local_shards
+            } else {
+                &mut self.remote_shards
+            };
+            if target_shards
+                .iter()
+                .all(|shard| shard.shard_id != routing_entry.shard_id)
+            {
+                target_shards.push(routing_entry);
+                num_inserted_shards += 1;
+            }
+        }
+        if num_inserted_shards > 0 {
+            for shards in [&mut self.local_shards, &mut self.remote_shards] {
+                shards.sort_unstable_by(|left, right| left.shard_id.cmp(&right.shard_id));
+            }
+        }
+    }
+
     /// Returns `true` if at least one shard in the table entry is open and has a leader available.
     /// As it goes through the list of shards in the entry, it populates `closed_shard_ids` and
     /// `unavailable_leaders` with the shard IDs of the closed shards and the node ID of the
@@ -166,6 +283,128 @@ impl RoutingTableEntry {
         None
     }
 
+    /// Returns the next open and available shard in the table entry in a weighted round-robin
+    /// fashion. Each shard is weighted by the health of its leader, so an ingester receives an
+    /// amount of traffic proportional to its number of open shards, discounted by its recent
+    /// persist latency and rejection rate. Local shards are preferred unless their leader is
+    /// degraded.
+    ///
+    /// When all the leaders are healthy, this is equivalent to
+    /// [`Self::next_open_shard_round_robin`].
+    pub fn next_open_shard_weighted(
+        &self,
+        ingester_pool: &IngesterPool,
+        leader_health: &LeaderHealthTable,
+    ) -> Option<&RoutingEntry> {
+        let local_candidates =
+            weighted_open_shards(&self.local_shards, ingester_pool, leader_health);
+        let remote_candidates =
+            weighted_open_shards(&self.remote_shards, ingester_pool, leader_health);
+
+        let all_healthy = local_candidates
+            .iter()
+            .chain(remote_candidates.iter())
+            .all(|(_, weight)| *weight >= 1.0);
+        if all_healthy {
+            return self.next_open_shard_round_robin(ingester_pool);
+        }
+        let is_local_degraded = local_candidates
+            .iter()
+            .all(|(_, weight)| *weight < DEGRADED_LEADER_WEIGHT);
+
+        let (candidates, round_robin_idx) = if !local_candidates.is_empty()
+            && (!is_local_degraded || remote_candidates.is_empty())
+        {
+            (local_candidates, &self.local_round_robin_idx)
+        } else if !remote_candidates.is_empty() {
+            (remote_candidates, &self.remote_round_robin_idx)
+        } else {
+            return None;
+        };
+        let total_weight: f64 = candidates.iter().map(|(_, weight)| weight).sum();
+        let shard_idx = round_robin_idx.fetch_add(1, Ordering::Relaxed);
+        let mut target_weight = (shard_idx as f64 * GOLDEN_RATIO_CONJUGATE).fract() * total_weight;
+
+        for (shard, weight) in &candidates {
+            if target_weight < *weight {
+                return Some(shard);
+            }
+            target_weight -= weight;
+        }
+        candidates.last().map(|(shard, _)| *shard)
+    }
+
+    /// Returns the open and available shard in the table entry with the highest affinity for the
+    /// routing key (rendezvous hashing). The affinities only depend on the shard IDs, so all the
+    /// routers aware of the same set of open shards route a given key to the same shard, and
+    /// opening or closing a shard only remaps the keys of that shard.
+    pub fn next_open_shard_for_routing_key(
+        &self,
+        ingester_pool: &IngesterPool,
+        routing_key: &str,
+    ) -> Option<&RoutingEntry> {
+        self.local_shards
+            .iter()
+            .chain(self.remote_shards.iter())
+            .filter(|shard| {
+                shard.shard_state.is_open() && ingester_pool.contains_key(&shard.leader_id)
+            })
+            .max_by_key(|shard| node_affinity(&shard.shard_id, &routing_key))
+    }
+
+    /// Returns the leaders of the open and available shards in the table entry.
+    pub fn open_shard_leaders<'a>(
+        &'a self,
+        ingester_pool: &'a IngesterPool,
+    ) -> impl Iterator<Item = &'a NodeId> + 'a {
+        self.local_shards
+            .iter()
+            .chain(self.remote_shards.iter())
+            .filter(|shard| {
+                shard.shard_state.is_open() && ingester_pool.contains_key(&shard.leader_id)
+            })
+            .map(|shard| &shard.leader_id)
+    }
+
+    /// Returns the next open and available shard in the table entry led by `leader_id`. The shard
+    /// is picked based on its affinity for the routing key if one is provided, and in a
+    /// round-robin fashion otherwise.
+    pub fn next_open_shard_on_leader(
+        &self,
+        ingester_pool: &IngesterPool,
+        leader_id: &NodeId,
+        routing_key_opt: Option<&str>,
+    ) -> Option<&RoutingEntry> {
+        if !ingester_pool.contains_key(leader_id) {
+            return None;
+        }
+        let candidate_shards: Vec<&RoutingEntry> = self
+            .local_shards
+            .iter()
+            .chain(self.remote_shards.iter())
+            .filter(|shard| shard.shard_state.is_open() && shard.leader_id == *leader_id)
+            .collect();
+
+        if let Some(routing_key) = routing_key_opt {
+            return candidate_shards
+                .into_iter()
+                .max_by_key(|shard| node_affinity(&shard.shard_id, &routing_key));
+        }
+        let first_shard = candidate_shards.first()?;
+
+        let round_robin_idx = if self
+            .local_shards
+            .first()
+            .is_some_and(|local_shard| local_shard.leader_id == first_shard.leader_id)
+        {
+            &self.local_round_robin_idx
+        } else {
+            &self.remote_round_robin_idx
+        };
+        let shard_idx = round_robin_idx.fetch_add(1, Ordering::Relaxed);
+        Some(candidate_shards[shard_idx % candidate_shards.len()])
+    }
+
     /// Inserts the open shards the routing table is not aware of.
     fn insert_open_shards(
         &mut self,
@@ -242,6 +481,7 @@ impl RoutingTableEntry {
 
     /// Clears local and remote shards.
     fn clear_shards(&mut self) {
+        self.shard_table_version = None;
         self.local_shards.clear();
         self.local_round_robin_idx = AtomicUsize::default();
         self.remote_shards.clear();
@@ -255,6 +495,10 @@ impl RoutingTableEntry {
         if self.index_uid != *index_uid {
             return;
         }
+        // The shards no longer match the version of the shard table received from the control
+        // plane, so the next update must be a full one.
+        self.shard_table_version = None;
+
         for shards in [&mut self.local_shards, &mut self.remote_shards] {
             if shards.is_empty() {
                 continue;
@@ -282,6 +526,8 @@ impl RoutingTableEntry {
         if self.index_uid != *index_uid {
             return;
         }
+        self.shard_table_version = None;
+
         for shards in [&mut self.local_shards, &mut self.remote_shards] {
             if shards.is_empty() {
                 continue;
@@ -328,6 +574,9 @@ impl RoutingTableEntry {
 pub(super) struct RoutingTable {
     pub self_node_id: NodeId,
     pub table: HashMap<(IndexId, SourceId), RoutingTableEntry>,
+    /// Maps the index UIDs to the keys of the entries keyed by a write alias rather than by the
+    /// index ID of the index UID, so that shard updates received for an index UID reach them.
+    pub alias_keys: HashMap<IndexUid, HashSet<(IndexId, SourceId)>>,
 }
 
 impl RoutingTable {
@@ -340,6 +589,108 @@ impl RoutingTable {
         self.table.get(&key)
     }
 
+    /// Returns a leader hosting open and available shards for all the given sources, preferably
+    /// this node. Atomic ingest requests must be persisted by a single leader so that it can
+    /// append all their subrequests or none of them.
+    pub fn find_common_leader<'a>(
+        &self,
+        sources: impl IntoIterator<Item = (&'a str, &'a str)>,
+        ingester_pool: &IngesterPool,
+    ) -> Option<NodeId> {
+        let mut common_leaders_opt: Option<BTreeSet<&NodeId>> = None;
+
+        for (index_id, source_id) in sources {
+            let entry = self.find_entry(index_id, source_id)?;
+            let leaders: BTreeSet<&NodeId> = entry.open_shard_leaders(ingester_pool).collect();
+
+            let common_leaders = match common_leaders_opt {
+                Some(common_leaders) => common_leaders.intersection(&leaders).copied().collect(),
+                None => leaders,
+            };
+            if common_leaders.is_empty() {
+                return None;
+            }
+            common_leaders_opt = Some(common_leaders);
+        }
+        let common_leaders = common_leaders_opt?;
+
+        if common_leaders.contains(&self.self_node_id) {
+            return Some(self.self_node_id.clone());
+        }
+        common_leaders.into_iter().next().cloned()
+    }
+
+    /// Returns a snapshot of the open shards of the routing table.
+    pub fn snapshot(&self) -> RoutingTableSnapshot {
+        let entries = self
+            .table
+            .iter()
+            .filter_map(|((index_id, source_id), entry)| {
+                let shards: Vec<Shard> = entry
+                    .local_shards
+                    .iter()
+                    .chain(entry.remote_shards.iter())
+                    .filter(|shard| shard.shard_state.is_open())
+                    .map(RoutingEntry::to_shard)
+                    .collect();
+                if shards.is_empty() {
+                    return None;
+                }
+                let snapshot_entry = RoutingTableSnapshotEntry {
+                    index_id: index_id.clone(),
+                    index_uid: entry.index_uid.clone(),
+                    source_id: source_id.clone(),
+                    shards,
+                };
+                Some(snapshot_entry)
+            })
+            .collect();
+        RoutingTableSnapshot { entries }
+    }
+
+    /// Inserts the entries of the snapshot the routing table does not know about yet and returns
+    /// the number of inserted entries.
+    pub fn restore_snapshot(&mut self, snapshot: RoutingTableSnapshot) -> usize {
+        let mut num_restored_entries = 0;
+
+        for snapshot_entry in snapshot.entries {
+            let key = (snapshot_entry.index_id, snapshot_entry.source_id);
+
+            if let Entry::Vacant(entry) = self.table.entry(key) {
+                let key = entry.key().clone();
+                entry.insert(RoutingTableEntry::new(
+                    &self.self_node_id,
+                    snapshot_entry.index_uid.clone(),
+                    key.1.clone(),
+                    snapshot_entry.shards,
+                ));
+                self.insert_alias_key(snapshot_entry.index_uid, key);
+                num_restored_entries += 1;
+            }
+        }
+        num_restored_entries
+    }
+
+    /// Replaces the routing table entry for the source with the provided shards unless the entry
+    /// already tracks a more recent incarnation of the index. Returns `true` if the entry was
+    /// replaced.
+    pub fn replace_shards_if_newer(
+        &mut self,
+        index_uid: IndexUid,
+        source_id: SourceId,
+        shards: Vec<Shard>,
+    ) -> bool {
+        let index_id: IndexId = index_uid.index_id.to_string();
+
+        if let Some(entry) = self.find_entry(index_id.clone(), source_id.clone()) {
+            if entry.index_uid > index_uid {
+                return false;
+            }
+        }
+        self.replace_shards_for_index_id(index_id, index_uid, source_id, shards);
+        true
+    }
+
     /// Returns `true` if the router already knows about a shard for a given source that has
     /// an available `leader`.
     ///
@@ -372,7 +723,34 @@ impl RoutingTable {
         result
     }
 
+    /// Returns `true` if the ingestion pressure of the source must be refreshed by asking the
+    /// control plane.
+    pub fn should_refresh_ingestion_pressure(
+        &self,
+        index_id: impl Into<IndexId>,
+        source_id: impl Into<SourceId>,
+    ) -> bool {
+        self.find_entry(index_id, source_id)
+            .map(|entry| entry.should_refresh_ingestion_pressure())
+            .unwrap_or(false)
+    }
+
+    /// Records the ingestion pressure of the source advertised by the control plane.
+    pub fn set_ingestion_pressure(
+        &mut self,
+        index_id: impl Into<IndexId>,
+        source_id: impl Into<SourceId>,
+        ingestion_pressure: IngestionPressure,
+    ) {
+        let key = (index_id.into(), source_id.into());
+
+        if let Some(entry) = self.table.get_mut(&key) {
+            entry.set_ingestion_pressure(ingestion_pressure);
+        }
+    }
+
     /// Replaces the routing table entry for the source with the provided shards.
+    #[cfg(test)]
     pub fn replace_shards(
         &mut self,
         index_uid: IndexUid,
@@ -380,32 +758,129 @@ impl RoutingTable {
         shards: Vec<Shard>,
     ) {
         let index_id: IndexId = index_uid.index_id.to_string();
+        self.replace_shards_for_index_id(index_id, index_uid, source_id, shards);
+    }
+
+    /// Replaces the routing table entry for the index ID and source with the provided shards. The
+    /// index ID may differ from the index ID of `index_uid` when it is a write alias.
+    ///
+    /// Updates for an index created before the index currently tracked by the entry are stale and
+    /// ignored.
+    pub fn replace_shards_for_index_id(
+        &mut self,
+        index_id: IndexId,
+        index_uid: IndexUid,
+        source_id: impl Into<SourceId>,
+        shards: Vec<Shard>,
+    ) {
         let source_id: SourceId = source_id.into();
         let key = (index_id, source_id.clone());
 
-        match self.table.entry(key) {
+        match self.table.entry(key.clone()) {
             Entry::Vacant(entry) => {
-                entry.insert(RoutingTableEntry::new(
+                let new_entry = RoutingTableEntry::new(
                     &self.self_node_id,
-                    index_uid,
+                    index_uid.clone(),
                     source_id,
                     shards,
-                ));
+                );
+                entry.insert(new_entry);
             }
             Entry::Occupied(mut entry) => {
-                assert!(
-                    entry.get().index_uid <= index_uid,
-                    "new index incarnation should be greater or equal"
-                );
-
-                entry.insert(RoutingTableEntry::new(
+                // The entry of a write alias moves from one index to another, so the index IDs
+                // cannot be compared. Incarnation IDs are ULIDs, which sort by creation time.
+                let current_index_uid = &entry.get().index_uid;
+
+                if current_index_uid.incarnation_id > index_uid.incarnation_id {
+                    warn!(
+                        current_index_uid=%current_index_uid,
+                        index_uid=%index_uid,
+                        "ignoring stale shards update for `{}/{}`",
+                        key.0,
+                        key.1
+                    );
+                    return;
+                }
+                let new_entry = RoutingTableEntry::new(
                     &self.self_node_id,
-                    index_uid,
+                    index_uid.clone(),
                     source_id,
                     shards,
-                ));
+                );
+                let previous_entry = entry.insert(new_entry);
+
+                if previous_entry.index_uid != index_uid {
+                    self.remove_alias_key(&previous_entry.index_uid, &key);
+                }
             }
         };
+        self.insert_alias_key(index_uid, key);
+    }
+
+    /// Records the key of an entry in the reverse map if the entry is keyed by a write alias.
+    fn insert_alias_key(&mut self, index_uid: IndexUid, key: (IndexId, SourceId)) {
+        if key.0 != index_uid.index_id {
+            self.alias_keys.entry(index_uid).or_default().insert(key);
+        }
+    }
+
+    fn remove_alias_key(&mut self, index_uid: &IndexUid, key: &(IndexId, SourceId)) {
+        if let Some(keys) = self.alias_keys.get_mut(index_uid) {
+            keys.remove(key);
+
+            if keys.is_empty() {
+                self.alias_keys.remove(index_uid);
+            }
+        }
+    }
+
+    /// Returns the version of the shard table of the source last received from the control plane.
+    pub fn shard_table_version(
+        &self,
+        index_id: impl Into<IndexId>,
+        source_id: impl Into<SourceId>,
+    ) -> Option<u64> {
+        self.find_entry(index_id, source_id)
+            .and_then(|entry| entry.shard_table_version)
+    }
+
+    /// Records the version of the shard table of the source received from the control plane. A
+    /// version of 0 means that the update was not versioned.
+    pub fn set_shard_table_version(
+        &mut self,
+        index_id: impl Into<IndexId>,
+        source_id: impl Into<SourceId>,
+        shard_table_version: u64,
+    ) {
+        let key = (index_id.into(), source_id.into());
+
+        if let Some(entry) = self.table.get_mut(&key) {
+            entry.shard_table_version = Some(shard_table_version).filter(|version| *version > 0);
+        }
+    }
+
+    /// Applies a delta of the open shards of the source received from the control plane. Returns
+    /// `false` if the entry does not exist or tracks another incarnation of the index, in which
+    /// case the delta cannot be applied and the entry must be fully refreshed.
+    pub fn apply_open_shards_delta(
+        &mut self,
+        index_id: IndexId,
+        index_uid: &IndexUid,
+        source_id: impl Into<SourceId>,
+        opened_shards: Vec<Shard>,
+        removed_shard_ids: &[ShardId],
+    ) -> bool {
+        let key = (index_id, source_id.into());
+
+        let Some(entry) = self.table.get_mut(&key) else {
+            return false;
+        };
+        if entry.index_uid != *index_uid {
+            entry.shard_table_version = None;
+            return false;
+        }
+        entry.apply_open_shards_delta(&self.self_node_id, opened_shards, removed_shard_ids);
+        true
     }
 
     /// Inserts the shards the routing table is not aware of.
@@ -433,10 +908,10 @@ impl RoutingTable {
         source_id: impl Into<SourceId>,
         shard_ids: &[ShardId],
     ) {
-        let key = (index_uid.index_id.clone(), source_id.into());
-        if let Some(entry) = self.table.get_mut(&key) {
-            entry.close_shards(index_uid, shard_ids);
-        }
+        let source_id: SourceId = source_id.into();
+        self.for_each_entry_mut(index_uid, source_id, |entry| {
+            entry.close_shards(index_uid, shard_ids)
+        });
     }
 
     /// Deletes the targeted shards.
@@ -446,9 +921,40 @@ impl RoutingTable {
         source_id: impl Into<SourceId>,
         shard_ids: &[ShardId],
     ) {
-        let key = (index_uid.index_id.clone(), source_id.into());
-        if let Some(entry) = self.table.get_mut(&key) {
-            entry.delete_shards(index_uid, shard_ids);
+        let source_id: SourceId = source_id.into();
+        self.for_each_entry_mut(index_uid, source_id, |entry| {
+            entry.delete_shards(index_uid, shard_ids)
+        });
+    }
+
+    /// Applies `f` to the entries of the given index UID and source ID: the entry keyed by the
+    /// index ID of the index UID and the entries keyed by the write aliases that resolve to it.
+    fn for_each_entry_mut(
+        &mut self,
+        index_uid: &IndexUid,
+        source_id: SourceId,
+        mut f: impl FnMut(&mut RoutingTableEntry),
+    ) {
+        let alias_keys = self
+            .alias_keys
+            .get(index_uid)
+            .into_iter()
+            .flatten()
+            .filter(|(_, alias_source_id)| *alias_source_id == source_id)
+            .cloned();
+        let keys: Vec<(IndexId, SourceId)> = alias_keys
+            .chain(std::iter::once((
+                index_uid.index_id.clone(),
+                source_id.clone(),
+            )))
+            .collect();
+
+        for key in keys {
+            if let Some(entry) = self.table.get_mut(&key) {
+                if entry.index_uid == *index_uid {
+                    f(entry);
+                }
+            }
         }
     }
 
@@ -573,6 +1079,7 @@ mod tests {
             local_round_robin_idx: AtomicUsize::default(),
             remote_shards: Vec::new(),
             remote_round_robin_idx: AtomicUsize::default(),
+            ..Default::default()
         };
         assert!(table_entry.has_open_shards(
             &ingester_pool,
@@ -614,6 +1121,7 @@ mod tests {
                 },
             ],
             remote_round_robin_idx: AtomicUsize::default(),
+            ..Default::default()
         };
         assert!(table_entry.has_open_shards(
             &ingester_pool,
@@ -668,6 +1176,7 @@ mod tests {
             local_round_robin_idx: AtomicUsize::default(),
             remote_shards: Vec::new(),
             remote_round_robin_idx: AtomicUsize::default(),
+            ..Default::default()
         };
         let shard = table_entry
             .next_open_shard_round_robin(&ingester_pool)
@@ -726,6 +1235,7 @@ mod tests {
                 },
             ],
             remote_round_robin_idx: AtomicUsize::default(),
+            ..Default::default()
         };
         let shard = table_entry
             .next_open_shard_round_robin(&ingester_pool)
@@ -743,6 +1253,303 @@ mod tests {
         assert_eq!(shard.shard_id, ShardId::from(2));
     }
 
+    #[test]
+    fn test_routing_table_entry_next_open_shard_weighted() {
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let source_id: SourceId = "test-source".into();
+        let ingester_pool = IngesterPool::default();
+        ingester_pool.insert("test-ingester-0".into(), IngesterServiceClient::mocked());
+        ingester_pool.insert("test-ingester-1".into(), IngesterServiceClient::mocked());
+        ingester_pool.insert("test-ingester-2".into(), IngesterServiceClient::mocked());
+
+        let routing_entry = |shard_id: u64, leader_id: &str| RoutingEntry {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+            shard_id: ShardId::from(shard_id),
+            shard_state: ShardState::Open,
+            leader_id: leader_id.into(),
+        };
+        let table_entry = RoutingTableEntry {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+            local_shards: vec![routing_entry(1, "test-ingester-0")],
+            remote_shards: vec![
+                routing_entry(2, "test-ingester-1"),
+                routing_entry(3, "test-ingester-1"),
+                routing_entry(4, "test-ingester-2"),
+            ],
+            ..Default::default()
+        };
+        let mut leader_health = LeaderHealthTable::default();
+
+        // All the leaders are healthy: the local shard is picked.
+        for _ in 0..3 {
+            let shard = table_entry
+                .next_open_shard_weighted(&ingester_pool, &leader_health)
+                .unwrap();
+            assert_eq!(shard.shard_id, ShardId::from(1));
+        }
+        // The local leader is degraded: the remote shards are weighted by the health of their
+        // leader.
+        for _ in 0..10 {
+            leader_health.record_persist_error(&"test-ingester-0".into(), Duration::from_secs(1));
+        }
+        leader_health.record_persist_response(
+            &"test-ingester-1".into(),
+            Duration::from_millis(400),
+            1,
+            0,
+        );
+        let mut num_picks_per_leader: HashMap<NodeId, usize> = HashMap::new();
+
+        for _ in 0..1_000 {
+            let shard = table_entry
+                .next_open_shard_weighted(&ingester_pool, &leader_health)
+                .unwrap();
+            *num_picks_per_leader
+                .entry(shard.leader_id.clone())
+                .or_default() += 1;
+        }
+        assert!(!num_picks_per_leader.contains_key("test-ingester-0"));
+
+        // The two shards of `test-ingester-1` weigh 0.25 each, the shard of `test-ingester-2`
+        // weighs 1.
+        let num_picks_ingester_1 = num_picks_per_leader["test-ingester-1"];
+        let num_picks_ingester_2 = num_picks_per_leader["test-ingester-2"];
+        assert_eq!(num_picks_ingester_1 + num_picks_ingester_2, 1_000);
+        assert!((323..=343).contains(&num_picks_ingester_1));
+
+        // Only degraded shards are available: they are still picked.
+        ingester_pool.remove(&"test-ingester-1".into());
+        ingester_pool.remove(&"test-ingester-2".into());
+
+        let shard = table_entry
+            .next_open_shard_weighted(&ingester_pool, &leader_health)
+            .unwrap();
+        assert_eq!(shard.shard_id, ShardId::from(1));
+    }
+
+    #[test]
+    fn test_routing_table_entry_next_open_shard_for_routing_key() {
+        let index_uid: IndexUid = IndexUid::from_parts("test-index", 0);
+        let source_id: SourceId = "test-source".into();
+        let table_entry = RoutingTableEntry::empty(index_uid.clone(), source_id.clone());
+        let ingester_pool = IngesterPool::default();
+
+        let shard_opt = table_entry.next_open_shard_for_routing_key(&ingester_pool, "foo");
+        assert!(shard_opt.is_none());
+
+        ingester_pool.insert("test-ingester-0".into(), IngesterServiceClient::mocked());
+        ingester_pool.insert("test-ingester-1".into(), IngesterServiceClient::mocked());
+
+        let routing_entry = |shard_id: u64, leader_id: &str| RoutingEntry {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+            shard_id: ShardId::from(shard_id),
+            shard_state: ShardState::Open,
+            leader_id: leader_id.into(),
+        };
+        let mut table_entry = RoutingTableEntry {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+            local_shards: (0..4)
+                .map(|shard_id| routing_entry(shard_id, "test-ingester-0"))
+                .collect(),
+            remote_shards: (4..8)
+                .map(|shard_id| routing_entry(shard_id, "test-ingester-1"))
+                .collect(),
+            ..Default::default()
+        };
+        let routing_keys: Vec<String> = (0..100)
+            .map(|key_idx| format!("test-routing-key-{key_idx}"))
+            .collect();
+        let route = |table_entry: &RoutingTableEntry, routing_key: &str| -> ShardId {
+            table_entry
+                .next_open_shard_for_routing_key(&ingester_pool, routing_key)
+                .unwrap()
+                .shard_id
+                .clone()
+        };
+        let shard_ids_before: Vec<ShardId> = routing_keys
+            .iter()
+            .map(|routing_key| route(&table_entry, routing_key))
+            .collect();
+
+        // The same key is consistently routed to the same shard and the keys are spread across the
+        // local and remote shards.
+        for (routing_key, shard_id) in routing_keys.iter().zip(&shard_ids_before) {
+            assert_eq!(route(&table_entry, routing_key), *shard_id);
+        }
+        let num_distinct_shards = shard_ids_before.iter().collect::<HashSet<_>>().len();
+        assert!(num_distinct_shards > 4);
+
+        // Closing a shard only remaps the keys routed to that shard.
+        let closed_shard_id = shard_ids_before[0].clone();
+        table_entry.close_shards(&index_uid, &[closed_shard_id.clone()]);
+
+        for (routing_key, shard_id_before) in routing_keys.iter().zip(&shard_ids_before) {
+            let shard_id_after = route(&table_entry, routing_key);
+            assert_ne!(shard_id_after, closed_shard_id);
+
+            if *shard_id_before != closed_shard_id {
+                assert_eq!(shard_id_after, *shard_id_before);
+            }
+        }
+        // Shards whose leader is unavailable are skipped.
+        ingester_pool.remove(&"test-ingester-1".into());
+
+        for routing_key in &routing_keys {
+            let shard = table_entry
+                .next_open_shard_for_routing_key(&ingester_pool, routing_key)
+                .unwrap();
+            assert_eq!(shard.leader_id, "test-ingester-0");
+        }
+    }
+
+    #[test]
+    fn test_routing_table_find_common_leader() {
+        let self_node_id: NodeId = "test-ingester-0".into();
+        let mut routing_table = RoutingTable {
+            self_node_id: self_node_id.clone(),
+            table: HashMap::default(),
+            alias_keys: HashMap::default(),
+        };
+        let ingester_pool = IngesterPool::default();
+        ingester_pool.insert("test-ingester-0".into(), IngesterServiceClient::mocked());
+        ingester_pool.insert("test-ingester-1".into(), IngesterServiceClient::mocked());
+        ingester_pool.insert("test-ingester-2".into(), IngesterServiceClient::mocked());
+
+        let shard = |index_uid: &IndexUid, shard_id: u64, leader_id: &str| Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: "test-source".to_string(),
+            shard_id: Some(ShardId::from(shard_id)),
+            shard_state: ShardState::Open as i32,
+            leader_id: leader_id.to_string(),
+            ..Default::default()
+        };
+        let index_uid_0: IndexUid = IndexUid::for_test("test-index-0", 0);
+        let index_uid_1: IndexUid = IndexUid::for_test("test-index-1", 0);
+        routing_table.replace_shards(
+            index_uid_0.clone(),
+            "test-source",
+            vec![
+                shard(&index_uid_0, 1, "test-ingester-1"),
+                shard(&index_uid_0, 2, "test-ingester-2"),
+            ],
+        );
+        routing_table.replace_shards(
+            index_uid_1.clone(),
+            "test-source",
+            vec![
+                shard(&index_uid_1, 1, "test-ingester-0"),
+                shard(&index_uid_1, 2, "test-ingester-2"),
+            ],
+        );
+        let sources = [
+            ("test-index-0", "test-source"),
+            ("test-index-1", "test-source"),
+        ];
+        let leader_id = routing_table
+            .find_common_leader(sources, &ingester_pool)
+            .unwrap();
+        assert_eq!(leader_id, "test-ingester-2");
+
+        let shard = routing_table
+            .find_entry("test-index-1", "test-source")
+            .unwrap()
+            .next_open_shard_on_leader(&ingester_pool, &leader_id, None)
+            .unwrap();
+        assert_eq!(shard.shard_id, ShardId::from(2));
+
+        ingester_pool.remove(&"test-ingester-2".into());
+
+        let leader_id_opt = routing_table.find_common_leader(sources, &ingester_pool);
+        assert!(leader_id_opt.is_none());
+
+        let leader_id_opt =
+            routing_table.find_common_leader([("test-index-1", "test-source")], &ingester_pool);
+        assert_eq!(leader_id_opt.unwrap(), self_node_id);
+
+        let leader_id_opt =
+            routing_table.find_common_leader([("test-index-2", "test-source")], &ingester_pool);
+        assert!(leader_id_opt.is_none());
+    }
+
+    #[test]
+    fn test_routing_table_snapshot_restore() {
+        let mut routing_table = RoutingTable {
+            self_node_id: "test-node-0".into(),
+            table: HashMap::default(),
+            alias_keys: HashMap::default(),
+        };
+        let index_uid_0: IndexUid = IndexUid::for_test("test-index", 0);
+        let index_uid_1: IndexUid = IndexUid::for_test("test-index", 1);
+        let shard = |index_uid: &IndexUid, shard_id: u64, shard_state: ShardState| Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: "test-source".to_string(),
+            shard_id: Some(ShardId::from(shard_id)),
+            shard_state: shard_state as i32,
+            leader_id: "test-node-0".to_string(),
+            ..Default::default()
+        };
+        routing_table.replace_shards_for_index_id(
+            "test-alias".to_string(),
+            index_uid_0.clone(),
+            "test-source",
+            vec![
+                shard(&index_uid_0, 1, ShardState::Open),
+                shard(&index_uid_0, 2, ShardState::Open),
+            ],
+        );
+        routing_table.close_shards(&index_uid_0, "test-source", &[ShardId::from(2)]);
+
+        let snapshot = routing_table.snapshot();
+        assert_eq!(snapshot.entries.len(), 1);
+        assert_eq!(snapshot.entries[0].index_id, "test-alias");
+        assert_eq!(snapshot.entries[0].shards.len(), 1);
+        assert_eq!(snapshot.entries[0].shards[0].shard_id(), ShardId::from(1));
+
+        let mut restored_routing_table = RoutingTable {
+            self_node_id: "test-node-0".into(),
+            table: HashMap::default(),
+            alias_keys: HashMap::default(),
+        };
+        assert_eq!(restored_routing_table.restore_snapshot(snapshot), 1);
+
+        let entry = restored_routing_table
+            .find_entry("test-alias", "test-source")
+            .unwrap();
+        assert_eq!(entry.index_uid, index_uid_0);
+        assert_eq!(entry.local_shards.len(), 1);
+        assert_eq!(entry.local_shards[0].shard_id, ShardId::from(1));
+
+        // Restoring a snapshot does not overwrite the entries the routing table already knows.
+        assert_eq!(
+            restored_routing_table.restore_snapshot(routing_table.snapshot()),
+            0
+        );
+
+        let replaced = restored_routing_table.replace_shards_if_newer(
+            index_uid_1.clone(),
+            "test-source".to_string(),
+            vec![shard(&index_uid_1, 3, ShardState::Open)],
+        );
+        assert!(replaced);
+
+        let replaced = restored_routing_table.replace_shards_if_newer(
+            index_uid_0.clone(),
+            "test-source".to_string(),
+            vec![shard(&index_uid_0, 4, ShardState::Open)],
+        );
+        assert!(!replaced);
+
+        let entry = restored_routing_table
+            .find_entry("test-index", "test-source")
+            .unwrap();
+        assert_eq!(entry.index_uid, index_uid_1);
+        assert_eq!(entry.local_shards[0].shard_id, ShardId::from(3));
+    }
+
     #[test]
     fn test_routing_table_entry_insert_open_shards() {
         let index_uid_0: IndexUid = IndexUid::from_parts("test-index", 0);
@@ -914,6 +1721,7 @@ mod tests {
                 },
             ],
             remote_round_robin_idx: AtomicUsize::default(),
+            ..Default::default()
         };
         table_entry.close_shards(
             &index_uid,
@@ -995,6 +1803,7 @@ mod tests {
                 },
             ],
             remote_round_robin_idx: AtomicUsize::default(),
+            ..Default::default()
         };
         table_entry.delete_shards(
             &index_uid,
@@ -1012,4 +1821,217 @@ mod tests {
         assert_eq!(table_entry.remote_shards[0].shard_id, ShardId::from(5));
         assert_eq!(table_entry.remote_shards[1].shard_id, ShardId::from(7));
     }
+
+    #[test]
+    fn test_routing_table_write_alias() {
+        let mut routing_table = RoutingTable {
+            self_node_id: "test-node-0".into(),
+            table: HashMap::default(),
+            alias_keys: HashMap::default(),
+        };
+        let index_uid_0 = IndexUid::for_test("test-logs-000001", 0);
+        let index_uid_1 = IndexUid::for_test("test-logs-000002", 1);
+
+        routing_table.replace_shards_for_index_id(
+            "test-logs".to_string(),
+            index_uid_0.clone(),
+            "test-source",
+            vec![Shard {
+                index_uid: Some(index_uid_0.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(1)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-node-0".to_string(),
+                ..Default::default()
+            }],
+        );
+        let entry = routing_table
+            .find_entry("test-logs", "test-source")
+            .unwrap();
+        assert_eq!(entry.index_uid, index_uid_0);
+        assert!(routing_table
+            .find_entry("test-logs-000001", "test-source")
+            .is_none());
+        assert!(routing_table.alias_keys.contains_key(&index_uid_0));
+
+        routing_table.close_shards(&index_uid_0, "test-source", &[ShardId::from(1)]);
+
+        let entry = routing_table
+            .find_entry("test-logs", "test-source")
+            .unwrap();
+        assert_eq!(entry.local_shards[0].shard_state, ShardState::Closed);
+
+        routing_table.replace_shards_for_index_id(
+            "test-logs".to_string(),
+            index_uid_1.clone(),
+            "test-source",
+            vec![Shard {
+                index_uid: Some(index_uid_1.clone()),
+                source_id: "test-source".to_string(),
+                shard_id: Some(ShardId::from(2)),
+                shard_state: ShardState::Open as i32,
+                leader_id: "test-node-0".to_string(),
+                ..Default::default()
+            }],
+        );
+        let entry = routing_table
+            .find_entry("test-logs", "test-source")
+            .unwrap();
+        assert_eq!(entry.index_uid, index_uid_1);
+
+        assert_eq!(routing_table.alias_keys.len(), 1);
+        assert!(routing_table.alias_keys[&index_uid_1]
+            .contains(&("test-logs".to_string(), "test-source".to_string())));
+
+        // A stale update for the previous write index is ignored.
+        routing_table.replace_shards_for_index_id(
+            "test-logs".to_string(),
+            index_uid_0.clone(),
+            "test-source",
+            Vec::new(),
+        );
+        let entry = routing_table
+            .find_entry("test-logs", "test-source")
+            .unwrap();
+        assert_eq!(entry.index_uid, index_uid_1);
+        assert_eq!(entry.local_shards.len(), 1);
+
+        // Deleting the shards of the previous write index is a no-op.
+        routing_table.delete_shards(&index_uid_0, "test-source", &[ShardId::from(2)]);
+
+        let entry = routing_table
+            .find_entry("test-logs", "test-source")
+            .unwrap();
+        assert_eq!(entry.local_shards.len(), 1);
+
+        routing_table.delete_shards(&index_uid_1, "test-source", &[ShardId::from(2)]);
+
+        let entry = routing_table
+            .find_entry("test-logs", "test-source")
+            .unwrap();
+        assert!(entry.local_shards.is_empty());
+    }
+
+    #[test]
+    fn test_routing_table_apply_open_shards_delta() {
+        let mut routing_table = RoutingTable {
+            self_node_id: "test-node-0".into(),
+            table: HashMap::default(),
+            alias_keys: HashMap::default(),
+        };
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let make_shard = |shard_id: u64, leader_id: &str| Shard {
+            index_uid: Some(index_uid.clone()),
+            source_id: "test-source".to_string(),
+            shard_id: Some(ShardId::from(shard_id)),
+            shard_state: ShardState::Open as i32,
+            leader_id: leader_id.to_string(),
+            ..Default::default()
+        };
+        // The entry does not exist yet.
+        assert!(!routing_table.apply_open_shards_delta(
+            "test-index".to_string(),
+            &index_uid,
+            "test-source",
+            vec![make_shard(1, "test-node-0")],
+            &[],
+        ));
+        routing_table.replace_shards(
+            index_uid.clone(),
+            "test-source",
+            vec![make_shard(1, "test-node-0"), make_shard(2, "test-node-1")],
+        );
+        routing_table.set_shard_table_version("test-index", "test-source", 42);
+        assert_eq!(
+            routing_table.shard_table_version("test-index", "test-source"),
+            Some(42)
+        );
+        assert!(routing_table.apply_open_shards_delta(
+            "test-index".to_string(),
+            &index_uid,
+            "test-source",
+            vec![make_shard(4, "test-node-1"), make_shard(3, "test-node-0")],
+            &[ShardId::from(1)],
+        ));
+        let entry = routing_table
+            .find_entry("test-index", "test-source")
+            .unwrap();
+        assert_eq!(entry.local_shards.len(), 1);
+        assert_eq!(entry.local_shards[0].shard_id, ShardId::from(3));
+        assert_eq!(entry.remote_shards.len(), 2);
+        assert_eq!(entry.remote_shards[0].shard_id, ShardId::from(2));
+        assert_eq!(entry.remote_shards[1].shard_id, ShardId::from(4));
+
+        // Closing shards locally invalidates the version.
+        routing_table.close_shards(&index_uid, "test-source", &[ShardId::from(2)]);
+        assert!(routing_table
+            .shard_table_version("test-index", "test-source")
+            .is_none());
+
+        routing_table.set_shard_table_version("test-index", "test-source", 43);
+
+        // Deltas for another incarnation of the index are rejected.
+        let other_index_uid = IndexUid::for_test("test-index", 1);
+        assert!(!routing_table.apply_open_shards_delta(
+            "test-index".to_string(),
+            &other_index_uid,
+            "test-source",
+            Vec::new(),
+            &[ShardId::from(3)],
+        ));
+        assert!(routing_table
+            .shard_table_version("test-index", "test-source")
+            .is_none());
+        let entry = routing_table
+            .find_entry("test-index", "test-source")
+            .unwrap();
+        assert_eq!(entry.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_routing_table_ingestion_pressure() {
+        let mut routing_table = RoutingTable {
+            self_node_id: "test-node-0".into(),
+            table: HashMap::default(),
+            alias_keys: HashMap::default(),
+        };
+        let index_uid = IndexUid::for_test("test-index", 0);
+
+        routing_table.set_ingestion_pressure("test-index", "test-source", IngestionPressure::High);
+        assert!(routing_table
+            .find_entry("test-index", "test-source")
+            .is_none());
+
+        routing_table.replace_shards(index_uid, "test-source", Vec::new());
+
+        let entry = routing_table
+            .find_entry("test-index", "test-source")
+            .unwrap();
+        assert!(!entry.is_under_high_ingestion_pressure());
+        assert!(routing_table.should_refresh_ingestion_pressure("test-index", "test-source"));
+
+        routing_table.set_ingestion_pressure("test-index", "test-source", IngestionPressure::High);
+
+        let entry = routing_table
+            .find_entry("test-index", "test-source")
+            .unwrap();
+        assert!(entry.is_under_high_ingestion_pressure());
+        assert!(!routing_table.should_refresh_ingestion_pressure("test-index", "test-source"));
+
+        tokio::time::sleep(INGESTION_PRESSURE_TTL * 2).await;
+
+        let entry = routing_table
+            .find_entry("test-index", "test-source")
+            .unwrap();
+        assert!(!entry.is_under_high_ingestion_pressure());
+        assert!(routing_table.should_refresh_ingestion_pressure("test-index", "test-source"));
+
+        routing_table.set_ingestion_pressure("test-index", "test-source", IngestionPressure::Low);
+        assert!(!routing_table.should_refresh_ingestion_pressure("test-index", "test-source"));
+
+        // A low ingestion pressure expires too, so that the router notices when the source
+        // becomes saturated.
+        tokio::time::sleep(INGESTION_PRESSURE_TTL * 2).await;
+        assert!(routing_table.should_refresh_ingestion_pressure("test-index", "test-source"));
+    }
 }
@@ -0,0 +1,132 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+use quickwit_proto::ingest::Shard;
+use quickwit_proto::types::{IndexId, IndexUid, SourceId};
+use serde::{Deserialize, Serialize};
+
+/// Copy of the open shards of the routing table that the router persists to disk in order to warm
+/// start after a restart.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct RoutingTableSnapshot {
+    pub entries: Vec<RoutingTableSnapshotEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct RoutingTableSnapshotEntry {
+    /// Index ID of the entry, which may be a write alias.
+    pub index_id: IndexId,
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    pub shards: Vec<Shard>,
+}
+
+impl RoutingTableSnapshot {
+    /// Loads the snapshot stored at `path`. Returns `None` if the file does not exist.
+    pub async fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        let snapshot_json = match tokio::fs::read(path).await {
+            Ok(snapshot_json) => snapshot_json,
+            Err(io_error) if io_error.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(io_error) => {
+                return Err(io_error).context("failed to read routing table snapshot");
+            }
+        };
+        let snapshot: Self = serde_json::from_slice(&snapshot_json)
+            .context("failed to deserialize routing table snapshot")?;
+        Ok(Some(snapshot))
+    }
+
+    /// Saves the snapshot to `path`. The snapshot is first written to a temporary file, which is
+    /// then renamed, so a crash never leaves a truncated snapshot behind.
+    pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let snapshot_json =
+            serde_json::to_vec(self).context("failed to serialize routing table snapshot")?;
+
+        if let Some(parent_dir) = path.parent() {
+            tokio::fs::create_dir_all(parent_dir).await?;
+        }
+        let temp_path = path.with_extension("tmp");
+        tokio::fs::write(&temp_path, snapshot_json)
+            .await
+            .context("failed to write routing table snapshot")?;
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .context("failed to write routing table snapshot")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::ingest::ShardState;
+    use quickwit_proto::types::ShardId;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_routing_table_snapshot_save_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let snapshot_path = temp_dir.path().join("routing-table.json");
+
+        let snapshot_opt = RoutingTableSnapshot::load(&snapshot_path).await.unwrap();
+        assert!(snapshot_opt.is_none());
+
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 0);
+        let snapshot = RoutingTableSnapshot {
+            entries: vec![RoutingTableSnapshotEntry {
+                index_id: "test-alias".to_string(),
+                index_uid: index_uid.clone(),
+                source_id: "test-source".to_string(),
+                shards: vec![Shard {
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "test-source".to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    shard_state: ShardState::Open as i32,
+                    leader_id: "test-ingester".to_string(),
+                    ..Default::default()
+                }],
+            }],
+        };
+        snapshot.save(&snapshot_path).await.unwrap();
+
+        let snapshot = RoutingTableSnapshot::load(&snapshot_path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.entries.len(), 1);
+
+        let entry = &snapshot.entries[0];
+        assert_eq!(entry.index_id, "test-alias");
+        assert_eq!(entry.index_uid, index_uid);
+        assert_eq!(entry.source_id, "test-source");
+        assert_eq!(entry.shards.len(), 1);
+        assert_eq!(entry.shards[0].shard_id(), ShardId::from(1));
+
+        tokio::fs::write(&snapshot_path, b"not json").await.unwrap();
+        RoutingTableSnapshot::load(&snapshot_path)
+            .await
+            .unwrap_err();
+    }
+}
@@ -32,12 +32,15 @@ use quickwit_proto::ingest::ingester::IngesterStatus;
 use quickwit_proto::ingest::{IngestV2Error, IngestV2Result, ShardState};
 use quickwit_proto::types::{Position, QueueId};
 use tokio::sync::{watch, Mutex, MutexGuard, RwLock, RwLockMappedWriteGuard, RwLockWriteGuard};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use super::models::IngesterShard;
 use super::rate_meter::RateMeter;
 use super::replication::{ReplicationStreamTaskHandle, ReplicationTaskHandle};
-use crate::ingest_v2::mrecordlog_utils::{force_delete_queue, queue_position_range};
+use crate::ingest_v2::mrecordlog_utils::{
+    append_rollback_record, force_delete_queue, queue_position_range, replay_idempotency_keys,
+    rollback_position,
+};
 use crate::mrecordlog_async::MultiRecordLogAsync;
 use crate::{FollowerId, LeaderId};
 
@@ -174,19 +177,27 @@ impl IngesterState {
 
         for queue_id in queue_ids {
             if let Some(position_range) = queue_position_range(&mrecordlog, &queue_id) {
-                // The queue is not empty: recover it.
-                let replication_position_inclusive = Position::offset(*position_range.end());
+                // The queue is not empty: recover it. The records of a shard rolled back before the
+                // restart must remain out of reach of the fetch streams.
+                let replication_position_inclusive = rollback_position(&mrecordlog, &queue_id)
+                    .unwrap_or_else(|| Position::offset(*position_range.end()));
                 let truncation_position_inclusive = if *position_range.start() == 0 {
                     Position::Beginning
                 } else {
                     Position::offset(*position_range.start() - 1)
                 };
-                let solo_shard = IngesterShard::new_solo(
+                let mut solo_shard = IngesterShard::new_solo(
                     ShardState::Closed,
                     replication_position_inclusive,
                     truncation_position_inclusive,
                     now,
                 );
+                // Replays of the batches persisted before the restart must still be deduplicated.
+                replay_idempotency_keys(&mrecordlog, &queue_id, &mut solo_shard.dedup_window);
+                solo_shard
+                    .dedup_window
+                    .forget_after(&solo_shard.replication_position_inclusive);
+
                 inner_guard.shards.insert(queue_id.clone(), solo_shard);
 
                 let rate_limiter = RateLimiter::from_settings(rate_limiter_settings);
@@ -350,6 +361,23 @@ impl FullyLockedIngesterState<'_> {
         };
     }
 
+    /// Rolls the shard identified by `queue_id` back to `position_inclusive` and closes it. A
+    /// rollback record is appended to the WAL queue so that the rollback survives a restart of the
+    /// ingester.
+    pub async fn rollback_shard(&mut self, queue_id: &QueueId, position_inclusive: Position) {
+        let Some(shard) = self.inner.shards.get_mut(queue_id) else {
+            return;
+        };
+        shard.rollback(position_inclusive.clone());
+        warn!("rolled back shard `{queue_id}` to {position_inclusive}");
+
+        if let Err(append_error) =
+            append_rollback_record(&mut self.mrecordlog, queue_id, &position_inclusive).await
+        {
+            error!("failed to append rollback record to shard `{queue_id}`: {append_error}");
+        }
+    }
+
     /// Truncates the shard identified by `queue_id` up to `truncate_up_to_position_inclusive` only
     /// if the current truncation position of the shard is smaller.
     pub async fn truncate_shard(
@@ -432,6 +460,7 @@ mod tests {
     use tokio::time::timeout;
 
     use super::*;
+    use crate::ingest_v2::mrecord::MRecord;
 
     #[tokio::test]
     async fn test_ingester_state_does_not_lock_while_initializing() {
@@ -480,4 +509,70 @@ mod tests {
         assert_eq!(locked_state.status(), IngesterStatus::Ready);
         assert_eq!(*locked_state.status_tx.borrow(), IngesterStatus::Ready);
     }
+
+    #[tokio::test]
+    async fn test_ingester_state_rollback_shard() {
+        let (temp_dir, state) = IngesterState::for_test().await;
+        let mut state_guard = state.lock_fully().await.unwrap();
+
+        let queue_id = "test-queue".to_string();
+        state_guard
+            .mrecordlog
+            .create_queue(&queue_id)
+            .await
+            .unwrap();
+
+        let records = [
+            MRecord::new_doc("test-doc-foo").encode(),
+            MRecord::IdempotencyKey("test-key-foo".to_string()).encode(),
+            MRecord::new_doc("test-doc-bar").encode(),
+            MRecord::IdempotencyKey("test-key-bar".to_string()).encode(),
+        ]
+        .into_iter();
+
+        state_guard
+            .mrecordlog
+            .append_records(&queue_id, None, records)
+            .await
+            .unwrap();
+
+        let mut solo_shard = IngesterShard::new_solo(
+            ShardState::Open,
+            Position::offset(3u64),
+            Position::Beginning,
+            Instant::now(),
+        );
+        solo_shard
+            .dedup_window
+            .insert("test-key-foo".to_string(), Position::offset(1u64));
+        solo_shard
+            .dedup_window
+            .insert("test-key-bar".to_string(), Position::offset(3u64));
+        state_guard.shards.insert(queue_id.clone(), solo_shard);
+
+        state_guard
+            .rollback_shard(&queue_id, Position::offset(1u64))
+            .await;
+
+        let solo_shard = state_guard.shards.get(&queue_id).unwrap();
+        solo_shard.assert_is_closed();
+        solo_shard.assert_replication_position(Position::offset(1u64));
+        assert!(solo_shard.dedup_window.get("test-key-foo").is_some());
+        assert!(solo_shard.dedup_window.get("test-key-bar").is_none());
+
+        state_guard.set_status(IngesterStatus::Initializing);
+        drop(state_guard);
+
+        state
+            .init(temp_dir.path(), RateLimiterSettings::default())
+            .await;
+
+        let state_guard = state.lock_fully().await.unwrap();
+
+        let solo_shard = state_guard.shards.get(&queue_id).unwrap();
+        solo_shard.assert_is_closed();
+        solo_shard.assert_replication_position(Position::offset(1u64));
+        assert!(solo_shard.dedup_window.get("test-key-foo").is_some());
+        assert!(solo_shard.dedup_window.get("test-key-bar").is_none());
+    }
 }
@@ -0,0 +1,1218 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::{BufMut, BytesMut};
+use bytesize::ByteSize;
+use mrecordlog::Record;
+use prost::Message;
+use quickwit_proto::ingest::{IngestV2Error, IngestV2Result, MRecordBatch};
+use quickwit_proto::types::{Position, QueueId};
+use quickwit_storage::{Storage, StorageErrorKind};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::models::IngesterShard;
+use super::state::{IngesterState, WeakIngesterState};
+use crate::with_lock_metrics;
+
+const RUN_INTERVAL_PERIOD: Duration = if cfg!(test) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(5)
+};
+
+/// Period at which the followers check whether the leaders of their replica shards have
+/// offloaded some records.
+const REPLICA_SYNC_PERIOD: Duration = if cfg!(test) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(30)
+};
+
+/// Maximum size of the segments uploaded to object storage.
+const MAX_SEGMENT_NUM_BYTES: usize = if cfg!(test) { 64 } else { 64 * 1024 * 1024 };
+
+/// Maximum total size of the offloaded segments cached in memory to serve the fetch streams.
+const MAX_SEGMENT_CACHE_NUM_BYTES: usize = if cfg!(test) { 64 } else { 256 * 1024 * 1024 };
+
+/// Lists the segments offloaded for a shard, ordered by position.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OffloadManifest {
+    segments: Vec<OffloadedSegment>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct OffloadedSegment {
+    first_position: u64,
+    last_position: u64,
+}
+
+impl OffloadedSegment {
+    fn path(&self, queue_id: &str) -> PathBuf {
+        Path::new(queue_id).join(format!(
+            "{:020}-{:020}",
+            self.first_position, self.last_position
+        ))
+    }
+}
+
+fn manifest_path(queue_id: &str) -> PathBuf {
+    Path::new(queue_id).join("manifest.json")
+}
+
+/// Decodes an offloaded segment and checks that it holds exactly the records of the positions it
+/// covers.
+fn decode_segment(
+    queue_id: &str,
+    segment: &OffloadedSegment,
+    segment_bytes: &[u8],
+) -> IngestV2Result<MRecordBatch> {
+    let segment_batch = MRecordBatch::decode(segment_bytes).map_err(|error| {
+        IngestV2Error::Internal(format!(
+            "failed to decode offloaded segment of shard `{queue_id}`: {error}"
+        ))
+    })?;
+    let expected_num_records = (segment.last_position - segment.first_position + 1) as usize;
+    let num_bytes: usize = segment_batch
+        .mrecord_lengths
+        .iter()
+        .map(|mrecord_length| *mrecord_length as usize)
+        .sum();
+
+    if segment_batch.mrecord_lengths.len() != expected_num_records
+        || num_bytes != segment_batch.mrecord_buffer.len()
+    {
+        return Err(IngestV2Error::Internal(format!(
+            "offloaded segment `{}` of shard `{queue_id}` is corrupted: expected {} records, got \
+             {} records totaling {num_bytes} bytes in a buffer of {} bytes",
+            segment.path(queue_id).display(),
+            expected_num_records,
+            segment_batch.mrecord_lengths.len(),
+            segment_batch.mrecord_buffer.len(),
+        )));
+    }
+    Ok(segment_batch)
+}
+
+/// Keeps in memory the offloaded segments being read by the fetch streams, so that consecutive
+/// fetches of the same segment do not download the manifest and the segment over and over. Each
+/// shard has at most one cached segment, which is evicted once it has been read to the end. The
+/// least recently read segments are evicted when the cache is full.
+#[derive(Default)]
+struct SegmentCache {
+    segments: HashMap<QueueId, CachedSegment>,
+    num_bytes: usize,
+}
+
+struct CachedSegment {
+    segment: OffloadedSegment,
+    segment_batch: MRecordBatch,
+    last_access: Instant,
+}
+
+impl SegmentCache {
+    fn get(&mut self, queue_id: &str, position: u64) -> Option<(OffloadedSegment, MRecordBatch)> {
+        let cached_segment = self.segments.get_mut(queue_id)?;
+
+        if !(cached_segment.segment.first_position..=cached_segment.segment.last_position)
+            .contains(&position)
+        {
+            return None;
+        }
+        cached_segment.last_access = Instant::now();
+        Some((cached_segment.segment, cached_segment.segment_batch.clone()))
+    }
+
+    fn insert(&mut self, queue_id: &str, segment: OffloadedSegment, segment_batch: MRecordBatch) {
+        self.remove(queue_id);
+
+        let segment_num_bytes = segment_batch.mrecord_buffer.len();
+
+        if segment_num_bytes > MAX_SEGMENT_CACHE_NUM_BYTES {
+            return;
+        }
+        while self.num_bytes + segment_num_bytes > MAX_SEGMENT_CACHE_NUM_BYTES {
+            let Some(lru_queue_id) = self
+                .segments
+                .iter()
+                .min_by_key(|(_, cached_segment)| cached_segment.last_access)
+                .map(|(queue_id, _)| queue_id.clone())
+            else {
+                break;
+            };
+            self.remove(&lru_queue_id);
+        }
+        self.num_bytes += segment_num_bytes;
+
+        let cached_segment = CachedSegment {
+            segment,
+            segment_batch,
+            last_access: Instant::now(),
+        };
+        self.segments.insert(queue_id.to_string(), cached_segment);
+    }
+
+    fn remove(&mut self, queue_id: &str) {
+        if let Some(cached_segment) = self.segments.remove(queue_id) {
+            self.num_bytes -= cached_segment.segment_batch.mrecord_buffer.len();
+        }
+    }
+}
+
+/// Tiers the WAL of the ingesters to object storage: when the disk usage of the WAL exceeds a
+/// threshold, or once the records reach a maximum local age, the records not yet consumed are
+/// uploaded to object storage and truncated locally. The fetch streams then serve them from the
+/// offloaded copy.
+///
+/// Each shard has its own directory holding its segments, i.e. the records of a contiguous range
+/// of positions encoded as an [`MRecordBatch`], and a manifest listing them. Only the primary and
+/// solo shards are offloaded. The followers read the manifests of their replica shards and
+/// truncate the records offloaded by the leaders, so that after the loss of a leader, the shard is
+/// replayed from object storage rather than from the local disk of the follower.
+#[derive(Clone)]
+pub struct WalOffload {
+    storage: Arc<dyn Storage>,
+    disk_usage_threshold: ByteSize,
+    max_local_age_opt: Option<Duration>,
+    segment_cache: Arc<Mutex<SegmentCache>>,
+}
+
+impl fmt::Debug for WalOffload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WalOffload")
+            .field("uri", self.storage.uri())
+            .field("disk_usage_threshold", &self.disk_usage_threshold)
+            .field("max_local_age", &self.max_local_age_opt)
+            .finish()
+    }
+}
+
+impl WalOffload {
+    pub fn new(storage: Arc<dyn Storage>, disk_usage_threshold: ByteSize) -> Self {
+        Self {
+            storage,
+            disk_usage_threshold,
+            max_local_age_opt: None,
+            segment_cache: Arc::default(),
+        }
+    }
+
+    /// Offloads the records older than `max_local_age` regardless of the disk usage of the WAL.
+    pub fn with_max_local_age(mut self, max_local_age_opt: Option<Duration>) -> Self {
+        self.max_local_age_opt = max_local_age_opt;
+        self
+    }
+
+    async fn load_manifest(&self, queue_id: &str) -> IngestV2Result<OffloadManifest> {
+        match self.storage.get_all(&manifest_path(queue_id)).await {
+            Ok(manifest_bytes) => {
+                serde_json::from_slice(manifest_bytes.as_slice()).map_err(|error| {
+                    IngestV2Error::Internal(format!(
+                        "failed to deserialize offload manifest of shard `{queue_id}`: {error}"
+                    ))
+                })
+            }
+            Err(error) if error.kind() == StorageErrorKind::NotFound => {
+                Ok(OffloadManifest::default())
+            }
+            Err(error) => Err(IngestV2Error::Internal(format!(
+                "failed to load offload manifest of shard `{queue_id}`: {error}"
+            ))),
+        }
+    }
+
+    async fn save_manifest(
+        &self,
+        queue_id: &str,
+        manifest: &OffloadManifest,
+    ) -> IngestV2Result<()> {
+        let manifest_path = manifest_path(queue_id);
+
+        let save_result = if manifest.segments.is_empty() {
+            self.storage.delete(&manifest_path).await
+        } else {
+            let manifest_json =
+                serde_json::to_vec(manifest).expect("offload manifest should be JSON serializable");
+            self.storage
+                .put(&manifest_path, Box::new(manifest_json))
+                .await
+        };
+        save_result.map_err(|error| {
+            IngestV2Error::Internal(format!(
+                "failed to save offload manifest of shard `{queue_id}`: {error}"
+            ))
+        })
+    }
+
+    /// Uploads a segment, then registers it in the manifest of the shard. The segment must start
+    /// right after the last segment of the manifest, if any.
+    async fn offload_segment(
+        &self,
+        queue_id: &str,
+        manifest: &mut OffloadManifest,
+        segment: OffloadedSegment,
+        mrecord_batch: MRecordBatch,
+    ) -> IngestV2Result<()> {
+        let segment_bytes = mrecord_batch.encode_to_vec();
+
+        self.storage
+            .put(&segment.path(queue_id), Box::new(segment_bytes))
+            .await
+            .map_err(|error| {
+                IngestV2Error::Internal(format!(
+                    "failed to offload segment of shard `{queue_id}`: {error}"
+                ))
+            })?;
+        manifest.segments.push(segment);
+        self.save_manifest(queue_id, manifest).await
+    }
+
+    /// Fetches the offloaded records of a shard starting at `from_position_inclusive`. The batch
+    /// holds at least one record and is at most `max_num_bytes` long otherwise. Returns `None` if
+    /// no offloaded segment contains the position.
+    pub(super) async fn fetch(
+        &self,
+        queue_id: &str,
+        from_position_inclusive: u64,
+        max_num_bytes: usize,
+    ) -> IngestV2Result<Option<MRecordBatch>> {
+        let cached_segment_opt = self
+            .segment_cache
+            .lock()
+            .expect("lock should not be poisoned")
+            .get(queue_id, from_position_inclusive);
+
+        let (segment, segment_batch) = if let Some(cached_segment) = cached_segment_opt {
+            cached_segment
+        } else {
+            let manifest = self.load_manifest(queue_id).await?;
+
+            let Some(segment) = manifest.segments.into_iter().find(|segment| {
+                (segment.first_position..=segment.last_position).contains(&from_position_inclusive)
+            }) else {
+                return Ok(None);
+            };
+            let segment_bytes = self
+                .storage
+                .get_all(&segment.path(queue_id))
+                .await
+                .map_err(|error| {
+                    IngestV2Error::Internal(format!(
+                        "failed to fetch offloaded segment of shard `{queue_id}`: {error}"
+                    ))
+                })?;
+            let segment_batch = decode_segment(queue_id, &segment, segment_bytes.as_slice())?;
+            (segment, segment_batch)
+        };
+        // The segment holds exactly one record per position, so the position is within bounds.
+        let num_skipped_records = (from_position_inclusive - segment.first_position) as usize;
+
+        let start_offset: usize = segment_batch.mrecord_lengths[..num_skipped_records]
+            .iter()
+            .map(|mrecord_length| *mrecord_length as usize)
+            .sum();
+        let mut end_offset = start_offset;
+        let mut mrecord_lengths = Vec::new();
+
+        for mrecord_length in &segment_batch.mrecord_lengths[num_skipped_records..] {
+            let mrecord_length = *mrecord_length as usize;
+
+            if !mrecord_lengths.is_empty()
+                && end_offset - start_offset + mrecord_length > max_num_bytes
+            {
+                break;
+            }
+            end_offset += mrecord_length;
+            mrecord_lengths.push(mrecord_length as u32);
+        }
+        let mrecord_batch = MRecordBatch {
+            mrecord_buffer: segment_batch.mrecord_buffer.slice(start_offset..end_offset),
+            mrecord_lengths,
+        };
+        let mut segment_cache = self
+            .segment_cache
+            .lock()
+            .expect("lock should not be poisoned");
+
+        if end_offset == segment_batch.mrecord_buffer.len() {
+            // The next fetch will read the next segment.
+            segment_cache.remove(queue_id);
+        } else {
+            segment_cache.insert(queue_id, segment, segment_batch);
+        }
+        Ok(Some(mrecord_batch))
+    }
+
+    /// Deletes the segments whose records have all been consumed, i.e. truncated by the indexing
+    /// pipelines up to `truncation_position_inclusive`.
+    async fn delete_segments(
+        &self,
+        queue_id: &str,
+        manifest: &mut OffloadManifest,
+        truncation_position_inclusive: u64,
+    ) -> IngestV2Result<()> {
+        let num_consumed_segments = manifest
+            .segments
+            .iter()
+            .take_while(|segment| segment.last_position <= truncation_position_inclusive)
+            .count();
+
+        if num_consumed_segments == 0 {
+            return Ok(());
+        }
+        self.segment_cache
+            .lock()
+            .expect("lock should not be poisoned")
+            .remove(queue_id);
+
+        let segment_paths: Vec<PathBuf> = manifest.segments[..num_consumed_segments]
+            .iter()
+            .map(|segment| segment.path(queue_id))
+            .collect();
+        let segment_path_refs: Vec<&Path> = segment_paths.iter().map(PathBuf::as_path).collect();
+
+        self.storage
+            .bulk_delete(&segment_path_refs)
+            .await
+            .map_err(|error| {
+                IngestV2Error::Internal(format!(
+                    "failed to delete offloaded segments of shard `{queue_id}`: {error:?}"
+                ))
+            })?;
+        manifest.segments.drain(..num_consumed_segments);
+        self.save_manifest(queue_id, manifest).await
+    }
+}
+
+/// Returns the position of the last record appended to the shard at or before `cutoff`.
+fn last_position_appended_before(shard: &IngesterShard, cutoff: Instant) -> Option<u64> {
+    shard
+        .append_instants
+        .iter()
+        .take_while(|(_, append_instant)| *append_instant <= cutoff)
+        .last()
+        .and_then(|(position, _)| position.as_u64())
+}
+
+/// Periodically offloads and truncates the shards when the disk usage of the WAL exceeds the
+/// threshold of the [`WalOffload`] or when their records reach the maximum local age, truncates
+/// the replica shards offloaded by their leader, and deletes the offloaded segments once they are
+/// consumed.
+pub(super) struct OffloadWalTask {
+    weak_state: WeakIngesterState,
+    wal_offload: WalOffload,
+    // Manifests of the shards offloaded by this task, which is their only writer.
+    manifests: HashMap<QueueId, OffloadManifest>,
+    // Replica shards truncated after their leader offloaded them.
+    offloaded_replicas: HashSet<QueueId>,
+    last_replica_sync_opt: Option<Instant>,
+}
+
+impl OffloadWalTask {
+    pub fn spawn(weak_state: WeakIngesterState, wal_offload: WalOffload) -> JoinHandle<()> {
+        let mut task = Self {
+            weak_state,
+            wal_offload,
+            manifests: HashMap::new(),
+            offloaded_replicas: HashSet::new(),
+            last_replica_sync_opt: None,
+        };
+        tokio::spawn(async move {
+            let Some(mut state) = task.weak_state.upgrade() else {
+                return;
+            };
+            state.wait_for_ready().await;
+            drop(state);
+
+            task.run().await
+        })
+    }
+
+    async fn run(&mut self) {
+        let mut interval = tokio::time::interval(RUN_INTERVAL_PERIOD);
+
+        loop {
+            interval.tick().await;
+
+            let Some(state) = self.weak_state.upgrade() else {
+                return;
+            };
+            self.delete_consumed_segments(&state).await;
+            self.offload_shards(&state).await;
+
+            if self
+                .last_replica_sync_opt
+                .map_or(true, |last_replica_sync| {
+                    last_replica_sync.elapsed() >= REPLICA_SYNC_PERIOD
+                })
+            {
+                self.truncate_offloaded_replicas(&state).await;
+                self.last_replica_sync_opt = Some(Instant::now());
+            }
+        }
+    }
+
+    async fn delete_consumed_segments(&mut self, state: &IngesterState) {
+        if self.manifests.is_empty() {
+            return;
+        }
+        let Ok(state_guard) =
+            with_lock_metrics!(state.lock_partially(), "offload_wal", "read").await
+        else {
+            return;
+        };
+        // The segments of the deleted shards are no longer needed.
+        let truncation_positions: Vec<(QueueId, u64)> = self
+            .manifests
+            .keys()
+            .filter_map(|queue_id| {
+                let truncation_position_inclusive = match state_guard.shards.get(queue_id) {
+                    Some(shard) => shard.truncation_position_inclusive.as_u64()?,
+                    None => u64::MAX,
+                };
+                Some((queue_id.clone(), truncation_position_inclusive))
+            })
+            .collect();
+        drop(state_guard);
+
+        for (queue_id, truncation_position_inclusive) in truncation_positions {
+            let manifest = self
+                .manifests
+                .get_mut(&queue_id)
+                .expect("manifest should exist");
+
+            if let Err(error) = self
+                .wal_offload
+                .delete_segments(&queue_id, manifest, truncation_position_inclusive)
+                .await
+            {
+                warn!("{error}");
+                continue;
+            }
+            if manifest.segments.is_empty() {
+                self.manifests.remove(&queue_id);
+            }
+        }
+    }
+
+    async fn offload_shards(&mut self, state: &IngesterState) {
+        let Ok(state_guard) = with_lock_metrics!(state.lock_fully(), "offload_wal", "write").await
+        else {
+            return;
+        };
+        let disk_used = ByteSize(state_guard.mrecordlog.resource_usage().disk_used_bytes as u64);
+        let is_disk_usage_exceeded = disk_used >= self.wal_offload.disk_usage_threshold;
+
+        // When the disk usage is below the threshold, only the records older than the maximum
+        // local age are offloaded.
+        let age_cutoff_opt = if is_disk_usage_exceeded {
+            None
+        } else {
+            let Some(age_cutoff) = self
+                .wal_offload
+                .max_local_age_opt
+                .and_then(|max_local_age| Instant::now().checked_sub(max_local_age))
+            else {
+                return;
+            };
+            Some(age_cutoff)
+        };
+        // The last record of each shard is kept locally so that the WAL queue is never empty and
+        // survives a restart.
+        let mut candidates: Vec<(QueueId, u64, u64)> = state_guard
+            .shards
+            .iter()
+            .filter(|(_, shard)| !shard.is_replica())
+            .filter_map(|(queue_id, shard)| {
+                let offload_from_position_inclusive = shard
+                    .truncation_position_inclusive
+                    .clone()
+                    .max(shard.offloaded_position_inclusive.clone())
+                    .as_u64()
+                    .map(|offset| offset + 1)
+                    .unwrap_or_default();
+                let mut offload_to_position_inclusive = shard
+                    .replication_position_inclusive
+                    .as_u64()?
+                    .checked_sub(1)?;
+
+                if let Some(age_cutoff) = age_cutoff_opt {
+                    offload_to_position_inclusive = offload_to_position_inclusive
+                        .min(last_position_appended_before(shard, age_cutoff)?);
+                }
+                if offload_from_position_inclusive > offload_to_position_inclusive {
+                    return None;
+                }
+                Some((
+                    queue_id.clone(),
+                    offload_from_position_inclusive,
+                    offload_to_position_inclusive,
+                ))
+            })
+            .collect();
+        drop(state_guard);
+
+        if candidates.is_empty() {
+            return;
+        }
+        if is_disk_usage_exceeded {
+            warn!(
+                "WAL disk usage ({disk_used}) exceeds offload threshold ({}): offloading {} \
+                 shard(s)",
+                self.wal_offload.disk_usage_threshold,
+                candidates.len()
+            );
+        } else {
+            debug!(
+                "offloading the records older than the max local age of {} shard(s)",
+                candidates.len()
+            );
+        }
+        // Offload the most lagging shards first.
+        candidates.sort_unstable_by_key(|(_, from_position_inclusive, to_position_inclusive)| {
+            Reverse(to_position_inclusive - from_position_inclusive)
+        });
+
+        for (queue_id, from_position_inclusive, to_position_inclusive) in candidates {
+            if let Err(error) = self
+                .offload_shard(
+                    state,
+                    &queue_id,
+                    from_position_inclusive,
+                    to_position_inclusive,
+                )
+                .await
+            {
+                warn!("{error}");
+                continue;
+            }
+            let mrecordlog = state.mrecordlog();
+            let mrecordlog_guard =
+                with_lock_metrics!(mrecordlog.read().await, "offload_wal", "read");
+
+            let Some(disk_used_bytes) = mrecordlog_guard
+                .as_ref()
+                .map(|mrecordlog| mrecordlog.resource_usage().disk_used_bytes)
+            else {
+                return;
+            };
+            if is_disk_usage_exceeded
+                && ByteSize(disk_used_bytes as u64) < self.wal_offload.disk_usage_threshold
+            {
+                break;
+            }
+        }
+    }
+
+    /// Uploads the records of a shard within the range of positions as one or several segments,
+    /// then truncates them locally.
+    async fn offload_shard(
+        &mut self,
+        state: &IngesterState,
+        queue_id: &QueueId,
+        from_position_inclusive: u64,
+        to_position_inclusive: u64,
+    ) -> IngestV2Result<()> {
+        let manifest = match self.manifests.entry(queue_id.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let manifest = self.wal_offload.load_manifest(queue_id).await?;
+                entry.insert(manifest)
+            }
+        };
+        let mrecordlog = state.mrecordlog();
+        let mut next_position_inclusive = from_position_inclusive;
+
+        while next_position_inclusive <= to_position_inclusive {
+            let mut mrecord_buffer = BytesMut::new();
+            let mut mrecord_lengths = Vec::new();
+            let mut segment_opt: Option<OffloadedSegment> = None;
+
+            let mrecordlog_guard =
+                with_lock_metrics!(mrecordlog.read().await, "offload_wal", "read");
+
+            let Some(Ok(records)) = mrecordlog_guard.as_ref().map(|mrecordlog| {
+                mrecordlog.range(queue_id, next_position_inclusive..=to_position_inclusive)
+            }) else {
+                // The shard was deleted in the meantime.
+                return Ok(());
+            };
+            for Record { position, payload } in records {
+                if !mrecord_lengths.is_empty()
+                    && mrecord_buffer.len() + payload.len() > MAX_SEGMENT_NUM_BYTES
+                {
+                    break;
+                }
+                mrecord_buffer.put(&*payload);
+                mrecord_lengths.push(payload.len() as u32);
+
+                let segment = segment_opt.get_or_insert(OffloadedSegment {
+                    first_position: position,
+                    last_position: position,
+                });
+                segment.last_position = position;
+            }
+            drop(mrecordlog_guard);
+
+            let Some(segment) = segment_opt else {
+                // The records were truncated by the indexing pipeline in the meantime.
+                break;
+            };
+            let mrecord_batch = MRecordBatch {
+                mrecord_buffer: mrecord_buffer.freeze(),
+                mrecord_lengths,
+            };
+            self.wal_offload
+                .offload_segment(queue_id, manifest, segment, mrecord_batch)
+                .await?;
+            next_position_inclusive = segment.last_position + 1;
+        }
+        if next_position_inclusive == from_position_inclusive {
+            return Ok(());
+        }
+        let offloaded_position_inclusive = next_position_inclusive - 1;
+        truncate_offloaded_shard(state, queue_id, offloaded_position_inclusive).await
+    }
+
+    /// Truncates the records of the replica shards up to the last position offloaded by their
+    /// leader, and deletes the offloaded segments of the replica shards that no longer exist.
+    async fn truncate_offloaded_replicas(&mut self, state: &IngesterState) {
+        let Ok(state_guard) =
+            with_lock_metrics!(state.lock_partially(), "offload_wal", "read").await
+        else {
+            return;
+        };
+        let candidates: Vec<(QueueId, u64, u64)> = state_guard
+            .shards
+            .iter()
+            .filter(|(_, shard)| shard.is_replica())
+            .filter_map(|(queue_id, shard)| {
+                let truncate_from_position_inclusive = shard
+                    .truncation_position_inclusive
+                    .clone()
+                    .max(shard.offloaded_position_inclusive.clone())
+                    .as_u64()
+                    .map(|offset| offset + 1)
+                    .unwrap_or_default();
+                let truncate_to_position_inclusive = shard
+                    .replication_position_inclusive
+                    .as_u64()?
+                    .checked_sub(1)?;
+
+                if truncate_from_position_inclusive > truncate_to_position_inclusive {
+                    return None;
+                }
+                Some((
+                    queue_id.clone(),
+                    truncate_from_position_inclusive,
+                    truncate_to_position_inclusive,
+                ))
+            })
+            .collect();
+        let deleted_replicas: Vec<QueueId> = self
+            .offloaded_replicas
+            .iter()
+            .filter(|queue_id| !state_guard.shards.contains_key(*queue_id))
+            .cloned()
+            .collect();
+        drop(state_guard);
+
+        // The leader of a deleted shard may be gone, so the follower cleans up its segments too.
+        for queue_id in deleted_replicas {
+            let delete_result = async {
+                let mut manifest = self.wal_offload.load_manifest(&queue_id).await?;
+                self.wal_offload
+                    .delete_segments(&queue_id, &mut manifest, u64::MAX)
+                    .await
+            }
+            .await;
+
+            if let Err(error) = delete_result {
+                warn!("{error}");
+                continue;
+            }
+            self.offloaded_replicas.remove(&queue_id);
+        }
+        for (queue_id, from_position_inclusive, to_position_inclusive) in candidates {
+            let manifest = match self.wal_offload.load_manifest(&queue_id).await {
+                Ok(manifest) => manifest,
+                Err(error) => {
+                    warn!("{error}");
+                    continue;
+                }
+            };
+            let Some(last_segment) = manifest.segments.last() else {
+                continue;
+            };
+            let offloaded_position_inclusive =
+                last_segment.last_position.min(to_position_inclusive);
+
+            if offloaded_position_inclusive < from_position_inclusive {
+                continue;
+            }
+            self.offloaded_replicas.insert(queue_id.clone());
+
+            if let Err(error) =
+                truncate_offloaded_shard(state, &queue_id, offloaded_position_inclusive).await
+            {
+                warn!("{error}");
+            }
+        }
+    }
+}
+
+/// Truncates locally the records of a shard offloaded to object storage.
+async fn truncate_offloaded_shard(
+    state: &IngesterState,
+    queue_id: &QueueId,
+    offloaded_position_inclusive: u64,
+) -> IngestV2Result<()> {
+    let mut state_guard = with_lock_metrics!(state.lock_fully(), "offload_wal", "write").await?;
+
+    let Some(shard) = state_guard.inner.shards.get_mut(queue_id) else {
+        return Ok(());
+    };
+    state_guard
+        .mrecordlog
+        .truncate(queue_id, offloaded_position_inclusive)
+        .await
+        .map_err(|error| {
+            IngestV2Error::Internal(format!(
+                "failed to truncate offloaded shard `{queue_id}`: {error:?}"
+            ))
+        })?;
+    shard.offloaded_position_inclusive = Position::offset(offloaded_position_inclusive);
+    info!("offloaded and truncated shard `{queue_id}` at {offloaded_position_inclusive}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use quickwit_proto::ingest::ingester::{fetch_message, OpenFetchStreamRequest};
+    use quickwit_proto::ingest::ShardState;
+    use quickwit_proto::types::{queue_id, IndexUid, ShardId};
+    use quickwit_storage::RamStorage;
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::ingest_v2::fetch::FetchStreamTask;
+    use crate::ingest_v2::models::IngesterShard;
+
+    fn mrecord_batch_for_test(mrecords: &[&'static str]) -> MRecordBatch {
+        MRecordBatch {
+            mrecord_buffer: Bytes::from(mrecords.concat()),
+            mrecord_lengths: mrecords
+                .iter()
+                .map(|mrecord| mrecord.len() as u32)
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wal_offload_fetch_and_delete_segments() {
+        let storage = Arc::new(RamStorage::default());
+        let wal_offload = WalOffload::new(storage.clone(), ByteSize(0));
+        let queue_id = "test-queue";
+
+        assert!(wal_offload
+            .fetch(queue_id, 0, 1024)
+            .await
+            .unwrap()
+            .is_none());
+
+        let mut manifest = wal_offload.load_manifest(queue_id).await.unwrap();
+        assert!(manifest.segments.is_empty());
+
+        let segment = OffloadedSegment {
+            first_position: 0,
+            last_position: 2,
+        };
+        let mrecord_batch = mrecord_batch_for_test(&["foo", "bar", "baz"]);
+        wal_offload
+            .offload_segment(queue_id, &mut manifest, segment, mrecord_batch)
+            .await
+            .unwrap();
+
+        let segment = OffloadedSegment {
+            first_position: 3,
+            last_position: 4,
+        };
+        let mrecord_batch = mrecord_batch_for_test(&["qux", "quux"]);
+        wal_offload
+            .offload_segment(queue_id, &mut manifest, segment, mrecord_batch)
+            .await
+            .unwrap();
+
+        let mrecord_batch = wal_offload.fetch(queue_id, 1, 1024).await.unwrap().unwrap();
+        assert_eq!(mrecord_batch.mrecord_lengths, [3, 3]);
+        assert_eq!(mrecord_batch.mrecord_buffer, "barbaz");
+
+        let mrecord_batch = wal_offload.fetch(queue_id, 0, 4).await.unwrap().unwrap();
+        assert_eq!(mrecord_batch.mrecord_lengths, [3]);
+        assert_eq!(mrecord_batch.mrecord_buffer, "foo");
+
+        let mrecord_batch = wal_offload.fetch(queue_id, 4, 1).await.unwrap().unwrap();
+        assert_eq!(mrecord_batch.mrecord_lengths, [4]);
+        assert_eq!(mrecord_batch.mrecord_buffer, "quux");
+
+        assert!(wal_offload
+            .fetch(queue_id, 5, 1024)
+            .await
+            .unwrap()
+            .is_none());
+
+        wal_offload
+            .delete_segments(queue_id, &mut manifest, 3)
+            .await
+            .unwrap();
+        assert_eq!(manifest.segments.len(), 1);
+        assert!(!storage
+            .exists(&Path::new(queue_id).join("00000000000000000000-00000000000000000002"))
+            .await
+            .unwrap());
+        assert!(wal_offload
+            .fetch(queue_id, 2, 1024)
+            .await
+            .unwrap()
+            .is_none());
+
+        let manifest = wal_offload.load_manifest(queue_id).await.unwrap();
+        assert_eq!(manifest.segments.len(), 1);
+
+        let mut manifest = manifest;
+        wal_offload
+            .delete_segments(queue_id, &mut manifest, 4)
+            .await
+            .unwrap();
+        assert!(manifest.segments.is_empty());
+        assert!(!storage.exists(&manifest_path(queue_id)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_wal_offload_fetch_caches_segments() {
+        let storage = Arc::new(RamStorage::default());
+        let wal_offload = WalOffload::new(storage.clone(), ByteSize(0));
+        let queue_id = "test-queue";
+
+        let mut manifest = OffloadManifest::default();
+        let segment = OffloadedSegment {
+            first_position: 0,
+            last_position: 2,
+        };
+        let mrecord_batch = mrecord_batch_for_test(&["foo", "bar", "baz"]);
+        wal_offload
+            .offload_segment(queue_id, &mut manifest, segment, mrecord_batch)
+            .await
+            .unwrap();
+
+        let mrecord_batch = wal_offload.fetch(queue_id, 0, 4).await.unwrap().unwrap();
+        assert_eq!(mrecord_batch.mrecord_buffer, "foo");
+
+        // The next fetches are served from the cached segment.
+        storage.delete(&segment.path(queue_id)).await.unwrap();
+        storage.delete(&manifest_path(queue_id)).await.unwrap();
+
+        let mrecord_batch = wal_offload.fetch(queue_id, 1, 4).await.unwrap().unwrap();
+        assert_eq!(mrecord_batch.mrecord_buffer, "bar");
+
+        // The segment is evicted once it has been read to the end.
+        let mrecord_batch = wal_offload.fetch(queue_id, 2, 4).await.unwrap().unwrap();
+        assert_eq!(mrecord_batch.mrecord_buffer, "baz");
+
+        assert!(wal_offload
+            .fetch(queue_id, 1, 1024)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wal_offload_fetch_corrupted_segment() {
+        let storage = Arc::new(RamStorage::default());
+        let wal_offload = WalOffload::new(storage.clone(), ByteSize(0));
+        let queue_id = "test-queue";
+
+        // The segment holds fewer records than the positions it covers.
+        let mut manifest = OffloadManifest::default();
+        let segment = OffloadedSegment {
+            first_position: 0,
+            last_position: 2,
+        };
+        let mrecord_batch = mrecord_batch_for_test(&["foo"]);
+        wal_offload
+            .offload_segment(queue_id, &mut manifest, segment, mrecord_batch)
+            .await
+            .unwrap();
+
+        let error = wal_offload.fetch(queue_id, 2, 1024).await.unwrap_err();
+        assert!(matches!(error, IngestV2Error::Internal(message) if message.contains("corrupted")));
+    }
+
+    #[tokio::test]
+    async fn test_offload_wal_task() {
+        let (_temp_dir, state) = IngesterState::for_test().await;
+        let storage = Arc::new(RamStorage::default());
+        let wal_offload = WalOffload::new(storage.clone(), ByteSize(0));
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let shard_id = ShardId::from(1);
+        let queue_id = queue_id(&index_uid, "test-source", &shard_id);
+
+        let mut state_guard = state.lock_fully().await.unwrap();
+        let solo_shard = IngesterShard::new_solo(
+            ShardState::Open,
+            Position::offset(3u64),
+            Position::Beginning,
+            Instant::now(),
+        );
+        let shard_status_rx = solo_shard.shard_status_rx.clone();
+        state_guard.shards.insert(queue_id.clone(), solo_shard);
+
+        state_guard
+            .mrecordlog
+            .create_queue(&queue_id)
+            .await
+            .unwrap();
+        let records = [
+            Bytes::from_static(b"test-doc-foo"),
+            Bytes::from_static(b"test-doc-bar"),
+            Bytes::from_static(b"test-doc-baz"),
+            Bytes::from_static(b"test-doc-qux"),
+        ]
+        .into_iter();
+        state_guard
+            .mrecordlog
+            .append_records(&queue_id, None, records)
+            .await
+            .unwrap();
+        drop(state_guard);
+
+        let join_handle = OffloadWalTask::spawn(state.weak(), wal_offload.clone());
+        tokio::time::sleep(RUN_INTERVAL_PERIOD * 2).await;
+
+        let state_guard = state.lock_fully().await.unwrap();
+        let shard = state_guard.shards.get(&queue_id).unwrap();
+        assert_eq!(shard.offloaded_position_inclusive, Position::offset(2u64));
+        assert_eq!(shard.truncation_position_inclusive, Position::Beginning);
+
+        // The last record is kept locally.
+        state_guard
+            .mrecordlog
+            .assert_records_eq(&queue_id, .., &[(3, "test-doc-qux")]);
+        drop(state_guard);
+
+        let open_fetch_stream_request = OpenFetchStreamRequest {
+            client_id: "test-client".to_string(),
+            index_uid: Some(index_uid.clone()),
+            source_id: "test-source".to_string(),
+            shard_id: Some(shard_id.clone()),
+            from_position_exclusive: Some(Position::Beginning),
+            to_position_inclusive: None,
+        };
+        let (mut fetch_stream, _fetch_task_handle) = FetchStreamTask::spawn(
+            open_fetch_stream_request,
+            state.mrecordlog(),
+            shard_status_rx,
+            1024,
+            Some(wal_offload),
+        );
+        let mut mrecord_buffer = BytesMut::new();
+
+        while mrecord_buffer.len() < 48 {
+            let fetch_message = timeout(Duration::from_millis(100), fetch_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let Some(fetch_message::Message::Payload(fetch_payload)) = fetch_message.message else {
+                panic!("expected fetch payload");
+            };
+            mrecord_buffer.put(fetch_payload.mrecord_batch.unwrap().mrecord_buffer);
+        }
+        assert_eq!(
+            mrecord_buffer,
+            "test-doc-footest-doc-bartest-doc-baztest-doc-qux"
+        );
+
+        // The indexing pipeline catches up.
+        let mut state_guard = state.lock_fully().await.unwrap();
+        state_guard
+            .truncate_shard(&queue_id, &Position::offset(3u64))
+            .await;
+        drop(state_guard);
+
+        tokio::time::sleep(RUN_INTERVAL_PERIOD * 2).await;
+
+        assert!(!storage.exists(&manifest_path(&queue_id)).await.unwrap());
+        join_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_offload_wal_task_max_local_age() {
+        let (_temp_dir, state) = IngesterState::for_test().await;
+        let storage = Arc::new(RamStorage::default());
+        let wal_offload = WalOffload::new(storage.clone(), ByteSize::gb(100))
+            .with_max_local_age(Some(Duration::from_secs(60)));
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let queue_id = queue_id(&index_uid, "test-source", &ShardId::from(1));
+
+        let mut state_guard = state.lock_fully().await.unwrap();
+        let now = Instant::now();
+        let mut solo_shard = IngesterShard::new_solo(
+            ShardState::Open,
+            Position::Beginning,
+            Position::Beginning,
+            now,
+        );
+        // Positions 0 and 1 are older than the max local age, positions 2 and 3 are not.
+        solo_shard.set_replication_position_inclusive(
+            Position::offset(1u64),
+            now - Duration::from_secs(120),
+        );
+        solo_shard.set_replication_position_inclusive(Position::offset(3u64), now);
+        state_guard.shards.insert(queue_id.clone(), solo_shard);
+
+        state_guard
+            .mrecordlog
+            .create_queue(&queue_id)
+            .await
+            .unwrap();
+        let records = [
+            Bytes::from_static(b"test-doc-foo"),
+            Bytes::from_static(b"test-doc-bar"),
+            Bytes::from_static(b"test-doc-baz"),
+            Bytes::from_static(b"test-doc-qux"),
+        ]
+        .into_iter();
+        state_guard
+            .mrecordlog
+            .append_records(&queue_id, None, records)
+            .await
+            .unwrap();
+        drop(state_guard);
+
+        let join_handle = OffloadWalTask::spawn(state.weak(), wal_offload.clone());
+        tokio::time::sleep(RUN_INTERVAL_PERIOD * 2).await;
+
+        let state_guard = state.lock_fully().await.unwrap();
+        let shard = state_guard.shards.get(&queue_id).unwrap();
+        assert_eq!(shard.offloaded_position_inclusive, Position::offset(1u64));
+        state_guard.mrecordlog.assert_records_eq(
+            &queue_id,
+            ..,
+            &[(2, "test-doc-baz"), (3, "test-doc-qux")],
+        );
+        drop(state_guard);
+
+        let manifest = wal_offload.load_manifest(&queue_id).await.unwrap();
+        assert_eq!(manifest.segments.last().unwrap().last_position, 1);
+        join_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_offload_wal_task_truncates_offloaded_replicas() {
+        let (_temp_dir, state) = IngesterState::for_test().await;
+        let storage = Arc::new(RamStorage::default());
+        let wal_offload = WalOffload::new(storage.clone(), ByteSize::gb(100));
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let queue_id = queue_id(&index_uid, "test-source", &ShardId::from(1));
+
+        // The leader offloaded the records up to position 1.
+        let mut manifest = OffloadManifest::default();
+        let segment = OffloadedSegment {
+            first_position: 0,
+            last_position: 1,
+        };
+        let mrecord_batch = mrecord_batch_for_test(&["test-doc-foo", "test-doc-bar"]);
+        wal_offload
+            .offload_segment(&queue_id, &mut manifest, segment, mrecord_batch)
+            .await
+            .unwrap();
+
+        let mut state_guard = state.lock_fully().await.unwrap();
+        let replica_shard = IngesterShard::new_replica(
+            "test-leader".into(),
+            ShardState::Open,
+            Position::offset(3u64),
+            Position::Beginning,
+            Instant::now(),
+        );
+        state_guard.shards.insert(queue_id.clone(), replica_shard);
+
+        state_guard
+            .mrecordlog
+            .create_queue(&queue_id)
+            .await
+            .unwrap();
+        let records = [
+            Bytes::from_static(b"test-doc-foo"),
+            Bytes::from_static(b"test-doc-bar"),
+            Bytes::from_static(b"test-doc-baz"),
+            Bytes::from_static(b"test-doc-qux"),
+        ]
+        .into_iter();
+        state_guard
+            .mrecordlog
+            .append_records(&queue_id, None, records)
+            .await
+            .unwrap();
+        drop(state_guard);
+
+        let join_handle = OffloadWalTask::spawn(state.weak(), wal_offload.clone());
+        tokio::time::sleep(RUN_INTERVAL_PERIOD * 2).await;
+
+        let mut state_guard = state.lock_fully().await.unwrap();
+        let shard = state_guard.shards.get(&queue_id).unwrap();
+        assert_eq!(shard.offloaded_position_inclusive, Position::offset(1u64));
+        state_guard.mrecordlog.assert_records_eq(
+            &queue_id,
+            ..,
+            &[(2, "test-doc-baz"), (3, "test-doc-qux")],
+        );
+        // The follower serves the truncated records from object storage.
+        let mrecord_batch = wal_offload
+            .fetch(&queue_id, 0, 1024)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(mrecord_batch.mrecord_buffer, "test-doc-footest-doc-bar");
+
+        // The shard is deleted, possibly after the loss of its leader.
+        state_guard.shards.remove(&queue_id);
+        drop(state_guard);
+
+        tokio::time::sleep(REPLICA_SYNC_PERIOD + RUN_INTERVAL_PERIOD * 2).await;
+
+        assert!(!storage.exists(&manifest_path(&queue_id)).await.unwrap());
+        join_handle.abort();
+    }
+}
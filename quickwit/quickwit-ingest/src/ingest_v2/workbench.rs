@@ -49,6 +49,8 @@ pub(super) struct IngestWorkbench {
     // (The point here is to make sure we do not wait for the failure detection to kick the node
     // out of the ingest node.)
     pub unavailable_leaders: HashSet<NodeId>,
+    /// Whether the subrequests must be persisted all together or not at all.
+    pub atomic: bool,
 }
 
 impl IngestWorkbench {
@@ -70,16 +72,34 @@ impl IngestWorkbench {
         }
     }
 
+    /// Creates a workbench for an atomic ingest request: the subrequests either all succeed or all
+    /// fail.
+    pub fn new_atomic(ingest_subrequests: Vec<IngestSubrequest>, max_num_attempts: usize) -> Self {
+        Self {
+            atomic: true,
+            ..Self::new(ingest_subrequests, max_num_attempts)
+        }
+    }
+
     pub fn new_attempt(&mut self) {
         self.num_attempts += 1;
     }
 
     /// Returns true if all subrequests were successful or if the number of
-    /// attempts has been exhausted.
+    /// attempts has been exhausted. Atomic workbenches are also complete as soon as one
+    /// subrequest fails permanently.
     pub fn is_complete(&self) -> bool {
         self.num_successes >= self.subworkbenches.len()
             || self.num_attempts >= self.max_num_attempts
             || self.has_no_pending_subrequests()
+            || (self.atomic && self.has_permanent_failures())
+    }
+
+    /// Returns true if at least one subrequest failed and retrying it will not help.
+    pub fn has_permanent_failures(&self) -> bool {
+        self.subworkbenches.values().any(|subworkbench| {
+            subworkbench.persist_success_opt.is_none() && !subworkbench.last_failure_is_transient()
+        })
     }
 
     pub fn is_last_attempt(&self) -> bool {
@@ -115,6 +135,8 @@ impl IngestWorkbench {
             GetOrCreateOpenShardsFailureReason::NoIngestersAvailable => {
                 SubworkbenchFailure::NoShardsAvailable
             }
+            GetOrCreateOpenShardsFailureReason::QuotaExceeded => SubworkbenchFailure::QuotaExceeded,
+            GetOrCreateOpenShardsFailureReason::SourcePaused => SubworkbenchFailure::SourcePaused,
             GetOrCreateOpenShardsFailureReason::Unspecified => {
                 warn!(
                     "failure reason for subrequest `{}` is unspecified",
@@ -188,6 +210,12 @@ impl IngestWorkbench {
         self.record_failure(subrequest_id, SubworkbenchFailure::NoShardsAvailable);
     }
 
+    /// Marks a subrequest as rejected because the control plane reported its source as under
+    /// high ingestion pressure.
+    pub fn record_high_ingestion_pressure(&mut self, subrequest_id: SubrequestId) {
+        self.record_failure(subrequest_id, SubworkbenchFailure::HighIngestionPressure);
+    }
+
     /// Marks a node as unavailable for the span of the workbench.
     ///
     /// Remaining attempts will treat the node as if it was not in the ingester pool.
@@ -195,6 +223,12 @@ impl IngestWorkbench {
         self.record_failure(subrequest_id, SubworkbenchFailure::Unavailable);
     }
 
+    /// Marks a subrequest of an atomic ingest request as aborted because another subrequest of the
+    /// same request could not be persisted.
+    pub fn record_aborted(&mut self, subrequest_id: SubrequestId) {
+        self.record_failure(subrequest_id, SubworkbenchFailure::Aborted);
+    }
+
     fn record_internal_error(&mut self, subrequest_id: SubrequestId) {
         self.record_failure(subrequest_id, SubworkbenchFailure::Internal);
     }
@@ -222,6 +256,15 @@ impl IngestWorkbench {
                     reason: failure.reason() as i32,
                 };
                 failures.push(failure);
+            } else if self.atomic {
+                // The subrequest was never attempted because a sibling failed permanently first.
+                let failure = IngestFailure {
+                    subrequest_id: subworkbench.subrequest.subrequest_id,
+                    index_id: subworkbench.subrequest.index_id,
+                    source_id: subworkbench.subrequest.source_id,
+                    reason: IngestFailureReason::Aborted as i32,
+                };
+                failures.push(failure);
             }
         }
         let num_successes = successes.len();
@@ -277,9 +320,18 @@ pub(super) enum SubworkbenchFailure {
     // This is an error returned by the ingester: e.g. shard not found, shard closed, rate
     // limited, resource exhausted, etc.
     Persist(PersistFailureReason),
+    // The control plane reported the source as under high ingestion pressure.
+    HighIngestionPressure,
+    // The control plane refused to open shards because the ingestion quota of the index or its
+    // tenant is exceeded.
+    QuotaExceeded,
+    // The control plane refused to open shards because the source is paused.
+    SourcePaused,
     Internal,
     // The ingester is no longer in the pool or a transport error occurred.
     Unavailable,
+    // Another subrequest of the same atomic ingest request could not be persisted.
+    Aborted,
 }
 
 impl SubworkbenchFailure {
@@ -294,6 +346,10 @@ impl SubworkbenchFailure {
             // We can consider that as a no shards available.
             Self::Unavailable => IngestFailureReason::NoShardsAvailable,
             Self::Persist(persist_failure_reason) => (*persist_failure_reason).into(),
+            Self::HighIngestionPressure => IngestFailureReason::RateLimited,
+            Self::QuotaExceeded => IngestFailureReason::QuotaExceeded,
+            Self::SourcePaused => IngestFailureReason::SourcePaused,
+            Self::Aborted => IngestFailureReason::Aborted,
         }
     }
 }
@@ -327,10 +383,17 @@ impl IngestSubworkbench {
         match self.last_failure_opt {
             Some(SubworkbenchFailure::IndexNotFound) => false,
             Some(SubworkbenchFailure::SourceNotFound) => false,
+            // Retrying right away is pointless: the ingestion pressure is only refreshed after a
+            // while, so we let the client retry later.
+            Some(SubworkbenchFailure::HighIngestionPressure) => false,
+            // Same as above: the quota usage is only refreshed with the next shard updates.
+            Some(SubworkbenchFailure::QuotaExceeded) => false,
+            Some(SubworkbenchFailure::SourcePaused) => false,
             Some(SubworkbenchFailure::Internal) => true,
             Some(SubworkbenchFailure::NoShardsAvailable) => true,
             Some(SubworkbenchFailure::Persist(_)) => true,
             Some(SubworkbenchFailure::Unavailable) => true,
+            Some(SubworkbenchFailure::Aborted) => true,
             None => true,
         }
     }
@@ -340,6 +403,7 @@ impl IngestSubworkbench {
 mod tests {
     use quickwit_proto::ingest::ingester::PersistFailureReason;
     use quickwit_proto::types::ShardId;
+    use tokio::time::Instant;
 
     use super::*;
 
@@ -370,6 +434,12 @@ mod tests {
         subworkbench.last_failure_opt = Some(SubworkbenchFailure::SourceNotFound);
         assert!(!subworkbench.is_pending());
         assert!(!subworkbench.last_failure_is_transient());
+        subworkbench.last_failure_opt = Some(SubworkbenchFailure::QuotaExceeded);
+        assert!(!subworkbench.is_pending());
+        assert!(!subworkbench.last_failure_is_transient());
+        subworkbench.last_failure_opt = Some(SubworkbenchFailure::SourcePaused);
+        assert!(!subworkbench.is_pending());
+        assert!(!subworkbench.last_failure_is_transient());
 
         subworkbench.last_failure_opt = Some(SubworkbenchFailure::Persist(
             PersistFailureReason::RateLimited,
@@ -542,6 +612,7 @@ mod tests {
         let persist_summary = PersistRequestSummary {
             leader_id: leader_id.clone(),
             subrequest_ids: vec![0],
+            sent_at: Instant::now(),
         };
         workbench.record_persist_error(persist_error, persist_summary);
 
@@ -568,6 +639,7 @@ mod tests {
         let persist_summary = PersistRequestSummary {
             leader_id: leader_id.clone(),
             subrequest_ids: vec![0],
+            sent_at: Instant::now(),
         };
         workbench.record_persist_error(persist_error, persist_summary);
 
@@ -595,6 +667,7 @@ mod tests {
         let persist_summary = PersistRequestSummary {
             leader_id: NodeId::from("test-leader"),
             subrequest_ids: vec![0],
+            sent_at: Instant::now(),
         };
         workbench.record_persist_error(persist_error, persist_summary);
 
@@ -662,6 +735,29 @@ mod tests {
         assert_eq!(subworkbench.num_attempts, 1);
     }
 
+    #[test]
+    fn test_ingest_workbench_record_high_ingestion_pressure() {
+        let ingest_subrequests = vec![IngestSubrequest {
+            subrequest_id: 0,
+            ..Default::default()
+        }];
+        let mut workbench = IngestWorkbench::new(ingest_subrequests, 3);
+        workbench.new_attempt();
+        workbench.record_high_ingestion_pressure(0);
+
+        assert!(workbench.is_complete());
+
+        let subworkbench = workbench.subworkbenches.get(&0).unwrap();
+        assert!(matches!(
+            subworkbench.last_failure_opt,
+            Some(SubworkbenchFailure::HighIngestionPressure)
+        ));
+        assert!(!subworkbench.is_pending());
+
+        let error = workbench.into_ingest_result().unwrap_err();
+        assert_eq!(error, IngestV2Error::TooManyRequests);
+    }
+
     #[test]
     fn test_ingest_workbench_into_ingest_result() {
         let workbench = IngestWorkbench::new(Vec::new(), 0);
@@ -710,4 +806,63 @@ mod tests {
         let error = workbench.into_ingest_result().unwrap_err();
         assert_eq!(error, IngestV2Error::TooManyRequests);
     }
+
+    #[test]
+    fn test_ingest_workbench_atomic() {
+        let ingest_subrequests = vec![
+            IngestSubrequest {
+                subrequest_id: 0,
+                ..Default::default()
+            },
+            IngestSubrequest {
+                subrequest_id: 1,
+                ..Default::default()
+            },
+        ];
+        let mut workbench = IngestWorkbench::new_atomic(ingest_subrequests, 3);
+        assert!(workbench.atomic);
+        assert!(!workbench.is_complete());
+
+        workbench.new_attempt();
+        workbench.record_no_shards_available(0);
+        workbench.record_aborted(1);
+        assert!(!workbench.has_permanent_failures());
+        assert!(!workbench.is_complete());
+        assert_eq!(workbench.pending_subrequests().count(), 2);
+
+        workbench.new_attempt();
+        let failure = SubworkbenchFailure::IndexNotFound;
+        workbench.record_failure(0, failure);
+        assert!(workbench.has_permanent_failures());
+        assert!(workbench.is_complete());
+
+        let response = workbench.into_ingest_result().unwrap();
+        assert!(response.successes.is_empty());
+        assert_eq!(response.failures.len(), 2);
+        assert_eq!(
+            response.failures[0].reason(),
+            IngestFailureReason::IndexNotFound
+        );
+        assert_eq!(response.failures[1].reason(), IngestFailureReason::Aborted);
+
+        let ingest_subrequests = vec![
+            IngestSubrequest {
+                subrequest_id: 0,
+                ..Default::default()
+            },
+            IngestSubrequest {
+                subrequest_id: 1,
+                ..Default::default()
+            },
+        ];
+        let mut workbench = IngestWorkbench::new_atomic(ingest_subrequests, 3);
+        workbench.new_attempt();
+        workbench.record_failure(0, SubworkbenchFailure::SourceNotFound);
+        assert!(workbench.is_complete());
+
+        let response = workbench.into_ingest_result().unwrap();
+        assert_eq!(response.failures.len(), 2);
+        assert_eq!(response.failures[1].subrequest_id, 1);
+        assert_eq!(response.failures[1].reason(), IngestFailureReason::Aborted);
+    }
 }
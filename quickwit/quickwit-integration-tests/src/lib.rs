@@ -19,7 +19,5 @@
 
 #![recursion_limit = "256"]
 
-#[cfg(test)]
-mod test_utils;
 #[cfg(test)]
 mod tests;
@@ -27,8 +27,7 @@ use quickwit_config::service::QuickwitService;
 use quickwit_rest_client::models::IngestSource;
 use quickwit_rest_client::rest_client::CommitType;
 use quickwit_serve::SearchRequestQueryString;
-
-use crate::test_utils::{ingest_with_retry, ClusterSandbox};
+use quickwit_test_cluster::{ingest_with_retry, ClusterSandbox};
 
 fn get_ndjson_filepath(ndjson_dataset_filename: &str) -> String {
     format!(
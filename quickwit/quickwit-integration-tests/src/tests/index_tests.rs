@@ -28,10 +28,7 @@ use quickwit_proto::opentelemetry::proto::trace::v1::{ResourceSpans, ScopeSpans,
 use quickwit_rest_client::error::{ApiError, Error};
 use quickwit_rest_client::rest_client::CommitType;
 use quickwit_serve::SearchRequestQueryString;
-use serde_json::json;
-
-use crate::ingest_json;
-use crate::test_utils::{ingest_with_retry, ClusterSandbox};
+use quickwit_test_cluster::{ingest_json, ingest_with_retry, ClusterSandbox};
 
 #[tokio::test]
 async fn test_single_node_cluster() {
@@ -24,10 +24,7 @@ use quickwit_config::service::QuickwitService;
 use quickwit_config::SearchSettings;
 use quickwit_rest_client::rest_client::CommitType;
 use quickwit_serve::{IndexUpdates, SearchRequestQueryString};
-use serde_json::json;
-
-use crate::ingest_json;
-use crate::test_utils::{ingest_with_retry, ClusterSandbox};
+use quickwit_test_cluster::{ingest_json, ingest_with_retry, ClusterSandbox};
 
 #[tokio::test]
 async fn test_update_on_multi_nodes_cluster() {
@@ -124,8 +121,11 @@ async fn test_update_on_multi_nodes_cluster() {
             IndexUpdates {
                 search_settings: SearchSettings {
                     default_search_fields: vec!["title".to_string(), "body".to_string()],
+                    ..Default::default()
                 },
                 retention_policy_opt: None,
+                legal_hold_opt: None,
+                doc_mapping_opt: None,
             },
         )
         .await
@@ -27,11 +27,11 @@ use async_trait::async_trait;
 use itertools::Itertools;
 use prost::Message;
 use prost_types::{Duration as WellKnownDuration, Timestamp as WellKnownTimestamp};
-use quickwit_config::JaegerConfig;
+use quickwit_config::{JaegerConfig, OtelRole};
 use quickwit_opentelemetry::otlp::{
     extract_otel_traces_index_id_patterns_from_metadata, Event as QwEvent, Link as QwLink,
-    Span as QwSpan, SpanFingerprint, SpanId, SpanKind as QwSpanKind, SpanStatus as QwSpanStatus,
-    TraceId, OTEL_TRACES_INDEX_ID,
+    OtelAuthenticator, Span as QwSpan, SpanFingerprint, SpanId, SpanKind as QwSpanKind,
+    SpanStatus as QwSpanStatus, TraceId, OTEL_TRACES_INDEX_ID,
 };
 use quickwit_proto::jaeger::api_v2::{
     KeyValue as JaegerKeyValue, Log as JaegerLog, Process as JaegerProcess, Span as JaegerSpan,
@@ -54,6 +54,7 @@ use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status};
 use tracing::field::Empty;
 use tracing::{debug, error, instrument, warn, Span as RuntimeSpan};
@@ -79,6 +80,7 @@ pub struct JaegerService {
     lookback_period_secs: i64,
     max_trace_duration_secs: i64,
     max_fetch_spans: u64,
+    authenticator: OtelAuthenticator,
 }
 
 impl JaegerService {
@@ -88,9 +90,28 @@ impl JaegerService {
             lookback_period_secs: config.lookback_period().as_secs() as i64,
             max_trace_duration_secs: config.max_trace_duration().as_secs() as i64,
             max_fetch_spans: config.max_fetch_spans.get(),
+            authenticator: OtelAuthenticator::default(),
         }
     }
 
+    /// Requires the callers to authenticate and to be granted the `read` role. The queries of the
+    /// callers confined to a tenant only target the indexes of that tenant.
+    pub fn with_authenticator(mut self, authenticator: OtelAuthenticator) -> Self {
+        self.authenticator = authenticator;
+        self
+    }
+
+    /// Authorizes the caller identified by the credentials found in `metadata` and restricts the
+    /// index ID patterns of its query to the indexes it may read.
+    pub fn authorize_index_id_patterns(
+        &self,
+        metadata: &MetadataMap,
+        index_id_patterns: Vec<String>,
+    ) -> JaegerResult<Vec<String>> {
+        let principal = self.authenticator.authorize(metadata, OtelRole::Read)?;
+        principal.scope_index_id_patterns(index_id_patterns)
+    }
+
     #[instrument("get_services", skip_all)]
     pub async fn get_services_for_indexes(
         &self,
@@ -479,8 +500,10 @@ impl SpanReaderPlugin for JaegerService {
         &self,
         request: Request<GetServicesRequest>,
     ) -> Result<Response<GetServicesResponse>, Status> {
-        let index_id_patterns =
-            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?;
+        let index_id_patterns = self.authorize_index_id_patterns(
+            request.metadata(),
+            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?,
+        )?;
         metrics!(
             self.get_services_for_indexes(request.into_inner(), index_id_patterns)
                 .await,
@@ -492,8 +515,10 @@ impl SpanReaderPlugin for JaegerService {
         &self,
         request: Request<GetOperationsRequest>,
     ) -> Result<Response<GetOperationsResponse>, Status> {
-        let index_id_patterns =
-            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?;
+        let index_id_patterns = self.authorize_index_id_patterns(
+            request.metadata(),
+            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?,
+        )?;
         metrics!(
             self.get_operations_for_indexes(request.into_inner(), index_id_patterns)
                 .await,
@@ -505,8 +530,10 @@ impl SpanReaderPlugin for JaegerService {
         &self,
         request: Request<FindTraceIDsRequest>,
     ) -> Result<Response<FindTraceIDsResponse>, Status> {
-        let index_id_patterns =
-            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?;
+        let index_id_patterns = self.authorize_index_id_patterns(
+            request.metadata(),
+            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?,
+        )?;
         metrics!(
             self.find_trace_ids_for_indexes(request.into_inner(), index_id_patterns)
                 .await,
@@ -518,8 +545,10 @@ impl SpanReaderPlugin for JaegerService {
         &self,
         request: Request<FindTracesRequest>,
     ) -> Result<Response<Self::FindTracesStream>, Status> {
-        let index_id_patterns =
-            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?;
+        let index_id_patterns = self.authorize_index_id_patterns(
+            request.metadata(),
+            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?,
+        )?;
         self.find_traces_for_indexes(
             request.into_inner(),
             "find_traces",
@@ -534,8 +563,10 @@ impl SpanReaderPlugin for JaegerService {
         &self,
         request: Request<GetTraceRequest>,
     ) -> Result<Response<Self::GetTraceStream>, Status> {
-        let index_id_patterns =
-            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?;
+        let index_id_patterns = self.authorize_index_id_patterns(
+            request.metadata(),
+            extract_otel_traces_index_id_patterns_from_metadata(request.metadata())?,
+        )?;
         self.get_trace_for_indexes(
             request.into_inner(),
             "get_trace",
@@ -1070,6 +1101,7 @@ where T: Deserialize<'a> {
 
 #[cfg(test)]
 mod tests {
+    use quickwit_config::{OtelApiKeyConfig, OtelAuthConfig};
     use quickwit_opentelemetry::otlp::{OtelSignal, OTEL_TRACES_INDEX_ID_PATTERN};
     use quickwit_proto::jaeger::api_v2::ValueType;
     use quickwit_search::{encode_term_for_test, MockSearchService, QuickwitAggregations};
@@ -2481,4 +2513,59 @@ mod tests {
         let response = jaeger.get_services(request).await.unwrap().into_inner();
         assert_eq!(response.services, &["service1", "service2", "service3"]);
     }
+
+    #[tokio::test]
+    async fn test_get_services_with_authenticator() {
+        let mut service = MockSearchService::new();
+        service
+            .expect_root_list_terms()
+            .withf(|req| req.index_id_patterns == vec!["otel-traces-v0_*-acme"])
+            .return_once(|_| {
+                Ok(quickwit_proto::search::ListTermsResponse {
+                    num_hits: 1,
+                    terms: vec![encode_term_for_test!("service1")],
+                    elapsed_time_micros: 0,
+                    errors: Vec::new(),
+                })
+            });
+
+        let auth_config = OtelAuthConfig {
+            api_keys: vec![
+                OtelApiKeyConfig {
+                    name: "grafana-acme".to_string(),
+                    api_key: "acme-secret".to_string(),
+                    roles: vec![OtelRole::Read],
+                    tenant_id: Some("acme".to_string()),
+                },
+                OtelApiKeyConfig {
+                    name: "collector".to_string(),
+                    api_key: "collector-secret".to_string(),
+                    roles: vec![OtelRole::Ingest],
+                    tenant_id: None,
+                },
+            ],
+            jwt: None,
+        };
+        let service = Arc::new(service);
+        let jaeger = JaegerService::new(JaegerConfig::default(), service)
+            .with_authenticator(OtelAuthenticator::new(&auth_config));
+
+        let request = tonic::Request::new(GetServicesRequest {});
+        let status = jaeger.get_services(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut request = tonic::Request::new(GetServicesRequest {});
+        request
+            .metadata_mut()
+            .insert("x-api-key", "collector-secret".parse().unwrap());
+        let status = jaeger.get_services(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        let mut request = tonic::Request::new(GetServicesRequest {});
+        request
+            .metadata_mut()
+            .insert("x-api-key", "acme-secret".parse().unwrap());
+        let response = jaeger.get_services(request).await.unwrap().into_inner();
+        assert_eq!(response.services, &["service1"]);
+    }
 }
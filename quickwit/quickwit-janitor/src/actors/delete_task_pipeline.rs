@@ -191,7 +191,15 @@ impl DeleteTaskPipeline {
         let doc_mapper =
             build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)?;
         let tag_fields = doc_mapper.tag_named_fields()?;
-        let packager = Packager::new("MergePackager", tag_fields, uploader_mailbox);
+        let term_digest_fields = doc_mapper.term_digest_named_fields()?;
+        let secondary_timestamp_fields = doc_mapper.secondary_timestamp_named_fields()?;
+        let packager = Packager::new(
+            "MergePackager",
+            tag_fields,
+            term_digest_fields,
+            uploader_mailbox,
+        )
+        .with_secondary_timestamp_fields(secondary_timestamp_fields);
         let (packager_mailbox, packager_supervisor_handler) = ctx.spawn_actor().supervise(packager);
         let index_pipeline_id = IndexingPipelineId {
             index_uid: self.index_uid.clone(),
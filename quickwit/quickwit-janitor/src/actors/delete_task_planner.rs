@@ -207,6 +207,7 @@ impl DeleteTaskPlanner {
                     &self.merge_scheduler_service,
                     tracked_delete_operation,
                     self.merge_split_downloader_mailbox.clone(),
+                    None,
                 )
                 .await?;
                 JANITOR_METRICS
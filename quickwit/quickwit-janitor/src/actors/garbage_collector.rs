@@ -125,6 +125,7 @@ impl GarbageCollector {
                 metastore,
                 STAGED_GRACE_PERIOD,
                 DELETION_GRACE_PERIOD,
+                index.index_config.legal_hold_opt.as_ref(),
                 false,
                 Some(ctx.progress()),
             ).await;
@@ -337,6 +338,7 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             STAGED_GRACE_PERIOD,
             DELETION_GRACE_PERIOD,
+            None,
             false,
             None,
         )
@@ -255,12 +255,13 @@ fn compute_deleted_indexes<'a>(
 
 #[cfg(test)]
 mod tests {
-    use std::ops::RangeInclusive;
+    use std::collections::BTreeSet;
+    use std::ops::{Bound, RangeInclusive};
 
     use mockall::Sequence;
     use quickwit_actors::Universe;
     use quickwit_common::ServiceStream;
-    use quickwit_config::RetentionPolicy;
+    use quickwit_config::{PartitionRetentionOverride, RetentionPolicy};
     use quickwit_metastore::{
         IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt, Split, SplitMetadata,
         SplitState,
@@ -268,6 +269,7 @@ mod tests {
     use quickwit_proto::metastore::{
         EmptyResponse, ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
     };
+    use time::OffsetDateTime;
 
     use super::*;
 
@@ -313,6 +315,7 @@ mod tests {
             index.retention_policy_opt = Some(RetentionPolicy {
                 retention_period: retention_period.to_string(),
                 evaluation_schedule: EVALUATION_SCHEDULE.to_string(),
+                partition_overrides: Vec::new(),
             })
         }
         index
@@ -346,6 +349,7 @@ mod tests {
         let scheduler = RetentionPolicy {
             retention_period: "".to_string(),
             evaluation_schedule: EVALUATION_SCHEDULE.to_string(),
+            partition_overrides: Vec::new(),
         };
 
         scheduler.duration_until_next_evaluation().unwrap() + Duration::from_secs(1)
@@ -504,4 +508,70 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_retention_policy_execution_with_partition_overrides() -> anyhow::Result<()> {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(..)
+            .returning(|_list_indexes_request| {
+                let mut index_config = make_index("index-1", None);
+                index_config.retention_policy_opt = Some(RetentionPolicy {
+                    retention_period: "30 days".to_string(),
+                    evaluation_schedule: EVALUATION_SCHEDULE.to_string(),
+                    partition_overrides: vec![PartitionRetentionOverride {
+                        tag: "tenant_id:foo".to_string(),
+                        retention_period: "1 hour".to_string(),
+                    }],
+                });
+                let indexes_metadata = vec![IndexMetadata::new(index_config)];
+                Ok(ListIndexesMetadataResponse::for_test(indexes_metadata))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .times(1..=2)
+            .returning(|list_splits_request| {
+                let query = list_splits_request.deserialize_list_splits_query().unwrap();
+                // The splits are listed according to the shortest retention period.
+                let max_retention_timestamp = OffsetDateTime::now_utc().unix_timestamp() - 3600;
+                let Bound::Included(end_timestamp) = query.time_range.end else {
+                    panic!("expected an inclusive time range end bound");
+                };
+                assert!(
+                    (max_retention_timestamp - 10..=max_retention_timestamp + 10)
+                        .contains(&end_timestamp)
+                );
+                let split_end_timestamp = OffsetDateTime::now_utc().unix_timestamp() - 2 * 3600;
+                let mut foo_split = make_split("split-foo", Some(0..=split_end_timestamp));
+                foo_split.split_metadata.tags =
+                    BTreeSet::from(["tenant_id!".to_string(), "tenant_id:foo".to_string()]);
+                let mut bar_split = make_split("split-bar", Some(0..=split_end_timestamp));
+                bar_split.split_metadata.tags =
+                    BTreeSet::from(["tenant_id!".to_string(), "tenant_id:bar".to_string()]);
+                let splits_response =
+                    ListSplitsResponse::try_from_splits(vec![foo_split, bar_split]).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        mock_metastore
+            .expect_mark_splits_for_deletion()
+            .times(1..=2)
+            .returning(|mark_splits_for_deletion_request| {
+                assert_eq!(mark_splits_for_deletion_request.split_ids, ["split-foo"]);
+                Ok(EmptyResponse {})
+            });
+
+        let retention_policy_executor =
+            RetentionPolicyExecutor::new(MetastoreServiceClient::from_mock(mock_metastore));
+        let universe = Universe::with_accelerated_time();
+        let (_mailbox, handle) = universe.spawn_builder().spawn(retention_policy_executor);
+
+        universe.sleep(shift_time_by()).await;
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_execution_passes, 1);
+        assert_eq!(counters.num_expired_splits, 1);
+        universe.assert_quit().await;
+
+        Ok(())
+    }
 }
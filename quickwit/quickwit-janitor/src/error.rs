@@ -19,6 +19,7 @@
 
 use quickwit_proto::metastore::MetastoreError;
 use quickwit_proto::{ServiceError, ServiceErrorCode};
+use quickwit_search::SearchError;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -32,6 +33,8 @@ pub enum JanitorError {
     InvalidDeleteQuery(String),
     #[error("metastore error: `{0}`")]
     Metastore(#[from] MetastoreError),
+    #[error("search error: `{0}`")]
+    Search(#[from] SearchError),
 }
 
 impl ServiceError for JanitorError {
@@ -40,6 +43,7 @@ impl ServiceError for JanitorError {
             Self::Internal(_) => ServiceErrorCode::Internal,
             Self::InvalidDeleteQuery(_) => ServiceErrorCode::BadRequest,
             Self::Metastore(metastore_error) => metastore_error.error_code(),
+            Self::Search(search_error) => search_error.error_code(),
         }
     }
 }
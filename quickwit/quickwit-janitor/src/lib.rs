@@ -22,7 +22,7 @@
 use quickwit_actors::{Mailbox, Universe};
 use quickwit_common::pubsub::EventBroker;
 use quickwit_config::NodeConfig;
-use quickwit_indexing::actors::MergeSchedulerService;
+use quickwit_indexing::actors::{init_upload_scheduler, MergeSchedulerService};
 use quickwit_metastore::SplitInfo;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_search::SearchJobPlacer;
@@ -61,6 +61,7 @@ pub async fn start_janitor_service(
     let (_, retention_policy_executor_handle) =
         universe.spawn_builder().spawn(retention_policy_executor);
     let delete_task_service_handle = if run_delete_task_service {
+        init_upload_scheduler(config.indexer_config.max_upload_bandwidth);
         let delete_task_service = DeleteTaskService::new(
             metastore,
             search_job_placer,
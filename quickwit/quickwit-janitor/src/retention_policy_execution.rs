@@ -29,7 +29,7 @@ use quickwit_proto::metastore::{
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use time::OffsetDateTime;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 use crate::actors::RetentionPolicyExecutor;
 
@@ -47,16 +47,17 @@ pub async fn run_execute_retention_policy(
     retention_policy: &RetentionPolicy,
     ctx: &ActorContext<RetentionPolicyExecutor>,
 ) -> anyhow::Result<Vec<SplitMetadata>> {
-    // Select splits that are published and older than the retention period.
-    let retention_period = retention_policy.retention_period()?;
+    // Select splits that are published and older than the shortest retention period of the
+    // policy. The retention period of each split is checked below, based on its partition.
+    let min_retention_period = retention_policy.min_retention_period()?;
     let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
-    let max_retention_timestamp = current_timestamp - retention_period.as_secs() as i64;
+    let max_retention_timestamp = current_timestamp - min_retention_period.as_secs() as i64;
     let query = ListSplitsQuery::for_index(index_uid.clone())
         .with_split_state(SplitState::Published)
         .with_time_range_end_lte(max_retention_timestamp);
 
     let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
-    let (expired_splits, ignored_splits): (Vec<SplitMetadata>, Vec<SplitMetadata>) = ctx
+    let (mut expired_splits, ignored_splits): (Vec<SplitMetadata>, Vec<SplitMetadata>) = ctx
         .protect_future(metastore.list_splits(list_splits_request))
         .await?
         .collect_splits_metadata()
@@ -76,6 +77,34 @@ pub async fn run_execute_retention_policy(
             ignored_split_ids.len()
         );
     }
+    let mut splits_to_retain = Vec::new();
+    if !retention_policy.partition_overrides.is_empty() {
+        let mut candidate_splits = Vec::with_capacity(expired_splits.len());
+        for split_metadata in expired_splits {
+            let split_retention_period =
+                retention_policy.split_retention_period(&split_metadata.tags)?;
+            let split_max_retention_timestamp =
+                current_timestamp - split_retention_period.as_secs() as i64;
+            let is_expired = split_metadata
+                .time_range
+                .as_ref()
+                .map(|time_range| *time_range.end() <= split_max_retention_timestamp)
+                .unwrap_or(false);
+            if is_expired {
+                candidate_splits.push(split_metadata);
+            } else {
+                splits_to_retain.push(split_metadata);
+            }
+        }
+        expired_splits = candidate_splits;
+    }
+    if !splits_to_retain.is_empty() {
+        debug!(
+            index_id=%index_uid.index_id,
+            "{} splits are retained by the retention period of their partition",
+            splits_to_retain.len()
+        );
+    }
     if expired_splits.is_empty() {
         return Ok(expired_splits);
     }
@@ -151,6 +151,8 @@ pub(super) async fn configure_source(
         enabled: true,
         source_params,
         transform_config,
+        json_schema_config: None,
+        wasm_plugin_config: None,
         input_format,
     })
 }
@@ -219,7 +221,7 @@ pub(super) async fn spawn_services(
 
     // spawn merge scheduler service
     let merge_scheduler_service =
-        MergeSchedulerService::new(node_config.indexer_config.merge_concurrency.get());
+        MergeSchedulerService::from_indexer_config(&node_config.indexer_config);
     let (merge_scheduler_service_mailbox, _) =
         universe.spawn_builder().spawn(merge_scheduler_service);
 
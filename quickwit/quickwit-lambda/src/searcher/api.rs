@@ -72,12 +72,16 @@ fn native_api(
 fn es_compat_api(
     search_service: Arc<dyn SearchService>,
     metastore: MetastoreServiceClient,
+    max_num_concurrent_msearch_searches: usize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     es_compat_search_handler(search_service.clone())
         .or(es_compat_index_search_handler(search_service.clone()))
         .or(es_compat_index_count_handler(search_service.clone()))
         .or(es_compat_scroll_handler(search_service.clone()))
-        .or(es_compat_index_multi_search_handler(search_service.clone()))
+        .or(es_compat_index_multi_search_handler(
+            search_service.clone(),
+            max_num_concurrent_msearch_searches,
+        ))
         .or(es_compat_index_field_capabilities_handler(
             search_service.clone(),
         ))
@@ -90,9 +94,14 @@ fn es_compat_api(
 fn v1_searcher_api(
     search_service: Arc<dyn SearchService>,
     metastore: MetastoreServiceClient,
+    max_num_concurrent_msearch_searches: usize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("api" / "v1" / ..)
-        .and(native_api(search_service.clone()).or(es_compat_api(search_service, metastore)))
+        .and(native_api(search_service.clone()).or(es_compat_api(
+            search_service,
+            metastore,
+            max_num_concurrent_msearch_searches,
+        )))
         .with(warp::filters::compression::gzip())
         .recover(|rejection| {
             error!(?rejection, "request rejected");
@@ -111,6 +120,9 @@ pub async fn setup_searcher_api(
     );
     let _telemetry_handle_opt = quickwit_telemetry::start_telemetry_loop(telemetry_info);
 
+    let max_num_concurrent_msearch_searches = node_config
+        .searcher_config
+        .max_num_concurrent_msearch_searches;
     let search_service = create_local_search_service(
         node_config.searcher_config,
         metastore.clone(),
@@ -137,7 +149,11 @@ pub async fn setup_searcher_api(
 
     let api = warp::any()
         .and(before_hook)
-        .and(v1_searcher_api(search_service, metastore))
+        .and(v1_searcher_api(
+            search_service,
+            metastore,
+            max_num_concurrent_msearch_searches,
+        ))
         .with(after_hook);
 
     Ok(api)
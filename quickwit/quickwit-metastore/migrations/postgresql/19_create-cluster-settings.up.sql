@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS cluster_settings (
+    -- The table holds at most one row.
+    id SMALLINT NOT NULL DEFAULT 1 CHECK (id = 1),
+    cluster_settings_json TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    PRIMARY KEY (id)
+);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS splits_time_range_end_split_id_idx;
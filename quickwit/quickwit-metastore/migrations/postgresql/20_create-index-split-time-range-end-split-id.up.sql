@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS splits_time_range_end_split_id_idx ON splits (time_range_end DESC NULLS FIRST, split_id ASC);
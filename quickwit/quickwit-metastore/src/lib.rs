@@ -31,9 +31,11 @@
 #[allow(missing_docs)]
 pub mod checkpoint;
 mod error;
+mod manifests;
 mod metastore;
 mod metastore_factory;
 mod metastore_resolver;
+mod reindex;
 mod split_metadata;
 mod split_metadata_version;
 #[cfg(test)]
@@ -42,6 +44,7 @@ pub(crate) mod tests;
 use std::ops::Range;
 
 pub use error::MetastoreResolverError;
+pub use manifests::{IndexManifest, SplitManifest};
 pub use metastore::control_plane_metastore::ControlPlaneMetastore;
 pub use metastore::file_backed::FileBackedMetastore;
 pub(crate) use metastore::index_metadata::serialize::{IndexMetadataV0_8, VersionedIndexMetadata};
@@ -58,7 +61,13 @@ pub use metastore_factory::{MetastoreFactory, UnsupportedMetastore};
 pub use metastore_resolver::MetastoreResolver;
 use quickwit_common::is_disjoint;
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
-pub use split_metadata::{Split, SplitInfo, SplitMaturity, SplitMetadata, SplitState};
+pub use reindex::{
+    get_reindex_progress, is_reindex_alias_flipped, reindex_alias_flipped_checkpoint_delta,
+    ReindexProgress, ReindexStage,
+};
+pub use split_metadata::{
+    Split, SplitInfo, SplitMaturity, SplitMetadata, SplitState, SplitStorageStats,
+};
 pub(crate) use split_metadata_version::{SplitMetadataV0_8, VersionedSplitMetadata};
 
 #[derive(utoipa::OpenApi)]
@@ -0,0 +1,211 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Manifests written to the index storage next to the splits, from which the metastore can be
+//! rebuilt when it is lost but the split files survive.
+//!
+//! - The split manifest is embedded in every split file and holds the metadata of the split.
+//! - The index manifest is written at the root of the index storage by the indexers and holds the
+//!   config and the sources of the index.
+
+use std::path::Path;
+
+use anyhow::Context;
+use quickwit_common::shared_consts::{INDEX_MANIFEST_FILE_NAME, SPLIT_MANIFEST_FILE_NAME};
+use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_storage::{BundleStorageFileOffsets, Storage};
+use serde::{Deserialize, Serialize};
+
+use crate::{IndexMetadata, SplitMetadata};
+
+/// Metadata of a split embedded in the split file itself.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SplitManifest {
+    /// Metadata of the split. The footer offsets are not stored: they depend on the size of the
+    /// manifest and are recomputed from the split file when the manifest is fetched.
+    pub split_metadata: SplitMetadata,
+    /// IDs of the splits merged into this split.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replaced_split_ids: Vec<String>,
+}
+
+impl SplitManifest {
+    /// Creates the manifest of a split.
+    pub fn new(mut split_metadata: SplitMetadata, replaced_split_ids: Vec<String>) -> Self {
+        split_metadata.footer_offsets = 0..0;
+        Self {
+            split_metadata,
+            replaced_split_ids,
+        }
+    }
+
+    /// Serializes the manifest to JSON.
+    pub fn serialize(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("split manifest should serialize to JSON")
+    }
+
+    /// Fetches the manifest embedded in the file of the split `split_id`. Returns `None` for the
+    /// splits created before the split manifests were introduced.
+    pub async fn fetch(storage: &dyn Storage, split_id: &str) -> anyhow::Result<Option<Self>> {
+        let split_file = quickwit_common::split_file(split_id);
+        let split_path = Path::new(&split_file);
+        let (footer_offsets, file_offsets) =
+            BundleStorageFileOffsets::fetch_from_split_file(storage, split_path)
+                .await
+                .with_context(|| format!("failed to read footer of split `{split_id}`"))?;
+
+        let Some(manifest_range) = file_offsets.get(Path::new(SPLIT_MANIFEST_FILE_NAME)) else {
+            return Ok(None);
+        };
+        let manifest_bytes = storage
+            .get_slice(
+                split_path,
+                manifest_range.start as usize..manifest_range.end as usize,
+            )
+            .await?;
+        let mut split_manifest: SplitManifest = serde_json::from_slice(&manifest_bytes)
+            .with_context(|| format!("failed to deserialize manifest of split `{split_id}`"))?;
+        split_manifest.split_metadata.footer_offsets = footer_offsets;
+        Ok(Some(split_manifest))
+    }
+}
+
+/// Config and sources of an index written at the root of the index storage.
+///
+/// The source checkpoints are not part of the manifest: they change with every published split
+/// and would be stale anyway.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexManifest {
+    /// Config of the index.
+    pub index_config: IndexConfig,
+    /// Sources of the index.
+    pub sources: Vec<SourceConfig>,
+}
+
+impl IndexManifest {
+    /// Creates the manifest of an index.
+    pub fn new(index_metadata: &IndexMetadata) -> Self {
+        let mut sources: Vec<SourceConfig> = index_metadata.sources.values().cloned().collect();
+        sources.sort_by(|left, right| left.source_id.cmp(&right.source_id));
+        Self {
+            index_config: index_metadata.index_config.clone(),
+            sources,
+        }
+    }
+
+    /// Writes the manifest to the root of the index storage, replacing the previous one.
+    pub async fn write(&self, index_storage: &dyn Storage) -> anyhow::Result<()> {
+        let manifest_json = serde_json::to_vec_pretty(self)?;
+        index_storage
+            .put(Path::new(INDEX_MANIFEST_FILE_NAME), Box::new(manifest_json))
+            .await?;
+        Ok(())
+    }
+
+    /// Fetches the manifest from the root of the index storage. Returns `None` if the index
+    /// storage does not hold a manifest.
+    pub async fn fetch(index_storage: &dyn Storage) -> anyhow::Result<Option<Self>> {
+        let manifest_path = Path::new(INDEX_MANIFEST_FILE_NAME);
+
+        if !index_storage.exists(manifest_path).await? {
+            return Ok(None);
+        }
+        let manifest_bytes = index_storage.get_all(manifest_path).await?;
+        let index_manifest = serde_json::from_slice(&manifest_bytes)
+            .context("failed to deserialize index manifest")?;
+        Ok(Some(index_manifest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_storage::{PutPayload, RamStorage, SplitPayloadBuilder};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_split_manifest_fetch() {
+        let storage = RamStorage::default();
+        let mut split_metadata = SplitMetadata::for_test("split-1".to_string());
+        split_metadata.num_docs = 42;
+        split_metadata.footer_offsets = 1..2;
+        let split_manifest = SplitManifest::new(split_metadata, vec!["split-0".to_string()]);
+        assert_eq!(split_manifest.split_metadata.footer_offsets, 0..0);
+
+        let mut split_payload_builder = SplitPayloadBuilder::default();
+        split_payload_builder.add_payload(
+            SPLIT_MANIFEST_FILE_NAME.to_string(),
+            Box::new(split_manifest.serialize()),
+        );
+        let split_payload = split_payload_builder.finalize(b"hotcache").unwrap();
+        let footer_range = split_payload.footer_range.clone();
+        storage
+            .put(Path::new("split-1.split"), Box::new(split_payload))
+            .await
+            .unwrap();
+
+        let fetched_split_manifest = SplitManifest::fetch(&storage, "split-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched_split_manifest.split_metadata.num_docs, 42);
+        assert_eq!(
+            fetched_split_manifest.split_metadata.footer_offsets,
+            footer_range
+        );
+        assert_eq!(fetched_split_manifest.replaced_split_ids, ["split-0"]);
+
+        let legacy_split_payload = SplitPayloadBuilder::get_split_payload(&[], &[], &[])
+            .unwrap()
+            .read_all()
+            .await
+            .unwrap();
+        storage
+            .put(
+                Path::new("split-2.split"),
+                Box::new(legacy_split_payload.to_vec()),
+            )
+            .await
+            .unwrap();
+        assert!(SplitManifest::fetch(&storage, "split-2")
+            .await
+            .unwrap()
+            .is_none());
+
+        SplitManifest::fetch(&storage, "split-3").await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_index_manifest_write_and_fetch() {
+        let storage = RamStorage::default();
+        assert!(IndexManifest::fetch(&storage).await.unwrap().is_none());
+
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        index_metadata
+            .add_source(SourceConfig::ingest_api_default())
+            .unwrap();
+        let index_manifest = IndexManifest::new(&index_metadata);
+        index_manifest.write(&storage).await.unwrap();
+
+        let fetched_index_manifest = IndexManifest::fetch(&storage).await.unwrap().unwrap();
+        assert_eq!(fetched_index_manifest, index_manifest);
+        assert_eq!(fetched_index_manifest.sources.len(), 1);
+    }
+}
@@ -23,19 +23,20 @@ use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 use quickwit_proto::control_plane::{ControlPlaneService, ControlPlaneServiceClient};
 use quickwit_proto::metastore::{
-    AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, CreateIndexRequest,
-    CreateIndexResponse, CreateIndexTemplateRequest, DeleteIndexRequest,
+    AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, ClusterSettingsResponse,
+    CreateIndexRequest, CreateIndexResponse, CreateIndexTemplateRequest, DeleteIndexRequest,
     DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest, DeleteSourceRequest,
     DeleteSplitsRequest, DeleteTask, EmptyResponse, FindIndexTemplateMatchesRequest,
-    FindIndexTemplateMatchesResponse, GetIndexTemplateRequest, GetIndexTemplateResponse,
-    IndexMetadataRequest, IndexMetadataResponse, LastDeleteOpstampRequest,
-    LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
-    ListIndexTemplatesRequest, ListIndexTemplatesResponse, ListIndexesMetadataRequest,
-    ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse, ListSplitsRequest,
-    ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreResult,
-    MetastoreService, MetastoreServiceClient, MetastoreServiceStream, OpenShardsRequest,
-    OpenShardsResponse, PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest,
-    ToggleSourceRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
+    FindIndexTemplateMatchesResponse, GetClusterSettingsRequest, GetIndexTemplateRequest,
+    GetIndexTemplateResponse, IndexMetadataRequest, IndexMetadataResponse,
+    LastDeleteOpstampRequest, LastDeleteOpstampResponse, ListDeleteTasksRequest,
+    ListDeleteTasksResponse, ListIndexTemplatesRequest, ListIndexTemplatesResponse,
+    ListIndexesMetadataRequest, ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse,
+    ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest, MarkShardsClosedRequest,
+    MarkSplitsForDeletionRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+    MetastoreServiceStream, OpenShardsRequest, OpenShardsResponse, PublishSplitsRequest,
+    ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest,
+    UpdateClusterSettingsRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
     UpdateSplitsDeleteOpstampResponse,
 };
 
@@ -86,6 +87,14 @@ impl MetastoreService for ControlPlaneMetastore {
         Ok(response)
     }
 
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let response = self.control_plane.update_index(request).await?;
+        Ok(response)
+    }
+
     async fn delete_index(
         &mut self,
         request: DeleteIndexRequest,
@@ -117,14 +126,6 @@ impl MetastoreService for ControlPlaneMetastore {
 
     // Other metastore API calls.
 
-    async fn update_index(
-        &mut self,
-        request: UpdateIndexRequest,
-    ) -> MetastoreResult<IndexMetadataResponse> {
-        let response = self.metastore.update_index(request).await?;
-        Ok(response)
-    }
-
     async fn index_metadata(
         &mut self,
         request: IndexMetadataRequest,
@@ -248,6 +249,13 @@ impl MetastoreService for ControlPlaneMetastore {
         self.metastore.delete_shards(request).await
     }
 
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.mark_shards_closed(request).await
+    }
+
     // Index Template API
 
     async fn create_index_template(
@@ -284,4 +292,20 @@ impl MetastoreService for ControlPlaneMetastore {
     ) -> MetastoreResult<EmptyResponse> {
         self.metastore.delete_index_templates(request).await
     }
+
+    // Cluster Settings API
+
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> MetastoreResult<ClusterSettingsResponse> {
+        self.metastore.update_cluster_settings(request).await
+    }
+
+    async fn get_cluster_settings(
+        &mut self,
+        request: GetClusterSettingsRequest,
+    ) -> MetastoreResult<ClusterSettingsResponse> {
+        self.metastore.get_cluster_settings(request).await
+    }
 }
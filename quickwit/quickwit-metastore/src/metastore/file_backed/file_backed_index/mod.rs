@@ -30,11 +30,13 @@ use std::ops::Bound;
 
 use itertools::Itertools;
 use quickwit_common::pretty::PrettySample;
-use quickwit_config::{RetentionPolicy, SearchSettings, SourceConfig, INGEST_V2_SOURCE_ID};
+use quickwit_config::{
+    DocMapping, LegalHold, RetentionPolicy, SearchSettings, SourceConfig, INGEST_V2_SOURCE_ID,
+};
 use quickwit_proto::metastore::{
     AcquireShardsRequest, AcquireShardsResponse, DeleteQuery, DeleteShardsRequest, DeleteTask,
-    EntityKind, ListShardsSubrequest, ListShardsSubresponse, MetastoreError, MetastoreResult,
-    OpenShardSubrequest, OpenShardSubresponse,
+    EntityKind, ListShardsSubrequest, ListShardsSubresponse, MarkShardsClosedRequest,
+    MetastoreError, MetastoreResult, OpenShardSubrequest, OpenShardSubresponse,
 };
 use quickwit_proto::types::{IndexUid, PublishToken, SourceId, SplitId};
 use serde::{Deserialize, Serialize};
@@ -227,6 +229,18 @@ impl FileBackedIndex {
         is_mutation
     }
 
+    /// Replaces the legal hold in the index config, returning whether a mutation occurred.
+    pub fn set_legal_hold(&mut self, legal_hold_opt: Option<LegalHold>) -> bool {
+        let is_mutation = self.metadata.index_config.legal_hold_opt != legal_hold_opt;
+        self.metadata.index_config.legal_hold_opt = legal_hold_opt;
+        is_mutation
+    }
+
+    /// Replaces the doc mapping in the index config, returning whether a mutation occurred.
+    pub fn set_doc_mapping(&mut self, doc_mapping: DocMapping) -> MetastoreResult<bool> {
+        self.metadata.set_doc_mapping(doc_mapping)
+    }
+
     /// Stages a single split.
     ///
     /// If a split already exists and is in the [SplitState::Staged] state,
@@ -423,6 +437,17 @@ impl FileBackedIndex {
                 .take(limit)
                 .cloned()
                 .collect()
+        } else if query.sort_by_time_range_end_desc {
+            self.splits
+                .values()
+                .filter(|split| split_query_predicate(split, query))
+                .sorted_unstable_by(|left_split, right_split| {
+                    cmp_splits_by_time_range_end_desc(left_split, right_split)
+                })
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect()
         } else {
             self.splits
                 .values()
@@ -630,6 +655,14 @@ impl FileBackedIndex {
             .delete_shards(request)
     }
 
+    pub(crate) fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> MetastoreResult<MutationOccurred<()>> {
+        self.get_shards_for_source_mut(&request.source_id)?
+            .mark_shards_closed(request)
+    }
+
     pub(crate) fn list_shards(
         &self,
         subrequest: ListShardsSubrequest,
@@ -715,9 +748,62 @@ fn split_query_predicate(split: &&Split, query: &ListSplitsQuery) -> bool {
         }
     }
 
+    if let Some((time_range_end_opt, split_id)) = &query.after_split_by_time_range_end_desc {
+        let split_time_range_end = split_time_range_end_or_max(split);
+        let after_time_range_end = time_range_end_opt.unwrap_or(i64::MAX);
+
+        match split_time_range_end.cmp(&after_time_range_end) {
+            std::cmp::Ordering::Greater => return false,
+            std::cmp::Ordering::Equal if split.split_metadata.split_id <= *split_id => {
+                return false;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(snapshot_timestamp) = query.snapshot_timestamp {
+        if !split
+            .publish_timestamp
+            .is_some_and(|publish_timestamp| publish_timestamp <= snapshot_timestamp)
+        {
+            return false;
+        }
+        if split.split_state == SplitState::MarkedForDeletion
+            && split.update_timestamp <= snapshot_timestamp
+        {
+            return false;
+        }
+    }
     true
 }
 
+/// Returns the end of the time range of the split, or `i64::MAX` for splits without a time range
+/// so that they sort first.
+fn split_time_range_end_or_max(split: &Split) -> i64 {
+    split
+        .split_metadata
+        .time_range
+        .as_ref()
+        .map(|time_range| *time_range.end())
+        .unwrap_or(i64::MAX)
+}
+
+/// Orders splits by time range end in descending order. Splits without a time range come first
+/// and ties are broken by split ID.
+pub(crate) fn cmp_splits_by_time_range_end_desc(
+    left_split: &Split,
+    right_split: &Split,
+) -> std::cmp::Ordering {
+    split_time_range_end_or_max(right_split)
+        .cmp(&split_time_range_end_or_max(left_split))
+        .then_with(|| {
+            left_split
+                .split_metadata
+                .split_id
+                .cmp(&right_split.split_metadata.split_id)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
@@ -729,7 +815,9 @@ mod tests {
     use quickwit_proto::types::{IndexUid, SourceId};
 
     use super::FileBackedIndex;
-    use crate::file_backed::file_backed_index::split_query_predicate;
+    use crate::file_backed::file_backed_index::{
+        cmp_splits_by_time_range_end_desc, split_query_predicate,
+    };
     use crate::{ListSplitsQuery, Split, SplitMetadata, SplitState};
 
     impl FileBackedIndex {
@@ -997,4 +1085,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_cmp_splits_by_time_range_end_desc() {
+        let [split_1, split_2, split_3] = make_splits();
+        let mut split_4 = split_1.clone();
+        split_4.split_metadata.split_id = "split-4".to_string();
+
+        let mut splits = [&split_4, &split_1, &split_3, &split_2];
+        splits.sort_by(|left_split, right_split| {
+            cmp_splits_by_time_range_end_desc(left_split, right_split)
+        });
+        let split_ids: Vec<&str> = splits
+            .iter()
+            .map(|split| split.split_metadata.split_id.as_str())
+            .collect();
+        assert_eq!(split_ids, ["split-2", "split-3", "split-1", "split-4"]);
+    }
 }
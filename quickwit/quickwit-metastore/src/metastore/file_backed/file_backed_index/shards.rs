@@ -24,8 +24,8 @@ use std::fmt;
 use quickwit_proto::ingest::{Shard, ShardState};
 use quickwit_proto::metastore::{
     AcquireShardsRequest, AcquireShardsResponse, DeleteShardsRequest, EntityKind,
-    ListShardsSubrequest, ListShardsSubresponse, MetastoreError, MetastoreResult,
-    OpenShardSubrequest, OpenShardSubresponse,
+    ListShardsSubrequest, ListShardsSubresponse, MarkShardsClosedRequest, MetastoreError,
+    MetastoreResult, OpenShardSubrequest, OpenShardSubresponse,
 };
 use quickwit_proto::types::{queue_id, IndexUid, Position, PublishToken, ShardId, SourceId};
 use tracing::{info, warn};
@@ -212,6 +212,30 @@ impl Shards {
         Ok(MutationOccurred::from(mutation_occurred))
     }
 
+    pub(super) fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> MetastoreResult<MutationOccurred<()>> {
+        let mut mutation_occurred = false;
+        for shard_id in request.shard_ids {
+            let Some(shard) = self.shards.get_mut(&shard_id) else {
+                continue;
+            };
+            if shard.shard_state() == ShardState::Closed {
+                continue;
+            }
+            info!(
+                index_id=%self.index_uid.index_id,
+                source_id=%self.source_id,
+                shard_id=%shard_id,
+                "closed shard",
+            );
+            shard.shard_state = ShardState::Closed as i32;
+            mutation_occurred = true;
+        }
+        Ok(MutationOccurred::from(mutation_occurred))
+    }
+
     pub(super) fn list_shards(
         &self,
         subrequest: ListShardsSubrequest,
@@ -22,7 +22,7 @@ use std::path::Path;
 
 use itertools::Itertools;
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexTemplate, IndexTemplateId, TestableForRegression};
+use quickwit_config::{ClusterSettings, IndexTemplate, IndexTemplateId, TestableForRegression};
 use quickwit_proto::metastore::{serde_utils, MetastoreError, MetastoreResult};
 use quickwit_proto::types::IndexId;
 use quickwit_storage::{OwnedBytes, Storage, StorageError, StorageErrorKind, StorageResult};
@@ -45,6 +45,7 @@ impl LegacyManifest {
         Manifest {
             indexes: self.indexes,
             templates: HashMap::new(),
+            cluster_settings_opt: None,
         }
     }
 }
@@ -69,6 +70,14 @@ pub(crate) struct Manifest {
     // The templates are serialized as a sorted `Vec<IndexTemplate>` so the btree map is
     // unnecessary here and we can pass the hash map as is to the `MetastoreState`
     pub templates: HashMap<IndexTemplateId, IndexTemplate>,
+    pub cluster_settings_opt: Option<ClusterSettingsEntry>,
+}
+
+/// Cluster settings along with their version, which is incremented on every update.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ClusterSettingsEntry {
+    pub settings: ClusterSettings,
+    pub version: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -97,6 +106,9 @@ impl From<VersionedManifest> for Manifest {
 struct ManifestV0_8 {
     indexes: BTreeMap<IndexId, IndexStatus>,
     templates: Vec<IndexTemplate>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster_settings: Option<ClusterSettingsEntry>,
 }
 
 impl From<Manifest> for ManifestV0_8 {
@@ -109,6 +121,7 @@ impl From<Manifest> for ManifestV0_8 {
         ManifestV0_8 {
             indexes: manifest.indexes,
             templates,
+            cluster_settings: manifest.cluster_settings_opt,
         }
     }
 }
@@ -121,7 +134,11 @@ impl From<ManifestV0_8> for Manifest {
             .into_iter()
             .map(|template| (template.template_id.clone(), template))
             .collect();
-        Manifest { indexes, templates }
+        Manifest {
+            indexes,
+            templates,
+            cluster_settings_opt: manifest.cluster_settings,
+        }
     }
 }
 
@@ -137,12 +154,17 @@ impl TestableForRegression for Manifest {
             "test-template-1".to_string(),
             IndexTemplate::sample_for_regression(),
         );
-        Manifest { indexes, templates }
+        Manifest {
+            indexes,
+            templates,
+            cluster_settings_opt: None,
+        }
     }
 
     fn assert_equality(&self, other: &Self) {
         assert_eq!(self.indexes, other.indexes);
         assert_eq!(self.templates, other.templates);
+        assert_eq!(self.cluster_settings_opt, other.cluster_settings_opt);
     }
 }
 
@@ -313,7 +335,19 @@ mod tests {
                 IndexTemplate::for_test("test-template-2", &["test-index-bar*"], 200),
             ),
         ]);
-        let manifest = Manifest { indexes, templates };
+        let cluster_settings = ClusterSettings {
+            max_num_concurrent_split_searches: Some(50),
+            ..Default::default()
+        };
+        let cluster_settings_opt = Some(ClusterSettingsEntry {
+            settings: cluster_settings,
+            version: 3,
+        });
+        let manifest = Manifest {
+            indexes,
+            templates,
+            cluster_settings_opt,
+        };
         let manifest_json = serde_json::to_string_pretty(&manifest).unwrap();
         let manifest_deserialized: Manifest = serde_json::from_str(&manifest_json).unwrap();
         assert_eq!(manifest, manifest_deserialized);
@@ -41,33 +41,36 @@ use async_trait::async_trait;
 use futures::future::try_join_all;
 use itertools::Itertools;
 use quickwit_common::ServiceStream;
-use quickwit_config::IndexTemplate;
+use quickwit_config::{ClusterSettings, IndexTemplate};
 use quickwit_proto::metastore::{
-    serde_utils, AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, CreateIndexRequest,
-    CreateIndexResponse, CreateIndexTemplateRequest, DeleteIndexRequest,
-    DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest, DeleteSourceRequest,
-    DeleteSplitsRequest, DeleteTask, EmptyResponse, EntityKind, FindIndexTemplateMatchesRequest,
-    FindIndexTemplateMatchesResponse, GetIndexTemplateRequest, GetIndexTemplateResponse,
-    IndexMetadataRequest, IndexMetadataResponse, IndexTemplateMatch, LastDeleteOpstampRequest,
-    LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
-    ListIndexTemplatesRequest, ListIndexTemplatesResponse, ListIndexesMetadataRequest,
-    ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse, ListSplitsRequest,
-    ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError,
+    serde_utils, AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest,
+    ClusterSettingsResponse, CreateIndexRequest, CreateIndexResponse, CreateIndexTemplateRequest,
+    DeleteIndexRequest, DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest,
+    DeleteSourceRequest, DeleteSplitsRequest, DeleteTask, EmptyResponse, EntityKind,
+    FindIndexTemplateMatchesRequest, FindIndexTemplateMatchesResponse, GetClusterSettingsRequest,
+    GetIndexTemplateRequest, GetIndexTemplateResponse, IndexMetadataRequest, IndexMetadataResponse,
+    IndexTemplateMatch, LastDeleteOpstampRequest, LastDeleteOpstampResponse,
+    ListDeleteTasksRequest, ListDeleteTasksResponse, ListIndexTemplatesRequest,
+    ListIndexTemplatesResponse, ListIndexesMetadataRequest, ListIndexesMetadataResponse,
+    ListShardsRequest, ListShardsResponse, ListSplitsRequest, ListSplitsResponse,
+    ListStaleSplitsRequest, MarkShardsClosedRequest, MarkSplitsForDeletionRequest, MetastoreError,
     MetastoreResult, MetastoreService, MetastoreServiceStream, OpenShardSubrequest,
     OpenShardsRequest, OpenShardsResponse, PublishSplitsRequest, ResetSourceCheckpointRequest,
-    StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
-    UpdateSplitsDeleteOpstampResponse,
+    StageSplitsRequest, ToggleSourceRequest, UpdateClusterSettingsRequest, UpdateIndexRequest,
+    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
 };
 use quickwit_proto::types::{IndexId, IndexUid};
 use quickwit_storage::Storage;
 use time::OffsetDateTime;
 use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
 
-use self::file_backed_index::FileBackedIndex;
+use self::file_backed_index::{cmp_splits_by_time_range_end_desc, FileBackedIndex};
 pub use self::file_backed_metastore_factory::FileBackedMetastoreFactory;
 use self::index_id_matcher::IndexIdMatcher;
 use self::lazy_file_backed_index::LazyFileBackedIndex;
-use self::manifest::{load_or_create_manifest, save_manifest, MANIFEST_FILE_NAME};
+use self::manifest::{
+    load_or_create_manifest, save_manifest, ClusterSettingsEntry, MANIFEST_FILE_NAME,
+};
 use self::state::MetastoreState;
 use self::store_operations::{delete_index, index_exists, load_index, put_index};
 use super::{
@@ -339,10 +342,27 @@ impl FileBackedMetastore {
     /// No error is returned if any of the requested `index_uid` does not exist.
     async fn inner_list_splits(&self, request: ListSplitsRequest) -> MetastoreResult<Vec<Split>> {
         let list_splits_query = request.deserialize_list_splits_query()?;
+
+        // When paginating through splits sorted by time over several indexes, the limit and offset
+        // must apply to the merged list rather than to each index individually.
+        let paginate_merged_splits = list_splits_query.sort_by_time_range_end_desc
+            && list_splits_query.index_uids.len() > 1
+            && (list_splits_query.limit.is_some() || list_splits_query.offset.is_some());
+
+        let per_index_query = if paginate_merged_splits {
+            let mut per_index_query = list_splits_query.clone();
+            per_index_query.offset = None;
+            per_index_query.limit = list_splits_query
+                .limit
+                .map(|limit| limit + list_splits_query.offset.unwrap_or_default());
+            per_index_query
+        } else {
+            list_splits_query.clone()
+        };
         let mut all_splits = Vec::new();
         for index_uid in &list_splits_query.index_uids {
             let splits = match self
-                .read(index_uid, |index| index.list_splits(&list_splits_query))
+                .read(index_uid, |index| index.list_splits(&per_index_query))
                 .await
             {
                 Ok(splits) => splits,
@@ -354,6 +374,14 @@ impl FileBackedMetastore {
             };
             all_splits.extend(splits);
         }
+        if paginate_merged_splits {
+            all_splits.sort_unstable_by(cmp_splits_by_time_range_end_desc);
+            all_splits = all_splits
+                .into_iter()
+                .skip(list_splits_query.offset.unwrap_or_default())
+                .take(list_splits_query.limit.unwrap_or(usize::MAX))
+                .collect();
+        }
         Ok(all_splits)
     }
 
@@ -463,13 +491,29 @@ impl MetastoreService for FileBackedMetastore {
     ) -> MetastoreResult<IndexMetadataResponse> {
         let search_settings = request.deserialize_search_settings()?;
         let retention_policy_opt = request.deserialize_retention_policy()?;
+        let legal_hold_update_opt = request.deserialize_legal_hold()?;
+        let doc_mapping_opt = request.deserialize_doc_mapping()?;
         let index_uid = request.index_uid();
 
         let metadata = self
             .mutate(index_uid, |index| {
                 let search_settings_mutated = index.set_search_settings(search_settings);
                 let retention_policy_mutated = index.set_retention_policy(retention_policy_opt);
-                if search_settings_mutated || retention_policy_mutated {
+                let legal_hold_mutated = if let Some(legal_hold_opt) = legal_hold_update_opt {
+                    index.set_legal_hold(legal_hold_opt)
+                } else {
+                    false
+                };
+                let doc_mapping_mutated = if let Some(doc_mapping) = doc_mapping_opt {
+                    index.set_doc_mapping(doc_mapping)?
+                } else {
+                    false
+                };
+                if search_settings_mutated
+                    || retention_policy_mutated
+                    || legal_hold_mutated
+                    || doc_mapping_mutated
+                {
                     Ok(MutationOccurred::Yes(index.metadata().clone()))
                 } else {
                     Ok(MutationOccurred::No(index.metadata().clone()))
@@ -822,6 +866,16 @@ impl MetastoreService for FileBackedMetastore {
         Ok(EmptyResponse {})
     }
 
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid = request.index_uid().clone();
+        self.mutate(&index_uid, |index| index.mark_shards_closed(request))
+            .await?;
+        Ok(EmptyResponse {})
+    }
+
     async fn list_shards(
         &mut self,
         request: ListShardsRequest,
@@ -1060,6 +1114,66 @@ impl MetastoreService for FileBackedMetastore {
         }
         Ok(EmptyResponse {})
     }
+
+    // Cluster Settings API
+
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> MetastoreResult<ClusterSettingsResponse> {
+        let cluster_settings: ClusterSettings =
+            serde_utils::from_json_str(&request.cluster_settings_json)?;
+
+        let mut state_wlock_guard = self.state.write().await;
+
+        let version = state_wlock_guard
+            .cluster_settings_opt
+            .as_ref()
+            .map(|cluster_settings_entry| cluster_settings_entry.version)
+            .unwrap_or_default()
+            + 1;
+        let cluster_settings_json = serde_utils::to_json_str(&cluster_settings)?;
+        let cluster_settings_entry = ClusterSettingsEntry {
+            settings: cluster_settings,
+            version,
+        };
+        let previous_cluster_settings_opt = state_wlock_guard
+            .cluster_settings_opt
+            .replace(cluster_settings_entry);
+
+        let manifest = state_wlock_guard.as_manifest();
+        let save_result = save_manifest(&*self.storage, &manifest).await;
+
+        // Rollback on error.
+        if let Err(error) = save_result {
+            state_wlock_guard.cluster_settings_opt = previous_cluster_settings_opt;
+            return Err(error);
+        }
+        let response = ClusterSettingsResponse {
+            cluster_settings_json,
+            version,
+        };
+        Ok(response)
+    }
+
+    async fn get_cluster_settings(
+        &mut self,
+        _request: GetClusterSettingsRequest,
+    ) -> MetastoreResult<ClusterSettingsResponse> {
+        let inner_rlock_guard = self.state.read().await;
+
+        let response = match &inner_rlock_guard.cluster_settings_opt {
+            Some(cluster_settings_entry) => ClusterSettingsResponse {
+                cluster_settings_json: serde_utils::to_json_str(&cluster_settings_entry.settings)?,
+                version: cluster_settings_entry.version,
+            },
+            None => ClusterSettingsResponse {
+                cluster_settings_json: serde_utils::to_json_str(&ClusterSettings::default())?,
+                version: 0,
+            },
+        };
+        Ok(response)
+    }
 }
 
 impl MetastoreServiceExt for FileBackedMetastore {}
@@ -28,7 +28,7 @@ use quickwit_storage::Storage;
 
 use super::index_template_matcher::IndexTemplateMatcher;
 use super::lazy_file_backed_index::LazyFileBackedIndex;
-use super::manifest::{IndexStatus, Manifest};
+use super::manifest::{ClusterSettingsEntry, IndexStatus, Manifest};
 use super::LazyIndexStatus;
 
 #[derive(Default)]
@@ -36,6 +36,7 @@ pub(super) struct MetastoreState {
     pub indexes: HashMap<IndexId, LazyIndexStatus>,
     pub templates: HashMap<IndexTemplateId, IndexTemplate>,
     pub template_matcher: IndexTemplateMatcher,
+    pub cluster_settings_opt: Option<ClusterSettingsEntry>,
 }
 
 impl MetastoreState {
@@ -69,6 +70,7 @@ impl MetastoreState {
             indexes,
             templates: manifest.templates,
             template_matcher,
+            cluster_settings_opt: manifest.cluster_settings_opt,
         };
         Ok(state)
     }
@@ -87,6 +89,11 @@ impl MetastoreState {
             })
             .collect();
         let templates = self.templates.clone();
-        Manifest { indexes, templates }
+        let cluster_settings_opt = self.cluster_settings_opt.clone();
+        Manifest {
+            indexes,
+            templates,
+            cluster_settings_opt,
+        }
     }
 }
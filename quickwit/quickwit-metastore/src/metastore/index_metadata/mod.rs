@@ -23,12 +23,15 @@ use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, HashMap};
 
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig, TestableForRegression};
+use quickwit_config::{
+    validate_doc_mapping_update, DocMapping, IndexConfig, SourceConfig, TestableForRegression,
+};
 use quickwit_proto::metastore::{EntityKind, MetastoreError, MetastoreResult};
 use quickwit_proto::types::{IndexUid, Position, SourceId};
 use serde::{Deserialize, Serialize};
 use serialize::VersionedIndexMetadata;
 use time::OffsetDateTime;
+use tracing::info;
 use ulid::Ulid;
 
 use crate::checkpoint::{IndexCheckpoint, PartitionId, SourceCheckpoint, SourceCheckpointDelta};
@@ -114,6 +117,41 @@ impl IndexMetadata {
         }
     }
 
+    /// Replaces the doc mapping of the index, returning whether a mutation occurred. The update
+    /// must be non-breaking (see [`validate_doc_mapping_update`]) and bumps the doc mapping
+    /// version so that the splits created afterwards can be told apart from the existing ones.
+    pub(crate) fn set_doc_mapping(&mut self, mut doc_mapping: DocMapping) -> MetastoreResult<bool> {
+        let current_doc_mapping_version = self.index_config.doc_mapping.doc_mapping_version;
+        doc_mapping.doc_mapping_version = current_doc_mapping_version;
+
+        if self.index_config.doc_mapping == doc_mapping {
+            return Ok(false);
+        }
+        let doc_mapping_update = validate_doc_mapping_update(
+            &self.index_config.doc_mapping,
+            &doc_mapping,
+            &self.index_config.search_settings,
+        )
+        .map_err(|error| MetastoreError::InvalidArgument {
+            message: format!(
+                "failed to update doc mapping of index `{}`: {error}",
+                self.index_id()
+            ),
+        })?;
+        doc_mapping.doc_mapping_version = current_doc_mapping_version + 1;
+        info!(
+            index_id=%self.index_id(),
+            doc_mapping_version=doc_mapping.doc_mapping_version,
+            new_fields=?doc_mapping_update.new_field_paths,
+            new_fast_fields=?doc_mapping_update.new_fast_field_paths,
+            disabled_fast_fields=?doc_mapping_update.disabled_fast_field_paths,
+            retokenized_fields=?doc_mapping_update.retokenized_field_paths,
+            "updated doc mapping"
+        );
+        self.index_config.doc_mapping = doc_mapping;
+        Ok(true)
+    }
+
     pub(crate) fn toggle_source(&mut self, source_id: &str, enable: bool) -> MetastoreResult<bool> {
         let Some(source_config) = self.sources.get_mut(source_id) else {
             return Err(MetastoreError::NotFound(EntityKind::Source {
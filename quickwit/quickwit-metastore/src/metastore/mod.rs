@@ -31,7 +31,9 @@ use bytes::Bytes;
 use futures::TryStreamExt;
 pub use index_metadata::IndexMetadata;
 use itertools::Itertools;
-use quickwit_config::{IndexConfig, RetentionPolicy, SearchSettings, SourceConfig};
+use quickwit_config::{
+    DocMapping, IndexConfig, LegalHold, RetentionPolicy, SearchSettings, SourceConfig,
+};
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteTask,
@@ -181,11 +183,14 @@ impl CreateIndexResponseExt for CreateIndexResponse {
 
 /// Helper trait to build a [`UpdateIndexRequest`] and deserialize its payload.
 pub trait UpdateIndexRequestExt {
-    /// Creates a new [`UpdateIndexRequest`] from the different updated fields.
+    /// Creates a new [`UpdateIndexRequest`] from the different updated fields. The legal hold is
+    /// left unchanged when `legal_hold_update_opt` is `None` and lifted when it is `Some(None)`.
     fn try_from_updates(
         index_uid: impl Into<IndexUid>,
         search_settings: &SearchSettings,
         retention_policy_opt: &Option<RetentionPolicy>,
+        legal_hold_update_opt: &Option<Option<LegalHold>>,
+        doc_mapping_opt: Option<&DocMapping>,
     ) -> MetastoreResult<UpdateIndexRequest>;
 
     /// Deserializes the `search_settings_json` field of an [`UpdateIndexRequest`] into a
@@ -195,6 +200,15 @@ pub trait UpdateIndexRequestExt {
     /// Deserializes the `retention_policy_json` field of an [`UpdateIndexRequest`] into a
     /// [`RetentionPolicy`] object.
     fn deserialize_retention_policy(&self) -> MetastoreResult<Option<RetentionPolicy>>;
+
+    /// Deserializes the `legal_hold_json` and `lift_legal_hold` fields of an
+    /// [`UpdateIndexRequest`] into a legal hold update: `None` if the legal hold is left unchanged,
+    /// `Some(None)` if it is lifted.
+    fn deserialize_legal_hold(&self) -> MetastoreResult<Option<Option<LegalHold>>>;
+
+    /// Deserializes the `doc_mapping_json` field of an [`UpdateIndexRequest`] into a
+    /// [`DocMapping`] object.
+    fn deserialize_doc_mapping(&self) -> MetastoreResult<Option<DocMapping>>;
 }
 
 impl UpdateIndexRequestExt for UpdateIndexRequest {
@@ -202,17 +216,29 @@ impl UpdateIndexRequestExt for UpdateIndexRequest {
         index_uid: impl Into<IndexUid>,
         search_settings: &SearchSettings,
         retention_policy_opt: &Option<RetentionPolicy>,
+        legal_hold_update_opt: &Option<Option<LegalHold>>,
+        doc_mapping_opt: Option<&DocMapping>,
     ) -> MetastoreResult<UpdateIndexRequest> {
         let search_settings_json = serde_utils::to_json_str(&search_settings)?;
         let retention_policy_json = retention_policy_opt
             .as_ref()
             .map(serde_utils::to_json_str)
             .transpose()?;
+        let legal_hold_json = legal_hold_update_opt
+            .as_ref()
+            .and_then(|legal_hold_opt| legal_hold_opt.as_ref())
+            .map(serde_utils::to_json_str)
+            .transpose()?;
+        let lift_legal_hold = matches!(legal_hold_update_opt, Some(None));
+        let doc_mapping_json = doc_mapping_opt.map(serde_utils::to_json_str).transpose()?;
 
         let update_request = UpdateIndexRequest {
             index_uid: Some(index_uid.into()),
             search_settings_json,
             retention_policy_json,
+            legal_hold_json,
+            doc_mapping_json,
+            lift_legal_hold,
         };
         Ok(update_request)
     }
@@ -227,6 +253,29 @@ impl UpdateIndexRequestExt for UpdateIndexRequest {
             .map(|policy| serde_utils::from_json_str(policy))
             .transpose()
     }
+
+    fn deserialize_legal_hold(&self) -> MetastoreResult<Option<Option<LegalHold>>> {
+        if self.lift_legal_hold {
+            if self.legal_hold_json.is_some() {
+                return Err(MetastoreError::InvalidArgument {
+                    message: "a legal hold cannot be placed and lifted at the same time"
+                        .to_string(),
+                });
+            }
+            return Ok(Some(None));
+        }
+        self.legal_hold_json
+            .as_ref()
+            .map(|legal_hold| serde_utils::from_json_str(legal_hold).map(Some))
+            .transpose()
+    }
+
+    fn deserialize_doc_mapping(&self) -> MetastoreResult<Option<DocMapping>> {
+        self.doc_mapping_json
+            .as_ref()
+            .map(|doc_mapping| serde_utils::from_json_str(doc_mapping))
+            .transpose()
+    }
 }
 
 /// Helper trait to build a [`IndexMetadataResponse`] and deserialize its payload.
@@ -532,6 +581,23 @@ pub struct ListSplitsQuery {
     /// Sorts the splits by staleness, i.e. by delete opstamp and publish timestamp in ascending
     /// order.
     pub sort_by_staleness: bool,
+
+    /// Sorts the splits by time range end in descending order, i.e. newest splits first. Splits
+    /// without a time range come first. Ties are broken by split ID.
+    #[serde(default)]
+    pub sort_by_time_range_end_desc: bool,
+
+    /// Only lists the splits that come after the split with this time range end and split ID in
+    /// the time range end descending order. Used to page through the splits without `offset`,
+    /// which gets slower as the pages go. `None` time range ends come first.
+    #[serde(default)]
+    pub after_split_by_time_range_end_desc: Option<(Option<i64>, SplitId)>,
+
+    /// Only lists the splits that were published at this timestamp, i.e. the splits published at
+    /// or before it that are still published or were marked for deletion after it. Used to page
+    /// through a consistent set of splits while splits are being published and merged.
+    #[serde(default)]
+    pub snapshot_timestamp: Option<i64>,
 }
 
 #[allow(unused_attributes)]
@@ -550,6 +616,9 @@ impl ListSplitsQuery {
             create_timestamp: Default::default(),
             mature: Bound::Unbounded,
             sort_by_staleness: false,
+            sort_by_time_range_end_desc: false,
+            after_split_by_time_range_end_desc: None,
+            snapshot_timestamp: None,
         }
     }
 
@@ -574,6 +643,9 @@ impl ListSplitsQuery {
             create_timestamp: Default::default(),
             mature: Bound::Unbounded,
             sort_by_staleness: false,
+            sort_by_time_range_end_desc: false,
+            after_split_by_time_range_end_desc: None,
+            snapshot_timestamp: None,
         })
     }
 
@@ -737,6 +809,33 @@ impl ListSplitsQuery {
         self.sort_by_staleness = true;
         self
     }
+
+    /// Sorts the splits by time range end in descending order, i.e. newest splits first.
+    pub fn sort_by_time_range_end_desc(mut self) -> Self {
+        self.sort_by_time_range_end_desc = true;
+        self
+    }
+
+    /// Sorts the splits by time range end in descending order and only lists the splits that come
+    /// after `split_metadata`, which is typically the last split of the previous page.
+    pub fn after_split_by_time_range_end_desc(mut self, split_metadata: &SplitMetadata) -> Self {
+        let time_range_end_opt = split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.end());
+        self.sort_by_time_range_end_desc = true;
+        self.after_split_by_time_range_end_desc =
+            Some((time_range_end_opt, split_metadata.split_id.clone()));
+        self
+    }
+
+    /// Only lists the splits that were published at `timestamp`. This overrides the split states
+    /// to filter by, since the splits merged since then are now marked for deletion.
+    pub fn with_snapshot_timestamp(mut self, timestamp: i64) -> Self {
+        self.split_states = vec![SplitState::Published, SplitState::MarkedForDeletion];
+        self.snapshot_timestamp = Some(timestamp);
+        self
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
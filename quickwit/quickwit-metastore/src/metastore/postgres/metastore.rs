@@ -25,24 +25,26 @@ use quickwit_common::pretty::PrettySample;
 use quickwit_common::uri::Uri;
 use quickwit_common::ServiceStream;
 use quickwit_config::{
-    validate_index_id_pattern, IndexTemplate, IndexTemplateId, PostgresMetastoreConfig,
-    INGEST_V2_SOURCE_ID,
+    validate_index_id_pattern, ClusterSettings, IndexTemplate, IndexTemplateId,
+    PostgresMetastoreConfig, INGEST_V2_SOURCE_ID,
 };
 use quickwit_proto::ingest::{Shard, ShardState};
 use quickwit_proto::metastore::{
-    serde_utils, AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, CreateIndexRequest,
-    CreateIndexResponse, CreateIndexTemplateRequest, DeleteIndexRequest,
-    DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest, DeleteSourceRequest,
-    DeleteSplitsRequest, DeleteTask, EmptyResponse, EntityKind, FindIndexTemplateMatchesRequest,
-    FindIndexTemplateMatchesResponse, GetIndexTemplateRequest, GetIndexTemplateResponse,
-    IndexMetadataRequest, IndexMetadataResponse, IndexTemplateMatch, LastDeleteOpstampRequest,
-    LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
-    ListIndexTemplatesRequest, ListIndexTemplatesResponse, ListIndexesMetadataRequest,
-    ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse, ListShardsSubresponse,
-    ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest,
-    MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceStream, OpenShardSubrequest,
-    OpenShardSubresponse, OpenShardsRequest, OpenShardsResponse, PublishSplitsRequest,
-    ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest,
+    serde_utils, AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest,
+    ClusterSettingsResponse, CreateIndexRequest, CreateIndexResponse, CreateIndexTemplateRequest,
+    DeleteIndexRequest, DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest,
+    DeleteSourceRequest, DeleteSplitsRequest, DeleteTask, EmptyResponse, EntityKind,
+    FindIndexTemplateMatchesRequest, FindIndexTemplateMatchesResponse, GetClusterSettingsRequest,
+    GetIndexTemplateRequest, GetIndexTemplateResponse, IndexMetadataRequest, IndexMetadataResponse,
+    IndexTemplateMatch, LastDeleteOpstampRequest, LastDeleteOpstampResponse,
+    ListDeleteTasksRequest, ListDeleteTasksResponse, ListIndexTemplatesRequest,
+    ListIndexTemplatesResponse, ListIndexesMetadataRequest, ListIndexesMetadataResponse,
+    ListShardsRequest, ListShardsResponse, ListShardsSubresponse, ListSplitsRequest,
+    ListSplitsResponse, ListStaleSplitsRequest, MarkShardsClosedRequest,
+    MarkSplitsForDeletionRequest, MetastoreError, MetastoreResult, MetastoreService,
+    MetastoreServiceStream, OpenShardSubrequest, OpenShardSubresponse, OpenShardsRequest,
+    OpenShardsResponse, PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest,
+    ToggleSourceRequest, UpdateClusterSettingsRequest, UpdateIndexRequest,
     UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
 };
 use quickwit_proto::types::{IndexId, IndexUid, Position, PublishToken, SourceId};
@@ -408,14 +410,30 @@ impl MetastoreService for PostgresqlMetastore {
     ) -> MetastoreResult<IndexMetadataResponse> {
         let retention_policy_opt = request.deserialize_retention_policy()?;
         let search_settings = request.deserialize_search_settings()?;
+        let legal_hold_update_opt = request.deserialize_legal_hold()?;
+        let doc_mapping_opt = request.deserialize_doc_mapping()?;
         let index_uid: IndexUid = request.index_uid().clone();
         let updated_metadata = run_with_tx!(self.connection_pool, tx, {
             mutate_index_metadata::<MetastoreError, _>(tx, index_uid, |index_metadata| {
+                let mut mutation_occurred = false;
+
                 if index_metadata.index_config.search_settings != search_settings
                     || index_metadata.index_config.retention_policy_opt != retention_policy_opt
                 {
                     index_metadata.index_config.search_settings = search_settings;
                     index_metadata.index_config.retention_policy_opt = retention_policy_opt;
+                    mutation_occurred = true;
+                }
+                if let Some(legal_hold_opt) = legal_hold_update_opt {
+                    if index_metadata.index_config.legal_hold_opt != legal_hold_opt {
+                        index_metadata.index_config.legal_hold_opt = legal_hold_opt;
+                        mutation_occurred = true;
+                    }
+                }
+                if let Some(doc_mapping) = doc_mapping_opt {
+                    mutation_occurred |= index_metadata.set_doc_mapping(doc_mapping)?;
+                }
+                if mutation_occurred {
                     Ok(MutationOccurred::Yes(()))
                 } else {
                     Ok(MutationOccurred::No(()))
@@ -1322,6 +1340,29 @@ impl MetastoreService for PostgresqlMetastore {
         Ok(EmptyResponse {})
     }
 
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        const CLOSE_SHARDS_QUERY: &str = include_str!("queries/shards/close.sql");
+
+        if request.shard_ids.is_empty() {
+            return Ok(Default::default());
+        }
+        let shard_ids: Vec<&str> = request
+            .shard_ids
+            .iter()
+            .map(|shard_id| shard_id.as_str())
+            .collect();
+        sqlx::query(CLOSE_SHARDS_QUERY)
+            .bind(request.index_uid().to_string())
+            .bind(&request.source_id)
+            .bind(&shard_ids)
+            .execute(&self.connection_pool)
+            .await?;
+        Ok(EmptyResponse {})
+    }
+
     // Index Template API
 
     async fn create_index_template(
@@ -1463,6 +1504,56 @@ impl MetastoreService for PostgresqlMetastore {
             .await?;
         Ok(EmptyResponse {})
     }
+
+    // Cluster Settings API
+
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> MetastoreResult<ClusterSettingsResponse> {
+        const UPSERT_CLUSTER_SETTINGS_QUERY: &str = r#"
+            INSERT INTO cluster_settings(id, cluster_settings_json, version)
+                VALUES (1, $1, 1)
+            ON CONFLICT (id)
+                DO UPDATE SET cluster_settings_json = $1, version = cluster_settings.version + 1
+            RETURNING version
+        "#;
+        let cluster_settings: ClusterSettings =
+            serde_utils::from_json_str(&request.cluster_settings_json)?;
+        let cluster_settings_json = serde_utils::to_json_str(&cluster_settings)?;
+
+        let (version,): (i64,) = sqlx::query_as(UPSERT_CLUSTER_SETTINGS_QUERY)
+            .bind(&cluster_settings_json)
+            .fetch_one(&self.connection_pool)
+            .await?;
+        let response = ClusterSettingsResponse {
+            cluster_settings_json,
+            version: version as u64,
+        };
+        Ok(response)
+    }
+
+    async fn get_cluster_settings(
+        &mut self,
+        _request: GetClusterSettingsRequest,
+    ) -> MetastoreResult<ClusterSettingsResponse> {
+        let pg_cluster_settings_opt: Option<(String, i64)> =
+            sqlx::query_as("SELECT cluster_settings_json, version FROM cluster_settings")
+                .fetch_optional(&self.connection_pool)
+                .await?;
+
+        let response = match pg_cluster_settings_opt {
+            Some((cluster_settings_json, version)) => ClusterSettingsResponse {
+                cluster_settings_json,
+                version: version as u64,
+            },
+            None => ClusterSettingsResponse {
+                cluster_settings_json: serde_utils::to_json_str(&ClusterSettings::default())?,
+                version: 0,
+            },
+        };
+        Ok(response)
+    }
 }
 
 async fn open_or_fetch_shard<'e>(
@@ -1620,7 +1711,7 @@ mod tests {
     use crate::metastore::postgres::model::{PgShard, Splits};
     use crate::tests::shard::ReadWriteShardsForTest;
     use crate::tests::DefaultForTest;
-    use crate::{metastore_test_suite, ListSplitsQuery, SplitState};
+    use crate::{metastore_test_suite, ListSplitsQuery, SplitMetadata, SplitState};
 
     #[async_trait]
     impl ReadWriteShardsForTest for PostgresqlMetastore {
@@ -1841,6 +1932,56 @@ mod tests {
                 r#"SELECT * FROM "splits" WHERE "index_uid" IN ('{index_uid}') ORDER BY "split_id" ASC OFFSET 4"#
             )
         );
+
+        let mut select_statement = Query::select();
+        let sql = select_statement.column(Asterisk).from(Splits::Table);
+
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .sort_by_time_range_end_desc()
+            .with_limit(10)
+            .with_offset(4);
+        append_query_filters(sql, &query);
+
+        assert_eq!(
+            sql.to_string(PostgresQueryBuilder),
+            format!(
+                r#"SELECT * FROM "splits" WHERE "index_uid" IN ('{index_uid}') ORDER BY "time_range_end" DESC NULLS FIRST, "split_id" ASC LIMIT 10 OFFSET 4"#
+            )
+        );
+
+        let mut select_statement = Query::select();
+        let sql = select_statement.column(Asterisk).from(Splits::Table);
+
+        let split_metadata = SplitMetadata {
+            split_id: "split-1".to_string(),
+            ..Default::default()
+        };
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .after_split_by_time_range_end_desc(&split_metadata)
+            .with_limit(10);
+        append_query_filters(sql, &query);
+
+        assert_eq!(
+            sql.to_string(PostgresQueryBuilder),
+            format!(
+                r#"SELECT * FROM "splits" WHERE "index_uid" IN ('{index_uid}') AND ("time_range_end" IS NOT NULL OR "split_id" > 'split-1') ORDER BY "time_range_end" DESC NULLS FIRST, "split_id" ASC LIMIT 10"#
+            )
+        );
+
+        let mut select_statement = Query::select();
+        let sql = select_statement.column(Asterisk).from(Splits::Table);
+
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_split_state(SplitState::Published)
+            .with_snapshot_timestamp(51);
+        append_query_filters(sql, &query);
+
+        assert_eq!(
+            sql.to_string(PostgresQueryBuilder),
+            format!(
+                r#"SELECT * FROM "splits" WHERE "index_uid" IN ('{index_uid}') AND "split_state" IN ('Published', 'MarkedForDeletion') AND "publish_timestamp" <= TO_TIMESTAMP(51) AND ("split_state" = 'Published' OR "update_timestamp" > TO_TIMESTAMP(51))"#
+            )
+        );
     }
 
     #[test]
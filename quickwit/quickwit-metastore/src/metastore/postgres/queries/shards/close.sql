@@ -0,0 +1,8 @@
+UPDATE
+    shards
+SET
+    shard_state = 'closed'
+WHERE
+    index_uid = $1
+    AND source_id = $2
+    AND shard_id = ANY ($3)
@@ -24,7 +24,7 @@ use std::time::Duration;
 
 use quickwit_common::uri::Uri;
 use quickwit_proto::metastore::{MetastoreError, MetastoreResult};
-use sea_query::{any, Expr, Func, Order, SelectStatement};
+use sea_query::{all, any, Expr, Func, NullOrdering, Order, SelectStatement};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::{ConnectOptions, Postgres};
 use tracing::error;
@@ -34,7 +34,7 @@ use super::model::{Splits, ToTimestampFunc};
 use super::pool::TrackedPool;
 use super::tags::generate_sql_condition;
 use crate::metastore::FilterRange;
-use crate::{ListSplitsQuery, SplitMaturity, SplitMetadata};
+use crate::{ListSplitsQuery, SplitMaturity, SplitMetadata, SplitState};
 
 /// Establishes a connection to the given database URI.
 pub(super) async fn establish_connection(
@@ -177,13 +177,54 @@ pub(super) fn append_query_filters(sql: &mut SelectStatement, query: &ListSplits
         Expr::expr(val)
     });
 
+    if let Some((time_range_end_opt, split_id)) = &query.after_split_by_time_range_end_desc {
+        // Keyset pagination: `NULL` time range ends sort first.
+        let after_split_cond = if let Some(time_range_end) = time_range_end_opt {
+            all![
+                Expr::col(Splits::TimeRangeEnd).is_not_null(),
+                any![
+                    Expr::col(Splits::TimeRangeEnd).lt(*time_range_end),
+                    all![
+                        Expr::col(Splits::TimeRangeEnd).eq(*time_range_end),
+                        Expr::col(Splits::SplitId).gt(split_id.as_str())
+                    ]
+                ]
+            ]
+        } else {
+            any![
+                Expr::col(Splits::TimeRangeEnd).is_not_null(),
+                Expr::col(Splits::SplitId).gt(split_id.as_str())
+            ]
+        };
+        sql.cond_where(after_split_cond);
+    }
+
+    if let Some(snapshot_timestamp) = query.snapshot_timestamp {
+        // The splits merged after the snapshot are now marked for deletion, but were still
+        // published at the time of the snapshot.
+        let snapshot_timestamp_expr =
+            || Func::cust(ToTimestampFunc).arg(Expr::val(snapshot_timestamp));
+        sql.cond_where(Expr::col(Splits::PublishTimestamp).lte(snapshot_timestamp_expr()));
+        sql.cond_where(any![
+            Expr::col(Splits::SplitState).eq(SplitState::Published.as_str()),
+            Expr::col(Splits::UpdateTimestamp).gt(snapshot_timestamp_expr())
+        ]);
+    }
+
+    if query.sort_by_time_range_end_desc {
+        sql.order_by_with_nulls(Splits::TimeRangeEnd, Order::Desc, NullOrdering::First)
+            .order_by(Splits::SplitId, Order::Asc);
+    }
+
     if let Some(limit) = query.limit {
         sql.limit(limit as u64);
     }
 
     if let Some(offset) = query.offset {
-        sql.order_by(Splits::SplitId, Order::Asc)
-            .offset(offset as u64);
+        if !query.sort_by_time_range_end_desc {
+            sql.order_by(Splits::SplitId, Order::Asc);
+        }
+        sql.offset(offset as u64);
     }
 }
 
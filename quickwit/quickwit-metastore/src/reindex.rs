@@ -0,0 +1,186 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_config::ReindexSourceParams;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, ListSplitsRequest, MetastoreResult, MetastoreService,
+    MetastoreServiceClient,
+};
+use quickwit_proto::types::Position;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::checkpoint::{PartitionId, SourceCheckpoint, SourceCheckpointDelta};
+use crate::{
+    IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    MetastoreServiceStreamSplitsExt, SplitState,
+};
+
+/// ID of the partition of the checkpoint of a reindex source recording that the control plane
+/// flipped the write alias of the reindex. The other partitions are split IDs, which never collide
+/// with this one.
+const ALIAS_FLIPPED_PARTITION_ID: &str = "__alias_flipped__";
+
+/// Stage of a reindex.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexStage {
+    /// The mature splits of the source index are being reindexed.
+    Backfill,
+    /// All the mature splits of the source index have been reindexed. The remaining splits are
+    /// reindexed as they mature.
+    CatchUp,
+    /// All the splits of the source index have been reindexed.
+    Completed,
+}
+
+impl ReindexStage {
+    /// Returns whether all the mature splits of the source index have been reindexed.
+    pub fn is_backfilled(&self) -> bool {
+        matches!(self, Self::CatchUp | Self::Completed)
+    }
+}
+
+/// Progress of a reindex, computed from the checkpoint of the reindex source and the splits of the
+/// source index.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ReindexProgress {
+    /// ID of the index whose documents are reindexed.
+    pub source_index_id: String,
+    /// Stage of the reindex.
+    pub stage: ReindexStage,
+    /// Whether the reindex source is paused, i.e. disabled.
+    pub paused: bool,
+    /// Whether the control plane flipped the write alias of the reindex to the index of the
+    /// source.
+    pub alias_flipped: bool,
+    /// Number of published splits of the source index.
+    pub num_splits: usize,
+    /// Number of splits of the source index entirely reindexed.
+    pub num_reindexed_splits: usize,
+    /// Number of splits not reindexed yet because they are immature.
+    pub num_immature_splits: usize,
+    /// Number of documents of the published splits of the source index.
+    pub num_docs: u64,
+    /// Number of documents reindexed so far.
+    pub num_reindexed_docs: u64,
+}
+
+/// Returns whether the checkpoint of a reindex source records that the write alias of the reindex
+/// was flipped to the index of the source.
+pub fn is_reindex_alias_flipped(checkpoint: &SourceCheckpoint) -> bool {
+    checkpoint
+        .position_for_partition(&PartitionId::from(ALIAS_FLIPPED_PARTITION_ID))
+        .is_some_and(|position| position.is_eof())
+}
+
+/// Returns the checkpoint delta that records, in the checkpoint of a reindex source, that the write
+/// alias of the reindex was flipped. Publishing it along with the splits of the index makes the
+/// flip durable.
+pub fn reindex_alias_flipped_checkpoint_delta() -> SourceCheckpointDelta {
+    SourceCheckpointDelta::from_partition_delta(
+        PartitionId::from(ALIAS_FLIPPED_PARTITION_ID),
+        Position::Beginning,
+        Position::eof(0u64),
+    )
+    .expect("checkpoint delta should be valid")
+}
+
+/// Computes the progress of a reindex source given its checkpoint.
+pub async fn get_reindex_progress(
+    metastore: &mut MetastoreServiceClient,
+    params: &ReindexSourceParams,
+    checkpoint: &SourceCheckpoint,
+    enabled: bool,
+) -> MetastoreResult<ReindexProgress> {
+    let index_metadata_request = IndexMetadataRequest::for_index_id(params.source_index_id.clone());
+    let source_index_uid = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?
+        .index_uid;
+    let list_splits_query =
+        ListSplitsQuery::for_index(source_index_uid).with_split_state(SplitState::Published);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&list_splits_query)?;
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits_metadata()
+        .await?;
+    let now = OffsetDateTime::now_utc();
+
+    let mut progress = ReindexProgress {
+        source_index_id: params.source_index_id.clone(),
+        stage: ReindexStage::Completed,
+        paused: !enabled,
+        alias_flipped: is_reindex_alias_flipped(checkpoint),
+        num_splits: splits.len(),
+        num_reindexed_splits: 0,
+        num_immature_splits: 0,
+        num_docs: 0,
+        num_reindexed_docs: 0,
+    };
+    for split in &splits {
+        progress.num_docs += split.num_docs as u64;
+
+        let position_opt = checkpoint.position_for_partition(&PartitionId::from(split.split_id()));
+        match position_opt {
+            Some(position) if position.is_eof() => {
+                progress.num_reindexed_splits += 1;
+                progress.num_reindexed_docs += split.num_docs as u64;
+                continue;
+            }
+            Some(position) => {
+                progress.num_reindexed_docs += position.as_u64().unwrap_or(0);
+            }
+            None => {}
+        }
+        if split.is_mature(now) {
+            progress.stage = ReindexStage::Backfill;
+        } else {
+            progress.num_immature_splits += 1;
+
+            if progress.stage == ReindexStage::Completed {
+                progress.stage = ReindexStage::CatchUp;
+            }
+        }
+    }
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reindex_alias_flipped_checkpoint_delta() {
+        let mut checkpoint = SourceCheckpoint::default();
+        assert!(!is_reindex_alias_flipped(&checkpoint));
+
+        checkpoint
+            .try_apply_delta(reindex_alias_flipped_checkpoint_delta())
+            .unwrap();
+        assert!(is_reindex_alias_flipped(&checkpoint));
+
+        // Flipping the alias twice is rejected by the checkpoint.
+        checkpoint
+            .try_apply_delta(reindex_alias_flipped_checkpoint_delta())
+            .unwrap_err();
+    }
+}
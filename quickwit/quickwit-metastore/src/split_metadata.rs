@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::ops::{Range, RangeInclusive};
 use std::path::PathBuf;
@@ -101,6 +101,15 @@ pub struct SplitMetadata {
     /// the split, expressed in seconds.
     pub time_range: Option<RangeInclusive<i64>>,
 
+    /// The min / max timestamps in the split of the fields registered in the
+    /// [`DocMapping`](quickwit_config::DocMapping) `secondary_timestamp_fields` attribute, keyed
+    /// by field name and expressed in seconds. Fields without any value in the split are
+    /// absent.
+    ///
+    /// The root searcher uses them to skip splits that cannot match the range queries on these
+    /// fields.
+    pub secondary_time_ranges: BTreeMap<String, RangeInclusive<i64>>,
+
     /// Timestamp for tracking when the split was created.
     pub create_timestamp: i64,
 
@@ -119,6 +128,14 @@ pub struct SplitMetadata {
     /// [`MAX_VALUES_PER_TAG_FIELD`]: https://github.com/quickwit-oss/quickwit/blob/main/quickwit-indexing/src/actors/packager.rs#L36
     pub tags: BTreeSet<String>,
 
+    /// Fields for which a compact digest of the terms of the split was computed at indexing,
+    /// i.e. the fields registered in the [`DocMapping`](quickwit_config::DocMapping)
+    /// `term_digest_fields` attribute. The digests are not stored in the metastore but in a
+    /// file uploaded next to the split file, see [`quickwit_common::term_digests_file`].
+    ///
+    /// The root searcher uses them to skip splits that cannot contain the searched terms.
+    pub term_digest_fields: BTreeSet<String>,
+
     /// Contains the range of bytes of the footer that needs to be downloaded
     /// in order to open a split.
     ///
@@ -132,6 +149,14 @@ pub struct SplitMetadata {
     /// Number of merge operations that was involved to create
     /// this split.
     pub num_merge_ops: usize,
+
+    /// Breakdown of the size of the split by component. Not available for the splits created
+    /// before these stats were recorded.
+    pub storage_stats: Option<SplitStorageStats>,
+
+    /// Version of the doc mapping of the index at the time the split was created. Splits built
+    /// under different versions of the doc mapping are never merged together.
+    pub doc_mapping_version: u64,
 }
 
 impl fmt::Debug for SplitMetadata {
@@ -173,9 +198,21 @@ impl fmt::Debug for SplitMetadata {
             tags_str.push('}');
             debug_struct.field("tags", &tags_str);
         }
+        if !self.term_digest_fields.is_empty() {
+            debug_struct.field("term_digest_fields", &self.term_digest_fields);
+        }
+        if !self.secondary_time_ranges.is_empty() {
+            debug_struct.field("secondary_time_ranges", &self.secondary_time_ranges);
+        }
         debug_struct.field("footer_offsets", &self.footer_offsets);
         debug_struct.field("delete_opstamp", &self.delete_opstamp);
         debug_struct.field("num_merge_ops", &self.num_merge_ops);
+        if let Some(storage_stats) = &self.storage_stats {
+            debug_struct.field("storage_stats", storage_stats);
+        }
+        if self.doc_mapping_version != 0 {
+            debug_struct.field("doc_mapping_version", &self.doc_mapping_version);
+        }
         debug_struct.finish()
     }
 }
@@ -274,13 +311,17 @@ impl quickwit_config::TestableForRegression for SplitMetadata {
             num_docs: 12303,
             uncompressed_docs_size_in_bytes: 234234,
             time_range: Some(121000..=130198),
+            secondary_time_ranges: BTreeMap::new(),
             create_timestamp: 3,
             maturity: SplitMaturity::Immature {
                 maturation_period: Duration::from_secs(4),
             },
             tags: ["234".to_string(), "aaa".to_string()].into_iter().collect(),
+            term_digest_fields: BTreeSet::new(),
             footer_offsets: 1000..2000,
             num_merge_ops: 3,
+            storage_stats: None,
+            doc_mapping_version: 0,
         }
     }
 
@@ -289,6 +330,23 @@ impl quickwit_config::TestableForRegression for SplitMetadata {
     }
 }
 
+/// Breakdown of the size of a split by component, computed when the split is packaged.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SplitStorageStats {
+    /// Size of the doc store in bytes.
+    pub doc_store_num_bytes: u64,
+    /// Size of the inverted index in bytes: term dictionaries, postings, positions, and field
+    /// norms.
+    pub postings_num_bytes: u64,
+    /// Size of the fast fields in bytes.
+    pub fast_fields_num_bytes: u64,
+    /// Size of the hotcache in bytes.
+    pub hotcache_num_bytes: u64,
+    /// Size of the inverted index and fast field data of each field in bytes.
+    #[serde(default)]
+    pub per_field_num_bytes: BTreeMap<String, u64>,
+}
+
 /// A split state.
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, utoipa::ToSchema)]
 pub enum SplitState {
@@ -409,6 +467,7 @@ mod tests {
             num_docs: 100,
             uncompressed_docs_size_in_bytes: 1024,
             time_range: Some(0..=100),
+            secondary_time_ranges: BTreeMap::new(),
             create_timestamp: 1629867600,
             maturity: SplitMaturity::Mature,
             tags: {
@@ -420,9 +479,12 @@ mod tests {
                 tags.insert("😿".to_string());
                 tags
             },
+            term_digest_fields: BTreeSet::new(),
             footer_offsets: 0..1024,
             delete_opstamp: 0,
             num_merge_ops: 0,
+            storage_stats: None,
+            doc_mapping_version: 0,
         };
 
         let expected_output = "SplitMetadata { split_id: \"split-1\", index_uid: IndexUid { \
@@ -17,13 +17,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Range, RangeInclusive};
 
+use quickwit_common::is_zero;
 use quickwit_proto::types::IndexUid;
 use serde::{Deserialize, Serialize};
 
-use crate::split_metadata::{utc_now_timestamp, SplitMaturity};
+use crate::split_metadata::{utc_now_timestamp, SplitMaturity, SplitStorageStats};
 use crate::SplitMetadata;
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -63,6 +64,12 @@ pub(crate) struct SplitMetadataV0_8 {
     /// the split.
     pub time_range: Option<RangeInclusive<i64>>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    /// The min / max timestamps in the split of the secondary timestamp fields.
+    pub secondary_time_ranges: BTreeMap<String, RangeInclusive<i64>>,
+
     /// Timestamp for tracking when the split was created.
     #[serde(default = "utc_now_timestamp")]
     pub create_timestamp: i64,
@@ -77,6 +84,13 @@ pub(crate) struct SplitMetadataV0_8 {
     /// A set of tags for categorizing and searching group of splits.
     pub tags: BTreeSet<String>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    /// Fields whose term digests, stored next to the split file, are used to prune splits at
+    /// search time.
+    pub term_digest_fields: BTreeSet<String>,
+
     #[schema(value_type = Object)]
     /// Contains the range of bytes of the footer that needs to be downloaded
     /// in order to open a split.
@@ -91,6 +105,14 @@ pub(crate) struct SplitMetadataV0_8 {
 
     #[serde(default)]
     num_merge_ops: usize,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_stats: Option<SplitStorageStats>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    doc_mapping_version: u64,
 }
 
 impl From<SplitMetadataV0_8> for SplitMetadata {
@@ -121,11 +143,15 @@ impl From<SplitMetadataV0_8> for SplitMetadata {
             num_docs: v8.num_docs,
             uncompressed_docs_size_in_bytes: v8.uncompressed_docs_size_in_bytes,
             time_range: v8.time_range,
+            secondary_time_ranges: v8.secondary_time_ranges,
             create_timestamp: v8.create_timestamp,
             maturity: v8.maturity,
             tags: v8.tags,
+            term_digest_fields: v8.term_digest_fields,
             footer_offsets: v8.footer_offsets,
             num_merge_ops: v8.num_merge_ops,
+            storage_stats: v8.storage_stats,
+            doc_mapping_version: v8.doc_mapping_version,
         }
     }
 }
@@ -142,11 +168,15 @@ impl From<SplitMetadata> for SplitMetadataV0_8 {
             num_docs: split.num_docs,
             uncompressed_docs_size_in_bytes: split.uncompressed_docs_size_in_bytes,
             time_range: split.time_range,
+            secondary_time_ranges: split.secondary_time_ranges,
             create_timestamp: split.create_timestamp,
             maturity: split.maturity,
             tags: split.tags,
+            term_digest_fields: split.term_digest_fields,
             footer_offsets: split.footer_offsets,
             num_merge_ops: split.num_merge_ops,
+            storage_stats: split.storage_stats,
+            doc_mapping_version: split.doc_mapping_version,
         }
     }
 }
@@ -0,0 +1,104 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use bytesize::ByteSize;
+use quickwit_config::ClusterSettings;
+use quickwit_proto::metastore::{
+    serde_utils, GetClusterSettingsRequest, MetastoreError, MetastoreService,
+    UpdateClusterSettingsRequest,
+};
+
+use super::DefaultForTest;
+use crate::MetastoreServiceExt;
+
+pub async fn test_metastore_update_cluster_settings<
+    MetastoreUnderTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreUnderTest::default_for_test().await;
+
+    // The cluster settings are a singleton shared by all the tests running against the same
+    // metastore, so we only rely on the version being incremented.
+    let initial_version = metastore
+        .get_cluster_settings(GetClusterSettingsRequest {})
+        .await
+        .unwrap()
+        .version;
+
+    let cluster_settings = ClusterSettings {
+        max_num_concurrent_split_searches: Some(50),
+        split_footer_cache_capacity: Some(ByteSize::mb(500)),
+        ..Default::default()
+    };
+    let update_cluster_settings_request = UpdateClusterSettingsRequest {
+        cluster_settings_json: serde_utils::to_json_str(&cluster_settings).unwrap(),
+    };
+    let update_cluster_settings_response = metastore
+        .update_cluster_settings(update_cluster_settings_request)
+        .await
+        .unwrap();
+    assert_eq!(
+        update_cluster_settings_response.version,
+        initial_version + 1
+    );
+
+    let get_cluster_settings_response = metastore
+        .get_cluster_settings(GetClusterSettingsRequest {})
+        .await
+        .unwrap();
+    assert_eq!(get_cluster_settings_response.version, initial_version + 1);
+
+    let fetched_cluster_settings: ClusterSettings =
+        serde_utils::from_json_str(&get_cluster_settings_response.cluster_settings_json).unwrap();
+    assert_eq!(fetched_cluster_settings, cluster_settings);
+
+    let update_cluster_settings_request = UpdateClusterSettingsRequest {
+        cluster_settings_json: "{}".to_string(),
+    };
+    let update_cluster_settings_response = metastore
+        .update_cluster_settings(update_cluster_settings_request)
+        .await
+        .unwrap();
+    assert_eq!(
+        update_cluster_settings_response.version,
+        initial_version + 2
+    );
+
+    let get_cluster_settings_response = metastore
+        .get_cluster_settings(GetClusterSettingsRequest {})
+        .await
+        .unwrap();
+    let fetched_cluster_settings: ClusterSettings =
+        serde_utils::from_json_str(&get_cluster_settings_response.cluster_settings_json).unwrap();
+    assert_eq!(fetched_cluster_settings, ClusterSettings::default());
+
+    let update_cluster_settings_request = UpdateClusterSettingsRequest {
+        cluster_settings_json: r#"{"unknown_setting": 1}"#.to_string(),
+    };
+    let error = metastore
+        .update_cluster_settings(update_cluster_settings_request)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MetastoreError::JsonDeserializeError { .. }));
+
+    let get_cluster_settings_response = metastore
+        .get_cluster_settings(GetClusterSettingsRequest {})
+        .await
+        .unwrap();
+    assert_eq!(get_cluster_settings_response.version, initial_version + 2);
+}
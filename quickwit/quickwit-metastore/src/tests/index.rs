@@ -29,7 +29,8 @@ use std::collections::BTreeSet;
 
 use quickwit_common::rand::append_random_suffix;
 use quickwit_config::{
-    IndexConfig, RetentionPolicy, SearchSettings, SourceConfig, CLI_SOURCE_ID, INGEST_V2_SOURCE_ID,
+    DocMapping, IndexConfig, LegalHold, RetentionPolicy, SearchSettings, SourceConfig,
+    CLI_SOURCE_ID, INGEST_V2_SOURCE_ID,
 };
 use quickwit_doc_mapper::FieldMappingType;
 use quickwit_proto::metastore::{
@@ -125,27 +126,46 @@ pub async fn test_metastore_update_index<
             .filter(|f| !current_defaults.contains(&f.name))
             .map(|f| f.name.clone())
             .collect(),
+        ..Default::default()
     };
 
     let new_retention_policy_opt = Some(RetentionPolicy {
         retention_period: String::from("3 days"),
         evaluation_schedule: String::from("daily"),
+        partition_overrides: Vec::new(),
     });
     assert_ne!(
         index_metadata.index_config.retention_policy_opt, new_retention_policy_opt,
         "original and updated value are the same, test became inefficient"
     );
 
-    // run same update twice to check idempotence, then None as a corner case check
-    for loop_retention_policy_opt in [
-        new_retention_policy_opt.clone(),
-        new_retention_policy_opt.clone(),
-        None,
+    let new_legal_hold_opt = Some(LegalHold {
+        split_ids: Vec::new(),
+        reason: Some("test-case".to_string()),
+    });
+
+    // run same update twice to check idempotence, then None as a corner case check. The legal
+    // hold is left unchanged when the update omits it and only lifted explicitly.
+    for (loop_retention_policy_opt, loop_legal_hold_update_opt, loop_legal_hold_opt) in [
+        (
+            new_retention_policy_opt.clone(),
+            Some(new_legal_hold_opt.clone()),
+            new_legal_hold_opt.clone(),
+        ),
+        (
+            new_retention_policy_opt.clone(),
+            Some(new_legal_hold_opt.clone()),
+            new_legal_hold_opt.clone(),
+        ),
+        (None, None, new_legal_hold_opt.clone()),
+        (None, Some(None), None),
     ] {
         let index_update = UpdateIndexRequest::try_from_updates(
             index_uid.clone(),
             &new_search_setting,
             &loop_retention_policy_opt,
+            &loop_legal_hold_update_opt,
+            None,
         )
         .unwrap();
         let response_metadata = metastore
@@ -163,6 +183,10 @@ pub async fn test_metastore_update_index<
             response_metadata.index_config.retention_policy_opt,
             loop_retention_policy_opt
         );
+        assert_eq!(
+            response_metadata.index_config.legal_hold_opt,
+            loop_legal_hold_opt
+        );
         let updated_metadata = metastore
             .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
             .await
@@ -175,6 +199,113 @@ pub async fn test_metastore_update_index<
     cleanup_index(&mut metastore, index_uid).await;
 }
 
+pub async fn test_metastore_update_doc_mapping<
+    MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-update-doc-mapping");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid: IndexUid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+
+    // Turn the `owner` field into a fast field.
+    let mut doc_mapping_json = serde_json::to_value(&index_config.doc_mapping).unwrap();
+    let owner_field_mapping = doc_mapping_json["field_mappings"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|field_mapping| field_mapping["name"] == "owner")
+        .unwrap();
+    owner_field_mapping["fast"] = serde_json::Value::Bool(true);
+    let new_doc_mapping: DocMapping = serde_json::from_value(doc_mapping_json).unwrap();
+
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.search_settings,
+        &None,
+        &None,
+        Some(&new_doc_mapping),
+    )
+    .unwrap();
+    let index_metadata = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    let mut expected_doc_mapping = new_doc_mapping.clone();
+    expected_doc_mapping.doc_mapping_version = 1;
+    assert_eq!(
+        index_metadata.index_config.doc_mapping,
+        expected_doc_mapping
+    );
+
+    // Turning the fast field back into a regular field is a new update.
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.search_settings,
+        &None,
+        &None,
+        Some(&index_config.doc_mapping),
+    )
+    .unwrap();
+    let index_metadata = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    let mut expected_doc_mapping = index_config.doc_mapping.clone();
+    expected_doc_mapping.doc_mapping_version = 2;
+    assert_eq!(
+        index_metadata.index_config.doc_mapping,
+        expected_doc_mapping
+    );
+
+    // Removing a field is not allowed.
+    let mut doc_mapping_json = serde_json::to_value(&index_config.doc_mapping).unwrap();
+    doc_mapping_json["field_mappings"]
+        .as_array_mut()
+        .unwrap()
+        .retain(|field_mapping| field_mapping["name"] != "response_payload");
+    let invalid_doc_mapping: DocMapping = serde_json::from_value(doc_mapping_json).unwrap();
+
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.search_settings,
+        &None,
+        &None,
+        Some(&invalid_doc_mapping),
+    )
+    .unwrap();
+    let error = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MetastoreError::InvalidArgument { .. }));
+
+    let index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(
+        index_metadata.index_config.doc_mapping,
+        expected_doc_mapping
+    );
+
+    cleanup_index(&mut metastore, index_uid).await;
+}
+
 pub async fn test_metastore_create_index_with_sources<
     MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
 >() {
@@ -237,6 +237,222 @@ pub async fn test_metastore_stream_splits<MetastoreToTest: MetastoreServiceExt +
     );
 }
 
+pub async fn test_metastore_list_splits_sorted_by_time_range_end_desc<
+    MetastoreToTest: MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-list-splits-sorted-by-time-range-end-desc");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid: IndexUid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+
+    let split_id_1 = format!("{index_id}--split-1");
+    let split_metadata_1 = SplitMetadata {
+        split_id: split_id_1.clone(),
+        index_uid: index_uid.clone(),
+        time_range: Some(0..=99),
+        ..Default::default()
+    };
+    let split_id_2 = format!("{index_id}--split-2");
+    let split_metadata_2 = SplitMetadata {
+        split_id: split_id_2.clone(),
+        index_uid: index_uid.clone(),
+        time_range: Some(100..=199),
+        ..Default::default()
+    };
+    let split_id_3 = format!("{index_id}--split-3");
+    let split_metadata_3 = SplitMetadata {
+        split_id: split_id_3.clone(),
+        index_uid: index_uid.clone(),
+        time_range: None,
+        ..Default::default()
+    };
+    let split_id_4 = format!("{index_id}--split-4");
+    let split_metadata_4 = SplitMetadata {
+        split_id: split_id_4.clone(),
+        index_uid: index_uid.clone(),
+        time_range: Some(50..=199),
+        ..Default::default()
+    };
+    let stage_splits_request = StageSplitsRequest::try_from_splits_metadata(
+        index_uid.clone(),
+        vec![
+            split_metadata_1,
+            split_metadata_2,
+            split_metadata_3,
+            split_metadata_4,
+        ],
+    )
+    .unwrap();
+    metastore.stage_splits(stage_splits_request).await.unwrap();
+
+    let query = ListSplitsQuery::for_index(index_uid.clone()).sort_by_time_range_end_desc();
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap();
+    let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id()).collect();
+    assert_eq!(
+        split_ids,
+        &[&split_id_3, &split_id_2, &split_id_4, &split_id_1]
+    );
+
+    let query = ListSplitsQuery::for_index(index_uid.clone())
+        .sort_by_time_range_end_desc()
+        .with_limit(2)
+        .with_offset(1);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap();
+    let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id()).collect();
+    assert_eq!(split_ids, &[&split_id_2, &split_id_4]);
+
+    // Keyset pagination: splits without a time range come first, then ties on the time range end
+    // are broken by split ID.
+    for (after_split_metadata, expected_split_ids) in [
+        (&splits[0].split_metadata, vec![&split_id_4, &split_id_1]),
+        (&splits[1].split_metadata, vec![&split_id_1]),
+    ] {
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .after_split_by_time_range_end_desc(after_split_metadata)
+            .with_limit(2);
+        let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+        let splits = metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        let split_ids: Vec<&String> = splits
+            .iter()
+            .map(|split| &split.split_metadata.split_id)
+            .collect();
+        assert_eq!(split_ids, expected_split_ids);
+    }
+    let query = ListSplitsQuery::for_index(index_uid.clone()).with_limit(10);
+    let split_metadata_3 = SplitMetadata {
+        split_id: split_id_3.clone(),
+        ..Default::default()
+    };
+    let query = query.after_split_by_time_range_end_desc(&split_metadata_3);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap();
+    let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id()).collect();
+    assert_eq!(split_ids, &[&split_id_2, &split_id_4, &split_id_1]);
+
+    cleanup_index(&mut metastore, index_uid.clone()).await;
+}
+
+pub async fn test_metastore_list_splits_with_snapshot_timestamp<
+    MetastoreToTest: MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-list-splits-with-snapshot-timestamp");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid: IndexUid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+
+    let split_ids: Vec<String> = (1..=3)
+        .map(|split_ord| format!("{index_id}--split-{split_ord}"))
+        .collect();
+    let splits_metadata: Vec<SplitMetadata> = split_ids
+        .iter()
+        .map(|split_id| SplitMetadata {
+            split_id: split_id.clone(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let stage_splits_request =
+        StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata).unwrap();
+    metastore.stage_splits(stage_splits_request).await.unwrap();
+
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid.clone()),
+        staged_split_ids: vec![split_ids[0].clone(), split_ids[1].clone()],
+        ..Default::default()
+    };
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    sleep(Duration::from_secs(1)).await;
+    let snapshot_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    sleep(Duration::from_secs(1)).await;
+
+    // Split 3 replaces split 1 after the snapshot, as a merge would.
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid.clone()),
+        staged_split_ids: vec![split_ids[2].clone()],
+        replaced_split_ids: vec![split_ids[0].clone()],
+        ..Default::default()
+    };
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    let query = ListSplitsQuery::for_index(index_uid.clone())
+        .with_split_state(SplitState::Published)
+        .with_snapshot_timestamp(snapshot_timestamp);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap();
+    assert_eq!(collect_split_ids(&splits), &[&split_ids[0], &split_ids[1]]);
+
+    let query =
+        ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap();
+    assert_eq!(collect_split_ids(&splits), &[&split_ids[1], &split_ids[2]]);
+
+    cleanup_index(&mut metastore, index_uid.clone()).await;
+}
+
 pub async fn test_metastore_list_splits<MetastoreToTest: MetastoreServiceExt + DefaultForTest>() {
     let mut metastore = MetastoreToTest::default_for_test().await;
 
@@ -30,6 +30,7 @@ use quickwit_proto::metastore::{
 use quickwit_proto::tonic::transport::Channel;
 use quickwit_proto::types::IndexUid;
 
+pub(crate) mod cluster_settings;
 pub(crate) mod delete_task;
 pub(crate) mod index;
 pub(crate) mod list_splits;
@@ -192,6 +193,12 @@ macro_rules! metastore_test_suite {
                 $crate::tests::index::test_metastore_update_index::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_update_doc_mapping() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::index::test_metastore_update_doc_mapping::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_create_index_with_sources() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -304,6 +311,18 @@ macro_rules! metastore_test_suite {
                 $crate::tests::list_splits::test_metastore_list_splits::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_list_splits_sorted_by_time_range_end_desc() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::list_splits::test_metastore_list_splits_sorted_by_time_range_end_desc::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_list_splits_with_snapshot_timestamp() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::list_splits::test_metastore_list_splits_with_snapshot_timestamp::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_split_update_timestamp() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -400,6 +419,11 @@ macro_rules! metastore_test_suite {
                 $crate::tests::shard::test_metastore_delete_shards::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_mark_shards_closed() {
+                $crate::tests::shard::test_metastore_mark_shards_closed::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_apply_checkpoint_delta_v2_single_shard() {
                 $crate::tests::shard::test_metastore_apply_checkpoint_delta_v2_single_shard::<$metastore_type>().await;
@@ -441,6 +465,14 @@ macro_rules! metastore_test_suite {
             async fn test_metastore_delete_index_templates() {
                 $crate::tests::template::test_metastore_delete_index_templates::<$metastore_type>().await;
             }
+
+            /// Cluster Settings API tests
+
+            #[tokio::test]
+            #[serial_test::serial]
+            async fn test_metastore_update_cluster_settings() {
+                $crate::tests::cluster_settings::test_metastore_update_cluster_settings::<$metastore_type>().await;
+            }
         }
     };
 }
@@ -23,8 +23,8 @@ use quickwit_config::{IndexConfig, SourceConfig};
 use quickwit_proto::ingest::{Shard, ShardState};
 use quickwit_proto::metastore::{
     AcquireShardsRequest, AddSourceRequest, CreateIndexRequest, DeleteShardsRequest, EntityKind,
-    ListShardsRequest, ListShardsSubrequest, MetastoreError, MetastoreService, OpenShardSubrequest,
-    OpenShardsRequest, PublishSplitsRequest,
+    ListShardsRequest, ListShardsSubrequest, MarkShardsClosedRequest, MetastoreError,
+    MetastoreService, OpenShardSubrequest, OpenShardsRequest, PublishSplitsRequest,
 };
 use quickwit_proto::types::{IndexUid, Position, ShardId, SourceId};
 
@@ -484,6 +484,82 @@ pub async fn test_metastore_delete_shards<
     cleanup_index(&mut metastore, test_index.index_uid).await;
 }
 
+pub async fn test_metastore_mark_shards_closed<
+    MetastoreUnderTest: MetastoreService + MetastoreServiceExt + DefaultForTest + ReadWriteShardsForTest,
+>() {
+    let mut metastore = MetastoreUnderTest::default_for_test().await;
+
+    let test_index = TestIndex::create_index_with_source(
+        &mut metastore,
+        "test-mark-shards-closed",
+        SourceConfig::ingest_v2(),
+    )
+    .await;
+
+    let shards = vec![
+        Shard {
+            index_uid: test_index.index_uid.clone().into(),
+            source_id: test_index.source_id.clone(),
+            shard_id: Some(ShardId::from(1)),
+            shard_state: ShardState::Open as i32,
+            publish_position_inclusive: Some(Position::Beginning),
+            ..Default::default()
+        },
+        Shard {
+            index_uid: test_index.index_uid.clone().into(),
+            source_id: test_index.source_id.clone(),
+            shard_id: Some(ShardId::from(2)),
+            shard_state: ShardState::Open as i32,
+            publish_position_inclusive: Some(Position::offset(42u64)),
+            ..Default::default()
+        },
+        Shard {
+            index_uid: test_index.index_uid.clone().into(),
+            source_id: test_index.source_id.clone(),
+            shard_id: Some(ShardId::from(3)),
+            shard_state: ShardState::Closed as i32,
+            publish_position_inclusive: Some(Position::Beginning),
+            ..Default::default()
+        },
+    ];
+    metastore
+        .insert_shards(&test_index.index_uid, &test_index.source_id, shards)
+        .await;
+
+    // Attempt to close shards #2, #3, and #4.
+    let mark_shards_closed_request = MarkShardsClosedRequest {
+        index_uid: test_index.index_uid.clone().into(),
+        source_id: test_index.source_id.clone(),
+        shard_ids: vec![ShardId::from(2), ShardId::from(3), ShardId::from(4)],
+    };
+    metastore
+        .mark_shards_closed(mark_shards_closed_request)
+        .await
+        .unwrap();
+
+    let mut all_shards = metastore
+        .list_all_shards(&test_index.index_uid, &test_index.source_id)
+        .await;
+    assert_eq!(all_shards.len(), 3);
+
+    all_shards.sort_unstable_by(|left, right| left.shard_id.cmp(&right.shard_id));
+
+    assert_eq!(all_shards[0].shard_id(), ShardId::from(1));
+    assert_eq!(all_shards[0].shard_state(), ShardState::Open);
+
+    assert_eq!(all_shards[1].shard_id(), ShardId::from(2));
+    assert_eq!(all_shards[1].shard_state(), ShardState::Closed);
+    assert_eq!(
+        all_shards[1].publish_position_inclusive(),
+        &Position::offset(42u64)
+    );
+
+    assert_eq!(all_shards[2].shard_id(), ShardId::from(3));
+    assert_eq!(all_shards[2].shard_state(), ShardState::Closed);
+
+    cleanup_index(&mut metastore, test_index.index_uid).await;
+}
+
 pub async fn test_metastore_apply_checkpoint_delta_v2_single_shard<
     MetastoreUnderTest: MetastoreService + MetastoreServiceExt + DefaultForTest + ReadWriteShardsForTest,
 >() {
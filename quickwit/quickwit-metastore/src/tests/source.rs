@@ -59,6 +59,8 @@ pub async fn test_metastore_add_source<MetastoreToTest: MetastoreServiceExt + De
         enabled: true,
         source_params: SourceParams::void(),
         transform_config: None,
+        json_schema_config: None,
+        wasm_plugin_config: None,
         input_format: SourceInputFormat::Json,
     };
 
@@ -158,6 +160,8 @@ pub async fn test_metastore_toggle_source<MetastoreToTest: MetastoreServiceExt +
         enabled: true,
         source_params: SourceParams::void(),
         transform_config: None,
+        json_schema_config: None,
+        wasm_plugin_config: None,
         input_format: SourceInputFormat::Json,
     };
     let add_source_request =
@@ -224,6 +228,8 @@ pub async fn test_metastore_delete_source<MetastoreToTest: MetastoreServiceExt +
         enabled: true,
         source_params: SourceParams::void(),
         transform_config: None,
+        json_schema_config: None,
+        wasm_plugin_config: None,
         input_format: SourceInputFormat::Json,
     };
 
@@ -345,6 +351,8 @@ pub async fn test_metastore_reset_checkpoint<
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
             input_format: SourceInputFormat::Json,
         };
         metastore
@@ -0,0 +1,489 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use quickwit_config::{
+    validate_index_id_pattern, validate_tenant_id, OtelApiKeyConfig, OtelAuthConfig, OtelJwtConfig,
+    OtelRole,
+};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use subtle::ConstantTimeEq;
+use tonic::metadata::MetadataMap;
+use tonic::Status;
+use tracing::debug;
+
+use super::flow_control::OTLP_API_KEY_HEADER;
+
+/// Caller of the OTLP ingest or Jaeger query endpoints, as identified by its credentials.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OtelPrincipal {
+    roles: Vec<OtelRole>,
+    tenant_id_opt: Option<String>,
+}
+
+impl OtelPrincipal {
+    /// Caller of the endpoints when authentication is disabled.
+    fn anonymous() -> Self {
+        Self {
+            roles: vec![OtelRole::Ingest, OtelRole::Read],
+            tenant_id_opt: None,
+        }
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(tenant_id_opt: Option<&str>) -> Self {
+        Self {
+            roles: vec![OtelRole::Ingest, OtelRole::Read],
+            tenant_id_opt: tenant_id_opt.map(ToString::to_string),
+        }
+    }
+
+    /// Tenant the caller is confined to, if any.
+    pub fn tenant_id(&self) -> Option<&str> {
+        self.tenant_id_opt.as_deref()
+    }
+
+    /// Restricts the index ID patterns of a query to the indexes of the tenant of the caller,
+    /// i.e. the indexes suffixed with `-{tenant_id}`. Tenant IDs cannot contain any `-`, so a
+    /// pattern cannot be crafted to match the indexes of another tenant.
+    pub fn scope_index_id_patterns(
+        &self,
+        index_id_patterns: Vec<String>,
+    ) -> Result<Vec<String>, Status> {
+        let Some(tenant_id) = self.tenant_id() else {
+            return Ok(index_id_patterns);
+        };
+        let mut scoped_index_id_patterns = Vec::with_capacity(index_id_patterns.len());
+
+        for index_id_pattern in index_id_patterns {
+            let scoped_index_id_pattern = format!("{index_id_pattern}-{tenant_id}");
+            validate_index_id_pattern(&scoped_index_id_pattern, true).map_err(|error| {
+                Status::invalid_argument(format!("invalid tenant index ID pattern: {error}"))
+            })?;
+            scoped_index_id_patterns.push(scoped_index_id_pattern);
+        }
+        Ok(scoped_index_id_patterns)
+    }
+}
+
+/// Authenticates the callers of the OTLP ingest and Jaeger query endpoints with API keys or JSON
+/// Web Tokens, and checks that they were granted the role required by the endpoint.
+#[derive(Clone, Default)]
+pub struct OtelAuthenticator {
+    inner_opt: Option<Arc<InnerOtelAuthenticator>>,
+}
+
+struct InnerOtelAuthenticator {
+    api_keys: Vec<OtelApiKeyConfig>,
+    jwt_verifier_opt: Option<JwtVerifier>,
+}
+
+impl std::fmt::Debug for OtelAuthenticator {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("OtelAuthenticator")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+impl OtelAuthenticator {
+    pub fn new(auth_config: &OtelAuthConfig) -> Self {
+        if !auth_config.is_enabled() {
+            return Self::default();
+        }
+        let inner = InnerOtelAuthenticator {
+            api_keys: auth_config.api_keys.clone(),
+            jwt_verifier_opt: auth_config.jwt.as_ref().map(JwtVerifier::new),
+        };
+        Self {
+            inner_opt: Some(Arc::new(inner)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner_opt.is_some()
+    }
+
+    /// Authenticates the caller from the credentials found in the metadata of the request and
+    /// checks that it was granted `role`. Returns `UNAUTHENTICATED` when the credentials are
+    /// missing or invalid and `PERMISSION_DENIED` when the role was not granted.
+    pub fn authorize(
+        &self,
+        metadata: &MetadataMap,
+        role: OtelRole,
+    ) -> Result<OtelPrincipal, Status> {
+        let Some(inner) = &self.inner_opt else {
+            return Ok(OtelPrincipal::anonymous());
+        };
+        let principal = inner.authenticate(metadata)?;
+
+        if !principal.roles.contains(&role) {
+            return Err(Status::permission_denied(format!(
+                "caller was not granted the `{}` role",
+                role_name(role)
+            )));
+        }
+        Ok(principal)
+    }
+}
+
+impl InnerOtelAuthenticator {
+    fn authenticate(&self, metadata: &MetadataMap) -> Result<OtelPrincipal, Status> {
+        if let Some(api_key) = metadata
+            .get(OTLP_API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            return self
+                .find_api_key(api_key.trim())
+                .ok_or_else(|| Status::unauthenticated("invalid API key"));
+        }
+        let Some(token) = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().strip_prefix("Bearer "))
+            .map(str::trim)
+        else {
+            return Err(Status::unauthenticated(format!(
+                "missing credentials: expected an `{OTLP_API_KEY_HEADER}` header or an \
+                 `authorization` bearer token"
+            )));
+        };
+        if let Some(principal) = self.find_api_key(token) {
+            return Ok(principal);
+        }
+        if let Some(jwt_verifier) = &self.jwt_verifier_opt {
+            return jwt_verifier.verify(token);
+        }
+        Err(Status::unauthenticated("invalid API key"))
+    }
+
+    /// Looks up an API key in constant time with respect to the value of the configured keys so
+    /// that they cannot be guessed from the response times.
+    fn find_api_key(&self, api_key: &str) -> Option<OtelPrincipal> {
+        let mut matching_api_key_opt: Option<&OtelApiKeyConfig> = None;
+
+        for api_key_config in &self.api_keys {
+            if bool::from(api_key_config.api_key.as_bytes().ct_eq(api_key.as_bytes())) {
+                matching_api_key_opt = Some(api_key_config);
+            }
+        }
+        let api_key_config = matching_api_key_opt?;
+        debug!(api_key_name=%api_key_config.name, "authenticated OTEL caller with API key");
+
+        let principal = OtelPrincipal {
+            roles: api_key_config.roles.clone(),
+            tenant_id_opt: api_key_config.tenant_id.clone(),
+        };
+        Some(principal)
+    }
+}
+
+struct JwtVerifier {
+    decoding_key: DecodingKey,
+    validation: Validation,
+    roles_claim: String,
+    tenant_claim: String,
+}
+
+impl JwtVerifier {
+    fn new(jwt_config: &OtelJwtConfig) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+
+        if let Some(issuer) = &jwt_config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &jwt_config.audience {
+            validation.set_audience(&[audience]);
+        } else {
+            validation.validate_aud = false;
+        }
+        Self {
+            decoding_key: DecodingKey::from_secret(jwt_config.hmac_secret.as_bytes()),
+            validation,
+            roles_claim: jwt_config.roles_claim.clone(),
+            tenant_claim: jwt_config.tenant_claim.clone(),
+        }
+    }
+
+    fn verify(&self, token: &str) -> Result<OtelPrincipal, Status> {
+        let claims = jsonwebtoken::decode::<JsonMap<String, JsonValue>>(
+            token,
+            &self.decoding_key,
+            &self.validation,
+        )
+        .map_err(|error| Status::unauthenticated(format!("invalid JWT: {error}")))?
+        .claims;
+
+        // Roles unknown to Quickwit are ignored so that the tokens can be shared with other
+        // services.
+        let roles: Vec<OtelRole> = match claims.get(&self.roles_claim) {
+            Some(JsonValue::Array(role_values)) => role_values
+                .iter()
+                .filter_map(|role_value| serde_json::from_value(role_value.clone()).ok())
+                .collect(),
+            None => Vec::new(),
+            Some(_) => {
+                return Err(Status::unauthenticated(format!(
+                    "invalid JWT: claim `{}` must be an array of strings",
+                    self.roles_claim
+                )));
+            }
+        };
+        let tenant_id_opt = match claims.get(&self.tenant_claim) {
+            Some(JsonValue::String(tenant_id)) => {
+                validate_tenant_id(tenant_id).map_err(|error| {
+                    Status::unauthenticated(format!(
+                        "invalid JWT: claim `{}` is not a valid tenant ID: {error}",
+                        self.tenant_claim
+                    ))
+                })?;
+                Some(tenant_id.clone())
+            }
+            None => None,
+            Some(_) => {
+                return Err(Status::unauthenticated(format!(
+                    "invalid JWT: claim `{}` must be a string",
+                    self.tenant_claim
+                )));
+            }
+        };
+        let principal = OtelPrincipal {
+            roles,
+            tenant_id_opt,
+        };
+        Ok(principal)
+    }
+}
+
+fn role_name(role: OtelRole) -> &'static str {
+    match role {
+        OtelRole::Ingest => "ingest",
+        OtelRole::Read => "read",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+    use tonic::Code;
+
+    use super::*;
+
+    const HMAC_SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn make_auth_config() -> OtelAuthConfig {
+        OtelAuthConfig {
+            api_keys: vec![
+                OtelApiKeyConfig {
+                    name: "collector-acme".to_string(),
+                    api_key: "acme-secret".to_string(),
+                    roles: vec![OtelRole::Ingest],
+                    tenant_id: Some("acme".to_string()),
+                },
+                OtelApiKeyConfig {
+                    name: "grafana".to_string(),
+                    api_key: "grafana-secret".to_string(),
+                    roles: vec![OtelRole::Read],
+                    tenant_id: None,
+                },
+            ],
+            jwt: Some(OtelJwtConfig {
+                hmac_secret: HMAC_SECRET.to_string(),
+                issuer: Some("https://auth.example.com".to_string()),
+                audience: None,
+                roles_claim: "roles".to_string(),
+                tenant_claim: "tenant".to_string(),
+            }),
+        }
+    }
+
+    fn make_metadata(key: &'static str, value: &str) -> MetadataMap {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(key, value.parse().unwrap());
+        metadata
+    }
+
+    fn make_jwt(claims: JsonValue, secret: &str) -> String {
+        jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn expiration() -> u64 {
+        jsonwebtoken::get_current_timestamp() + 3_600
+    }
+
+    #[test]
+    fn test_principal_scope_index_id_patterns() {
+        let index_id_patterns = vec![
+            "otel-traces-v0_*".to_string(),
+            "-otel-traces-v0_7".to_string(),
+        ];
+        let scoped_index_id_patterns = OtelPrincipal::for_test(None)
+            .scope_index_id_patterns(index_id_patterns.clone())
+            .unwrap();
+        assert_eq!(scoped_index_id_patterns, index_id_patterns);
+
+        let scoped_index_id_patterns = OtelPrincipal::for_test(Some("acme"))
+            .scope_index_id_patterns(index_id_patterns)
+            .unwrap();
+        assert_eq!(
+            scoped_index_id_patterns,
+            ["otel-traces-v0_*-acme", "-otel-traces-v0_7-acme"]
+        );
+    }
+
+    #[test]
+    fn test_authenticator_disabled() {
+        let authenticator = OtelAuthenticator::new(&OtelAuthConfig::default());
+        assert!(!authenticator.is_enabled());
+
+        let principal = authenticator
+            .authorize(&MetadataMap::new(), OtelRole::Ingest)
+            .unwrap();
+        assert!(principal.tenant_id().is_none());
+
+        authenticator
+            .authorize(&MetadataMap::new(), OtelRole::Read)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_authenticator_api_keys() {
+        let authenticator = OtelAuthenticator::new(&make_auth_config());
+        assert!(authenticator.is_enabled());
+
+        let status = authenticator
+            .authorize(&MetadataMap::new(), OtelRole::Ingest)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let metadata = make_metadata("x-api-key", "wrong-secret");
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Ingest)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let metadata = make_metadata("x-api-key", "acme-secret");
+        let principal = authenticator
+            .authorize(&metadata, OtelRole::Ingest)
+            .unwrap();
+        assert_eq!(principal.tenant_id(), Some("acme"));
+
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Read)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+
+        let metadata = make_metadata("authorization", "Bearer grafana-secret");
+        let principal = authenticator.authorize(&metadata, OtelRole::Read).unwrap();
+        assert!(principal.tenant_id().is_none());
+    }
+
+    #[test]
+    fn test_authenticator_jwt() {
+        let authenticator = OtelAuthenticator::new(&make_auth_config());
+
+        let token = make_jwt(
+            json!({
+                "iss": "https://auth.example.com",
+                "exp": expiration(),
+                "roles": ["read", "admin"],
+                "tenant": "globex",
+            }),
+            HMAC_SECRET,
+        );
+        let metadata = make_metadata("authorization", &format!("Bearer {token}"));
+        let principal = authenticator.authorize(&metadata, OtelRole::Read).unwrap();
+        assert_eq!(principal.tenant_id(), Some("globex"));
+
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Ingest)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+
+        // The token is signed with another secret.
+        let token = make_jwt(
+            json!({
+                "iss": "https://auth.example.com",
+                "exp": expiration(),
+                "roles": ["read"],
+            }),
+            "fedcba9876543210fedcba9876543210",
+        );
+        let metadata = make_metadata("authorization", &format!("Bearer {token}"));
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Read)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // The token is issued by another issuer.
+        let token = make_jwt(
+            json!({
+                "iss": "https://evil.example.com",
+                "exp": expiration(),
+                "roles": ["read"],
+            }),
+            HMAC_SECRET,
+        );
+        let metadata = make_metadata("authorization", &format!("Bearer {token}"));
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Read)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // The token has expired.
+        let token = make_jwt(
+            json!({
+                "iss": "https://auth.example.com",
+                "exp": jsonwebtoken::get_current_timestamp() - 3_600,
+                "roles": ["read"],
+            }),
+            HMAC_SECRET,
+        );
+        let metadata = make_metadata("authorization", &format!("Bearer {token}"));
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Read)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // The tenant claim is not a valid tenant ID.
+        let token = make_jwt(
+            json!({
+                "iss": "https://auth.example.com",
+                "exp": expiration(),
+                "roles": ["read"],
+                "tenant": "acme-*",
+            }),
+            HMAC_SECRET,
+        );
+        let metadata = make_metadata("authorization", &format!("Bearer {token}"));
+        let status = authenticator
+            .authorize(&metadata, OtelRole::Read)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use prost::bytes::Bytes;
+use prost::Message;
+use quickwit_common::rate_limiter::{RateLimiter, RateLimiterSettings};
+use quickwit_common::tower::ConstantRate;
+use quickwit_config::OtlpApiKeyQuotaConfig;
+use quickwit_ingest::{IngestRequest, IngestService, IngestServiceClient, IngestServiceError};
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Status};
+use tracing::warn;
+
+use super::OtelSignal;
+use crate::otlp::metrics::OTLP_SERVICE_METRICS;
+
+/// Header carrying the API key of the OTLP exports. The API key can also be sent as an
+/// `authorization` bearer token.
+pub const OTLP_API_KEY_HEADER: &str = "x-api-key";
+
+const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+
+/// Number of times an ingest request rejected because the downstream shards are rate limited is
+/// retried before the export is rejected.
+const MAX_NUM_RATE_LIMITED_RETRIES: u32 = 3;
+
+const RATE_LIMITED_INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Delay hinted to the clients when the downstream shards are rate limited.
+const RATE_LIMITED_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const QUOTA_REFILL_PERIOD: Duration = Duration::from_millis(100);
+
+/// `google.rpc.Status` message, sent to the clients in the `grpc-status-details-bin` trailer.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+/// `google.rpc.RetryInfo` message, honored by the OTLP exporters to delay their next attempt.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, Message)]
+struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    retry_delay: Option<prost_types::Duration>,
+}
+
+/// Returns a `RESOURCE_EXHAUSTED` status carrying a `RetryInfo` detail.
+pub(crate) fn resource_exhausted_status(message: String, retry_delay: Duration) -> Status {
+    let retry_info = RetryInfo {
+        retry_delay: Some(prost_types::Duration {
+            seconds: retry_delay.as_secs() as i64,
+            nanos: retry_delay.subsec_nanos() as i32,
+        }),
+    };
+    let rpc_status = RpcStatus {
+        code: Code::ResourceExhausted as i32,
+        message: message.clone(),
+        details: vec![prost_types::Any {
+            type_url: RETRY_INFO_TYPE_URL.to_string(),
+            value: retry_info.encode_to_vec(),
+        }],
+    };
+    Status::with_details(
+        Code::ResourceExhausted,
+        message,
+        Bytes::from(rpc_status.encode_to_vec()),
+    )
+}
+
+/// Extracts the API key of an export from the `x-api-key` header or, if absent, from the
+/// `authorization` bearer token.
+pub fn extract_api_key_from_metadata(metadata: &MetadataMap) -> Option<String> {
+    if let Some(api_key) = metadata
+        .get(OTLP_API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Some(api_key.trim().to_string());
+    }
+    metadata
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+struct SignalQuota {
+    rate_limiter: RateLimiter,
+    max_items_per_sec: u64,
+}
+
+impl SignalQuota {
+    fn new(max_items_per_sec: u64) -> Self {
+        // The refill period must be long enough for each refill to grant at least one permit.
+        let refill_period = if max_items_per_sec * QUOTA_REFILL_PERIOD.as_millis() as u64 >= 1_000 {
+            QUOTA_REFILL_PERIOD
+        } else {
+            Duration::from_secs(1)
+        };
+        let settings = RateLimiterSettings {
+            burst_limit: max_items_per_sec,
+            rate_limit: ConstantRate::new(max_items_per_sec, Duration::from_secs(1)),
+            refill_period,
+        };
+        Self {
+            rate_limiter: RateLimiter::from_settings(settings),
+            max_items_per_sec,
+        }
+    }
+
+    /// Acquires the permits for `num_items` items. A batch larger than the quota is admitted
+    /// when the full burst is available. Otherwise, returns how long the client should wait
+    /// before retrying.
+    fn acquire(&mut self, num_items: u64) -> Result<(), Duration> {
+        let num_permits = num_items.min(self.max_items_per_sec);
+
+        if self.rate_limiter.acquire(num_permits) {
+            return Ok(());
+        }
+        let missing_permits = num_permits - self.rate_limiter.available_permits();
+        let retry_delay =
+            Duration::from_secs_f64(missing_permits as f64 / self.max_items_per_sec as f64)
+                .max(QUOTA_REFILL_PERIOD);
+        Err(retry_delay)
+    }
+}
+
+struct ApiKeyQuota {
+    tenant_id: String,
+    spans_quota_opt: Option<Mutex<SignalQuota>>,
+    log_records_quota_opt: Option<Mutex<SignalQuota>>,
+}
+
+/// Flow control of the OTLP services: enforces the per-API-key throughput quotas and applies
+/// backpressure to the clients when the downstream shards are rate limited.
+#[derive(Clone, Default)]
+pub(crate) struct OtlpFlowControl {
+    quotas: Arc<HashMap<String, ApiKeyQuota>>,
+}
+
+impl OtlpFlowControl {
+    pub fn new(quota_configs: &[OtlpApiKeyQuotaConfig]) -> Self {
+        let quotas = quota_configs
+            .iter()
+            .map(|quota_config| {
+                let quota = ApiKeyQuota {
+                    tenant_id: quota_config.tenant_id.clone(),
+                    spans_quota_opt: quota_config.max_spans_per_sec.map(|max_spans_per_sec| {
+                        Mutex::new(SignalQuota::new(max_spans_per_sec.get() as u64))
+                    }),
+                    log_records_quota_opt: quota_config.max_log_records_per_sec.map(
+                        |max_log_records_per_sec| {
+                            Mutex::new(SignalQuota::new(max_log_records_per_sec.get() as u64))
+                        },
+                    ),
+                };
+                (quota_config.api_key.clone(), quota)
+            })
+            .collect();
+        Self {
+            quotas: Arc::new(quotas),
+        }
+    }
+
+    /// Charges `num_items` spans or log records to the quota of the API key. The exports sent
+    /// without an API key or with an API key that has no quota are not throttled.
+    pub fn check_quota(
+        &self,
+        api_key_opt: Option<&str>,
+        otel_signal: OtelSignal,
+        index_id: &str,
+        num_items: u64,
+    ) -> Result<(), Status> {
+        let Some(quota) = api_key_opt.and_then(|api_key| self.quotas.get(api_key)) else {
+            return Ok(());
+        };
+        let (signal_quota_opt, item_name) = match otel_signal {
+            OtelSignal::Logs => (&quota.log_records_quota_opt, "log records"),
+            OtelSignal::Traces => (&quota.spans_quota_opt, "spans"),
+        };
+        let Some(signal_quota) = signal_quota_opt else {
+            return Ok(());
+        };
+        let Err(retry_delay) = signal_quota
+            .lock()
+            .expect("the lock should not be poisoned")
+            .acquire(num_items)
+        else {
+            return Ok(());
+        };
+        OTLP_SERVICE_METRICS
+            .throttled_requests_total
+            .with_label_values([otel_signal.service_label(), index_id, "quota"])
+            .inc();
+        let message = format!(
+            "tenant `{}` exceeded its {item_name} throughput quota, retry in {}ms",
+            quota.tenant_id,
+            retry_delay.as_millis()
+        );
+        Err(resource_exhausted_status(message, retry_delay))
+    }
+
+    /// Sends the ingest request, retrying with an exponential backoff while the downstream
+    /// shards are rate limited. Once the retries are exhausted, the export is rejected with a
+    /// `RESOURCE_EXHAUSTED` status hinting the client to retry later.
+    pub async fn ingest(
+        &self,
+        ingest_service: &mut IngestServiceClient,
+        ingest_request: IngestRequest,
+        otel_signal: OtelSignal,
+        index_id: &str,
+    ) -> Result<(), Status> {
+        let mut backoff = RATE_LIMITED_INITIAL_BACKOFF;
+        let mut num_retries = 0;
+
+        loop {
+            match ingest_service.ingest(ingest_request.clone()).await {
+                Ok(_) => return Ok(()),
+                Err(IngestServiceError::RateLimited)
+                    if num_retries < MAX_NUM_RATE_LIMITED_RETRIES =>
+                {
+                    num_retries += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(IngestServiceError::RateLimited) => {
+                    OTLP_SERVICE_METRICS
+                        .throttled_requests_total
+                        .with_label_values([otel_signal.service_label(), index_id, "backpressure"])
+                        .inc();
+                    warn!(
+                        index_id,
+                        num_retries, "rejecting OTLP export: downstream shards are rate limited"
+                    );
+                    let message = "downstream shards are rate limited, retry later".to_string();
+                    return Err(resource_exhausted_status(message, RATE_LIMITED_RETRY_DELAY));
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use quickwit_ingest::{CommitType, DocBatchBuilder, IngestResponse, MockIngestService};
+
+    use super::*;
+
+    fn decode_retry_delay(status: &Status) -> Duration {
+        let rpc_status = RpcStatus::decode(status.details()).unwrap();
+        assert_eq!(rpc_status.code, Code::ResourceExhausted as i32);
+        assert_eq!(rpc_status.details.len(), 1);
+        assert_eq!(rpc_status.details[0].type_url, RETRY_INFO_TYPE_URL);
+        let retry_info = RetryInfo::decode(&rpc_status.details[0].value[..]).unwrap();
+        let retry_delay = retry_info.retry_delay.unwrap();
+        Duration::new(retry_delay.seconds as u64, retry_delay.nanos as u32)
+    }
+
+    #[test]
+    fn test_resource_exhausted_status() {
+        let status =
+            resource_exhausted_status("slow down".to_string(), Duration::from_millis(1500));
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(status.message(), "slow down");
+        assert_eq!(decode_retry_delay(&status), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_extract_api_key_from_metadata() {
+        let metadata = MetadataMap::new();
+        assert!(extract_api_key_from_metadata(&metadata).is_none());
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-api-key", "my-key".parse().unwrap());
+        metadata.insert("authorization", "Bearer other-key".parse().unwrap());
+        assert_eq!(extract_api_key_from_metadata(&metadata).unwrap(), "my-key");
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert("authorization", "Bearer my-key".parse().unwrap());
+        assert_eq!(extract_api_key_from_metadata(&metadata).unwrap(), "my-key");
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert("authorization", "Basic dXNlcjpwYXNz".parse().unwrap());
+        assert!(extract_api_key_from_metadata(&metadata).is_none());
+    }
+
+    #[test]
+    fn test_flow_control_check_quota() {
+        let flow_control = OtlpFlowControl::new(&[OtlpApiKeyQuotaConfig {
+            api_key: "my-key".to_string(),
+            tenant_id: "my-tenant".to_string(),
+            max_spans_per_sec: NonZeroU32::new(100),
+            max_log_records_per_sec: None,
+        }]);
+        flow_control
+            .check_quota(Some("my-key"), OtelSignal::Traces, "my-index", 60)
+            .unwrap();
+        let status = flow_control
+            .check_quota(Some("my-key"), OtelSignal::Traces, "my-index", 60)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert!(status.message().contains("my-tenant"));
+        assert!(!status.message().contains("my-key"));
+        assert!(decode_retry_delay(&status) >= QUOTA_REFILL_PERIOD);
+
+        // Log records are not capped for this API key.
+        flow_control
+            .check_quota(Some("my-key"), OtelSignal::Logs, "my-index", 1_000)
+            .unwrap();
+        // Unknown API keys and anonymous exports are not throttled.
+        flow_control
+            .check_quota(Some("other-key"), OtelSignal::Traces, "my-index", 1_000)
+            .unwrap();
+        flow_control
+            .check_quota(None, OtelSignal::Traces, "my-index", 1_000)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_signal_quota_admits_batch_larger_than_quota() {
+        let mut signal_quota = SignalQuota::new(10);
+        signal_quota.acquire(50).unwrap();
+        let retry_delay = signal_quota.acquire(1).unwrap_err();
+        assert!(retry_delay >= QUOTA_REFILL_PERIOD);
+        assert!(retry_delay <= Duration::from_secs(1));
+    }
+
+    fn make_ingest_request() -> IngestRequest {
+        let mut doc_batch_builder = DocBatchBuilder::new("test-index".to_string());
+        doc_batch_builder.ingest_doc(&b"{}"[..]);
+        IngestRequest {
+            doc_batches: vec![doc_batch_builder.build()],
+            commit: CommitType::Auto.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flow_control_ingest_retries_when_rate_limited() {
+        let mut mock_ingest_service = MockIngestService::new();
+        let mut num_calls = 0;
+        mock_ingest_service
+            .expect_ingest()
+            .times(3)
+            .returning(move |_| {
+                num_calls += 1;
+                if num_calls < 3 {
+                    Err(IngestServiceError::RateLimited)
+                } else {
+                    Ok(IngestResponse {
+                        num_docs_for_processing: 1,
+                    })
+                }
+            });
+        let mut ingest_service = IngestServiceClient::from_mock(mock_ingest_service);
+        OtlpFlowControl::default()
+            .ingest(
+                &mut ingest_service,
+                make_ingest_request(),
+                OtelSignal::Logs,
+                "test-index",
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_flow_control_ingest_rejects_when_rate_limited() {
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service
+            .expect_ingest()
+            .times(MAX_NUM_RATE_LIMITED_RETRIES as usize + 1)
+            .returning(|_| Err(IngestServiceError::RateLimited));
+        let mut ingest_service = IngestServiceClient::from_mock(mock_ingest_service);
+        let status = OtlpFlowControl::default()
+            .ingest(
+                &mut ingest_service,
+                make_ingest_request(),
+                OtelSignal::Traces,
+                "test-index",
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(decode_retry_delay(&status), RATE_LIMITED_RETRY_DELAY);
+    }
+}
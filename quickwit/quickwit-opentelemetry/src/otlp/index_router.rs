@@ -0,0 +1,275 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use quickwit_config::{validate_identifier, validate_tenant_id};
+use quickwit_proto::opentelemetry::proto::common::v1::any_value::Value as OtlpValue;
+use quickwit_proto::opentelemetry::proto::resource::v1::Resource as OtlpResource;
+use quickwit_proto::types::IndexId;
+use tonic::Status;
+
+use super::OtelPrincipal;
+
+/// Routes the resources of the OTLP exports to per-tenant indexes. The records of a resource
+/// carrying the tenant attribute are ingested into the index `{index_id}-{attribute_value}`, where
+/// `index_id` is the index targeted by the export. The records of the other resources are
+/// ingested into `index_id`.
+///
+/// The tenant of an authenticated caller takes precedence over the resource attribute: all the
+/// records it exports are ingested into `{index_id}-{tenant_id}` so that it cannot write into the
+/// indexes of another tenant.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OtlpIndexRouter {
+    tenant_resource_attribute_opt: Option<Arc<str>>,
+}
+
+impl OtlpIndexRouter {
+    pub fn new(tenant_resource_attribute_opt: Option<String>) -> Self {
+        Self {
+            tenant_resource_attribute_opt: tenant_resource_attribute_opt.map(Arc::from),
+        }
+    }
+
+    /// Groups the resources of an export by destination index. An export without any resource is
+    /// routed to `index_id` as is, unless the caller is confined to a tenant.
+    pub fn route<T>(
+        &self,
+        index_id: IndexId,
+        principal: &OtelPrincipal,
+        resources: Vec<T>,
+        resource_fn: impl Fn(&T) -> Option<&OtlpResource>,
+    ) -> Result<Vec<(IndexId, Vec<T>)>, Status> {
+        if let Some(tenant_id) = principal.tenant_id() {
+            // The tenant IDs of the principals are validated when they are authenticated, but the
+            // concatenation may still exceed the maximum length of an index ID.
+            let tenant_index_id = format!("{index_id}-{tenant_id}");
+            validate_identifier("Index", &tenant_index_id).map_err(|error| {
+                Status::invalid_argument(format!("invalid tenant index: {error}"))
+            })?;
+            return Ok(vec![(tenant_index_id, resources)]);
+        }
+        let Some(tenant_resource_attribute) = self.tenant_resource_attribute_opt.as_deref() else {
+            return Ok(vec![(index_id, resources)]);
+        };
+        if resources.is_empty() {
+            return Ok(vec![(index_id, resources)]);
+        }
+        let mut routed_resources: BTreeMap<IndexId, Vec<T>> = BTreeMap::new();
+
+        for resource in resources {
+            let tenant_id_opt = resource_fn(&resource)
+                .map(|resource| extract_tenant_id(resource, tenant_resource_attribute))
+                .transpose()?
+                .flatten();
+            let routed_index_id = if let Some(tenant_id) = tenant_id_opt {
+                let tenant_index_id = format!("{index_id}-{tenant_id}");
+                validate_identifier("Index", &tenant_index_id).map_err(|error| {
+                    Status::invalid_argument(format!(
+                        "invalid tenant in resource attribute `{tenant_resource_attribute}`: \
+                         {error}"
+                    ))
+                })?;
+                tenant_index_id
+            } else {
+                index_id.clone()
+            };
+            routed_resources
+                .entry(routed_index_id)
+                .or_default()
+                .push(resource);
+        }
+        Ok(routed_resources.into_iter().collect())
+    }
+}
+
+/// Returns the value of the tenant attribute of a resource. Only string values are accepted so
+/// that a tenant cannot be spelled in two different ways. The tenant IDs containing a `-` are
+/// rejected as well: `{index_id}-{tenant_id}` would otherwise be ambiguous and match the index ID
+/// patterns of another tenant.
+fn extract_tenant_id<'a>(
+    resource: &'a OtlpResource,
+    tenant_resource_attribute: &str,
+) -> Result<Option<&'a str>, Status> {
+    let Some(attribute) = resource
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key == tenant_resource_attribute)
+    else {
+        return Ok(None);
+    };
+    match attribute
+        .value
+        .as_ref()
+        .and_then(|value| value.value.as_ref())
+    {
+        Some(OtlpValue::StringValue(tenant_id)) => {
+            validate_tenant_id(tenant_id).map_err(|error| {
+                Status::invalid_argument(format!(
+                    "invalid tenant in resource attribute `{tenant_resource_attribute}`: {error}"
+                ))
+            })?;
+            Ok(Some(tenant_id))
+        }
+        _ => Err(Status::invalid_argument(format!(
+            "resource attribute `{tenant_resource_attribute}` must be a string"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::opentelemetry::proto::common::v1::{
+        AnyValue as OtlpAnyValue, KeyValue as OtlpKeyValue,
+    };
+
+    use super::*;
+
+    fn make_resource(attributes: &[(&str, OtlpValue)]) -> OtlpResource {
+        OtlpResource {
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| OtlpKeyValue {
+                    key: key.to_string(),
+                    value: Some(OtlpAnyValue {
+                        value: Some(value.clone()),
+                    }),
+                })
+                .collect(),
+            dropped_attributes_count: 0,
+        }
+    }
+
+    fn as_resource(resource: &OtlpResource) -> Option<&OtlpResource> {
+        Some(resource)
+    }
+
+    fn tenant(tenant_id: &str) -> OtlpValue {
+        OtlpValue::StringValue(tenant_id.to_string())
+    }
+
+    #[test]
+    fn test_index_router_disabled() {
+        let index_router = OtlpIndexRouter::default();
+        let anonymous = OtelPrincipal::for_test(None);
+        let resources = vec![make_resource(&[("tenant", tenant("acme"))])];
+        let routed_resources = index_router
+            .route("otel-logs".to_string(), &anonymous, resources, as_resource)
+            .unwrap();
+        assert_eq!(routed_resources.len(), 1);
+        assert_eq!(routed_resources[0].0, "otel-logs");
+        assert_eq!(routed_resources[0].1.len(), 1);
+    }
+
+    #[test]
+    fn test_index_router_routes_resources_by_tenant() {
+        let index_router = OtlpIndexRouter::new(Some("tenant".to_string()));
+        let anonymous = OtelPrincipal::for_test(None);
+
+        let routed_resources = index_router
+            .route("otel-logs".to_string(), &anonymous, Vec::new(), as_resource)
+            .unwrap();
+        assert_eq!(routed_resources.len(), 1);
+        assert_eq!(routed_resources[0].0, "otel-logs");
+        assert!(routed_resources[0].1.is_empty());
+
+        let resources = vec![
+            make_resource(&[("tenant", tenant("acme"))]),
+            make_resource(&[("service.name", tenant("quickwit"))]),
+            make_resource(&[("tenant", tenant("globex"))]),
+            make_resource(&[("tenant", tenant("acme"))]),
+        ];
+        let routed_resources = index_router
+            .route("otel-logs".to_string(), &anonymous, resources, as_resource)
+            .unwrap();
+        let routed_index_ids: Vec<(&str, usize)> = routed_resources
+            .iter()
+            .map(|(index_id, resources)| (index_id.as_str(), resources.len()))
+            .collect();
+        assert_eq!(
+            routed_index_ids,
+            [
+                ("otel-logs", 1),
+                ("otel-logs-acme", 2),
+                ("otel-logs-globex", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_router_routes_resources_by_principal_tenant() {
+        let index_router = OtlpIndexRouter::new(Some("tenant".to_string()));
+        let principal = OtelPrincipal::for_test(Some("acme"));
+
+        let resources = vec![
+            make_resource(&[("tenant", tenant("globex"))]),
+            make_resource(&[("service.name", tenant("quickwit"))]),
+        ];
+        let routed_resources = index_router
+            .route("otel-logs".to_string(), &principal, resources, as_resource)
+            .unwrap();
+        assert_eq!(routed_resources.len(), 1);
+        assert_eq!(routed_resources[0].0, "otel-logs-acme");
+        assert_eq!(routed_resources[0].1.len(), 2);
+
+        let routed_resources = OtlpIndexRouter::default()
+            .route("otel-logs".to_string(), &principal, Vec::new(), as_resource)
+            .unwrap();
+        assert_eq!(routed_resources.len(), 1);
+        assert_eq!(routed_resources[0].0, "otel-logs-acme");
+    }
+
+    #[test]
+    fn test_index_router_rejects_invalid_tenants() {
+        let index_router = OtlpIndexRouter::new(Some("tenant".to_string()));
+        let anonymous = OtelPrincipal::for_test(None);
+
+        let resources = vec![make_resource(&[("tenant", tenant("acme/corp"))])];
+        let status = index_router
+            .route("otel-logs".to_string(), &anonymous, resources, as_resource)
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        // `otel-logs-acme-bar` would be readable by the tenant `bar` of the index `otel-logs-acme`.
+        let resources = vec![
+            make_resource(&[("tenant", tenant("acme"))]),
+            make_resource(&[("tenant", tenant("acme-bar"))]),
+        ];
+        let status = index_router
+            .route("otel-logs".to_string(), &anonymous, resources, as_resource)
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "invalid tenant in resource attribute `tenant`: Tenant ID `acme-bar` is invalid: \
+             tenant IDs must not contain `-`"
+        );
+
+        let resources = vec![make_resource(&[("tenant", OtlpValue::IntValue(42))])];
+        let status = index_router
+            .route("otel-logs".to_string(), &anonymous, resources, as_resource)
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "resource attribute `tenant` must be a string"
+        );
+    }
+}
@@ -24,10 +24,10 @@ use async_trait::async_trait;
 use prost::Message;
 use quickwit_common::rate_limited_error;
 use quickwit_common::uri::Uri;
-use quickwit_config::{load_index_config_from_user_config, ConfigFormat, IndexConfig};
-use quickwit_ingest::{
-    CommitType, DocBatch, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+use quickwit_config::{
+    load_index_config_from_user_config, ConfigFormat, IndexConfig, OtelRole, OtlpApiKeyQuotaConfig,
 };
+use quickwit_ingest::{CommitType, DocBatch, DocBatchBuilder, IngestRequest, IngestServiceClient};
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::logs_service_server::LogsService;
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::{
     ExportLogsPartialSuccess, ExportLogsServiceRequest, ExportLogsServiceResponse,
@@ -43,7 +43,10 @@ use super::{
     extract_otel_index_id_from_metadata, is_zero, parse_log_record_body, OtelSignal, SpanId,
     TraceId, TryFromSpanIdError, TryFromTraceIdError,
 };
+use crate::otlp::auth::OtelAuthenticator;
 use crate::otlp::extract_attributes;
+use crate::otlp::flow_control::{extract_api_key_from_metadata, OtlpFlowControl};
+use crate::otlp::index_router::OtlpIndexRouter;
 use crate::otlp::metrics::OTLP_SERVICE_METRICS;
 
 pub const OTEL_LOGS_INDEX_ID: &str = "otel-logs-v0_7";
@@ -234,11 +237,41 @@ struct ParsedLogRecords {
 #[derive(Clone)]
 pub struct OtlpGrpcLogsService {
     ingest_service: IngestServiceClient,
+    flow_control: OtlpFlowControl,
+    index_router: OtlpIndexRouter,
+    authenticator: OtelAuthenticator,
 }
 
 impl OtlpGrpcLogsService {
     pub fn new(ingest_service: IngestServiceClient) -> Self {
-        Self { ingest_service }
+        Self {
+            ingest_service,
+            flow_control: OtlpFlowControl::default(),
+            index_router: OtlpIndexRouter::default(),
+            authenticator: OtelAuthenticator::default(),
+        }
+    }
+
+    /// Enforces the log record throughput quotas of the API keys.
+    pub fn with_api_key_quotas(mut self, quota_configs: &[OtlpApiKeyQuotaConfig]) -> Self {
+        self.flow_control = OtlpFlowControl::new(quota_configs);
+        self
+    }
+
+    /// Routes the log records to per-tenant indexes based on the value of a resource attribute.
+    pub fn with_tenant_resource_attribute(
+        mut self,
+        tenant_resource_attribute_opt: Option<String>,
+    ) -> Self {
+        self.index_router = OtlpIndexRouter::new(tenant_resource_attribute_opt);
+        self
+    }
+
+    /// Requires the callers to authenticate and to be granted the `ingest` role. The log records of
+    /// the callers confined to a tenant are routed to the indexes of that tenant.
+    pub fn with_authenticator(mut self, authenticator: OtelAuthenticator) -> Self {
+        self.authenticator = authenticator;
+        self
     }
 
     pub fn index_config(default_index_root_uri: &Uri) -> anyhow::Result<IndexConfig> {
@@ -255,6 +288,7 @@ impl OtlpGrpcLogsService {
         &mut self,
         request: ExportLogsServiceRequest,
         index_id: IndexId,
+        api_key_opt: Option<String>,
         labels: [&str; 4],
     ) -> Result<ExportLogsServiceResponse, Status> {
         let ParsedLogRecords {
@@ -264,6 +298,7 @@ impl OtlpGrpcLogsService {
             error_message,
         } = tokio::task::spawn_blocking({
             let parent_span = RuntimeSpan::current();
+            let index_id = index_id.clone();
             || Self::parse_logs(request, parent_span, index_id)
         })
         .await
@@ -274,6 +309,12 @@ impl OtlpGrpcLogsService {
         if num_log_records == num_parse_errors {
             return Err(tonic::Status::internal(error_message));
         }
+        self.flow_control.check_quota(
+            api_key_opt.as_deref(),
+            OtelSignal::Logs,
+            &index_id,
+            num_log_records,
+        )?;
         let num_bytes = doc_batch.num_bytes() as u64;
         self.store_logs(doc_batch).await?;
 
@@ -331,18 +372,26 @@ impl OtlpGrpcLogsService {
 
     #[instrument(skip_all, fields(num_bytes = doc_batch.num_bytes()))]
     async fn store_logs(&mut self, doc_batch: DocBatch) -> Result<(), tonic::Status> {
+        let index_id = doc_batch.index_id.clone();
         let ingest_request = IngestRequest {
             doc_batches: vec![doc_batch],
             commit: CommitType::Auto.into(),
         };
-        self.ingest_service.ingest(ingest_request).await?;
-        Ok(())
+        self.flow_control
+            .ingest(
+                &mut self.ingest_service,
+                ingest_request,
+                OtelSignal::Logs,
+                &index_id,
+            )
+            .await
     }
 
     async fn export_instrumented(
         &mut self,
         request: ExportLogsServiceRequest,
         index_id: IndexId,
+        api_key_opt: Option<String>,
     ) -> Result<ExportLogsServiceResponse, Status> {
         let start = std::time::Instant::now();
 
@@ -352,17 +401,19 @@ impl OtlpGrpcLogsService {
             .requests_total
             .with_label_values(labels)
             .inc();
-        let (export_res, is_error) =
-            match self.export_inner(request, index_id.clone(), labels).await {
-                ok @ Ok(_) => (ok, "false"),
-                err @ Err(_) => {
-                    OTLP_SERVICE_METRICS
-                        .request_errors_total
-                        .with_label_values(labels)
-                        .inc();
-                    (err, "true")
-                }
-            };
+        let (export_res, is_error) = match self
+            .export_inner(request, index_id.clone(), api_key_opt, labels)
+            .await
+        {
+            ok @ Ok(_) => (ok, "false"),
+            err @ Err(_) => {
+                OTLP_SERVICE_METRICS
+                    .request_errors_total
+                    .with_label_values(labels)
+                    .inc();
+                (err, "true")
+            }
+        };
         let elapsed = start.elapsed().as_secs_f64();
         let labels = ["logs", &index_id, "grpc", "protobuf", is_error];
         OTLP_SERVICE_METRICS
@@ -381,12 +432,42 @@ impl LogsService for OtlpGrpcLogsService {
         &self,
         request: Request<ExportLogsServiceRequest>,
     ) -> Result<Response<ExportLogsServiceResponse>, Status> {
+        let principal = self
+            .authenticator
+            .authorize(request.metadata(), OtelRole::Ingest)?;
         let index_id = extract_otel_index_id_from_metadata(request.metadata(), OtelSignal::Logs)?;
+        let api_key_opt = extract_api_key_from_metadata(request.metadata());
         let request = request.into_inner();
-        self.clone()
-            .export_instrumented(request, index_id)
-            .await
-            .map(Response::new)
+        let routed_resource_logs = self.index_router.route(
+            index_id,
+            &principal,
+            request.resource_logs,
+            |resource_logs| resource_logs.resource.as_ref(),
+        )?;
+        let mut rejected_log_records = 0;
+        let mut error_message = String::new();
+
+        for (index_id, resource_logs) in routed_resource_logs {
+            let request = ExportLogsServiceRequest { resource_logs };
+            let response = self
+                .clone()
+                .export_instrumented(request, index_id, api_key_opt.clone())
+                .await?;
+            if let Some(partial_success) = response.partial_success {
+                rejected_log_records += partial_success.rejected_log_records;
+
+                if !partial_success.error_message.is_empty() {
+                    error_message = partial_success.error_message;
+                }
+            }
+        }
+        let response = ExportLogsServiceResponse {
+            partial_success: Some(ExportLogsPartialSuccess {
+                rejected_log_records,
+                error_message,
+            }),
+        };
+        Ok(Response::new(response))
     }
 }
 
@@ -26,7 +26,9 @@ pub struct OtlpServiceMetrics {
     pub request_duration_seconds: HistogramVec<5>,
     pub ingested_log_records_total: IntCounterVec<4>,
     pub ingested_spans_total: IntCounterVec<4>,
+    pub sampled_out_spans_total: IntCounterVec<4>,
     pub ingested_bytes_total: IntCounterVec<4>,
+    pub throttled_requests_total: IntCounterVec<3>,
 }
 
 impl Default for OtlpServiceMetrics {
@@ -67,6 +69,13 @@ impl Default for OtlpServiceMetrics {
                 &[],
                 ["service", "index", "transport", "format"],
             ),
+            sampled_out_spans_total: new_counter_vec(
+                "sampled_out_spans_total",
+                "Number of spans discarded or rolled up by trace sampling",
+                "otlp",
+                &[],
+                ["service", "index", "transport", "format"],
+            ),
             ingested_bytes_total: new_counter_vec(
                 "ingested_bytes_total",
                 "Number of bytes ingested",
@@ -74,6 +83,13 @@ impl Default for OtlpServiceMetrics {
                 &[],
                 ["service", "index", "transport", "format"],
             ),
+            throttled_requests_total: new_counter_vec(
+                "throttled_requests_total",
+                "Number of requests rejected because of a quota or of downstream backpressure",
+                "otlp",
+                &[],
+                ["service", "index", "reason"],
+            ),
         }
     }
 }
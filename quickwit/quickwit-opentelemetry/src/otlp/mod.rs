@@ -26,14 +26,20 @@ use quickwit_proto::opentelemetry::proto::common::v1::{
 };
 use serde_json::{Number as JsonNumber, Value as JsonValue};
 
+mod auth;
+mod flow_control;
+mod index_router;
 mod logs;
 mod metrics;
 mod span_id;
 #[cfg(any(test, feature = "testsuite"))]
 mod test_utils;
 mod trace_id;
+mod trace_sampling;
 mod traces;
 
+pub use auth::{OtelAuthenticator, OtelPrincipal};
+pub use flow_control::{extract_api_key_from_metadata, OTLP_API_KEY_HEADER};
 pub use logs::{
     parse_otlp_logs_json, parse_otlp_logs_protobuf, JsonLogIterator, OtlpGrpcLogsService,
     OtlpLogsError, OTEL_LOGS_INDEX_ID,
@@ -63,6 +69,13 @@ impl OtelSignal {
         }
     }
 
+    pub(crate) fn service_label(&self) -> &'static str {
+        match self {
+            OtelSignal::Logs => "logs",
+            OtelSignal::Traces => "trace",
+        }
+    }
+
     pub fn default_index_id(&self) -> &'static str {
         match self {
             OtelSignal::Logs => OTEL_LOGS_INDEX_ID,
@@ -0,0 +1,269 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use quickwit_config::TraceSamplingConfig;
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use serde::Serialize;
+use tracing::warn;
+
+use super::Span;
+
+/// Period during which the trace sampling config of an index is reused before being fetched again
+/// from the metastore.
+const TRACE_SAMPLING_CONFIG_TTL: Duration = Duration::from_secs(60);
+
+const NANOS_PER_MILLI: u64 = 1_000_000;
+
+const NANOS_PER_MINUTE: u64 = 60 * 1_000_000_000;
+
+/// Caches the trace sampling configs declared in the indexing settings of the indexes.
+#[derive(Clone, Default)]
+pub(crate) struct TraceSamplingConfigs {
+    metastore_opt: Option<MetastoreServiceClient>,
+    cache: Arc<Mutex<HashMap<IndexId, (Instant, Option<TraceSamplingConfig>)>>>,
+}
+
+impl fmt::Debug for TraceSamplingConfigs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TraceSamplingConfigs")
+            .field("enabled", &self.metastore_opt.is_some())
+            .finish()
+    }
+}
+
+impl TraceSamplingConfigs {
+    pub fn new(metastore: MetastoreServiceClient) -> Self {
+        Self {
+            metastore_opt: Some(metastore),
+            cache: Arc::default(),
+        }
+    }
+
+    /// Returns the trace sampling config of the index. When the config cannot be fetched, the
+    /// previous config is reused if any, and the spans are not sampled otherwise.
+    pub async fn get(&self, index_id: &IndexId) -> Option<TraceSamplingConfig> {
+        let metastore = self.metastore_opt.as_ref()?;
+
+        let cached_config_opt = self.cache.lock().unwrap().get(index_id).cloned();
+
+        if let Some((fetched_at, config_opt)) = &cached_config_opt {
+            if fetched_at.elapsed() < TRACE_SAMPLING_CONFIG_TTL {
+                return config_opt.clone();
+            }
+        }
+        let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+        let index_metadata_result = metastore
+            .clone()
+            .index_metadata(index_metadata_request)
+            .await
+            .and_then(|response| response.deserialize_index_metadata());
+
+        let config_opt = match index_metadata_result {
+            Ok(index_metadata) => index_metadata.index_config.indexing_settings.trace_sampling,
+            Err(MetastoreError::NotFound(_)) => None,
+            Err(error) => {
+                warn!(%error, "failed to fetch trace sampling config of index `{index_id}`");
+                cached_config_opt.and_then(|(_, config_opt)| config_opt)
+            }
+        };
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(index_id.clone(), (Instant::now(), config_opt.clone()));
+        config_opt
+    }
+}
+
+/// Span count and durations of the sampled-out spans of a service operation over one minute.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct SpanRollup {
+    pub rollup_start_timestamp_secs: u64,
+    pub service_name: String,
+    pub span_name: String,
+    pub span_kind: u32,
+    pub span_count: u64,
+    pub span_duration_millis_sum: u64,
+    pub span_duration_millis_min: u64,
+    pub span_duration_millis_max: u64,
+}
+
+/// Rolls up the spans per service, operation, and minute of their start timestamp.
+pub(crate) fn rollup_spans<'a>(spans: impl IntoIterator<Item = &'a Span>) -> Vec<SpanRollup> {
+    let mut rollups: BTreeMap<(u64, &str, &str, u32), SpanRollup> = BTreeMap::new();
+
+    for span in spans {
+        let rollup_start_timestamp_nanos =
+            span.span_start_timestamp_nanos - span.span_start_timestamp_nanos % NANOS_PER_MINUTE;
+        let span_duration_millis = span
+            .span_end_timestamp_nanos
+            .saturating_sub(span.span_start_timestamp_nanos)
+            / NANOS_PER_MILLI;
+        let key = (
+            rollup_start_timestamp_nanos,
+            span.service_name.as_str(),
+            span.span_name.as_str(),
+            span.span_kind,
+        );
+        let rollup = rollups.entry(key).or_insert_with(|| SpanRollup {
+            rollup_start_timestamp_secs: rollup_start_timestamp_nanos / 1_000_000_000,
+            service_name: span.service_name.clone(),
+            span_name: span.span_name.clone(),
+            span_kind: span.span_kind,
+            span_count: 0,
+            span_duration_millis_sum: 0,
+            span_duration_millis_min: u64::MAX,
+            span_duration_millis_max: 0,
+        });
+        rollup.span_count += 1;
+        rollup.span_duration_millis_sum += span_duration_millis;
+        rollup.span_duration_millis_min = rollup.span_duration_millis_min.min(span_duration_millis);
+        rollup.span_duration_millis_max = rollup.span_duration_millis_max.max(span_duration_millis);
+    }
+    rollups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{IndexConfig, IndexingSettings};
+    use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::metastore::{IndexMetadataResponse, MockMetastoreService};
+    use quickwit_proto::types::IndexUid;
+
+    use super::*;
+    use crate::otlp::{SpanStatus, TraceId};
+
+    fn span_for_test(
+        service_name: &str,
+        span_name: &str,
+        start_timestamp_secs: u64,
+        duration_millis: u64,
+    ) -> Span {
+        let span_start_timestamp_nanos = start_timestamp_secs * 1_000_000_000;
+        Span {
+            trace_id: TraceId::new([1; 16]),
+            trace_state: None,
+            service_name: service_name.to_string(),
+            resource_attributes: HashMap::new(),
+            resource_dropped_attributes_count: 0,
+            scope_name: None,
+            scope_version: None,
+            scope_attributes: HashMap::new(),
+            scope_dropped_attributes_count: 0,
+            span_id: crate::otlp::SpanId::new([1; 8]),
+            span_kind: 2,
+            span_name: span_name.to_string(),
+            span_fingerprint: None,
+            span_start_timestamp_nanos,
+            span_end_timestamp_nanos: span_start_timestamp_nanos
+                + duration_millis * NANOS_PER_MILLI,
+            span_duration_millis: Some(duration_millis),
+            span_attributes: HashMap::new(),
+            span_dropped_attributes_count: 0,
+            span_dropped_events_count: 0,
+            span_dropped_links_count: 0,
+            span_status: SpanStatus::default(),
+            parent_span_id: None,
+            events: Vec::new(),
+            event_names: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rollup_spans() {
+        let spans = [
+            span_for_test("checkout", "charge", 120, 10),
+            span_for_test("checkout", "charge", 150, 30),
+            span_for_test("checkout", "charge", 180, 5),
+            span_for_test("checkout", "refund", 130, 7),
+        ];
+        let rollups = rollup_spans(&spans);
+        assert_eq!(rollups.len(), 3);
+
+        assert_eq!(
+            rollups[0],
+            SpanRollup {
+                rollup_start_timestamp_secs: 120,
+                service_name: "checkout".to_string(),
+                span_name: "charge".to_string(),
+                span_kind: 2,
+                span_count: 2,
+                span_duration_millis_sum: 40,
+                span_duration_millis_min: 10,
+                span_duration_millis_max: 30,
+            }
+        );
+        assert_eq!(rollups[1].span_name, "refund");
+        assert_eq!(rollups[1].span_count, 1);
+
+        assert_eq!(rollups[2].rollup_start_timestamp_secs, 180);
+        assert_eq!(rollups[2].span_count, 1);
+        assert_eq!(rollups[2].span_duration_millis_sum, 5);
+    }
+
+    #[tokio::test]
+    async fn test_trace_sampling_configs() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_index_metadata()
+            .once()
+            .returning(|request| {
+                assert_eq!(request.index_id.as_ref().unwrap(), "otel-traces-v0_7");
+
+                let mut index_config =
+                    IndexConfig::for_test("otel-traces-v0_7", "ram:///indexes/otel-traces-v0_7");
+                index_config.indexing_settings = IndexingSettings {
+                    trace_sampling: Some(TraceSamplingConfig {
+                        ok_traces_sample_percent: 10,
+                        rollup_index_id: None,
+                    }),
+                    ..Default::default()
+                };
+                let index_uid = IndexUid::for_test("otel-traces-v0_7", 0);
+                let index_metadata = IndexMetadata::new_with_index_uid(index_uid, index_config);
+                let response =
+                    IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap();
+                Ok(response)
+            });
+        let trace_sampling_configs =
+            TraceSamplingConfigs::new(MetastoreServiceClient::from_mock(mock_metastore));
+
+        // The second call is served from the cache.
+        for _ in 0..2 {
+            let trace_sampling_config = trace_sampling_configs
+                .get(&"otel-traces-v0_7".to_string())
+                .await
+                .unwrap();
+            assert_eq!(trace_sampling_config.ok_traces_sample_percent, 10);
+        }
+        assert!(TraceSamplingConfigs::default()
+            .get(&"otel-traces-v0_7".to_string())
+            .await
+            .is_none());
+    }
+}
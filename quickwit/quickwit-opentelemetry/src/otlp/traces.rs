@@ -18,16 +18,18 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp::{Ord, Ordering, PartialEq, PartialOrd};
-use std::collections::{btree_set, BTreeSet, HashMap};
+use std::collections::{btree_set, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 
 use async_trait::async_trait;
 use prost::Message;
 use quickwit_common::uri::Uri;
-use quickwit_config::{load_index_config_from_user_config, ConfigFormat, IndexConfig};
-use quickwit_ingest::{
-    CommitType, DocBatch, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+use quickwit_config::{
+    load_index_config_from_user_config, ConfigFormat, IndexConfig, OtelRole, OtlpApiKeyQuotaConfig,
+    TraceSamplingConfig,
 };
+use quickwit_ingest::{CommitType, DocBatch, DocBatchBuilder, IngestRequest, IngestServiceClient};
+use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::opentelemetry::proto::collector::trace::v1::trace_service_server::TraceService;
 use quickwit_proto::opentelemetry::proto::collector::trace::v1::{
     ExportTracePartialSuccess, ExportTraceServiceRequest, ExportTraceServiceResponse,
@@ -48,7 +50,11 @@ use super::{
     extract_otel_index_id_from_metadata, is_zero, OtelSignal, TryFromSpanIdError,
     TryFromTraceIdError,
 };
+use crate::otlp::auth::OtelAuthenticator;
+use crate::otlp::flow_control::{extract_api_key_from_metadata, OtlpFlowControl};
+use crate::otlp::index_router::OtlpIndexRouter;
 use crate::otlp::metrics::OTLP_SERVICE_METRICS;
+use crate::otlp::trace_sampling::{rollup_spans, TraceSamplingConfigs};
 use crate::otlp::{extract_attributes, SpanId, TraceId};
 
 pub const OTEL_TRACES_INDEX_ID: &str = "otel-traces-v0_7";
@@ -668,9 +674,45 @@ fn parse_otlp_spans(
     Ok(spans)
 }
 
+/// Splits the spans into the spans to keep and the spans sampled out. The traces with at least one
+/// error span are always kept, while the other traces are kept with a probability of
+/// `ok_traces_sample_percent`.
+///
+/// The sampling decision is made per request, so the error spans of a trace must be exported in
+/// the same request as its OK spans for the trace to be kept in its entirety. The decision for OK
+/// traces only depends on the trace ID, so it is consistent across requests and nodes.
+fn sample_traces(
+    spans: BTreeSet<OrdSpan>,
+    ok_traces_sample_percent: u8,
+) -> (BTreeSet<OrdSpan>, BTreeSet<OrdSpan>) {
+    let error_trace_ids: HashSet<TraceId> = spans
+        .iter()
+        .filter(|span| span.0.span_status.code == OtlpStatusCode::Error)
+        .map(|span| span.0.trace_id)
+        .collect();
+    spans.into_iter().partition(|span| {
+        error_trace_ids.contains(&span.0.trace_id)
+            || is_ok_trace_sampled(span.0.trace_id, ok_traces_sample_percent)
+    })
+}
+
+/// Uses the lower 64 bits of the trace ID, which are random for W3C trace context compliant
+/// tracers.
+fn is_ok_trace_sampled(trace_id: TraceId, ok_traces_sample_percent: u8) -> bool {
+    let trace_id_bytes = trace_id.into_bytes();
+    let trace_id_low = u64::from_be_bytes(
+        trace_id_bytes[8..]
+            .try_into()
+            .expect("slice should be 8 bytes long"),
+    );
+    trace_id_low % 100 < ok_traces_sample_percent as u64
+}
+
 struct ParsedSpans {
     doc_batch: DocBatch,
+    rollup_doc_batch_opt: Option<DocBatch>,
     num_spans: u64,
+    num_sampled_out_spans: u64,
     num_parse_errors: u64,
     error_message: String,
 }
@@ -679,6 +721,10 @@ struct ParsedSpans {
 pub struct OtlpGrpcTracesService {
     ingest_service: IngestServiceClient,
     commit_type: CommitType,
+    trace_sampling_configs: TraceSamplingConfigs,
+    flow_control: OtlpFlowControl,
+    index_router: OtlpIndexRouter,
+    authenticator: OtelAuthenticator,
 }
 
 impl OtlpGrpcTracesService {
@@ -686,9 +732,43 @@ impl OtlpGrpcTracesService {
         Self {
             ingest_service,
             commit_type: commit_type_opt.unwrap_or_default(),
+            trace_sampling_configs: Default::default(),
+            flow_control: OtlpFlowControl::default(),
+            index_router: OtlpIndexRouter::default(),
+            authenticator: OtelAuthenticator::default(),
         }
     }
 
+    /// Enables the trace sampling declared in the indexing settings of the indexes, which are
+    /// fetched from the metastore.
+    pub fn with_trace_sampling(mut self, metastore: MetastoreServiceClient) -> Self {
+        self.trace_sampling_configs = TraceSamplingConfigs::new(metastore);
+        self
+    }
+
+    /// Enforces the span throughput quotas of the API keys.
+    pub fn with_api_key_quotas(mut self, quota_configs: &[OtlpApiKeyQuotaConfig]) -> Self {
+        self.flow_control = OtlpFlowControl::new(quota_configs);
+        self
+    }
+
+    /// Routes the spans to per-tenant indexes based on the value of a resource attribute. The
+    /// trace sampling configured for an index applies to the spans routed to that index.
+    pub fn with_tenant_resource_attribute(
+        mut self,
+        tenant_resource_attribute_opt: Option<String>,
+    ) -> Self {
+        self.index_router = OtlpIndexRouter::new(tenant_resource_attribute_opt);
+        self
+    }
+
+    /// Requires the callers to authenticate and to be granted the `ingest` role. The spans of the
+    /// callers confined to a tenant are routed to the indexes of that tenant.
+    pub fn with_authenticator(mut self, authenticator: OtelAuthenticator) -> Self {
+        self.authenticator = authenticator;
+        self
+    }
+
     pub fn index_config(default_index_root_uri: &Uri) -> anyhow::Result<IndexConfig> {
         let index_config_str =
             OTEL_TRACES_INDEX_CONFIG.replace("${INDEX_ID}", OTEL_TRACES_INDEX_ID);
@@ -704,16 +784,21 @@ impl OtlpGrpcTracesService {
         &mut self,
         request: ExportTraceServiceRequest,
         index_id: IndexId,
+        api_key_opt: Option<String>,
         labels: [&str; 4],
     ) -> Result<ExportTraceServiceResponse, Status> {
+        let sampling_config_opt = self.trace_sampling_configs.get(&index_id).await;
         let ParsedSpans {
             doc_batch,
+            rollup_doc_batch_opt,
             num_spans,
+            num_sampled_out_spans,
             num_parse_errors,
             error_message,
         } = tokio::task::spawn_blocking({
             let parent_span = RuntimeSpan::current();
-            || Self::parse_spans(request, parent_span, index_id)
+            let index_id = index_id.clone();
+            || Self::parse_spans(request, parent_span, index_id, sampling_config_opt)
         })
         .await
         .map_err(|join_error| {
@@ -726,18 +811,31 @@ impl OtlpGrpcTracesService {
         if num_spans == num_parse_errors {
             return Err(tonic::Status::internal(error_message));
         }
+        self.flow_control.check_quota(
+            api_key_opt.as_deref(),
+            OtelSignal::Traces,
+            &index_id,
+            num_spans,
+        )?;
         let num_bytes = doc_batch.num_bytes() as u64;
-        self.store_spans(doc_batch).await?;
+        self.store_spans(doc_batch, rollup_doc_batch_opt).await?;
 
         OTLP_SERVICE_METRICS
             .ingested_spans_total
             .with_label_values(labels)
-            .inc_by(num_spans);
+            .inc_by(num_spans - num_sampled_out_spans);
         OTLP_SERVICE_METRICS
             .ingested_bytes_total
             .with_label_values(labels)
             .inc_by(num_bytes);
 
+        if num_sampled_out_spans > 0 {
+            OTLP_SERVICE_METRICS
+                .sampled_out_spans_total
+                .with_label_values(labels)
+                .inc_by(num_sampled_out_spans);
+        }
+
         let response = ExportTraceServiceResponse {
             // `rejected_spans=0` and `error_message=""` is considered a "full" success.
             partial_success: Some(ExportTracePartialSuccess {
@@ -748,17 +846,25 @@ impl OtlpGrpcTracesService {
         Ok(response)
     }
 
-    #[instrument(skip_all, parent = parent_span, fields(num_spans = Empty, num_bytes = Empty, num_parse_errors = Empty))]
+    #[instrument(skip_all, parent = parent_span, fields(num_spans = Empty, num_sampled_out_spans = Empty, num_bytes = Empty, num_parse_errors = Empty))]
     fn parse_spans(
         request: ExportTraceServiceRequest,
         parent_span: RuntimeSpan,
         index_id: IndexId,
+        sampling_config_opt: Option<TraceSamplingConfig>,
     ) -> tonic::Result<ParsedSpans> {
         let spans = parse_otlp_spans(request)?;
         let num_spans = spans.len() as u64;
         let mut num_parse_errors = 0;
         let mut error_message = String::new();
 
+        let (spans, sampled_out_spans) = if let Some(sampling_config) = &sampling_config_opt {
+            sample_traces(spans, sampling_config.ok_traces_sample_percent)
+        } else {
+            (spans, BTreeSet::new())
+        };
+        let num_sampled_out_spans = sampled_out_spans.len() as u64;
+
         let mut doc_batch_builder = DocBatchBuilder::new(index_id).json_writer();
         for span in spans {
             if let Err(error) = doc_batch_builder.ingest_doc(&span.0) {
@@ -768,14 +874,30 @@ impl OtlpGrpcTracesService {
             }
         }
         let doc_batch = doc_batch_builder.build();
+
+        let rollup_index_id_opt = sampling_config_opt
+            .and_then(|sampling_config| sampling_config.rollup_index_id)
+            .filter(|_| !sampled_out_spans.is_empty());
+        let rollup_doc_batch_opt = rollup_index_id_opt.map(|rollup_index_id| {
+            let mut rollup_doc_batch_builder = DocBatchBuilder::new(rollup_index_id).json_writer();
+            for span_rollup in rollup_spans(sampled_out_spans.iter().map(|span| &span.0)) {
+                if let Err(error) = rollup_doc_batch_builder.ingest_doc(&span_rollup) {
+                    error!(error=?error, "failed to JSON serialize span rollup");
+                }
+            }
+            rollup_doc_batch_builder.build()
+        });
         let current_span = RuntimeSpan::current();
         current_span.record("num_spans", num_spans);
+        current_span.record("num_sampled_out_spans", num_sampled_out_spans);
         current_span.record("num_bytes", doc_batch.num_bytes());
         current_span.record("num_parse_errors", num_parse_errors);
 
         let parsed_spans = ParsedSpans {
             doc_batch,
+            rollup_doc_batch_opt,
             num_spans,
+            num_sampled_out_spans,
             num_parse_errors,
             error_message,
         };
@@ -783,19 +905,39 @@ impl OtlpGrpcTracesService {
     }
 
     #[instrument(skip_all, fields(num_bytes = doc_batch.num_bytes()))]
-    async fn store_spans(&mut self, doc_batch: DocBatch) -> Result<(), tonic::Status> {
+    async fn store_spans(
+        &mut self,
+        doc_batch: DocBatch,
+        rollup_doc_batch_opt: Option<DocBatch>,
+    ) -> Result<(), tonic::Status> {
+        let index_id = doc_batch.index_id.clone();
+        let doc_batches: Vec<DocBatch> = std::iter::once(doc_batch)
+            .chain(rollup_doc_batch_opt)
+            .filter(|doc_batch| !doc_batch.is_empty())
+            .collect();
+        if doc_batches.is_empty() {
+            // All the spans were sampled out.
+            return Ok(());
+        }
         let ingest_request = IngestRequest {
-            doc_batches: vec![doc_batch],
+            doc_batches,
             commit: self.commit_type.into(),
         };
-        self.ingest_service.ingest(ingest_request).await?;
-        Ok(())
+        self.flow_control
+            .ingest(
+                &mut self.ingest_service,
+                ingest_request,
+                OtelSignal::Traces,
+                &index_id,
+            )
+            .await
     }
 
     async fn export_instrumented(
         &mut self,
         request: ExportTraceServiceRequest,
         index_id: IndexId,
+        api_key_opt: Option<String>,
     ) -> Result<ExportTraceServiceResponse, Status> {
         let start = std::time::Instant::now();
 
@@ -805,17 +947,19 @@ impl OtlpGrpcTracesService {
             .requests_total
             .with_label_values(labels)
             .inc();
-        let (export_res, is_error) =
-            match self.export_inner(request, index_id.clone(), labels).await {
-                ok @ Ok(_) => (ok, "false"),
-                err @ Err(_) => {
-                    OTLP_SERVICE_METRICS
-                        .request_errors_total
-                        .with_label_values(labels)
-                        .inc();
-                    (err, "true")
-                }
-            };
+        let (export_res, is_error) = match self
+            .export_inner(request, index_id.clone(), api_key_opt, labels)
+            .await
+        {
+            ok @ Ok(_) => (ok, "false"),
+            err @ Err(_) => {
+                OTLP_SERVICE_METRICS
+                    .request_errors_total
+                    .with_label_values(labels)
+                    .inc();
+                (err, "true")
+            }
+        };
         let elapsed = start.elapsed().as_secs_f64();
         let labels = ["trace", &index_id, "grpc", "protobuf", is_error];
         OTLP_SERVICE_METRICS
@@ -834,12 +978,42 @@ impl TraceService for OtlpGrpcTracesService {
         &self,
         request: Request<ExportTraceServiceRequest>,
     ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+        let principal = self
+            .authenticator
+            .authorize(request.metadata(), OtelRole::Ingest)?;
         let index_id = extract_otel_index_id_from_metadata(request.metadata(), OtelSignal::Traces)?;
+        let api_key_opt = extract_api_key_from_metadata(request.metadata());
         let request = request.into_inner();
-        self.clone()
-            .export_instrumented(request, index_id)
-            .await
-            .map(Response::new)
+        let routed_resource_spans = self.index_router.route(
+            index_id,
+            &principal,
+            request.resource_spans,
+            |resource_spans| resource_spans.resource.as_ref(),
+        )?;
+        let mut rejected_spans = 0;
+        let mut error_message = String::new();
+
+        for (index_id, resource_spans) in routed_resource_spans {
+            let request = ExportTraceServiceRequest { resource_spans };
+            let response = self
+                .clone()
+                .export_instrumented(request, index_id, api_key_opt.clone())
+                .await?;
+            if let Some(partial_success) = response.partial_success {
+                rejected_spans += partial_success.rejected_spans;
+
+                if !partial_success.error_message.is_empty() {
+                    error_message = partial_success.error_message;
+                }
+            }
+        }
+        let response = ExportTraceServiceResponse {
+            partial_success: Some(ExportTracePartialSuccess {
+                rejected_spans,
+                error_message,
+            }),
+        };
+        Ok(Response::new(response))
     }
 }
 
@@ -1392,4 +1566,99 @@ mod tests {
         );
         assert!(json_span_iterator.next().is_none());
     }
+
+    #[test]
+    fn test_is_ok_trace_sampled() {
+        let mut trace_id_bytes = [0xff; 16];
+        trace_id_bytes[8..].copy_from_slice(&42u64.to_be_bytes());
+        let trace_id = TraceId::new(trace_id_bytes);
+
+        assert!(!is_ok_trace_sampled(trace_id, 0));
+        assert!(!is_ok_trace_sampled(trace_id, 42));
+        assert!(is_ok_trace_sampled(trace_id, 43));
+        assert!(is_ok_trace_sampled(trace_id, 100));
+
+        let num_sampled_traces = (0..10_000u64)
+            .filter(|trace_id_low| {
+                let mut trace_id_bytes = [0; 16];
+                trace_id_bytes[8..].copy_from_slice(&trace_id_low.to_be_bytes());
+                is_ok_trace_sampled(TraceId::new(trace_id_bytes), 10)
+            })
+            .count();
+        assert_eq!(num_sampled_traces, 1_000);
+    }
+
+    #[test]
+    fn test_sample_traces() {
+        let span = |trace_id_low: u64, span_id: u8, status_code: OtlpStatusCode| {
+            let mut trace_id_bytes = [0; 16];
+            trace_id_bytes[8..].copy_from_slice(&trace_id_low.to_be_bytes());
+            OrdSpan(Span {
+                trace_id: TraceId::new(trace_id_bytes),
+                trace_state: None,
+                service_name: "quickwit".to_string(),
+                resource_attributes: HashMap::new(),
+                resource_dropped_attributes_count: 0,
+                scope_name: None,
+                scope_version: None,
+                scope_attributes: HashMap::new(),
+                scope_dropped_attributes_count: 0,
+                span_id: SpanId::new([span_id; 8]),
+                span_kind: 0,
+                span_name: "publish_splits".to_string(),
+                span_fingerprint: None,
+                span_start_timestamp_nanos: 1_000_000_001,
+                span_end_timestamp_nanos: 1_000_000_002,
+                span_duration_millis: Some(1),
+                span_attributes: HashMap::new(),
+                span_dropped_attributes_count: 0,
+                span_dropped_events_count: 0,
+                span_dropped_links_count: 0,
+                span_status: SpanStatus {
+                    code: status_code,
+                    message: None,
+                },
+                parent_span_id: None,
+                events: Vec::new(),
+                event_names: Vec::new(),
+                links: Vec::new(),
+            })
+        };
+        let spans = || {
+            BTreeSet::from_iter([
+                // Error trace: always kept.
+                span(99, 1, OtlpStatusCode::Ok),
+                span(99, 2, OtlpStatusCode::Error),
+                // OK trace sampled in.
+                span(5, 3, OtlpStatusCode::Unset),
+                span(5, 4, OtlpStatusCode::Ok),
+                // OK trace sampled out.
+                span(50, 5, OtlpStatusCode::Ok),
+                span(50, 6, OtlpStatusCode::Unset),
+            ])
+        };
+        let (kept_spans, sampled_out_spans) = sample_traces(spans(), 10);
+
+        let kept_span_ids: Vec<SpanId> = kept_spans.iter().map(|span| span.0.span_id).collect();
+        assert_eq!(kept_span_ids.len(), 4);
+        for span_id in [1, 2, 3, 4] {
+            assert!(kept_span_ids.contains(&SpanId::new([span_id; 8])));
+        }
+        let sampled_out_span_ids: Vec<SpanId> = sampled_out_spans
+            .iter()
+            .map(|span| span.0.span_id)
+            .collect();
+        assert_eq!(sampled_out_span_ids.len(), 2);
+        for span_id in [5, 6] {
+            assert!(sampled_out_span_ids.contains(&SpanId::new([span_id; 8])));
+        }
+
+        let (kept_spans, sampled_out_spans) = sample_traces(spans(), 0);
+        assert_eq!(kept_spans.len(), 2);
+        assert_eq!(sampled_out_spans.len(), 4);
+
+        let (kept_spans, sampled_out_spans) = sample_traces(spans(), 100);
+        assert_eq!(kept_spans.len(), 6);
+        assert!(sampled_out_spans.is_empty());
+    }
 }
@@ -38,7 +38,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Control plane.
     let mut prost_config = prost_build::Config::default();
-    prost_config.extern_path(".quickwit.common.IndexUid", "crate::types::IndexUid");
+    prost_config
+        .extern_path(".quickwit.common.IndexUid", "crate::types::IndexUid")
+        .extern_path(".quickwit.ingest.ShardId", "crate::types::ShardId");
 
     Codegen::builder()
         .with_prost_config(prost_config)
@@ -170,9 +172,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .type_attribute("ListFieldSerialized", "#[derive(Eq)]")
         .type_attribute("SortByValue", "#[derive(Ord, PartialOrd)]")
         .type_attribute("SortField", "#[derive(Eq, Hash)]")
+        .type_attribute("LookupEnrichment", "#[derive(Eq, Hash)]")
+        .type_attribute("HighlightRequest", "#[derive(Eq, Hash)]")
         .out_dir("src/codegen/quickwit")
         .compile_with_config(prost_config, &["protos/quickwit/search.proto"], &["protos"])?;
 
+    // Document enrichment service.
+    let mut prost_config = prost_build::Config::default();
+    prost_config.bytes(["EnrichDocsRequest.docs", "EnrichDocsResponse.docs"]);
+
+    tonic_build::configure()
+        .out_dir("src/codegen/quickwit")
+        .compile_with_config(
+            prost_config,
+            &["protos/quickwit/enrichment.proto"],
+            &["protos"],
+        )?;
+
     // Jaeger proto
     let protos = find_protos("protos/third-party/jaeger");
 
@@ -9,9 +9,13 @@ pub struct GetOrCreateOpenShardsRequest {
     /// The control plane should return shards that are not present on the supplied leaders.
     ///
     /// The control plane does not change the status of those leaders just from this signal.
-    /// It will check the status of its own ingester pool.
+    /// It will check the status of its own ingester pool, unless a quorum of distinct
+    /// routers reports the same leader as unavailable.
     #[prost(string, repeated, tag = "3")]
     pub unavailable_leaders: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The node ID of the router that issued the request.
+    #[prost(string, tag = "4")]
+    pub router_id: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -23,6 +27,10 @@ pub struct GetOrCreateOpenShardsSubrequest {
     pub index_id: ::prost::alloc::string::String,
     #[prost(string, tag = "3")]
     pub source_id: ::prost::alloc::string::String,
+    /// Version of the shard table of the source last received by the router from the control plane, if any. When the
+    /// control plane still knows this version, it only returns the changes that occurred since.
+    #[prost(uint64, optional, tag = "4")]
+    pub known_shard_table_version: ::core::option::Option<u64>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -43,8 +51,25 @@ pub struct GetOrCreateOpenShardsSuccess {
     pub index_uid: ::core::option::Option<crate::types::IndexUid>,
     #[prost(string, tag = "3")]
     pub source_id: ::prost::alloc::string::String,
+    /// Open shards of the source, sorted by shard ID. Routers hash the routing keys of the subrequests over this stable
+    /// set of shards.
     #[prost(message, repeated, tag = "4")]
     pub open_shards: ::prost::alloc::vec::Vec<super::ingest::Shard>,
+    /// Ingestion pressure of the source, computed by the control plane from the ingestion rates of its open shards.
+    /// Routers use it to reject requests early when the source is about to saturate.
+    #[prost(enumeration = "IngestionPressure", tag = "5")]
+    pub ingestion_pressure: i32,
+    /// Version of the shard table of the source described by this response. Versions are monotonically increasing. A
+    /// version of 0 indicates that the response is not versioned and must not be reported back to the control plane.
+    #[prost(uint64, tag = "6")]
+    pub shard_table_version: u64,
+    /// When true, `open_shards` only contains the shards opened since `known_shard_table_version` and
+    /// `removed_shard_ids` lists the shards that are no longer open. Otherwise, `open_shards` is the complete list of open
+    /// shards of the source.
+    #[prost(bool, tag = "7")]
+    pub is_delta: bool,
+    #[prost(message, repeated, tag = "8")]
+    pub removed_shard_ids: ::prost::alloc::vec::Vec<crate::types::ShardId>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -65,6 +90,9 @@ pub struct GetOrCreateOpenShardsFailure {
 pub struct AdviseResetShardsRequest {
     #[prost(message, repeated, tag = "1")]
     pub shard_ids: ::prost::alloc::vec::Vec<super::ingest::ShardIds>,
+    /// Continuation token returned by a previous truncated response for the same request.
+    #[prost(string, optional, tag = "2")]
+    pub continuation_token: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -74,6 +102,289 @@ pub struct AdviseResetShardsResponse {
     pub shards_to_delete: ::prost::alloc::vec::Vec<super::ingest::ShardIds>,
     #[prost(message, repeated, tag = "2")]
     pub shards_to_truncate: ::prost::alloc::vec::Vec<super::ingest::ShardIdPositions>,
+    /// Set when the response was truncated to stay under the maximum response size. The client must send the same request
+    /// again along with this token to obtain the advice for the remaining shards.
+    #[prost(string, optional, tag = "3")]
+    pub continuation_token: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DecommissionIngesterRequest {
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DecommissionIngesterResponse {
+    /// Number of shards (open or closed) still hosted by the ingester.
+    #[prost(uint32, tag = "1")]
+    pub num_remaining_shards: u32,
+    /// Whether the ingester has been drained and removed from the ingester pool.
+    #[prost(bool, tag = "2")]
+    pub is_decommissioned: bool,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MoveShardRequest {
+    #[prost(message, optional, tag = "1")]
+    pub shard_id: ::core::option::Option<crate::types::ShardId>,
+    #[prost(string, tag = "2")]
+    pub target_ingester_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MoveShardResponse {
+    /// The shard opened on the target ingester to replace the moved shard.
+    #[prost(message, optional, tag = "1")]
+    pub shard: ::core::option::Option<super::ingest::Shard>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetShardEventsRequest {
+    /// Only returns the events of the shards of this index if set.
+    #[prost(string, optional, tag = "1")]
+    pub index_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Only returns the events of the shards of this source if set.
+    #[prost(string, optional, tag = "2")]
+    pub source_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Only returns the events of this shard if set.
+    #[prost(message, optional, tag = "3")]
+    pub shard_id: ::core::option::Option<crate::types::ShardId>,
+    /// Maximum number of events to return. Only the most recent events are returned if the limit is reached.
+    #[prost(uint32, optional, tag = "4")]
+    pub limit: ::core::option::Option<u32>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetShardEventsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<ShardEvent>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShardEvent {
+    /// Unix timestamp of the event in milliseconds.
+    #[prost(int64, tag = "1")]
+    pub timestamp_millis: i64,
+    #[prost(enumeration = "ShardEventType", tag = "2")]
+    pub event_type: i32,
+    #[prost(message, optional, tag = "3")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "4")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "5")]
+    pub shard_id: ::core::option::Option<crate::types::ShardId>,
+    #[prost(string, tag = "6")]
+    pub leader_id: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "7")]
+    pub follower_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Human-readable reason of the transition.
+    #[prost(string, tag = "8")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RebalanceShardsDryRunRequest {}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RebalanceShardsDryRunResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub shard_moves: ::prost::alloc::vec::Vec<ShardMove>,
+    /// Number of open shard replicas, leaders and followers, hosted by each ingester before and after the rebalance.
+    #[prost(message, repeated, tag = "2")]
+    pub shard_distribution: ::prost::alloc::vec::Vec<IngesterShardDistribution>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShardMove {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub shard_id: ::core::option::Option<crate::types::ShardId>,
+    #[prost(string, tag = "4")]
+    pub leader_id: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "5")]
+    pub follower_id: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "6")]
+    pub target_leader_id: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "7")]
+    pub target_follower_id: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IngesterShardDistribution {
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub num_shard_replicas_before: u32,
+    #[prost(uint32, tag = "3")]
+    pub num_shard_replicas_after: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListShardsRequest {
+    /// Only returns the shards of this index if set.
+    #[prost(string, optional, tag = "1")]
+    pub index_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Only returns the shards of this source if set.
+    #[prost(string, optional, tag = "2")]
+    pub source_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Only returns the shards in this state if set.
+    #[prost(enumeration = "super::ingest::ShardState", optional, tag = "3")]
+    pub shard_state: ::core::option::Option<i32>,
+    /// Only returns the shards led by this ingester if set.
+    #[prost(string, optional, tag = "4")]
+    pub leader_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Number of matching shards to skip.
+    #[prost(uint32, tag = "5")]
+    pub offset: u32,
+    /// Maximum number of shards to return.
+    #[prost(uint32, optional, tag = "6")]
+    pub limit: ::core::option::Option<u32>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListShardsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub shards: ::prost::alloc::vec::Vec<ShardInfo>,
+    /// Total number of shards matching the filters, regardless of the offset and limit.
+    #[prost(uint32, tag = "2")]
+    pub num_matching_shards: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShardInfo {
+    #[prost(message, optional, tag = "1")]
+    pub shard: ::core::option::Option<super::ingest::Shard>,
+    /// Ingestion rate of the shard in MiB/s, as last reported by its leader.
+    #[prost(uint32, tag = "2")]
+    pub ingestion_rate_mib_per_sec: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProjectSourceShardsRequest {
+    #[prost(string, tag = "1")]
+    pub index_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    /// Hypothetical ingestion rates of the source in MiB/s for which to project the number of open shards.
+    #[prost(float, repeated, tag = "3")]
+    pub ingestion_rates_mib_per_sec: ::prost::alloc::vec::Vec<f32>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProjectSourceShardsResponse {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "3")]
+    pub num_open_shards: u32,
+    /// Ingestion rate of the source in MiB/s, summed over its open shards.
+    #[prost(float, tag = "4")]
+    pub ingestion_rate_mib_per_sec: f32,
+    /// Number of open shards the ingest controller converges to at the current ingestion rate.
+    #[prost(uint32, tag = "5")]
+    pub target_num_open_shards: u32,
+    /// Average ingestion rate per open shard in MiB/s above which the ingest controller scales up the source.
+    #[prost(float, tag = "6")]
+    pub scale_up_threshold_mib_per_sec: f32,
+    /// Average ingestion rate per open shard in MiB/s below which the ingest controller scales down the source.
+    #[prost(float, tag = "7")]
+    pub scale_down_threshold_mib_per_sec: f32,
+    #[prost(uint32, tag = "8")]
+    pub replication_factor: u32,
+    #[prost(message, repeated, tag = "9")]
+    pub projections: ::prost::alloc::vec::Vec<ShardCountProjection>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShardCountProjection {
+    #[prost(float, tag = "1")]
+    pub ingestion_rate_mib_per_sec: f32,
+    /// Number of open shards the ingest controller converges to at this ingestion rate.
+    #[prost(uint32, tag = "2")]
+    pub num_open_shards: u32,
+    /// Number of shard replicas, leaders and followers, hosted by the ingesters for these open shards.
+    #[prost(uint32, tag = "3")]
+    pub num_shard_replicas: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ForceCloseShardsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub shard_pkeys: ::prost::alloc::vec::Vec<super::ingest::ShardPKey>,
+    /// Why the shards are force-closed. The reason is logged and recorded in the shard events for auditing purposes.
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ForceCloseShardsResponse {
+    /// Shards that were not already closed in the model of the control plane.
+    #[prost(message, repeated, tag = "1")]
+    pub closed_shard_pkeys: ::prost::alloc::vec::Vec<super::ingest::ShardPKey>,
+    /// Shards that their leader acknowledged closing.
+    #[prost(message, repeated, tag = "2")]
+    pub acknowledged_shard_pkeys: ::prost::alloc::vec::Vec<super::ingest::ShardPKey>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseSourceRequest {
+    #[prost(string, tag = "1")]
+    pub index_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseSourceResponse {
+    /// Whether the source was already paused.
+    #[prost(bool, tag = "1")]
+    pub was_paused: bool,
+    /// Number of open shards of the source closed by the control plane.
+    #[prost(uint32, tag = "2")]
+    pub num_closed_shards: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResumeSourceRequest {
+    #[prost(string, tag = "1")]
+    pub index_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResumeSourceResponse {
+    /// Whether the source was paused.
+    #[prost(bool, tag = "1")]
+    pub was_paused: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
@@ -84,6 +395,10 @@ pub enum GetOrCreateOpenShardsFailureReason {
     IndexNotFound = 1,
     SourceNotFound = 2,
     NoIngestersAvailable = 3,
+    /// The aggregate ingestion rate of the index or its tenant reached its ingestion quota.
+    QuotaExceeded = 4,
+    /// The source was paused with the `PauseSource` API.
+    SourcePaused = 5,
 }
 impl GetOrCreateOpenShardsFailureReason {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -104,6 +419,12 @@ impl GetOrCreateOpenShardsFailureReason {
             GetOrCreateOpenShardsFailureReason::NoIngestersAvailable => {
                 "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_NO_INGESTERS_AVAILABLE"
             }
+            GetOrCreateOpenShardsFailureReason::QuotaExceeded => {
+                "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_QUOTA_EXCEEDED"
+            }
+            GetOrCreateOpenShardsFailureReason::SourcePaused => {
+                "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_SOURCE_PAUSED"
+            }
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -121,6 +442,83 @@ impl GetOrCreateOpenShardsFailureReason {
             "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_NO_INGESTERS_AVAILABLE" => {
                 Some(Self::NoIngestersAvailable)
             }
+            "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_QUOTA_EXCEEDED" => {
+                Some(Self::QuotaExceeded)
+            }
+            "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_SOURCE_PAUSED" => {
+                Some(Self::SourcePaused)
+            }
+            _ => None,
+        }
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum IngestionPressure {
+    Low = 0,
+    Moderate = 1,
+    High = 2,
+}
+impl IngestionPressure {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            IngestionPressure::Low => "INGESTION_PRESSURE_LOW",
+            IngestionPressure::Moderate => "INGESTION_PRESSURE_MODERATE",
+            IngestionPressure::High => "INGESTION_PRESSURE_HIGH",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INGESTION_PRESSURE_LOW" => Some(Self::Low),
+            "INGESTION_PRESSURE_MODERATE" => Some(Self::Moderate),
+            "INGESTION_PRESSURE_HIGH" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ShardEventType {
+    Unspecified = 0,
+    Opened = 1,
+    Closed = 2,
+    Moved = 3,
+    Unavailable = 4,
+    Deleted = 5,
+}
+impl ShardEventType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ShardEventType::Unspecified => "SHARD_EVENT_TYPE_UNSPECIFIED",
+            ShardEventType::Opened => "SHARD_EVENT_TYPE_OPENED",
+            ShardEventType::Closed => "SHARD_EVENT_TYPE_CLOSED",
+            ShardEventType::Moved => "SHARD_EVENT_TYPE_MOVED",
+            ShardEventType::Unavailable => "SHARD_EVENT_TYPE_UNAVAILABLE",
+            ShardEventType::Deleted => "SHARD_EVENT_TYPE_DELETED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SHARD_EVENT_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "SHARD_EVENT_TYPE_OPENED" => Some(Self::Opened),
+            "SHARD_EVENT_TYPE_CLOSED" => Some(Self::Closed),
+            "SHARD_EVENT_TYPE_MOVED" => Some(Self::Moved),
+            "SHARD_EVENT_TYPE_UNAVAILABLE" => Some(Self::Unavailable),
+            "SHARD_EVENT_TYPE_DELETED" => Some(Self::Deleted),
             _ => None,
         }
     }
@@ -137,6 +535,11 @@ pub trait ControlPlaneService: std::fmt::Debug + dyn_clone::DynClone + Send + Sy
         &mut self,
         request: super::metastore::CreateIndexRequest,
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::CreateIndexResponse>;
+    /// Updates an index.
+    async fn update_index(
+        &mut self,
+        request: super::metastore::UpdateIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<super::metastore::IndexMetadataResponse>;
     /// Deletes an index.
     async fn delete_index(
         &mut self,
@@ -168,6 +571,62 @@ pub trait ControlPlaneService: std::fmt::Debug + dyn_clone::DynClone + Send + Sy
         &mut self,
         request: AdviseResetShardsRequest,
     ) -> crate::control_plane::ControlPlaneResult<AdviseResetShardsResponse>;
+    /// Decommissions an ingester. The control plane stops allocating new shards to the ingester, moves its open shards
+    /// to other ingesters, and removes it from the ingester pool once all its shards have been fully published.
+    async fn decommission_ingester(
+        &mut self,
+        request: DecommissionIngesterRequest,
+    ) -> crate::control_plane::ControlPlaneResult<DecommissionIngesterResponse>;
+    /// Moves an open shard to the target ingester. A new shard is opened on the target ingester, then the original shard
+    /// is closed.
+    async fn move_shard(
+        &mut self,
+        request: MoveShardRequest,
+    ) -> crate::control_plane::ControlPlaneResult<MoveShardResponse>;
+    /// Returns the shard lifecycle events recorded by the control plane (shards opened, closed, moved, marked as
+    /// unavailable, or deleted), oldest first. This API is meant for debugging.
+    async fn get_shard_events(
+        &mut self,
+        request: GetShardEventsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<GetShardEventsResponse>;
+    /// Computes the shards that the rebalancer would move and the resulting distribution of the shard replicas across
+    /// ingesters without opening or closing any shard. This API is meant for debugging.
+    async fn rebalance_shards_dry_run(
+        &mut self,
+        request: RebalanceShardsDryRunRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceShardsDryRunResponse>;
+    /// Returns the shards of the shard table of the control plane with their ingestion rate, optionally filtered by index,
+    /// source, state, and leader. Shards are sorted by index UID, source ID, and shard ID, and can be paginated.
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ListShardsResponse>;
+    /// Returns the number of open shards of a source, the number of open shards the ingest controller targets given the
+    /// current ingestion rate of the source and its scaling thresholds, and the number of open shards it would target at
+    /// hypothetical ingestion rates. This API is meant for capacity planning.
+    async fn project_source_shards(
+        &mut self,
+        request: ProjectSourceShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ProjectSourceShardsResponse>;
+    /// Closes a set of shards regardless of their state: the shards are marked as closed in the metastore and in the
+    /// model of the control plane, then their leaders are asked to close them on a best-effort basis. This API is meant
+    /// for shards stuck in the open state, for instance because their leader lost its WAL.
+    async fn force_close_shards(
+        &mut self,
+        request: ForceCloseShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ForceCloseShardsResponse>;
+    /// Pauses the ingestion of a source: the control plane closes the open shards of the source and refuses to open new
+    /// ones, and the indexing scheduler stops scheduling its pipelines. The paused state lives in the model of the control
+    /// plane and is not persisted in the metastore. This API is meant for planned maintenance of downstream storage.
+    async fn pause_source(
+        &mut self,
+        request: PauseSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<PauseSourceResponse>;
+    /// Resumes the ingestion of a paused source.
+    async fn resume_source(
+        &mut self,
+        request: ResumeSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ResumeSourceResponse>;
 }
 dyn_clone::clone_trait_object!(ControlPlaneService);
 #[cfg(any(test, feature = "testsuite"))]
@@ -271,6 +730,14 @@ impl ControlPlaneService for ControlPlaneServiceClient {
     > {
         self.inner.create_index(request).await
     }
+    async fn update_index(
+        &mut self,
+        request: super::metastore::UpdateIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.inner.update_index(request).await
+    }
     async fn delete_index(
         &mut self,
         request: super::metastore::DeleteIndexRequest,
@@ -307,6 +774,60 @@ impl ControlPlaneService for ControlPlaneServiceClient {
     ) -> crate::control_plane::ControlPlaneResult<AdviseResetShardsResponse> {
         self.inner.advise_reset_shards(request).await
     }
+    async fn decommission_ingester(
+        &mut self,
+        request: DecommissionIngesterRequest,
+    ) -> crate::control_plane::ControlPlaneResult<DecommissionIngesterResponse> {
+        self.inner.decommission_ingester(request).await
+    }
+    async fn move_shard(
+        &mut self,
+        request: MoveShardRequest,
+    ) -> crate::control_plane::ControlPlaneResult<MoveShardResponse> {
+        self.inner.move_shard(request).await
+    }
+    async fn get_shard_events(
+        &mut self,
+        request: GetShardEventsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<GetShardEventsResponse> {
+        self.inner.get_shard_events(request).await
+    }
+    async fn rebalance_shards_dry_run(
+        &mut self,
+        request: RebalanceShardsDryRunRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceShardsDryRunResponse> {
+        self.inner.rebalance_shards_dry_run(request).await
+    }
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ListShardsResponse> {
+        self.inner.list_shards(request).await
+    }
+    async fn project_source_shards(
+        &mut self,
+        request: ProjectSourceShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ProjectSourceShardsResponse> {
+        self.inner.project_source_shards(request).await
+    }
+    async fn force_close_shards(
+        &mut self,
+        request: ForceCloseShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ForceCloseShardsResponse> {
+        self.inner.force_close_shards(request).await
+    }
+    async fn pause_source(
+        &mut self,
+        request: PauseSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<PauseSourceResponse> {
+        self.inner.pause_source(request).await
+    }
+    async fn resume_source(
+        &mut self,
+        request: ResumeSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ResumeSourceResponse> {
+        self.inner.resume_source(request).await
+    }
 }
 #[cfg(any(test, feature = "testsuite"))]
 pub mod mock_control_plane_service {
@@ -325,6 +846,14 @@ pub mod mock_control_plane_service {
         > {
             self.inner.lock().await.create_index(request).await
         }
+        async fn update_index(
+            &mut self,
+            request: super::super::metastore::UpdateIndexRequest,
+        ) -> crate::control_plane::ControlPlaneResult<
+            super::super::metastore::IndexMetadataResponse,
+        > {
+            self.inner.lock().await.update_index(request).await
+        }
         async fn delete_index(
             &mut self,
             request: super::super::metastore::DeleteIndexRequest,
@@ -371,6 +900,60 @@ pub mod mock_control_plane_service {
         ) -> crate::control_plane::ControlPlaneResult<super::AdviseResetShardsResponse> {
             self.inner.lock().await.advise_reset_shards(request).await
         }
+        async fn decommission_ingester(
+            &mut self,
+            request: super::DecommissionIngesterRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::DecommissionIngesterResponse> {
+            self.inner.lock().await.decommission_ingester(request).await
+        }
+        async fn move_shard(
+            &mut self,
+            request: super::MoveShardRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::MoveShardResponse> {
+            self.inner.lock().await.move_shard(request).await
+        }
+        async fn get_shard_events(
+            &mut self,
+            request: super::GetShardEventsRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::GetShardEventsResponse> {
+            self.inner.lock().await.get_shard_events(request).await
+        }
+        async fn rebalance_shards_dry_run(
+            &mut self,
+            request: super::RebalanceShardsDryRunRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::RebalanceShardsDryRunResponse> {
+            self.inner.lock().await.rebalance_shards_dry_run(request).await
+        }
+        async fn list_shards(
+            &mut self,
+            request: super::ListShardsRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::ListShardsResponse> {
+            self.inner.lock().await.list_shards(request).await
+        }
+        async fn project_source_shards(
+            &mut self,
+            request: super::ProjectSourceShardsRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::ProjectSourceShardsResponse> {
+            self.inner.lock().await.project_source_shards(request).await
+        }
+        async fn force_close_shards(
+            &mut self,
+            request: super::ForceCloseShardsRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::ForceCloseShardsResponse> {
+            self.inner.lock().await.force_close_shards(request).await
+        }
+        async fn pause_source(
+            &mut self,
+            request: super::PauseSourceRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::PauseSourceResponse> {
+            self.inner.lock().await.pause_source(request).await
+        }
+        async fn resume_source(
+            &mut self,
+            request: super::ResumeSourceRequest,
+        ) -> crate::control_plane::ControlPlaneResult<super::ResumeSourceResponse> {
+            self.inner.lock().await.resume_source(request).await
+        }
     }
 }
 pub type BoxFuture<T, E> = std::pin::Pin<
@@ -393,6 +976,23 @@ for Box<dyn ControlPlaneService> {
         Box::pin(fut)
     }
 }
+impl tower::Service<super::metastore::UpdateIndexRequest>
+for Box<dyn ControlPlaneService> {
+    type Response = super::metastore::IndexMetadataResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: super::metastore::UpdateIndexRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.update_index(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<super::metastore::DeleteIndexRequest>
 for Box<dyn ControlPlaneService> {
     type Response = super::metastore::EmptyResponse;
@@ -493,32 +1093,181 @@ impl tower::Service<AdviseResetShardsRequest> for Box<dyn ControlPlaneService> {
         Box::pin(fut)
     }
 }
-/// A tower service stack is a set of tower services.
-#[derive(Debug)]
-struct ControlPlaneServiceTowerServiceStack {
-    inner: Box<dyn ControlPlaneService>,
-    create_index_svc: quickwit_common::tower::BoxService<
-        super::metastore::CreateIndexRequest,
-        super::metastore::CreateIndexResponse,
-        crate::control_plane::ControlPlaneError,
-    >,
-    delete_index_svc: quickwit_common::tower::BoxService<
-        super::metastore::DeleteIndexRequest,
-        super::metastore::EmptyResponse,
-        crate::control_plane::ControlPlaneError,
-    >,
-    add_source_svc: quickwit_common::tower::BoxService<
-        super::metastore::AddSourceRequest,
-        super::metastore::EmptyResponse,
-        crate::control_plane::ControlPlaneError,
-    >,
-    toggle_source_svc: quickwit_common::tower::BoxService<
-        super::metastore::ToggleSourceRequest,
-        super::metastore::EmptyResponse,
-        crate::control_plane::ControlPlaneError,
-    >,
-    delete_source_svc: quickwit_common::tower::BoxService<
-        super::metastore::DeleteSourceRequest,
+impl tower::Service<DecommissionIngesterRequest> for Box<dyn ControlPlaneService> {
+    type Response = DecommissionIngesterResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: DecommissionIngesterRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.decommission_ingester(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<MoveShardRequest> for Box<dyn ControlPlaneService> {
+    type Response = MoveShardResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: MoveShardRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.move_shard(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<GetShardEventsRequest> for Box<dyn ControlPlaneService> {
+    type Response = GetShardEventsResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: GetShardEventsRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.get_shard_events(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<RebalanceShardsDryRunRequest> for Box<dyn ControlPlaneService> {
+    type Response = RebalanceShardsDryRunResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: RebalanceShardsDryRunRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.rebalance_shards_dry_run(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<ListShardsRequest> for Box<dyn ControlPlaneService> {
+    type Response = ListShardsResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ListShardsRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.list_shards(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<ProjectSourceShardsRequest> for Box<dyn ControlPlaneService> {
+    type Response = ProjectSourceShardsResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ProjectSourceShardsRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.project_source_shards(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<ForceCloseShardsRequest> for Box<dyn ControlPlaneService> {
+    type Response = ForceCloseShardsResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ForceCloseShardsRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.force_close_shards(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<PauseSourceRequest> for Box<dyn ControlPlaneService> {
+    type Response = PauseSourceResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: PauseSourceRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.pause_source(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<ResumeSourceRequest> for Box<dyn ControlPlaneService> {
+    type Response = ResumeSourceResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ResumeSourceRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.resume_source(request).await };
+        Box::pin(fut)
+    }
+}
+/// A tower service stack is a set of tower services.
+#[derive(Debug)]
+struct ControlPlaneServiceTowerServiceStack {
+    inner: Box<dyn ControlPlaneService>,
+    create_index_svc: quickwit_common::tower::BoxService<
+        super::metastore::CreateIndexRequest,
+        super::metastore::CreateIndexResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    update_index_svc: quickwit_common::tower::BoxService<
+        super::metastore::UpdateIndexRequest,
+        super::metastore::IndexMetadataResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    delete_index_svc: quickwit_common::tower::BoxService<
+        super::metastore::DeleteIndexRequest,
+        super::metastore::EmptyResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    add_source_svc: quickwit_common::tower::BoxService<
+        super::metastore::AddSourceRequest,
+        super::metastore::EmptyResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    toggle_source_svc: quickwit_common::tower::BoxService<
+        super::metastore::ToggleSourceRequest,
+        super::metastore::EmptyResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    delete_source_svc: quickwit_common::tower::BoxService<
+        super::metastore::DeleteSourceRequest,
         super::metastore::EmptyResponse,
         crate::control_plane::ControlPlaneError,
     >,
@@ -532,18 +1281,73 @@ struct ControlPlaneServiceTowerServiceStack {
         AdviseResetShardsResponse,
         crate::control_plane::ControlPlaneError,
     >,
+    decommission_ingester_svc: quickwit_common::tower::BoxService<
+        DecommissionIngesterRequest,
+        DecommissionIngesterResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    move_shard_svc: quickwit_common::tower::BoxService<
+        MoveShardRequest,
+        MoveShardResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    get_shard_events_svc: quickwit_common::tower::BoxService<
+        GetShardEventsRequest,
+        GetShardEventsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    rebalance_shards_dry_run_svc: quickwit_common::tower::BoxService<
+        RebalanceShardsDryRunRequest,
+        RebalanceShardsDryRunResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    list_shards_svc: quickwit_common::tower::BoxService<
+        ListShardsRequest,
+        ListShardsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    project_source_shards_svc: quickwit_common::tower::BoxService<
+        ProjectSourceShardsRequest,
+        ProjectSourceShardsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    force_close_shards_svc: quickwit_common::tower::BoxService<
+        ForceCloseShardsRequest,
+        ForceCloseShardsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    pause_source_svc: quickwit_common::tower::BoxService<
+        PauseSourceRequest,
+        PauseSourceResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    resume_source_svc: quickwit_common::tower::BoxService<
+        ResumeSourceRequest,
+        ResumeSourceResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
 }
 impl Clone for ControlPlaneServiceTowerServiceStack {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
             create_index_svc: self.create_index_svc.clone(),
+            update_index_svc: self.update_index_svc.clone(),
             delete_index_svc: self.delete_index_svc.clone(),
             add_source_svc: self.add_source_svc.clone(),
             toggle_source_svc: self.toggle_source_svc.clone(),
             delete_source_svc: self.delete_source_svc.clone(),
             get_or_create_open_shards_svc: self.get_or_create_open_shards_svc.clone(),
             advise_reset_shards_svc: self.advise_reset_shards_svc.clone(),
+            decommission_ingester_svc: self.decommission_ingester_svc.clone(),
+            move_shard_svc: self.move_shard_svc.clone(),
+            get_shard_events_svc: self.get_shard_events_svc.clone(),
+            rebalance_shards_dry_run_svc: self.rebalance_shards_dry_run_svc.clone(),
+            list_shards_svc: self.list_shards_svc.clone(),
+            project_source_shards_svc: self.project_source_shards_svc.clone(),
+            force_close_shards_svc: self.force_close_shards_svc.clone(),
+            pause_source_svc: self.pause_source_svc.clone(),
+            resume_source_svc: self.resume_source_svc.clone(),
         }
     }
 }
@@ -557,6 +1361,14 @@ impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
     > {
         self.create_index_svc.ready().await?.call(request).await
     }
+    async fn update_index(
+        &mut self,
+        request: super::metastore::UpdateIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.update_index_svc.ready().await?.call(request).await
+    }
     async fn delete_index(
         &mut self,
         request: super::metastore::DeleteIndexRequest,
@@ -593,6 +1405,60 @@ impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
     ) -> crate::control_plane::ControlPlaneResult<AdviseResetShardsResponse> {
         self.advise_reset_shards_svc.ready().await?.call(request).await
     }
+    async fn decommission_ingester(
+        &mut self,
+        request: DecommissionIngesterRequest,
+    ) -> crate::control_plane::ControlPlaneResult<DecommissionIngesterResponse> {
+        self.decommission_ingester_svc.ready().await?.call(request).await
+    }
+    async fn move_shard(
+        &mut self,
+        request: MoveShardRequest,
+    ) -> crate::control_plane::ControlPlaneResult<MoveShardResponse> {
+        self.move_shard_svc.ready().await?.call(request).await
+    }
+    async fn get_shard_events(
+        &mut self,
+        request: GetShardEventsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<GetShardEventsResponse> {
+        self.get_shard_events_svc.ready().await?.call(request).await
+    }
+    async fn rebalance_shards_dry_run(
+        &mut self,
+        request: RebalanceShardsDryRunRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceShardsDryRunResponse> {
+        self.rebalance_shards_dry_run_svc.ready().await?.call(request).await
+    }
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ListShardsResponse> {
+        self.list_shards_svc.ready().await?.call(request).await
+    }
+    async fn project_source_shards(
+        &mut self,
+        request: ProjectSourceShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ProjectSourceShardsResponse> {
+        self.project_source_shards_svc.ready().await?.call(request).await
+    }
+    async fn force_close_shards(
+        &mut self,
+        request: ForceCloseShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ForceCloseShardsResponse> {
+        self.force_close_shards_svc.ready().await?.call(request).await
+    }
+    async fn pause_source(
+        &mut self,
+        request: PauseSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<PauseSourceResponse> {
+        self.pause_source_svc.ready().await?.call(request).await
+    }
+    async fn resume_source(
+        &mut self,
+        request: ResumeSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ResumeSourceResponse> {
+        self.resume_source_svc.ready().await?.call(request).await
+    }
 }
 type CreateIndexLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
@@ -604,6 +1470,16 @@ type CreateIndexLayer = quickwit_common::tower::BoxLayer<
     super::metastore::CreateIndexResponse,
     crate::control_plane::ControlPlaneError,
 >;
+type UpdateIndexLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        super::metastore::UpdateIndexRequest,
+        super::metastore::IndexMetadataResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    super::metastore::UpdateIndexRequest,
+    super::metastore::IndexMetadataResponse,
+    crate::control_plane::ControlPlaneError,
+>;
 type DeleteIndexLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         super::metastore::DeleteIndexRequest,
@@ -664,15 +1540,115 @@ type AdviseResetShardsLayer = quickwit_common::tower::BoxLayer<
     AdviseResetShardsResponse,
     crate::control_plane::ControlPlaneError,
 >;
+type DecommissionIngesterLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        DecommissionIngesterRequest,
+        DecommissionIngesterResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    DecommissionIngesterRequest,
+    DecommissionIngesterResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type MoveShardLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        MoveShardRequest,
+        MoveShardResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    MoveShardRequest,
+    MoveShardResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type GetShardEventsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        GetShardEventsRequest,
+        GetShardEventsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    GetShardEventsRequest,
+    GetShardEventsResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type RebalanceShardsDryRunLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        RebalanceShardsDryRunRequest,
+        RebalanceShardsDryRunResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    RebalanceShardsDryRunRequest,
+    RebalanceShardsDryRunResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type ListShardsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ListShardsRequest,
+        ListShardsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    ListShardsRequest,
+    ListShardsResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type ProjectSourceShardsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ProjectSourceShardsRequest,
+        ProjectSourceShardsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    ProjectSourceShardsRequest,
+    ProjectSourceShardsResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type ForceCloseShardsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ForceCloseShardsRequest,
+        ForceCloseShardsResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    ForceCloseShardsRequest,
+    ForceCloseShardsResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type PauseSourceLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        PauseSourceRequest,
+        PauseSourceResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    PauseSourceRequest,
+    PauseSourceResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type ResumeSourceLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ResumeSourceRequest,
+        ResumeSourceResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    ResumeSourceRequest,
+    ResumeSourceResponse,
+    crate::control_plane::ControlPlaneError,
+>;
 #[derive(Debug, Default)]
 pub struct ControlPlaneServiceTowerLayerStack {
     create_index_layers: Vec<CreateIndexLayer>,
+    update_index_layers: Vec<UpdateIndexLayer>,
     delete_index_layers: Vec<DeleteIndexLayer>,
     add_source_layers: Vec<AddSourceLayer>,
     toggle_source_layers: Vec<ToggleSourceLayer>,
     delete_source_layers: Vec<DeleteSourceLayer>,
     get_or_create_open_shards_layers: Vec<GetOrCreateOpenShardsLayer>,
     advise_reset_shards_layers: Vec<AdviseResetShardsLayer>,
+    decommission_ingester_layers: Vec<DecommissionIngesterLayer>,
+    move_shard_layers: Vec<MoveShardLayer>,
+    get_shard_events_layers: Vec<GetShardEventsLayer>,
+    rebalance_shards_dry_run_layers: Vec<RebalanceShardsDryRunLayer>,
+    list_shards_layers: Vec<ListShardsLayer>,
+    project_source_shards_layers: Vec<ProjectSourceShardsLayer>,
+    force_close_shards_layers: Vec<ForceCloseShardsLayer>,
+    pause_source_layers: Vec<PauseSourceLayer>,
+    resume_source_layers: Vec<ResumeSourceLayer>,
 }
 impl ControlPlaneServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -864,44 +1840,310 @@ impl ControlPlaneServiceTowerLayerStack {
                 crate::control_plane::ControlPlaneError,
             >,
         >>::Service as tower::Service<AdviseResetShardsRequest>>::Future: Send + 'static,
-    {
-        self.create_index_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self.delete_index_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self.add_source_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self.toggle_source_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self.delete_source_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self.get_or_create_open_shards_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self.advise_reset_shards_layers
-            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
-        self
-    }
-    pub fn stack_create_index_layer<L>(mut self, layer: L) -> Self
-    where
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
-                    super::metastore::CreateIndexRequest,
-                    super::metastore::CreateIndexResponse,
+                    DecommissionIngesterRequest,
+                    DecommissionIngesterResponse,
                     crate::control_plane::ControlPlaneError,
                 >,
-            > + Send + Sync + 'static,
-        L::Service: tower::Service<
-                super::metastore::CreateIndexRequest,
-                Response = super::metastore::CreateIndexResponse,
-                Error = crate::control_plane::ControlPlaneError,
             > + Clone + Send + Sync + 'static,
-        <L::Service as tower::Service<
-            super::metastore::CreateIndexRequest,
-        >>::Future: Send + 'static,
-    {
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                DecommissionIngesterRequest,
+                DecommissionIngesterResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                DecommissionIngesterRequest,
+                Response = DecommissionIngesterResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                DecommissionIngesterRequest,
+                DecommissionIngesterResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<DecommissionIngesterRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    MoveShardRequest,
+                    MoveShardResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                MoveShardRequest,
+                MoveShardResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                MoveShardRequest,
+                Response = MoveShardResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                MoveShardRequest,
+                MoveShardResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<MoveShardRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    GetShardEventsRequest,
+                    GetShardEventsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                GetShardEventsRequest,
+                GetShardEventsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                GetShardEventsRequest,
+                Response = GetShardEventsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                GetShardEventsRequest,
+                GetShardEventsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<GetShardEventsRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    RebalanceShardsDryRunRequest,
+                    RebalanceShardsDryRunResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                RebalanceShardsDryRunRequest,
+                RebalanceShardsDryRunResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                RebalanceShardsDryRunRequest,
+                Response = RebalanceShardsDryRunResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                RebalanceShardsDryRunRequest,
+                RebalanceShardsDryRunResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<RebalanceShardsDryRunRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ListShardsRequest,
+                    ListShardsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ListShardsRequest,
+                ListShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                ListShardsRequest,
+                Response = ListShardsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ListShardsRequest,
+                ListShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<ListShardsRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ProjectSourceShardsRequest,
+                    ProjectSourceShardsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ProjectSourceShardsRequest,
+                ProjectSourceShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                ProjectSourceShardsRequest,
+                Response = ProjectSourceShardsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ProjectSourceShardsRequest,
+                ProjectSourceShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<ProjectSourceShardsRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ForceCloseShardsRequest,
+                    ForceCloseShardsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ForceCloseShardsRequest,
+                ForceCloseShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                ForceCloseShardsRequest,
+                Response = ForceCloseShardsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ForceCloseShardsRequest,
+                ForceCloseShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<ForceCloseShardsRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    PauseSourceRequest,
+                    PauseSourceResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                PauseSourceRequest,
+                PauseSourceResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                PauseSourceRequest,
+                Response = PauseSourceResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                PauseSourceRequest,
+                PauseSourceResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<PauseSourceRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ResumeSourceRequest,
+                    ResumeSourceResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ResumeSourceRequest,
+                ResumeSourceResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                ResumeSourceRequest,
+                Response = ResumeSourceResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ResumeSourceRequest,
+                ResumeSourceResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<ResumeSourceRequest>>::Future: Send + 'static,
+    {
+        self.create_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.update_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.delete_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.add_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.toggle_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.delete_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.get_or_create_open_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.advise_reset_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.decommission_ingester_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.move_shard_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.get_shard_events_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.rebalance_shards_dry_run_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.list_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.project_source_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.force_close_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.pause_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.resume_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self
+    }
+    pub fn stack_create_index_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    super::metastore::CreateIndexRequest,
+                    super::metastore::CreateIndexResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                super::metastore::CreateIndexRequest,
+                Response = super::metastore::CreateIndexResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            super::metastore::CreateIndexRequest,
+        >>::Future: Send + 'static,
+    {
         self.create_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_update_index_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    super::metastore::UpdateIndexRequest,
+                    super::metastore::IndexMetadataResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                super::metastore::UpdateIndexRequest,
+                Response = super::metastore::IndexMetadataResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            super::metastore::UpdateIndexRequest,
+        >>::Future: Send + 'static,
+    {
+        self.update_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_delete_index_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -1028,50 +2270,230 @@ impl ControlPlaneServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
-    pub fn build<T>(self, instance: T) -> ControlPlaneServiceClient
+    pub fn stack_decommission_ingester_layer<L>(mut self, layer: L) -> Self
     where
-        T: ControlPlaneService,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    DecommissionIngesterRequest,
+                    DecommissionIngesterResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                DecommissionIngesterRequest,
+                Response = DecommissionIngesterResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<DecommissionIngesterRequest>>::Future: Send + 'static,
     {
-        self.build_from_boxed(Box::new(instance))
-    }
-    pub fn build_from_channel(
-        self,
-        addr: std::net::SocketAddr,
-        channel: tonic::transport::Channel,
-        max_message_size: bytesize::ByteSize,
-    ) -> ControlPlaneServiceClient {
-        self.build_from_boxed(
-            Box::new(
-                ControlPlaneServiceClient::from_channel(addr, channel, max_message_size),
-            ),
-        )
-    }
-    pub fn build_from_balance_channel(
-        self,
-        balance_channel: quickwit_common::tower::BalanceChannel<std::net::SocketAddr>,
-        max_message_size: bytesize::ByteSize,
-    ) -> ControlPlaneServiceClient {
-        self.build_from_boxed(
-            Box::new(
-                ControlPlaneServiceClient::from_balance_channel(
-                    balance_channel,
-                    max_message_size,
-                ),
-            ),
-        )
+        self.decommission_ingester_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
     }
-    pub fn build_from_mailbox<A>(
-        self,
-        mailbox: quickwit_actors::Mailbox<A>,
-    ) -> ControlPlaneServiceClient
+    pub fn stack_move_shard_layer<L>(mut self, layer: L) -> Self
     where
-        A: quickwit_actors::Actor + std::fmt::Debug + Send + 'static,
-        ControlPlaneServiceMailbox<A>: ControlPlaneService,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    MoveShardRequest,
+                    MoveShardResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                MoveShardRequest,
+                Response = MoveShardResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<MoveShardRequest>>::Future: Send + 'static,
     {
-        self.build_from_boxed(Box::new(ControlPlaneServiceMailbox::new(mailbox)))
+        self.move_shard_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
     }
-    #[cfg(any(test, feature = "testsuite"))]
-    pub fn build_from_mock(
+    pub fn stack_get_shard_events_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    GetShardEventsRequest,
+                    GetShardEventsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                GetShardEventsRequest,
+                Response = GetShardEventsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<GetShardEventsRequest>>::Future: Send + 'static,
+    {
+        self.get_shard_events_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_rebalance_shards_dry_run_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    RebalanceShardsDryRunRequest,
+                    RebalanceShardsDryRunResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                RebalanceShardsDryRunRequest,
+                Response = RebalanceShardsDryRunResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<RebalanceShardsDryRunRequest>>::Future: Send + 'static,
+    {
+        self.rebalance_shards_dry_run_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_list_shards_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ListShardsRequest,
+                    ListShardsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ListShardsRequest,
+                Response = ListShardsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ListShardsRequest>>::Future: Send + 'static,
+    {
+        self.list_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_project_source_shards_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ProjectSourceShardsRequest,
+                    ProjectSourceShardsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ProjectSourceShardsRequest,
+                Response = ProjectSourceShardsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ProjectSourceShardsRequest>>::Future: Send + 'static,
+    {
+        self.project_source_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_force_close_shards_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ForceCloseShardsRequest,
+                    ForceCloseShardsResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ForceCloseShardsRequest,
+                Response = ForceCloseShardsResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ForceCloseShardsRequest>>::Future: Send + 'static,
+    {
+        self.force_close_shards_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_pause_source_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    PauseSourceRequest,
+                    PauseSourceResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                PauseSourceRequest,
+                Response = PauseSourceResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<PauseSourceRequest>>::Future: Send + 'static,
+    {
+        self.pause_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_resume_source_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ResumeSourceRequest,
+                    ResumeSourceResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ResumeSourceRequest,
+                Response = ResumeSourceResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ResumeSourceRequest>>::Future: Send + 'static,
+    {
+        self.resume_source_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn build<T>(self, instance: T) -> ControlPlaneServiceClient
+    where
+        T: ControlPlaneService,
+    {
+        self.build_from_boxed(Box::new(instance))
+    }
+    pub fn build_from_channel(
+        self,
+        addr: std::net::SocketAddr,
+        channel: tonic::transport::Channel,
+        max_message_size: bytesize::ByteSize,
+    ) -> ControlPlaneServiceClient {
+        self.build_from_boxed(
+            Box::new(
+                ControlPlaneServiceClient::from_channel(addr, channel, max_message_size),
+            ),
+        )
+    }
+    pub fn build_from_balance_channel(
+        self,
+        balance_channel: quickwit_common::tower::BalanceChannel<std::net::SocketAddr>,
+        max_message_size: bytesize::ByteSize,
+    ) -> ControlPlaneServiceClient {
+        self.build_from_boxed(
+            Box::new(
+                ControlPlaneServiceClient::from_balance_channel(
+                    balance_channel,
+                    max_message_size,
+                ),
+            ),
+        )
+    }
+    pub fn build_from_mailbox<A>(
+        self,
+        mailbox: quickwit_actors::Mailbox<A>,
+    ) -> ControlPlaneServiceClient
+    where
+        A: quickwit_actors::Actor + std::fmt::Debug + Send + 'static,
+        ControlPlaneServiceMailbox<A>: ControlPlaneService,
+    {
+        self.build_from_boxed(Box::new(ControlPlaneServiceMailbox::new(mailbox)))
+    }
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn build_from_mock(
         self,
         mock: MockControlPlaneService,
     ) -> ControlPlaneServiceClient {
@@ -1089,6 +2511,14 @@ impl ControlPlaneServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let update_index_svc = self
+            .update_index_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let delete_index_svc = self
             .delete_index_layers
             .into_iter()
@@ -1137,15 +2567,97 @@ impl ControlPlaneServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let decommission_ingester_svc = self
+            .decommission_ingester_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let move_shard_svc = self
+            .move_shard_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let get_shard_events_svc = self
+            .get_shard_events_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let rebalance_shards_dry_run_svc = self
+            .rebalance_shards_dry_run_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let list_shards_svc = self
+            .list_shards_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let project_source_shards_svc = self
+            .project_source_shards_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let force_close_shards_svc = self
+            .force_close_shards_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let pause_source_svc = self
+            .pause_source_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let resume_source_svc = self
+            .resume_source_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = ControlPlaneServiceTowerServiceStack {
             inner: boxed_instance.clone(),
             create_index_svc,
+            update_index_svc,
             delete_index_svc,
             add_source_svc,
             toggle_source_svc,
             delete_source_svc,
             get_or_create_open_shards_svc,
             advise_reset_shards_svc,
+            decommission_ingester_svc,
+            move_shard_svc,
+            get_shard_events_svc,
+            rebalance_shards_dry_run_svc,
+            list_shards_svc,
+            project_source_shards_svc,
+            force_close_shards_svc,
+            pause_source_svc,
+            resume_source_svc,
         };
         ControlPlaneServiceClient::new(tower_svc_stack)
     }
@@ -1284,6 +2796,87 @@ where
                 AdviseResetShardsResponse,
                 crate::control_plane::ControlPlaneError,
             >,
+        >
+        + tower::Service<
+            DecommissionIngesterRequest,
+            Response = DecommissionIngesterResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                DecommissionIngesterResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            MoveShardRequest,
+            Response = MoveShardResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                MoveShardResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            GetShardEventsRequest,
+            Response = GetShardEventsResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                GetShardEventsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            RebalanceShardsDryRunRequest,
+            Response = RebalanceShardsDryRunResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                RebalanceShardsDryRunResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            ListShardsRequest,
+            Response = ListShardsResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                ListShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            ProjectSourceShardsRequest,
+            Response = ProjectSourceShardsResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                ProjectSourceShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            ForceCloseShardsRequest,
+            Response = ForceCloseShardsResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                ForceCloseShardsResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            PauseSourceRequest,
+            Response = PauseSourceResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                PauseSourceResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            ResumeSourceRequest,
+            Response = ResumeSourceResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                ResumeSourceResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
         >,
 {
     async fn create_index(
@@ -1294,6 +2887,14 @@ where
     > {
         self.call(request).await
     }
+    async fn update_index(
+        &mut self,
+        request: super::metastore::UpdateIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.call(request).await
+    }
     async fn delete_index(
         &mut self,
         request: super::metastore::DeleteIndexRequest,
@@ -1330,8 +2931,62 @@ where
     ) -> crate::control_plane::ControlPlaneResult<AdviseResetShardsResponse> {
         self.call(request).await
     }
-}
-#[derive(Debug, Clone)]
+    async fn decommission_ingester(
+        &mut self,
+        request: DecommissionIngesterRequest,
+    ) -> crate::control_plane::ControlPlaneResult<DecommissionIngesterResponse> {
+        self.call(request).await
+    }
+    async fn move_shard(
+        &mut self,
+        request: MoveShardRequest,
+    ) -> crate::control_plane::ControlPlaneResult<MoveShardResponse> {
+        self.call(request).await
+    }
+    async fn get_shard_events(
+        &mut self,
+        request: GetShardEventsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<GetShardEventsResponse> {
+        self.call(request).await
+    }
+    async fn rebalance_shards_dry_run(
+        &mut self,
+        request: RebalanceShardsDryRunRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceShardsDryRunResponse> {
+        self.call(request).await
+    }
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ListShardsResponse> {
+        self.call(request).await
+    }
+    async fn project_source_shards(
+        &mut self,
+        request: ProjectSourceShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ProjectSourceShardsResponse> {
+        self.call(request).await
+    }
+    async fn force_close_shards(
+        &mut self,
+        request: ForceCloseShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ForceCloseShardsResponse> {
+        self.call(request).await
+    }
+    async fn pause_source(
+        &mut self,
+        request: PauseSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<PauseSourceResponse> {
+        self.call(request).await
+    }
+    async fn resume_source(
+        &mut self,
+        request: ResumeSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ResumeSourceResponse> {
+        self.call(request).await
+    }
+}
+#[derive(Debug, Clone)]
 pub struct ControlPlaneServiceGrpcClientAdapter<T> {
     inner: T,
     #[allow(dead_code)]
@@ -1380,6 +3035,21 @@ where
                 super::metastore::CreateIndexRequest::rpc_name(),
             ))
     }
+    async fn update_index(
+        &mut self,
+        request: super::metastore::UpdateIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.inner
+            .update_index(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                super::metastore::UpdateIndexRequest::rpc_name(),
+            ))
+    }
     async fn delete_index(
         &mut self,
         request: super::metastore::DeleteIndexRequest,
@@ -1458,6 +3128,123 @@ where
                 AdviseResetShardsRequest::rpc_name(),
             ))
     }
+    async fn decommission_ingester(
+        &mut self,
+        request: DecommissionIngesterRequest,
+    ) -> crate::control_plane::ControlPlaneResult<DecommissionIngesterResponse> {
+        self.inner
+            .decommission_ingester(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                DecommissionIngesterRequest::rpc_name(),
+            ))
+    }
+    async fn move_shard(
+        &mut self,
+        request: MoveShardRequest,
+    ) -> crate::control_plane::ControlPlaneResult<MoveShardResponse> {
+        self.inner
+            .move_shard(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                MoveShardRequest::rpc_name(),
+            ))
+    }
+    async fn get_shard_events(
+        &mut self,
+        request: GetShardEventsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<GetShardEventsResponse> {
+        self.inner
+            .get_shard_events(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                GetShardEventsRequest::rpc_name(),
+            ))
+    }
+    async fn rebalance_shards_dry_run(
+        &mut self,
+        request: RebalanceShardsDryRunRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceShardsDryRunResponse> {
+        self.inner
+            .rebalance_shards_dry_run(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                RebalanceShardsDryRunRequest::rpc_name(),
+            ))
+    }
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ListShardsResponse> {
+        self.inner
+            .list_shards(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                ListShardsRequest::rpc_name(),
+            ))
+    }
+    async fn project_source_shards(
+        &mut self,
+        request: ProjectSourceShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ProjectSourceShardsResponse> {
+        self.inner
+            .project_source_shards(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                ProjectSourceShardsRequest::rpc_name(),
+            ))
+    }
+    async fn force_close_shards(
+        &mut self,
+        request: ForceCloseShardsRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ForceCloseShardsResponse> {
+        self.inner
+            .force_close_shards(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                ForceCloseShardsRequest::rpc_name(),
+            ))
+    }
+    async fn pause_source(
+        &mut self,
+        request: PauseSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<PauseSourceResponse> {
+        self.inner
+            .pause_source(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                PauseSourceRequest::rpc_name(),
+            ))
+    }
+    async fn resume_source(
+        &mut self,
+        request: ResumeSourceRequest,
+    ) -> crate::control_plane::ControlPlaneResult<ResumeSourceResponse> {
+        self.inner
+            .resume_source(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                ResumeSourceRequest::rpc_name(),
+            ))
+    }
 }
 #[derive(Debug)]
 pub struct ControlPlaneServiceGrpcServerAdapter {
@@ -1485,6 +3272,17 @@ for ControlPlaneServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn update_index(
+        &self,
+        request: tonic::Request<super::metastore::UpdateIndexRequest>,
+    ) -> Result<tonic::Response<super::metastore::IndexMetadataResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .update_index(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
     async fn delete_index(
         &self,
         request: tonic::Request<super::metastore::DeleteIndexRequest>,
@@ -1551,6 +3349,105 @@ for ControlPlaneServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn decommission_ingester(
+        &self,
+        request: tonic::Request<DecommissionIngesterRequest>,
+    ) -> Result<tonic::Response<DecommissionIngesterResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .decommission_ingester(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn move_shard(
+        &self,
+        request: tonic::Request<MoveShardRequest>,
+    ) -> Result<tonic::Response<MoveShardResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .move_shard(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn get_shard_events(
+        &self,
+        request: tonic::Request<GetShardEventsRequest>,
+    ) -> Result<tonic::Response<GetShardEventsResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .get_shard_events(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn rebalance_shards_dry_run(
+        &self,
+        request: tonic::Request<RebalanceShardsDryRunRequest>,
+    ) -> Result<tonic::Response<RebalanceShardsDryRunResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .rebalance_shards_dry_run(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn list_shards(
+        &self,
+        request: tonic::Request<ListShardsRequest>,
+    ) -> Result<tonic::Response<ListShardsResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .list_shards(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn project_source_shards(
+        &self,
+        request: tonic::Request<ProjectSourceShardsRequest>,
+    ) -> Result<tonic::Response<ProjectSourceShardsResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .project_source_shards(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn force_close_shards(
+        &self,
+        request: tonic::Request<ForceCloseShardsRequest>,
+    ) -> Result<tonic::Response<ForceCloseShardsResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .force_close_shards(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn pause_source(
+        &self,
+        request: tonic::Request<PauseSourceRequest>,
+    ) -> Result<tonic::Response<PauseSourceResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .pause_source(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn resume_source(
+        &self,
+        request: tonic::Request<ResumeSourceRequest>,
+    ) -> Result<tonic::Response<ResumeSourceResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .resume_source(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
 }
 /// Generated client implementations.
 pub mod control_plane_service_grpc_client {
@@ -1670,6 +3567,37 @@ pub mod control_plane_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Updates an index.
+        pub async fn update_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::metastore::UpdateIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::IndexMetadataResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/UpdateIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "UpdateIndex",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Deletes an index.
         pub async fn delete_index(
             &mut self,
@@ -1861,36 +3789,334 @@ pub mod control_plane_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod control_plane_service_grpc_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with ControlPlaneServiceGrpcServer.
-    #[async_trait]
-    pub trait ControlPlaneServiceGrpc: Send + Sync + 'static {
-        /// Creates a new index.
-        async fn create_index(
-            &self,
-            request: tonic::Request<super::super::metastore::CreateIndexRequest>,
+        /// Decommissions an ingester. The control plane stops allocating new shards to the ingester, moves its open shards
+        /// to other ingesters, and removes it from the ingester pool once all its shards have been fully published.
+        pub async fn decommission_ingester(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DecommissionIngesterRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::super::metastore::CreateIndexResponse>,
+            tonic::Response<super::DecommissionIngesterResponse>,
             tonic::Status,
-        >;
-        /// Deletes an index.
-        async fn delete_index(
-            &self,
-            request: tonic::Request<super::super::metastore::DeleteIndexRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/DecommissionIngester",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "DecommissionIngester",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Moves an open shard to the target ingester. A new shard is opened on the target ingester, then the original shard
+        /// is closed.
+        pub async fn move_shard(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MoveShardRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::super::metastore::EmptyResponse>,
+            tonic::Response<super::MoveShardResponse>,
             tonic::Status,
-        >;
-        /// Adds a source to an index.
-        async fn add_source(
-            &self,
-            request: tonic::Request<super::super::metastore::AddSourceRequest>,
-        ) -> std::result::Result<
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/MoveShard",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "MoveShard",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the shard lifecycle events recorded by the control plane (shards opened, closed, moved, marked as
+        /// unavailable, or deleted), oldest first. This API is meant for debugging.
+        pub async fn get_shard_events(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetShardEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetShardEventsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/GetShardEvents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "GetShardEvents",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Computes the shards that the rebalancer would move and the resulting distribution of the shard replicas across
+        /// ingesters without opening or closing any shard. This API is meant for debugging.
+        pub async fn rebalance_shards_dry_run(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RebalanceShardsDryRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RebalanceShardsDryRunResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/RebalanceShardsDryRun",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "RebalanceShardsDryRun",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the shards of the shard table of the control plane with their ingestion rate, optionally filtered by index,
+        /// source, state, and leader. Shards are sorted by index UID, source ID, and shard ID, and can be paginated.
+        pub async fn list_shards(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListShardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListShardsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/ListShards",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "ListShards",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the number of open shards of a source, the number of open shards the ingest controller targets given the
+        /// current ingestion rate of the source and its scaling thresholds, and the number of open shards it would target at
+        /// hypothetical ingestion rates. This API is meant for capacity planning.
+        pub async fn project_source_shards(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ProjectSourceShardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ProjectSourceShardsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/ProjectSourceShards",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "ProjectSourceShards",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Closes a set of shards regardless of their state: the shards are marked as closed in the metastore and in the
+        /// model of the control plane, then their leaders are asked to close them on a best-effort basis. This API is meant
+        /// for shards stuck in the open state, for instance because their leader lost its WAL.
+        pub async fn force_close_shards(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ForceCloseShardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ForceCloseShardsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/ForceCloseShards",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "ForceCloseShards",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Pauses the ingestion of a source: the control plane closes the open shards of the source and refuses to open new
+        /// ones, and the indexing scheduler stops scheduling its pipelines. The paused state lives in the model of the control
+        /// plane and is not persisted in the metastore. This API is meant for planned maintenance of downstream storage.
+        pub async fn pause_source(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PauseSourceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PauseSourceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/PauseSource",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "PauseSource",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Resumes the ingestion of a paused source.
+        pub async fn resume_source(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ResumeSourceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResumeSourceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/ResumeSource",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "ResumeSource",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod control_plane_service_grpc_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ControlPlaneServiceGrpcServer.
+    #[async_trait]
+    pub trait ControlPlaneServiceGrpc: Send + Sync + 'static {
+        /// Creates a new index.
+        async fn create_index(
+            &self,
+            request: tonic::Request<super::super::metastore::CreateIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::CreateIndexResponse>,
+            tonic::Status,
+        >;
+        /// Updates an index.
+        async fn update_index(
+            &self,
+            request: tonic::Request<super::super::metastore::UpdateIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::IndexMetadataResponse>,
+            tonic::Status,
+        >;
+        /// Deletes an index.
+        async fn delete_index(
+            &self,
+            request: tonic::Request<super::super::metastore::DeleteIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::EmptyResponse>,
+            tonic::Status,
+        >;
+        /// Adds a source to an index.
+        async fn add_source(
+            &self,
+            request: tonic::Request<super::super::metastore::AddSourceRequest>,
+        ) -> std::result::Result<
             tonic::Response<super::super::metastore::EmptyResponse>,
             tonic::Status,
         >;
@@ -1927,31 +4153,114 @@ pub mod control_plane_service_grpc_server {
             tonic::Response<super::AdviseResetShardsResponse>,
             tonic::Status,
         >;
-    }
-    #[derive(Debug)]
-    pub struct ControlPlaneServiceGrpcServer<T: ControlPlaneServiceGrpc> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    struct _Inner<T>(Arc<T>);
-    impl<T: ControlPlaneServiceGrpc> ControlPlaneServiceGrpcServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
-        }
-        pub fn with_interceptor<F>(
+        /// Decommissions an ingester. The control plane stops allocating new shards to the ingester, moves its open shards
+        /// to other ingesters, and removes it from the ingester pool once all its shards have been fully published.
+        async fn decommission_ingester(
+            &self,
+            request: tonic::Request<super::DecommissionIngesterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DecommissionIngesterResponse>,
+            tonic::Status,
+        >;
+        /// Moves an open shard to the target ingester. A new shard is opened on the target ingester, then the original shard
+        /// is closed.
+        async fn move_shard(
+            &self,
+            request: tonic::Request<super::MoveShardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MoveShardResponse>,
+            tonic::Status,
+        >;
+        /// Returns the shard lifecycle events recorded by the control plane (shards opened, closed, moved, marked as
+        /// unavailable, or deleted), oldest first. This API is meant for debugging.
+        async fn get_shard_events(
+            &self,
+            request: tonic::Request<super::GetShardEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetShardEventsResponse>,
+            tonic::Status,
+        >;
+        /// Computes the shards that the rebalancer would move and the resulting distribution of the shard replicas across
+        /// ingesters without opening or closing any shard. This API is meant for debugging.
+        async fn rebalance_shards_dry_run(
+            &self,
+            request: tonic::Request<super::RebalanceShardsDryRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RebalanceShardsDryRunResponse>,
+            tonic::Status,
+        >;
+        /// Returns the shards of the shard table of the control plane with their ingestion rate, optionally filtered by index,
+        /// source, state, and leader. Shards are sorted by index UID, source ID, and shard ID, and can be paginated.
+        async fn list_shards(
+            &self,
+            request: tonic::Request<super::ListShardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListShardsResponse>,
+            tonic::Status,
+        >;
+        /// Returns the number of open shards of a source, the number of open shards the ingest controller targets given the
+        /// current ingestion rate of the source and its scaling thresholds, and the number of open shards it would target at
+        /// hypothetical ingestion rates. This API is meant for capacity planning.
+        async fn project_source_shards(
+            &self,
+            request: tonic::Request<super::ProjectSourceShardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ProjectSourceShardsResponse>,
+            tonic::Status,
+        >;
+        /// Closes a set of shards regardless of their state: the shards are marked as closed in the metastore and in the
+        /// model of the control plane, then their leaders are asked to close them on a best-effort basis. This API is meant
+        /// for shards stuck in the open state, for instance because their leader lost its WAL.
+        async fn force_close_shards(
+            &self,
+            request: tonic::Request<super::ForceCloseShardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ForceCloseShardsResponse>,
+            tonic::Status,
+        >;
+        /// Pauses the ingestion of a source: the control plane closes the open shards of the source and refuses to open new
+        /// ones, and the indexing scheduler stops scheduling its pipelines. The paused state lives in the model of the control
+        /// plane and is not persisted in the metastore. This API is meant for planned maintenance of downstream storage.
+        async fn pause_source(
+            &self,
+            request: tonic::Request<super::PauseSourceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PauseSourceResponse>,
+            tonic::Status,
+        >;
+        /// Resumes the ingestion of a paused source.
+        async fn resume_source(
+            &self,
+            request: tonic::Request<super::ResumeSourceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResumeSourceResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct ControlPlaneServiceGrpcServer<T: ControlPlaneServiceGrpc> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: ControlPlaneServiceGrpc> ControlPlaneServiceGrpcServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
             inner: T,
             interceptor: F,
         ) -> InterceptedService<Self, F>
@@ -2010,26 +4319,317 @@ pub mod control_plane_service_grpc_server {
             match req.uri().path() {
                 "/quickwit.control_plane.ControlPlaneService/CreateIndex" => {
                     #[allow(non_camel_case_types)]
-                    struct CreateIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    struct CreateIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::CreateIndexRequest,
+                    > for CreateIndexSvc<T> {
+                        type Response = super::super::metastore::CreateIndexResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::CreateIndexRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).create_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/UpdateIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::UpdateIndexRequest,
+                    > for UpdateIndexSvc<T> {
+                        type Response = super::super::metastore::IndexMetadataResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::UpdateIndexRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/DeleteIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::DeleteIndexRequest,
+                    > for DeleteIndexSvc<T> {
+                        type Response = super::super::metastore::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::DeleteIndexRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).delete_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/AddSource" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::AddSourceRequest,
+                    > for AddSourceSvc<T> {
+                        type Response = super::super::metastore::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::AddSourceRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).add_source(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddSourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/ToggleSource" => {
+                    #[allow(non_camel_case_types)]
+                    struct ToggleSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::ToggleSourceRequest,
+                    > for ToggleSourceSvc<T> {
+                        type Response = super::super::metastore::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::ToggleSourceRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).toggle_source(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ToggleSourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/DeleteSource" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::DeleteSourceRequest,
+                    > for DeleteSourceSvc<T> {
+                        type Response = super::super::metastore::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::DeleteSourceRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).delete_source(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteSourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/GetOrCreateOpenShards" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetOrCreateOpenShardsSvc<T: ControlPlaneServiceGrpc>(
+                        pub Arc<T>,
+                    );
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<
-                        super::super::metastore::CreateIndexRequest,
-                    > for CreateIndexSvc<T> {
-                        type Response = super::super::metastore::CreateIndexResponse;
+                    > tonic::server::UnaryService<super::GetOrCreateOpenShardsRequest>
+                    for GetOrCreateOpenShardsSvc<T> {
+                        type Response = super::GetOrCreateOpenShardsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<
-                                super::super::metastore::CreateIndexRequest,
-                            >,
+                            request: tonic::Request<super::GetOrCreateOpenShardsRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).create_index(request).await
+                                (*inner).get_or_create_open_shards(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2041,7 +4641,7 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CreateIndexSvc(inner);
+                        let method = GetOrCreateOpenShardsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -2057,28 +4657,25 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
-                "/quickwit.control_plane.ControlPlaneService/DeleteIndex" => {
+                "/quickwit.control_plane.ControlPlaneService/AdviseResetShards" => {
                     #[allow(non_camel_case_types)]
-                    struct DeleteIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    struct AdviseResetShardsSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<
-                        super::super::metastore::DeleteIndexRequest,
-                    > for DeleteIndexSvc<T> {
-                        type Response = super::super::metastore::EmptyResponse;
+                    > tonic::server::UnaryService<super::AdviseResetShardsRequest>
+                    for AdviseResetShardsSvc<T> {
+                        type Response = super::AdviseResetShardsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<
-                                super::super::metastore::DeleteIndexRequest,
-                            >,
+                            request: tonic::Request<super::AdviseResetShardsRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).delete_index(request).await
+                                (*inner).advise_reset_shards(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2090,7 +4687,7 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = DeleteIndexSvc(inner);
+                        let method = AdviseResetShardsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -2106,27 +4703,26 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
-                "/quickwit.control_plane.ControlPlaneService/AddSource" => {
+                "/quickwit.control_plane.ControlPlaneService/DecommissionIngester" => {
                     #[allow(non_camel_case_types)]
-                    struct AddSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    struct DecommissionIngesterSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<
-                        super::super::metastore::AddSourceRequest,
-                    > for AddSourceSvc<T> {
-                        type Response = super::super::metastore::EmptyResponse;
+                    > tonic::server::UnaryService<super::DecommissionIngesterRequest>
+                    for DecommissionIngesterSvc<T> {
+                        type Response = super::DecommissionIngesterResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<
-                                super::super::metastore::AddSourceRequest,
-                            >,
+                            request: tonic::Request<super::DecommissionIngesterRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move { (*inner).add_source(request).await };
+                            let fut = async move {
+                                (*inner).decommission_ingester(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -2137,7 +4733,7 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AddSourceSvc(inner);
+                        let method = DecommissionIngesterSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -2153,28 +4749,25 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
-                "/quickwit.control_plane.ControlPlaneService/ToggleSource" => {
+                "/quickwit.control_plane.ControlPlaneService/MoveShard" => {
                     #[allow(non_camel_case_types)]
-                    struct ToggleSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    struct MoveShardSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<
-                        super::super::metastore::ToggleSourceRequest,
-                    > for ToggleSourceSvc<T> {
-                        type Response = super::super::metastore::EmptyResponse;
+                    > tonic::server::UnaryService<super::MoveShardRequest>
+                    for MoveShardSvc<T> {
+                        type Response = super::MoveShardResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<
-                                super::super::metastore::ToggleSourceRequest,
-                            >,
+                            request: tonic::Request<super::MoveShardRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).toggle_source(request).await
+                                (*inner).move_shard(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2186,7 +4779,7 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = ToggleSourceSvc(inner);
+                        let method = MoveShardSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -2202,28 +4795,25 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
-                "/quickwit.control_plane.ControlPlaneService/DeleteSource" => {
+                "/quickwit.control_plane.ControlPlaneService/GetShardEvents" => {
                     #[allow(non_camel_case_types)]
-                    struct DeleteSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    struct GetShardEventsSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<
-                        super::super::metastore::DeleteSourceRequest,
-                    > for DeleteSourceSvc<T> {
-                        type Response = super::super::metastore::EmptyResponse;
+                    > tonic::server::UnaryService<super::GetShardEventsRequest>
+                    for GetShardEventsSvc<T> {
+                        type Response = super::GetShardEventsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<
-                                super::super::metastore::DeleteSourceRequest,
-                            >,
+                            request: tonic::Request<super::GetShardEventsRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).delete_source(request).await
+                                (*inner).get_shard_events(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2235,7 +4825,7 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = DeleteSourceSvc(inner);
+                        let method = GetShardEventsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -2251,27 +4841,25 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
-                "/quickwit.control_plane.ControlPlaneService/GetOrCreateOpenShards" => {
+                "/quickwit.control_plane.ControlPlaneService/RebalanceShardsDryRun" => {
                     #[allow(non_camel_case_types)]
-                    struct GetOrCreateOpenShardsSvc<T: ControlPlaneServiceGrpc>(
-                        pub Arc<T>,
-                    );
+                    struct RebalanceShardsDryRunSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<super::GetOrCreateOpenShardsRequest>
-                    for GetOrCreateOpenShardsSvc<T> {
-                        type Response = super::GetOrCreateOpenShardsResponse;
+                    > tonic::server::UnaryService<super::RebalanceShardsDryRunRequest>
+                    for RebalanceShardsDryRunSvc<T> {
+                        type Response = super::RebalanceShardsDryRunResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetOrCreateOpenShardsRequest>,
+                            request: tonic::Request<super::RebalanceShardsDryRunRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).get_or_create_open_shards(request).await
+                                (*inner).rebalance_shards_dry_run(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2283,7 +4871,7 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetOrCreateOpenShardsSvc(inner);
+                        let method = RebalanceShardsDryRunSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -2299,25 +4887,25 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
-                "/quickwit.control_plane.ControlPlaneService/AdviseResetShards" => {
+                "/quickwit.control_plane.ControlPlaneService/ListShards" => {
                     #[allow(non_camel_case_types)]
-                    struct AdviseResetShardsSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    struct ListShardsSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
                     impl<
                         T: ControlPlaneServiceGrpc,
-                    > tonic::server::UnaryService<super::AdviseResetShardsRequest>
-                    for AdviseResetShardsSvc<T> {
-                        type Response = super::AdviseResetShardsResponse;
+                    > tonic::server::UnaryService<super::ListShardsRequest>
+                    for ListShardsSvc<T> {
+                        type Response = super::ListShardsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::AdviseResetShardsRequest>,
+                            request: tonic::Request<super::ListShardsRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).advise_reset_shards(request).await
+                                (*inner).list_shards(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2329,7 +4917,191 @@ pub mod control_plane_service_grpc_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AdviseResetShardsSvc(inner);
+                        let method = ListShardsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/ProjectSourceShards" => {
+                    #[allow(non_camel_case_types)]
+                    struct ProjectSourceShardsSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<super::ProjectSourceShardsRequest>
+                    for ProjectSourceShardsSvc<T> {
+                        type Response = super::ProjectSourceShardsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ProjectSourceShardsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).project_source_shards(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ProjectSourceShardsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/ForceCloseShards" => {
+                    #[allow(non_camel_case_types)]
+                    struct ForceCloseShardsSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<super::ForceCloseShardsRequest>
+                    for ForceCloseShardsSvc<T> {
+                        type Response = super::ForceCloseShardsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ForceCloseShardsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).force_close_shards(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ForceCloseShardsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/PauseSource" => {
+                    #[allow(non_camel_case_types)]
+                    struct PauseSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<super::PauseSourceRequest>
+                    for PauseSourceSvc<T> {
+                        type Response = super::PauseSourceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PauseSourceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).pause_source(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PauseSourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/ResumeSource" => {
+                    #[allow(non_camel_case_types)]
+                    struct ResumeSourceSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<super::ResumeSourceRequest>
+                    for ResumeSourceSvc<T> {
+                        type Response = super::ResumeSourceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ResumeSourceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).resume_source(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ResumeSourceSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -15,11 +15,42 @@ pub struct RetainShardsForSource {
 pub struct RetainShardsRequest {
     #[prost(message, repeated, tag = "1")]
     pub retain_shards_for_sources: ::prost::alloc::vec::Vec<RetainShardsForSource>,
+    /// When true, the request only lists the sources whose shards changed since the last sync acknowledged by the
+    /// ingester, and the shards of the other sources are left untouched. A source listed without shard IDs loses all its
+    /// shards.
+    #[prost(bool, tag = "2")]
+    pub incremental: bool,
+    /// For incremental requests, digest of all the shards that the ingester should host once the request is applied.
+    #[prost(uint64, tag = "3")]
+    pub expected_digest: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RetainShardsResponse {
+    /// Set by the ingesters that support incremental requests.
+    #[prost(bool, tag = "1")]
+    pub supports_incremental: bool,
+    /// Set when the digest of the shards hosted by the ingester once an incremental request is applied differs from the
+    /// expected digest.
+    #[prost(bool, tag = "2")]
+    pub digest_mismatch: bool,
+    /// Digests of all the sources hosted by the ingester, reported along with a digest mismatch so that the control plane
+    /// can find out which sources diverged.
+    #[prost(message, repeated, tag = "3")]
+    pub source_digests: ::prost::alloc::vec::Vec<SourceShardsDigest>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct RetainShardsResponse {}
+pub struct SourceShardsDigest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub digest: u64,
+}
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -30,6 +61,9 @@ pub struct PersistRequest {
     pub commit_type: i32,
     #[prost(message, repeated, tag = "4")]
     pub subrequests: ::prost::alloc::vec::Vec<PersistSubrequest>,
+    /// When set, the ingester persists either all the subrequests or none of them.
+    #[prost(bool, tag = "5")]
+    pub atomic: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -45,6 +79,8 @@ pub struct PersistSubrequest {
     pub shard_id: ::core::option::Option<crate::types::ShardId>,
     #[prost(message, optional, tag = "5")]
     pub doc_batch: ::core::option::Option<super::DocBatchV2>,
+    #[prost(string, optional, tag = "6")]
+    pub idempotency_key: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -199,6 +235,17 @@ pub struct ReplicateSubrequest {
     pub from_position_exclusive: ::core::option::Option<crate::types::Position>,
     #[prost(message, optional, tag = "6")]
     pub doc_batch: ::core::option::Option<super::DocBatchV2>,
+    /// Compression codec of the documents of `doc_batch`. The documents are replicated compressed so
+    /// that the follower writes them to its WAL as is.
+    #[prost(enumeration = "super::DocCompression", tag = "7")]
+    pub doc_compression: i32,
+    /// Idempotency key of `doc_batch`, appended to the WAL after the documents like on the leader.
+    #[prost(string, optional, tag = "8")]
+    pub idempotency_key: ::core::option::Option<::prost::alloc::string::String>,
+    /// When set, the follower rolls the replica shard back to this position and closes it instead of
+    /// replicating `doc_batch`. The leader sends it when it aborts an atomic persist request.
+    #[prost(message, optional, tag = "9")]
+    pub rollback_to_position_inclusive: ::core::option::Option<crate::types::Position>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -288,6 +335,29 @@ pub struct OpenFetchStreamRequest {
     pub shard_id: ::core::option::Option<crate::types::ShardId>,
     #[prost(message, optional, tag = "5")]
     pub from_position_exclusive: ::core::option::Option<crate::types::Position>,
+    /// When set, the stream ends after the record at this position is fetched.
+    #[prost(message, optional, tag = "6")]
+    pub to_position_inclusive: ::core::option::Option<crate::types::Position>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchShardRequest {
+    /// Identifies the consumer for logging and debugging purposes.
+    #[prost(string, tag = "1")]
+    pub client_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "3")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    pub shard_id: ::core::option::Option<crate::types::ShardId>,
+    /// Position of the last record acknowledged by the consumer, or `Beginning`.
+    #[prost(message, optional, tag = "5")]
+    pub from_position_exclusive: ::core::option::Option<crate::types::Position>,
+    /// When unset, the stream follows the shard until it is closed.
+    #[prost(message, optional, tag = "6")]
+    pub to_position_inclusive: ::core::option::Option<crate::types::Position>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -412,6 +482,17 @@ pub struct DecommissionResponse {}
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PingRequest {}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PingResponse {
+    #[prost(enumeration = "IngesterStatus", tag = "1")]
+    pub status: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OpenObservationStreamRequest {}
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -433,6 +514,8 @@ pub enum PersistFailureReason {
     RateLimited = 3,
     ResourceExhausted = 4,
     Timeout = 5,
+    /// Another subrequest of the same atomic persist request failed.
+    Aborted = 6,
 }
 impl PersistFailureReason {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -451,6 +534,7 @@ impl PersistFailureReason {
                 "PERSIST_FAILURE_REASON_RESOURCE_EXHAUSTED"
             }
             PersistFailureReason::Timeout => "PERSIST_FAILURE_REASON_TIMEOUT",
+            PersistFailureReason::Aborted => "PERSIST_FAILURE_REASON_ABORTED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -462,6 +546,7 @@ impl PersistFailureReason {
             "PERSIST_FAILURE_REASON_RATE_LIMITED" => Some(Self::RateLimited),
             "PERSIST_FAILURE_REASON_RESOURCE_EXHAUSTED" => Some(Self::ResourceExhausted),
             "PERSIST_FAILURE_REASON_TIMEOUT" => Some(Self::Timeout),
+            "PERSIST_FAILURE_REASON_ABORTED" => Some(Self::Aborted),
             _ => None,
         }
     }
@@ -576,6 +661,11 @@ impl RpcName for OpenFetchStreamRequest {
         "open_fetch_stream"
     }
 }
+impl RpcName for FetchShardRequest {
+    fn rpc_name() -> &'static str {
+        "fetch_shard"
+    }
+}
 impl RpcName for OpenObservationStreamRequest {
     fn rpc_name() -> &'static str {
         "open_observation_stream"
@@ -606,6 +696,11 @@ impl RpcName for DecommissionRequest {
         "decommission"
     }
 }
+impl RpcName for PingRequest {
+    fn rpc_name() -> &'static str {
+        "ping"
+    }
+}
 pub type IngesterServiceStream<T> = quickwit_common::ServiceStream<
     crate::ingest::IngestV2Result<T>,
 >;
@@ -628,6 +723,12 @@ pub trait IngesterService: std::fmt::Debug + dyn_clone::DynClone + Send + Sync +
         &mut self,
         request: OpenFetchStreamRequest,
     ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>>;
+    /// Streams the records of a shard between two positions to an external consumer (change data capture). The
+    /// stream ends once the upper bound or the end of the shard is reached.
+    async fn fetch_shard(
+        &mut self,
+        request: FetchShardRequest,
+    ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>>;
     /// Streams status updates, called "observations", from an ingester.
     async fn open_observation_stream(
         &mut self,
@@ -659,6 +760,12 @@ pub trait IngesterService: std::fmt::Debug + dyn_clone::DynClone + Send + Sync +
         &mut self,
         request: DecommissionRequest,
     ) -> crate::ingest::IngestV2Result<DecommissionResponse>;
+    /// Pings the ingester to check that it is alive and reports its status.
+    /// This RPC is called by the control plane health prober.
+    async fn ping(
+        &mut self,
+        request: PingRequest,
+    ) -> crate::ingest::IngestV2Result<PingResponse>;
 }
 dyn_clone::clone_trait_object!(IngesterService);
 #[cfg(any(test, feature = "testsuite"))]
@@ -772,6 +879,12 @@ impl IngesterService for IngesterServiceClient {
     ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
         self.inner.open_fetch_stream(request).await
     }
+    async fn fetch_shard(
+        &mut self,
+        request: FetchShardRequest,
+    ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
+        self.inner.fetch_shard(request).await
+    }
     async fn open_observation_stream(
         &mut self,
         request: OpenObservationStreamRequest,
@@ -808,6 +921,12 @@ impl IngesterService for IngesterServiceClient {
     ) -> crate::ingest::IngestV2Result<DecommissionResponse> {
         self.inner.decommission(request).await
     }
+    async fn ping(
+        &mut self,
+        request: PingRequest,
+    ) -> crate::ingest::IngestV2Result<PingResponse> {
+        self.inner.ping(request).await
+    }
 }
 #[cfg(any(test, feature = "testsuite"))]
 pub mod mock_ingester_service {
@@ -838,6 +957,12 @@ pub mod mock_ingester_service {
         ) -> crate::ingest::IngestV2Result<IngesterServiceStream<super::FetchMessage>> {
             self.inner.lock().await.open_fetch_stream(request).await
         }
+        async fn fetch_shard(
+            &mut self,
+            request: super::FetchShardRequest,
+        ) -> crate::ingest::IngestV2Result<IngesterServiceStream<super::FetchMessage>> {
+            self.inner.lock().await.fetch_shard(request).await
+        }
         async fn open_observation_stream(
             &mut self,
             request: super::OpenObservationStreamRequest,
@@ -876,6 +1001,12 @@ pub mod mock_ingester_service {
         ) -> crate::ingest::IngestV2Result<super::DecommissionResponse> {
             self.inner.lock().await.decommission(request).await
         }
+        async fn ping(
+            &mut self,
+            request: super::PingRequest,
+        ) -> crate::ingest::IngestV2Result<super::PingResponse> {
+            self.inner.lock().await.ping(request).await
+        }
     }
 }
 pub type BoxFuture<T, E> = std::pin::Pin<
@@ -933,6 +1064,22 @@ impl tower::Service<OpenFetchStreamRequest> for Box<dyn IngesterService> {
         Box::pin(fut)
     }
 }
+impl tower::Service<FetchShardRequest> for Box<dyn IngesterService> {
+    type Response = IngesterServiceStream<FetchMessage>;
+    type Error = crate::ingest::IngestV2Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: FetchShardRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.fetch_shard(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<OpenObservationStreamRequest> for Box<dyn IngesterService> {
     type Response = IngesterServiceStream<ObservationMessage>;
     type Error = crate::ingest::IngestV2Error;
@@ -1029,6 +1176,22 @@ impl tower::Service<DecommissionRequest> for Box<dyn IngesterService> {
         Box::pin(fut)
     }
 }
+impl tower::Service<PingRequest> for Box<dyn IngesterService> {
+    type Response = PingResponse;
+    type Error = crate::ingest::IngestV2Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: PingRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.ping(request).await };
+        Box::pin(fut)
+    }
+}
 /// A tower service stack is a set of tower services.
 #[derive(Debug)]
 struct IngesterServiceTowerServiceStack {
@@ -1048,6 +1211,11 @@ struct IngesterServiceTowerServiceStack {
         IngesterServiceStream<FetchMessage>,
         crate::ingest::IngestV2Error,
     >,
+    fetch_shard_svc: quickwit_common::tower::BoxService<
+        FetchShardRequest,
+        IngesterServiceStream<FetchMessage>,
+        crate::ingest::IngestV2Error,
+    >,
     open_observation_stream_svc: quickwit_common::tower::BoxService<
         OpenObservationStreamRequest,
         IngesterServiceStream<ObservationMessage>,
@@ -1078,6 +1246,11 @@ struct IngesterServiceTowerServiceStack {
         DecommissionResponse,
         crate::ingest::IngestV2Error,
     >,
+    ping_svc: quickwit_common::tower::BoxService<
+        PingRequest,
+        PingResponse,
+        crate::ingest::IngestV2Error,
+    >,
 }
 impl Clone for IngesterServiceTowerServiceStack {
     fn clone(&self) -> Self {
@@ -1086,12 +1259,14 @@ impl Clone for IngesterServiceTowerServiceStack {
             persist_svc: self.persist_svc.clone(),
             open_replication_stream_svc: self.open_replication_stream_svc.clone(),
             open_fetch_stream_svc: self.open_fetch_stream_svc.clone(),
+            fetch_shard_svc: self.fetch_shard_svc.clone(),
             open_observation_stream_svc: self.open_observation_stream_svc.clone(),
             init_shards_svc: self.init_shards_svc.clone(),
             retain_shards_svc: self.retain_shards_svc.clone(),
             truncate_shards_svc: self.truncate_shards_svc.clone(),
             close_shards_svc: self.close_shards_svc.clone(),
             decommission_svc: self.decommission_svc.clone(),
+            ping_svc: self.ping_svc.clone(),
         }
     }
 }
@@ -1115,6 +1290,12 @@ impl IngesterService for IngesterServiceTowerServiceStack {
     ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
         self.open_fetch_stream_svc.ready().await?.call(request).await
     }
+    async fn fetch_shard(
+        &mut self,
+        request: FetchShardRequest,
+    ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
+        self.fetch_shard_svc.ready().await?.call(request).await
+    }
     async fn open_observation_stream(
         &mut self,
         request: OpenObservationStreamRequest,
@@ -1151,6 +1332,12 @@ impl IngesterService for IngesterServiceTowerServiceStack {
     ) -> crate::ingest::IngestV2Result<DecommissionResponse> {
         self.decommission_svc.ready().await?.call(request).await
     }
+    async fn ping(
+        &mut self,
+        request: PingRequest,
+    ) -> crate::ingest::IngestV2Result<PingResponse> {
+        self.ping_svc.ready().await?.call(request).await
+    }
 }
 type PersistLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
@@ -1182,6 +1369,16 @@ type OpenFetchStreamLayer = quickwit_common::tower::BoxLayer<
     IngesterServiceStream<FetchMessage>,
     crate::ingest::IngestV2Error,
 >;
+type FetchShardLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        FetchShardRequest,
+        IngesterServiceStream<FetchMessage>,
+        crate::ingest::IngestV2Error,
+    >,
+    FetchShardRequest,
+    IngesterServiceStream<FetchMessage>,
+    crate::ingest::IngestV2Error,
+>;
 type OpenObservationStreamLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         OpenObservationStreamRequest,
@@ -1242,17 +1439,29 @@ type DecommissionLayer = quickwit_common::tower::BoxLayer<
     DecommissionResponse,
     crate::ingest::IngestV2Error,
 >;
+type PingLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        PingRequest,
+        PingResponse,
+        crate::ingest::IngestV2Error,
+    >,
+    PingRequest,
+    PingResponse,
+    crate::ingest::IngestV2Error,
+>;
 #[derive(Debug, Default)]
 pub struct IngesterServiceTowerLayerStack {
     persist_layers: Vec<PersistLayer>,
     open_replication_stream_layers: Vec<OpenReplicationStreamLayer>,
     open_fetch_stream_layers: Vec<OpenFetchStreamLayer>,
+    fetch_shard_layers: Vec<FetchShardLayer>,
     open_observation_stream_layers: Vec<OpenObservationStreamLayer>,
     init_shards_layers: Vec<InitShardsLayer>,
     retain_shards_layers: Vec<RetainShardsLayer>,
     truncate_shards_layers: Vec<TruncateShardsLayer>,
     close_shards_layers: Vec<CloseShardsLayer>,
     decommission_layers: Vec<DecommissionLayer>,
+    ping_layers: Vec<PingLayer>,
 }
 impl IngesterServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -1334,6 +1543,31 @@ impl IngesterServiceTowerLayerStack {
                 crate::ingest::IngestV2Error,
             >,
         >>::Service as tower::Service<OpenFetchStreamRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    FetchShardRequest,
+                    IngesterServiceStream<FetchMessage>,
+                    crate::ingest::IngestV2Error,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                FetchShardRequest,
+                IngesterServiceStream<FetchMessage>,
+                crate::ingest::IngestV2Error,
+            >,
+        >>::Service: tower::Service<
+                FetchShardRequest,
+                Response = IngesterServiceStream<FetchMessage>,
+                Error = crate::ingest::IngestV2Error,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                FetchShardRequest,
+                IngesterServiceStream<FetchMessage>,
+                crate::ingest::IngestV2Error,
+            >,
+        >>::Service as tower::Service<FetchShardRequest>>::Future: Send + 'static,
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
                     OpenObservationStreamRequest,
@@ -1486,12 +1720,39 @@ impl IngesterServiceTowerLayerStack {
                 crate::ingest::IngestV2Error,
             >,
         >>::Service as tower::Service<DecommissionRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    PingRequest,
+                    PingResponse,
+                    crate::ingest::IngestV2Error,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                PingRequest,
+                PingResponse,
+                crate::ingest::IngestV2Error,
+            >,
+        >>::Service: tower::Service<
+                PingRequest,
+                Response = PingResponse,
+                Error = crate::ingest::IngestV2Error,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                PingRequest,
+                PingResponse,
+                crate::ingest::IngestV2Error,
+            >,
+        >>::Service as tower::Service<PingRequest>>::Future: Send + 'static,
     {
         self.persist_layers.push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.open_replication_stream_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.open_fetch_stream_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.fetch_shard_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.open_observation_stream_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.init_shards_layers
@@ -1504,6 +1765,8 @@ impl IngesterServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.decommission_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.ping_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self
     }
     pub fn stack_persist_layer<L>(mut self, layer: L) -> Self
@@ -1566,6 +1829,25 @@ impl IngesterServiceTowerLayerStack {
         self.open_fetch_stream_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_fetch_shard_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    FetchShardRequest,
+                    IngesterServiceStream<FetchMessage>,
+                    crate::ingest::IngestV2Error,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                FetchShardRequest,
+                Response = IngesterServiceStream<FetchMessage>,
+                Error = crate::ingest::IngestV2Error,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<FetchShardRequest>>::Future: Send + 'static,
+    {
+        self.fetch_shard_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_open_observation_stream_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -1683,6 +1965,25 @@ impl IngesterServiceTowerLayerStack {
         self.decommission_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_ping_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    PingRequest,
+                    PingResponse,
+                    crate::ingest::IngestV2Error,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                PingRequest,
+                Response = PingResponse,
+                Error = crate::ingest::IngestV2Error,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<PingRequest>>::Future: Send + 'static,
+    {
+        self.ping_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn build<T>(self, instance: T) -> IngesterServiceClient
     where
         T: IngesterService,
@@ -1757,6 +2058,14 @@ impl IngesterServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let fetch_shard_svc = self
+            .fetch_shard_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let open_observation_stream_svc = self
             .open_observation_stream_layers
             .into_iter()
@@ -1805,17 +2114,27 @@ impl IngesterServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let ping_svc = self
+            .ping_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = IngesterServiceTowerServiceStack {
             inner: boxed_instance.clone(),
             persist_svc,
             open_replication_stream_svc,
             open_fetch_stream_svc,
+            fetch_shard_svc,
             open_observation_stream_svc,
             init_shards_svc,
             retain_shards_svc,
             truncate_shards_svc,
             close_shards_svc,
             decommission_svc,
+            ping_svc,
         };
         IngesterServiceClient::new(tower_svc_stack)
     }
@@ -1916,6 +2235,15 @@ where
                 crate::ingest::IngestV2Error,
             >,
         >
+        + tower::Service<
+            FetchShardRequest,
+            Response = IngesterServiceStream<FetchMessage>,
+            Error = crate::ingest::IngestV2Error,
+            Future = BoxFuture<
+                IngesterServiceStream<FetchMessage>,
+                crate::ingest::IngestV2Error,
+            >,
+        >
         + tower::Service<
             OpenObservationStreamRequest,
             Response = IngesterServiceStream<ObservationMessage>,
@@ -1954,6 +2282,12 @@ where
             Response = DecommissionResponse,
             Error = crate::ingest::IngestV2Error,
             Future = BoxFuture<DecommissionResponse, crate::ingest::IngestV2Error>,
+        >
+        + tower::Service<
+            PingRequest,
+            Response = PingResponse,
+            Error = crate::ingest::IngestV2Error,
+            Future = BoxFuture<PingResponse, crate::ingest::IngestV2Error>,
         >,
 {
     async fn persist(
@@ -1974,6 +2308,12 @@ where
     ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
         self.call(request).await
     }
+    async fn fetch_shard(
+        &mut self,
+        request: FetchShardRequest,
+    ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
+        self.call(request).await
+    }
     async fn open_observation_stream(
         &mut self,
         request: OpenObservationStreamRequest,
@@ -2010,6 +2350,12 @@ where
     ) -> crate::ingest::IngestV2Result<DecommissionResponse> {
         self.call(request).await
     }
+    async fn ping(
+        &mut self,
+        request: PingRequest,
+    ) -> crate::ingest::IngestV2Result<PingResponse> {
+        self.call(request).await
+    }
 }
 #[derive(Debug, Clone)]
 pub struct IngesterServiceGrpcClientAdapter<T> {
@@ -2100,6 +2446,27 @@ where
                 OpenFetchStreamRequest::rpc_name(),
             ))
     }
+    async fn fetch_shard(
+        &mut self,
+        request: FetchShardRequest,
+    ) -> crate::ingest::IngestV2Result<IngesterServiceStream<FetchMessage>> {
+        self.inner
+            .fetch_shard(request)
+            .await
+            .map(|response| {
+                let streaming: tonic::Streaming<_> = response.into_inner();
+                let stream = quickwit_common::ServiceStream::from(streaming);
+                stream
+                    .map_err(|status| crate::error::grpc_status_to_service_error(
+                        status,
+                        FetchShardRequest::rpc_name(),
+                    ))
+            })
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                FetchShardRequest::rpc_name(),
+            ))
+    }
     async fn open_observation_stream(
         &mut self,
         request: OpenObservationStreamRequest,
@@ -2186,6 +2553,19 @@ where
                 DecommissionRequest::rpc_name(),
             ))
     }
+    async fn ping(
+        &mut self,
+        request: PingRequest,
+    ) -> crate::ingest::IngestV2Result<PingResponse> {
+        self.inner
+            .ping(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                PingRequest::rpc_name(),
+            ))
+    }
 }
 #[derive(Debug)]
 pub struct IngesterServiceGrpcServerAdapter {
@@ -2248,6 +2628,22 @@ for IngesterServiceGrpcServerAdapter {
             ))
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    type FetchShardStream = quickwit_common::ServiceStream<
+        tonic::Result<FetchMessage>,
+    >;
+    async fn fetch_shard(
+        &self,
+        request: tonic::Request<FetchShardRequest>,
+    ) -> Result<tonic::Response<Self::FetchShardStream>, tonic::Status> {
+        self.inner
+            .clone()
+            .fetch_shard(request.into_inner())
+            .await
+            .map(|stream| tonic::Response::new(
+                stream.map_err(crate::error::grpc_error_to_grpc_status),
+            ))
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
     type OpenObservationStreamStream = quickwit_common::ServiceStream<
         tonic::Result<ObservationMessage>,
     >;
@@ -2319,6 +2715,17 @@ for IngesterServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn ping(
+        &self,
+        request: tonic::Request<PingRequest>,
+    ) -> Result<tonic::Response<PingResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .ping(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
 }
 /// Generated client implementations.
 pub mod ingester_service_grpc_client {
@@ -2501,6 +2908,38 @@ pub mod ingester_service_grpc_client {
                 );
             self.inner.server_streaming(req, path, codec).await
         }
+        /// Streams the records of a shard between two positions to an external consumer (change data capture). The
+        /// stream ends once the upper bound or the end of the shard is reached.
+        pub async fn fetch_shard(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FetchShardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::FetchMessage>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.ingest.ingester.IngesterService/FetchShard",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.ingest.ingester.IngesterService",
+                        "FetchShard",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
         /// Streams status updates, called "observations", from an ingester.
         pub async fn open_observation_stream(
             &mut self,
@@ -2688,6 +3127,38 @@ pub mod ingester_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Pings the ingester to check that it is alive and reports its status.
+        /// This RPC is called by the control plane health prober.
+        pub async fn ping(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PingRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PingResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.ingest.ingester.IngesterService/Ping",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.ingest.ingester.IngesterService",
+                        "Ping",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -2731,6 +3202,21 @@ pub mod ingester_service_grpc_server {
             tonic::Response<Self::OpenFetchStreamStream>,
             tonic::Status,
         >;
+        /// Server streaming response type for the FetchShard method.
+        type FetchShardStream: futures_core::Stream<
+                Item = std::result::Result<super::FetchMessage, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Streams the records of a shard between two positions to an external consumer (change data capture). The
+        /// stream ends once the upper bound or the end of the shard is reached.
+        async fn fetch_shard(
+            &self,
+            request: tonic::Request<super::FetchShardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::FetchShardStream>,
+            tonic::Status,
+        >;
         /// Server streaming response type for the OpenObservationStream method.
         type OpenObservationStreamStream: futures_core::Stream<
                 Item = std::result::Result<super::ObservationMessage, tonic::Status>,
@@ -2786,6 +3272,15 @@ pub mod ingester_service_grpc_server {
             tonic::Response<super::DecommissionResponse>,
             tonic::Status,
         >;
+        /// Pings the ingester to check that it is alive and reports its status.
+        /// This RPC is called by the control plane health prober.
+        async fn ping(
+            &self,
+            request: tonic::Request<super::PingRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PingResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct IngesterServiceGrpcServer<T: IngesterServiceGrpc> {
@@ -3007,6 +3502,54 @@ pub mod ingester_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.ingest.ingester.IngesterService/FetchShard" => {
+                    #[allow(non_camel_case_types)]
+                    struct FetchShardSvc<T: IngesterServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: IngesterServiceGrpc,
+                    > tonic::server::ServerStreamingService<
+                        super::FetchShardRequest,
+                    > for FetchShardSvc<T> {
+                        type Response = super::FetchMessage;
+                        type ResponseStream = T::FetchShardStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FetchShardRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).fetch_shard(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = FetchShardSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.ingest.ingester.IngesterService/OpenObservationStream" => {
                     #[allow(non_camel_case_types)]
                     struct OpenObservationStreamSvc<T: IngesterServiceGrpc>(pub Arc<T>);
@@ -3283,6 +3826,52 @@ pub mod ingester_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.ingest.ingester.IngesterService/Ping" => {
+                    #[allow(non_camel_case_types)]
+                    struct PingSvc<T: IngesterServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: IngesterServiceGrpc,
+                    > tonic::server::UnaryService<super::PingRequest>
+                    for PingSvc<T> {
+                        type Response = super::PingResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PingRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).ping(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PingSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
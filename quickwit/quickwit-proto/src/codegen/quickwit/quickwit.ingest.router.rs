@@ -6,6 +6,13 @@ pub struct IngestRequestV2 {
     pub subrequests: ::prost::alloc::vec::Vec<IngestSubrequest>,
     #[prost(enumeration = "super::CommitTypeV2", tag = "2")]
     pub commit_type: i32,
+    /// When set, the router sends all the subrequests to the same leader, which persists all of them
+    /// or none of them and rolls back and closes the shards of an aborted request. The atomicity is
+    /// best effort with replication: a replica that cannot be reached during the rollback, or that
+    /// received part of the request before its leader failed, keeps the records it received, and
+    /// they may be indexed if the shard is later read from that replica.
+    #[prost(bool, tag = "3")]
+    pub best_effort_atomic: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -22,6 +29,15 @@ pub struct IngestSubrequest {
     pub source_id: ::prost::alloc::string::String,
     #[prost(message, optional, tag = "4")]
     pub doc_batch: ::core::option::Option<super::DocBatchV2>,
+    /// If set, the subrequests sharing the same routing key are routed to the same shard of the source, as long as
+    /// the set of open shards of the source does not change.
+    #[prost(string, optional, tag = "5")]
+    pub routing_key: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, replays of a subrequest with the same idempotency key are acknowledged without
+    /// re-appending the documents, as long as the key is still in the dedup window of the shard.
+    /// Subrequests without a routing key are routed by idempotency key.
+    #[prost(string, optional, tag = "6")]
+    pub idempotency_key: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -74,6 +90,10 @@ pub enum IngestFailureReason {
     RateLimited = 5,
     ResourceExhausted = 6,
     Timeout = 7,
+    QuotaExceeded = 8,
+    SourcePaused = 9,
+    /// Another subrequest of the same atomic ingest request failed.
+    Aborted = 10,
 }
 impl IngestFailureReason {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -96,6 +116,9 @@ impl IngestFailureReason {
                 "INGEST_FAILURE_REASON_RESOURCE_EXHAUSTED"
             }
             IngestFailureReason::Timeout => "INGEST_FAILURE_REASON_TIMEOUT",
+            IngestFailureReason::QuotaExceeded => "INGEST_FAILURE_REASON_QUOTA_EXCEEDED",
+            IngestFailureReason::SourcePaused => "INGEST_FAILURE_REASON_SOURCE_PAUSED",
+            IngestFailureReason::Aborted => "INGEST_FAILURE_REASON_ABORTED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -109,6 +132,9 @@ impl IngestFailureReason {
             "INGEST_FAILURE_REASON_RATE_LIMITED" => Some(Self::RateLimited),
             "INGEST_FAILURE_REASON_RESOURCE_EXHAUSTED" => Some(Self::ResourceExhausted),
             "INGEST_FAILURE_REASON_TIMEOUT" => Some(Self::Timeout),
+            "INGEST_FAILURE_REASON_QUOTA_EXCEEDED" => Some(Self::QuotaExceeded),
+            "INGEST_FAILURE_REASON_SOURCE_PAUSED" => Some(Self::SourcePaused),
+            "INGEST_FAILURE_REASON_ABORTED" => Some(Self::Aborted),
             _ => None,
         }
     }
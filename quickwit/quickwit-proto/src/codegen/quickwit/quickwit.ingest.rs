@@ -128,6 +128,39 @@ impl CommitTypeV2 {
         }
     }
 }
+/// Compression codec applied to the documents of a batch before they are written to the WAL.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum DocCompression {
+    /// The documents are not compressed.
+    Unspecified = 0,
+    Lz4 = 1,
+    Zstd = 2,
+}
+impl DocCompression {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            DocCompression::Unspecified => "DOC_COMPRESSION_UNSPECIFIED",
+            DocCompression::Lz4 => "DOC_COMPRESSION_LZ4",
+            DocCompression::Zstd => "DOC_COMPRESSION_ZSTD",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "DOC_COMPRESSION_UNSPECIFIED" => Some(Self::Unspecified),
+            "DOC_COMPRESSION_LZ4" => Some(Self::Lz4),
+            "DOC_COMPRESSION_ZSTD" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -30,6 +30,16 @@ pub struct UpdateIndexRequest {
     pub search_settings_json: ::prost::alloc::string::String,
     #[prost(string, optional, tag = "3")]
     pub retention_policy_json: ::core::option::Option<::prost::alloc::string::String>,
+    /// Legal hold placed on the index. The current legal hold is left unchanged when unset.
+    #[prost(string, optional, tag = "4")]
+    pub legal_hold_json: ::core::option::Option<::prost::alloc::string::String>,
+    /// Updated doc mapping of the index. Only the `fast` option of existing fields can be enabled. The doc mapping
+    /// is left unchanged when unset.
+    #[prost(string, optional, tag = "5")]
+    pub doc_mapping_json: ::core::option::Option<::prost::alloc::string::String>,
+    /// Lifts the current legal hold of the index. Cannot be set along with `legal_hold_json`.
+    #[prost(bool, tag = "6")]
+    pub lift_legal_hold: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -448,6 +458,38 @@ pub struct DeleteIndexTemplatesRequest {
     pub template_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateClusterSettingsRequest {
+    #[prost(string, tag = "1")]
+    pub cluster_settings_json: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetClusterSettingsRequest {}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClusterSettingsResponse {
+    #[prost(string, tag = "1")]
+    pub cluster_settings_json: ::prost::alloc::string::String,
+    /// The version is incremented on every update. Version 0 means the settings have never been set.
+    #[prost(uint64, tag = "2")]
+    pub version: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MarkShardsClosedRequest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub shard_ids: ::prost::alloc::vec::Vec<crate::types::ShardId>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -468,6 +510,14 @@ pub enum SourceType {
     Pulsar = 9,
     Vec = 10,
     Void = 11,
+    /// Documents of another Quickwit index
+    Reindex = 12,
+    /// Syslog messages received over the network
+    Syslog = 13,
+    /// Amazon SQS queue of raw messages or S3 event notifications
+    Sqs = 14,
+    /// Parquet files of an external table
+    Parquet = 15,
 }
 impl SourceType {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -488,6 +538,10 @@ impl SourceType {
             SourceType::Pulsar => "SOURCE_TYPE_PULSAR",
             SourceType::Vec => "SOURCE_TYPE_VEC",
             SourceType::Void => "SOURCE_TYPE_VOID",
+            SourceType::Reindex => "SOURCE_TYPE_REINDEX",
+            SourceType::Syslog => "SOURCE_TYPE_SYSLOG",
+            SourceType::Sqs => "SOURCE_TYPE_SQS",
+            SourceType::Parquet => "SOURCE_TYPE_PARQUET",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -505,6 +559,10 @@ impl SourceType {
             "SOURCE_TYPE_PULSAR" => Some(Self::Pulsar),
             "SOURCE_TYPE_VEC" => Some(Self::Vec),
             "SOURCE_TYPE_VOID" => Some(Self::Void),
+            "SOURCE_TYPE_REINDEX" => Some(Self::Reindex),
+            "SOURCE_TYPE_SYSLOG" => Some(Self::Syslog),
+            "SOURCE_TYPE_SQS" => Some(Self::Sqs),
+            "SOURCE_TYPE_PARQUET" => Some(Self::Parquet),
             _ => None,
         }
     }
@@ -654,6 +712,21 @@ impl RpcName for DeleteIndexTemplatesRequest {
         "delete_index_templates"
     }
 }
+impl RpcName for UpdateClusterSettingsRequest {
+    fn rpc_name() -> &'static str {
+        "update_cluster_settings"
+    }
+}
+impl RpcName for GetClusterSettingsRequest {
+    fn rpc_name() -> &'static str {
+        "get_cluster_settings"
+    }
+}
+impl RpcName for MarkShardsClosedRequest {
+    fn rpc_name() -> &'static str {
+        "mark_shards_closed"
+    }
+}
 pub type MetastoreServiceStream<T> = quickwit_common::ServiceStream<
     crate::metastore::MetastoreResult<T>,
 >;
@@ -810,6 +883,22 @@ pub trait MetastoreService: std::fmt::Debug + dyn_clone::DynClone + Send + Sync
         &mut self,
         request: DeleteIndexTemplatesRequest,
     ) -> crate::metastore::MetastoreResult<EmptyResponse>;
+    /// Updates the cluster settings and increments their version.
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse>;
+    /// Fetches the cluster settings.
+    async fn get_cluster_settings(
+        &mut self,
+        request: GetClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse>;
+    /// Marks a set of shards as closed. This RPC is used by the control plane to force-close shards that are stuck
+    /// in the open state. If a shard does not exist, it is ignored.
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse>;
     async fn check_connectivity(&mut self) -> anyhow::Result<()>;
     fn endpoints(&self) -> Vec<quickwit_common::uri::Uri>;
 }
@@ -1075,6 +1164,24 @@ impl MetastoreService for MetastoreServiceClient {
     ) -> crate::metastore::MetastoreResult<EmptyResponse> {
         self.inner.delete_index_templates(request).await
     }
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.inner.update_cluster_settings(request).await
+    }
+    async fn get_cluster_settings(
+        &mut self,
+        request: GetClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.inner.get_cluster_settings(request).await
+    }
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.inner.mark_shards_closed(request).await
+    }
     async fn check_connectivity(&mut self) -> anyhow::Result<()> {
         self.inner.check_connectivity().await
     }
@@ -1263,6 +1370,24 @@ pub mod mock_metastore_service {
         ) -> crate::metastore::MetastoreResult<super::EmptyResponse> {
             self.inner.lock().await.delete_index_templates(request).await
         }
+        async fn update_cluster_settings(
+            &mut self,
+            request: super::UpdateClusterSettingsRequest,
+        ) -> crate::metastore::MetastoreResult<super::ClusterSettingsResponse> {
+            self.inner.lock().await.update_cluster_settings(request).await
+        }
+        async fn get_cluster_settings(
+            &mut self,
+            request: super::GetClusterSettingsRequest,
+        ) -> crate::metastore::MetastoreResult<super::ClusterSettingsResponse> {
+            self.inner.lock().await.get_cluster_settings(request).await
+        }
+        async fn mark_shards_closed(
+            &mut self,
+            request: super::MarkShardsClosedRequest,
+        ) -> crate::metastore::MetastoreResult<super::EmptyResponse> {
+            self.inner.lock().await.mark_shards_closed(request).await
+        }
         async fn check_connectivity(&mut self) -> anyhow::Result<()> {
             self.inner.lock().await.check_connectivity().await
         }
@@ -1722,6 +1847,54 @@ impl tower::Service<DeleteIndexTemplatesRequest> for Box<dyn MetastoreService> {
         Box::pin(fut)
     }
 }
+impl tower::Service<UpdateClusterSettingsRequest> for Box<dyn MetastoreService> {
+    type Response = ClusterSettingsResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: UpdateClusterSettingsRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.update_cluster_settings(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<GetClusterSettingsRequest> for Box<dyn MetastoreService> {
+    type Response = ClusterSettingsResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: GetClusterSettingsRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.get_cluster_settings(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<MarkShardsClosedRequest> for Box<dyn MetastoreService> {
+    type Response = EmptyResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: MarkShardsClosedRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.mark_shards_closed(request).await };
+        Box::pin(fut)
+    }
+}
 /// A tower service stack is a set of tower services.
 #[derive(Debug)]
 struct MetastoreServiceTowerServiceStack {
@@ -1866,6 +2039,21 @@ struct MetastoreServiceTowerServiceStack {
         EmptyResponse,
         crate::metastore::MetastoreError,
     >,
+    update_cluster_settings_svc: quickwit_common::tower::BoxService<
+        UpdateClusterSettingsRequest,
+        ClusterSettingsResponse,
+        crate::metastore::MetastoreError,
+    >,
+    get_cluster_settings_svc: quickwit_common::tower::BoxService<
+        GetClusterSettingsRequest,
+        ClusterSettingsResponse,
+        crate::metastore::MetastoreError,
+    >,
+    mark_shards_closed_svc: quickwit_common::tower::BoxService<
+        MarkShardsClosedRequest,
+        EmptyResponse,
+        crate::metastore::MetastoreError,
+    >,
 }
 impl Clone for MetastoreServiceTowerServiceStack {
     fn clone(&self) -> Self {
@@ -1903,6 +2091,9 @@ impl Clone for MetastoreServiceTowerServiceStack {
                 .clone(),
             list_index_templates_svc: self.list_index_templates_svc.clone(),
             delete_index_templates_svc: self.delete_index_templates_svc.clone(),
+            update_cluster_settings_svc: self.update_cluster_settings_svc.clone(),
+            get_cluster_settings_svc: self.get_cluster_settings_svc.clone(),
+            mark_shards_closed_svc: self.mark_shards_closed_svc.clone(),
         }
     }
 }
@@ -2076,6 +2267,24 @@ impl MetastoreService for MetastoreServiceTowerServiceStack {
     ) -> crate::metastore::MetastoreResult<EmptyResponse> {
         self.delete_index_templates_svc.ready().await?.call(request).await
     }
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.update_cluster_settings_svc.ready().await?.call(request).await
+    }
+    async fn get_cluster_settings(
+        &mut self,
+        request: GetClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.get_cluster_settings_svc.ready().await?.call(request).await
+    }
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.mark_shards_closed_svc.ready().await?.call(request).await
+    }
     async fn check_connectivity(&mut self) -> anyhow::Result<()> {
         self.inner.check_connectivity().await
     }
@@ -2363,6 +2572,36 @@ type DeleteIndexTemplatesLayer = quickwit_common::tower::BoxLayer<
     EmptyResponse,
     crate::metastore::MetastoreError,
 >;
+type UpdateClusterSettingsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        UpdateClusterSettingsRequest,
+        ClusterSettingsResponse,
+        crate::metastore::MetastoreError,
+    >,
+    UpdateClusterSettingsRequest,
+    ClusterSettingsResponse,
+    crate::metastore::MetastoreError,
+>;
+type GetClusterSettingsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        GetClusterSettingsRequest,
+        ClusterSettingsResponse,
+        crate::metastore::MetastoreError,
+    >,
+    GetClusterSettingsRequest,
+    ClusterSettingsResponse,
+    crate::metastore::MetastoreError,
+>;
+type MarkShardsClosedLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        MarkShardsClosedRequest,
+        EmptyResponse,
+        crate::metastore::MetastoreError,
+    >,
+    MarkShardsClosedRequest,
+    EmptyResponse,
+    crate::metastore::MetastoreError,
+>;
 #[derive(Debug, Default)]
 pub struct MetastoreServiceTowerLayerStack {
     create_index_layers: Vec<CreateIndexLayer>,
@@ -2393,6 +2632,9 @@ pub struct MetastoreServiceTowerLayerStack {
     find_index_template_matches_layers: Vec<FindIndexTemplateMatchesLayer>,
     list_index_templates_layers: Vec<ListIndexTemplatesLayer>,
     delete_index_templates_layers: Vec<DeleteIndexTemplatesLayer>,
+    update_cluster_settings_layers: Vec<UpdateClusterSettingsLayer>,
+    get_cluster_settings_layers: Vec<GetClusterSettingsLayer>,
+    mark_shards_closed_layers: Vec<MarkShardsClosedLayer>,
 }
 impl MetastoreServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -3113,6 +3355,87 @@ impl MetastoreServiceTowerLayerStack {
         >>::Service as tower::Service<
             DeleteIndexTemplatesRequest,
         >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    UpdateClusterSettingsRequest,
+                    ClusterSettingsResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                UpdateClusterSettingsRequest,
+                ClusterSettingsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                UpdateClusterSettingsRequest,
+                Response = ClusterSettingsResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                UpdateClusterSettingsRequest,
+                ClusterSettingsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<
+            UpdateClusterSettingsRequest,
+        >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    GetClusterSettingsRequest,
+                    ClusterSettingsResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                GetClusterSettingsRequest,
+                ClusterSettingsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                GetClusterSettingsRequest,
+                Response = ClusterSettingsResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                GetClusterSettingsRequest,
+                ClusterSettingsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<
+            GetClusterSettingsRequest,
+        >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    MarkShardsClosedRequest,
+                    EmptyResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                MarkShardsClosedRequest,
+                EmptyResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                MarkShardsClosedRequest,
+                Response = EmptyResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                MarkShardsClosedRequest,
+                EmptyResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<
+            MarkShardsClosedRequest,
+        >>::Future: Send + 'static,
     {
         self.create_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
@@ -3170,6 +3493,12 @@ impl MetastoreServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.delete_index_templates_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.update_cluster_settings_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.get_cluster_settings_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.mark_shards_closed_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self
     }
     pub fn stack_create_index_layer<L>(mut self, layer: L) -> Self
@@ -3731,6 +4060,72 @@ impl MetastoreServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_update_cluster_settings_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    UpdateClusterSettingsRequest,
+                    ClusterSettingsResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                UpdateClusterSettingsRequest,
+                Response = ClusterSettingsResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            UpdateClusterSettingsRequest,
+        >>::Future: Send + 'static,
+    {
+        self.update_cluster_settings_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_get_cluster_settings_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    GetClusterSettingsRequest,
+                    ClusterSettingsResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                GetClusterSettingsRequest,
+                Response = ClusterSettingsResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            GetClusterSettingsRequest,
+        >>::Future: Send + 'static,
+    {
+        self.get_cluster_settings_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_mark_shards_closed_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    MarkShardsClosedRequest,
+                    EmptyResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                MarkShardsClosedRequest,
+                Response = EmptyResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            MarkShardsClosedRequest,
+        >>::Future: Send + 'static,
+    {
+        self.mark_shards_closed_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn build<T>(self, instance: T) -> MetastoreServiceClient
     where
         T: MetastoreService,
@@ -4005,6 +4400,30 @@ impl MetastoreServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let update_cluster_settings_svc = self
+            .update_cluster_settings_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let get_cluster_settings_svc = self
+            .get_cluster_settings_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let mark_shards_closed_svc = self
+            .mark_shards_closed_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = MetastoreServiceTowerServiceStack {
             inner: boxed_instance.clone(),
             create_index_svc,
@@ -4035,6 +4454,9 @@ impl MetastoreServiceTowerLayerStack {
             find_index_template_matches_svc,
             list_index_templates_svc,
             delete_index_templates_svc,
+            update_cluster_settings_svc,
+            get_cluster_settings_svc,
+            mark_shards_closed_svc,
         };
         MetastoreServiceClient::new(tower_svc_stack)
     }
@@ -4299,6 +4721,24 @@ where
             Response = EmptyResponse,
             Error = crate::metastore::MetastoreError,
             Future = BoxFuture<EmptyResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            UpdateClusterSettingsRequest,
+            Response = ClusterSettingsResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<ClusterSettingsResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            GetClusterSettingsRequest,
+            Response = ClusterSettingsResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<ClusterSettingsResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            MarkShardsClosedRequest,
+            Response = EmptyResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<EmptyResponse, crate::metastore::MetastoreError>,
         >,
 {
     async fn create_index(
@@ -4469,6 +4909,24 @@ where
     ) -> crate::metastore::MetastoreResult<EmptyResponse> {
         self.call(request).await
     }
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.call(request).await
+    }
+    async fn get_cluster_settings(
+        &mut self,
+        request: GetClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.call(request).await
+    }
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.call(request).await
+    }
     async fn check_connectivity(&mut self) -> anyhow::Result<()> {
         if self.inner.is_disconnected() {
             anyhow::bail!("actor `{}` is disconnected", self.inner.actor_instance_id())
@@ -4888,6 +5346,45 @@ where
                 DeleteIndexTemplatesRequest::rpc_name(),
             ))
     }
+    async fn update_cluster_settings(
+        &mut self,
+        request: UpdateClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.inner
+            .update_cluster_settings(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                UpdateClusterSettingsRequest::rpc_name(),
+            ))
+    }
+    async fn get_cluster_settings(
+        &mut self,
+        request: GetClusterSettingsRequest,
+    ) -> crate::metastore::MetastoreResult<ClusterSettingsResponse> {
+        self.inner
+            .get_cluster_settings(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                GetClusterSettingsRequest::rpc_name(),
+            ))
+    }
+    async fn mark_shards_closed(
+        &mut self,
+        request: MarkShardsClosedRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.inner
+            .mark_shards_closed(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                MarkShardsClosedRequest::rpc_name(),
+            ))
+    }
     async fn check_connectivity(&mut self) -> anyhow::Result<()> {
         if self.connection_addrs_rx.borrow().len() == 0 {
             anyhow::bail!("no server currently available")
@@ -5232,6 +5729,39 @@ for MetastoreServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn update_cluster_settings(
+        &self,
+        request: tonic::Request<UpdateClusterSettingsRequest>,
+    ) -> Result<tonic::Response<ClusterSettingsResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .update_cluster_settings(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn get_cluster_settings(
+        &self,
+        request: tonic::Request<GetClusterSettingsRequest>,
+    ) -> Result<tonic::Response<ClusterSettingsResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .get_cluster_settings(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn mark_shards_closed(
+        &self,
+        request: tonic::Request<MarkShardsClosedRequest>,
+    ) -> Result<tonic::Response<EmptyResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .mark_shards_closed(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
 }
 /// Generated client implementations.
 pub mod metastore_service_grpc_client {
@@ -6179,6 +6709,91 @@ pub mod metastore_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Updates the cluster settings and increments their version.
+        pub async fn update_cluster_settings(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateClusterSettingsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ClusterSettingsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/UpdateClusterSettings",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.metastore.MetastoreService",
+                        "UpdateClusterSettings",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Fetches the cluster settings.
+        pub async fn get_cluster_settings(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetClusterSettingsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ClusterSettingsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/GetClusterSettings",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.metastore.MetastoreService",
+                        "GetClusterSettings",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Marks a set of shards as closed. This RPC is used by the control plane to force-close shards that are stuck
+        /// in the open state. If a shard does not exist, it is ignored.
+        pub async fn mark_shards_closed(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MarkShardsClosedRequest>,
+        ) -> std::result::Result<tonic::Response<super::EmptyResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/MarkShardsClosed",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.metastore.MetastoreService",
+                        "MarkShardsClosed",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -6386,6 +7001,22 @@ pub mod metastore_service_grpc_server {
             &self,
             request: tonic::Request<super::DeleteIndexTemplatesRequest>,
         ) -> std::result::Result<tonic::Response<super::EmptyResponse>, tonic::Status>;
+        /// Updates the cluster settings and increments their version.
+        async fn update_cluster_settings(
+            &self,
+            request: tonic::Request<super::UpdateClusterSettingsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ClusterSettingsResponse>, tonic::Status>;
+        /// Fetches the cluster settings.
+        async fn get_cluster_settings(
+            &self,
+            request: tonic::Request<super::GetClusterSettingsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ClusterSettingsResponse>, tonic::Status>;
+        /// Marks a set of shards as closed. This RPC is used by the control plane to force-close shards that are stuck
+        /// in the open state. If a shard does not exist, it is ignored.
+        async fn mark_shards_closed(
+            &self,
+            request: tonic::Request<super::MarkShardsClosedRequest>,
+        ) -> std::result::Result<tonic::Response<super::EmptyResponse>, tonic::Status>;
     }
     /// Metastore meant to manage Quickwit's indexes, their splits and delete tasks.
     ///
@@ -7803,6 +8434,144 @@ pub mod metastore_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.metastore.MetastoreService/UpdateClusterSettings" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateClusterSettingsSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::UpdateClusterSettingsRequest>
+                    for UpdateClusterSettingsSvc<T> {
+                        type Response = super::ClusterSettingsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateClusterSettingsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_cluster_settings(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateClusterSettingsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.metastore.MetastoreService/GetClusterSettings" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClusterSettingsSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::GetClusterSettingsRequest>
+                    for GetClusterSettingsSvc<T> {
+                        type Response = super::ClusterSettingsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetClusterSettingsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).get_cluster_settings(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetClusterSettingsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.metastore.MetastoreService/MarkShardsClosed" => {
+                    #[allow(non_camel_case_types)]
+                    struct MarkShardsClosedSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::MarkShardsClosedRequest>
+                    for MarkShardsClosedSvc<T> {
+                        type Response = super::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MarkShardsClosedRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).mark_shards_closed(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MarkShardsClosedSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -9,6 +9,28 @@ pub struct ScrollRequest {
     #[prost(uint32, optional, tag = "2")]
     pub scroll_ttl_secs: ::core::option::Option<u32>,
 }
+/// Captures the set of splits published at creation time so that subsequent searches run against
+/// that frozen set.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenPointInTimeRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub index_id_patterns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Duration after which the point in time expires.
+    #[prost(uint32, tag = "2")]
+    pub keep_alive_secs: u32,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenPointInTimeResponse {
+    /// ID to pass to subsequent search requests.
+    #[prost(string, tag = "1")]
+    pub pit_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub num_splits: u64,
+}
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -191,6 +213,75 @@ pub struct SearchRequest {
     pub search_after: ::core::option::Option<PartialHit>,
     #[prost(enumeration = "CountHits", tag = "17")]
     pub count_hits: i32,
+    /// If set, the splits under legal hold that have been marked for deletion are
+    /// searched as well. Requires `legal_hold_token` to authenticate a caller allowed to search them.
+    #[prost(bool, tag = "18")]
+    pub include_held_splits: bool,
+    /// If set, the search caches are not read. Fresh results are still cached for subsequent
+    /// requests.
+    #[prost(bool, tag = "19")]
+    pub bypass_cache: bool,
+    /// If set, a response computed at most `max_staleness_secs` seconds ago for the same request
+    /// may be returned instead of running the search again.
+    #[prost(uint32, optional, tag = "20")]
+    pub max_staleness_secs: ::core::option::Option<u32>,
+    /// Tenant issuing the request. The searchers share their storage read bandwidth fairly between
+    /// tenants.
+    #[prost(string, optional, tag = "21")]
+    pub tenant_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Priority of the storage reads of the request.
+    #[prost(enumeration = "SearchPriority", tag = "22")]
+    pub priority: i32,
+    /// If set, the response includes a breakdown of the time spent searching each split.
+    #[prost(bool, tag = "23")]
+    pub profile: bool,
+    /// If set, the search runs against the splits captured by this point in time instead of the
+    /// splits currently published.
+    #[prost(string, optional, tag = "24")]
+    pub pit_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Lookup tables used to enrich the hits and the keys of the terms aggregations.
+    #[prost(message, repeated, tag = "25")]
+    pub lookups: ::prost::alloc::vec::Vec<LookupEnrichment>,
+    /// Fields whose matched terms are highlighted in the hits.
+    #[prost(message, optional, tag = "26")]
+    pub highlight: ::core::option::Option<HighlightRequest>,
+    /// Token of the caller allowed to search the splits under legal hold. Only read by the root
+    /// searcher, which removes it from the request.
+    #[prost(string, optional, tag = "27")]
+    pub legal_hold_token: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LookupEnrichment {
+    /// Name of the lookup table.
+    #[prost(string, tag = "1")]
+    pub table: ::prost::alloc::string::String,
+    /// Field of the hits and of the terms aggregations whose values are looked up in the table.
+    #[prost(string, tag = "2")]
+    pub field: ::prost::alloc::string::String,
+    /// Field receiving the row of the table matching the value. Defaults to the name of the table.
+    #[prost(string, optional, tag = "3")]
+    pub target_field: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HighlightRequest {
+    /// Stored text fields to highlight.
+    #[prost(string, repeated, tag = "1")]
+    pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Tag inserted before each matched term. Defaults to `<em>`.
+    #[prost(string, optional, tag = "2")]
+    pub pre_tag: ::core::option::Option<::prost::alloc::string::String>,
+    /// Tag inserted after each matched term. Defaults to `</em>`.
+    #[prost(string, optional, tag = "3")]
+    pub post_tag: ::core::option::Option<::prost::alloc::string::String>,
+    /// Maximum number of characters of each snippet. Defaults to 150.
+    #[prost(uint32, optional, tag = "4")]
+    pub fragment_size: ::core::option::Option<u32>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Eq, Hash)]
@@ -230,6 +321,86 @@ pub struct SearchResponse {
     /// Scroll Id (only set if scroll_secs was set in the request)
     #[prost(string, optional, tag = "6")]
     pub scroll_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Search profile (only set if profile was set in the request)
+    #[prost(message, optional, tag = "7")]
+    pub profile: ::core::option::Option<SearchProfile>,
+    /// Indexes matched by the index ID patterns of the request that could not be searched.
+    #[prost(message, repeated, tag = "8")]
+    pub failed_indexes: ::prost::alloc::vec::Vec<IndexSearchFailure>,
+}
+/// An index skipped by a search request targeting several indexes.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IndexSearchFailure {
+    #[prost(string, tag = "1")]
+    pub index_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+}
+/// Breakdown of the time and resources spent searching a single split.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SplitSearchProfile {
+    #[prost(string, tag = "1")]
+    pub split_id: ::prost::alloc::string::String,
+    /// Time spent opening the split and building the query.
+    #[prost(uint64, tag = "2")]
+    pub planning_micros: u64,
+    /// Time spent waiting for the data required by the query to be fetched from storage.
+    #[prost(uint64, tag = "3")]
+    pub io_wait_micros: u64,
+    /// Time spent matching and scoring documents.
+    #[prost(uint64, tag = "4")]
+    pub match_micros: u64,
+    /// Time spent collecting aggregations.
+    #[prost(uint64, tag = "5")]
+    pub aggregate_micros: u64,
+    /// Time spent fetching the documents of the hits.
+    #[prost(uint64, tag = "6")]
+    pub fetch_micros: u64,
+    /// Number of bytes read from the index storage.
+    #[prost(uint64, tag = "7")]
+    pub num_bytes_read: u64,
+    /// Number of requests issued to the index storage.
+    #[prost(uint64, tag = "8")]
+    pub num_storage_requests: u64,
+    /// Whether the split footer was served by the split footer cache.
+    #[prost(bool, tag = "9")]
+    pub footer_cache_hit: bool,
+    /// Whether the result was served by the leaf search cache.
+    #[prost(bool, tag = "10")]
+    pub leaf_cache_hit: bool,
+}
+/// Search profile of a request, aggregated over all the splits it searched.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchProfile {
+    #[prost(uint64, tag = "1")]
+    pub num_splits: u64,
+    #[prost(uint64, tag = "2")]
+    pub planning_micros: u64,
+    #[prost(uint64, tag = "3")]
+    pub io_wait_micros: u64,
+    #[prost(uint64, tag = "4")]
+    pub match_micros: u64,
+    #[prost(uint64, tag = "5")]
+    pub aggregate_micros: u64,
+    #[prost(uint64, tag = "6")]
+    pub fetch_micros: u64,
+    #[prost(uint64, tag = "7")]
+    pub num_bytes_read: u64,
+    #[prost(uint64, tag = "8")]
+    pub num_storage_requests: u64,
+    #[prost(uint64, tag = "9")]
+    pub num_footer_cache_hits: u64,
+    #[prost(uint64, tag = "10")]
+    pub num_leaf_cache_hits: u64,
+    /// Per-split profiles, slowest first.
+    #[prost(message, repeated, tag = "11")]
+    pub splits: ::prost::alloc::vec::Vec<SplitSearchProfile>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -312,6 +483,9 @@ pub struct LeafHit {
     /// A snippet of the matching content
     #[prost(string, optional, tag = "3")]
     pub leaf_snippet_json: ::core::option::Option<::prost::alloc::string::String>,
+    /// The highlighted snippets of the matching content, per field.
+    #[prost(string, optional, tag = "4")]
+    pub leaf_highlight_json: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -329,6 +503,9 @@ pub struct Hit {
     /// The index id of the hit
     #[prost(string, tag = "4")]
     pub index_id: ::prost::alloc::string::String,
+    /// The highlighted snippets of the matching content, per field.
+    #[prost(string, optional, tag = "5")]
+    pub highlight: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// A partial hit, is a hit for which we have not fetch the content yet.
 /// Instead, it holds a document_uri which is enough information to
@@ -411,6 +588,9 @@ pub struct LeafSearchResponse {
     pub intermediate_aggregation_result: ::core::option::Option<
         ::prost::alloc::vec::Vec<u8>,
     >,
+    /// Profiles of the splits searched (only set if profile was set in the request).
+    #[prost(message, repeated, tag = "7")]
+    pub split_profiles: ::prost::alloc::vec::Vec<SplitSearchProfile>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -420,6 +600,8 @@ pub struct SnippetRequest {
     pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     #[prost(string, tag = "2")]
     pub query_ast_resolved: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub highlight: ::core::option::Option<HighlightRequest>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -442,6 +624,9 @@ pub struct FetchDocsRequest {
     /// `DocMapper` as json serialized trait.
     #[prost(string, tag = "6")]
     pub doc_mapper: ::prost::alloc::string::String,
+    /// If set, the response includes the time spent fetching the documents of each split.
+    #[prost(bool, tag = "8")]
+    pub profile: bool,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -450,6 +635,9 @@ pub struct FetchDocsResponse {
     /// List of complete hits.
     #[prost(message, repeated, tag = "1")]
     pub hits: ::prost::alloc::vec::Vec<LeafHit>,
+    /// Fetch profiles of the splits (only set if profile was set in the request).
+    #[prost(message, repeated, tag = "2")]
+    pub split_profiles: ::prost::alloc::vec::Vec<SplitSearchProfile>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -554,6 +742,9 @@ pub struct SearchStreamRequest {
     /// Fields to extract snippet on.
     #[prost(string, repeated, tag = "10")]
     pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Tenant issuing the request. Stream requests are always run with the `BATCH` priority.
+    #[prost(string, optional, tag = "12")]
+    pub tenant_id: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -642,6 +833,37 @@ impl ListFieldType {
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum SearchPriority {
+    /// Queries whose results a user is waiting for.
+    Interactive = 0,
+    /// Throughput-oriented queries, such as exports. Their storage reads yield to the reads of
+    /// interactive queries.
+    Batch = 1,
+}
+impl SearchPriority {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SearchPriority::Interactive => "INTERACTIVE",
+            SearchPriority::Batch => "BATCH",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INTERACTIVE" => Some(Self::Interactive),
+            "BATCH" => Some(Self::Batch),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum CountHits {
     /// Count all hits, querying all splits.
     CountAll = 0,
@@ -33,8 +33,12 @@ pub type ControlPlaneResult<T> = std::result::Result<T, ControlPlaneError>;
 pub enum ControlPlaneError {
     #[error("internal error: {0}")]
     Internal(String),
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
     #[error("metastore error: {0}")]
     Metastore(#[from] MetastoreError),
+    #[error("not found: {0}")]
+    NotFound(String),
     #[error("request timed out: {0}")]
     Timeout(String),
     #[error("too many requests")]
@@ -53,7 +57,9 @@ impl ServiceError for ControlPlaneError {
     fn error_code(&self) -> ServiceErrorCode {
         match self {
             Self::Internal(_) => ServiceErrorCode::Internal,
+            Self::InvalidArgument(_) => ServiceErrorCode::BadRequest,
             Self::Metastore(metastore_error) => metastore_error.error_code(),
+            Self::NotFound(_) => ServiceErrorCode::NotFound,
             Self::Timeout(_) => ServiceErrorCode::Timeout,
             Self::TooManyRequests => ServiceErrorCode::TooManyRequests,
             Self::Unavailable(_) => ServiceErrorCode::Unavailable,
@@ -92,7 +98,14 @@ impl From<ControlPlaneError> for MetastoreError {
                 message: "an internal metastore error occurred".to_string(),
                 cause: message,
             },
+            ControlPlaneError::InvalidArgument(message) => {
+                MetastoreError::InvalidArgument { message }
+            }
             ControlPlaneError::Metastore(error) => error,
+            ControlPlaneError::NotFound(message) => MetastoreError::Internal {
+                message: "an internal metastore error occurred".to_string(),
+                cause: message,
+            },
             ControlPlaneError::Timeout(message) => MetastoreError::Timeout(message),
             ControlPlaneError::TooManyRequests => MetastoreError::TooManyRequests,
             ControlPlaneError::Unavailable(message) => MetastoreError::Unavailable(message),
@@ -125,3 +138,57 @@ impl RpcName for AdviseResetShardsRequest {
         "advise_reset_shards"
     }
 }
+
+impl RpcName for DecommissionIngesterRequest {
+    fn rpc_name() -> &'static str {
+        "decommission_ingester"
+    }
+}
+
+impl RpcName for MoveShardRequest {
+    fn rpc_name() -> &'static str {
+        "move_shard"
+    }
+}
+
+impl RpcName for GetShardEventsRequest {
+    fn rpc_name() -> &'static str {
+        "get_shard_events"
+    }
+}
+
+impl RpcName for RebalanceShardsDryRunRequest {
+    fn rpc_name() -> &'static str {
+        "rebalance_shards_dry_run"
+    }
+}
+
+impl RpcName for ListShardsRequest {
+    fn rpc_name() -> &'static str {
+        "list_shards"
+    }
+}
+
+impl RpcName for ProjectSourceShardsRequest {
+    fn rpc_name() -> &'static str {
+        "project_source_shards"
+    }
+}
+
+impl RpcName for ForceCloseShardsRequest {
+    fn rpc_name() -> &'static str {
+        "force_close_shards"
+    }
+}
+
+impl RpcName for PauseSourceRequest {
+    fn rpc_name() -> &'static str {
+        "pause_source"
+    }
+}
+
+impl RpcName for ResumeSourceRequest {
+    fn rpc_name() -> &'static str {
+        "resume_source"
+    }
+}
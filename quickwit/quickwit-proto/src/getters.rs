@@ -27,6 +27,9 @@ generate_getters! {
     impl fn index_uid() -> IndexUid {} for
     // Control Plane API
     GetOrCreateOpenShardsSuccess,
+    ProjectSourceShardsResponse,
+    ShardEvent,
+    ShardMove,
 
     // Indexing API
     IndexingTask,
@@ -34,6 +37,7 @@ generate_getters! {
     // Ingest API
     FetchEof,
     FetchPayload,
+    FetchShardRequest,
     IngestSuccess,
     OpenFetchStreamRequest,
     PersistFailure,
@@ -46,6 +50,7 @@ generate_getters! {
     Shard,
     ShardIds,
     ShardPKey,
+    SourceShardsDigest,
     TruncateShardsSubrequest,
 
     // Metastore API
@@ -62,6 +67,7 @@ generate_getters! {
     ListShardsSubrequest,
     ListShardsSubresponse,
     ListStaleSplitsRequest,
+    MarkShardsClosedRequest,
     MarkSplitsForDeletionRequest,
     OpenShardSubrequest,
     PublishSplitsRequest,
@@ -76,7 +82,8 @@ generate_getters! {
     impl fn shard() -> Shard {} for
 
     InitShardSubrequest,
-    InitShardSuccess
+    InitShardSuccess,
+    MoveShardResponse
 }
 
 generate_getters! {
@@ -89,6 +96,9 @@ generate_getters! {
     impl fn shard_id() -> ShardId {} for
 
     InitShardFailure,
+    MoveShardRequest,
     OpenShardSubrequest,
+    ShardEvent,
+    ShardMove,
     ShardPKey
 }
@@ -19,7 +19,7 @@
 
 use bytesize::ByteSize;
 
-use crate::types::{queue_id, Position, QueueId, ShardId};
+use crate::types::{queue_id, IndexUid, Position, QueueId, ShardId};
 
 include!("../codegen/quickwit/quickwit.ingest.ingester.rs");
 
@@ -116,6 +116,23 @@ impl IngesterStatus {
             Self::Failed => "failed",
         }
     }
+
+    pub fn from_json_str_name(status_str: &str) -> Option<Self> {
+        match status_str {
+            "unspecified" => Some(Self::Unspecified),
+            "initializing" => Some(Self::Initializing),
+            "ready" => Some(Self::Ready),
+            "decommissioning" => Some(Self::Decommissioning),
+            "decommissioned" => Some(Self::Decommissioned),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the ingester is being or has been decommissioned.
+    pub fn is_decommissioning(&self) -> bool {
+        matches!(self, Self::Decommissioning | Self::Decommissioned)
+    }
 }
 
 impl OpenFetchStreamRequest {
@@ -136,6 +153,37 @@ impl OpenFetchStreamRequest {
     }
 }
 
+impl FetchShardRequest {
+    pub fn shard_id(&self) -> &ShardId {
+        self.shard_id
+            .as_ref()
+            .expect("`shard_id` should be a required field")
+    }
+
+    pub fn queue_id(&self) -> QueueId {
+        queue_id(self.index_uid(), &self.source_id, self.shard_id())
+    }
+
+    pub fn from_position_exclusive(&self) -> &Position {
+        self.from_position_exclusive
+            .as_ref()
+            .expect("`from_position_exclusive` should be a required field")
+    }
+}
+
+impl From<FetchShardRequest> for OpenFetchStreamRequest {
+    fn from(fetch_shard_request: FetchShardRequest) -> Self {
+        Self {
+            client_id: fetch_shard_request.client_id,
+            index_uid: fetch_shard_request.index_uid,
+            source_id: fetch_shard_request.source_id,
+            shard_id: fetch_shard_request.shard_id,
+            from_position_exclusive: fetch_shard_request.from_position_exclusive,
+            to_position_inclusive: fetch_shard_request.to_position_inclusive,
+        }
+    }
+}
+
 impl PersistSubrequest {
     pub fn shard_id(&self) -> &ShardId {
         self.shard_id
@@ -301,3 +349,62 @@ impl TruncateShardsSubrequest {
             .expect("`truncate_up_to_position_inclusive` should be a required field")
     }
 }
+
+/// Returns the digest of a shard identified by its queue ID. The digest of a set of shards is the
+/// wrapping sum of the digests of its shards: it does not depend on the order of the shards, and
+/// the digests of several sources can be summed. The control plane and the ingesters compare
+/// digests to detect diverging shard assignments without exchanging the lists of shards.
+pub fn queue_id_digest(queue_id: &str) -> u64 {
+    // FNV-1a, followed by the finalizer of MurmurHash3 to spread the bits of the hash so that the
+    // sum of the digests is well distributed.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for byte in queue_id.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+/// Returns the digest of the shards of a source. See [`queue_id_digest`].
+pub fn source_shards_digest<'a>(
+    index_uid: &IndexUid,
+    source_id: &str,
+    shard_ids: impl IntoIterator<Item = &'a ShardId>,
+) -> u64 {
+    shard_ids.into_iter().fold(0, |digest, shard_id| {
+        digest.wrapping_add(queue_id_digest(&queue_id(index_uid, source_id, shard_id)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_shards_digest() {
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let shard_ids = [ShardId::from(1), ShardId::from(2)];
+
+        let digest = source_shards_digest(&index_uid, "test-source", &shard_ids);
+        let reversed_digest =
+            source_shards_digest(&index_uid, "test-source", shard_ids.iter().rev());
+        assert_eq!(digest, reversed_digest);
+
+        let digest_1 = source_shards_digest(&index_uid, "test-source", &shard_ids[..1]);
+        let digest_2 = source_shards_digest(&index_uid, "test-source", &shard_ids[1..]);
+        assert_eq!(digest, digest_1.wrapping_add(digest_2));
+        assert_ne!(digest_1, digest_2);
+
+        assert_eq!(source_shards_digest(&index_uid, "test-source", &[]), 0);
+        assert_ne!(
+            source_shards_digest(&index_uid, "other-source", &shard_ids),
+            digest
+        );
+    }
+}
@@ -309,6 +309,7 @@ impl From<PersistFailureReason> for IngestFailureReason {
             PersistFailureReason::ResourceExhausted => IngestFailureReason::ResourceExhausted,
             PersistFailureReason::RateLimited => IngestFailureReason::RateLimited,
             PersistFailureReason::Timeout => IngestFailureReason::Timeout,
+            PersistFailureReason::Aborted => IngestFailureReason::Aborted,
         }
     }
 }
@@ -34,6 +34,7 @@ pub mod cluster;
 pub mod control_plane;
 pub use {bytes, tonic};
 pub mod developer;
+pub mod enrichment;
 pub mod error;
 mod getters;
 pub mod indexing;
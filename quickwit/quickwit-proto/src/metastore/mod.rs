@@ -232,8 +232,12 @@ impl SourceType {
             SourceType::Kafka => "kafka",
             SourceType::Kinesis => "kinesis",
             SourceType::Nats => "nats",
+            SourceType::Parquet => "parquet",
             SourceType::PubSub => "pubsub",
             SourceType::Pulsar => "pulsar",
+            SourceType::Reindex => "reindex",
+            SourceType::Sqs => "sqs",
+            SourceType::Syslog => "syslog",
             SourceType::Unspecified => "unspecified",
             SourceType::Vec => "vec",
             SourceType::Void => "void",
@@ -22,6 +22,7 @@ use std::fmt;
 use std::io::{self, Read};
 
 use prost::Message;
+use serde::{Deserialize, Serialize};
 pub use sort_by_value::SortValue;
 
 include!("../codegen/quickwit/quickwit.search.rs");
@@ -227,6 +228,79 @@ impl PartialHit {
     }
 }
 
+/// Chitchat key prefix under which the searchers advertise the query access statistics of the
+/// indexes they search. The key is suffixed with the index UID.
+pub const QUERY_ACCESS_STATS_KEY_PREFIX: &str = "searcher.query_access_stats:";
+
+/// Smoothing factor of the moving averages of the query access statistics.
+const QUERY_ACCESS_STATS_SMOOTHING_FACTOR: f64 = 0.05;
+
+/// Describes how the queries hitting an index access its documents. The selectivity and latency
+/// are exponentially weighted moving averages, so the statistics follow the recent access
+/// patterns of the index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct QueryAccessStats {
+    /// Number of root searches that targeted the index.
+    pub num_queries: u64,
+    /// Number of root searches that had to read the whole searched data: match-all queries and
+    /// aggregations.
+    pub num_scan_queries: u64,
+    /// Ratio between the number of hits and the number of documents searched, from 0 to 1.
+    pub selectivity: f64,
+    /// Latency of the root searches in milliseconds.
+    pub latency_millis: f64,
+}
+
+impl QueryAccessStats {
+    /// Records the outcome of a root search.
+    pub fn record_query(&mut self, selectivity: f64, latency_millis: f64, is_scan: bool) {
+        let selectivity = selectivity.clamp(0.0, 1.0);
+
+        if self.num_queries == 0 {
+            self.selectivity = selectivity;
+            self.latency_millis = latency_millis;
+        } else {
+            let alpha = QUERY_ACCESS_STATS_SMOOTHING_FACTOR;
+            self.selectivity += alpha * (selectivity - self.selectivity);
+            self.latency_millis += alpha * (latency_millis - self.latency_millis);
+        }
+        self.num_queries += 1;
+
+        if is_scan {
+            self.num_scan_queries += 1;
+        }
+    }
+
+    /// Returns the fraction of the queries that were scans.
+    pub fn scan_ratio(&self) -> f64 {
+        if self.num_queries == 0 {
+            return 0.0;
+        }
+        self.num_scan_queries as f64 / self.num_queries as f64
+    }
+
+    /// Merges the statistics of an index collected by several searchers. The averages are
+    /// weighted by the number of queries.
+    pub fn merge<'a>(stats: impl IntoIterator<Item = &'a QueryAccessStats>) -> QueryAccessStats {
+        let mut merged_stats = QueryAccessStats::default();
+
+        for stats in stats {
+            let num_queries = merged_stats.num_queries + stats.num_queries;
+
+            if num_queries == 0 {
+                continue;
+            }
+            let weight = stats.num_queries as f64 / num_queries as f64;
+            merged_stats.selectivity += weight * (stats.selectivity - merged_stats.selectivity);
+            merged_stats.latency_millis +=
+                weight * (stats.latency_millis - merged_stats.latency_millis);
+            merged_stats.num_queries = num_queries;
+            merged_stats.num_scan_queries += stats.num_scan_queries;
+        }
+        merged_stats
+    }
+}
+
 /// Serializes the Split fields.
 ///
 /// `fields_metadata` has to be sorted.
@@ -0,0 +1,111 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// Quickwit flavor of the `has_child` and `has_parent` queries.
+///
+/// Quickwit does not support the `join` field type: parent and child documents are instead
+/// related through a join key held by the fast field `join_field`. Both queries match the
+/// documents sharing their join key with at least one document matching `query`.
+///
+/// # Unsupported features
+/// - `type` and `parent_type`
+/// - `score_mode`, `max_children`, `min_children`
+/// - `ignore_unmapped`
+/// - `inner_hits`
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct JoinQuery {
+    join_field: String,
+    query: Box<ElasticQueryDslInner>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for JoinQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let underlying = self.query.convert_to_query_ast()?;
+        let join_query_ast: QueryAst = query_ast::JoinQuery {
+            join_field: self.join_field,
+            query: Box::new(underlying),
+        }
+        .into();
+        Ok(join_query_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+    use crate::elastic_query_dsl::ElasticQueryDsl;
+
+    #[test]
+    fn test_dsl_join_query_deserialize() {
+        let join_query_json = r#"{
+            "join_field": "trace_id",
+            "query": { "term": { "status": { "value": "error" } } }
+        }"#;
+        let join_query: JoinQuery = serde_json::from_str(join_query_json).unwrap();
+        assert_eq!(
+            join_query,
+            JoinQuery {
+                join_field: "trace_id".to_string(),
+                query: Box::new(term_query_from_field_value("status", "error").into()),
+                boost: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_dsl_has_child_and_has_parent_to_query_ast() {
+        for query_type in ["has_child", "has_parent"] {
+            let query_dsl_json = format!(
+                r#"{{
+                    "{query_type}": {{
+                        "join_field": "trace_id",
+                        "query": {{ "term": {{ "status": {{ "value": "error" }} }} }}
+                    }}
+                }}"#
+            );
+            let query_dsl: ElasticQueryDsl = serde_json::from_str(&query_dsl_json).unwrap();
+            let query_ast = QueryAst::try_from(query_dsl).unwrap();
+            let QueryAst::Join(join_query) = query_ast else {
+                panic!("expected a join query, got {query_ast:?}");
+            };
+            assert_eq!(join_query.join_field, "trace_id");
+            assert!(matches!(*join_query.query, QueryAst::Term(_)));
+        }
+    }
+
+    #[test]
+    fn test_dsl_join_query_rejects_parent_type() {
+        let join_query_json = r#"{
+            "parent_type": "trace",
+            "join_field": "trace_id",
+            "query": { "match_all": {} }
+        }"#;
+        serde_json::from_str::<JoinQuery>(join_query_json).unwrap_err();
+    }
+}
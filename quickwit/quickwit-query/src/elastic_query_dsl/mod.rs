@@ -21,6 +21,7 @@ use serde::{Deserialize, Serialize};
 
 mod bool_query;
 mod exists_query;
+mod join_query;
 mod match_bool_prefix;
 mod match_phrase_query;
 mod match_query;
@@ -34,6 +35,7 @@ mod term_query;
 mod terms_query;
 
 use bool_query::BoolQuery;
+use join_query::JoinQuery;
 pub use one_field_map::OneFieldMap;
 use phrase_prefix_query::MatchPhrasePrefixQuery;
 pub(crate) use query_string_query::QueryStringQuery;
@@ -79,6 +81,8 @@ pub(crate) enum ElasticQueryDslInner {
     MultiMatch(MultiMatchQuery),
     Range(RangeQuery),
     Exists(ExistsQuery),
+    HasChild(JoinQuery),
+    HasParent(JoinQuery),
 }
 
 #[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -126,6 +130,9 @@ impl ConvertableToQueryAst for ElasticQueryDslInner {
             Self::Match(match_query) => match_query.convert_to_query_ast(),
             Self::Exists(exists_query) => exists_query.convert_to_query_ast(),
             Self::MultiMatch(multi_match_query) => multi_match_query.convert_to_query_ast(),
+            Self::HasChild(join_query) | Self::HasParent(join_query) => {
+                join_query.convert_to_query_ast()
+            }
         }
     }
 }
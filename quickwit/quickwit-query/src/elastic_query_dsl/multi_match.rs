@@ -22,6 +22,7 @@ use serde_with::formats::PreferMany;
 use serde_with::{serde_as, OneOrMany};
 
 use crate::elastic_query_dsl::bool_query::BoolQuery;
+use crate::elastic_query_dsl::match_bool_prefix::MatchBoolPrefixQuery;
 use crate::elastic_query_dsl::match_phrase_query::{MatchPhraseQuery, MatchPhraseQueryParams};
 use crate::elastic_query_dsl::match_query::{MatchQuery, MatchQueryParams};
 use crate::elastic_query_dsl::phrase_prefix_query::{
@@ -86,6 +87,16 @@ fn deserialize_match_query_for_one_field(
             };
             Ok(ElasticQueryDslInner::Match(match_query))
         }
+        MatchType::BoolPrefix => {
+            let match_query_params: MatchQueryParams = serde_json::from_value(json_val)?;
+            let match_bool_prefix_query = MatchBoolPrefixQuery {
+                field: field.to_string(),
+                params: match_query_params,
+            };
+            Ok(ElasticQueryDslInner::MatchBoolPrefix(
+                match_bool_prefix_query,
+            ))
+        }
     }
 }
 
@@ -141,6 +152,7 @@ pub enum MatchType {
     MostFields,
     Phrase,
     PhrasePrefix,
+    BoolPrefix,
 }
 
 impl ConvertableToQueryAst for MultiMatchQuery {
@@ -203,6 +215,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_multimatch_bool_prefix_query_deserialization() {
+        test_multimatch_query_ok_aux(
+            r#"{
+                "query": "quick brown f",
+                "type": "bool_prefix",
+                "fields": ["title", "body"]
+            }"#,
+            BoolQuery::union(vec![
+                ElasticQueryDslInner::MatchBoolPrefix(MatchBoolPrefixQuery {
+                    field: "title".to_string(),
+                    params: MatchQueryParams {
+                        query: "quick brown f".to_string(),
+                        operator: crate::BooleanOperand::Or,
+                        zero_terms_query: Default::default(),
+                        _lenient: false,
+                    },
+                }),
+                ElasticQueryDslInner::MatchBoolPrefix(MatchBoolPrefixQuery {
+                    field: "body".to_string(),
+                    params: MatchQueryParams {
+                        query: "quick brown f".to_string(),
+                        operator: crate::BooleanOperand::Or,
+                        zero_terms_query: Default::default(),
+                        _lenient: false,
+                    },
+                }),
+            ]),
+        );
+    }
+
     #[test]
     fn test_multimatch_unsupported() {
         test_multimatch_query_err_aux(
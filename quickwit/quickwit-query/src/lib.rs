@@ -39,14 +39,15 @@ pub use elastic_query_dsl::{ElasticQueryDsl, OneFieldMap};
 pub use error::InvalidQuery;
 pub use json_literal::{InterpretUserInput, JsonLiteral};
 pub(crate) use not_nan_f32::NotNaNf32;
-pub use query_ast::utils::find_field_or_hit_dynamic;
+pub use query_ast::utils::{find_field_or_hit_dynamic, index_prefixes_term};
 use serde::{Deserialize, Serialize};
 pub use tantivy::query::Query as TantivyQuery;
 #[cfg(feature = "multilang")]
 pub use tokenizers::MultiLangTokenizer;
 pub use tokenizers::{
     create_default_quickwit_tokenizer_manager, get_quickwit_fastfield_normalizer_manager,
-    CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH,
+    index_prefixes_field_name, index_prefixes_tokenizer_name, CodeTokenizer,
+    IndexPrefixesTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH,
 };
 
 #[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
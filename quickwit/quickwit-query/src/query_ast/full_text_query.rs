@@ -31,7 +31,7 @@ use tantivy::tokenizer::{TextAnalyzer, TokenStream};
 use tantivy::Term;
 
 use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
-use crate::query_ast::utils::full_text_query;
+use crate::query_ast::utils::{full_text_query, index_prefixes_term};
 use crate::query_ast::{BuildTantivyAst, QueryAst};
 use crate::tokenizers::TokenizerManager;
 use crate::{find_field_or_hit_dynamic, BooleanOperand, InvalidQuery, MatchAllOrNone};
@@ -109,11 +109,13 @@ impl FullTextParams {
         &self,
         mut terms: Vec<(usize, Term)>,
         index_record_option: IndexRecordOption,
+        schema: &TantivySchema,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         if terms.is_empty() {
             return Ok(self.zero_terms_query.into());
         }
-        if terms.len() == 1 {
+        // In bool prefix mode, a single term still has to be matched as a prefix.
+        if terms.len() == 1 && !matches!(self.mode, FullTextMode::BoolPrefix { .. }) {
             let term = terms.pop().unwrap().1;
             return Ok(TantivyTermQuery::new(term, IndexRecordOption::WithFreqs).into());
         }
@@ -134,11 +136,17 @@ impl FullTextParams {
                     .into_iter()
                     .map(|(_, term)| TantivyTermQuery::new(term, index_record_option).into())
                     .collect();
-                if let Some(term_with_prefix) = term_with_prefix {
-                    let mut phrase_prefix_query =
-                        TantivyPhrasePrefixQuery::new_with_offset(vec![term_with_prefix]);
-                    phrase_prefix_query.set_max_expansions(max_expansions);
-                    leaf_queries.push(phrase_prefix_query.into());
+                if let Some((_, term_with_prefix)) = term_with_prefix {
+                    if let Some(prefixes_term) = index_prefixes_term(schema, &term_with_prefix) {
+                        leaf_queries.push(
+                            TantivyTermQuery::new(prefixes_term, IndexRecordOption::Basic).into(),
+                        );
+                    } else {
+                        let mut phrase_prefix_query =
+                            TantivyPhrasePrefixQuery::new_with_offset(vec![(0, term_with_prefix)]);
+                        phrase_prefix_query.set_max_expansions(max_expansions);
+                        leaf_queries.push(phrase_prefix_query.into());
+                    }
                 }
                 Ok(TantivyBoolQuery::build_clause(operator, leaf_queries).into())
             }
@@ -361,6 +369,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bool_prefix_mode_single_term() {
+        let full_text_query = FullTextQuery {
+            field: "body".to_string(),
+            text: "Hel".to_string(),
+            params: super::FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::BoolPrefix {
+                    operator: BooleanOperand::Or,
+                    max_expansions: 50,
+                },
+                zero_terms_query: crate::MatchAllOrNone::MatchNone,
+            },
+        };
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let ast: TantivyQueryAst = full_text_query
+            .build_tantivy_ast_call(
+                &schema,
+                &create_default_quickwit_tokenizer_manager(),
+                &[],
+                true,
+            )
+            .unwrap();
+        // A single term must still be matched as a prefix.
+        let ast_str = format!("{ast:?}");
+        assert!(ast_str.contains("PhrasePrefixQuery"), "{ast_str}");
+        assert!(ast_str.contains("\"hel\""), "{ast_str}");
+    }
+
     #[test]
     fn test_full_text_specific_tokenizer() {
         let full_text_query = FullTextQuery {
@@ -0,0 +1,118 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::FuzzyTermQuery as TantivyFuzzyTermQuery;
+use tantivy::schema::Schema as TantivySchema;
+
+use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::utils::extract_single_term;
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::InvalidQuery;
+
+/// Maximum edit distance supported by the Levenshtein automata of tantivy.
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// A fuzzy term query matches the terms within a given Levenshtein distance of the normalized
+/// value, for instance `bond` with `bomd~1`. A transposition of two adjacent characters counts as
+/// a single edit.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct FuzzyTermQuery {
+    pub field: String,
+    pub value: String,
+    pub distance: u8,
+}
+
+impl From<FuzzyTermQuery> for QueryAst {
+    fn from(fuzzy_term_query: FuzzyTermQuery) -> Self {
+        Self::FuzzyTerm(fuzzy_term_query)
+    }
+}
+
+impl BuildTantivyAst for FuzzyTermQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        _search_fields: &[String],
+        _with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        if self.distance > MAX_FUZZY_DISTANCE {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "fuzzy distance must be lower or equal to {MAX_FUZZY_DISTANCE}, got {}",
+                self.distance
+            )));
+        }
+        let (_, term) =
+            extract_single_term(&self.field, &self.value, "fuzzy", schema, tokenizer_manager)?;
+        let fuzzy_term_query = TantivyFuzzyTermQuery::new(term, self.distance, true);
+        Ok(fuzzy_term_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+
+    #[test]
+    fn test_fuzzy_term_query() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_u64_field("count", tantivy::schema::INDEXED);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let fuzzy_term_query = FuzzyTermQuery {
+            field: "body".to_string(),
+            value: "Bomd".to_string(),
+            distance: 1,
+        };
+        let tantivy_query_ast = fuzzy_term_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap();
+        let leaf = tantivy_query_ast.as_leaf().unwrap();
+        let debug_str = format!("{leaf:?}");
+        assert!(debug_str.contains("FuzzyTermQuery"));
+        assert!(debug_str.contains("bomd"));
+
+        let fuzzy_term_query = FuzzyTermQuery {
+            field: "body".to_string(),
+            value: "bomd".to_string(),
+            distance: 3,
+        };
+        let error = fuzzy_term_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap_err();
+        assert!(error.to_string().contains("fuzzy distance"));
+
+        let fuzzy_term_query = FuzzyTermQuery {
+            field: "count".to_string(),
+            value: "10".to_string(),
+            distance: 1,
+        };
+        let error = fuzzy_term_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+}
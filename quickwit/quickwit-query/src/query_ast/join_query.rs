@@ -0,0 +1,332 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::{EmptyScorer, EnableScoring, Explanation, Scorer, Weight};
+use tantivy::schema::Schema as TantivySchema;
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BuildTantivyAst, QueryAst};
+use crate::tokenizers::TokenizerManager;
+use crate::{find_field_or_hit_dynamic, InvalidQuery, TantivyQuery};
+
+/// Block-join style query matching the documents sharing their join key with at least one
+/// document matching the underlying query.
+///
+/// For instance, on an index of spans, a join query on `trace_id` with a `error:true` underlying
+/// query matches all the spans of the traces containing an error.
+///
+/// The join is evaluated within each split: for the result to be exact, the documents sharing
+/// a join key must end up in the same split, which is usually achieved by partitioning the index
+/// on the join key.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JoinQuery {
+    /// Name of the field holding the join key. It must be a fast field.
+    pub join_field: String,
+    pub query: Box<QueryAst>,
+}
+
+impl From<JoinQuery> for QueryAst {
+    fn from(join_query: JoinQuery) -> Self {
+        QueryAst::Join(join_query)
+    }
+}
+
+impl BuildTantivyAst for JoinQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        search_fields: &[String],
+        with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let (_field, field_entry, path) = find_field_or_hit_dynamic(&self.join_field, schema)?;
+        if !field_entry.is_fast() {
+            return Err(InvalidQuery::SchemaError(format!(
+                "join field `{}` is not a fast field",
+                self.join_field
+            )));
+        }
+        let join_column_name = if path.is_empty() {
+            field_entry.name().to_string()
+        } else {
+            format!("{}.{}", field_entry.name(), path)
+        };
+        let underlying = self.query.build_tantivy_ast_call(
+            schema,
+            tokenizer_manager,
+            search_fields,
+            with_validation,
+        )?;
+        let join_query = TantivyJoinQuery {
+            join_column_name,
+            underlying: underlying.simplify().into(),
+        };
+        Ok(join_query.into())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TantivyJoinQuery {
+    join_column_name: String,
+    underlying: Box<dyn TantivyQuery>,
+}
+
+impl TantivyQuery for TantivyJoinQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        // The underlying query only selects the join keys, so its score is irrelevant.
+        let underlying_enable_scoring =
+            EnableScoring::disabled_from_schema(enable_scoring.schema());
+        let underlying_weight = self.underlying.weight(underlying_enable_scoring)?;
+        Ok(Box::new(JoinWeight {
+            join_column_name: self.join_column_name.clone(),
+            underlying_weight,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.underlying.query_terms(visitor);
+    }
+}
+
+struct JoinWeight {
+    join_column_name: String,
+    underlying_weight: Box<dyn Weight>,
+}
+
+impl JoinWeight {
+    /// Returns the sorted list of the documents of the segment sharing their join key with at
+    /// least one document matching the underlying query.
+    fn matching_docs(&self, segment_reader: &SegmentReader) -> tantivy::Result<Vec<DocId>> {
+        // String columns are read through their term ordinals, which are consistent within a
+        // segment, so all join keys can be compared as `u64` values.
+        let Some((join_column, _column_type)) = segment_reader
+            .fast_fields()
+            .u64_lenient(&self.join_column_name)?
+        else {
+            return Ok(Vec::new());
+        };
+        let alive_bitset_opt = segment_reader.alive_bitset();
+        let is_alive =
+            |doc: DocId| alive_bitset_opt.map_or(true, |alive_bitset| alive_bitset.is_alive(doc));
+
+        let mut join_keys: HashSet<u64> = HashSet::new();
+        let mut underlying_scorer = self.underlying_weight.scorer(segment_reader, 1.0)?;
+        let mut doc = underlying_scorer.doc();
+
+        while doc != TERMINATED {
+            if is_alive(doc) {
+                join_keys.extend(join_column.values_for_doc(doc));
+            }
+            doc = underlying_scorer.advance();
+        }
+        if join_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let matching_docs = (0..segment_reader.max_doc())
+            .filter(|doc| {
+                join_column
+                    .values_for_doc(*doc)
+                    .any(|join_key| join_keys.contains(&join_key))
+            })
+            .collect();
+        Ok(matching_docs)
+    }
+}
+
+impl Weight for JoinWeight {
+    fn scorer(
+        &self,
+        segment_reader: &SegmentReader,
+        boost: Score,
+    ) -> tantivy::Result<Box<dyn Scorer>> {
+        let matching_docs = self.matching_docs(segment_reader)?;
+
+        if matching_docs.is_empty() {
+            return Ok(Box::new(EmptyScorer));
+        }
+        Ok(Box::new(JoinScorer {
+            matching_docs,
+            cursor: 0,
+            score: boost,
+        }))
+    }
+
+    fn explain(&self, segment_reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(segment_reader, 1.0)?;
+
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("JoinQuery", scorer.score()))
+    }
+}
+
+struct JoinScorer {
+    matching_docs: Vec<DocId>,
+    cursor: usize,
+    score: Score,
+}
+
+impl DocSet for JoinScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.matching_docs.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.matching_docs
+            .get(self.cursor)
+            .copied()
+            .unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        (self.matching_docs.len() - self.cursor) as u32
+    }
+}
+
+impl Scorer for JoinScorer {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, DocSetCollector};
+    use tantivy::schema::{Schema, FAST, STRING};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+    use crate::query_ast::TermQuery;
+
+    fn build_join_query_ast(join_field: &str, field: &str, value: &str) -> QueryAst {
+        JoinQuery {
+            join_field: join_field.to_string(),
+            query: Box::new(
+                TermQuery {
+                    field: field.to_string(),
+                    value: value.to_string(),
+                }
+                .into(),
+            ),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_join_query_matches_siblings() {
+        let mut schema_builder = Schema::builder();
+        let trace_id_field = schema_builder.add_text_field("trace_id", STRING | FAST);
+        let status_field = schema_builder.add_text_field("status", STRING);
+        let span_id_field = schema_builder.add_u64_field("span_id", FAST);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for (trace_id, status, span_id) in [
+            ("trace-1", "ok", 0u64),
+            ("trace-1", "error", 1u64),
+            ("trace-2", "ok", 2u64),
+            ("trace-3", "error", 3u64),
+            ("trace-3", "ok", 4u64),
+            ("trace-3", "ok", 5u64),
+        ] {
+            index_writer
+                .add_document(doc!(
+                    trace_id_field => trace_id,
+                    status_field => status,
+                    span_id_field => span_id,
+                ))
+                .unwrap();
+        }
+        // Documents without a join key never match.
+        index_writer
+            .add_document(doc!(status_field => "error"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let query = build_join_query_ast("trace_id", "status", "error")
+            .build_tantivy_query(&schema, &tokenizer_manager, &[], true)
+            .unwrap();
+        let doc_addresses = searcher.search(&query, &DocSetCollector).unwrap();
+        let mut doc_ids: Vec<DocId> = doc_addresses
+            .into_iter()
+            .map(|doc_address| doc_address.doc_id)
+            .collect();
+        doc_ids.sort();
+        assert_eq!(doc_ids, [0, 1, 3, 4, 5]);
+
+        let query = build_join_query_ast("trace_id", "status", "unknown")
+            .build_tantivy_query(&schema, &tokenizer_manager, &[], true)
+            .unwrap();
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 0);
+
+        // Numeric join keys are supported as well.
+        let query = build_join_query_ast("span_id", "status", "error")
+            .build_tantivy_query(&schema, &tokenizer_manager, &[], true)
+            .unwrap();
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_join_query_requires_fast_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("trace_id", STRING);
+        schema_builder.add_text_field("status", STRING);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let error = build_join_query_ast("trace_id", "status", "error")
+            .build_tantivy_ast_impl(&schema, &tokenizer_manager, &[], true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+
+    #[test]
+    fn test_join_query_serde() {
+        let join_query_ast = build_join_query_ast("trace_id", "status", "error");
+        let join_query_json = serde_json::to_value(&join_query_ast).unwrap();
+        assert_eq!(
+            join_query_json,
+            serde_json::json!({
+                "type": "join",
+                "join_field": "trace_id",
+                "query": {
+                    "type": "term",
+                    "field": "status",
+                    "value": "error"
+                }
+            })
+        );
+        let deserialized_join_query_ast: QueryAst =
+            serde_json::from_value(join_query_json).unwrap();
+        assert_eq!(deserialized_join_query_ast, join_query_ast);
+    }
+}
@@ -26,8 +26,11 @@ use crate::tokenizers::TokenizerManager;
 mod bool_query;
 mod field_presence;
 mod full_text_query;
+mod fuzzy_term_query;
+mod join_query;
 mod phrase_prefix_query;
 mod range_query;
+mod regex_query;
 mod tantivy_query_ast;
 mod term_query;
 mod term_set_query;
@@ -39,8 +42,11 @@ mod wildcard_query;
 pub use bool_query::BoolQuery;
 pub use field_presence::FieldPresenceQuery;
 pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery};
+pub use fuzzy_term_query::{FuzzyTermQuery, MAX_FUZZY_DISTANCE};
+pub use join_query::JoinQuery;
 pub use phrase_prefix_query::PhrasePrefixQuery;
 pub use range_query::RangeQuery;
+pub use regex_query::RegexQuery;
 use tantivy_query_ast::TantivyQueryAst;
 pub use term_query::TermQuery;
 pub use term_set_query::TermSetQuery;
@@ -63,6 +69,9 @@ pub enum QueryAst {
     Range(RangeQuery),
     UserInput(UserInputQuery),
     Wildcard(WildcardQuery),
+    FuzzyTerm(FuzzyTermQuery),
+    Regex(RegexQuery),
+    Join(JoinQuery),
     MatchAll,
     MatchNone,
     Boost {
@@ -103,10 +112,20 @@ impl QueryAst {
             | ast @ QueryAst::MatchNone
             | ast @ QueryAst::FieldPresence(_)
             | ast @ QueryAst::Range(_)
-            | ast @ QueryAst::Wildcard(_) => Ok(ast),
+            | ast @ QueryAst::Wildcard(_)
+            | ast @ QueryAst::FuzzyTerm(_)
+            | ast @ QueryAst::Regex(_) => Ok(ast),
             QueryAst::UserInput(user_text_query) => {
                 user_text_query.parse_user_query(default_search_fields)
             }
+            QueryAst::Join(JoinQuery { join_field, query }) => {
+                let query = query.parse_user_query(default_search_fields)?;
+                Ok(JoinQuery {
+                    join_field,
+                    query: Box::new(query),
+                }
+                .into())
+            }
             QueryAst::Boost { underlying, boost } => {
                 let underlying = underlying.parse_user_query(default_search_fields)?;
                 Ok(QueryAst::Boost {
@@ -247,6 +266,24 @@ impl BuildTantivyAst for QueryAst {
                 search_fields,
                 with_validation,
             ),
+            QueryAst::FuzzyTerm(fuzzy_term) => fuzzy_term.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+            ),
+            QueryAst::Regex(regex) => regex.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+            ),
+            QueryAst::Join(join_query) => join_query.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+            ),
         }
     }
 }
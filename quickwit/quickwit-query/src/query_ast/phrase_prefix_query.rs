@@ -18,14 +18,16 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use serde::{Deserialize, Serialize};
-use tantivy::query::PhrasePrefixQuery as TantivyPhrasePrefixQuery;
-use tantivy::schema::{Field, FieldType, Schema as TantivySchema};
+use tantivy::query::{
+    PhrasePrefixQuery as TantivyPhrasePrefixQuery, TermQuery as TantivyTermQuery,
+};
+use tantivy::schema::{Field, FieldType, IndexRecordOption, Schema as TantivySchema};
 use tantivy::Term;
 
 use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
 use crate::query_ast::{BuildTantivyAst, FullTextParams, QueryAst};
 use crate::tokenizers::TokenizerManager;
-use crate::{find_field_or_hit_dynamic, InvalidQuery};
+use crate::{find_field_or_hit_dynamic, index_prefixes_term, InvalidQuery};
 
 /// The PhraseQuery node is meant to be tokenized and searched.
 ///
@@ -101,6 +103,19 @@ impl PhrasePrefixQuery {
             )),
         }
     }
+
+    /// Returns the term to search in the prefixes field of the targeted field if the phrase
+    /// consists of a single prefix that can be looked up there directly.
+    pub fn index_prefixes_term(
+        &self,
+        schema: &TantivySchema,
+        terms: &[(usize, Term)],
+    ) -> Option<Term> {
+        let [(_, prefix_term)] = terms else {
+            return None;
+        };
+        index_prefixes_term(schema, prefix_term)
+    }
 }
 
 impl From<PhrasePrefixQuery> for QueryAst {
@@ -125,6 +140,8 @@ impl BuildTantivyAst for PhrasePrefixQuery {
             } else {
                 Ok(TantivyQueryAst::match_all())
             }
+        } else if let Some(prefixes_term) = self.index_prefixes_term(schema, &terms) {
+            Ok(TantivyTermQuery::new(prefixes_term, IndexRecordOption::Basic).into())
         } else {
             let mut phrase_prefix_query = TantivyPhrasePrefixQuery::new_with_offset(terms);
             phrase_prefix_query.set_max_expansions(self.max_expansions);
@@ -0,0 +1,159 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tantivy::query::RegexQuery as TantivyRegexQuery;
+use tantivy::schema::{FieldType, Schema as TantivySchema};
+use tantivy_fst::{Automaton, Regex};
+
+use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::{find_field_or_hit_dynamic, InvalidQuery};
+
+/// A regex query matches the terms of a text field against a regular expression, for instance
+/// `bond` with `b[aeiou]nd`. The regular expression must match the whole term, and is not
+/// normalized: it is matched against the terms as they were indexed.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct RegexQuery {
+    pub field: String,
+    pub regex: String,
+}
+
+impl From<RegexQuery> for QueryAst {
+    fn from(regex_query: RegexQuery) -> Self {
+        Self::Regex(regex_query)
+    }
+}
+
+impl RegexQuery {
+    fn compile(&self) -> anyhow::Result<Regex> {
+        Regex::new(&self.regex).with_context(|| format!("invalid regex `{}`", self.regex))
+    }
+
+    /// Compiles the regex and checks that the automaton matched against the term dictionary has
+    /// at most `max_num_states` states.
+    pub fn validate_automaton(&self, max_num_states: usize) -> anyhow::Result<()> {
+        let regex = self.compile()?;
+        let mut visited_states = HashSet::new();
+        let mut states_to_visit = vec![regex.start()];
+
+        while let Some(state) = states_to_visit.pop() {
+            if !regex.can_match(&state) || !visited_states.insert(state) {
+                continue;
+            }
+            if visited_states.len() > max_num_states {
+                anyhow::bail!(
+                    "the automaton of regex `{}` exceeds the limit of {max_num_states} states",
+                    self.regex
+                );
+            }
+            for byte in 0..=u8::MAX {
+                states_to_visit.push(regex.accept(&state, byte));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BuildTantivyAst for RegexQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _tokenizer_manager: &TokenizerManager,
+        _search_fields: &[String],
+        _with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let (field, field_entry, _json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
+
+        let FieldType::Str(text_options) = field_entry.field_type() else {
+            return Err(InvalidQuery::SchemaError(format!(
+                "trying to run a regex query on field `{}`, which is not a text field",
+                self.field
+            )));
+        };
+        if text_options.get_indexing_options().is_none() {
+            return Err(InvalidQuery::SchemaError(format!(
+                "field {} is not full-text searchable",
+                field_entry.name()
+            )));
+        }
+        let regex = self.compile()?;
+        let regex_query = TantivyRegexQuery::from_regex(regex, field);
+        Ok(regex_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+
+    fn regex_query(regex: &str) -> RegexQuery {
+        RegexQuery {
+            field: "body".to_string(),
+            regex: regex.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_regex_query() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_json_field("attributes", TEXT);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let tantivy_query_ast = regex_query("b[aeiou]nd")
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap();
+        let leaf = tantivy_query_ast.as_leaf().unwrap();
+        assert!(format!("{leaf:?}").contains("RegexQuery"));
+
+        let error = regex_query("b[aeiou")
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid regex"));
+
+        let json_regex_query = RegexQuery {
+            field: "attributes.color".to_string(),
+            regex: "bl.*".to_string(),
+        };
+        let error = json_regex_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+
+    #[test]
+    fn test_regex_query_validate_automaton() {
+        regex_query("bond").validate_automaton(10).unwrap();
+        regex_query("bond").validate_automaton(2).unwrap_err();
+        regex_query("[a-z]{1,20}").validate_automaton(100).unwrap();
+        regex_query("[a-z]{1,20}")
+            .validate_automaton(10)
+            .unwrap_err();
+        regex_query("b[aeiou").validate_automaton(100).unwrap_err();
+    }
+}
@@ -31,12 +31,17 @@ use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
 use crate::query_ast::{
     self, BuildTantivyAst, FieldPresenceQuery, FullTextMode, FullTextParams, QueryAst,
+    MAX_FUZZY_DISTANCE,
 };
 use crate::tokenizers::TokenizerManager;
 use crate::{BooleanOperand, InvalidQuery, JsonLiteral};
 
 const DEFAULT_PHRASE_QUERY_MAX_EXPANSION: u32 = 50;
 
+/// Private use character standing for a regex in the user text handed to the tantivy query
+/// grammar, which does not support regexes.
+const REGEX_PLACEHOLDER: char = '\u{E000}';
+
 /// A query expressed in the tantivy query grammar DSL.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct UserInputQuery {
@@ -67,13 +72,14 @@ impl UserInputQuery {
             .as_ref()
             .map(|search_fields| &search_fields[..])
             .unwrap_or(default_search_fields);
-        let user_input_ast = tantivy::query_grammar::parse_query(&self.user_text)
+        let (user_text, regexes) = extract_regexes(&self.user_text)?;
+        let user_input_ast = tantivy::query_grammar::parse_query(&user_text)
             .map_err(|_| anyhow::anyhow!("failed to parse query: `{}`", &self.user_text))?;
         let default_occur = match self.default_operator {
             BooleanOperand::And => Occur::Must,
             BooleanOperand::Or => Occur::Should,
         };
-        convert_user_input_ast_to_query_ast(user_input_ast, default_occur, search_fields)
+        convert_user_input_ast_to_query_ast(user_input_ast, default_occur, search_fields, &regexes)
     }
 }
 
@@ -95,10 +101,98 @@ impl BuildTantivyAst for UserInputQuery {
     }
 }
 
+/// Returns true if a term can start right after `prev_char_opt`.
+fn is_term_start(prev_char_opt: Option<char>) -> bool {
+    match prev_char_opt {
+        Some(prev_char) => prev_char.is_whitespace() || ['(', ':', '+', '-'].contains(&prev_char),
+        None => true,
+    }
+}
+
+/// Parses the regex starting with the slash at position `start` and returns it along with the
+/// position following its closing slash. Within a regex, `\/` stands for a slash.
+fn parse_regex(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut regex = String::new();
+    let mut pos = start + 1;
+
+    while pos < chars.len() {
+        match chars[pos] {
+            '\\' if chars.get(pos + 1) == Some(&'/') => {
+                regex.push('/');
+                pos += 2;
+            }
+            '\\' if pos + 1 < chars.len() => {
+                regex.push('\\');
+                regex.push(chars[pos + 1]);
+                pos += 2;
+            }
+            '/' => {
+                let is_term_end = match chars.get(pos + 1) {
+                    Some(next_char) => next_char.is_whitespace() || [')', '^'].contains(next_char),
+                    None => true,
+                };
+                if regex.is_empty() || !is_term_end {
+                    return None;
+                }
+                return Some((regex, pos + 1));
+            }
+            c => {
+                regex.push(c);
+                pos += 1;
+            }
+        }
+    }
+    None
+}
+
+/// Replaces the regexes of the user text, delimited by slashes as in `field:/b[aeiou]nd/`, with
+/// placeholders that the tantivy query grammar parses as regular words, and returns them.
+fn extract_regexes(user_text: &str) -> anyhow::Result<(String, Vec<String>)> {
+    if user_text.contains(REGEX_PLACEHOLDER) {
+        anyhow::bail!("query contains the reserved character `U+E000`");
+    }
+    let chars: Vec<char> = user_text.chars().collect();
+    let mut text = String::with_capacity(user_text.len());
+    let mut regexes = Vec::new();
+    let mut quote_opt: Option<char> = None;
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let prev_char_opt = pos.checked_sub(1).map(|prev_pos| chars[prev_pos]);
+
+        if c == '\\' && pos + 1 < chars.len() {
+            text.push(c);
+            text.push(chars[pos + 1]);
+            pos += 2;
+            continue;
+        }
+        if let Some(quote) = quote_opt {
+            if c == quote {
+                quote_opt = None;
+            }
+        } else if (c == '"' || c == '\'') && is_term_start(prev_char_opt) {
+            quote_opt = Some(c);
+        } else if c == '/' && is_term_start(prev_char_opt) {
+            if let Some((regex, end)) = parse_regex(&chars, pos) {
+                text.push(REGEX_PLACEHOLDER);
+                text.push_str(&regexes.len().to_string());
+                regexes.push(regex);
+                pos = end;
+                continue;
+            }
+        }
+        text.push(c);
+        pos += 1;
+    }
+    Ok((text, regexes))
+}
+
 fn convert_user_input_ast_to_query_ast(
     user_input_ast: UserInputAst,
     default_occur: Occur,
     default_search_fields: &[String],
+    regexes: &[String],
 ) -> anyhow::Result<QueryAst> {
     match user_input_ast {
         UserInputAst::Clause(clause) => {
@@ -108,6 +202,7 @@ fn convert_user_input_ast_to_query_ast(
                     sub_ast,
                     default_occur,
                     default_search_fields,
+                    regexes,
                 )?;
                 let children_ast_for_occur: &mut Vec<QueryAst> =
                     match occur_opt.unwrap_or(default_occur) {
@@ -121,7 +216,7 @@ fn convert_user_input_ast_to_query_ast(
         }
         UserInputAst::Leaf(leaf) => match *leaf {
             UserInputLeaf::Literal(literal) => {
-                convert_user_input_literal(literal, default_search_fields)
+                convert_user_input_literal(literal, default_search_fields, regexes)
             }
             UserInputLeaf::All => Ok(QueryAst::MatchAll),
             UserInputLeaf::Range {
@@ -170,6 +265,7 @@ fn convert_user_input_ast_to_query_ast(
                 *underlying,
                 default_occur,
                 default_search_fields,
+                regexes,
             )?;
             let boost: NotNaNf32 = (boost as f32)
                 .try_into()
@@ -207,9 +303,58 @@ fn is_wildcard(phrase: &str) -> bool {
         .is_break()
 }
 
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped_char) = chars.next() {
+                unescaped.push(escaped_char);
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Parses a fuzzy term such as `bomd~1`. Depending on the word, the grammar parses the distance
+/// as the slop of the literal or leaves it at the end of the phrase. A missing distance, as in
+/// `bomd~`, stands for the maximum distance.
+fn parse_fuzzy_term(phrase: &str, slop: u32) -> anyhow::Result<Option<(String, u8)>> {
+    let (value, distance) = if slop > 0 {
+        (phrase, slop)
+    } else if let Some((value, distance_str)) = phrase.rsplit_once('~') {
+        if value.is_empty() || value.ends_with('\\') {
+            return Ok(None);
+        }
+        let distance = if distance_str.is_empty() {
+            MAX_FUZZY_DISTANCE as u32
+        } else if let Ok(distance) = distance_str.parse() {
+            distance
+        } else {
+            return Ok(None);
+        };
+        (value, distance)
+    } else {
+        return Ok(None);
+    };
+    if distance > MAX_FUZZY_DISTANCE as u32 {
+        anyhow::bail!(
+            "fuzzy distance must be lower or equal to {MAX_FUZZY_DISTANCE}, got {distance}"
+        );
+    }
+    if is_wildcard(value) {
+        anyhow::bail!("fuzzy queries cannot contain wildcards");
+    }
+    Ok(Some((unescape(value), distance as u8)))
+}
+
 fn convert_user_input_literal(
     user_input_literal: UserInputLiteral,
     default_search_fields: &[String],
+    regexes: &[String],
 ) -> anyhow::Result<QueryAst> {
     let UserInputLiteral {
         field_name,
@@ -241,11 +386,39 @@ fn convert_user_input_literal(
         mode,
         zero_terms_query: crate::MatchAllOrNone::MatchNone,
     };
+    let mut regex_opt: Option<&String> = None;
+    let mut fuzzy_term_opt: Option<(String, u8)> = None;
+
+    if delimiter == Delimiter::None {
+        if let Some(regex_ord) = phrase.strip_prefix(REGEX_PLACEHOLDER) {
+            let regex = regex_ord
+                .parse::<usize>()
+                .ok()
+                .and_then(|regex_ord| regexes.get(regex_ord))
+                .context("failed to parse regex")?;
+            regex_opt = Some(regex);
+        } else if !prefix {
+            fuzzy_term_opt = parse_fuzzy_term(&phrase, slop)?;
+        }
+    }
     let wildcard = delimiter == Delimiter::None && is_wildcard(&phrase);
     let mut phrase_queries: Vec<QueryAst> = field_names
         .into_iter()
         .map(|field_name| {
-            if prefix {
+            if let Some(regex) = regex_opt {
+                query_ast::RegexQuery {
+                    field: field_name,
+                    regex: regex.clone(),
+                }
+                .into()
+            } else if let Some((value, distance)) = &fuzzy_term_opt {
+                query_ast::FuzzyTermQuery {
+                    field: field_name,
+                    value: value.clone(),
+                    distance: *distance,
+                }
+                .into()
+            } else if prefix {
                 query_ast::PhrasePrefixQuery {
                     field: field_name,
                     phrase: phrase.clone(),
@@ -284,11 +457,22 @@ fn convert_user_input_literal(
 
 #[cfg(test)]
 mod tests {
+    use super::extract_regexes;
     use crate::query_ast::{
-        BoolQuery, BuildTantivyAst, FullTextMode, FullTextQuery, QueryAst, UserInputQuery,
+        BoolQuery, BuildTantivyAst, FullTextMode, FullTextQuery, FuzzyTermQuery, QueryAst,
+        RegexQuery, UserInputQuery,
     };
     use crate::{create_default_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery};
 
+    fn parse_user_query(user_text: &str) -> anyhow::Result<QueryAst> {
+        UserInputQuery {
+            user_text: user_text.to_string(),
+            default_fields: None,
+            default_operator: BooleanOperand::And,
+        }
+        .parse_user_query(&["body".to_string()])
+    }
+
     #[test]
     fn test_user_input_query_not_parsed_error() {
         let user_input_query = UserInputQuery {
@@ -505,4 +689,89 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_user_input_query_fuzzy_term() {
+        let ast = parse_user_query("title:bomd~1").unwrap();
+        assert_eq!(
+            ast,
+            QueryAst::FuzzyTerm(FuzzyTermQuery {
+                field: "title".to_string(),
+                value: "bomd".to_string(),
+                distance: 1,
+            })
+        );
+        let ast = parse_user_query("bomd~").unwrap();
+        assert_eq!(
+            ast,
+            QueryAst::FuzzyTerm(FuzzyTermQuery {
+                field: "body".to_string(),
+                value: "bomd".to_string(),
+                distance: 2,
+            })
+        );
+        let error = parse_user_query("title:bomd~3").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "fuzzy distance must be lower or equal to 2, got 3"
+        );
+        let error = parse_user_query("title:bo*d~1").unwrap_err();
+        assert_eq!(error.to_string(), "fuzzy queries cannot contain wildcards");
+
+        let ast = parse_user_query("title:\"bomd~1\"").unwrap();
+        assert!(matches!(ast, QueryAst::FullText(_)));
+    }
+
+    #[test]
+    fn test_user_input_query_regex() {
+        let ast = parse_user_query("title:/b[aeiou]nd/").unwrap();
+        assert_eq!(
+            ast,
+            QueryAst::Regex(RegexQuery {
+                field: "title".to_string(),
+                regex: "b[aeiou]nd".to_string(),
+            })
+        );
+        let ast = parse_user_query("/(bond|james) 00\\d/^2 AND title:hello").unwrap();
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else {
+            panic!()
+        };
+        assert_eq!(must.len(), 2);
+        let QueryAst::Boost { underlying, .. } = &must[0] else {
+            panic!()
+        };
+        assert_eq!(
+            **underlying,
+            QueryAst::Regex(RegexQuery {
+                field: "body".to_string(),
+                regex: "(bond|james) 00\\d".to_string(),
+            })
+        );
+        let ast = parse_user_query("path:/var/log/syslog").unwrap();
+        let QueryAst::FullText(full_text_query) = ast else {
+            panic!()
+        };
+        assert_eq!(full_text_query.text, "/var/log/syslog");
+
+        parse_user_query("title:\u{E000}0").unwrap_err();
+    }
+
+    #[test]
+    fn test_extract_regexes() {
+        let (text, regexes) = extract_regexes("hello").unwrap();
+        assert_eq!(text, "hello");
+        assert!(regexes.is_empty());
+
+        let (text, regexes) = extract_regexes("title:/a\\/b/ -(/c d/)").unwrap();
+        assert_eq!(text, "title:\u{E000}0 -(\u{E000}1)");
+        assert_eq!(regexes, ["a/b", "c d"]);
+
+        let (text, regexes) = extract_regexes("title:\"/a/\" 'b /c/' /d/").unwrap();
+        assert_eq!(text, "title:\"/a/\" 'b /c/' \u{E000}0");
+        assert_eq!(regexes, ["d"]);
+
+        let (text, regexes) = extract_regexes("a/b/ /c/d //").unwrap();
+        assert_eq!(text, "a/b/ /c/d //");
+        assert!(regexes.is_empty());
+    }
 }
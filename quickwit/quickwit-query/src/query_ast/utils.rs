@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use anyhow::Context;
 use tantivy::json_utils::convert_to_fast_value_and_append_to_json_term;
 use tantivy::query::TermQuery as TantivyTermQuery;
 use tantivy::schema::{
@@ -28,7 +29,9 @@ use tantivy::Term;
 use crate::json_literal::InterpretUserInput;
 use crate::query_ast::full_text_query::FullTextParams;
 use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
-use crate::tokenizers::TokenizerManager;
+use crate::tokenizers::{
+    index_prefixes_field_name, parse_index_prefixes_tokenizer_name, TokenizerManager,
+};
 use crate::InvalidQuery;
 
 const DYNAMIC_FIELD_NAME: &str = "_dynamic";
@@ -68,6 +71,108 @@ pub fn find_field_or_hit_dynamic<'a>(
     Ok((field, field_entry, path))
 }
 
+/// Returns the term to search in the field indexing the prefixes of the tokens of the field of
+/// `prefix_term`, if the field has `index_prefixes` enabled and the length of the prefix is within
+/// the indexed range. Looking up this term is much cheaper than expanding the prefix over the
+/// term dictionary of the field.
+pub fn index_prefixes_term(schema: &TantivySchema, prefix_term: &Term) -> Option<Term> {
+    let prefix = prefix_term.value().as_str()?;
+    let field_name = schema.get_field_name(prefix_term.field());
+    let prefixes_field = schema
+        .get_field(&index_prefixes_field_name(field_name))
+        .ok()?;
+    let FieldType::Str(text_options) = schema.get_field_entry(prefixes_field).field_type() else {
+        return None;
+    };
+    let tokenizer_name = text_options.get_indexing_options()?.tokenizer();
+    let (_, min_chars, max_chars) = parse_index_prefixes_tokenizer_name(tokenizer_name)?;
+    let prefix_num_chars = prefix.chars().count();
+
+    if prefix_num_chars < min_chars || prefix_num_chars > max_chars {
+        return None;
+    }
+    Some(Term::from_field_text(prefixes_field, prefix))
+}
+
+fn extract_unique_token(mut tokens: Vec<Term>, query_kind: &str) -> anyhow::Result<Term> {
+    let term = tokens
+        .pop()
+        .with_context(|| format!("{query_kind} query generated no term"))?;
+    if !tokens.is_empty() {
+        anyhow::bail!("{query_kind} query generated more than one term");
+    }
+    Ok(term)
+}
+
+/// Normalizes `text` with the tokenizer of the text or JSON field `full_path` and returns the
+/// single term it produces. `query_kind` is only used in error messages.
+pub(crate) fn extract_single_term(
+    full_path: &str,
+    text: &str,
+    query_kind: &str,
+    schema: &TantivySchema,
+    tokenizer_manager: &TokenizerManager,
+) -> Result<(Field, Term), InvalidQuery> {
+    let (field, field_entry, json_path) = find_field_or_hit_dynamic(full_path, schema)?;
+
+    match field_entry.field_type() {
+        FieldType::Str(ref text_options) => {
+            let text_field_indexing = text_options.get_indexing_options().ok_or_else(|| {
+                InvalidQuery::SchemaError(format!(
+                    "field {} is not full-text searchable",
+                    field_entry.name()
+                ))
+            })?;
+            let tokenizer_name = text_field_indexing.tokenizer();
+            let mut normalizer = tokenizer_manager
+                .get_normalizer(tokenizer_name)
+                .with_context(|| {
+                    format!("no tokenizer named `{}` is registered", tokenizer_name)
+                })?;
+            let mut token_stream = normalizer.token_stream(text);
+            let mut tokens = Vec::new();
+            token_stream.process(&mut |token| {
+                let term: Term = Term::from_field_text(field, &token.text);
+                tokens.push(term);
+            });
+            let term = extract_unique_token(tokens, query_kind)?;
+            Ok((field, term))
+        }
+        FieldType::JsonObject(json_options) => {
+            let text_field_indexing =
+                json_options.get_text_indexing_options().ok_or_else(|| {
+                    InvalidQuery::SchemaError(format!(
+                        "field {} is not full-text searchable",
+                        field_entry.name()
+                    ))
+                })?;
+            let tokenizer_name = text_field_indexing.tokenizer();
+            let mut normalizer = tokenizer_manager
+                .get_normalizer(tokenizer_name)
+                .with_context(|| {
+                    format!("no tokenizer named `{}` is registered", tokenizer_name)
+                })?;
+            let mut token_stream = normalizer.token_stream(text);
+            let mut tokens = Vec::new();
+
+            token_stream.process(&mut |token| {
+                let mut term = Term::from_field_json_path(
+                    field,
+                    json_path,
+                    json_options.is_expand_dots_enabled(),
+                );
+                term.append_type_and_str(&token.text);
+                tokens.push(term);
+            });
+            let term = extract_unique_token(tokens, query_kind)?;
+            Ok((field, term))
+        }
+        _ => Err(InvalidQuery::SchemaError(format!(
+            "trying to run a {query_kind} query on a non-text field"
+        ))),
+    }
+}
+
 /// Creates a full text query.
 ///
 /// If tokenize is set to true, the text will be tokenized.
@@ -85,6 +190,7 @@ pub(crate) fn full_text_query(
         path,
         text_query,
         full_text_params,
+        schema,
         tokenizer_manager,
     )
 }
@@ -109,6 +215,7 @@ fn compute_query_with_field(
     json_path: &str,
     value: &str,
     full_text_params: &FullTextParams,
+    schema: &TantivySchema,
     tokenizer_manager: &TokenizerManager,
 ) -> Result<TantivyQueryAst, InvalidQuery> {
     let field_type = field_entry.field_type();
@@ -151,7 +258,7 @@ fn compute_query_with_field(
                 text_field_indexing,
                 tokenizer_manager,
             )?;
-            full_text_params.make_query(terms, text_field_indexing.index_option())
+            full_text_params.make_query(terms, text_field_indexing.index_option(), schema)
         }
         FieldType::IpAddr(_) => {
             let ip_v6 = parse_value_from_user_text(value, field_entry.name())?;
@@ -164,6 +271,7 @@ fn compute_query_with_field(
             value,
             full_text_params,
             json_options,
+            schema,
             tokenizer_manager,
         ),
         FieldType::Facet(_) => Err(InvalidQuery::SchemaError(
@@ -183,6 +291,7 @@ fn compute_tantivy_ast_query_for_json(
     text: &str,
     full_text_params: &FullTextParams,
     json_options: &JsonObjectOptions,
+    schema: &TantivySchema,
     tokenizer_manager: &TokenizerManager,
 ) -> Result<TantivyQueryAst, InvalidQuery> {
     let mut bool_query = TantivyBoolQuery::default();
@@ -203,8 +312,10 @@ fn compute_tantivy_ast_query_for_json(
         .get_text_indexing_options()
         .map(|text_indexing_options| text_indexing_options.index_option())
         .unwrap_or(IndexRecordOption::Basic);
-    bool_query
-        .should
-        .push(full_text_params.make_query(position_terms, index_record_option)?);
+    bool_query.should.push(full_text_params.make_query(
+        position_terms,
+        index_record_option,
+        schema,
+    )?);
     Ok(bool_query.into())
 }
@@ -21,8 +21,8 @@ use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::field_presence::FieldPresenceQuery;
 use crate::query_ast::user_input_query::UserInputQuery;
 use crate::query_ast::{
-    BoolQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, RangeQuery, TermQuery, TermSetQuery,
-    WildcardQuery,
+    BoolQuery, FullTextQuery, FuzzyTermQuery, JoinQuery, PhrasePrefixQuery, QueryAst, RangeQuery,
+    RegexQuery, TermQuery, TermSetQuery, WildcardQuery,
 };
 
 /// Simple trait to implement a Visitor over the QueryAst.
@@ -45,6 +45,9 @@ pub trait QueryAstVisitor<'a> {
             QueryAst::UserInput(user_text_query) => self.visit_user_text(user_text_query),
             QueryAst::FieldPresence(exists) => self.visit_exists(exists),
             QueryAst::Wildcard(wildcard) => self.visit_wildcard(wildcard),
+            QueryAst::FuzzyTerm(fuzzy_term) => self.visit_fuzzy_term(fuzzy_term),
+            QueryAst::Regex(regex) => self.visit_regex(regex),
+            QueryAst::Join(join_query) => self.visit_join(join_query),
         }
     }
 
@@ -111,6 +114,18 @@ pub trait QueryAstVisitor<'a> {
     fn visit_wildcard(&mut self, _wildcard_query: &'a WildcardQuery) -> Result<(), Self::Err> {
         Ok(())
     }
+
+    fn visit_fuzzy_term(&mut self, _fuzzy_term_query: &'a FuzzyTermQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, _regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_join(&mut self, join_query: &'a JoinQuery) -> Result<(), Self::Err> {
+        self.visit(&join_query.query)
+    }
 }
 
 /// Simple trait to implement a Visitor over the QueryAst.
@@ -133,6 +148,9 @@ pub trait QueryAstTransformer {
             QueryAst::UserInput(user_text_query) => self.transform_user_text(user_text_query),
             QueryAst::FieldPresence(exists) => self.transform_exists(exists),
             QueryAst::Wildcard(wildcard) => self.transform_wildcard(wildcard),
+            QueryAst::FuzzyTerm(fuzzy_term) => self.transform_fuzzy_term(fuzzy_term),
+            QueryAst::Regex(regex) => self.transform_regex(regex),
+            QueryAst::Join(join_query) => self.transform_join(join_query),
         }
     }
 
@@ -231,4 +249,28 @@ pub trait QueryAstTransformer {
     ) -> Result<Option<QueryAst>, Self::Err> {
         Ok(Some(QueryAst::Wildcard(wildcard_query)))
     }
+
+    fn transform_fuzzy_term(
+        &mut self,
+        fuzzy_term_query: FuzzyTermQuery,
+    ) -> Result<Option<QueryAst>, Self::Err> {
+        Ok(Some(QueryAst::FuzzyTerm(fuzzy_term_query)))
+    }
+
+    fn transform_regex(&mut self, regex_query: RegexQuery) -> Result<Option<QueryAst>, Self::Err> {
+        Ok(Some(QueryAst::Regex(regex_query)))
+    }
+
+    fn transform_join(&mut self, join_query: JoinQuery) -> Result<Option<QueryAst>, Self::Err> {
+        let JoinQuery { join_field, query } = join_query;
+        self.transform(*query).map(|maybe_ast| {
+            maybe_ast.map(|query| {
+                JoinQuery {
+                    join_field,
+                    query: Box::new(query),
+                }
+                .into()
+            })
+        })
+    }
 }
@@ -17,15 +17,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use anyhow::{anyhow, bail, Context};
+use anyhow::{anyhow, bail};
 use serde::{Deserialize, Serialize};
-use tantivy::schema::{Field, FieldType, Schema as TantivySchema};
+use tantivy::schema::{Field, Schema as TantivySchema};
 use tantivy::Term;
 
 use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::utils::extract_single_term;
 use crate::query_ast::TantivyQueryAst;
 use crate::tokenizers::TokenizerManager;
-use crate::{find_field_or_hit_dynamic, InvalidQuery};
+use crate::InvalidQuery;
 
 /// A Wildcard query allows to match 'bond' with a query like 'b*d'.
 ///
@@ -52,16 +53,6 @@ impl WildcardQuery {
     }
 }
 
-fn extract_unique_token(mut tokens: Vec<Term>) -> anyhow::Result<Term> {
-    let term = tokens
-        .pop()
-        .with_context(|| "wildcard query generated no term")?;
-    if !tokens.is_empty() {
-        anyhow::bail!("wildcard query generated more than one term");
-    }
-    Ok(term)
-}
-
 fn unescape_with_final_wildcard(phrase: &str) -> anyhow::Result<String> {
     enum State {
         Normal,
@@ -118,67 +109,8 @@ impl WildcardQuery {
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
     ) -> Result<(Field, Term), InvalidQuery> {
-        let (field, field_entry, json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
-        let field_type = field_entry.field_type();
-
         let prefix = unescape_with_final_wildcard(&self.value)?;
-
-        match field_type {
-            FieldType::Str(ref text_options) => {
-                let text_field_indexing = text_options.get_indexing_options().ok_or_else(|| {
-                    InvalidQuery::SchemaError(format!(
-                        "field {} is not full-text searchable",
-                        field_entry.name()
-                    ))
-                })?;
-                let tokenizer_name = text_field_indexing.tokenizer();
-                let mut normalizer = tokenizer_manager
-                    .get_normalizer(tokenizer_name)
-                    .with_context(|| {
-                        format!("no tokenizer named `{}` is registered", tokenizer_name)
-                    })?;
-                let mut token_stream = normalizer.token_stream(&prefix);
-                let mut tokens = Vec::new();
-                token_stream.process(&mut |token| {
-                    let term: Term = Term::from_field_text(field, &token.text);
-                    tokens.push(term);
-                });
-                let term = extract_unique_token(tokens)?;
-                Ok((field, term))
-            }
-            FieldType::JsonObject(json_options) => {
-                let text_field_indexing =
-                    json_options.get_text_indexing_options().ok_or_else(|| {
-                        InvalidQuery::SchemaError(format!(
-                            "field {} is not full-text searchable",
-                            field_entry.name()
-                        ))
-                    })?;
-                let tokenizer_name = text_field_indexing.tokenizer();
-                let mut normalizer = tokenizer_manager
-                    .get_normalizer(tokenizer_name)
-                    .with_context(|| {
-                        format!("no tokenizer named `{}` is registered", tokenizer_name)
-                    })?;
-                let mut token_stream = normalizer.token_stream(&prefix);
-                let mut tokens = Vec::new();
-
-                token_stream.process(&mut |token| {
-                    let mut term = Term::from_field_json_path(
-                        field,
-                        json_path,
-                        json_options.is_expand_dots_enabled(),
-                    );
-                    term.append_type_and_str(&token.text);
-                    tokens.push(term);
-                });
-                let term = extract_unique_token(tokens)?;
-                Ok((field, term))
-            }
-            _ => Err(InvalidQuery::SchemaError(
-                "trying to run a Wildcard query on a non-text field".to_string(),
-            )),
-        }
+        extract_single_term(&self.field, &prefix, "wildcard", schema, tokenizer_manager)
     }
 }
 
@@ -0,0 +1,181 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::tokenizer::{TextAnalyzer, Token, TokenStream, Tokenizer};
+
+/// Suffix appended to the name of a text field to get the name of the hidden field in which
+/// the prefixes of its tokens are indexed.
+pub const INDEX_PREFIXES_FIELD_SUFFIX: &str = "#prefixes";
+
+const INDEX_PREFIXES_TOKENIZER_INFIX: &str = "#prefixes_";
+
+/// Returns the name of the field holding the prefixes of the tokens of `field_name`.
+pub fn index_prefixes_field_name(field_name: &str) -> String {
+    format!("{field_name}{INDEX_PREFIXES_FIELD_SUFFIX}")
+}
+
+/// Returns the name under which the tokenizer emitting the prefixes of `min_chars` to
+/// `max_chars` characters of the tokens of `tokenizer_name` is registered.
+pub fn index_prefixes_tokenizer_name(
+    tokenizer_name: &str,
+    min_chars: usize,
+    max_chars: usize,
+) -> String {
+    format!("{tokenizer_name}{INDEX_PREFIXES_TOKENIZER_INFIX}{min_chars}_{max_chars}")
+}
+
+/// Parses a tokenizer name built with [`index_prefixes_tokenizer_name`] into the name of the
+/// underlying tokenizer and the range of prefix lengths.
+pub fn parse_index_prefixes_tokenizer_name(tokenizer_name: &str) -> Option<(&str, usize, usize)> {
+    let (base_tokenizer_name, prefix_lengths) =
+        tokenizer_name.rsplit_once(INDEX_PREFIXES_TOKENIZER_INFIX)?;
+    let (min_chars_str, max_chars_str) = prefix_lengths.split_once('_')?;
+    let min_chars: usize = min_chars_str.parse().ok()?;
+    let max_chars: usize = max_chars_str.parse().ok()?;
+    if min_chars == 0 || min_chars > max_chars {
+        return None;
+    }
+    Some((base_tokenizer_name, min_chars, max_chars))
+}
+
+/// Emits, for each token produced by the wrapped analyzer, its prefixes of `min_chars` to
+/// `max_chars` characters. The prefixes keep the position and offsets of the original token.
+#[derive(Clone)]
+pub struct IndexPrefixesTokenizer {
+    analyzer: TextAnalyzer,
+    min_chars: usize,
+    max_chars: usize,
+}
+
+impl IndexPrefixesTokenizer {
+    pub fn new(analyzer: TextAnalyzer, min_chars: usize, max_chars: usize) -> Self {
+        assert!(min_chars > 0 && min_chars <= max_chars);
+        Self {
+            analyzer,
+            min_chars,
+            max_chars,
+        }
+    }
+}
+
+impl Tokenizer for IndexPrefixesTokenizer {
+    type TokenStream<'a> = IndexPrefixesTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let mut tokens = Vec::new();
+        let mut token_stream = self.analyzer.token_stream(text);
+
+        while let Some(token) = token_stream.next() {
+            let prefix_lens = token
+                .text
+                .char_indices()
+                .map(|(char_offset, chr)| char_offset + chr.len_utf8())
+                .skip(self.min_chars - 1)
+                .take(self.max_chars - self.min_chars + 1);
+
+            for prefix_len in prefix_lens {
+                let mut prefix_token = token.clone();
+                prefix_token.text.truncate(prefix_len);
+                tokens.push(prefix_token);
+            }
+        }
+        IndexPrefixesTokenStream {
+            tokens,
+            num_advances: 0,
+        }
+    }
+}
+
+pub struct IndexPrefixesTokenStream {
+    tokens: Vec<Token>,
+    num_advances: usize,
+}
+
+impl TokenStream for IndexPrefixesTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.num_advances == self.tokens.len() {
+            return false;
+        }
+        self.num_advances += 1;
+        true
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.num_advances - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.num_advances - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::tokenizer::{LowerCaser, SimpleTokenizer};
+
+    use super::*;
+
+    #[test]
+    fn test_index_prefixes_tokenizer_name() {
+        let tokenizer_name = index_prefixes_tokenizer_name("default", 2, 5);
+        assert_eq!(tokenizer_name, "default#prefixes_2_5");
+        assert_eq!(
+            parse_index_prefixes_tokenizer_name(&tokenizer_name),
+            Some(("default", 2, 5))
+        );
+        assert_eq!(parse_index_prefixes_tokenizer_name("default"), None);
+        assert_eq!(
+            parse_index_prefixes_tokenizer_name("default#prefixes_2"),
+            None
+        );
+        assert_eq!(
+            parse_index_prefixes_tokenizer_name("default#prefixes_0_5"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_index_prefixes_tokenizer() {
+        let analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(LowerCaser)
+            .build();
+        let mut tokenizer = IndexPrefixesTokenizer::new(analyzer, 2, 4);
+        let mut token_stream = tokenizer.token_stream("Quick é brûlée");
+        let mut tokens = Vec::new();
+
+        while let Some(token) = token_stream.next() {
+            tokens.push((token.text.clone(), token.position));
+        }
+        let expected_tokens = [
+            ("qu", 0),
+            ("qui", 0),
+            ("quic", 0),
+            ("br", 2),
+            ("brû", 2),
+            ("brûl", 2),
+        ];
+        assert_eq!(
+            tokens,
+            expected_tokens
+                .iter()
+                .map(|(text, position)| (text.to_string(), *position))
+                .collect::<Vec<_>>()
+        );
+    }
+}
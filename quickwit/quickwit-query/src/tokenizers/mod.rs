@@ -19,6 +19,7 @@
 
 mod chinese_compatible;
 mod code_tokenizer;
+mod index_prefixes;
 #[cfg(feature = "multilang")]
 mod multilang;
 mod tokenizer_manager;
@@ -31,6 +32,10 @@ use tantivy::tokenizer::{
 
 use self::chinese_compatible::ChineseTokenizer;
 pub use self::code_tokenizer::CodeTokenizer;
+pub use self::index_prefixes::{
+    index_prefixes_field_name, index_prefixes_tokenizer_name, parse_index_prefixes_tokenizer_name,
+    IndexPrefixesTokenizer, INDEX_PREFIXES_FIELD_SUFFIX,
+};
 #[cfg(feature = "multilang")]
 pub use self::multilang::MultiLangTokenizer;
 pub use self::tokenizer_manager::TokenizerManager;
@@ -25,9 +25,11 @@ use quickwit_config::{ConfigFormat, SourceConfig};
 use quickwit_indexing::actors::IndexingServiceCounters;
 pub use quickwit_ingest::CommitType;
 use quickwit_metastore::{IndexMetadata, Split, SplitInfo};
+use quickwit_proto::control_plane::{DecommissionIngesterResponse, ListShardsResponse};
 use quickwit_search::SearchResponseRest;
 use quickwit_serve::{
-    IndexUpdates, ListSplitsQueryParams, ListSplitsResponse, SearchRequestQueryString,
+    IndexUpdates, ListShardsQueryParams, ListSplitsQueryParams, ListSplitsResponse,
+    SearchRequestQueryString,
 };
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use reqwest::{Client, ClientBuilder, Method, StatusCode, Url};
@@ -242,6 +244,14 @@ impl QuickwitClient {
         SourceClient::new(&self.transport, self.timeout, index_id)
     }
 
+    pub fn shards(&self) -> ShardClient {
+        ShardClient::new(&self.transport, self.timeout)
+    }
+
+    pub fn ingesters(&self) -> IngesterClient {
+        IngesterClient::new(&self.transport, self.timeout)
+    }
+
     pub fn cluster(&self) -> ClusterClient {
         ClusterClient::new(&self.transport, self.timeout)
     }
@@ -576,6 +586,59 @@ impl<'a> SourceClient<'a> {
     }
 }
 
+/// Client for shard APIs.
+pub struct ShardClient<'a> {
+    transport: &'a Transport,
+    timeout: Timeout,
+}
+
+impl<'a> ShardClient<'a> {
+    fn new(transport: &'a Transport, timeout: Timeout) -> Self {
+        Self { transport, timeout }
+    }
+
+    pub async fn list(
+        &self,
+        list_shards_query_params: ListShardsQueryParams,
+    ) -> Result<ListShardsResponse, Error> {
+        let response = self
+            .transport
+            .send(
+                Method::GET,
+                "control-plane/shards",
+                None,
+                Some(&list_shards_query_params),
+                None,
+                self.timeout,
+            )
+            .await?;
+        let list_shards_response = response.deserialize().await?;
+        Ok(list_shards_response)
+    }
+}
+
+/// Client for ingester APIs.
+pub struct IngesterClient<'a> {
+    transport: &'a Transport,
+    timeout: Timeout,
+}
+
+impl<'a> IngesterClient<'a> {
+    fn new(transport: &'a Transport, timeout: Timeout) -> Self {
+        Self { transport, timeout }
+    }
+
+    pub async fn decommission(&self, node_id: &str) -> Result<DecommissionIngesterResponse, Error> {
+        let path = format!("control-plane/ingesters/{node_id}/decommission");
+        let response = self
+            .transport
+            .send::<()>(Method::POST, &path, None, None, None, self.timeout)
+            .await?;
+        let decommission_ingester_response = response.deserialize().await?;
+        Ok(decommission_ingester_response)
+    }
+}
+
 /// Client for Cluster APIs.
 pub struct ClusterClient<'a> {
     transport: &'a Transport,
@@ -676,8 +739,15 @@ mod test {
     use quickwit_indexing::mock_split;
     use quickwit_ingest::CommitType;
     use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::control_plane::{
+        DecommissionIngesterResponse, ListShardsResponse, ShardInfo,
+    };
+    use quickwit_proto::ingest::{Shard, ShardState};
+    use quickwit_proto::types::{IndexUid, ShardId};
     use quickwit_search::SearchResponseRest;
-    use quickwit_serve::{ListSplitsQueryParams, ListSplitsResponse, SearchRequestQueryString};
+    use quickwit_serve::{
+        ListShardsQueryParams, ListSplitsQueryParams, ListSplitsResponse, SearchRequestQueryString,
+    };
     use reqwest::header::CONTENT_TYPE;
     use reqwest::{StatusCode, Url};
     use serde_json::json;
@@ -716,9 +786,12 @@ mod test {
             num_hits: 0,
             hits: Vec::new(),
             snippets: None,
+            highlights: None,
             aggregations: None,
             elapsed_time_micros: 100,
             errors: Vec::new(),
+            profile: None,
+            failed_indexes: Vec::new(),
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/search"))
@@ -1045,6 +1118,84 @@ mod test {
             .unwrap_err();
     }
 
+    #[tokio::test]
+    async fn test_shards_endpoints() {
+        let mock_server = MockServer::start().await;
+        let server_url = Url::parse(&mock_server.uri()).unwrap();
+        let qw_client = QuickwitClientBuilder::new(server_url).build();
+        // GET shards
+        let list_shards_params = ListShardsQueryParams {
+            index_id: Some("my-index".to_string()),
+            shard_state: Some(ShardState::Open),
+            ..Default::default()
+        };
+        let response = ListShardsResponse {
+            shards: vec![ShardInfo {
+                shard: Some(Shard {
+                    index_uid: Some(IndexUid::new_with_random_ulid("my-index")),
+                    source_id: "my-source".to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    leader_id: "my-ingester".to_string(),
+                    shard_state: ShardState::Open as i32,
+                    ..Default::default()
+                }),
+                ingestion_rate_mib_per_sec: 1,
+            }],
+            num_matching_shards: 1,
+        };
+        Mock::given(method("GET"))
+            .and(path("/api/v1/control-plane/shards"))
+            .and(query_param("index_id", "my-index"))
+            .and(query_param("shard_state", "open"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(&response))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        assert_eq!(
+            qw_client.shards().list(list_shards_params).await.unwrap(),
+            response
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingesters_endpoints() {
+        let mock_server = MockServer::start().await;
+        let server_url = Url::parse(&mock_server.uri()).unwrap();
+        let qw_client = QuickwitClientBuilder::new(server_url).build();
+        // POST decommission ingester
+        let response = DecommissionIngesterResponse {
+            num_remaining_shards: 2,
+            is_decommissioned: false,
+        };
+        Mock::given(method("POST"))
+            .and(path(
+                "/api/v1/control-plane/ingesters/my-ingester/decommission",
+            ))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(&response))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        assert_eq!(
+            qw_client
+                .ingesters()
+                .decommission("my-ingester")
+                .await
+                .unwrap(),
+            response
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/v1/control-plane/ingesters/unknown/decommission"))
+            .respond_with(ResponseTemplate::new(StatusCode::NOT_FOUND))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        qw_client
+            .ingesters()
+            .decommission("unknown")
+            .await
+            .unwrap_err();
+    }
+
     #[tokio::test]
     async fn test_sources_endpoints() {
         let mock_server = MockServer::start().await;
@@ -0,0 +1,400 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+
+/// A parsed `bucket_script` expression.
+///
+/// Painless scripts are not supported: the scripts are arithmetic expressions over the variables
+/// declared in `buckets_path`, referred to as `params.<name>` or simply `<name>`, supporting the
+/// `+`, `-`, `*`, `/`, and `%` operators, parentheses, numeric literals, and the `Math.abs`,
+/// `Math.ceil`, `Math.exp`, `Math.floor`, `Math.log`, `Math.log10`, `Math.max`, `Math.min`,
+/// `Math.pow`, `Math.round`, and `Math.sqrt` functions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ScriptExpr {
+    Number(f64),
+    Variable(String),
+    Neg(Box<ScriptExpr>),
+    Binary(BinaryOp, Box<ScriptExpr>, Box<ScriptExpr>),
+    Call(MathFunction, Vec<ScriptExpr>),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum MathFunction {
+    Abs,
+    Ceil,
+    Exp,
+    Floor,
+    Log,
+    Log10,
+    Max,
+    Min,
+    Pow,
+    Round,
+    Sqrt,
+}
+
+impl MathFunction {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        let function = match name {
+            "abs" => Self::Abs,
+            "ceil" => Self::Ceil,
+            "exp" => Self::Exp,
+            "floor" => Self::Floor,
+            "log" => Self::Log,
+            "log10" => Self::Log10,
+            "max" => Self::Max,
+            "min" => Self::Min,
+            "pow" => Self::Pow,
+            "round" => Self::Round,
+            "sqrt" => Self::Sqrt,
+            _ => bail!("unknown function `Math.{name}`"),
+        };
+        Ok(function)
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Self::Max | Self::Min | Self::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(&self, args: &[f64]) -> f64 {
+        match self {
+            Self::Abs => args[0].abs(),
+            Self::Ceil => args[0].ceil(),
+            Self::Exp => args[0].exp(),
+            Self::Floor => args[0].floor(),
+            Self::Log => args[0].ln(),
+            Self::Log10 => args[0].log10(),
+            Self::Max => args[0].max(args[1]),
+            Self::Min => args[0].min(args[1]),
+            Self::Pow => args[0].powf(args[1]),
+            Self::Round => args[0].round(),
+            Self::Sqrt => args[0].sqrt(),
+        }
+    }
+}
+
+impl ScriptExpr {
+    /// Parses a script. A leading `return` keyword and a trailing semicolon are tolerated.
+    pub fn parse(script: &str) -> anyhow::Result<Self> {
+        let mut source = script.trim();
+        source = source.strip_suffix(';').unwrap_or(source).trim_end();
+
+        if let Some(stripped_source) = source.strip_prefix("return ") {
+            source = stripped_source;
+        }
+        let tokens = tokenize(source).with_context(|| format!("invalid script `{script}`"))?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser
+            .parse_additive()
+            .with_context(|| format!("invalid script `{script}`"))?;
+
+        if let Some(token) = parser.peek() {
+            bail!("invalid script `{script}`: unexpected token `{token:?}`");
+        }
+        Ok(expr)
+    }
+
+    /// Calls `visitor` on the names of the variables referenced by the expression.
+    pub fn visit_variables<'a>(&'a self, visitor: &mut impl FnMut(&'a str)) {
+        match self {
+            Self::Number(_) => {}
+            Self::Variable(name) => visitor(name),
+            Self::Neg(expr) => expr.visit_variables(visitor),
+            Self::Binary(_, left, right) => {
+                left.visit_variables(visitor);
+                right.visit_variables(visitor);
+            }
+            Self::Call(_, args) => {
+                for arg in args {
+                    arg.visit_variables(visitor);
+                }
+            }
+        }
+    }
+
+    /// Evaluates the expression. Returns `None` if a variable is missing.
+    pub fn eval(&self, variables: &HashMap<&str, f64>) -> Option<f64> {
+        let value = match self {
+            Self::Number(value) => *value,
+            Self::Variable(name) => *variables.get(name.as_str())?,
+            Self::Neg(expr) => -expr.eval(variables)?,
+            Self::Binary(op, left, right) => {
+                let left = left.eval(variables)?;
+                let right = right.eval(variables)?;
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Rem => left % right,
+                }
+            }
+            Self::Call(function, args) => {
+                let args: Vec<f64> = args
+                    .iter()
+                    .map(|arg| arg.eval(variables))
+                    .collect::<Option<_>>()?;
+                function.apply(&args)
+            }
+        };
+        Some(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let mut end = start;
+            while let Some(&(idx, ch)) = chars.peek() {
+                if !ch.is_ascii_digit() && ch != '.' {
+                    break;
+                }
+                end = idx + ch.len_utf8();
+                chars.next();
+            }
+            let literal = &source[start..end];
+            let value: f64 = literal
+                .parse()
+                .with_context(|| format!("invalid number `{literal}`"))?;
+            tokens.push(Token::Number(value));
+        } else if ch.is_alphabetic() || ch == '_' {
+            let mut end = start;
+            while let Some(&(idx, ch)) = chars.peek() {
+                if !ch.is_alphanumeric() && ch != '_' && ch != '.' {
+                    break;
+                }
+                end = idx + ch.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Ident(source[start..end].to_string()));
+        } else {
+            let token = match ch {
+                '+' | '-' | '*' | '/' | '%' => Token::Op(ch),
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                ',' => Token::Comma,
+                _ => bail!("unexpected character `{ch}`"),
+            };
+            tokens.push(token);
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser for the grammar:
+/// ```text
+/// additive       = multiplicative (("+" | "-") multiplicative)*
+/// multiplicative = unary (("*" | "/" | "%") unary)*
+/// unary          = "-" unary | primary
+/// primary        = number | ident | ident "(" args ")" | "(" additive ")"
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_additive(&mut self) -> anyhow::Result<ScriptExpr> {
+        let mut expr = self.parse_multiplicative()?;
+
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek() {
+            let op = if *op == '+' {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Sub
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            expr = ScriptExpr::Binary(op, Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_multiplicative(&mut self) -> anyhow::Result<ScriptExpr> {
+        let mut expr = self.parse_unary()?;
+
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek() {
+            let op = match *op {
+                '*' => BinaryOp::Mul,
+                '/' => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            expr = ScriptExpr::Binary(op, Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> anyhow::Result<ScriptExpr> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            let expr = self.parse_unary()?;
+            return Ok(ScriptExpr::Neg(Box::new(expr)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> anyhow::Result<ScriptExpr> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(ScriptExpr::Number(value)),
+            Some(Token::LeftParen) => {
+                let expr = self.parse_additive()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => {
+                if self.peek() != Some(&Token::LeftParen) {
+                    let name = ident.strip_prefix("params.").unwrap_or(&ident);
+                    return Ok(ScriptExpr::Variable(name.to_string()));
+                }
+                self.pos += 1;
+                let Some(function_name) = ident.strip_prefix("Math.") else {
+                    bail!("unknown function `{ident}`");
+                };
+                let function = MathFunction::parse(function_name)?;
+                let mut args = Vec::new();
+
+                if self.peek() != Some(&Token::RightParen) {
+                    loop {
+                        args.push(self.parse_additive()?);
+
+                        if self.peek() != Some(&Token::Comma) {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                self.expect(Token::RightParen)?;
+
+                if args.len() != function.arity() {
+                    bail!(
+                        "function `{ident}` expects {} argument(s), got {}",
+                        function.arity(),
+                        args.len()
+                    );
+                }
+                Ok(ScriptExpr::Call(function, args))
+            }
+            Some(token) => bail!("unexpected token `{token:?}`"),
+            None => bail!("unexpected end of script"),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> anyhow::Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => bail!("expected `{expected:?}`, got `{token:?}`"),
+            None => bail!("expected `{expected:?}`, got end of script"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(script: &str, variables: &[(&str, f64)]) -> Option<f64> {
+        let variables: HashMap<&str, f64> = variables.iter().copied().collect();
+        ScriptExpr::parse(script).unwrap().eval(&variables)
+    }
+
+    #[test]
+    fn test_script_expr_eval() {
+        assert_eq!(eval("1 + 2 * 3", &[]), Some(7.0));
+        assert_eq!(eval("(1 + 2) * 3", &[]), Some(9.0));
+        assert_eq!(eval("-2 - -3", &[]), Some(1.0));
+        assert_eq!(eval("7 % 4 / 2", &[]), Some(1.5));
+        assert_eq!(
+            eval(
+                "params.errors / params.total * 100",
+                &[("errors", 5.0), ("total", 20.0)]
+            ),
+            Some(25.0)
+        );
+        assert_eq!(eval("return a + b;", &[("a", 1.0), ("b", 0.5)]), Some(1.5));
+        assert_eq!(
+            eval("Math.max(a, 2) + Math.sqrt(16)", &[("a", 1.0)]),
+            Some(6.0)
+        );
+        assert_eq!(eval("Math.pow(2, 10)", &[]), Some(1024.0));
+        assert_eq!(eval("a + 1", &[]), None);
+    }
+
+    #[test]
+    fn test_script_expr_variables() {
+        let expr = ScriptExpr::parse("params.a / (b + Math.abs(params.a))").unwrap();
+        let mut variables = Vec::new();
+        expr.visit_variables(&mut |name| variables.push(name));
+        assert_eq!(variables, ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_script_expr_parse_invalid() {
+        ScriptExpr::parse("").unwrap_err();
+        ScriptExpr::parse("1 +").unwrap_err();
+        ScriptExpr::parse("(1 + 2").unwrap_err();
+        ScriptExpr::parse("1 2").unwrap_err();
+        ScriptExpr::parse("a > b").unwrap_err();
+        ScriptExpr::parse("Math.foo(1)").unwrap_err();
+        ScriptExpr::parse("Math.max(1)").unwrap_err();
+        ScriptExpr::parse("doc['field'].value").unwrap_err();
+    }
+}
@@ -0,0 +1,694 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::columnar::{Column, StrColumn};
+use tantivy::common::BitSet;
+use tantivy::{DocId, Score, SegmentReader};
+
+/// Default value of the `precision_threshold` parameter, which is also the default of
+/// Elasticsearch.
+const DEFAULT_PRECISION_THRESHOLD: u32 = 3_000;
+
+/// Maximum value of the `precision_threshold` parameter. Higher values are clamped.
+const MAX_PRECISION_THRESHOLD: u32 = 40_000;
+
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 18;
+
+/// Computes the approximate number of distinct values of some fast fields among the documents
+/// matching the query.
+///
+/// Each leaf builds a HyperLogLog++ sketch per aggregation, hashing the values of the field, and
+/// the root merges the sketches of all the splits before estimating the cardinality. The sketches
+/// of string fields are built from the terms rather than from the term ordinals, which are local
+/// to a segment.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<String, CardinalityAggregationWrapper>")]
+pub struct CardinalityCollector {
+    /// The cardinality aggregations, by name.
+    pub aggregations: BTreeMap<String, CardinalityAggregation>,
+}
+
+impl TryFrom<BTreeMap<String, CardinalityAggregationWrapper>> for CardinalityCollector {
+    type Error = &'static str;
+
+    fn try_from(
+        aggregations: BTreeMap<String, CardinalityAggregationWrapper>,
+    ) -> Result<Self, Self::Error> {
+        // An empty aggregation request is left to Tantivy.
+        if aggregations.is_empty() {
+            return Err("cardinality collector requires at least one aggregation");
+        }
+        let aggregations = aggregations
+            .into_iter()
+            .map(|(name, wrapper)| (name, wrapper.cardinality))
+            .collect();
+        Ok(Self { aggregations })
+    }
+}
+
+/// A `cardinality` aggregation, as found in the aggregation request.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardinalityAggregationWrapper {
+    cardinality: CardinalityAggregation,
+}
+
+/// A `cardinality` aggregation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardinalityAggregation {
+    /// The fast field whose distinct values are counted.
+    pub field: String,
+    /// The cardinality below which counts are expected to be close to exact. Higher values
+    /// increase the size of the sketches exchanged between the leaves and the root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision_threshold: Option<u32>,
+}
+
+impl CardinalityAggregation {
+    /// Returns the precision of the HyperLogLog++ sketch, that is the number of bits of the hash
+    /// used to pick a register. The mapping from the precision threshold follows Elasticsearch.
+    fn precision(&self) -> u8 {
+        let precision_threshold = self
+            .precision_threshold
+            .unwrap_or(DEFAULT_PRECISION_THRESHOLD)
+            .min(MAX_PRECISION_THRESHOLD) as f64;
+        let hash_table_entries = (precision_threshold / 0.75).ceil();
+        let precision = (hash_table_entries * 4.0).log2().ceil() as u8;
+        precision.clamp(MIN_PRECISION, MAX_PRECISION)
+    }
+}
+
+impl CardinalityCollector {
+    /// Returns the fast fields read by the collector.
+    pub(crate) fn fast_field_names(&self) -> impl Iterator<Item = &str> {
+        self.aggregations
+            .values()
+            .map(|aggregation| aggregation.field.as_str())
+    }
+
+    /// Turns the merged fruit of the collector into the final aggregation result.
+    pub(crate) fn finalize(&self, fruit: Cardinality) -> BTreeMap<String, CardinalityResult> {
+        self.aggregations
+            .iter()
+            .zip(
+                fruit
+                    .sketches
+                    .iter()
+                    .map(Some)
+                    .chain(std::iter::repeat(None)),
+            )
+            .map(|((name, _), sketch_opt)| {
+                let value = sketch_opt.map(HyperLogLog::estimate).unwrap_or(0);
+                (name.clone(), CardinalityResult { value })
+            })
+            .collect()
+    }
+}
+
+/// Returns the name of a `cardinality` aggregation of the request that the
+/// [`CardinalityCollector`] cannot compute, because it is nested under a bucket aggregation or
+/// requested along with other kinds of aggregations.
+pub(crate) fn find_unsupported_cardinality_aggregation(aggregations_json: &str) -> Option<String> {
+    let aggregations: JsonMap<String, JsonValue> = serde_json::from_str(aggregations_json).ok()?;
+
+    if aggregations.values().all(is_cardinality_aggregation) {
+        return None;
+    }
+    find_cardinality_aggregation(&aggregations)
+}
+
+fn is_cardinality_aggregation(aggregation: &JsonValue) -> bool {
+    aggregation.get("cardinality").is_some()
+}
+
+fn find_cardinality_aggregation(aggregations: &JsonMap<String, JsonValue>) -> Option<String> {
+    for (name, aggregation) in aggregations {
+        if is_cardinality_aggregation(aggregation) {
+            return Some(name.clone());
+        }
+        for sub_aggregations_key in ["aggs", "aggregations"] {
+            if let Some(JsonValue::Object(sub_aggregations)) = aggregation.get(sub_aggregations_key)
+            {
+                if let Some(name) = find_cardinality_aggregation(sub_aggregations) {
+                    return Some(name);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Intermediate result of the [`CardinalityCollector`]: one sketch per aggregation, in the order
+/// of the aggregation names.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Cardinality {
+    sketches: Vec<HyperLogLog>,
+}
+
+impl Cardinality {
+    pub(crate) fn merge(&mut self, other: Cardinality) {
+        if self.sketches.is_empty() {
+            self.sketches = other.sketches;
+            return;
+        }
+        for (sketch, other_sketch) in self.sketches.iter_mut().zip(other.sketches) {
+            sketch.merge(other_sketch);
+        }
+    }
+}
+
+pub(crate) fn merge_fruits(fruits: Vec<Cardinality>) -> Cardinality {
+    let mut merged_fruit = Cardinality::default();
+
+    for fruit in fruits {
+        merged_fruit.merge(fruit);
+    }
+    merged_fruit
+}
+
+/// Final result of a `cardinality` aggregation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct CardinalityResult {
+    /// Approximate number of distinct values.
+    pub value: u64,
+}
+
+/// HyperLogLog++ sketch.
+///
+/// The sketch starts with a sparse representation, which only stores the non-empty registers and
+/// keeps small sketches small, and switches to a dense array of registers once it fills up. Small
+/// cardinalities are estimated with linear counting.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Registers,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+enum Registers {
+    Sparse(BTreeMap<u32, u8>),
+    Dense(Vec<u8>),
+}
+
+impl HyperLogLog {
+    /// Creates an empty sketch with `2^precision` registers.
+    pub fn new(precision: u8) -> Self {
+        assert!((MIN_PRECISION..=MAX_PRECISION).contains(&precision));
+        Self {
+            precision,
+            registers: Registers::Sparse(BTreeMap::new()),
+        }
+    }
+
+    fn num_registers(&self) -> usize {
+        1 << self.precision
+    }
+
+    /// Adds a value to the sketch.
+    pub fn insert_bytes(&mut self, bytes: &[u8]) {
+        let mut hasher = fnv::FnvHasher::default();
+        hasher.write(bytes);
+        self.insert_hash(fmix64(hasher.finish()));
+    }
+
+    /// Adds a numerical value, in its fast field representation, to the sketch.
+    pub fn insert_u64(&mut self, value: u64) {
+        self.insert_bytes(&value.to_le_bytes());
+    }
+
+    fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as u32;
+        // The guard bit bounds the rank when the remaining bits of the hash are all zeros.
+        let remaining_bits = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = remaining_bits.leading_zeros() as u8 + 1;
+        self.update_register(index, rank);
+    }
+
+    fn update_register(&mut self, index: u32, rank: u8) {
+        match &mut self.registers {
+            Registers::Sparse(registers) => {
+                let register = registers.entry(index).or_default();
+                *register = (*register).max(rank);
+
+                if registers.len() > self.num_registers() / 8 {
+                    self.densify();
+                }
+            }
+            Registers::Dense(registers) => {
+                let register = &mut registers[index as usize];
+                *register = (*register).max(rank);
+            }
+        }
+    }
+
+    fn densify(&mut self) {
+        let Registers::Sparse(sparse_registers) = &self.registers else {
+            return;
+        };
+        let mut registers = vec![0u8; self.num_registers()];
+
+        for (&index, &rank) in sparse_registers {
+            registers[index as usize] = rank;
+        }
+        self.registers = Registers::Dense(registers);
+    }
+
+    /// Merges another sketch into this one. The result is the sketch of the union of the values.
+    pub fn merge(&mut self, other: HyperLogLog) {
+        // Sketches built from the same request share the same precision. Otherwise, the merge
+        // would be meaningless, so we keep the sketch with the highest precision.
+        if self.precision != other.precision {
+            if other.precision > self.precision {
+                *self = other;
+            }
+            return;
+        }
+        match other.registers {
+            Registers::Sparse(other_registers) => {
+                for (index, rank) in other_registers {
+                    self.update_register(index, rank);
+                }
+            }
+            Registers::Dense(other_registers) => {
+                self.densify();
+
+                if let Registers::Dense(registers) = &mut self.registers {
+                    for (register, other_register) in registers.iter_mut().zip(other_registers) {
+                        *register = (*register).max(other_register);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Estimates the number of distinct values added to the sketch.
+    pub fn estimate(&self) -> u64 {
+        let num_registers = self.num_registers() as f64;
+
+        let (num_zero_registers, sum) = match &self.registers {
+            Registers::Sparse(registers) => {
+                let num_zero_registers = self.num_registers() - registers.len();
+                let sum = num_zero_registers as f64
+                    + registers
+                        .values()
+                        .map(|&rank| 2f64.powi(-(rank as i32)))
+                        .sum::<f64>();
+                (num_zero_registers, sum)
+            }
+            Registers::Dense(registers) => {
+                let num_zero_registers = registers.iter().filter(|&&rank| rank == 0).count();
+                let sum = registers
+                    .iter()
+                    .map(|&rank| 2f64.powi(-(rank as i32)))
+                    .sum::<f64>();
+                (num_zero_registers, sum)
+            }
+        };
+        let alpha = match self.precision {
+            4 => 0.673,
+            5 => 0.697,
+            6 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / num_registers),
+        };
+        let raw_estimate = alpha * num_registers * num_registers / sum;
+
+        let estimate = if raw_estimate <= 2.5 * num_registers && num_zero_registers > 0 {
+            // Linear counting is much more accurate for small cardinalities.
+            num_registers * (num_registers / num_zero_registers as f64).ln()
+        } else {
+            raw_estimate
+        };
+        estimate.round() as u64
+    }
+}
+
+/// Finalizer of MurmurHash3, which spreads the entropy of the FNV hash over all the bits.
+fn fmix64(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+impl Collector for CardinalityCollector {
+    type Fruit = Cardinality;
+    type Child = CardinalitySegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: u32,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let fast_fields = segment_reader.fast_fields();
+        let mut segment_sketches = Vec::with_capacity(self.aggregations.len());
+
+        for aggregation in self.aggregations.values() {
+            let sketch = HyperLogLog::new(aggregation.precision());
+
+            let segment_sketch = if let Some(str_column) = fast_fields.str(&aggregation.field)? {
+                let num_terms = str_column.dictionary().num_terms();
+                SegmentSketch::Str {
+                    term_ords: BitSet::with_max_value(num_terms as u32),
+                    str_column,
+                    sketch,
+                }
+            } else if let Some((column, _)) = fast_fields.u64_lenient(&aggregation.field)? {
+                SegmentSketch::U64 { column, sketch }
+            } else {
+                // The field is absent from the segment.
+                SegmentSketch::Empty { sketch }
+            };
+            segment_sketches.push(segment_sketch);
+        }
+        Ok(CardinalitySegmentCollector { segment_sketches })
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        Ok(merge_fruits(segment_fruits))
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+}
+
+enum SegmentSketch {
+    /// The term ordinals are collected first and resolved into terms once, at harvest time.
+    Str {
+        str_column: StrColumn,
+        term_ords: BitSet,
+        sketch: HyperLogLog,
+    },
+    U64 {
+        column: Column<u64>,
+        sketch: HyperLogLog,
+    },
+    Empty {
+        sketch: HyperLogLog,
+    },
+}
+
+impl SegmentSketch {
+    fn collect(&mut self, doc: DocId) {
+        match self {
+            SegmentSketch::Str {
+                str_column,
+                term_ords,
+                ..
+            } => {
+                for term_ord in str_column.ords().values_for_doc(doc) {
+                    term_ords.insert(term_ord as u32);
+                }
+            }
+            SegmentSketch::U64 { column, sketch } => {
+                for value in column.values_for_doc(doc) {
+                    sketch.insert_u64(value);
+                }
+            }
+            SegmentSketch::Empty { .. } => {}
+        }
+    }
+
+    fn harvest(self) -> HyperLogLog {
+        match self {
+            SegmentSketch::Str {
+                str_column,
+                term_ords,
+                mut sketch,
+            } => {
+                let mut term = Vec::new();
+
+                for term_ord in 0..term_ords.max_value() {
+                    if !term_ords.contains(term_ord) {
+                        continue;
+                    }
+                    term.clear();
+
+                    if let Ok(true) = str_column.ord_to_bytes(term_ord as u64, &mut term) {
+                        sketch.insert_bytes(&term);
+                    }
+                }
+                sketch
+            }
+            SegmentSketch::U64 { sketch, .. } | SegmentSketch::Empty { sketch } => sketch,
+        }
+    }
+}
+
+/// Segment collector of the [`CardinalityCollector`].
+pub struct CardinalitySegmentCollector {
+    segment_sketches: Vec<SegmentSketch>,
+}
+
+impl SegmentCollector for CardinalitySegmentCollector {
+    type Fruit = Cardinality;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        for segment_sketch in &mut self.segment_sketches {
+            segment_sketch.collect(doc);
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let sketches = self
+            .segment_sketches
+            .into_iter()
+            .map(SegmentSketch::harvest)
+            .collect();
+        Cardinality { sketches }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::query::{AllQuery, TermQuery};
+    use tantivy::schema::{IndexRecordOption, Schema, FAST, STRING};
+    use tantivy::{doc, Index, Term};
+
+    use super::*;
+    use crate::collector::QuickwitAggregations;
+
+    #[test]
+    fn test_cardinality_collector_serde() {
+        let aggregation: QuickwitAggregations = serde_json::from_str(
+            r#"{
+                "unique_users": {"cardinality": {"field": "user_id"}},
+                "unique_ips": {"cardinality": {"field": "ip", "precision_threshold": 100}}
+            }"#,
+        )
+        .unwrap();
+        let QuickwitAggregations::CardinalityAggregation(collector) = aggregation else {
+            panic!("expected CardinalityAggregation");
+        };
+        assert_eq!(collector.aggregations.len(), 2);
+        assert_eq!(collector.aggregations["unique_users"].field, "user_id");
+        assert_eq!(collector.aggregations["unique_users"].precision(), 14);
+        assert_eq!(collector.aggregations["unique_ips"].precision(), 10);
+
+        // Cardinality aggregations mixed with other aggregations are left to Tantivy.
+        let aggregation_res = serde_json::from_str::<QuickwitAggregations>(
+            r#"{
+                "unique_users": {"cardinality": {"field": "user_id"}},
+                "severities": {"terms": {"field": "severity"}}
+            }"#,
+        );
+        assert!(!matches!(
+            aggregation_res,
+            Ok(QuickwitAggregations::CardinalityAggregation(_))
+        ));
+        let aggregation: QuickwitAggregations = serde_json::from_str("{}").unwrap();
+        assert!(matches!(
+            aggregation,
+            QuickwitAggregations::TantivyAggregations(_)
+        ));
+    }
+
+    #[test]
+    fn test_find_unsupported_cardinality_aggregation() {
+        assert_eq!(
+            find_unsupported_cardinality_aggregation(
+                r#"{"unique_users": {"cardinality": {"field": "user_id"}}}"#
+            ),
+            None
+        );
+        assert_eq!(
+            find_unsupported_cardinality_aggregation(
+                r#"{"severities": {"terms": {"field": "severity"}}}"#
+            ),
+            None
+        );
+        assert_eq!(
+            find_unsupported_cardinality_aggregation(
+                r#"{
+                    "unique_users": {"cardinality": {"field": "user_id"}},
+                    "severities": {"terms": {"field": "severity"}}
+                }"#
+            ),
+            Some("unique_users".to_string())
+        );
+        assert_eq!(
+            find_unsupported_cardinality_aggregation(
+                r#"{
+                    "services": {
+                        "terms": {"field": "service"},
+                        "aggs": {
+                            "per_day": {
+                                "date_histogram": {"field": "timestamp", "fixed_interval": "1d"},
+                                "aggregations": {
+                                    "unique_users": {"cardinality": {"field": "user_id"}}
+                                }
+                            }
+                        }
+                    }
+                }"#
+            ),
+            Some("unique_users".to_string())
+        );
+        assert_eq!(find_unsupported_cardinality_aggregation("not json"), None);
+    }
+
+    #[test]
+    fn test_cardinality_aggregation_precision() {
+        let precision = |precision_threshold: u32| {
+            CardinalityAggregation {
+                field: "field".to_string(),
+                precision_threshold: Some(precision_threshold),
+            }
+            .precision()
+        };
+        assert_eq!(precision(0), MIN_PRECISION);
+        assert_eq!(precision(1), MIN_PRECISION);
+        assert_eq!(precision(3_000), 14);
+        assert_eq!(precision(40_000), MAX_PRECISION);
+        assert_eq!(precision(u32::MAX), MAX_PRECISION);
+    }
+
+    #[test]
+    fn test_hyper_log_log_accuracy() {
+        for num_values in [0, 1, 10, 1_000, 100_000] {
+            let mut sketch = HyperLogLog::new(14);
+
+            for value in 0..num_values {
+                sketch.insert_u64(value);
+                // Duplicates do not change the estimate.
+                sketch.insert_u64(value);
+            }
+            let estimate = sketch.estimate() as f64;
+            let error = (estimate - num_values as f64).abs() / (num_values as f64).max(1.0);
+            assert!(
+                error < 0.05,
+                "estimate {estimate} is too far from {num_values}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_hyper_log_log_merge() {
+        let mut left_sketch = HyperLogLog::new(12);
+        let mut right_sketch = HyperLogLog::new(12);
+        let mut sketch = HyperLogLog::new(12);
+
+        for value in 0..20_000u64 {
+            if value < 15_000 {
+                left_sketch.insert_u64(value);
+            }
+            if value >= 5_000 {
+                right_sketch.insert_u64(value);
+            }
+            sketch.insert_u64(value);
+        }
+        let mut small_sketch = HyperLogLog::new(12);
+        small_sketch.insert_u64(7);
+
+        left_sketch.merge(right_sketch);
+        left_sketch.merge(small_sketch);
+        assert_eq!(left_sketch, sketch);
+
+        let mut sparse_sketch = HyperLogLog::new(12);
+        sparse_sketch.insert_u64(1);
+        sparse_sketch.merge(sketch.clone());
+        assert_eq!(sparse_sketch, sketch);
+    }
+
+    #[test]
+    fn test_cardinality_collector() {
+        let mut schema_builder = Schema::builder();
+        let user_field = schema_builder.add_text_field("user", STRING | FAST);
+        let status_field = schema_builder.add_u64_field("status", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+
+        // Two segments with overlapping users, to check that terms rather than term ordinals are
+        // counted.
+        for (user, status) in [("alice", 200u64), ("bob", 200), ("carol", 500)] {
+            index_writer
+                .add_document(doc!(user_field => user, status_field => status))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        for user in ["bob", "dave"] {
+            index_writer.add_document(doc!(user_field => user)).unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        let searcher = index.reader().unwrap().searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let collector: CardinalityCollector = serde_json::from_str(
+            r#"{
+                "unique_users": {"cardinality": {"field": "user"}},
+                "unique_statuses": {"cardinality": {"field": "status"}},
+                "unique_missing": {"cardinality": {"field": "missing"}}
+            }"#,
+        )
+        .unwrap();
+        let fruit = searcher.search(&AllQuery, &collector).unwrap();
+        let result = collector.finalize(fruit);
+        assert_eq!(result["unique_users"].value, 4);
+        assert_eq!(result["unique_statuses"].value, 2);
+        assert_eq!(result["unique_missing"].value, 0);
+
+        let fruit = searcher
+            .search(
+                &TermQuery::new(
+                    Term::from_field_text(user_field, "bob"),
+                    IndexRecordOption::Basic,
+                ),
+                &collector,
+            )
+            .unwrap();
+        let result = collector.finalize(fruit);
+        assert_eq!(result["unique_users"].value, 1);
+        assert_eq!(result["unique_statuses"].value, 1);
+    }
+}
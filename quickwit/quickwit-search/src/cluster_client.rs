@@ -281,6 +281,9 @@ fn merge_leaf_search_response(
     left_response
         .partial_hits
         .extend(right_response.partial_hits);
+    left_response
+        .split_profiles
+        .extend(right_response.split_profiles);
     let intermediate_aggregation_result: Option<Vec<u8>> = match (
         left_response.intermediate_aggregation_result,
         right_response.intermediate_aggregation_result,
@@ -301,6 +304,7 @@ fn merge_leaf_search_response(
             + right_response.num_attempted_splits,
         failed_splits: right_response.failed_splits,
         partial_hits: left_response.partial_hits,
+        split_profiles: left_response.split_profiles,
     })
 }
 
@@ -425,6 +429,7 @@ mod tests {
             fast_field: "fast".to_string(),
             output_format: 0,
             partition_by_field: None,
+            tenant_id: None,
         };
         LeafSearchStreamRequest {
             request: Some(search_request),
@@ -455,7 +460,10 @@ mod tests {
         let mut mock_search_service = MockSearchService::new();
         mock_search_service.expect_fetch_docs().return_once(
             |_: quickwit_proto::search::FetchDocsRequest| {
-                Ok(quickwit_proto::search::FetchDocsResponse { hits: Vec::new() })
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: Vec::new(),
+                    split_profiles: Vec::new(),
+                })
             },
         );
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
@@ -484,7 +492,10 @@ mod tests {
         let mut mock_search_service_2 = MockSearchService::new();
         mock_search_service_2.expect_fetch_docs().return_once(
             |_: quickwit_proto::search::FetchDocsRequest| {
-                Ok(quickwit_proto::search::FetchDocsResponse { hits: Vec::new() })
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: Vec::new(),
+                    split_profiles: Vec::new(),
+                })
             },
         );
         let searcher_pool = searcher_pool_for_test([
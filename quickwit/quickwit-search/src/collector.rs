@@ -20,13 +20,16 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use itertools::Itertools;
 use quickwit_common::binary_heap::{SortKeyMapper, TopK};
 use quickwit_doc_mapper::WarmupInfo;
 use quickwit_proto::search::{
     LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortOrder, SortValue,
-    SplitSearchError,
+    SplitSearchError, SplitSearchProfile,
 };
 use serde::Deserialize;
 use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
@@ -37,7 +40,17 @@ use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
 use tantivy::fastfield::Column;
 use tantivy::{DateTime, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
+use crate::cardinality_collector::{
+    self, Cardinality, CardinalityCollector, CardinalitySegmentCollector,
+};
+use crate::field_coverage_collector::{
+    self, FieldCoverage, FieldCoverageCollector, FieldCoverageSegmentCollector,
+};
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector, Span};
+use crate::percentiles_collector::{
+    self, Percentiles, PercentilesCollector, PercentilesSegmentCollector,
+};
+use crate::post_aggregation::push_down_post_aggregations;
 use crate::top_k_collector::{specialized_top_k_segment_collector, QuickwitSegmentTopKCollector};
 use crate::GlobalDocAddress;
 
@@ -514,6 +527,9 @@ impl Eq for PartialHitHeapItem {}
 
 enum AggregationSegmentCollectors {
     FindTraceIdsSegmentCollector(Box<FindTraceIdsSegmentCollector>),
+    FieldCoverageSegmentCollector(FieldCoverageSegmentCollector),
+    CardinalitySegmentCollector(CardinalitySegmentCollector),
+    PercentilesSegmentCollector(PercentilesSegmentCollector),
     TantivyAggregationSegmentCollector(AggregationSegmentCollector),
 }
 
@@ -522,6 +538,8 @@ pub struct QuickwitSegmentCollector {
     segment_top_k_collector: Option<Box<dyn QuickwitSegmentTopKCollector>>,
     aggregation: Option<AggregationSegmentCollectors>,
     num_hits: u64,
+    aggregation_nanos_opt: Option<Arc<AtomicU64>>,
+    aggregation_elapsed: Duration,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -578,15 +596,28 @@ impl SegmentCollector for QuickwitSegmentCollector {
             segment_top_k_collector.collect_top_k_block(filtered_docs);
         }
 
+        let aggregation_start_opt = self.aggregation_nanos_opt.as_ref().map(|_| Instant::now());
         match self.aggregation.as_mut() {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
                 collector.collect_block(filtered_docs)
             }
+            Some(AggregationSegmentCollectors::FieldCoverageSegmentCollector(collector)) => {
+                collector.collect_block(filtered_docs)
+            }
+            Some(AggregationSegmentCollectors::CardinalitySegmentCollector(collector)) => {
+                collector.collect_block(filtered_docs)
+            }
+            Some(AggregationSegmentCollectors::PercentilesSegmentCollector(collector)) => {
+                collector.collect_block(filtered_docs)
+            }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 collector.collect_block(filtered_docs)
             }
             None => (),
         }
+        if let Some(aggregation_start) = aggregation_start_opt {
+            self.aggregation_elapsed += aggregation_start.elapsed();
+        }
     }
 
     #[inline]
@@ -596,15 +627,28 @@ impl SegmentCollector for QuickwitSegmentCollector {
             segment_top_k_collector.collect_top_k(doc_id, score);
         }
 
+        let aggregation_start_opt = self.aggregation_nanos_opt.as_ref().map(|_| Instant::now());
         match self.aggregation.as_mut() {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
                 collector.collect(doc_id, score)
             }
+            Some(AggregationSegmentCollectors::FieldCoverageSegmentCollector(collector)) => {
+                collector.collect(doc_id, score)
+            }
+            Some(AggregationSegmentCollectors::CardinalitySegmentCollector(collector)) => {
+                collector.collect(doc_id, score)
+            }
+            Some(AggregationSegmentCollectors::PercentilesSegmentCollector(collector)) => {
+                collector.collect(doc_id, score)
+            }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 collector.collect(doc_id, score)
             }
             None => (),
         }
+        if let Some(aggregation_start) = aggregation_start_opt {
+            self.aggregation_elapsed += aggregation_start.elapsed();
+        }
     }
 
     fn harvest(self) -> Self::Fruit {
@@ -613,6 +657,7 @@ impl SegmentCollector for QuickwitSegmentCollector {
             partial_hits = segment_top_k_collector.get_top_k();
         }
 
+        let aggregation_start = Instant::now();
         let intermediate_aggregation_result = match self.aggregation {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
                 let fruit: Vec<Span> = collector.harvest();
@@ -620,6 +665,24 @@ impl SegmentCollector for QuickwitSegmentCollector {
                     postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
                 Some(serialized)
             }
+            Some(AggregationSegmentCollectors::FieldCoverageSegmentCollector(collector)) => {
+                let fruit: FieldCoverage = collector.harvest();
+                let serialized =
+                    postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
+                Some(serialized)
+            }
+            Some(AggregationSegmentCollectors::CardinalitySegmentCollector(collector)) => {
+                let fruit: Cardinality = collector.harvest();
+                let serialized =
+                    postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
+                Some(serialized)
+            }
+            Some(AggregationSegmentCollectors::PercentilesSegmentCollector(collector)) => {
+                let fruit: Percentiles = collector.harvest();
+                let serialized =
+                    postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
+                Some(serialized)
+            }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 let serialized = postcard::to_allocvec(&collector.harvest()?)
                     .expect("Collector fruit should be serializable.");
@@ -627,12 +690,20 @@ impl SegmentCollector for QuickwitSegmentCollector {
             }
             None => None,
         };
+        if let Some(aggregation_nanos) = &self.aggregation_nanos_opt {
+            let aggregation_elapsed = self.aggregation_elapsed + aggregation_start.elapsed();
+            aggregation_nanos.fetch_add(
+                aggregation_elapsed.as_nanos() as u64,
+                AtomicOrdering::Relaxed,
+            );
+        }
         Ok(LeafSearchResponse {
             intermediate_aggregation_result,
             num_hits: self.num_hits,
             partial_hits,
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
+            split_profiles: Vec::new(),
         })
     }
 }
@@ -644,6 +715,15 @@ pub enum QuickwitAggregations {
     /// Aggregation used by the Jaeger service to find trace IDs that match a
     /// [`quickwit_proto::jaeger::storage::v1::FindTraceIDsRequest`].
     FindTraceIdsAggregation(FindTraceIdsCollector),
+    /// Aggregation computing the percentage of the documents matching the query in which each of
+    /// the requested fields is present.
+    FieldCoverageAggregation(FieldCoverageCollector),
+    /// Top-level `cardinality` aggregations, estimating the number of distinct values of fast
+    /// fields with HyperLogLog++ sketches.
+    CardinalityAggregation(CardinalityCollector),
+    /// Top-level `percentiles` aggregations, estimating percentiles of numerical fast fields with
+    /// t-digests.
+    PercentilesAggregation(PercentilesCollector),
     /// Your classic Tantivy aggregation.
     TantivyAggregations(Aggregations),
 }
@@ -654,6 +734,16 @@ impl QuickwitAggregations {
             QuickwitAggregations::FindTraceIdsAggregation(collector) => {
                 collector.fast_field_names()
             }
+            // The fast fields read by the field presence queries are warmed up along with them.
+            QuickwitAggregations::FieldCoverageAggregation(_) => HashSet::new(),
+            QuickwitAggregations::CardinalityAggregation(collector) => collector
+                .fast_field_names()
+                .map(ToString::to_string)
+                .collect(),
+            QuickwitAggregations::PercentilesAggregation(collector) => collector
+                .fast_field_names()
+                .map(ToString::to_string)
+                .collect(),
             QuickwitAggregations::TantivyAggregations(aggregations) => {
                 get_fast_field_names(aggregations)
             }
@@ -665,6 +755,24 @@ impl QuickwitAggregations {
             QuickwitAggregations::FindTraceIdsAggregation(aggreg) => {
                 QuickwitIncrementalAggregations::FindTraceIdsAggregation(aggreg.clone(), Vec::new())
             }
+            QuickwitAggregations::FieldCoverageAggregation(aggreg) => {
+                QuickwitIncrementalAggregations::FieldCoverageAggregation(
+                    aggreg.clone(),
+                    FieldCoverage::default(),
+                )
+            }
+            QuickwitAggregations::CardinalityAggregation(aggreg) => {
+                QuickwitIncrementalAggregations::CardinalityAggregation(
+                    aggreg.clone(),
+                    Cardinality::default(),
+                )
+            }
+            QuickwitAggregations::PercentilesAggregation(aggreg) => {
+                QuickwitIncrementalAggregations::PercentilesAggregation(
+                    aggreg.clone(),
+                    Percentiles::default(),
+                )
+            }
             QuickwitAggregations::TantivyAggregations(aggreg) => {
                 QuickwitIncrementalAggregations::TantivyAggregations(aggreg.clone(), Vec::new())
             }
@@ -675,6 +783,9 @@ impl QuickwitAggregations {
 #[derive(Clone)]
 enum QuickwitIncrementalAggregations {
     FindTraceIdsAggregation(FindTraceIdsCollector, Vec<Vec<Span>>),
+    FieldCoverageAggregation(FieldCoverageCollector, FieldCoverage),
+    CardinalityAggregation(CardinalityCollector, Cardinality),
+    PercentilesAggregation(PercentilesCollector, Percentiles),
     TantivyAggregations(Aggregations, Vec<Vec<u8>>),
     NoAggregation,
 }
@@ -691,6 +802,21 @@ impl QuickwitIncrementalAggregations {
                     state.push(new_state);
                 }
             }
+            QuickwitIncrementalAggregations::FieldCoverageAggregation(_, ref mut state) => {
+                let fruit: FieldCoverage =
+                    postcard::from_bytes(&intermediate_result).map_err(map_error)?;
+                state.merge(fruit);
+            }
+            QuickwitIncrementalAggregations::CardinalityAggregation(_, ref mut state) => {
+                let fruit: Cardinality =
+                    postcard::from_bytes(&intermediate_result).map_err(map_error)?;
+                state.merge(fruit);
+            }
+            QuickwitIncrementalAggregations::PercentilesAggregation(_, ref mut state) => {
+                let fruit: Percentiles =
+                    postcard::from_bytes(&intermediate_result).map_err(map_error)?;
+                state.merge(fruit);
+            }
             QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
                 state.push(intermediate_result);
             }
@@ -720,6 +846,9 @@ impl QuickwitIncrementalAggregations {
                 }
                 None
             }
+            QuickwitIncrementalAggregations::FieldCoverageAggregation(_, _) => None,
+            QuickwitIncrementalAggregations::CardinalityAggregation(_, _) => None,
+            QuickwitIncrementalAggregations::PercentilesAggregation(_, _) => None,
             QuickwitIncrementalAggregations::TantivyAggregations(_, _) => None,
             QuickwitIncrementalAggregations::NoAggregation => None,
         }
@@ -736,6 +865,18 @@ impl QuickwitIncrementalAggregations {
                 let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
                 Ok(Some(serialized))
             }
+            QuickwitIncrementalAggregations::FieldCoverageAggregation(_, state) => {
+                let serialized = postcard::to_allocvec(&state).map_err(map_error)?;
+                Ok(Some(serialized))
+            }
+            QuickwitIncrementalAggregations::CardinalityAggregation(_, state) => {
+                let serialized = postcard::to_allocvec(&state).map_err(map_error)?;
+                Ok(Some(serialized))
+            }
+            QuickwitIncrementalAggregations::PercentilesAggregation(_, state) => {
+                let serialized = postcard::to_allocvec(&state).map_err(map_error)?;
+                Ok(Some(serialized))
+            }
             QuickwitIncrementalAggregations::TantivyAggregations(aggregation, state) => {
                 merge_intermediate_aggregation_result(
                     &Some(QuickwitAggregations::TantivyAggregations(aggregation)),
@@ -760,6 +901,9 @@ pub(crate) struct QuickwitCollector {
     pub aggregation: Option<QuickwitAggregations>,
     pub aggregation_limits: AggregationLimits,
     search_after: Option<PartialHit>,
+    /// When set, the time spent collecting aggregations is accumulated in this counter, in
+    /// nanoseconds. This is only used to profile searches.
+    pub aggregation_nanos_opt: Option<Arc<AtomicU64>>,
 }
 
 impl QuickwitCollector {
@@ -803,6 +947,21 @@ impl Collector for QuickwitCollector {
                     Box::new(collector.for_segment(0, segment_reader)?),
                 ))
             }
+            Some(QuickwitAggregations::FieldCoverageAggregation(collector)) => {
+                Some(AggregationSegmentCollectors::FieldCoverageSegmentCollector(
+                    collector.for_segment(0, segment_reader)?,
+                ))
+            }
+            Some(QuickwitAggregations::CardinalityAggregation(collector)) => {
+                Some(AggregationSegmentCollectors::CardinalitySegmentCollector(
+                    collector.for_segment(0, segment_reader)?,
+                ))
+            }
+            Some(QuickwitAggregations::PercentilesAggregation(collector)) => {
+                Some(AggregationSegmentCollectors::PercentilesSegmentCollector(
+                    collector.for_segment(0, segment_reader)?,
+                ))
+            }
             Some(QuickwitAggregations::TantivyAggregations(aggs)) => Some(
                 AggregationSegmentCollectors::TantivyAggregationSegmentCollector(
                     AggregationSegmentCollector::from_agg_req_and_reader(
@@ -837,6 +996,8 @@ impl Collector for QuickwitCollector {
             num_hits: 0,
             segment_top_k_collector,
             aggregation,
+            aggregation_nanos_opt: self.aggregation_nanos_opt.clone(),
+            aggregation_elapsed: Duration::default(),
         })
     }
 
@@ -908,6 +1069,37 @@ fn merge_intermediate_aggregation_result<'a>(
             let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
             Some(serialized)
         }
+        Some(QuickwitAggregations::FieldCoverageAggregation(collector)) => {
+            let fruits: Vec<FieldCoverage> = intermediate_aggregation_results
+                .map(|intermediate_aggregation_result| {
+                    postcard::from_bytes(intermediate_aggregation_result).map_err(map_error)
+                })
+                .collect::<Result<_, _>>()?;
+            let merged_fruit: FieldCoverage =
+                field_coverage_collector::merge_fruits(fruits, collector.field_names.len());
+            let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
+            Some(serialized)
+        }
+        Some(QuickwitAggregations::CardinalityAggregation(_)) => {
+            let fruits: Vec<Cardinality> = intermediate_aggregation_results
+                .map(|intermediate_aggregation_result| {
+                    postcard::from_bytes(intermediate_aggregation_result).map_err(map_error)
+                })
+                .collect::<Result<_, _>>()?;
+            let merged_fruit: Cardinality = cardinality_collector::merge_fruits(fruits);
+            let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
+            Some(serialized)
+        }
+        Some(QuickwitAggregations::PercentilesAggregation(_)) => {
+            let fruits: Vec<Percentiles> = intermediate_aggregation_results
+                .map(|intermediate_aggregation_result| {
+                    postcard::from_bytes(intermediate_aggregation_result).map_err(map_error)
+                })
+                .collect::<Result<_, _>>()?;
+            let merged_fruit: Percentiles = percentiles_collector::merge_fruits(fruits);
+            let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
+            Some(serialized)
+        }
         Some(QuickwitAggregations::TantivyAggregations(_)) => {
             let fruits: Vec<IntermediateAggregationResults> = intermediate_aggregation_results
                 .map(|intermediate_aggregation_result| {
@@ -967,6 +1159,10 @@ fn merge_leaf_responses(
         .flat_map(|leaf_response| leaf_response.failed_splits.iter())
         .cloned()
         .collect_vec();
+    let split_profiles = leaf_responses
+        .iter_mut()
+        .flat_map(|leaf_response| std::mem::take(&mut leaf_response.split_profiles))
+        .collect_vec();
     let all_partial_hits: Vec<PartialHit> = leaf_responses
         .into_iter()
         .flat_map(|leaf_response| leaf_response.partial_hits)
@@ -983,6 +1179,7 @@ fn merge_leaf_responses(
         partial_hits: top_k_partial_hits,
         failed_splits,
         num_attempted_splits,
+        split_profiles,
     })
 }
 
@@ -1049,7 +1246,10 @@ pub(crate) fn make_collector_for_split(
     aggregation_limits: AggregationLimits,
 ) -> crate::Result<QuickwitCollector> {
     let aggregation = match &search_request.aggregation_request {
-        Some(aggregation) => Some(serde_json::from_str(aggregation)?),
+        Some(aggregation) => {
+            let (aggregation, _) = push_down_post_aggregations(aggregation)?;
+            Some(serde_json::from_str(&aggregation)?)
+        }
         None => None,
     };
     let sort_by = sort_by_from_request(search_request);
@@ -1061,6 +1261,7 @@ pub(crate) fn make_collector_for_split(
         aggregation,
         aggregation_limits,
         search_after: search_request.search_after.clone(),
+        aggregation_nanos_opt: None,
     })
 }
 
@@ -1070,7 +1271,10 @@ pub(crate) fn make_merge_collector(
     aggregation_limits: &AggregationLimits,
 ) -> crate::Result<QuickwitCollector> {
     let aggregation = match &search_request.aggregation_request {
-        Some(aggregation) => Some(serde_json::from_str(aggregation)?),
+        Some(aggregation) => {
+            let (aggregation, _) = push_down_post_aggregations(aggregation)?;
+            Some(serde_json::from_str(&aggregation)?)
+        }
         None => None,
     };
     let sort_by = sort_by_from_request(search_request);
@@ -1082,6 +1286,7 @@ pub(crate) fn make_merge_collector(
         aggregation,
         aggregation_limits: aggregation_limits.clone(),
         search_after: search_request.search_after.clone(),
+        aggregation_nanos_opt: None,
     })
 }
 
@@ -1205,6 +1410,7 @@ pub(crate) struct IncrementalCollector {
     failed_splits: Vec<SplitSearchError>,
     num_attempted_splits: u64,
     start_offset: usize,
+    split_profiles: Vec<SplitSearchProfile>,
 }
 
 impl IncrementalCollector {
@@ -1224,6 +1430,7 @@ impl IncrementalCollector {
             num_hits: 0,
             failed_splits: Vec::new(),
             num_attempted_splits: 0,
+            split_profiles: Vec::new(),
         }
     }
 
@@ -1235,12 +1442,14 @@ impl IncrementalCollector {
             failed_splits,
             num_attempted_splits,
             intermediate_aggregation_result,
+            split_profiles,
         } = leaf_response;
 
         self.num_hits += num_hits;
         self.top_k_hits.add_entries(partial_hits.into_iter());
         self.failed_splits.extend(failed_splits);
         self.num_attempted_splits += num_attempted_splits;
+        self.split_profiles.extend(split_profiles);
         if let Some(intermediate_aggregation_result) = intermediate_aggregation_result {
             self.incremental_aggregation
                 .add(intermediate_aggregation_result)?;
@@ -1284,6 +1493,7 @@ impl IncrementalCollector {
             failed_splits: self.failed_splits,
             num_attempted_splits: self.num_attempted_splits,
             intermediate_aggregation_result,
+            split_profiles: self.split_profiles,
         })
     }
 }
@@ -1884,6 +2094,7 @@ mod tests {
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
                 intermediate_aggregation_result: None,
+                split_profiles: Vec::new(),
             }],
         );
 
@@ -1901,6 +2112,7 @@ mod tests {
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
                 intermediate_aggregation_result: None
+                split_profiles: Vec::new(),
             }
         );
 
@@ -1938,6 +2150,7 @@ mod tests {
                     failed_splits: Vec::new(),
                     num_attempted_splits: 3,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -1955,6 +2168,7 @@ mod tests {
                     }],
                     num_attempted_splits: 2,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
                 },
             ],
         );
@@ -1986,6 +2200,7 @@ mod tests {
                 }],
                 num_attempted_splits: 5,
                 intermediate_aggregation_result: None
+                split_profiles: Vec::new(),
             }
         );
 
@@ -2024,6 +2239,7 @@ mod tests {
                     failed_splits: Vec::new(),
                     num_attempted_splits: 3,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -2041,6 +2257,7 @@ mod tests {
                     }],
                     num_attempted_splits: 2,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
                 },
             ],
         );
@@ -2072,6 +2289,7 @@ mod tests {
                 }],
                 num_attempted_splits: 5,
                 intermediate_aggregation_result: None
+                split_profiles: Vec::new(),
             }
         );
         // TODO would be nice to test aggregation too.
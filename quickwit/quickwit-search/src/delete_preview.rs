@@ -0,0 +1,261 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+
+use futures::future::try_join_all;
+use quickwit_config::build_doc_mapper;
+use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    DeleteQuery, IndexMetadataRequest, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::search::{CountHits, SearchRequest};
+use quickwit_query::query_ast::QueryAst;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    jobs_to_leaf_requests, list_relevant_splits, ClusterClient, IndexMetasForLeafSearch,
+    SearchError, SearchJob,
+};
+
+/// Documents of a split matched by a delete query.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SplitDeletePreview {
+    /// ID of the split.
+    pub split_id: String,
+    /// Number of documents in the split.
+    pub num_docs: u64,
+    /// Number of documents of the split matched by the delete query.
+    pub num_matching_docs: u64,
+}
+
+/// Documents a delete query would delete if it was executed now.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeleteQueryPreview {
+    /// Number of documents matched by the delete query.
+    pub num_matching_docs: u64,
+    /// Number of splits searched, i.e. the published splits overlapping the time range of the
+    /// delete query and not pruned by its tags.
+    pub num_splits_searched: u64,
+    /// Splits containing documents matched by the delete query, which would be rewritten by the
+    /// delete pipeline.
+    pub affected_splits: Vec<SplitDeletePreview>,
+}
+
+/// Counts the documents matched by a delete query, split by split, without creating the delete
+/// task. Documents indexed in the meantime may be matched by the delete task once it is created,
+/// so the counts are an estimate.
+pub async fn preview_delete_query(
+    delete_query: DeleteQuery,
+    metastore: &mut MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<DeleteQueryPreview> {
+    let index_uid = delete_query.index_uid().clone();
+    let index_metadata_request = IndexMetadataRequest::for_index_uid(index_uid.clone());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let index_uri = index_metadata.index_uri().clone();
+    let index_config = index_metadata.into_index_config();
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| SearchError::Internal(format!("failed to build doc mapper: {error}")))?;
+    let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|error| {
+        SearchError::Internal(format!("failed to serialize doc mapper: {error}"))
+    })?;
+    let query_ast: QueryAst = serde_json::from_str(&delete_query.query_ast)
+        .map_err(|error| SearchError::InvalidQuery(error.to_string()))?;
+    let tags_filter_opt = extract_tags_from_query(query_ast);
+
+    let mut search_request = SearchRequest::try_from(delete_query)
+        .map_err(|error| SearchError::InvalidQuery(error.to_string()))?;
+    search_request.count_hits = CountHits::CountAll as i32;
+
+    let split_metadatas = list_relevant_splits(
+        vec![index_uid.clone()],
+        search_request.start_timestamp,
+        search_request.end_timestamp,
+        tags_filter_opt,
+        metastore,
+    )
+    .await?;
+    let num_splits_searched = split_metadatas.len() as u64;
+    let num_docs_per_split: HashMap<String, u64> = split_metadatas
+        .iter()
+        .map(|split_metadata| {
+            (
+                split_metadata.split_id.clone(),
+                split_metadata.num_docs as u64,
+            )
+        })
+        .collect();
+
+    let mut indexes_metas_for_leaf_search = HashMap::new();
+    indexes_metas_for_leaf_search.insert(
+        index_uid,
+        IndexMetasForLeafSearch {
+            index_uri,
+            doc_mapper_str,
+        },
+    );
+    let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
+    let assigned_jobs = cluster_client
+        .search_job_placer
+        .assign_jobs(jobs, &HashSet::default())
+        .await?;
+
+    // We send one leaf request per split so that the leaf responses give the number of matching
+    // documents of each split.
+    let mut leaf_search_tasks = Vec::new();
+    for (client, client_jobs) in assigned_jobs {
+        for job in client_jobs {
+            let split_id = job.offsets.split_id.clone();
+            for leaf_request in
+                jobs_to_leaf_requests(&search_request, &indexes_metas_for_leaf_search, vec![job])?
+            {
+                let split_id = split_id.clone();
+                let leaf_search_future = cluster_client.leaf_search(leaf_request, client.clone());
+                leaf_search_tasks.push(async move {
+                    leaf_search_future
+                        .await
+                        .map(|leaf_response| (split_id, leaf_response))
+                });
+            }
+        }
+    }
+    let leaf_responses = try_join_all(leaf_search_tasks).await?;
+
+    let mut num_matching_docs = 0;
+    let mut affected_splits = Vec::new();
+    for (split_id, leaf_response) in leaf_responses {
+        if let Some(failed_split) = leaf_response.failed_splits.first() {
+            return Err(SearchError::Internal(format!(
+                "failed to search split `{}`: {}",
+                failed_split.split_id, failed_split.error
+            )));
+        }
+        if leaf_response.num_hits == 0 {
+            continue;
+        }
+        num_matching_docs += leaf_response.num_hits;
+        affected_splits.push(SplitDeletePreview {
+            num_docs: num_docs_per_split.get(&split_id).copied().unwrap_or(0),
+            split_id,
+            num_matching_docs: leaf_response.num_hits,
+        });
+    }
+    affected_splits.sort_by(|left, right| left.split_id.cmp(&right.split_id));
+
+    Ok(DeleteQueryPreview {
+        num_matching_docs,
+        num_splits_searched,
+        affected_splits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::ServiceStream;
+    use quickwit_indexing::MockSplitBuilder;
+    use quickwit_metastore::{IndexMetadata, ListSplitsResponseExt};
+    use quickwit_proto::metastore::{
+        IndexMetadataResponse, ListSplitsResponse, MockMetastoreService,
+    };
+    use quickwit_proto::search::{LeafSearchRequest, LeafSearchResponse};
+
+    use super::*;
+    use crate::{searcher_pool_for_test, MockSearchService, SearchJobPlacer};
+
+    #[tokio::test]
+    async fn test_preview_delete_query() {
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        let index_uid_clone = index_uid.clone();
+        mock_metastore
+            .expect_list_splits()
+            .times(1)
+            .returning(move |_| {
+                let splits = ["split-1", "split-2", "split-3"]
+                    .into_iter()
+                    .map(|split_id| {
+                        MockSplitBuilder::new(split_id)
+                            .with_index_uid(&index_uid_clone)
+                            .build()
+                    })
+                    .collect();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().times(3).returning(
+            |leaf_search_request: LeafSearchRequest| {
+                assert_eq!(leaf_search_request.split_offsets.len(), 1);
+                let num_hits = match leaf_search_request.split_offsets[0].split_id.as_str() {
+                    "split-1" => 3,
+                    "split-3" => 1,
+                    _ => 0,
+                };
+                Ok(LeafSearchResponse {
+                    num_hits,
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+
+        let delete_query = DeleteQuery {
+            index_uid: Some(index_uid),
+            start_timestamp: None,
+            end_timestamp: None,
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+        };
+        let preview = preview_delete_query(delete_query, &mut metastore, &cluster_client)
+            .await
+            .unwrap();
+        assert_eq!(
+            preview,
+            DeleteQueryPreview {
+                num_matching_docs: 4,
+                num_splits_searched: 3,
+                affected_splits: vec![
+                    SplitDeletePreview {
+                        split_id: "split-1".to_string(),
+                        num_docs: 10,
+                        num_matching_docs: 3,
+                    },
+                    SplitDeletePreview {
+                        split_id: "split-3".to_string(),
+                        num_docs: 10,
+                        num_matching_docs: 1,
+                    },
+                ],
+            }
+        );
+    }
+}
@@ -38,10 +38,14 @@ pub enum SearchError {
     Internal(String),
     #[error("invalid aggregation request: {0}")]
     InvalidAggregationRequest(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
     #[error("{0}")]
     InvalidQuery(String),
+    #[error("query rejected by the query rules of index `{index_id}`: {reason}")]
+    QueryRejected { index_id: String, reason: String },
     #[error("storage not found: `{0}`)")]
     StorageResolver(#[from] StorageResolverError),
     #[error("request timed out: {0}")]
@@ -56,10 +60,12 @@ impl ServiceError for SearchError {
     fn error_code(&self) -> ServiceErrorCode {
         match self {
             Self::IndexesNotFound { .. } => ServiceErrorCode::NotFound,
+            Self::Forbidden(_) => ServiceErrorCode::Forbidden,
             Self::Internal(_) => ServiceErrorCode::Internal,
             Self::InvalidAggregationRequest(_) => ServiceErrorCode::BadRequest,
             Self::InvalidArgument(_) => ServiceErrorCode::BadRequest,
             Self::InvalidQuery(_) => ServiceErrorCode::BadRequest,
+            Self::QueryRejected { .. } => ServiceErrorCode::BadRequest,
             Self::StorageResolver(_) => ServiceErrorCode::Internal,
             Self::Timeout(_) => ServiceErrorCode::Timeout,
             Self::TooManyRequests => ServiceErrorCode::TooManyRequests,
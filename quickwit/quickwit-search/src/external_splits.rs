@@ -0,0 +1,259 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use quickwit_common::uri::Uri;
+use quickwit_config::{IndexConfig, SourceConfig, SourceParams};
+use quickwit_metastore::checkpoint::PartitionId;
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::types::IndexUid;
+use tracing::info;
+
+use crate::{SearchError, SearcherContext};
+
+/// Files of a lazy Parquet source that are not materialized yet and may hold documents matching a
+/// search request.
+#[derive(Clone, Debug)]
+pub struct MaterializeExternalSplitsRequest {
+    /// UID of the index the source belongs to.
+    pub index_uid: IndexUid,
+    /// Config of the index the source belongs to.
+    pub index_config: IndexConfig,
+    /// Config of the lazy Parquet source.
+    pub source_config: SourceConfig,
+    /// Files of the source not materialized yet.
+    pub filepaths: Vec<Uri>,
+    /// Start of the time range of the search request, in seconds.
+    pub start_timestamp: Option<i64>,
+    /// End of the time range of the search request, in seconds.
+    pub end_timestamp: Option<i64>,
+}
+
+/// Turns the files of lazy Parquet sources into regular splits, published in the metastore along
+/// with the checkpoint of the source, so each file is only materialized once.
+#[mockall::automock]
+#[async_trait]
+pub trait ExternalSplitMaterializer: Send + Sync + 'static {
+    /// Materializes the files of the request that overlap its time range. Returns once the
+    /// resulting splits are published.
+    async fn materialize_external_splits(
+        &self,
+        request: MaterializeExternalSplitsRequest,
+    ) -> anyhow::Result<()>;
+}
+
+/// Lists the files of the lazy Parquet sources of the indexes that are not materialized yet.
+fn plan_external_splits(
+    indexes_metadata: &[IndexMetadata],
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+) -> Vec<MaterializeExternalSplitsRequest> {
+    let mut requests = Vec::new();
+
+    for index_metadata in indexes_metadata {
+        for source_config in index_metadata.sources.values() {
+            let SourceParams::Parquet(parquet_params) = &source_config.source_params else {
+                continue;
+            };
+            if !parquet_params.lazy || !source_config.enabled {
+                continue;
+            }
+            let source_checkpoint_opt = index_metadata
+                .checkpoint
+                .source_checkpoint(&source_config.source_id);
+            let filepaths: Vec<Uri> = parquet_params
+                .filepaths
+                .iter()
+                .filter(|filepath| {
+                    !source_checkpoint_opt
+                        .and_then(|source_checkpoint| {
+                            source_checkpoint
+                                .position_for_partition(&PartitionId::from(filepath.as_str()))
+                        })
+                        .map_or(false, |position| position.is_eof())
+                })
+                .cloned()
+                .collect();
+            if filepaths.is_empty() {
+                continue;
+            }
+            requests.push(MaterializeExternalSplitsRequest {
+                index_uid: index_metadata.index_uid.clone(),
+                index_config: index_metadata.index_config.clone(),
+                source_config: source_config.clone(),
+                filepaths,
+                start_timestamp,
+                end_timestamp,
+            });
+        }
+    }
+    requests
+}
+
+/// Materializes the files of the lazy Parquet sources of the searched indexes that are not
+/// materialized yet, so the splits listed afterwards by the root search cover them.
+pub(crate) async fn materialize_external_splits(
+    searcher_context: &SearcherContext,
+    indexes_metadata: &[IndexMetadata],
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+) -> crate::Result<()> {
+    let requests = plan_external_splits(indexes_metadata, start_timestamp, end_timestamp);
+
+    if requests.is_empty() {
+        return Ok(());
+    }
+    let Some(materializer) = &searcher_context.external_split_materializer_opt else {
+        let index_id = &requests[0].index_uid.index_id;
+        return Err(SearchError::Internal(format!(
+            "index `{index_id}` has lazy Parquet sources, which this searcher cannot materialize"
+        )));
+    };
+    let materialization_futures = requests.into_iter().map(|request| {
+        info!(
+            index_uid=%request.index_uid,
+            source_id=%request.source_config.source_id,
+            num_files=request.filepaths.len(),
+            "materializing external splits"
+        );
+        let index_id = request.index_uid.index_id.clone();
+        let source_id = request.source_config.source_id.clone();
+
+        async move {
+            materializer
+                .materialize_external_splits(request)
+                .await
+                .map_err(|error| {
+                    SearchError::Internal(format!(
+                        "failed to materialize the files of source `{source_id}` of index \
+                         `{index_id}`: {error:#}"
+                    ))
+                })
+        }
+    });
+    try_join_all(materialization_futures).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
+
+    use quickwit_config::{ParquetSourceParams, SourceInputFormat};
+    use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
+    use quickwit_proto::types::Position;
+
+    use super::*;
+
+    fn parquet_source_config(
+        source_id: &str,
+        filepaths: &[&'static str],
+        lazy: bool,
+    ) -> SourceConfig {
+        SourceConfig {
+            source_id: source_id.to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Parquet(ParquetSourceParams {
+                filepaths: filepaths.iter().copied().map(Uri::for_test).collect(),
+                columns: Vec::new(),
+                lazy,
+            }),
+            transform_config: None,
+            json_schema_config: None,
+            wasm_plugin_config: None,
+            input_format: SourceInputFormat::Json,
+        }
+    }
+
+    fn test_index_metadata() -> IndexMetadata {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata
+            .add_source(parquet_source_config(
+                "lazy-source",
+                &["s3://bucket/part-0.parquet", "s3://bucket/part-1.parquet"],
+                true,
+            ))
+            .unwrap();
+        index_metadata
+            .add_source(parquet_source_config(
+                "eager-source",
+                &["s3://bucket/part-2.parquet"],
+                false,
+            ))
+            .unwrap();
+        let source_delta = SourceCheckpointDelta::from_partition_delta(
+            PartitionId::from("s3://bucket/part-0.parquet"),
+            Position::Beginning,
+            Position::eof(10u64),
+        )
+        .unwrap();
+        index_metadata
+            .checkpoint
+            .try_apply_delta(IndexCheckpointDelta {
+                source_id: "lazy-source".to_string(),
+                source_delta,
+            })
+            .unwrap();
+        index_metadata
+    }
+
+    #[test]
+    fn test_plan_external_splits() {
+        let index_metadata = test_index_metadata();
+        let requests = plan_external_splits(&[index_metadata], Some(10), Some(20));
+        assert_eq!(requests.len(), 1);
+
+        let request = &requests[0];
+        assert_eq!(request.index_uid.index_id, "test-index");
+        assert_eq!(request.source_config.source_id, "lazy-source");
+        assert_eq!(
+            request.filepaths,
+            [Uri::for_test("s3://bucket/part-1.parquet")]
+        );
+        assert_eq!(request.start_timestamp, Some(10));
+        assert_eq!(request.end_timestamp, Some(20));
+    }
+
+    #[tokio::test]
+    async fn test_materialize_external_splits() {
+        let index_metadata = test_index_metadata();
+
+        let searcher_context = SearcherContext::for_test();
+        let error =
+            materialize_external_splits(&searcher_context, &[index_metadata.clone()], None, None)
+                .await
+                .unwrap_err();
+        assert!(error.to_string().contains("cannot materialize"));
+
+        let mut mock_materializer = MockExternalSplitMaterializer::new();
+        mock_materializer
+            .expect_materialize_external_splits()
+            .once()
+            .withf(|request| request.filepaths == [Uri::for_test("s3://bucket/part-1.parquet")])
+            .returning(|_| Ok(()));
+        let searcher_context = SearcherContext::for_test()
+            .with_external_split_materializer(Arc::new(mock_materializer));
+        materialize_external_splits(&searcher_context, &[index_metadata], None, None)
+            .await
+            .unwrap();
+    }
+}
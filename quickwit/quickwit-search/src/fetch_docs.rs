@@ -19,18 +19,21 @@
 
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{Context, Ok};
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::search::{
-    FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
+    FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets, SplitSearchProfile,
 };
-use quickwit_storage::Storage;
+use quickwit_storage::{wrap_storage_with_read_stats, Storage};
 use tantivy::query::Query;
-use tantivy::schema::{Document as DocumentTrait, Field, OwnedValue, TantivyDocument, Value};
-use tantivy::{ReloadPolicy, Score, Searcher, SnippetGenerator, Term};
+use tantivy::schema::{
+    Document as DocumentTrait, Field, OwnedValue, Schema, TantivyDocument, Value,
+};
+use tantivy::{ReloadPolicy, Score, Searcher, Snippet, SnippetGenerator, Term};
 use tracing::{error, Instrument};
 
 use crate::leaf::open_index_with_caches;
@@ -38,9 +41,11 @@ use crate::service::SearcherContext;
 use crate::{convert_document_to_json_string, GlobalDocAddress};
 
 const SNIPPET_MAX_NUM_CHARS: usize = 150;
+const DEFAULT_HIGHLIGHT_PRE_TAG: &str = "<em>";
+const DEFAULT_HIGHLIGHT_POST_TAG: &str = "</em>";
 
 /// Given a list of global doc address, fetches all the documents and
-/// returns them as a hashmap.
+/// returns them as a hashmap, along with the fetch profiles of the splits if `profile` is set.
 async fn fetch_docs_to_map(
     searcher_context: Arc<SearcherContext>,
     mut global_doc_addrs: Vec<GlobalDocAddress>,
@@ -48,7 +53,8 @@ async fn fetch_docs_to_map(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
-) -> anyhow::Result<HashMap<GlobalDocAddress, Document>> {
+    profile: bool,
+) -> anyhow::Result<(HashMap<GlobalDocAddress, Document>, Vec<SplitSearchProfile>)> {
     let mut split_fetch_docs_futures = Vec::new();
 
     let split_offsets_map: HashMap<&str, &SplitIdAndFooterOffsets> = splits
@@ -68,39 +74,43 @@ async fn fetch_docs_to_map(
         let split_and_offset = split_offsets_map
             .get(split_id)
             .ok_or_else(|| anyhow::anyhow!("failed to find offset for split {}", split_id))?;
-        split_fetch_docs_futures.push(fetch_docs_in_split(
+        split_fetch_docs_futures.push(profiled_fetch_docs_in_split(
             searcher_context.clone(),
             global_doc_addrs,
             index_storage.clone(),
             split_and_offset,
             doc_mapper.clone(),
             snippet_request_opt,
+            profile,
         ));
     }
 
-    let split_fetch_docs: Vec<Vec<(GlobalDocAddress, Document)>> = futures::future::try_join_all(
-        split_fetch_docs_futures,
-    )
-    .await
-    .map_err(|error| {
-        let split_ids = splits
-            .iter()
-            .map(|split| split.split_id.clone())
-            .collect_vec();
-        error!(split_ids = ?split_ids, error = ?error, "error when fetching docs in splits");
-        anyhow::anyhow!(
-            "error when fetching docs for splits {:?}: {:?}",
-            split_ids,
-            error
-        )
-    })?;
+    let split_fetch_docs: Vec<(
+        Vec<(GlobalDocAddress, Document)>,
+        Option<SplitSearchProfile>,
+    )> = futures::future::try_join_all(split_fetch_docs_futures)
+        .await
+        .map_err(|error| {
+            let split_ids = splits
+                .iter()
+                .map(|split| split.split_id.clone())
+                .collect_vec();
+            error!(split_ids = ?split_ids, error = ?error, "error when fetching docs in splits");
+            anyhow::anyhow!(
+                "error when fetching docs for splits {:?}: {:?}",
+                split_ids,
+                error
+            )
+        })?;
 
-    let global_doc_addr_to_doc_json: HashMap<GlobalDocAddress, Document> = split_fetch_docs
-        .into_iter()
-        .flat_map(|docs| docs.into_iter())
-        .collect();
+    let mut split_profiles = Vec::new();
+    let mut global_doc_addr_to_doc_json: HashMap<GlobalDocAddress, Document> = HashMap::new();
 
-    Ok(global_doc_addr_to_doc_json)
+    for (docs, split_profile_opt) in split_fetch_docs {
+        global_doc_addr_to_doc_json.extend(docs);
+        split_profiles.extend(split_profile_opt);
+    }
+    Ok((global_doc_addr_to_doc_json, split_profiles))
 }
 
 /// `fetch_docs` step of search.
@@ -115,19 +125,21 @@ pub async fn fetch_docs(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    profile: bool,
 ) -> anyhow::Result<FetchDocsResponse> {
     let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
         .iter()
         .map(GlobalDocAddress::from_partial_hit)
         .collect();
 
-    let mut global_doc_addr_to_doc_json = fetch_docs_to_map(
+    let (mut global_doc_addr_to_doc_json, split_profiles) = fetch_docs_to_map(
         searcher_context,
         global_doc_addrs,
         index_storage,
         splits,
         doc_mapper,
         snippet_request_opt,
+        profile,
     )
     .await?;
 
@@ -141,23 +153,74 @@ pub async fn fetch_docs(
                     leaf_json: document.content_json,
                     partial_hit: Some(partial_hit.clone()),
                     leaf_snippet_json: document.snippet_json,
+                    leaf_highlight_json: document.highlight_json,
                 })
             } else {
                 None
             }
         })
         .collect();
-    Ok(FetchDocsResponse { hits })
+    Ok(FetchDocsResponse {
+        hits,
+        split_profiles,
+    })
 }
 
 // number of concurrent fetch allowed for a single split.
 const NUM_CONCURRENT_REQUESTS: usize = 30;
 
-/// A struct for holding a fetched document's content and snippet.
+/// A struct for holding a fetched document's content, snippet and highlight.
 #[derive(Debug)]
 struct Document {
     content_json: String,
     snippet_json: Option<String>,
+    highlight_json: Option<String>,
+}
+
+/// Fetches docs from a specific split, measuring the time and bytes it took if `profile` is set.
+async fn profiled_fetch_docs_in_split(
+    searcher_context: Arc<SearcherContext>,
+    global_doc_addrs: Vec<GlobalDocAddress>,
+    index_storage: Arc<dyn Storage>,
+    split: &SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    snippet_request_opt: Option<&SnippetRequest>,
+    profile: bool,
+) -> anyhow::Result<(
+    Vec<(GlobalDocAddress, Document)>,
+    Option<SplitSearchProfile>,
+)> {
+    if !profile {
+        let docs = fetch_docs_in_split(
+            searcher_context,
+            global_doc_addrs,
+            index_storage,
+            split,
+            doc_mapper,
+            snippet_request_opt,
+        )
+        .await?;
+        return Ok((docs, None));
+    }
+    let (index_storage, read_stats) = wrap_storage_with_read_stats(index_storage);
+    let fetch_start = Instant::now();
+    let docs = fetch_docs_in_split(
+        searcher_context,
+        global_doc_addrs,
+        index_storage,
+        split,
+        doc_mapper,
+        snippet_request_opt,
+    )
+    .await?;
+    let split_profile = SplitSearchProfile {
+        split_id: split.split_id.clone(),
+        fetch_micros: fetch_start.elapsed().as_micros() as u64,
+        num_bytes_read: read_stats.num_bytes_read(),
+        num_storage_requests: read_stats.num_requests(),
+        ..Default::default()
+    };
+    Ok((docs, Some(split_profile)))
 }
 
 /// Fetching docs from a specific split.
@@ -188,16 +251,18 @@ async fn fetch_docs_in_split(
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = Arc::new(index_reader.searcher());
-    let fields_snippet_generator_opt = if let Some(snippet_request) = snippet_request_opt {
-        Some(create_fields_snippet_generator(&searcher, doc_mapper.clone(), snippet_request).await?)
-    } else {
-        None
-    };
+    let (fields_snippet_generator_opt, fields_highlight_generator_opt) =
+        if let Some(snippet_request) = snippet_request_opt {
+            create_fields_snippet_generators(&searcher, doc_mapper.clone(), snippet_request).await?
+        } else {
+            (None, None)
+        };
 
     let doc_futures = global_doc_addrs.into_iter().map(|global_doc_addr| {
         let moved_searcher = searcher.clone();
         let moved_doc_mapper = doc_mapper.clone();
         let fields_snippet_generator_opt_clone = fields_snippet_generator_opt.clone();
+        let fields_highlight_generator_opt_clone = fields_highlight_generator_opt.clone();
         async move {
             let doc: TantivyDocument = moved_searcher
                 .doc_async(global_doc_addr.doc_addr)
@@ -207,42 +272,24 @@ async fn fetch_docs_in_split(
             let named_field_doc = doc.to_named_doc(moved_searcher.schema());
             let content_json =
                 convert_document_to_json_string(named_field_doc, &*moved_doc_mapper)?;
-            if fields_snippet_generator_opt_clone.is_none() {
-                return Ok((
-                    global_doc_addr,
-                    Document {
-                        content_json,
-                        snippet_json: None,
-                    },
-                ));
-            }
-
-            let fields_snippet_generator_clone = fields_snippet_generator_opt_clone.unwrap();
-            if fields_snippet_generator_clone.is_empty() {
-                return Ok((
-                    global_doc_addr,
-                    Document {
-                        content_json,
-                        snippet_json: None,
-                    },
-                ));
-            }
-
-            let mut snippets = HashMap::new();
-            for (field, field_values) in doc.get_sorted_field_values() {
-                let field_name = moved_searcher.schema().get_field_name(field);
-                if let Some(values) = fields_snippet_generator_clone
-                    .snippets_from_field_values(field_name, field_values)
-                {
-                    snippets.insert(field_name, values);
-                }
-            }
-            let snippet_json = serde_json::to_string(&snippets)?;
+            let snippet_json = fields_snippet_generator_opt_clone
+                .map(|fields_snippet_generator| {
+                    fields_snippet_generator.snippets_json(&doc, moved_searcher.schema())
+                })
+                .transpose()?
+                .flatten();
+            let highlight_json = fields_highlight_generator_opt_clone
+                .map(|fields_highlight_generator| {
+                    fields_highlight_generator.snippets_json(&doc, moved_searcher.schema())
+                })
+                .transpose()?
+                .flatten();
             Ok((
                 global_doc_addr,
                 Document {
                     content_json,
-                    snippet_json: Some(snippet_json),
+                    snippet_json,
+                    highlight_json,
                 },
             ))
         }
@@ -255,11 +302,19 @@ async fn fetch_docs_in_split(
         .await
 }
 
+/// Tags wrapping the matched terms of the highlighted snippets.
+struct HighlightTags {
+    pre_tag: String,
+    post_tag: String,
+}
+
 // A struct to hold the snippet generators associated to
 // the snippet fields from a search request.
 #[derive(Clone)]
 struct FieldsSnippetGenerator {
     field_generators: Arc<HashMap<String, SnippetGenerator>>,
+    // Snippets are rendered as HTML if no highlight tags are set.
+    highlight_tags_opt: Option<Arc<HighlightTags>>,
 }
 
 impl FieldsSnippetGenerator {
@@ -276,7 +331,7 @@ impl FieldsSnippetGenerator {
                     value.as_str().and_then(|text| {
                         let snippet = snippet_generator.snippet(text);
                         match snippet.is_empty() {
-                            false => Some(snippet.to_html()),
+                            false => Some(self.render_snippet(&snippet)),
                             _ => None,
                         }
                     })
@@ -288,30 +343,132 @@ impl FieldsSnippetGenerator {
         }
     }
 
+    fn render_snippet(&self, snippet: &Snippet) -> String {
+        let Some(highlight_tags) = &self.highlight_tags_opt else {
+            return snippet.to_html();
+        };
+        let fragment = snippet.fragment();
+        let mut highlighted = String::with_capacity(fragment.len());
+        let mut start = 0;
+
+        for range in snippet.highlighted() {
+            highlighted.push_str(&fragment[start..range.start]);
+            highlighted.push_str(&highlight_tags.pre_tag);
+            highlighted.push_str(&fragment[range.clone()]);
+            highlighted.push_str(&highlight_tags.post_tag);
+            start = range.end;
+        }
+        highlighted.push_str(&fragment[start..]);
+        highlighted
+    }
+
+    // Returns the snippets of the document serialized as a JSON object keyed by field name, or
+    // `None` if no snippet field was requested. Highlights omit the fields without any match.
+    fn snippets_json(
+        &self,
+        doc: &TantivyDocument,
+        schema: &Schema,
+    ) -> anyhow::Result<Option<String>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let mut snippets = HashMap::new();
+        for (field, field_values) in doc.get_sorted_field_values() {
+            let field_name = schema.get_field_name(field);
+            if let Some(values) = self.snippets_from_field_values(field_name, field_values) {
+                if values.is_empty() && self.highlight_tags_opt.is_some() {
+                    continue;
+                }
+                snippets.insert(field_name, values);
+            }
+        }
+        let snippet_json = serde_json::to_string(&snippets)?;
+        Ok(Some(snippet_json))
+    }
+
     fn is_empty(&self) -> bool {
         self.field_generators.is_empty()
     }
 }
 
-// Creates FieldsSnippetGenerator.
-async fn create_fields_snippet_generator(
+// Creates the generators of the snippets and of the highlights of a request.
+async fn create_fields_snippet_generators(
     searcher: &Searcher,
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request: &SnippetRequest,
-) -> anyhow::Result<FieldsSnippetGenerator> {
+) -> anyhow::Result<(
+    Option<FieldsSnippetGenerator>,
+    Option<FieldsSnippetGenerator>,
+)> {
     let schema = searcher.schema();
     let query_ast_resolved = serde_json::from_str(&snippet_request.query_ast_resolved)
         .context("failed to deserialize QueryAst")?;
     let (query, _) = doc_mapper.query(schema.clone(), &query_ast_resolved, false)?;
+    let fields_snippet_generator = create_fields_snippet_generator(
+        searcher,
+        &*query,
+        &snippet_request.snippet_fields,
+        SNIPPET_MAX_NUM_CHARS,
+        None,
+    )
+    .await?;
+    let fields_highlight_generator_opt = if let Some(highlight_request) = &snippet_request.highlight
+    {
+        let highlight_tags = HighlightTags {
+            pre_tag: highlight_request
+                .pre_tag
+                .clone()
+                .unwrap_or_else(|| DEFAULT_HIGHLIGHT_PRE_TAG.to_string()),
+            post_tag: highlight_request
+                .post_tag
+                .clone()
+                .unwrap_or_else(|| DEFAULT_HIGHLIGHT_POST_TAG.to_string()),
+        };
+        let max_num_chars = highlight_request
+            .fragment_size
+            .map(|fragment_size| fragment_size as usize)
+            .unwrap_or(SNIPPET_MAX_NUM_CHARS);
+        let fields_highlight_generator = create_fields_snippet_generator(
+            searcher,
+            &*query,
+            &highlight_request.fields,
+            max_num_chars,
+            Some(highlight_tags),
+        )
+        .await?;
+        Some(fields_highlight_generator)
+    } else {
+        None
+    };
+    Ok((
+        Some(fields_snippet_generator),
+        fields_highlight_generator_opt,
+    ))
+}
+
+// Creates FieldsSnippetGenerator.
+async fn create_fields_snippet_generator(
+    searcher: &Searcher,
+    query: &dyn Query,
+    field_names: &[String],
+    max_num_chars: usize,
+    highlight_tags_opt: Option<HighlightTags>,
+) -> anyhow::Result<FieldsSnippetGenerator> {
+    let schema = searcher.schema();
     let mut snippet_generators = HashMap::new();
-    for field_name in &snippet_request.snippet_fields {
-        let field = schema.get_field(field_name)?;
-        let snippet_generator = create_snippet_generator(searcher, &query, field).await?;
+    for field_name in field_names {
+        // The field may have been added to the doc mapping after the split was created.
+        let Ok(field) = schema.get_field(field_name) else {
+            continue;
+        };
+        let snippet_generator =
+            create_snippet_generator(searcher, query, field, max_num_chars).await?;
         snippet_generators.insert(field_name.clone(), snippet_generator);
     }
 
     Ok(FieldsSnippetGenerator {
         field_generators: Arc::new(snippet_generators),
+        highlight_tags_opt: highlight_tags_opt.map(Arc::new),
     })
 }
 
@@ -320,6 +477,7 @@ async fn create_snippet_generator(
     searcher: &Searcher,
     query: &dyn Query,
     field: Field,
+    max_num_chars: usize,
 ) -> anyhow::Result<SnippetGenerator> {
     let mut terms: Vec<&Term> = Vec::new();
     // TODO ok with termset?
@@ -345,6 +503,6 @@ async fn create_snippet_generator(
         terms_text,
         tokenizer,
         field,
-        SNIPPET_MAX_NUM_CHARS,
+        max_num_chars,
     ))
 }
@@ -0,0 +1,336 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::query::{EnableScoring, Query, Scorer};
+use tantivy::{DocId, DocSet, Score, SegmentReader};
+
+/// Computes, for each of the requested fields, the number of documents matching the query in
+/// which the field is present. The presence of a field is read from the `_field_presence` field or
+/// from the fast field column, like for an `exists` query. This helps auditing the schema drift
+/// of the indexes relying on dynamic mapping.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCoverageCollector {
+    /// The names of the fields whose coverage is computed.
+    #[serde(rename = "field_coverage")]
+    pub field_names: Vec<String>,
+    /// The queries matching the documents in which each field is present. They are built
+    /// against the schema of the split by the leaf before searching it.
+    #[serde(skip)]
+    pub(crate) field_presence_queries: Vec<Arc<dyn Query>>,
+}
+
+impl FieldCoverageCollector {
+    /// Sets the queries matching the documents in which each field is present. The queries must
+    /// be in the same order as `field_names`.
+    pub(crate) fn set_field_presence_queries(
+        &mut self,
+        field_presence_queries: Vec<Arc<dyn Query>>,
+    ) {
+        assert_eq!(field_presence_queries.len(), self.field_names.len());
+        self.field_presence_queries = field_presence_queries;
+    }
+
+    /// Turns the merged fruit of the collector into the final aggregation result.
+    pub(crate) fn finalize(&self, fruit: FieldCoverage) -> FieldCoverageResult {
+        let fields = self
+            .field_names
+            .iter()
+            .zip(
+                fruit
+                    .field_num_docs
+                    .iter()
+                    .copied()
+                    .chain(std::iter::repeat(0)),
+            )
+            .map(|(field_name, num_docs)| {
+                let coverage_percent = if fruit.num_docs == 0 {
+                    0.0
+                } else {
+                    num_docs as f64 * 100.0 / fruit.num_docs as f64
+                };
+                let field_coverage = FieldCoverageResultEntry {
+                    doc_count: num_docs,
+                    coverage_percent,
+                };
+                (field_name.clone(), field_coverage)
+            })
+            .collect();
+        FieldCoverageResult {
+            doc_count: fruit.num_docs,
+            fields,
+        }
+    }
+}
+
+/// Intermediate result of the [`FieldCoverageCollector`].
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FieldCoverage {
+    /// Number of documents matching the query.
+    pub num_docs: u64,
+    /// Number of documents matching the query in which the field is present, in the order of the
+    /// requested fields.
+    pub field_num_docs: Vec<u64>,
+}
+
+impl FieldCoverage {
+    fn new(num_fields: usize) -> Self {
+        Self {
+            num_docs: 0,
+            field_num_docs: vec![0; num_fields],
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: FieldCoverage) {
+        self.num_docs += other.num_docs;
+
+        if self.field_num_docs.len() < other.field_num_docs.len() {
+            self.field_num_docs.resize(other.field_num_docs.len(), 0);
+        }
+        for (num_docs, other_num_docs) in self.field_num_docs.iter_mut().zip(other.field_num_docs) {
+            *num_docs += other_num_docs;
+        }
+    }
+}
+
+/// Final result of the [`FieldCoverageCollector`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldCoverageResult {
+    /// Number of documents matching the query.
+    pub doc_count: u64,
+    /// Coverage of each requested field.
+    pub fields: BTreeMap<String, FieldCoverageResultEntry>,
+}
+
+/// Coverage of a single field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldCoverageResultEntry {
+    /// Number of documents matching the query in which the field is present.
+    pub doc_count: u64,
+    /// Percentage of the documents matching the query in which the field is present.
+    pub coverage_percent: f64,
+}
+
+impl Collector for FieldCoverageCollector {
+    type Fruit = FieldCoverage;
+    type Child = FieldCoverageSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: u32,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        if self.field_presence_queries.len() != self.field_names.len() {
+            return Err(tantivy::TantivyError::InternalError(
+                "field presence queries of the field coverage collector are not set".to_string(),
+            ));
+        }
+        let field_presence_scorers = self
+            .field_presence_queries
+            .iter()
+            .map(|field_presence_query| {
+                let enable_scoring = EnableScoring::disabled_from_schema(segment_reader.schema());
+                field_presence_query
+                    .weight(enable_scoring)?
+                    .scorer(segment_reader, 1.0)
+            })
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        Ok(FieldCoverageSegmentCollector {
+            field_presence_scorers,
+            field_coverage: FieldCoverage::new(self.field_names.len()),
+        })
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        Ok(merge_fruits(segment_fruits, self.field_names.len()))
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) fn merge_fruits(fruits: Vec<FieldCoverage>, num_fields: usize) -> FieldCoverage {
+    let mut merged_fruit = FieldCoverage::new(num_fields);
+
+    for fruit in fruits {
+        merged_fruit.merge(fruit);
+    }
+    merged_fruit
+}
+
+/// Segment collector of the [`FieldCoverageCollector`].
+pub struct FieldCoverageSegmentCollector {
+    field_presence_scorers: Vec<Box<dyn Scorer>>,
+    field_coverage: FieldCoverage,
+}
+
+impl SegmentCollector for FieldCoverageSegmentCollector {
+    type Fruit = FieldCoverage;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        self.field_coverage.num_docs += 1;
+
+        // The matching documents are collected in increasing order, so we can advance the field
+        // presence doc sets monotonically.
+        for (field_presence_scorer, num_docs) in self
+            .field_presence_scorers
+            .iter_mut()
+            .zip(self.field_coverage.field_num_docs.iter_mut())
+        {
+            if field_presence_scorer.doc() < doc {
+                field_presence_scorer.seek(doc);
+            }
+            if field_presence_scorer.doc() == doc {
+                *num_docs += 1;
+            }
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.field_coverage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::query::{AllQuery, ExistsQuery, TermQuery};
+    use tantivy::schema::{IndexRecordOption, Schema, FAST, STRING};
+    use tantivy::{doc, Index, Term};
+
+    use super::*;
+    use crate::collector::QuickwitAggregations;
+
+    #[test]
+    fn test_field_coverage_collector_serde() {
+        let aggregation: QuickwitAggregations =
+            serde_json::from_str(r#"{"field_coverage": ["severity_text", "attributes.color"]}"#)
+                .unwrap();
+        let QuickwitAggregations::FieldCoverageAggregation(collector) = aggregation else {
+            panic!("expected FieldCoverageAggregation");
+        };
+        assert_eq!(collector.field_names, ["severity_text", "attributes.color"]);
+
+        // A Tantivy aggregation named `field_coverage` is not mistaken for a field coverage
+        // aggregation.
+        let aggregation: QuickwitAggregations =
+            serde_json::from_str(r#"{"field_coverage": {"terms": {"field": "severity_text"}}}"#)
+                .unwrap();
+        assert!(matches!(
+            aggregation,
+            QuickwitAggregations::TantivyAggregations(_)
+        ));
+    }
+
+    #[test]
+    fn test_field_coverage_collector() {
+        let mut schema_builder = Schema::builder();
+        let severity_field = schema_builder.add_text_field("severity", STRING | FAST);
+        let status_field = schema_builder.add_u64_field("status", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(severity_field => "INFO", status_field => 200u64))
+            .unwrap();
+        index_writer
+            .add_document(doc!(severity_field => "ERROR"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(severity_field => "INFO"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(status_field => 500u64))
+            .unwrap();
+        index_writer.commit().unwrap();
+
+        let searcher = index.reader().unwrap().searcher();
+
+        let mut collector = FieldCoverageCollector {
+            field_names: vec!["severity".to_string(), "status".to_string()],
+            field_presence_queries: Vec::new(),
+        };
+        let field_presence_queries: Vec<Arc<dyn Query>> = vec![
+            Arc::new(ExistsQuery::new_exists_query("severity".to_string())),
+            Arc::new(ExistsQuery::new_exists_query("status".to_string())),
+        ];
+        collector.set_field_presence_queries(field_presence_queries);
+
+        let fruit = searcher.search(&AllQuery, &collector).unwrap();
+        assert_eq!(
+            fruit,
+            FieldCoverage {
+                num_docs: 4,
+                field_num_docs: vec![3, 2],
+            }
+        );
+        let result = collector.finalize(fruit);
+        assert_eq!(result.doc_count, 4);
+        assert_eq!(result.fields["severity"].doc_count, 3);
+        assert_eq!(result.fields["severity"].coverage_percent, 75.0);
+        assert_eq!(result.fields["status"].doc_count, 2);
+        assert_eq!(result.fields["status"].coverage_percent, 50.0);
+
+        let fruit = searcher
+            .search(
+                &TermQuery::new(
+                    Term::from_field_text(severity_field, "INFO"),
+                    IndexRecordOption::Basic,
+                ),
+                &collector,
+            )
+            .unwrap();
+        assert_eq!(
+            fruit,
+            FieldCoverage {
+                num_docs: 2,
+                field_num_docs: vec![2, 1],
+            }
+        );
+    }
+
+    #[test]
+    fn test_field_coverage_merge_fruits() {
+        let fruits = vec![
+            FieldCoverage {
+                num_docs: 3,
+                field_num_docs: vec![1, 2],
+            },
+            FieldCoverage {
+                num_docs: 5,
+                field_num_docs: vec![4, 0],
+            },
+        ];
+        let merged_fruit = merge_fruits(fruits, 2);
+        assert_eq!(
+            merged_fruit,
+            FieldCoverage {
+                num_docs: 8,
+                field_num_docs: vec![5, 2],
+            }
+        );
+    }
+}
@@ -20,7 +20,9 @@
 use std::collections::{HashMap, HashSet};
 use std::ops::Bound;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use anyhow::Context;
 use futures::future::try_join_all;
@@ -29,20 +31,26 @@ use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
 use quickwit_doc_mapper::{DocMapper, TermRange, WarmupInfo};
 use quickwit_proto::search::{
     CountHits, LeafSearchResponse, PartialHit, SearchRequest, SortOrder, SortValue,
-    SplitIdAndFooterOffsets, SplitSearchError,
+    SplitIdAndFooterOffsets, SplitSearchError, SplitSearchProfile,
+};
+use quickwit_query::query_ast::{
+    BoolQuery, FieldPresenceQuery, JoinQuery, QueryAst, QueryAstTransformer, RangeQuery, TermQuery,
 };
-use quickwit_query::query_ast::{BoolQuery, QueryAst, QueryAstTransformer, RangeQuery, TermQuery};
 use quickwit_query::tokenizers::TokenizerManager;
 use quickwit_storage::{
-    wrap_storage_with_cache, BundleStorage, MemorySizedCache, OwnedBytes, SplitCache, Storage,
+    wrap_storage_with_cache, wrap_storage_with_read_stats, BundleStorage, MemorySizedCache,
+    OwnedBytes, SplitCache, Storage,
 };
+use tantivy::collector::Collector;
 use tantivy::directory::FileSlice;
 use tantivy::fastfield::FastFieldReaders;
-use tantivy::schema::Field;
-use tantivy::{DateTime, Index, ReloadPolicy, Searcher, Term};
+use tantivy::schema::{Field, FieldType, Schema};
+use tantivy::{DateTime, DateTimePrecision, Index, ReloadPolicy, Searcher, Term};
 use tracing::*;
 
 use crate::collector::{make_collector_for_split, make_merge_collector, IncrementalCollector};
+use crate::field_coverage_collector::FieldCoverageCollector;
+use crate::query_limits::check_query_limits;
 use crate::service::SearcherContext;
 use crate::SearchError;
 
@@ -335,17 +343,47 @@ async fn leaf_search_single_split(
     split: SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
 ) -> crate::Result<LeafSearchResponse> {
+    let timestamp_precision = doc_mapper
+        .timestamp_field_name()
+        .map(|timestamp_field| timestamp_field_precision(&doc_mapper.schema(), timestamp_field))
+        .unwrap_or_default();
+    // The request time range is folded into the query AST by the rewrite below.
+    let request_time_range = (search_request.start_timestamp, search_request.end_timestamp);
     rewrite_request(
         &mut search_request,
         &split,
         doc_mapper.timestamp_field_name(),
+        timestamp_precision,
     );
-    if let Some(cached_answer) = searcher_context
+    // Profiling does not change the result of the search, so it is not part of the cache key.
+    let profile = std::mem::take(&mut search_request.profile);
+
+    if let Some(mut cached_answer) = searcher_context
         .leaf_search_cache
         .get(split.clone(), search_request.clone())
     {
+        if profile {
+            cached_answer.split_profiles = vec![SplitSearchProfile {
+                split_id: split.split_id.clone(),
+                leaf_cache_hit: true,
+                ..Default::default()
+            }];
+        }
         return Ok(cached_answer);
     }
+    let planning_start = Instant::now();
+
+    let (storage, read_stats_opt) = if profile {
+        let (storage, read_stats) = wrap_storage_with_read_stats(storage);
+        (storage, Some(read_stats))
+    } else {
+        (storage, None)
+    };
+    let footer_cache_hit = profile
+        && searcher_context
+            .split_footer_cache
+            .get(&split.split_id)
+            .is_some();
 
     let split_id = split.split_id.to_string();
     let index = open_index_with_caches(
@@ -358,41 +396,316 @@ async fn leaf_search_single_split(
     .await?;
     let split_schema = index.schema();
 
-    let quickwit_collector = make_collector_for_split(
+    let mut quickwit_collector = make_collector_for_split(
         split_id.clone(),
         &search_request,
         searcher_context.get_aggregation_limits(),
     )?;
-    let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
-        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-    let (query, mut warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
+    if profile {
+        quickwit_collector.aggregation_nanos_opt = Some(Arc::new(AtomicU64::new(0)));
+    }
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = reader.searcher();
 
+    let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast = if searcher_context.searcher_config.enable_fast_field_pushdown {
+        push_down_term_filters_to_fast_fields(
+            query_ast,
+            &searcher,
+            &split,
+            doc_mapper.timestamp_field_name(),
+            request_time_range,
+            searcher_context
+                .searcher_config
+                .fast_field_scan_min_doc_freq,
+            quickwit_collector.requires_scoring(),
+        )
+        .await?
+    } else {
+        query_ast
+    };
+    let (query, mut warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
+
+    if let Some(crate::QuickwitAggregations::FieldCoverageAggregation(field_coverage_collector)) =
+        quickwit_collector.aggregation.as_mut()
+    {
+        let field_presence_warmup_info = set_field_presence_queries(
+            field_coverage_collector,
+            &split_schema,
+            doc_mapper.as_ref(),
+        )?;
+        warmup_info.merge(field_presence_warmup_info);
+    }
     let collector_warmup_info = quickwit_collector.warmup_info();
     warmup_info.merge(collector_warmup_info);
     warmup_info.simplify();
+    let planning_elapsed = planning_start.elapsed();
 
+    let warmup_start = Instant::now();
     warmup(&searcher, &warmup_info).await?;
+    let io_wait_elapsed = warmup_start.elapsed();
+
+    let aggregation_nanos_opt = quickwit_collector.aggregation_nanos_opt.clone();
     let span = info_span!("tantivy_search");
-    let leaf_search_response = crate::run_cpu_intensive(move || {
+    let (leaf_search_result, search_elapsed) = crate::run_cpu_intensive(move || {
         let _span_guard = span.enter();
-        searcher.search(&query, &quickwit_collector)
+        let search_start = Instant::now();
+        let leaf_search_result = searcher.search(&query, &quickwit_collector);
+        (leaf_search_result, search_start.elapsed())
     })
     .await
-    .map_err(|_| {
-        crate::SearchError::Internal(format!("leaf search panicked. split={split_id}"))
-    })??;
+    .map_err(|_| crate::SearchError::Internal(format!("leaf search panicked. split={split_id}")))?;
+    let mut leaf_search_response = leaf_search_result?;
 
     searcher_context
         .leaf_search_cache
         .put(split, search_request, leaf_search_response.clone());
+
+    if let Some(read_stats) = read_stats_opt {
+        let aggregate_micros = aggregation_nanos_opt
+            .map(|aggregation_nanos| aggregation_nanos.load(Ordering::Relaxed) / 1_000)
+            .unwrap_or_default();
+        let search_micros = search_elapsed.as_micros() as u64;
+        leaf_search_response.split_profiles = vec![SplitSearchProfile {
+            split_id,
+            planning_micros: planning_elapsed.as_micros() as u64,
+            io_wait_micros: io_wait_elapsed.as_micros() as u64,
+            match_micros: search_micros.saturating_sub(aggregate_micros),
+            aggregate_micros,
+            fetch_micros: 0,
+            num_bytes_read: read_stats.num_bytes_read(),
+            num_storage_requests: read_stats.num_requests(),
+            footer_cache_hit,
+            leaf_cache_hit: false,
+        }];
+    }
     Ok(leaf_search_response)
 }
 
+/// Builds the queries matching the documents in which the fields of a field coverage aggregation
+/// are present, and returns the information required to warm them up.
+fn set_field_presence_queries(
+    field_coverage_collector: &mut FieldCoverageCollector,
+    split_schema: &Schema,
+    doc_mapper: &dyn DocMapper,
+) -> crate::Result<WarmupInfo> {
+    let mut warmup_info = WarmupInfo::default();
+    let mut field_presence_queries = Vec::with_capacity(field_coverage_collector.field_names.len());
+
+    for field_name in &field_coverage_collector.field_names {
+        let field_presence_query_ast: QueryAst = FieldPresenceQuery {
+            field: field_name.clone(),
+        }
+        .into();
+        let (field_presence_query, field_presence_warmup_info) =
+            doc_mapper.query(split_schema.clone(), &field_presence_query_ast, false)?;
+        field_presence_queries.push(Arc::from(field_presence_query));
+        warmup_info.merge(field_presence_warmup_info);
+    }
+    field_coverage_collector.set_field_presence_queries(field_presence_queries);
+    Ok(warmup_info)
+}
+
+/// A term filter is evaluated by scanning its fast field over the time slice targeted by the query,
+/// rather than by reading its posting list, when the posting list is more than this many times
+/// larger than the estimated number of documents in the time slice.
+const FAST_FIELD_SCAN_DOC_FREQ_RATIO: u64 = 8;
+
+/// Replaces the term queries of a bool query filtering on a time range by equivalent range queries
+/// when the latter are cheaper to evaluate.
+///
+/// On low-cardinality fields (status codes, severity levels, ...), the posting list of a term can
+/// be huge while the time range only selects a small slice of the split. In that case, scanning
+/// the fast field of the documents of the time slice is cheaper than fetching and intersecting the
+/// whole posting list. This only applies to numerical fields that are both indexed and fast, for
+/// which a range query with equal bounds is executed as a fast field scan.
+///
+/// Term queries in `must` clauses are only rewritten when scoring is not required, since range
+/// queries do not score documents like term queries do. Posting lists with fewer than
+/// `min_doc_freq` documents are always considered cheap enough to read.
+async fn push_down_term_filters_to_fast_fields(
+    query_ast: QueryAst,
+    searcher: &Searcher,
+    split: &SplitIdAndFooterOffsets,
+    timestamp_field_opt: Option<&str>,
+    request_time_range: (Option<i64>, Option<i64>),
+    min_doc_freq: u64,
+    requires_scoring: bool,
+) -> crate::Result<QueryAst> {
+    let Some(timestamp_field) = timestamp_field_opt else {
+        return Ok(query_ast);
+    };
+    let QueryAst::Bool(mut bool_query) = query_ast else {
+        return Ok(query_ast);
+    };
+    let Some(time_slice_num_docs) = estimate_time_slice_num_docs(
+        &bool_query,
+        split,
+        timestamp_field,
+        request_time_range,
+        searcher.num_docs(),
+    ) else {
+        return Ok(bool_query.into());
+    };
+    let schema = searcher.schema();
+
+    for query_ast in &mut bool_query.filter {
+        push_down_term_query(
+            query_ast,
+            schema,
+            searcher,
+            time_slice_num_docs,
+            min_doc_freq,
+        )
+        .await?;
+    }
+    if !requires_scoring {
+        for query_ast in &mut bool_query.must {
+            push_down_term_query(
+                query_ast,
+                schema,
+                searcher,
+                time_slice_num_docs,
+                min_doc_freq,
+            )
+            .await?;
+        }
+    }
+    Ok(bool_query.into())
+}
+
+async fn push_down_term_query(
+    query_ast: &mut QueryAst,
+    schema: &Schema,
+    searcher: &Searcher,
+    time_slice_num_docs: u64,
+    min_doc_freq: u64,
+) -> crate::Result<()> {
+    let QueryAst::Term(term_query) = query_ast else {
+        return Ok(());
+    };
+    let Some(term) = fast_field_term(schema, term_query) else {
+        return Ok(());
+    };
+    let doc_freq = searcher.doc_freq_async(&term).await?;
+
+    if !should_scan_fast_field(doc_freq, time_slice_num_docs, min_doc_freq) {
+        return Ok(());
+    }
+    debug!(
+        field = term_query.field,
+        doc_freq, time_slice_num_docs, "pushing down term filter to fast field"
+    );
+    let field = term_query.field.clone();
+    let value = quickwit_query::JsonLiteral::String(term_query.value.clone());
+    *query_ast = RangeQuery {
+        field,
+        lower_bound: Bound::Included(value.clone()),
+        upper_bound: Bound::Included(value),
+    }
+    .into();
+    Ok(())
+}
+
+/// Returns the term looked up by a term query targeting a numerical field that is both indexed and
+/// fast.
+fn fast_field_term(schema: &Schema, term_query: &TermQuery) -> Option<Term> {
+    use quickwit_query::InterpretUserInput;
+
+    let field = schema.get_field(&term_query.field).ok()?;
+    let field_entry = schema.get_field_entry(field);
+
+    if !field_entry.is_indexed() || !field_entry.is_fast() {
+        return None;
+    }
+    match field_entry.field_type() {
+        FieldType::U64(_) => {
+            u64::interpret_str(&term_query.value).map(|value| Term::from_field_u64(field, value))
+        }
+        FieldType::I64(_) => {
+            i64::interpret_str(&term_query.value).map(|value| Term::from_field_i64(field, value))
+        }
+        FieldType::F64(_) => {
+            f64::interpret_str(&term_query.value).map(|value| Term::from_field_f64(field, value))
+        }
+        _ => None,
+    }
+}
+
+/// Estimates the number of documents of the split matching the timestamp range filter of a bool
+/// query and the time range `[start_timestamp, end_timestamp)` of the request, assuming documents
+/// are evenly distributed over the time range of the split.
+///
+/// Returns `None` if neither the query nor the request filter on time or the split has no time
+/// range.
+fn estimate_time_slice_num_docs(
+    bool_query: &BoolQuery,
+    split: &SplitIdAndFooterOffsets,
+    timestamp_field: &str,
+    request_time_range: (Option<i64>, Option<i64>),
+    num_docs: u64,
+) -> Option<u64> {
+    use quickwit_query::InterpretUserInput;
+
+    let split_start_secs = split.timestamp_start?;
+    let split_end_secs = split.timestamp_end?;
+
+    let timestamp_range_opt = bool_query
+        .filter
+        .iter()
+        .find_map(|query_ast| match query_ast {
+            QueryAst::Range(range_query) if range_query.field == timestamp_field => {
+                Some(range_query)
+            }
+            _ => None,
+        });
+    let (request_start_secs_opt, request_end_secs_opt) = request_time_range;
+
+    if timestamp_range_opt.is_none()
+        && request_start_secs_opt.is_none()
+        && request_end_secs_opt.is_none()
+    {
+        return None;
+    }
+    let mut time_slice_start_secs = split_start_secs;
+    let mut time_slice_end_secs = split_end_secs;
+
+    if let Some(timestamp_range) = timestamp_range_opt {
+        if let Bound::Included(bound) | Bound::Excluded(bound) = &timestamp_range.lower_bound {
+            let query_start_secs = DateTime::interpret_json(bound)?.into_timestamp_secs();
+            time_slice_start_secs = time_slice_start_secs.max(query_start_secs);
+        }
+        if let Bound::Included(bound) | Bound::Excluded(bound) = &timestamp_range.upper_bound {
+            let query_end_secs = DateTime::interpret_json(bound)?.into_timestamp_secs();
+            time_slice_end_secs = time_slice_end_secs.min(query_end_secs);
+        }
+    }
+    if let Some(request_start_secs) = request_start_secs_opt {
+        time_slice_start_secs = time_slice_start_secs.max(request_start_secs);
+    }
+    if let Some(request_end_secs) = request_end_secs_opt {
+        time_slice_end_secs = time_slice_end_secs.min(request_end_secs.saturating_sub(1));
+    }
+
+    if time_slice_end_secs < time_slice_start_secs {
+        return Some(0);
+    }
+    // Split time ranges are inclusive.
+    let split_duration_secs = (split_end_secs - split_start_secs + 1) as u128;
+    let time_slice_duration_secs = (time_slice_end_secs - time_slice_start_secs + 1) as u128;
+    let time_slice_num_docs = num_docs as u128 * time_slice_duration_secs / split_duration_secs;
+    Some(time_slice_num_docs as u64)
+}
+
+fn should_scan_fast_field(doc_freq: u64, time_slice_num_docs: u64, min_doc_freq: u64) -> bool {
+    doc_freq >= min_doc_freq
+        && doc_freq > time_slice_num_docs.saturating_mul(FAST_FIELD_SCAN_DOC_FREQ_RATIO)
+}
+
 /// Rewrite a request removing parts which incure additional download or computation with no
 /// effect.
 ///
@@ -402,12 +715,29 @@ fn rewrite_request(
     search_request: &mut SearchRequest,
     split: &SplitIdAndFooterOffsets,
     timestamp_field: Option<&str>,
+    timestamp_precision: DateTimePrecision,
 ) {
     if search_request.max_hits == 0 {
         search_request.sort_fields = Vec::new();
     }
     if let Some(timestamp_field) = timestamp_field {
-        remove_redundant_timestamp_range(search_request, split, timestamp_field);
+        remove_redundant_timestamp_range(
+            search_request,
+            split,
+            timestamp_field,
+            timestamp_precision,
+        );
+    }
+}
+
+/// Returns the precision with which the timestamp field is stored in the fast field.
+fn timestamp_field_precision(schema: &Schema, timestamp_field: &str) -> DateTimePrecision {
+    let Ok(field) = schema.get_field(timestamp_field) else {
+        return DateTimePrecision::default();
+    };
+    match schema.get_field_entry(field).field_type() {
+        FieldType::Date(date_options) => date_options.get_precision(),
+        _ => DateTimePrecision::default(),
     }
 }
 
@@ -477,10 +807,14 @@ fn min_bound<T: Ord + Copy>(left: Bound<T>, right: Bound<T>) -> Bound<T> {
 ///
 /// this can save us from doing double the work in some cases, and help with the partial request
 /// cache.
+///
+/// Split time ranges are truncated to the second. When the timestamp field has a sub-second
+/// precision, the split may contain documents up to the very end of its last second.
 fn remove_redundant_timestamp_range(
     search_request: &mut SearchRequest,
     split: &SplitIdAndFooterOffsets,
     timestamp_field: &str,
+    timestamp_precision: DateTimePrecision,
 ) {
     let Ok(query_ast) = serde_json::from_str(search_request.query_ast.as_str()) else {
         // an error will get raised a bit after anyway
@@ -529,10 +863,19 @@ fn remove_redundant_timestamp_range(
         (Bound::Unbounded, Some(_)) => Bound::Unbounded,
         (timestamp, None) => timestamp,
     };
-    let final_end_timestamp = match (
-        visitor.end_timestamp,
-        split.timestamp_end.map(DateTime::from_timestamp_secs),
-    ) {
+    let split_end_timestamp = split.timestamp_end.map(|timestamp_end| {
+        if timestamp_precision == DateTimePrecision::Seconds {
+            DateTime::from_timestamp_secs(timestamp_end)
+        } else {
+            DateTime::from_timestamp_nanos(
+                timestamp_end
+                    .saturating_add(1)
+                    .saturating_mul(1_000_000_000)
+                    .saturating_sub(1),
+            )
+        }
+    });
+    let final_end_timestamp = match (visitor.end_timestamp, split_end_timestamp) {
         (Bound::Included(query_ts), Some(split_ts)) => {
             if query_ts < split_ts {
                 Bound::Included(query_ts)
@@ -675,6 +1018,11 @@ impl<'a> QueryAstTransformer for RemoveTimestampRange<'a> {
         // doesn't require loading a fastfield
         Ok(Some(QueryAst::Term(term_query)))
     }
+
+    fn transform_join(&mut self, join_query: JoinQuery) -> Result<Option<QueryAst>, Self::Err> {
+        // The timestamp range of the underlying query does not bound the matched documents.
+        Ok(Some(QueryAst::Join(join_query)))
+    }
 }
 
 pub(crate) fn rewrite_start_end_time_bounds(
@@ -830,6 +1178,10 @@ pub async fn leaf_search(
 ) -> Result<LeafSearchResponse, SearchError> {
     info!(splits_num = splits.len(), split_offsets = ?PrettySample::new(&splits, 5));
 
+    let query_ast: QueryAst = serde_json::from_str(request.query_ast.as_str())
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    check_query_limits(&query_ast, &searcher_context.searcher_config)?;
+
     let split_filter = CanSplitDoBetter::from_request(&request, doc_mapper.timestamp_field_name());
     split_filter.optimize_split_order(&mut splits);
 
@@ -972,6 +1324,9 @@ async fn leaf_search_single_split_wrapper(
 mod tests {
     use std::ops::Bound;
 
+    use tantivy::schema::{FAST, INDEXED};
+    use tantivy::TantivyDocument;
+
     use super::*;
 
     fn bool_filter(ast: impl Into<QueryAst>) -> QueryAst {
@@ -1001,7 +1356,12 @@ mod tests {
 
         // test the query directly
         let mut request_direct = request.clone();
-        remove_redundant_timestamp_range(&mut request_direct, split, timestamp_field);
+        remove_redundant_timestamp_range(
+            &mut request_direct,
+            split,
+            timestamp_field,
+            DateTimePrecision::Seconds,
+        );
         let expected_direct = expected
             .clone()
             .map(bool_filter)
@@ -1196,7 +1556,301 @@ mod tests {
             timestamp_end: Some(time3),
             ..SplitIdAndFooterOffsets::default()
         };
-        remove_redundant_timestamp_range(&mut search_request, &split, &timestamp_field);
+        remove_redundant_timestamp_range(
+            &mut search_request,
+            &split,
+            &timestamp_field,
+            DateTimePrecision::Seconds,
+        );
         assert_ast_eq(&search_request, &QueryAst::MatchAll);
     }
+
+    #[test]
+    fn test_remove_timestamp_range_sub_second_precision() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let time1 = 1700001000;
+        let time2 = 1700002000;
+        let timestamp_field = "timestamp";
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time1),
+            timestamp_end: Some(time2),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Unbounded,
+                upper_bound: Bound::Included(time2.into()),
+            }))
+            .unwrap(),
+            ..SearchRequest::default()
+        };
+        // With a precision of a second, the split ends at time2 and the range is redundant.
+        let mut search_request_secs = search_request.clone();
+        remove_redundant_timestamp_range(
+            &mut search_request_secs,
+            &split,
+            timestamp_field,
+            DateTimePrecision::Seconds,
+        );
+        assert_ast_eq(&search_request_secs, &QueryAst::MatchAll);
+
+        // With a sub-second precision, the split may hold documents until the very end of time2,
+        // so the upper bound must be kept.
+        let mut search_request_nanos = search_request.clone();
+        remove_redundant_timestamp_range(
+            &mut search_request_nanos,
+            &split,
+            timestamp_field,
+            DateTimePrecision::Nanoseconds,
+        );
+        let expected = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Included((time2 * S_TO_NS).into()),
+        };
+        assert_ast_eq(&search_request_nanos, &bool_filter(expected));
+    }
+
+    #[test]
+    fn test_estimate_time_slice_num_docs() {
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(1_000),
+            timestamp_end: Some(1_999),
+            ..Default::default()
+        };
+        let timestamp_range =
+            |lower_bound_secs: Bound<i64>, upper_bound_secs: Bound<i64>| BoolQuery {
+                filter: vec![RangeQuery {
+                    field: "timestamp".to_string(),
+                    lower_bound: map_bound(lower_bound_secs, |secs| (secs * 1_000_000_000).into()),
+                    upper_bound: map_bound(upper_bound_secs, |secs| (secs * 1_000_000_000).into()),
+                }
+                .into()],
+                ..Default::default()
+            };
+        let bool_query = timestamp_range(Bound::Included(1_500), Bound::Excluded(1_600));
+        assert_eq!(
+            estimate_time_slice_num_docs(&bool_query, &split, "timestamp", (None, None), 10_000),
+            Some(1_010)
+        );
+        assert_eq!(
+            estimate_time_slice_num_docs(
+                &bool_query,
+                &split,
+                "other_timestamp",
+                (None, None),
+                10_000
+            ),
+            None
+        );
+        let bool_query = timestamp_range(Bound::Included(1_900), Bound::Unbounded);
+        assert_eq!(
+            estimate_time_slice_num_docs(&bool_query, &split, "timestamp", (None, None), 10_000),
+            Some(1_000)
+        );
+        let bool_query = timestamp_range(Bound::Included(3_000), Bound::Unbounded);
+        assert_eq!(
+            estimate_time_slice_num_docs(&bool_query, &split, "timestamp", (None, None), 10_000),
+            Some(0)
+        );
+        let split_without_time_range = SplitIdAndFooterOffsets::default();
+        assert_eq!(
+            estimate_time_slice_num_docs(
+                &bool_query,
+                &split_without_time_range,
+                "timestamp",
+                (None, None),
+                10_000
+            ),
+            None
+        );
+
+        // The time slice is clamped to the time range of the request.
+        let bool_query = timestamp_range(Bound::Included(1_500), Bound::Unbounded);
+        assert_eq!(
+            estimate_time_slice_num_docs(
+                &bool_query,
+                &split,
+                "timestamp",
+                (Some(1_200), Some(1_600)),
+                10_000
+            ),
+            Some(1_000)
+        );
+        let bool_query = BoolQuery::default();
+        assert_eq!(
+            estimate_time_slice_num_docs(
+                &bool_query,
+                &split,
+                "timestamp",
+                (None, Some(1_100)),
+                10_000
+            ),
+            Some(1_000)
+        );
+        assert_eq!(
+            estimate_time_slice_num_docs(&bool_query, &split, "timestamp", (None, None), 10_000),
+            None
+        );
+    }
+
+    #[test]
+    fn test_should_scan_fast_field() {
+        assert!(!should_scan_fast_field(0, 0, 0));
+        assert!(should_scan_fast_field(1, 0, 0));
+        assert!(!should_scan_fast_field(800, 100, 0));
+        assert!(should_scan_fast_field(801, 100, 0));
+
+        assert!(!should_scan_fast_field(9_999, 0, 10_000));
+        assert!(should_scan_fast_field(10_000, 0, 10_000));
+    }
+
+    #[tokio::test]
+    async fn test_push_down_term_filters_to_fast_fields() {
+        let mut schema_builder = Schema::builder();
+        let timestamp_field = schema_builder.add_date_field("timestamp", FAST);
+        let status_field = schema_builder.add_u64_field("status", INDEXED | FAST);
+        let level_field = schema_builder.add_u64_field("level", INDEXED);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+
+        for i in 0..100 {
+            let mut doc = TantivyDocument::default();
+            doc.add_date(timestamp_field, DateTime::from_timestamp_secs(i));
+            doc.add_u64(status_field, 200);
+            doc.add_u64(level_field, 1);
+            index_writer.add_document(doc).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(0),
+            timestamp_end: Some(99),
+            ..Default::default()
+        };
+        let timestamp_range: QueryAst = RangeQuery {
+            field: "timestamp".to_string(),
+            lower_bound: Bound::Included(0i64.into()),
+            upper_bound: Bound::Excluded(5_000_000_000i64.into()),
+        }
+        .into();
+        let term_query = |field: &str, value: &str| -> QueryAst {
+            TermQuery {
+                field: field.to_string(),
+                value: value.to_string(),
+            }
+            .into()
+        };
+        let fast_field_range = |field: &str, value: &str| -> QueryAst {
+            RangeQuery {
+                field: field.to_string(),
+                lower_bound: Bound::Included(value.to_string().into()),
+                upper_bound: Bound::Included(value.to_string().into()),
+            }
+            .into()
+        };
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query("status", "200")],
+            filter: vec![
+                term_query("status", "200"),
+                term_query("status", "404"),
+                term_query("level", "1"),
+                timestamp_range.clone(),
+            ],
+            ..Default::default()
+        }
+        .into();
+
+        let pushed_down_query_ast = push_down_term_filters_to_fast_fields(
+            query_ast.clone(),
+            &searcher,
+            &split,
+            Some("timestamp"),
+            (None, None),
+            0,
+            true,
+        )
+        .await
+        .unwrap();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![term_query("status", "200")],
+            filter: vec![
+                fast_field_range("status", "200"),
+                term_query("status", "404"),
+                term_query("level", "1"),
+                timestamp_range.clone(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(pushed_down_query_ast, expected_query_ast);
+
+        let pushed_down_query_ast = push_down_term_filters_to_fast_fields(
+            query_ast.clone(),
+            &searcher,
+            &split,
+            Some("timestamp"),
+            (None, None),
+            0,
+            false,
+        )
+        .await
+        .unwrap();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![fast_field_range("status", "200")],
+            filter: vec![
+                fast_field_range("status", "200"),
+                term_query("status", "404"),
+                term_query("level", "1"),
+                timestamp_range.clone(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(pushed_down_query_ast, expected_query_ast);
+
+        // The posting list of `status:200` holds 100 documents: it is only replaced by a fast field
+        // scan if it reaches the minimum document frequency.
+        let pushed_down_query_ast = push_down_term_filters_to_fast_fields(
+            query_ast.clone(),
+            &searcher,
+            &split,
+            Some("timestamp"),
+            (None, None),
+            100,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pushed_down_query_ast, expected_query_ast);
+
+        let pushed_down_query_ast = push_down_term_filters_to_fast_fields(
+            query_ast.clone(),
+            &searcher,
+            &split,
+            Some("timestamp"),
+            (None, None),
+            101,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pushed_down_query_ast, query_ast);
+
+        let pushed_down_query_ast = push_down_term_filters_to_fast_fields(
+            query_ast.clone(),
+            &searcher,
+            &split,
+            None,
+            (None, None),
+            0,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pushed_down_query_ast, query_ast);
+    }
 }
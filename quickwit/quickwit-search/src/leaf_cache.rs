@@ -21,7 +21,7 @@ use std::ops::Bound;
 
 use prost::Message;
 use quickwit_proto::search::{
-    CountHits, LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets,
+    CountHits, LeafSearchResponse, SearchPriority, SearchRequest, SplitIdAndFooterOffsets,
 };
 use quickwit_storage::{MemorySizedCache, OwnedBytes};
 
@@ -59,6 +59,9 @@ impl LeafSearchCache {
         split_info: SplitIdAndFooterOffsets,
         search_request: SearchRequest,
     ) -> Option<LeafSearchResponse> {
+        if search_request.bypass_cache {
+            return None;
+        }
         let key = CacheKey::from_split_meta_and_request(split_info, search_request);
         let encoded_result = self.content.get(&key)?;
         // this should never fail
@@ -76,6 +79,10 @@ impl LeafSearchCache {
         let encoded_result = result.encode_to_vec();
         self.content.put(key, OwnedBytes::new(encoded_result));
     }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.content.set_capacity_in_bytes(capacity);
+    }
 }
 
 /// A key inside a [`LeafSearchCache`].
@@ -104,6 +111,13 @@ impl CacheKey {
         // it doesn't matter whether or not we count all hits at the scale of a
         // single split: either we did process it and got everything, or we didn't.
         search_request.count_hits = CountHits::CountAll.into();
+        // splits are immutable so cached leaf responses never go stale: the cache options of the
+        // request are irrelevant here.
+        search_request.bypass_cache = false;
+        search_request.max_staleness_secs = None;
+        // Neither are the tenant and the priority of the request.
+        search_request.tenant_id = None;
+        search_request.priority = SearchPriority::Interactive as i32;
 
         CacheKey {
             split_id: split_info.split_id,
@@ -248,14 +262,27 @@ mod tests {
                 sort_value2: None,
                 split_id: "split_1".to_string(),
             }],
+            split_profiles: Vec::new(),
         };
 
         assert!(cache.get(split_1.clone(), query_1.clone()).is_none());
 
         cache.put(split_1.clone(), query_1.clone(), result.clone());
         assert_eq!(cache.get(split_1.clone(), query_1.clone()).unwrap(), result);
-        assert!(cache.get(split_2, query_1).is_none());
-        assert!(cache.get(split_1, query_2).is_none());
+        assert!(cache.get(split_2, query_1.clone()).is_none());
+        assert!(cache.get(split_1.clone(), query_2).is_none());
+
+        let query_1_stale = SearchRequest {
+            max_staleness_secs: Some(30),
+            ..query_1.clone()
+        };
+        assert_eq!(cache.get(split_1.clone(), query_1_stale).unwrap(), result);
+
+        let query_1_bypass = SearchRequest {
+            bypass_cache: true,
+            ..query_1
+        };
+        assert!(cache.get(split_1, query_1_bypass).is_none());
     }
 
     #[test]
@@ -334,6 +361,7 @@ mod tests {
                 sort_value2: None,
                 split_id: "split_1".to_string(),
             }],
+            split_profiles: Vec::new(),
         };
 
         // for split_1, 1 and 1bis cover different timestamp ranges
@@ -22,11 +22,16 @@
 #![allow(clippy::bool_assert_comparison)]
 #![deny(clippy::disallowed_methods)]
 
+mod bucket_script;
+mod cardinality_collector;
 mod client;
 mod cluster_client;
 mod collector;
+mod delete_preview;
 mod error;
+mod external_splits;
 mod fetch_docs;
+mod field_coverage_collector;
 mod filters;
 mod find_trace_ids_collector;
 mod leaf;
@@ -34,13 +39,24 @@ mod leaf_cache;
 mod list_fields;
 mod list_fields_cache;
 mod list_terms;
+mod lookup_table;
+mod percentiles_collector;
+mod point_in_time;
+mod post_aggregation;
+mod profile;
+mod query_access_stats;
+mod query_limits;
+mod query_rules;
+mod rerank;
 mod retry;
 mod root;
 mod scroll_context;
 mod search_job_placer;
+mod search_response_cache;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod term_digest_store;
 mod thread_pool;
 pub(crate) mod top_k_collector;
 
@@ -61,11 +77,15 @@ use tantivy::schema::NamedFieldDocument;
 /// Refer to this as `crate::Result<T>`.
 pub type Result<T> = std::result::Result<T, SearchError>;
 
+use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 
+pub use cardinality_collector::CardinalityCollector;
+pub use field_coverage_collector::FieldCoverageCollector;
 pub use find_trace_ids_collector::FindTraceIdsCollector;
-use quickwit_config::SearcherConfig;
+pub use percentiles_collector::PercentilesCollector;
+use quickwit_config::{LegalHold, SearcherConfig};
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_metastore::{
     IndexMetadata, ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
@@ -81,9 +101,19 @@ pub use crate::client::{
     create_search_client_from_channel, create_search_client_from_grpc_addr, SearchServiceClient,
 };
 pub use crate::cluster_client::ClusterClient;
+pub use crate::delete_preview::{preview_delete_query, DeleteQueryPreview, SplitDeletePreview};
 pub use crate::error::{parse_grpc_error, SearchError};
+pub use crate::external_splits::{
+    ExternalSplitMaterializer, MaterializeExternalSplitsRequest, MockExternalSplitMaterializer,
+};
 use crate::fetch_docs::fetch_docs;
 use crate::leaf::leaf_search;
+pub use crate::lookup_table::{
+    LookupTable, LookupTableStore, LOOKUP_TABLES_DIRECTORY_NAME, MAX_LOOKUP_TABLE_NUM_BYTES,
+};
+pub use crate::point_in_time::open_point_in_time;
+pub use crate::query_access_stats::QueryAccessTracker;
+use crate::root::split_comma_separated_index_id_patterns;
 pub use crate::root::{
     check_all_index_metadata_found, jobs_to_leaf_requests, root_search, IndexMetasForLeafSearch,
     SearchJob,
@@ -92,6 +122,7 @@ pub use crate::search_job_placer::{Job, SearchJobPlacer};
 pub use crate::search_response_rest::SearchResponseRest;
 pub use crate::search_stream::root_search_stream;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+pub use crate::term_digest_store::TermDigestStore;
 use crate::thread_pool::run_cpu_intensive;
 
 /// A pool of searcher clients identified by their gRPC socket address.
@@ -189,8 +220,87 @@ pub async fn list_relevant_splits(
     tags_filter_opt: Option<TagFilterAst>,
     metastore: &mut MetastoreServiceClient,
 ) -> crate::Result<Vec<SplitMetadata>> {
-    let mut query =
-        ListSplitsQuery::try_from_index_uids(index_uids)?.with_split_state(SplitState::Published);
+    let query = relevant_splits_query(index_uids, start_timestamp, end_timestamp, tags_filter_opt)?;
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let splits_metadata: Vec<SplitMetadata> = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits_metadata()
+        .await?;
+    Ok(splits_metadata)
+}
+
+/// Lists the splits under legal hold that have been marked for deletion and are relevant for a
+/// given request.
+pub(crate) async fn list_held_splits(
+    indexes_metadata: &[IndexMetadata],
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<Vec<SplitMetadata>> {
+    let legal_holds: HashMap<&IndexUid, &LegalHold> = indexes_metadata
+        .iter()
+        .filter_map(|index_metadata| {
+            let legal_hold = index_metadata.index_config.legal_hold_opt.as_ref()?;
+            Some((&index_metadata.index_uid, legal_hold))
+        })
+        .collect();
+    if legal_holds.is_empty() {
+        return Ok(Vec::new());
+    }
+    let index_uids: Vec<IndexUid> = legal_holds
+        .keys()
+        .map(|index_uid| (*index_uid).clone())
+        .collect();
+    let query = splits_query(
+        index_uids,
+        SplitState::MarkedForDeletion,
+        start_timestamp,
+        end_timestamp,
+        tags_filter_opt,
+    )?;
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let held_splits: Vec<SplitMetadata> = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits_metadata()
+        .await?
+        .into_iter()
+        .filter(|split| {
+            legal_holds
+                .get(&split.index_uid)
+                .is_some_and(|legal_hold| legal_hold.holds_split(&split.split_id))
+        })
+        .collect();
+    Ok(held_splits)
+}
+
+/// Builds the query listing the published splits relevant for a search over the given indexes,
+/// time range, and tags.
+pub(crate) fn relevant_splits_query(
+    index_uids: Vec<IndexUid>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+) -> crate::Result<ListSplitsQuery> {
+    splits_query(
+        index_uids,
+        SplitState::Published,
+        start_timestamp,
+        end_timestamp,
+        tags_filter_opt,
+    )
+}
+
+fn splits_query(
+    index_uids: Vec<IndexUid>,
+    split_state: SplitState,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+) -> crate::Result<ListSplitsQuery> {
+    let mut query = ListSplitsQuery::try_from_index_uids(index_uids)?.with_split_state(split_state);
 
     if let Some(start_ts) = start_timestamp {
         query = query.with_time_range_start_gte(start_ts);
@@ -201,13 +311,7 @@ pub async fn list_relevant_splits(
     if let Some(tags_filter) = tags_filter_opt {
         query = query.with_tags_filter(tags_filter);
     }
-    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
-    let splits_metadata: Vec<SplitMetadata> = metastore
-        .list_splits(list_splits_request)
-        .await?
-        .collect_splits_metadata()
-        .await?;
-    Ok(splits_metadata)
+    Ok(query)
 }
 
 /// Resolve index patterns and returns IndexMetadata for found indices.
@@ -216,11 +320,12 @@ pub async fn resolve_index_patterns(
     index_id_patterns: &[String],
     metastore: &mut MetastoreServiceClient,
 ) -> crate::Result<Vec<IndexMetadata>> {
+    let index_id_patterns = split_comma_separated_index_id_patterns(index_id_patterns);
     let list_indexes_metadata_request = if index_id_patterns.is_empty() {
         ListIndexesMetadataRequest::all()
     } else {
         ListIndexesMetadataRequest {
-            index_id_patterns: index_id_patterns.to_owned(),
+            index_id_patterns: index_id_patterns.clone(),
         }
     };
 
@@ -230,7 +335,7 @@ pub async fn resolve_index_patterns(
         .await?
         .deserialize_indexes_metadata()
         .await?;
-    check_all_index_metadata_found(&indexes_metadata, index_id_patterns)?;
+    check_all_index_metadata_found(&indexes_metadata, &index_id_patterns)?;
     Ok(indexes_metadata)
 }
 
@@ -51,6 +51,10 @@ impl ListFieldsCache {
         let encoded_result = serialize_split_fields(list_fields);
         self.content.put(key, OwnedBytes::new(encoded_result));
     }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.content.set_capacity_in_bytes(capacity);
+    }
 }
 
 /// A key inside a [`ListFieldsCache`].
@@ -0,0 +1,712 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Context};
+use quickwit_proto::search::{Hit, LookupEnrichment, SearchResponse};
+use quickwit_storage::{Storage, StorageErrorKind, StorageResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::SearchError;
+
+/// Maximum size of a serialized lookup table. Lookup tables are loaded in memory by the searchers,
+/// so they are meant to be small.
+pub const MAX_LOOKUP_TABLE_NUM_BYTES: usize = 16 * 1024 * 1024;
+
+/// Name of the directory of the default index root URI where the lookup tables are stored. Index
+/// IDs must start with a letter, so it cannot collide with an index directory.
+pub const LOOKUP_TABLES_DIRECTORY_NAME: &str = "_lookup-tables";
+
+/// Duration during which a searcher serves a lookup table from its cache. Updates of a table made
+/// through another node become visible after at most this duration.
+const LOOKUP_TABLE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// A lookup table maps the values of a field (the keys) to rows of values, which are added to the
+/// hits and the aggregation buckets of the searches referencing the table.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LookupTable {
+    /// Name of the column holding the keys.
+    pub key_column: String,
+    /// Rows of the table, indexed by key. The rows do not include the key column.
+    pub rows: BTreeMap<String, JsonMap<String, JsonValue>>,
+}
+
+impl LookupTable {
+    /// Parses a CSV document whose first record is the header. The keys are read from
+    /// `key_column`, or from the first column if not set. All the values are strings.
+    pub fn from_csv(csv: &str, key_column_opt: Option<&str>) -> anyhow::Result<Self> {
+        let mut records = parse_csv_records(csv)?.into_iter();
+
+        let Some(header) = records.next() else {
+            bail!("CSV document is empty");
+        };
+        let key_column_idx = match key_column_opt {
+            Some(key_column) => header
+                .iter()
+                .position(|column| column == key_column)
+                .with_context(|| {
+                    format!("CSV header does not contain key column `{key_column}`")
+                })?,
+            None => 0,
+        };
+        let mut lookup_table = LookupTable {
+            key_column: header[key_column_idx].clone(),
+            rows: BTreeMap::new(),
+        };
+        for (record_idx, record) in records.enumerate() {
+            ensure!(
+                record.len() == header.len(),
+                "CSV record #{} has {} fields, expected {}",
+                record_idx + 1,
+                record.len(),
+                header.len()
+            );
+            let mut key_opt = None;
+            let mut row = JsonMap::with_capacity(header.len() - 1);
+
+            for (column_idx, (column, value)) in header.iter().zip(record).enumerate() {
+                if column_idx == key_column_idx {
+                    key_opt = Some(value);
+                } else {
+                    row.insert(column.clone(), JsonValue::String(value));
+                }
+            }
+            let key = key_opt.expect("key column should be present");
+            lookup_table.insert_row(key, row)?;
+        }
+        Ok(lookup_table)
+    }
+
+    /// Parses a JSON document, either an array of objects whose keys are read from `key_column`,
+    /// or an object mapping each key to its row.
+    pub fn from_json(json: &[u8], key_column_opt: Option<&str>) -> anyhow::Result<Self> {
+        let json_value: JsonValue =
+            serde_json::from_slice(json).context("failed to parse JSON document")?;
+
+        match json_value {
+            JsonValue::Array(json_objs) => {
+                let key_column = key_column_opt
+                    .context("key column is required to load an array of JSON objects")?;
+                let mut lookup_table = LookupTable {
+                    key_column: key_column.to_string(),
+                    rows: BTreeMap::new(),
+                };
+                for (json_obj_idx, json_obj) in json_objs.into_iter().enumerate() {
+                    let JsonValue::Object(mut row) = json_obj else {
+                        bail!("JSON array element #{json_obj_idx} is not an object");
+                    };
+                    let key = row
+                        .remove(key_column)
+                        .as_ref()
+                        .and_then(lookup_key)
+                        .with_context(|| {
+                            format!(
+                                "JSON object #{json_obj_idx} does not have a string or number \
+                                 value for key column `{key_column}`"
+                            )
+                        })?;
+                    lookup_table.insert_row(key, row)?;
+                }
+                Ok(lookup_table)
+            }
+            JsonValue::Object(json_obj) => {
+                let mut lookup_table = LookupTable {
+                    key_column: key_column_opt.unwrap_or("key").to_string(),
+                    rows: BTreeMap::new(),
+                };
+                for (key, value) in json_obj {
+                    let JsonValue::Object(row) = value else {
+                        bail!("JSON value of key `{key}` is not an object");
+                    };
+                    lookup_table.insert_row(key, row)?;
+                }
+                Ok(lookup_table)
+            }
+            _ => bail!("JSON document must be an array or an object"),
+        }
+    }
+
+    fn insert_row(&mut self, key: String, row: JsonMap<String, JsonValue>) -> anyhow::Result<()> {
+        ensure!(!key.is_empty(), "lookup table keys must not be empty");
+
+        if self.rows.insert(key.clone(), row).is_some() {
+            bail!("duplicate key `{key}`");
+        }
+        Ok(())
+    }
+
+    /// Returns the row associated with the given key.
+    pub fn lookup(&self, key: &str) -> Option<&JsonMap<String, JsonValue>> {
+        self.rows.get(key)
+    }
+
+    /// Returns the number of rows of the table.
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+/// Parses a CSV document into records. Fields may be enclosed in double quotes, in which case they
+/// may contain commas, line breaks, and escaped (doubled) double quotes. Blank lines are ignored.
+fn parse_csv_records(csv: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            if ch != '"' {
+                field.push(ch);
+            } else if chars.peek() == Some(&'"') {
+                field.push('"');
+                chars.next();
+            } else {
+                in_quotes = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(ch),
+        }
+    }
+    ensure!(
+        !in_quotes,
+        "CSV document contains an unterminated quoted field"
+    );
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|record| record.len() > 1 || !record[0].is_empty());
+    Ok(records)
+}
+
+/// Converts a JSON value into a lookup key. Integral floats are converted into integers so that
+/// the numeric keys of the aggregation buckets match the keys of the tables.
+fn lookup_key(json_value: &JsonValue) -> Option<String> {
+    match json_value {
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Bool(boolean) => Some(boolean.to_string()),
+        JsonValue::Number(number) => {
+            if number.is_f64() {
+                let float = number.as_f64()?;
+
+                if float.fract() == 0.0 && float.abs() < i64::MAX as f64 {
+                    return Some((float as i64).to_string());
+                }
+            }
+            Some(number.to_string())
+        }
+        _ => None,
+    }
+}
+
+struct CachedLookupTable {
+    lookup_table: Arc<LookupTable>,
+    cached_at: Instant,
+}
+
+/// Stores the lookup tables as JSON files in a storage, usually under the default index root URI,
+/// and caches them in memory.
+pub struct LookupTableStore {
+    storage: Arc<dyn Storage>,
+    cache: Mutex<HashMap<String, CachedLookupTable>>,
+}
+
+impl LookupTableStore {
+    /// Creates a store of lookup tables backed by the given storage.
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            cache: Mutex::default(),
+        }
+    }
+
+    fn lookup_table_path(table_name: &str) -> PathBuf {
+        PathBuf::from(format!("{table_name}.json"))
+    }
+
+    /// Creates or replaces a lookup table.
+    pub async fn put_table(
+        &self,
+        table_name: &str,
+        lookup_table: LookupTable,
+    ) -> StorageResult<()> {
+        let payload = serde_json::to_vec(&lookup_table)
+            .map_err(|error| StorageErrorKind::Internal.with_error(error))?;
+        self.storage
+            .put(&Self::lookup_table_path(table_name), Box::new(payload))
+            .await?;
+        self.cache_table(table_name, Arc::new(lookup_table));
+        Ok(())
+    }
+
+    /// Returns a lookup table, from the cache if it was loaded recently.
+    pub async fn get_table(&self, table_name: &str) -> StorageResult<Arc<LookupTable>> {
+        let cached_lookup_table_opt = self
+            .cache
+            .lock()
+            .expect("lock should not be poisoned")
+            .get(table_name)
+            .filter(|cached_lookup_table| {
+                cached_lookup_table.cached_at.elapsed() < LOOKUP_TABLE_CACHE_TTL
+            })
+            .map(|cached_lookup_table| cached_lookup_table.lookup_table.clone());
+
+        if let Some(cached_lookup_table) = cached_lookup_table_opt {
+            return Ok(cached_lookup_table);
+        }
+        let payload = self
+            .storage
+            .get_all(&Self::lookup_table_path(table_name))
+            .await?;
+        let lookup_table: LookupTable = serde_json::from_slice(payload.as_slice())
+            .map_err(|error| StorageErrorKind::Internal.with_error(error))?;
+        let lookup_table = Arc::new(lookup_table);
+        self.cache_table(table_name, lookup_table.clone());
+        Ok(lookup_table)
+    }
+
+    /// Deletes a lookup table. Returns a not found error if the table does not exist.
+    pub async fn delete_table(&self, table_name: &str) -> StorageResult<()> {
+        let lookup_table_path = Self::lookup_table_path(table_name);
+
+        if !self.storage.exists(&lookup_table_path).await? {
+            return Err(StorageErrorKind::NotFound.with_error(anyhow::anyhow!(
+                "lookup table `{table_name}` does not exist"
+            )));
+        }
+        self.storage.delete(&lookup_table_path).await?;
+        self.cache
+            .lock()
+            .expect("lock should not be poisoned")
+            .remove(table_name);
+        Ok(())
+    }
+
+    fn cache_table(&self, table_name: &str, lookup_table: Arc<LookupTable>) {
+        let cached_lookup_table = CachedLookupTable {
+            lookup_table,
+            cached_at: Instant::now(),
+        };
+        self.cache
+            .lock()
+            .expect("lock should not be poisoned")
+            .insert(table_name.to_string(), cached_lookup_table);
+    }
+}
+
+/// A lookup of a search request along with its table.
+struct ResolvedLookup<'a> {
+    field: &'a str,
+    target_field: &'a str,
+    lookup_table: Arc<LookupTable>,
+}
+
+async fn resolve_lookups<'a>(
+    lookup_table_store_opt: Option<&LookupTableStore>,
+    lookups: &'a [LookupEnrichment],
+) -> crate::Result<Vec<ResolvedLookup<'a>>> {
+    let Some(lookup_table_store) = lookup_table_store_opt else {
+        return Err(SearchError::InvalidArgument(
+            "lookup tables are not available on this searcher".to_string(),
+        ));
+    };
+    let mut resolved_lookups = Vec::with_capacity(lookups.len());
+
+    for lookup in lookups {
+        let lookup_table = lookup_table_store
+            .get_table(&lookup.table)
+            .await
+            .map_err(|error| {
+                if error.kind() == StorageErrorKind::NotFound {
+                    SearchError::InvalidArgument(format!(
+                        "lookup table `{}` does not exist",
+                        lookup.table
+                    ))
+                } else {
+                    SearchError::Internal(format!(
+                        "failed to load lookup table `{}`: {error}",
+                        lookup.table
+                    ))
+                }
+            })?;
+        let resolved_lookup = ResolvedLookup {
+            field: &lookup.field,
+            target_field: lookup.target_field.as_deref().unwrap_or(&lookup.table),
+            lookup_table,
+        };
+        resolved_lookups.push(resolved_lookup);
+    }
+    Ok(resolved_lookups)
+}
+
+/// Adds the rows of the lookup tables to the hits and to the buckets of the terms aggregations of
+/// the response.
+pub(crate) async fn apply_lookups(
+    lookup_table_store_opt: Option<&LookupTableStore>,
+    lookups: &[LookupEnrichment],
+    aggregation_request_opt: Option<&str>,
+    search_response: &mut SearchResponse,
+) -> crate::Result<()> {
+    if lookups.is_empty() {
+        return Ok(());
+    }
+    let resolved_lookups = resolve_lookups(lookup_table_store_opt, lookups).await?;
+    enrich_hits(&resolved_lookups, &mut search_response.hits)?;
+
+    if let (Some(aggregation_request), Some(aggregation)) =
+        (aggregation_request_opt, &mut search_response.aggregation)
+    {
+        let aggregation_request: JsonMap<String, JsonValue> =
+            serde_json::from_str(aggregation_request)?;
+        let mut aggregation_json: JsonMap<String, JsonValue> = serde_json::from_str(aggregation)?;
+        enrich_aggregation_buckets(
+            &resolved_lookups,
+            &aggregation_request,
+            &mut aggregation_json,
+        );
+        *aggregation = serde_json::to_string(&aggregation_json)?;
+    }
+    Ok(())
+}
+
+/// Adds the rows of the lookup tables to the hits.
+pub(crate) async fn apply_lookups_to_hits(
+    lookup_table_store_opt: Option<&LookupTableStore>,
+    lookups: &[LookupEnrichment],
+    hits: &mut [Hit],
+) -> crate::Result<()> {
+    if lookups.is_empty() {
+        return Ok(());
+    }
+    let resolved_lookups = resolve_lookups(lookup_table_store_opt, lookups).await?;
+    enrich_hits(&resolved_lookups, hits)
+}
+
+fn enrich_hits(resolved_lookups: &[ResolvedLookup], hits: &mut [Hit]) -> crate::Result<()> {
+    for hit in hits {
+        let mut document: JsonMap<String, JsonValue> = serde_json::from_str(&hit.json)?;
+        let mut is_enriched = false;
+
+        for resolved_lookup in resolved_lookups {
+            let Some(key) = field_value(&document, resolved_lookup.field).and_then(lookup_key)
+            else {
+                continue;
+            };
+            if let Some(row) = resolved_lookup.lookup_table.lookup(&key) {
+                document.insert(
+                    resolved_lookup.target_field.to_string(),
+                    JsonValue::Object(row.clone()),
+                );
+                is_enriched = true;
+            }
+        }
+        if is_enriched {
+            hit.json = serde_json::to_string(&document)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the value of a field of a document. Nested fields are addressed with dots.
+fn field_value<'a>(document: &'a JsonMap<String, JsonValue>, field: &str) -> Option<&'a JsonValue> {
+    if let Some(value) = document.get(field) {
+        return Some(value);
+    }
+    let (parent_field, child_field) = field.split_once('.')?;
+    let JsonValue::Object(child_document) = document.get(parent_field)? else {
+        return None;
+    };
+    field_value(child_document, child_field)
+}
+
+/// Walks the aggregation request and result in parallel, and adds the rows of the lookup tables to
+/// the buckets of the terms aggregations over the looked up fields.
+fn enrich_aggregation_buckets(
+    resolved_lookups: &[ResolvedLookup],
+    aggregation_request: &JsonMap<String, JsonValue>,
+    aggregation_result: &mut JsonMap<String, JsonValue>,
+) {
+    for (aggregation_name, aggregation_def) in aggregation_request {
+        let Some(JsonValue::Object(aggregation)) = aggregation_result.get_mut(aggregation_name)
+        else {
+            continue;
+        };
+        let terms_field_opt = aggregation_def
+            .get("terms")
+            .and_then(|terms| terms.get("field"))
+            .and_then(JsonValue::as_str);
+        let sub_aggregation_request_opt = aggregation_def
+            .get("aggs")
+            .or_else(|| aggregation_def.get("aggregations"))
+            .and_then(JsonValue::as_object);
+
+        let buckets: Vec<&mut JsonValue> = match aggregation.get_mut("buckets") {
+            Some(JsonValue::Array(buckets)) => buckets.iter_mut().collect(),
+            Some(JsonValue::Object(keyed_buckets)) => keyed_buckets.values_mut().collect(),
+            _ => continue,
+        };
+        for bucket in buckets {
+            let JsonValue::Object(bucket) = bucket else {
+                continue;
+            };
+            if let Some(terms_field) = terms_field_opt {
+                let key_opt = bucket.get("key").and_then(lookup_key);
+
+                for resolved_lookup in resolved_lookups {
+                    if resolved_lookup.field != terms_field {
+                        continue;
+                    }
+                    let Some(row) = key_opt
+                        .as_ref()
+                        .and_then(|key| resolved_lookup.lookup_table.lookup(key))
+                    else {
+                        continue;
+                    };
+                    bucket.insert(
+                        resolved_lookup.target_field.to_string(),
+                        JsonValue::Object(row.clone()),
+                    );
+                }
+            }
+            if let Some(sub_aggregation_request) = sub_aggregation_request_opt {
+                enrich_aggregation_buckets(resolved_lookups, sub_aggregation_request, bucket);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_storage::RamStorage;
+    use serde_json::json;
+
+    use super::*;
+
+    fn teams_lookup_table() -> LookupTable {
+        let csv = "service_id,team,\"on call\"\n1,search,\"alice, bob\"\n\n2,\"ingest \
+                   \"\"v2\"\"\",carol\n";
+        LookupTable::from_csv(csv, None).unwrap()
+    }
+
+    #[test]
+    fn test_lookup_table_from_csv() {
+        let lookup_table = teams_lookup_table();
+        assert_eq!(lookup_table.key_column, "service_id");
+        assert_eq!(lookup_table.num_rows(), 2);
+        assert_eq!(
+            JsonValue::Object(lookup_table.lookup("1").unwrap().clone()),
+            json!({"team": "search", "on call": "alice, bob"})
+        );
+        assert_eq!(
+            JsonValue::Object(lookup_table.lookup("2").unwrap().clone()),
+            json!({"team": "ingest \"v2\"", "on call": "carol"})
+        );
+        let lookup_table =
+            LookupTable::from_csv("team,service_id\nsearch,1\n", Some("service_id")).unwrap();
+        assert_eq!(
+            JsonValue::Object(lookup_table.lookup("1").unwrap().clone()),
+            json!({"team": "search"})
+        );
+        LookupTable::from_csv("", None).unwrap_err();
+        LookupTable::from_csv("a,b\n1\n", None).unwrap_err();
+        LookupTable::from_csv("a,b\n1,2\n1,3\n", None).unwrap_err();
+        LookupTable::from_csv("a,b\n\"1,2\n", None).unwrap_err();
+        LookupTable::from_csv("a,b\n1,2\n", Some("c")).unwrap_err();
+    }
+
+    #[test]
+    fn test_lookup_table_from_json() {
+        let json = json!([
+            {"ip": "10.0.0.1", "country": "FR"},
+            {"ip": "10.0.0.2", "country": "US"},
+        ]);
+        let lookup_table = LookupTable::from_json(json.to_string().as_bytes(), Some("ip")).unwrap();
+        assert_eq!(lookup_table.key_column, "ip");
+        assert_eq!(
+            JsonValue::Object(lookup_table.lookup("10.0.0.2").unwrap().clone()),
+            json!({"country": "US"})
+        );
+        LookupTable::from_json(json.to_string().as_bytes(), None).unwrap_err();
+
+        let json = json!({"42": {"team": "search"}});
+        let lookup_table = LookupTable::from_json(json.to_string().as_bytes(), None).unwrap();
+        assert_eq!(lookup_table.key_column, "key");
+        assert_eq!(
+            JsonValue::Object(lookup_table.lookup("42").unwrap().clone()),
+            json!({"team": "search"})
+        );
+        LookupTable::from_json(b"42", None).unwrap_err();
+    }
+
+    #[test]
+    fn test_lookup_key() {
+        assert_eq!(lookup_key(&json!("foo")).unwrap(), "foo");
+        assert_eq!(lookup_key(&json!(42)).unwrap(), "42");
+        assert_eq!(lookup_key(&json!(42.0)).unwrap(), "42");
+        assert_eq!(lookup_key(&json!(4.2)).unwrap(), "4.2");
+        assert_eq!(lookup_key(&json!(true)).unwrap(), "true");
+        assert!(lookup_key(&json!(null)).is_none());
+        assert!(lookup_key(&json!([1])).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_table_store() {
+        let lookup_table_store = LookupTableStore::new(Arc::new(RamStorage::default()));
+        let error = lookup_table_store.get_table("teams").await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::NotFound);
+
+        lookup_table_store
+            .put_table("teams", teams_lookup_table())
+            .await
+            .unwrap();
+        let lookup_table = lookup_table_store.get_table("teams").await.unwrap();
+        assert_eq!(*lookup_table, teams_lookup_table());
+
+        // A store sharing the same storage, e.g. on another node, reads the table.
+        let other_lookup_table_store = LookupTableStore::new(lookup_table_store.storage.clone());
+        let lookup_table = other_lookup_table_store.get_table("teams").await.unwrap();
+        assert_eq!(*lookup_table, teams_lookup_table());
+
+        lookup_table_store.delete_table("teams").await.unwrap();
+        lookup_table_store.get_table("teams").await.unwrap_err();
+
+        let error = lookup_table_store.delete_table("teams").await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_apply_lookups() {
+        let lookup_table_store = LookupTableStore::new(Arc::new(RamStorage::default()));
+        lookup_table_store
+            .put_table("teams", teams_lookup_table())
+            .await
+            .unwrap();
+        let aggregation_request = json!({
+            "services": {
+                "terms": {"field": "service.id"},
+                "aggs": {
+                    "hosts": {"terms": {"field": "host"}},
+                }
+            },
+        });
+        let lookups = vec![LookupEnrichment {
+            table: "teams".to_string(),
+            field: "service.id".to_string(),
+            target_field: Some("owner".to_string()),
+        }];
+        let aggregation = json!({
+            "services": {
+                "buckets": [
+                    {
+                        "key": 1.0,
+                        "doc_count": 2,
+                        "hosts": {"buckets": [{"key": "host-1", "doc_count": 2}]},
+                    },
+                    {"key": 3.0, "doc_count": 1, "hosts": {"buckets": []}},
+                ]
+            }
+        });
+        let mut search_response = SearchResponse {
+            hits: vec![
+                Hit {
+                    json: json!({"service": {"id": 2}, "host": "host-1"}).to_string(),
+                    ..Default::default()
+                },
+                Hit {
+                    json: json!({"service": {"id": 3}}).to_string(),
+                    ..Default::default()
+                },
+            ],
+            aggregation: Some(aggregation.to_string()),
+            ..Default::default()
+        };
+        apply_lookups(
+            Some(&lookup_table_store),
+            &lookups,
+            Some(&aggregation_request.to_string()),
+            &mut search_response,
+        )
+        .await
+        .unwrap();
+
+        let hit_json: JsonValue = serde_json::from_str(&search_response.hits[0].json).unwrap();
+        assert_eq!(
+            hit_json,
+            json!({
+                "service": {"id": 2},
+                "host": "host-1",
+                "owner": {"team": "ingest \"v2\"", "on call": "carol"},
+            })
+        );
+        let hit_json: JsonValue = serde_json::from_str(&search_response.hits[1].json).unwrap();
+        assert_eq!(hit_json, json!({"service": {"id": 3}}));
+
+        let aggregation_json: JsonValue =
+            serde_json::from_str(search_response.aggregation.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            aggregation_json["services"]["buckets"][0]["owner"],
+            json!({"team": "search", "on call": "alice, bob"})
+        );
+        assert!(
+            aggregation_json["services"]["buckets"][0]["hosts"]["buckets"][0]
+                .get("owner")
+                .is_none()
+        );
+        assert!(aggregation_json["services"]["buckets"][1]
+            .get("owner")
+            .is_none());
+
+        let lookups = vec![LookupEnrichment {
+            table: "missing".to_string(),
+            field: "service.id".to_string(),
+            target_field: None,
+        }];
+        let error = apply_lookups(
+            Some(&lookup_table_store),
+            &lookups,
+            None,
+            &mut search_response,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let error = apply_lookups(None, &lookups, None, &mut search_response)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+}
@@ -28,6 +28,7 @@ pub struct SearchMetrics {
     pub leaf_searches_splits_total: IntCounter,
     pub leaf_search_split_duration_secs: Histogram,
     pub active_search_threads_count: IntGauge,
+    pub rerank_failures_total: IntCounter,
 }
 
 impl Default for SearchMetrics {
@@ -50,6 +51,12 @@ impl Default for SearchMetrics {
                 "search",
                 &[],
             ),
+            rerank_failures_total: new_counter(
+                "rerank_failures_total",
+                "Number of searches for which the re-ranking of the hits by the scoring service \
+                 failed.",
+                "search",
+            ),
         }
     }
 }
@@ -0,0 +1,791 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Serialize, Serializer};
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::columnar::{Column, ColumnType, MonotonicallyMappableToU64};
+use tantivy::{DateTime, DocId, Score, SegmentReader};
+
+/// Percents returned when the request does not list any, which are also the defaults of
+/// Elasticsearch.
+const DEFAULT_PERCENTS: [f64; 7] = [1.0, 5.0, 25.0, 50.0, 75.0, 95.0, 99.0];
+
+/// Default value of the `compression` parameter, which is also the default of Elasticsearch.
+const DEFAULT_COMPRESSION: f64 = 100.0;
+
+/// Bounds of the `compression` parameter. Values out of these bounds are clamped.
+const MIN_COMPRESSION: f64 = 20.0;
+const MAX_COMPRESSION: f64 = 10_000.0;
+
+/// Computes percentiles of some numerical fast fields among the documents matching the query.
+///
+/// Each leaf builds a t-digest per aggregation, and the root merges the digests of all the splits
+/// before estimating the percentiles, like Elasticsearch does.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<String, PercentilesAggregationWrapper>")]
+pub struct PercentilesCollector {
+    /// The percentiles aggregations, by name.
+    pub aggregations: BTreeMap<String, PercentilesAggregation>,
+}
+
+impl TryFrom<BTreeMap<String, PercentilesAggregationWrapper>> for PercentilesCollector {
+    type Error = String;
+
+    fn try_from(
+        aggregations: BTreeMap<String, PercentilesAggregationWrapper>,
+    ) -> Result<Self, Self::Error> {
+        // An empty aggregation request is left to Tantivy.
+        if aggregations.is_empty() {
+            return Err("percentiles collector requires at least one aggregation".to_string());
+        }
+        let mut percentiles_aggregations = BTreeMap::new();
+
+        for (name, wrapper) in aggregations {
+            let aggregation = wrapper.percentiles;
+
+            if let Some(percent) = aggregation
+                .percents
+                .iter()
+                .flatten()
+                .find(|percent| !(0.0..=100.0).contains(*percent))
+            {
+                return Err(format!(
+                    "percents of aggregation `{name}` must be between 0 and 100, got {percent}"
+                ));
+            }
+            percentiles_aggregations.insert(name, aggregation);
+        }
+        Ok(Self {
+            aggregations: percentiles_aggregations,
+        })
+    }
+}
+
+/// A `percentiles` aggregation, as found in the aggregation request.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PercentilesAggregationWrapper {
+    percentiles: PercentilesAggregation,
+}
+
+/// A `percentiles` aggregation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PercentilesAggregation {
+    /// The numerical fast field whose percentiles are estimated.
+    pub field: String,
+    /// The percents of the returned percentiles, between 0 and 100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percents: Option<Vec<f64>>,
+    /// Whether the percentiles are returned as an object keyed by percent rather than as an
+    /// array of key-value pairs. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyed: Option<bool>,
+    /// The value used for the documents without a value for the field. By default, these
+    /// documents are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<f64>,
+    /// The parameters of the t-digest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tdigest: Option<TDigestParameters>,
+}
+
+/// Parameters of the t-digest of a `percentiles` aggregation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TDigestParameters {
+    /// The maximum number of centroids of the digest is about half the compression. Higher
+    /// values improve the accuracy at the cost of larger digests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<f64>,
+}
+
+impl PercentilesAggregation {
+    fn compression(&self) -> f64 {
+        self.tdigest
+            .and_then(|tdigest| tdigest.compression)
+            .unwrap_or(DEFAULT_COMPRESSION)
+            .clamp(MIN_COMPRESSION, MAX_COMPRESSION)
+    }
+
+    fn percents(&self) -> &[f64] {
+        self.percents.as_deref().unwrap_or(&DEFAULT_PERCENTS)
+    }
+}
+
+impl PercentilesCollector {
+    /// Returns the fast fields read by the collector.
+    pub(crate) fn fast_field_names(&self) -> impl Iterator<Item = &str> {
+        self.aggregations
+            .values()
+            .map(|aggregation| aggregation.field.as_str())
+    }
+
+    /// Turns the merged fruit of the collector into the final aggregation result.
+    pub(crate) fn finalize(&self, fruit: Percentiles) -> BTreeMap<String, PercentilesResult> {
+        self.aggregations
+            .iter()
+            .zip(
+                fruit
+                    .digests
+                    .iter()
+                    .map(Some)
+                    .chain(std::iter::repeat(None)),
+            )
+            .map(|((name, aggregation), digest_opt)| {
+                let percentiles: Vec<(f64, Option<f64>)> = aggregation
+                    .percents()
+                    .iter()
+                    .map(|&percent| {
+                        let value_opt =
+                            digest_opt.and_then(|digest| digest.quantile(percent / 100.0));
+                        (percent, value_opt)
+                    })
+                    .collect();
+                let values = if aggregation.keyed.unwrap_or(true) {
+                    PercentileValues::Keyed(percentiles)
+                } else {
+                    PercentileValues::Unkeyed(percentiles)
+                };
+                (name.clone(), PercentilesResult { values })
+            })
+            .collect()
+    }
+}
+
+/// Intermediate result of the [`PercentilesCollector`]: one digest per aggregation, in the order
+/// of the aggregation names.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Percentiles {
+    digests: Vec<TDigest>,
+}
+
+impl Percentiles {
+    pub(crate) fn merge(&mut self, other: Percentiles) {
+        if self.digests.is_empty() {
+            self.digests = other.digests;
+            return;
+        }
+        for (digest, other_digest) in self.digests.iter_mut().zip(other.digests) {
+            digest.merge(other_digest);
+        }
+    }
+}
+
+pub(crate) fn merge_fruits(fruits: Vec<Percentiles>) -> Percentiles {
+    let mut merged_fruit = Percentiles::default();
+
+    for fruit in fruits {
+        merged_fruit.merge(fruit);
+    }
+    merged_fruit
+}
+
+/// Final result of a `percentiles` aggregation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PercentilesResult {
+    /// The estimated percentiles, which are `null` when no document has a value.
+    pub values: PercentileValues,
+}
+
+/// Estimated percentiles, by percent, in the order of the request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PercentileValues {
+    /// Serialized as `{"50.0": 12.5}`.
+    Keyed(Vec<(f64, Option<f64>)>),
+    /// Serialized as `[{"key": 50.0, "value": 12.5}]`.
+    Unkeyed(Vec<(f64, Option<f64>)>),
+}
+
+impl Serialize for PercentileValues {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PercentileValues::Keyed(percentiles) => serializer.collect_map(
+                percentiles
+                    .iter()
+                    .map(|(percent, value_opt)| (format!("{percent:?}"), value_opt)),
+            ),
+            PercentileValues::Unkeyed(percentiles) => {
+                #[derive(Serialize)]
+                struct PercentileEntry {
+                    key: f64,
+                    value: Option<f64>,
+                }
+                let mut seq = serializer.serialize_seq(Some(percentiles.len()))?;
+
+                for &(key, value) in percentiles {
+                    seq.serialize_element(&PercentileEntry { key, value })?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+impl Centroid {
+    fn add(&mut self, other: Centroid) {
+        self.weight += other.weight;
+        self.mean += (other.mean - self.mean) * other.weight / self.weight;
+    }
+}
+
+/// Merging t-digest, as described in "Computing extremely accurate quantiles using t-digests" by
+/// Ted Dunning and Otmar Ertl.
+///
+/// Values are buffered and periodically merged into centroids sorted by mean. The size of the
+/// centroids is bounded by the `k1` scale function, so that centroids are small near the tails of
+/// the distribution, where extreme percentiles need more accuracy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    /// Values not merged into the centroids yet. The buffer is flushed before the digest is sent
+    /// to the root.
+    buffer: Vec<f64>,
+    total_weight: f64,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    /// Creates an empty digest.
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            total_weight: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn buffer_capacity(&self) -> usize {
+        (self.compression as usize) * 5
+    }
+
+    /// Adds a value to the digest.
+    pub fn insert(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.buffer.push(value);
+        self.total_weight += 1.0;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        if self.buffer.len() >= self.buffer_capacity() {
+            self.flush();
+        }
+    }
+
+    /// Merges another digest into this one. The result is the digest of the union of the values.
+    pub fn merge(&mut self, mut other: TDigest) {
+        if other.total_weight == 0.0 {
+            return;
+        }
+        other.flush();
+        self.flush();
+        self.total_weight += other.total_weight;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.compress(other.centroids);
+    }
+
+    /// Merges the buffered values into the centroids.
+    pub fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let new_centroids = self
+            .buffer
+            .drain(..)
+            .map(|value| Centroid {
+                mean: value,
+                weight: 1.0,
+            })
+            .collect();
+        self.compress(new_centroids);
+    }
+
+    /// Merges new centroids into the centroids of the digest. A centroid absorbs its successors as
+    /// long as its weight stays within the bound given by the scale function.
+    fn compress(&mut self, mut new_centroids: Vec<Centroid>) {
+        new_centroids.append(&mut self.centroids);
+        new_centroids.sort_unstable_by(|left, right| left.mean.total_cmp(&right.mean));
+
+        let total_weight: f64 = new_centroids.iter().map(|centroid| centroid.weight).sum();
+        let mut new_centroids_iter = new_centroids.into_iter();
+        let Some(mut current_centroid) = new_centroids_iter.next() else {
+            return;
+        };
+        let mut weight_so_far = 0.0;
+        let mut weight_limit = total_weight * self.next_quantile_limit(0.0);
+
+        for centroid in new_centroids_iter {
+            if weight_so_far + current_centroid.weight + centroid.weight <= weight_limit {
+                current_centroid.add(centroid);
+            } else {
+                weight_so_far += current_centroid.weight;
+                self.centroids.push(current_centroid);
+                weight_limit =
+                    total_weight * self.next_quantile_limit(weight_so_far / total_weight);
+                current_centroid = centroid;
+            }
+        }
+        self.centroids.push(current_centroid);
+    }
+
+    /// Returns the quantile up to which the centroid starting at quantile `q` can grow, which is
+    /// one unit further along the `k1` scale function `k(q) = δ / 2π * asin(2q - 1)`.
+    fn next_quantile_limit(&self, q: f64) -> f64 {
+        let normalizer = self.compression / (2.0 * PI);
+        let next_k = normalizer * (2.0 * q - 1.0).clamp(-1.0, 1.0).asin() + 1.0;
+        let angle = next_k / normalizer;
+
+        if angle >= PI / 2.0 {
+            return 1.0;
+        }
+        (angle.sin() + 1.0) / 2.0
+    }
+
+    /// Estimates the `q`-quantile of the values, interpolating between the centroids. The minimum
+    /// and maximum values are tracked exactly and bound the estimates at the tails.
+    ///
+    /// Returns `None` if the digest is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.total_weight == 0.0 {
+            return None;
+        }
+        if !self.buffer.is_empty() {
+            let mut digest = self.clone();
+            digest.flush();
+            return digest.quantile(q);
+        }
+        let centroids = &self.centroids;
+        let num_centroids = centroids.len();
+
+        if num_centroids == 1 {
+            return Some(centroids[0].mean);
+        }
+        let total_weight = self.total_weight;
+        let index = q.clamp(0.0, 1.0) * total_weight;
+
+        if index < 1.0 {
+            return Some(self.min);
+        }
+        let first_centroid = centroids[0];
+
+        if first_centroid.weight > 1.0 && index < first_centroid.weight / 2.0 {
+            return Some(
+                self.min
+                    + (index - 1.0) / (first_centroid.weight / 2.0 - 1.0)
+                        * (first_centroid.mean - self.min),
+            );
+        }
+        if index > total_weight - 1.0 {
+            return Some(self.max);
+        }
+        let last_centroid = centroids[num_centroids - 1];
+
+        if last_centroid.weight > 2.0 && total_weight - index <= last_centroid.weight / 2.0 {
+            return Some(
+                self.max
+                    - (total_weight - index - 1.0) / (last_centroid.weight / 2.0 - 1.0)
+                        * (self.max - last_centroid.mean),
+            );
+        }
+        let mut weight_so_far = first_centroid.weight / 2.0;
+
+        for window in centroids.windows(2) {
+            let (left, right) = (window[0], window[1]);
+            let delta_weight = (left.weight + right.weight) / 2.0;
+
+            if weight_so_far + delta_weight > index {
+                // Centroids of weight one are single values, which are returned as is.
+                let mut left_unit = 0.0;
+                if left.weight == 1.0 {
+                    if index - weight_so_far < 0.5 {
+                        return Some(left.mean);
+                    }
+                    left_unit = 0.5;
+                }
+                let mut right_unit = 0.0;
+                if right.weight == 1.0 {
+                    if weight_so_far + delta_weight - index <= 0.5 {
+                        return Some(right.mean);
+                    }
+                    right_unit = 0.5;
+                }
+                let left_distance = index - weight_so_far - left_unit;
+                let right_distance = weight_so_far + delta_weight - index - right_unit;
+                let value = (left.mean * right_distance + right.mean * left_distance)
+                    / (left_distance + right_distance);
+                return Some(value.clamp(left.mean, right.mean));
+            }
+            weight_so_far += delta_weight;
+        }
+        Some(self.max)
+    }
+}
+
+impl Collector for PercentilesCollector {
+    type Fruit = Percentiles;
+    type Child = PercentilesSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: u32,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let fast_fields = segment_reader.fast_fields();
+        let mut segment_digests = Vec::with_capacity(self.aggregations.len());
+
+        for aggregation in self.aggregations.values() {
+            let column_opt = fast_fields
+                .u64_lenient(&aggregation.field)?
+                .filter(|(_, column_type)| is_numerical(*column_type));
+            segment_digests.push(SegmentDigest {
+                column_opt,
+                missing_opt: aggregation.missing,
+                digest: TDigest::new(aggregation.compression()),
+            });
+        }
+        Ok(PercentilesSegmentCollector { segment_digests })
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        Ok(merge_fruits(segment_fruits))
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+}
+
+fn is_numerical(column_type: ColumnType) -> bool {
+    matches!(
+        column_type,
+        ColumnType::U64
+            | ColumnType::I64
+            | ColumnType::F64
+            | ColumnType::DateTime
+            | ColumnType::Bool
+    )
+}
+
+/// Converts the fast field representation of a value of a numerical column into the value added
+/// to the digest. Dates are converted to milliseconds, like in Elasticsearch.
+fn to_f64(value: u64, column_type: ColumnType) -> f64 {
+    match column_type {
+        ColumnType::I64 => i64::from_u64(value) as f64,
+        ColumnType::F64 => f64::from_u64(value),
+        ColumnType::DateTime => DateTime::from_u64(value).into_timestamp_millis() as f64,
+        _ => value as f64,
+    }
+}
+
+struct SegmentDigest {
+    /// `None` if the field is absent from the segment or is not numerical.
+    column_opt: Option<(Column<u64>, ColumnType)>,
+    missing_opt: Option<f64>,
+    digest: TDigest,
+}
+
+impl SegmentDigest {
+    fn collect(&mut self, doc: DocId) {
+        let mut has_value = false;
+
+        if let Some((column, column_type)) = &self.column_opt {
+            for value in column.values_for_doc(doc) {
+                self.digest.insert(to_f64(value, *column_type));
+                has_value = true;
+            }
+        }
+        if !has_value {
+            if let Some(missing) = self.missing_opt {
+                self.digest.insert(missing);
+            }
+        }
+    }
+}
+
+/// Segment collector of the [`PercentilesCollector`].
+pub struct PercentilesSegmentCollector {
+    segment_digests: Vec<SegmentDigest>,
+}
+
+impl SegmentCollector for PercentilesSegmentCollector {
+    type Fruit = Percentiles;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        for segment_digest in &mut self.segment_digests {
+            segment_digest.collect(doc);
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let digests = self
+            .segment_digests
+            .into_iter()
+            .map(|mut segment_digest| {
+                segment_digest.digest.flush();
+                segment_digest.digest
+            })
+            .collect();
+        Percentiles { digests }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::query::AllQuery;
+    use tantivy::schema::{Schema, FAST, STRING};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::collector::QuickwitAggregations;
+
+    #[test]
+    fn test_percentiles_collector_serde() {
+        let aggregation: QuickwitAggregations = serde_json::from_str(
+            r#"{
+                "latencies": {"percentiles": {"field": "latency_ms"}},
+                "sizes": {
+                    "percentiles": {
+                        "field": "size",
+                        "percents": [50, 99.9],
+                        "keyed": false,
+                        "missing": 0,
+                        "tdigest": {"compression": 200}
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let QuickwitAggregations::PercentilesAggregation(collector) = aggregation else {
+            panic!("expected PercentilesAggregation");
+        };
+        assert_eq!(collector.aggregations.len(), 2);
+        assert_eq!(
+            collector.aggregations["latencies"].percents(),
+            DEFAULT_PERCENTS
+        );
+        assert_eq!(collector.aggregations["latencies"].compression(), 100.0);
+        assert_eq!(collector.aggregations["sizes"].percents(), [50.0, 99.9]);
+        assert_eq!(collector.aggregations["sizes"].compression(), 200.0);
+
+        // Percentiles aggregations mixed with other aggregations are left to Tantivy.
+        let aggregation = serde_json::from_str::<QuickwitAggregations>(
+            r#"{
+                "latencies": {"percentiles": {"field": "latency_ms"}},
+                "severities": {"terms": {"field": "severity"}}
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            aggregation,
+            QuickwitAggregations::TantivyAggregations(_)
+        ));
+        let error = PercentilesCollector::try_from(
+            serde_json::from_str::<BTreeMap<String, PercentilesAggregationWrapper>>(
+                r#"{"latencies": {"percentiles": {"field": "latency_ms", "percents": [101]}}}"#,
+            )
+            .unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            "percents of aggregation `latencies` must be between 0 and 100, got 101"
+        );
+    }
+
+    #[test]
+    fn test_tdigest_accuracy() {
+        let mut digest = TDigest::new(DEFAULT_COMPRESSION);
+        assert_eq!(digest.quantile(0.5), None);
+
+        // Values are inserted in a scrambled order.
+        let num_values = 100_000u64;
+        for i in 0..num_values {
+            digest.insert(((i * 7_919) % num_values) as f64);
+        }
+        assert!(digest.centroids.len() <= DEFAULT_COMPRESSION as usize);
+        assert_eq!(digest.quantile(0.0), Some(0.0));
+        assert_eq!(digest.quantile(1.0), Some(99_999.0));
+
+        for q in [0.01, 0.05, 0.25, 0.5, 0.75, 0.95, 0.99, 0.999] {
+            let estimate = digest.quantile(q).unwrap();
+            let expected = q * num_values as f64;
+            // The error is expressed in rank, which is what the t-digest bounds.
+            let rank_error = (estimate - expected).abs() / num_values as f64;
+            assert!(
+                rank_error < 0.005,
+                "quantile {q}: expected ~{expected}, got {estimate}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tdigest_small() {
+        let mut digest = TDigest::new(DEFAULT_COMPRESSION);
+        digest.insert(42.0);
+        assert_eq!(digest.quantile(0.0), Some(42.0));
+        assert_eq!(digest.quantile(0.5), Some(42.0));
+        assert_eq!(digest.quantile(1.0), Some(42.0));
+
+        // Small digests keep every value, so percentiles are exact.
+        for value in [3.0, 1.0, 2.0, 5.0] {
+            digest.insert(value);
+        }
+        assert_eq!(digest.quantile(0.0), Some(1.0));
+        assert_eq!(digest.quantile(0.5), Some(3.0));
+        assert_eq!(digest.quantile(1.0), Some(42.0));
+
+        digest.insert(f64::NAN);
+        assert_eq!(digest.total_weight, 5.0);
+    }
+
+    #[test]
+    fn test_tdigest_merge() {
+        let mut digests: Vec<TDigest> = (0..10u64)
+            .map(|digest_ord| {
+                let mut digest = TDigest::new(DEFAULT_COMPRESSION);
+                // Each digest holds a disjoint range of values, like splits of a time series.
+                for value in digest_ord * 1_000..(digest_ord + 1) * 1_000 {
+                    digest.insert(value as f64);
+                }
+                digest
+            })
+            .collect();
+        let mut merged_digest = TDigest::new(DEFAULT_COMPRESSION);
+        merged_digest.merge(TDigest::new(DEFAULT_COMPRESSION));
+
+        for digest in digests.drain(..) {
+            merged_digest.merge(digest);
+        }
+        assert_eq!(merged_digest.total_weight, 10_000.0);
+        assert!(merged_digest.centroids.len() <= DEFAULT_COMPRESSION as usize);
+        assert_eq!(merged_digest.quantile(0.0), Some(0.0));
+        assert_eq!(merged_digest.quantile(1.0), Some(9_999.0));
+
+        for q in [0.01, 0.5, 0.99] {
+            let estimate = merged_digest.quantile(q).unwrap();
+            let expected = q * 10_000.0;
+            assert!(
+                (estimate - expected).abs() < 50.0,
+                "quantile {q}: expected ~{expected}, got {estimate}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_percentile_values_serialization() {
+        let keyed = PercentilesResult {
+            values: PercentileValues::Keyed(vec![(5.0, Some(1.5)), (25.0, None)]),
+        };
+        assert_eq!(
+            serde_json::to_string(&keyed).unwrap(),
+            r#"{"values":{"5.0":1.5,"25.0":null}}"#
+        );
+        let unkeyed = PercentilesResult {
+            values: PercentileValues::Unkeyed(vec![(99.9, Some(3.0))]),
+        };
+        assert_eq!(
+            serde_json::to_string(&unkeyed).unwrap(),
+            r#"{"values":[{"key":99.9,"value":3.0}]}"#
+        );
+    }
+
+    #[test]
+    fn test_percentiles_collector() {
+        let mut schema_builder = Schema::builder();
+        let latency_field = schema_builder.add_i64_field("latency", FAST);
+        let name_field = schema_builder.add_text_field("name", STRING | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+
+        for latency in 1..=50i64 {
+            index_writer
+                .add_document(doc!(latency_field => latency))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        for latency in 51..=100i64 {
+            index_writer
+                .add_document(doc!(latency_field => latency))
+                .unwrap();
+        }
+        index_writer
+            .add_document(doc!(name_field => "no latency"))
+            .unwrap();
+        index_writer.commit().unwrap();
+
+        let searcher = index.reader().unwrap().searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let collector: PercentilesCollector = serde_json::from_str(
+            r#"{
+                "latencies": {"percentiles": {"field": "latency", "percents": [0, 50, 100]}},
+                "latencies_with_missing": {
+                    "percentiles": {"field": "latency", "percents": [0], "missing": -1}
+                },
+                "names": {"percentiles": {"field": "name", "percents": [50]}},
+                "missing_field": {"percentiles": {"field": "missing", "percents": [50]}}
+            }"#,
+        )
+        .unwrap();
+        let fruit = searcher.search(&AllQuery, &collector).unwrap();
+        let result = collector.finalize(fruit);
+        let PercentileValues::Keyed(latencies) = &result["latencies"].values else {
+            panic!("expected keyed percentiles");
+        };
+        assert_eq!(latencies[0], (0.0, Some(1.0)));
+        assert_eq!(latencies[1].0, 50.0);
+        let median = latencies[1].1.unwrap();
+        assert!((50.0..=51.0).contains(&median), "median: {median}");
+        assert_eq!(latencies[2], (100.0, Some(100.0)));
+        assert_eq!(
+            result["latencies_with_missing"].values,
+            PercentileValues::Keyed(vec![(0.0, Some(-1.0))])
+        );
+        assert_eq!(
+            result["names"].values,
+            PercentileValues::Keyed(vec![(50.0, None)])
+        );
+        assert_eq!(
+            result["missing_field"].values,
+            PercentileValues::Keyed(vec![(50.0, None)])
+        );
+    }
+}
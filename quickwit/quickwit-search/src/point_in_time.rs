@@ -0,0 +1,380 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata};
+use quickwit_proto::metastore::{
+    ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::search::{OpenPointInTimeRequest, OpenPointInTimeResponse};
+use quickwit_proto::types::IndexUid;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use ulid::Ulid;
+
+use crate::root::{check_all_index_metadata_found, MAX_SCROLL_TTL};
+use crate::{list_relevant_splits, ClusterClient, SearchError};
+
+/// Prefix of the keys under which the point in time contexts are stored in the searchers' KV
+/// store, so that they never collide with scroll contexts.
+const POINT_IN_TIME_KEY_PREFIX: &[u8] = b"pit:";
+
+/// Set of splits captured when a point in time is opened. Searches issued with the point in
+/// time run against these splits, whatever merges or publishes happened since.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PointInTimeContext {
+    pub index_uids: Vec<IndexUid>,
+    pub split_metadatas: Vec<SplitMetadata>,
+}
+
+impl PointInTimeContext {
+    pub fn serialize(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("point in time context should be JSON serializable")
+    }
+
+    pub fn load(payload: &[u8]) -> anyhow::Result<Self> {
+        let point_in_time_context =
+            serde_json::from_slice(payload).context("failed to deserialize context")?;
+        Ok(point_in_time_context)
+    }
+
+    /// Returns the IDs of the indexes captured by the point in time.
+    pub fn index_ids(&self) -> Vec<String> {
+        self.index_uids
+            .iter()
+            .map(|index_uid| index_uid.index_id.clone())
+            .collect()
+    }
+
+    /// Checks that the indexes resolved for a search are the ones captured by the point in time,
+    /// i.e. that none of them has been deleted and recreated since.
+    pub fn check_index_uids(&self, index_uids: &[IndexUid]) -> crate::Result<()> {
+        for index_uid in index_uids {
+            if !self.index_uids.contains(index_uid) {
+                return Err(SearchError::InvalidArgument(format!(
+                    "index `{}` was recreated after the point in time was opened",
+                    index_uid.index_id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the captured splits that may contain documents in the given time range and
+    /// matching the given tags filter. The filtering mirrors the one applied by the metastore
+    /// when listing the relevant splits of a regular search.
+    pub fn relevant_splits(
+        &self,
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+        tags_filter_opt: Option<&TagFilterAst>,
+    ) -> Vec<SplitMetadata> {
+        self.split_metadatas
+            .iter()
+            .filter(|split_metadata| {
+                if let Some(time_range) = &split_metadata.time_range {
+                    if start_timestamp.is_some_and(|start_ts| *time_range.end() < start_ts) {
+                        return false;
+                    }
+                    if end_timestamp.is_some_and(|end_ts| *time_range.start() >= end_ts) {
+                        return false;
+                    }
+                }
+                tags_filter_opt
+                    .map(|tags_filter| tags_filter.evaluate(&split_metadata.tags))
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn point_in_time_key(pit_ulid: Ulid) -> Vec<u8> {
+    let mut key = POINT_IN_TIME_KEY_PREFIX.to_vec();
+    key.extend_from_slice(&u128::from(pit_ulid).to_le_bytes());
+    key
+}
+
+/// Captures the splits currently published for the requested indexes and stores them in the
+/// searchers' KV store for `keep_alive_secs` seconds.
+///
+/// The keep alive is capped so that it always expires before the splits marked for deletion by a
+/// merge are garbage collected.
+pub async fn open_point_in_time(
+    open_request: OpenPointInTimeRequest,
+    metastore: &mut MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<OpenPointInTimeResponse> {
+    let keep_alive = Duration::from_secs(open_request.keep_alive_secs as u64);
+    if keep_alive.is_zero() {
+        return Err(SearchError::InvalidArgument(
+            "point in time keep alive must be greater than 0".to_string(),
+        ));
+    }
+    if keep_alive > MAX_SCROLL_TTL {
+        return Err(SearchError::InvalidArgument(format!(
+            "Quickwit only supports point in time keep alive up to {} secs",
+            MAX_SCROLL_TTL.as_secs()
+        )));
+    }
+    let list_indexes_metadatas_request = ListIndexesMetadataRequest {
+        index_id_patterns: open_request.index_id_patterns.clone(),
+    };
+    let indexes_metadata: Vec<IndexMetadata> = metastore
+        .list_indexes_metadata(list_indexes_metadatas_request)
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+    check_all_index_metadata_found(&indexes_metadata, &open_request.index_id_patterns)?;
+
+    if indexes_metadata.is_empty() {
+        return Err(SearchError::IndexesNotFound {
+            index_ids: open_request.index_id_patterns,
+        });
+    }
+    let index_uids: Vec<IndexUid> = indexes_metadata
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_uid)
+        .collect();
+    let split_metadatas =
+        list_relevant_splits(index_uids.clone(), None, None, None, metastore).await?;
+    let num_splits = split_metadatas.len() as u64;
+
+    let point_in_time_context = PointInTimeContext {
+        index_uids,
+        split_metadatas,
+    };
+    let pit_ulid = Ulid::new();
+    let payload = point_in_time_context.serialize();
+    cluster_client
+        .put_kv(&point_in_time_key(pit_ulid), &payload, keep_alive)
+        .await;
+    info!(
+        pit_id = %pit_ulid,
+        index_ids = ?point_in_time_context.index_ids(),
+        num_splits,
+        "opened point in time"
+    );
+    Ok(OpenPointInTimeResponse {
+        pit_id: pit_ulid.to_string(),
+        num_splits,
+    })
+}
+
+/// Loads the context of the point in time `pit_id` from the searchers' KV store.
+pub(crate) async fn load_point_in_time(
+    pit_id: &str,
+    cluster_client: &ClusterClient,
+) -> crate::Result<PointInTimeContext> {
+    let pit_ulid = Ulid::from_str(pit_id).map_err(|_| {
+        SearchError::InvalidArgument(format!("invalid point in time ID `{pit_id}`"))
+    })?;
+    let payload = cluster_client
+        .get_kv(&point_in_time_key(pit_ulid))
+        .await
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!(
+                "point in time `{pit_id}` does not exist or has expired"
+            ))
+        })?;
+    PointInTimeContext::load(&payload)
+        .map_err(|_| SearchError::Internal("corrupted point in time context".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+    use std::sync::{Arc, RwLock};
+
+    use quickwit_common::ServiceStream;
+    use quickwit_doc_mapper::tag_pruning::{no_tag, tag};
+    use quickwit_indexing::MockSplitBuilder;
+    use quickwit_metastore::ListSplitsResponseExt;
+    use quickwit_proto::metastore::{
+        ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
+    };
+
+    use super::*;
+    use crate::{searcher_pool_for_test, MockSearchService, SearchJobPlacer};
+
+    fn split_metadata_for_test(
+        split_id: &str,
+        time_range_opt: Option<std::ops::RangeInclusive<i64>>,
+        tags: &[&str],
+    ) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range: time_range_opt,
+            tags: tags
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect::<BTreeSet<_>>(),
+            ..Default::default()
+        }
+    }
+
+    fn split_ids(split_metadatas: &[SplitMetadata]) -> Vec<&str> {
+        split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id())
+            .collect()
+    }
+
+    #[test]
+    fn test_point_in_time_relevant_splits() {
+        let point_in_time_context = PointInTimeContext {
+            index_uids: vec![IndexUid::for_test("test-index", 0)],
+            split_metadatas: vec![
+                split_metadata_for_test("split-1", Some(0..=9), &["tenant:a"]),
+                split_metadata_for_test("split-2", Some(10..=19), &["tenant:b"]),
+                split_metadata_for_test("split-3", None, &[]),
+            ],
+        };
+        let splits = point_in_time_context.relevant_splits(None, None, None);
+        assert_eq!(split_ids(&splits), ["split-1", "split-2", "split-3"]);
+
+        let splits = point_in_time_context.relevant_splits(Some(10), None, None);
+        assert_eq!(split_ids(&splits), ["split-2", "split-3"]);
+
+        let splits = point_in_time_context.relevant_splits(None, Some(10), None);
+        assert_eq!(split_ids(&splits), ["split-1", "split-3"]);
+
+        let splits = point_in_time_context.relevant_splits(Some(5), Some(15), None);
+        assert_eq!(split_ids(&splits), ["split-1", "split-2", "split-3"]);
+
+        let splits = point_in_time_context.relevant_splits(None, None, Some(&tag("tenant:a")));
+        assert_eq!(split_ids(&splits), ["split-1"]);
+
+        let splits = point_in_time_context.relevant_splits(None, None, Some(&no_tag("tenant:a")));
+        assert_eq!(split_ids(&splits), ["split-2", "split-3"]);
+    }
+
+    #[test]
+    fn test_point_in_time_check_index_uids() {
+        let point_in_time_context = PointInTimeContext {
+            index_uids: vec![IndexUid::for_test("test-index", 0)],
+            split_metadatas: Vec::new(),
+        };
+        point_in_time_context
+            .check_index_uids(&[IndexUid::for_test("test-index", 0)])
+            .unwrap();
+        let error = point_in_time_context
+            .check_index_uids(&[IndexUid::for_test("test-index", 1)])
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+
+    #[tokio::test]
+    async fn test_open_and_load_point_in_time() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .times(1)
+            .returning(move |_| {
+                let splits = vec![
+                    MockSplitBuilder::new("split-1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split-2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let mut mock_search_service = MockSearchService::new();
+        let kv: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>> = Default::default();
+        let kv_clone = kv.clone();
+        mock_search_service
+            .expect_put_kv()
+            .returning(move |put_kv_req| {
+                assert_eq!(put_kv_req.ttl_secs, 60);
+                kv_clone
+                    .write()
+                    .unwrap()
+                    .insert(put_kv_req.key, put_kv_req.payload);
+            });
+        mock_search_service
+            .expect_get_kv()
+            .returning(move |get_kv_req| kv.read().unwrap().get(&get_kv_req.key).cloned());
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+
+        let open_request = OpenPointInTimeRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            keep_alive_secs: 60,
+        };
+        let open_response = open_point_in_time(open_request, &mut metastore, &cluster_client)
+            .await
+            .unwrap();
+        assert_eq!(open_response.num_splits, 2);
+
+        let point_in_time_context = load_point_in_time(&open_response.pit_id, &cluster_client)
+            .await
+            .unwrap();
+        assert_eq!(point_in_time_context.index_ids(), ["test-index"]);
+        assert_eq!(
+            split_ids(&point_in_time_context.split_metadatas),
+            ["split-1", "split-2"]
+        );
+
+        let error = load_point_in_time(&Ulid::new().to_string(), &cluster_client)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let error = load_point_in_time("not-a-pit-id", &cluster_client)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+
+    #[tokio::test]
+    async fn test_open_point_in_time_invalid_keep_alive() {
+        let mut metastore = MetastoreServiceClient::from_mock(MockMetastoreService::new());
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+
+        for keep_alive_secs in [0, MAX_SCROLL_TTL.as_secs() as u32 + 1] {
+            let open_request = OpenPointInTimeRequest {
+                index_id_patterns: vec!["test-index".to_string()],
+                keep_alive_secs,
+            };
+            let error = open_point_in_time(open_request, &mut metastore, &cluster_client)
+                .await
+                .unwrap_err();
+            assert!(matches!(error, SearchError::InvalidArgument(_)));
+        }
+    }
+}
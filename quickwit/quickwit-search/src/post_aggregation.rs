@@ -0,0 +1,1537 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::{bail, Context};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::bucket_script::ScriptExpr;
+
+/// Keys under which the sub-aggregations of an aggregation are declared.
+const SUB_AGGREGATION_KEYS: [&str; 2] = ["aggs", "aggregations"];
+
+/// Aggregation types on which a `bucket_sort` or `bucket_script` post-aggregation can be declared.
+const MULTI_BUCKET_AGGREGATION_TYPES: [&str; 4] = ["date_histogram", "histogram", "range", "terms"];
+
+/// Aggregation types on which the post-aggregations computed over consecutive buckets, such as
+/// `derivative`, can be declared.
+const HISTOGRAM_AGGREGATION_TYPES: [&str; 2] = ["date_histogram", "histogram"];
+
+/// Post-aggregations entirely computed by the root from the merged buckets of their parent
+/// aggregation.
+const PIPELINE_AGGREGATION_TYPES: [&str; 5] = [
+    "bucket_script",
+    "cumulative_sum",
+    "derivative",
+    "moving_avg",
+    "moving_fn",
+];
+
+const POST_AGGREGATION_TYPES: [&str; 7] = [
+    "bucket_script",
+    "bucket_sort",
+    "cumulative_sum",
+    "derivative",
+    "moving_avg",
+    "moving_fn",
+    "rate",
+];
+
+/// Default number of buckets over which `moving_avg` aggregations are computed.
+const DEFAULT_MOVING_AVG_WINDOW: usize = 5;
+
+/// Default smoothing factor of the `ewma` model of `moving_avg` aggregations.
+const DEFAULT_EWMA_ALPHA: f64 = 0.3;
+
+/// Post-aggregations are aggregations computed from the results of other aggregations, which
+/// Grafana and Kibana commonly express in their requests, but which tantivy does not support.
+///
+/// They are pushed down to the leaves as much as possible: before the request is executed, each
+/// post-aggregation is rewritten into the tantivy aggregation it derives from, and only the
+/// remaining, cheap, part of its computation is applied by the root to the final results:
+/// - `rate` computes the value of a metric per time unit in each bucket of a `date_histogram`. The
+///   leaves compute the underlying `sum` or `value_count` metric, or nothing when the rate is a
+///   number of documents, and the root normalizes the value by the interval of the histogram.
+/// - `bucket_sort` sorts and truncates the buckets of its parent aggregation. When it keeps the top
+///   k buckets of a `terms` aggregation in the same order as the `terms` aggregation itself, the
+///   size of the `terms` aggregation is reduced to k, so that the leaves only return the top k
+///   terms of each segment instead of the top `size` terms.
+/// - The pipeline aggregations `derivative`, `cumulative_sum`, `moving_avg`, `moving_fn`, and
+///   `bucket_script` are entirely computed by the root from the metrics of the merged buckets of
+///   their parent aggregation, which the leaves compute anyway.
+#[derive(Debug, Default)]
+pub(crate) struct PostAggregations {
+    post_aggregations: Vec<PostAggregation>,
+}
+
+#[derive(Debug)]
+struct PostAggregation {
+    /// Names of the aggregations leading to the parent aggregation of the post-aggregation.
+    parent_path: Vec<String>,
+    kind: PostAggregationKind,
+}
+
+#[derive(Debug)]
+enum PostAggregationKind {
+    Rate {
+        name: String,
+        value_source: RateValueSource,
+        /// The rate is the value of the metric in the bucket multiplied by this factor, i.e. the
+        /// rate unit divided by the interval of the histogram.
+        factor: f64,
+    },
+    BucketSort {
+        sort: Vec<(String, SortOrder)>,
+        from: usize,
+        size_opt: Option<usize>,
+    },
+    Derivative {
+        name: String,
+        buckets_path: String,
+        gap_policy: GapPolicy,
+        /// When a `unit` is set, the derivative is also returned per unit of time, i.e. multiplied
+        /// by this factor.
+        unit_factor_opt: Option<f64>,
+    },
+    CumulativeSum {
+        name: String,
+        buckets_path: String,
+    },
+    /// Computes a moving function over the values of the previous buckets. Unlike `moving_fn`,
+    /// `moving_avg` windows are counted in values rather than in buckets, so gaps do not shrink
+    /// them.
+    MovingAvg {
+        name: String,
+        buckets_path: String,
+        gap_policy: GapPolicy,
+        window: usize,
+        function: MovingFunction,
+    },
+    MovingFn {
+        name: String,
+        buckets_path: String,
+        gap_policy: GapPolicy,
+        window: usize,
+        shift: i64,
+        function: MovingFunction,
+    },
+    BucketScript {
+        name: String,
+        /// Variables of the script and the buckets paths they are bound to.
+        buckets_paths: Vec<(String, String)>,
+        script: ScriptExpr,
+        gap_policy: GapPolicy,
+    },
+}
+
+/// Policy applied when the value designated by a buckets path is missing from a bucket or when
+/// the bucket is empty.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum GapPolicy {
+    /// Gaps are skipped.
+    Skip,
+    /// Gaps are replaced by zeros.
+    InsertZeros,
+}
+
+impl GapPolicy {
+    fn parse(params: &JsonValue) -> anyhow::Result<Self> {
+        match params.get("gap_policy").and_then(JsonValue::as_str) {
+            None | Some("skip") => Ok(Self::Skip),
+            Some("insert_zeros") => Ok(Self::InsertZeros),
+            Some(gap_policy) => {
+                bail!("unknown gap policy `{gap_policy}`, expected `skip` or `insert_zeros`")
+            }
+        }
+    }
+}
+
+/// Functions computed over the window of values of `moving_avg` and `moving_fn` aggregations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MovingFunction {
+    UnweightedAvg,
+    LinearWeightedAvg,
+    Ewma { alpha: f64 },
+    Max,
+    Min,
+    Sum,
+}
+
+impl MovingFunction {
+    /// Parses the `model` of a `moving_avg` aggregation.
+    fn parse_model(params: &JsonValue) -> anyhow::Result<Self> {
+        let function = match params.get("model").and_then(JsonValue::as_str) {
+            None | Some("simple") => Self::UnweightedAvg,
+            Some("linear") => Self::LinearWeightedAvg,
+            Some("ewma") => {
+                let alpha = params
+                    .get("settings")
+                    .and_then(|settings| settings.get("alpha"))
+                    .and_then(JsonValue::as_f64)
+                    .unwrap_or(DEFAULT_EWMA_ALPHA);
+                Self::Ewma { alpha }
+            }
+            Some(model) => {
+                bail!(
+                    "unsupported moving average model `{model}`, expected `simple`, `linear`, or \
+                     `ewma`"
+                )
+            }
+        };
+        Ok(function)
+    }
+
+    /// Parses the script of a `moving_fn` aggregation, which must call one of the built-in
+    /// functions of `MovingFunctions` on the window values, e.g. `MovingFunctions.max(values)`.
+    fn parse_script(script: &str) -> anyhow::Result<Self> {
+        let invalid_script = || {
+            anyhow::anyhow!(
+                "unsupported moving function script `{script}`, expected \
+                 `MovingFunctions.<function>(values)`"
+            )
+        };
+        let call = script.trim().trim_end_matches(';').trim_end();
+        let (function_name, args) = call
+            .strip_prefix("MovingFunctions.")
+            .and_then(|call| call.strip_suffix(')'))
+            .and_then(|call| call.split_once('('))
+            .ok_or_else(invalid_script)?;
+        let mut args = args.split(',').map(str::trim);
+
+        if args.next() != Some("values") {
+            return Err(invalid_script());
+        }
+        let extra_args: Vec<&str> = args.collect();
+
+        let function = match (function_name, extra_args.as_slice()) {
+            ("unweightedAvg", []) => Self::UnweightedAvg,
+            ("linearWeightedAvg", []) => Self::LinearWeightedAvg,
+            ("ewma", [alpha]) => {
+                let alpha: f64 = alpha.parse().map_err(|_| invalid_script())?;
+                Self::Ewma { alpha }
+            }
+            ("max", []) => Self::Max,
+            ("min", []) => Self::Min,
+            ("sum", []) => Self::Sum,
+            _ => return Err(invalid_script()),
+        };
+        Ok(function)
+    }
+
+    fn apply<'a>(&self, values: impl ExactSizeIterator<Item = &'a f64>) -> f64 {
+        let num_values = values.len();
+        match self {
+            Self::UnweightedAvg => values.sum::<f64>() / num_values as f64,
+            Self::LinearWeightedAvg => {
+                let mut weighted_sum = 0.0;
+                let mut weights_sum = 0.0;
+
+                for (idx, value) in values.enumerate() {
+                    let weight = (idx + 1) as f64;
+                    weighted_sum += value * weight;
+                    weights_sum += weight;
+                }
+                weighted_sum / weights_sum
+            }
+            Self::Ewma { alpha } => values
+                .copied()
+                .reduce(|avg, value| alpha * value + (1.0 - alpha) * avg)
+                .unwrap_or(f64::NAN),
+            Self::Max => values.copied().reduce(f64::max).unwrap_or(f64::NAN),
+            Self::Min => values.copied().reduce(f64::min).unwrap_or(f64::NAN),
+            Self::Sum => values.sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RateValueSource {
+    /// The rate is computed from the number of documents in the bucket.
+    DocCount,
+    /// The rate is computed from the value of the rewritten metric aggregation.
+    Metric,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn parse(order: &str) -> anyhow::Result<Self> {
+        match order {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            _ => bail!("unknown sort order `{order}`, expected `asc` or `desc`"),
+        }
+    }
+}
+
+/// Rewrites the post-aggregations of an aggregation request into aggregations that the leaves can
+/// execute. Returns the rewritten aggregation request along with the post-aggregations that the
+/// root must apply to the final aggregation results.
+pub(crate) fn push_down_post_aggregations(
+    aggregation_request: &str,
+) -> anyhow::Result<(Cow<str>, PostAggregations)> {
+    // Fast path: most requests do not contain any post-aggregation.
+    if !POST_AGGREGATION_TYPES.iter().any(|post_aggregation_type| {
+        aggregation_request.contains(&format!("\"{post_aggregation_type}\""))
+    }) {
+        return Ok((
+            Cow::Borrowed(aggregation_request),
+            PostAggregations::default(),
+        ));
+    }
+    let Ok(JsonValue::Object(mut aggregations)) = serde_json::from_str(aggregation_request) else {
+        // Invalid requests are rejected later on with a proper error message.
+        return Ok((
+            Cow::Borrowed(aggregation_request),
+            PostAggregations::default(),
+        ));
+    };
+    let mut post_aggregations = Vec::new();
+
+    for (name, aggregation) in aggregations.iter_mut() {
+        let Some(aggregation) = aggregation.as_object_mut() else {
+            continue;
+        };
+        if POST_AGGREGATION_TYPES
+            .iter()
+            .any(|post_aggregation_type| aggregation.contains_key(*post_aggregation_type))
+        {
+            bail!(
+                "post-aggregation `{name}` must be declared as a sub-aggregation of a bucket \
+                 aggregation"
+            );
+        }
+        let mut path = vec![name.clone()];
+        push_down_sub_aggregations(aggregation, &mut path, &mut post_aggregations)?;
+    }
+    if post_aggregations.is_empty() {
+        return Ok((
+            Cow::Borrowed(aggregation_request),
+            PostAggregations::default(),
+        ));
+    }
+    let aggregation_request = serde_json::to_string(&aggregations)?;
+    Ok((
+        Cow::Owned(aggregation_request),
+        PostAggregations { post_aggregations },
+    ))
+}
+
+fn push_down_sub_aggregations(
+    aggregation: &mut JsonMap<String, JsonValue>,
+    path: &mut Vec<String>,
+    post_aggregations: &mut Vec<PostAggregation>,
+) -> anyhow::Result<()> {
+    let Some(sub_aggregation_key) = SUB_AGGREGATION_KEYS
+        .into_iter()
+        .find(|key| aggregation.contains_key(*key))
+    else {
+        return Ok(());
+    };
+    let Some(JsonValue::Object(mut sub_aggregations)) = aggregation.remove(sub_aggregation_key)
+    else {
+        bail!("sub-aggregations of `{}` must be an object", path.join("."));
+    };
+    let mut post_aggregation_names = Vec::new();
+    // Pipeline aggregations and bucket sorts are applied after the other post-aggregations of the
+    // same parent aggregation, whose results they may refer to.
+    let mut pipeline_aggregations = Vec::new();
+    let mut bucket_sorts = Vec::new();
+
+    for (name, sub_aggregation) in sub_aggregations.iter_mut() {
+        let Some(sub_aggregation) = sub_aggregation.as_object_mut() else {
+            continue;
+        };
+        if let Some(rate_params) = sub_aggregation.get("rate") {
+            let (kind, rewritten_aggregation_opt) = push_down_rate(name, rate_params, aggregation)?;
+
+            if let Some(rewritten_aggregation) = rewritten_aggregation_opt {
+                *sub_aggregation = rewritten_aggregation;
+            } else {
+                post_aggregation_names.push(name.clone());
+            }
+            post_aggregations.push(PostAggregation {
+                parent_path: path.clone(),
+                kind,
+            });
+        } else if let Some(bucket_sort_params) = sub_aggregation.get("bucket_sort") {
+            let kind = push_down_bucket_sort(name, bucket_sort_params, aggregation)?;
+            post_aggregation_names.push(name.clone());
+            bucket_sorts.push(PostAggregation {
+                parent_path: path.clone(),
+                kind,
+            });
+        } else if let Some(kind) = parse_pipeline_aggregation(name, sub_aggregation, aggregation)? {
+            post_aggregation_names.push(name.clone());
+            pipeline_aggregations.push(PostAggregation {
+                parent_path: path.clone(),
+                kind,
+            });
+        } else {
+            path.push(name.clone());
+            push_down_sub_aggregations(sub_aggregation, path, post_aggregations)?;
+            path.pop();
+        }
+    }
+    post_aggregations.extend(sort_pipeline_aggregations(pipeline_aggregations)?);
+    post_aggregations.extend(bucket_sorts);
+
+    for post_aggregation_name in post_aggregation_names {
+        sub_aggregations.remove(&post_aggregation_name);
+    }
+    if !sub_aggregations.is_empty() {
+        aggregation.insert(
+            sub_aggregation_key.to_string(),
+            JsonValue::Object(sub_aggregations),
+        );
+    }
+    Ok(())
+}
+
+/// Rewrites a `rate` aggregation into the metric aggregation computed by the leaves, if any.
+fn push_down_rate(
+    name: &str,
+    rate_params: &JsonValue,
+    parent_aggregation: &JsonMap<String, JsonValue>,
+) -> anyhow::Result<(PostAggregationKind, Option<JsonMap<String, JsonValue>>)> {
+    let Some(fixed_interval) = date_histogram_fixed_interval(parent_aggregation) else {
+        bail!(
+            "rate aggregation `{name}` must be a sub-aggregation of a `date_histogram` \
+             aggregation with a `fixed_interval`"
+        );
+    };
+    let interval_millis = parse_fixed_interval_millis(fixed_interval)?;
+    let unit_millis = match rate_params.get("unit").and_then(JsonValue::as_str) {
+        Some(unit) => parse_rate_unit_millis(unit)?,
+        None => interval_millis,
+    };
+    let factor = unit_millis as f64 / interval_millis as f64;
+
+    let Some(field) = rate_params.get("field") else {
+        let kind = PostAggregationKind::Rate {
+            name: name.to_string(),
+            value_source: RateValueSource::DocCount,
+            factor,
+        };
+        return Ok((kind, None));
+    };
+    let metric_type = match rate_params.get("mode").and_then(JsonValue::as_str) {
+        None | Some("sum") => "sum",
+        Some("value_count") => "value_count",
+        Some(mode) => {
+            bail!("unknown rate mode `{mode}`, expected `sum` or `value_count`")
+        }
+    };
+    let mut metric_aggregation = JsonMap::new();
+    metric_aggregation.insert(metric_type.to_string(), json!({ "field": field }));
+
+    let kind = PostAggregationKind::Rate {
+        name: name.to_string(),
+        value_source: RateValueSource::Metric,
+        factor,
+    };
+    Ok((kind, Some(metric_aggregation)))
+}
+
+/// Returns the fixed interval of a `date_histogram` aggregation.
+fn date_histogram_fixed_interval(aggregation: &JsonMap<String, JsonValue>) -> Option<&str> {
+    aggregation
+        .get("date_histogram")
+        .and_then(|date_histogram_params| date_histogram_params.get("fixed_interval"))
+        .and_then(JsonValue::as_str)
+}
+
+/// Parses a pipeline aggregation. Returns `None` if the aggregation is not a pipeline
+/// aggregation.
+fn parse_pipeline_aggregation(
+    name: &str,
+    aggregation: &JsonMap<String, JsonValue>,
+    parent_aggregation: &JsonMap<String, JsonValue>,
+) -> anyhow::Result<Option<PostAggregationKind>> {
+    let Some((aggregation_type, params)) =
+        PIPELINE_AGGREGATION_TYPES
+            .into_iter()
+            .find_map(|aggregation_type| {
+                aggregation
+                    .get(aggregation_type)
+                    .map(|params| (aggregation_type, params))
+            })
+    else {
+        return Ok(None);
+    };
+    let parent_aggregation_types: &[&str] = if aggregation_type == "bucket_script" {
+        &MULTI_BUCKET_AGGREGATION_TYPES
+    } else {
+        &HISTOGRAM_AGGREGATION_TYPES
+    };
+    if !parent_aggregation_types
+        .iter()
+        .any(|parent_aggregation_type| parent_aggregation.contains_key(*parent_aggregation_type))
+    {
+        bail!(
+            "{aggregation_type} aggregation `{name}` must be a sub-aggregation of a {} aggregation",
+            parent_aggregation_types.join(" or ")
+        );
+    }
+    let name = name.to_string();
+    let gap_policy = GapPolicy::parse(params)?;
+
+    if matches!(aggregation_type, "moving_avg" | "moving_fn")
+        && parse_usize_param(params, "window")? == Some(0)
+    {
+        bail!("window of {aggregation_type} aggregation `{name}` must be greater than zero");
+    }
+
+    let kind = match aggregation_type {
+        "derivative" => {
+            let unit_factor_opt = match params.get("unit").and_then(JsonValue::as_str) {
+                Some(unit) => {
+                    let Some(fixed_interval) = date_histogram_fixed_interval(parent_aggregation)
+                    else {
+                        bail!(
+                            "derivative aggregation `{name}` must be a sub-aggregation of a \
+                             `date_histogram` aggregation with a `fixed_interval` to use a `unit`"
+                        );
+                    };
+                    let interval_millis = parse_fixed_interval_millis(fixed_interval)?;
+                    let unit_millis = parse_rate_unit_millis(unit)
+                        .or_else(|_| parse_fixed_interval_millis(unit))?;
+                    Some(unit_millis as f64 / interval_millis as f64)
+                }
+                None => None,
+            };
+            PostAggregationKind::Derivative {
+                name: name.clone(),
+                buckets_path: parse_buckets_path(&name, params)?,
+                gap_policy,
+                unit_factor_opt,
+            }
+        }
+        "cumulative_sum" => PostAggregationKind::CumulativeSum {
+            name: name.clone(),
+            buckets_path: parse_buckets_path(&name, params)?,
+        },
+        "moving_avg" => {
+            if params.get("predict").is_some() || params.get("minimize").is_some() {
+                bail!("moving average predictions are not supported");
+            }
+            let window = parse_usize_param(params, "window")?.unwrap_or(DEFAULT_MOVING_AVG_WINDOW);
+            PostAggregationKind::MovingAvg {
+                name: name.clone(),
+                buckets_path: parse_buckets_path(&name, params)?,
+                gap_policy,
+                window,
+                function: MovingFunction::parse_model(params)?,
+            }
+        }
+        "moving_fn" => {
+            let window = parse_usize_param(params, "window")?.with_context(|| {
+                format!("moving function aggregation `{name}` requires a `window`")
+            })?;
+            let shift = params
+                .get("shift")
+                .map_or(Some(0), JsonValue::as_i64)
+                .context("`shift` must be an integer")?;
+            let script = parse_script(&name, params)?;
+            PostAggregationKind::MovingFn {
+                name: name.clone(),
+                buckets_path: parse_buckets_path(&name, params)?,
+                gap_policy,
+                window,
+                shift,
+                function: MovingFunction::parse_script(script)?,
+            }
+        }
+        "bucket_script" => {
+            let Some(JsonValue::Object(buckets_paths_params)) = params.get("buckets_path") else {
+                bail!("bucket script aggregation `{name}` requires a `buckets_path` object");
+            };
+            let mut buckets_paths = Vec::with_capacity(buckets_paths_params.len());
+
+            for (variable, buckets_path) in buckets_paths_params {
+                let Some(buckets_path) = buckets_path.as_str() else {
+                    bail!("buckets path of variable `{variable}` must be a string");
+                };
+                check_buckets_path(buckets_path)?;
+                buckets_paths.push((variable.clone(), buckets_path.to_string()));
+            }
+            let script = ScriptExpr::parse(parse_script(&name, params)?)?;
+            let mut undeclared_variable_opt = None;
+
+            script.visit_variables(&mut |variable| {
+                if !buckets_paths_params.contains_key(variable) {
+                    undeclared_variable_opt.get_or_insert(variable);
+                }
+            });
+            if let Some(undeclared_variable) = undeclared_variable_opt {
+                bail!(
+                    "variable `{undeclared_variable}` of bucket script aggregation `{name}` is \
+                     not declared in `buckets_path`"
+                );
+            }
+            PostAggregationKind::BucketScript {
+                name: name.clone(),
+                buckets_paths,
+                script,
+                gap_policy,
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(Some(kind))
+}
+
+fn parse_buckets_path(name: &str, params: &JsonValue) -> anyhow::Result<String> {
+    let Some(buckets_path) = params.get("buckets_path").and_then(JsonValue::as_str) else {
+        bail!("pipeline aggregation `{name}` requires a `buckets_path` string");
+    };
+    check_buckets_path(buckets_path)?;
+    Ok(buckets_path.to_string())
+}
+
+/// Buckets paths designate a value of the buckets of the parent aggregation: `_count`, `_key`,
+/// the name of a single-value metric sub-aggregation, or `<name>.<value>`. Paths traversing
+/// sub-aggregations with `>` are not supported.
+fn check_buckets_path(buckets_path: &str) -> anyhow::Result<()> {
+    if buckets_path.is_empty() || buckets_path.contains('>') {
+        bail!("unsupported buckets path `{buckets_path}`");
+    }
+    Ok(())
+}
+
+/// Returns the source of the script of a pipeline aggregation, which is either a string or an
+/// object with a `source` or `inline` property.
+fn parse_script<'a>(name: &str, params: &'a JsonValue) -> anyhow::Result<&'a str> {
+    let script_opt = match params.get("script") {
+        Some(JsonValue::String(script)) => Some(script.as_str()),
+        Some(JsonValue::Object(script_params)) => script_params
+            .get("source")
+            .or_else(|| script_params.get("inline"))
+            .and_then(JsonValue::as_str),
+        _ => None,
+    };
+    script_opt.with_context(|| format!("pipeline aggregation `{name}` requires a `script`"))
+}
+
+/// Orders the pipeline aggregations of a parent aggregation so that the aggregations referring to
+/// the results of other pipeline aggregations come after them.
+fn sort_pipeline_aggregations(
+    mut pipeline_aggregations: Vec<PostAggregation>,
+) -> anyhow::Result<Vec<PostAggregation>> {
+    let mut sorted_pipeline_aggregations = Vec::with_capacity(pipeline_aggregations.len());
+
+    while !pipeline_aggregations.is_empty() {
+        let Some(idx) = pipeline_aggregations.iter().position(|candidate| {
+            candidate.kind.buckets_paths().all(|buckets_path| {
+                let referenced_name = buckets_path.split('.').next().unwrap_or(buckets_path);
+                pipeline_aggregations
+                    .iter()
+                    .all(|pending| pending.kind.name() != Some(referenced_name))
+            })
+        }) else {
+            bail!("pipeline aggregations must not refer to each other cyclically");
+        };
+        sorted_pipeline_aggregations.push(pipeline_aggregations.remove(idx));
+    }
+    Ok(sorted_pipeline_aggregations)
+}
+
+/// Parses a `bucket_sort` aggregation and reduces the size of its parent `terms` aggregation when
+/// the `bucket_sort` aggregation only truncates its buckets.
+fn push_down_bucket_sort(
+    name: &str,
+    bucket_sort_params: &JsonValue,
+    parent_aggregation: &mut JsonMap<String, JsonValue>,
+) -> anyhow::Result<PostAggregationKind> {
+    if !MULTI_BUCKET_AGGREGATION_TYPES
+        .iter()
+        .any(|aggregation_type| parent_aggregation.contains_key(*aggregation_type))
+    {
+        bail!("bucket sort aggregation `{name}` must be a sub-aggregation of a bucket aggregation");
+    }
+    let sort = match bucket_sort_params.get("sort") {
+        None => Vec::new(),
+        Some(JsonValue::Array(sort_entries)) => sort_entries
+            .iter()
+            .map(parse_sort_entry)
+            .collect::<anyhow::Result<_>>()?,
+        Some(sort_entry) => vec![parse_sort_entry(sort_entry)?],
+    };
+    let from = parse_usize_param(bucket_sort_params, "from")?.unwrap_or(0);
+    let size_opt = parse_usize_param(bucket_sort_params, "size")?;
+
+    if let (Some(size), Some(JsonValue::Object(terms_params))) =
+        (size_opt, parent_aggregation.get_mut("terms"))
+    {
+        let is_sorted_by_terms_order = terms_order(terms_params)
+            .is_some_and(|terms_order| sort.is_empty() || sort == [terms_order]);
+
+        if is_sorted_by_terms_order {
+            // The size of `terms` aggregations defaults to 10.
+            let terms_size = terms_params
+                .get("size")
+                .and_then(JsonValue::as_u64)
+                .unwrap_or(10) as usize;
+            let pushed_down_size = terms_size.min(from + size);
+
+            if pushed_down_size < terms_size {
+                // The segment size defaults to 10 times the size: we pin it to the value derived
+                // from the original size so that the buckets and their error bounds do not change.
+                if !terms_params.contains_key("segment_size") {
+                    terms_params.insert("segment_size".to_string(), json!(terms_size * 10));
+                }
+                terms_params.insert("size".to_string(), json!(pushed_down_size));
+            }
+        }
+    }
+    Ok(PostAggregationKind::BucketSort {
+        sort,
+        from,
+        size_opt,
+    })
+}
+
+/// Returns the sort key and order of a `terms` aggregation, which defaults to the document count
+/// in descending order, or `None` if the aggregation is sorted by several keys.
+fn terms_order(terms_params: &JsonMap<String, JsonValue>) -> Option<(String, SortOrder)> {
+    let Some(order) = terms_params.get("order") else {
+        return Some(("_count".to_string(), SortOrder::Desc));
+    };
+    let JsonValue::Object(order) = order else {
+        return None;
+    };
+    if order.len() != 1 {
+        return None;
+    }
+    let (key, JsonValue::String(order)) = order.iter().next()? else {
+        return None;
+    };
+    let order = SortOrder::parse(order).ok()?;
+    Some((key.clone(), order))
+}
+
+/// Parses a sort entry of a `bucket_sort` aggregation: `"key"`, `{"key": "desc"}`, or
+/// `{"key": {"order": "desc"}}`. The sort order defaults to ascending.
+fn parse_sort_entry(sort_entry: &JsonValue) -> anyhow::Result<(String, SortOrder)> {
+    match sort_entry {
+        JsonValue::String(key) => Ok((key.clone(), SortOrder::Asc)),
+        JsonValue::Object(sort_entry) if sort_entry.len() == 1 => {
+            let (key, order) = sort_entry
+                .iter()
+                .next()
+                .expect("sort entry should not be empty");
+            let order = match order {
+                JsonValue::String(order) => SortOrder::parse(order)?,
+                JsonValue::Object(order_params) => match order_params.get("order") {
+                    Some(JsonValue::String(order)) => SortOrder::parse(order)?,
+                    None => SortOrder::Asc,
+                    Some(order) => bail!("invalid sort order `{order}`"),
+                },
+                _ => bail!("invalid sort order `{order}`"),
+            };
+            Ok((key.clone(), order))
+        }
+        _ => bail!("invalid bucket sort entry `{sort_entry}`"),
+    }
+}
+
+fn parse_usize_param(params: &JsonValue, key: &str) -> anyhow::Result<Option<usize>> {
+    let Some(value) = params.get(key) else {
+        return Ok(None);
+    };
+    let value = value
+        .as_u64()
+        .with_context(|| format!("`{key}` must be a non-negative integer"))?;
+    Ok(Some(value as usize))
+}
+
+/// Parses a fixed interval of a `date_histogram` aggregation, e.g. `30s` or `1h`.
+fn parse_fixed_interval_millis(fixed_interval: &str) -> anyhow::Result<u64> {
+    let split_idx = fixed_interval
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(fixed_interval.len());
+    let (value, unit) = fixed_interval.split_at(split_idx);
+    let value: u64 = value
+        .parse()
+        .with_context(|| format!("invalid fixed interval `{fixed_interval}`"))?;
+    let unit_millis = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => bail!("invalid fixed interval `{fixed_interval}`"),
+    };
+    if value == 0 {
+        bail!("fixed interval `{fixed_interval}` must be greater than zero");
+    }
+    Ok(value * unit_millis)
+}
+
+fn parse_rate_unit_millis(unit: &str) -> anyhow::Result<u64> {
+    let unit_millis = match unit {
+        "second" => 1_000,
+        "minute" => 60_000,
+        "hour" => 3_600_000,
+        "day" => 86_400_000,
+        "week" => 7 * 86_400_000,
+        "month" | "quarter" | "year" => {
+            bail!("calendar rate unit `{unit}` is not supported")
+        }
+        _ => bail!("unknown rate unit `{unit}`"),
+    };
+    Ok(unit_millis)
+}
+
+impl PostAggregations {
+    pub fn is_empty(&self) -> bool {
+        self.post_aggregations.is_empty()
+    }
+
+    /// Applies the post-aggregations to the final aggregation results.
+    pub fn apply(&self, aggregation_results: &mut JsonValue) {
+        for post_aggregation in &self.post_aggregations {
+            visit_buckets(
+                aggregation_results,
+                &post_aggregation.parent_path,
+                &mut |buckets| post_aggregation.kind.apply(buckets),
+            );
+        }
+    }
+}
+
+impl PostAggregationKind {
+    fn apply(&self, buckets: &mut JsonValue) {
+        match self {
+            Self::Rate {
+                name,
+                value_source,
+                factor,
+            } => {
+                for bucket in bucket_values_mut(buckets) {
+                    let value_opt = match value_source {
+                        RateValueSource::DocCount => bucket.get("doc_count"),
+                        RateValueSource::Metric => bucket.get(name).and_then(|v| v.get("value")),
+                    }
+                    .and_then(JsonValue::as_f64);
+                    let rate = value_opt.map(|value| value * factor);
+
+                    if let Some(bucket) = bucket.as_object_mut() {
+                        bucket.insert(name.clone(), json!({ "value": rate }));
+                    }
+                }
+            }
+            Self::BucketSort {
+                sort,
+                from,
+                size_opt,
+            } => {
+                // Keyed buckets are serialized as an object, which cannot be reordered.
+                let JsonValue::Array(buckets) = buckets else {
+                    return;
+                };
+                if !sort.is_empty() {
+                    buckets.sort_by(|left, right| compare_buckets(left, right, sort));
+                }
+                buckets.drain(..(*from).min(buckets.len()));
+
+                if let Some(size) = size_opt {
+                    buckets.truncate(*size);
+                }
+            }
+            Self::Derivative {
+                name,
+                buckets_path,
+                gap_policy,
+                unit_factor_opt,
+            } => {
+                let mut previous_value_opt: Option<f64> = None;
+
+                for bucket in bucket_values_mut(buckets) {
+                    let value_opt = resolve_bucket_value(bucket, buckets_path, *gap_policy);
+
+                    if let (Some(value), Some(previous_value)) = (value_opt, previous_value_opt) {
+                        let derivative = value - previous_value;
+                        let result = if let Some(unit_factor) = unit_factor_opt {
+                            json!({
+                                "value": derivative,
+                                "normalized_value": derivative * unit_factor,
+                            })
+                        } else {
+                            json!({ "value": derivative })
+                        };
+                        insert_result(bucket, name, result);
+                    }
+                    previous_value_opt = value_opt;
+                }
+            }
+            Self::CumulativeSum { name, buckets_path } => {
+                let mut sum = 0.0;
+
+                for bucket in bucket_values_mut(buckets) {
+                    sum += resolve_bucket_value(bucket, buckets_path, GapPolicy::InsertZeros)
+                        .unwrap_or(0.0);
+                    insert_result(bucket, name, json!({ "value": sum }));
+                }
+            }
+            Self::MovingAvg {
+                name,
+                buckets_path,
+                gap_policy,
+                window,
+                function,
+            } => {
+                let mut window_values: VecDeque<f64> = VecDeque::with_capacity(*window);
+
+                for bucket in bucket_values_mut(buckets) {
+                    let Some(value) = resolve_bucket_value(bucket, buckets_path, *gap_policy)
+                    else {
+                        continue;
+                    };
+                    if !window_values.is_empty() {
+                        let moving_avg = function.apply(window_values.iter());
+                        insert_result(bucket, name, json!({ "value": moving_avg }));
+                    }
+                    if window_values.len() == *window {
+                        window_values.pop_front();
+                    }
+                    window_values.push_back(value);
+                }
+            }
+            Self::MovingFn {
+                name,
+                buckets_path,
+                gap_policy,
+                window,
+                shift,
+                function,
+            } => {
+                let values: Vec<Option<f64>> = bucket_values_mut(buckets)
+                    .map(|bucket| resolve_bucket_value(bucket, buckets_path, *gap_policy))
+                    .collect();
+                let num_buckets = values.len() as i64;
+
+                for (idx, bucket) in bucket_values_mut(buckets).enumerate() {
+                    if values[idx].is_none() {
+                        continue;
+                    }
+                    let window_end = (idx as i64 + shift).clamp(0, num_buckets);
+                    let window_start = (window_end - *window as i64).max(0);
+                    let window_values: Vec<f64> = values
+                        [window_start as usize..window_end as usize]
+                        .iter()
+                        .flatten()
+                        .copied()
+                        .collect();
+                    let result = function.apply(window_values.iter());
+                    insert_result(bucket, name, json!({ "value": result }));
+                }
+            }
+            Self::BucketScript {
+                name,
+                buckets_paths,
+                script,
+                gap_policy,
+            } => {
+                for bucket in bucket_values_mut(buckets) {
+                    let variables_opt: Option<HashMap<&str, f64>> = buckets_paths
+                        .iter()
+                        .map(|(variable, buckets_path)| {
+                            let value = resolve_bucket_value(bucket, buckets_path, *gap_policy)?;
+                            Some((variable.as_str(), value))
+                        })
+                        .collect();
+                    let Some(variables) = variables_opt else {
+                        continue;
+                    };
+                    if let Some(value) = script.eval(&variables) {
+                        insert_result(bucket, name, json!({ "value": value }));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the name of the post-aggregation, if it produces a result in the buckets.
+    fn name(&self) -> Option<&str> {
+        match self {
+            Self::Rate { name, .. }
+            | Self::Derivative { name, .. }
+            | Self::CumulativeSum { name, .. }
+            | Self::MovingAvg { name, .. }
+            | Self::MovingFn { name, .. }
+            | Self::BucketScript { name, .. } => Some(name),
+            Self::BucketSort { .. } => None,
+        }
+    }
+
+    /// Returns the buckets paths of the values the post-aggregation is computed from.
+    fn buckets_paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        match self {
+            Self::Derivative { buckets_path, .. }
+            | Self::CumulativeSum { buckets_path, .. }
+            | Self::MovingAvg { buckets_path, .. }
+            | Self::MovingFn { buckets_path, .. } => {
+                Box::new(std::iter::once(buckets_path.as_str()))
+            }
+            Self::BucketScript { buckets_paths, .. } => Box::new(
+                buckets_paths
+                    .iter()
+                    .map(|(_, buckets_path)| buckets_path.as_str()),
+            ),
+            Self::Rate { .. } | Self::BucketSort { .. } => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+/// Resolves the value designated by a buckets path in a bucket. Missing values and the values of
+/// empty buckets are gaps, handled according to the gap policy.
+fn resolve_bucket_value(
+    bucket: &JsonValue,
+    buckets_path: &str,
+    gap_policy: GapPolicy,
+) -> Option<f64> {
+    let is_empty_bucket =
+        buckets_path != "_count" && bucket.get("doc_count").and_then(JsonValue::as_u64) == Some(0);
+    let value_opt = if is_empty_bucket {
+        None
+    } else {
+        bucket_path_value(bucket, buckets_path)
+            .and_then(JsonValue::as_f64)
+            .filter(|value| value.is_finite())
+    };
+    match gap_policy {
+        GapPolicy::Skip => value_opt,
+        GapPolicy::InsertZeros => Some(value_opt.unwrap_or(0.0)),
+    }
+}
+
+fn insert_result(bucket: &mut JsonValue, name: &str, result: JsonValue) {
+    if let Some(bucket) = bucket.as_object_mut() {
+        bucket.insert(name.to_string(), result);
+    }
+}
+
+/// Calls `visitor` on the buckets of the aggregation designated by `path` in every bucket of its
+/// ancestors.
+fn visit_buckets(
+    aggregation_results: &mut JsonValue,
+    path: &[String],
+    visitor: &mut dyn FnMut(&mut JsonValue),
+) {
+    let Some((name, path_tail)) = path.split_first() else {
+        return;
+    };
+    let Some(buckets) = aggregation_results
+        .get_mut(name)
+        .and_then(|aggregation_result| aggregation_result.get_mut("buckets"))
+    else {
+        return;
+    };
+    if path_tail.is_empty() {
+        visitor(buckets);
+        return;
+    }
+    for bucket in bucket_values_mut(buckets) {
+        visit_buckets(bucket, path_tail, visitor);
+    }
+}
+
+fn bucket_values_mut(buckets: &mut JsonValue) -> Box<dyn Iterator<Item = &mut JsonValue> + '_> {
+    match buckets {
+        JsonValue::Array(buckets) => Box::new(buckets.iter_mut()),
+        JsonValue::Object(buckets) => Box::new(buckets.values_mut()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn compare_buckets(left: &JsonValue, right: &JsonValue, sort: &[(String, SortOrder)]) -> Ordering {
+    for (key, order) in sort {
+        let left_value = bucket_path_value(left, key);
+        let right_value = bucket_path_value(right, key);
+
+        // Buckets without a value always come last.
+        let ordering = match (left_value, right_value) {
+            (Some(left_value), Some(right_value)) => {
+                let ordering = compare_json_values(left_value, right_value);
+                match order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Returns the value of a bucket for a sort key or a buckets path: `_count`, `_key`, the name of a
+/// single-value metric sub-aggregation, or `<name>.<value>` for a multi-value metric
+/// sub-aggregation.
+fn bucket_path_value<'a>(bucket: &'a JsonValue, key: &str) -> Option<&'a JsonValue> {
+    let value = match key {
+        "_count" => bucket.get("doc_count"),
+        "_key" => bucket.get("key"),
+        _ => {
+            if let Some((name, value_name)) = key.split_once('.') {
+                bucket.get(name)?.get(value_name)
+            } else {
+                bucket.get(key)?.get("value")
+            }
+        }
+    }?;
+    (!value.is_null()).then_some(value)
+}
+
+fn compare_json_values(left: &JsonValue, right: &JsonValue) -> Ordering {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(left), Some(right)) => left.total_cmp(&right),
+        _ => match (left.as_str(), right.as_str()) {
+            (Some(left), Some(right)) => left.cmp(right),
+            _ => Ordering::Equal,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_down_post_aggregations_no_post_aggregation() {
+        let aggregation_request = r#"{"hosts": {"terms": {"field": "host"}}}"#;
+        let (rewritten_request, post_aggregations) =
+            push_down_post_aggregations(aggregation_request).unwrap();
+        assert!(matches!(rewritten_request, Cow::Borrowed(_)));
+        assert!(post_aggregations.is_empty());
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_rate() {
+        let aggregation_request = json!({
+            "histo": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1m"},
+                "aggs": {
+                    "bytes_per_sec": {"rate": {"field": "bytes", "unit": "second"}},
+                    "docs_per_hour": {"rate": {"unit": "hour"}},
+                }
+            }
+        })
+        .to_string();
+        let (rewritten_request, post_aggregations) =
+            push_down_post_aggregations(&aggregation_request).unwrap();
+        let rewritten_request: JsonValue = serde_json::from_str(&rewritten_request).unwrap();
+        assert_eq!(
+            rewritten_request,
+            json!({
+                "histo": {
+                    "date_histogram": {"field": "timestamp", "fixed_interval": "1m"},
+                    "aggs": {
+                        "bytes_per_sec": {"sum": {"field": "bytes"}},
+                    }
+                }
+            })
+        );
+        let mut aggregation_results = json!({
+            "histo": {
+                "buckets": [
+                    {"key": 0.0, "doc_count": 3, "bytes_per_sec": {"value": 600.0}},
+                    {"key": 60000.0, "doc_count": 0, "bytes_per_sec": {"value": null}},
+                ]
+            }
+        });
+        post_aggregations.apply(&mut aggregation_results);
+        assert_eq!(
+            aggregation_results,
+            json!({
+                "histo": {
+                    "buckets": [
+                        {
+                            "key": 0.0,
+                            "doc_count": 3,
+                            "bytes_per_sec": {"value": 10.0},
+                            "docs_per_hour": {"value": 180.0},
+                        },
+                        {
+                            "key": 60000.0,
+                            "doc_count": 0,
+                            "bytes_per_sec": {"value": null},
+                            "docs_per_hour": {"value": 0.0},
+                        },
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_bucket_sort() {
+        let aggregation_request = json!({
+            "hosts": {
+                "terms": {"field": "host", "size": 500},
+                "aggs": {
+                    "top": {"bucket_sort": {"sort": [{"_count": {"order": "desc"}}], "size": 2}},
+                    "latency": {"avg": {"field": "latency"}},
+                }
+            },
+            "services": {
+                "terms": {"field": "service", "size": 100},
+                "aggs": {
+                    "slowest": {"bucket_sort": {"sort": [{"latency": "desc"}], "size": 1}},
+                    "latency": {"avg": {"field": "latency"}},
+                }
+            }
+        })
+        .to_string();
+        let (rewritten_request, post_aggregations) =
+            push_down_post_aggregations(&aggregation_request).unwrap();
+        let rewritten_request: JsonValue = serde_json::from_str(&rewritten_request).unwrap();
+        // The top-k of the first terms aggregation is pushed down, not the second one whose order
+        // differs.
+        assert_eq!(rewritten_request["hosts"]["terms"]["size"], 2);
+        // The segment size derived from the original size is preserved.
+        assert_eq!(rewritten_request["hosts"]["terms"]["segment_size"], 5000);
+        assert!(rewritten_request["hosts"]["aggs"].get("top").is_none());
+        assert_eq!(rewritten_request["services"]["terms"]["size"], 100);
+        assert!(rewritten_request["services"]["terms"]
+            .get("segment_size")
+            .is_none());
+        assert!(rewritten_request["services"]["aggs"]
+            .get("slowest")
+            .is_none());
+
+        let mut aggregation_results = json!({
+            "hosts": {
+                "buckets": [
+                    {"key": "host-1", "doc_count": 3, "latency": {"value": 1.0}},
+                    {"key": "host-2", "doc_count": 2, "latency": {"value": 2.0}},
+                ]
+            },
+            "services": {
+                "buckets": [
+                    {"key": "service-1", "doc_count": 3, "latency": {"value": 1.0}},
+                    {"key": "service-2", "doc_count": 2, "latency": {"value": null}},
+                    {"key": "service-3", "doc_count": 1, "latency": {"value": 3.0}},
+                ]
+            }
+        });
+        post_aggregations.apply(&mut aggregation_results);
+        assert_eq!(
+            aggregation_results["hosts"]["buckets"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            aggregation_results["services"]["buckets"],
+            json!([{"key": "service-3", "doc_count": 1, "latency": {"value": 3.0}}])
+        );
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_bucket_sort_skips_multi_key_order() {
+        let aggregation_request = json!({
+            "hosts": {
+                "terms": {
+                    "field": "host",
+                    "size": 500,
+                    "order": [{"_count": "desc"}, {"_key": "asc"}]
+                },
+                "aggs": {
+                    "top": {"bucket_sort": {"size": 2}}
+                }
+            }
+        })
+        .to_string();
+        let (rewritten_request, _post_aggregations) =
+            push_down_post_aggregations(&aggregation_request).unwrap();
+        let rewritten_request: JsonValue = serde_json::from_str(&rewritten_request).unwrap();
+        assert_eq!(rewritten_request["hosts"]["terms"]["size"], 500);
+        assert!(rewritten_request["hosts"]["terms"]
+            .get("segment_size")
+            .is_none());
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_pipeline() {
+        let aggregation_request = json!({
+            "histo": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1m"},
+                "aggs": {
+                    "bytes": {"sum": {"field": "bytes"}},
+                    "errors": {"sum": {"field": "errors"}},
+                    "bytes_deriv": {"derivative": {"buckets_path": "bytes", "unit": "second"}},
+                    "bytes_cumsum": {"cumulative_sum": {"buckets_path": "bytes"}},
+                    // Refers to another pipeline aggregation declared after it.
+                    "a_cumsum_deriv": {"derivative": {"buckets_path": "bytes_cumsum"}},
+                    "bytes_movavg": {"moving_avg": {"buckets_path": "bytes", "window": 2}},
+                    "bytes_movmax": {
+                        "moving_fn": {
+                            "buckets_path": "bytes",
+                            "window": 2,
+                            "script": "MovingFunctions.max(values)"
+                        }
+                    },
+                    "error_ratio": {
+                        "bucket_script": {
+                            "buckets_path": {"errors": "errors", "count": "_count"},
+                            "script": {"source": "params.errors / params.count"}
+                        }
+                    },
+                }
+            }
+        })
+        .to_string();
+        let (rewritten_request, post_aggregations) =
+            push_down_post_aggregations(&aggregation_request).unwrap();
+        let rewritten_request: JsonValue = serde_json::from_str(&rewritten_request).unwrap();
+        assert_eq!(
+            rewritten_request,
+            json!({
+                "histo": {
+                    "date_histogram": {"field": "timestamp", "fixed_interval": "1m"},
+                    "aggs": {
+                        "bytes": {"sum": {"field": "bytes"}},
+                        "errors": {"sum": {"field": "errors"}},
+                    }
+                }
+            })
+        );
+        let mut aggregation_results = json!({
+            "histo": {
+                "buckets": [
+                    {
+                        "key": 0.0,
+                        "doc_count": 2,
+                        "bytes": {"value": 60.0},
+                        "errors": {"value": 1.0},
+                    },
+                    {
+                        "key": 60000.0,
+                        "doc_count": 0,
+                        "bytes": {"value": 0.0},
+                        "errors": {"value": 0.0},
+                    },
+                    {
+                        "key": 120000.0,
+                        "doc_count": 4,
+                        "bytes": {"value": 180.0},
+                        "errors": {"value": 2.0},
+                    },
+                    {
+                        "key": 180000.0,
+                        "doc_count": 1,
+                        "bytes": {"value": 120.0},
+                        "errors": {"value": 0.0},
+                    },
+                ]
+            }
+        });
+        post_aggregations.apply(&mut aggregation_results);
+        let buckets = &aggregation_results["histo"]["buckets"];
+
+        // The empty bucket is a gap: the derivative is not computed for it nor for the next
+        // bucket.
+        assert!(buckets[0].get("bytes_deriv").is_none());
+        assert!(buckets[1].get("bytes_deriv").is_none());
+        assert!(buckets[2].get("bytes_deriv").is_none());
+        assert_eq!(
+            buckets[3]["bytes_deriv"],
+            json!({"value": -60.0, "normalized_value": -1.0})
+        );
+        assert_eq!(buckets[0]["bytes_cumsum"]["value"], 60.0);
+        assert_eq!(buckets[1]["bytes_cumsum"]["value"], 60.0);
+        assert_eq!(buckets[2]["bytes_cumsum"]["value"], 240.0);
+        assert_eq!(buckets[3]["bytes_cumsum"]["value"], 360.0);
+
+        assert!(buckets[0].get("a_cumsum_deriv").is_none());
+        assert!(buckets[1].get("a_cumsum_deriv").is_none());
+        assert!(buckets[2].get("a_cumsum_deriv").is_none());
+        assert_eq!(buckets[3]["a_cumsum_deriv"]["value"], 120.0);
+
+        assert!(buckets[0].get("bytes_movavg").is_none());
+        assert!(buckets[1].get("bytes_movavg").is_none());
+        assert_eq!(buckets[2]["bytes_movavg"]["value"], 60.0);
+        assert_eq!(buckets[3]["bytes_movavg"]["value"], 120.0);
+
+        assert_eq!(buckets[0]["bytes_movmax"]["value"], JsonValue::Null);
+        assert!(buckets[1].get("bytes_movmax").is_none());
+        assert_eq!(buckets[2]["bytes_movmax"]["value"], 60.0);
+        assert_eq!(buckets[3]["bytes_movmax"]["value"], 180.0);
+
+        assert_eq!(buckets[0]["error_ratio"]["value"], 0.5);
+        assert!(buckets[1].get("error_ratio").is_none());
+        assert_eq!(buckets[2]["error_ratio"]["value"], 0.5);
+        assert_eq!(buckets[3]["error_ratio"]["value"], 0.0);
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_pipeline_insert_zeros() {
+        let aggregation_request = json!({
+            "histo": {
+                "histogram": {"field": "latency", "interval": 10},
+                "aggs": {
+                    "count_deriv": {
+                        "derivative": {"buckets_path": "max_size", "gap_policy": "insert_zeros"}
+                    },
+                    "max_size": {"max": {"field": "size"}},
+                    "top": {"bucket_sort": {"size": 1, "from": 1}},
+                }
+            }
+        })
+        .to_string();
+        let (_, post_aggregations) = push_down_post_aggregations(&aggregation_request).unwrap();
+
+        let mut aggregation_results = json!({
+            "histo": {
+                "buckets": [
+                    {"key": 0.0, "doc_count": 2, "max_size": {"value": 5.0}},
+                    {"key": 10.0, "doc_count": 0, "max_size": {"value": null}},
+                ]
+            }
+        });
+        post_aggregations.apply(&mut aggregation_results);
+        // The bucket sort is applied after the derivative.
+        assert_eq!(
+            aggregation_results["histo"]["buckets"],
+            json!([{
+                "key": 10.0,
+                "doc_count": 0,
+                "max_size": {"value": null},
+                "count_deriv": {"value": -5.0},
+            }])
+        );
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_pipeline_invalid() {
+        for pipeline_aggregation in [
+            json!({"derivative": {}}),
+            json!({"derivative": {"buckets_path": "a>b"}}),
+            json!({"derivative": {"buckets_path": "a", "gap_policy": "keep_values"}}),
+            json!({"moving_avg": {"buckets_path": "a", "model": "holt"}}),
+            json!({"moving_avg": {"buckets_path": "a", "window": 0}}),
+            json!({"moving_fn": {"buckets_path": "a", "script": "MovingFunctions.max(values)"}}),
+            json!({"moving_fn": {"buckets_path": "a", "window": 5, "script": "values[0]"}}),
+            json!({"bucket_script": {"buckets_path": "a", "script": "a"}}),
+            json!({"bucket_script": {"buckets_path": {"a": "a"}, "script": "a / b"}}),
+        ] {
+            let aggregation_request = json!({
+                "histo": {
+                    "histogram": {"field": "latency", "interval": 10},
+                    "aggs": {"pipeline": pipeline_aggregation}
+                }
+            })
+            .to_string();
+            push_down_post_aggregations(&aggregation_request).unwrap_err();
+        }
+        let aggregation_request = json!({
+            "hosts": {
+                "terms": {"field": "host"},
+                "aggs": {"deriv": {"derivative": {"buckets_path": "_count"}}}
+            }
+        })
+        .to_string();
+        let error = push_down_post_aggregations(&aggregation_request).unwrap_err();
+        assert!(error.to_string().contains("histogram"));
+
+        let aggregation_request = json!({
+            "histo": {
+                "histogram": {"field": "latency", "interval": 10},
+                "aggs": {
+                    "a": {"cumulative_sum": {"buckets_path": "b"}},
+                    "b": {"cumulative_sum": {"buckets_path": "a"}},
+                }
+            }
+        })
+        .to_string();
+        let error = push_down_post_aggregations(&aggregation_request).unwrap_err();
+        assert!(error.to_string().contains("cyclically"));
+    }
+
+    #[test]
+    fn test_moving_function_apply() {
+        let values = [1.0, 2.0, 3.0];
+        assert_eq!(MovingFunction::UnweightedAvg.apply(values.iter()), 2.0);
+        assert_eq!(
+            MovingFunction::LinearWeightedAvg.apply(values.iter()),
+            14.0 / 6.0
+        );
+        assert_eq!(
+            MovingFunction::Ewma { alpha: 0.5 }.apply(values.iter()),
+            2.25
+        );
+        assert_eq!(MovingFunction::Max.apply(values.iter()), 3.0);
+        assert_eq!(MovingFunction::Min.apply(values.iter()), 1.0);
+        assert_eq!(MovingFunction::Sum.apply(values.iter()), 6.0);
+        assert!(MovingFunction::Max.apply([].iter()).is_nan());
+        assert_eq!(
+            MovingFunction::parse_script("MovingFunctions.ewma(values, 0.5);").unwrap(),
+            MovingFunction::Ewma { alpha: 0.5 }
+        );
+    }
+
+    #[test]
+    fn test_push_down_post_aggregations_invalid() {
+        let aggregation_request = r#"{"rate": {"rate": {"unit": "second"}}}"#;
+        push_down_post_aggregations(aggregation_request).unwrap_err();
+
+        let aggregation_request = json!({
+            "hosts": {
+                "terms": {"field": "host"},
+                "aggs": {"rate": {"rate": {"unit": "second"}}}
+            }
+        })
+        .to_string();
+        let error = push_down_post_aggregations(&aggregation_request).unwrap_err();
+        assert!(error.to_string().contains("date_histogram"));
+
+        let aggregation_request = json!({
+            "histo": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1d"},
+                "aggs": {"rate": {"rate": {"unit": "month"}}}
+            }
+        })
+        .to_string();
+        push_down_post_aggregations(&aggregation_request).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_fixed_interval_millis() {
+        assert_eq!(parse_fixed_interval_millis("10ms").unwrap(), 10);
+        assert_eq!(parse_fixed_interval_millis("30s").unwrap(), 30_000);
+        assert_eq!(parse_fixed_interval_millis("2h").unwrap(), 7_200_000);
+        parse_fixed_interval_millis("0s").unwrap_err();
+        parse_fixed_interval_millis("1M").unwrap_err();
+        parse_fixed_interval_millis("s").unwrap_err();
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use quickwit_proto::search::{SearchProfile, SplitSearchProfile};
+
+fn split_total_micros(split_profile: &SplitSearchProfile) -> u64 {
+    split_profile.planning_micros
+        + split_profile.io_wait_micros
+        + split_profile.match_micros
+        + split_profile.aggregate_micros
+        + split_profile.fetch_micros
+}
+
+/// Builds the profile of a search request from the profiles recorded by the leaves.
+///
+/// A split may be reported several times, typically once by the search phase and once by the
+/// fetch docs phase: these profiles are merged together. Splits are sorted by decreasing total
+/// time so that the slowest ones come first.
+pub(crate) fn build_search_profile(
+    split_profiles: impl IntoIterator<Item = SplitSearchProfile>,
+) -> SearchProfile {
+    let mut merged_split_profiles: HashMap<String, SplitSearchProfile> = HashMap::new();
+
+    for split_profile in split_profiles {
+        let Some(merged_split_profile) = merged_split_profiles.get_mut(&split_profile.split_id)
+        else {
+            merged_split_profiles.insert(split_profile.split_id.clone(), split_profile);
+            continue;
+        };
+        merged_split_profile.planning_micros += split_profile.planning_micros;
+        merged_split_profile.io_wait_micros += split_profile.io_wait_micros;
+        merged_split_profile.match_micros += split_profile.match_micros;
+        merged_split_profile.aggregate_micros += split_profile.aggregate_micros;
+        merged_split_profile.fetch_micros += split_profile.fetch_micros;
+        merged_split_profile.num_bytes_read += split_profile.num_bytes_read;
+        merged_split_profile.num_storage_requests += split_profile.num_storage_requests;
+        merged_split_profile.footer_cache_hit |= split_profile.footer_cache_hit;
+        merged_split_profile.leaf_cache_hit |= split_profile.leaf_cache_hit;
+    }
+    let mut splits: Vec<SplitSearchProfile> = merged_split_profiles.into_values().collect();
+    splits.sort_unstable_by(|left, right| {
+        split_total_micros(right)
+            .cmp(&split_total_micros(left))
+            .then_with(|| left.split_id.cmp(&right.split_id))
+    });
+    let mut search_profile = SearchProfile {
+        num_splits: splits.len() as u64,
+        ..Default::default()
+    };
+    for split_profile in &splits {
+        search_profile.planning_micros += split_profile.planning_micros;
+        search_profile.io_wait_micros += split_profile.io_wait_micros;
+        search_profile.match_micros += split_profile.match_micros;
+        search_profile.aggregate_micros += split_profile.aggregate_micros;
+        search_profile.fetch_micros += split_profile.fetch_micros;
+        search_profile.num_bytes_read += split_profile.num_bytes_read;
+        search_profile.num_storage_requests += split_profile.num_storage_requests;
+        search_profile.num_footer_cache_hits += split_profile.footer_cache_hit as u64;
+        search_profile.num_leaf_cache_hits += split_profile.leaf_cache_hit as u64;
+    }
+    search_profile.splits = splits;
+    search_profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_search_profile() {
+        let search_profile = build_search_profile(Vec::new());
+        assert_eq!(search_profile, SearchProfile::default());
+
+        let split_profiles = vec![
+            SplitSearchProfile {
+                split_id: "split-1".to_string(),
+                planning_micros: 10,
+                io_wait_micros: 100,
+                match_micros: 20,
+                num_bytes_read: 1_000,
+                num_storage_requests: 2,
+                footer_cache_hit: true,
+                ..Default::default()
+            },
+            SplitSearchProfile {
+                split_id: "split-2".to_string(),
+                leaf_cache_hit: true,
+                ..Default::default()
+            },
+            SplitSearchProfile {
+                split_id: "split-3".to_string(),
+                planning_micros: 5,
+                io_wait_micros: 10,
+                match_micros: 30,
+                aggregate_micros: 40,
+                num_bytes_read: 500,
+                num_storage_requests: 1,
+                ..Default::default()
+            },
+            SplitSearchProfile {
+                split_id: "split-3".to_string(),
+                fetch_micros: 200,
+                num_bytes_read: 100,
+                num_storage_requests: 1,
+                ..Default::default()
+            },
+        ];
+        let search_profile = build_search_profile(split_profiles);
+        assert_eq!(search_profile.num_splits, 3);
+        assert_eq!(search_profile.planning_micros, 15);
+        assert_eq!(search_profile.io_wait_micros, 110);
+        assert_eq!(search_profile.match_micros, 50);
+        assert_eq!(search_profile.aggregate_micros, 40);
+        assert_eq!(search_profile.fetch_micros, 200);
+        assert_eq!(search_profile.num_bytes_read, 1_600);
+        assert_eq!(search_profile.num_storage_requests, 4);
+        assert_eq!(search_profile.num_footer_cache_hits, 1);
+        assert_eq!(search_profile.num_leaf_cache_hits, 1);
+
+        let split_ids: Vec<&str> = search_profile
+            .splits
+            .iter()
+            .map(|split_profile| split_profile.split_id.as_str())
+            .collect();
+        assert_eq!(split_ids, ["split-3", "split-1", "split-2"]);
+
+        let split_profile_3 = &search_profile.splits[0];
+        assert_eq!(split_profile_3.aggregate_micros, 40);
+        assert_eq!(split_profile_3.fetch_micros, 200);
+        assert_eq!(split_profile_3.num_bytes_read, 600);
+        assert_eq!(split_profile_3.num_storage_requests, 2);
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use quickwit_proto::search::{QueryAccessStats, SearchRequest};
+use quickwit_proto::types::IndexUid;
+use quickwit_query::query_ast::QueryAst;
+
+/// Collects the query access statistics of the indexes searched by the root searches of this
+/// node. The statistics are advertised to the indexers, which adapt the target size of the splits
+/// of the indexes to their access patterns.
+#[derive(Debug, Default)]
+pub struct QueryAccessTracker {
+    stats_per_index: Mutex<HashMap<IndexUid, QueryAccessStats>>,
+}
+
+impl QueryAccessTracker {
+    /// Records the outcome of a root search. The hits of a multi-index search are not broken
+    /// down per index, so all the searched indexes are assigned the same selectivity.
+    pub fn record_search(
+        &self,
+        index_uids: &[IndexUid],
+        num_docs_searched: u64,
+        num_hits: u64,
+        elapsed: Duration,
+        is_scan: bool,
+    ) {
+        if index_uids.is_empty() {
+            return;
+        }
+        let selectivity = if num_docs_searched == 0 {
+            0.0
+        } else {
+            num_hits as f64 / num_docs_searched as f64
+        };
+        let latency_millis = elapsed.as_secs_f64() * 1_000.0;
+        let mut stats_per_index = self
+            .stats_per_index
+            .lock()
+            .expect("the lock should not be poisoned");
+
+        for index_uid in index_uids {
+            stats_per_index
+                .entry(index_uid.clone())
+                .or_default()
+                .record_query(selectivity, latency_millis, is_scan);
+        }
+    }
+
+    /// Returns the current statistics of the indexes searched by this node.
+    pub fn snapshot(&self) -> HashMap<IndexUid, QueryAccessStats> {
+        self.stats_per_index
+            .lock()
+            .expect("the lock should not be poisoned")
+            .clone()
+    }
+}
+
+/// Returns whether the search reads all the searched documents rather than a selection of them.
+pub(crate) fn is_scan_query(query_ast: &QueryAst, search_request: &SearchRequest) -> bool {
+    search_request.aggregation_request.is_some() || matches!(query_ast, QueryAst::MatchAll)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_access_tracker() {
+        let query_access_tracker = QueryAccessTracker::default();
+        let index_uid_foo = IndexUid::for_test("foo", 0);
+        let index_uid_bar = IndexUid::for_test("bar", 0);
+
+        query_access_tracker.record_search(
+            &[index_uid_foo.clone(), index_uid_bar.clone()],
+            1_000,
+            10,
+            Duration::from_millis(20),
+            false,
+        );
+        query_access_tracker.record_search(
+            &[index_uid_foo.clone()],
+            1_000,
+            1_000,
+            Duration::from_millis(40),
+            true,
+        );
+        let snapshot = query_access_tracker.snapshot();
+        assert_eq!(snapshot.len(), 2);
+
+        let foo_stats = snapshot[&index_uid_foo];
+        assert_eq!(foo_stats.num_queries, 2);
+        assert_eq!(foo_stats.num_scan_queries, 1);
+        assert_eq!(foo_stats.scan_ratio(), 0.5);
+        assert!(foo_stats.selectivity > 0.01 && foo_stats.selectivity < 1.0);
+        assert!(foo_stats.latency_millis > 20.0 && foo_stats.latency_millis < 40.0);
+
+        let bar_stats = snapshot[&index_uid_bar];
+        assert_eq!(bar_stats.num_queries, 1);
+        assert_eq!(bar_stats.num_scan_queries, 0);
+        assert_eq!(bar_stats.selectivity, 0.01);
+        assert_eq!(bar_stats.latency_millis, 20.0);
+
+        let merged_stats = QueryAccessStats::merge([&foo_stats, &bar_stats]);
+        assert_eq!(merged_stats.num_queries, 3);
+        assert_eq!(merged_stats.num_scan_queries, 1);
+        let expected_selectivity = (2.0 * foo_stats.selectivity + 0.01) / 3.0;
+        assert!((merged_stats.selectivity - expected_selectivity).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_is_scan_query() {
+        let search_request = SearchRequest::default();
+        assert!(is_scan_query(&QueryAst::MatchAll, &search_request));
+        assert!(!is_scan_query(&QueryAst::MatchNone, &search_request));
+
+        let search_request = SearchRequest {
+            aggregation_request: Some("{}".to_string()),
+            ..Default::default()
+        };
+        assert!(is_scan_query(&QueryAst::MatchNone, &search_request));
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_config::SearcherConfig;
+use quickwit_query::query_ast::{QueryAst, QueryAstVisitor, RegexQuery};
+
+use crate::SearchError;
+
+/// Checks that the regex queries of a query AST are within the limits of the searcher: their
+/// automata are expensive to compile and to match against the term dictionaries of the splits.
+pub(crate) fn check_query_limits(
+    query_ast: &QueryAst,
+    searcher_config: &SearcherConfig,
+) -> crate::Result<()> {
+    let mut automaton_limits_checker = AutomatonLimitsChecker { searcher_config };
+    automaton_limits_checker
+        .visit(query_ast)
+        .map_err(SearchError::InvalidQuery)
+}
+
+struct AutomatonLimitsChecker<'a> {
+    searcher_config: &'a SearcherConfig,
+}
+
+impl<'a> QueryAstVisitor<'a> for AutomatonLimitsChecker<'_> {
+    type Err = String;
+
+    fn visit_regex(&mut self, regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        let max_regex_length = self.searcher_config.max_regex_length;
+        let regex_length = regex_query.regex.chars().count();
+
+        if regex_length > max_regex_length {
+            return Err(format!(
+                "the regex of the query on field `{}` is {regex_length} characters long, which \
+                 exceeds the limit of {max_regex_length} characters",
+                regex_query.field
+            ));
+        }
+        regex_query
+            .validate_automaton(self.searcher_config.max_regex_automaton_states)
+            .map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::{qast_helper, BoolQuery};
+
+    use super::*;
+
+    fn regex_query(regex: &str) -> QueryAst {
+        RegexQuery {
+            field: "body".to_string(),
+            regex: regex.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_check_query_limits() {
+        let searcher_config = SearcherConfig {
+            max_regex_length: 20,
+            max_regex_automaton_states: 100,
+            ..Default::default()
+        };
+        let query_ast = qast_helper("body:hello", &[]);
+        check_query_limits(&query_ast, &searcher_config).unwrap();
+
+        let query_ast = regex_query("b[aeiou]nd");
+        check_query_limits(&query_ast, &searcher_config).unwrap();
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![regex_query("b[aeiou]nd"), regex_query("a{1,10}b{1,10}c")],
+            ..Default::default()
+        }
+        .into();
+        let search_error = check_query_limits(&query_ast, &searcher_config).unwrap_err();
+        let SearchError::InvalidQuery(message) = search_error else {
+            panic!("expected invalid query error, got `{search_error:?}`");
+        };
+        assert!(message.contains("exceeds the limit of 20 characters"));
+
+        let query_ast = regex_query("[a-z]{1,200}");
+        let search_error = check_query_limits(&query_ast, &searcher_config).unwrap_err();
+        let SearchError::InvalidQuery(message) = search_error else {
+            panic!("expected invalid query error, got `{search_error:?}`");
+        };
+        assert!(message.contains("exceeds the limit of 100 states"));
+    }
+}
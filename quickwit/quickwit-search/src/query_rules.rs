@@ -0,0 +1,300 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_config::{BlockedQueryKind, BlockedQueryPattern, QueryRules};
+use quickwit_query::query_ast::{
+    query_ast_from_user_text, BoolQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor,
+    WildcardQuery,
+};
+use time::OffsetDateTime;
+
+use crate::SearchError;
+
+/// Applies the query rules of an index to a resolved query AST: the query is rejected if it
+/// matches one of the blocked patterns, otherwise the filters of the index are added to it.
+pub(crate) fn apply_query_rules(
+    index_id: &str,
+    query_rules: &QueryRules,
+    query_ast: QueryAst,
+    default_search_fields: &[String],
+) -> crate::Result<QueryAst> {
+    let mut blocked_query_finder = BlockedQueryFinder {
+        blocked_queries: &query_rules.blocked_queries,
+    };
+    blocked_query_finder
+        .visit(&query_ast)
+        .map_err(|reason| SearchError::QueryRejected {
+            index_id: index_id.to_string(),
+            reason,
+        })?;
+
+    if query_rules.filters.is_empty() {
+        return Ok(query_ast);
+    }
+    let mut filters = Vec::with_capacity(query_rules.filters.len());
+
+    for filter in &query_rules.filters {
+        let filter_ast = query_ast_from_user_text(filter, None)
+            .parse_user_query(default_search_fields)
+            .map_err(|error| {
+                SearchError::Internal(format!(
+                    "failed to parse filter `{filter}` of the query rules of index `{index_id}`: \
+                     {error}"
+                ))
+            })?;
+        filters.push(filter_ast);
+    }
+    let query_ast = BoolQuery {
+        must: vec![query_ast],
+        filter: filters,
+        ..Default::default()
+    }
+    .into();
+    Ok(query_ast)
+}
+
+/// Narrows down the `[start_timestamp..end_timestamp)` time range of a query so that it does not
+/// exceed `max_time_range`, keeping the most recent part of the range. An unbounded end is
+/// interpreted as now.
+pub(crate) fn cap_time_range(
+    max_time_range: Duration,
+    start_timestamp: &mut Option<i64>,
+    end_timestamp: Option<i64>,
+) {
+    let end_timestamp = end_timestamp.unwrap_or_else(|| OffsetDateTime::now_utc().unix_timestamp());
+    let min_start_timestamp = end_timestamp.saturating_sub(max_time_range.as_secs() as i64);
+
+    *start_timestamp = Some(
+        start_timestamp.map_or(min_start_timestamp, |start_timestamp| {
+            start_timestamp.max(min_start_timestamp)
+        }),
+    );
+}
+
+/// Visits a query AST and returns the reason for rejecting it as an error if it contains a query
+/// matching one of the blocked patterns.
+struct BlockedQueryFinder<'a> {
+    blocked_queries: &'a [BlockedQueryPattern],
+}
+
+impl BlockedQueryFinder<'_> {
+    fn check(&self, kind: BlockedQueryKind, field_opt: Option<&str>) -> Result<(), String> {
+        let is_blocked = self.blocked_queries.iter().any(|blocked_query| {
+            blocked_query.kind == kind
+                && (blocked_query.field.is_none() || blocked_query.field.as_deref() == field_opt)
+        });
+        if !is_blocked {
+            return Ok(());
+        }
+        let kind_str = match kind {
+            BlockedQueryKind::Wildcard => "wildcard",
+            BlockedQueryKind::LeadingWildcard => "leading wildcard",
+            BlockedQueryKind::PhrasePrefix => "phrase prefix",
+            BlockedQueryKind::MatchAll => "match all",
+        };
+        let reason = if let Some(field) = field_opt {
+            format!("{kind_str} queries on field `{field}` are not allowed")
+        } else {
+            format!("{kind_str} queries are not allowed")
+        };
+        Err(reason)
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for BlockedQueryFinder<'_> {
+    type Err = String;
+
+    fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> Result<(), Self::Err> {
+        let field = Some(wildcard_query.field.as_str());
+        self.check(BlockedQueryKind::Wildcard, field)?;
+
+        if wildcard_query.value.starts_with(['*', '?']) {
+            self.check(BlockedQueryKind::LeadingWildcard, field)?;
+        }
+        Ok(())
+    }
+
+    fn visit_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: &'a PhrasePrefixQuery,
+    ) -> Result<(), Self::Err> {
+        self.check(
+            BlockedQueryKind::PhrasePrefix,
+            Some(phrase_prefix_query.field.as_str()),
+        )
+    }
+
+    fn visit_match_all(&mut self) -> Result<(), Self::Err> {
+        self.check(BlockedQueryKind::MatchAll, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::qast_helper;
+
+    use super::*;
+
+    fn blocked_query(kind: BlockedQueryKind, field: Option<&str>) -> BlockedQueryPattern {
+        BlockedQueryPattern {
+            kind,
+            field: field.map(ToString::to_string),
+        }
+    }
+
+    fn wildcard_query(field: &str, value: &str) -> QueryAst {
+        WildcardQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_apply_query_rules_blocked_queries() {
+        let default_search_fields = ["body".to_string()];
+        let query_rules = QueryRules {
+            blocked_queries: vec![
+                blocked_query(BlockedQueryKind::LeadingWildcard, Some("body")),
+                blocked_query(BlockedQueryKind::PhrasePrefix, None),
+                blocked_query(BlockedQueryKind::MatchAll, None),
+            ],
+            ..Default::default()
+        };
+        let query_ast = wildcard_query("body", "err*");
+        let query_ast_rewritten = apply_query_rules(
+            "test-index",
+            &query_rules,
+            query_ast.clone(),
+            &default_search_fields,
+        )
+        .unwrap();
+        assert_eq!(query_ast_rewritten, query_ast);
+
+        let query_ast = wildcard_query("owner", "*rror");
+        apply_query_rules(
+            "test-index",
+            &query_rules,
+            query_ast,
+            &default_search_fields,
+        )
+        .unwrap();
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![qast_helper("owner:foo", &[])],
+            filter: vec![wildcard_query("body", "*rror")],
+            ..Default::default()
+        }
+        .into();
+        let search_error = apply_query_rules(
+            "test-index",
+            &query_rules,
+            query_ast,
+            &default_search_fields,
+        )
+        .unwrap_err();
+        assert_eq!(
+            search_error.to_string(),
+            "query rejected by the query rules of index `test-index`: leading wildcard queries on \
+             field `body` are not allowed"
+        );
+
+        let query_ast = qast_helper(r#"owner:"foo ba"*"#, &[]);
+        let search_error = apply_query_rules(
+            "test-index",
+            &query_rules,
+            query_ast,
+            &default_search_fields,
+        )
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::QueryRejected { .. }));
+
+        let query_ast = qast_helper("*", &[]);
+        let search_error = apply_query_rules(
+            "test-index",
+            &query_rules,
+            query_ast,
+            &default_search_fields,
+        )
+        .unwrap_err();
+        assert_eq!(
+            search_error.to_string(),
+            "query rejected by the query rules of index `test-index`: match all queries are not \
+             allowed"
+        );
+    }
+
+    #[test]
+    fn test_apply_query_rules_filters() {
+        let default_search_fields = ["body".to_string()];
+        let query_rules = QueryRules {
+            filters: vec!["owner:acme".to_string()],
+            ..Default::default()
+        };
+        let query_ast = qast_helper("error", &["body"]);
+        let query_ast_rewritten = apply_query_rules(
+            "test-index",
+            &query_rules,
+            query_ast.clone(),
+            &default_search_fields,
+        )
+        .unwrap();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![query_ast],
+            filter: vec![qast_helper("owner:acme", &[])],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(query_ast_rewritten, expected_query_ast);
+
+        // The filter requires a default search field.
+        let query_rules = QueryRules {
+            filters: vec!["acme".to_string()],
+            ..Default::default()
+        };
+        let query_ast = qast_helper("error", &["body"]);
+        let search_error =
+            apply_query_rules("test-index", &query_rules, query_ast, &[]).unwrap_err();
+        assert!(matches!(search_error, SearchError::Internal(_)));
+    }
+
+    #[test]
+    fn test_cap_time_range() {
+        let max_time_range = Duration::from_secs(3_600);
+
+        let mut start_timestamp = Some(1_000);
+        cap_time_range(max_time_range, &mut start_timestamp, Some(10_000));
+        assert_eq!(start_timestamp, Some(6_400));
+
+        let mut start_timestamp = Some(8_000);
+        cap_time_range(max_time_range, &mut start_timestamp, Some(10_000));
+        assert_eq!(start_timestamp, Some(8_000));
+
+        let mut start_timestamp = None;
+        cap_time_range(max_time_range, &mut start_timestamp, Some(10_000));
+        assert_eq!(start_timestamp, Some(6_400));
+
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let mut start_timestamp = None;
+        cap_time_range(max_time_range, &mut start_timestamp, None);
+        assert!(start_timestamp.unwrap() >= now_timestamp - 3_600);
+    }
+}
@@ -0,0 +1,516 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use quickwit_common::rate_limited_warn;
+use quickwit_config::{RerankerConfig, RerankerFailurePolicy, WasmPluginConfig};
+use quickwit_proto::search::{Hit, SearchRequest};
+use quickwit_query::query_ast::{
+    FullTextQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, TermQuery,
+};
+use quickwit_wasm::{load_wasm_plugin, WasmPlugin, WasmPluginInstance};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::metrics::SEARCH_METRICS;
+use crate::SearchError;
+
+/// Client of the external scoring service, abstracted away for testing and so that other scorers
+/// can be plugged in.
+#[async_trait]
+pub(crate) trait ScoreHitsClient: Send + Sync + 'static {
+    /// Returns one score per document, in the same order. Documents without a score are filtered
+    /// out.
+    async fn score_hits(
+        &self,
+        query: &str,
+        documents: Vec<JsonValue>,
+    ) -> anyhow::Result<Vec<Option<f64>>>;
+}
+
+#[derive(Serialize)]
+struct ScoreHitsRequest<'a> {
+    query: &'a str,
+    documents: Vec<JsonValue>,
+}
+
+/// Scoring services either return the list of scores directly or wrap it in an object. A `null`
+/// score filters out the document.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScoreHitsResponse {
+    Scores(Vec<Option<f64>>),
+    Object { scores: Vec<Option<f64>> },
+}
+
+impl ScoreHitsResponse {
+    fn into_scores(self) -> Vec<Option<f64>> {
+        match self {
+            ScoreHitsResponse::Scores(scores) => scores,
+            ScoreHitsResponse::Object { scores } => scores,
+        }
+    }
+}
+
+/// The HTTP client is shared by all the indexes, the timeout is set on each request instead.
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+/// Calls a scoring service over HTTP: the query and the documents are posted as
+/// `{"query": "...", "documents": [...]}` and the service returns one score per document, in the
+/// same order.
+struct HttpScoreHitsClient {
+    endpoint: String,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl ScoreHitsClient for HttpScoreHitsClient {
+    async fn score_hits(
+        &self,
+        query: &str,
+        documents: Vec<JsonValue>,
+    ) -> anyhow::Result<Vec<Option<f64>>> {
+        let request = ScoreHitsRequest { query, documents };
+        let response = HTTP_CLIENT
+            .post(&self.endpoint)
+            .timeout(self.timeout)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<ScoreHitsResponse>()
+            .await
+            .context("scoring service returned an invalid response")?;
+        Ok(response.into_scores())
+    }
+}
+
+/// Name of the function scoring the hits exported by the WASM plugins. It takes the same input
+/// as the scoring services and returns the same output.
+const WASM_SCORE_HITS_FUNCTION_NAME: &str = "score_hits";
+
+/// The WASM plugins are compiled once per process and shared by all the searches.
+static WASM_HIT_SCORERS: Lazy<Mutex<HashMap<WasmPluginConfig, Arc<WasmHitScorer>>>> =
+    Lazy::new(Mutex::default);
+
+/// A compiled WASM plugin along with a pool of instances. An instance serves one call at a time.
+struct WasmHitScorer {
+    plugin: WasmPlugin,
+    instance_pool: Mutex<Vec<WasmPluginInstance>>,
+}
+
+impl WasmHitScorer {
+    /// Returns the scorer of a plugin, compiling the plugin on first use. Plugins failing to load
+    /// are not cached, so that they are loaded again on the next search.
+    fn get_or_load(wasm_plugin_config: &WasmPluginConfig) -> anyhow::Result<Arc<Self>> {
+        let mut wasm_hit_scorers = WASM_HIT_SCORERS
+            .lock()
+            .expect("lock should not be poisoned");
+
+        if let Some(wasm_hit_scorer) = wasm_hit_scorers.get(wasm_plugin_config) {
+            return Ok(wasm_hit_scorer.clone());
+        }
+        let plugin = load_wasm_plugin(wasm_plugin_config)?;
+        let wasm_hit_scorer = Arc::new(Self {
+            plugin,
+            instance_pool: Mutex::default(),
+        });
+        wasm_hit_scorers.insert(wasm_plugin_config.clone(), wasm_hit_scorer.clone());
+        Ok(wasm_hit_scorer)
+    }
+
+    fn score_hits(&self, request: &ScoreHitsRequest) -> anyhow::Result<Vec<Option<f64>>> {
+        let instance_opt = self
+            .instance_pool
+            .lock()
+            .expect("lock should not be poisoned")
+            .pop();
+        let mut instance = match instance_opt {
+            Some(instance) => instance,
+            None => self.plugin.instantiate()?,
+        };
+        let call_result =
+            instance.call_json::<_, ScoreHitsResponse>(WASM_SCORE_HITS_FUNCTION_NAME, request);
+        self.instance_pool
+            .lock()
+            .expect("lock should not be poisoned")
+            .push(instance);
+        let response = call_result?.context("WASM plugin returned no scores")?;
+        Ok(response.into_scores())
+    }
+}
+
+/// Scores the hits with a WASM plugin, on the search thread pool. The plugin is sandboxed and its
+/// calls are bounded by its fuel, so the timeout of the reranker does not apply.
+struct WasmScoreHitsClient {
+    wasm_plugin_config: WasmPluginConfig,
+}
+
+#[async_trait]
+impl ScoreHitsClient for WasmScoreHitsClient {
+    async fn score_hits(
+        &self,
+        query: &str,
+        documents: Vec<JsonValue>,
+    ) -> anyhow::Result<Vec<Option<f64>>> {
+        let wasm_plugin_config = self.wasm_plugin_config.clone();
+        let query = query.to_string();
+
+        crate::run_cpu_intensive(move || {
+            let wasm_hit_scorer = WasmHitScorer::get_or_load(&wasm_plugin_config)?;
+            let request = ScoreHitsRequest {
+                query: &query,
+                documents,
+            };
+            wasm_hit_scorer.score_hits(&request)
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("WASM plugin panicked"))?
+    }
+}
+
+/// Re-ranking stage of the root search. The `top_n` first hits of a search sorted by relevance are
+/// sent to the scoring service and reordered by decreasing score. The hits ranked below `top_n`
+/// keep their original order, and the hits the scoring service returns no score for are removed.
+/// When the scoring service fails, the hits are either returned in their original order or the
+/// search fails, depending on the failure policy.
+pub(crate) struct HitReranker {
+    client: Arc<dyn ScoreHitsClient>,
+    top_n: usize,
+    fields: Vec<String>,
+    on_failure: RerankerFailurePolicy,
+}
+
+impl HitReranker {
+    pub fn from_reranker_config(reranker_config: &RerankerConfig) -> Self {
+        let client: Arc<dyn ScoreHitsClient> =
+            if let Some(wasm_plugin_config) = &reranker_config.wasm_plugin {
+                Arc::new(WasmScoreHitsClient {
+                    wasm_plugin_config: wasm_plugin_config.clone(),
+                })
+            } else {
+                Arc::new(HttpScoreHitsClient {
+                    endpoint: reranker_config.endpoint.clone(),
+                    timeout: reranker_config.timeout(),
+                })
+            };
+        Self::new(client, reranker_config)
+    }
+
+    fn new(client: Arc<dyn ScoreHitsClient>, reranker_config: &RerankerConfig) -> Self {
+        Self {
+            client,
+            top_n: reranker_config.top_n,
+            fields: reranker_config.fields.clone(),
+            on_failure: reranker_config.on_failure,
+        }
+    }
+
+    /// Returns the number of hits the root search must retrieve to serve the request: the hits
+    /// re-ranked are always the `top_n` first ones, whatever the requested page.
+    pub fn num_hits_to_retrieve(&self, search_request: &SearchRequest) -> u64 {
+        (search_request.start_offset + search_request.max_hits).max(self.top_n as u64)
+    }
+
+    /// Reorders the hits in place. See [`query_text_for_reranking`] for the query.
+    pub async fn rerank_hits(&self, query: &str, hits: &mut Vec<Hit>) -> crate::Result<()> {
+        let Err(error) = self.rerank_hits_inner(query, hits).await else {
+            return Ok(());
+        };
+        SEARCH_METRICS.rerank_failures_total.inc();
+
+        if self.on_failure == RerankerFailurePolicy::Fail {
+            return Err(SearchError::Internal(format!(
+                "failed to re-rank hits: {error:#}"
+            )));
+        }
+        rate_limited_warn!(
+            limit_per_min = 10,
+            "failed to re-rank hits, returning them in their original order: {error:#}"
+        );
+        Ok(())
+    }
+
+    async fn rerank_hits_inner(&self, query: &str, hits: &mut Vec<Hit>) -> anyhow::Result<()> {
+        let num_candidates = self.top_n.min(hits.len());
+
+        if num_candidates < 2 {
+            return Ok(());
+        }
+        let documents = hits[..num_candidates]
+            .iter()
+            .map(|hit| self.document_for_reranking(hit))
+            .collect::<anyhow::Result<Vec<JsonValue>>>()?;
+        let scores = self.client.score_hits(query, documents).await?;
+        ensure!(
+            scores.len() == num_candidates,
+            "scoring service returned {} scores for {num_candidates} documents",
+            scores.len()
+        );
+        let mut scored_hits: Vec<(f64, Hit)> = scores
+            .into_iter()
+            .zip(hits.drain(..num_candidates))
+            .filter_map(|(score_opt, hit)| score_opt.map(|score| (score, hit)))
+            .collect();
+        // The sort is stable: hits with equal scores keep their original order.
+        scored_hits.sort_by(|(left_score, _), (right_score, _)| right_score.total_cmp(left_score));
+        hits.splice(0..0, scored_hits.into_iter().map(|(_, hit)| hit));
+        Ok(())
+    }
+
+    /// Returns the document of a hit restricted to the configured fields, if any.
+    fn document_for_reranking(&self, hit: &Hit) -> anyhow::Result<JsonValue> {
+        let document: JsonValue =
+            serde_json::from_str(&hit.json).context("failed to deserialize hit")?;
+
+        if self.fields.is_empty() {
+            return Ok(document);
+        }
+        let JsonValue::Object(mut json_obj) = document else {
+            return Ok(document);
+        };
+        let document_fields: JsonMap<String, JsonValue> = self
+            .fields
+            .iter()
+            .filter_map(|field| json_obj.remove_entry(field))
+            .collect();
+        Ok(JsonValue::Object(document_fields))
+    }
+}
+
+/// Only the searches sorted by relevance and not paginated with `search_after` can be re-ranked.
+pub(crate) fn is_rerankable(search_request: &SearchRequest) -> bool {
+    search_request.max_hits > 0
+        && search_request.search_after.is_none()
+        && search_request
+            .sort_fields
+            .first()
+            .is_some_and(|sort_field| sort_field.field_name == "_score")
+}
+
+/// Returns the query sent to the scoring service: the texts and terms of the query, in order and
+/// deduplicated since the user query is expanded over each default search field.
+pub(crate) fn query_text_for_reranking(query_ast: &QueryAst) -> String {
+    let mut query_text_collector = QueryTextCollector::default();
+    let _: Result<(), Infallible> = query_text_collector.visit(query_ast);
+    query_text_collector.texts.join(" ")
+}
+
+#[derive(Default)]
+struct QueryTextCollector<'a> {
+    texts: Vec<&'a str>,
+}
+
+impl<'a> QueryTextCollector<'a> {
+    fn push(&mut self, text: &'a str) {
+        if !text.is_empty() && !self.texts.contains(&text) {
+            self.texts.push(text);
+        }
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for QueryTextCollector<'a> {
+    type Err = Infallible;
+
+    fn visit_term(&mut self, term_query: &'a TermQuery) -> Result<(), Self::Err> {
+        self.push(&term_query.value);
+        Ok(())
+    }
+
+    fn visit_full_text(&mut self, full_text_query: &'a FullTextQuery) -> Result<(), Self::Err> {
+        self.push(&full_text_query.text);
+        Ok(())
+    }
+
+    fn visit_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: &'a PhrasePrefixQuery,
+    ) -> Result<(), Self::Err> {
+        self.push(&phrase_prefix_query.phrase);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use quickwit_proto::search::{SortField, SortOrder};
+    use quickwit_query::query_ast::query_ast_from_user_text;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Scores a document with the value of its `score` field, filters it out if the field is
+    /// null, and fails if the query is `fail`.
+    #[derive(Default)]
+    struct MockScoreHitsClient {
+        num_calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ScoreHitsClient for MockScoreHitsClient {
+        async fn score_hits(
+            &self,
+            query: &str,
+            documents: Vec<JsonValue>,
+        ) -> anyhow::Result<Vec<Option<f64>>> {
+            self.num_calls.fetch_add(1, Ordering::Relaxed);
+
+            if query == "fail" {
+                anyhow::bail!("scoring service unavailable");
+            }
+            let scores = documents
+                .iter()
+                .map(|document| document["score"].as_f64())
+                .collect();
+            Ok(scores)
+        }
+    }
+
+    fn hits_for_test(scores: &[f64]) -> Vec<Hit> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(doc_id, score)| Hit {
+                json: json!({"doc_id": doc_id, "score": score}).to_string(),
+                index_id: "test-index".to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn doc_ids(hits: &[Hit]) -> Vec<u64> {
+        hits.iter()
+            .map(|hit| {
+                let document: JsonValue = serde_json::from_str(&hit.json).unwrap();
+                document["doc_id"].as_u64().unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_hit_reranker_reorders_top_n_hits() {
+        let client = Arc::new(MockScoreHitsClient::default());
+        let reranker_config = RerankerConfig {
+            top_n: 4,
+            ..RerankerConfig::for_test("http://reranker:8080")
+        };
+        let hit_reranker = HitReranker::new(client.clone(), &reranker_config);
+
+        let mut hits = hits_for_test(&[0.1, 0.5, 0.2, 0.5, 0.9]);
+        hit_reranker.rerank_hits("foo", &mut hits).await.unwrap();
+        // The fifth hit is not re-ranked, ties keep their original order.
+        assert_eq!(doc_ids(&hits), [1, 3, 2, 0, 4]);
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_hit_reranker_filters_out_unscored_hits() {
+        let client = Arc::new(MockScoreHitsClient::default());
+        let reranker_config = RerankerConfig::for_test("http://reranker:8080");
+        let hit_reranker = HitReranker::new(client, &reranker_config);
+
+        let mut hits = hits_for_test(&[0.1, 0.5, 0.2]);
+        hits[1].json = json!({"doc_id": 1, "score": null}).to_string();
+        hit_reranker.rerank_hits("foo", &mut hits).await.unwrap();
+        assert_eq!(doc_ids(&hits), [2, 0]);
+    }
+
+    #[test]
+    fn test_score_hits_response_deserialization() {
+        let response: ScoreHitsResponse = serde_json::from_str("[1.0, null]").unwrap();
+        assert_eq!(response.into_scores(), [Some(1.0), None]);
+
+        let response: ScoreHitsResponse =
+            serde_json::from_str(r#"{"scores": [null, 2.0]}"#).unwrap();
+        assert_eq!(response.into_scores(), [None, Some(2.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_hit_reranker_failure_policy() {
+        let client = Arc::new(MockScoreHitsClient::default());
+        let mut reranker_config = RerankerConfig::for_test("http://reranker:8080");
+        let hit_reranker = HitReranker::new(client.clone(), &reranker_config);
+
+        let mut hits = hits_for_test(&[0.1, 0.5]);
+        hit_reranker.rerank_hits("fail", &mut hits).await.unwrap();
+        assert_eq!(doc_ids(&hits), [0, 1]);
+
+        reranker_config.on_failure = RerankerFailurePolicy::Fail;
+        let hit_reranker = HitReranker::new(client.clone(), &reranker_config);
+
+        let error = hit_reranker
+            .rerank_hits("fail", &mut hits)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("scoring service unavailable"));
+        assert_eq!(client.num_calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_hit_reranker_document_for_reranking() {
+        let reranker_config = RerankerConfig {
+            fields: vec!["title".to_string(), "missing".to_string()],
+            ..RerankerConfig::for_test("http://reranker:8080")
+        };
+        let hit_reranker =
+            HitReranker::new(Arc::new(MockScoreHitsClient::default()), &reranker_config);
+        let hit = Hit {
+            json: json!({"title": "foo", "body": "bar"}).to_string(),
+            ..Default::default()
+        };
+        let document = hit_reranker.document_for_reranking(&hit).unwrap();
+        assert_eq!(document, json!({"title": "foo"}));
+    }
+
+    #[test]
+    fn test_is_rerankable() {
+        let mut search_request = SearchRequest {
+            max_hits: 10,
+            ..Default::default()
+        };
+        assert!(!is_rerankable(&search_request));
+
+        search_request.sort_fields = vec![SortField {
+            field_name: "_score".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+        }];
+        assert!(is_rerankable(&search_request));
+
+        search_request.max_hits = 0;
+        assert!(!is_rerankable(&search_request));
+    }
+
+    #[test]
+    fn test_query_text_for_reranking() {
+        let query_ast = query_ast_from_user_text("foo AND \"bar baz\" AND title:qux", None)
+            .parse_user_query(&["body".to_string(), "title".to_string()])
+            .unwrap();
+        assert_eq!(query_text_for_reranking(&query_ast), "foo bar baz qux");
+    }
+}
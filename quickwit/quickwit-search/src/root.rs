@@ -18,51 +18,75 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
+use std::pin::pin;
 use std::time::Duration;
 
 use anyhow::Context;
 use futures::future::try_join_all;
+use futures::stream::FuturesUnordered;
+use futures::{Stream, StreamExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_common::pretty::PrettySample;
-use quickwit_common::shared_consts::{DELETION_GRACE_PERIOD, SCROLL_BATCH_LEN};
+use quickwit_common::shared_consts::{AUDIT_LOG_TARGET, DELETION_GRACE_PERIOD, SCROLL_BATCH_LEN};
 use quickwit_common::uri::Uri;
-use quickwit_config::build_doc_mapper;
+use quickwit_config::{build_doc_mapper, RerankerConfig};
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_doc_mapper::DYNAMIC_FIELD_NAME;
-use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata};
+use quickwit_metastore::{
+    IndexMetadata, ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    MetastoreServiceStreamSplitsExt, SplitMetadata,
+};
 use quickwit_proto::metastore::{
-    ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
+    ListIndexesMetadataRequest, ListSplitsRequest, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::search::{
-    FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafSearchRequest, LeafSearchResponse,
-    PartialHit, SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat, SortField,
-    SortValue, SplitIdAndFooterOffsets,
+    FetchDocsRequest, FetchDocsResponse, Hit, IndexSearchFailure, LeafHit, LeafSearchRequest,
+    LeafSearchResponse, PartialHit, SearchRequest, SearchResponse, SnippetRequest,
+    SortDatetimeFormat, SortField, SortValue, SplitIdAndFooterOffsets, SplitSearchProfile,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_query::query_ast::{
-    BoolQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
+    BoolQuery, JoinQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
 };
 use serde::{Deserialize, Serialize};
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::collector::Collector;
 use tantivy::schema::{FieldEntry, FieldType, Schema};
+use tantivy::time::OffsetDateTime;
 use tantivy::TantivyError;
 use tracing::{debug, error, info, info_span, instrument};
 
+use crate::cardinality_collector::{find_unsupported_cardinality_aggregation, Cardinality};
 use crate::cluster_client::ClusterClient;
 use crate::collector::{make_merge_collector, QuickwitAggregations};
+use crate::external_splits::materialize_external_splits;
+use crate::field_coverage_collector::FieldCoverage;
 use crate::find_trace_ids_collector::Span;
+use crate::lookup_table::apply_lookups;
+use crate::percentiles_collector::Percentiles;
+use crate::point_in_time::load_point_in_time;
+use crate::post_aggregation::push_down_post_aggregations;
+use crate::profile::build_search_profile;
+use crate::query_access_stats::is_scan_query;
+use crate::query_rules::{apply_query_rules, cap_time_range};
+use crate::rerank::{is_rerankable, query_text_for_reranking, HitReranker};
 use crate::scroll_context::{ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_job_placer::{group_by, group_jobs_by_index_id, Job};
+use crate::search_response_cache::SearchResponseCache;
 use crate::service::SearcherContext;
 use crate::{
-    extract_split_and_footer_offsets, list_relevant_splits, SearchError, SearchJobPlacer,
-    SearchServiceClient,
+    extract_split_and_footer_offsets, list_held_splits, list_relevant_splits,
+    relevant_splits_query, SearchError, SearchJobPlacer, SearchServiceClient,
 };
 
-/// Maximum accepted scroll TTL.
-const MAX_SCROLL_TTL: Duration = Duration::from_secs(DELETION_GRACE_PERIOD.as_secs() - 60 * 2);
+/// Number of splits listed per metastore call when the root planner pages through the relevant
+/// splits.
+const LIST_SPLITS_PAGE_SIZE: usize = 10_000;
+
+/// Maximum accepted scroll TTL and point in time keep alive.
+pub(crate) const MAX_SCROLL_TTL: Duration =
+    Duration::from_secs(DELETION_GRACE_PERIOD.as_secs() - 60 * 2);
 
 const SORT_DOC_FIELD_NAMES: &[&str] = &["_shard_doc", "_doc"];
 
@@ -156,15 +180,28 @@ struct RequestMetadata {
     query_ast_resolved: QueryAst,
     indexes_meta_for_leaf_search: IndexesMetasForLeafSearch,
     sort_fields_is_datetime: HashMap<String, bool>,
+    /// The most restrictive time range cap set by the query rules of the indexes, if any.
+    max_time_range_opt: Option<Duration>,
+    /// The indexes skipped because the request could not be validated against their doc mapping.
+    failed_indexes: Vec<IndexSearchFailure>,
+    /// The reranker shared by all the indexes, if any.
+    reranker_config_opt: Option<RerankerConfig>,
 }
 
-/// Validates request against each index's doc mapper and ensures that:
+/// Validates request against each index's doc mapper, applies the query rules of each index, and
+/// ensures that:
 /// - timestamp fields (if any) are equal across indexes.
 /// - resolved query ASTs are the same across indexes.
 /// - if a sort field is of type datetime, it must be a datetime field on all indexes. This
 ///   contraint come from the need to support datetime formatting on sort values.
 /// Returns the timestamp field, the resolved query AST and the indexes metadatas
 /// needed for leaf search requests.
+///
+/// The user query is resolved against the union of the default search fields of the indexes, and
+/// it is valid as long as it can be built against the doc mapping of at least one index. Indexes
+/// whose doc mapping cannot serve the request are skipped and reported as failed indexes, unless
+/// all of them fail, in which case the first error is returned.
+///
 /// Note: the requirements on timestamp fields and resolved query ASTs can be lifted
 /// but it adds complexity that does not seem needed right now.
 fn validate_request_and_build_metadata(
@@ -182,27 +219,87 @@ fn validate_request_and_build_metadata(
     let mut query_ast_resolved_opt: Option<QueryAst> = None;
     let mut timestamp_field_opt: Option<String> = None;
     let mut sort_fields_is_datetime: HashMap<String, bool> = HashMap::new();
+    let mut max_time_range_opt: Option<Duration> = None;
+    let mut failed_indexes: Vec<IndexSearchFailure> = Vec::new();
+    let mut first_error_opt: Option<SearchError> = None;
+
+    let mut searchable_indexes = Vec::with_capacity(indexes_metadata.len());
 
     for index_metadata in indexes_metadata {
-        let doc_mapper = build_doc_mapper(
+        let doc_mapper_res = build_doc_mapper(
             &index_metadata.index_config.doc_mapping,
             &index_metadata.index_config.search_settings,
         )
-        .map_err(|err| {
-            SearchError::Internal(format!("failed to build doc mapper. cause: {err}"))
-        })?;
-        let query_ast_resolved_for_index = query_ast
-            .clone()
-            .parse_user_query(doc_mapper.default_search_fields())
-            // We convert the error to return a 400 to the user (and not a 500).
-            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        .map_err(|err| SearchError::Internal(format!("failed to build doc mapper. cause: {err}")))
+        .and_then(|doc_mapper| {
+            // Validate request against the current index schema.
+            validate_request(
+                &doc_mapper.schema(),
+                &doc_mapper.timestamp_field_name(),
+                search_request,
+            )?;
+            Ok(doc_mapper)
+        });
+        match doc_mapper_res {
+            Ok(doc_mapper) => searchable_indexes.push((index_metadata, doc_mapper)),
+            Err(error) => {
+                failed_indexes.push(IndexSearchFailure {
+                    index_id: index_metadata.index_id().to_string(),
+                    error: error.to_string(),
+                });
+                first_error_opt.get_or_insert(error);
+            }
+        }
+    }
+    if searchable_indexes.is_empty() {
+        return Err(first_error_opt.unwrap_or_else(|| {
+            SearchError::Internal("no index to search. this should never happen".to_string())
+        }));
+    }
+    // The default search fields of the indexes are merged so that the user query resolves to the
+    // same query AST for every index.
+    let mut default_search_fields: Vec<String> = Vec::new();
+    for (_, doc_mapper) in &searchable_indexes {
+        for default_search_field in doc_mapper.default_search_fields() {
+            if !default_search_fields.contains(default_search_field) {
+                default_search_fields.push(default_search_field.clone());
+            }
+        }
+    }
+    // The hits are only re-ranked when all the indexes share the same reranker.
+    let reranker_config_opt: Option<RerankerConfig> = searchable_indexes
+        .iter()
+        .map(|(index_metadata, _)| &index_metadata.index_config.search_settings.reranker)
+        .all_equal_value()
+        .ok()
+        .and_then(Option::clone);
+    let query_ast_resolved = query_ast
+        .parse_user_query(&default_search_fields)
+        // We convert the error to return a 400 to the user (and not a 500).
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let mut query_error_opt: Option<SearchError> = None;
+    let mut is_query_valid = false;
+
+    for (index_metadata, doc_mapper) in searchable_indexes {
+        let query_rules = &index_metadata.index_config.search_settings.query_rules;
+        let query_ast_resolved_for_index = apply_query_rules(
+            index_metadata.index_id(),
+            query_rules,
+            query_ast_resolved.clone(),
+            &default_search_fields,
+        )?;
+        if let Some(max_time_range) = query_rules.max_time_range()? {
+            max_time_range_opt = Some(
+                max_time_range_opt.map_or(max_time_range, |current| current.min(max_time_range)),
+            );
+        }
 
         // Validate uniqueness of resolved query AST.
         if let Some(query_ast_resolved) = &query_ast_resolved_opt {
             if query_ast_resolved != &query_ast_resolved_for_index {
                 return Err(SearchError::InvalidQuery(
-                    "resolved query ASTs must be the same across indexes. resolving queries with \
-                     different default fields are different between indexes is not supported"
+                    "resolved query ASTs must be the same across indexes. searching indexes with \
+                     different query rules is not supported"
                         .to_string(),
                 ));
             }
@@ -225,10 +322,7 @@ fn validate_request_and_build_metadata(
                 _ => {}
             }
         }
-
-        // Validate request against the current index schema.
         let schema = doc_mapper.schema();
-        validate_request(&schema, &doc_mapper.timestamp_field_name(), search_request)?;
 
         validate_sort_field_types(
             &schema,
@@ -236,8 +330,14 @@ fn validate_request_and_build_metadata(
             &mut sort_fields_is_datetime,
         )?;
 
-        // Validates the query by effectively building it against the current schema.
-        doc_mapper.query(doc_mapper.schema(), &query_ast_resolved_for_index, true)?;
+        // Validates the query by effectively building it against the current schema. The fields
+        // of the query only need to exist in one of the indexes.
+        match doc_mapper.query(schema, &query_ast_resolved_for_index, true) {
+            Ok(_) => is_query_valid = true,
+            Err(error) => {
+                query_error_opt.get_or_insert(error.into());
+            }
+        }
 
         let index_metadata_for_leaf_search = IndexMetasForLeafSearch {
             index_uri: index_metadata.index_uri().clone(),
@@ -250,6 +350,11 @@ fn validate_request_and_build_metadata(
             index_metadata_for_leaf_search,
         );
     }
+    if !is_query_valid {
+        if let Some(query_error) = query_error_opt {
+            return Err(query_error);
+        }
+    }
 
     let query_ast_resolved = query_ast_resolved_opt.ok_or_else(|| {
         SearchError::Internal(
@@ -262,6 +367,9 @@ fn validate_request_and_build_metadata(
         query_ast_resolved,
         indexes_meta_for_leaf_search,
         sort_fields_is_datetime,
+        max_time_range_opt,
+        failed_indexes,
+        reranker_config_opt,
     })
 }
 
@@ -348,12 +456,23 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         aggregation_request: None,
         // We remove the snippet fields. This feature is not supported for scroll requests.
         snippet_fields: Vec::new(),
+        highlight: None,
         // We remove the scroll ttl parameter. It is irrelevant to process later request
         scroll_ttl_secs: None,
         search_after: None,
         // request is simplified after initial query, and we cache the hit count, so we don't need
         // to recompute it afterward.
         count_hits: quickwit_proto::search::CountHits::Underestimate as i32,
+        include_held_splits: req.include_held_splits,
+        legal_hold_token: None,
+        bypass_cache: req.bypass_cache,
+        // Scroll responses are never served from the search response cache.
+        max_staleness_secs: None,
+        tenant_id: req.tenant_id.clone(),
+        priority: req.priority,
+        profile: false,
+        pit_id: req.pit_id.clone(),
+        lookups: req.lookups.clone(),
     })
 }
 
@@ -458,6 +577,52 @@ fn validate_sort_by_field_type(
     Ok(())
 }
 
+/// Returns the name of a `top_hits` aggregation of the request sorted by `_score`. The top hits
+/// aggregation is computed by the aggregation framework of Tantivy, which does not score the
+/// documents it collects, so these requests are rejected rather than silently returning hits in
+/// an arbitrary order.
+fn find_top_hits_aggregation_sorted_by_score(aggregations_json: &str) -> Option<String> {
+    let aggregations: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(aggregations_json).ok()?;
+    find_top_hits_aggregation_sorted_by_score_aux(&aggregations)
+}
+
+fn find_top_hits_aggregation_sorted_by_score_aux(
+    aggregations: &serde_json::Map<String, serde_json::Value>,
+) -> Option<String> {
+    for (name, aggregation) in aggregations {
+        if let Some(sort) = aggregation
+            .get("top_hits")
+            .and_then(|top_hits| top_hits.get("sort"))
+        {
+            if sorts_by_score(sort) {
+                return Some(name.clone());
+            }
+        }
+        for sub_aggregations_key in ["aggs", "aggregations"] {
+            if let Some(serde_json::Value::Object(sub_aggregations)) =
+                aggregation.get(sub_aggregations_key)
+            {
+                if let Some(name) = find_top_hits_aggregation_sorted_by_score_aux(sub_aggregations)
+                {
+                    return Some(name);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Sorts can be written as `"_score"`, `{"_score": "desc"}`, or a list of these.
+fn sorts_by_score(sort: &serde_json::Value) -> bool {
+    match sort {
+        serde_json::Value::String(sort_field) => sort_field == "_score",
+        serde_json::Value::Object(sort_fields) => sort_fields.contains_key("_score"),
+        serde_json::Value::Array(sorts) => sorts.iter().any(sorts_by_score),
+        _ => false,
+    }
+}
+
 fn validate_request(
     schema: &Schema,
     timestamp_field_name: &Option<&str>,
@@ -474,13 +639,31 @@ fn validate_request(
     }
 
     validate_requested_snippet_fields(schema, &search_request.snippet_fields)?;
+    if let Some(highlight_request) = &search_request.highlight {
+        validate_requested_snippet_fields(schema, &highlight_request.fields)?;
+    }
 
     if let Some(agg) = search_request.aggregation_request.as_ref() {
-        let _aggs: QuickwitAggregations = serde_json::from_str(agg).map_err(|_err| {
-            let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(agg)
+        let (agg, _) = push_down_post_aggregations(agg)
+            .map_err(|err| SearchError::InvalidAggregationRequest(err.to_string()))?;
+        let _aggs: QuickwitAggregations = serde_json::from_str(&agg).map_err(|_err| {
+            if let Some(aggregation_name) = find_unsupported_cardinality_aggregation(&agg) {
+                return SearchError::InvalidAggregationRequest(format!(
+                    "cardinality aggregation `{aggregation_name}` is not supported: cardinality \
+                     aggregations cannot be nested under bucket aggregations nor combined with \
+                     other kinds of aggregations"
+                ));
+            }
+            let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(&agg)
                 .unwrap_err();
             SearchError::InvalidAggregationRequest(err.to_string())
         })?;
+        if let Some(aggregation_name) = find_top_hits_aggregation_sorted_by_score(&agg) {
+            return Err(SearchError::InvalidAggregationRequest(format!(
+                "top_hits aggregation `{aggregation_name}` cannot be sorted by `_score`: top hits \
+                 can only be sorted by fast fields"
+            )));
+        }
     };
 
     if search_request.start_offset > 10_000 {
@@ -527,10 +710,7 @@ async fn search_partial_hits_phase_with_scroll(
     if let Some(scroll_ttl) = scroll_ttl_opt {
         let max_hits = search_request.max_hits;
         // This is a scroll request.
-        //
-        // We increase max hits to add populate the scroll cache.
-        search_request.max_hits = search_request.max_hits.max(SCROLL_BATCH_LEN as u64);
-        search_request.scroll_ttl_secs = None;
+        prepare_first_scroll_search_request(&mut search_request);
         let mut leaf_search_resp = search_partial_hits_phase(
             searcher_context,
             indexes_metas_for_leaf_search,
@@ -539,39 +719,16 @@ async fn search_partial_hits_phase_with_scroll(
             cluster_client,
         )
         .await?;
-        let cached_partial_hits = leaf_search_resp.partial_hits.clone();
-        leaf_search_resp.partial_hits.truncate(max_hits as usize);
-        let last_hit = leaf_search_resp
-            .partial_hits
-            .last()
-            .cloned()
-            .unwrap_or_default();
-
-        let scroll_context_search_request =
-            simplify_search_request_for_scroll_api(&search_request)?;
-        let mut scroll_ctx = ScrollContext {
-            indexes_metas_for_leaf_search: indexes_metas_for_leaf_search.clone(),
-            split_metadatas: split_metadatas.to_vec(),
-            search_request: scroll_context_search_request,
-            total_num_hits: leaf_search_resp.num_hits,
-            max_hits_per_page: max_hits,
-            cached_partial_hits_start_offset: search_request.start_offset,
-            cached_partial_hits,
-        };
-        let scroll_key_and_start_offset: ScrollKeyAndStartOffset =
-            ScrollKeyAndStartOffset::new_with_start_offset(
-                scroll_ctx.search_request.start_offset,
-                max_hits as u32,
-                last_hit.clone(),
-            )
-            .next_page(leaf_search_resp.partial_hits.len() as u64, last_hit);
-
-        scroll_ctx.clear_cache_if_unneeded();
-        let payload: Vec<u8> = scroll_ctx.serialize();
-        let scroll_key = scroll_key_and_start_offset.scroll_key();
-        cluster_client
-            .put_kv(&scroll_key, &payload, scroll_ttl)
-            .await;
+        let scroll_key_and_start_offset = save_scroll_context(
+            indexes_metas_for_leaf_search,
+            &search_request,
+            max_hits,
+            split_metadatas,
+            &mut leaf_search_resp,
+            scroll_ttl,
+            cluster_client,
+        )
+        .await?;
         Ok((leaf_search_resp, Some(scroll_key_and_start_offset)))
     } else {
         let leaf_search_resp = search_partial_hits_phase(
@@ -586,6 +743,59 @@ async fn search_partial_hits_phase_with_scroll(
     }
 }
 
+/// Prepares the search request of the first page of a scroll.
+fn prepare_first_scroll_search_request(search_request: &mut SearchRequest) {
+    // We increase max hits to add populate the scroll cache.
+    search_request.max_hits = search_request.max_hits.max(SCROLL_BATCH_LEN as u64);
+    search_request.scroll_ttl_secs = None;
+}
+
+/// Truncates the partial hits of the first page of a scroll to `max_hits` and saves the scroll
+/// context, which caches the remaining hits and holds the splits to search for the next pages.
+async fn save_scroll_context(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    max_hits: u64,
+    split_metadatas: &[SplitMetadata],
+    leaf_search_resp: &mut LeafSearchResponse,
+    scroll_ttl: Duration,
+    cluster_client: &ClusterClient,
+) -> crate::Result<ScrollKeyAndStartOffset> {
+    let cached_partial_hits = leaf_search_resp.partial_hits.clone();
+    leaf_search_resp.partial_hits.truncate(max_hits as usize);
+    let last_hit = leaf_search_resp
+        .partial_hits
+        .last()
+        .cloned()
+        .unwrap_or_default();
+
+    let scroll_context_search_request = simplify_search_request_for_scroll_api(search_request)?;
+    let mut scroll_ctx = ScrollContext {
+        indexes_metas_for_leaf_search: indexes_metas_for_leaf_search.clone(),
+        split_metadatas: split_metadatas.to_vec(),
+        search_request: scroll_context_search_request,
+        total_num_hits: leaf_search_resp.num_hits,
+        max_hits_per_page: max_hits,
+        cached_partial_hits_start_offset: search_request.start_offset,
+        cached_partial_hits,
+    };
+    let scroll_key_and_start_offset: ScrollKeyAndStartOffset =
+        ScrollKeyAndStartOffset::new_with_start_offset(
+            scroll_ctx.search_request.start_offset,
+            max_hits as u32,
+            last_hit.clone(),
+        )
+        .next_page(leaf_search_resp.partial_hits.len() as u64, last_hit);
+
+    scroll_ctx.clear_cache_if_unneeded();
+    let payload: Vec<u8> = scroll_ctx.serialize();
+    let scroll_key = scroll_key_and_start_offset.scroll_key();
+    cluster_client
+        .put_kv(&scroll_key, &payload, scroll_ttl)
+        .await;
+    Ok(scroll_key_and_start_offset)
+}
+
 /// Check if the request is a count request without any filters, so we can just return the split
 /// metadata count.
 ///
@@ -604,7 +814,10 @@ fn is_metadata_count_request(request: &SearchRequest) -> bool {
     if request.start_timestamp.is_some() || request.end_timestamp.is_some() {
         return false;
     }
-    if request.aggregation_request.is_some() || !request.snippet_fields.is_empty() {
+    if request.aggregation_request.is_some()
+        || !request.snippet_fields.is_empty()
+        || request.highlight.is_some()
+    {
         return false;
     }
     true
@@ -620,6 +833,7 @@ fn get_count_from_metadata(split_metadatas: &[SplitMetadata]) -> Vec<LeafSearchR
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
             intermediate_aggregation_result: None,
+            split_profiles: Vec::new(),
         })
         .collect()
 }
@@ -636,26 +850,110 @@ pub(crate) async fn search_partial_hits_phase(
         if is_metadata_count_request(search_request) {
             get_count_from_metadata(split_metadatas)
         } else {
-            let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
-            let assigned_leaf_search_jobs = cluster_client
-                .search_job_placer
-                .assign_jobs(jobs, &HashSet::default())
-                .await?;
-            let mut leaf_request_tasks = Vec::new();
-            for (client, client_jobs) in assigned_leaf_search_jobs {
-                let leaf_requests = jobs_to_leaf_requests(
-                    search_request,
-                    indexes_metas_for_leaf_search,
-                    client_jobs,
-                )?;
-                for leaf_request in leaf_requests {
-                    leaf_request_tasks
-                        .push(cluster_client.leaf_search(leaf_request, client.clone()));
-                }
-            }
+            let leaf_requests = assign_leaf_search_requests(
+                indexes_metas_for_leaf_search,
+                search_request,
+                split_metadatas,
+                cluster_client,
+            )
+            .await?;
+            let leaf_request_tasks = leaf_requests
+                .into_iter()
+                .map(|(client, leaf_request)| cluster_client.leaf_search(leaf_request, client));
             try_join_all(leaf_request_tasks).await?
         };
+    merge_leaf_search_responses(searcher_context, search_request, leaf_search_responses).await
+}
+
+/// Performs the first phase of a search while the relevant splits are still being listed, page by
+/// page, from the metastore. The leaf search jobs of a page are dispatched as soon as the page is
+/// received, so that leaf searches over the first pages overlap with the listing of the following
+/// ones.
+///
+/// Returns the merged leaf search response along with all the splits that were listed.
+#[instrument(level = "debug", skip_all)]
+async fn search_partial_hits_phase_paginated(
+    searcher_context: &SearcherContext,
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    split_metadata_pages: impl Stream<Item = crate::Result<Vec<SplitMetadata>>>,
+    cluster_client: &ClusterClient,
+) -> crate::Result<(LeafSearchResponse, Vec<SplitMetadata>)> {
+    let is_metadata_count_request = is_metadata_count_request(search_request);
+
+    let mut split_metadata_pages = pin!(split_metadata_pages);
+    let mut split_metadata_pages_exhausted = false;
+    let mut split_metadatas: Vec<SplitMetadata> = Vec::new();
+
+    let mut leaf_request_tasks = FuturesUnordered::new();
+    let mut leaf_search_responses: Vec<LeafSearchResponse> = Vec::new();
+
+    loop {
+        tokio::select! {
+            page_opt = split_metadata_pages.next(), if !split_metadata_pages_exhausted => {
+                let Some(page_res) = page_opt else {
+                    split_metadata_pages_exhausted = true;
+                    continue;
+                };
+                let split_metadata_page = page_res?;
+
+                if is_metadata_count_request {
+                    leaf_search_responses.extend(get_count_from_metadata(&split_metadata_page));
+                } else if !split_metadata_page.is_empty() {
+                    let leaf_requests = assign_leaf_search_requests(
+                        indexes_metas_for_leaf_search,
+                        search_request,
+                        &split_metadata_page,
+                        cluster_client,
+                    )
+                    .await?;
+                    for (client, leaf_request) in leaf_requests {
+                        leaf_request_tasks.push(cluster_client.leaf_search(leaf_request, client));
+                    }
+                }
+                split_metadatas.extend(split_metadata_page);
+            }
+            Some(leaf_search_response_res) = leaf_request_tasks.next() => {
+                leaf_search_responses.push(leaf_search_response_res?);
+            }
+            else => break,
+        }
+    }
+    let leaf_search_response =
+        merge_leaf_search_responses(searcher_context, search_request, leaf_search_responses)
+            .await?;
+    Ok((leaf_search_response, split_metadatas))
+}
 
+/// Assigns the leaf search jobs of the given splits to the searchers of the cluster and builds the
+/// corresponding leaf requests.
+async fn assign_leaf_search_requests(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<Vec<(SearchServiceClient, LeafSearchRequest)>> {
+    let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
+    let assigned_leaf_search_jobs = cluster_client
+        .search_job_placer
+        .assign_jobs(jobs, &HashSet::default())
+        .await?;
+    let mut leaf_requests = Vec::new();
+    for (client, client_jobs) in assigned_leaf_search_jobs {
+        for leaf_request in
+            jobs_to_leaf_requests(search_request, indexes_metas_for_leaf_search, client_jobs)?
+        {
+            leaf_requests.push((client.clone(), leaf_request));
+        }
+    }
+    Ok(leaf_requests)
+}
+
+async fn merge_leaf_search_responses(
+    searcher_context: &SearcherContext,
+    search_request: &SearchRequest,
+    leaf_search_responses: Vec<LeafSearchResponse>,
+) -> crate::Result<LeafSearchResponse> {
     // Creates a collector which merges responses into one
     let merge_collector =
         make_merge_collector(search_request, &searcher_context.get_aggregation_limits())?;
@@ -690,12 +988,13 @@ pub(crate) async fn search_partial_hits_phase(
 }
 
 pub(crate) fn get_snippet_request(search_request: &SearchRequest) -> Option<SnippetRequest> {
-    if search_request.snippet_fields.is_empty() {
+    if search_request.snippet_fields.is_empty() && search_request.highlight.is_none() {
         return None;
     }
     Some(SnippetRequest {
         snippet_fields: search_request.snippet_fields.clone(),
         query_ast_resolved: search_request.query_ast.clone(),
+        highlight: search_request.highlight.clone(),
     })
 }
 
@@ -706,7 +1005,7 @@ pub(crate) async fn fetch_docs_phase(
     split_metadatas: &[SplitMetadata],
     search_request: &SearchRequest,
     cluster_client: &ClusterClient,
-) -> crate::Result<Vec<Hit>> {
+) -> crate::Result<(Vec<Hit>, Vec<SplitSearchProfile>)> {
     let snippet_request: Option<SnippetRequest> = get_snippet_request(search_request);
     let hit_order: HashMap<(String, u32, u32), usize> = partial_hits
         .iter()
@@ -735,16 +1034,21 @@ pub(crate) async fn fetch_docs_phase(
             indexes_metas_for_leaf_search,
             client_jobs,
         )?;
-        for fetch_docs_request in fetch_jobs_requests {
+        for mut fetch_docs_request in fetch_jobs_requests {
+            fetch_docs_request.profile = search_request.profile;
             fetch_docs_tasks.push(cluster_client.fetch_docs(fetch_docs_request, client.clone()));
         }
     }
     let fetch_docs_responses: Vec<FetchDocsResponse> = try_join_all(fetch_docs_tasks).await?;
 
     // Merge the fetched docs.
-    let leaf_hits = fetch_docs_responses
-        .into_iter()
-        .flat_map(|response| response.hits.into_iter());
+    let mut leaf_hits: Vec<LeafHit> = Vec::new();
+    let mut split_profiles: Vec<SplitSearchProfile> = Vec::new();
+
+    for fetch_docs_response in fetch_docs_responses {
+        leaf_hits.extend(fetch_docs_response.hits);
+        split_profiles.extend(fetch_docs_response.split_profiles);
+    }
 
     // Build map of Split ID > index ID to add the index ID to the hits.
     // Used for ES compatibility.
@@ -763,6 +1067,7 @@ pub(crate) async fn fetch_docs_phase(
     let sort_field_2_datetime_format_opt: Option<SortDatetimeFormat> =
         get_sort_field_datetime_format(sort_field_iter.next())?;
     let mut hits_with_position: Vec<(usize, Hit)> = leaf_hits
+        .into_iter()
         .map(|leaf_hit| {
             build_hit_with_position(
                 leaf_hit,
@@ -780,7 +1085,7 @@ pub(crate) async fn fetch_docs_phase(
         .map(|(_position, hit)| hit)
         .collect();
 
-    Ok(hits)
+    Ok((hits, split_profiles))
 }
 
 fn build_hit_with_position(
@@ -830,6 +1135,7 @@ fn build_hit_with_position(
             partial_hit: leaf_hit.partial_hit,
             snippet: leaf_hit.leaf_snippet_json,
             index_id,
+            highlight: leaf_hit.leaf_highlight_json,
         },
     ))
 }
@@ -874,14 +1180,46 @@ async fn root_search_aux(
     )
     .await?;
 
-    let hits = fetch_docs_phase(
+    fetch_docs_and_build_search_response(
+        searcher_context,
         indexes_metas_for_leaf_search,
-        &first_phase_result.partial_hits,
+        search_request,
+        first_phase_result,
+        scroll_key_and_start_offset_opt,
         &split_metadatas[..],
+        cluster_client,
+    )
+    .await
+}
+
+/// Performs the second phase of a search, i.e. fetches the docs of the partial hits returned by
+/// the first phase, and builds the search response.
+async fn fetch_docs_and_build_search_response(
+    searcher_context: &SearcherContext,
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: SearchRequest,
+    first_phase_result: LeafSearchResponse,
+    scroll_key_and_start_offset_opt: Option<ScrollKeyAndStartOffset>,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<SearchResponse> {
+    let (hits, fetch_split_profiles) = fetch_docs_phase(
+        indexes_metas_for_leaf_search,
+        &first_phase_result.partial_hits,
+        split_metadatas,
         &search_request,
         cluster_client,
     )
     .await?;
+    let profile_opt = if search_request.profile {
+        let split_profiles = first_phase_result
+            .split_profiles
+            .into_iter()
+            .chain(fetch_split_profiles);
+        Some(build_search_profile(split_profiles))
+    } else {
+        None
+    };
 
     let mut aggregation_result_json_opt = finalize_aggregation_if_any(
         &search_request,
@@ -902,6 +1240,8 @@ async fn root_search_aux(
         scroll_id: scroll_key_and_start_offset_opt
             .as_ref()
             .map(ToString::to_string),
+        profile: profile_opt,
+        failed_indexes: Vec::new(),
     })
 }
 
@@ -921,6 +1261,36 @@ fn finalize_aggregation(
             let aggs: Vec<Span> = postcard::from_bytes(&intermediate_aggregation_result_bytes)?;
             serde_json::to_string(&aggs)?
         }
+        QuickwitAggregations::FieldCoverageAggregation(collector) => {
+            let field_coverage: FieldCoverage = if let Some(intermediate_aggregation_result_bytes) =
+                intermediate_aggregation_result_bytes_opt
+            {
+                postcard::from_bytes(&intermediate_aggregation_result_bytes)?
+            } else {
+                FieldCoverage::default()
+            };
+            serde_json::to_string(&collector.finalize(field_coverage))?
+        }
+        QuickwitAggregations::CardinalityAggregation(collector) => {
+            let cardinality: Cardinality = if let Some(intermediate_aggregation_result_bytes) =
+                intermediate_aggregation_result_bytes_opt
+            {
+                postcard::from_bytes(&intermediate_aggregation_result_bytes)?
+            } else {
+                Cardinality::default()
+            };
+            serde_json::to_string(&collector.finalize(cardinality))?
+        }
+        QuickwitAggregations::PercentilesAggregation(collector) => {
+            let percentiles: Percentiles = if let Some(intermediate_aggregation_result_bytes) =
+                intermediate_aggregation_result_bytes_opt
+            {
+                postcard::from_bytes(&intermediate_aggregation_result_bytes)?
+            } else {
+                Percentiles::default()
+            };
+            serde_json::to_string(&collector.finalize(percentiles))?
+        }
         QuickwitAggregations::TantivyAggregations(aggregations) => {
             let intermediate_aggregation_results =
                 if let Some(intermediate_aggregation_result_bytes) =
@@ -949,13 +1319,35 @@ fn finalize_aggregation_if_any(
     let Some(aggregations_json) = search_request.aggregation_request.as_ref() else {
         return Ok(None);
     };
-    let aggregations: QuickwitAggregations = serde_json::from_str(aggregations_json)?;
-    let aggregation_result_json = finalize_aggregation(
+    let (aggregations_json, post_aggregations) = push_down_post_aggregations(aggregations_json)?;
+    let aggregations: QuickwitAggregations = serde_json::from_str(&aggregations_json)?;
+    let aggregation_result_json_opt = finalize_aggregation(
         intermediate_aggregation_result_bytes_opt,
         aggregations,
         searcher_context,
     )?;
-    Ok(aggregation_result_json)
+    if post_aggregations.is_empty() {
+        return Ok(aggregation_result_json_opt);
+    }
+    let Some(aggregation_result_json) = aggregation_result_json_opt else {
+        return Ok(None);
+    };
+    let mut aggregation_results: serde_json::Value =
+        serde_json::from_str(&aggregation_result_json)?;
+    post_aggregations.apply(&mut aggregation_results);
+    Ok(Some(serde_json::to_string(&aggregation_results)?))
+}
+
+/// Splits the comma-separated lists of index ID patterns, as in `logs-*,metrics`, into individual
+/// index ID patterns.
+pub(crate) fn split_comma_separated_index_id_patterns(index_id_patterns: &[String]) -> Vec<String> {
+    index_id_patterns
+        .iter()
+        .flat_map(|index_id_pattern| index_id_pattern.split(','))
+        .map(str::trim)
+        .filter(|index_id_pattern| !index_id_pattern.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 /// Checks that all of the index researched as found.
@@ -998,11 +1390,134 @@ pub fn check_all_index_metadata_found(
     Ok(())
 }
 
+/// Pages through the splits matching a [`ListSplitsQuery`], newest splits first.
+struct ListSplitMetadataPages {
+    list_splits_query: ListSplitsQuery,
+    metastore: MetastoreServiceClient,
+    page_size: usize,
+    // Last split of the previous page, from which the next page starts.
+    last_split_metadata_opt: Option<SplitMetadata>,
+    exhausted: bool,
+}
+
+impl ListSplitMetadataPages {
+    async fn next_page(mut self) -> crate::Result<Option<(Vec<SplitMetadata>, Self)>> {
+        if self.exhausted {
+            return Ok(None);
+        }
+        let mut page_query = self.list_splits_query.clone().with_limit(self.page_size);
+
+        if let Some(last_split_metadata) = &self.last_split_metadata_opt {
+            page_query = page_query.after_split_by_time_range_end_desc(last_split_metadata);
+        }
+        let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&page_query)?;
+        let split_metadata_page: Vec<SplitMetadata> = self
+            .metastore
+            .list_splits(list_splits_request)
+            .await?
+            .collect_splits_metadata()
+            .await?;
+        self.exhausted = split_metadata_page.len() < self.page_size;
+        self.last_split_metadata_opt = split_metadata_page.last().cloned();
+        Ok(Some((split_metadata_page, self)))
+    }
+}
+
+/// Lists the splits matching `list_splits_query` in pages of `page_size` splits, newest splits
+/// first. Each page is listed with a separate metastore call so that no single call has to return
+/// millions of splits. Pages start after the last split of the previous page rather than at an
+/// offset, so listing a page does not get slower as the pages go.
+///
+/// All the pages list the splits published when the listing starts, so the splits published or
+/// merged meanwhile are neither missed nor listed twice.
+fn list_split_metadata_pages(
+    list_splits_query: ListSplitsQuery,
+    metastore: MetastoreServiceClient,
+    page_size: usize,
+) -> impl Stream<Item = crate::Result<Vec<SplitMetadata>>> {
+    let snapshot_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let list_split_metadata_pages = ListSplitMetadataPages {
+        list_splits_query: list_splits_query
+            .sort_by_time_range_end_desc()
+            .with_snapshot_timestamp(snapshot_timestamp),
+        metastore,
+        page_size,
+        last_split_metadata_opt: None,
+        exhausted: false,
+    };
+    futures::stream::try_unfold(list_split_metadata_pages, ListSplitMetadataPages::next_page)
+}
+
+/// Removes the splits whose term digests prove that they cannot match the query, if the term
+/// digest store is available on this searcher.
+async fn prune_splits_with_term_digests(
+    searcher_context: &SearcherContext,
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    query_ast: &QueryAst,
+    split_metadatas: &mut Vec<SplitMetadata>,
+) {
+    if let Some(term_digest_store) = &searcher_context.term_digest_store_opt {
+        term_digest_store
+            .prune_splits(indexes_metas_for_leaf_search, query_ast, split_metadatas)
+            .await;
+    }
+}
+
+/// Removes the splits whose secondary time ranges do not intersect the time range implied by the
+/// query on the same field. Splits that did not record a range for a field are kept.
+fn prune_splits_with_secondary_time_ranges(
+    query_ast: &QueryAst,
+    split_metadatas: &mut Vec<SplitMetadata>,
+) {
+    let mut query_time_ranges: HashMap<String, (Option<i64>, Option<i64>)> = HashMap::new();
+    let num_splits_before = split_metadatas.len();
+    split_metadatas.retain(|split_metadata| {
+        split_metadata
+            .secondary_time_ranges
+            .iter()
+            .all(|(field_name, split_time_range)| {
+                let (start_timestamp_opt, end_timestamp_opt) = *query_time_ranges
+                    .entry(field_name.clone())
+                    .or_insert_with(|| {
+                        let mut start_timestamp = None;
+                        let mut end_timestamp = None;
+                        refine_start_end_timestamp_from_ast(
+                            query_ast,
+                            field_name,
+                            &mut start_timestamp,
+                            &mut end_timestamp,
+                        );
+                        (start_timestamp, end_timestamp)
+                    });
+                let after_start = start_timestamp_opt.map_or(true, |start_timestamp| {
+                    *split_time_range.end() >= start_timestamp
+                });
+                let before_end = end_timestamp_opt.map_or(true, |end_timestamp| {
+                    *split_time_range.start() < end_timestamp
+                });
+                after_start && before_end
+            })
+    });
+    let num_pruned_splits = num_splits_before - split_metadatas.len();
+    if num_pruned_splits > 0 {
+        debug!(
+            num_pruned_splits,
+            "pruned splits with secondary time ranges"
+        );
+    }
+}
+
 /// Performs a distributed search.
 /// 1. Sends leaf request over gRPC to multiple leaf nodes.
 /// 2. Merges the search results.
 /// 3. Sends fetch docs requests to multiple leaf nodes.
 /// 4. Builds the response with docs and returns.
+///
+/// Unless a scroll is requested, the relevant splits are listed page by page, newest first, and
+/// leaf search jobs are dispatched for each page while the following pages are still loading.
+///
+/// If the request carries a point in time ID, the search runs against the splits captured when
+/// the point in time was opened instead of the splits currently published.
 #[instrument(skip_all)]
 pub async fn root_search(
     searcher_context: &SearcherContext,
@@ -1010,12 +1525,61 @@ pub async fn root_search(
     mut metastore: MetastoreServiceClient,
     cluster_client: &ClusterClient,
 ) -> crate::Result<SearchResponse> {
+    // The token must neither be logged nor forwarded to the leaves.
+    let legal_hold_token_opt = search_request.legal_hold_token.take();
     info!(searcher_context = ?searcher_context, search_request = ?search_request);
     let start_instant = tokio::time::Instant::now();
+
+    let legal_hold_searcher_opt = if search_request.include_held_splits {
+        let legal_hold_searcher = legal_hold_token_opt
+            .as_deref()
+            .and_then(|token| {
+                searcher_context
+                    .searcher_config
+                    .find_legal_hold_searcher(token)
+            })
+            .ok_or_else(|| {
+                SearchError::Forbidden(
+                    "searching splits under legal hold requires the token of an allowed caller"
+                        .to_string(),
+                )
+            })?;
+        Some(legal_hold_searcher.name.clone())
+    } else {
+        None
+    };
+    if let Some(mut search_response) = searcher_context.search_response_cache.get(&search_request) {
+        search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
+        return Ok(search_response);
+    }
+    // The request is rewritten below, so we keep the original one to key the cache entry.
+    let cacheable_search_request_opt =
+        SearchResponseCache::is_cacheable(&search_request).then(|| search_request.clone());
+    // The lookups apply to the aggregation request as submitted by the user.
+    let lookups = search_request.lookups.clone();
+    let aggregation_request_opt = search_request.aggregation_request.clone();
+
+    let point_in_time_opt = if let Some(pit_id) = &search_request.pit_id {
+        if search_request.include_held_splits {
+            return Err(SearchError::InvalidArgument(
+                "searching splits under legal hold is not supported with a point in time"
+                    .to_string(),
+            ));
+        }
+        let point_in_time = load_point_in_time(pit_id, cluster_client).await?;
+        // The point in time determines the searched indexes.
+        search_request.index_id_patterns = point_in_time.index_ids();
+        Some(point_in_time)
+    } else {
+        search_request.index_id_patterns =
+            split_comma_separated_index_id_patterns(&search_request.index_id_patterns);
+        None
+    };
+
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
     };
-    let indexes_metadata: Vec<IndexMetadata> = metastore
+    let mut indexes_metadata: Vec<IndexMetadata> = metastore
         .list_indexes_metadata(list_indexes_metadatas_request)
         .await?
         .deserialize_indexes_metadata()
@@ -1043,7 +1607,21 @@ pub async fn root_search(
         .iter()
         .map(|index_metadata| index_metadata.index_uid.clone())
         .collect_vec();
+    if let Some(point_in_time) = &point_in_time_opt {
+        point_in_time.check_index_uids(&index_uids)?;
+    }
     let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
+    // The indexes that cannot serve the request are reported in the response instead of being
+    // searched.
+    indexes_metadata.retain(|index_metadata| {
+        request_metadata
+            .indexes_meta_for_leaf_search
+            .contains_key(&index_metadata.index_uid)
+    });
+    let index_uids = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_uid.clone())
+        .collect_vec();
     search_request.query_ast = serde_json::to_string(&request_metadata.query_ast_resolved)?;
 
     // convert search_after datetime values from input datetime format to nanos.
@@ -1061,29 +1639,223 @@ pub async fn root_search(
             &mut search_request.end_timestamp,
         );
     }
+    if let Some(max_time_range) = request_metadata.max_time_range_opt {
+        cap_time_range(
+            max_time_range,
+            &mut search_request.start_timestamp,
+            search_request.end_timestamp,
+        );
+    }
+    // The splits of a point in time are frozen, so their lazy sources are not materialized.
+    if point_in_time_opt.is_none() {
+        materialize_external_splits(
+            searcher_context,
+            &indexes_metadata,
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+        )
+        .await?;
+    }
+    let query_ast_resolved = request_metadata.query_ast_resolved.clone();
     let tag_filter_ast = extract_tags_from_query(request_metadata.query_ast_resolved);
+    let is_scan = is_scan_query(&query_ast_resolved, &search_request);
+    let searched_index_uids = index_uids.clone();
+    let num_docs_searched: u64;
 
     // TODO if search after is set, we sort by timestamp and we don't want to count all results,
     // we can refine more here. Same if we sort by _shard_doc
-    let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
-        index_uids,
-        search_request.start_timestamp,
-        search_request.end_timestamp,
-        tag_filter_ast,
-        &mut metastore,
-    )
-    .await?;
+    let mut search_response = if search_request.include_held_splits || point_in_time_opt.is_some() {
+        // Legal hold searches are rare and must be audited as a whole, so we list their splits
+        // upfront. Point in time searches read their splits from the frozen split set instead.
+        let mut split_metadatas: Vec<SplitMetadata> =
+            if let Some(point_in_time) = &point_in_time_opt {
+                point_in_time.relevant_splits(
+                    search_request.start_timestamp,
+                    search_request.end_timestamp,
+                    tag_filter_ast.as_ref(),
+                )
+            } else {
+                list_relevant_splits(
+                    index_uids,
+                    search_request.start_timestamp,
+                    search_request.end_timestamp,
+                    tag_filter_ast.clone(),
+                    &mut metastore,
+                )
+                .await?
+            };
+        if search_request.include_held_splits {
+            let held_split_metadatas = list_held_splits(
+                &indexes_metadata,
+                search_request.start_timestamp,
+                search_request.end_timestamp,
+                tag_filter_ast,
+                &mut metastore,
+            )
+            .await?;
+            let held_split_ids: Vec<&str> = held_split_metadatas
+                .iter()
+                .map(|split_metadata| split_metadata.split_id())
+                .collect();
+            info!(
+                target: AUDIT_LOG_TARGET,
+                caller = legal_hold_searcher_opt.as_deref().unwrap_or_default(),
+                index_ids = ?search_request.index_id_patterns,
+                query_ast = %search_request.query_ast,
+                held_split_ids = ?held_split_ids,
+                "search including splits under legal hold"
+            );
+            split_metadatas.extend(held_split_metadatas);
+        }
+        prune_splits_with_term_digests(
+            searcher_context,
+            &request_metadata.indexes_meta_for_leaf_search,
+            &query_ast_resolved,
+            &mut split_metadatas,
+        )
+        .await;
+        prune_splits_with_secondary_time_ranges(&query_ast_resolved, &mut split_metadatas);
+        num_docs_searched = split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.num_docs as u64)
+            .sum();
+        root_search_aux(
+            searcher_context,
+            &request_metadata.indexes_meta_for_leaf_search,
+            search_request,
+            split_metadatas,
+            cluster_client,
+        )
+        .await?
+    } else {
+        // The searches sorted by relevance are re-ranked by the scoring service of the indexes,
+        // if any. The re-ranked hits are always the first ones, so we retrieve them all and
+        // paginate afterwards.
+        let scroll_ttl_opt = get_scroll_ttl_duration(&search_request)?;
+        let hit_reranker_opt = request_metadata
+            .reranker_config_opt
+            .as_ref()
+            .filter(|_| scroll_ttl_opt.is_none() && is_rerankable(&search_request))
+            .map(HitReranker::from_reranker_config);
+        let requested_page_opt = hit_reranker_opt.as_ref().map(|hit_reranker| {
+            let requested_page = (search_request.start_offset, search_request.max_hits);
+            search_request.max_hits = hit_reranker.num_hits_to_retrieve(&search_request);
+            search_request.start_offset = 0;
+            requested_page
+        });
+        let rerank_query = query_text_for_reranking(&query_ast_resolved);
 
-    let mut search_response = root_search_aux(
-        searcher_context,
-        &request_metadata.indexes_meta_for_leaf_search,
-        search_request,
-        split_metadatas,
-        cluster_client,
+        let list_splits_query = relevant_splits_query(
+            index_uids,
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+            tag_filter_ast,
+        )?;
+        let split_metadata_pages =
+            list_split_metadata_pages(list_splits_query, metastore, LIST_SPLITS_PAGE_SIZE)
+                .and_then(|mut split_metadata_page| {
+                    let query_ast_resolved = &query_ast_resolved;
+                    let indexes_metas_for_leaf_search =
+                        &request_metadata.indexes_meta_for_leaf_search;
+                    async move {
+                        prune_splits_with_term_digests(
+                            searcher_context,
+                            indexes_metas_for_leaf_search,
+                            query_ast_resolved,
+                            &mut split_metadata_page,
+                        )
+                        .await;
+                        prune_splits_with_secondary_time_ranges(
+                            query_ast_resolved,
+                            &mut split_metadata_page,
+                        );
+                        Ok(split_metadata_page)
+                    }
+                });
+        // The scroll context is saved once all the splits have been listed and searched.
+        let mut first_phase_search_request = search_request.clone();
+
+        if scroll_ttl_opt.is_some() {
+            prepare_first_scroll_search_request(&mut first_phase_search_request);
+        }
+        let (mut first_phase_result, split_metadatas) = search_partial_hits_phase_paginated(
+            searcher_context,
+            &request_metadata.indexes_meta_for_leaf_search,
+            &first_phase_search_request,
+            split_metadata_pages,
+            cluster_client,
+        )
+        .await?;
+        let scroll_key_and_start_offset_opt = if let Some(scroll_ttl) = scroll_ttl_opt {
+            let scroll_key_and_start_offset = save_scroll_context(
+                &request_metadata.indexes_meta_for_leaf_search,
+                &first_phase_search_request,
+                search_request.max_hits,
+                &split_metadatas,
+                &mut first_phase_result,
+                scroll_ttl,
+                cluster_client,
+            )
+            .await?;
+            Some(scroll_key_and_start_offset)
+        } else {
+            None
+        };
+        num_docs_searched = split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.num_docs as u64)
+            .sum();
+        let mut search_response = fetch_docs_and_build_search_response(
+            searcher_context,
+            &request_metadata.indexes_meta_for_leaf_search,
+            search_request,
+            first_phase_result,
+            scroll_key_and_start_offset_opt,
+            &split_metadatas,
+            cluster_client,
+        )
+        .await?;
+
+        if let (Some(hit_reranker), Some((start_offset, max_hits))) =
+            (hit_reranker_opt, requested_page_opt)
+        {
+            hit_reranker
+                .rerank_hits(&rerank_query, &mut search_response.hits)
+                .await?;
+            search_response.hits = search_response
+                .hits
+                .into_iter()
+                .skip(start_offset as usize)
+                .take(max_hits as usize)
+                .collect();
+        }
+        search_response
+    };
+    search_response.failed_indexes = request_metadata.failed_indexes;
+
+    apply_lookups(
+        searcher_context.lookup_table_store_opt.as_deref(),
+        &lookups,
+        aggregation_request_opt.as_deref(),
+        &mut search_response,
     )
     .await?;
+    let elapsed = start_instant.elapsed();
+    search_response.elapsed_time_micros = elapsed.as_micros() as u64;
+
+    searcher_context.query_access_tracker.record_search(
+        &searched_index_uids,
+        num_docs_searched,
+        search_response.num_hits,
+        elapsed,
+        is_scan,
+    );
 
-    search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
+    if let Some(cacheable_search_request) = cacheable_search_request_opt {
+        searcher_context
+            .search_response_cache
+            .put(cacheable_search_request, &search_response);
+    }
     Ok(search_response)
 }
 
@@ -1238,21 +2010,15 @@ struct ExtractTimestampRange<'a> {
 }
 
 impl<'a> ExtractTimestampRange<'a> {
-    fn update_start_timestamp(
-        &mut self,
-        lower_bound: &quickwit_query::JsonLiteral,
-        included: bool,
-    ) {
+    /// The lower bound is rounded down to the second whether it is included or not: split time
+    /// ranges are truncated to the second, so timestamps with a sub-second precision can fall
+    /// between an excluded bound and the next second. The range query itself remains exact.
+    fn update_start_timestamp(&mut self, lower_bound: &quickwit_query::JsonLiteral) {
         use quickwit_query::InterpretUserInput;
         let Some(lower_bound) = tantivy::DateTime::interpret_json(lower_bound) else {
             return;
         };
-        let mut lower_bound = lower_bound.into_timestamp_secs();
-        if !included {
-            // TODO saturating isn't exactly right, we should replace the RangeQuery with
-            // a match_none, but the visitor doesn't allow mutation.
-            lower_bound = lower_bound.saturating_add(1);
-        }
+        let lower_bound = lower_bound.into_timestamp_secs();
         self.start_timestamp = Some(
             self.start_timestamp
                 .map_or(lower_bound, |current| current.max(lower_bound)),
@@ -1289,13 +2055,20 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
         Ok(())
     }
 
+    fn visit_join(&mut self, _join_query: &'b JoinQuery) -> Result<(), Self::Err> {
+        // The documents matched by a join query are not bounded by the timestamp range of its
+        // underlying query.
+        Ok(())
+    }
+
     fn visit_range(&mut self, range_query: &'b RangeQuery) -> Result<(), Self::Err> {
         use std::ops::Bound;
 
         if range_query.field == self.timestamp_field {
             match &range_query.lower_bound {
-                Bound::Included(lower_bound) => self.update_start_timestamp(lower_bound, true),
-                Bound::Excluded(lower_bound) => self.update_start_timestamp(lower_bound, false),
+                Bound::Included(lower_bound) | Bound::Excluded(lower_bound) => {
+                    self.update_start_timestamp(lower_bound)
+                }
                 Bound::Unbounded => (),
             }
             match &range_query.upper_bound {
@@ -1312,7 +2085,7 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
         if term_query.field == self.timestamp_field {
             // TODO when fixing #3323, this may need to be modified to support numbers too
             let json_term = quickwit_query::JsonLiteral::String(term_query.value.clone());
-            self.update_start_timestamp(&json_term, true);
+            self.update_start_timestamp(&json_term);
             self.update_end_timestamp(&json_term, true);
         }
         Ok(())
@@ -1325,7 +2098,7 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
             // ordering to get the start and end quickly.
             if let Some(first) = term_set.first() {
                 let json_term = quickwit_query::JsonLiteral::String(first.clone());
-                self.update_start_timestamp(&json_term, true);
+                self.update_start_timestamp(&json_term);
             }
             if let Some(last) = term_set.last() {
                 let json_term = quickwit_query::JsonLiteral::String(last.clone());
@@ -1461,6 +2234,7 @@ pub fn jobs_to_fetch_docs_requests(
                 index_uri: index_meta.index_uri.to_string(),
                 snippet_request: snippet_request_opt.clone(),
                 doc_mapper: index_meta.doc_mapper_str.clone(),
+                profile: false,
             };
             fetch_docs_requests.push(fetch_docs_req);
 
@@ -1472,15 +2246,20 @@ pub fn jobs_to_fetch_docs_requests(
 
 #[cfg(test)]
 mod tests {
-    use std::ops::Range;
+    use std::ops::{Range, RangeInclusive};
     use std::str::FromStr;
     use std::sync::{Arc, RwLock};
 
     use quickwit_common::shared_consts::SCROLL_BATCH_LEN;
     use quickwit_common::ServiceStream;
-    use quickwit_config::{DocMapping, IndexConfig, IndexingSettings, SearchSettings};
+    use quickwit_config::{
+        BlockedQueryKind, BlockedQueryPattern, DocMapping, IndexConfig, IndexingSettings,
+        LegalHold, LegalHoldSearcher, QueryRules, SearchSettings, SearcherConfig,
+    };
     use quickwit_indexing::MockSplitBuilder;
-    use quickwit_metastore::{IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt};
+    use quickwit_metastore::{
+        IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt, Split, SplitState,
+    };
     use quickwit_proto::metastore::{
         ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
     };
@@ -1503,6 +2282,90 @@ mod tests {
         validate_requested_snippet_fields(&schema, snippet_fields)
     }
 
+    #[test]
+    fn test_prune_splits_with_secondary_time_ranges() {
+        use std::ops::Bound;
+
+        use quickwit_query::JsonLiteral;
+
+        let split_metadata_for_test =
+            |split_id: &str, time_range: RangeInclusive<i64>| SplitMetadata {
+                split_id: split_id.to_string(),
+                secondary_time_ranges: [("event_time".to_string(), time_range)]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            };
+        let mut split_metadatas = vec![
+            split_metadata_for_test("split-1", 1_000..=1_999),
+            split_metadata_for_test("split-2", 2_000..=2_999),
+            split_metadata_for_test("split-3", 3_000..=3_999),
+            SplitMetadata {
+                split_id: "split-without-range".to_string(),
+                ..Default::default()
+            },
+        ];
+        let query_ast: QueryAst = RangeQuery {
+            field: "event_time".to_string(),
+            lower_bound: Bound::Excluded(JsonLiteral::String("1970-01-01T00:33:20.5Z".to_string())),
+            upper_bound: Bound::Excluded(JsonLiteral::String("1970-01-01T00:50:00Z".to_string())),
+        }
+        .into();
+        prune_splits_with_secondary_time_ranges(&query_ast, &mut split_metadatas);
+        let split_ids: Vec<&str> = split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id())
+            .collect();
+        assert_eq!(split_ids, ["split-2", "split-without-range"]);
+
+        // A query that does not filter on the secondary field prunes nothing.
+        prune_splits_with_secondary_time_ranges(
+            &qast_helper("body:foo", &[]),
+            &mut split_metadatas,
+        );
+        assert_eq!(split_metadatas.len(), 2);
+    }
+
+    #[test]
+    fn test_find_top_hits_aggregation_sorted_by_score() {
+        assert_eq!(
+            find_top_hits_aggregation_sorted_by_score(
+                r#"{"latest": {"top_hits": {"size": 1, "sort": [{"timestamp": "desc"}]}}}"#
+            ),
+            None
+        );
+        assert_eq!(
+            find_top_hits_aggregation_sorted_by_score(
+                r#"{"most_relevant": {"top_hits": {"size": 1, "sort": ["_score"]}}}"#
+            ),
+            Some("most_relevant".to_string())
+        );
+        assert_eq!(
+            find_top_hits_aggregation_sorted_by_score(
+                r#"{
+                    "services": {
+                        "terms": {"field": "service"},
+                        "aggs": {
+                            "most_relevant": {
+                                "top_hits": {
+                                    "sort": [{"timestamp": "desc"}, {"_score": {"order": "desc"}}]
+                                }
+                            }
+                        }
+                    }
+                }"#
+            ),
+            Some("most_relevant".to_string())
+        );
+        // A field named `_score` in a terms order is not a top hits sort.
+        assert_eq!(
+            find_top_hits_aggregation_sorted_by_score(
+                r#"{"services": {"terms": {"field": "service", "order": {"_score": "desc"}}}}"#
+            ),
+            None
+        );
+    }
+
     #[test]
     fn test_validate_requested_snippet_fields() {
         check_snippet_fields_validation(&["desc".to_string()]).unwrap();
@@ -1571,6 +2434,7 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            ..Default::default()
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -1579,6 +2443,8 @@ mod tests {
             indexing_settings,
             search_settings,
             retention_policy_opt: Default::default(),
+            replication_factor_opt: None,
+            legal_hold_opt: None,
         })
     }
 
@@ -1684,7 +2550,7 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_request_and_build_metadatas_fail_with_different_resolved_qast() {
+    fn test_validate_request_and_build_metadatas_merges_default_search_fields() {
         let qast = query_ast_from_user_text("test", None);
         let search_request = quickwit_proto::search::SearchRequest {
             index_id_patterns: vec!["test-index".to_string()],
@@ -1699,15 +2565,110 @@ mod tests {
             .index_config
             .search_settings
             .default_search_fields = vec!["owner".to_string()];
-        let timestamp_field_different = validate_request_and_build_metadata(
+        let request_metadata = validate_request_and_build_metadata(
             &[index_metadata_1, index_metadata_2],
             &search_request,
         )
-        .unwrap_err();
+        .unwrap();
+        let expected_default_search_fields = [
+            "body".to_string(),
+            "attributes.server".to_string(),
+            r"attributes.server\.status".to_string(),
+            "owner".to_string(),
+        ];
+        let expected_query_ast = query_ast_from_user_text("test", None)
+            .parse_user_query(&expected_default_search_fields)
+            .unwrap();
+        assert_eq!(request_metadata.query_ast_resolved, expected_query_ast);
+        assert_eq!(request_metadata.indexes_meta_for_leaf_search.len(), 2);
+        assert!(request_metadata.failed_indexes.is_empty());
+    }
+
+    #[test]
+    fn test_validate_request_and_build_metadatas_reports_failed_indexes() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-*".to_string()],
+            query_ast: qast_json_helper("body:test", &[]),
+            max_hits: 10,
+            snippet_fields: vec!["owner".to_string()],
+            ..Default::default()
+        };
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let mut index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+        let doc_mapping_json_2 = r#"{
+            "field_mappings": [
+                {
+                    "name": "body",
+                    "type": "text"
+                }
+            ]
+        }"#;
+        index_metadata_2.index_config.doc_mapping =
+            serde_json::from_str(doc_mapping_json_2).unwrap();
+        index_metadata_2
+            .index_config
+            .search_settings
+            .default_search_fields = Vec::new();
+        let request_metadata = validate_request_and_build_metadata(
+            &[index_metadata_1, index_metadata_2.clone()],
+            &search_request,
+        )
+        .unwrap();
+        assert_eq!(request_metadata.indexes_meta_for_leaf_search.len(), 1);
+        assert_eq!(request_metadata.failed_indexes.len(), 1);
+        assert_eq!(request_metadata.failed_indexes[0].index_id, "test-index-2");
+
+        // The first error is returned when no index can serve the request.
+        validate_request_and_build_metadata(&[index_metadata_2], &search_request).unwrap_err();
+    }
+
+    #[test]
+    fn test_validate_request_and_build_metadatas_with_field_missing_in_some_indexes() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-*".to_string()],
+            query_ast: qast_json_helper("owner:paul", &[]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let mut index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+        let doc_mapping_json_2 = r#"{
+            "mode": "strict",
+            "field_mappings": [
+                {
+                    "name": "body",
+                    "type": "text"
+                }
+            ]
+        }"#;
+        index_metadata_2.index_config.doc_mapping =
+            serde_json::from_str(doc_mapping_json_2).unwrap();
+        index_metadata_2
+            .index_config
+            .search_settings
+            .default_search_fields = Vec::new();
+        let request_metadata = validate_request_and_build_metadata(
+            &[index_metadata_1, index_metadata_2.clone()],
+            &search_request,
+        )
+        .unwrap();
+        assert_eq!(request_metadata.indexes_meta_for_leaf_search.len(), 2);
+        assert!(request_metadata.failed_indexes.is_empty());
+
+        let search_error =
+            validate_request_and_build_metadata(&[index_metadata_2], &search_request).unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidQuery(_)));
+    }
+
+    #[test]
+    fn test_split_comma_separated_index_id_patterns() {
+        assert!(split_comma_separated_index_id_patterns(&[]).is_empty());
         assert_eq!(
-            timestamp_field_different.to_string(),
-            "resolved query ASTs must be the same across indexes. resolving queries with \
-             different default fields are different between indexes is not supported"
+            split_comma_separated_index_id_patterns(&[
+                "logs-*,metrics".to_string(),
+                " traces ,".to_string()
+            ]),
+            ["logs-*", "metrics", "traces"]
         );
     }
 
@@ -1743,6 +2704,7 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            ..Default::default()
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -1751,6 +2713,8 @@ mod tests {
             indexing_settings,
             search_settings,
             retention_policy_opt: Default::default(),
+            replication_factor_opt: None,
+            legal_hold_opt: None,
         })
     }
 
@@ -2240,32 +3204,197 @@ mod tests {
                 .expect("Json serialization should not fail"),
                 partial_hit: Some(req),
                 leaf_snippet_json: None,
+                leaf_highlight_json: None,
             })
             .collect()
     }
 
     #[tokio::test]
-    async fn test_root_search_offset_out_of_bounds_1085() -> anyhow::Result<()> {
-        let search_request = quickwit_proto::search::SearchRequest {
-            index_id_patterns: vec!["test-index".to_string()],
-            query_ast: qast_json_helper("test", &["body"]),
-            max_hits: 10,
-            start_offset: 10,
-            ..Default::default()
-        };
+    async fn test_root_search_offset_out_of_bounds_1085() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            start_offset: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_indexes_metadata_request| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_filter| {
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service_2 = MockSearchService::new();
+        mock_search_service_2.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 3,
+                    partial_hits: vec![
+                        mock_partial_hit("split1", 3, 1),
+                        mock_partial_hit("split1", 2, 2),
+                        mock_partial_hit("split1", 1, 3),
+                    ],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service_2.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
+                })
+            },
+        );
+        let mut mock_search_service_1 = MockSearchService::new();
+        mock_search_service_1.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 2,
+                    partial_hits: vec![
+                        mock_partial_hit("split2", 3, 1),
+                        mock_partial_hit("split2", 1, 3),
+                    ],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service_1.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", mock_search_service_1),
+            ("127.0.0.1:1002", mock_search_service_2),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 5);
+        assert_eq!(search_response.hits.len(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_root_search_single_split() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build()];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 3,
+                    partial_hits: vec![
+                        mock_partial_hit("split1", 3, 1),
+                        mock_partial_hit("split1", 2, 2),
+                        mock_partial_hit("split1", 1, 3),
+                    ],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+
+        let searcher_context = SearcherContext::for_test();
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 3);
+        assert_eq!(search_response.hits.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_root_search_with_point_in_time() {
         let mut mock_metastore = MockMetastoreService::new();
         let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
         let index_uid = index_metadata.index_uid.clone();
         mock_metastore
             .expect_list_indexes_metadata()
-            .returning(move |_indexes_metadata_request| {
+            .returning(move |_index_ids_query| {
                 Ok(ListIndexesMetadataResponse::for_test(vec![
                     index_metadata.clone()
                 ]))
             });
+        // The splits are only listed when the point in time is opened.
         mock_metastore
             .expect_list_splits()
-            .returning(move |_filter| {
+            .times(1)
+            .returning(move |_list_splits_request| {
                 let splits = vec![
                     MockSplitBuilder::new("split1")
                         .with_index_uid(&index_uid)
@@ -2277,81 +3406,222 @@ mod tests {
                 let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
                 Ok(ServiceStream::from(vec![Ok(splits_response)]))
             });
-        let mut mock_search_service_2 = MockSearchService::new();
-        mock_search_service_2.expect_leaf_search().returning(
-            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let mut mock_search_service = MockSearchService::new();
+        let kv: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>> = Default::default();
+        let kv_clone = kv.clone();
+        mock_search_service
+            .expect_put_kv()
+            .returning(move |put_kv_req| {
+                kv_clone
+                    .write()
+                    .unwrap()
+                    .insert(put_kv_req.key, put_kv_req.payload);
+            });
+        mock_search_service
+            .expect_get_kv()
+            .returning(move |get_kv_req| kv.read().unwrap().get(&get_kv_req.key).cloned());
+        mock_search_service.expect_leaf_search().times(2).returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let mut split_ids: Vec<&str> = leaf_search_req
+                    .split_offsets
+                    .iter()
+                    .map(|split_offsets| split_offsets.split_id.as_str())
+                    .collect();
+                split_ids.sort_unstable();
+                assert_eq!(split_ids, ["split1", "split2"]);
                 Ok(quickwit_proto::search::LeafSearchResponse {
-                    num_hits: 3,
+                    num_hits: 2,
                     partial_hits: vec![
-                        mock_partial_hit("split1", 3, 1),
-                        mock_partial_hit("split1", 2, 2),
-                        mock_partial_hit("split1", 1, 3),
+                        mock_partial_hit("split1", 2, 1),
+                        mock_partial_hit("split2", 1, 1),
                     ],
-                    failed_splits: Vec::new(),
-                    num_attempted_splits: 1,
+                    num_attempted_splits: 2,
                     ..Default::default()
                 })
             },
         );
-        mock_search_service_2.expect_fetch_docs().returning(
+        mock_search_service.expect_fetch_docs().returning(
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
-        let mut mock_search_service_1 = MockSearchService::new();
-        mock_search_service_1.expect_leaf_search().returning(
-            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let searcher_context = SearcherContext::for_test();
+
+        let open_request = quickwit_proto::search::OpenPointInTimeRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            keep_alive_secs: 60,
+        };
+        let open_response =
+            crate::open_point_in_time(open_request, &mut metastore.clone(), &cluster_client)
+                .await
+                .unwrap();
+        assert_eq!(open_response.num_splits, 2);
+
+        for _ in 0..2 {
+            let search_request = quickwit_proto::search::SearchRequest {
+                query_ast: qast_json_helper("test", &["body"]),
+                max_hits: 10,
+                pit_id: Some(open_response.pit_id.clone()),
+                ..Default::default()
+            };
+            let search_response = root_search(
+                &searcher_context,
+                search_request,
+                metastore.clone(),
+                &cluster_client,
+            )
+            .await
+            .unwrap();
+            assert_eq!(search_response.num_hits, 2);
+            assert_eq!(search_response.hits.len(), 2);
+        }
+        let search_request = quickwit_proto::search::SearchRequest {
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            pit_id: Some(ulid::Ulid::new().to_string()),
+            ..Default::default()
+        };
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+    }
+
+    #[tokio::test]
+    async fn test_root_search_with_query_rules() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata.index_config.search_settings.query_rules = QueryRules {
+            blocked_queries: vec![BlockedQueryPattern {
+                kind: BlockedQueryKind::MatchAll,
+                field: None,
+            }],
+            max_time_range: Some("1 hour".to_string()),
+            filters: vec!["owner:acme".to_string()],
+        };
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build()];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let search_request = leaf_search_req.search_request.unwrap();
+                assert_eq!(search_request.start_timestamp, Some(6_400));
+                assert_eq!(search_request.end_timestamp, Some(10_000));
+
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let expected_query_ast: QueryAst = BoolQuery {
+                    must: vec![qast_helper("test", &["body"])],
+                    filter: vec![qast_helper("owner:acme", &[])],
+                    ..Default::default()
+                }
+                .into();
+                assert_eq!(query_ast, expected_query_ast);
+
                 Ok(quickwit_proto::search::LeafSearchResponse {
-                    num_hits: 2,
-                    partial_hits: vec![
-                        mock_partial_hit("split2", 3, 1),
-                        mock_partial_hit("split2", 1, 3),
-                    ],
+                    num_hits: 1,
+                    partial_hits: vec![mock_partial_hit("split1", 1, 1)],
                     failed_splits: Vec::new(),
                     num_attempted_splits: 1,
                     ..Default::default()
                 })
             },
         );
-        mock_search_service_1.expect_fetch_docs().returning(
+        mock_search_service.expect_fetch_docs().returning(
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
-        let searcher_pool = searcher_pool_for_test([
-            ("127.0.0.1:1001", mock_search_service_1),
-            ("127.0.0.1:1002", mock_search_service_2),
-        ]);
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
         let search_job_placer = SearchJobPlacer::new(searcher_pool);
         let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let searcher_context = SearcherContext::for_test();
 
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            end_timestamp: Some(10_000),
+            max_hits: 10,
+            ..Default::default()
+        };
         let search_response = root_search(
-            &SearcherContext::for_test(),
+            &searcher_context,
             search_request,
-            MetastoreServiceClient::from_mock(mock_metastore),
+            metastore.clone(),
             &cluster_client,
         )
         .await
         .unwrap();
-        assert_eq!(search_response.num_hits, 5);
-        assert_eq!(search_response.hits.len(), 0);
-        Ok(())
+        assert_eq!(search_response.num_hits, 1);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("*", &["body"]),
+            end_timestamp: Some(10_000),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            search_error.to_string(),
+            "query rejected by the query rules of index `test-index`: match all queries are not \
+             allowed"
+        );
     }
 
     #[tokio::test]
-    async fn test_root_search_single_split() -> anyhow::Result<()> {
+    async fn test_root_search_include_held_splits() {
         let search_request = quickwit_proto::search::SearchRequest {
             index_id_patterns: vec!["test-index".to_string()],
             query_ast: qast_json_helper("test", &["body"]),
             max_hits: 10,
+            include_held_splits: true,
             ..Default::default()
         };
         let mut mock_metastore = MockMetastoreService::new();
-        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata.index_config.legal_hold_opt = Some(LegalHold {
+            split_ids: vec!["split2".to_string()],
+            reason: None,
+        });
         let index_uid = index_metadata.index_uid.clone();
         mock_metastore
             .expect_list_indexes_metadata()
@@ -2362,25 +3632,46 @@ mod tests {
             });
         mock_metastore
             .expect_list_splits()
-            .returning(move |_list_splits_request| {
-                let splits = vec![MockSplitBuilder::new("split1")
-                    .with_index_uid(&index_uid)
-                    .build()];
+            .returning(move |list_splits_request| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                let split_ids = if list_splits_query.split_states == [SplitState::Published] {
+                    vec!["split1"]
+                } else {
+                    assert_eq!(
+                        list_splits_query.split_states,
+                        [SplitState::MarkedForDeletion]
+                    );
+                    vec!["split2", "split3"]
+                };
+                let splits = split_ids
+                    .into_iter()
+                    .map(|split_id| {
+                        MockSplitBuilder::new(split_id)
+                            .with_index_uid(&index_uid)
+                            .build()
+                    })
+                    .collect();
                 let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
                 Ok(ServiceStream::from(vec![Ok(splits_response)]))
             });
         let mut mock_search_service = MockSearchService::new();
         mock_search_service.expect_leaf_search().returning(
-            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let split_ids: HashSet<&str> = leaf_search_req
+                    .split_offsets
+                    .iter()
+                    .map(|split_offsets| split_offsets.split_id.as_str())
+                    .collect();
+                assert_eq!(split_ids, HashSet::from_iter(["split1", "split2"]));
                 Ok(quickwit_proto::search::LeafSearchResponse {
-                    num_hits: 3,
+                    num_hits: 2,
                     partial_hits: vec![
-                        mock_partial_hit("split1", 3, 1),
-                        mock_partial_hit("split1", 2, 2),
-                        mock_partial_hit("split1", 1, 3),
+                        mock_partial_hit("split1", 2, 1),
+                        mock_partial_hit("split2", 1, 1),
                     ],
                     failed_splits: Vec::new(),
-                    num_attempted_splits: 1,
+                    num_attempted_splits: 2,
                     ..Default::default()
                 })
             },
@@ -2389,25 +3680,59 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
         let search_job_placer = SearchJobPlacer::new(searcher_pool);
         let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
 
         let searcher_context = SearcherContext::for_test();
+        let error = root_search(
+            &searcher_context,
+            search_request.clone(),
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, SearchError::Forbidden(_)));
+
+        let searcher_config = SearcherConfig {
+            legal_hold_searchers: vec![LegalHoldSearcher {
+                name: "legal-team".to_string(),
+                token: "secret".to_string(),
+            }],
+            ..Default::default()
+        };
+        let searcher_context = SearcherContext::new(searcher_config, None);
+
+        let mut unauthenticated_search_request = search_request.clone();
+        unauthenticated_search_request.legal_hold_token = Some("wrong-secret".to_string());
+        let error = root_search(
+            &searcher_context,
+            unauthenticated_search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, SearchError::Forbidden(_)));
+
+        let mut search_request = search_request;
+        search_request.legal_hold_token = Some("secret".to_string());
         let search_response = root_search(
             &searcher_context,
             search_request,
-            MetastoreServiceClient::from_mock(mock_metastore),
+            metastore,
             &cluster_client,
         )
         .await
         .unwrap();
-        assert_eq!(search_response.num_hits, 3);
-        assert_eq!(search_response.hits.len(), 3);
-        Ok(())
+        assert_eq!(search_response.num_hits, 2);
+        assert_eq!(search_response.hits.len(), 2);
     }
 
     #[tokio::test]
@@ -2461,6 +3786,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2480,6 +3806,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2502,6 +3829,101 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_search_partial_hits_phase_paginated() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let splits: Vec<Split> = (0..5)
+            .map(|split_idx| {
+                MockSplitBuilder::new(&format!("split{split_idx}"))
+                    .with_index_uid(&index_uid)
+                    .build()
+            })
+            .collect();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_splits()
+            .times(3)
+            .returning(move |list_splits_request| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                assert!(list_splits_query.sort_by_time_range_end_desc);
+                assert!(list_splits_query.offset.is_none());
+                assert!(list_splits_query.snapshot_timestamp.is_some());
+                assert_eq!(list_splits_query.limit, Some(2));
+
+                let page: Vec<Split> = splits
+                    .iter()
+                    .skip_while(|split| {
+                        list_splits_query
+                            .after_split_by_time_range_end_desc
+                            .as_ref()
+                            .map(|(_, split_id)| split.split_id() <= split_id.as_str())
+                            .unwrap_or(false)
+                    })
+                    .take(2)
+                    .cloned()
+                    .collect();
+                let splits_response = ListSplitsResponse::try_from_splits(page).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().times(3).returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let num_splits = leaf_search_req.split_offsets.len();
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: num_splits as u64,
+                    num_attempted_splits: num_splits as u64,
+                    ..Default::default()
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer);
+
+        let mut indexes_metas_for_leaf_search = IndexesMetasForLeafSearch::new();
+        indexes_metas_for_leaf_search.insert(
+            index_uid.clone(),
+            IndexMetasForLeafSearch {
+                index_uri: Uri::for_test("ram:///test-index"),
+                doc_mapper_str: "{}".to_string(),
+            },
+        );
+        let list_splits_query = ListSplitsQuery::for_index(index_uid);
+        let split_metadata_pages = list_split_metadata_pages(
+            list_splits_query,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            2,
+        );
+        let (leaf_search_response, split_metadatas) = search_partial_hits_phase_paginated(
+            &SearcherContext::for_test(),
+            &indexes_metas_for_leaf_search,
+            &search_request,
+            split_metadata_pages,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(leaf_search_response.num_hits, 5);
+        assert_eq!(leaf_search_response.num_attempted_splits, 5);
+
+        let split_ids: Vec<&str> = split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id())
+            .collect();
+        assert_eq!(
+            split_ids,
+            ["split0", "split1", "split2", "split3", "split4"]
+        );
+    }
+
     #[tokio::test]
     async fn test_root_search_multiple_splits_sort_heteregeneous_field_ascending(
     ) -> anyhow::Result<()> {
@@ -2571,6 +3993,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2612,6 +4035,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2753,6 +4177,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2794,6 +4219,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2939,6 +4365,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -2966,6 +4393,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -3059,6 +4487,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -3098,6 +4527,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -3180,6 +4610,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -3308,6 +4739,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -3398,6 +4830,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -3699,7 +5132,8 @@ mod tests {
         timestamp_range_extractor.start_timestamp = None;
         timestamp_range_extractor.end_timestamp = None;
         timestamp_range_extractor.visit(&unusual_bounds).unwrap();
-        assert_eq!(timestamp_range_extractor.start_timestamp, Some(1618353942));
+        // the excluded lower bound is not rounded up, as documents from X.001 to X.999 match
+        assert_eq!(timestamp_range_extractor.start_timestamp, Some(1618353941));
         assert_eq!(timestamp_range_extractor.end_timestamp, Some(1620283880));
 
         let wrong_field = quickwit_query::query_ast::RangeQuery {
@@ -3792,7 +5226,12 @@ mod tests {
             });
         mock_metastore
             .expect_list_splits()
-            .returning(move |_filter| {
+            .returning(move |list_splits_request| {
+                // Scroll requests list their splits in pages too.
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                assert_eq!(list_splits_query.limit, Some(LIST_SPLITS_PAGE_SIZE));
+
                 let splits = vec![
                     MockSplitBuilder::new("split1")
                         .with_index_uid(&index_uid)
@@ -3871,6 +5310,7 @@ mod tests {
                 assert!(fetch_docs_req.partial_hits.len() <= MAX_HITS_PER_PAGE);
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -4054,6 +5494,7 @@ mod tests {
                 assert!(fetch_docs_req.partial_hits.len() <= MAX_HITS_PER_PAGE_LARGE);
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             },
         );
@@ -4236,6 +5677,7 @@ mod tests {
             .returning(|fetch_docs_req| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    split_profiles: Vec::new(),
                 })
             });
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service_1)]);
@@ -22,6 +22,8 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::bail;
 use async_trait::async_trait;
@@ -31,6 +33,14 @@ use quickwit_proto::search::{ReportSplit, ReportSplitsRequest};
 
 use crate::{SearchJob, SearchServiceClient, SearcherPool};
 
+/// Period over which the jobs of a searcher node in maintenance are gradually shifted to the other
+/// nodes. Shifting the jobs progressively avoids a cache stampede on the remaining nodes.
+const DRAIN_PERIOD: Duration = if cfg!(test) {
+    Duration::from_secs(10)
+} else {
+    Duration::from_secs(60)
+};
+
 /// Job.
 /// The unit in which distributed search is performed.
 ///
@@ -61,6 +71,8 @@ pub trait Job {
 pub struct SearchJobPlacer {
     /// Search clients pool.
     searcher_pool: SearcherPool,
+    /// Searcher nodes in maintenance, along with the instant their draining started.
+    draining_nodes: Arc<Mutex<HashMap<SocketAddr, Instant>>>,
 }
 
 #[async_trait]
@@ -103,7 +115,39 @@ impl fmt::Debug for SearchJobPlacer {
 impl SearchJobPlacer {
     /// Returns an [`SearchJobPlacer`] from a search service client pool.
     pub fn new(searcher_pool: SearcherPool) -> Self {
-        Self { searcher_pool }
+        Self {
+            searcher_pool,
+            draining_nodes: Arc::default(),
+        }
+    }
+
+    /// Marks a searcher node as draining or not. The share of the jobs assigned to a draining node
+    /// decreases linearly over the drain period until the node stops receiving jobs altogether.
+    pub fn set_node_draining(&self, grpc_addr: SocketAddr, is_draining: bool) {
+        let mut draining_nodes = self
+            .draining_nodes
+            .lock()
+            .expect("the lock should not be poisoned");
+        if is_draining {
+            draining_nodes.entry(grpc_addr).or_insert_with(Instant::now);
+        } else {
+            draining_nodes.remove(&grpc_addr);
+        }
+    }
+
+    /// Returns the weight of each draining node, between 0 (fully drained) and 1.
+    fn drain_weights(&self) -> HashMap<SocketAddr, f32> {
+        let now = Instant::now();
+        self.draining_nodes
+            .lock()
+            .expect("the lock should not be poisoned")
+            .iter()
+            .map(|(grpc_addr, drain_start)| {
+                let drain_progress = now.saturating_duration_since(*drain_start).as_secs_f32()
+                    / DRAIN_PERIOD.as_secs_f32();
+                (*grpc_addr, (1.0 - drain_progress).max(0.0))
+            })
+            .collect()
     }
 }
 
@@ -150,6 +194,7 @@ impl SearchJobPlacer {
         excluded_addrs: &HashSet<SocketAddr>,
     ) -> anyhow::Result<impl Iterator<Item = (SearchServiceClient, Vec<J>)>> {
         let num_nodes = self.searcher_pool.len();
+        let drain_weights = self.drain_weights();
 
         let mut candidate_nodes: Vec<CandidateNodes> = self
             .searcher_pool
@@ -164,6 +209,7 @@ impl SearchJobPlacer {
                 grpc_addr,
                 client,
                 load: 0,
+                weight: drain_weights.get(&grpc_addr).copied().unwrap_or(1.0),
             })
             .collect();
 
@@ -179,8 +225,21 @@ impl SearchJobPlacer {
 
         for job in jobs {
             sort_by_rendez_vous_hash(&mut candidate_nodes, job.split_id());
+            // Draining nodes that no longer accept the job are moved to the back of the list while
+            // preserving the rendez-vous order. When no node accepts the job, we fall back to all
+            // the nodes.
+            candidate_nodes.sort_by_key(|node| !node.accepts_job(job.split_id()));
+            let num_accepting_nodes = candidate_nodes
+                .iter()
+                .take_while(|node| node.accepts_job(job.split_id()))
+                .count();
+            let num_eligible_nodes = if num_accepting_nodes == 0 {
+                candidate_nodes.len()
+            } else {
+                num_accepting_nodes
+            };
             // Select the least loaded node.
-            let chosen_node_idx = if candidate_nodes.len() >= 2 {
+            let chosen_node_idx = if num_eligible_nodes >= 2 {
                 usize::from(candidate_nodes[0].load > candidate_nodes[1].load)
             } else {
                 0
@@ -218,6 +277,20 @@ struct CandidateNodes {
     pub grpc_addr: SocketAddr,
     pub client: SearchServiceClient,
     pub load: usize,
+    pub weight: f32,
+}
+
+impl CandidateNodes {
+    /// Returns whether the node accepts the job. A draining node with a weight `w` accepts a
+    /// stable fraction `w` of the splits, so that each split moves at most once while the node is
+    /// being drained.
+    fn accepts_job(&self, split_id: &str) -> bool {
+        if self.weight >= 1.0 {
+            return true;
+        }
+        let drain_affinity = node_affinity(("drain", self.grpc_addr), &split_id);
+        (drain_affinity as f64 / u64::MAX as f64) < self.weight as f64
+    }
 }
 
 impl Hash for CandidateNodes {
@@ -411,4 +484,59 @@ mod tests {
             assert_eq!(assigned_jobs, expected_assigned_jobs);
         }
     }
+
+    #[tokio::test]
+    async fn test_search_job_placer_drains_nodes_gradually() {
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", MockSearchService::new()),
+            ("127.0.0.1:1002", MockSearchService::new()),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let draining_addr: SocketAddr = ([127, 0, 0, 1], 1001).into();
+
+        async fn count_jobs_assigned_to_node(
+            search_job_placer: &SearchJobPlacer,
+            grpc_addr: SocketAddr,
+        ) -> usize {
+            let jobs: Vec<SearchJob> = (0..100)
+                .map(|split_idx| SearchJob::for_test(&format!("split{split_idx}"), 1))
+                .collect();
+            search_job_placer
+                .assign_jobs(jobs, &HashSet::new())
+                .await
+                .unwrap()
+                .filter(|(client, _)| client.grpc_addr() == grpc_addr)
+                .map(|(_, jobs)| jobs.len())
+                .sum()
+        }
+        let num_jobs_before_drain =
+            count_jobs_assigned_to_node(&search_job_placer, draining_addr).await;
+        assert_eq!(num_jobs_before_drain, 50);
+
+        // A quarter of the drain period remains: the node only accepts about a quarter of the
+        // splits.
+        search_job_placer
+            .draining_nodes
+            .lock()
+            .unwrap()
+            .insert(draining_addr, Instant::now() - DRAIN_PERIOD * 3 / 4);
+        let num_jobs_during_drain =
+            count_jobs_assigned_to_node(&search_job_placer, draining_addr).await;
+        assert!(num_jobs_during_drain > 0);
+        assert!(num_jobs_during_drain < 40);
+
+        search_job_placer
+            .draining_nodes
+            .lock()
+            .unwrap()
+            .insert(draining_addr, Instant::now() - DRAIN_PERIOD);
+        let num_jobs_after_drain =
+            count_jobs_assigned_to_node(&search_job_placer, draining_addr).await;
+        assert_eq!(num_jobs_after_drain, 0);
+
+        search_job_placer.set_node_draining(draining_addr, false);
+        let num_jobs_after_maintenance =
+            count_jobs_assigned_to_node(&search_job_placer, draining_addr).await;
+        assert_eq!(num_jobs_after_maintenance, 50);
+    }
 }
@@ -0,0 +1,205 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use prost::Message;
+use quickwit_proto::search::{SearchPriority, SearchRequest, SearchResponse};
+use quickwit_storage::{MemorySizedCache, OwnedBytes};
+
+/// A cache memoizing root search responses, for clients that accept slightly stale results.
+///
+/// Only requests with a `max_staleness_secs` are cached. A cached response is served if it was
+/// computed at most `max_staleness_secs` seconds ago and the request does not bypass the cache.
+pub struct SearchResponseCache {
+    content: MemorySizedCache<CacheKey>,
+    // Reference point for the insertion instants stored along with the responses.
+    start_instant: Instant,
+}
+
+impl SearchResponseCache {
+    pub fn new(capacity: usize) -> SearchResponseCache {
+        SearchResponseCache {
+            content: MemorySizedCache::with_capacity_in_bytes(
+                capacity,
+                &quickwit_storage::STORAGE_METRICS.search_response_cache,
+            ),
+            start_instant: Instant::now(),
+        }
+    }
+
+    /// Returns whether the response to this request can be stored in the cache.
+    pub fn is_cacheable(search_request: &SearchRequest) -> bool {
+        // Scroll responses carry a scroll ID tied to a scroll context, they can't be shared.
+        // Profiled searches are meant to measure the actual execution of the search.
+        search_request.max_staleness_secs.is_some()
+            && search_request.scroll_ttl_secs.is_none()
+            && !search_request.profile
+    }
+
+    pub fn get(&self, search_request: &SearchRequest) -> Option<SearchResponse> {
+        self.get_at(search_request, Instant::now())
+    }
+
+    fn get_at(&self, search_request: &SearchRequest, now: Instant) -> Option<SearchResponse> {
+        if search_request.bypass_cache || !Self::is_cacheable(search_request) {
+            return None;
+        }
+        let max_staleness = Duration::from_secs(search_request.max_staleness_secs? as u64);
+        let key = CacheKey::from_request(search_request.clone());
+        let encoded_entry = self.content.get(&key)?;
+        let (inserted_at_millis_bytes, encoded_response) = encoded_entry.as_slice().split_at(8);
+        let inserted_at_millis = u64::from_le_bytes(inserted_at_millis_bytes.try_into().ok()?);
+        let inserted_at = self.start_instant + Duration::from_millis(inserted_at_millis);
+
+        if now.saturating_duration_since(inserted_at) > max_staleness {
+            return None;
+        }
+        // this should never fail
+        SearchResponse::decode(encoded_response).ok()
+    }
+
+    pub fn put(&self, search_request: SearchRequest, search_response: &SearchResponse) {
+        self.put_at(search_request, search_response, Instant::now())
+    }
+
+    fn put_at(
+        &self,
+        search_request: SearchRequest,
+        search_response: &SearchResponse,
+        now: Instant,
+    ) {
+        if !Self::is_cacheable(&search_request) {
+            return;
+        }
+        let key = CacheKey::from_request(search_request);
+        let inserted_at_millis = now.duration_since(self.start_instant).as_millis() as u64;
+
+        let mut encoded_entry = Vec::with_capacity(8 + search_response.encoded_len());
+        encoded_entry.extend_from_slice(&inserted_at_millis.to_le_bytes());
+        search_response
+            .encode(&mut encoded_entry)
+            .expect("buffer should have enough capacity");
+        self.content.put(key, OwnedBytes::new(encoded_entry));
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.content.set_capacity_in_bytes(capacity);
+    }
+}
+
+/// A key inside a [`SearchResponseCache`].
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct CacheKey {
+    /// The request this matches, stripped of its cache options.
+    request: SearchRequest,
+}
+
+impl CacheKey {
+    fn from_request(mut search_request: SearchRequest) -> Self {
+        // Requests accepting different stalenesses share the same entries.
+        search_request.bypass_cache = false;
+        search_request.max_staleness_secs = None;
+        // Responses do not depend on who issued the request, nor on how it was scheduled.
+        search_request.tenant_id = None;
+        search_request.priority = SearchPriority::Interactive as i32;
+        CacheKey {
+            request: search_request,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_response_cache() {
+        let cache = SearchResponseCache::new(64_000_000);
+        let now = Instant::now();
+
+        let search_request = SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: "{}".to_string(),
+            max_hits: 10,
+            max_staleness_secs: Some(30),
+            ..Default::default()
+        };
+        let search_response = SearchResponse {
+            num_hits: 42,
+            ..Default::default()
+        };
+        assert!(cache.get_at(&search_request, now).is_none());
+
+        cache.put_at(search_request.clone(), &search_response, now);
+        assert_eq!(
+            cache.get_at(&search_request, now + Duration::from_secs(30)),
+            Some(search_response.clone())
+        );
+        assert!(cache
+            .get_at(&search_request, now + Duration::from_secs(31))
+            .is_none());
+
+        let stricter_search_request = SearchRequest {
+            max_staleness_secs: Some(5),
+            ..search_request.clone()
+        };
+        assert_eq!(
+            cache.get_at(&stricter_search_request, now + Duration::from_secs(5)),
+            Some(search_response.clone())
+        );
+        assert!(cache
+            .get_at(&stricter_search_request, now + Duration::from_secs(6))
+            .is_none());
+
+        let bypass_search_request = SearchRequest {
+            bypass_cache: true,
+            ..search_request.clone()
+        };
+        assert!(cache.get_at(&bypass_search_request, now).is_none());
+
+        let other_search_request = SearchRequest {
+            max_hits: 20,
+            ..search_request.clone()
+        };
+        assert!(cache.get_at(&other_search_request, now).is_none());
+    }
+
+    #[test]
+    fn test_search_response_cache_ignores_non_cacheable_requests() {
+        let cache = SearchResponseCache::new(64_000_000);
+        let now = Instant::now();
+        let search_response = SearchResponse::default();
+
+        let search_request = SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            ..Default::default()
+        };
+        cache.put_at(search_request.clone(), &search_response, now);
+        assert!(cache.get_at(&search_request, now).is_none());
+
+        let scroll_search_request = SearchRequest {
+            scroll_ttl_secs: Some(60),
+            max_staleness_secs: Some(30),
+            ..search_request
+        };
+        cache.put_at(scroll_search_request.clone(), &search_response, now);
+        assert!(cache.get_at(&scroll_search_request, now).is_none());
+    }
+}
@@ -20,7 +20,7 @@
 use std::convert::TryFrom;
 
 use quickwit_common::truncate_str;
-use quickwit_proto::search::SearchResponse;
+use quickwit_proto::search::{IndexSearchFailure, SearchProfile, SearchResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -39,6 +39,10 @@ pub struct SearchResponseRest {
     #[schema(value_type = Vec<Object>)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub snippets: Option<Vec<JsonValue>>,
+    /// List of highlighted snippets, per field.
+    #[schema(value_type = Vec<Object>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<Vec<JsonValue>>,
     /// Elapsed time.
     pub elapsed_time_micros: u64,
     /// Search errors.
@@ -47,6 +51,14 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<JsonValue>,
+    /// Search profile.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<SearchProfile>,
+    /// Indexes matched by the request that could not be searched.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed_indexes: Vec<IndexSearchFailure>,
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
@@ -55,6 +67,7 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
     fn try_from(search_response: SearchResponse) -> Result<Self, Self::Error> {
         let mut documents = Vec::with_capacity(search_response.hits.len());
         let mut snippets = Vec::new();
+        let mut highlights = Vec::new();
         for hit in search_response.hits {
             let document: JsonValue = serde_json::from_str(&hit.json).map_err(|err| {
                 SearchError::Internal(format!(
@@ -74,6 +87,15 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
                     })?;
                 snippets.push(snippet_opt);
             }
+            if let Some(highlight_json) = hit.highlight {
+                let highlight: JsonValue =
+                    serde_json::from_str(&highlight_json).map_err(|err| {
+                        SearchError::Internal(format!(
+                            "failed to serialize highlight `{highlight_json}` to JSON: `{err}`"
+                        ))
+                    })?;
+                highlights.push(highlight);
+            }
         }
 
         let snippet_opt = if !snippets.is_empty() {
@@ -81,6 +103,11 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
         } else {
             None
         };
+        let highlights_opt = if !highlights.is_empty() {
+            Some(highlights)
+        } else {
+            None
+        };
 
         let aggregations_opt = if let Some(aggregation_json) = search_response.aggregation {
             let aggregation: JsonValue = serde_json::from_str(&aggregation_json)
@@ -94,9 +121,12 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             num_hits: search_response.num_hits,
             hits: documents,
             snippets: snippet_opt,
+            highlights: highlights_opt,
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            profile: search_response.profile,
+            failed_indexes: search_response.failed_indexes,
         })
     }
 }
@@ -42,6 +42,7 @@ use super::collector::{PartionnedFastFieldCollector, PartitionValues};
 use super::FastFieldCollector;
 use crate::filters::{create_timestamp_filter_builder, TimestampFilterBuilder};
 use crate::leaf::{open_index_with_caches, rewrite_start_end_time_bounds, warmup};
+use crate::query_limits::check_query_limits;
 use crate::service::SearcherContext;
 use crate::{Result, SearchError};
 
@@ -90,9 +91,7 @@ async fn leaf_search_results_stream(
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
 ) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Sync + Send + 'static {
-    let max_num_concurrent_split_streams = searcher_context
-        .searcher_config
-        .max_num_concurrent_split_streams;
+    let max_num_concurrent_split_streams = searcher_context.num_split_stream_permits();
     futures::stream::iter(splits)
         .map(move |split| {
             leaf_search_stream_single_split(
@@ -159,6 +158,7 @@ async fn leaf_search_stream_single_split(
     let search_request = Arc::new(SearchRequest::try_from(stream_request.clone())?);
     let query_ast = serde_json::from_str(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    check_query_limits(&query_ast, &searcher_context.searcher_config)?;
     let (query, mut warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
     let reader = index
         .reader_builder()
@@ -498,6 +498,7 @@ mod tests {
             fast_field: "ts".to_string(),
             output_format: 0,
             partition_by_field: None,
+            tenant_id: None,
         };
         let splits = test_sandbox
             .metastore()
@@ -577,6 +578,7 @@ mod tests {
             fast_field: "ts".to_string(),
             output_format: 0,
             partition_by_field: None,
+            tenant_id: None,
         };
         let splits = test_sandbox
             .metastore()
@@ -634,6 +636,7 @@ mod tests {
             fast_field: "app".to_string(),
             output_format: 0,
             partition_by_field: None,
+            tenant_id: None,
         };
         let splits = test_sandbox
             .metastore()
@@ -724,6 +727,7 @@ mod tests {
             fast_field: "fast_field".to_string(),
             output_format: 1,
             partition_by_field: Some(String::from("partition_by_fast_field")),
+            tenant_id: None,
         };
         let splits = test_sandbox
             .metastore()
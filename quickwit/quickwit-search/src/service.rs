@@ -19,37 +19,48 @@
 
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use quickwit_common::uri::Uri;
-use quickwit_config::SearcherConfig;
+use quickwit_config::{ClusterSettings, SearcherConfig};
 use quickwit_doc_mapper::DocMapper;
-use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::metastore::{DeleteQuery, MetastoreServiceClient};
 use quickwit_proto::search::{
     FetchDocsRequest, FetchDocsResponse, GetKvRequest, Hit, LeafListFieldsRequest,
     LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse,
     LeafSearchStreamRequest, LeafSearchStreamResponse, ListFieldsRequest, ListFieldsResponse,
-    ListTermsRequest, ListTermsResponse, PutKvRequest, ReportSplitsRequest, ReportSplitsResponse,
-    ScrollRequest, SearchRequest, SearchResponse, SearchStreamRequest, SnippetRequest,
+    ListTermsRequest, ListTermsResponse, OpenPointInTimeRequest, OpenPointInTimeResponse,
+    PutKvRequest, ReportSplitsRequest, ReportSplitsResponse, ScrollRequest, SearchPriority,
+    SearchRequest, SearchResponse, SearchStreamRequest, SnippetRequest,
 };
 use quickwit_storage::{
-    MemorySizedCache, QuickwitCache, SplitCache, StorageCache, StorageResolver,
+    wrap_storage_with_bandwidth_scheduler, BandwidthScheduler, MemorySizedCache, QuickwitCache,
+    ReadPriority, ReadToken, SplitCache, Storage, StorageCache, StorageResolver,
 };
 use tantivy::aggregation::AggregationLimits;
-use tokio::sync::Semaphore;
+use tokio::sync::{Mutex, Semaphore};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+use crate::external_splits::ExternalSplitMaterializer;
 use crate::leaf_cache::LeafSearchCache;
 use crate::list_fields::{leaf_list_fields, root_list_fields};
 use crate::list_fields_cache::ListFieldsCache;
 use crate::list_terms::{leaf_list_terms, root_list_terms};
+use crate::lookup_table::{apply_lookups_to_hits, LookupTableStore};
+use crate::query_access_stats::QueryAccessTracker;
 use crate::root::fetch_docs_phase;
 use crate::scroll_context::{MiniKV, ScrollContext, ScrollKeyAndStartOffset};
+use crate::search_response_cache::SearchResponseCache;
 use crate::search_stream::{leaf_search_stream, root_search_stream};
-use crate::{fetch_docs, leaf_search, root_search, ClusterClient, SearchError};
+use crate::term_digest_store::TermDigestStore;
+use crate::{
+    fetch_docs, leaf_search, open_point_in_time, preview_delete_query, root_search, ClusterClient,
+    DeleteQueryPreview, SearchError,
+};
 
 #[derive(Clone)]
 /// The search service implementation.
@@ -124,6 +135,21 @@ pub trait SearchService: 'static + Send + Sync {
     /// Performs a scroll request.
     async fn scroll(&self, scroll_request: ScrollRequest) -> crate::Result<SearchResponse>;
 
+    /// Captures the set of splits currently published for the requested indexes. Searches
+    /// issued with the returned point in time ID run against this frozen set of splits until the
+    /// point in time expires.
+    async fn open_point_in_time(
+        &self,
+        open_request: OpenPointInTimeRequest,
+    ) -> crate::Result<OpenPointInTimeResponse>;
+
+    /// Counts the documents a delete query would delete, split by split, without creating the
+    /// delete task.
+    async fn preview_delete_query(
+        &self,
+        delete_query: DeleteQuery,
+    ) -> crate::Result<DeleteQueryPreview>;
+
     /// Stores a Key value in the local cache.
     /// This operation is not distributed. The distribution logic lives in
     /// the `ClusterClient`.
@@ -166,6 +192,33 @@ impl SearchServiceImpl {
             search_after_cache: MiniKV::default(),
         }
     }
+
+    /// Resolves the storage of an index. Its reads are scheduled on behalf of the flow identified
+    /// by `read_token`.
+    async fn resolve_index_storage(
+        &self,
+        index_uri: &Uri,
+        read_token: ReadToken,
+    ) -> crate::Result<Arc<dyn Storage>> {
+        let storage = self.storage_resolver.resolve(index_uri).await?;
+        let scheduled_storage = wrap_storage_with_bandwidth_scheduler(
+            storage,
+            &self.searcher_context.storage_bandwidth_scheduler,
+            read_token,
+        );
+        Ok(scheduled_storage)
+    }
+}
+
+fn read_token_from_search_request(search_request: &SearchRequest) -> ReadToken {
+    let priority = match search_request.priority() {
+        SearchPriority::Interactive => ReadPriority::Interactive,
+        SearchPriority::Batch => ReadPriority::Batch,
+    };
+    ReadToken {
+        tenant_id: search_request.tenant_id.clone(),
+        priority,
+    }
 }
 
 fn deserialize_doc_mapper(doc_mapper_str: &str) -> crate::Result<Arc<dyn DocMapper>> {
@@ -197,7 +250,8 @@ impl SearchService for SearchServiceImpl {
             .ok_or_else(|| SearchError::Internal("no search request".to_string()))?
             .into();
         let index_uri = Uri::from_str(&leaf_search_request.index_uri)?;
-        let storage = self.storage_resolver.resolve(&index_uri).await?;
+        let read_token = read_token_from_search_request(&search_request);
+        let storage = self.resolve_index_storage(&index_uri, read_token).await?;
         let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
 
         let leaf_search_response = leaf_search(
@@ -217,7 +271,9 @@ impl SearchService for SearchServiceImpl {
         fetch_docs_request: FetchDocsRequest,
     ) -> crate::Result<FetchDocsResponse> {
         let index_uri = Uri::from_str(&fetch_docs_request.index_uri)?;
-        let storage = self.storage_resolver.resolve(&index_uri).await?;
+        let storage = self
+            .resolve_index_storage(&index_uri, ReadToken::default())
+            .await?;
         let snippet_request_opt: Option<&SnippetRequest> =
             fetch_docs_request.snippet_request.as_ref();
         let doc_mapper = deserialize_doc_mapper(&fetch_docs_request.doc_mapper)?;
@@ -228,6 +284,7 @@ impl SearchService for SearchServiceImpl {
             &fetch_docs_request.split_offsets,
             doc_mapper,
             snippet_request_opt,
+            fetch_docs_request.profile,
         )
         .await?;
 
@@ -255,7 +312,12 @@ impl SearchService for SearchServiceImpl {
             .request
             .ok_or_else(|| SearchError::Internal("no search request".to_string()))?;
         let index_uri = Uri::from_str(&leaf_stream_request.index_uri)?;
-        let storage = self.storage_resolver.resolve(&index_uri).await?;
+        // Stream requests are exports: they yield to interactive requests.
+        let read_token = ReadToken {
+            tenant_id: stream_request.tenant_id.clone(),
+            priority: ReadPriority::Batch,
+        };
+        let storage = self.resolve_index_storage(&index_uri, read_token).await?;
         let doc_mapper = deserialize_doc_mapper(&leaf_stream_request.doc_mapper)?;
         let leaf_receiver = leaf_search_stream(
             self.searcher_context.clone(),
@@ -290,7 +352,9 @@ impl SearchService for SearchServiceImpl {
             .list_terms_request
             .ok_or_else(|| SearchError::Internal("no search request".to_string()))?;
         let index_uri = Uri::from_str(&leaf_search_request.index_uri)?;
-        let storage = self.storage_resolver.resolve(&index_uri).await?;
+        let storage = self
+            .resolve_index_storage(&index_uri, ReadToken::default())
+            .await?;
         let split_ids = leaf_search_request.split_offsets;
 
         let leaf_search_response = leaf_list_terms(
@@ -308,6 +372,30 @@ impl SearchService for SearchServiceImpl {
         scroll(scroll_request, &self.cluster_client, &self.searcher_context).await
     }
 
+    async fn open_point_in_time(
+        &self,
+        open_request: OpenPointInTimeRequest,
+    ) -> crate::Result<OpenPointInTimeResponse> {
+        open_point_in_time(
+            open_request,
+            &mut self.metastore.clone(),
+            &self.cluster_client,
+        )
+        .await
+    }
+
+    async fn preview_delete_query(
+        &self,
+        delete_query: DeleteQuery,
+    ) -> crate::Result<DeleteQueryPreview> {
+        preview_delete_query(
+            delete_query,
+            &mut self.metastore.clone(),
+            &self.cluster_client,
+        )
+        .await
+    }
+
     async fn put_kv(&self, put_request: PutKvRequest) {
         let ttl = Duration::from_secs(put_request.ttl_secs as u64);
         self.search_after_cache
@@ -344,7 +432,9 @@ impl SearchService for SearchServiceImpl {
         list_fields_req: LeafListFieldsRequest,
     ) -> crate::Result<ListFieldsResponse> {
         let index_uri = Uri::from_str(&list_fields_req.index_uri)?;
-        let storage = self.storage_resolver.resolve(&index_uri).await?;
+        let storage = self
+            .resolve_index_storage(&index_uri, ReadToken::default())
+            .await?;
         let index_id = list_fields_req.index_id;
         let split_ids = list_fields_req.split_offsets;
         leaf_list_fields(
@@ -396,7 +486,7 @@ pub(crate) async fn scroll(
     }
 
     // Fetch the actual documents.
-    let hits: Vec<Hit> = fetch_docs_phase(
+    let (mut hits, _): (Vec<Hit>, _) = fetch_docs_phase(
         &scroll_context.indexes_metas_for_leaf_search,
         &partial_hits[..],
         &scroll_context.split_metadatas[..],
@@ -404,6 +494,12 @@ pub(crate) async fn scroll(
         cluster_client,
     )
     .await?;
+    apply_lookups_to_hits(
+        searcher_context.lookup_table_store_opt.as_deref(),
+        &scroll_context.search_request.lookups,
+        &mut hits,
+    )
+    .await?;
 
     let next_scroll_id = current_scroll.next_page(
         hits.len() as u64,
@@ -428,6 +524,8 @@ pub(crate) async fn scroll(
         scroll_id: Some(next_scroll_id.to_string()),
         errors: Vec::new(),
         aggregation: None,
+        profile: None,
+        failed_indexes: Vec::new(),
     })
 }
 /// [`SearcherContext`] provides a common set of variables
@@ -450,6 +548,28 @@ pub struct SearcherContext {
     pub split_cache_opt: Option<Arc<SplitCache>>,
     /// List fields cache. Caches the list fields response for a given split.
     pub list_fields_cache: ListFieldsCache,
+    /// Root search response cache, for requests accepting stale results.
+    pub search_response_cache: SearchResponseCache,
+    /// Shares the storage read bandwidth between tenants and priorities.
+    pub storage_bandwidth_scheduler: BandwidthScheduler,
+    /// Lookup tables referenced by the search requests. `None` if lookup tables are not
+    /// available on this searcher.
+    pub lookup_table_store_opt: Option<Arc<LookupTableStore>>,
+    /// Term digests of the splits, used by root searches to prune splits. `None` if splits are
+    /// not pruned with term digests on this searcher.
+    pub term_digest_store_opt: Option<Arc<TermDigestStore>>,
+    /// Materializes the files of lazy Parquet sources into splits. `None` if lazy sources cannot
+    /// be searched from this searcher.
+    pub external_split_materializer_opt: Option<Arc<dyn ExternalSplitMaterializer>>,
+    /// Query access statistics of the indexes searched by the root searches of this node.
+    pub query_access_tracker: QueryAccessTracker,
+    /// Number of permits of the leaf search split semaphore, which differs from the searcher
+    /// config when overridden by the cluster settings.
+    num_split_search_permits: AtomicUsize,
+    /// Number of permits of the split stream semaphore.
+    num_split_stream_permits: AtomicUsize,
+    /// Serializes the application of cluster settings.
+    cluster_settings_lock: Mutex<()>,
 }
 
 impl std::fmt::Debug for SearcherContext {
@@ -479,17 +599,21 @@ impl SearcherContext {
             capacity_in_bytes,
             &quickwit_storage::STORAGE_METRICS.split_footer_cache,
         );
-        let leaf_search_split_semaphore = Arc::new(Semaphore::new(
-            searcher_config.max_num_concurrent_split_searches,
-        ));
-        let split_stream_semaphore =
-            Semaphore::new(searcher_config.max_num_concurrent_split_streams);
+        let num_split_search_permits = searcher_config.max_num_concurrent_split_searches;
+        let leaf_search_split_semaphore = Arc::new(Semaphore::new(num_split_search_permits));
+        let num_split_stream_permits = searcher_config.max_num_concurrent_split_streams;
+        let split_stream_semaphore = Semaphore::new(num_split_stream_permits);
         let fast_field_cache_capacity = searcher_config.fast_field_cache_capacity.as_u64() as usize;
         let storage_long_term_cache = Arc::new(QuickwitCache::new(fast_field_cache_capacity));
         let leaf_search_cache =
             LeafSearchCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
         let list_fields_cache =
             ListFieldsCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
+        let search_response_cache = SearchResponseCache::new(
+            searcher_config.partial_request_cache_capacity.as_u64() as usize,
+        );
+        let storage_bandwidth_scheduler =
+            BandwidthScheduler::new(searcher_config.max_storage_read_bandwidth);
 
         Self {
             searcher_config,
@@ -499,10 +623,95 @@ impl SearcherContext {
             split_stream_semaphore,
             leaf_search_cache,
             list_fields_cache,
+            search_response_cache,
+            storage_bandwidth_scheduler,
+            lookup_table_store_opt: None,
+            term_digest_store_opt: None,
+            external_split_materializer_opt: None,
+            query_access_tracker: QueryAccessTracker::default(),
             split_cache_opt,
+            num_split_search_permits: AtomicUsize::new(num_split_search_permits),
+            num_split_stream_permits: AtomicUsize::new(num_split_stream_permits),
+            cluster_settings_lock: Mutex::new(()),
         }
     }
 
+    /// Sets the store of the lookup tables referenced by the search requests.
+    pub fn with_lookup_table_store(mut self, lookup_table_store: Arc<LookupTableStore>) -> Self {
+        self.lookup_table_store_opt = Some(lookup_table_store);
+        self
+    }
+
+    /// Sets the store of the term digests used to prune splits.
+    pub fn with_term_digest_store(mut self, term_digest_store: Arc<TermDigestStore>) -> Self {
+        self.term_digest_store_opt = Some(term_digest_store);
+        self
+    }
+
+    /// Sets the materializer of the files of the lazy Parquet sources.
+    pub fn with_external_split_materializer(
+        mut self,
+        external_split_materializer: Arc<dyn ExternalSplitMaterializer>,
+    ) -> Self {
+        self.external_split_materializer_opt = Some(external_split_materializer);
+        self
+    }
+
+    /// Returns the current maximum number of concurrent split searches.
+    pub fn num_split_search_permits(&self) -> usize {
+        self.num_split_search_permits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the current maximum number of concurrent split streams.
+    pub fn num_split_stream_permits(&self) -> usize {
+        self.num_split_stream_permits.load(Ordering::Relaxed)
+    }
+
+    /// Applies the searcher settings of the cluster settings. Settings that are not set fall back
+    /// to the searcher config, so removing an override restores the value of the node config.
+    ///
+    /// Shrinking the number of concurrent split searches or streams waits for the permits in
+    /// use to be released.
+    pub async fn apply_cluster_settings(&self, cluster_settings: &ClusterSettings) {
+        let _guard = self.cluster_settings_lock.lock().await;
+
+        let split_footer_cache_capacity = cluster_settings
+            .split_footer_cache_capacity
+            .unwrap_or(self.searcher_config.split_footer_cache_capacity);
+        self.split_footer_cache
+            .set_capacity_in_bytes(split_footer_cache_capacity.as_u64() as usize);
+
+        let partial_request_cache_capacity = cluster_settings
+            .partial_request_cache_capacity
+            .unwrap_or(self.searcher_config.partial_request_cache_capacity);
+        self.leaf_search_cache
+            .set_capacity(partial_request_cache_capacity.as_u64() as usize);
+        self.list_fields_cache
+            .set_capacity(partial_request_cache_capacity.as_u64() as usize);
+        self.search_response_cache
+            .set_capacity(partial_request_cache_capacity.as_u64() as usize);
+
+        let num_split_search_permits = cluster_settings
+            .max_num_concurrent_split_searches
+            .unwrap_or(self.searcher_config.max_num_concurrent_split_searches);
+        resize_semaphore(
+            &self.leaf_search_split_semaphore,
+            &self.num_split_search_permits,
+            num_split_search_permits,
+        )
+        .await;
+
+        let num_split_stream_permits = cluster_settings
+            .max_num_concurrent_split_streams
+            .unwrap_or(self.searcher_config.max_num_concurrent_split_streams);
+        resize_semaphore(
+            &self.split_stream_semaphore,
+            &self.num_split_stream_permits,
+            num_split_stream_permits,
+        )
+        .await;
+    }
+
     /// Returns a new instance to track the aggregation memory usage.
     pub fn get_aggregation_limits(&self) -> AggregationLimits {
         AggregationLimits::new(
@@ -511,3 +720,102 @@ impl SearcherContext {
         )
     }
 }
+
+/// Grows or shrinks the number of permits of a semaphore. Shrinking waits for the permits in use
+/// to be released.
+async fn resize_semaphore(
+    semaphore: &Semaphore,
+    num_permits: &AtomicUsize,
+    target_num_permits: usize,
+) {
+    let current_num_permits = num_permits.load(Ordering::Relaxed);
+
+    if target_num_permits > current_num_permits {
+        semaphore.add_permits(target_num_permits - current_num_permits);
+    } else if target_num_permits < current_num_permits {
+        let num_permits_to_forget = (current_num_permits - target_num_permits) as u32;
+        semaphore
+            .acquire_many(num_permits_to_forget)
+            .await
+            .expect("semaphore should not be closed")
+            .forget();
+    }
+    num_permits.store(target_num_permits, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_searcher_context_apply_cluster_settings() {
+        let searcher_config = SearcherConfig {
+            max_num_concurrent_split_searches: 10,
+            max_num_concurrent_split_streams: 5,
+            ..Default::default()
+        };
+        let searcher_context = SearcherContext::new(searcher_config, None);
+
+        let cluster_settings = ClusterSettings {
+            max_num_concurrent_split_searches: Some(20),
+            max_num_concurrent_split_streams: Some(2),
+            split_footer_cache_capacity: Some(ByteSize::mb(1)),
+            ..Default::default()
+        };
+        searcher_context
+            .apply_cluster_settings(&cluster_settings)
+            .await;
+        assert_eq!(searcher_context.num_split_search_permits(), 20);
+        assert_eq!(
+            searcher_context
+                .leaf_search_split_semaphore
+                .available_permits(),
+            20
+        );
+        assert_eq!(searcher_context.num_split_stream_permits(), 2);
+        assert_eq!(
+            searcher_context.split_stream_semaphore.available_permits(),
+            2
+        );
+
+        // Removing the overrides restores the searcher config.
+        searcher_context
+            .apply_cluster_settings(&ClusterSettings::default())
+            .await;
+        assert_eq!(searcher_context.num_split_search_permits(), 10);
+        assert_eq!(
+            searcher_context
+                .leaf_search_split_semaphore
+                .available_permits(),
+            10
+        );
+        assert_eq!(searcher_context.num_split_stream_permits(), 5);
+        assert_eq!(
+            searcher_context.split_stream_semaphore.available_permits(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resize_semaphore_waits_for_permits_in_use() {
+        let semaphore = Arc::new(Semaphore::new(4));
+        let num_permits = Arc::new(AtomicUsize::new(4));
+        let permits = semaphore.clone().acquire_many_owned(3).await.unwrap();
+
+        let semaphore_clone = semaphore.clone();
+        let num_permits_clone = num_permits.clone();
+        let resize_handle = tokio::spawn(async move {
+            resize_semaphore(&semaphore_clone, &num_permits_clone, 1).await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!resize_handle.is_finished());
+        assert_eq!(semaphore.available_permits(), 0);
+
+        drop(permits);
+        resize_handle.await.unwrap();
+        assert_eq!(num_permits.load(Ordering::Relaxed), 1);
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+}
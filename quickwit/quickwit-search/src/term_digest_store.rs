@@ -0,0 +1,217 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use bytesize::ByteSize;
+use futures::StreamExt;
+use quickwit_common::rate_limited_warn;
+use quickwit_common::uri::Uri;
+use quickwit_doc_mapper::term_digest::{can_match_term_digests, prunable_fields, SplitTermDigests};
+use quickwit_metastore::SplitMetadata;
+use quickwit_query::query_ast::QueryAst;
+use quickwit_storage::{MemorySizedCache, StorageResolver};
+use tracing::debug;
+
+use crate::root::IndexesMetasForLeafSearch;
+
+/// Default capacity of the cache of term digests.
+const DEFAULT_TERM_DIGESTS_CACHE_CAPACITY: ByteSize = ByteSize::mib(256);
+
+/// Maximum number of term digests files downloaded concurrently by a root search.
+const MAX_CONCURRENT_TERM_DIGESTS_DOWNLOADS: usize = 32;
+
+/// Loads the term digests of the splits, stored next to the split files, and caches them in
+/// memory. Term digests are immutable, so they are cached by split ID.
+pub struct TermDigestStore {
+    storage_resolver: StorageResolver,
+    cache: MemorySizedCache<String>,
+}
+
+impl TermDigestStore {
+    /// Creates a term digest store resolving the storages of the indexes with `storage_resolver`.
+    pub fn new(storage_resolver: StorageResolver) -> Self {
+        let cache = MemorySizedCache::with_capacity_in_bytes(
+            DEFAULT_TERM_DIGESTS_CACHE_CAPACITY.as_u64() as usize,
+            &quickwit_storage::STORAGE_METRICS.term_digests_cache,
+        );
+        Self {
+            storage_resolver,
+            cache,
+        }
+    }
+
+    async fn load_term_digests(
+        &self,
+        index_uri: &Uri,
+        split_id: &str,
+    ) -> anyhow::Result<SplitTermDigests> {
+        if let Some(term_digests_bytes) = self.cache.get(split_id) {
+            return SplitTermDigests::open(term_digests_bytes);
+        }
+        let storage = self.storage_resolver.resolve(index_uri).await?;
+        let term_digests_path = PathBuf::from(quickwit_common::term_digests_file(split_id));
+        let term_digests_bytes = storage
+            .get_all(&term_digests_path)
+            .await
+            .with_context(|| format!("failed to fetch term digests of split `{split_id}`"))?;
+        let term_digests = SplitTermDigests::open(term_digests_bytes.clone())
+            .with_context(|| format!("failed to open term digests of split `{split_id}`"))?;
+        self.cache.put(split_id.to_string(), term_digests_bytes);
+        Ok(term_digests)
+    }
+
+    /// Removes the splits whose term digests prove that they cannot match the query. The digests
+    /// of a split are only loaded if the query searches some of its digest fields. Splits whose
+    /// digests cannot be loaded are kept.
+    pub(crate) async fn prune_splits(
+        &self,
+        indexes_metas: &IndexesMetasForLeafSearch,
+        query_ast: &QueryAst,
+        split_metadatas: &mut Vec<SplitMetadata>,
+    ) {
+        let query_fields = prunable_fields(query_ast);
+
+        if query_fields.is_empty() {
+            return;
+        }
+        let can_match_futures = split_metadatas.iter().map(|split_metadata| async {
+            let searches_digest_field = split_metadata
+                .term_digest_fields
+                .iter()
+                .any(|field| query_fields.contains(field.as_str()));
+            if !searches_digest_field {
+                return true;
+            }
+            let Some(index_metas) = indexes_metas.get(&split_metadata.index_uid) else {
+                return true;
+            };
+            match self
+                .load_term_digests(&index_metas.index_uri, &split_metadata.split_id)
+                .await
+            {
+                Ok(term_digests) => can_match_term_digests(query_ast, &term_digests),
+                Err(error) => {
+                    rate_limited_warn!(limit_per_min = 10, "{error:#}");
+                    true
+                }
+            }
+        });
+        let can_match: Vec<bool> = futures::stream::iter(can_match_futures)
+            .buffered(MAX_CONCURRENT_TERM_DIGESTS_DOWNLOADS)
+            .collect()
+            .await;
+
+        let num_splits_before = split_metadatas.len();
+        let mut can_match_iter = can_match.into_iter();
+        split_metadatas.retain(|_| can_match_iter.next().unwrap_or(true));
+        let num_pruned_splits = num_splits_before - split_metadatas.len();
+
+        if num_pruned_splits > 0 {
+            debug!(num_pruned_splits, "pruned splits with term digests");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use quickwit_doc_mapper::term_digest::TermDigest;
+    use quickwit_proto::types::IndexUid;
+    use quickwit_query::query_ast::qast_helper;
+
+    use super::*;
+    use crate::root::IndexMetasForLeafSearch;
+
+    #[tokio::test]
+    async fn test_term_digest_store_prune_splits() {
+        let storage_resolver = StorageResolver::for_test();
+        let index_uri = Uri::for_test("ram:///indexes/test-index");
+        let storage = storage_resolver.resolve(&index_uri).await.unwrap();
+        let index_uid = IndexUid::for_test("test-index", 0);
+
+        let mut split_metadatas = Vec::new();
+
+        for split_idx in 0..3 {
+            let split_id = format!("split-{split_idx}");
+            let mut term_digest = TermDigest::with_num_terms(1);
+            term_digest.insert(format!("trace-{split_idx}").as_bytes());
+            let term_digests = BTreeMap::from([("trace_id".to_string(), term_digest)]);
+            let term_digests_bytes = SplitTermDigests::serialize(&term_digests);
+
+            // The digests of the last split are missing, so it is never pruned.
+            if split_idx < 2 {
+                storage
+                    .put(
+                        &PathBuf::from(quickwit_common::term_digests_file(&split_id)),
+                        Box::new(term_digests_bytes),
+                    )
+                    .await
+                    .unwrap();
+            }
+            split_metadatas.push(SplitMetadata {
+                split_id,
+                index_uid: index_uid.clone(),
+                term_digest_fields: ["trace_id".to_string()].into_iter().collect(),
+                ..Default::default()
+            });
+        }
+        // A split without digests is never pruned either.
+        split_metadatas.push(SplitMetadata {
+            split_id: "split-without-digests".to_string(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        });
+        let indexes_metas = HashMap::from([(
+            index_uid,
+            IndexMetasForLeafSearch {
+                index_uri,
+                doc_mapper_str: String::new(),
+            },
+        )]);
+        let term_digest_store = TermDigestStore::new(storage_resolver);
+
+        let mut pruned_split_metadatas = split_metadatas.clone();
+        term_digest_store
+            .prune_splits(
+                &indexes_metas,
+                &qast_helper("trace_id:trace-1", &[]),
+                &mut pruned_split_metadatas,
+            )
+            .await;
+        let split_ids: Vec<&str> = pruned_split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id())
+            .collect();
+        assert_eq!(split_ids, ["split-1", "split-2", "split-without-digests"]);
+
+        // Queries that do not search the digest fields do not load any digest.
+        let mut pruned_split_metadatas = split_metadatas.clone();
+        term_digest_store
+            .prune_splits(
+                &indexes_metas,
+                &qast_helper("service:foo", &[]),
+                &mut pruned_split_metadatas,
+            )
+            .await;
+        assert_eq!(pruned_split_metadatas.len(), 4);
+    }
+}
@@ -132,6 +132,66 @@ async fn test_single_node_termset() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_search_profile() -> anyhow::Result<()> {
+    let index_id = "single-node-search-profile";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+              - name: views
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "snoopy", "body": "Snoopy is an anthropomorphic beagle.", "views": 10}),
+        json!({"title": "beagle", "body": "The beagle is a breed of small scent hound.", "views": 20}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("beagle", &["body"]),
+        aggregation_request: Some(r#"{"max_views": {"max": {"field": "views"}}}"#.to_string()),
+        max_hits: 2,
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request.clone(),
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(search_response.num_hits, 2);
+    assert!(search_response.profile.is_none());
+
+    let search_response = single_node_search(
+        SearchRequest {
+            profile: true,
+            ..search_request
+        },
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(search_response.num_hits, 2);
+    let profile = search_response.profile.unwrap();
+    assert_eq!(profile.num_splits, 1);
+    assert_eq!(profile.splits.len(), 1);
+    assert_eq!(profile.num_leaf_cache_hits, 0);
+
+    let split_profile = &profile.splits[0];
+    assert!(!split_profile.split_id.is_empty());
+    assert!(!split_profile.leaf_cache_hit);
+    assert!(split_profile.num_bytes_read > 0);
+    assert!(split_profile.num_storage_requests > 0);
+    assert_eq!(profile.num_bytes_read, split_profile.num_bytes_read);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_search_with_snippet() -> anyhow::Result<()> {
     let index_id = "single-node-with-snippet";
@@ -1404,6 +1464,335 @@ async fn test_single_node_aggregation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_percentiles_aggregation_across_splits() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-percentiles";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: latency_ms
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[]).await?;
+    // Each batch of documents ends up in its own split, so the leaves return one sketch per split
+    // which the root has to merge.
+    for split_ord in 0..3u64 {
+        let docs: Vec<JsonValue> = (1..=100u64)
+            .map(|latency_ms| json!({"latency_ms": split_ord * 100 + latency_ms}))
+            .collect();
+        test_sandbox.add_documents(docs).await?;
+    }
+    let agg_req = r#"
+ {
+   "latencies": {
+     "percentiles": {
+       "field": "latency_ms",
+       "percents": [50, 95, 99]
+     }
+   },
+   "latencies_unkeyed": {
+     "percentiles": {
+       "field": "latency_ms",
+       "percents": [50],
+       "keyed": false
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 300);
+
+    let agg_res_json: JsonValue = serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+    // Small t-digests keep every value, so the merged percentiles are close to the exact ones.
+    for (percent_key, expected_value) in [("50.0", 150.0), ("95.0", 285.0), ("99.0", 297.0)] {
+        let value = agg_res_json["latencies"]["values"][percent_key]
+            .as_f64()
+            .unwrap();
+        assert!(
+            (value - expected_value).abs() <= expected_value * 0.02,
+            "percentile {percent_key}: expected ~{expected_value}, got {value}"
+        );
+    }
+    let unkeyed_values = agg_res_json["latencies_unkeyed"]["values"]
+        .as_array()
+        .unwrap();
+    assert_eq!(unkeyed_values.len(), 1);
+    assert_eq!(unkeyed_values[0]["key"], 50.0);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_top_hits_aggregation_across_splits() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-top-hits";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: severity
+                type: text
+                tokenizer: raw
+              - name: message
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: timestamp
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["severity"]).await?;
+    // The latest error of each service is not in the same split, so the root has to merge the
+    // top hits of each bucket returned by the leaves.
+    test_sandbox
+        .add_documents(vec![
+            json!({"service": "api", "severity": "ERROR", "message": "api-1", "timestamp": 1}),
+            json!({"service": "api", "severity": "ERROR", "message": "api-4", "timestamp": 4}),
+            json!({"service": "db", "severity": "ERROR", "message": "db-5", "timestamp": 5}),
+            json!({"service": "db", "severity": "INFO", "message": "db-9", "timestamp": 9}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"service": "api", "severity": "ERROR", "message": "api-2", "timestamp": 2}),
+            json!({"service": "db", "severity": "ERROR", "message": "db-3", "timestamp": 3}),
+            json!({"service": "db", "severity": "ERROR", "message": "db-6", "timestamp": 6}),
+        ])
+        .await?;
+    let agg_req = r#"
+ {
+   "services": {
+     "terms": {
+       "field": "service",
+       "order": { "_key": "asc" }
+     },
+     "aggs": {
+       "latest_errors": {
+         "top_hits": {
+           "size": 2,
+           "sort": [{ "timestamp": "desc" }],
+           "docvalue_fields": ["message"]
+         }
+       }
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("severity:ERROR", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 6);
+
+    let agg_res_json: JsonValue = serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+    let buckets = agg_res_json["services"]["buckets"].as_array().unwrap();
+    assert_eq!(buckets.len(), 2);
+
+    let latest_messages = |bucket: &JsonValue| -> Vec<String> {
+        bucket["latest_errors"]["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| {
+                hit["docvalue_fields"]["message"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    };
+    assert_eq!(buckets[0]["key"], "api");
+    assert_eq!(latest_messages(&buckets[0]), ["api-4", "api-2"]);
+    assert_eq!(buckets[1]["key"], "db");
+    assert_eq!(latest_messages(&buckets[1]), ["db-6", "db-5"]);
+
+    // Tantivy's top hits aggregation does not score documents, so sorting by score is rejected.
+    let agg_req = r#"
+ {
+   "services": {
+     "terms": { "field": "service" },
+     "aggs": {
+       "most_relevant_errors": {
+         "top_hits": {
+           "size": 2,
+           "sort": [{ "_score": "desc" }]
+         }
+       }
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("severity:ERROR", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_error = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    let SearchError::InvalidAggregationRequest(error_msg) = single_node_error else {
+        panic!("expected an invalid aggregation request error, got {single_node_error:?}");
+    };
+    assert_eq!(
+        error_msg,
+        "top_hits aggregation `most_relevant_errors` cannot be sorted by `_score`: top hits can \
+         only be sorted by fast fields"
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_cardinality_aggregation_across_splits() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-cardinality";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: user_id
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: status
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[]).await?;
+    // The users of consecutive splits overlap, so the sketches of the splits must be merged rather
+    // than summed.
+    for split_ord in 0..3u64 {
+        let docs: Vec<JsonValue> = (0..1_000u64)
+            .map(|user_ord| {
+                json!({
+                    "user_id": format!("user-{}", split_ord * 500 + user_ord),
+                    "status": 200 + user_ord % 3,
+                })
+            })
+            .collect();
+        test_sandbox.add_documents(docs).await?;
+    }
+    let agg_req = r#"
+ {
+   "unique_users": {
+     "cardinality": {
+       "field": "user_id",
+       "precision_threshold": 10000
+     }
+   },
+   "unique_statuses": {
+     "cardinality": {
+       "field": "status"
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 3_000);
+
+    let agg_res_json: JsonValue = serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+    let unique_users = agg_res_json["unique_users"]["value"].as_u64().unwrap();
+    assert!(
+        (1_960..=2_040).contains(&unique_users),
+        "expected ~2000 unique users, got {unique_users}"
+    );
+    assert_eq!(agg_res_json["unique_statuses"]["value"], 3);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_nested_cardinality_aggregation() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-nested-cardinality";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: user_id
+                type: text
+                tokenizer: raw
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[]).await?;
+    test_sandbox
+        .add_documents(vec![json!({"service": "api", "user_id": "alice"})])
+        .await?;
+    let agg_req = r#"
+ {
+   "services": {
+     "terms": {
+       "field": "service"
+     },
+     "aggs": {
+       "unique_users": {
+         "cardinality": {
+           "field": "user_id"
+         }
+       }
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_error = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    let SearchError::InvalidAggregationRequest(error_msg) = single_node_error else {
+        panic!("expected an invalid aggregation request error, got {single_node_error:?}");
+    };
+    assert_eq!(
+        error_msg,
+        "cardinality aggregation `unique_users` is not supported: cardinality aggregations cannot \
+         be nested under bucket aggregations nor combined with other kinds of aggregations"
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_aggregation_missing_fast_field() {
     let index_id = "single-node-agg-2";
@@ -0,0 +1,272 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use quickwit_actors::Mailbox;
+use quickwit_cluster::{Cluster, ListenerHandle};
+use quickwit_config::ClusterSettings;
+use quickwit_control_plane::control_plane::{ApplyClusterSettings, ControlPlane};
+use quickwit_proto::metastore::{
+    serde_utils, GetClusterSettingsRequest, MetastoreResult, MetastoreService,
+    MetastoreServiceClient,
+};
+use quickwit_search::SearcherContext;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+/// Chitchat key under which a node advertises the last cluster settings it has written to the
+/// metastore.
+pub(crate) const CLUSTER_SETTINGS_KEY: &str = "cluster_settings";
+
+/// Cluster settings along with their version, which is incremented by the metastore on every
+/// update.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct VersionedClusterSettings {
+    pub version: u64,
+    pub settings: ClusterSettings,
+}
+
+/// Applies the cluster settings to the services running on the node. Settings are applied in
+/// version order: stale settings received through gossip are ignored.
+#[derive(Clone, Default)]
+pub(crate) struct ClusterSettingsApplier {
+    searcher_context_opt: Option<Arc<SearcherContext>>,
+    control_plane_mailbox_opt: Option<Mailbox<ControlPlane>>,
+    applied_version: Arc<Mutex<u64>>,
+}
+
+impl ClusterSettingsApplier {
+    pub fn new(
+        searcher_context_opt: Option<Arc<SearcherContext>>,
+        control_plane_mailbox_opt: Option<Mailbox<ControlPlane>>,
+    ) -> Self {
+        Self {
+            searcher_context_opt,
+            control_plane_mailbox_opt,
+            applied_version: Arc::default(),
+        }
+    }
+
+    /// Applies the settings if they are more recent than the last settings applied. Returns
+    /// whether the settings were applied.
+    pub async fn apply(&self, versioned_cluster_settings: &VersionedClusterSettings) -> bool {
+        let mut applied_version_guard = self.applied_version.lock().await;
+
+        if versioned_cluster_settings.version <= *applied_version_guard {
+            return false;
+        }
+        info!(
+            version = versioned_cluster_settings.version,
+            "applying cluster settings"
+        );
+        let cluster_settings = &versioned_cluster_settings.settings;
+
+        if let Some(searcher_context) = &self.searcher_context_opt {
+            searcher_context
+                .apply_cluster_settings(cluster_settings)
+                .await;
+        }
+        if let Some(control_plane_mailbox) = &self.control_plane_mailbox_opt {
+            let apply_cluster_settings = ApplyClusterSettings(cluster_settings.clone());
+
+            if let Err(error) = control_plane_mailbox
+                .send_message(apply_cluster_settings)
+                .await
+            {
+                warn!(%error, "failed to apply cluster settings to the control plane");
+            }
+        }
+        *applied_version_guard = versioned_cluster_settings.version;
+        true
+    }
+}
+
+/// Fetches the cluster settings from the metastore.
+pub(crate) async fn load_cluster_settings(
+    metastore: &mut MetastoreServiceClient,
+) -> MetastoreResult<VersionedClusterSettings> {
+    let response = metastore
+        .get_cluster_settings(GetClusterSettingsRequest {})
+        .await?;
+    let settings: ClusterSettings = serde_utils::from_json_str(&response.cluster_settings_json)?;
+    let versioned_cluster_settings = VersionedClusterSettings {
+        version: response.version,
+        settings,
+    };
+    Ok(versioned_cluster_settings)
+}
+
+/// Advertises the cluster settings to the other nodes of the cluster.
+pub(crate) async fn publish_cluster_settings(
+    cluster: &Cluster,
+    versioned_cluster_settings: &VersionedClusterSettings,
+) {
+    let versioned_cluster_settings_json = serde_json::to_string(versioned_cluster_settings)
+        .expect("cluster settings should be JSON serializable");
+    cluster
+        .set_self_key_value(CLUSTER_SETTINGS_KEY, versioned_cluster_settings_json)
+        .await;
+}
+
+/// Applies the cluster settings stored in the metastore, then listens for the updates gossiped by
+/// the other nodes.
+pub(crate) async fn setup_cluster_settings_listener(
+    cluster: &Cluster,
+    mut metastore: MetastoreServiceClient,
+    cluster_settings_applier: ClusterSettingsApplier,
+) -> ListenerHandle {
+    let (cluster_settings_tx, mut cluster_settings_rx) = mpsc::unbounded_channel();
+
+    // We subscribe before loading the settings from the metastore so we don't miss any update.
+    let listener_handle = cluster
+        .subscribe(
+            CLUSTER_SETTINGS_KEY,
+            move |event| match serde_json::from_str::<VersionedClusterSettings>(event.value) {
+                Ok(versioned_cluster_settings) => {
+                    let _ = cluster_settings_tx.send(versioned_cluster_settings);
+                }
+                Err(error) => {
+                    warn!(%error, "failed to parse cluster settings `{}`", event.value);
+                }
+            },
+        )
+        .await;
+
+    match load_cluster_settings(&mut metastore).await {
+        Ok(versioned_cluster_settings) => {
+            cluster_settings_applier
+                .apply(&versioned_cluster_settings)
+                .await;
+        }
+        Err(error) => {
+            warn!(%error, "failed to load cluster settings from metastore");
+        }
+    }
+    tokio::spawn(async move {
+        while let Some(versioned_cluster_settings) = cluster_settings_rx.recv().await {
+            cluster_settings_applier
+                .apply(&versioned_cluster_settings)
+                .await;
+        }
+    });
+    listener_handle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_config::SearcherConfig;
+    use quickwit_proto::metastore::{ClusterSettingsResponse, MockMetastoreService};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cluster_settings_applier_ignores_stale_settings() {
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let cluster_settings_applier =
+            ClusterSettingsApplier::new(Some(searcher_context.clone()), None);
+
+        let versioned_cluster_settings = VersionedClusterSettings {
+            version: 2,
+            settings: ClusterSettings {
+                max_num_concurrent_split_searches: Some(42),
+                ..Default::default()
+            },
+        };
+        assert!(
+            cluster_settings_applier
+                .apply(&versioned_cluster_settings)
+                .await
+        );
+        assert_eq!(searcher_context.num_split_search_permits(), 42);
+
+        let versioned_cluster_settings = VersionedClusterSettings {
+            version: 1,
+            settings: ClusterSettings {
+                max_num_concurrent_split_searches: Some(7),
+                ..Default::default()
+            },
+        };
+        assert!(
+            !cluster_settings_applier
+                .apply(&versioned_cluster_settings)
+                .await
+        );
+        assert_eq!(searcher_context.num_split_search_permits(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_setup_cluster_settings_listener() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_get_cluster_settings()
+            .return_once(|_| {
+                let cluster_settings = ClusterSettings {
+                    max_num_concurrent_split_searches: Some(42),
+                    ..Default::default()
+                };
+                Ok(ClusterSettingsResponse {
+                    cluster_settings_json: serde_json::to_string(&cluster_settings).unwrap(),
+                    version: 1,
+                })
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let cluster_settings_applier =
+            ClusterSettingsApplier::new(Some(searcher_context.clone()), None);
+        let _listener_handle =
+            setup_cluster_settings_listener(&cluster, metastore, cluster_settings_applier).await;
+        assert_eq!(searcher_context.num_split_search_permits(), 42);
+
+        let peer_cluster = create_cluster_for_test(
+            vec![cluster.gossip_listen_addr.to_string()],
+            &["searcher"],
+            &transport,
+            true,
+        )
+        .await
+        .unwrap();
+        let versioned_cluster_settings = VersionedClusterSettings {
+            version: 2,
+            settings: ClusterSettings {
+                max_num_concurrent_split_searches: Some(7),
+                ..Default::default()
+            },
+        };
+        publish_cluster_settings(&peer_cluster, &versioned_cluster_settings).await;
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while searcher_context.num_split_search_permits() != 7 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -17,6 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod cluster_settings;
 mod rest_handler;
 
+pub(crate) use cluster_settings::{setup_cluster_settings_listener, ClusterSettingsApplier};
 pub use rest_handler::{cluster_handler, ClusterApi};
+pub(crate) use rest_handler::{
+    cluster_settings_handlers, cluster_upgrade_status_handler, node_maintenance_handlers,
+};
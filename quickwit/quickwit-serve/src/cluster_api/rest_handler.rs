@@ -19,16 +19,45 @@
 
 use std::convert::Infallible;
 
-use quickwit_cluster::{Cluster, ClusterSnapshot, NodeIdSchema};
+use bytes::Bytes;
+use quickwit_cluster::{
+    Cluster, ClusterFeature, ClusterSnapshot, ClusterUpgradeStatus, NodeIdSchema, NodeUpgradeStatus,
+};
+use quickwit_config::ClusterSettings;
+use quickwit_proto::metastore::{
+    serde_utils, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
+    UpdateClusterSettingsRequest,
+};
+use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
+use super::cluster_settings::{
+    load_cluster_settings, publish_cluster_settings, ClusterSettingsApplier,
+    VersionedClusterSettings,
+};
 use crate::format::extract_format_from_qs;
 use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_cluster),
-    components(schemas(ClusterSnapshot, NodeIdSchema,))
+    paths(
+        get_cluster,
+        get_cluster_settings,
+        update_cluster_settings,
+        get_node_maintenance,
+        update_node_maintenance,
+        get_cluster_upgrade_status
+    ),
+    components(schemas(
+        ClusterSnapshot,
+        NodeIdSchema,
+        VersionedClusterSettings,
+        NodeMaintenance,
+        ClusterUpgradeStatus,
+        NodeUpgradeStatus,
+        ClusterFeature,
+    ))
 )]
 pub struct ClusterApi;
 
@@ -59,3 +88,378 @@ async fn get_cluster(cluster: Cluster) -> Result<ClusterSnapshot, Infallible> {
     let snapshot = cluster.snapshot().await;
     Ok(snapshot)
 }
+
+pub(crate) fn cluster_settings_handlers(
+    cluster: Cluster,
+    metastore: MetastoreServiceClient,
+    cluster_settings_applier: ClusterSettingsApplier,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    get_cluster_settings_handler(metastore.clone()).or(update_cluster_settings_handler(
+        cluster,
+        metastore,
+        cluster_settings_applier,
+    ))
+}
+
+fn get_cluster_settings_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "settings")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_cluster_settings)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/cluster/settings",
+    responses(
+        (status = 200, description = "Successfully fetched cluster settings.", body = VersionedClusterSettings)
+    )
+)]
+/// Get the cluster settings.
+async fn get_cluster_settings(
+    mut metastore: MetastoreServiceClient,
+) -> MetastoreResult<VersionedClusterSettings> {
+    load_cluster_settings(&mut metastore).await
+}
+
+fn update_cluster_settings_handler(
+    cluster: Cluster,
+    metastore: MetastoreServiceClient,
+    cluster_settings_applier: ClusterSettingsApplier,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "settings")
+        .and(warp::put())
+        .and(warp::filters::body::bytes())
+        .and(with_arg(cluster))
+        .and(with_arg(metastore))
+        .and(with_arg(cluster_settings_applier))
+        .then(update_cluster_settings)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Cluster Info",
+    path = "/cluster/settings",
+    request_body = ClusterSettings,
+    responses(
+        (status = 200, description = "Successfully updated cluster settings.", body = VersionedClusterSettings),
+        (status = 400, description = "The cluster settings are invalid.")
+    )
+)]
+/// Replaces the cluster settings. The new settings are stored in the metastore, applied to this
+/// node, and gossiped to the other nodes of the cluster.
+async fn update_cluster_settings(
+    body: Bytes,
+    cluster: Cluster,
+    mut metastore: MetastoreServiceClient,
+    cluster_settings_applier: ClusterSettingsApplier,
+) -> MetastoreResult<VersionedClusterSettings> {
+    let cluster_settings: ClusterSettings =
+        serde_json::from_slice(&body).map_err(|error| MetastoreError::InvalidArgument {
+            message: format!("failed to parse cluster settings: {error}"),
+        })?;
+    cluster_settings
+        .validate()
+        .map_err(|error| MetastoreError::InvalidArgument {
+            message: format!("invalid cluster settings: {error}"),
+        })?;
+    let update_cluster_settings_request = UpdateClusterSettingsRequest {
+        cluster_settings_json: serde_utils::to_json_str(&cluster_settings)?,
+    };
+    let response = metastore
+        .update_cluster_settings(update_cluster_settings_request)
+        .await?;
+    let versioned_cluster_settings = VersionedClusterSettings {
+        version: response.version,
+        settings: cluster_settings,
+    };
+    publish_cluster_settings(&cluster, &versioned_cluster_settings).await;
+    cluster_settings_applier
+        .apply(&versioned_cluster_settings)
+        .await;
+    Ok(versioned_cluster_settings)
+}
+
+/// Maintenance state of a node. Searcher nodes in maintenance are gradually drained: the other
+/// nodes progressively stop assigning them search jobs.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NodeMaintenance {
+    pub maintenance: bool,
+}
+
+pub(crate) fn node_maintenance_handlers(
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    get_node_maintenance_handler(cluster.clone()).or(update_node_maintenance_handler(cluster))
+}
+
+fn get_node_maintenance_handler(
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "maintenance")
+        .and(warp::get())
+        .and(with_arg(cluster))
+        .then(get_node_maintenance)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/cluster/maintenance",
+    responses(
+        (status = 200, description = "Successfully fetched the maintenance state of the node.", body = NodeMaintenance)
+    )
+)]
+/// Get the maintenance state of the node.
+async fn get_node_maintenance(cluster: Cluster) -> Result<NodeMaintenance, Infallible> {
+    let maintenance = cluster.is_self_node_in_maintenance().await;
+    Ok(NodeMaintenance { maintenance })
+}
+
+fn update_node_maintenance_handler(
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "maintenance")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(with_arg(cluster))
+        .then(update_node_maintenance)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Cluster Info",
+    path = "/cluster/maintenance",
+    request_body = NodeMaintenance,
+    responses(
+        (status = 200, description = "Successfully updated the maintenance state of the node.", body = NodeMaintenance)
+    )
+)]
+/// Puts the node in or out of maintenance. The maintenance state is gossiped to the other nodes of
+/// the cluster.
+async fn update_node_maintenance(
+    node_maintenance: NodeMaintenance,
+    cluster: Cluster,
+) -> Result<NodeMaintenance, Infallible> {
+    cluster
+        .set_self_node_maintenance(node_maintenance.maintenance)
+        .await;
+    Ok(node_maintenance)
+}
+
+pub(crate) fn cluster_upgrade_status_handler(
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "upgrade-status")
+        .and(warp::get())
+        .and(with_arg(cluster))
+        .then(get_cluster_upgrade_status)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/cluster/upgrade-status",
+    responses(
+        (status = 200, description = "Successfully fetched the upgrade status of the cluster.", body = ClusterUpgradeStatus)
+    )
+)]
+/// Get the upgrade status of the cluster: the protocol version and the features supported by each
+/// node, and the features that remain disabled until all the nodes support them.
+async fn get_cluster_upgrade_status(cluster: Cluster) -> Result<ClusterUpgradeStatus, Infallible> {
+    let upgrade_status = cluster.upgrade_status().await;
+    Ok(upgrade_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_proto::metastore::{ClusterSettingsResponse, MockMetastoreService};
+    use serde_json::json;
+
+    use super::*;
+    use crate::cluster_api::cluster_settings::CLUSTER_SETTINGS_KEY;
+
+    #[tokio::test]
+    async fn test_get_cluster_settings() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_get_cluster_settings()
+            .return_once(|_| {
+                Ok(ClusterSettingsResponse {
+                    cluster_settings_json: r#"{"max_num_concurrent_split_searches": 50}"#
+                        .to_string(),
+                    version: 3,
+                })
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let get_cluster_settings_handler = get_cluster_settings_handler(metastore);
+
+        let response = warp::test::request()
+            .path("/cluster/settings")
+            .method("GET")
+            .reply(&get_cluster_settings_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let versioned_cluster_settings: VersionedClusterSettings =
+            serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(versioned_cluster_settings.version, 3);
+        assert_eq!(
+            versioned_cluster_settings
+                .settings
+                .max_num_concurrent_split_searches,
+            Some(50)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_cluster_settings() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["metastore"], &transport, true)
+            .await
+            .unwrap();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_update_cluster_settings()
+            .return_once(|request| {
+                let cluster_settings: ClusterSettings =
+                    serde_json::from_str(&request.cluster_settings_json).unwrap();
+                assert_eq!(
+                    cluster_settings.split_footer_cache_capacity,
+                    Some(ByteSize::mb(100))
+                );
+                Ok(ClusterSettingsResponse {
+                    cluster_settings_json: request.cluster_settings_json,
+                    version: 4,
+                })
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let cluster_settings_handlers = cluster_settings_handlers(
+            cluster.clone(),
+            metastore,
+            ClusterSettingsApplier::default(),
+        );
+
+        let response = warp::test::request()
+            .path("/cluster/settings")
+            .method("PUT")
+            .json(&json!({
+                "split_footer_cache_capacity": "100MB",
+            }))
+            .reply(&cluster_settings_handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let versioned_cluster_settings: VersionedClusterSettings =
+            serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(versioned_cluster_settings.version, 4);
+
+        let published_cluster_settings_json = cluster
+            .get_self_key_value(CLUSTER_SETTINGS_KEY)
+            .await
+            .unwrap();
+        let published_cluster_settings: VersionedClusterSettings =
+            serde_json::from_str(&published_cluster_settings_json).unwrap();
+        assert_eq!(published_cluster_settings, versioned_cluster_settings);
+
+        // Invalid settings are rejected before reaching the metastore.
+        let response = warp::test::request()
+            .path("/cluster/settings")
+            .method("PUT")
+            .json(&json!({
+                "max_num_concurrent_split_searches": 0,
+            }))
+            .reply(&cluster_settings_handlers)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        let response = warp::test::request()
+            .path("/cluster/settings")
+            .method("PUT")
+            .json(&json!({
+                "unknown_setting": 1,
+            }))
+            .reply(&cluster_settings_handlers)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_node_maintenance() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let node_maintenance_handlers = node_maintenance_handlers(cluster.clone());
+
+        let response = warp::test::request()
+            .path("/cluster/maintenance")
+            .method("GET")
+            .reply(&node_maintenance_handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let node_maintenance: NodeMaintenance = serde_json::from_slice(response.body()).unwrap();
+        assert!(!node_maintenance.maintenance);
+
+        let response = warp::test::request()
+            .path("/cluster/maintenance")
+            .method("PUT")
+            .json(&json!({
+                "maintenance": true,
+            }))
+            .reply(&node_maintenance_handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert!(cluster.is_self_node_in_maintenance().await);
+
+        let response = warp::test::request()
+            .path("/cluster/maintenance")
+            .method("GET")
+            .reply(&node_maintenance_handlers)
+            .await;
+        let node_maintenance: NodeMaintenance = serde_json::from_slice(response.body()).unwrap();
+        assert!(node_maintenance.maintenance);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_upgrade_status() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let cluster_upgrade_status_handler = cluster_upgrade_status_handler(cluster);
+
+        let response = warp::test::request()
+            .path("/cluster/upgrade-status")
+            .method("GET")
+            .reply(&cluster_upgrade_status_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let upgrade_status: ClusterUpgradeStatus = serde_json::from_slice(response.body()).unwrap();
+        assert!(upgrade_status.is_upgrade_complete);
+        assert_eq!(
+            upgrade_status.max_protocol_version,
+            quickwit_cluster::PROTOCOL_VERSION
+        );
+        assert!(upgrade_status.pending_features.is_empty());
+    }
+}
@@ -0,0 +1,898 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_proto::control_plane::{
+    ControlPlaneError, ControlPlaneResult, ControlPlaneService, ControlPlaneServiceClient,
+    DecommissionIngesterRequest, DecommissionIngesterResponse, ForceCloseShardsRequest,
+    ForceCloseShardsResponse, GetShardEventsRequest, GetShardEventsResponse, ListShardsRequest,
+    ListShardsResponse, MoveShardRequest, MoveShardResponse, PauseSourceRequest,
+    PauseSourceResponse, ProjectSourceShardsRequest, ProjectSourceShardsResponse,
+    RebalanceShardsDryRunRequest, RebalanceShardsDryRunResponse, ResumeSourceRequest,
+    ResumeSourceResponse,
+};
+use quickwit_proto::ingest::ShardState;
+use quickwit_proto::types::ShardId;
+use serde::{Deserialize, Serialize};
+use warp::reject::Rejection;
+use warp::{Filter, Reply};
+
+use crate::format::extract_format_from_qs;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        decommission_ingester,
+        move_shard,
+        get_shard_events,
+        rebalance_shards_dry_run,
+        list_shards,
+        project_source_shards,
+        force_close_shards,
+        pause_source,
+        resume_source
+    ),
+    components(schemas(
+        DecommissionIngesterResponse,
+        GetShardEventsResponse,
+        MoveShardBody,
+        MoveShardResponse,
+        RebalanceShardsDryRunResponse,
+        ListShardsResponse,
+        ProjectSourceShardsResponse,
+        ForceCloseShardsRequest,
+        ForceCloseShardsResponse,
+        PauseSourceResponse,
+        ResumeSourceResponse
+    ))
+)]
+pub(crate) struct ControlPlaneApi;
+
+pub(crate) fn control_plane_api_handlers(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    decommission_ingester_handler(control_plane_client.clone())
+        .or(move_shard_handler(control_plane_client.clone()))
+        .or(get_shard_events_handler(control_plane_client.clone()))
+        .or(rebalance_shards_dry_run_handler(
+            control_plane_client.clone(),
+        ))
+        .or(list_shards_handler(control_plane_client.clone()))
+        .or(project_source_shards_handler(control_plane_client.clone()))
+        .or(force_close_shards_handler(control_plane_client.clone()))
+        .or(pause_source_handler(control_plane_client.clone()))
+        .or(resume_source_handler(control_plane_client))
+}
+
+fn decommission_ingester_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "ingesters" / String / "decommission")
+        .and(warp::post())
+        .and(with_arg(control_plane_client))
+        .then(decommission_ingester)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Control Plane",
+    path = "/control-plane/ingesters/{node_id}/decommission",
+    responses(
+        (status = 200, description = "The ingester decommissioning status.", body = DecommissionIngesterResponse)
+    ),
+    params(
+        ("node_id" = String, Path, description = "The ID of the ingester to decommission."),
+    )
+)]
+/// Decommissions an ingester.
+///
+/// The control plane stops allocating new shards to the ingester, moves its open shards to other
+/// ingesters, and removes it from the ingester pool once all its shards have been fully published.
+/// The request is idempotent and can be repeated to track the progress of the decommissioning.
+async fn decommission_ingester(
+    node_id: String,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<DecommissionIngesterResponse> {
+    let decommission_ingester_request = DecommissionIngesterRequest { node_id };
+    control_plane_client
+        .decommission_ingester(decommission_ingester_request)
+        .await
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct MoveShardBody {
+    /// The ID of the ingester to move the shard to.
+    target_ingester_id: String,
+}
+
+fn move_shard_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "shards" / String / "move")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_arg(control_plane_client))
+        .then(move_shard)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Control Plane",
+    path = "/control-plane/shards/{shard_id}/move",
+    request_body = MoveShardBody,
+    responses(
+        (status = 200, description = "The shard opened on the target ingester.", body = MoveShardResponse)
+    ),
+    params(
+        ("shard_id" = String, Path, description = "The ID of the shard to move."),
+    )
+)]
+/// Moves a shard to another ingester.
+///
+/// A new shard is opened with the target ingester as leader, then the original shard is closed.
+async fn move_shard(
+    shard_id: String,
+    move_shard_body: MoveShardBody,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<MoveShardResponse> {
+    let move_shard_request = MoveShardRequest {
+        shard_id: Some(ShardId::from(shard_id)),
+        target_ingester_id: move_shard_body.target_ingester_id,
+    };
+    control_plane_client.move_shard(move_shard_request).await
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct GetShardEventsQueryParams {
+    /// Only returns the events of the shards of this index.
+    #[serde(default)]
+    index_id: Option<String>,
+    /// Only returns the events of the shards of this source.
+    #[serde(default)]
+    source_id: Option<String>,
+    /// Only returns the events of this shard.
+    #[serde(default)]
+    shard_id: Option<String>,
+    /// Maximum number of events to return. The most recent events are returned.
+    #[serde(default)]
+    limit: Option<u32>,
+}
+
+fn get_shard_events_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "shard-events")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(control_plane_client))
+        .then(get_shard_events)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Control Plane",
+    path = "/control-plane/shard-events",
+    responses(
+        (status = 200, description = "The recent shard lifecycle events, oldest first.", body = GetShardEventsResponse)
+    ),
+    params(
+        GetShardEventsQueryParams,
+    )
+)]
+/// Returns the recent shard lifecycle events.
+///
+/// The control plane keeps a bounded, in-memory log of the shard transitions (opened, closed,
+/// moved, unavailable, deleted) it observed since it started.
+async fn get_shard_events(
+    query_params: GetShardEventsQueryParams,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<GetShardEventsResponse> {
+    let get_shard_events_request = GetShardEventsRequest {
+        index_id: query_params.index_id,
+        source_id: query_params.source_id,
+        shard_id: query_params.shard_id.map(ShardId::from),
+        limit: query_params.limit,
+    };
+    control_plane_client
+        .get_shard_events(get_shard_events_request)
+        .await
+}
+
+fn rebalance_shards_dry_run_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "rebalance" / "dry-run")
+        .and(warp::get())
+        .and(with_arg(control_plane_client))
+        .then(rebalance_shards_dry_run)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Control Plane",
+    path = "/control-plane/rebalance/dry-run",
+    responses(
+        (status = 200, description = "The shards the rebalancer would move and the resulting shard distribution.", body = RebalanceShardsDryRunResponse)
+    ),
+)]
+/// Previews the impact of a shard rebalance.
+///
+/// Returns the open shards that the rebalancer would move along with their target ingesters, and
+/// the number of open shard replicas hosted by each ingester before and after the rebalance. No
+/// shard is actually opened or closed.
+async fn rebalance_shards_dry_run(
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<RebalanceShardsDryRunResponse> {
+    control_plane_client
+        .rebalance_shards_dry_run(RebalanceShardsDryRunRequest {})
+        .await
+}
+
+/// This struct represents the query string passed to the REST API to filter and paginate the
+/// shards of the shard table.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ListShardsQueryParams {
+    /// Only returns the shards of this index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_id: Option<String>,
+    /// Only returns the shards of this source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    /// Only returns the shards in this state: `open`, `unavailable`, or `closed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard_state: Option<ShardState>,
+    /// Only returns the shards led by this ingester.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leader_id: Option<String>,
+    /// Number of matching shards to skip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    /// Maximum number of shards to return.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+fn list_shards_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "shards")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(control_plane_client))
+        .then(list_shards)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Control Plane",
+    path = "/control-plane/shards",
+    responses(
+        (status = 200, description = "The shards matching the filters, sorted by index UID, source ID, and shard ID.", body = ListShardsResponse)
+    ),
+    params(
+        ListShardsQueryParams,
+    )
+)]
+/// Lists the shards of the shard table.
+///
+/// Returns the shards known to the control plane along with their state, leader, follower,
+/// ingestion rate, and publish position.
+async fn list_shards(
+    query_params: ListShardsQueryParams,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<ListShardsResponse> {
+    let list_shards_request = ListShardsRequest {
+        index_id: query_params.index_id,
+        source_id: query_params.source_id,
+        shard_state: query_params
+            .shard_state
+            .map(|shard_state| shard_state as i32),
+        leader_id: query_params.leader_id,
+        offset: query_params.offset.unwrap_or_default(),
+        limit: query_params.limit,
+    };
+    control_plane_client.list_shards(list_shards_request).await
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct ProjectSourceShardsQueryParams {
+    /// Comma-separated list of hypothetical ingestion rates of the source in MiB/s for which to
+    /// project the number of open shards.
+    #[serde(default)]
+    ingestion_rates: Option<String>,
+}
+
+fn project_source_shards_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "indexes" / String / "sources" / String / "shard-projection")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(control_plane_client))
+        .then(project_source_shards)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Control Plane",
+    path = "/control-plane/indexes/{index_id}/sources/{source_id}/shard-projection",
+    responses(
+        (status = 200, description = "The current and projected number of open shards of the source.", body = ProjectSourceShardsResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The ID of the index of the source."),
+        ("source_id" = String, Path, description = "The ID of the source."),
+        ProjectSourceShardsQueryParams,
+    )
+)]
+/// Projects the number of open shards of a source.
+///
+/// Returns the current number of open shards and ingestion rate of the source, the number of open
+/// shards the ingest controller targets under its current scaling thresholds, and the number of
+/// open shards and shard replicas it would target at each hypothetical ingestion rate.
+async fn project_source_shards(
+    index_id: String,
+    source_id: String,
+    query_params: ProjectSourceShardsQueryParams,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<ProjectSourceShardsResponse> {
+    let ingestion_rates_mib_per_sec = query_params
+        .ingestion_rates
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|ingestion_rate_str| !ingestion_rate_str.is_empty())
+        .map(|ingestion_rate_str| {
+            ingestion_rate_str.parse::<f32>().map_err(|_| {
+                let message = format!("invalid ingestion rate `{ingestion_rate_str}`");
+                ControlPlaneError::InvalidArgument(message)
+            })
+        })
+        .collect::<ControlPlaneResult<Vec<f32>>>()?;
+    let project_source_shards_request = ProjectSourceShardsRequest {
+        index_id,
+        source_id,
+        ingestion_rates_mib_per_sec,
+    };
+    control_plane_client
+        .project_source_shards(project_source_shards_request)
+        .await
+}
+
+fn force_close_shards_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "shards" / "force-close")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_arg(control_plane_client))
+        .then(force_close_shards)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Control Plane",
+    path = "/control-plane/shards/force-close",
+    request_body = ForceCloseShardsRequest,
+    responses(
+        (status = 200, description = "The shards closed by the control plane and the shards whose leader acknowledged the closing.", body = ForceCloseShardsResponse)
+    ),
+)]
+/// Force-closes shards.
+///
+/// The shards are marked as closed in the metastore and in the model of the control plane
+/// regardless of their state, then their leaders are asked to close them on a best-effort basis.
+/// This is meant for shards stuck in the open state, for instance because their leader lost its
+/// WAL. A reason must be provided: it is recorded in the audit log and in the shard events. The
+/// endpoint is disabled unless `enable_force_close_shards` is set in the control plane config.
+async fn force_close_shards(
+    force_close_shards_request: ForceCloseShardsRequest,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<ForceCloseShardsResponse> {
+    control_plane_client
+        .force_close_shards(force_close_shards_request)
+        .await
+}
+
+fn pause_source_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "indexes" / String / "sources" / String / "pause")
+        .and(warp::post())
+        .and(with_arg(control_plane_client))
+        .then(pause_source)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Control Plane",
+    path = "/control-plane/indexes/{index_id}/sources/{source_id}/pause",
+    responses(
+        (status = 200, description = "The source is paused.", body = PauseSourceResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The ID of the index of the source."),
+        ("source_id" = String, Path, description = "The ID of the source."),
+    )
+)]
+/// Pauses the ingestion of a source.
+///
+/// The control plane closes the open shards of the source and refuses to open new ones, so ingest
+/// requests for the source are rejected, and the indexing scheduler stops scheduling its
+/// pipelines. Unlike disabling a source, pausing a source is not persisted in the metastore.
+async fn pause_source(
+    index_id: String,
+    source_id: String,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<PauseSourceResponse> {
+    let pause_source_request = PauseSourceRequest {
+        index_id,
+        source_id,
+    };
+    control_plane_client
+        .pause_source(pause_source_request)
+        .await
+}
+
+fn resume_source_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("control-plane" / "indexes" / String / "sources" / String / "resume")
+        .and(warp::post())
+        .and(with_arg(control_plane_client))
+        .then(resume_source)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Control Plane",
+    path = "/control-plane/indexes/{index_id}/sources/{source_id}/resume",
+    responses(
+        (status = 200, description = "The source is resumed.", body = ResumeSourceResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The ID of the index of the source."),
+        ("source_id" = String, Path, description = "The ID of the source."),
+    )
+)]
+/// Resumes the ingestion of a paused source.
+async fn resume_source(
+    index_id: String,
+    source_id: String,
+    mut control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<ResumeSourceResponse> {
+    let resume_source_request = ResumeSourceRequest {
+        index_id,
+        source_id,
+    };
+    control_plane_client
+        .resume_source(resume_source_request)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::control_plane::{
+        IngesterShardDistribution, MockControlPlaneService, ShardCountProjection, ShardEvent,
+        ShardEventType, ShardInfo, ShardMove,
+    };
+    use quickwit_proto::ingest::{Shard, ShardPKey};
+    use quickwit_proto::types::IndexUid;
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decommission_ingester() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_decommission_ingester()
+            .return_once(|request| {
+                assert_eq!(request.node_id, "test-ingester");
+
+                let response = DecommissionIngesterResponse {
+                    num_remaining_shards: 3,
+                    is_decommissioned: false,
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path("/control-plane/ingesters/test-ingester/decommission")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["num_remaining_shards"], 3);
+        assert_eq!(response_json["is_decommissioned"], false);
+    }
+
+    #[tokio::test]
+    async fn test_move_shard() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_move_shard()
+            .return_once(|request| {
+                assert_eq!(request.shard_id(), ShardId::from("test-shard"));
+                assert_eq!(request.target_ingester_id, "test-ingester");
+
+                let response = MoveShardResponse {
+                    shard: Some(Shard {
+                        index_uid: Some(IndexUid::for_test("test-index", 0)),
+                        source_id: "test-source".to_string(),
+                        shard_id: Some(ShardId::from("test-new-shard")),
+                        leader_id: "test-ingester".to_string(),
+                        shard_state: ShardState::Open as i32,
+                        ..Default::default()
+                    }),
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path("/control-plane/shards/test-shard/move")
+            .method("POST")
+            .json(&serde_json::json!({"target_ingester_id": "test-ingester"}))
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["shard"]["shard_id"], "test-new-shard");
+        assert_eq!(response_json["shard"]["leader_id"], "test-ingester");
+    }
+
+    #[tokio::test]
+    async fn test_get_shard_events() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_get_shard_events()
+            .return_once(|request| {
+                assert_eq!(request.index_id(), "test-index");
+                assert!(request.source_id.is_none());
+                assert_eq!(request.shard_id, Some(ShardId::from("test-shard")));
+                assert_eq!(request.limit(), 10);
+
+                let response = GetShardEventsResponse {
+                    events: vec![ShardEvent {
+                        timestamp_millis: 1_000,
+                        event_type: ShardEventType::Closed as i32,
+                        index_uid: Some(IndexUid::for_test("test-index", 0)),
+                        source_id: "test-source".to_string(),
+                        shard_id: Some(ShardId::from("test-shard")),
+                        leader_id: "test-ingester".to_string(),
+                        follower_id: None,
+                        reason: "scale down".to_string(),
+                    }],
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path("/control-plane/shard-events?index_id=test-index&shard_id=test-shard&limit=10")
+            .method("GET")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let events = response_json["events"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["shard_id"], "test-shard");
+        assert_eq!(events[0]["event_type"], ShardEventType::Closed as i32);
+        assert_eq!(events[0]["reason"], "scale down");
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_shards_dry_run() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_rebalance_shards_dry_run()
+            .return_once(|_| {
+                let response = RebalanceShardsDryRunResponse {
+                    shard_moves: vec![ShardMove {
+                        index_uid: Some(IndexUid::for_test("test-index", 0)),
+                        source_id: "test-source".to_string(),
+                        shard_id: Some(ShardId::from("test-shard")),
+                        leader_id: "test-ingester-0".to_string(),
+                        follower_id: None,
+                        target_leader_id: "test-ingester-1".to_string(),
+                        target_follower_id: None,
+                    }],
+                    shard_distribution: vec![
+                        IngesterShardDistribution {
+                            node_id: "test-ingester-0".to_string(),
+                            num_shard_replicas_before: 2,
+                            num_shard_replicas_after: 1,
+                        },
+                        IngesterShardDistribution {
+                            node_id: "test-ingester-1".to_string(),
+                            num_shard_replicas_before: 0,
+                            num_shard_replicas_after: 1,
+                        },
+                    ],
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path("/control-plane/rebalance/dry-run")
+            .method("GET")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let shard_moves = response_json["shard_moves"].as_array().unwrap();
+        assert_eq!(shard_moves.len(), 1);
+        assert_eq!(shard_moves[0]["shard_id"], "test-shard");
+        assert_eq!(shard_moves[0]["target_leader_id"], "test-ingester-1");
+
+        let shard_distribution = response_json["shard_distribution"].as_array().unwrap();
+        assert_eq!(shard_distribution.len(), 2);
+        assert_eq!(shard_distribution[0]["num_shard_replicas_before"], 2);
+        assert_eq!(shard_distribution[0]["num_shard_replicas_after"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_shards() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_list_shards()
+            .return_once(|request| {
+                assert_eq!(request.index_id(), "test-index");
+                assert!(request.source_id.is_none());
+                assert_eq!(request.shard_state(), ShardState::Open);
+                assert_eq!(request.leader_id(), "test-ingester");
+                assert_eq!(request.offset, 0);
+                assert_eq!(request.limit(), 10);
+
+                let response = ListShardsResponse {
+                    shards: vec![ShardInfo {
+                        shard: Some(Shard {
+                            index_uid: Some(IndexUid::for_test("test-index", 0)),
+                            source_id: "test-source".to_string(),
+                            shard_id: Some(ShardId::from("test-shard")),
+                            leader_id: "test-ingester".to_string(),
+                            shard_state: ShardState::Open as i32,
+                            ..Default::default()
+                        }),
+                        ingestion_rate_mib_per_sec: 2,
+                    }],
+                    num_matching_shards: 1,
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path(
+                "/control-plane/shards?index_id=test-index&shard_state=open&\
+                 leader_id=test-ingester&limit=10",
+            )
+            .method("GET")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["num_matching_shards"], 1);
+        let shards = response_json["shards"].as_array().unwrap();
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0]["shard"]["shard_id"], "test-shard");
+        assert_eq!(shards[0]["ingestion_rate_mib_per_sec"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_project_source_shards() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_project_source_shards()
+            .return_once(|request| {
+                assert_eq!(request.index_id, "test-index");
+                assert_eq!(request.source_id, "test-source");
+                assert_eq!(request.ingestion_rates_mib_per_sec, [10., 100.]);
+
+                let response = ProjectSourceShardsResponse {
+                    index_uid: Some(IndexUid::for_test("test-index", 0)),
+                    source_id: "test-source".to_string(),
+                    num_open_shards: 1,
+                    ingestion_rate_mib_per_sec: 2.,
+                    target_num_open_shards: 1,
+                    scale_up_threshold_mib_per_sec: 4.,
+                    scale_down_threshold_mib_per_sec: 1.,
+                    replication_factor: 1,
+                    projections: vec![
+                        ShardCountProjection {
+                            ingestion_rate_mib_per_sec: 10.,
+                            num_open_shards: 3,
+                            num_shard_replicas: 3,
+                        },
+                        ShardCountProjection {
+                            ingestion_rate_mib_per_sec: 100.,
+                            num_open_shards: 26,
+                            num_shard_replicas: 26,
+                        },
+                    ],
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path(
+                "/control-plane/indexes/test-index/sources/test-source/shard-projection?\
+                 ingestion_rates=10,100",
+            )
+            .method("GET")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["num_open_shards"], 1);
+        assert_eq!(response_json["target_num_open_shards"], 1);
+        let projections = response_json["projections"].as_array().unwrap();
+        assert_eq!(projections.len(), 2);
+        assert_eq!(projections[1]["num_open_shards"], 26);
+
+        let response = warp::test::request()
+            .path(
+                "/control-plane/indexes/test-index/sources/test-source/shard-projection?\
+                 ingestion_rates=foo",
+            )
+            .method("GET")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_force_close_shards() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_force_close_shards()
+            .return_once(|request| {
+                assert_eq!(request.shard_pkeys.len(), 1);
+                assert_eq!(
+                    request.shard_pkeys[0].shard_id(),
+                    ShardId::from("test-shard")
+                );
+                assert_eq!(request.reason, "ingester lost its WAL");
+
+                let response = ForceCloseShardsResponse {
+                    closed_shard_pkeys: request.shard_pkeys,
+                    acknowledged_shard_pkeys: Vec::new(),
+                };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let shard_pkey = ShardPKey {
+            index_uid: Some(IndexUid::for_test("test-index", 0)),
+            source_id: "test-source".to_string(),
+            shard_id: Some(ShardId::from("test-shard")),
+        };
+        let response = warp::test::request()
+            .path("/control-plane/shards/force-close")
+            .method("POST")
+            .json(&serde_json::json!({
+                "shard_pkeys": [shard_pkey],
+                "reason": "ingester lost its WAL",
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let closed_shard_pkeys = response_json["closed_shard_pkeys"].as_array().unwrap();
+        assert_eq!(closed_shard_pkeys.len(), 1);
+        assert_eq!(closed_shard_pkeys[0]["shard_id"], "test-shard");
+        assert!(response_json["acknowledged_shard_pkeys"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_source() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_pause_source()
+            .return_once(|request| {
+                assert_eq!(request.index_id, "test-index");
+                assert_eq!(request.source_id, "test-source");
+
+                let response = PauseSourceResponse {
+                    was_paused: false,
+                    num_closed_shards: 2,
+                };
+                Ok(response)
+            });
+        mock_control_plane
+            .expect_resume_source()
+            .return_once(|request| {
+                assert_eq!(request.index_id, "test-index");
+                assert_eq!(request.source_id, "test-source");
+
+                let response = ResumeSourceResponse { was_paused: true };
+                Ok(response)
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = control_plane_api_handlers(control_plane_client);
+
+        let response = warp::test::request()
+            .path("/control-plane/indexes/test-index/sources/test-source/pause")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["was_paused"], false);
+        assert_eq!(response_json["num_closed_shards"], 2);
+
+        let response = warp::test::request()
+            .path("/control-plane/indexes/test-index/sources/test-source/resume")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["was_paused"], true);
+    }
+}
@@ -17,6 +17,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
 use quickwit_config::build_doc_mapper;
 use quickwit_janitor::error::JanitorError;
 use quickwit_metastore::IndexMetadataResponseExt;
@@ -26,18 +29,25 @@ use quickwit_proto::metastore::{
 };
 use quickwit_proto::search::SearchRequest;
 use quickwit_proto::types::IndexUid;
-use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
-use serde::Deserialize;
+use quickwit_query::query_ast::{query_ast_from_user_text, BoolQuery, QueryAst, TermQuery};
+use quickwit_search::{DeleteQueryPreview, SearchService, SplitDeletePreview};
+use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
 use crate::format::extract_format_from_qs;
 use crate::rest_api_response::into_rest_api_response;
-use crate::with_arg;
+use crate::{with_arg, BodyFormat};
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_delete_tasks, post_delete_request),
-    components(schemas(DeleteQueryRequest, DeleteTask, DeleteQuery,))
+    paths(get_delete_tasks, post_delete_request, post_delete_by_query),
+    components(schemas(
+        DeleteQueryRequest,
+        DeleteTask,
+        DeleteQuery,
+        DeleteQueryPreview,
+        SplitDeletePreview,
+    ))
 )]
 pub struct DeleteTaskApi;
 
@@ -46,22 +56,50 @@ pub struct DeleteTaskApi;
 #[derive(Deserialize, Debug, Eq, PartialEq, Default, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct DeleteQueryRequest {
-    /// Query text. The query language is that of tantivy.
+    /// Query text. The query language is that of tantivy. Can be omitted if `filters` is set.
+    #[serde(default)]
     pub query: String,
     // Fields to search on
     #[serde(default)]
     pub search_fields: Vec<String>,
+    /// Field-level filters: restrict delete to documents whose field is exactly equal to the
+    /// given value, for every field of the map.
+    #[serde(default)]
+    pub filters: BTreeMap<String, String>,
     /// If set, restrict delete to documents with a `timestamp >= start_timestamp`.
     pub start_timestamp: Option<i64>,
     /// If set, restrict delete to documents with a `timestamp < end_timestamp``.
     pub end_timestamp: Option<i64>,
 }
 
+/// This struct represents the query string of the delete-by-query REST API.
+#[derive(Debug, Default, Eq, PartialEq, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub struct DeleteByQueryQueryString {
+    /// If true, returns the documents and splits matched by the delete query instead of
+    /// creating the delete task.
+    #[serde(default)]
+    pub preview: bool,
+}
+
+/// Response of the delete-by-query REST API: the created delete task, or the preview of the
+/// delete query in preview mode.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum DeleteByQueryResponse {
+    DeleteTask(DeleteTask),
+    Preview(DeleteQueryPreview),
+}
+
 /// Delete query API handlers.
 pub fn delete_task_api_handlers(
     metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    get_delete_tasks_handler(metastore.clone()).or(post_delete_tasks_handler(metastore.clone()))
+    get_delete_tasks_handler(metastore.clone())
+        .or(post_delete_tasks_handler(metastore.clone()))
+        .or(post_delete_by_query_handler(metastore, search_service))
 }
 
 pub fn get_delete_tasks_handler(
@@ -144,15 +182,73 @@ pub async fn post_delete_request(
     delete_request: DeleteQueryRequest,
     mut metastore: MetastoreServiceClient,
 ) -> Result<DeleteTask, JanitorError> {
+    let delete_query = build_delete_query(&index_id, delete_request, &mut metastore).await?;
+    let delete_task = metastore.create_delete_task(delete_query).await?;
+    Ok(delete_task)
+}
+
+pub fn post_delete_by_query_handler(
+    metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "delete-by-query")
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(warp::body::json())
+        .and(with_arg(metastore))
+        .and(with_arg(search_service))
+        .then(post_delete_by_query)
+        .map(|result| into_rest_api_response(result, BodyFormat::default()))
+}
+
+#[utoipa::path(
+    post,
+    tag = "Delete Tasks",
+    path = "/{index_id}/delete-by-query",
+    request_body = DeleteQueryRequest,
+    responses(
+        (status = 200, description = "Successfully added a new delete task, or previewed the delete query.", body = DeleteTask)
+    ),
+    params(
+        DeleteByQueryQueryString,
+        ("index_id" = String, Path, description = "The index ID to delete documents from."),
+    )
+)]
+/// Delete By Query
+///
+/// Creates a delete task like the delete tasks endpoint. With `preview=true`, the delete task is
+/// not created: the response gives the number of documents matched by the delete query and the
+/// splits containing them instead.
+pub async fn post_delete_by_query(
+    index_id: String,
+    query_string: DeleteByQueryQueryString,
+    delete_request: DeleteQueryRequest,
+    mut metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
+) -> Result<DeleteByQueryResponse, JanitorError> {
+    let delete_query = build_delete_query(&index_id, delete_request, &mut metastore).await?;
+    if query_string.preview {
+        let preview = search_service.preview_delete_query(delete_query).await?;
+        return Ok(DeleteByQueryResponse::Preview(preview));
+    }
+    let delete_task = metastore.create_delete_task(delete_query).await?;
+    Ok(DeleteByQueryResponse::DeleteTask(delete_task))
+}
+
+/// Builds the delete query of a delete request and validates it against the current doc mapping
+/// of the index.
+async fn build_delete_query(
+    index_id: &str,
+    delete_request: DeleteQueryRequest,
+    metastore: &mut MetastoreServiceClient,
+) -> Result<DeleteQuery, JanitorError> {
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let metadata = metastore
         .index_metadata(index_metadata_request)
         .await?
         .deserialize_index_metadata()?;
     let index_uid: IndexUid = metadata.index_uid.clone();
-    let query_ast = query_ast_from_user_text(&delete_request.query, Some(Vec::new()))
-        .parse_user_query(&[])
-        .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
+    let query_ast = build_delete_query_ast(&delete_request)?;
     let query_ast_json = serde_json::to_string(&query_ast).map_err(|_err| {
         JanitorError::Internal("failed to serialized delete query ast".to_string())
     })?;
@@ -175,14 +271,47 @@ pub async fn post_delete_request(
     doc_mapper
         .query(doc_mapper.schema(), &query_ast, true)
         .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
-    let delete_task = metastore.create_delete_task(delete_query).await?;
-    Ok(delete_task)
+    Ok(delete_query)
+}
+
+/// Combines the query text and the field-level filters of a delete request into a single query.
+fn build_delete_query_ast(delete_request: &DeleteQueryRequest) -> Result<QueryAst, JanitorError> {
+    let mut must_clauses: Vec<QueryAst> = Vec::new();
+    if !delete_request.query.trim().is_empty() {
+        let query_ast = query_ast_from_user_text(&delete_request.query, Some(Vec::new()))
+            .parse_user_query(&[])
+            .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
+        must_clauses.push(query_ast);
+    }
+    for (field, value) in &delete_request.filters {
+        let term_query = TermQuery {
+            field: field.clone(),
+            value: value.clone(),
+        };
+        must_clauses.push(term_query.into());
+    }
+    match must_clauses.len() {
+        0 => Err(JanitorError::InvalidDeleteQuery(
+            "either `query` or `filters` must be set".to_string(),
+        )),
+        1 => Ok(must_clauses
+            .pop()
+            .expect("there should be exactly one clause")),
+        _ => Ok(BoolQuery {
+            must: must_clauses,
+            ..Default::default()
+        }
+        .into()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use quickwit_indexing::TestSandbox;
     use quickwit_proto::metastore::DeleteTask;
+    use quickwit_search::{DeleteQueryPreview, MockSearchService, SplitDeletePreview};
     use warp::Filter;
 
     use crate::rest::recover_fn;
@@ -205,7 +334,8 @@ mod tests {
             .unwrap();
         let metastore = test_sandbox.metastore();
         let delete_query_api_handlers =
-            super::delete_task_api_handlers(metastore).recover(recover_fn);
+            super::delete_task_api_handlers(metastore, Arc::new(MockSearchService::new()))
+                .recover(recover_fn);
         let resp = warp::test::request()
             .path("/test-delete-task-rest/delete-tasks")
             .method("POST")
@@ -246,4 +376,93 @@ mod tests {
         assert_eq!(delete_tasks.len(), 1);
         test_sandbox.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_delete_by_query_api() {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = "test-delete-by-query-rest";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: user_id
+                type: text
+                tokenizer: raw
+            mode: lenient
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_preview_delete_query()
+            .times(1)
+            .returning(|delete_query| {
+                assert_eq!(
+                    delete_query.query_ast,
+                    r#"{"type":"bool","must":[{"type":"full_text","field":"body","text":"myterm","params":{"mode":{"type":"phrase_fallback_to_intersection"}}},{"type":"term","field":"user_id","value":"1234"}]}"#
+                );
+                assert_eq!(delete_query.start_timestamp, Some(1));
+                assert_eq!(delete_query.end_timestamp, Some(10));
+                Ok(DeleteQueryPreview {
+                    num_matching_docs: 2,
+                    num_splits_searched: 3,
+                    affected_splits: vec![SplitDeletePreview {
+                        split_id: "split-1".to_string(),
+                        num_docs: 10,
+                        num_matching_docs: 2,
+                    }],
+                })
+            });
+        let delete_query_api_handlers =
+            super::delete_task_api_handlers(metastore, Arc::new(mock_search_service))
+                .recover(recover_fn);
+
+        // Preview the delete query: no delete task is created.
+        let body = r#"{"query": "body:myterm", "filters": {"user_id": "1234"}, "start_timestamp": 1, "end_timestamp": 10}"#;
+        let resp = warp::test::request()
+            .path("/test-delete-by-query-rest/delete-by-query?preview=true")
+            .method("POST")
+            .body(body)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let preview: DeleteQueryPreview = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(preview.num_matching_docs, 2);
+        assert_eq!(preview.affected_splits.len(), 1);
+
+        let resp = warp::test::request()
+            .path("/test-delete-by-query-rest/delete-tasks")
+            .reply(&delete_query_api_handlers)
+            .await;
+        let delete_tasks: Vec<DeleteTask> = serde_json::from_slice(resp.body()).unwrap();
+        assert!(delete_tasks.is_empty());
+
+        // Create the delete task with filters only.
+        let resp = warp::test::request()
+            .path("/test-delete-by-query-rest/delete-by-query")
+            .method("POST")
+            .body(r#"{"filters": {"user_id": "1234"}}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let created_delete_task: DeleteTask = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            created_delete_task.delete_query.unwrap().query_ast,
+            r#"{"type":"term","field":"user_id","value":"1234"}"#
+        );
+
+        // A delete request requires a query or filters.
+        let resp = warp::test::request()
+            .path("/test-delete-by-query-rest/delete-by-query")
+            .method("POST")
+            .body(r#"{"start_timestamp": 1}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert!(String::from_utf8_lossy(resp.body()).contains("either `query` or `filters`"));
+
+        test_sandbox.assert_quit().await;
+    }
 }
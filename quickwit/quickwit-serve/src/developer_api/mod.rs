@@ -19,24 +19,37 @@
 
 mod debug;
 mod log_level;
+mod search_diff;
 mod server;
 
+use std::sync::Arc;
+
 use debug::debug_handler;
 use log_level::log_level_handler;
 use quickwit_cluster::Cluster;
+use quickwit_search::SearchService;
+use search_diff::search_diff_handler;
 pub(crate) use server::DeveloperApiServer;
 use warp::{Filter, Rejection};
 
 use crate::EnvFilterReloadFn;
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(debug::debug_handler, log_level::log_level_handler))]
+#[openapi(paths(
+    debug::debug_handler,
+    log_level::log_level_handler,
+    search_diff::search_diff_handler
+))]
 pub struct DeveloperApi;
 
 pub(crate) fn developer_api_routes(
     cluster: Cluster,
     env_filter_reload_fn: EnvFilterReloadFn,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("api" / "developer" / ..)
-        .and(debug_handler(cluster.clone()).or(log_level_handler(env_filter_reload_fn.clone())))
+    warp::path!("api" / "developer" / ..).and(
+        debug_handler(cluster.clone())
+            .or(log_level_handler(env_filter_reload_fn.clone()))
+            .or(search_diff_handler(search_service)),
+    )
 }
@@ -0,0 +1,602 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use quickwit_proto::search::{SearchRequest, SearchResponse};
+use quickwit_search::{SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::rest_api_response::into_rest_api_response;
+use crate::search_api::{search_request_from_api_request, SearchRequestQueryString};
+use crate::with_arg;
+
+/// Describes one of the two code paths compared by the search diff endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchDiffTarget {
+    index_id_patterns: Vec<String>,
+    search_request: SearchRequestQueryString,
+}
+
+/// Compares the results of two searches, for instance against two indexes.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchDiffTargets {
+    left: SearchDiffTarget,
+    right: SearchDiffTarget,
+}
+
+/// Compares the results of a search, on the left, with the ones of the same search with some flags
+/// toggled, on the right.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchDiffToggle {
+    index_id_patterns: Vec<String>,
+    search_request: SearchRequestQueryString,
+    toggle: Vec<SearchDiffFlag>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SearchDiffRequest {
+    Targets(SearchDiffTargets),
+    Toggle(SearchDiffToggle),
+}
+
+/// A flag of a search request that selects the code path executing the search.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SearchDiffFlag {
+    BypassCache,
+}
+
+impl SearchDiffFlag {
+    fn toggle(self, search_request: &mut SearchRequest) {
+        match self {
+            Self::BypassCache => {
+                search_request.bypass_cache = !search_request.bypass_cache;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct SearchDiffSummary {
+    num_hits: u64,
+    num_returned_hits: usize,
+    elapsed_time_micros: u64,
+    errors: Vec<String>,
+}
+
+impl From<&SearchResponse> for SearchDiffSummary {
+    fn from(search_response: &SearchResponse) -> Self {
+        Self {
+            num_hits: search_response.num_hits,
+            num_returned_hits: search_response.hits.len(),
+            elapsed_time_micros: search_response.elapsed_time_micros,
+            errors: search_response.errors.clone(),
+        }
+    }
+}
+
+/// A value that differs between the left and right aggregation results. `None` means the value is
+/// absent on that side.
+#[derive(Debug, PartialEq, Serialize)]
+struct JsonValueDiff {
+    path: String,
+    left: Option<JsonValue>,
+    right: Option<JsonValue>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct SearchDiffResponse {
+    left: SearchDiffSummary,
+    right: SearchDiffSummary,
+    /// Whether both code paths returned the same hits, in the same order, and the same
+    /// aggregations.
+    is_identical: bool,
+    num_hits_differ: bool,
+    /// Whether both code paths returned the same set of documents but in a different order.
+    hits_order_differs: bool,
+    hits_only_in_left: Vec<JsonValue>,
+    hits_only_in_right: Vec<JsonValue>,
+    aggregation_diffs: Vec<JsonValueDiff>,
+}
+
+#[utoipa::path(
+    post,
+    tag = "Debug",
+    path = "/search-diff",
+    responses(
+        (status = 200, description = "Successfully compared the results of the two searches."),
+    ),
+)]
+/// Executes two search requests and returns a structured diff of their hits and aggregations.
+///
+/// This endpoint is meant for correctness testing. The request either holds two searches, `left`
+/// and `right`, for instance to compare two indexes, or a single search along with the flags to
+/// `toggle`, for instance `bypass_cache`, to compare the results of the same search with and
+/// without an optimization.
+pub(super) fn search_diff_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path("search-diff")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and(with_arg(search_service))
+        .then(search_diff)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+async fn search_diff(
+    search_diff_request: SearchDiffRequest,
+    search_service: Arc<dyn SearchService>,
+) -> Result<SearchDiffResponse, SearchError> {
+    let (left_search_request, right_search_request) = match search_diff_request {
+        SearchDiffRequest::Targets(search_diff_targets) => {
+            let left_search_request = search_request_from_api_request(
+                search_diff_targets.left.index_id_patterns,
+                search_diff_targets.left.search_request,
+            )?;
+            let right_search_request = search_request_from_api_request(
+                search_diff_targets.right.index_id_patterns,
+                search_diff_targets.right.search_request,
+            )?;
+            (left_search_request, right_search_request)
+        }
+        SearchDiffRequest::Toggle(search_diff_toggle) => {
+            if search_diff_toggle.toggle.is_empty() {
+                return Err(SearchError::InvalidArgument(
+                    "at least one flag to toggle must be specified".to_string(),
+                ));
+            }
+            let left_search_request = search_request_from_api_request(
+                search_diff_toggle.index_id_patterns,
+                search_diff_toggle.search_request,
+            )?;
+            let mut right_search_request = left_search_request.clone();
+
+            for flag in search_diff_toggle.toggle {
+                flag.toggle(&mut right_search_request);
+            }
+            (left_search_request, right_search_request)
+        }
+    };
+    diff_searches(&*search_service, left_search_request, right_search_request).await
+}
+
+async fn diff_searches(
+    search_service: &dyn SearchService,
+    left_search_request: SearchRequest,
+    right_search_request: SearchRequest,
+) -> Result<SearchDiffResponse, SearchError> {
+    let (left_search_response, right_search_response) = tokio::try_join!(
+        search_service.root_search(left_search_request),
+        search_service.root_search(right_search_request),
+    )?;
+    diff_search_responses(&left_search_response, &right_search_response)
+}
+
+fn diff_search_responses(
+    left_search_response: &SearchResponse,
+    right_search_response: &SearchResponse,
+) -> Result<SearchDiffResponse, SearchError> {
+    let left_docs = parse_hit_docs(left_search_response)?;
+    let right_docs = parse_hit_docs(right_search_response)?;
+
+    // Hits are matched by document content so that searches against different indexes can be
+    // compared.
+    let mut hits_only_in_right: Vec<Option<&JsonValue>> = right_docs.iter().map(Some).collect();
+    let mut hits_only_in_left: Vec<JsonValue> = Vec::new();
+
+    for left_doc in &left_docs {
+        let matching_right_doc_opt = hits_only_in_right
+            .iter_mut()
+            .find(|right_doc_opt| **right_doc_opt == Some(left_doc));
+
+        if let Some(matching_right_doc) = matching_right_doc_opt {
+            *matching_right_doc = None;
+        } else {
+            hits_only_in_left.push(left_doc.clone());
+        }
+    }
+    let hits_only_in_right: Vec<JsonValue> =
+        hits_only_in_right.into_iter().flatten().cloned().collect();
+    let hits_order_differs =
+        hits_only_in_left.is_empty() && hits_only_in_right.is_empty() && left_docs != right_docs;
+
+    let left_aggregation = parse_aggregation(left_search_response)?;
+    let right_aggregation = parse_aggregation(right_search_response)?;
+    let mut aggregation_diffs = Vec::new();
+    diff_json_values(
+        &mut String::new(),
+        left_aggregation.as_ref(),
+        right_aggregation.as_ref(),
+        &mut aggregation_diffs,
+    );
+    let num_hits_differ = left_search_response.num_hits != right_search_response.num_hits;
+    let is_identical = !num_hits_differ
+        && !hits_order_differs
+        && hits_only_in_left.is_empty()
+        && hits_only_in_right.is_empty()
+        && aggregation_diffs.is_empty();
+
+    let search_diff_response = SearchDiffResponse {
+        left: SearchDiffSummary::from(left_search_response),
+        right: SearchDiffSummary::from(right_search_response),
+        is_identical,
+        num_hits_differ,
+        hits_order_differs,
+        hits_only_in_left,
+        hits_only_in_right,
+        aggregation_diffs,
+    };
+    Ok(search_diff_response)
+}
+
+fn parse_hit_docs(search_response: &SearchResponse) -> Result<Vec<JsonValue>, SearchError> {
+    search_response
+        .hits
+        .iter()
+        .map(|hit| serde_json::from_str(&hit.json).map_err(SearchError::from))
+        .collect()
+}
+
+fn parse_aggregation(search_response: &SearchResponse) -> Result<Option<JsonValue>, SearchError> {
+    search_response
+        .aggregation
+        .as_ref()
+        .map(|aggregation_json| serde_json::from_str(aggregation_json).map_err(SearchError::from))
+        .transpose()
+}
+
+/// Recursively compares two JSON values and records the paths (e.g. `.terms_agg.buckets[0]`) where
+/// they differ.
+fn diff_json_values(
+    path: &mut String,
+    left_opt: Option<&JsonValue>,
+    right_opt: Option<&JsonValue>,
+    diffs: &mut Vec<JsonValueDiff>,
+) {
+    match (left_opt, right_opt) {
+        (Some(JsonValue::Object(left_map)), Some(JsonValue::Object(right_map))) => {
+            let mut keys: Vec<&String> = left_map.keys().chain(right_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let path_len = path.len();
+                path.push('.');
+                path.push_str(key);
+                diff_json_values(path, left_map.get(key), right_map.get(key), diffs);
+                path.truncate(path_len);
+            }
+        }
+        (Some(JsonValue::Array(left_values)), Some(JsonValue::Array(right_values))) => {
+            let num_values = left_values.len().max(right_values.len());
+
+            for idx in 0..num_values {
+                let path_len = path.len();
+                path.push_str(&format!("[{idx}]"));
+                diff_json_values(path, left_values.get(idx), right_values.get(idx), diffs);
+                path.truncate(path_len);
+            }
+        }
+        (left_opt, right_opt) if left_opt != right_opt => {
+            let diff = JsonValueDiff {
+                path: path.clone(),
+                left: left_opt.cloned(),
+                right: right_opt.cloned(),
+            };
+            diffs.push(diff);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use quickwit_config::SearcherConfig;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_proto::search::Hit;
+    use quickwit_query::query_ast::{BoolQuery, QueryAst, TermQuery};
+    use quickwit_search::{
+        ClusterClient, MockSearchService, SearchJobPlacer, SearchServiceClient, SearchServiceImpl,
+        SearcherContext, SearcherPool,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn hit(json: JsonValue) -> Hit {
+        Hit {
+            json: json.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_json_values() {
+        let left = json!({
+            "agg": {
+                "buckets": [{"key": "a", "doc_count": 1}, {"key": "b", "doc_count": 2}],
+                "sum_other_doc_count": 0,
+            }
+        });
+        let right = json!({
+            "agg": {
+                "buckets": [
+                    {"key": "a", "doc_count": 1},
+                    {"key": "b", "doc_count": 3},
+                    {"key": "c", "doc_count": 1},
+                ],
+            }
+        });
+        let mut diffs = Vec::new();
+        diff_json_values(&mut String::new(), Some(&left), Some(&right), &mut diffs);
+
+        assert_eq!(
+            diffs,
+            [
+                JsonValueDiff {
+                    path: ".agg.buckets[1].doc_count".to_string(),
+                    left: Some(json!(2)),
+                    right: Some(json!(3)),
+                },
+                JsonValueDiff {
+                    path: ".agg.buckets[2]".to_string(),
+                    left: None,
+                    right: Some(json!({"key": "c", "doc_count": 1})),
+                },
+                JsonValueDiff {
+                    path: ".agg.sum_other_doc_count".to_string(),
+                    left: Some(json!(0)),
+                    right: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_search_responses() {
+        let left_search_response = SearchResponse {
+            num_hits: 3,
+            hits: vec![hit(json!({"id": 1})), hit(json!({"id": 2}))],
+            ..Default::default()
+        };
+        let search_diff_response =
+            diff_search_responses(&left_search_response, &left_search_response).unwrap();
+        assert!(search_diff_response.is_identical);
+
+        let right_search_response = SearchResponse {
+            num_hits: 3,
+            hits: vec![hit(json!({"id": 2})), hit(json!({"id": 1}))],
+            ..Default::default()
+        };
+        let search_diff_response =
+            diff_search_responses(&left_search_response, &right_search_response).unwrap();
+        assert!(!search_diff_response.is_identical);
+        assert!(!search_diff_response.num_hits_differ);
+        assert!(search_diff_response.hits_order_differs);
+        assert!(search_diff_response.hits_only_in_left.is_empty());
+        assert!(search_diff_response.hits_only_in_right.is_empty());
+
+        let right_search_response = SearchResponse {
+            num_hits: 4,
+            hits: vec![hit(json!({"id": 1})), hit(json!({"id": 3}))],
+            aggregation: Some(json!({"count": {"value": 4}}).to_string()),
+            ..Default::default()
+        };
+        let search_diff_response =
+            diff_search_responses(&left_search_response, &right_search_response).unwrap();
+        assert!(!search_diff_response.is_identical);
+        assert!(search_diff_response.num_hits_differ);
+        assert!(!search_diff_response.hits_order_differs);
+        assert_eq!(search_diff_response.hits_only_in_left, [json!({"id": 2})]);
+        assert_eq!(search_diff_response.hits_only_in_right, [json!({"id": 3})]);
+        assert_eq!(search_diff_response.aggregation_diffs.len(), 1);
+        assert_eq!(search_diff_response.aggregation_diffs[0].path, "");
+    }
+
+    #[tokio::test]
+    async fn test_search_diff_handler() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(|search_request| {
+                let num_hits = if search_request.index_id_patterns == ["index-v1"] {
+                    1
+                } else {
+                    2
+                };
+                Ok(SearchResponse {
+                    num_hits,
+                    hits: vec![hit(json!({"id": 1}))],
+                    ..Default::default()
+                })
+            });
+        let search_diff_handler = search_diff_handler(Arc::new(mock_search_service));
+        let response = warp::test::request()
+            .path("/search-diff")
+            .method("POST")
+            .json(&json!({
+                "left": {
+                    "index_id_patterns": ["index-v1"],
+                    "search_request": {"query": "body:foo"},
+                },
+                "right": {
+                    "index_id_patterns": ["index-v2"],
+                    "search_request": {"query": "body:foo"},
+                },
+            }))
+            .reply(&search_diff_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["is_identical"], false);
+        assert_eq!(response_json["num_hits_differ"], true);
+        assert_eq!(response_json["left"]["num_hits"], 1);
+        assert_eq!(response_json["right"]["num_hits"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_diff_handler_toggle() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(|search_request| {
+                assert_eq!(search_request.index_id_patterns, ["index-v1"]);
+
+                let num_hits = if search_request.bypass_cache { 2 } else { 1 };
+                Ok(SearchResponse {
+                    num_hits,
+                    hits: vec![hit(json!({"id": 1}))],
+                    ..Default::default()
+                })
+            });
+        let search_diff_handler = search_diff_handler(Arc::new(mock_search_service));
+        let response = warp::test::request()
+            .path("/search-diff")
+            .method("POST")
+            .json(&json!({
+                "index_id_patterns": ["index-v1"],
+                "search_request": {"query": "body:foo"},
+                "toggle": ["bypass_cache"],
+            }))
+            .reply(&search_diff_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["is_identical"], false);
+        assert_eq!(response_json["left"]["num_hits"], 1);
+        assert_eq!(response_json["right"]["num_hits"], 2);
+
+        let search_diff_handler = search_diff_handler(Arc::new(MockSearchService::new()));
+        let response = warp::test::request()
+            .path("/search-diff")
+            .method("POST")
+            .json(&json!({
+                "index_id_patterns": ["index-v1"],
+                "search_request": {"query": "body:foo"},
+                "toggle": [],
+            }))
+            .reply(&search_diff_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_search_diff_fast_field_pushdown() {
+        let index_id = "test-search-diff-fast-field-pushdown";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: ts
+                type: datetime
+                input_formats:
+                  - unix_timestamp
+                fast: true
+              - name: status
+                type: u64
+                fast: true
+            timestamp_field: ts
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[])
+            .await
+            .unwrap();
+        let start_timestamp: i64 = 1_700_000_000;
+        let docs: Vec<JsonValue> = (0..100)
+            .map(|i| {
+                let status = if i % 10 == 0 { 404 } else { 200 };
+                json!({"ts": start_timestamp + i, "status": status})
+            })
+            .collect();
+        test_sandbox.add_documents(docs).await.unwrap();
+
+        let search_service_for_test = |enable_fast_field_pushdown: bool| {
+            let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 7280);
+            let searcher_pool = SearcherPool::default();
+            let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool.clone()));
+            // Term filters are always candidates to the pushdown, whatever the size of their
+            // posting list.
+            let searcher_config = SearcherConfig {
+                enable_fast_field_pushdown,
+                fast_field_scan_min_doc_freq: 0,
+                ..Default::default()
+            };
+            let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+            let search_service = Arc::new(SearchServiceImpl::new(
+                test_sandbox.metastore(),
+                test_sandbox.storage_resolver(),
+                cluster_client,
+                searcher_context,
+            ));
+            let search_service_client =
+                SearchServiceClient::from_service(search_service.clone(), socket_addr);
+            searcher_pool.insert(socket_addr, search_service_client);
+            search_service
+        };
+        // The posting list of `status:200` is much larger than the time slice targeted by the
+        // request, so the term filter is evaluated by scanning the `status` fast field.
+        let query_ast: QueryAst = BoolQuery {
+            filter: vec![TermQuery {
+                field: "status".to_string(),
+                value: "200".to_string(),
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: serde_json::to_string(&query_ast).unwrap(),
+            start_timestamp: Some(start_timestamp + 10),
+            end_timestamp: Some(start_timestamp + 15),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let left_search_response = search_service_for_test(true)
+            .root_search(search_request.clone())
+            .await
+            .unwrap();
+        let right_search_response = search_service_for_test(false)
+            .root_search(search_request)
+            .await
+            .unwrap();
+        let search_diff_response =
+            diff_search_responses(&left_search_response, &right_search_response).unwrap();
+        assert!(search_diff_response.is_identical);
+        assert_eq!(search_diff_response.left.num_hits, 4);
+        assert_eq!(search_diff_response.right.num_hits, 4);
+
+        test_sandbox.assert_quit().await;
+    }
+}
@@ -113,7 +113,8 @@ pub(crate) async fn elastic_bulk_ingest_v2(
                     Some(ErrorCauseException::ActionRequestValidation),
                 )
             })?;
-        let subrequest_id = ingest_request_builder.add_doc(index_id, source);
+        let subrequest_id =
+            ingest_request_builder.add_doc_with_routing_key(index_id, meta.routing, source);
 
         per_subrequest_id_es_doc_ids
             .entry(subrequest_id)
@@ -127,9 +128,11 @@ pub(crate) async fn elastic_bulk_ingest_v2(
     }
     let ingest_request_opt = ingest_request_builder.build(INGEST_V2_SOURCE_ID, commit_type);
 
-    let Some(ingest_request) = ingest_request_opt else {
+    let Some(mut ingest_request) = ingest_request_opt else {
         return Ok(ElasticBulkResponse::default());
     };
+    ingest_request.best_effort_atomic = bulk_options.best_effort_atomic;
+
     let ingest_response_v2 = ingest_router.ingest(ingest_request).await?;
     let errors = !ingest_response_v2.failures.is_empty();
     let mut items = Vec::new();
@@ -276,6 +279,64 @@ mod tests {
         assert!(!bulk_response.errors);
     }
 
+    #[tokio::test]
+    async fn test_bulk_api_forwards_routing_keys() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .once()
+            .returning(|ingest_request| {
+                assert_eq!(ingest_request.subrequests.len(), 2);
+
+                let mut subrequests = ingest_request.subrequests;
+                subrequests.sort_by(|left, right| left.routing_key.cmp(&right.routing_key));
+
+                assert_eq!(subrequests[0].index_id, "my-index-1");
+                assert!(subrequests[0].routing_key.is_none());
+                assert_eq!(subrequests[0].doc_batch.as_ref().unwrap().num_docs(), 1);
+
+                assert_eq!(subrequests[1].index_id, "my-index-1");
+                assert_eq!(subrequests[1].routing_key(), "tenant-1");
+                assert_eq!(subrequests[1].doc_batch.as_ref().unwrap().num_docs(), 2);
+
+                let successes = subrequests
+                    .iter()
+                    .map(|subrequest| IngestSuccess {
+                        subrequest_id: subrequest.subrequest_id,
+                        index_uid: Some(IndexUid::for_test("my-index-1", 0)),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shard_id: Some(ShardId::from(1)),
+                        replication_position_inclusive: Some(Position::offset(0u64)),
+                    })
+                    .collect();
+                Ok(IngestResponseV2 {
+                    successes,
+                    failures: Vec::new(),
+                })
+            });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let handler = es_compat_bulk_handler_v2(ingest_router);
+
+        let payload = r#"
+            {"create": {"_index": "my-index-1", "routing": "tenant-1"}}
+            {"ts": 1, "message": "my-message-1"}
+            {"create": {"_index": "my-index-1"}}
+            {"ts": 1, "message": "my-message-1"}
+            {"create": {"_index": "my-index-1", "_routing": "tenant-1"}}
+            {"ts": 2, "message": "my-message-2"}
+        "#;
+        let response = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(!bulk_response.errors);
+    }
+
     #[tokio::test]
     async fn test_bulk_api_accepts_empty_requests() {
         let ingest_router = IngestRouterServiceClient::mocked();
@@ -25,7 +25,8 @@ use warp::{Filter, Rejection};
 
 use super::model::{
     CatIndexQueryParams, DeleteQueryParams, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
-    MultiSearchQueryParams, SearchQueryParamsCount,
+    MultiSearchQueryParams, OpenPointInTimeQueryParams, ResolveIndexQueryParams,
+    SearchQueryParamsCount,
 };
 use crate::decompression::get_body_bytes;
 use crate::elasticsearch_api::model::{
@@ -53,10 +54,11 @@ pub(crate) fn elastic_cluster_info_filter() -> impl Filter<Extract = (), Error =
 
 #[utoipa::path(get, tag = "Search", path = "/_search")]
 pub(crate) fn elasticsearch_filter(
-) -> impl Filter<Extract = (SearchQueryParams,), Error = Rejection> + Clone {
+) -> impl Filter<Extract = (SearchQueryParams, SearchBody), Error = Rejection> + Clone {
     warp::path!("_elastic" / "_search")
         .and(warp::get().or(warp::post()).unify())
         .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(json_or_empty())
 }
 
 #[utoipa::path(
@@ -213,6 +215,15 @@ pub(crate) fn elastic_cat_indices_filter(
         .and(serde_qs::warp::query(serde_qs::Config::default()))
 }
 
+#[utoipa::path(get, tag = "Metadata", path = "/_resolve/index/{expression}")]
+pub(crate) fn elastic_resolve_index_filter(
+) -> impl Filter<Extract = (Vec<String>, ResolveIndexQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_resolve" / "index" / String)
+        .and_then(extract_index_id_patterns)
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
 #[utoipa::path(get, tag = "Search", path = "/{index}/_search")]
 pub(crate) fn elastic_index_search_filter(
 ) -> impl Filter<Extract = (Vec<String>, SearchQueryParams, SearchBody), Error = Rejection> + Clone
@@ -224,6 +235,15 @@ pub(crate) fn elastic_index_search_filter(
         .and(json_or_empty())
 }
 
+#[utoipa::path(post, tag = "Search", path = "/{index}/_pit")]
+pub(crate) fn elastic_open_point_in_time_filter(
+) -> impl Filter<Extract = (Vec<String>, OpenPointInTimeQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_pit")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
 #[utoipa::path(post, tag = "Search", path = "/_msearch")]
 pub(crate) fn elastic_multi_search_filter(
 ) -> impl Filter<Extract = (Bytes, MultiSearchQueryParams), Error = Rejection> + Clone {
@@ -38,8 +38,9 @@ pub use rest_handler::{
     es_compat_cat_indices_handler, es_compat_cluster_info_handler, es_compat_delete_index_handler,
     es_compat_index_cat_indices_handler, es_compat_index_count_handler,
     es_compat_index_field_capabilities_handler, es_compat_index_multi_search_handler,
-    es_compat_index_search_handler, es_compat_index_stats_handler, es_compat_scroll_handler,
-    es_compat_search_handler, es_compat_stats_handler,
+    es_compat_index_search_handler, es_compat_index_stats_handler,
+    es_compat_open_point_in_time_handler, es_compat_resolve_index_handler,
+    es_compat_scroll_handler, es_compat_search_handler, es_compat_stats_handler,
 };
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
@@ -60,12 +61,19 @@ pub fn elastic_api_handlers(
     metastore: MetastoreServiceClient,
     index_service: IndexService,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let max_num_concurrent_msearch_searches = node_config
+        .searcher_config
+        .max_num_concurrent_msearch_searches;
     es_compat_cluster_info_handler(node_config, BuildInfo::get())
         .or(es_compat_search_handler(search_service.clone()))
         .or(es_compat_index_search_handler(search_service.clone()))
         .or(es_compat_index_count_handler(search_service.clone()))
         .or(es_compat_scroll_handler(search_service.clone()))
-        .or(es_compat_index_multi_search_handler(search_service.clone()))
+        .or(es_compat_open_point_in_time_handler(search_service.clone()))
+        .or(es_compat_index_multi_search_handler(
+            search_service.clone(),
+            max_num_concurrent_msearch_searches,
+        ))
         .or(es_compat_index_field_capabilities_handler(
             search_service.clone(),
         ))
@@ -79,6 +87,7 @@ pub fn elastic_api_handlers(
         .or(es_compat_stats_handler(metastore.clone()))
         .or(es_compat_index_cat_indices_handler(metastore.clone()))
         .or(es_compat_cat_indices_handler(metastore.clone()))
+        .or(es_compat_resolve_index_handler(metastore.clone()))
     // Register newly created handlers here.
 }
 
@@ -260,6 +269,40 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_msearch_api_return_400_with_zero_max_concurrent_searches() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mock_search_service = MockSearchService::new();
+
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+        );
+        let msearch_payload = r#"
+            {"index":"index-1"}
+            {"query":{"query_string":{"query":"test"}}}
+            "#;
+        let resp = warp::test::request()
+            .path("/_elastic/_msearch?max_concurrent_searches=0")
+            .method("POST")
+            .body(msearch_payload)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            es_error.error.reason.unwrap(),
+            "Invalid argument: `max_concurrent_searches` must be strictly positive"
+        );
+    }
+
     #[tokio::test]
     async fn test_msearch_api_return_400_with_malformed_request_header() {
         let config = Arc::new(NodeConfig::for_test());
@@ -439,6 +482,109 @@ mod tests {
         assert_eq!(resp.status(), 200);
     }
 
+    #[tokio::test]
+    async fn test_open_point_in_time_and_search_with_it() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_open_point_in_time()
+            .with(predicate::function(
+                |open_request: &quickwit_proto::search::OpenPointInTimeRequest| {
+                    open_request.index_id_patterns == vec!["index-1".to_string()]
+                        && open_request.keep_alive_secs == 60
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::search::OpenPointInTimeResponse {
+                    pit_id: "01HRF4B3XA0V4KBH4Z5C0NF5YR".to_string(),
+                    num_splits: 3,
+                })
+            });
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns.is_empty()
+                        && search_request.pit_id.as_deref() == Some("01HRF4B3XA0V4KBH4Z5C0NF5YR")
+                        && search_request.search_after.is_some()
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_pit?keep_alive=1m")
+            .method("POST")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            serde_json::json!({"id": "01HRF4B3XA0V4KBH4Z5C0NF5YR"})
+        );
+
+        let search_payload = r#"{
+            "pit": {"id": "01HRF4B3XA0V4KBH4Z5C0NF5YR", "keep_alive": "1m"},
+            "sort": [{"timestamp": "desc"}],
+            "search_after": [1700000000]
+        }"#;
+        let resp = warp::test::request()
+            .path("/_elastic/_search")
+            .method("POST")
+            .body(search_payload)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["pit_id"], "01HRF4B3XA0V4KBH4Z5C0NF5YR");
+    }
+
+    #[tokio::test]
+    async fn test_search_with_point_in_time_return_400_with_index() {
+        let config = Arc::new(NodeConfig::for_test());
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(MockSearchService::new()),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search")
+            .method("POST")
+            .body(r#"{"pit": {"id": "01HRF4B3XA0V4KBH4Z5C0NF5YR"}}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            es_error.error.reason.unwrap(),
+            "Invalid argument: indexes cannot be specified when searching with a point in time"
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search")
+            .method("POST")
+            .body(r#"{"query": {"match_all": {}}}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 501);
+    }
+
     #[tokio::test]
     async fn test_es_compat_cluster_info_handler() {
         let build_info = BuildInfo::get();
@@ -51,6 +51,10 @@ pub struct BulkActionMeta {
     #[serde(alias = "_id")]
     #[serde(default)]
     pub es_doc_id: Option<String>,
+    /// Documents sharing the same routing value are routed to the same shard.
+    #[serde(alias = "_routing")]
+    #[serde(default)]
+    pub routing: Option<String>,
 }
 
 #[cfg(test)]
@@ -73,6 +77,7 @@ mod tests {
                 BulkAction::Create(BulkActionMeta {
                     index_id: Some("test".to_string()),
                     es_doc_id: Some("2".to_string()),
+                    routing: None,
                 })
             );
         }
@@ -88,6 +93,7 @@ mod tests {
                 BulkAction::Create(BulkActionMeta {
                     index_id: Some("test".to_string()),
                     es_doc_id: None,
+                    routing: None,
                 })
             );
         }
@@ -103,6 +109,24 @@ mod tests {
                 BulkAction::Create(BulkActionMeta {
                     index_id: None,
                     es_doc_id: Some("3".to_string()),
+                    routing: None,
+                })
+            );
+        }
+        {
+            let bulk_action_json = r#"{
+                "index": {
+                    "_index": "test",
+                    "routing": "tenant-1"
+                }
+            }"#;
+            let bulk_action = serde_json::from_str::<BulkAction>(bulk_action_json).unwrap();
+            assert_eq!(
+                bulk_action,
+                BulkAction::Index(BulkActionMeta {
+                    index_id: Some("test".to_string()),
+                    es_doc_id: None,
+                    routing: Some("tenant-1".to_string()),
                 })
             );
         }
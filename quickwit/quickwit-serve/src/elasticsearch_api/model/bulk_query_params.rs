@@ -27,6 +27,10 @@ pub struct ElasticBulkOptions {
     pub refresh: ElasticRefresh,
     #[serde(default)]
     pub enable_ingest_v2: bool,
+    /// Ingests the documents of all the target indexes all or none, on a best effort basis when
+    /// the ingest requests are replicated (ingest API v2 only).
+    #[serde(default)]
+    pub best_effort_atomic: bool,
 }
 
 /// ?refresh parameter for elasticsearch bulk request
@@ -117,4 +121,18 @@ mod tests {
             "unknown variant `wait`, expected one of `false`, `true`, `wait_for`"
         );
     }
+
+    #[test]
+    fn test_elastic_bulk_options_best_effort_atomic_parsing() {
+        assert!(
+            !serde_qs::from_str::<ElasticBulkOptions>("")
+                .unwrap()
+                .best_effort_atomic
+        );
+        assert!(
+            serde_qs::from_str::<ElasticBulkOptions>("best_effort_atomic=true")
+                .unwrap()
+                .best_effort_atomic
+        );
+    }
 }
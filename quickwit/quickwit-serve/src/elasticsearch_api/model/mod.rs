@@ -23,6 +23,8 @@ mod cat_indices;
 mod error;
 mod field_capability;
 mod multi_search;
+mod point_in_time;
+mod resolve_index;
 mod scroll;
 mod search_body;
 mod search_query_params;
@@ -39,7 +41,15 @@ pub use field_capability::{
 pub use multi_search::{
     MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
 };
+pub(crate) use point_in_time::parse_keep_alive;
+pub use point_in_time::{
+    ElasticsearchOpenPointInTimeResponse, OpenPointInTimeQueryParams, PointInTime,
+};
 use quickwit_proto::search::{SortDatetimeFormat, SortOrder};
+pub use resolve_index::{
+    resolve_index_expression, ElasticsearchResolveIndexResponse, ResolveIndexQueryParams,
+    ResolvedAlias, ResolvedIndex,
+};
 pub use scroll::ScrollQueryParams;
 pub use search_body::SearchBody;
 pub use search_query_params::{DeleteQueryParams, SearchQueryParams, SearchQueryParamsCount};
@@ -0,0 +1,83 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_search::SearchError;
+use serde::{Deserialize, Serialize};
+
+/// Point in time passed in the body of a search request.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PointInTime {
+    pub id: String,
+    /// Accepted for compatibility. Quickwit does not extend the point in time: it expires after
+    /// the keep alive passed when it was opened.
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenPointInTimeQueryParams {
+    pub keep_alive: String,
+}
+
+impl OpenPointInTimeQueryParams {
+    pub fn parse_keep_alive(&self) -> Result<Duration, SearchError> {
+        parse_keep_alive(&self.keep_alive)
+    }
+}
+
+pub(crate) fn parse_keep_alive(keep_alive: &str) -> Result<Duration, SearchError> {
+    humantime::parse_duration(keep_alive).map_err(|_| {
+        SearchError::InvalidArgument(format!("invalid point in time keep alive: `{keep_alive}`"))
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElasticsearchOpenPointInTimeResponse {
+    pub id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_in_time_deserialization() {
+        let point_in_time: PointInTime =
+            serde_json::from_str(r#"{"id": "01HRF4B3XA0V4KBH4Z5C0NF5YR", "keep_alive": "1m"}"#)
+                .unwrap();
+        assert_eq!(point_in_time.id, "01HRF4B3XA0V4KBH4Z5C0NF5YR");
+        assert_eq!(point_in_time.keep_alive.as_deref(), Some("1m"));
+
+        serde_json::from_str::<PointInTime>(r#"{"id": "pit", "unknown": 1}"#).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_keep_alive() {
+        assert_eq!(parse_keep_alive("5m").unwrap(), Duration::from_secs(300));
+        let error = parse_keep_alive("five minutes").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: invalid point in time keep alive: `five minutes`"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use hyper::StatusCode;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{ElasticsearchError, ErrorCauseException};
+
+#[serde_with::skip_serializing_none]
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResolveIndexQueryParams {
+    /// If false, the request returns an error if a wildcard expression does not match any index
+    /// or alias. Defaults to true.
+    #[serde(default)]
+    pub allow_no_indices: Option<bool>,
+    /// Type of indexes that wildcard expressions can match. Quickwit indexes are always open.
+    /// Unsupported for now.
+    #[serde(default)]
+    pub expand_wildcards: Option<String>,
+    /// If true, missing indexes and aliases are ignored instead of returning an error. Defaults
+    /// to false.
+    #[serde(default)]
+    pub ignore_unavailable: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ElasticsearchResolveIndexResponse {
+    pub indices: Vec<ResolvedIndex>,
+    pub aliases: Vec<ResolvedAlias>,
+    /// Quickwit has no data streams. The field is kept for compatibility.
+    pub data_streams: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ResolvedIndex {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub attributes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ResolvedAlias {
+    pub name: String,
+    pub indices: Vec<String>,
+}
+
+/// Resolves a list of index ID patterns against the existing indexes and aliases, following the
+/// semantics of the Elasticsearch `_resolve/index` API:
+/// - a name without `*` designates an index or an alias, which must exist unless
+///   `ignore_unavailable` is set;
+/// - a pattern with `*` matches any number of indexes and aliases;
+/// - a pattern prefixed with `-` excludes the indexes and aliases it matches.
+///
+/// `aliases` maps each alias to the indexes it points to.
+pub fn resolve_index_expression(
+    index_id_patterns: &[String],
+    index_ids: &BTreeSet<String>,
+    aliases: &BTreeMap<String, Vec<String>>,
+    query_params: &ResolveIndexQueryParams,
+) -> Result<ElasticsearchResolveIndexResponse, ElasticsearchError> {
+    let mut resolved_index_ids: BTreeSet<&str> = BTreeSet::new();
+    let mut resolved_aliases: BTreeSet<&str> = BTreeSet::new();
+
+    for index_id_pattern in index_id_patterns {
+        if let Some(negative_pattern) = index_id_pattern.strip_prefix('-') {
+            let regex = build_pattern_regex(negative_pattern);
+            resolved_index_ids.retain(|index_id| !regex.is_match(index_id));
+            resolved_aliases.retain(|alias| !regex.is_match(alias));
+            continue;
+        }
+        if !index_id_pattern.contains('*') {
+            if let Some(index_id) = index_ids.get(index_id_pattern) {
+                resolved_index_ids.insert(index_id);
+            } else if let Some((alias, _)) = aliases.get_key_value(index_id_pattern) {
+                resolved_aliases.insert(alias);
+            } else if !query_params.ignore_unavailable.unwrap_or(false) {
+                return Err(ElasticsearchError::new(
+                    StatusCode::NOT_FOUND,
+                    format!("no such index [{index_id_pattern}]"),
+                    Some(ErrorCauseException::IndexNotFound),
+                ));
+            }
+            continue;
+        }
+        let regex = build_pattern_regex(index_id_pattern);
+        let num_resolved_before = resolved_index_ids.len() + resolved_aliases.len();
+
+        resolved_index_ids.extend(
+            index_ids
+                .iter()
+                .map(String::as_str)
+                .filter(|index_id| regex.is_match(index_id)),
+        );
+        resolved_aliases.extend(
+            aliases
+                .keys()
+                .map(String::as_str)
+                .filter(|alias| regex.is_match(alias)),
+        );
+        let num_resolved_after = resolved_index_ids.len() + resolved_aliases.len();
+
+        if num_resolved_before == num_resolved_after
+            && !query_params.allow_no_indices.unwrap_or(true)
+        {
+            return Err(ElasticsearchError::new(
+                StatusCode::NOT_FOUND,
+                format!("no such index [{index_id_pattern}]"),
+                Some(ErrorCauseException::IndexNotFound),
+            ));
+        }
+    }
+    let indices = resolved_index_ids
+        .into_iter()
+        .map(|index_id| {
+            let index_aliases = aliases
+                .iter()
+                .filter(|(_, alias_index_ids)| alias_index_ids.iter().any(|id| id == index_id))
+                .map(|(alias, _)| alias.clone())
+                .collect();
+            ResolvedIndex {
+                name: index_id.to_string(),
+                aliases: index_aliases,
+                attributes: vec!["open".to_string()],
+            }
+        })
+        .collect();
+    let aliases = resolved_aliases
+        .into_iter()
+        .map(|alias| ResolvedAlias {
+            name: alias.to_string(),
+            indices: aliases[alias].clone(),
+        })
+        .collect();
+    Ok(ElasticsearchResolveIndexResponse {
+        indices,
+        aliases,
+        data_streams: Vec::new(),
+    })
+}
+
+/// Builds a regex matching the index IDs and aliases matched by `pattern`, where `*` matches any
+/// sequence of characters.
+fn build_pattern_regex(pattern: &str) -> Regex {
+    let regex = format!(
+        "^{}$",
+        pattern
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*")
+    );
+    Regex::new(&regex).expect("regular expression should compile")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(
+        expression: &str,
+        query_params: ResolveIndexQueryParams,
+    ) -> Result<ElasticsearchResolveIndexResponse, ElasticsearchError> {
+        let index_ids: BTreeSet<String> = ["logs-000001", "logs-000002", "traces"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let aliases: BTreeMap<String, Vec<String>> = BTreeMap::from_iter([(
+            "logs".to_string(),
+            vec!["logs-000001".to_string(), "logs-000002".to_string()],
+        )]);
+        let index_id_patterns: Vec<String> = expression.split(',').map(String::from).collect();
+        resolve_index_expression(&index_id_patterns, &index_ids, &aliases, &query_params)
+    }
+
+    #[test]
+    fn test_resolve_index_expression() {
+        let response = resolve("logs*", ResolveIndexQueryParams::default()).unwrap();
+        let index_names: Vec<&str> = response
+            .indices
+            .iter()
+            .map(|index| index.name.as_str())
+            .collect();
+        assert_eq!(index_names, ["logs-000001", "logs-000002"]);
+        assert_eq!(response.indices[0].aliases, ["logs"]);
+        assert_eq!(response.indices[0].attributes, ["open"]);
+        assert_eq!(
+            response.aliases,
+            [ResolvedAlias {
+                name: "logs".to_string(),
+                indices: vec!["logs-000001".to_string(), "logs-000002".to_string()],
+            }]
+        );
+
+        let response = resolve("logs", ResolveIndexQueryParams::default()).unwrap();
+        assert!(response.indices.is_empty());
+        assert_eq!(response.aliases.len(), 1);
+
+        let response = resolve("*,-logs-000001,-logs", ResolveIndexQueryParams::default()).unwrap();
+        let index_names: Vec<&str> = response
+            .indices
+            .iter()
+            .map(|index| index.name.as_str())
+            .collect();
+        assert_eq!(index_names, ["logs-000002", "traces"]);
+        assert!(response.aliases.is_empty());
+
+        let response = resolve("foo*", ResolveIndexQueryParams::default()).unwrap();
+        assert_eq!(response, ElasticsearchResolveIndexResponse::default());
+    }
+
+    #[test]
+    fn test_resolve_index_expression_missing() {
+        let error = resolve("traces,foo", ResolveIndexQueryParams::default()).unwrap_err();
+        assert_eq!(error.status, StatusCode::NOT_FOUND);
+        assert_eq!(error.error.ty.unwrap(), "index_not_found_exception");
+
+        let query_params = ResolveIndexQueryParams {
+            ignore_unavailable: Some(true),
+            ..Default::default()
+        };
+        let response = resolve("traces,foo", query_params).unwrap();
+        assert_eq!(response.indices.len(), 1);
+
+        let query_params = ResolveIndexQueryParams {
+            allow_no_indices: Some(false),
+            ..Default::default()
+        };
+        let error = resolve("foo*", query_params).unwrap_err();
+        assert_eq!(error.status, StatusCode::NOT_FOUND);
+    }
+}
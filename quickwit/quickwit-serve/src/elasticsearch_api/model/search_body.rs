@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use quickwit_proto::search::SortOrder;
@@ -25,7 +25,7 @@ use quickwit_query::{ElasticQueryDsl, OneFieldMap};
 use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use super::ElasticDateFormat;
+use super::{ElasticDateFormat, PointInTime};
 use crate::elasticsearch_api::model::{default_elasticsearch_sort_order, SortField};
 use crate::elasticsearch_api::TrackTotalHits;
 
@@ -86,6 +86,27 @@ pub struct SearchBody {
     pub stored_fields: Option<BTreeSet<String>>,
     #[serde(default)]
     pub search_after: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub profile: bool,
+    #[serde(default)]
+    pub pit: Option<PointInTime>,
+    #[serde(default)]
+    pub highlight: Option<Highlight>,
+}
+
+/// Highlighting options of an Elasticsearch search request.
+///
+/// Per-field options are accepted but ignored.
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+pub struct Highlight {
+    #[serde(default)]
+    pub fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub pre_tags: Vec<String>,
+    #[serde(default)]
+    pub post_tags: Vec<String>,
+    #[serde(default)]
+    pub fragment_size: Option<u32>,
 }
 
 struct FieldSortVecVisitor;
@@ -249,6 +270,32 @@ mod tests {
         assert_eq!(field_sorts[3].order, SortOrder::Asc);
     }
 
+    #[test]
+    fn test_highlight() {
+        let json = r#"
+        {
+            "highlight": {
+                "pre_tags": ["<b>"],
+                "post_tags": ["</b>"],
+                "fragment_size": 100,
+                "fields": {
+                    "body": {},
+                    "title": { "number_of_fragments": 0 }
+                }
+            }
+        }
+        "#;
+        let search_body: SearchBody = serde_json::from_str(json).unwrap();
+        let highlight = search_body.highlight.unwrap();
+        assert_eq!(
+            highlight.fields.keys().collect::<Vec<_>>(),
+            vec!["body", "title"]
+        );
+        assert_eq!(highlight.pre_tags, vec!["<b>".to_string()]);
+        assert_eq!(highlight.post_tags, vec!["</b>".to_string()]);
+        assert_eq!(highlight.fragment_size, Some(100));
+    }
+
     #[test]
     fn test_unknown_field_behaviour() {
         let json = r#"
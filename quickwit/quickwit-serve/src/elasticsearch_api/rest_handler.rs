@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::str::from_utf8;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -29,13 +29,16 @@ use futures_util::StreamExt;
 use hyper::StatusCode;
 use itertools::Itertools;
 use quickwit_common::truncate_str;
-use quickwit_config::{validate_index_id_pattern, NodeConfig};
+use quickwit_config::{validate_index_id_pattern, IndexTemplate, NodeConfig, RolloverPolicy};
 use quickwit_index_management::IndexService;
 use quickwit_metastore::*;
-use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::metastore::{
+    FindIndexTemplateMatchesRequest, ListIndexesMetadataRequest, MetastoreService,
+    MetastoreServiceClient,
+};
 use quickwit_proto::search::{
-    CountHits, ListFieldsResponse, PartialHit, ScrollRequest, SearchResponse, SortByValue,
-    SortDatetimeFormat,
+    CountHits, HighlightRequest, ListFieldsResponse, OpenPointInTimeRequest, PartialHit,
+    ScrollRequest, SearchPriority, SearchResponse, SortByValue, SortDatetimeFormat,
 };
 use quickwit_proto::types::IndexUid;
 use quickwit_query::query_ast::{BoolQuery, QueryAst, UserInputQuery};
@@ -50,14 +53,17 @@ use super::filter::{
     elastic_field_capabilities_filter, elastic_index_cat_indices_filter,
     elastic_index_count_filter, elastic_index_field_capabilities_filter,
     elastic_index_search_filter, elastic_index_stats_filter, elastic_multi_search_filter,
-    elastic_scroll_filter, elastic_stats_filter, elasticsearch_filter,
+    elastic_open_point_in_time_filter, elastic_resolve_index_filter, elastic_scroll_filter,
+    elastic_stats_filter, elasticsearch_filter,
 };
 use super::model::{
     build_list_field_request_for_es_api, convert_to_es_field_capabilities_response,
-    CatIndexQueryParams, DeleteQueryParams, ElasticsearchCatIndexResponse, ElasticsearchError,
-    ElasticsearchStatsResponse, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
-    FieldCapabilityResponse, MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse,
-    MultiSearchSingleResponse, ScrollQueryParams, SearchBody, SearchQueryParams,
+    parse_keep_alive, resolve_index_expression, CatIndexQueryParams, DeleteQueryParams,
+    ElasticsearchCatIndexResponse, ElasticsearchError, ElasticsearchOpenPointInTimeResponse,
+    ElasticsearchResolveIndexResponse, ElasticsearchStatsResponse, FieldCapabilityQueryParams,
+    FieldCapabilityRequestBody, FieldCapabilityResponse, MultiSearchHeader, MultiSearchQueryParams,
+    MultiSearchResponse, MultiSearchSingleResponse, OpenPointInTimeQueryParams,
+    ResolveIndexQueryParams, ScrollQueryParams, SearchBody, SearchQueryParams,
     SearchQueryParamsCount, StatsResponseEntry,
 };
 use super::{make_elastic_api_response, TrackTotalHits};
@@ -91,22 +97,33 @@ pub fn es_compat_cluster_info_handler(
 
 /// GET or POST _elastic/_search
 pub fn es_compat_search_handler(
-    _search_service: Arc<dyn SearchService>,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    elasticsearch_filter().then(|_params: SearchQueryParams| async move {
-        // TODO
-        let api_error = RestApiError {
-            status_code: StatusCode::NOT_IMPLEMENTED,
-            message: "_elastic/_search is not supported yet. Please try the index search endpoint \
-                      (_elastic/{index}/search)"
-                .to_string(),
-        };
-        RestApiResponse::new::<(), _>(
-            &Err(api_error),
-            StatusCode::NOT_IMPLEMENTED,
-            BodyFormat::default(),
-        )
-    })
+    elasticsearch_filter().and(with_arg(search_service)).then(
+        |search_params: SearchQueryParams,
+         search_body: SearchBody,
+         search_service: Arc<dyn SearchService>| async move {
+            // Without an index in the path, we only support searches on a point in time, which
+            // determines the searched indexes.
+            if search_body.pit.is_none() {
+                let api_error = RestApiError {
+                    status_code: StatusCode::NOT_IMPLEMENTED,
+                    message: "_elastic/_search is only supported with a point in time. Please try \
+                              the index search endpoint (_elastic/{index}/search)"
+                        .to_string(),
+                };
+                return RestApiResponse::new::<(), _>(
+                    &Err(api_error),
+                    StatusCode::NOT_IMPLEMENTED,
+                    BodyFormat::default(),
+                );
+            }
+            let search_result =
+                es_compat_index_search(Vec::new(), search_params, search_body, search_service)
+                    .await;
+            make_elastic_api_response(search_result, BodyFormat::default())
+        },
+    )
 }
 
 /// GET or POST _elastic/{index}/_field_caps
@@ -171,6 +188,16 @@ pub fn es_compat_index_cat_indices_handler(
         .map(|result| make_elastic_api_response(result, BodyFormat::default()))
 }
 
+/// GET _elastic/_resolve/index/{expression}
+pub fn es_compat_resolve_index_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_resolve_index_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_resolve_index)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+}
+
 /// GET or POST _elastic/{index}/_search
 pub fn es_compat_index_search_handler(
     search_service: Arc<dyn SearchService>,
@@ -191,12 +218,14 @@ pub fn es_compat_index_count_handler(
         .map(|result| make_elastic_api_response(result, BodyFormat::default()))
 }
 
-/// POST _elastic/_search
+/// POST _elastic/_msearch
 pub fn es_compat_index_multi_search_handler(
     search_service: Arc<dyn SearchService>,
+    max_num_concurrent_searches: usize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     elastic_multi_search_filter()
         .and(with_arg(search_service))
+        .and(with_arg(max_num_concurrent_searches))
         .then(es_compat_index_multi_search)
         .map(|result: Result<MultiSearchResponse, ElasticsearchError>| {
             let status_code = match &result {
@@ -207,6 +236,16 @@ pub fn es_compat_index_multi_search_handler(
         })
 }
 
+/// POST _elastic/{index}/_pit
+pub fn es_compat_open_point_in_time_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_open_point_in_time_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_open_point_in_time)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+}
+
 /// GET or POST _elastic/_search/scroll
 pub fn es_compat_scroll_handler(
     search_service: Arc<dyn SearchService>,
@@ -304,6 +343,41 @@ fn build_request_for_es_api(
     let scroll_duration: Option<Duration> = search_params.parse_scroll_ttl()?;
     let scroll_ttl_secs: Option<u32> = scroll_duration.map(|duration| duration.as_secs() as u32);
 
+    let pit_id: Option<String> = if let Some(point_in_time) = search_body.pit {
+        if !index_id_patterns.is_empty() {
+            return Err(ElasticsearchError::from(SearchError::InvalidArgument(
+                "indexes cannot be specified when searching with a point in time".to_string(),
+            )));
+        }
+        if scroll_ttl_secs.is_some() {
+            return Err(ElasticsearchError::from(SearchError::InvalidArgument(
+                "scroll cannot be used with a point in time".to_string(),
+            )));
+        }
+        if let Some(keep_alive) = &point_in_time.keep_alive {
+            parse_keep_alive(keep_alive)?;
+        }
+        Some(point_in_time.id)
+    } else {
+        None
+    };
+
+    let highlight: Option<HighlightRequest> = if let Some(highlight) = search_body.highlight {
+        if let Some(field_pattern) = highlight.fields.keys().find(|field| field.contains('*')) {
+            return Err(ElasticsearchError::from(SearchError::InvalidArgument(
+                format!("highlight field patterns are not supported. got `{field_pattern}`"),
+            )));
+        }
+        Some(HighlightRequest {
+            fields: highlight.fields.into_keys().collect(),
+            pre_tag: highlight.pre_tags.into_iter().next(),
+            post_tag: highlight.post_tags.into_iter().next(),
+            fragment_size: highlight.fragment_size,
+        })
+    } else {
+        None
+    };
+
     let has_doc_id_field = sort_fields.iter().any(is_doc_field);
     let search_after = partial_hit_from_search_after_param(search_body.search_after, &sort_fields)?;
 
@@ -321,6 +395,16 @@ fn build_request_for_es_api(
             scroll_ttl_secs,
             search_after,
             count_hits,
+            include_held_splits: false,
+            legal_hold_token: None,
+            bypass_cache: false,
+            max_staleness_secs: None,
+            tenant_id: None,
+            priority: SearchPriority::Interactive as i32,
+            profile: search_body.profile,
+            pit_id,
+            lookups: Vec::new(),
+            highlight,
         },
         has_doc_id_field,
     ))
@@ -414,14 +498,16 @@ async fn es_compat_index_search(
     search_params: SearchQueryParams,
     search_body: SearchBody,
     search_service: Arc<dyn SearchService>,
-) -> Result<ElasticsearchResponse, ElasticsearchError> {
+) -> Result<serde_json::Value, ElasticsearchError> {
     let _source_excludes = search_params._source_excludes.clone();
     let _source_includes = search_params._source_includes.clone();
     let start_instant = Instant::now();
     let (search_request, append_shard_doc) =
         build_request_for_es_api(index_id_patterns, search_params, search_body)?;
-    let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    let pit_id_opt = search_request.pit_id.clone();
+    let mut search_response: SearchResponse = search_service.root_search(search_request).await?;
     let elapsed = start_instant.elapsed();
+    let profile_opt = search_response.profile.take();
     let mut search_response_rest: ElasticsearchResponse = convert_to_es_search_response(
         search_response,
         append_shard_doc,
@@ -429,7 +515,36 @@ async fn es_compat_index_search(
         _source_includes,
     );
     search_response_rest.took = elapsed.as_millis() as u32;
-    Ok(search_response_rest)
+
+    let mut search_response_json = serde_json::to_value(search_response_rest)
+        .map_err(|error| SearchError::Internal(error.to_string()))?;
+    // The search profile is returned in the Quickwit format, which differs from the format of
+    // the Elasticsearch profile API.
+    if let Some(profile) = profile_opt {
+        search_response_json["profile"] = serde_json::to_value(profile)
+            .map_err(|error| SearchError::Internal(error.to_string()))?;
+    }
+    // The point in time is not extended by searches, so its ID never changes.
+    if let Some(pit_id) = pit_id_opt {
+        search_response_json["pit_id"] = serde_json::Value::String(pit_id);
+    }
+    Ok(search_response_json)
+}
+
+async fn es_compat_open_point_in_time(
+    index_id_patterns: Vec<String>,
+    query_params: OpenPointInTimeQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ElasticsearchOpenPointInTimeResponse, ElasticsearchError> {
+    let keep_alive = query_params.parse_keep_alive()?;
+    let open_request = OpenPointInTimeRequest {
+        index_id_patterns,
+        keep_alive_secs: u32::try_from(keep_alive.as_secs()).unwrap_or(u32::MAX),
+    };
+    let open_response = search_service.open_point_in_time(open_request).await?;
+    Ok(ElasticsearchOpenPointInTimeResponse {
+        id: open_response.pit_id,
+    })
 }
 
 /// Returns JSON in the format:
@@ -542,6 +657,81 @@ async fn es_compat_index_cat_indices(
     Ok(search_response_rest)
 }
 
+async fn es_compat_resolve_index(
+    index_id_patterns: Vec<String>,
+    query_params: ResolveIndexQueryParams,
+    mut metastore: MetastoreServiceClient,
+) -> Result<ElasticsearchResolveIndexResponse, ElasticsearchError> {
+    let index_ids: BTreeSet<String> = metastore
+        .list_indexes_metadata(ListIndexesMetadataRequest::all())
+        .await
+        .map_err(SearchError::from)?
+        .deserialize_indexes_metadata()
+        .await
+        .map_err(SearchError::from)?
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_id().to_string())
+        .collect();
+    let aliases = resolve_write_aliases(&index_ids, &mut metastore).await?;
+    resolve_index_expression(&index_id_patterns, &index_ids, &aliases, &query_params)
+}
+
+/// Returns the write aliases of the indexes `index_ids`, mapped to their write indexes. Write
+/// aliases are not persisted: an index `<alias>-<generation>` belongs to the alias `<alias>` if
+/// `<alias>` is not an index itself and matches an index template with rollover enabled.
+async fn resolve_write_aliases(
+    index_ids: &BTreeSet<String>,
+    metastore: &mut MetastoreServiceClient,
+) -> Result<BTreeMap<String, Vec<String>>, ElasticsearchError> {
+    let mut aliases: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for index_id in index_ids {
+        let Some((alias, _)) = index_id.rsplit_once('-') else {
+            continue;
+        };
+        if !index_ids.contains(alias) && RolloverPolicy::parse_generation(alias, index_id).is_some()
+        {
+            aliases
+                .entry(alias.to_string())
+                .or_default()
+                .push(index_id.clone());
+        }
+    }
+    if aliases.is_empty() {
+        return Ok(aliases);
+    }
+    let find_index_template_matches_request = FindIndexTemplateMatchesRequest {
+        index_ids: aliases.keys().cloned().collect(),
+    };
+    let index_template_matches = metastore
+        .find_index_template_matches(find_index_template_matches_request)
+        .await
+        .map_err(SearchError::from)?
+        .matches;
+
+    let mut write_aliases = BTreeMap::new();
+
+    for index_template_match in index_template_matches {
+        let index_template: IndexTemplate =
+            serde_json::from_str(&index_template_match.index_template_json).map_err(|error| {
+                ElasticsearchError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("failed to deserialize index template: {error}"),
+                    None,
+                )
+            })?;
+        if index_template.rollover_policy_opt.is_none() {
+            continue;
+        }
+        let alias = index_template_match.index_id;
+
+        if let Some(write_index_ids) = aliases.remove(&alias) {
+            write_aliases.insert(alias, write_index_ids);
+        }
+    }
+    Ok(write_aliases)
+}
+
 async fn es_compat_index_field_capabilities(
     index_id_patterns: Vec<String>,
     search_params: FieldCapabilityQueryParams,
@@ -680,7 +870,10 @@ fn convert_hit(
         score: None,
         nested: None,
         source,
-        highlight: Default::default(),
+        highlight: hit
+            .highlight
+            .and_then(|highlight_json| serde_json::from_str(&highlight_json).ok())
+            .unwrap_or_default(),
         inner_hits: Default::default(),
         matched_queries: Vec::default(),
         sort,
@@ -691,7 +884,20 @@ async fn es_compat_index_multi_search(
     payload: Bytes,
     multi_search_params: MultiSearchQueryParams,
     search_service: Arc<dyn SearchService>,
+    max_num_concurrent_searches: usize,
 ) -> Result<MultiSearchResponse, ElasticsearchError> {
+    // The request can lower the limit set in the searcher config, but not raise it.
+    let max_concurrent_searches = match multi_search_params.max_concurrent_searches {
+        Some(0) => {
+            return Err(ElasticsearchError::from(SearchError::InvalidArgument(
+                "`max_concurrent_searches` must be strictly positive".to_string(),
+            )));
+        }
+        Some(max_concurrent_searches) => {
+            (max_concurrent_searches as usize).min(max_num_concurrent_searches)
+        }
+        None => max_num_concurrent_searches,
+    };
     let mut search_requests = Vec::new();
     let str_payload = from_utf8(&payload)
         .map_err(|err| SearchError::InvalidQuery(format!("invalid UTF-8: {}", err)))?;
@@ -770,10 +976,9 @@ async fn es_compat_index_multi_search(
                 Ok::<_, ElasticsearchError>(search_response_rest)
             }
         });
-    let max_concurrent_searches =
-        multi_search_params.max_concurrent_searches.unwrap_or(10) as usize;
+    // Responses must be returned in the order of the requests.
     let search_responses = futures::stream::iter(futures)
-        .buffer_unordered(max_concurrent_searches)
+        .buffered(max_concurrent_searches)
         .collect::<Vec<_>>()
         .await;
     let responses = search_responses
@@ -0,0 +1,45 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use async_trait::async_trait;
+use quickwit_indexing::ParquetSplitMaterializer;
+use quickwit_search::{ExternalSplitMaterializer, MaterializeExternalSplitsRequest};
+
+/// Materializes the files of the lazy Parquet sources targeted by the root searches of this node
+/// with one-shot indexing pipelines.
+pub(crate) struct ParquetExternalSplitMaterializer(pub ParquetSplitMaterializer);
+
+#[async_trait]
+impl ExternalSplitMaterializer for ParquetExternalSplitMaterializer {
+    async fn materialize_external_splits(
+        &self,
+        request: MaterializeExternalSplitsRequest,
+    ) -> anyhow::Result<()> {
+        self.0
+            .materialize(
+                request.index_uid,
+                request.index_config,
+                request.source_config,
+                request.filepaths,
+                request.start_timestamp,
+                request.end_timestamp,
+            )
+            .await
+    }
+}
@@ -20,18 +20,24 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
+use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_config::{
-    load_source_config_from_user_config, validate_index_id_pattern, ConfigFormat, NodeConfig,
-    RetentionPolicy, SearchSettings, SourceConfig, SourceParams, CLI_SOURCE_ID,
-    INGEST_API_SOURCE_ID,
+    load_source_config_from_user_config, validate_index_id_pattern, ConfigFormat, DocMapping,
+    LegalHold, NodeConfig, RetentionPolicy, SearchSettings, SourceConfig, SourceParams,
+    CLI_SOURCE_ID, INGEST_API_SOURCE_ID,
 };
 use quickwit_doc_mapper::{analyze_text, TokenizerConfig};
 use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_indexing::merge_policy::{
+    decide_split_num_docs_target, AccessPattern, SplitSizeDecision,
+};
+use quickwit_indexing::models::QueryAccessStatsRegistry;
+use quickwit_indexing::{dry_run_transform, TransformDryRunAction, TransformDryRunResult};
 use quickwit_metastore::{
-    IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt, ListSplitsQuery,
-    ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, Split, SplitInfo, SplitState,
-    UpdateIndexRequestExt,
+    get_reindex_progress, IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt,
+    ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, ReindexProgress,
+    ReindexStage, Split, SplitInfo, SplitState, SplitStorageStats, UpdateIndexRequestExt,
 };
 use quickwit_proto::metastore::{
     DeleteSourceRequest, EntityKind, IndexMetadataRequest, ListIndexesMetadataRequest,
@@ -39,6 +45,7 @@ use quickwit_proto::metastore::{
     MetastoreService, MetastoreServiceClient, ResetSourceCheckpointRequest, ToggleSourceRequest,
     UpdateIndexRequest,
 };
+use quickwit_proto::search::QueryAccessStats;
 use quickwit_proto::types::IndexUid;
 use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
 use serde::de::DeserializeOwned;
@@ -61,13 +68,29 @@ use crate::with_arg;
         list_indexes_metadata,
         list_splits,
         describe_index,
+        get_index_storage_stats,
         mark_splits_for_deletion,
         create_source,
         reset_source_checkpoint,
         toggle_source,
+        get_source_reindex_progress,
+        dry_run_source_transform,
         delete_source,
     ),
-    components(schemas(ToggleSource, SplitsForDeletion, IndexStats, IndexUpdates))
+    components(schemas(
+        ToggleSource,
+        SplitsForDeletion,
+        IndexStats,
+        SplitSizeDecision,
+        AccessPattern,
+        QueryAccessStats,
+        IndexStorageStats,
+        IndexUpdates,
+        ReindexProgress,
+        ReindexStage,
+        TransformDryRunAction,
+        TransformDryRunResult,
+    ))
 )]
 pub struct IndexApi;
 
@@ -85,6 +108,7 @@ fn log_failure<T, E: std::fmt::Display>(
 pub fn index_management_handlers(
     index_service: IndexService,
     node_config: Arc<NodeConfig>,
+    query_access_stats_registry: QueryAccessStatsRegistry,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     // Indexes handlers.
     get_index_metadata_handler(index_service.metastore())
@@ -95,11 +119,19 @@ pub fn index_management_handlers(
         .or(delete_index_handler(index_service.clone()))
         // Splits handlers
         .or(list_splits_handler(index_service.metastore()))
-        .or(describe_index_handler(index_service.metastore()))
+        .or(describe_index_handler(
+            index_service.metastore(),
+            query_access_stats_registry,
+        ))
+        .or(get_index_storage_stats_handler(index_service.metastore()))
         .or(mark_splits_for_deletion_handler(index_service.metastore()))
         // Sources handlers.
         .or(reset_source_checkpoint_handler(index_service.metastore()))
         .or(toggle_source_handler(index_service.metastore()))
+        .or(get_source_reindex_progress_handler(
+            index_service.metastore(),
+        ))
+        .or(dry_run_source_transform_handler(index_service.metastore()))
         .or(create_source_handler(index_service.clone()))
         .or(get_source_handler(index_service.metastore()))
         .or(delete_source_handler(index_service.metastore()))
@@ -174,6 +206,9 @@ struct IndexStats {
     pub timestamp_field_name: Option<String>,
     pub min_timestamp: Option<i64>,
     pub max_timestamp: Option<i64>,
+    /// Target number of documents of the splits of the index, adapted to its query access
+    /// patterns when the adaptive split size is enabled.
+    pub split_size: SplitSizeDecision,
 }
 
 #[utoipa::path(
@@ -192,6 +227,7 @@ struct IndexStats {
 async fn describe_index(
     index_id: String,
     mut metastore: MetastoreServiceClient,
+    query_access_stats_registry: QueryAccessStatsRegistry,
 ) -> MetastoreResult<IndexStats> {
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_metadata = metastore
@@ -230,7 +266,12 @@ async fn describe_index(
         }
     }
 
+    let query_access_stats_opt = query_access_stats_registry.index_stats(&index_metadata.index_uid);
     let index_config = index_metadata.into_index_config();
+    let split_size = decide_split_num_docs_target(
+        &index_config.indexing_settings,
+        query_access_stats_opt.as_ref(),
+    );
     let index_stats = IndexStats {
         index_id,
         index_uri: index_config.index_uri.clone(),
@@ -241,6 +282,7 @@ async fn describe_index(
         timestamp_field_name: index_config.doc_mapping.timestamp_field,
         min_timestamp,
         max_timestamp,
+        split_size,
     };
 
     Ok(index_stats)
@@ -248,15 +290,153 @@ async fn describe_index(
 
 fn describe_index_handler(
     metastore: MetastoreServiceClient,
+    query_access_stats_registry: QueryAccessStatsRegistry,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes" / String / "describe")
         .and(warp::get())
         .and(with_arg(metastore))
+        .and(with_arg(query_access_stats_registry))
         .then(describe_index)
         .and(extract_format_from_qs())
         .map(into_rest_api_response)
 }
 
+#[derive(Debug, Clone, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct IndexStorageStatsQueryParams {
+    /// Maximum number of fields to return, largest first.
+    #[serde(default = "IndexStorageStatsQueryParams::default_top_fields")]
+    top_fields: usize,
+}
+
+impl IndexStorageStatsQueryParams {
+    fn default_top_fields() -> usize {
+        10
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexStorageStats {
+    pub index_id: String,
+    pub num_published_splits: usize,
+    /// Number of published splits created before their storage stats were recorded. These splits
+    /// only contribute to `total_num_bytes`.
+    pub num_splits_without_storage_stats: usize,
+    pub total_num_bytes: u64,
+    pub doc_store_num_bytes: u64,
+    pub postings_num_bytes: u64,
+    pub fast_fields_num_bytes: u64,
+    pub hotcache_num_bytes: u64,
+    pub top_fields: Vec<FieldStorageStats>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FieldStorageStats {
+    pub field_name: String,
+    pub num_bytes: u64,
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/storage-stats",
+    responses(
+        (status = 200, description = "Successfully fetched the storage stats of the index.", body = IndexStorageStats)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to get the storage stats of."),
+        IndexStorageStatsQueryParams,
+    )
+)]
+/// Gets the storage stats of an index.
+///
+/// Breaks down the size of the published splits of the index by component (doc store, postings,
+/// fast fields, hotcache) and reports the largest fields. The stats of each split are computed when
+/// the split is created and summed up on request.
+async fn get_index_storage_stats(
+    index_id: String,
+    query_params: IndexStorageStatsQueryParams,
+    mut metastore: MetastoreServiceClient,
+) -> MetastoreResult<IndexStorageStats> {
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+    let index_uid = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?
+        .index_uid;
+    let query = ListSplitsQuery::for_index(index_uid).with_split_state(SplitState::Published);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits()
+        .await?;
+    let mut total_num_bytes = 0;
+    let mut num_splits_without_storage_stats = 0;
+    let mut index_storage_stats = SplitStorageStats::default();
+
+    for split in &splits {
+        total_num_bytes += split.split_metadata.footer_offsets.end;
+
+        let Some(split_storage_stats) = &split.split_metadata.storage_stats else {
+            num_splits_without_storage_stats += 1;
+            continue;
+        };
+        index_storage_stats.doc_store_num_bytes += split_storage_stats.doc_store_num_bytes;
+        index_storage_stats.postings_num_bytes += split_storage_stats.postings_num_bytes;
+        index_storage_stats.fast_fields_num_bytes += split_storage_stats.fast_fields_num_bytes;
+        index_storage_stats.hotcache_num_bytes += split_storage_stats.hotcache_num_bytes;
+
+        for (field_name, num_bytes) in &split_storage_stats.per_field_num_bytes {
+            *index_storage_stats
+                .per_field_num_bytes
+                .entry(field_name.clone())
+                .or_default() += num_bytes;
+        }
+    }
+    let top_fields = index_storage_stats
+        .per_field_num_bytes
+        .into_iter()
+        .sorted_by(
+            |(left_name, left_num_bytes), (right_name, right_num_bytes)| {
+                right_num_bytes
+                    .cmp(left_num_bytes)
+                    .then_with(|| left_name.cmp(right_name))
+            },
+        )
+        .take(query_params.top_fields)
+        .map(|(field_name, num_bytes)| FieldStorageStats {
+            field_name,
+            num_bytes,
+        })
+        .collect();
+
+    let index_storage_stats = IndexStorageStats {
+        index_id,
+        num_published_splits: splits.len(),
+        num_splits_without_storage_stats,
+        total_num_bytes,
+        doc_store_num_bytes: index_storage_stats.doc_store_num_bytes,
+        postings_num_bytes: index_storage_stats.postings_num_bytes,
+        fast_fields_num_bytes: index_storage_stats.fast_fields_num_bytes,
+        hotcache_num_bytes: index_storage_stats.hotcache_num_bytes,
+        top_fields,
+    };
+    Ok(index_storage_stats)
+}
+
+fn get_index_storage_stats_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "storage-stats")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(metastore))
+        .then(get_index_storage_stats)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
 /// This struct represents the QueryString passed to
 /// the rest API to filter splits.
 #[derive(Debug, Clone, Deserialize, Serialize, utoipa::IntoParams, utoipa::ToSchema, Default)]
@@ -527,13 +707,25 @@ async fn create_index(
 }
 
 /// The body of the index update request. All fields will be replaced in the
-/// existing configuration.
-#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Default, utoipa::ToSchema)]
+/// existing configuration, except the legal hold and the doc mapping, which are left unchanged
+/// when omitted.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Default, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)] // Remove when adding new fields to allow to ensure forward compatibility
 pub struct IndexUpdates {
     pub search_settings: SearchSettings,
     #[serde(rename = "retention_policy")]
     pub retention_policy_opt: Option<RetentionPolicy>,
+    /// The legal hold is lifted when explicitly set to `null`.
+    #[serde(rename = "legal_hold")]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<LegalHold>)]
+    pub legal_hold_opt: Option<Option<LegalHold>>,
+    /// Only the `fast` option of existing fields can be enabled.
+    #[serde(rename = "doc_mapping")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_mapping_opt: Option<DocMapping>,
 }
 
 fn update_index_handler(
@@ -565,7 +757,10 @@ fn update_index_handler(
 ///
 /// This endpoint has PUT semantics, which means that all the updatable fields of the index
 /// configuration are replaced by the values specified in the request. In particular, omitting an
-/// optional field like `retention_policy` will delete the associated configuration.
+/// optional field like `retention_policy` will delete the associated configuration. The legal hold
+/// is an exception: it is left unchanged when omitted and only lifted when explicitly set to
+/// `null`. The doc mapping is the other exception: it is left unchanged when omitted, and only the
+/// `fast` option of its existing fields can be enabled.
 async fn update_index(
     index_id: String,
     request: IndexUpdates,
@@ -583,6 +778,8 @@ async fn update_index(
         index_uid,
         &request.search_settings,
         &request.retention_policy_opt,
+        &request.legal_hold_opt,
+        request.doc_mapping_opt.as_ref(),
     )?;
     let update_resp = metastore.update_index(update_request).await?;
     Ok(update_resp.deserialize_index_metadata()?)
@@ -859,6 +1056,128 @@ async fn toggle_source(
     Ok(())
 }
 
+fn get_source_reindex_progress_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "reindex-progress")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_source_reindex_progress)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/reindex-progress",
+    responses(
+        (status = 200, description = "Successfully fetched reindex progress.", body = ReindexProgress)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The ID of the reindex source."),
+    )
+)]
+/// Gets the progress of a reindex source.
+async fn get_source_reindex_progress(
+    index_id: String,
+    source_id: String,
+    mut metastore: MetastoreServiceClient,
+) -> Result<ReindexProgress, IndexServiceError> {
+    info!(index_id = %index_id, source_id = %source_id, "get-source-reindex-progress");
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let Some(source_config) = index_metadata.sources.get(&source_id) else {
+        return Err(MetastoreError::NotFound(EntityKind::Source {
+            index_id,
+            source_id,
+        })
+        .into());
+    };
+    let SourceParams::Reindex(reindex_params) = &source_config.source_params else {
+        return Err(IndexServiceError::OperationNotAllowed(format!(
+            "source `{source_id}` is not a reindex source"
+        )));
+    };
+    let checkpoint = index_metadata
+        .checkpoint
+        .source_checkpoint(&source_id)
+        .cloned()
+        .unwrap_or_default();
+    let reindex_progress = get_reindex_progress(
+        &mut metastore,
+        reindex_params,
+        &checkpoint,
+        source_config.enabled,
+    )
+    .await?;
+    Ok(reindex_progress)
+}
+
+fn dry_run_source_transform_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "transform" / "dry-run")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::filters::body::bytes())
+        .and(with_arg(metastore))
+        .then(dry_run_source_transform)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/transform/dry-run",
+    request_body(content = String, description = "Sample document, in the input format of the source.", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successfully ran the sample document through the transform.", body = TransformDryRunResult)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The source ID whose transform is run."),
+    )
+)]
+/// Runs a sample document through the transform of a source without indexing it.
+async fn dry_run_source_transform(
+    index_id: String,
+    source_id: String,
+    sample_doc: Bytes,
+    mut metastore: MetastoreServiceClient,
+) -> Result<TransformDryRunResult, IndexServiceError> {
+    info!(index_id = %index_id, source_id = %source_id, "dry-run-source-transform");
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let Some(source_config) = index_metadata.sources.get(&source_id) else {
+        return Err(MetastoreError::NotFound(EntityKind::Source {
+            index_id,
+            source_id,
+        })
+        .into());
+    };
+    let Some(transform_config) = source_config.transform_config.clone() else {
+        return Err(IndexServiceError::OperationNotAllowed(format!(
+            "source `{source_id}` has no transform"
+        )));
+    };
+    dry_run_transform(
+        &index_id,
+        transform_config,
+        source_config.input_format,
+        sample_doc,
+    )
+    .map_err(IndexServiceError::InvalidConfig)
+}
+
 fn delete_source_handler(
     metastore: MetastoreServiceClient,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
@@ -1030,9 +1349,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/test-index")
             .reply(&index_management_handler)
@@ -1054,9 +1376,12 @@ mod tests {
     async fn test_get_non_existing_index() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/test-index")
             .reply(&index_management_handler)
@@ -1103,9 +1428,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         {
             let resp = warp::test::request()
                 .path(
@@ -1147,6 +1475,17 @@ mod tests {
         let index_metadata =
             IndexMetadata::for_test("quickwit-demo-index", "ram:///indexes/quickwit-demo-index");
         let index_uid = index_metadata.index_uid.clone();
+        let query_access_stats_registry = QueryAccessStatsRegistry::default();
+        query_access_stats_registry.update(
+            "searcher".into(),
+            index_uid.clone(),
+            QueryAccessStats {
+                num_queries: 1_000,
+                num_scan_queries: 10,
+                selectivity: 0.0001,
+                latency_millis: 25.0,
+            },
+        );
         mock_metastore
             .expect_index_metadata()
             .return_once(move |_| {
@@ -1179,9 +1518,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            query_access_stats_registry,
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/describe")
             .reply(&index_management_handler)
@@ -1199,12 +1541,109 @@ mod tests {
             "timestamp_field_name": "timestamp",
             "min_timestamp": split_1_time_range.start() - 10,
             "max_timestamp": split_1_time_range.end() + 10,
+            "split_size": {
+                "adaptive": false,
+                "access_pattern": "selective",
+                "base_split_num_docs_target": 10_000_000,
+                "split_num_docs_target": 10_000_000,
+                "query_access_stats": {
+                    "num_queries": 1_000,
+                    "num_scan_queries": 10,
+                    "selectivity": 0.0001,
+                    "latency_millis": 25.0,
+                },
+            },
         });
 
         assert_eq!(actual_response_json, expected_response_json);
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_index_storage_stats() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata =
+            IndexMetadata::for_test("quickwit-demo-index", "ram:///indexes/quickwit-demo-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_index_metadata()
+            .return_once(move |_| {
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            });
+        let mut split_1 = MockSplitBuilder::new("split_1")
+            .with_index_uid(&index_uid)
+            .build();
+        split_1.split_metadata.storage_stats = Some(SplitStorageStats {
+            doc_store_num_bytes: 400,
+            postings_num_bytes: 200,
+            fast_fields_num_bytes: 100,
+            hotcache_num_bytes: 50,
+            per_field_num_bytes: [("body".to_string(), 250), ("timestamp".to_string(), 50)]
+                .into_iter()
+                .collect(),
+        });
+        let mut split_2 = MockSplitBuilder::new("split_2")
+            .with_index_uid(&index_uid)
+            .build();
+        split_2.split_metadata.storage_stats = Some(SplitStorageStats {
+            doc_store_num_bytes: 300,
+            postings_num_bytes: 100,
+            fast_fields_num_bytes: 200,
+            hotcache_num_bytes: 50,
+            per_field_num_bytes: [("body".to_string(), 100), ("severity".to_string(), 200)]
+                .into_iter()
+                .collect(),
+        });
+        let split_3 = MockSplitBuilder::new("split_3")
+            .with_index_uid(&index_uid)
+            .build();
+        mock_metastore
+            .expect_list_splits()
+            .withf(move |list_split_request| -> bool {
+                let list_split_query = list_split_request.deserialize_list_splits_query().unwrap();
+                list_split_query.index_uids.contains(&index_uid)
+                    && list_split_query.split_states == [SplitState::Published]
+            })
+            .return_once(move |_| {
+                let splits = vec![split_1, split_2, split_3];
+                let splits = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits)]))
+            });
+
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from_mock(mock_metastore),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/storage-stats?top_fields=2")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let actual_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_id": "quickwit-demo-index",
+            "num_published_splits": 3,
+            "num_splits_without_storage_stats": 1,
+            "total_num_bytes": 2400,
+            "doc_store_num_bytes": 700,
+            "postings_num_bytes": 300,
+            "fast_fields_num_bytes": 300,
+            "hotcache_num_bytes": 100,
+            "top_fields": [
+                {"field_name": "body", "num_bytes": 350},
+                {"field_name": "severity", "num_bytes": 200},
+            ],
+        });
+        assert_eq!(actual_response_json, expected_response_json);
+    }
+
     #[tokio::test]
     async fn test_get_all_splits() {
         let mut mock_metastore = MockMetastoreService::new();
@@ -1236,9 +1675,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/splits")
             .reply(&index_management_handler)
@@ -1283,9 +1725,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/splits/mark-for-deletion")
             .method("PUT")
@@ -1323,9 +1768,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes?index_id_patterns=test-index-*")
             .reply(&index_management_handler)
@@ -1375,9 +1823,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/clear")
             .method("PUT")
@@ -1423,9 +1874,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         {
             // Dry run
             let resp = warp::test::request()
@@ -1461,9 +1915,12 @@ mod tests {
     async fn test_delete_on_non_existing_index() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index")
             .method("DELETE")
@@ -1478,8 +1935,11 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config));
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        );
         {
             let resp = warp::test::request()
                 .path("/indexes?overwrite=true")
@@ -1518,8 +1978,11 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config));
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        );
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1635,15 +2098,136 @@ mod tests {
         assert!(indexes.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_get_source_reindex_progress() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
+
+        for index_id in ["source-logs", "dest-logs"] {
+            let resp = warp::test::request()
+                .path("/indexes")
+                .method("POST")
+                .json(&true)
+                .body(format!(
+                    r#"{{"version": "0.7", "index_id": "{index_id}", "doc_mapping": {{}}}}"#
+                ))
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+        }
+        let source_config_bodies = [
+            r#"{"version": "0.7", "source_id": "reindex-source", "source_type": "reindex", "params": {"source_index_id": "source-logs"}}"#,
+            r#"{"version": "0.7", "source_id": "vec-source", "source_type": "vec", "params": {"docs": [], "batch_num_docs": 10}}"#,
+        ];
+        for source_config_body in source_config_bodies {
+            let resp = warp::test::request()
+                .path("/indexes/dest-logs/sources")
+                .method("POST")
+                .json(&true)
+                .body(source_config_body)
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+        }
+        let resp = warp::test::request()
+            .path("/indexes/dest-logs/sources/reindex-source/reindex-progress")
+            .method("GET")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "source_index_id": "source-logs",
+            "stage": "completed",
+            "paused": false,
+            "alias_flipped": false,
+            "num_splits": 0,
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        let resp = warp::test::request()
+            .path("/indexes/dest-logs/sources/vec-source/reindex-progress")
+            .method("GET")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = warp::test::request()
+            .path("/indexes/dest-logs/sources/unknown-source/reindex-progress")
+            .method("GET")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_source_transform() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "source_id": "vec-source", "source_type": "vec", "params": {"docs": [], "batch_num_docs": 10}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources/vec-source/transform/dry-run")
+            .method("POST")
+            .body(r#"{"body": "foo"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources/unknown-source/transform/dry-run")
+            .method("POST")
+            .body(r#"{"body": "foo"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
     #[tokio::test]
     async fn test_create_file_source_returns_403() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let source_config_body = r#"{"version": "0.7", "source_id": "file-source", "source_type":
     "file", "params": {"filepath": "FILEPATH"}}"#;
         let resp = warp::test::request()
@@ -1663,9 +2247,12 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1701,9 +2288,12 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1737,9 +2327,12 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1758,9 +2351,12 @@ mod tests {
             MetastoreServiceClient::mocked(),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1784,8 +2380,11 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config));
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        );
         {
             let resp = warp::test::request()
                 .path("/indexes")
@@ -1840,13 +2439,147 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_update_index_doc_mapping() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true}, {"name": "status", "type": "u64"}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"search_settings": {}, "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true}, {"name": "status", "type": "u64", "fast": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_config": {
+                "doc_mapping": {
+                    "field_mappings": [
+                        {"name": "timestamp", "fast": true},
+                        {"name": "status", "fast": true}
+                    ]
+                }
+            }
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        // Omitting the doc mapping leaves it unchanged.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"search_settings": {}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        // Other updates of the doc mapping are rejected.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"search_settings": {}, "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true}, {"name": "status", "type": "u64", "fast": true, "indexed": false}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(body.contains("field `status` cannot be updated"));
+    }
+
+    #[tokio::test]
+    async fn test_update_index_legal_hold() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(node_config),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"search_settings": {}, "legal_hold": {"reason": "case-1"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_config": {
+                "legal_hold": {"reason": "case-1"}
+            }
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        // Omitting the legal hold leaves it in place.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"search_settings": {}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        // Setting the legal hold to `null` lifts it.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"search_settings": {}, "legal_hold": null}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert!(resp_json["index_config"].get("legal_hold").is_none());
+    }
+
     #[tokio::test]
     async fn test_create_source_with_bad_config() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         {
             // Source config with bad version.
             let resp = warp::test::request()
@@ -1913,9 +2646,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/sources/foo-source")
             .method("DELETE")
@@ -1960,9 +2696,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/sources/source-to-reset/reset-checkpoint")
             .method("PUT")
@@ -2014,9 +2753,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         // Check server returns 405 if sources root path is used.
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/sources/source-to-toggle")
@@ -2075,9 +2817,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/analyze")
             .method("POST")
@@ -2111,9 +2856,12 @@ mod tests {
             MetastoreServiceClient::mocked(),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Arc::new(NodeConfig::for_test()),
+            QueryAccessStatsRegistry::default(),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/parse-query")
             .method("POST")
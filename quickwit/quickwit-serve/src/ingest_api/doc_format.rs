@@ -0,0 +1,236 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Write;
+
+use bytes::Bytes;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+use tokio::task;
+use warp::reject::Reject;
+use warp::Filter;
+
+use crate::decompression::get_body_bytes;
+use crate::Body;
+
+/// Format of the documents in the body of an ingest request, negotiated via the `content-type`
+/// header. CBOR and MessagePack payloads are transcoded to NDJSON before being ingested.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum DocFormat {
+    #[default]
+    Ndjson,
+    Cbor,
+    MessagePack,
+}
+
+impl DocFormat {
+    /// Any content type other than CBOR or MessagePack, including none, is interpreted as NDJSON
+    /// for backward compatibility with clients that do not set a content type.
+    pub fn from_content_type(content_type: &str) -> Self {
+        let mime_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match mime_type.as_str() {
+            "application/cbor" => Self::Cbor,
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Self::MessagePack
+            }
+            _ => Self::Ndjson,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "NDJSON",
+            Self::Cbor => "CBOR",
+            Self::MessagePack => "MessagePack",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to decode {format} documents: {message}")]
+pub(crate) struct InvalidDocs {
+    format: &'static str,
+    message: String,
+}
+
+impl Reject for InvalidDocs {}
+
+/// Transcodes a payload of documents to NDJSON. The payload is a sequence of CBOR data items or
+/// MessagePack values, each holding a single document or an array of documents.
+fn transcode_docs_to_ndjson(doc_format: DocFormat, payload: &[u8]) -> Result<Bytes, InvalidDocs> {
+    if doc_format == DocFormat::Ndjson {
+        return Ok(Bytes::copy_from_slice(payload));
+    }
+    let invalid_docs = |message: String| InvalidDocs {
+        format: doc_format.as_str(),
+        message,
+    };
+    // The JSON representation of the documents is usually larger than the binary one.
+    let mut ndjson = Vec::with_capacity(payload.len() * 2);
+    let mut remaining = payload;
+
+    while !remaining.is_empty() {
+        let value: JsonValue = match doc_format {
+            DocFormat::Ndjson => unreachable!("NDJSON payloads are not transcoded"),
+            DocFormat::Cbor => ciborium::de::from_reader(&mut remaining)
+                .map_err(|error| invalid_docs(error.to_string()))?,
+            DocFormat::MessagePack => rmp_serde::from_read(&mut remaining)
+                .map_err(|error| invalid_docs(error.to_string()))?,
+        };
+        let docs = match value {
+            JsonValue::Array(docs) => docs,
+            doc => vec![doc],
+        };
+        for doc in docs {
+            serde_json::to_writer(&mut ndjson, &doc)
+                .map_err(|error| invalid_docs(error.to_string()))?;
+            ndjson
+                .write_all(b"\n")
+                .expect("writing to a vec should not fail");
+        }
+    }
+    Ok(Bytes::from(ndjson))
+}
+
+/// Custom filter that extracts the body of an ingest request as NDJSON, decompressing and
+/// transcoding it if needed.
+pub(crate) fn get_ingest_body() -> impl Filter<Extract = (Body,), Error = warp::Rejection> + Clone {
+    warp::header::optional("content-type")
+        .and(get_body_bytes())
+        .and_then(|content_type_opt: Option<String>, body: Body| async move {
+            let doc_format = content_type_opt
+                .as_deref()
+                .map(DocFormat::from_content_type)
+                .unwrap_or_default();
+
+            if doc_format == DocFormat::Ndjson {
+                return Ok(body);
+            }
+            // Decoding is CPU bound, so it runs on the blocking thread pool like decompression.
+            let ndjson =
+                task::spawn_blocking(move || transcode_docs_to_ndjson(doc_format, &body.content))
+                    .await
+                    .map_err(|join_error| {
+                        warp::reject::custom(InvalidDocs {
+                            format: doc_format.as_str(),
+                            message: join_error.to_string(),
+                        })
+                    })?
+                    .map_err(warp::reject::custom)?;
+            Result::<_, warp::Rejection>::Ok(Body::from(ndjson))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::ingest_api::lines;
+
+    #[test]
+    fn test_doc_format_from_content_type() {
+        assert_eq!(
+            DocFormat::from_content_type("application/json"),
+            DocFormat::Ndjson
+        );
+        assert_eq!(
+            DocFormat::from_content_type("application/x-ndjson"),
+            DocFormat::Ndjson
+        );
+        assert_eq!(
+            DocFormat::from_content_type("application/cbor"),
+            DocFormat::Cbor
+        );
+        assert_eq!(
+            DocFormat::from_content_type("Application/CBOR; charset=binary"),
+            DocFormat::Cbor
+        );
+        assert_eq!(
+            DocFormat::from_content_type("application/msgpack"),
+            DocFormat::MessagePack
+        );
+        assert_eq!(
+            DocFormat::from_content_type("application/x-msgpack"),
+            DocFormat::MessagePack
+        );
+        assert_eq!(
+            DocFormat::from_content_type("application/vnd.msgpack"),
+            DocFormat::MessagePack
+        );
+    }
+
+    #[test]
+    fn test_transcode_cbor_docs_to_ndjson() {
+        let mut payload = Vec::new();
+        ciborium::ser::into_writer(&json!({"id": 1, "message": "push"}), &mut payload).unwrap();
+        ciborium::ser::into_writer(
+            &json!([{"id": 2, "message": "push"}, {"id": 3, "tags": ["a", "b"]}]),
+            &mut payload,
+        )
+        .unwrap();
+
+        let ndjson = transcode_docs_to_ndjson(DocFormat::Cbor, &payload).unwrap();
+        let docs: Vec<JsonValue> = lines(&ndjson)
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(
+            docs,
+            [
+                json!({"id": 1, "message": "push"}),
+                json!({"id": 2, "message": "push"}),
+                json!({"id": 3, "tags": ["a", "b"]}),
+            ]
+        );
+
+        let error =
+            transcode_docs_to_ndjson(DocFormat::Cbor, &payload[..payload.len() - 1]).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("failed to decode CBOR documents"));
+    }
+
+    #[test]
+    fn test_transcode_msgpack_docs_to_ndjson() {
+        let mut payload = rmp_serde::to_vec(&json!({"id": 1, "message": "push"})).unwrap();
+        payload.extend(rmp_serde::to_vec(&json!([{"id": 2, "nested": {"value": 1.5}}])).unwrap());
+
+        let ndjson = transcode_docs_to_ndjson(DocFormat::MessagePack, &payload).unwrap();
+        let docs: Vec<JsonValue> = lines(&ndjson)
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(
+            docs,
+            [
+                json!({"id": 1, "message": "push"}),
+                json!({"id": 2, "nested": {"value": 1.5}}),
+            ]
+        );
+
+        let error = transcode_docs_to_ndjson(DocFormat::MessagePack, &[0xc1]).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("failed to decode MessagePack documents"));
+    }
+}
@@ -17,8 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod doc_format;
 mod rest_handler;
 
+#[cfg(test)]
+pub(crate) use doc_format::InvalidDocs;
 #[cfg(test)]
 pub(crate) use rest_handler::tests::setup_ingest_service;
 pub(crate) use rest_handler::{ingest_api_handlers, lines};
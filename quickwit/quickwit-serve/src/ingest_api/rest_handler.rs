@@ -21,18 +21,18 @@ use bytes::{Buf, Bytes};
 use quickwit_config::{IngestApiConfig, INGEST_V2_SOURCE_ID};
 use quickwit_ingest::{
     CommitType, DocBatchBuilder, DocBatchV2Builder, FetchResponse, IngestRequest, IngestResponse,
-    IngestService, IngestServiceClient, IngestServiceError, TailRequest,
+    IngestService, IngestServiceClient, IngestServiceError, TailRequest, MAX_IDEMPOTENCY_KEY_LEN,
 };
 use quickwit_proto::ingest::router::{
     IngestFailureReason, IngestRequestV2, IngestResponseV2, IngestRouterService,
     IngestRouterServiceClient, IngestSubrequest,
 };
 use quickwit_proto::types::IndexId;
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 use thiserror::Error;
 use warp::{Filter, Rejection};
 
-use crate::decompression::get_body_bytes;
+use super::doc_format::get_ingest_body;
 use crate::format::extract_format_from_qs;
 use crate::rest_api_response::into_rest_api_response;
 use crate::{with_arg, Body, BodyFormat};
@@ -61,6 +61,29 @@ struct IngestOptions {
     #[serde(alias = "commit")]
     #[serde(default)]
     commit_type: CommitType,
+    /// Routes all the documents of the request to the same shard (ingest V2 only).
+    #[serde(default)]
+    routing: Option<String>,
+    /// Acknowledges replays of a request with the same key without ingesting the documents
+    /// twice (ingest V2 only).
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_idempotency_key")]
+    idempotency_key: Option<String>,
+}
+
+fn deserialize_idempotency_key<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where D: Deserializer<'de> {
+    let idempotency_key_opt: Option<String> = Option::deserialize(deserializer)?;
+
+    if let Some(idempotency_key) = &idempotency_key_opt {
+        if idempotency_key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+            return Err(de::Error::custom(format!(
+                "idempotency key must not exceed {MAX_IDEMPOTENCY_KEY_LEN} bytes, got {}",
+                idempotency_key.len()
+            )));
+        }
+    }
+    Ok(idempotency_key_opt)
 }
 
 pub(crate) fn ingest_api_handlers(
@@ -81,7 +104,7 @@ fn ingest_filter(
         .and(warp::body::content_length_limit(
             config.content_length_limit.as_u64(),
         ))
-        .and(get_body_bytes())
+        .and(get_ingest_body())
         .and(serde_qs::warp::query::<IngestOptions>(
             serde_qs::Config::default(),
         ))
@@ -105,7 +128,7 @@ fn ingest_v2_filter(
         .and(warp::body::content_length_limit(
             config.content_length_limit.as_u64(),
         ))
-        .and(get_body_bytes())
+        .and(get_ingest_body())
         .and(serde_qs::warp::query::<IngestOptions>(
             serde_qs::Config::default(),
         ))
@@ -146,10 +169,13 @@ async fn ingest_v2(
         index_id,
         source_id: INGEST_V2_SOURCE_ID.to_string(),
         doc_batch: Some(doc_batch),
+        routing_key: ingest_options.routing,
+        idempotency_key: ingest_options.idempotency_key,
     };
     let request = IngestRequestV2 {
         commit_type: ingest_options.commit_type as i32,
         subrequests: vec![subrequest],
+        best_effort_atomic: false,
     };
     let response = ingest_router.ingest(request).await?;
     convert_ingest_response_v2(response, num_docs)
@@ -187,9 +213,17 @@ fn convert_ingest_response_v2(
         IngestFailureReason::NoShardsAvailable => IngestServiceError::Unavailable,
         IngestFailureReason::RateLimited => IngestServiceError::RateLimited,
         IngestFailureReason::ResourceExhausted => IngestServiceError::RateLimited,
+        IngestFailureReason::QuotaExceeded => IngestServiceError::QuotaExceeded {
+            index_id: ingest_failure.index_id,
+        },
+        IngestFailureReason::SourcePaused => IngestServiceError::SourcePaused {
+            index_id: ingest_failure.index_id,
+            source_id: ingest_failure.source_id,
+        },
         IngestFailureReason::Timeout => {
             IngestServiceError::Internal("request timed out".to_string())
         }
+        IngestFailureReason::Aborted => IngestServiceError::Unavailable,
     })
 }
 
@@ -197,7 +231,7 @@ fn convert_ingest_response_v2(
     post,
     tag = "Ingest",
     path = "/{index_id}/ingest",
-    request_body(content = String, description = "Documents to ingest in NDJSON format and limited to 10MB", content_type = "application/json"),
+    request_body(content = String, description = "Documents to ingest in NDJSON, CBOR, or MessagePack format and limited to 10MB", content_type = "application/json"),
     responses(
         (status = 200, description = "Successfully ingested documents.", body = IngestResponse)
     ),
@@ -286,8 +320,9 @@ pub(crate) mod tests {
         QUEUES_DIR_NAME,
     };
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
+    use serde_json::json;
 
-    use super::ingest_api_handlers;
+    use super::{ingest_api_handlers, IngestOptions};
     use crate::ingest_api::lines;
 
     #[test]
@@ -308,6 +343,19 @@ pub(crate) mod tests {
         }
     }
 
+    #[test]
+    fn test_ingest_options_idempotency_key() {
+        let ingest_options: IngestOptions = serde_qs::from_str("idempotency_key=my-key").unwrap();
+        assert_eq!(ingest_options.idempotency_key.as_deref(), Some("my-key"));
+
+        let ingest_options: IngestOptions = serde_qs::from_str("commit=force").unwrap();
+        assert!(ingest_options.idempotency_key.is_none());
+
+        let query_string = format!("idempotency_key={}", "k".repeat(257));
+        let error = serde_qs::from_str::<IngestOptions>(&query_string).unwrap_err();
+        assert!(error.to_string().contains("must not exceed 256 bytes"));
+    }
+
     pub(crate) async fn setup_ingest_service(
         queues: &[&str],
         config: &IngestApiConfig,
@@ -396,6 +444,52 @@ pub(crate) mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_returns_200_when_ingest_cbor_and_fetch() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_router, ingest_service, IngestApiConfig::default());
+        let mut payload = Vec::new();
+        ciborium::ser::into_writer(
+            &json!([{"id": 1, "message": "push"}, {"id": 2, "message": "push"}]),
+            &mut payload,
+        )
+        .unwrap();
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .header("content-type", "application/cbor")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: IngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+
+        let resp = warp::test::request()
+            .path("/my-index/tail")
+            .method("GET")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let fetch_response: FetchResponse = serde_json::from_slice(resp.body()).unwrap();
+        let doc_batch = fetch_response.doc_batch.unwrap();
+        assert_eq!(doc_batch.num_docs(), 2);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .header("content-type", "application/msgpack")
+            .body(&[0xc1][..])
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_ingest_api_return_429_if_above_limits() {
         let config = IngestApiConfig {
@@ -28,6 +28,7 @@ use quickwit_proto::jaeger::storage::v1::{
     SpansResponseChunk, TraceQueryParameters,
 };
 use quickwit_proto::tonic;
+use quickwit_proto::tonic::metadata::MetadataMap;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 use tracing::error;
@@ -39,6 +40,7 @@ use crate::jaeger_api::model::{
     JaegerError, JaegerResponseBody, JaegerSpan, JaegerTrace, TracesSearchQueryParams,
     DEFAULT_NUMBER_OF_TRACES,
 };
+use crate::otlp_api::otlp_metadata_filter;
 use crate::rest_api_response::RestApiResponse;
 use crate::search_api::extract_index_id_patterns;
 use crate::{require, BodyFormat};
@@ -68,10 +70,34 @@ pub(crate) fn jaeger_api_handlers(
         .or(jaeger_traces_handler(jaeger_service_opt.clone()))
 }
 
-fn jaeger_api_path_filter() -> impl Filter<Extract = (Vec<String>,), Error = Rejection> + Clone {
+fn jaeger_api_path_filter(
+) -> impl Filter<Extract = (Vec<String>, MetadataMap), Error = Rejection> + Clone {
     warp::path!(String / "jaeger" / "api" / ..)
         .and(warp::get())
         .and_then(extract_index_id_patterns)
+        .and(otlp_metadata_filter())
+}
+
+/// Authorizes the caller identified by the credentials forwarded in `metadata` and restricts the
+/// index ID patterns of its query to the indexes it may read.
+fn authorize_index_id_patterns(
+    jaeger_service: &JaegerService,
+    metadata: &MetadataMap,
+    index_id_patterns: Vec<String>,
+) -> Result<Vec<String>, JaegerError> {
+    jaeger_service
+        .authorize_index_id_patterns(metadata, index_id_patterns)
+        .map_err(|status| {
+            let status_code = match status.code() {
+                tonic::Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+                tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            JaegerError {
+                status: status_code,
+                message: status.message().to_string(),
+            }
+        })
 }
 
 #[utoipa::path(
@@ -172,8 +198,11 @@ pub fn jaeger_traces_handler(
 
 async fn jaeger_services(
     index_id_patterns: Vec<String>,
+    metadata: MetadataMap,
     jaeger_service: JaegerService,
 ) -> Result<JaegerResponseBody<Vec<String>>, JaegerError> {
+    let index_id_patterns =
+        authorize_index_id_patterns(&jaeger_service, &metadata, index_id_patterns)?;
     let get_services_response = jaeger_service
         .get_services_for_indexes(GetServicesRequest {}, index_id_patterns)
         .await
@@ -188,9 +217,12 @@ async fn jaeger_services(
 
 async fn jaeger_service_operations(
     index_id_patterns: Vec<String>,
+    metadata: MetadataMap,
     service_name: String,
     jaeger_service: JaegerService,
 ) -> Result<JaegerResponseBody<Vec<String>>, JaegerError> {
+    let index_id_patterns =
+        authorize_index_id_patterns(&jaeger_service, &metadata, index_id_patterns)?;
     let get_operations_request = GetOperationsRequest {
         service: service_name,
         span_kind: "".to_string(),
@@ -213,9 +245,12 @@ async fn jaeger_service_operations(
 
 async fn jaeger_traces_search(
     index_id_patterns: Vec<String>,
+    metadata: MetadataMap,
     search_params: TracesSearchQueryParams,
     jaeger_service: JaegerService,
 ) -> Result<JaegerResponseBody<Vec<JaegerTrace>>, JaegerError> {
+    let index_id_patterns =
+        authorize_index_id_patterns(&jaeger_service, &metadata, index_id_patterns)?;
     let duration_min = search_params
         .min_duration
         .map(parse_duration_with_units)
@@ -289,9 +324,12 @@ async fn collect_and_build_jaeger_spans(
 
 async fn jaeger_get_trace_by_id(
     index_id_patterns: Vec<String>,
+    metadata: MetadataMap,
     trace_id_string: String,
     jaeger_service: JaegerService,
 ) -> Result<JaegerResponseBody<Vec<JaegerTrace>>, JaegerError> {
+    let index_id_patterns =
+        authorize_index_id_patterns(&jaeger_service, &metadata, index_id_patterns)?;
     let trace_id = hex::decode(trace_id_string.clone()).map_err(|error| {
         error!(error = ?error, "failed to decode trace `{}`", trace_id_string.clone());
         JaegerError {
@@ -339,8 +377,8 @@ mod tests {
     use std::collections::HashMap;
     use std::sync::Arc;
 
-    use quickwit_config::JaegerConfig;
-    use quickwit_opentelemetry::otlp::OTEL_TRACES_INDEX_ID;
+    use quickwit_config::{JaegerConfig, OtelApiKeyConfig, OtelAuthConfig, OtelRole};
+    use quickwit_opentelemetry::otlp::{OtelAuthenticator, OTEL_TRACES_INDEX_ID};
     use quickwit_search::MockSearchService;
     use serde_json::Value as JsonValue;
 
@@ -397,6 +435,63 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_jaeger_services_with_authenticator() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_list_terms()
+            .withf(|req| req.index_id_patterns == vec!["otel-traces-v0_7-acme"])
+            .return_once(|_| {
+                Ok(quickwit_proto::search::ListTermsResponse {
+                    num_hits: 0,
+                    terms: Vec::new(),
+                    elapsed_time_micros: 0,
+                    errors: Vec::new(),
+                })
+            });
+        let auth_config = OtelAuthConfig {
+            api_keys: vec![
+                OtelApiKeyConfig {
+                    name: "grafana-acme".to_string(),
+                    api_key: "acme-secret".to_string(),
+                    roles: vec![OtelRole::Read],
+                    tenant_id: Some("acme".to_string()),
+                },
+                OtelApiKeyConfig {
+                    name: "collector".to_string(),
+                    api_key: "collector-secret".to_string(),
+                    roles: vec![OtelRole::Ingest],
+                    tenant_id: None,
+                },
+            ],
+            jwt: None,
+        };
+        let mock_search_service = Arc::new(mock_search_service);
+        let jaeger = JaegerService::new(JaegerConfig::default(), mock_search_service)
+            .with_authenticator(OtelAuthenticator::new(&auth_config));
+        let jaeger_api_handler = jaeger_api_handlers(Some(jaeger)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/otel-traces-v0_7/jaeger/api/services")
+            .reply(&jaeger_api_handler)
+            .await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = warp::test::request()
+            .path("/otel-traces-v0_7/jaeger/api/services")
+            .header("x-api-key", "collector-secret")
+            .reply(&jaeger_api_handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = warp::test::request()
+            .path("/otel-traces-v0_7/jaeger/api/services")
+            .header("authorization", "Bearer acme-secret")
+            .reply(&jaeger_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
     #[tokio::test]
     async fn test_jaeger_service_operations() {
         let mut mock_search_service = MockSearchService::new();
@@ -460,6 +555,8 @@ mod tests {
                     errors: Vec::new(),
                     aggregation: None,
                     scroll_id: None,
+                    profile: None,
+                    failed_indexes: Vec::new(),
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -491,6 +588,8 @@ mod tests {
                     errors: Vec::new(),
                     aggregation: None,
                     scroll_id: None,
+                    profile: None,
+                    failed_indexes: Vec::new(),
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -0,0 +1,963 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Endpoint speaking a subset of the Kafka wire protocol so that Kafka producers can write
+//! directly into the ingest API v2. Topics are mapped to indexes and the records of a topic are
+//! ingested into the `_ingest-source` source of the corresponding index. The endpoint behaves like
+//! a single-broker cluster: it only implements the `ApiVersions`, `Metadata`, and `Produce` APIs
+//! and does not assign offsets to the produced records, so idempotent and transactional producers
+//! are not supported.
+//!
+//! The clients must authenticate with the SASL/PLAIN mechanism (`SaslHandshake` v1 followed by
+//! `SaslAuthenticate`) before sending any request other than `ApiVersions`. Producers using
+//! `acks=0` are handled like `acks=1` except that no response is sent: the records are written to
+//! the ingest API before the next request of the connection is read, which keeps the memory used
+//! by a connection bounded.
+
+mod protocol;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use quickwit_config::{KafkaApiConfig, INGEST_V2_SOURCE_ID};
+use quickwit_ingest::IngestRequestV2Builder;
+use quickwit_proto::ingest::router::{
+    IngestFailureReason, IngestRouterService, IngestRouterServiceClient,
+};
+use quickwit_proto::ingest::CommitTypeV2;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tracing::{debug, info, warn};
+
+use self::protocol::{
+    decode_record_batches, error_code, is_supported_version, parse_sasl_plain_token,
+    ApiVersionsResponse, Decoder, Encoder, MetadataBroker, MetadataRequest, MetadataResponse,
+    MetadataTopic, ProducePartitionResponse, ProduceRequest, ProduceResponse, ProduceTopicResponse,
+    ProtocolError, RequestHeader, SaslAuthenticateRequest, SaslAuthenticateResponse,
+    SaslHandshakeRequest, SaslHandshakeResponse, API_VERSIONS_API_KEY, METADATA_API_KEY,
+    PRODUCE_API_KEY, SASL_AUTHENTICATE_API_KEY, SASL_HANDSHAKE_API_KEY, SASL_PLAIN_MECHANISM,
+};
+
+/// ID of the single broker advertised to the clients.
+const BROKER_ID: i32 = 0;
+
+/// Number of partitions advertised for each topic. The partitions are only used by the producers
+/// to batch their records.
+const NUM_PARTITIONS: i32 = 1;
+
+/// Maximum ratio between the size of the decompressed records of a produce request and the maximum
+/// size of a request. Compressed records expanding beyond this limit are rejected.
+const MAX_DECOMPRESSION_RATIO: usize = 10;
+
+/// Maximum size of the requests sent before the client authenticates.
+const MAX_UNAUTHENTICATED_FRAME_SIZE: usize = 64 * 1024;
+
+/// Initial capacity of the buffer a request is read into. The buffer then grows as the bytes of
+/// the request arrive, so announcing a large request does not reserve the memory up front.
+const INITIAL_FRAME_CAPACITY: usize = 64 * 1024;
+
+/// Authentication state of a connection.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum SessionState {
+    /// Waiting for a `SaslHandshake` request.
+    #[default]
+    AwaitingHandshake,
+    /// Waiting for a `SaslAuthenticate` request carrying the PLAIN credentials.
+    AwaitingAuthentication,
+    Authenticated,
+    /// The credentials were rejected: the connection is closed once the response is sent.
+    AuthenticationFailed,
+}
+
+struct KafkaApiState {
+    kafka_config: KafkaApiConfig,
+    cluster_id: String,
+    advertise_host: String,
+    ingest_router: IngestRouterServiceClient,
+    /// Maximum size of the decompressed records of a produce request.
+    max_decompressed_size: usize,
+}
+
+/// Starts the Kafka-compatible endpoint on `listen_addr` and serves the connections until the
+/// process exits.
+pub(crate) async fn start_kafka_api_server(
+    listen_addr: SocketAddr,
+    advertise_host: String,
+    cluster_id: String,
+    kafka_config: KafkaApiConfig,
+    max_frame_size: usize,
+    ingest_router: IngestRouterServiceClient,
+) -> anyhow::Result<()> {
+    let tcp_listener = TcpListener::bind(listen_addr).await?;
+    info!("starting Kafka API server listening on {listen_addr}");
+
+    let connection_permits = Arc::new(Semaphore::new(kafka_config.max_connections.get()));
+    let state = Arc::new(KafkaApiState {
+        kafka_config,
+        cluster_id,
+        advertise_host,
+        ingest_router,
+        max_decompressed_size: max_frame_size.saturating_mul(MAX_DECOMPRESSION_RATIO),
+    });
+    loop {
+        // Connections beyond the limit wait in the backlog of the listener.
+        let connection_permit = connection_permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore should not be closed");
+        let (tcp_stream, peer_addr) = match tcp_listener.accept().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                warn!(%error, "failed to accept Kafka API connection");
+                continue;
+            }
+        };
+        let state = state.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = serve_connection(&state, tcp_stream, max_frame_size).await {
+                debug!(%error, %peer_addr, "closing Kafka API connection");
+            }
+            drop(connection_permit);
+        });
+    }
+}
+
+/// Serves the requests of a connection one at a time until the client disconnects or fails to
+/// authenticate.
+async fn serve_connection<S>(
+    state: &KafkaApiState,
+    mut stream: S,
+    max_frame_size: usize,
+) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session_state = SessionState::default();
+
+    loop {
+        let frame_len = match stream.read_i32().await {
+            Ok(frame_len) => frame_len,
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        let max_frame_size = if session_state == SessionState::Authenticated {
+            max_frame_size
+        } else {
+            max_frame_size.min(MAX_UNAUTHENTICATED_FRAME_SIZE)
+        };
+        if frame_len < 0 || frame_len as usize > max_frame_size {
+            anyhow::bail!("request size `{frame_len}` exceeds limit `{max_frame_size}`");
+        }
+        let frame_len = frame_len as usize;
+        let mut frame = Vec::with_capacity(frame_len.min(INITIAL_FRAME_CAPACITY));
+        (&mut stream)
+            .take(frame_len as u64)
+            .read_to_end(&mut frame)
+            .await?;
+
+        if frame.len() < frame_len {
+            anyhow::bail!("connection closed in the middle of a request");
+        }
+        let response_opt = state.handle_request(&mut session_state, &frame).await?;
+
+        if let Some(response) = response_opt {
+            stream.write_i32(response.len() as i32).await?;
+            stream.write_all(&response).await?;
+            stream.flush().await?;
+        }
+        if session_state == SessionState::AuthenticationFailed {
+            anyhow::bail!("client failed to authenticate");
+        }
+    }
+}
+
+impl KafkaApiState {
+    /// Handles a request frame and returns the response frame, if any. Returns an error when the
+    /// request is malformed, not supported, or not allowed in the current `session_state`, in
+    /// which case the connection is closed like a Kafka broker would do.
+    async fn handle_request(
+        &self,
+        session_state: &mut SessionState,
+        frame: &[u8],
+    ) -> Result<Option<Vec<u8>>, ProtocolError> {
+        let mut decoder = Decoder::new(frame);
+        let header = RequestHeader::decode(&mut decoder)?;
+
+        let mut encoder = Encoder::default();
+        encoder.put_i32(header.correlation_id);
+
+        if !is_supported_version(header.api_key, header.api_version) {
+            if header.api_key != API_VERSIONS_API_KEY {
+                return Err(ProtocolError::Invalid(format!(
+                    "unsupported API key `{}` or version `{}`",
+                    header.api_key, header.api_version
+                )));
+            }
+            // Clients retry with the highest version we support, which they find in the v0
+            // response.
+            let api_versions_response = ApiVersionsResponse {
+                error_code: error_code::UNSUPPORTED_VERSION,
+            };
+            api_versions_response.encode(0, &mut encoder);
+            return Ok(Some(encoder.into_bytes()));
+        }
+        let expected_session_state = match header.api_key {
+            API_VERSIONS_API_KEY => *session_state,
+            SASL_HANDSHAKE_API_KEY => SessionState::AwaitingHandshake,
+            SASL_AUTHENTICATE_API_KEY => SessionState::AwaitingAuthentication,
+            _ => SessionState::Authenticated,
+        };
+        if *session_state != expected_session_state {
+            return Err(ProtocolError::Invalid(format!(
+                "API key `{}` is not allowed in session state `{session_state:?}`",
+                header.api_key
+            )));
+        }
+        match header.api_key {
+            API_VERSIONS_API_KEY => {
+                let api_versions_response = ApiVersionsResponse {
+                    error_code: error_code::NONE,
+                };
+                api_versions_response.encode(header.api_version, &mut encoder);
+            }
+            SASL_HANDSHAKE_API_KEY => {
+                let sasl_handshake_request = SaslHandshakeRequest::decode(&mut decoder)?;
+
+                let error_code = if sasl_handshake_request.mechanism == SASL_PLAIN_MECHANISM {
+                    *session_state = SessionState::AwaitingAuthentication;
+                    error_code::NONE
+                } else {
+                    error_code::UNSUPPORTED_SASL_MECHANISM
+                };
+                let sasl_handshake_response = SaslHandshakeResponse { error_code };
+                sasl_handshake_response.encode(&mut encoder);
+            }
+            SASL_AUTHENTICATE_API_KEY => {
+                let sasl_authenticate_request = SaslAuthenticateRequest::decode(&mut decoder)?;
+                let sasl_authenticate_response =
+                    self.authenticate(session_state, sasl_authenticate_request);
+                sasl_authenticate_response.encode(header.api_version, &mut encoder);
+            }
+            METADATA_API_KEY => {
+                let metadata_request = MetadataRequest::decode(header.api_version, &mut decoder)?;
+                let metadata_response = self.metadata(metadata_request);
+                metadata_response.encode(header.api_version, &mut encoder);
+            }
+            PRODUCE_API_KEY => {
+                let produce_request = ProduceRequest::decode(&mut decoder)?;
+                let acks = produce_request.acks;
+                let produce_response = self.produce(produce_request).await;
+
+                if acks == 0 {
+                    return Ok(None);
+                }
+                produce_response.encode(header.api_version, &mut encoder);
+            }
+            _ => unreachable!("API key should be supported"),
+        }
+        Ok(Some(encoder.into_bytes()))
+    }
+
+    fn authenticate(
+        &self,
+        session_state: &mut SessionState,
+        sasl_authenticate_request: SaslAuthenticateRequest,
+    ) -> SaslAuthenticateResponse {
+        let credentials_res = parse_sasl_plain_token(sasl_authenticate_request.auth_bytes);
+
+        let authenticated = match credentials_res {
+            Ok((username, password)) => {
+                let authenticated = self.kafka_config.authenticate(username, password).is_some();
+
+                if !authenticated {
+                    warn!(username, "Kafka client failed to authenticate");
+                }
+                authenticated
+            }
+            Err(error) => {
+                warn!(%error, "Kafka client sent an invalid SASL/PLAIN token");
+                false
+            }
+        };
+        if authenticated {
+            *session_state = SessionState::Authenticated;
+
+            SaslAuthenticateResponse {
+                error_code: error_code::NONE,
+                error_message: None,
+            }
+        } else {
+            *session_state = SessionState::AuthenticationFailed;
+
+            SaslAuthenticateResponse {
+                error_code: error_code::SASL_AUTHENTICATION_FAILED,
+                error_message: Some("invalid username or password".to_string()),
+            }
+        }
+    }
+
+    fn metadata(&self, metadata_request: MetadataRequest) -> MetadataResponse {
+        let topic_names = metadata_request
+            .topics
+            .unwrap_or_else(|| self.kafka_config.topic_mapping.keys().cloned().collect());
+        let topics = topic_names
+            .into_iter()
+            .map(|name| {
+                if self.kafka_config.index_id_for_topic(&name).is_some() {
+                    MetadataTopic {
+                        error_code: error_code::NONE,
+                        name,
+                        partitions: (0..NUM_PARTITIONS).collect(),
+                    }
+                } else {
+                    MetadataTopic {
+                        error_code: error_code::UNKNOWN_TOPIC_OR_PARTITION,
+                        name,
+                        partitions: Vec::new(),
+                    }
+                }
+            })
+            .collect();
+        MetadataResponse {
+            broker: MetadataBroker {
+                node_id: BROKER_ID,
+                host: self.advertise_host.clone(),
+                port: self.kafka_config.listen_port as i32,
+            },
+            cluster_id: self.cluster_id.clone(),
+            topics,
+        }
+    }
+
+    async fn produce(&self, produce_request: ProduceRequest<'_>) -> ProduceResponse {
+        let mut produce_response = ProduceResponse::default();
+
+        if produce_request.transactional_id.is_some() {
+            for topic in produce_request.topics {
+                let partitions = topic
+                    .partitions
+                    .iter()
+                    .map(|partition| ProducePartitionResponse {
+                        index: partition.index,
+                        error_code: error_code::INVALID_REQUEST,
+                        error_message: Some("transactions are not supported".to_string()),
+                    })
+                    .collect();
+                produce_response.topics.push(ProduceTopicResponse {
+                    name: topic.name,
+                    partitions,
+                });
+            }
+            return produce_response;
+        }
+        let mut ingest_request_builder = IngestRequestV2Builder::default();
+        // Shared by all the partitions of the request.
+        let mut decompressed_size_budget = self.max_decompressed_size;
+        // Subrequest IDs of each partition, by topic and partition position in the request.
+        let mut per_partition_subrequest_ids: HashMap<(usize, usize), Vec<u32>> = HashMap::new();
+
+        for (topic_ord, topic) in produce_request.topics.iter().enumerate() {
+            let Some(index_id) = self.kafka_config.index_id_for_topic(&topic.name) else {
+                let partitions = topic
+                    .partitions
+                    .iter()
+                    .map(|partition| ProducePartitionResponse {
+                        index: partition.index,
+                        error_code: error_code::UNKNOWN_TOPIC_OR_PARTITION,
+                        error_message: Some(format!(
+                            "topic `{}` is not mapped to an index",
+                            topic.name
+                        )),
+                    })
+                    .collect();
+                produce_response.topics.push(ProduceTopicResponse {
+                    name: topic.name.clone(),
+                    partitions,
+                });
+                continue;
+            };
+            let mut partitions = Vec::with_capacity(topic.partitions.len());
+
+            for (partition_ord, partition) in topic.partitions.iter().enumerate() {
+                let mut partition_response = ProducePartitionResponse {
+                    index: partition.index,
+                    error_code: error_code::NONE,
+                    error_message: None,
+                };
+                let records_res = decode_record_batches(
+                    partition.records.unwrap_or_default(),
+                    &mut decompressed_size_budget,
+                );
+                let records = match records_res {
+                    Ok(records) => records,
+                    Err(error) => {
+                        partition_response.error_code = match error {
+                            ProtocolError::UnsupportedCompression(_) => {
+                                error_code::UNSUPPORTED_COMPRESSION_TYPE
+                            }
+                            ProtocolError::DecompressedSizeExceeded => {
+                                error_code::MESSAGE_TOO_LARGE
+                            }
+                            _ => error_code::CORRUPT_MESSAGE,
+                        };
+                        partition_response.error_message = Some(error.to_string());
+                        partitions.push(partition_response);
+                        continue;
+                    }
+                };
+                let subrequest_ids = per_partition_subrequest_ids
+                    .entry((topic_ord, partition_ord))
+                    .or_default();
+
+                for record in records {
+                    // Tombstones have no document to index.
+                    let Some(value) = record.value else {
+                        continue;
+                    };
+                    let routing_key_opt = record
+                        .key
+                        .map(|key| String::from_utf8_lossy(&key).into_owned());
+                    let subrequest_id = ingest_request_builder.add_doc_with_routing_key(
+                        index_id.to_string(),
+                        routing_key_opt,
+                        &value,
+                    );
+                    if !subrequest_ids.contains(&subrequest_id) {
+                        subrequest_ids.push(subrequest_id);
+                    }
+                }
+                partitions.push(partition_response);
+            }
+            produce_response.topics.push(ProduceTopicResponse {
+                name: topic.name.clone(),
+                partitions,
+            });
+        }
+        let Some(ingest_request) =
+            ingest_request_builder.build(INGEST_V2_SOURCE_ID, CommitTypeV2::Auto)
+        else {
+            return produce_response;
+        };
+        let timeout = Duration::from_millis(produce_request.timeout_ms.max(0) as u64);
+        let ingest_result =
+            tokio::time::timeout(timeout, self.ingest_router.clone().ingest(ingest_request)).await;
+
+        let per_subrequest_error: HashMap<u32, (i16, String)> = match ingest_result {
+            Ok(Ok(ingest_response)) => ingest_response
+                .failures
+                .into_iter()
+                .map(|failure| {
+                    let reason = failure.reason();
+                    let error_code = ingest_failure_reason_to_error_code(reason);
+                    (
+                        failure.subrequest_id,
+                        (error_code, reason.as_str_name().to_string()),
+                    )
+                })
+                .collect(),
+            Ok(Err(error)) => {
+                warn!(%error, "failed to ingest Kafka records");
+                let error = (error_code::UNKNOWN_SERVER_ERROR, error.to_string());
+                return fail_pending_partitions(
+                    produce_response,
+                    per_partition_subrequest_ids,
+                    error,
+                );
+            }
+            Err(_) => {
+                let error = (
+                    error_code::REQUEST_TIMED_OUT,
+                    "request timed out".to_string(),
+                );
+                return fail_pending_partitions(
+                    produce_response,
+                    per_partition_subrequest_ids,
+                    error,
+                );
+            }
+        };
+        for ((topic_ord, partition_ord), subrequest_ids) in per_partition_subrequest_ids {
+            let error_opt = subrequest_ids
+                .iter()
+                .find_map(|subrequest_id| per_subrequest_error.get(subrequest_id));
+
+            if let Some((error_code, error_message)) = error_opt {
+                let partition_response =
+                    &mut produce_response.topics[topic_ord].partitions[partition_ord];
+                partition_response.error_code = *error_code;
+                partition_response.error_message = Some(error_message.clone());
+            }
+        }
+        produce_response
+    }
+}
+
+/// Sets `error` on the partitions whose records were sent to the ingest router.
+fn fail_pending_partitions(
+    mut produce_response: ProduceResponse,
+    per_partition_subrequest_ids: HashMap<(usize, usize), Vec<u32>>,
+    (error_code, error_message): (i16, String),
+) -> ProduceResponse {
+    for ((topic_ord, partition_ord), subrequest_ids) in per_partition_subrequest_ids {
+        if subrequest_ids.is_empty() {
+            continue;
+        }
+        let partition_response = &mut produce_response.topics[topic_ord].partitions[partition_ord];
+        partition_response.error_code = error_code;
+        partition_response.error_message = Some(error_message.clone());
+    }
+    produce_response
+}
+
+/// Maps the failures of the ingest router to the error codes Kafka clients know how to handle:
+/// producers retry on retriable errors such as `LEADER_NOT_AVAILABLE` or `REQUEST_TIMED_OUT`.
+fn ingest_failure_reason_to_error_code(reason: IngestFailureReason) -> i16 {
+    match reason {
+        IngestFailureReason::IndexNotFound | IngestFailureReason::SourceNotFound => {
+            error_code::UNKNOWN_TOPIC_OR_PARTITION
+        }
+        IngestFailureReason::NoShardsAvailable
+        | IngestFailureReason::SourcePaused
+        | IngestFailureReason::Aborted => error_code::LEADER_NOT_AVAILABLE,
+        IngestFailureReason::RateLimited
+        | IngestFailureReason::ResourceExhausted
+        | IngestFailureReason::QuotaExceeded
+        | IngestFailureReason::Timeout => error_code::REQUEST_TIMED_OUT,
+        IngestFailureReason::Unspecified | IngestFailureReason::Internal => {
+            error_code::UNKNOWN_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::num::NonZeroUsize;
+
+    use quickwit_config::KafkaSaslPlainUser;
+    use quickwit_proto::ingest::router::{
+        IngestFailure, IngestResponseV2, IngestSuccess, MockIngestRouterService,
+    };
+    use quickwit_proto::types::{IndexUid, Position, ShardId};
+
+    use super::protocol::{encode_record_batch, Record, SUPPORTED_API_VERSIONS};
+    use super::*;
+
+    fn kafka_api_state_for_test(mock_ingest_router: MockIngestRouterService) -> KafkaApiState {
+        let kafka_config = KafkaApiConfig {
+            listen_port: 9092,
+            topic_mapping: BTreeMap::from_iter([
+                ("test-topic-foo".to_string(), "test-index-foo".to_string()),
+                ("test-topic-bar".to_string(), "test-index-bar".to_string()),
+            ]),
+            sasl_plain_users: vec![KafkaSaslPlainUser {
+                username: "test-user".to_string(),
+                password: "test-password".to_string(),
+            }],
+            max_connections: NonZeroUsize::new(1).unwrap(),
+        };
+        KafkaApiState {
+            kafka_config,
+            cluster_id: "test-cluster".to_string(),
+            advertise_host: "127.0.0.1".to_string(),
+            ingest_router: IngestRouterServiceClient::from_mock(mock_ingest_router),
+            max_decompressed_size: 10 * 1024 * 1024,
+        }
+    }
+
+    fn encode_request(api_key: i16, api_version: i16, body: &[u8]) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.put_i16(api_key);
+        encoder.put_i16(api_version);
+        encoder.put_i32(42);
+        encoder.put_nullable_string(Some("test-client"));
+        let mut frame = encoder.into_bytes();
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    fn encode_sasl_authenticate_request(username: &str, password: &str) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        let auth_bytes = format!("\0{username}\0{password}");
+        encoder.put_nullable_bytes(Some(auth_bytes.as_bytes()));
+        encode_request(SASL_AUTHENTICATE_API_KEY, 1, &encoder.into_bytes())
+    }
+
+    fn encode_sasl_handshake_request(mechanism: &str) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.put_string(mechanism);
+        encode_request(SASL_HANDSHAKE_API_KEY, 1, &encoder.into_bytes())
+    }
+
+    #[tokio::test]
+    async fn test_kafka_api_versions() {
+        let state = kafka_api_state_for_test(MockIngestRouterService::new());
+
+        let mut session_state = SessionState::default();
+
+        let frame = encode_request(API_VERSIONS_API_KEY, 2, &[]);
+        let response = state
+            .handle_request(&mut session_state, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(decoder.read_i16().unwrap(), error_code::NONE);
+        assert_eq!(
+            decoder.read_array_len().unwrap(),
+            Some(SUPPORTED_API_VERSIONS.len())
+        );
+
+        let frame = encode_request(API_VERSIONS_API_KEY, 3, &[]);
+        let response = state
+            .handle_request(&mut session_state, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(decoder.read_i16().unwrap(), error_code::UNSUPPORTED_VERSION);
+
+        let frame = encode_request(PRODUCE_API_KEY, 9, &[]);
+        state
+            .handle_request(&mut SessionState::Authenticated, &frame)
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_kafka_sasl_plain_authentication() {
+        let state = kafka_api_state_for_test(MockIngestRouterService::new());
+
+        let mut encoder = Encoder::default();
+        encoder.put_array_len(0);
+        let metadata_frame = encode_request(METADATA_API_KEY, 1, &encoder.into_bytes());
+
+        // Requests other than `ApiVersions` are rejected before authentication.
+        let mut session_state = SessionState::default();
+        state
+            .handle_request(&mut session_state, &metadata_frame)
+            .await
+            .unwrap_err();
+
+        let frame = encode_sasl_authenticate_request("test-user", "test-password");
+        state
+            .handle_request(&mut session_state, &frame)
+            .await
+            .unwrap_err();
+
+        let frame = encode_sasl_handshake_request("SCRAM-SHA-256");
+        let response = state
+            .handle_request(&mut session_state, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(
+            decoder.read_i16().unwrap(),
+            error_code::UNSUPPORTED_SASL_MECHANISM
+        );
+        assert_eq!(decoder.read_array_len().unwrap(), Some(1));
+        assert_eq!(decoder.read_string().unwrap(), "PLAIN");
+        assert_eq!(session_state, SessionState::AwaitingHandshake);
+
+        let frame = encode_sasl_handshake_request("PLAIN");
+        let response = state
+            .handle_request(&mut session_state, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(decoder.read_i16().unwrap(), error_code::NONE);
+        assert_eq!(session_state, SessionState::AwaitingAuthentication);
+
+        let mut failed_session_state = session_state;
+        let frame = encode_sasl_authenticate_request("test-user", "wrong-password");
+        let response = state
+            .handle_request(&mut failed_session_state, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(
+            decoder.read_i16().unwrap(),
+            error_code::SASL_AUTHENTICATION_FAILED
+        );
+        assert_eq!(failed_session_state, SessionState::AuthenticationFailed);
+
+        let frame = encode_sasl_authenticate_request("test-user", "test-password");
+        let response = state
+            .handle_request(&mut session_state, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(decoder.read_i16().unwrap(), error_code::NONE);
+        assert!(decoder.read_nullable_string().unwrap().is_none());
+        assert_eq!(decoder.read_nullable_bytes().unwrap(), Some(&[][..]));
+        assert_eq!(decoder.read_i64().unwrap(), 0);
+        assert!(decoder.is_empty());
+        assert_eq!(session_state, SessionState::Authenticated);
+
+        state
+            .handle_request(&mut session_state, &metadata_frame)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kafka_produce() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .once()
+            .returning(|ingest_request| {
+                assert_eq!(ingest_request.commit_type(), CommitTypeV2::Auto);
+                assert_eq!(ingest_request.subrequests.len(), 2);
+
+                let mut subrequests = ingest_request.subrequests;
+                subrequests.sort_by(|left, right| left.index_id.cmp(&right.index_id));
+
+                assert_eq!(subrequests[0].index_id, "test-index-bar");
+                assert_eq!(subrequests[0].source_id, INGEST_V2_SOURCE_ID);
+                assert_eq!(subrequests[0].doc_batch.as_ref().unwrap().num_docs(), 1);
+
+                assert_eq!(subrequests[1].index_id, "test-index-foo");
+                assert_eq!(subrequests[1].routing_key.as_deref(), Some("test-key"));
+                assert_eq!(subrequests[1].doc_batch.as_ref().unwrap().num_docs(), 2);
+
+                Ok(IngestResponseV2 {
+                    successes: vec![IngestSuccess {
+                        subrequest_id: subrequests[1].subrequest_id,
+                        index_uid: Some(IndexUid::for_test("test-index-foo", 0)),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shard_id: Some(ShardId::from(1)),
+                        replication_position_inclusive: Some(Position::offset(1u64)),
+                    }],
+                    failures: vec![IngestFailure {
+                        subrequest_id: subrequests[0].subrequest_id,
+                        index_id: "test-index-bar".to_string(),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        reason: IngestFailureReason::IndexNotFound as i32,
+                    }],
+                })
+            });
+        let state = kafka_api_state_for_test(mock_ingest_router);
+
+        let foo_records = encode_record_batch(&[
+            Record {
+                key: Some(b"test-key".to_vec()),
+                value: Some(br#"{"message": "foo-1"}"#.to_vec()),
+            },
+            Record {
+                key: Some(b"test-key".to_vec()),
+                value: Some(br#"{"message": "foo-2"}"#.to_vec()),
+            },
+        ]);
+        let bar_records = encode_record_batch(&[Record {
+            key: None,
+            value: Some(br#"{"message": "bar"}"#.to_vec()),
+        }]);
+        let mut encoder = Encoder::default();
+        encoder.put_nullable_string(None);
+        encoder.put_i16(1);
+        encoder.put_i32(30_000);
+        encoder.put_array_len(3);
+
+        for (topic, records) in [
+            ("test-topic-foo", &foo_records),
+            ("test-topic-bar", &bar_records),
+            // The topic is not mapped to an index, so its records are not ingested.
+            ("test-topic-baz", &bar_records),
+        ] {
+            encoder.put_string(topic);
+            encoder.put_array_len(1);
+            encoder.put_i32(0);
+            encoder.put_nullable_bytes(Some(records));
+        }
+        let frame = encode_request(PRODUCE_API_KEY, 8, &encoder.into_bytes());
+
+        let response = state
+            .handle_request(&mut SessionState::Authenticated, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(decoder.read_array_len().unwrap(), Some(3));
+
+        let expected_topics = [
+            ("test-topic-foo", error_code::NONE),
+            ("test-topic-bar", error_code::UNKNOWN_TOPIC_OR_PARTITION),
+            ("test-topic-baz", error_code::UNKNOWN_TOPIC_OR_PARTITION),
+        ];
+        for (expected_name, expected_error_code) in expected_topics {
+            assert_eq!(decoder.read_string().unwrap(), expected_name);
+            assert_eq!(decoder.read_array_len().unwrap(), Some(1));
+            assert_eq!(decoder.read_i32().unwrap(), 0);
+            assert_eq!(decoder.read_i16().unwrap(), expected_error_code);
+            // Base offset, log append time, and log start offset.
+            for _ in 0..3 {
+                assert_eq!(decoder.read_i64().unwrap(), -1);
+            }
+            assert_eq!(decoder.read_array_len().unwrap(), Some(0));
+            let error_message_opt = decoder.read_nullable_string().unwrap();
+            assert_eq!(
+                error_message_opt.is_some(),
+                expected_error_code != error_code::NONE
+            );
+        }
+        assert_eq!(decoder.read_i32().unwrap(), 0);
+        assert!(decoder.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kafka_produce_transactional() {
+        let state = kafka_api_state_for_test(MockIngestRouterService::new());
+
+        let mut encoder = Encoder::default();
+        encoder.put_nullable_string(Some("test-transaction"));
+        encoder.put_i16(-1);
+        encoder.put_i32(30_000);
+        encoder.put_array_len(1);
+        encoder.put_string("test-topic-foo");
+        encoder.put_array_len(1);
+        encoder.put_i32(0);
+        encoder.put_nullable_bytes(None);
+        let frame = encode_request(PRODUCE_API_KEY, 3, &encoder.into_bytes());
+
+        let response = state
+            .handle_request(&mut SessionState::Authenticated, &frame)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        assert_eq!(decoder.read_array_len().unwrap(), Some(1));
+        assert_eq!(decoder.read_string().unwrap(), "test-topic-foo");
+        assert_eq!(decoder.read_array_len().unwrap(), Some(1));
+        assert_eq!(decoder.read_i32().unwrap(), 0);
+        assert_eq!(decoder.read_i16().unwrap(), error_code::INVALID_REQUEST);
+    }
+
+    async fn send_request<S>(client: &mut S, frame: &[u8]) -> Vec<u8>
+    where S: AsyncRead + AsyncWrite + Unpin {
+        client.write_i32(frame.len() as i32).await.unwrap();
+        client.write_all(frame).await.unwrap();
+
+        let response_len = client.read_i32().await.unwrap();
+        let mut response = vec![0; response_len as usize];
+        client.read_exact(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_kafka_serve_connection_metadata() {
+        let state = kafka_api_state_for_test(MockIngestRouterService::new());
+        let (mut client, server) = tokio::io::duplex(1024);
+
+        let serve_future = serve_connection(&state, server, 1024);
+        let client_future = async move {
+            let frame = encode_sasl_handshake_request("PLAIN");
+            send_request(&mut client, &frame).await;
+
+            let frame = encode_sasl_authenticate_request("test-user", "test-password");
+            send_request(&mut client, &frame).await;
+
+            let mut encoder = Encoder::default();
+            encoder.put_array_len(2);
+            encoder.put_string("test-topic-foo");
+            encoder.put_string("test-topic-baz");
+            let frame = encode_request(METADATA_API_KEY, 1, &encoder.into_bytes());
+            let response = send_request(&mut client, &frame).await;
+            drop(client);
+            response
+        };
+        let (serve_result, response) = tokio::join!(serve_future, client_future);
+        serve_result.unwrap();
+
+        let mut decoder = Decoder::new(&response);
+        assert_eq!(decoder.read_i32().unwrap(), 42);
+        // Brokers.
+        assert_eq!(decoder.read_array_len().unwrap(), Some(1));
+        assert_eq!(decoder.read_i32().unwrap(), BROKER_ID);
+        assert_eq!(decoder.read_string().unwrap(), "127.0.0.1");
+        assert_eq!(decoder.read_i32().unwrap(), 9092);
+        // Rack and controller ID.
+        assert!(decoder.read_nullable_string().unwrap().is_none());
+        assert_eq!(decoder.read_i32().unwrap(), BROKER_ID);
+        // Topics.
+        assert_eq!(decoder.read_array_len().unwrap(), Some(2));
+        assert_eq!(decoder.read_i16().unwrap(), error_code::NONE);
+        assert_eq!(decoder.read_string().unwrap(), "test-topic-foo");
+        // Is internal.
+        assert_eq!(decoder.read_i8().unwrap(), 0);
+        assert_eq!(decoder.read_array_len().unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_kafka_serve_connection_closes_after_failed_authentication() {
+        let state = kafka_api_state_for_test(MockIngestRouterService::new());
+        let (mut client, server) = tokio::io::duplex(1024);
+
+        let serve_future = serve_connection(&state, server, 1024);
+        let client_future = async move {
+            let frame = encode_sasl_handshake_request("PLAIN");
+            send_request(&mut client, &frame).await;
+
+            let frame = encode_sasl_authenticate_request("test-user", "wrong-password");
+            let response = send_request(&mut client, &frame).await;
+            let mut decoder = Decoder::new(&response);
+            assert_eq!(decoder.read_i32().unwrap(), 42);
+            assert_eq!(
+                decoder.read_i16().unwrap(),
+                error_code::SASL_AUTHENTICATION_FAILED
+            );
+        };
+        let (serve_result, _) = tokio::join!(serve_future, client_future);
+        let error = serve_result.unwrap_err();
+        assert_eq!(error.to_string(), "client failed to authenticate");
+    }
+
+    #[tokio::test]
+    async fn test_kafka_serve_connection_rejects_large_unauthenticated_request() {
+        let state = kafka_api_state_for_test(MockIngestRouterService::new());
+        let (mut client, server) = tokio::io::duplex(1024);
+
+        client
+            .write_i32(MAX_UNAUTHENTICATED_FRAME_SIZE as i32 + 1)
+            .await
+            .unwrap();
+        let error = serve_connection(&state, server, 10 * 1024 * 1024)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("exceeds limit"));
+    }
+}
@@ -0,0 +1,1016 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Subset of the Kafka wire protocol: request headers, `ApiVersions`, `SaslHandshake`,
+//! `SaslAuthenticate`, `Metadata`, and `Produce`. Only the non-flexible versions of the APIs are
+//! supported, and produced records must use the record batch format (magic 2).
+//!
+//! See <https://kafka.apache.org/protocol.html> for the specification.
+
+use std::borrow::Cow;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use thiserror::Error;
+
+pub(super) const PRODUCE_API_KEY: i16 = 0;
+pub(super) const METADATA_API_KEY: i16 = 3;
+pub(super) const SASL_HANDSHAKE_API_KEY: i16 = 17;
+pub(super) const API_VERSIONS_API_KEY: i16 = 18;
+pub(super) const SASL_AUTHENTICATE_API_KEY: i16 = 36;
+
+/// Supported API keys with their minimum and maximum versions. `SaslHandshake` v0 is not
+/// supported because the SASL tokens that follow it are not framed as Kafka requests.
+pub(super) const SUPPORTED_API_VERSIONS: [(i16, i16, i16); 5] = [
+    (PRODUCE_API_KEY, 3, 8),
+    (METADATA_API_KEY, 0, 8),
+    (SASL_HANDSHAKE_API_KEY, 1, 1),
+    (API_VERSIONS_API_KEY, 0, 2),
+    (SASL_AUTHENTICATE_API_KEY, 0, 1),
+];
+
+/// The only SASL mechanism supported.
+pub(super) const SASL_PLAIN_MECHANISM: &str = "PLAIN";
+
+pub(super) mod error_code {
+    pub const NONE: i16 = 0;
+    pub const UNKNOWN_SERVER_ERROR: i16 = -1;
+    pub const CORRUPT_MESSAGE: i16 = 2;
+    pub const UNKNOWN_TOPIC_OR_PARTITION: i16 = 3;
+    pub const LEADER_NOT_AVAILABLE: i16 = 5;
+    pub const REQUEST_TIMED_OUT: i16 = 7;
+    pub const MESSAGE_TOO_LARGE: i16 = 10;
+    pub const UNSUPPORTED_SASL_MECHANISM: i16 = 33;
+    pub const ILLEGAL_SASL_STATE: i16 = 34;
+    pub const UNSUPPORTED_VERSION: i16 = 35;
+    pub const INVALID_REQUEST: i16 = 42;
+    pub const SASL_AUTHENTICATION_FAILED: i16 = 58;
+    pub const UNSUPPORTED_COMPRESSION_TYPE: i16 = 76;
+}
+
+/// Returns `true` if the version of the API is supported.
+pub(super) fn is_supported_version(api_key: i16, api_version: i16) -> bool {
+    SUPPORTED_API_VERSIONS
+        .iter()
+        .any(|(key, min_version, max_version)| {
+            *key == api_key && (*min_version..=*max_version).contains(&api_version)
+        })
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(super) enum ProtocolError {
+    #[error("unexpected end of message")]
+    UnexpectedEof,
+    #[error("invalid message: {0}")]
+    Invalid(String),
+    #[error("unsupported compression type `{0}`")]
+    UnsupportedCompression(i16),
+    #[error("decompressed records exceed the size limit of the request")]
+    DecompressedSizeExceeded,
+    #[error("record batch CRC mismatch: expected `{expected:#010x}`, got `{actual:#010x}`")]
+    CrcMismatch { expected: u32, actual: u32 },
+}
+
+type ProtocolResult<T> = Result<T, ProtocolError>;
+
+/// Reads the primitive types of the protocol from a buffer.
+pub(super) struct Decoder<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    fn take(&mut self, num_bytes: usize) -> ProtocolResult<&'a [u8]> {
+        if self.buffer.len() < num_bytes {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        let (head, tail) = self.buffer.split_at(num_bytes);
+        self.buffer = tail;
+        Ok(head)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> ProtocolResult<[u8; N]> {
+        let bytes = self.take(N)?;
+        Ok(bytes
+            .try_into()
+            .expect("slice should have the right length"))
+    }
+
+    pub fn read_i8(&mut self) -> ProtocolResult<i8> {
+        self.take_array().map(i8::from_be_bytes)
+    }
+
+    pub fn read_i16(&mut self) -> ProtocolResult<i16> {
+        self.take_array().map(i16::from_be_bytes)
+    }
+
+    pub fn read_i32(&mut self) -> ProtocolResult<i32> {
+        self.take_array().map(i32::from_be_bytes)
+    }
+
+    pub fn read_i64(&mut self) -> ProtocolResult<i64> {
+        self.take_array().map(i64::from_be_bytes)
+    }
+
+    pub fn read_string(&mut self) -> ProtocolResult<String> {
+        self.read_nullable_string()?
+            .ok_or_else(|| ProtocolError::Invalid("unexpected null string".to_string()))
+    }
+
+    pub fn read_nullable_string(&mut self) -> ProtocolResult<Option<String>> {
+        let len = self.read_i16()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        let bytes = self.take(len as usize)?;
+        let string = String::from_utf8(bytes.to_vec())
+            .map_err(|_| ProtocolError::Invalid("string is not valid UTF-8".to_string()))?;
+        Ok(Some(string))
+    }
+
+    pub fn read_nullable_bytes(&mut self) -> ProtocolResult<Option<&'a [u8]>> {
+        let len = self.read_i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+
+    /// Reads the length of an array, which is `None` for null arrays.
+    pub fn read_array_len(&mut self) -> ProtocolResult<Option<usize>> {
+        let len = self.read_i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        // Every element takes at least one byte, which protects us from bogus lengths.
+        if len as usize > self.buffer.len() {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        Ok(Some(len as usize))
+    }
+
+    fn read_unsigned_varlong(&mut self) -> ProtocolResult<u64> {
+        let mut value: u64 = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.take_array::<1>()?[0];
+            value |= ((byte & 0x7F) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ProtocolError::Invalid("varint is too long".to_string()))
+    }
+
+    /// Reads a zigzag-encoded variable-length integer.
+    pub fn read_varlong(&mut self) -> ProtocolResult<i64> {
+        let value = self.read_unsigned_varlong()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    pub fn read_varint(&mut self) -> ProtocolResult<i32> {
+        let value = self.read_varlong()?;
+        i32::try_from(value).map_err(|_| ProtocolError::Invalid("varint overflow".to_string()))
+    }
+
+    /// Reads bytes prefixed by their zigzag-encoded length, which is negative for null bytes.
+    fn read_varint_bytes(&mut self) -> ProtocolResult<Option<&'a [u8]>> {
+        let len = self.read_varint()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+}
+
+/// Writes the primitive types of the protocol into a buffer.
+#[derive(Default)]
+pub(super) struct Encoder {
+    buffer: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn put_bool(&mut self, value: bool) {
+        self.buffer.push(value as u8);
+    }
+
+    pub fn put_i16(&mut self, value: i16) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn put_i32(&mut self, value: i32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn put_i64(&mut self, value: i64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn put_string(&mut self, value: &str) {
+        self.put_i16(value.len() as i16);
+        self.buffer.extend_from_slice(value.as_bytes());
+    }
+
+    pub fn put_nullable_string(&mut self, value_opt: Option<&str>) {
+        if let Some(value) = value_opt {
+            self.put_string(value);
+        } else {
+            self.put_i16(-1);
+        }
+    }
+
+    pub fn put_nullable_bytes(&mut self, value_opt: Option<&[u8]>) {
+        if let Some(value) = value_opt {
+            self.put_i32(value.len() as i32);
+            self.buffer.extend_from_slice(value);
+        } else {
+            self.put_i32(-1);
+        }
+    }
+
+    pub fn put_array_len(&mut self, len: usize) {
+        self.put_i32(len as i32);
+    }
+}
+
+/// Request header v1, shared by the non-flexible versions of all the APIs.
+#[derive(Debug)]
+pub(super) struct RequestHeader {
+    pub api_key: i16,
+    pub api_version: i16,
+    pub correlation_id: i32,
+}
+
+impl RequestHeader {
+    pub fn decode(decoder: &mut Decoder) -> ProtocolResult<Self> {
+        let api_key = decoder.read_i16()?;
+        let api_version = decoder.read_i16()?;
+        let correlation_id = decoder.read_i32()?;
+        // Flexible requests (e.g. `ApiVersions` v3) use a different header, but the fields above
+        // are enough to reject them.
+        if is_supported_version(api_key, api_version) {
+            let _client_id = decoder.read_nullable_string()?;
+        }
+        Ok(Self {
+            api_key,
+            api_version,
+            correlation_id,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct ApiVersionsResponse {
+    pub error_code: i16,
+}
+
+impl ApiVersionsResponse {
+    pub fn encode(&self, api_version: i16, encoder: &mut Encoder) {
+        encoder.put_i16(self.error_code);
+        encoder.put_array_len(SUPPORTED_API_VERSIONS.len());
+
+        for (api_key, min_version, max_version) in SUPPORTED_API_VERSIONS {
+            encoder.put_i16(api_key);
+            encoder.put_i16(min_version);
+            encoder.put_i16(max_version);
+        }
+        if api_version >= 1 {
+            // Throttle time.
+            encoder.put_i32(0);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct SaslHandshakeRequest {
+    pub mechanism: String,
+}
+
+impl SaslHandshakeRequest {
+    pub fn decode(decoder: &mut Decoder) -> ProtocolResult<Self> {
+        let mechanism = decoder.read_string()?;
+        Ok(Self { mechanism })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct SaslHandshakeResponse {
+    pub error_code: i16,
+}
+
+impl SaslHandshakeResponse {
+    pub fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_i16(self.error_code);
+        encoder.put_array_len(1);
+        encoder.put_string(SASL_PLAIN_MECHANISM);
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct SaslAuthenticateRequest<'a> {
+    pub auth_bytes: &'a [u8],
+}
+
+impl<'a> SaslAuthenticateRequest<'a> {
+    pub fn decode(decoder: &mut Decoder<'a>) -> ProtocolResult<Self> {
+        let auth_bytes = decoder
+            .read_nullable_bytes()?
+            .ok_or_else(|| ProtocolError::Invalid("unexpected null auth bytes".to_string()))?;
+        Ok(Self { auth_bytes })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct SaslAuthenticateResponse {
+    pub error_code: i16,
+    pub error_message: Option<String>,
+}
+
+impl SaslAuthenticateResponse {
+    pub fn encode(&self, api_version: i16, encoder: &mut Encoder) {
+        encoder.put_i16(self.error_code);
+        encoder.put_nullable_string(self.error_message.as_deref());
+        // Auth bytes: the PLAIN mechanism does not send a server challenge.
+        encoder.put_nullable_bytes(Some(&[]));
+
+        if api_version >= 1 {
+            // Session lifetime: the clients never need to re-authenticate.
+            encoder.put_i64(0);
+        }
+    }
+}
+
+/// Parses a SASL/PLAIN token, `[authzid] NUL authcid NUL passwd` (RFC 4616), and returns the
+/// username and the password. The authorization identity, if any, must match the username.
+pub(super) fn parse_sasl_plain_token(auth_bytes: &[u8]) -> ProtocolResult<(&str, &str)> {
+    let token = std::str::from_utf8(auth_bytes)
+        .map_err(|_| ProtocolError::Invalid("SASL/PLAIN token is not valid UTF-8".to_string()))?;
+    let mut parts = token.split('\0');
+
+    let (Some(authzid), Some(username), Some(password), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ProtocolError::Invalid(
+            "SASL/PLAIN token must have three fields".to_string(),
+        ));
+    };
+    if !authzid.is_empty() && authzid != username {
+        return Err(ProtocolError::Invalid(
+            "SASL/PLAIN authorization identity must match the username".to_string(),
+        ));
+    }
+    Ok((username, password))
+}
+
+#[derive(Debug)]
+pub(super) struct MetadataRequest {
+    /// Requested topics. `None` means all topics.
+    pub topics: Option<Vec<String>>,
+}
+
+impl MetadataRequest {
+    pub fn decode(api_version: i16, decoder: &mut Decoder) -> ProtocolResult<Self> {
+        let topics = match decoder.read_array_len()? {
+            // In v0, an empty array means all topics.
+            Some(0) if api_version == 0 => None,
+            Some(num_topics) => {
+                let topics = (0..num_topics)
+                    .map(|_| decoder.read_string())
+                    .collect::<ProtocolResult<_>>()?;
+                Some(topics)
+            }
+            None => None,
+        };
+        // The remaining fields (`allow_auto_topic_creation`, authorized operations) are ignored.
+        Ok(Self { topics })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct MetadataBroker {
+    pub node_id: i32,
+    pub host: String,
+    pub port: i32,
+}
+
+#[derive(Debug)]
+pub(super) struct MetadataTopic {
+    pub error_code: i16,
+    pub name: String,
+    /// Partition indexes, all led by the single broker.
+    pub partitions: Vec<i32>,
+}
+
+#[derive(Debug)]
+pub(super) struct MetadataResponse {
+    pub broker: MetadataBroker,
+    pub cluster_id: String,
+    pub topics: Vec<MetadataTopic>,
+}
+
+impl MetadataResponse {
+    pub fn encode(&self, api_version: i16, encoder: &mut Encoder) {
+        if api_version >= 3 {
+            // Throttle time.
+            encoder.put_i32(0);
+        }
+        encoder.put_array_len(1);
+        encoder.put_i32(self.broker.node_id);
+        encoder.put_string(&self.broker.host);
+        encoder.put_i32(self.broker.port);
+
+        if api_version >= 1 {
+            // Rack.
+            encoder.put_nullable_string(None);
+        }
+        if api_version >= 2 {
+            encoder.put_nullable_string(Some(&self.cluster_id));
+        }
+        if api_version >= 1 {
+            // Controller ID.
+            encoder.put_i32(self.broker.node_id);
+        }
+        encoder.put_array_len(self.topics.len());
+
+        for topic in &self.topics {
+            encoder.put_i16(topic.error_code);
+            encoder.put_string(&topic.name);
+
+            if api_version >= 1 {
+                // Is internal.
+                encoder.put_bool(false);
+            }
+            encoder.put_array_len(topic.partitions.len());
+
+            for partition_index in &topic.partitions {
+                encoder.put_i16(error_code::NONE);
+                encoder.put_i32(*partition_index);
+                // Leader ID.
+                encoder.put_i32(self.broker.node_id);
+
+                if api_version >= 7 {
+                    // Leader epoch.
+                    encoder.put_i32(0);
+                }
+                // Replica nodes and in-sync replica nodes.
+                for _ in 0..2 {
+                    encoder.put_array_len(1);
+                    encoder.put_i32(self.broker.node_id);
+                }
+                if api_version >= 5 {
+                    // Offline replicas.
+                    encoder.put_array_len(0);
+                }
+            }
+            if api_version >= 8 {
+                // Topic authorized operations: not provided.
+                encoder.put_i32(i32::MIN);
+            }
+        }
+        if api_version >= 8 {
+            // Cluster authorized operations: not provided.
+            encoder.put_i32(i32::MIN);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct ProduceRequest<'a> {
+    pub transactional_id: Option<String>,
+    pub acks: i16,
+    pub timeout_ms: i32,
+    pub topics: Vec<ProduceTopicData<'a>>,
+}
+
+#[derive(Debug)]
+pub(super) struct ProduceTopicData<'a> {
+    pub name: String,
+    pub partitions: Vec<ProducePartitionData<'a>>,
+}
+
+#[derive(Debug)]
+pub(super) struct ProducePartitionData<'a> {
+    pub index: i32,
+    pub records: Option<&'a [u8]>,
+}
+
+impl<'a> ProduceRequest<'a> {
+    pub fn decode(decoder: &mut Decoder<'a>) -> ProtocolResult<Self> {
+        let transactional_id = decoder.read_nullable_string()?;
+        let acks = decoder.read_i16()?;
+        let timeout_ms = decoder.read_i32()?;
+        let num_topics = decoder.read_array_len()?.unwrap_or(0);
+        let mut topics = Vec::with_capacity(num_topics);
+
+        for _ in 0..num_topics {
+            let name = decoder.read_string()?;
+            let num_partitions = decoder.read_array_len()?.unwrap_or(0);
+            let mut partitions = Vec::with_capacity(num_partitions);
+
+            for _ in 0..num_partitions {
+                let index = decoder.read_i32()?;
+                let records = decoder.read_nullable_bytes()?;
+                partitions.push(ProducePartitionData { index, records });
+            }
+            topics.push(ProduceTopicData { name, partitions });
+        }
+        Ok(Self {
+            transactional_id,
+            acks,
+            timeout_ms,
+            topics,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct ProduceResponse {
+    pub topics: Vec<ProduceTopicResponse>,
+}
+
+#[derive(Debug)]
+pub(super) struct ProduceTopicResponse {
+    pub name: String,
+    pub partitions: Vec<ProducePartitionResponse>,
+}
+
+#[derive(Debug)]
+pub(super) struct ProducePartitionResponse {
+    pub index: i32,
+    pub error_code: i16,
+    pub error_message: Option<String>,
+}
+
+impl ProduceResponse {
+    pub fn encode(&self, api_version: i16, encoder: &mut Encoder) {
+        encoder.put_array_len(self.topics.len());
+
+        for topic in &self.topics {
+            encoder.put_string(&topic.name);
+            encoder.put_array_len(topic.partitions.len());
+
+            for partition in &topic.partitions {
+                encoder.put_i32(partition.index);
+                encoder.put_i16(partition.error_code);
+                // Base offset: the records are not assigned Kafka offsets.
+                encoder.put_i64(-1);
+
+                if api_version >= 2 {
+                    // Log append time.
+                    encoder.put_i64(-1);
+                }
+                if api_version >= 5 {
+                    // Log start offset.
+                    encoder.put_i64(-1);
+                }
+                if api_version >= 8 {
+                    // Record errors.
+                    encoder.put_array_len(0);
+                    encoder.put_nullable_string(partition.error_message.as_deref());
+                }
+            }
+        }
+        // Throttle time.
+        encoder.put_i32(0);
+    }
+}
+
+/// A record of a record batch. Headers, timestamps, and offsets are dropped.
+#[derive(Debug, PartialEq)]
+pub(super) struct Record {
+    pub key: Option<Vec<u8>>,
+    pub value: Option<Vec<u8>>,
+}
+
+const COMPRESSION_CODEC_MASK: i16 = 0x07;
+const CONTROL_BATCH_FLAG: i16 = 0x20;
+
+/// Reads a decompressed record batch from `reader`, reading at most `decompressed_size_budget`
+/// bytes so that a small compressed batch cannot expand into an arbitrarily large buffer. The size
+/// of the batch is deducted from the budget.
+fn read_decompressed_records(
+    reader: impl Read,
+    decompressed_size_budget: &mut usize,
+) -> ProtocolResult<Vec<u8>> {
+    let mut decompressed_records = Vec::new();
+    reader
+        .take(*decompressed_size_budget as u64 + 1)
+        .read_to_end(&mut decompressed_records)
+        .map_err(|error| ProtocolError::Invalid(error.to_string()))?;
+
+    if decompressed_records.len() > *decompressed_size_budget {
+        return Err(ProtocolError::DecompressedSizeExceeded);
+    }
+    *decompressed_size_budget -= decompressed_records.len();
+    Ok(decompressed_records)
+}
+
+/// Decodes the records of a sequence of record batches (magic 2). Control batches are skipped.
+/// The CRC-32C of each batch is verified before the batch is decompressed. Compressed batches are
+/// decompressed within `decompressed_size_budget`, see [`read_decompressed_records`].
+pub(super) fn decode_record_batches(
+    bytes: &[u8],
+    decompressed_size_budget: &mut usize,
+) -> ProtocolResult<Vec<Record>> {
+    let mut decoder = Decoder::new(bytes);
+    let mut records = Vec::new();
+
+    while !decoder.is_empty() {
+        let _base_offset = decoder.read_i64()?;
+        let batch_len = decoder.read_i32()?;
+
+        if batch_len < 0 {
+            return Err(ProtocolError::Invalid("negative batch length".to_string()));
+        }
+        let mut batch_decoder = Decoder::new(decoder.take(batch_len as usize)?);
+        let _partition_leader_epoch = batch_decoder.read_i32()?;
+        let magic = batch_decoder.read_i8()?;
+
+        if magic != 2 {
+            return Err(ProtocolError::Invalid(format!(
+                "unsupported record batch magic `{magic}`"
+            )));
+        }
+        let expected_crc = batch_decoder.read_i32()? as u32;
+        // The CRC covers the rest of the batch, from the attributes to the last record.
+        let actual_crc = crc32c::crc32c(batch_decoder.buffer);
+
+        if actual_crc != expected_crc {
+            return Err(ProtocolError::CrcMismatch {
+                expected: expected_crc,
+                actual: actual_crc,
+            });
+        }
+        let attributes = batch_decoder.read_i16()?;
+        // Last offset delta, base timestamp, max timestamp, producer ID, producer epoch, and
+        // base sequence.
+        batch_decoder.take(4 + 8 + 8 + 8 + 2 + 4)?;
+        let num_records = batch_decoder.read_i32()?;
+
+        if attributes & CONTROL_BATCH_FLAG != 0 {
+            continue;
+        }
+        let compressed_records = batch_decoder.buffer;
+        let records_bytes: Cow<[u8]> = match attributes & COMPRESSION_CODEC_MASK {
+            0 => Cow::Borrowed(compressed_records),
+            1 => {
+                let gzip_decoder = GzDecoder::new(compressed_records);
+                let decompressed_records =
+                    read_decompressed_records(gzip_decoder, decompressed_size_budget)?;
+                Cow::Owned(decompressed_records)
+            }
+            4 => {
+                let zstd_decoder = zstd::stream::read::Decoder::new(compressed_records)
+                    .map_err(|error| ProtocolError::Invalid(error.to_string()))?;
+                let decompressed_records =
+                    read_decompressed_records(zstd_decoder, decompressed_size_budget)?;
+                Cow::Owned(decompressed_records)
+            }
+            compression_codec => {
+                return Err(ProtocolError::UnsupportedCompression(compression_codec));
+            }
+        };
+        let mut records_decoder = Decoder::new(&records_bytes);
+
+        for _ in 0..num_records {
+            let record_len = records_decoder.read_varint()?;
+
+            if record_len < 0 {
+                return Err(ProtocolError::Invalid("negative record length".to_string()));
+            }
+            let mut record_decoder = Decoder::new(records_decoder.take(record_len as usize)?);
+            let _attributes = record_decoder.read_i8()?;
+            let _timestamp_delta = record_decoder.read_varlong()?;
+            let _offset_delta = record_decoder.read_varint()?;
+            let key = record_decoder.read_varint_bytes()?.map(<[u8]>::to_vec);
+            let value = record_decoder.read_varint_bytes()?.map(<[u8]>::to_vec);
+            // Headers are ignored.
+            records.push(Record { key, value });
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+impl Encoder {
+    pub fn put_i8(&mut self, value: i8) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn put_varlong(&mut self, value: i64) {
+        let mut value = ((value << 1) ^ (value >> 63)) as u64;
+
+        while value >= 0x80 {
+            self.buffer.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+
+    pub fn put_varint(&mut self, value: i32) {
+        self.put_varlong(value as i64);
+    }
+
+    pub fn put_varint_bytes(&mut self, value_opt: Option<&[u8]>) {
+        if let Some(value) = value_opt {
+            self.put_varint(value.len() as i32);
+            self.buffer.extend_from_slice(value);
+        } else {
+            self.put_varint(-1);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) fn encode_record_batch(records: &[Record]) -> Vec<u8> {
+    encode_compressed_record_batch(records, 0)
+}
+
+#[cfg(test)]
+pub(super) fn encode_compressed_record_batch(
+    records: &[Record],
+    compression_codec: i16,
+) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut records_encoder = Encoder::default();
+
+    for (offset_delta, record) in records.iter().enumerate() {
+        let mut record_encoder = Encoder::default();
+        record_encoder.put_i8(0);
+        record_encoder.put_varlong(0);
+        record_encoder.put_varint(offset_delta as i32);
+        record_encoder.put_varint_bytes(record.key.as_deref());
+        record_encoder.put_varint_bytes(record.value.as_deref());
+        record_encoder.put_varint(0);
+
+        let record_bytes = record_encoder.into_bytes();
+        records_encoder.put_varint(record_bytes.len() as i32);
+        records_encoder.buffer.extend_from_slice(&record_bytes);
+    }
+    let records_bytes = match compression_codec {
+        0 => records_encoder.into_bytes(),
+        1 => {
+            let mut gzip_encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            gzip_encoder
+                .write_all(&records_encoder.into_bytes())
+                .unwrap();
+            gzip_encoder.finish().unwrap()
+        }
+        4 => zstd::encode_all(&records_encoder.into_bytes()[..], 0).unwrap(),
+        _ => panic!("compression codec `{compression_codec}` is not supported"),
+    };
+    let mut crc_encoder = Encoder::default();
+    crc_encoder.put_i16(compression_codec);
+    crc_encoder.put_i32(records.len() as i32 - 1);
+    crc_encoder.put_i64(0);
+    crc_encoder.put_i64(0);
+    crc_encoder.put_i64(-1);
+    crc_encoder.put_i16(-1);
+    crc_encoder.put_i32(-1);
+    crc_encoder.put_i32(records.len() as i32);
+    crc_encoder.buffer.extend_from_slice(&records_bytes);
+    let crc_bytes = crc_encoder.into_bytes();
+
+    let mut batch_encoder = Encoder::default();
+    batch_encoder.put_i32(0);
+    batch_encoder.put_i8(2);
+    batch_encoder.put_i32(crc32c::crc32c(&crc_bytes) as i32);
+    batch_encoder.buffer.extend_from_slice(&crc_bytes);
+    let batch_bytes = batch_encoder.into_bytes();
+
+    let mut encoder = Encoder::default();
+    encoder.put_i64(0);
+    encoder.put_i32(batch_bytes.len() as i32);
+    encoder.buffer.extend_from_slice(&batch_bytes);
+    encoder.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for value in [
+            0,
+            1,
+            -1,
+            63,
+            -64,
+            64,
+            300,
+            -300,
+            i32::MAX as i64,
+            i64::MIN,
+            i64::MAX,
+        ] {
+            let mut encoder = Encoder::default();
+            encoder.put_varlong(value);
+            let bytes = encoder.into_bytes();
+            let mut decoder = Decoder::new(&bytes);
+            assert_eq!(decoder.read_varlong().unwrap(), value);
+            assert!(decoder.is_empty());
+        }
+        let mut encoder = Encoder::default();
+        encoder.put_varint(-1);
+        assert_eq!(encoder.into_bytes(), [0x01]);
+    }
+
+    #[test]
+    fn test_decode_record_batches() {
+        let records = vec![
+            Record {
+                key: Some(b"key-1".to_vec()),
+                value: Some(br#"{"message": "foo"}"#.to_vec()),
+            },
+            Record {
+                key: None,
+                value: Some(br#"{"message": "bar"}"#.to_vec()),
+            },
+            Record {
+                key: Some(b"key-2".to_vec()),
+                value: None,
+            },
+        ];
+        let mut bytes = encode_record_batch(&records[..2]);
+        bytes.extend(encode_record_batch(&records[2..]));
+
+        let decoded_records = decode_record_batches(&bytes, &mut 0).unwrap();
+        assert_eq!(decoded_records, records);
+
+        let error = decode_record_batches(&bytes[..bytes.len() - 1], &mut 0).unwrap_err();
+        assert_eq!(error, ProtocolError::UnexpectedEof);
+
+        // Batch offset and length, partition leader epoch, magic, and CRC.
+        let crc_start = 12 + 4 + 1;
+        let attributes_start = crc_start + 4;
+
+        let mut bytes = encode_record_batch(&records);
+        let last_byte = bytes.last_mut().unwrap();
+        *last_byte = last_byte.wrapping_add(1);
+        let error = decode_record_batches(&bytes, &mut 0).unwrap_err();
+        assert!(matches!(error, ProtocolError::CrcMismatch { .. }));
+
+        // Snappy compression is not supported.
+        let mut bytes = encode_record_batch(&records);
+        bytes[attributes_start + 1] = 2;
+        let crc = crc32c::crc32c(&bytes[attributes_start..]);
+        bytes[crc_start..attributes_start].copy_from_slice(&crc.to_be_bytes());
+        let error = decode_record_batches(&bytes, &mut 0).unwrap_err();
+        assert_eq!(error, ProtocolError::UnsupportedCompression(2));
+    }
+
+    #[test]
+    fn test_parse_sasl_plain_token() {
+        assert_eq!(
+            parse_sasl_plain_token(b"\0producer\0secret").unwrap(),
+            ("producer", "secret")
+        );
+        assert_eq!(
+            parse_sasl_plain_token(b"producer\0producer\0secret").unwrap(),
+            ("producer", "secret")
+        );
+        parse_sasl_plain_token(b"admin\0producer\0secret").unwrap_err();
+        parse_sasl_plain_token(b"producer\0secret").unwrap_err();
+        parse_sasl_plain_token(b"\0producer\0secret\0").unwrap_err();
+    }
+
+    #[test]
+    fn test_decode_compressed_record_batches() {
+        let records = vec![Record {
+            key: None,
+            value: Some(vec![b'a'; 100_000]),
+        }];
+        for compression_codec in [1, 4] {
+            let bytes = encode_compressed_record_batch(&records, compression_codec);
+            assert!(bytes.len() < 1_000);
+
+            let mut decompressed_size_budget = 200_000;
+            let decoded_records =
+                decode_record_batches(&bytes, &mut decompressed_size_budget).unwrap();
+            assert_eq!(decoded_records, records);
+            assert!(decompressed_size_budget < 100_000);
+
+            // The second batch does not fit in what is left of the budget.
+            let mut two_batches = bytes.clone();
+            two_batches.extend(&bytes);
+            let mut decompressed_size_budget = 150_000;
+            let error =
+                decode_record_batches(&two_batches, &mut decompressed_size_budget).unwrap_err();
+            assert_eq!(error, ProtocolError::DecompressedSizeExceeded);
+
+            let error = decode_record_batches(&bytes, &mut 1_000).unwrap_err();
+            assert_eq!(error, ProtocolError::DecompressedSizeExceeded);
+        }
+    }
+
+    #[test]
+    fn test_decode_produce_request() {
+        let record_batch = encode_record_batch(&[Record {
+            key: None,
+            value: Some(b"{}".to_vec()),
+        }]);
+        let mut encoder = Encoder::default();
+        encoder.put_nullable_string(None);
+        encoder.put_i16(1);
+        encoder.put_i32(30_000);
+        encoder.put_array_len(1);
+        encoder.put_string("test-topic");
+        encoder.put_array_len(1);
+        encoder.put_i32(0);
+        encoder.put_nullable_bytes(Some(&record_batch));
+        let bytes = encoder.into_bytes();
+
+        let produce_request = ProduceRequest::decode(&mut Decoder::new(&bytes)).unwrap();
+        assert!(produce_request.transactional_id.is_none());
+        assert_eq!(produce_request.acks, 1);
+        assert_eq!(produce_request.timeout_ms, 30_000);
+        assert_eq!(produce_request.topics.len(), 1);
+        assert_eq!(produce_request.topics[0].name, "test-topic");
+        assert_eq!(produce_request.topics[0].partitions.len(), 1);
+        assert_eq!(produce_request.topics[0].partitions[0].index, 0);
+        assert_eq!(
+            produce_request.topics[0].partitions[0].records,
+            Some(&record_batch[..])
+        );
+    }
+
+    #[test]
+    fn test_decode_metadata_request() {
+        let mut encoder = Encoder::default();
+        encoder.put_array_len(0);
+        let bytes = encoder.into_bytes();
+
+        let metadata_request = MetadataRequest::decode(0, &mut Decoder::new(&bytes)).unwrap();
+        assert!(metadata_request.topics.is_none());
+
+        let metadata_request = MetadataRequest::decode(1, &mut Decoder::new(&bytes)).unwrap();
+        assert_eq!(metadata_request.topics.unwrap().len(), 0);
+
+        let mut encoder = Encoder::default();
+        encoder.put_array_len(1);
+        encoder.put_string("test-topic");
+        encoder.put_bool(true);
+        let bytes = encoder.into_bytes();
+
+        let metadata_request = MetadataRequest::decode(4, &mut Decoder::new(&bytes)).unwrap();
+        assert_eq!(metadata_request.topics.unwrap(), ["test-topic"]);
+    }
+
+    #[test]
+    fn test_request_header_decode() {
+        let mut encoder = Encoder::default();
+        encoder.put_i16(API_VERSIONS_API_KEY);
+        encoder.put_i16(3);
+        encoder.put_i32(42);
+        let bytes = encoder.into_bytes();
+
+        let header = RequestHeader::decode(&mut Decoder::new(&bytes)).unwrap();
+        assert_eq!(header.api_key, API_VERSIONS_API_KEY);
+        assert_eq!(header.api_version, 3);
+        assert_eq!(header.correlation_id, 42);
+        assert!(Decoder::new(&bytes[8..]).is_empty());
+
+        let mut encoder = Encoder::default();
+        encoder.put_i16(PRODUCE_API_KEY);
+        encoder.put_i16(7);
+        encoder.put_i32(43);
+        encoder.put_nullable_string(Some("test-client"));
+        let bytes = encoder.into_bytes();
+
+        let header = RequestHeader::decode(&mut Decoder::new(&bytes)).unwrap();
+        assert_eq!(header.api_key, PRODUCE_API_KEY);
+        assert_eq!(header.api_version, 7);
+        assert_eq!(header.correlation_id, 43);
+    }
+}
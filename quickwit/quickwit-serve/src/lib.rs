@@ -21,10 +21,12 @@
 
 mod build_info;
 mod cluster_api;
+mod control_plane_api;
 mod decompression;
 mod delete_task_api;
 mod developer_api;
 mod elasticsearch_api;
+mod external_splits;
 mod format;
 mod grpc;
 mod health_check_api;
@@ -32,6 +34,8 @@ mod index_api;
 mod indexing_api;
 mod ingest_api;
 mod jaeger_api;
+mod kafka_api;
+mod lookup_table_api;
 mod metrics;
 mod metrics_api;
 mod node_info_handler;
@@ -42,6 +46,7 @@ mod rest;
 mod rest_api_response;
 mod search_api;
 pub(crate) mod simple_list;
+mod state_api;
 mod template_api;
 mod ui_handler;
 
@@ -63,7 +68,8 @@ use itertools::Itertools;
 use once_cell::sync::Lazy;
 use quickwit_actors::{ActorExitStatus, Mailbox, SpawnContext, Universe};
 use quickwit_cluster::{
-    start_cluster_service, Cluster, ClusterChange, ClusterChangeStream, ListenerHandle,
+    start_cluster_service, Cluster, ClusterChange, ClusterChangeStream, EnabledClusterFeatures,
+    ListenerHandle,
 };
 use quickwit_common::pubsub::{EventBroker, EventSubscriptionHandle};
 use quickwit_common::rate_limiter::RateLimiterSettings;
@@ -77,30 +83,33 @@ use quickwit_common::tower::{
 };
 use quickwit_common::uri::Uri;
 use quickwit_config::service::QuickwitService;
-use quickwit_config::{ClusterConfig, NodeConfig};
+use quickwit_config::{ClusterConfig, NodeConfig, ShardIdStrategy, WalCompression};
 use quickwit_control_plane::control_plane::{ControlPlane, ControlPlaneEventSubscriber};
-use quickwit_control_plane::{IndexerNodeInfo, IndexerPool};
+use quickwit_control_plane::ingest::IngestControllerTimeouts;
+use quickwit_control_plane::{IndexerNodeInfo, IndexerPool, ModelSnapshotStore};
 use quickwit_index_management::{IndexService as IndexManager, IndexServiceError};
 use quickwit_indexing::actors::IndexingService;
-use quickwit_indexing::models::ShardPositionsService;
-use quickwit_indexing::start_indexing_service;
+use quickwit_indexing::models::{QueryAccessStatsRegistry, ShardPositionsService};
+use quickwit_indexing::{start_indexing_service, ParquetSplitMaterializer};
 use quickwit_ingest::{
     get_idle_shard_timeout, setup_local_shards_update_listener, start_ingest_api_service,
     wait_for_ingester_decommission, wait_for_ingester_status, GetMemoryCapacity, IngestRequest,
-    IngestRouter, IngestServiceClient, Ingester, IngesterPool, LocalShardsUpdate,
+    IngestRouter, IngestServiceClient, Ingester, IngesterPool, LocalShardsUpdate, WalOffload,
 };
 use quickwit_jaeger::JaegerService;
 use quickwit_janitor::{start_janitor_service, JanitorService};
 use quickwit_metastore::{
     ControlPlaneMetastore, ListIndexesMetadataResponseExt, MetastoreResolver,
 };
-use quickwit_opentelemetry::otlp::{OtlpGrpcLogsService, OtlpGrpcTracesService};
+use quickwit_opentelemetry::otlp::{OtelAuthenticator, OtlpGrpcLogsService, OtlpGrpcTracesService};
 use quickwit_proto::control_plane::ControlPlaneServiceClient;
 use quickwit_proto::indexing::{IndexingServiceClient, ShardPositionsUpdate};
 use quickwit_proto::ingest::ingester::{
     IngesterService, IngesterServiceClient, IngesterServiceTowerLayerStack, IngesterStatus,
+    OpenObservationStreamRequest,
 };
 use quickwit_proto::ingest::router::IngestRouterServiceClient;
+use quickwit_proto::ingest::DocCompression;
 use quickwit_proto::metastore::{
     EntityKind, ListIndexesMetadataRequest, MetastoreError, MetastoreService,
     MetastoreServiceClient,
@@ -108,8 +117,9 @@ use quickwit_proto::metastore::{
 use quickwit_proto::search::ReportSplitsRequest;
 use quickwit_proto::types::NodeId;
 use quickwit_search::{
-    create_search_client_from_channel, start_searcher_service, SearchJobPlacer, SearchService,
-    SearchServiceClient, SearcherContext, SearcherPool,
+    create_search_client_from_channel, start_searcher_service, LookupTableStore, SearchJobPlacer,
+    SearchService, SearchServiceClient, SearcherContext, SearcherPool, TermDigestStore,
+    LOOKUP_TABLES_DIRECTORY_NAME,
 };
 use quickwit_storage::{SplitCache, StorageResolver};
 use tokio::sync::oneshot;
@@ -119,11 +129,15 @@ use tracing::{debug, error, info, warn};
 use warp::{Filter, Rejection};
 
 pub use crate::build_info::{BuildInfo, RuntimeInfo};
+use crate::cluster_api::{setup_cluster_settings_listener, ClusterSettingsApplier};
+pub use crate::control_plane_api::ListShardsQueryParams;
+use crate::external_splits::ParquetExternalSplitMaterializer;
 pub use crate::index_api::{IndexUpdates, ListSplitsQueryParams, ListSplitsResponse};
 pub use crate::metrics::SERVE_METRICS;
 use crate::rate_modulator::RateModulator;
 #[cfg(test)]
 use crate::rest::recover_fn;
+use crate::search_api::spawn_query_access_stats_publisher;
 pub use crate::search_api::{search_request_from_api_request, SearchRequestQueryString, SortBy};
 
 const READINESS_REPORTING_INTERVAL: Duration = if cfg!(any(test, feature = "testsuite")) {
@@ -199,14 +213,23 @@ struct QuickwitServices {
     /// It is only used to serve the rest API calls and will only execute
     /// the root requests.
     pub search_service: Arc<dyn SearchService>,
+    pub lookup_table_store: Arc<LookupTableStore>,
 
     pub env_filter_reload_fn: EnvFilterReloadFn,
 
+    pub cluster_settings_applier: ClusterSettingsApplier,
+
+    /// Query access statistics of the indexes advertised by the searchers, reported by the index
+    /// stats API.
+    pub query_access_stats_registry: QueryAccessStatsRegistry,
+
     /// The control plane listens to various events.
     /// We must maintain a reference to the subscription handles to continue receiving
     /// notifications. Otherwise, the subscriptions are dropped.
     _local_shards_update_listener_handle_opt: Option<ListenerHandle>,
     _report_splits_subscription_handle_opt: Option<EventSubscriptionHandle>,
+    _cluster_settings_listener_handle_opt: Option<ListenerHandle>,
+    _query_access_stats_listener_handle_opt: Option<ListenerHandle>,
 }
 
 impl QuickwitServices {
@@ -308,6 +331,7 @@ async fn start_control_plane_if_needed(
     universe: &Universe,
     indexer_pool: &IndexerPool,
     ingester_pool: &IngesterPool,
+    storage_resolver: &StorageResolver,
 ) -> anyhow::Result<(Option<Mailbox<ControlPlane>>, ControlPlaneServiceClient)> {
     if node_config.is_service_enabled(QuickwitService::ControlPlane) {
         check_cluster_configuration(
@@ -324,6 +348,23 @@ async fn start_control_plane_if_needed(
             .expect("replication factor should have been validated")
             .get();
 
+        let model_snapshot_store_opt = if let Some(model_snapshot_uri) =
+            &node_config.control_plane_config.model_snapshot_uri
+        {
+            let storage = storage_resolver
+                .resolve(model_snapshot_uri)
+                .await
+                .context("failed to resolve control plane model snapshot storage")?;
+            let model_snapshot_store = ModelSnapshotStore::new(
+                storage,
+                node_config.control_plane_config.model_snapshot_max_age(),
+            );
+            Some(model_snapshot_store)
+        } else {
+            None
+        };
+        let ingest_controller_timeouts =
+            IngestControllerTimeouts::from(&node_config.control_plane_config);
         let control_plane_mailbox = setup_control_plane(
             universe,
             event_broker,
@@ -334,6 +375,11 @@ async fn start_control_plane_if_needed(
             metastore_client.clone(),
             node_config.default_index_root_uri.clone(),
             replication_factor,
+            node_config.ingest_api_config.shard_id_strategy,
+            model_snapshot_store_opt,
+            ingest_controller_timeouts,
+            node_config.control_plane_config.auto_create_indexes,
+            node_config.control_plane_config.enable_force_close_shards,
         )
         .await?;
 
@@ -347,11 +393,21 @@ async fn start_control_plane_if_needed(
         let control_plane_client = ControlPlaneServiceClient::tower()
             .stack_layer(shared_layers)
             .stack_create_index_layer(OneTaskPerCallLayer)
+            .stack_update_index_layer(OneTaskPerCallLayer)
             .stack_delete_index_layer(OneTaskPerCallLayer)
             .stack_add_source_layer(OneTaskPerCallLayer)
             .stack_toggle_source_layer(OneTaskPerCallLayer)
             .stack_delete_source_layer(OneTaskPerCallLayer)
             .stack_get_or_create_open_shards_layer(OneTaskPerCallLayer)
+            .stack_decommission_ingester_layer(OneTaskPerCallLayer)
+            .stack_move_shard_layer(OneTaskPerCallLayer)
+            .stack_get_shard_events_layer(OneTaskPerCallLayer)
+            .stack_rebalance_shards_dry_run_layer(OneTaskPerCallLayer)
+            .stack_list_shards_layer(OneTaskPerCallLayer)
+            .stack_project_source_shards_layer(OneTaskPerCallLayer)
+            .stack_force_close_shards_layer(OneTaskPerCallLayer)
+            .stack_pause_source_layer(OneTaskPerCallLayer)
+            .stack_resume_source_layer(OneTaskPerCallLayer)
             .build_from_mailbox(control_plane_mailbox);
         Ok((control_plane_server_opt, control_plane_client))
     } else {
@@ -498,6 +554,7 @@ pub async fn serve_quickwit(
         &universe,
         &indexer_pool,
         &ingester_pool,
+        &storage_resolver,
     )
     .await
     .context("failed to start control plane service")?;
@@ -546,6 +603,7 @@ pub async fn serve_quickwit(
         &event_broker,
         control_plane_client.clone(),
         ingester_pool,
+        &storage_resolver,
     )
     .await
     .context("failed to start ingest v2 service")?;
@@ -608,10 +666,52 @@ pub async fn serve_quickwit(
             None
         };
 
-    let searcher_context = Arc::new(SearcherContext::new(
-        node_config.searcher_config.clone(),
-        split_cache_opt,
-    ));
+    let lookup_tables_uri = node_config
+        .default_index_root_uri
+        .join(LOOKUP_TABLES_DIRECTORY_NAME)
+        .context("failed to build lookup tables URI")?;
+    let lookup_tables_storage = storage_resolver
+        .resolve(&lookup_tables_uri)
+        .await
+        .context("failed to resolve lookup tables storage")?;
+    let lookup_table_store = Arc::new(LookupTableStore::new(lookup_tables_storage));
+    let term_digest_store = Arc::new(TermDigestStore::new(storage_resolver.clone()));
+    let parquet_split_materializer = ParquetSplitMaterializer::new(
+        node_config.node_id.clone(),
+        node_config.data_dir_path.clone(),
+        metastore_through_control_plane.clone(),
+        storage_resolver.clone(),
+    )
+    .await
+    .context("failed to initialize Parquet split materializer")?;
+    let external_split_materializer =
+        Arc::new(ParquetExternalSplitMaterializer(parquet_split_materializer));
+
+    let searcher_context = Arc::new(
+        SearcherContext::new(node_config.searcher_config.clone(), split_cache_opt)
+            .with_lookup_table_store(lookup_table_store.clone())
+            .with_term_digest_store(term_digest_store)
+            .with_external_split_materializer(external_split_materializer),
+    );
+
+    // Every node applies the dynamic cluster settings stored in the metastore and listens for the
+    // updates gossiped by the other nodes.
+    let cluster_settings_applier = ClusterSettingsApplier::new(
+        Some(searcher_context.clone()),
+        control_plane_server_opt.clone(),
+    );
+    let cluster_settings_listener_handle = setup_cluster_settings_listener(
+        &cluster,
+        metastore_through_control_plane.clone(),
+        cluster_settings_applier.clone(),
+    )
+    .await;
+
+    // Every node runs root searches, so every node advertises the query access statistics of the
+    // indexes it searches.
+    spawn_query_access_stats_publisher(cluster.clone(), searcher_context.clone());
+    let (query_access_stats_registry, query_access_stats_listener_handle) =
+        QueryAccessStatsRegistry::subscribe(&cluster).await;
 
     let (search_job_placer, search_service) = setup_searcher(
         &node_config,
@@ -661,14 +761,15 @@ pub async fn serve_quickwit(
         None
     };
 
+    let otel_authenticator = OtelAuthenticator::new(&node_config.otel_auth_config);
+
     let jaeger_service_opt = if node_config.jaeger_config.enable_endpoint
         && node_config.is_service_enabled(QuickwitService::Searcher)
     {
         let search_service = search_service.clone();
-        Some(JaegerService::new(
-            node_config.jaeger_config.clone(),
-            search_service,
-        ))
+        let jaeger_service = JaegerService::new(node_config.jaeger_config.clone(), search_service)
+            .with_authenticator(otel_authenticator.clone());
+        Some(jaeger_service)
     } else {
         None
     };
@@ -676,7 +777,16 @@ pub async fn serve_quickwit(
     let otlp_logs_service_opt = if node_config.is_service_enabled(QuickwitService::Indexer)
         && node_config.indexer_config.enable_otlp_endpoint
     {
-        Some(OtlpGrpcLogsService::new(ingest_service.clone()))
+        let otlp_logs_service = OtlpGrpcLogsService::new(ingest_service.clone())
+            .with_api_key_quotas(&node_config.indexer_config.otlp_api_key_quotas)
+            .with_tenant_resource_attribute(
+                node_config
+                    .indexer_config
+                    .otlp_tenant_resource_attribute
+                    .clone(),
+            )
+            .with_authenticator(otel_authenticator.clone());
+        Some(otlp_logs_service)
     } else {
         None
     };
@@ -684,13 +794,43 @@ pub async fn serve_quickwit(
     let otlp_traces_service_opt = if node_config.is_service_enabled(QuickwitService::Indexer)
         && node_config.indexer_config.enable_otlp_endpoint
     {
-        Some(OtlpGrpcTracesService::new(ingest_service.clone(), None))
+        let otlp_traces_service = OtlpGrpcTracesService::new(ingest_service.clone(), None)
+            .with_trace_sampling(metastore_through_control_plane.clone())
+            .with_api_key_quotas(&node_config.indexer_config.otlp_api_key_quotas)
+            .with_tenant_resource_attribute(
+                node_config
+                    .indexer_config
+                    .otlp_tenant_resource_attribute
+                    .clone(),
+            )
+            .with_authenticator(otel_authenticator.clone());
+        Some(otlp_traces_service)
     } else {
         None
     };
 
     let grpc_listen_addr = node_config.grpc_listen_addr;
     let rest_listen_addr = node_config.rest_config.listen_addr;
+
+    if let Some(kafka_config) = node_config.ingest_api_config.kafka.clone() {
+        let kafka_listen_addr = SocketAddr::new(rest_listen_addr.ip(), kafka_config.listen_port);
+        let kafka_api_server = kafka_api::start_kafka_api_server(
+            kafka_listen_addr,
+            node_config.grpc_advertise_addr.ip().to_string(),
+            node_config.cluster_id.clone(),
+            kafka_config,
+            node_config.ingest_api_config.content_length_limit.as_u64() as usize,
+            ingest_router_service.clone(),
+        );
+        spawn_named_task(
+            async move {
+                if let Err(error) = kafka_api_server.await {
+                    error!(%error, "failed to start Kafka API server");
+                }
+            },
+            "kafka_api_server",
+        );
+    }
     let quickwit_services: Arc<QuickwitServices> = Arc::new(QuickwitServices {
         node_config: Arc::new(node_config),
         cluster: cluster.clone(),
@@ -700,6 +840,8 @@ pub async fn serve_quickwit(
         control_plane_client,
         _local_shards_update_listener_handle_opt: local_shards_update_listener_handle_opt,
         _report_splits_subscription_handle_opt: report_splits_subscription_handle_opt,
+        _cluster_settings_listener_handle_opt: Some(cluster_settings_listener_handle),
+        _query_access_stats_listener_handle_opt: Some(query_access_stats_listener_handle),
         index_manager,
         indexing_service_opt,
         ingest_router_service,
@@ -710,7 +852,10 @@ pub async fn serve_quickwit(
         otlp_logs_service_opt,
         otlp_traces_service_opt,
         search_service,
+        lookup_table_store,
         env_filter_reload_fn,
+        cluster_settings_applier,
+        query_access_stats_registry,
     });
     // Setup and start gRPC server.
     let (grpc_readiness_trigger_tx, grpc_readiness_signal_rx) = oneshot::channel::<()>();
@@ -754,6 +899,12 @@ pub async fn serve_quickwit(
 
     // Node readiness indicates that the server is ready to receive requests.
     // Thus readiness task is started once gRPC and REST servers are started.
+    if let Some(ingester) = ingester_opt.clone() {
+        spawn_named_task(
+            ingester_status_reporting_task(cluster.clone(), ingester),
+            "ingester_status_reporting",
+        );
+    }
     spawn_named_task(
         node_readiness_reporting_task(
             cluster,
@@ -824,6 +975,7 @@ async fn setup_ingest_v2(
     event_broker: &EventBroker,
     control_plane: ControlPlaneServiceClient,
     ingester_pool: IngesterPool,
+    storage_resolver: &StorageResolver,
 ) -> anyhow::Result<(IngestRouterServiceClient, Option<Ingester>)> {
     // Instantiate ingest router.
     let self_node_id: NodeId = cluster.self_node_id().into();
@@ -838,9 +990,21 @@ async fn setup_ingest_v2(
         control_plane.clone(),
         ingester_pool.clone(),
         replication_factor,
+        EnabledClusterFeatures::spawn(cluster.change_stream()),
     );
     ingest_router.subscribe(event_broker);
 
+    // Restore the routing table persisted before the last restart and keep persisting it.
+    let routing_table_snapshot_path = node_config.data_dir_path.join("routing-table.json");
+    let mut warm_start_router = ingest_router.clone();
+    let warm_start_snapshot_path = routing_table_snapshot_path.clone();
+    tokio::spawn(async move {
+        warm_start_router
+            .warm_start(&warm_start_snapshot_path)
+            .await
+    });
+    ingest_router.spawn_routing_table_snapshot_task(routing_table_snapshot_path);
+
     // Any node can serve ingest requests, so we always instantiate an ingest router.
     // TODO: I'm not sure that's such a good idea.
     let ingest_router_service = IngestRouterServiceClient::tower()
@@ -863,6 +1027,31 @@ async fn setup_ingest_v2(
         fs::create_dir_all(&wal_dir_path)?;
 
         let idle_shard_timeout = get_idle_shard_timeout();
+        let doc_compression = match node_config.ingest_api_config.wal_compression {
+            WalCompression::None => DocCompression::Unspecified,
+            WalCompression::Lz4 => DocCompression::Lz4,
+            WalCompression::Zstd => DocCompression::Zstd,
+        };
+        let wal_offload_opt =
+            if let Some(wal_offload_config) = &node_config.ingest_api_config.wal_offload {
+                let storage = storage_resolver
+                    .resolve(&wal_offload_config.uri)
+                    .await
+                    .context("failed to resolve WAL offload storage")?;
+                let max_queue_disk_usage = node_config.ingest_api_config.max_queue_disk_usage;
+                let disk_usage_threshold = ByteSize(
+                    max_queue_disk_usage.as_u64()
+                        * wal_offload_config.disk_usage_threshold_percent as u64
+                        / 100,
+                );
+                let max_local_age_opt = wal_offload_config.max_local_age()?;
+                Some(
+                    WalOffload::new(storage, disk_usage_threshold)
+                        .with_max_local_age(max_local_age_opt),
+                )
+            } else {
+                None
+            };
         let ingester = Ingester::try_new(
             cluster.clone(),
             control_plane,
@@ -873,6 +1062,8 @@ async fn setup_ingest_v2(
             rate_limiter_settings,
             replication_factor,
             idle_shard_timeout,
+            doc_compression,
+            wal_offload_opt,
         )
         .await?;
         ingester.subscribe(event_broker);
@@ -891,6 +1082,22 @@ async fn setup_ingest_v2(
         let ingester_opt_clone_clone = ingester_opt_clone.clone();
         Box::pin(async move {
             match cluster_change {
+                // Decommissioned ingesters no longer accept writes and have had all their shards
+                // indexed, so we evict them from the pool on every node instead of waiting for
+                // them to leave the cluster.
+                ClusterChange::Add(node) | ClusterChange::Update(node)
+                    if node.is_indexer()
+                        && node.ingester_status() == Some(IngesterStatus::Decommissioned) =>
+                {
+                    let chitchat_id = node.chitchat_id();
+                    info!(
+                        node_id = chitchat_id.node_id,
+                        generation_id = chitchat_id.generation_id,
+                        "removing decommissioned node `{}` from ingester pool",
+                        chitchat_id.node_id,
+                    );
+                    Some(Change::Remove(node.node_id().into()))
+                }
                 ClusterChange::Add(node) if node.is_indexer() => {
                     let chitchat_id = node.chitchat_id();
                     info!(
@@ -959,9 +1166,11 @@ async fn setup_searcher(
     )
     .await?;
     let search_service_clone = search_service.clone();
+    let search_job_placer_clone = search_job_placer.clone();
     let max_message_size = node_config.grpc_config.max_message_size;
     let searcher_change_stream = cluster_change_stream.filter_map(move |cluster_change| {
         let search_service_clone = search_service_clone.clone();
+        let search_job_placer_clone = search_job_placer_clone.clone();
         Box::pin(async move {
             match cluster_change {
                 ClusterChange::Add(node) if node.is_searcher() => {
@@ -973,6 +1182,7 @@ async fn setup_searcher(
                         chitchat_id.node_id,
                     );
                     let grpc_addr = node.grpc_advertise_addr();
+                    search_job_placer_clone.set_node_draining(grpc_addr, node.is_in_maintenance());
 
                     if node.is_self_node() {
                         let search_client =
@@ -996,8 +1206,14 @@ async fn setup_searcher(
                         "removing node `{}` from searcher pool",
                         chitchat_id.node_id,
                     );
+                    search_job_placer_clone.set_node_draining(node.grpc_advertise_addr(), false);
                     Some(Change::Remove(node.grpc_advertise_addr()))
                 }
+                ClusterChange::Update(node) if node.is_searcher() => {
+                    search_job_placer_clone
+                        .set_node_draining(node.grpc_advertise_addr(), node.is_in_maintenance());
+                    None
+                }
                 _ => None,
             }
         })
@@ -1017,13 +1233,20 @@ async fn setup_control_plane(
     metastore: MetastoreServiceClient,
     default_index_root_uri: Uri,
     replication_factor: usize,
+    shard_id_strategy: ShardIdStrategy,
+    model_snapshot_store_opt: Option<ModelSnapshotStore>,
+    ingest_controller_timeouts: IngestControllerTimeouts,
+    auto_create_indexes: bool,
+    enable_force_close_shards: bool,
 ) -> anyhow::Result<Mailbox<ControlPlane>> {
     let cluster_id = cluster.cluster_id().to_string();
     let cluster_config = ClusterConfig {
         cluster_id,
-        auto_create_indexes: true,
+        auto_create_indexes,
+        enable_force_close_shards,
         default_index_root_uri,
         replication_factor,
+        shard_id_strategy,
     };
     let (control_plane_mailbox, _control_plane_handle, mut readiness_rx) = ControlPlane::spawn(
         universe,
@@ -1033,6 +1256,8 @@ async fn setup_control_plane(
         indexer_pool,
         ingester_pool,
         metastore,
+        model_snapshot_store_opt,
+        ingest_controller_timeouts,
     );
     let subscriber = ControlPlaneEventSubscriber::new(control_plane_mailbox.downgrade());
     event_broker
@@ -1210,6 +1435,31 @@ async fn node_readiness_reporting_task(
     }
 }
 
+/// Advertises the status of the local ingester to the cluster so that the control plane and the
+/// ingester pools of the other nodes can track its decommissioning.
+async fn ingester_status_reporting_task(cluster: Cluster, mut ingester: impl IngesterService) {
+    let mut observation_stream = match ingester
+        .open_observation_stream(OpenObservationStreamRequest {})
+        .await
+    {
+        Ok(observation_stream) => observation_stream,
+        Err(error) => {
+            error!(%error, "failed to open ingester observation stream");
+            return;
+        }
+    };
+    let mut last_status_opt: Option<IngesterStatus> = None;
+
+    while let Some(Ok(observation_message)) = observation_stream.next().await {
+        let status = observation_message.status();
+
+        if last_status_opt != Some(status) {
+            cluster.set_self_ingester_status(status).await;
+            last_status_opt = Some(status);
+        }
+    }
+}
+
 /// Displays some warnings if the cluster runs a file-backed metastore or serves file-backed
 /// indexes.
 async fn check_cluster_configuration(
@@ -1261,8 +1511,8 @@ pub mod lambda_search_api {
         es_compat_cat_indices_handler, es_compat_index_cat_indices_handler,
         es_compat_index_count_handler, es_compat_index_field_capabilities_handler,
         es_compat_index_multi_search_handler, es_compat_index_search_handler,
-        es_compat_index_stats_handler, es_compat_scroll_handler, es_compat_search_handler,
-        es_compat_stats_handler,
+        es_compat_index_stats_handler, es_compat_resolve_index_handler, es_compat_scroll_handler,
+        es_compat_search_handler, es_compat_stats_handler,
     };
     pub use crate::rest::recover_fn;
     pub use crate::search_api::{search_get_handler, search_post_handler};
@@ -1367,6 +1617,46 @@ mod tests {
         assert!(!cluster.is_self_node_ready().await);
     }
 
+    #[tokio::test]
+    async fn test_ingester_status_reporting_task() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let (ingester_status_tx, ingester_status_rx) = watch::channel(IngesterStatus::Initializing);
+        let mut mock_ingester = MockIngesterService::new();
+        mock_ingester
+            .expect_open_observation_stream()
+            .returning(move |_| {
+                let status_stream = ServiceStream::from(ingester_status_rx.clone());
+                let observation_stream = status_stream.map(|status| {
+                    let message = ObservationMessage {
+                        node_id: "test-node".to_string(),
+                        status: status as i32,
+                    };
+                    Ok(message)
+                });
+                Ok(observation_stream)
+            });
+        tokio::spawn(ingester_status_reporting_task(
+            cluster.clone(),
+            mock_ingester,
+        ));
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert_eq!(
+            cluster.get_self_key_value("ingester_status").await.unwrap(),
+            "initializing"
+        );
+        ingester_status_tx
+            .send(IngesterStatus::Decommissioning)
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert_eq!(
+            cluster.get_self_key_value("ingester_status").await.unwrap(),
+            "decommissioning"
+        );
+    }
+
     #[tokio::test]
     async fn test_setup_indexer_pool() {
         let universe = Universe::with_accelerated_time();
@@ -0,0 +1,349 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use hyper::header::CONTENT_TYPE;
+use quickwit_config::validate_identifier;
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use quickwit_search::{LookupTable, LookupTableStore, MAX_LOOKUP_TABLE_NUM_BYTES};
+use quickwit_storage::{StorageError, StorageErrorKind};
+use serde::{Deserialize, Serialize};
+use warp::reject::Rejection;
+use warp::{Filter, Reply};
+
+use crate::format::extract_format_from_qs;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(put_lookup_table, get_lookup_table, delete_lookup_table),
+    components(schemas(LookupTableSummary))
+)]
+pub(crate) struct LookupTableApi;
+
+#[derive(Debug, Clone, thiserror::Error, Serialize)]
+pub(crate) enum LookupTableApiError {
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("lookup table `{0}` not found")]
+    NotFound(String),
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl ServiceError for LookupTableApiError {
+    fn error_code(&self) -> ServiceErrorCode {
+        match self {
+            LookupTableApiError::InvalidArgument(_) => ServiceErrorCode::BadRequest,
+            LookupTableApiError::NotFound(_) => ServiceErrorCode::NotFound,
+            LookupTableApiError::Internal(_) => ServiceErrorCode::Internal,
+        }
+    }
+}
+
+impl LookupTableApiError {
+    fn from_storage_error(table_name: String, storage_error: StorageError) -> Self {
+        if storage_error.kind() == StorageErrorKind::NotFound {
+            LookupTableApiError::NotFound(table_name)
+        } else {
+            LookupTableApiError::Internal(storage_error.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PutLookupTableQueryParams {
+    /// Name of the column holding the keys.
+    #[serde(default)]
+    key_column: Option<String>,
+}
+
+/// Summary of a lookup table returned by the put endpoint.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct LookupTableSummary {
+    pub table_name: String,
+    pub key_column: String,
+    pub num_rows: usize,
+}
+
+pub(crate) fn lookup_table_api_handlers(
+    lookup_table_store: Arc<LookupTableStore>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    put_lookup_table_handler(lookup_table_store.clone())
+        .or(get_lookup_table_handler(lookup_table_store.clone()))
+        .or(delete_lookup_table_handler(lookup_table_store))
+}
+
+fn put_lookup_table_handler(
+    lookup_table_store: Arc<LookupTableStore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("lookup-tables" / String)
+        .and(warp::put())
+        .and(warp::body::content_length_limit(
+            MAX_LOOKUP_TABLE_NUM_BYTES as u64,
+        ))
+        .and(warp::filters::body::bytes())
+        .and(warp::header::optional::<String>(CONTENT_TYPE.as_str()))
+        .and(serde_qs::warp::query::<PutLookupTableQueryParams>(
+            serde_qs::Config::default(),
+        ))
+        .and(with_arg(lookup_table_store))
+        .then(put_lookup_table)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Lookup Tables",
+    path = "/lookup-tables/{table_name}",
+    request_body(
+        content = String,
+        description = "CSV document with a header or JSON array or object of rows.",
+        content_type = "text/csv"
+    ),
+    responses(
+        (status = 200, description = "The lookup table was stored.", body = LookupTableSummary),
+        (status = 400, description = "The lookup table is invalid.")
+    ),
+    params(
+        ("table_name" = String, Path, description = "The name of the lookup table."),
+        ("key_column" = Option<String>, Query, description = "The column holding the keys."),
+    )
+)]
+/// Creates or replaces a lookup table.
+///
+/// CSV documents are expected when the content type is `text/csv`, JSON documents otherwise.
+async fn put_lookup_table(
+    table_name: String,
+    body: Bytes,
+    content_type_opt: Option<String>,
+    query_params: PutLookupTableQueryParams,
+    lookup_table_store: Arc<LookupTableStore>,
+) -> Result<LookupTableSummary, LookupTableApiError> {
+    validate_identifier("lookup table", &table_name)
+        .map_err(|error| LookupTableApiError::InvalidArgument(error.to_string()))?;
+
+    let key_column_opt = query_params.key_column.as_deref();
+    let is_csv = content_type_opt
+        .as_deref()
+        .map(|content_type| content_type.starts_with("text/csv"))
+        .unwrap_or(false);
+    let lookup_table_res = if is_csv {
+        std::str::from_utf8(&body)
+            .map_err(anyhow::Error::from)
+            .and_then(|csv| LookupTable::from_csv(csv, key_column_opt))
+    } else {
+        LookupTable::from_json(&body, key_column_opt)
+    };
+    let lookup_table = lookup_table_res.map_err(|error| {
+        LookupTableApiError::InvalidArgument(format!("invalid lookup table: {error:#}"))
+    })?;
+    let summary = LookupTableSummary {
+        table_name: table_name.clone(),
+        key_column: lookup_table.key_column.clone(),
+        num_rows: lookup_table.num_rows(),
+    };
+    lookup_table_store
+        .put_table(&table_name, lookup_table)
+        .await
+        .map_err(|error| LookupTableApiError::from_storage_error(table_name, error))?;
+    Ok(summary)
+}
+
+fn get_lookup_table_handler(
+    lookup_table_store: Arc<LookupTableStore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("lookup-tables" / String)
+        .and(warp::get())
+        .and(with_arg(lookup_table_store))
+        .then(get_lookup_table)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Lookup Tables",
+    path = "/lookup-tables/{table_name}",
+    responses(
+        (status = 200, description = "The lookup table was successfully retrieved."),
+        (status = 404, description = "The lookup table was not found.")
+    ),
+    params(
+        ("table_name" = String, Path, description = "The name of the lookup table."),
+    )
+)]
+/// Retrieves the lookup table identified by `table_name`.
+async fn get_lookup_table(
+    table_name: String,
+    lookup_table_store: Arc<LookupTableStore>,
+) -> Result<LookupTable, LookupTableApiError> {
+    let lookup_table = lookup_table_store
+        .get_table(&table_name)
+        .await
+        .map_err(|error| LookupTableApiError::from_storage_error(table_name, error))?;
+    Ok(LookupTable::clone(&lookup_table))
+}
+
+fn delete_lookup_table_handler(
+    lookup_table_store: Arc<LookupTableStore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("lookup-tables" / String)
+        .and(warp::delete())
+        .and(with_arg(lookup_table_store))
+        .then(delete_lookup_table)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    delete,
+    tag = "Lookup Tables",
+    path = "/lookup-tables/{table_name}",
+    responses(
+        (status = 200, description = "The lookup table was successfully deleted."),
+        (status = 404, description = "The lookup table was not found.")
+    ),
+    params(
+        ("table_name" = String, Path, description = "The name of the lookup table."),
+    )
+)]
+/// Deletes the lookup table identified by `table_name`.
+async fn delete_lookup_table(
+    table_name: String,
+    lookup_table_store: Arc<LookupTableStore>,
+) -> Result<(), LookupTableApiError> {
+    lookup_table_store
+        .delete_table(&table_name)
+        .await
+        .map_err(|error| LookupTableApiError::from_storage_error(table_name, error))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_storage::RamStorage;
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+
+    fn lookup_table_store_for_test() -> Arc<LookupTableStore> {
+        Arc::new(LookupTableStore::new(Arc::new(RamStorage::default())))
+    }
+
+    #[tokio::test]
+    async fn test_lookup_table_api_handlers() {
+        let lookup_table_store = lookup_table_store_for_test();
+        let handlers = lookup_table_api_handlers(lookup_table_store.clone());
+
+        let response = warp::test::request()
+            .path("/lookup-tables/countries?key_column=code")
+            .method("PUT")
+            .header("content-type", "text/csv")
+            .body("code,name\nFR,France\nJP,Japan\n")
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+        let summary: LookupTableSummary = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(summary.table_name, "countries");
+        assert_eq!(summary.key_column, "code");
+        assert_eq!(summary.num_rows, 2);
+
+        let response = warp::test::request()
+            .path("/lookup-tables/countries")
+            .method("GET")
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+        let lookup_table_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let expected_lookup_table_json = json!({
+            "key_column": "code",
+            "rows": {
+                "FR": {"name": "France"},
+                "JP": {"name": "Japan"},
+            }
+        });
+        assert_eq!(lookup_table_json, expected_lookup_table_json);
+
+        let response = warp::test::request()
+            .path("/lookup-tables/countries")
+            .method("DELETE")
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .path("/lookup-tables/countries")
+            .method("GET")
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 404);
+
+        let response = warp::test::request()
+            .path("/lookup-tables/countries")
+            .method("DELETE")
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_put_lookup_table_json() {
+        let handlers = lookup_table_api_handlers(lookup_table_store_for_test());
+
+        let response = warp::test::request()
+            .path("/lookup-tables/users")
+            .method("PUT")
+            .json(&json!({
+                "u1": {"team": "search"},
+                "u2": {"team": "ingest"},
+            }))
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 200);
+        let summary: LookupTableSummary = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(summary.key_column, "key");
+        assert_eq!(summary.num_rows, 2);
+    }
+
+    #[tokio::test]
+    async fn test_put_lookup_table_invalid() {
+        let handlers = lookup_table_api_handlers(lookup_table_store_for_test());
+
+        let response = warp::test::request()
+            .path("/lookup-tables/_invalid")
+            .method("PUT")
+            .json(&json!({}))
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        let response = warp::test::request()
+            .path("/lookup-tables/users")
+            .method("PUT")
+            .body("not json")
+            .reply(&handlers)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+}
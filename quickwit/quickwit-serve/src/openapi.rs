@@ -28,6 +28,7 @@ use utoipa::openapi::Tag;
 use utoipa::OpenApi;
 
 use crate::cluster_api::ClusterApi;
+use crate::control_plane_api::ControlPlaneApi;
 use crate::delete_task_api::DeleteTaskApi;
 use crate::developer_api::DeveloperApi;
 use crate::elasticsearch_api::ElasticCompatibleApi;
@@ -36,9 +37,11 @@ use crate::index_api::IndexApi;
 use crate::indexing_api::IndexingApi;
 use crate::ingest_api::{IngestApi, IngestApiSchemas};
 use crate::jaeger_api::JaegerApi;
+use crate::lookup_table_api::LookupTableApi;
 use crate::metrics_api::MetricsApi;
 use crate::node_info_handler::NodeInfoApi;
 use crate::search_api::SearchApi;
+use crate::state_api::StateApi;
 use crate::template_api::IndexTemplateApi;
 
 /// Builds the OpenApi docs structure using the registered/merged docs.
@@ -74,16 +77,20 @@ pub fn build_docs() -> utoipa::openapi::OpenApi {
         Tag::new("Sources"),
         Tag::new("Get Metrics"),
         Tag::new("Cluster Info"),
+        Tag::new("Control Plane"),
         Tag::new("Node Info"),
         Tag::new("Indexing"),
         Tag::new("Splits"),
         Tag::new("Jaeger"),
         Tag::new("Debugging"),
+        Tag::new("State"),
+        Tag::new("Lookup Tables"),
     ];
     docs_base.tags = Some(tags);
 
     // Routing
     docs_base.merge_components_and_paths(ClusterApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(ControlPlaneApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(DeleteTaskApi::openapi().with_path_prefix("/api/v1"));
     docs_base
         .merge_components_and_paths(DeveloperApi::openapi().with_path_prefix("/api/developer"));
@@ -95,9 +102,11 @@ pub fn build_docs() -> utoipa::openapi::OpenApi {
     docs_base.merge_components_and_paths(IndexTemplateApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(IngestApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(JaegerApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(LookupTableApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(MetricsApi::openapi().with_path_prefix("/metrics"));
     docs_base.merge_components_and_paths(NodeInfoApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(SearchApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(StateApi::openapi().with_path_prefix("/api/v1"));
 
     // Schemas
     docs_base.merge_components_and_paths(MetastoreApiSchemas::openapi());
@@ -0,0 +1,615 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of the OTLP/HTTP requests encoded in JSON, which follows the protobuf JSON mapping
+//! with two exceptions: trace and span IDs are hex-encoded, and enums are integers.
+//! See <https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding>.
+
+use std::fmt;
+use std::str::FromStr;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_proto::opentelemetry::proto::collector::logs::v1::ExportLogsServiceRequest;
+use quickwit_proto::opentelemetry::proto::collector::trace::v1::ExportTraceServiceRequest;
+use quickwit_proto::opentelemetry::proto::common::v1::{
+    any_value, AnyValue, ArrayValue, InstrumentationScope, KeyValue, KeyValueList,
+};
+use quickwit_proto::opentelemetry::proto::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+use quickwit_proto::opentelemetry::proto::resource::v1::Resource;
+use quickwit_proto::opentelemetry::proto::trace::v1::span::{Event, Link};
+use quickwit_proto::opentelemetry::proto::trace::v1::{ResourceSpans, ScopeSpans, Span, Status};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+pub(super) fn parse_export_logs_request(
+    body: &[u8],
+) -> serde_json::Result<ExportLogsServiceRequest> {
+    let json_request: JsonExportLogsServiceRequest = serde_json::from_slice(body)?;
+    Ok(json_request.into())
+}
+
+pub(super) fn parse_export_trace_request(
+    body: &[u8],
+) -> serde_json::Result<ExportTraceServiceRequest> {
+    let json_request: JsonExportTraceServiceRequest = serde_json::from_slice(body)?;
+    Ok(json_request.into())
+}
+
+/// Deserializes a 64-bit integer encoded either as a JSON number or as a string.
+fn deserialize_int64<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+    match NumberOrString::<T>::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number),
+        NumberOrString::String(number_str) => number_str.parse().map_err(D::Error::custom),
+    }
+}
+
+fn deserialize_opt_int64<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where D: Deserializer<'de> {
+    deserialize_int64(deserializer).map(Some)
+}
+
+fn deserialize_hex_id<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where D: Deserializer<'de> {
+    let id_hex = String::deserialize(deserializer)?;
+    hex::decode(id_hex).map_err(|error| D::Error::custom(format!("invalid hex ID: {error}")))
+}
+
+fn deserialize_opt_base64<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+where D: Deserializer<'de> {
+    let bytes_base64 = String::deserialize(deserializer)?;
+    BASE64_STANDARD
+        .decode(bytes_base64)
+        .map(Some)
+        .map_err(|error| D::Error::custom(format!("invalid base64 bytes: {error}")))
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonAnyValue {
+    string_value: Option<String>,
+    bool_value: Option<bool>,
+    #[serde(deserialize_with = "deserialize_opt_int64")]
+    int_value: Option<i64>,
+    double_value: Option<f64>,
+    array_value: Option<JsonArrayValue>,
+    kvlist_value: Option<JsonKeyValueList>,
+    #[serde(deserialize_with = "deserialize_opt_base64")]
+    bytes_value: Option<Vec<u8>>,
+}
+
+impl From<JsonAnyValue> for AnyValue {
+    fn from(json_value: JsonAnyValue) -> Self {
+        let value = if let Some(string_value) = json_value.string_value {
+            Some(any_value::Value::StringValue(string_value))
+        } else if let Some(bool_value) = json_value.bool_value {
+            Some(any_value::Value::BoolValue(bool_value))
+        } else if let Some(int_value) = json_value.int_value {
+            Some(any_value::Value::IntValue(int_value))
+        } else if let Some(double_value) = json_value.double_value {
+            Some(any_value::Value::DoubleValue(double_value))
+        } else if let Some(array_value) = json_value.array_value {
+            Some(any_value::Value::ArrayValue(array_value.into()))
+        } else if let Some(kvlist_value) = json_value.kvlist_value {
+            Some(any_value::Value::KvlistValue(kvlist_value.into()))
+        } else {
+            json_value.bytes_value.map(any_value::Value::BytesValue)
+        };
+        AnyValue { value }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonArrayValue {
+    values: Vec<JsonAnyValue>,
+}
+
+impl From<JsonArrayValue> for ArrayValue {
+    fn from(json_array: JsonArrayValue) -> Self {
+        ArrayValue {
+            values: json_array.values.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonKeyValueList {
+    values: Vec<JsonKeyValue>,
+}
+
+impl From<JsonKeyValueList> for KeyValueList {
+    fn from(json_kvlist: JsonKeyValueList) -> Self {
+        KeyValueList {
+            values: convert_attributes(json_kvlist.values),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonKeyValue {
+    key: String,
+    value: Option<JsonAnyValue>,
+}
+
+fn convert_attributes(json_attributes: Vec<JsonKeyValue>) -> Vec<KeyValue> {
+    json_attributes
+        .into_iter()
+        .map(|json_attribute| KeyValue {
+            key: json_attribute.key,
+            value: json_attribute.value.map(Into::into),
+        })
+        .collect()
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonResource {
+    attributes: Vec<JsonKeyValue>,
+    dropped_attributes_count: u32,
+}
+
+impl From<JsonResource> for Resource {
+    fn from(json_resource: JsonResource) -> Self {
+        Resource {
+            attributes: convert_attributes(json_resource.attributes),
+            dropped_attributes_count: json_resource.dropped_attributes_count,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonInstrumentationScope {
+    name: String,
+    version: String,
+    attributes: Vec<JsonKeyValue>,
+    dropped_attributes_count: u32,
+}
+
+impl From<JsonInstrumentationScope> for InstrumentationScope {
+    fn from(json_scope: JsonInstrumentationScope) -> Self {
+        InstrumentationScope {
+            name: json_scope.name,
+            version: json_scope.version,
+            attributes: convert_attributes(json_scope.attributes),
+            dropped_attributes_count: json_scope.dropped_attributes_count,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonExportLogsServiceRequest {
+    resource_logs: Vec<JsonResourceLogs>,
+}
+
+impl From<JsonExportLogsServiceRequest> for ExportLogsServiceRequest {
+    fn from(json_request: JsonExportLogsServiceRequest) -> Self {
+        ExportLogsServiceRequest {
+            resource_logs: json_request
+                .resource_logs
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonResourceLogs {
+    resource: Option<JsonResource>,
+    scope_logs: Vec<JsonScopeLogs>,
+    schema_url: String,
+}
+
+impl From<JsonResourceLogs> for ResourceLogs {
+    fn from(json_resource_logs: JsonResourceLogs) -> Self {
+        ResourceLogs {
+            resource: json_resource_logs.resource.map(Into::into),
+            scope_logs: json_resource_logs
+                .scope_logs
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            schema_url: json_resource_logs.schema_url,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonScopeLogs {
+    scope: Option<JsonInstrumentationScope>,
+    log_records: Vec<JsonLogRecord>,
+    schema_url: String,
+}
+
+impl From<JsonScopeLogs> for ScopeLogs {
+    fn from(json_scope_logs: JsonScopeLogs) -> Self {
+        ScopeLogs {
+            scope: json_scope_logs.scope.map(Into::into),
+            log_records: json_scope_logs
+                .log_records
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            schema_url: json_scope_logs.schema_url,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonLogRecord {
+    #[serde(deserialize_with = "deserialize_int64")]
+    time_unix_nano: u64,
+    #[serde(deserialize_with = "deserialize_int64")]
+    observed_time_unix_nano: u64,
+    severity_number: i32,
+    severity_text: String,
+    body: Option<JsonAnyValue>,
+    attributes: Vec<JsonKeyValue>,
+    dropped_attributes_count: u32,
+    flags: u32,
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    trace_id: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    span_id: Vec<u8>,
+}
+
+impl From<JsonLogRecord> for LogRecord {
+    fn from(json_log_record: JsonLogRecord) -> Self {
+        LogRecord {
+            time_unix_nano: json_log_record.time_unix_nano,
+            observed_time_unix_nano: json_log_record.observed_time_unix_nano,
+            severity_number: json_log_record.severity_number,
+            severity_text: json_log_record.severity_text,
+            body: json_log_record.body.map(Into::into),
+            attributes: convert_attributes(json_log_record.attributes),
+            dropped_attributes_count: json_log_record.dropped_attributes_count,
+            flags: json_log_record.flags,
+            trace_id: json_log_record.trace_id,
+            span_id: json_log_record.span_id,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonExportTraceServiceRequest {
+    resource_spans: Vec<JsonResourceSpans>,
+}
+
+impl From<JsonExportTraceServiceRequest> for ExportTraceServiceRequest {
+    fn from(json_request: JsonExportTraceServiceRequest) -> Self {
+        ExportTraceServiceRequest {
+            resource_spans: json_request
+                .resource_spans
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonResourceSpans {
+    resource: Option<JsonResource>,
+    scope_spans: Vec<JsonScopeSpans>,
+    schema_url: String,
+}
+
+impl From<JsonResourceSpans> for ResourceSpans {
+    fn from(json_resource_spans: JsonResourceSpans) -> Self {
+        ResourceSpans {
+            resource: json_resource_spans.resource.map(Into::into),
+            scope_spans: json_resource_spans
+                .scope_spans
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            schema_url: json_resource_spans.schema_url,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonScopeSpans {
+    scope: Option<JsonInstrumentationScope>,
+    spans: Vec<JsonSpan>,
+    schema_url: String,
+}
+
+impl From<JsonScopeSpans> for ScopeSpans {
+    fn from(json_scope_spans: JsonScopeSpans) -> Self {
+        ScopeSpans {
+            scope: json_scope_spans.scope.map(Into::into),
+            spans: json_scope_spans.spans.into_iter().map(Into::into).collect(),
+            schema_url: json_scope_spans.schema_url,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonSpan {
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    trace_id: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    span_id: Vec<u8>,
+    trace_state: String,
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    parent_span_id: Vec<u8>,
+    name: String,
+    kind: i32,
+    #[serde(deserialize_with = "deserialize_int64")]
+    start_time_unix_nano: u64,
+    #[serde(deserialize_with = "deserialize_int64")]
+    end_time_unix_nano: u64,
+    attributes: Vec<JsonKeyValue>,
+    dropped_attributes_count: u32,
+    events: Vec<JsonEvent>,
+    dropped_events_count: u32,
+    links: Vec<JsonLink>,
+    dropped_links_count: u32,
+    status: Option<JsonStatus>,
+}
+
+impl From<JsonSpan> for Span {
+    fn from(json_span: JsonSpan) -> Self {
+        Span {
+            trace_id: json_span.trace_id,
+            span_id: json_span.span_id,
+            trace_state: json_span.trace_state,
+            parent_span_id: json_span.parent_span_id,
+            name: json_span.name,
+            kind: json_span.kind,
+            start_time_unix_nano: json_span.start_time_unix_nano,
+            end_time_unix_nano: json_span.end_time_unix_nano,
+            attributes: convert_attributes(json_span.attributes),
+            dropped_attributes_count: json_span.dropped_attributes_count,
+            events: json_span.events.into_iter().map(Into::into).collect(),
+            dropped_events_count: json_span.dropped_events_count,
+            links: json_span.links.into_iter().map(Into::into).collect(),
+            dropped_links_count: json_span.dropped_links_count,
+            status: json_span.status.map(Into::into),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonEvent {
+    #[serde(deserialize_with = "deserialize_int64")]
+    time_unix_nano: u64,
+    name: String,
+    attributes: Vec<JsonKeyValue>,
+    dropped_attributes_count: u32,
+}
+
+impl From<JsonEvent> for Event {
+    fn from(json_event: JsonEvent) -> Self {
+        Event {
+            time_unix_nano: json_event.time_unix_nano,
+            name: json_event.name,
+            attributes: convert_attributes(json_event.attributes),
+            dropped_attributes_count: json_event.dropped_attributes_count,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonLink {
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    trace_id: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_hex_id")]
+    span_id: Vec<u8>,
+    trace_state: String,
+    attributes: Vec<JsonKeyValue>,
+    dropped_attributes_count: u32,
+}
+
+impl From<JsonLink> for Link {
+    fn from(json_link: JsonLink) -> Self {
+        Link {
+            trace_id: json_link.trace_id,
+            span_id: json_link.span_id,
+            trace_state: json_link.trace_state,
+            attributes: convert_attributes(json_link.attributes),
+            dropped_attributes_count: json_link.dropped_attributes_count,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonStatus {
+    message: String,
+    code: i32,
+}
+
+impl From<JsonStatus> for Status {
+    fn from(json_status: JsonStatus) -> Self {
+        Status {
+            message: json_status.message,
+            code: json_status.code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export_logs_request() {
+        let body = r#"{
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": [{"key": "service.name", "value": {"stringValue": "my-app"}}]
+                },
+                "scopeLogs": [{
+                    "scope": {"name": "my-scope"},
+                    "logRecords": [{
+                        "timeUnixNano": "1704036033047000000",
+                        "observedTimeUnixNano": 1704036033047000001,
+                        "severityNumber": 17,
+                        "severityText": "ERROR",
+                        "traceId": "5b8efff798038103d269b633813fc60c",
+                        "spanId": "eee19b7ec3c1b174",
+                        "body": {"stringValue": "an error occurred"},
+                        "attributes": [
+                            {"key": "count", "value": {"intValue": "3"}},
+                            {"key": "tags", "value": {"arrayValue": {"values": [
+                                {"boolValue": true}, {"doubleValue": 1.5}
+                            ]}}},
+                            {"key": "payload", "value": {"bytesValue": "aGVsbG8="}}
+                        ],
+                        "unknownField": 0
+                    }]
+                }]
+            }]
+        }"#;
+        let request = parse_export_logs_request(body.as_bytes()).unwrap();
+        assert_eq!(request.resource_logs.len(), 1);
+
+        let resource_logs = &request.resource_logs[0];
+        let resource = resource_logs.resource.as_ref().unwrap();
+        assert_eq!(resource.attributes[0].key, "service.name");
+        assert_eq!(
+            resource.attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("my-app".to_string()))
+        );
+        let scope_logs = &resource_logs.scope_logs[0];
+        assert_eq!(scope_logs.scope.as_ref().unwrap().name, "my-scope");
+
+        let log_record = &scope_logs.log_records[0];
+        assert_eq!(log_record.time_unix_nano, 1704036033047000000);
+        assert_eq!(log_record.observed_time_unix_nano, 1704036033047000001);
+        assert_eq!(log_record.severity_number, 17);
+        assert_eq!(log_record.severity_text, "ERROR");
+        assert_eq!(
+            log_record.trace_id,
+            hex::decode("5b8efff798038103d269b633813fc60c").unwrap()
+        );
+        assert_eq!(log_record.span_id, hex::decode("eee19b7ec3c1b174").unwrap());
+        assert_eq!(
+            log_record.body.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue(
+                "an error occurred".to_string()
+            ))
+        );
+        assert_eq!(
+            log_record.attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::IntValue(3))
+        );
+        let Some(any_value::Value::ArrayValue(array_value)) =
+            &log_record.attributes[1].value.as_ref().unwrap().value
+        else {
+            panic!("expected array value");
+        };
+        assert_eq!(
+            array_value.values[0].value,
+            Some(any_value::Value::BoolValue(true))
+        );
+        assert_eq!(
+            array_value.values[1].value,
+            Some(any_value::Value::DoubleValue(1.5))
+        );
+        assert_eq!(
+            log_record.attributes[2].value.as_ref().unwrap().value,
+            Some(any_value::Value::BytesValue(b"hello".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_parse_export_trace_request() {
+        let body = r#"{
+            "resourceSpans": [{
+                "scopeSpans": [{
+                    "spans": [{
+                        "traceId": "5B8EFFF798038103D269B633813FC60C",
+                        "spanId": "eee19b7ec3c1b174",
+                        "parentSpanId": "",
+                        "name": "my-span",
+                        "kind": 2,
+                        "startTimeUnixNano": "1544712660000000000",
+                        "endTimeUnixNano": "1544712661000000000",
+                        "events": [{"timeUnixNano": "1544712660500000000", "name": "my-event"}],
+                        "links": [{
+                            "traceId": "5b8efff798038103d269b633813fc60c",
+                            "spanId": "eee19b7ec3c1b173"
+                        }],
+                        "status": {"code": 2, "message": "failed"}
+                    }]
+                }]
+            }]
+        }"#;
+        let request = parse_export_trace_request(body.as_bytes()).unwrap();
+        let span = &request.resource_spans[0].scope_spans[0].spans[0];
+        assert_eq!(
+            span.trace_id,
+            hex::decode("5b8efff798038103d269b633813fc60c").unwrap()
+        );
+        assert!(span.parent_span_id.is_empty());
+        assert_eq!(span.name, "my-span");
+        assert_eq!(span.kind, 2);
+        assert_eq!(span.start_time_unix_nano, 1544712660000000000);
+        assert_eq!(span.end_time_unix_nano, 1544712661000000000);
+        assert_eq!(span.events[0].name, "my-event");
+        assert_eq!(span.events[0].time_unix_nano, 1544712660500000000);
+        assert_eq!(
+            span.links[0].span_id,
+            hex::decode("eee19b7ec3c1b173").unwrap()
+        );
+        let status = span.status.as_ref().unwrap();
+        assert_eq!(status.code, 2);
+        assert_eq!(status.message, "failed");
+    }
+
+    #[test]
+    fn test_parse_export_request_invalid() {
+        parse_export_logs_request(b"not json").unwrap_err();
+
+        let body = r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{"traceId": "xyz"}]}]}]}"#;
+        let error = parse_export_logs_request(body.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("invalid hex ID"));
+
+        let body =
+            r#"{"resourceSpans": [{"scopeSpans": [{"spans": [{"startTimeUnixNano": "abc"}]}]}]}"#;
+        parse_export_trace_request(body.as_bytes()).unwrap_err();
+    }
+}
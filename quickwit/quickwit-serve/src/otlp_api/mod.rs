@@ -17,5 +17,6 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod json;
 mod rest_handler;
-pub(crate) use rest_handler::otlp_ingest_api_handlers;
+pub(crate) use rest_handler::{otlp_ingest_api_handlers, otlp_metadata_filter};
@@ -17,9 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::convert::Infallible;
+
 use bytes::Bytes;
 use quickwit_opentelemetry::otlp::{
     OtlpGrpcLogsService, OtlpGrpcTracesService, OTEL_LOGS_INDEX_ID, OTEL_TRACES_INDEX_ID,
+    OTLP_API_KEY_HEADER,
 };
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::logs_service_server::LogsService;
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::{
@@ -32,8 +35,10 @@ use quickwit_proto::opentelemetry::proto::collector::trace::v1::{
 use quickwit_proto::{tonic, ServiceError, ServiceErrorCode};
 use serde::{self, Serialize};
 use tracing::error;
+use warp::http::HeaderMap;
 use warp::{Filter, Rejection};
 
+use super::json::{parse_export_logs_request, parse_export_trace_request};
 use crate::rest_api_response::into_rest_api_response;
 use crate::{require, with_arg, BodyFormat};
 
@@ -52,19 +57,66 @@ pub(crate) fn otlp_ingest_api_handlers(
         .or(otlp_ingest_traces_handler(otlp_traces_service))
 }
 
+/// Encodings of the OTLP/HTTP request bodies.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OtlpEncoding {
+    Protobuf,
+    Json,
+}
+
+/// Extracts the encoding of the request body from its `content-type` header and rejects the
+/// requests with an unsupported content type.
+fn otlp_encoding_filter() -> impl Filter<Extract = (OtlpEncoding,), Error = Rejection> + Clone {
+    warp::header::<String>("content-type").and_then(|content_type: String| async move {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+
+        if media_type.eq_ignore_ascii_case("application/x-protobuf") {
+            Ok(OtlpEncoding::Protobuf)
+        } else if media_type.eq_ignore_ascii_case("application/json") {
+            Ok(OtlpEncoding::Json)
+        } else {
+            Err(warp::reject())
+        }
+    })
+}
+
+/// Forwards the headers carrying the credentials and the API key of the export to the OTLP
+/// services.
+pub(crate) fn otlp_metadata_filter(
+) -> impl Filter<Extract = (tonic::metadata::MetadataMap,), Error = Infallible> + Clone {
+    warp::header::headers_cloned().map(|headers: HeaderMap| {
+        let mut metadata = tonic::metadata::MetadataMap::new();
+        for header_name in [OTLP_API_KEY_HEADER, "authorization"] {
+            if let Some(header_value) = headers
+                .get(header_name)
+                .and_then(|header_value| header_value.to_str().ok())
+                .and_then(|header_value| header_value.parse().ok())
+            {
+                metadata.insert(header_name, header_value);
+            }
+        }
+        metadata
+    })
+}
+
 pub(crate) fn otlp_default_logs_handler(
     otlp_logs_service: Option<OtlpGrpcLogsService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_logs_service)
         .and(warp::path!("otlp" / "v1" / "logs"))
-        .and(warp::header::exact_ignore_case(
-            "content-type",
-            "application/x-protobuf",
-        ))
+        .and(otlp_encoding_filter())
         .and(warp::post())
+        .and(otlp_metadata_filter())
         .and(warp::body::bytes())
-        .then(|otlp_logs_service, body| async move {
-            otlp_ingest_logs(otlp_logs_service, OTEL_LOGS_INDEX_ID.to_string(), body).await
+        .then(|otlp_logs_service, encoding, metadata, body| async move {
+            otlp_ingest_logs(
+                otlp_logs_service,
+                OTEL_LOGS_INDEX_ID.to_string(),
+                encoding,
+                metadata,
+                body,
+            )
+            .await
         })
         .and(with_arg(BodyFormat::default()))
         .map(into_rest_api_response)
@@ -75,11 +127,9 @@ pub(crate) fn otlp_logs_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_log_service)
         .and(warp::path!(String / "otlp" / "v1" / "logs"))
-        .and(warp::header::exact_ignore_case(
-            "content-type",
-            "application/x-protobuf",
-        ))
+        .and(otlp_encoding_filter())
         .and(warp::post())
+        .and(otlp_metadata_filter())
         .and(warp::body::bytes())
         .then(otlp_ingest_logs)
         .and(with_arg(BodyFormat::default()))
@@ -91,14 +141,19 @@ pub(crate) fn otlp_default_traces_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_traces_service)
         .and(warp::path!("otlp" / "v1" / "traces"))
-        .and(warp::header::exact_ignore_case(
-            "content-type",
-            "application/x-protobuf",
-        ))
+        .and(otlp_encoding_filter())
         .and(warp::post())
+        .and(otlp_metadata_filter())
         .and(warp::body::bytes())
-        .then(|otlp_traces_service, body| async move {
-            otlp_ingest_traces(otlp_traces_service, OTEL_TRACES_INDEX_ID.to_string(), body).await
+        .then(|otlp_traces_service, encoding, metadata, body| async move {
+            otlp_ingest_traces(
+                otlp_traces_service,
+                OTEL_TRACES_INDEX_ID.to_string(),
+                encoding,
+                metadata,
+                body,
+            )
+            .await
         })
         .and(with_arg(BodyFormat::default()))
         .map(into_rest_api_response)
@@ -109,11 +164,9 @@ pub(crate) fn otlp_ingest_traces_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_traces_service)
         .and(warp::path!(String / "otlp" / "v1" / "traces"))
-        .and(warp::header::exact_ignore_case(
-            "content-type",
-            "application/x-protobuf",
-        ))
+        .and(otlp_encoding_filter())
         .and(warp::post())
+        .and(otlp_metadata_filter())
         .and(warp::body::bytes())
         .then(otlp_ingest_traces)
         .and(with_arg(BodyFormat::default()))
@@ -126,6 +179,12 @@ pub enum OtlpApiError {
     InvalidPayload(String),
     #[error("error when ingesting payload: {0}")]
     Ingest(String),
+    #[error("too many requests: {0}")]
+    TooManyRequests(String),
+    #[error("unauthenticated: {0}")]
+    Unauthenticated(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
 }
 
 impl ServiceError for OtlpApiError {
@@ -133,6 +192,27 @@ impl ServiceError for OtlpApiError {
         match self {
             OtlpApiError::InvalidPayload(_) => ServiceErrorCode::BadRequest,
             OtlpApiError::Ingest(_) => ServiceErrorCode::Internal,
+            OtlpApiError::TooManyRequests(_) => ServiceErrorCode::TooManyRequests,
+            OtlpApiError::Unauthenticated(_) => ServiceErrorCode::Unauthenticated,
+            OtlpApiError::Forbidden(_) => ServiceErrorCode::Forbidden,
+        }
+    }
+}
+
+impl From<tonic::Status> for OtlpApiError {
+    fn from(status: tonic::Status) -> Self {
+        match status.code() {
+            tonic::Code::ResourceExhausted => {
+                OtlpApiError::TooManyRequests(status.message().to_string())
+            }
+            tonic::Code::Unauthenticated => {
+                OtlpApiError::Unauthenticated(status.message().to_string())
+            }
+            tonic::Code::PermissionDenied => OtlpApiError::Forbidden(status.message().to_string()),
+            tonic::Code::InvalidArgument => {
+                OtlpApiError::InvalidPayload(status.message().to_string())
+            }
+            _ => OtlpApiError::Ingest(status.to_string()),
         }
     }
 }
@@ -140,38 +220,51 @@ impl ServiceError for OtlpApiError {
 async fn otlp_ingest_logs(
     otlp_logs_service: OtlpGrpcLogsService,
     _index_id: String, // <- TODO: use index ID when gRPC service supports it.
+    encoding: OtlpEncoding,
+    metadata: tonic::metadata::MetadataMap,
     body: Bytes,
 ) -> Result<ExportLogsServiceResponse, OtlpApiError> {
     // TODO: use index ID.
-    let export_logs_request: ExportLogsServiceRequest = prost::Message::decode(&body[..])
-        .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?;
-    let result = otlp_logs_service
-        .export(tonic::Request::new(export_logs_request))
-        .await
-        .map_err(|err| OtlpApiError::Ingest(err.to_string()))?;
+    let export_logs_request: ExportLogsServiceRequest = match encoding {
+        OtlpEncoding::Protobuf => prost::Message::decode(&body[..])
+            .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?,
+        OtlpEncoding::Json => parse_export_logs_request(&body)
+            .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?,
+    };
+    let mut request = tonic::Request::new(export_logs_request);
+    *request.metadata_mut() = metadata;
+    let result = otlp_logs_service.export(request).await?;
     Ok(result.into_inner())
 }
 
 async fn otlp_ingest_traces(
     otlp_traces_service: OtlpGrpcTracesService,
     _index_id: String, // <- TODO: use index ID when gRPC service supports it.
+    encoding: OtlpEncoding,
+    metadata: tonic::metadata::MetadataMap,
     body: Bytes,
 ) -> Result<ExportTraceServiceResponse, OtlpApiError> {
-    let export_traces_request: ExportTraceServiceRequest = prost::Message::decode(&body[..])
-        .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?;
-    let response = otlp_traces_service
-        .export(tonic::Request::new(export_traces_request))
-        .await
-        .map_err(|err| OtlpApiError::Ingest(err.to_string()))?;
+    let export_traces_request: ExportTraceServiceRequest = match encoding {
+        OtlpEncoding::Protobuf => prost::Message::decode(&body[..])
+            .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?,
+        OtlpEncoding::Json => parse_export_trace_request(&body)
+            .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?,
+    };
+    let mut request = tonic::Request::new(export_traces_request);
+    *request.metadata_mut() = metadata;
+    let response = otlp_traces_service.export(request).await?;
     Ok(response.into_inner())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroU32;
+
     use prost::Message;
+    use quickwit_config::{OtelApiKeyConfig, OtelAuthConfig, OtelRole, OtlpApiKeyQuotaConfig};
     use quickwit_ingest::{CommitType, IngestResponse, IngestServiceClient, MockIngestService};
     use quickwit_opentelemetry::otlp::{
-        make_resource_spans_for_test, OtlpGrpcLogsService, OtlpGrpcTracesService,
+        make_resource_spans_for_test, OtelAuthenticator, OtlpGrpcLogsService, OtlpGrpcTracesService,
     };
     use quickwit_proto::opentelemetry::proto::collector::logs::v1::{
         ExportLogsServiceRequest, ExportLogsServiceResponse,
@@ -179,6 +272,8 @@ mod tests {
     use quickwit_proto::opentelemetry::proto::collector::trace::v1::{
         ExportTraceServiceRequest, ExportTraceServiceResponse,
     };
+    use quickwit_proto::opentelemetry::proto::common::v1::any_value::Value as OtlpAnyValueValue;
+    use quickwit_proto::opentelemetry::proto::common::v1::{AnyValue, KeyValue};
     use quickwit_proto::opentelemetry::proto::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
     use quickwit_proto::opentelemetry::proto::resource::v1::Resource;
     use warp::Filter;
@@ -277,6 +372,181 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_otlp_ingest_logs_handler_routes_tenants() {
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service
+            .expect_ingest()
+            .withf(|request| request.doc_batches[0].index_id == "otel-logs-v0_7")
+            .times(1)
+            .returning(|_| {
+                Ok(IngestResponse {
+                    num_docs_for_processing: 1,
+                })
+            });
+        mock_ingest_service
+            .expect_ingest()
+            .withf(|request| {
+                request.doc_batches[0].index_id == "otel-logs-v0_7-acme"
+                    && request.doc_batches[0].doc_lengths.len() == 2
+            })
+            .times(1)
+            .returning(|_| {
+                Ok(IngestResponse {
+                    num_docs_for_processing: 2,
+                })
+            });
+        let ingest_service_client = IngestServiceClient::from_mock(mock_ingest_service);
+        let logs_service = OtlpGrpcLogsService::new(ingest_service_client)
+            .with_tenant_resource_attribute(Some("tenant".to_string()));
+
+        let make_resource_logs = |tenant_opt: Option<&str>, num_log_records: usize| {
+            let attributes = tenant_opt
+                .map(|tenant| KeyValue {
+                    key: "tenant".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(OtlpAnyValueValue::StringValue(tenant.to_string())),
+                    }),
+                })
+                .into_iter()
+                .collect();
+            let log_records = (0..num_log_records)
+                .map(|i| LogRecord {
+                    body: None,
+                    attributes: Vec::new(),
+                    dropped_attributes_count: 0,
+                    time_unix_nano: 1704036033047000000 + i as u64,
+                    severity_number: 0,
+                    severity_text: "ERROR".to_string(),
+                    span_id: Vec::new(),
+                    trace_id: Vec::new(),
+                    flags: 0,
+                    observed_time_unix_nano: 0,
+                })
+                .collect();
+            ResourceLogs {
+                resource: Some(Resource {
+                    attributes,
+                    dropped_attributes_count: 0,
+                }),
+                scope_logs: vec![ScopeLogs {
+                    log_records,
+                    scope: None,
+                    schema_url: "".to_string(),
+                }],
+                schema_url: "".to_string(),
+            }
+        };
+        let export_logs_request = ExportLogsServiceRequest {
+            resource_logs: vec![
+                make_resource_logs(Some("acme"), 1),
+                make_resource_logs(None, 1),
+                make_resource_logs(Some("acme"), 1),
+            ],
+        };
+        let body = export_logs_request.encode_to_vec();
+        let otlp_logs_api_handler =
+            otlp_ingest_api_handlers(Some(logs_service), None).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/otlp/v1/logs")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(body)
+            .reply(&otlp_logs_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let actual_response: ExportLogsServiceResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            actual_response
+                .partial_success
+                .unwrap()
+                .rejected_log_records,
+            0
+        );
+
+        // Tenants containing a `-` would be ambiguous.
+        let export_logs_request = ExportLogsServiceRequest {
+            resource_logs: vec![make_resource_logs(Some("acme-bar"), 1)],
+        };
+        let body = export_logs_request.encode_to_vec();
+        let resp = warp::test::request()
+            .path("/otlp/v1/logs")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(body)
+            .reply(&otlp_logs_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_json_logs_handler() {
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service
+            .expect_ingest()
+            .withf(|request| {
+                request.doc_batches.len() == 1 && request.doc_batches[0].doc_lengths.len() == 2
+            })
+            .returning(|_| {
+                Ok(IngestResponse {
+                    num_docs_for_processing: 2,
+                })
+            });
+        let ingest_service_client = IngestServiceClient::from_mock(mock_ingest_service);
+        let logs_service = OtlpGrpcLogsService::new(ingest_service_client.clone());
+        let traces_service =
+            OtlpGrpcTracesService::new(ingest_service_client, Some(CommitType::Force));
+        let otlp_api_handler =
+            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service)).recover(recover_fn);
+
+        let body = r#"{
+            "resourceLogs": [{
+                "scopeLogs": [{
+                    "logRecords": [
+                        {"timeUnixNano": "1704036033047000000", "body": {"stringValue": "foo"}},
+                        {"timeUnixNano": "1704036033047000001", "body": {"stringValue": "bar"}}
+                    ]
+                }]
+            }]
+        }"#;
+        let resp = warp::test::request()
+            .path("/otlp/v1/logs")
+            .method("POST")
+            .header("content-type", "application/json; charset=utf-8")
+            .body(body)
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let actual_response: ExportLogsServiceResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            actual_response
+                .partial_success
+                .unwrap()
+                .rejected_log_records,
+            0
+        );
+
+        let resp = warp::test::request()
+            .path("/otlp/v1/logs")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(r#"{"resourceLogs": {}}"#)
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/otlp/v1/logs")
+            .method("POST")
+            .header("content-type", "text/plain")
+            .body(body)
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
     #[tokio::test]
     async fn test_otlp_ingest_traces_handler() {
         let mut mock_ingest_service = MockIngestService::new();
@@ -333,4 +603,121 @@ mod tests {
             assert_eq!(actual_response.partial_success.unwrap().rejected_spans, 0);
         }
     }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_traces_handler_api_key_quota() {
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service.expect_ingest().times(1).returning(|_| {
+            Ok(IngestResponse {
+                num_docs_for_processing: 5,
+            })
+        });
+        let ingest_service_client = IngestServiceClient::from_mock(mock_ingest_service);
+        let quota_configs = [OtlpApiKeyQuotaConfig {
+            api_key: "my-key".to_string(),
+            tenant_id: "my-tenant".to_string(),
+            max_spans_per_sec: NonZeroU32::new(5),
+            max_log_records_per_sec: None,
+        }];
+        let traces_service = OtlpGrpcTracesService::new(ingest_service_client, None)
+            .with_api_key_quotas(&quota_configs);
+        let export_trace_request = ExportTraceServiceRequest {
+            resource_spans: make_resource_spans_for_test(),
+        };
+        let body = export_trace_request.encode_to_vec();
+        let otlp_traces_api_handler =
+            otlp_ingest_api_handlers(None, Some(traces_service)).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .header("authorization", "Bearer my-key")
+            .body(body.clone())
+            .reply(&otlp_traces_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .header("x-api-key", "my-key")
+            .body(body)
+            .reply(&otlp_traces_api_handler)
+            .await;
+        assert_eq!(resp.status(), 429);
+        assert!(resp.headers().contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_traces_handler_authenticator() {
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service
+            .expect_ingest()
+            .withf(|request| {
+                request.doc_batches.len() == 1
+                    && request.doc_batches[0].index_id == "otel-traces-v0_7-acme"
+            })
+            .times(1)
+            .returning(|_| {
+                Ok(IngestResponse {
+                    num_docs_for_processing: 5,
+                })
+            });
+        let ingest_service_client = IngestServiceClient::from_mock(mock_ingest_service);
+        let auth_config = OtelAuthConfig {
+            api_keys: vec![
+                OtelApiKeyConfig {
+                    name: "collector-acme".to_string(),
+                    api_key: "acme-secret".to_string(),
+                    roles: vec![OtelRole::Ingest],
+                    tenant_id: Some("acme".to_string()),
+                },
+                OtelApiKeyConfig {
+                    name: "grafana".to_string(),
+                    api_key: "grafana-secret".to_string(),
+                    roles: vec![OtelRole::Read],
+                    tenant_id: None,
+                },
+            ],
+            jwt: None,
+        };
+        let traces_service = OtlpGrpcTracesService::new(ingest_service_client, None)
+            .with_authenticator(OtelAuthenticator::new(&auth_config));
+        let export_trace_request = ExportTraceServiceRequest {
+            resource_spans: make_resource_spans_for_test(),
+        };
+        let body = export_trace_request.encode_to_vec();
+        let otlp_traces_api_handler =
+            otlp_ingest_api_handlers(None, Some(traces_service)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(body.clone())
+            .reply(&otlp_traces_api_handler)
+            .await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .header("x-api-key", "grafana-secret")
+            .body(body.clone())
+            .reply(&otlp_traces_api_handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .header("authorization", "Bearer acme-secret")
+            .body(body)
+            .reply(&otlp_traces_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
 }
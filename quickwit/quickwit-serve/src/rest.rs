@@ -31,7 +31,11 @@ use tower_http::cors::CorsLayer;
 use tracing::{error, info};
 use warp::{redirect, Filter, Rejection, Reply};
 
-use crate::cluster_api::cluster_handler;
+use crate::cluster_api::{
+    cluster_handler, cluster_settings_handlers, cluster_upgrade_status_handler,
+    node_maintenance_handlers,
+};
+use crate::control_plane_api::control_plane_api_handlers;
 use crate::decompression::{CorruptedData, UnsupportedEncoding};
 use crate::delete_task_api::delete_task_api_handlers;
 use crate::developer_api::developer_api_routes;
@@ -39,13 +43,17 @@ use crate::elasticsearch_api::elastic_api_handlers;
 use crate::health_check_api::health_check_handlers;
 use crate::index_api::index_management_handlers;
 use crate::indexing_api::indexing_get_handler;
-use crate::ingest_api::ingest_api_handlers;
+use crate::ingest_api::{ingest_api_handlers, InvalidDocs};
 use crate::jaeger_api::jaeger_api_handlers;
+use crate::lookup_table_api::lookup_table_api_handlers;
 use crate::metrics_api::metrics_handler;
 use crate::node_info_handler::node_info_handler;
 use crate::otlp_api::otlp_ingest_api_handlers;
 use crate::rest_api_response::{RestApiError, RestApiResponse};
-use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
+use crate::search_api::{
+    open_point_in_time_handler, search_get_handler, search_post_handler, search_stream_handler,
+};
+use crate::state_api::state_api_handlers;
 use crate::template_api::index_template_api_handlers;
 use crate::ui_handler::ui_handler;
 use crate::{BodyFormat, BuildInfo, QuickwitServices, RuntimeInfo};
@@ -96,6 +104,7 @@ pub(crate) async fn start_rest_server(
     let developer_routes = developer_api_routes(
         quickwit_services.cluster.clone(),
         quickwit_services.env_filter_reload_fn.clone(),
+        quickwit_services.search_service.clone(),
     );
 
     // `/api/v1/*` routes.
@@ -169,6 +178,15 @@ fn api_v1_routes(
     let api_v1_root_url = warp::path!("api" / "v1" / ..);
     api_v1_root_url.and(
         cluster_handler(quickwit_services.cluster.clone())
+            .or(cluster_settings_handlers(
+                quickwit_services.cluster.clone(),
+                quickwit_services.metastore_client.clone(),
+                quickwit_services.cluster_settings_applier.clone(),
+            ))
+            .or(node_maintenance_handlers(quickwit_services.cluster.clone()))
+            .or(cluster_upgrade_status_handler(
+                quickwit_services.cluster.clone(),
+            ))
             .or(node_info_handler(
                 BuildInfo::get(),
                 RuntimeInfo::get(),
@@ -184,6 +202,9 @@ fn api_v1_routes(
             .or(search_stream_handler(
                 quickwit_services.search_service.clone(),
             ))
+            .or(open_point_in_time_handler(
+                quickwit_services.search_service.clone(),
+            ))
             .or(ingest_api_handlers(
                 quickwit_services.ingest_router_service.clone(),
                 quickwit_services.ingest_service.clone(),
@@ -196,9 +217,11 @@ fn api_v1_routes(
             .or(index_management_handlers(
                 quickwit_services.index_manager.clone(),
                 quickwit_services.node_config.clone(),
+                quickwit_services.query_access_stats_registry.clone(),
             ))
             .or(delete_task_api_handlers(
                 quickwit_services.metastore_client.clone(),
+                quickwit_services.search_service.clone(),
             ))
             .or(jaeger_api_handlers(
                 quickwit_services.jaeger_service_opt.clone(),
@@ -213,6 +236,16 @@ fn api_v1_routes(
             ))
             .or(index_template_api_handlers(
                 quickwit_services.metastore_client.clone(),
+            ))
+            .or(lookup_table_api_handlers(
+                quickwit_services.lookup_table_store.clone(),
+            ))
+            .or(state_api_handlers(
+                quickwit_services.index_manager.clone(),
+                quickwit_services.node_config.clone(),
+            ))
+            .or(control_plane_api_handlers(
+                quickwit_services.control_plane_client.clone(),
             )),
     )
 }
@@ -287,6 +320,11 @@ fn get_status_with_error(rejection: Rejection) -> RestApiError {
             status_code: StatusCode::BAD_REQUEST,
             message: error.to_string(),
         }
+    } else if let Some(error) = rejection.find::<InvalidDocs>() {
+        RestApiError {
+            status_code: StatusCode::BAD_REQUEST,
+            message: error.to_string(),
+        }
     } else if let Some(error) = rejection.find::<warp::reject::InvalidQuery>() {
         RestApiError {
             status_code: StatusCode::BAD_REQUEST,
@@ -364,15 +402,17 @@ mod tests {
     use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
     use quickwit_config::NodeConfig;
     use quickwit_index_management::IndexService;
+    use quickwit_indexing::models::QueryAccessStatsRegistry;
     use quickwit_ingest::{IngestApiService, IngestServiceClient};
     use quickwit_proto::control_plane::ControlPlaneServiceClient;
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
     use quickwit_proto::metastore::MetastoreServiceClient;
-    use quickwit_search::MockSearchService;
-    use quickwit_storage::StorageResolver;
+    use quickwit_search::{LookupTableStore, MockSearchService};
+    use quickwit_storage::{RamStorage, StorageResolver};
     use tower::Service;
 
     use super::*;
+    use crate::cluster_api::ClusterSettingsApplier;
 
     pub(crate) fn ingest_service_client() -> IngestServiceClient {
         let universe = quickwit_actors::Universe::new();
@@ -614,6 +654,8 @@ mod tests {
         let quickwit_services = QuickwitServices {
             _report_splits_subscription_handle_opt: None,
             _local_shards_update_listener_handle_opt: None,
+            _cluster_settings_listener_handle_opt: None,
+            _query_access_stats_listener_handle_opt: None,
             cluster,
             control_plane_server_opt: None,
             control_plane_client,
@@ -629,8 +671,11 @@ mod tests {
             metastore_server_opt: None,
             node_config: Arc::new(node_config.clone()),
             search_service: Arc::new(MockSearchService::new()),
+            lookup_table_store: Arc::new(LookupTableStore::new(Arc::new(RamStorage::default()))),
             jaeger_service_opt: None,
             env_filter_reload_fn: crate::do_nothing_env_filter_reload_fn(),
+            cluster_settings_applier: ClusterSettingsApplier::default(),
+            query_access_stats_registry: QueryAccessStatsRegistry::default(),
         };
 
         let handler = api_v1_routes(Arc::new(quickwit_services))
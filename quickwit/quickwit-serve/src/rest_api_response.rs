@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use hyper::header::CONTENT_TYPE;
+use hyper::header::{CONTENT_TYPE, RETRY_AFTER};
 use hyper::http::HeaderValue;
 use hyper::{Body, Response, StatusCode};
 use quickwit_proto::ServiceError;
@@ -28,6 +28,10 @@ use crate::format::BodyFormat;
 
 const JSON_SERIALIZATION_ERROR: &str = "JSON serialization failed.";
 
+/// Number of seconds clients are asked to wait before retrying a request rejected with a
+/// `429 Too Many Requests` status code.
+const RETRY_AFTER_SECS: &str = "1";
+
 #[derive(Serialize)]
 pub(crate) struct RestApiError {
     // For now, we want to keep [`RestApiError`] as simple as possible
@@ -81,6 +85,11 @@ impl Reply for RestApiResponse {
                 response
                     .headers_mut()
                     .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                if self.status_code == StatusCode::TOO_MANY_REQUESTS {
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from_static(RETRY_AFTER_SECS));
+                }
                 *response.status_mut() = self.status_code;
                 response
             }
@@ -92,3 +101,26 @@ impl Reply for RestApiResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::ingest::IngestV2Error;
+
+    use super::*;
+
+    #[test]
+    fn test_rest_api_response_retry_after() {
+        let result: Result<(), IngestV2Error> = Err(IngestV2Error::TooManyRequests);
+        let response = into_rest_api_response(result, BodyFormat::default()).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response.headers().get(RETRY_AFTER).unwrap(),
+            RETRY_AFTER_SECS
+        );
+
+        let result: Result<(), IngestV2Error> = Err(IngestV2Error::Internal("error".to_string()));
+        let response = into_rest_api_response(result, BodyFormat::default()).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response.headers().get(RETRY_AFTER).is_none());
+    }
+}
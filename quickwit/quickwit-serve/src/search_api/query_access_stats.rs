@@ -0,0 +1,109 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use quickwit_cluster::Cluster;
+use quickwit_common::spawn_named_task;
+use quickwit_proto::search::{QueryAccessStats, QUERY_ACCESS_STATS_KEY_PREFIX};
+use quickwit_proto::types::IndexUid;
+use quickwit_search::SearcherContext;
+
+const QUERY_ACCESS_STATS_PUBLISH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Periodically advertises to the cluster the query access statistics of the indexes searched by
+/// this node. The indexers adapt the split size of the indexes to these statistics.
+pub(crate) fn spawn_query_access_stats_publisher(
+    cluster: Cluster,
+    searcher_context: Arc<SearcherContext>,
+) {
+    let mut published_stats: HashMap<IndexUid, QueryAccessStats> = HashMap::new();
+
+    spawn_named_task(
+        async move {
+            let mut interval = tokio::time::interval(QUERY_ACCESS_STATS_PUBLISH_INTERVAL);
+
+            loop {
+                interval.tick().await;
+                publish_query_access_stats(&cluster, &searcher_context, &mut published_stats).await;
+            }
+        },
+        "query_access_stats_publisher",
+    );
+}
+
+/// Publishes the statistics that changed since the last publication.
+async fn publish_query_access_stats(
+    cluster: &Cluster,
+    searcher_context: &SearcherContext,
+    published_stats: &mut HashMap<IndexUid, QueryAccessStats>,
+) {
+    for (index_uid, stats) in searcher_context.query_access_tracker.snapshot() {
+        if published_stats.get(&index_uid) == Some(&stats) {
+            continue;
+        }
+        let key = format!("{QUERY_ACCESS_STATS_KEY_PREFIX}{index_uid}");
+        let value =
+            serde_json::to_string(&stats).expect("query access stats should be JSON serializable");
+        cluster
+            .set_self_key_value_delete_after_ttl(key, value)
+            .await;
+        published_stats.insert(index_uid, stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_config::SearcherConfig;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_query_access_stats() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let searcher_context = SearcherContext::new(SearcherConfig::default(), None);
+        let mut published_stats = HashMap::new();
+
+        publish_query_access_stats(&cluster, &searcher_context, &mut published_stats).await;
+        assert!(published_stats.is_empty());
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        searcher_context.query_access_tracker.record_search(
+            &[index_uid.clone()],
+            1_000,
+            10,
+            Duration::from_millis(12),
+            false,
+        );
+        publish_query_access_stats(&cluster, &searcher_context, &mut published_stats).await;
+
+        let key = format!("{QUERY_ACCESS_STATS_KEY_PREFIX}{index_uid}");
+        let value = cluster.get_self_key_value(&key).await.unwrap();
+        let stats: QueryAccessStats = serde_json::from_str(&value).unwrap();
+        assert_eq!(stats.num_queries, 1);
+        assert_eq!(stats.selectivity, 0.01);
+        assert_eq!(published_stats[&index_uid], stats);
+    }
+}
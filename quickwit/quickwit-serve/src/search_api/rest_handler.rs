@@ -24,8 +24,13 @@ use futures::stream::StreamExt;
 use hyper::header::HeaderValue;
 use hyper::HeaderMap;
 use percent_encoding::percent_decode_str;
+use quickwit_common::is_false;
 use quickwit_config::validate_index_id_pattern;
-use quickwit_proto::search::{CountHits, OutputFormat, SortField, SortOrder};
+use quickwit_proto::search::{
+    CountHits, HighlightRequest, IndexSearchFailure, LookupEnrichment, OpenPointInTimeRequest,
+    OpenPointInTimeResponse, OutputFormat, SearchPriority, SearchProfile, SortField, SortOrder,
+    SplitSearchProfile,
+};
 use quickwit_proto::ServiceError;
 use quickwit_query::query_ast::query_ast_from_user_text;
 use quickwit_search::{SearchError, SearchResponseRest, SearchService};
@@ -42,15 +47,25 @@ use crate::{with_arg, BodyFormat};
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(search_get_handler, search_post_handler, search_stream_handler,),
+    paths(
+        search_get_handler,
+        search_post_handler,
+        search_stream_handler,
+        open_point_in_time_handler,
+    ),
     components(schemas(
         BodyFormat,
+        IndexSearchFailure,
+        OpenPointInTimeResponse,
         OutputFormat,
+        SearchPriority,
+        SearchProfile,
         SearchRequestQueryString,
         SearchResponseRest,
         SortBy,
         SortField,
         SortOrder,
+        SplitSearchProfile,
     ),)
 )]
 pub struct SearchApi;
@@ -231,6 +246,52 @@ pub struct SearchRequestQueryString {
     #[serde(with = "count_hits_from_bool")]
     #[serde(default = "count_hits_from_bool::default")]
     pub count_all: CountHits,
+    /// If set, the splits under legal hold that have been marked for deletion are searched as
+    /// well. The request must carry the token of an allowed caller in the
+    /// `x-quickwit-legal-hold-token` header.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub include_held_splits: bool,
+    /// If set, the search caches are not read and the search is run from scratch.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub bypass_cache: bool,
+    /// If set, a response computed at most `max_staleness_secs` seconds ago for the same request
+    /// may be returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_staleness_secs: Option<u32>,
+    /// Tenant issuing the request. The searchers share their storage read bandwidth fairly between
+    /// tenants.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// Priority of the storage reads of the request: `interactive` (default) or `batch`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<SearchPriority>,
+    /// If set, the response includes a breakdown of the time spent searching each split.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub profile: bool,
+    /// If set, the search runs against the splits captured by this point in time.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_id: Option<String>,
+    /// Lookup tables to enrich the hits and the terms aggregation buckets with, formatted as
+    /// `table:field` or `table:field:target_field`.
+    #[param(value_type = Vec<String>)]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub lookup: Option<Vec<String>>,
+    /// Stored text fields whose matched terms are highlighted in the hits, along with the tags
+    /// wrapping the matched terms and the maximum number of characters of the snippets.
+    #[param(value_type = Object)]
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<HighlightRequest>,
 }
 
 mod count_hits_from_bool {
@@ -261,6 +322,31 @@ mod count_hits_from_bool {
     }
 }
 
+fn parse_lookup_enrichment(lookup: &str) -> Result<LookupEnrichment, SearchError> {
+    let mut parts = lookup.split(':');
+    let (Some(table), Some(field)) = (parts.next(), parts.next()) else {
+        return Err(SearchError::InvalidArgument(format!(
+            "invalid lookup `{lookup}`: expected `table:field` or `table:field:target_field`"
+        )));
+    };
+    let target_field = parts.next().map(ToString::to_string);
+
+    if table.is_empty()
+        || field.is_empty()
+        || target_field.as_deref() == Some("")
+        || parts.next().is_some()
+    {
+        return Err(SearchError::InvalidArgument(format!(
+            "invalid lookup `{lookup}`: expected `table:field` or `table:field:target_field`"
+        )));
+    }
+    Ok(LookupEnrichment {
+        table: table.to_string(),
+        field: field.to_string(),
+        target_field,
+    })
+}
+
 pub fn search_request_from_api_request(
     index_id_patterns: Vec<String>,
     search_request: SearchRequestQueryString,
@@ -270,6 +356,12 @@ pub fn search_request_from_api_request(
     // the user of the docmapper default fields (which we do not have at this point).
     let query_ast = query_ast_from_user_text(&search_request.query, search_request.search_fields);
     let query_ast_json = serde_json::to_string(&query_ast)?;
+    let lookups = search_request
+        .lookup
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| parse_lookup_enrichment(lookup))
+        .collect::<Result<Vec<_>, _>>()?;
     let search_request = quickwit_proto::search::SearchRequest {
         index_id_patterns,
         query_ast: query_ast_json,
@@ -285,6 +377,18 @@ pub fn search_request_from_api_request(
         scroll_ttl_secs: None,
         search_after: None,
         count_hits: search_request.count_all.into(),
+        include_held_splits: search_request.include_held_splits,
+        legal_hold_token: None,
+        bypass_cache: search_request.bypass_cache,
+        max_staleness_secs: search_request.max_staleness_secs,
+        tenant_id: search_request.tenant_id,
+        priority: search_request
+            .priority
+            .unwrap_or(SearchPriority::Interactive) as i32,
+        profile: search_request.profile,
+        pit_id: search_request.pit_id,
+        lookups,
+        highlight: search_request.highlight,
     };
     Ok(search_request)
 }
@@ -292,9 +396,11 @@ pub fn search_request_from_api_request(
 async fn search_endpoint(
     index_id_patterns: Vec<String>,
     search_request: SearchRequestQueryString,
+    legal_hold_token_opt: Option<String>,
     search_service: &dyn SearchService,
 ) -> Result<SearchResponseRest, SearchError> {
-    let search_request = search_request_from_api_request(index_id_patterns, search_request)?;
+    let mut search_request = search_request_from_api_request(index_id_patterns, search_request)?;
+    search_request.legal_hold_token = legal_hold_token_opt;
     let search_response = search_service.root_search(search_request).await?;
     let search_response_rest = SearchResponseRest::try_from(search_response)?;
     Ok(search_response_rest)
@@ -317,14 +423,24 @@ fn search_post_filter(
         .and(warp::body::json())
 }
 
+/// Header carrying the token of a caller allowed to search the splits under legal hold.
+const LEGAL_HOLD_TOKEN_HEADER: &str = "x-quickwit-legal-hold-token";
+
 async fn search(
     index_id_patterns: Vec<String>,
     search_request: SearchRequestQueryString,
+    legal_hold_token_opt: Option<String>,
     search_service: Arc<dyn SearchService>,
 ) -> impl warp::Reply {
     info!(request =? search_request, "search");
     let body_format = search_request.format;
-    let result = search_endpoint(index_id_patterns, search_request, &*search_service).await;
+    let result = search_endpoint(
+        index_id_patterns,
+        search_request,
+        legal_hold_token_opt,
+        &*search_service,
+    )
+    .await;
     into_rest_api_response(result, body_format)
 }
 
@@ -347,6 +463,7 @@ pub fn search_get_handler(
     search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     search_get_filter()
+        .and(warp::header::optional::<String>(LEGAL_HOLD_TOKEN_HEADER))
         .and(with_arg(search_service))
         .then(search)
 }
@@ -372,10 +489,66 @@ pub fn search_post_handler(
     search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     search_post_filter()
+        .and(warp::header::optional::<String>(LEGAL_HOLD_TOKEN_HEADER))
         .and(with_arg(search_service))
         .then(search)
 }
 
+/// This struct represents the query string of the open point in time REST API.
+#[derive(Debug, Eq, PartialEq, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+struct OpenPointInTimeQueryString {
+    /// Duration in seconds after which the point in time expires.
+    pub keep_alive_secs: u32,
+}
+
+fn open_point_in_time_filter(
+) -> impl Filter<Extract = (Vec<String>, OpenPointInTimeQueryString), Error = Rejection> + Clone {
+    warp::path!(String / "pit")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+async fn open_point_in_time(
+    index_id_patterns: Vec<String>,
+    query_string: OpenPointInTimeQueryString,
+    search_service: Arc<dyn SearchService>,
+) -> impl warp::Reply {
+    info!(index_id_patterns=?index_id_patterns, "open-point-in-time");
+    let open_request = OpenPointInTimeRequest {
+        index_id_patterns,
+        keep_alive_secs: query_string.keep_alive_secs,
+    };
+    let result = search_service.open_point_in_time(open_request).await;
+    into_rest_api_response(result, BodyFormat::default())
+}
+
+#[utoipa::path(
+    post,
+    tag = "Search",
+    path = "/{index_id}/pit",
+    responses(
+        (status = 200, description = "Successfully opened point in time.", body = OpenPointInTimeResponse)
+    ),
+    params(
+        OpenPointInTimeQueryString,
+        ("index_id" = String, Path, description = "The index ID(s) captured by the point in time."),
+    )
+)]
+/// Open Point in Time
+///
+/// Captures the splits currently published for the index(es). Searches passing the returned
+/// `pit_id` run against these splits until the point in time expires.
+pub fn open_point_in_time_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    open_point_in_time_filter()
+        .and(with_arg(search_service))
+        .then(open_point_in_time)
+}
+
 #[utoipa::path(
     get,
     tag = "Search",
@@ -428,6 +601,10 @@ struct SearchStreamRequestQueryString {
     pub output_format: OutputFormat,
     #[serde(default)]
     pub partition_by_field: Option<String>,
+    /// Tenant issuing the request. Stream requests share the storage read bandwidth of the
+    /// searchers with the `batch` priority.
+    #[serde(default)]
+    pub tenant_id: Option<String>,
 }
 
 async fn search_stream_endpoint(
@@ -446,6 +623,7 @@ async fn search_stream_endpoint(
         fast_field: search_request.fast_field,
         output_format: search_request.output_format as i32,
         partition_by_field: search_request.partition_by_field,
+        tenant_id: search_request.tenant_id,
     };
     let mut data = search_service.root_search_stream(request).await?;
     let (mut sender, body) = hyper::Body::channel();
@@ -536,7 +714,8 @@ mod tests {
         let mock_search_service_in_arc = Arc::new(mock_search_service);
         search_get_handler(mock_search_service_in_arc.clone())
             .or(search_post_handler(mock_search_service_in_arc.clone()))
-            .or(search_stream_handler(mock_search_service_in_arc))
+            .or(search_stream_handler(mock_search_service_in_arc.clone()))
+            .or(open_point_in_time_handler(mock_search_service_in_arc))
             .recover(recover_fn)
     }
 
@@ -563,15 +742,70 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn test_parse_lookup_enrichment() {
+        assert_eq!(
+            parse_lookup_enrichment("countries:country_code").unwrap(),
+            LookupEnrichment {
+                table: "countries".to_string(),
+                field: "country_code".to_string(),
+                target_field: None,
+            }
+        );
+        assert_eq!(
+            parse_lookup_enrichment("countries:geo.country_code:country").unwrap(),
+            LookupEnrichment {
+                table: "countries".to_string(),
+                field: "geo.country_code".to_string(),
+                target_field: Some("country".to_string()),
+            }
+        );
+        parse_lookup_enrichment("countries").unwrap_err();
+        parse_lookup_enrichment("countries:").unwrap_err();
+        parse_lookup_enrichment(":country_code").unwrap_err();
+        parse_lookup_enrichment("countries:country_code:").unwrap_err();
+        parse_lookup_enrichment("countries:country_code:country:extra").unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_lookup() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&lookup=countries:country_code,users:user_id:\
+                 user",
+            )
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            req.lookup,
+            Some(vec![
+                "countries:country_code".to_string(),
+                "users:user_id:user".to_string()
+            ])
+        );
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.lookups.len(), 2);
+        assert_eq!(
+            search_request.lookups[1].target_field.as_deref(),
+            Some("user")
+        );
+    }
+
     #[test]
     fn test_serialize_search_response() -> anyhow::Result<()> {
         let search_response = SearchResponseRest {
             num_hits: 55,
             hits: Vec::new(),
             snippets: None,
+            highlights: None,
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            profile: None,
+            failed_indexes: Vec::new(),
         };
         let search_response_json: JsonValue = serde_json::to_value(search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -669,6 +903,137 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_route_cache_options() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&max_staleness_secs=30")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.max_staleness_secs, Some(30));
+        assert!(!req.bypass_cache);
+
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&bypass_cache=true")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.max_staleness_secs, None);
+        assert!(req.bypass_cache);
+
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert!(search_request.bypass_cache);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_tenant_and_priority() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert!(req.tenant_id.is_none());
+        assert!(req.priority.is_none());
+
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.priority(), SearchPriority::Interactive);
+
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&tenant_id=acme&priority=batch")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(req.priority, Some(SearchPriority::Batch));
+
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(search_request.priority(), SearchPriority::Batch);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_profile() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert!(!req.profile);
+
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&profile=true")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert!(req.profile);
+
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert!(search_request.profile);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_pit_id() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&pit_id=01HPQ3RWSWZ5ZKCJR8XMSJK2EZ")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.pit_id.as_deref(), Some("01HPQ3RWSWZ5ZKCJR8XMSJK2EZ"));
+
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(
+            search_request.pit_id.as_deref(),
+            Some("01HPQ3RWSWZ5ZKCJR8XMSJK2EZ")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_open_point_in_time_api() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_open_point_in_time()
+            .with(predicate::function(
+                |open_request: &OpenPointInTimeRequest| {
+                    open_request.index_id_patterns == ["quickwit-demo-index"]
+                        && open_request.keep_alive_secs == 300
+                },
+            ))
+            .returning(|_| {
+                Ok(OpenPointInTimeResponse {
+                    pit_id: "01HPQ3RWSWZ5ZKCJR8XMSJK2EZ".to_string(),
+                    num_splits: 3,
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/pit?keep_alive_secs=300")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_json_eq!(
+            resp_json,
+            json!({"pit_id": "01HPQ3RWSWZ5ZKCJR8XMSJK2EZ", "num_splits": 3})
+        );
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/pit")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_route_simple() {
         let rest_search_api_filter = search_get_filter();
@@ -1006,6 +1371,30 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_legal_hold_token_header() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.include_held_splits
+                        && search_request.legal_hold_token.as_deref() == Some("secret")
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        assert_eq!(
+            warp::test::request()
+                .path("/quickwit-demo-index/search?query=*&include_held_splits=true")
+                .header(LEGAL_HOLD_TOKEN_HEADER, "secret")
+                .reply(&rest_search_api_handler)
+                .await
+                .status(),
+            200
+        );
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_with_index_does_not_exist() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -1104,6 +1493,7 @@ mod tests {
                 fast_field: "external_id".to_string(),
                 output_format: OutputFormat::Csv,
                 partition_by_field: None,
+                tenant_id: None,
             }
         );
     }
@@ -1130,6 +1520,7 @@ mod tests {
                 fast_field: "external_id".to_string(),
                 output_format: OutputFormat::ClickHouseRowBinary,
                 partition_by_field: None,
+                tenant_id: None,
             }
         );
     }
@@ -1175,6 +1566,7 @@ mod tests {
                     partial_hit: None,
                     snippet: Some(r#"{"title": [], "body": ["foo <em>bar</em> baz"]}"#.to_string()),
                     index_id: "quickwit-demo-index".to_string(),
+                    highlight: None,
                 }],
                 num_hits: 1,
                 elapsed_time_micros: 16,
@@ -1204,6 +1596,65 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_route_serialize_results_with_highlight() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.highlight
+                        == Some(quickwit_proto::search::HighlightRequest {
+                            fields: vec!["body".to_string()],
+                            pre_tag: Some("<b>".to_string()),
+                            post_tag: Some("</b>".to_string()),
+                            fragment_size: None,
+                        })
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    hits: vec![quickwit_proto::search::Hit {
+                        json: r#"{"title": "foo", "body": "foo bar baz"}"#.to_string(),
+                        partial_hit: None,
+                        snippet: None,
+                        index_id: "quickwit-demo-index".to_string(),
+                        highlight: Some(r#"{"body": ["foo <b>bar</b> baz"]}"#.to_string()),
+                    }],
+                    num_hits: 1,
+                    elapsed_time_micros: 16,
+                    errors: Vec::new(),
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search")
+            .method("POST")
+            .json(&serde_json::json!({
+                "query": "body:bar",
+                "highlight": {
+                    "fields": ["body"],
+                    "pre_tag": "<b>",
+                    "post_tag": "</b>",
+                },
+            }))
+            .reply(&rest_search_api_handler)
+            .await;
+
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "num_hits": 1,
+            "hits": [{"title": "foo", "body": "foo bar baz"}],
+            "highlights": [{"body": ["foo <b>bar</b> baz"]}],
+            "elapsed_time_micros": 16,
+            "errors": [],
+        });
+        assert_json_eq!(resp_json, expected_response_json);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_multi_indexes() {
         {
@@ -17,6 +17,6 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-mod cluster_sandbox;
+mod rest_handler;
 
-pub(crate) use cluster_sandbox::{ingest_with_retry, ClusterSandbox};
+pub(crate) use rest_handler::{state_api_handlers, StateApi};
@@ -0,0 +1,710 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use quickwit_config::{
+    load_index_config_from_user_config, load_source_config_from_user_config, ConfigFormat,
+    IndexConfig, NodeConfig, SourceConfig, SourceParams, CLI_SOURCE_ID, INGEST_API_SOURCE_ID,
+    INGEST_V2_SOURCE_ID,
+};
+use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt, UpdateIndexRequestExt};
+use quickwit_proto::metastore::{
+    DeleteSourceRequest, EntityKind, IndexMetadataRequest, MetastoreError, MetastoreService,
+    ToggleSourceRequest, UpdateIndexRequest,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use crate::format::{extract_config_format, extract_format_from_qs};
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_index_state, apply_index_state),
+    components(schemas(IndexStateDiff))
+)]
+pub(crate) struct StateApi;
+
+pub(crate) fn state_api_handlers(
+    index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    get_index_state_handler(index_service.clone())
+        .or(apply_index_state_handler(index_service, node_config))
+}
+
+/// Sources created along with every index and managed by Quickwit. They are not part of the
+/// declarative state of an index.
+const MANAGED_SOURCE_IDS: [&str; 3] = [CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID];
+
+/// Desired state of an index: its configuration and its user-managed sources.
+#[derive(Debug)]
+struct IndexState {
+    index_config: IndexConfig,
+    /// Whether `index_uri` was set explicitly. When omitted, the URI of an existing index is
+    /// left as is instead of being compared to the default one.
+    index_uri_specified: bool,
+    source_configs: BTreeMap<String, SourceConfig>,
+}
+
+/// Changes required to bring an index to its desired state.
+#[derive(Debug, Default, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct IndexStateDiff {
+    /// Whether the index is created.
+    pub index_created: bool,
+    /// Updated index settings, among `doc_mapping`, `search_settings`, `retention_policy`, and
+    /// `legal_hold`.
+    pub updated_settings: Vec<String>,
+    pub created_sources: Vec<String>,
+    /// Sources whose configuration changed. They are deleted and created again, which resets
+    /// their checkpoint.
+    pub replaced_sources: Vec<String>,
+    /// Sources that are only enabled or disabled.
+    pub toggled_sources: Vec<String>,
+    pub deleted_sources: Vec<String>,
+    /// Whether the changes were computed without being applied.
+    pub dry_run: bool,
+}
+
+impl IndexStateDiff {
+    fn is_empty(&self) -> bool {
+        !self.index_created
+            && self.updated_settings.is_empty()
+            && self.created_sources.is_empty()
+            && self.replaced_sources.is_empty()
+            && self.toggled_sources.is_empty()
+            && self.deleted_sources.is_empty()
+    }
+}
+
+fn get_index_state_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("state" / "indexes" / String)
+        .and(warp::get())
+        .and(with_arg(index_service))
+        .then(get_index_state)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "State",
+    path = "/state/indexes/{index_id}",
+    responses(
+        (status = 200, description = "Successfully fetched the state of the index.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID."),
+    )
+)]
+/// Returns the current state of an index, in the format accepted by `PUT
+/// /state/indexes/{index_id}`: the index configuration along with a `sources` array.
+async fn get_index_state(
+    index_id: String,
+    index_service: IndexService,
+) -> Result<JsonValue, IndexServiceError> {
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = index_service
+        .metastore()
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+
+    let mut index_state_json = serialize_to_json_object(&index_metadata.index_config)?;
+    let mut source_configs: Vec<&SourceConfig> = index_metadata
+        .sources
+        .values()
+        .filter(|source_config| !is_managed_source(&source_config.source_id))
+        .collect();
+    source_configs.sort_by(|left, right| left.source_id.cmp(&right.source_id));
+
+    let sources_json = source_configs
+        .into_iter()
+        .map(|source_config| serialize_to_json_object(source_config).map(JsonValue::Object))
+        .collect::<Result<Vec<JsonValue>, IndexServiceError>>()?;
+    index_state_json.insert("sources".to_string(), JsonValue::Array(sources_json));
+    Ok(JsonValue::Object(index_state_json))
+}
+
+#[derive(Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct ApplyIndexStateQueryParams {
+    /// Computes the changes without applying them.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn apply_index_state_handler(
+    index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("state" / "indexes" / String)
+        .and(warp::put())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(extract_config_format())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::filters::body::bytes())
+        .and(with_arg(index_service))
+        .and(with_arg(node_config))
+        .then(apply_index_state)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "State",
+    path = "/state/indexes/{index_id}",
+    request_body = VersionedIndexConfig,
+    responses(
+        (status = 200, description = "Successfully applied the index state.", body = IndexStateDiff)
+    ),
+    params(
+        ApplyIndexStateQueryParams,
+        ("index_id" = String, Path, description = "The index ID."),
+    )
+)]
+/// Brings an index to the desired state and returns the changes that were applied.
+///
+/// The body is an index configuration with an additional `sources` array of source
+/// configurations. The index is created if it does not exist. Otherwise, its updatable settings
+/// are replaced, the sources missing from the array are deleted, and the new sources are created.
+/// Applying the same state twice is a no-op. Sources managed by Quickwit are left untouched.
+async fn apply_index_state(
+    index_id: String,
+    query_params: ApplyIndexStateQueryParams,
+    config_format: ConfigFormat,
+    body: Bytes,
+    mut index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> Result<IndexStateDiff, IndexServiceError> {
+    let index_state = parse_index_state(
+        &index_id,
+        config_format,
+        &body,
+        &node_config.default_index_root_uri,
+    )?;
+    let mut metastore = index_service.metastore();
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+
+    let index_metadata_opt = match metastore.index_metadata(index_metadata_request).await {
+        Ok(response) => Some(response.deserialize_index_metadata()?),
+        Err(MetastoreError::NotFound(EntityKind::Index { .. })) => None,
+        Err(error) => return Err(error.into()),
+    };
+    let mut diff = if let Some(index_metadata) = &index_metadata_opt {
+        diff_index_state(index_metadata, &index_state)?
+    } else {
+        IndexStateDiff {
+            index_created: true,
+            created_sources: index_state.source_configs.keys().cloned().collect(),
+            ..Default::default()
+        }
+    };
+    diff.dry_run = query_params.dry_run;
+
+    info!(index_id=%index_id, dry_run=diff.dry_run, diff=?diff, "apply-index-state");
+
+    if diff.dry_run || diff.is_empty() {
+        return Ok(diff);
+    }
+    let IndexState {
+        index_config,
+        mut source_configs,
+        ..
+    } = index_state;
+
+    let index_uid = if let Some(index_metadata) = index_metadata_opt {
+        if !diff.updated_settings.is_empty() {
+            // The legal hold is only placed or lifted when the desired state changes it.
+            let legal_hold_update_opt = diff
+                .updated_settings
+                .iter()
+                .any(|setting| setting == "legal_hold")
+                .then(|| index_config.legal_hold_opt.clone());
+            let update_request = UpdateIndexRequest::try_from_updates(
+                index_metadata.index_uid.clone(),
+                &index_config.search_settings,
+                &index_config.retention_policy_opt,
+                &legal_hold_update_opt,
+                Some(&index_config.doc_mapping),
+            )?;
+            metastore.update_index(update_request).await?;
+        }
+        index_metadata.index_uid
+    } else {
+        index_service
+            .create_index(index_config, false)
+            .await?
+            .index_uid
+    };
+    for source_id in diff.deleted_sources.iter().chain(&diff.replaced_sources) {
+        let delete_source_request = DeleteSourceRequest {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+        };
+        metastore.delete_source(delete_source_request).await?;
+    }
+    for source_id in &diff.toggled_sources {
+        let toggle_source_request = ToggleSourceRequest {
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.clone(),
+            enable: source_configs[source_id].enabled,
+        };
+        metastore.toggle_source(toggle_source_request).await?;
+    }
+    for source_id in diff.created_sources.iter().chain(&diff.replaced_sources) {
+        let source_config = source_configs
+            .remove(source_id)
+            .expect("source should be in the desired state");
+        index_service
+            .add_source(index_uid.clone(), source_config)
+            .await?;
+    }
+    Ok(diff)
+}
+
+/// Parses an index configuration holding an additional `sources` array. Sources that do not
+/// specify a `version` inherit the one of the index configuration.
+fn parse_index_state(
+    index_id: &str,
+    config_format: ConfigFormat,
+    body: &[u8],
+    default_index_root_uri: &quickwit_common::uri::Uri,
+) -> Result<IndexState, IndexServiceError> {
+    let mut index_state_json: JsonMap<String, JsonValue> = config_format
+        .parse(body)
+        .map_err(IndexServiceError::InvalidConfig)?;
+
+    let sources_json = match index_state_json.remove("sources") {
+        Some(JsonValue::Array(sources_json)) => sources_json,
+        Some(_) => {
+            let error = anyhow::anyhow!("`sources` must be an array of source configs");
+            return Err(IndexServiceError::InvalidConfig(error));
+        }
+        None => Vec::new(),
+    };
+    // YAML parses `version: 0.8` as a number, which the config deserializers reject once
+    // converted to JSON.
+    if let Some(JsonValue::Number(version_number)) = index_state_json.get("version") {
+        let version = JsonValue::String(version_number.to_string());
+        index_state_json.insert("version".to_string(), version);
+    }
+    let version_opt = index_state_json.get("version").cloned();
+    let index_uri_specified = index_state_json.contains_key("index_uri");
+
+    let index_config_bytes = serde_json::to_vec(&index_state_json)
+        .map_err(|error| IndexServiceError::Internal(error.to_string()))?;
+    let index_config = load_index_config_from_user_config(
+        ConfigFormat::Json,
+        &index_config_bytes,
+        default_index_root_uri,
+    )
+    .map_err(IndexServiceError::InvalidConfig)?;
+
+    if index_config.index_id != index_id {
+        let error = anyhow::anyhow!(
+            "index ID `{}` does not match the index ID `{index_id}` of the path",
+            index_config.index_id
+        );
+        return Err(IndexServiceError::InvalidConfig(error));
+    }
+    let mut source_configs = BTreeMap::new();
+
+    for mut source_json in sources_json {
+        if let (JsonValue::Object(source_json_obj), Some(version)) =
+            (&mut source_json, &version_opt)
+        {
+            source_json_obj
+                .entry("version")
+                .or_insert_with(|| version.clone());
+        }
+        let source_config_bytes = serde_json::to_vec(&source_json)
+            .map_err(|error| IndexServiceError::Internal(error.to_string()))?;
+        let source_config =
+            load_source_config_from_user_config(ConfigFormat::Json, &source_config_bytes)
+                .map_err(IndexServiceError::InvalidConfig)?;
+
+        if is_managed_source(&source_config.source_id) {
+            return Err(IndexServiceError::OperationNotAllowed(format!(
+                "source `{}` is managed by Quickwit and cannot be declared",
+                source_config.source_id
+            )));
+        }
+        if let SourceParams::File(_) = &source_config.source_params {
+            return Err(IndexServiceError::OperationNotAllowed(
+                "file sources are limited to a local usage".to_string(),
+            ));
+        }
+        let source_id = source_config.source_id.clone();
+
+        if source_configs
+            .insert(source_id.clone(), source_config)
+            .is_some()
+        {
+            let error = anyhow::anyhow!("source `{source_id}` is declared more than once");
+            return Err(IndexServiceError::InvalidConfig(error));
+        }
+    }
+    Ok(IndexState {
+        index_config,
+        index_uri_specified,
+        source_configs,
+    })
+}
+
+/// Compares the current state of an index with its desired state. Fails if the desired state
+/// changes a setting that cannot be updated.
+fn diff_index_state(
+    index_metadata: &IndexMetadata,
+    index_state: &IndexState,
+) -> Result<IndexStateDiff, IndexServiceError> {
+    let current = &index_metadata.index_config;
+    let desired = &index_state.index_config;
+
+    let mut immutable_settings = Vec::new();
+
+    if index_state.index_uri_specified && current.index_uri != desired.index_uri {
+        immutable_settings.push("index_uri");
+    }
+    if current.indexing_settings != desired.indexing_settings {
+        immutable_settings.push("indexing_settings");
+    }
+    if current.replication_factor_opt != desired.replication_factor_opt {
+        immutable_settings.push("replication_factor");
+    }
+    if !immutable_settings.is_empty() {
+        return Err(IndexServiceError::OperationNotAllowed(format!(
+            "the following settings of index `{}` cannot be updated: {}; delete and recreate the \
+             index instead",
+            current.index_id,
+            immutable_settings.join(", ")
+        )));
+    }
+    let mut diff = IndexStateDiff::default();
+
+    if current.doc_mapping != desired.doc_mapping {
+        diff.updated_settings.push("doc_mapping".to_string());
+    }
+    if current.search_settings != desired.search_settings {
+        diff.updated_settings.push("search_settings".to_string());
+    }
+    if current.retention_policy_opt != desired.retention_policy_opt {
+        diff.updated_settings.push("retention_policy".to_string());
+    }
+    if current.legal_hold_opt != desired.legal_hold_opt {
+        diff.updated_settings.push("legal_hold".to_string());
+    }
+    let current_sources: HashMap<&str, &SourceConfig> = index_metadata
+        .sources
+        .iter()
+        .filter(|(source_id, _)| !is_managed_source(source_id))
+        .map(|(source_id, source_config)| (source_id.as_str(), source_config))
+        .collect();
+
+    for (source_id, desired_source) in &index_state.source_configs {
+        let Some(current_source) = current_sources.get(source_id.as_str()) else {
+            diff.created_sources.push(source_id.clone());
+            continue;
+        };
+        if *current_source == desired_source {
+            continue;
+        }
+        let toggled_source = SourceConfig {
+            enabled: desired_source.enabled,
+            ..(*current_source).clone()
+        };
+        if toggled_source == *desired_source {
+            diff.toggled_sources.push(source_id.clone());
+        } else {
+            diff.replaced_sources.push(source_id.clone());
+        }
+    }
+    for source_id in current_sources.keys() {
+        if !index_state.source_configs.contains_key(*source_id) {
+            diff.deleted_sources.push(source_id.to_string());
+        }
+    }
+    diff.deleted_sources.sort();
+    Ok(diff)
+}
+
+fn is_managed_source(source_id: &str) -> bool {
+    MANAGED_SOURCE_IDS.contains(&source_id)
+}
+
+fn serialize_to_json_object<T: Serialize>(
+    value: &T,
+) -> Result<JsonMap<String, JsonValue>, IndexServiceError> {
+    match serde_json::to_value(value) {
+        Ok(JsonValue::Object(json_obj)) => Ok(json_obj),
+        Ok(_) => Err(IndexServiceError::Internal(
+            "config should serialize to a JSON object".to_string(),
+        )),
+        Err(error) => Err(IndexServiceError::Internal(error.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::uri::Uri;
+    use quickwit_metastore::metastore_for_test;
+    use quickwit_storage::StorageResolver;
+    use serde_json::json;
+
+    use super::*;
+    use crate::recover_fn;
+
+    const INDEX_STATE_YAML: &str = r#"
+        version: 0.8
+        index_id: hdfs-logs
+        doc_mapping:
+          field_mappings:
+            - name: timestamp
+              type: datetime
+              fast: true
+          timestamp_field: timestamp
+        sources:
+          - source_id: vec-source-1
+            source_type: vec
+            params:
+              docs: []
+              batch_num_docs: 10
+          - source_id: vec-source-2
+            source_type: vec
+            params:
+              docs: []
+              batch_num_docs: 10
+        "#;
+
+    #[tokio::test]
+    async fn test_apply_index_state() {
+        let mut metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let state_api_handlers =
+            super::state_api_handlers(index_service, Arc::new(node_config)).recover(recover_fn);
+
+        // Dry run.
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs?dry_run=true")
+            .method("PUT")
+            .header("content-type", "application/yaml")
+            .body(INDEX_STATE_YAML)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            json!({
+                "index_created": true,
+                "updated_settings": [],
+                "created_sources": ["vec-source-1", "vec-source-2"],
+                "replaced_sources": [],
+                "toggled_sources": [],
+                "deleted_sources": [],
+                "dry_run": true,
+            })
+        );
+        metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("hdfs-logs".to_string()))
+            .await
+            .unwrap_err();
+
+        // Create.
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .header("content-type", "application/yaml")
+            .body(INDEX_STATE_YAML)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("hdfs-logs".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert!(index_metadata.sources.contains_key("vec-source-1"));
+        assert!(index_metadata.sources.contains_key("vec-source-2"));
+        assert!(index_metadata.sources.contains_key(INGEST_API_SOURCE_ID));
+
+        // Applying the same state is a no-op.
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .header("content-type", "application/yaml")
+            .body(INDEX_STATE_YAML)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["index_created"], false);
+        assert_eq!(resp_json["created_sources"], json!([]));
+        assert_eq!(resp_json["updated_settings"], json!([]));
+
+        // The current state can be applied as is.
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("GET")
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_state_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(index_state_json["sources"].as_array().unwrap().len(), 2);
+
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&index_state_json)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["deleted_sources"], json!([]));
+        assert_eq!(resp_json["replaced_sources"], json!([]));
+
+        // Update.
+        let index_state_yaml = r#"
+            version: 0.8
+            index_id: hdfs-logs
+            doc_mapping:
+              field_mappings:
+                - name: timestamp
+                  type: datetime
+                  fast: true
+              timestamp_field: timestamp
+            retention:
+              period: 90 days
+              schedule: daily
+            sources:
+              - source_id: vec-source-1
+                source_type: vec
+                enabled: false
+                params:
+                  docs: []
+                  batch_num_docs: 10
+              - source_id: vec-source-3
+                source_type: vec
+                params:
+                  docs: []
+                  batch_num_docs: 10
+            "#;
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .header("content-type", "application/yaml")
+            .body(index_state_yaml)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            json!({
+                "index_created": false,
+                "updated_settings": ["retention_policy"],
+                "created_sources": ["vec-source-3"],
+                "replaced_sources": [],
+                "toggled_sources": ["vec-source-1"],
+                "deleted_sources": ["vec-source-2"],
+                "dry_run": false,
+            })
+        );
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("hdfs-logs".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert!(index_metadata.index_config.retention_policy_opt.is_some());
+        assert!(!index_metadata.sources["vec-source-1"].enabled);
+        assert!(!index_metadata.sources.contains_key("vec-source-2"));
+        assert!(index_metadata.sources.contains_key("vec-source-3"));
+    }
+
+    #[tokio::test]
+    async fn test_apply_index_state_rejects_invalid_states() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let state_api_handlers =
+            super::state_api_handlers(index_service, Arc::new(node_config)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/state/indexes/other-index")
+            .method("PUT")
+            .header("content-type", "application/yaml")
+            .body(INDEX_STATE_YAML)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&json!({
+                "version": "0.8",
+                "index_id": "hdfs-logs",
+                "doc_mapping": {},
+                "sources": [{
+                    "source_id": INGEST_API_SOURCE_ID,
+                    "source_type": "ingest-api",
+                }],
+            }))
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .header("content-type", "application/yaml")
+            .body(INDEX_STATE_YAML)
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/state/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&json!({
+                "version": "0.8",
+                "index_id": "hdfs-logs",
+                "index_uri": "file:///other-index-root-uri/hdfs-logs",
+                "doc_mapping": {},
+            }))
+            .reply(&state_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+    }
+}
@@ -0,0 +1,427 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytesize::ByteSize;
+use quickwit_common::io::{self, Limiter};
+use quickwit_common::uri::Uri;
+use tokio::io::AsyncRead;
+use tokio::sync::oneshot;
+
+use crate::storage::SendableAsync;
+use crate::{BulkDeleteError, OwnedBytes, PutPayload, Storage, StorageResult};
+
+/// Reads acquire their bandwidth in chunks of this size so that a small interactive read arriving
+/// in the middle of a large batch read does not have to wait for the whole batch read.
+const READ_CHUNK_NUM_BYTES: u64 = if cfg!(test) {
+    64 * 1024 // 64 KiB
+} else {
+    4 * 1024 * 1024 // 4 MiB
+};
+
+/// Priority of a storage read.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ReadPriority {
+    /// Reads of queries whose results a user is waiting for.
+    #[default]
+    Interactive,
+    /// Reads of throughput-oriented queries, such as exports.
+    Batch,
+}
+
+impl ReadPriority {
+    /// Share of the bandwidth of a flow of reads with this priority relative to the other flows.
+    fn weight(&self) -> f64 {
+        match self {
+            Self::Interactive => 4.,
+            Self::Batch => 1.,
+        }
+    }
+}
+
+/// Identifies the flow of reads a storage read belongs to. The bandwidth is shared fairly between
+/// the flows, weighted by their priority.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ReadToken {
+    /// Tenant issuing the reads. The reads without tenant form a flow of their own.
+    pub tenant_id: Option<String>,
+    /// Priority of the reads.
+    pub priority: ReadPriority,
+}
+
+/// Caps the aggregate storage read bandwidth of the node and shares it between the flows of
+/// reads (see [`ReadToken`]) with weighted fair queuing: while several flows are competing for
+/// the bandwidth, each flow gets a share proportional to the weight of its priority, so a tenant
+/// running a large export cannot monopolize the bandwidth needed by interactive queries.
+///
+/// Reads acquire their bandwidth before being issued, so the bandwidth is capped on average rather
+/// than instantaneously.
+#[derive(Clone, Default)]
+pub struct BandwidthScheduler {
+    inner_opt: Option<Arc<BandwidthSchedulerInner>>,
+}
+
+impl fmt::Debug for BandwidthScheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BandwidthScheduler")
+            .field("is_enabled", &self.inner_opt.is_some())
+            .finish()
+    }
+}
+
+struct BandwidthSchedulerInner {
+    limiter: Limiter,
+    state: Mutex<SchedulerState>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    /// Start tag of the last dispatched read.
+    virtual_time: f64,
+    /// Finish tag of the last read enqueued by each flow.
+    finish_tags: HashMap<ReadToken, f64>,
+    pending_reads: BinaryHeap<PendingRead>,
+    sequence: u64,
+    is_dispatching: bool,
+}
+
+struct PendingRead {
+    start_tag: f64,
+    sequence: u64,
+    num_bytes: u64,
+    permit_tx: oneshot::Sender<()>,
+}
+
+impl PartialEq for PendingRead {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingRead {}
+
+impl PartialOrd for PendingRead {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingRead {
+    // Reversed so that the binary heap pops the read with the smallest start tag first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .start_tag
+            .total_cmp(&self.start_tag)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl BandwidthScheduler {
+    /// Creates a scheduler capping the read bandwidth to `max_bandwidth_opt`. When `None`, the
+    /// reads are not scheduled at all.
+    pub fn new(max_bandwidth_opt: Option<ByteSize>) -> Self {
+        let inner_opt = max_bandwidth_opt.map(|max_bandwidth| {
+            Arc::new(BandwidthSchedulerInner {
+                limiter: io::limiter(max_bandwidth),
+                state: Mutex::default(),
+            })
+        });
+        Self { inner_opt }
+    }
+
+    /// Waits until the flow identified by `read_token` is allowed to read `num_bytes`.
+    pub async fn acquire_bandwidth(&self, read_token: &ReadToken, num_bytes: u64) {
+        let Some(inner) = &self.inner_opt else {
+            return;
+        };
+        let mut num_remaining_bytes = num_bytes;
+
+        while num_remaining_bytes > 0 {
+            let chunk_num_bytes = num_remaining_bytes.min(READ_CHUNK_NUM_BYTES);
+            let permit_rx = inner.enqueue(read_token, chunk_num_bytes);
+            // The dispatcher only drops the sender when the runtime shuts down.
+            let _ = permit_rx.await;
+            num_remaining_bytes -= chunk_num_bytes;
+        }
+    }
+}
+
+impl BandwidthSchedulerInner {
+    fn enqueue(self: &Arc<Self>, read_token: &ReadToken, num_bytes: u64) -> oneshot::Receiver<()> {
+        let (permit_tx, permit_rx) = oneshot::channel();
+        let mut state = self.state.lock().unwrap();
+
+        // Start-time fair queuing: a read starts when the previous read of its flow finishes or
+        // when it is enqueued, whichever comes last, and lasts inversely to the flow weight.
+        let last_finish_tag = state
+            .finish_tags
+            .get(read_token)
+            .copied()
+            .unwrap_or_default();
+        let start_tag = state.virtual_time.max(last_finish_tag);
+        let finish_tag = start_tag + num_bytes as f64 / read_token.priority.weight();
+        state.finish_tags.insert(read_token.clone(), finish_tag);
+
+        state.sequence += 1;
+        let pending_read = PendingRead {
+            start_tag,
+            sequence: state.sequence,
+            num_bytes,
+            permit_tx,
+        };
+        state.pending_reads.push(pending_read);
+
+        if !state.is_dispatching {
+            state.is_dispatching = true;
+            tokio::spawn(self.clone().dispatch());
+        }
+        permit_rx
+    }
+
+    async fn dispatch(self: Arc<Self>) {
+        loop {
+            let pending_read = {
+                let mut state = self.state.lock().unwrap();
+
+                let Some(pending_read) = state.pending_reads.pop() else {
+                    // All the flows are idle: the next busy period starts from scratch.
+                    state.is_dispatching = false;
+                    state.virtual_time = 0.;
+                    state.finish_tags.clear();
+                    return;
+                };
+                state.virtual_time = pending_read.start_tag;
+                pending_read
+            };
+            // The read is not charged if the reader gave up.
+            if pending_read.permit_tx.send(()).is_ok() {
+                self.limiter.consume(pending_read.num_bytes as usize).await;
+            }
+        }
+    }
+}
+
+/// Storage whose reads are scheduled by a [`BandwidthScheduler`] on behalf of a flow of reads.
+/// Writes and deletes are not scheduled.
+struct ScheduledStorage {
+    storage: Arc<dyn Storage>,
+    bandwidth_scheduler: BandwidthScheduler,
+    read_token: ReadToken,
+}
+
+impl fmt::Debug for ScheduledStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScheduledStorage")
+            .field("uri", self.storage.uri())
+            .field("read_token", &self.read_token)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for ScheduledStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.storage.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.storage.put(path, payload).await
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        let num_bytes = self.storage.file_num_bytes(path).await?;
+        self.bandwidth_scheduler
+            .acquire_bandwidth(&self.read_token, num_bytes)
+            .await;
+        self.storage.copy_to(path, output).await
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<u64> {
+        let num_bytes = self.storage.file_num_bytes(path).await?;
+        self.bandwidth_scheduler
+            .acquire_bandwidth(&self.read_token, num_bytes)
+            .await;
+        self.storage.copy_to_file(path, output_path).await
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        self.bandwidth_scheduler
+            .acquire_bandwidth(&self.read_token, range.len() as u64)
+            .await;
+        self.storage.get_slice(path, range).await
+    }
+
+    async fn get_slice_stream(
+        &self,
+        path: &Path,
+        range: Range<usize>,
+    ) -> StorageResult<Box<dyn AsyncRead + Send + Unpin>> {
+        self.bandwidth_scheduler
+            .acquire_bandwidth(&self.read_token, range.len() as u64)
+            .await;
+        self.storage.get_slice_stream(path, range).await
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        // The size of the file is not known in advance, so the read is charged once completed.
+        let bytes = self.storage.get_all(path).await?;
+        self.bandwidth_scheduler
+            .acquire_bandwidth(&self.read_token, bytes.len() as u64)
+            .await;
+        Ok(bytes)
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.storage.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        self.storage.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.storage.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.storage.file_num_bytes(path).await
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        self.storage.list_files(prefix).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.storage.uri()
+    }
+}
+
+/// Wraps the given storage so that its reads are scheduled by `bandwidth_scheduler` on behalf of
+/// the flow identified by `read_token`. The storage is returned as is if the scheduler does not
+/// cap the bandwidth.
+pub fn wrap_storage_with_bandwidth_scheduler(
+    storage: Arc<dyn Storage>,
+    bandwidth_scheduler: &BandwidthScheduler,
+    read_token: ReadToken,
+) -> Arc<dyn Storage> {
+    if bandwidth_scheduler.inner_opt.is_none() {
+        return storage;
+    }
+    Arc::new(ScheduledStorage {
+        storage,
+        bandwidth_scheduler: bandwidth_scheduler.clone(),
+        read_token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::RamStorage;
+
+    #[tokio::test]
+    async fn test_bandwidth_scheduler_disabled() {
+        let bandwidth_scheduler = BandwidthScheduler::new(None);
+        let now = Instant::now();
+        bandwidth_scheduler
+            .acquire_bandwidth(&ReadToken::default(), 1024 * 1024 * 1024)
+            .await;
+        assert!(now.elapsed() < Duration::from_millis(100));
+
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let wrapped_storage = wrap_storage_with_bandwidth_scheduler(
+            storage.clone(),
+            &bandwidth_scheduler,
+            ReadToken::default(),
+        );
+        assert!(Arc::ptr_eq(&storage, &wrapped_storage));
+    }
+
+    #[tokio::test]
+    async fn test_bandwidth_scheduler_prioritizes_interactive_reads() {
+        // 10 MiB/s
+        let bandwidth_scheduler = BandwidthScheduler::new(Some(ByteSize::mib(10)));
+
+        let batch_read_token = ReadToken {
+            tenant_id: Some("tenant-1".to_string()),
+            priority: ReadPriority::Batch,
+        };
+        let batch_scheduler = bandwidth_scheduler.clone();
+        // Takes about 400ms on its own.
+        let batch_read_handle = tokio::spawn(async move {
+            batch_scheduler
+                .acquire_bandwidth(&batch_read_token, 4 * 1024 * 1024)
+                .await;
+            Instant::now()
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let interactive_read_token = ReadToken {
+            tenant_id: Some("tenant-2".to_string()),
+            priority: ReadPriority::Interactive,
+        };
+        // Takes about 50ms with 80% of the bandwidth.
+        bandwidth_scheduler
+            .acquire_bandwidth(&interactive_read_token, 512 * 1024)
+            .await;
+        let interactive_read_finished_at = Instant::now();
+
+        let batch_read_finished_at = batch_read_handle.await.unwrap();
+        assert!(interactive_read_finished_at < batch_read_finished_at);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_storage() {
+        let storage = RamStorage::builder().put("foo", b"hello, world!").build();
+        let bandwidth_scheduler = BandwidthScheduler::new(Some(ByteSize::mib(10)));
+        let scheduled_storage = wrap_storage_with_bandwidth_scheduler(
+            Arc::new(storage),
+            &bandwidth_scheduler,
+            ReadToken::default(),
+        );
+        let bytes = scheduled_storage
+            .get_slice(Path::new("foo"), 0..5)
+            .await
+            .unwrap();
+        assert_eq!(bytes.as_slice(), b"hello");
+
+        let bytes = scheduled_storage.get_all(Path::new("foo")).await.unwrap();
+        assert_eq!(bytes.as_slice(), b"hello, world!");
+
+        let state = bandwidth_scheduler
+            .inner_opt
+            .as_ref()
+            .unwrap()
+            .state
+            .lock()
+            .unwrap();
+        assert!(state.pending_reads.is_empty());
+    }
+}
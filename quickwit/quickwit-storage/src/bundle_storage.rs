@@ -152,7 +152,7 @@ impl BundleStorageFileOffsets {
     /// See docs/internals/split-format.md
     /// [Files, FileMetadata, FileMetadata Len, HotCache, HotCache Len]
     /// Returns (Hotcache, Self)
-    fn open_from_split_data(file: FileSlice) -> anyhow::Result<(FileSlice, Self)> {
+    pub fn open_from_split_data(file: FileSlice) -> anyhow::Result<(FileSlice, Self)> {
         let (bundle_and_hotcache_bytes, hotcache_num_bytes_data) =
             file.split_from_end(SPLIT_HOTBYTES_FOOTER_LENGTH_NUM_BYTES);
         let hotcache_num_bytes: u32 = u32::from_le_bytes(
@@ -187,6 +187,38 @@ impl BundleStorageFileOffsets {
         BundleStorageFileOffsetsVersions::try_read_component(&mut bundle_storage_file_offsets_data)
     }
 
+    /// Fetches the file offsets of a split file stored in `storage` when the range of its footer
+    /// is unknown, for instance because the split metadata is lost. The footer is located by
+    /// reading the lengths stored at the end of the file.
+    ///
+    /// Returns the byte range of the footer (file metadata and hotcache) and the file offsets.
+    pub async fn fetch_from_split_file(
+        storage: &dyn Storage,
+        split_path: &Path,
+    ) -> anyhow::Result<(Range<u64>, Self)> {
+        let split_num_bytes = storage.file_num_bytes(split_path).await? as usize;
+        let hotcache_end = split_num_bytes
+            .checked_sub(SPLIT_HOTBYTES_FOOTER_LENGTH_NUM_BYTES)
+            .context("split file is too small")?;
+        let hotcache_num_bytes = fetch_u32_le(storage, split_path, hotcache_end).await? as usize;
+        let bundle_end = hotcache_end
+            .checked_sub(hotcache_num_bytes)
+            .context("invalid hotcache length")?;
+        let file_metadata_end = bundle_end
+            .checked_sub(BUNDLE_METADATA_LENGTH_NUM_BYTES)
+            .context("split file is too small")?;
+        let file_metadata_num_bytes =
+            fetch_u32_le(storage, split_path, file_metadata_end).await? as usize;
+        let footer_start = file_metadata_end
+            .checked_sub(file_metadata_num_bytes)
+            .context("invalid file metadata length")?;
+        let bundle_footer_bytes = storage
+            .get_slice(split_path, footer_start..bundle_end)
+            .await?;
+        let file_offsets = Self::open(FileSlice::new(Arc::new(bundle_footer_bytes)))?;
+        Ok((footer_start as u64..split_num_bytes as u64, file_offsets))
+    }
+
     /// Returns file offsets for given path.
     pub fn get(&self, path: &Path) -> Option<Range<u64>> {
         self.files.get(path).cloned()
@@ -296,11 +328,28 @@ impl Storage for BundleStorage {
         Ok(file_range.end - file_range.start)
     }
 
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        let files = self
+            .iter_files()
+            .filter(|path| path.starts_with(prefix))
+            .cloned()
+            .collect();
+        Ok(files)
+    }
+
     fn uri(&self) -> &Uri {
         self.storage.uri()
     }
 }
 
+async fn fetch_u32_le(storage: &dyn Storage, path: &Path, offset: usize) -> anyhow::Result<u32> {
+    let bytes = storage
+        .get_slice(path, offset..offset + std::mem::size_of::<u32>())
+        .await?;
+    let num = u32::from_le_bytes(bytes.as_slice().try_into()?);
+    Ok(num)
+}
+
 impl HasLen for BundleStorage {
     fn len(&self) -> usize {
         unimplemented!()
@@ -328,6 +377,8 @@ mod tests {
     use std::fs::{self, File};
     use std::io::Write;
 
+    use quickwit_common::shared_consts::SPLIT_FIELDS_FILE_NAME;
+
     use super::*;
     use crate::{PutPayload, RamStorageBuilder, SplitPayloadBuilder};
 
@@ -373,6 +424,34 @@ mod tests {
 
         Ok(())
     }
+    #[tokio::test]
+    async fn test_bundle_storage_file_offsets_fetch_from_split_file() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let test_filepath = temp_dir.path().join("f1");
+        fs::write(&test_filepath, [123, 76])?;
+
+        let split_payload =
+            SplitPayloadBuilder::get_split_payload(&[test_filepath], b"fields", &[1, 3, 3, 7])?;
+        let footer_range = split_payload.footer_range.clone();
+        let buffer = split_payload.read_all().await?;
+
+        let split_path = Path::new("split");
+        let ram_storage = RamStorageBuilder::default()
+            .put(&split_path.to_string_lossy(), &buffer)
+            .build();
+        let (fetched_footer_range, file_offsets) =
+            BundleStorageFileOffsets::fetch_from_split_file(&ram_storage, split_path).await?;
+        assert_eq!(fetched_footer_range, footer_range);
+        assert_eq!(file_offsets.get(Path::new("f1")), Some(0..2));
+        assert!(file_offsets.exists(Path::new(SPLIT_FIELDS_FILE_NAME)));
+
+        ram_storage.put(split_path, Box::new(vec![1, 2])).await?;
+        BundleStorageFileOffsets::fetch_from_split_file(&ram_storage, split_path)
+            .await
+            .unwrap_err();
+        Ok(())
+    }
+
     #[tokio::test]
     async fn bundle_storage_test() -> anyhow::Result<()> {
         let temp_dir = tempfile::tempdir()?;
@@ -128,6 +128,19 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
         }
     }
 
+    /// Updates the capacity of the cache, evicting the least recently used items until the cache
+    /// fits in the new capacity.
+    fn set_capacity(&mut self, capacity: Capacity) {
+        self.capacity = capacity;
+
+        while self.capacity.exceeds_capacity(self.num_bytes as usize) {
+            let Some((_, bytes)) = self.lru_cache.pop_lru() else {
+                break;
+            };
+            self.drop_item(bytes.len() as u64);
+        }
+    }
+
     /// Attempt to put the given amount of data in the cache.
     /// This may fail silently if the owned_bytes slice is larger than the cache
     /// capacity.
@@ -222,6 +235,15 @@ impl<K: Hash + Eq> MemorySizedCache<K> {
     pub fn put(&self, val: K, bytes: OwnedBytes) {
         self.inner.lock().unwrap().put(val, bytes);
     }
+
+    /// Updates the capacity of the cache. If the cache holds more data than the new capacity,
+    /// the least recently used items are evicted right away.
+    pub fn set_capacity_in_bytes(&self, capacity_in_bytes: usize) {
+        self.inner
+            .lock()
+            .unwrap()
+            .set_capacity(Capacity::InBytes(capacity_in_bytes));
+    }
 }
 
 impl MemorySizedCache<SliceAddress> {
@@ -304,6 +326,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cache_set_capacity_in_bytes() {
+        let cache = MemorySizedCache::with_capacity_in_bytes(10, &CACHE_METRICS_FOR_TESTS);
+        cache.put("1".to_string(), OwnedBytes::new(&b"abc"[..]));
+        cache.put("2".to_string(), OwnedBytes::new(&b"de"[..]));
+        cache.put("3".to_string(), OwnedBytes::new(&b"fghi"[..]));
+
+        cache.set_capacity_in_bytes(20);
+        assert!(cache.get(&"1".to_string()).is_some());
+        assert!(cache.get(&"2".to_string()).is_some());
+        assert!(cache.get(&"3".to_string()).is_some());
+
+        // "1" was accessed first, so it is the least recently used item.
+        cache.set_capacity_in_bytes(6);
+        assert!(cache.get(&"1".to_string()).is_none());
+        assert_eq!(cache.get(&"2".to_string()).unwrap(), &b"de"[..]);
+        assert_eq!(cache.get(&"3".to_string()).unwrap(), &b"fghi"[..]);
+
+        cache.set_capacity_in_bytes(0);
+        assert!(cache.get(&"2".to_string()).is_none());
+        assert!(cache.get(&"3".to_string()).is_none());
+
+        cache.set_capacity_in_bytes(5);
+        cache.put("4".to_string(), OwnedBytes::new(&b"jklmn"[..]));
+        assert_eq!(cache.get(&"4".to_string()).unwrap(), &b"jklmn"[..]);
+    }
+
     #[test]
     fn test_cache() {
         let cache = MemorySizedCache::with_capacity_in_bytes(10_000, &CACHE_METRICS_FOR_TESTS);
@@ -30,6 +30,7 @@
 //! etc.
 //!
 //! - The `BundleStorage` bundles together multiple files into a single file.
+mod bandwidth_scheduler;
 mod cache;
 mod debouncer;
 mod file_descriptor_cache;
@@ -52,6 +53,7 @@ mod opendal_storage;
 mod payload;
 mod prefix_storage;
 mod ram_storage;
+mod read_stats_storage;
 mod split;
 mod split_cache;
 mod storage_factory;
@@ -63,6 +65,9 @@ pub use split_cache::SplitCache;
 pub use tantivy::directory::OwnedBytes;
 pub use versioned_component::VersionedComponent;
 
+pub use self::bandwidth_scheduler::{
+    wrap_storage_with_bandwidth_scheduler, BandwidthScheduler, ReadPriority, ReadToken,
+};
 pub use self::bundle_storage::{BundleStorage, BundleStorageFileOffsets};
 #[cfg(any(test, feature = "testsuite"))]
 pub use self::cache::MockStorageCache;
@@ -80,6 +85,7 @@ pub use self::opendal_storage::new_emulated_google_cloud_storage;
 #[cfg(feature = "gcs")]
 pub use self::opendal_storage::GoogleCloudStorageFactory;
 pub use self::ram_storage::{RamStorage, RamStorageBuilder};
+pub use self::read_stats_storage::{wrap_storage_with_read_stats, StorageReadStats};
 pub use self::split::{SplitPayload, SplitPayloadBuilder};
 #[cfg(any(test, feature = "testsuite"))]
 pub use self::storage::MockStorage;
@@ -154,7 +160,7 @@ mod tests {
 #[cfg(any(test, feature = "integration-testsuite"))]
 pub(crate) mod test_suite {
 
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use anyhow::Context;
     use tokio::io::AsyncReadExt;
@@ -310,6 +316,25 @@ pub(crate) mod test_suite {
         Ok(())
     }
 
+    async fn test_list_files(storage: &mut dyn Storage) -> anyhow::Result<()> {
+        let test_paths = [Path::new("list_files/foo"), Path::new("list_files/bar/baz")];
+        for test_path in test_paths {
+            storage.put(test_path, Box::new(b"123".to_vec())).await?;
+        }
+        let files = storage.list_files(Path::new("")).await?;
+
+        for test_path in test_paths {
+            assert!(files.iter().any(|file| file == test_path));
+        }
+        assert!(!files.iter().any(|file| file == Path::new("list_files/bar")));
+
+        let files = storage.list_files(Path::new("list_files/bar")).await?;
+        assert_eq!(files, [PathBuf::from("list_files/bar/baz")]);
+
+        storage.bulk_delete(&test_paths).await?;
+        Ok(())
+    }
+
     /// Generic test suite for a storage.
     pub async fn storage_test_suite(storage: &mut dyn Storage) -> anyhow::Result<()> {
         test_get_inexistent_file(storage)
@@ -339,6 +364,7 @@ pub(crate) mod test_suite {
         test_delete_missing_file(storage)
             .await
             .context("delete_missing_file")?;
+        test_list_files(storage).await.context("list_files")?;
         Ok(())
     }
 
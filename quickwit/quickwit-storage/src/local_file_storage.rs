@@ -347,6 +347,29 @@ impl Storage for LocalFileStorage {
             }
         }
     }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut directories = vec![prefix.to_path_buf()];
+
+        while let Some(directory) = directories.pop() {
+            let mut read_dir = match tokio::fs::read_dir(self.root.join(&directory)).await {
+                Ok(read_dir) => read_dir,
+                // The root directory is only created when the first file is written.
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            while let Some(entry) = read_dir.next_entry().await? {
+                let path = directory.join(entry.file_name());
+                if entry.file_type().await?.is_dir() {
+                    directories.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        Ok(files)
+    }
 }
 
 /// A File storage resolver
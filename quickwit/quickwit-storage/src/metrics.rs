@@ -30,6 +30,8 @@ pub struct StorageMetrics {
     pub fast_field_cache: CacheMetrics,
     pub split_footer_cache: CacheMetrics,
     pub searcher_split_cache: CacheMetrics,
+    pub search_response_cache: CacheMetrics,
+    pub term_digests_cache: CacheMetrics,
     pub object_storage_get_total: IntCounter,
     pub object_storage_put_total: IntCounter,
     pub object_storage_put_parts: IntCounter,
@@ -44,8 +46,10 @@ impl Default for StorageMetrics {
             fd_cache_metrics: CacheMetrics::for_component("fd"),
             partial_request_cache: CacheMetrics::for_component("partial_request"),
             searcher_split_cache: CacheMetrics::for_component("searcher_split"),
+            search_response_cache: CacheMetrics::for_component("search_response"),
             shortlived_cache: CacheMetrics::for_component("shortlived"),
             split_footer_cache: CacheMetrics::for_component("splitfooter"),
+            term_digests_cache: CacheMetrics::for_component("term_digests"),
 
             object_storage_get_total: new_counter(
                 "object_storage_gets_total",
@@ -495,6 +495,29 @@ impl Storage for AzureBlobStorage {
         }
     }
 
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        // Joining an empty path appends a trailing separator to the prefix, so that listing
+        // `indexes` does not return the blobs of `indexes-archive`.
+        let prefix = self.blob_name(&prefix.join(""));
+        let mut list_blobs_stream = self
+            .container_client
+            .list_blobs()
+            .prefix(prefix)
+            .into_stream();
+        let mut files = Vec::new();
+
+        while let Some(list_blobs_result) = list_blobs_stream.next().await {
+            let list_blobs_response = list_blobs_result.map_err(AzureErrorWrapper::from)?;
+
+            for blob in list_blobs_response.blobs.blobs() {
+                if let Ok(relative_path) = Path::new(&blob.name).strip_prefix(&self.prefix) {
+                    files.push(relative_path.to_path_buf());
+                }
+            }
+        }
+        Ok(files)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
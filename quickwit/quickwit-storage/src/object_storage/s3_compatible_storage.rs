@@ -93,6 +93,7 @@ pub struct S3CompatibleObjectStorage {
     retry_params: RetryParams,
     disable_multi_object_delete: bool,
     disable_multipart_upload: bool,
+    disable_checksums: bool,
 }
 
 impl fmt::Debug for S3CompatibleObjectStorage {
@@ -129,11 +130,21 @@ fn get_region(s3_storage_config: &S3StorageConfig) -> Option<Region> {
     })
 }
 
+/// Region used for custom endpoints when no region is configured.
+const DEFAULT_REGION: &str = "us-east-1";
+
 async fn create_s3_client(s3_storage_config: &S3StorageConfig) -> S3Client {
     let aws_config = get_aws_config().await;
     let credentials_provider =
         get_credentials_provider(s3_storage_config).or(aws_config.credentials_provider().cloned());
-    let region = get_region(s3_storage_config).or(aws_config.region().cloned());
+    let mut region = get_region(s3_storage_config).or(aws_config.region().cloned());
+
+    // Self-hosted S3-compatible providers usually ignore the region, but the SDK refuses to sign
+    // requests without one.
+    if region.is_none() && s3_storage_config.endpoint().is_some() {
+        info!("using default S3 region `{DEFAULT_REGION}` with custom endpoint");
+        region = Some(Region::new(DEFAULT_REGION));
+    }
     let mut s3_config = aws_sdk_s3::Config::builder().region(region);
 
     s3_config.set_credentials_cache(aws_config.credentials_cache().cloned());
@@ -158,19 +169,30 @@ impl S3CompatibleObjectStorage {
         uri: Uri,
         bucket: String,
     ) -> Result<Self, StorageResolverError> {
+        let s3_storage_config = s3_storage_config.for_bucket(&bucket);
         let s3_client = create_s3_client(s3_storage_config).await;
         let retry_params = RetryParams::aggressive();
         let disable_multi_object_delete = s3_storage_config.disable_multi_object_delete;
         let disable_multipart_upload = s3_storage_config.disable_multipart_upload;
+        let disable_checksums = s3_storage_config.disable_checksums;
+        let mut multipart_policy = MultiPartPolicy::default();
+
+        if let Some(multipart_threshold) = s3_storage_config.multipart_threshold {
+            multipart_policy.multipart_threshold_num_bytes = multipart_threshold.as_u64();
+        }
+        if let Some(multipart_part_size) = s3_storage_config.multipart_part_size {
+            multipart_policy.target_part_num_bytes = multipart_part_size.as_u64() as usize;
+        }
         Ok(Self {
             s3_client,
             uri,
             bucket,
             prefix: PathBuf::new(),
-            multipart_policy: MultiPartPolicy::default(),
+            multipart_policy,
             retry_params,
             disable_multi_object_delete,
             disable_multipart_upload,
+            disable_checksums,
         })
     }
 
@@ -201,6 +223,7 @@ impl S3CompatibleObjectStorage {
             retry_params: self.retry_params,
             disable_multi_object_delete: self.disable_multi_object_delete,
             disable_multipart_upload: self.disable_multipart_upload,
+            disable_checksums: self.disable_checksums,
         }
     }
 
@@ -238,7 +261,7 @@ struct MultipartUploadId(pub String);
 struct Part {
     pub part_number: usize,
     pub range: Range<u64>,
-    pub md5: md5::Digest,
+    pub md5_opt: Option<md5::Digest>,
 }
 
 impl Part {
@@ -358,16 +381,19 @@ impl S3CompatibleObjectStorage {
         let mut parts = Vec::with_capacity(multipart_ranges.len());
 
         for (multipart_id, multipart_range) in multipart_ranges.into_iter().enumerate() {
-            let read = payload
-                .range_byte_stream(multipart_range.clone())
-                .await?
-                .into_async_read();
-            let md5 = compute_md5(read).await?;
-
+            let md5_opt = if self.disable_checksums {
+                None
+            } else {
+                let read = payload
+                    .range_byte_stream(multipart_range.clone())
+                    .await?
+                    .into_async_read();
+                Some(compute_md5(read).await?)
+            };
             let part = Part {
                 part_number: multipart_id + 1, // parts are 1-indexed
                 range: multipart_range,
-                md5,
+                md5_opt,
             };
             parts.push(part);
         }
@@ -386,7 +412,7 @@ impl S3CompatibleObjectStorage {
             .await
             .map_err(StorageError::from)
             .map_err(Retry::Permanent)?;
-        let md5 = BASE64_STANDARD.encode(part.md5.0);
+        let md5_opt = part.md5_opt.map(|md5| BASE64_STANDARD.encode(md5.0));
         crate::STORAGE_METRICS.object_storage_put_parts.inc();
         crate::STORAGE_METRICS
             .object_storage_upload_num_bytes
@@ -399,7 +425,7 @@ impl S3CompatibleObjectStorage {
             .key(key)
             .body(byte_stream)
             .content_length(part.len() as i64)
-            .content_md5(md5)
+            .set_content_md5(md5_opt)
             .part_number(part.part_number as i32)
             .upload_id(upload_id.0)
             .send()
@@ -817,6 +843,43 @@ impl Storage for S3CompatibleObjectStorage {
         Ok(head_object_output.content_length() as u64)
     }
 
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        let _permit = REQUEST_SEMAPHORE.acquire().await;
+        let bucket = self.bucket.clone();
+        // Joining an empty path appends a trailing separator to the prefix, so that listing
+        // `indexes` does not return the objects of `indexes-archive`.
+        let prefix = self.key(&prefix.join(""));
+        let mut files = Vec::new();
+        let mut continuation_token_opt: Option<String> = None;
+
+        loop {
+            let list_objects_output = aws_retry(&self.retry_params, || async {
+                self.s3_client
+                    .list_objects_v2()
+                    .bucket(&bucket)
+                    .prefix(&prefix)
+                    .set_continuation_token(continuation_token_opt.clone())
+                    .send()
+                    .await
+            })
+            .await?;
+
+            for object in list_objects_output.contents().unwrap_or_default() {
+                if let Some(key) = object.key() {
+                    files.push(self.relative_path(key));
+                }
+            }
+            continuation_token_opt = list_objects_output
+                .next_continuation_token()
+                .map(ToString::to_string);
+
+            if continuation_token_opt.is_none() {
+                break;
+            }
+        }
+        Ok(files)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
@@ -909,6 +972,7 @@ mod tests {
             retry_params: RetryParams::for_test(),
             disable_multi_object_delete: false,
             disable_multipart_upload: false,
+            disable_checksums: false,
         };
         assert_eq!(
             s3_storage.relative_path("indexes/foo"),
@@ -963,6 +1027,7 @@ mod tests {
             retry_params: RetryParams::for_test(),
             disable_multi_object_delete: true,
             disable_multipart_upload: false,
+            disable_checksums: false,
         };
         let _ = s3_storage
             .bulk_delete(&[Path::new("foo"), Path::new("bar")])
@@ -1007,6 +1072,7 @@ mod tests {
             retry_params: RetryParams::for_test(),
             disable_multi_object_delete: false,
             disable_multipart_upload: false,
+            disable_checksums: false,
         };
         let _ = s3_storage
             .bulk_delete(&[Path::new("foo"), Path::new("bar")])
@@ -1092,6 +1158,7 @@ mod tests {
             retry_params: RetryParams::for_test(),
             disable_multi_object_delete: false,
             disable_multipart_upload: false,
+            disable_checksums: false,
         };
         let bulk_delete_error = s3_storage
             .bulk_delete(&[
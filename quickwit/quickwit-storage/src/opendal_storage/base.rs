@@ -19,7 +19,7 @@
 
 use std::fmt;
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 use bytesize::ByteSize;
@@ -194,6 +194,18 @@ impl Storage for OpendalStorage {
         Ok(meta.content_length())
     }
 
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        // Directories are designated by a trailing separator.
+        let directory = format!("{}/", prefix.to_string_lossy().trim_end_matches('/'));
+        let entries = self.op.list_with(&directory).recursive(true).await?;
+        let files = entries
+            .into_iter()
+            .filter(|entry| entry.metadata().mode().is_file())
+            .map(|entry| PathBuf::from(entry.path()))
+            .collect();
+        Ok(files)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
@@ -119,6 +119,21 @@ impl Storage for PrefixStorage {
     async fn file_num_bytes(&self, path: &Path) -> crate::StorageResult<u64> {
         self.storage.file_num_bytes(&self.prefix.join(path)).await
     }
+
+    async fn list_files(&self, prefix: &Path) -> crate::StorageResult<Vec<PathBuf>> {
+        let files = self
+            .storage
+            .list_files(&self.prefix.join(prefix))
+            .await?
+            .into_iter()
+            .filter_map(|path| {
+                path.strip_prefix(&self.prefix)
+                    .ok()
+                    .map(|relative_path| relative_path.to_path_buf())
+            })
+            .collect();
+        Ok(files)
+    }
 }
 
 /// Creates a [`PrefixStorage`] using an underlying storage and a prefix.
@@ -191,7 +206,29 @@ mod tests {
     use std::collections::HashMap;
 
     use super::*;
-    use crate::DeleteFailure;
+    use crate::{DeleteFailure, RamStorage};
+
+    #[tokio::test]
+    async fn test_prefix_storage_list_files() {
+        let ram_storage = Arc::new(RamStorage::default());
+        for path in ["indexes/foo", "indexes/bar/baz", "other/qux"] {
+            ram_storage
+                .put(Path::new(path), Box::new(b"123".to_vec()))
+                .await
+                .unwrap();
+        }
+        let prefix_storage = add_prefix_to_storage(
+            ram_storage,
+            PathBuf::from("indexes"),
+            Uri::for_test("ram:///indexes"),
+        );
+        let mut files = prefix_storage.list_files(Path::new("")).await.unwrap();
+        files.sort();
+        assert_eq!(files, [PathBuf::from("bar/baz"), PathBuf::from("foo")]);
+
+        let files = prefix_storage.list_files(Path::new("bar")).await.unwrap();
+        assert_eq!(files, [PathBuf::from("bar/baz")]);
+    }
 
     #[test]
     fn test_strip_prefix_from_error() {
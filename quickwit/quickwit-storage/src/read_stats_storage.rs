@@ -0,0 +1,172 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use tokio::io::AsyncRead;
+
+use crate::storage::SendableAsync;
+use crate::{BulkDeleteError, OwnedBytes, PutPayload, Storage, StorageResult};
+
+/// Number of bytes and requests read from a storage, as recorded by a storage wrapped with
+/// [`wrap_storage_with_read_stats`].
+#[derive(Debug, Default)]
+pub struct StorageReadStats {
+    num_bytes_read: AtomicU64,
+    num_requests: AtomicU64,
+}
+
+impl StorageReadStats {
+    /// Returns the number of bytes read so far.
+    pub fn num_bytes_read(&self) -> u64 {
+        self.num_bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of read requests issued so far.
+    pub fn num_requests(&self) -> u64 {
+        self.num_requests.load(Ordering::Relaxed)
+    }
+
+    fn record_read(&self, num_bytes: u64) {
+        self.num_bytes_read.fetch_add(num_bytes, Ordering::Relaxed);
+        self.num_requests.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+struct ReadStatsStorage {
+    storage: Arc<dyn Storage>,
+    read_stats: Arc<StorageReadStats>,
+}
+
+#[async_trait]
+impl Storage for ReadStatsStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.storage.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.storage.put(path, payload).await
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        let num_bytes = self.storage.file_num_bytes(path).await?;
+        self.storage.copy_to(path, output).await?;
+        self.read_stats.record_read(num_bytes);
+        Ok(())
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<u64> {
+        let num_bytes = self.storage.copy_to_file(path, output_path).await?;
+        self.read_stats.record_read(num_bytes);
+        Ok(num_bytes)
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        let bytes = self.storage.get_slice(path, range).await?;
+        self.read_stats.record_read(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    async fn get_slice_stream(
+        &self,
+        path: &Path,
+        range: Range<usize>,
+    ) -> StorageResult<Box<dyn AsyncRead + Send + Unpin>> {
+        let num_bytes = range.len() as u64;
+        let stream = self.storage.get_slice_stream(path, range).await?;
+        self.read_stats.record_read(num_bytes);
+        Ok(stream)
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let bytes = self.storage.get_all(path).await?;
+        self.read_stats.record_read(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.storage.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        self.storage.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.storage.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.storage.file_num_bytes(path).await
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        self.storage.list_files(prefix).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.storage.uri()
+    }
+}
+
+/// Wraps the given storage so that the bytes and requests it serves are recorded in the returned
+/// [`StorageReadStats`].
+pub fn wrap_storage_with_read_stats(
+    storage: Arc<dyn Storage>,
+) -> (Arc<dyn Storage>, Arc<StorageReadStats>) {
+    let read_stats = Arc::new(StorageReadStats::default());
+    let read_stats_storage = ReadStatsStorage {
+        storage,
+        read_stats: read_stats.clone(),
+    };
+    (Arc::new(read_stats_storage), read_stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamStorage;
+
+    #[tokio::test]
+    async fn test_read_stats_storage() {
+        let ram_storage = RamStorage::default();
+        let path = Path::new("foo");
+        ram_storage
+            .put(path, Box::new(b"hello world".to_vec()))
+            .await
+            .unwrap();
+        let (storage, read_stats) = wrap_storage_with_read_stats(Arc::new(ram_storage));
+
+        storage.get_slice(path, 0..5).await.unwrap();
+        assert_eq!(read_stats.num_bytes_read(), 5);
+        assert_eq!(read_stats.num_requests(), 1);
+
+        storage.get_all(path).await.unwrap();
+        assert_eq!(read_stats.num_bytes_read(), 16);
+        assert_eq!(read_stats.num_requests(), 2);
+
+        storage.exists(path).await.unwrap();
+        assert_eq!(read_stats.num_requests(), 2);
+    }
+}
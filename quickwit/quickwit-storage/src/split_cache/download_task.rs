@@ -62,12 +62,21 @@ async fn perform_eviction_and_download(
     let split_cache_clone = split_cache.clone();
     let _ = tokio::task::spawn_blocking(move || {
         split_cache_clone.evict(&splits_to_delete[..]);
+        // The split may have been evicted recently, and its file not deleted yet.
+        split_cache_clone.cancel_split_file_deletion(split_ulid);
     })
     .await;
     let num_bytes =
         download_split(&split_cache.root_path, &split_to_download, storage_resolver).await?;
-    let mut shared_split_table_lock = split_cache.split_table.lock().unwrap();
-    shared_split_table_lock.register_as_downloaded(split_ulid, num_bytes);
+    split_cache
+        .split_table
+        .lock()
+        .unwrap()
+        .register_as_downloaded(split_ulid, num_bytes);
+    let _ = tokio::task::spawn_blocking(move || {
+        split_cache.record_downloaded_split(split_ulid, num_bytes);
+    })
+    .await;
     Ok(())
 }
 
@@ -0,0 +1,193 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use ulid::Ulid;
+
+/// Name of the file, stored in the split cache directory, listing the splits in cache.
+pub(crate) const MANIFEST_FILENAME: &str = "split_cache_manifest.json";
+
+const CHECKSUM_CHUNK_SIZE: usize = 1_000_000;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ManifestEntry {
+    pub num_bytes: u64,
+    /// Hex-encoded MD5 digest of the split file.
+    pub checksum: String,
+}
+
+/// Index of the split files present in the cache directory, persisted alongside them so that
+/// the cache can be reloaded after a restart.
+///
+/// A split file is only listed in the manifest once it has been fully downloaded and its
+/// checksum has been computed. Files that are not listed in the manifest upon startup are not
+/// trusted and get deleted.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SplitCacheManifest {
+    // Ulids are serialized as strings.
+    splits: BTreeMap<String, ManifestEntry>,
+}
+
+impl SplitCacheManifest {
+    /// Loads the manifest from the cache directory.
+    ///
+    /// A missing or corrupted manifest is not an error: we just start with an empty manifest,
+    /// which results in the cached split files being discarded.
+    pub fn load(root_path: &Path) -> SplitCacheManifest {
+        let manifest_path = root_path.join(MANIFEST_FILENAME);
+        let manifest_json = match std::fs::read(&manifest_path) {
+            Ok(manifest_json) => manifest_json,
+            Err(io_err) => {
+                if io_err.kind() != io::ErrorKind::NotFound {
+                    warn!(path=%manifest_path.display(), error=?io_err, "failed to read split cache manifest");
+                }
+                return SplitCacheManifest::default();
+            }
+        };
+        match serde_json::from_slice(&manifest_json) {
+            Ok(manifest) => manifest,
+            Err(serde_err) => {
+                warn!(path=%manifest_path.display(), error=?serde_err, "failed to parse split cache manifest");
+                SplitCacheManifest::default()
+            }
+        }
+    }
+
+    /// Atomically writes the manifest to the cache directory.
+    ///
+    /// The manifest is first written to a temporary file, which is then renamed.
+    pub fn persist(&self, root_path: &Path) -> io::Result<()> {
+        let manifest_json = serde_json::to_vec(self)?;
+        let manifest_path = root_path.join(MANIFEST_FILENAME);
+        let temp_manifest_path = temp_manifest_path(root_path);
+        let mut temp_file = File::create(&temp_manifest_path)?;
+        temp_file.write_all(&manifest_json)?;
+        temp_file.sync_all()?;
+        std::fs::rename(&temp_manifest_path, manifest_path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, split_ulid: Ulid) -> Option<&ManifestEntry> {
+        self.splits.get(&split_ulid.to_string())
+    }
+
+    pub fn insert(&mut self, split_ulid: Ulid, entry: ManifestEntry) {
+        self.splits.insert(split_ulid.to_string(), entry);
+    }
+
+    /// Removes the given splits from the manifest. Returns true if at least one split was
+    /// removed.
+    pub fn remove(&mut self, split_ulids: &[Ulid]) -> bool {
+        let mut removed = false;
+        for split_ulid in split_ulids {
+            removed |= self.splits.remove(&split_ulid.to_string()).is_some();
+        }
+        removed
+    }
+
+    /// Removes the entries for which `predicate` returns false.
+    pub fn retain(&mut self, mut predicate: impl FnMut(Ulid, &ManifestEntry) -> bool) {
+        self.splits.retain(|split_id, entry| {
+            Ulid::from_str(split_id)
+                .map(|split_ulid| predicate(split_ulid, entry))
+                .unwrap_or(false)
+        });
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.splits.len()
+    }
+}
+
+fn temp_manifest_path(root_path: &Path) -> PathBuf {
+    // The `.temp` extension ensures a leftover file gets cleaned up on startup.
+    root_path.join(format!("{MANIFEST_FILENAME}.temp"))
+}
+
+/// Computes the checksum of a file, reading it by chunks.
+pub(crate) fn compute_file_checksum(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut checksum = md5::Context::new();
+    let mut buf = vec![0; CHECKSUM_CHUNK_SIZE];
+    loop {
+        let read_len = file.read(&mut buf)?;
+        if read_len == 0 {
+            return Ok(format!("{:x}", checksum.compute()));
+        }
+        checksum.consume(&buf[..read_len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_cache_manifest_persist_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_path = temp_dir.path();
+
+        let manifest = SplitCacheManifest::load(root_path);
+        assert_eq!(manifest.len(), 0);
+
+        let mut manifest = SplitCacheManifest::default();
+        let split_ulid = Ulid::new();
+        let entry = ManifestEntry {
+            num_bytes: 3,
+            checksum: "checksum".to_string(),
+        };
+        manifest.insert(split_ulid, entry.clone());
+        manifest.persist(root_path).unwrap();
+        assert!(!temp_manifest_path(root_path).exists());
+
+        let mut manifest = SplitCacheManifest::load(root_path);
+        assert_eq!(manifest.get(split_ulid), Some(&entry));
+
+        assert!(manifest.remove(&[split_ulid]));
+        assert!(!manifest.remove(&[split_ulid]));
+        assert_eq!(manifest.len(), 0);
+    }
+
+    #[test]
+    fn test_split_cache_manifest_load_corrupted() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join(MANIFEST_FILENAME), b"{not json").unwrap();
+        let manifest = SplitCacheManifest::load(temp_dir.path());
+        assert_eq!(manifest.len(), 0);
+    }
+
+    #[test]
+    fn test_compute_file_checksum() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("file");
+        std::fs::write(&path, b"hello").unwrap();
+        assert_eq!(
+            compute_file_checksum(&path).unwrap(),
+            "5d41402abc4b2a76b9719d911017c592"
+        );
+    }
+}
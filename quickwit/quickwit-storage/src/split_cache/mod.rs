@@ -18,15 +18,17 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod download_task;
+mod manifest;
 mod split_table;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsStr;
-use std::io;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use std::{io, mem};
 
 use async_trait::async_trait;
 use quickwit_common::split_file;
@@ -34,14 +36,21 @@ use quickwit_common::uri::Uri;
 use quickwit_config::SplitCacheLimits;
 use quickwit_proto::search::ReportSplit;
 use tantivy::directory::OwnedBytes;
+use tokio::sync::OnceCell;
 use tracing::{error, info, instrument, warn};
 use ulid::Ulid;
 
 use crate::file_descriptor_cache::{FileDescriptorCache, SplitFile};
 use crate::split_cache::download_task::spawn_download_task;
+use crate::split_cache::manifest::{
+    compute_file_checksum, ManifestEntry, SplitCacheManifest, MANIFEST_FILENAME,
+};
 use crate::split_cache::split_table::SplitTable;
 use crate::{wrap_storage_with_cache, Storage, StorageCache};
 
+/// Interval at which the modifications of the manifest are persisted.
+const MANIFEST_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
 /// On disk Cache of splits for searchers.
 ///
 /// The search acts receives reports of splits.
@@ -52,9 +61,32 @@ pub struct SplitCache {
     // In memory structure, listing the splits we know about regardless
     // of whether they are in cache, being downloaded, or just available for download.
     split_table: Mutex<SplitTable>,
+    // Persisted index of the split files in cache, used to reload the cache after a restart.
+    manifest_state: Mutex<ManifestState>,
+    // Splits reloaded from a previous run, whose checksum still needs to be checked. This is done
+    // lazily, on first access, to avoid reading the whole cache on startup.
+    pending_validations: Mutex<HashMap<Ulid, Arc<PendingValidation>>>,
     fd_cache: FileDescriptorCache,
 }
 
+/// In-memory copy of the manifest. Its modifications are batched and periodically persisted by
+/// the manifest flush task, since the whole manifest is rewritten every time.
+#[derive(Default)]
+struct ManifestState {
+    manifest: SplitCacheManifest,
+    // Whether the manifest was modified since it was last persisted.
+    is_dirty: bool,
+    // Split files evicted from the cache. They are deleted only once the manifest no longer
+    // listing them has been persisted, so that a file listed in the persisted manifest is never
+    // missing or partially deleted.
+    splits_to_delete: Vec<Ulid>,
+}
+
+struct PendingValidation {
+    expected_checksum: String,
+    is_valid: OnceCell<bool>,
+}
+
 impl SplitCache {
     /// Creates a new SplitCache and spawns the task that will continuously search for
     /// download opportunities.
@@ -64,7 +96,9 @@ impl SplitCache {
         limits: SplitCacheLimits,
     ) -> io::Result<Arc<SplitCache>> {
         std::fs::create_dir_all(&root_path)?;
+        let mut manifest = SplitCacheManifest::load(&root_path);
         let mut existing_splits: BTreeMap<Ulid, u64> = Default::default();
+        let mut pending_validations: HashMap<Ulid, Arc<PendingValidation>> = HashMap::new();
         for dir_entry_res in std::fs::read_dir(&root_path)? {
             let dir_entry = dir_entry_res?;
             let path = dir_entry.path();
@@ -72,6 +106,9 @@ impl SplitCache {
             if meta.is_dir() {
                 continue;
             }
+            if path.file_name() == Some(OsStr::new(MANIFEST_FILENAME)) {
+                continue;
+            }
             let ext = path.extension().and_then(OsStr::to_str).unwrap_or("");
             match ext {
                 "temp" => {
@@ -86,7 +123,23 @@ impl SplitCache {
                 }
                 "split" => {
                     if let Some(split_ulid) = split_id_from_path(&path) {
-                        existing_splits.insert(split_ulid, meta.len());
+                        match manifest.get(split_ulid) {
+                            Some(entry) if entry.num_bytes == meta.len() => {
+                                existing_splits.insert(split_ulid, meta.len());
+                                let pending_validation = PendingValidation {
+                                    expected_checksum: entry.checksum.clone(),
+                                    is_valid: OnceCell::new(),
+                                };
+                                pending_validations
+                                    .insert(split_ulid, Arc::new(pending_validation));
+                            }
+                            _ => {
+                                // We cannot vouch for the integrity of this file: it may have been
+                                // written by a download that did not complete.
+                                warn!(path=%path.display(), "split file missing from the split cache manifest, removing");
+                                delete_evicted_splits(&root_path, &[split_ulid]);
+                            }
+                        }
                     } else {
                         warn!(path=%path.display(), ".split file with invalid ulid in split cache directory, ignoring");
                     }
@@ -96,6 +149,11 @@ impl SplitCache {
                 }
             }
         }
+        manifest.retain(|split_ulid, _| existing_splits.contains_key(&split_ulid));
+        info!(
+            num_splits = existing_splits.len(),
+            "reloaded splits from the searcher split cache directory"
+        );
         let mut split_table = SplitTable::with_limits_and_existing_splits(limits, existing_splits);
 
         // In case of a setting change, it could be useful to evict some splits on startup.
@@ -106,11 +164,24 @@ impl SplitCache {
                 "Evicting splits from the searcher cache. Has the node configuration changed?"
             );
             delete_evicted_splits(&root_path, &splits_to_remove[..]);
+            manifest.remove(&splits_to_remove[..]);
+            for split_ulid in &splits_to_remove {
+                pending_validations.remove(split_ulid);
+            }
+        }
+        if let Err(io_err) = manifest.persist(&root_path) {
+            error!(error=?io_err, "failed to persist split cache manifest");
         }
         let fd_cache = FileDescriptorCache::with_fd_cache_capacity(limits.max_file_descriptors);
+        let manifest_state = ManifestState {
+            manifest,
+            ..Default::default()
+        };
         let split_cache = Arc::new(SplitCache {
             root_path,
             split_table: Mutex::new(split_table),
+            manifest_state: Mutex::new(manifest_state),
+            pending_validations: Mutex::new(pending_validations),
             fd_cache,
         });
 
@@ -119,15 +190,130 @@ impl SplitCache {
             storage_resolver,
             limits.num_concurrent_downloads,
         );
+        spawn_manifest_flush_task(Arc::downgrade(&split_cache));
 
         Ok(split_cache)
     }
 
-    /// Remove splits from both the fd cache and the split cache.
+    /// Remove splits from both the fd cache and the split cache. The split files are deleted on
+    /// the next flush of the manifest.
     /// This method does NOT update the split table.
     pub(crate) fn evict(&self, splits_to_evict: &[Ulid]) {
+        if splits_to_evict.is_empty() {
+            return;
+        }
+        {
+            let mut pending_validations = self.pending_validations.lock().unwrap();
+            for split_ulid in splits_to_evict {
+                pending_validations.remove(split_ulid);
+            }
+        }
+        {
+            let mut manifest_state = self.manifest_state.lock().unwrap();
+            manifest_state.is_dirty |= manifest_state.manifest.remove(splits_to_evict);
+            manifest_state
+                .splits_to_delete
+                .extend_from_slice(splits_to_evict);
+        }
         self.fd_cache.evict_split_files(splits_to_evict);
-        delete_evicted_splits(&self.root_path, splits_to_evict);
+    }
+
+    /// Cancels the pending deletion of a split file evicted earlier, before downloading it
+    /// again.
+    pub(crate) fn cancel_split_file_deletion(&self, split_ulid: Ulid) {
+        self.manifest_state
+            .lock()
+            .unwrap()
+            .splits_to_delete
+            .retain(|split_to_delete| *split_to_delete != split_ulid);
+    }
+
+    /// Persists the manifest if it was modified, then deletes the files of the evicted splits.
+    ///
+    /// This method performs blocking IO.
+    pub(crate) fn flush_manifest(&self) {
+        let mut manifest_state = self.manifest_state.lock().unwrap();
+
+        if manifest_state.is_dirty {
+            if let Err(io_err) = manifest_state.manifest.persist(&self.root_path) {
+                // The evicted split files are kept until the manifest is successfully persisted.
+                error!(error=?io_err, "failed to persist split cache manifest");
+                return;
+            }
+            manifest_state.is_dirty = false;
+        }
+        // The files are deleted while holding the lock so that the deletion of a split file cannot
+        // be cancelled concurrently.
+        let splits_to_delete = mem::take(&mut manifest_state.splits_to_delete);
+        delete_evicted_splits(&self.root_path, &splits_to_delete);
+    }
+
+    /// Records a freshly downloaded split file in the manifest, computing its checksum.
+    ///
+    /// This method performs blocking IO.
+    pub(crate) fn record_downloaded_split(&self, split_ulid: Ulid, num_bytes: u64) {
+        let split_path = self.root_path.join(split_file(split_ulid));
+        let checksum = match compute_file_checksum(&split_path) {
+            Ok(checksum) => checksum,
+            Err(io_err) => {
+                // The split will simply not survive a restart.
+                error!(path=%split_path.display(), error=?io_err, "failed to compute split file checksum");
+                return;
+            }
+        };
+        let entry = ManifestEntry {
+            num_bytes,
+            checksum,
+        };
+        let mut manifest_state = self.manifest_state.lock().unwrap();
+        manifest_state.manifest.insert(split_ulid, entry);
+        manifest_state.is_dirty = true;
+    }
+
+    /// Checks the integrity of a split file reloaded from a previous run, the first time it is
+    /// accessed. Corrupted files are evicted from the cache.
+    ///
+    /// Returns false if the split file is corrupted.
+    async fn validate_split_file_if_necessary(self: &Arc<Self>, split_ulid: Ulid) -> bool {
+        let Some(pending_validation) = self
+            .pending_validations
+            .lock()
+            .unwrap()
+            .get(&split_ulid)
+            .cloned()
+        else {
+            return true;
+        };
+        let split_path = self.root_path.join(split_file(split_ulid));
+        let is_valid = *pending_validation
+            .is_valid
+            .get_or_init(|| async {
+                let split_path_clone = split_path.clone();
+                let checksum_res =
+                    tokio::task::spawn_blocking(move || compute_file_checksum(&split_path_clone))
+                        .await;
+                let expected_checksum = &pending_validation.expected_checksum;
+                matches!(checksum_res, Ok(Ok(checksum)) if &checksum == expected_checksum)
+            })
+            .await;
+        self.pending_validations.lock().unwrap().remove(&split_ulid);
+
+        if !is_valid {
+            error!(path=%split_path.display(), "split file in cache is corrupted, evicting");
+            let was_on_disk = self
+                .split_table
+                .lock()
+                .unwrap()
+                .remove_on_disk_split(split_ulid);
+            if was_on_disk {
+                let split_cache = self.clone();
+                let _ = tokio::task::spawn_blocking(move || {
+                    split_cache.evict(&[split_ulid]);
+                })
+                .await;
+            }
+        }
+        is_valid
     }
 
     /// Wraps a storage with our split cache.
@@ -157,7 +343,11 @@ impl SplitCache {
 
     // Returns a split guard object. As long as it is not dropped, the
     // split won't be evinced from the cache.
-    async fn get_split_file(&self, split_id: Ulid, storage_uri: &Uri) -> Option<SplitFile> {
+    async fn get_split_file(
+        self: &Arc<Self>,
+        split_id: Ulid,
+        storage_uri: &Uri,
+    ) -> Option<SplitFile> {
         // We touch before even checking the fd cache in order to update the file's last access time
         // for the file cache.
         let num_bytes_opt: Option<u64> = self
@@ -167,6 +357,9 @@ impl SplitCache {
             .touch(split_id, storage_uri);
 
         let num_bytes = num_bytes_opt?;
+        if !self.validate_split_file_if_necessary(split_id).await {
+            return None;
+        }
         self.fd_cache
             .get_or_open_split_file(&self.root_path, split_id, num_bytes)
             .await
@@ -174,6 +367,21 @@ impl SplitCache {
     }
 }
 
+/// Spawns the task that periodically persists the manifest and deletes the evicted split files.
+/// The task stops once the split cache is dropped.
+fn spawn_manifest_flush_task(weak_split_cache: Weak<SplitCache>) {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(MANIFEST_FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(split_cache) = weak_split_cache.upgrade() else {
+                return;
+            };
+            let _ = tokio::task::spawn_blocking(move || split_cache.flush_manifest()).await;
+        }
+    });
+}
+
 /// Removes the evicted split files from the file system.
 /// This function just logs errors, and swallows them.
 ///
@@ -249,3 +457,130 @@ impl StorageCache for SplitCacheBackingStorage {
     async fn put(&self, _path: PathBuf, _byte_range: Range<usize>, _bytes: OwnedBytes) {}
     async fn put_all(&self, _path: PathBuf, _bytes: OwnedBytes) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use bytesize::ByteSize;
+
+    use super::*;
+    use crate::StorageResolver;
+
+    fn test_split_cache_limits() -> SplitCacheLimits {
+        SplitCacheLimits {
+            max_num_bytes: ByteSize::mb(10),
+            max_num_splits: NonZeroU32::new(10).unwrap(),
+            num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+            max_file_descriptors: NonZeroU32::new(10).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_split_cache_reloads_splits_after_restart() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_path = temp_dir.path().to_path_buf();
+        let storage_uri = Uri::for_test("s3://test-bucket/index");
+
+        let valid_split = Ulid::new();
+        let corrupted_split = Ulid::new();
+        let untracked_split = Ulid::new();
+
+        let mut manifest = SplitCacheManifest::default();
+        for split_ulid in [valid_split, corrupted_split] {
+            let split_path = root_path.join(split_file(split_ulid));
+            std::fs::write(&split_path, b"split-payload").unwrap();
+            let entry = ManifestEntry {
+                num_bytes: 13,
+                checksum: compute_file_checksum(&split_path).unwrap(),
+            };
+            manifest.insert(split_ulid, entry);
+        }
+        manifest.persist(&root_path).unwrap();
+
+        // Same size, different content.
+        std::fs::write(
+            root_path.join(split_file(corrupted_split)),
+            b"split-pAyload",
+        )
+        .unwrap();
+        std::fs::write(root_path.join(split_file(untracked_split)), b"untracked").unwrap();
+
+        let split_cache = SplitCache::with_root_path(
+            root_path.clone(),
+            StorageResolver::unconfigured(),
+            test_split_cache_limits(),
+        )
+        .unwrap();
+
+        assert!(!root_path.join(split_file(untracked_split)).exists());
+
+        let split_file_opt = split_cache.get_split_file(valid_split, &storage_uri).await;
+        let bytes = split_file_opt.unwrap().get_all().await.unwrap();
+        assert_eq!(bytes.as_slice(), b"split-payload");
+
+        assert!(split_cache
+            .get_split_file(corrupted_split, &storage_uri)
+            .await
+            .is_none());
+        // The corrupted split file is deleted once the manifest is flushed.
+        split_cache.flush_manifest();
+        assert!(!root_path.join(split_file(corrupted_split)).exists());
+
+        let manifest = SplitCacheManifest::load(&root_path);
+        assert!(manifest.get(valid_split).is_some());
+        assert!(manifest.get(corrupted_split).is_none());
+        assert!(manifest.get(untracked_split).is_none());
+    }
+
+    #[test]
+    fn test_split_cache_batches_manifest_updates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_path = temp_dir.path().to_path_buf();
+        let limits = test_split_cache_limits();
+        let split_table = SplitTable::with_limits_and_existing_splits(limits, BTreeMap::new());
+        let split_cache = SplitCache {
+            root_path: root_path.clone(),
+            split_table: Mutex::new(split_table),
+            manifest_state: Mutex::new(ManifestState::default()),
+            pending_validations: Mutex::new(HashMap::new()),
+            fd_cache: FileDescriptorCache::with_fd_cache_capacity(limits.max_file_descriptors),
+        };
+        let split_ulid = Ulid::new();
+        let split_path = root_path.join(split_file(split_ulid));
+        std::fs::write(&split_path, b"split-payload").unwrap();
+
+        split_cache.record_downloaded_split(split_ulid, 13);
+        assert!(SplitCacheManifest::load(&root_path)
+            .get(split_ulid)
+            .is_none());
+
+        split_cache.flush_manifest();
+        assert!(SplitCacheManifest::load(&root_path)
+            .get(split_ulid)
+            .is_some());
+
+        // The split file is deleted only after the manifest no longer listing it is persisted.
+        split_cache.evict(&[split_ulid]);
+        assert!(split_path.exists());
+        assert!(SplitCacheManifest::load(&root_path)
+            .get(split_ulid)
+            .is_some());
+
+        split_cache.flush_manifest();
+        assert!(!split_path.exists());
+        assert!(SplitCacheManifest::load(&root_path)
+            .get(split_ulid)
+            .is_none());
+
+        // Downloading an evicted split again cancels the deletion of its file.
+        std::fs::write(&split_path, b"split-payload").unwrap();
+        split_cache.record_downloaded_split(split_ulid, 13);
+        split_cache.flush_manifest();
+
+        split_cache.evict(&[split_ulid]);
+        split_cache.cancel_split_file_deletion(split_ulid);
+        split_cache.flush_manifest();
+        assert!(split_path.exists());
+    }
+}
@@ -332,6 +332,21 @@ impl SplitTable {
         self.change_split_status(split_ulid, Status::OnDisk { num_bytes });
     }
 
+    /// Removes a split that is on disk from the table, for instance because its file turned out
+    /// to be corrupted. The file itself is NOT deleted.
+    ///
+    /// Returns false if the split was not on disk.
+    pub(crate) fn remove_on_disk_split(&mut self, split_ulid: Ulid) -> bool {
+        let Some(split_info) = self.remove(split_ulid) else {
+            return false;
+        };
+        if !matches!(split_info.status, Status::OnDisk { .. }) {
+            self.insert(split_info);
+            return false;
+        }
+        true
+    }
+
     /// Change the state of the given split from candidate to downloading state,
     /// and returns its URI.
     ///
@@ -662,4 +677,31 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_split_table_remove_on_disk_split() {
+        let split_ulids = sorted_split_ulids(2);
+        let existing_splits = [(split_ulids[0], 1_000)].into_iter().collect();
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::mb(10),
+                max_num_splits: NonZeroU32::new(5).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            existing_splits,
+        );
+        split_table.report(split_ulids[1], Uri::for_test(TEST_STORAGE_URI));
+        assert_eq!(split_table.num_bytes(), 1_000);
+
+        assert!(!split_table.remove_on_disk_split(split_ulids[1]));
+        assert!(split_table.start_download(split_ulids[1]).is_some());
+
+        assert!(split_table.remove_on_disk_split(split_ulids[0]));
+        assert_eq!(split_table.num_bytes(), 0);
+        assert!(!split_table.remove_on_disk_split(split_ulids[0]));
+        assert!(split_table
+            .touch(split_ulids[0], &Uri::for_test(TEST_STORAGE_URI))
+            .is_none());
+    }
 }
@@ -138,6 +138,19 @@ pub trait Storage: fmt::Debug + Send + Sync + 'static {
     /// Returns a file size.
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64>;
 
+    /// Lists the files located under the `prefix` "directory" of the storage, including the files
+    /// of its "subdirectories", in no particular order. An empty prefix lists all the files of the
+    /// storage. The paths are relative to the root of the storage.
+    ///
+    /// Listing is meant for maintenance operations and may be slow on large storages. Storages
+    /// that cannot list their files return an error.
+    async fn list_files(&self, _prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        Err(StorageErrorKind::Internal.with_error(anyhow::anyhow!(
+            "storage `{}` does not support listing files",
+            self.uri()
+        )))
+    }
+
     /// Returns an URI identifying the storage
     fn uri(&self) -> &Uri;
 }
@@ -52,7 +52,9 @@ use tracing::debug;
 /// set of services.
 #[derive(Clone)]
 pub struct TestNodeConfig {
+    /// Configuration of the node.
     pub node_config: NodeConfig,
+    /// Services run by the node.
     pub services: HashSet<QuickwitService>,
 }
 
@@ -84,13 +86,20 @@ impl ClusterShutdownTrigger {
 /// The goal is to start several nodes and use the gRPC or REST clients to
 /// test it.
 ///
+/// The REST clients target the first searcher and the first indexer of the cluster, so the
+/// cluster must run at least one of each.
+///
 /// WARNING: Currently, we cannot start an indexer in a different test as it will
 /// will share the same `INGEST_API_SERVICE_INSTANCE`. The ingest API will be
 /// dropped by the first running test and the other tests will fail.
 pub struct ClusterSandbox {
+    /// Configurations of the nodes of the cluster.
     pub node_configs: Vec<TestNodeConfig>,
+    /// REST client of the first searcher.
     pub searcher_rest_client: QuickwitClient,
+    /// REST client of the first indexer.
     pub indexer_rest_client: QuickwitClient,
+    /// OTLP trace service client of the first indexer.
     pub trace_client: TraceServiceClient<tonic::transport::Channel>,
     _temp_dir: TempDir,
     join_handles: Vec<JoinHandle<Result<HashMap<String, ActorExitStatus>, anyhow::Error>>>,
@@ -104,14 +113,17 @@ fn transport_url(addr: SocketAddr) -> Url {
     url
 }
 
+/// Builds an [`IngestSource`] from a JSON literal.
 #[macro_export]
 macro_rules! ingest_json {
     ($($json:tt)+) => {
-        quickwit_rest_client::models::IngestSource::Str(json!($($json)+).to_string())
+        $crate::IngestSource::Str($crate::serde_json::json!($($json)+).to_string())
     };
 }
 
-pub(crate) async fn ingest_with_retry(
+/// Ingests documents into an index, retrying until the ingest API accepts them or a 10 seconds
+/// timeout elapses. Indexes are not ready to accept documents right after their creation.
+pub async fn ingest_with_retry(
     client: &QuickwitClient,
     index_id: &str,
     ingest_source: IngestSource,
@@ -142,6 +154,8 @@ pub(crate) async fn ingest_with_retry(
 }
 
 impl ClusterSandbox {
+    /// Starts one node per node config. The data of the nodes is stored in `temp_dir`, which is
+    /// deleted when the sandbox is dropped.
     pub async fn start_cluster_with_configs(
         temp_dir: TempDir,
         node_configs: Vec<TestNodeConfig>,
@@ -212,12 +226,13 @@ impl ClusterSandbox {
         })
     }
 
+    /// Makes the REST clients use the ingest V2 API.
     pub fn enable_ingest_v2(&mut self) {
         self.indexer_rest_client.enable_ingest_v2();
         self.searcher_rest_client.enable_ingest_v2();
     }
 
-    // Starts one node that runs all the services.
+    /// Starts one node that runs all the services.
     pub async fn start_standalone_node() -> anyhow::Result<Self> {
         let temp_dir = tempfile::tempdir()?;
         let services = QuickwitService::supported_services();
@@ -246,6 +261,8 @@ impl ClusterSandbox {
         Ok(sandbox)
     }
 
+    /// Starts nodes with corresponding services given by `nodes_services`, with the OTLP
+    /// endpoints enabled on the indexers.
     pub async fn start_cluster_with_otlp_service(
         nodes_services: &[HashSet<QuickwitService>],
     ) -> anyhow::Result<Self> {
@@ -260,7 +277,7 @@ impl ClusterSandbox {
         Self::start_cluster_with_configs(temp_dir, node_configs).await
     }
 
-    // Starts nodes with corresponding services given by `nodes_services`.
+    /// Starts nodes with corresponding services given by `nodes_services`.
     pub async fn start_cluster_nodes(
         nodes_services: &[HashSet<QuickwitService>],
     ) -> anyhow::Result<Self> {
@@ -269,6 +286,23 @@ impl ClusterSandbox {
         Self::start_cluster_with_configs(temp_dir, node_configs).await
     }
 
+    /// Starts nodes with corresponding services given by `nodes_services`, sharing the metastore
+    /// located at `metastore_uri`, for instance a PostgreSQL database. Resolving PostgreSQL URIs
+    /// requires the `postgres` feature.
+    pub async fn start_cluster_nodes_with_metastore_uri(
+        nodes_services: &[HashSet<QuickwitService>],
+        metastore_uri: QuickwitUri,
+    ) -> anyhow::Result<Self> {
+        let temp_dir = tempfile::tempdir()?;
+        let node_configs = build_node_configs_with_metastore_uri(
+            temp_dir.path().to_path_buf(),
+            nodes_services,
+            metastore_uri,
+        );
+        Self::start_cluster_with_configs(temp_dir, node_configs).await
+    }
+
+    /// Waits until the cluster has `expected_num_ready_nodes` ready nodes.
     pub async fn wait_for_cluster_num_ready_nodes(
         &self,
         expected_num_ready_nodes: usize,
@@ -301,7 +335,7 @@ impl ClusterSandbox {
         Ok(())
     }
 
-    // Waits for the needed number of indexing pipeline to start.
+    /// Waits for the needed number of indexing pipeline to start.
     pub async fn wait_for_indexing_pipelines(
         &self,
         required_pipeline_num: usize,
@@ -333,7 +367,7 @@ impl ClusterSandbox {
         Ok(())
     }
 
-    // Waits for the needed number of indexing pipeline to start.
+    /// Waits until the index has `required_splits_num` splits in the given states.
     pub async fn wait_for_splits(
         &self,
         index_id: &str,
@@ -379,6 +413,7 @@ impl ClusterSandbox {
         Ok(())
     }
 
+    /// Shuts down all the nodes and returns the exit statuses of their actors.
     pub async fn shutdown(self) -> Result<Vec<HashMap<String, ActorExitStatus>>, anyhow::Error> {
         // We need to drop rest clients first because reqwest can hold connections open
         // preventing rest server's graceful shutdown.
@@ -405,6 +440,19 @@ impl ClusterSandbox {
 pub fn build_node_configs(
     root_data_dir: PathBuf,
     nodes_services: &[HashSet<QuickwitService>],
+) -> Vec<TestNodeConfig> {
+    let unique_dir_name = new_coolid("test-dir");
+    let metastore_uri =
+        QuickwitUri::from_str(&format!("ram:///{unique_dir_name}/metastore")).unwrap();
+    build_node_configs_with_metastore_uri(root_data_dir, nodes_services, metastore_uri)
+}
+
+/// Same as [`build_node_configs`], except that the nodes share the metastore located at
+/// `metastore_uri`.
+pub fn build_node_configs_with_metastore_uri(
+    root_data_dir: PathBuf,
+    nodes_services: &[HashSet<QuickwitService>],
+    metastore_uri: QuickwitUri,
 ) -> Vec<TestNodeConfig> {
     let cluster_id = new_coolid("test-cluster");
     let mut node_configs = Vec::new();
@@ -416,8 +464,7 @@ pub fn build_node_configs(
         config.cluster_id = cluster_id.clone();
         config.node_id = format!("test-node-{node_idx}");
         config.data_dir_path = root_data_dir.join(&config.node_id);
-        config.metastore_uri =
-            QuickwitUri::from_str(&format!("ram:///{unique_dir_name}/metastore")).unwrap();
+        config.metastore_uri = metastore_uri.clone();
         config.default_index_root_uri =
             QuickwitUri::from_str(&format!("ram:///{unique_dir_name}/indexes")).unwrap();
         peers.push(config.gossip_advertise_addr.to_string());
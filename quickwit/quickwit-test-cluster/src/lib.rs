@@ -0,0 +1,40 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! In-process Quickwit cluster for integration tests.
+//!
+//! [`ClusterSandbox`] starts one or several Quickwit nodes in the current process, backed by a
+//! temporary directory and an in-memory or PostgreSQL metastore, and exposes REST and gRPC clients
+//! to interact with them. It lets applications integrating with Quickwit's APIs run hermetic
+//! integration tests.
+
+#![warn(missing_docs)]
+#![deny(clippy::disallowed_methods)]
+
+mod cluster_sandbox;
+
+pub use cluster_sandbox::{
+    build_node_configs, build_node_configs_with_metastore_uri, ingest_with_retry, ClusterSandbox,
+    TestNodeConfig,
+};
+pub use quickwit_config::service::QuickwitService;
+pub use quickwit_rest_client::models::IngestSource;
+pub use quickwit_rest_client::rest_client::{CommitType, QuickwitClient};
+#[doc(hidden)]
+pub use serde_json;
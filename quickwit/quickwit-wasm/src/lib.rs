@@ -0,0 +1,124 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+#![deny(clippy::disallowed_methods)]
+
+//! Sandboxed runtime for the WebAssembly plugins extending Quickwit without forking it, such as
+//! the document transforms of the sources and the scoring functions of the searchers.
+//!
+//! # Plugin ABI
+//!
+//! Plugins are WebAssembly modules, in binary or text format, importing nothing and exporting:
+//! - `memory`, their linear memory;
+//! - `alloc(len: i32) -> i32`, which allocates `len` bytes and returns their address;
+//! - optionally `dealloc(ptr: i32, len: i32)`, which frees the buffers allocated by `alloc` and the
+//!   outputs once the host has read them;
+//! - the plugin functions themselves, with the signature `(ptr: i32, len: i32) -> i64`. They take
+//!   their input, a JSON document, from the `len` bytes at address `ptr`, and return the address of
+//!   their output in the 32 high bits and its length in the 32 low bits, or a negative value when
+//!   they have no output.
+//!
+//! Every call is granted a fixed amount of fuel, so that a plugin looping forever is interrupted,
+//! and the linear memory of a plugin cannot grow beyond a configured size.
+
+#[cfg(feature = "wasmtime")]
+mod runtime;
+#[cfg(not(feature = "wasmtime"))]
+mod runtime_disabled;
+
+use std::path::Path;
+
+use quickwit_config::WasmPluginConfig;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "wasmtime")]
+pub use crate::runtime::{WasmPlugin, WasmPluginInstance};
+#[cfg(not(feature = "wasmtime"))]
+pub use crate::runtime_disabled::{WasmPlugin, WasmPluginInstance};
+
+/// Name of the function plugins export to allocate the buffers of their inputs.
+pub const ALLOC_FUNCTION_NAME: &str = "alloc";
+
+/// Name of the function plugins may export to free the buffers of their inputs and outputs.
+pub const DEALLOC_FUNCTION_NAME: &str = "dealloc";
+
+/// Resources granted to a plugin.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WasmPluginLimits {
+    /// Fuel granted to each call. Executing a WebAssembly instruction consumes about one unit of
+    /// fuel.
+    pub max_fuel_per_call: u64,
+    /// Maximum size of the linear memory of a plugin instance.
+    pub max_memory_num_bytes: usize,
+}
+
+impl From<&WasmPluginConfig> for WasmPluginLimits {
+    fn from(wasm_plugin_config: &WasmPluginConfig) -> Self {
+        Self {
+            max_fuel_per_call: wasm_plugin_config.max_fuel_per_call,
+            max_memory_num_bytes: wasm_plugin_config.max_memory.as_u64() as usize,
+        }
+    }
+}
+
+/// Reads and compiles the plugin described by a plugin config.
+pub fn load_wasm_plugin(wasm_plugin_config: &WasmPluginConfig) -> WasmPluginResult<WasmPlugin> {
+    let limits = WasmPluginLimits::from(wasm_plugin_config);
+    WasmPlugin::load(Path::new(&wasm_plugin_config.path), limits)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WasmPluginError {
+    #[error("WASM plugins are not enabled: please recompile with the `wasm` feature")]
+    NotEnabled,
+    #[error("failed to load WASM plugin: {0}")]
+    Load(String),
+    #[error("WASM plugin does not export function `{0}`")]
+    MissingFunction(String),
+    #[error("WASM plugin ran out of fuel")]
+    OutOfFuel,
+    #[error("WASM plugin trapped: {0}")]
+    Trap(String),
+    #[error("WASM plugin input is invalid: {0}")]
+    InvalidInput(String),
+    #[error("WASM plugin output is invalid: {0}")]
+    InvalidOutput(String),
+}
+
+pub type WasmPluginResult<T> = Result<T, WasmPluginError>;
+
+impl WasmPluginInstance {
+    /// Calls a plugin function with a value serialized to JSON and deserializes its JSON output,
+    /// if any.
+    pub fn call_json<I: Serialize, O: DeserializeOwned>(
+        &mut self,
+        function_name: &str,
+        input: &I,
+    ) -> WasmPluginResult<Option<O>> {
+        let input_json = serde_json::to_vec(input)
+            .map_err(|error| WasmPluginError::InvalidInput(error.to_string()))?;
+        let Some(output_json) = self.call(function_name, &input_json)? else {
+            return Ok(None);
+        };
+        let output = serde_json::from_slice(&output_json)
+            .map_err(|error| WasmPluginError::InvalidOutput(error.to_string()))?;
+        Ok(Some(output))
+    }
+}
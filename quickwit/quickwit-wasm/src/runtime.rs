@@ -0,0 +1,322 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::path::Path;
+
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    TypedFunc,
+};
+
+use crate::{
+    WasmPluginError, WasmPluginLimits, WasmPluginResult, ALLOC_FUNCTION_NAME, DEALLOC_FUNCTION_NAME,
+};
+
+/// A compiled plugin. Cloning it is cheap and does not recompile the module.
+#[derive(Clone)]
+pub struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+    limits: WasmPluginLimits,
+}
+
+impl fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
+impl WasmPlugin {
+    /// Compiles a plugin from a WebAssembly module in binary or text format.
+    pub fn compile(wasm: &[u8], limits: WasmPluginLimits) -> WasmPluginResult<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine =
+            Engine::new(&config).map_err(|error| WasmPluginError::Load(format!("{error:#}")))?;
+        let module = Module::new(&engine, wasm)
+            .map_err(|error| WasmPluginError::Load(format!("{error:#}")))?;
+        let plugin = Self {
+            engine,
+            module,
+            limits,
+        };
+        if !plugin.exports_function(ALLOC_FUNCTION_NAME) {
+            return Err(WasmPluginError::MissingFunction(
+                ALLOC_FUNCTION_NAME.to_string(),
+            ));
+        }
+        Ok(plugin)
+    }
+
+    /// Reads and compiles a plugin from a local file.
+    pub fn load(path: &Path, limits: WasmPluginLimits) -> WasmPluginResult<Self> {
+        let wasm = std::fs::read(path).map_err(|error| {
+            WasmPluginError::Load(format!("failed to read `{}`: {error}", path.display()))
+        })?;
+        Self::compile(&wasm, limits)
+    }
+
+    /// Returns whether the plugin exports a function named `function_name`.
+    pub fn exports_function(&self, function_name: &str) -> bool {
+        self.module
+            .exports()
+            .any(|export| export.name() == function_name && export.ty().func().is_some())
+    }
+
+    /// Returns a new instance of the plugin. Instances are not shared between threads: each
+    /// caller keeps its own instance, with its own linear memory.
+    pub fn instantiate(&self) -> WasmPluginResult<WasmPluginInstance> {
+        let inner = self.instantiate_inner()?;
+        Ok(WasmPluginInstance {
+            plugin: self.clone(),
+            inner_opt: Some(inner),
+        })
+    }
+
+    fn instantiate_inner(&self) -> WasmPluginResult<InstanceInner> {
+        let store_limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.max_memory_num_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, store_limits);
+        store.limiter(|store_limits| store_limits);
+        // The fuel also bounds the execution of the start function, if any.
+        store
+            .set_fuel(self.limits.max_fuel_per_call)
+            .map_err(|error| WasmPluginError::Load(format!("{error:#}")))?;
+        let instance = Instance::new(&mut store, &self.module, &[])
+            .map_err(|error| WasmPluginError::Load(format!("{error:#}")))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| WasmPluginError::Load("plugin does not export `memory`".to_string()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, ALLOC_FUNCTION_NAME)
+            .map_err(|error| WasmPluginError::Load(format!("{error:#}")))?;
+        let dealloc_opt = if self.exports_function(DEALLOC_FUNCTION_NAME) {
+            let dealloc = instance
+                .get_typed_func::<(i32, i32), ()>(&mut store, DEALLOC_FUNCTION_NAME)
+                .map_err(|error| WasmPluginError::Load(format!("{error:#}")))?;
+            Some(dealloc)
+        } else {
+            None
+        };
+        Ok(InstanceInner {
+            store,
+            instance,
+            memory,
+            alloc,
+            dealloc_opt,
+        })
+    }
+}
+
+/// An instance of a plugin, with its own linear memory.
+pub struct WasmPluginInstance {
+    plugin: WasmPlugin,
+    /// `None` after a trap: the state of the instance is then unspecified, so a new instance is
+    /// created on the next call.
+    inner_opt: Option<InstanceInner>,
+}
+
+impl fmt::Debug for WasmPluginInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPluginInstance")
+            .field("plugin", &self.plugin)
+            .finish()
+    }
+}
+
+impl WasmPluginInstance {
+    /// Calls the plugin function `function_name` and returns its output, if any.
+    pub fn call(&mut self, function_name: &str, input: &[u8]) -> WasmPluginResult<Option<Vec<u8>>> {
+        let inner = match &mut self.inner_opt {
+            Some(inner) => inner,
+            inner_opt @ None => inner_opt.insert(self.plugin.instantiate_inner()?),
+        };
+        let call_result = inner.call(function_name, input, self.plugin.limits.max_fuel_per_call);
+
+        if matches!(
+            call_result,
+            Err(WasmPluginError::OutOfFuel) | Err(WasmPluginError::Trap(_))
+        ) {
+            self.inner_opt = None;
+        }
+        call_result
+    }
+}
+
+struct InstanceInner {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc_opt: Option<TypedFunc<(i32, i32), ()>>,
+}
+
+impl InstanceInner {
+    fn call(
+        &mut self,
+        function_name: &str,
+        input: &[u8],
+        max_fuel: u64,
+    ) -> WasmPluginResult<Option<Vec<u8>>> {
+        let function = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&mut self.store, function_name)
+            .map_err(|_| WasmPluginError::MissingFunction(function_name.to_string()))?;
+        let input_len = i32::try_from(input.len()).map_err(|_| {
+            WasmPluginError::InvalidInput(format!("input of {} bytes is too large", input.len()))
+        })?;
+        self.store.set_fuel(max_fuel).map_err(into_plugin_error)?;
+
+        let input_ptr = self
+            .alloc
+            .call(&mut self.store, input_len)
+            .map_err(into_plugin_error)?;
+        self.memory
+            .write(&mut self.store, input_ptr as u32 as usize, input)
+            .map_err(|error| WasmPluginError::Trap(format!("failed to write input: {error}")))?;
+        let packed_output = function
+            .call(&mut self.store, (input_ptr, input_len))
+            .map_err(into_plugin_error)?;
+        self.dealloc(input_ptr, input_len)?;
+
+        if packed_output < 0 {
+            return Ok(None);
+        }
+        let output_ptr = (packed_output >> 32) as u32;
+        let output_len = packed_output as u32;
+        let mut output = vec![0u8; output_len as usize];
+        self.memory
+            .read(&self.store, output_ptr as usize, &mut output)
+            .map_err(|error| {
+                WasmPluginError::InvalidOutput(format!("failed to read output: {error}"))
+            })?;
+        self.dealloc(output_ptr as i32, output_len as i32)?;
+        Ok(Some(output))
+    }
+
+    fn dealloc(&mut self, ptr: i32, len: i32) -> WasmPluginResult<()> {
+        if let Some(dealloc) = &self.dealloc_opt {
+            dealloc
+                .call(&mut self.store, (ptr, len))
+                .map_err(into_plugin_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn into_plugin_error(error: wasmtime::Error) -> WasmPluginError {
+    if let Some(Trap::OutOfFuel) = error.downcast_ref::<Trap>() {
+        return WasmPluginError::OutOfFuel;
+    }
+    WasmPluginError::Trap(format!("{error:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: WasmPluginLimits = WasmPluginLimits {
+        max_fuel_per_call: 1_000_000,
+        max_memory_num_bytes: 1 << 20,
+    };
+
+    // Bump allocator: the inputs are written after the first page, which holds the outputs.
+    const ECHO_PLUGIN_WAT: &str = r#"
+        (module
+            (memory (export "memory") 2)
+            (global $next (mut i32) (i32.const 65536))
+            (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $len)))
+                (local.get $ptr))
+            (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))
+            (func (export "nothing") (param $ptr i32) (param $len i32) (result i64)
+                (i64.const -1))
+            (func (export "spin") (param $ptr i32) (param $len i32) (result i64)
+                (loop $forever (br $forever))
+                (i64.const -1))
+        )
+    "#;
+
+    #[test]
+    fn test_wasm_plugin_call() {
+        let plugin = WasmPlugin::compile(ECHO_PLUGIN_WAT.as_bytes(), LIMITS).unwrap();
+        assert!(plugin.exports_function("echo"));
+        assert!(!plugin.exports_function("memory"));
+        assert!(!plugin.exports_function("missing"));
+
+        let mut instance = plugin.instantiate().unwrap();
+        let output = instance.call("echo", b"hello").unwrap();
+        assert_eq!(output.as_deref(), Some(&b"hello"[..]));
+
+        let output = instance.call("nothing", b"hello").unwrap();
+        assert!(output.is_none());
+
+        let error = instance.call("missing", b"hello").unwrap_err();
+        assert!(matches!(error, WasmPluginError::MissingFunction(_)));
+
+        let output: Option<serde_json::Value> = instance
+            .call_json("echo", &serde_json::json!({"foo": "bar"}))
+            .unwrap();
+        assert_eq!(output, Some(serde_json::json!({"foo": "bar"})));
+    }
+
+    #[test]
+    fn test_wasm_plugin_out_of_fuel() {
+        let plugin = WasmPlugin::compile(ECHO_PLUGIN_WAT.as_bytes(), LIMITS).unwrap();
+        let mut instance = plugin.instantiate().unwrap();
+
+        let error = instance.call("spin", b"").unwrap_err();
+        assert!(matches!(error, WasmPluginError::OutOfFuel));
+
+        // The instance is recreated after the trap.
+        let output = instance.call("echo", b"hello").unwrap();
+        assert_eq!(output.as_deref(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn test_wasm_plugin_memory_limit() {
+        let limits = WasmPluginLimits {
+            max_fuel_per_call: 1_000_000,
+            max_memory_num_bytes: 1 << 16,
+        };
+        let plugin = WasmPlugin::compile(ECHO_PLUGIN_WAT.as_bytes(), limits).unwrap();
+        let error = plugin.instantiate().unwrap_err();
+        assert!(matches!(error, WasmPluginError::Load(_)));
+    }
+
+    #[test]
+    fn test_wasm_plugin_invalid_module() {
+        let error = WasmPlugin::compile(b"not a module", LIMITS).unwrap_err();
+        assert!(matches!(error, WasmPluginError::Load(_)));
+
+        let error =
+            WasmPlugin::compile(br#"(module (memory (export "memory") 1))"#, LIMITS).unwrap_err();
+        assert!(matches!(error, WasmPluginError::MissingFunction(_)));
+    }
+}
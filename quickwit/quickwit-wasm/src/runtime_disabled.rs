@@ -0,0 +1,58 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+use crate::{WasmPluginError, WasmPluginLimits, WasmPluginResult};
+
+/// Placeholder of the plugins when the crate is compiled without the `wasmtime` feature. It
+/// cannot be constructed: loading a plugin always fails.
+#[derive(Clone, Debug)]
+pub enum WasmPlugin {}
+
+impl WasmPlugin {
+    pub fn compile(_wasm: &[u8], _limits: WasmPluginLimits) -> WasmPluginResult<Self> {
+        Err(WasmPluginError::NotEnabled)
+    }
+
+    pub fn load(_path: &Path, _limits: WasmPluginLimits) -> WasmPluginResult<Self> {
+        Err(WasmPluginError::NotEnabled)
+    }
+
+    pub fn exports_function(&self, _function_name: &str) -> bool {
+        match *self {}
+    }
+
+    pub fn instantiate(&self) -> WasmPluginResult<WasmPluginInstance> {
+        match *self {}
+    }
+}
+
+#[derive(Debug)]
+pub enum WasmPluginInstance {}
+
+impl WasmPluginInstance {
+    pub fn call(
+        &mut self,
+        _function_name: &str,
+        _input: &[u8],
+    ) -> WasmPluginResult<Option<Vec<u8>>> {
+        match *self {}
+    }
+}